@@ -1,26 +1,45 @@
+use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Output;
 
 use serde::Deserialize;
 
 use crate::process::{format_output, run_args};
 
-pub fn run_bench_suite() -> Result<(), String> {
-    run_bench_once(5000)?;
-    run_bench_once(10000)?;
-    Ok(())
-}
+/// The `bench` blocks `xu bench` runs for the suite.
+const SUITE: &str = "tests/benchmarks/xu/suite.xu";
+
+const BASELINE: &str = "perf/bench_baseline.json";
 
-fn run_bench_once(scale: usize) -> Result<(), String> {
-    let arg = scale.to_string();
-    let o = run_args("bash", &["scripts/run_cross_lang_bench.sh", arg.as_str()])?;
-    if o.status.success() {
+/// Growth of a mean time over the baseline, in percent, that fails the suite.
+const THRESHOLD_PCT: &str = "25";
+
+/// Runs the Xu suite with `xu bench` against the saved baseline, or saves a
+/// new baseline with `update-baseline`.
+pub fn run_bench_suite(mode: Option<&str>) -> Result<(), String> {
+    let update_baseline = matches!(mode, Some("update-baseline"))
+        || env::var("XU_BENCH_UPDATE").ok().as_deref() == Some("1");
+    let build = run_args("cargo", &["build", "-q", "--release", "-p", "xu_cli", "--bin", "xu"])?;
+    if !build.status.success() {
+        return Err(format!("Failed to build xu_cli:\n{}", format_output(&build)));
+    }
+    let bin = crate::slim::xu_release_bin_path();
+    let mut args = vec!["bench"];
+    if update_baseline {
+        args.extend(["--save", BASELINE]);
+    } else if Path::new(BASELINE).exists() {
+        args.extend(["--baseline", BASELINE, "--threshold", THRESHOLD_PCT]);
+    }
+    args.push(SUITE);
+    let out = run_args(bin.to_str().unwrap(), &args)?;
+    eprint!("{}", String::from_utf8_lossy(&out.stdout));
+    if out.status.success() {
         return Ok(());
     }
     Err(format!(
-        "run_cross_lang_bench.sh {scale} failed:\n{}",
-        format_output(&o)
+        "bench gate failed (set XU_BENCH_UPDATE=1 to refresh baseline):\n{}",
+        String::from_utf8_lossy(&out.stderr)
     ))
 }
 
@@ -11,6 +11,9 @@ mod bench;
 mod perf;
 mod slim;
 
+const FIXTURES_DIR: &str = "crates/xu_cli/tests/fixtures";
+const CONFORMANCE_DIR: &str = "tests/conformance";
+
 fn main() {
     let mut args = env::args().skip(1);
     let cmd = args.next().unwrap_or_else(|| "verify".to_string());
@@ -23,10 +26,14 @@ fn main() {
         "check-unused" => check_unused(),
         "test" => test_workspace(),
         "examples" => verify_examples(),
+        "fixtures" => verify_fixtures(next.as_deref()),
         "codegen-examples" => codegen_examples(),
+        "conformance" => conformance(next.as_deref()),
         "slim-baseline" => slim::slim_baseline(),
         "perf" => perf::perf(next.as_deref()),
+        "bench" => bench::run_bench_suite(next.as_deref()),
         "bench-report" => bench::bench_report(next.as_deref()),
+        "wasm" => wasm_build(),
         _ => usage_error(&cmd),
     };
     if let Err(msg) = result {
@@ -37,7 +44,7 @@ fn main() {
 
 fn usage_error(cmd: &str) -> Result<(), String> {
     Err(format!(
-        "Unknown command: {cmd}\nUsage: cargo run -p xtask -- <verify|fmt|clippy|lint|check-unused|test|examples|codegen-examples|slim-baseline|perf [update-baseline]|bench-report [scales]>"
+        "Unknown command: {cmd}\nUsage: cargo run -p xtask -- <verify|fmt|clippy|lint|check-unused|test|examples|fixtures [update|<dir>]|codegen-examples|conformance [update]|slim-baseline|perf [update-baseline|dispatch]|bench [update-baseline]|bench-report [scales]|wasm>"
     ))
 }
 
@@ -45,7 +52,9 @@ fn verify() -> Result<(), String> {
     fmt_check()?;
     lint_strict()?;
     test_workspace()?;
+    test_plugins()?;
     verify_examples()?;
+    verify_fixtures(None)?;
     verify_optional_projects()?;
     Ok(())
 }
@@ -97,10 +106,29 @@ fn check_unused() -> Result<(), String> {
     }
     Ok(())
 }
+/// Builds the playground crate for the browser, which checks that the
+/// runtime and driver compile without their `sys` feature.
+fn wasm_build() -> Result<(), String> {
+    let o = process::run_args("cargo", &["build", "-p", "xu_wasm", "--target", "wasm32-unknown-unknown"])?;
+    if !o.status.success() {
+        return Err(format!("wasm build failed:\n{}", process::format_output(&o)));
+    }
+    Ok(())
+}
+
 fn test_workspace() -> Result<(), String> {
     process::run_args("cargo", &["test", "--workspace"]).map(|_| ())
 }
 
+/// The plugin registry is behind a feature `test --workspace` leaves off.
+fn test_plugins() -> Result<(), String> {
+    process::run_args(
+        "cargo",
+        &["test", "-p", "xu_runtime", "--features", "plugins", "--test", "plugins"],
+    )
+    .map(|_| ())
+}
+
 fn verify_examples() -> Result<(), String> {
     let manifest = load_example_manifest()?;
     let (valid, invalid) = list_examples(&manifest, "examples")?;
@@ -127,6 +155,41 @@ fn verify_examples() -> Result<(), String> {
     Ok(())
 }
 
+/// Runs the CLI fixture suite (`crates/xu_cli/tests/fixtures`, or `dir`)
+/// against a fresh debug build; `update` re-records the expected files.
+fn verify_fixtures(arg: Option<&str>) -> Result<(), String> {
+    let (root, update) = match arg {
+        Some("update") => (PathBuf::from(FIXTURES_DIR), true),
+        Some(dir) => (PathBuf::from(dir), false),
+        None => (PathBuf::from(FIXTURES_DIR), false),
+    };
+    let xu_bin = build_xu_cli()?;
+    let n = xu_cli::fixture::Harness::new(xu_bin)
+        .update(update)
+        .run_all(&root)?;
+    println!("{n} fixtures {}", if update { "updated" } else { "passed" });
+    Ok(())
+}
+
+/// Runs the conformance suite on the interpreter, the VM and the code
+/// generators and prints the parity matrix; `update` re-records the
+/// expected output from the interpreter first.
+fn conformance(arg: Option<&str>) -> Result<(), String> {
+    let update = match arg {
+        Some("update") => true,
+        Some(other) => return Err(format!("Unknown conformance argument: {other}")),
+        None => false,
+    };
+    let report = xu_cli::conformance::run_all(Path::new(CONFORMANCE_DIR), update)?;
+    print!("{}", report.matrix());
+    let failures = report.failures();
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} conformance failures\n\n{}", failures.len(), failures.join("\n")))
+    }
+}
+
 fn build_xu_cli() -> Result<PathBuf, String> {
     let output = run_owned("cargo", &[
         "build".to_string(),
@@ -162,28 +225,27 @@ fn codegen_examples() -> Result<(), String> {
     ensure_runtime_assets()?;
     let xu_bin = build_xu_cli()?;
     let mut ok_codegen = 0usize;
-    let mut ok_run_js = 0usize;
-    let mut ok_run_py = 0usize;
+    let mut ok_run_lua = 0usize;
     let mut total = 0usize;
     for f in valid {
         total += 1;
         let src = fs::read_to_string(&f).unwrap_or_default();
         let run_friendly = is_run_friendly(&src);
-        // JS codegen with runtime injection
-        let js_out = temp_file_path(&f, "generated.js");
-        let js_args = vec![
+        // Lua codegen with runtime injection
+        let lua_out = temp_file_path(&f, "generated.lua");
+        let lua_args = vec![
             "codegen".to_string(),
             f.display().to_string(),
-            "js".to_string(),
-            js_out.display().to_string(),
+            "lua".to_string(),
+            lua_out.display().to_string(),
             "--inject-runtime".to_string(),
         ];
-        let cg = run_owned(xu_bin.to_str().unwrap(), &js_args)?;
+        let cg = run_owned(xu_bin.to_str().unwrap(), &lua_args)?;
         if cg.status.success() {
             ok_codegen += 1;
         } else {
             eprintln!(
-                "JS codegen failed for {}:\n{}",
+                "Lua codegen failed for {}:\n{}",
                 f.display(),
                 format_output(&cg)
             );
@@ -193,54 +255,21 @@ fn codegen_examples() -> Result<(), String> {
             && should_run_example(&manifest, &f)
             && !should_expect_fail_run(&manifest, &f)
         {
-            let run = run_args("node", &[js_out.to_string_lossy().as_ref()])?;
+            let run = run_args("lua", &[lua_out.to_string_lossy().as_ref()])?;
             if run.status.success() {
-                ok_run_js += 1;
+                ok_run_lua += 1;
             } else {
                 eprintln!(
-                    "JS run failed for {}:\n{}",
+                    "Lua run failed for {}:\n{}",
                     f.display(),
                     format_output(&run)
                 );
             }
         }
-        // Python codegen with runtime injection
-        let py_out = temp_file_path(&f, "generated.py");
-        let py_args = vec![
-            "codegen".to_string(),
-            f.display().to_string(),
-            "py".to_string(),
-            py_out.display().to_string(),
-            "--inject-runtime".to_string(),
-        ];
-        let cg_py = run_owned(xu_bin.to_str().unwrap(), &py_args)?;
-        if !cg_py.status.success() {
-            eprintln!(
-                "Py codegen failed for {}:\n{}",
-                f.display(),
-                format_output(&cg_py)
-            );
-            continue;
-        }
-        if run_friendly
-            && should_run_example(&manifest, &f)
-            && !should_expect_fail_run(&manifest, &f)
-        {
-            let runp = run_args("python3", &[py_out.to_string_lossy().as_ref()])?;
-            if runp.status.success() {
-                ok_run_py += 1;
-            } else {
-                eprintln!(
-                    "Py run failed for {}:\n{}",
-                    f.display(),
-                    format_output(&runp)
-                );
-            }
-        }
     }
     eprintln!(
-        "Codegen summary: total={} ok_codegen={} ok_run_js={} ok_run_py={}",
-        total, ok_codegen, ok_run_js, ok_run_py
+        "Codegen summary: total={} ok_codegen={} ok_run_lua={}",
+        total, ok_codegen, ok_run_lua
     );
     Ok(())
 }
@@ -424,7 +453,10 @@ fn verify_optional_projects() -> Result<(), String> {
         perf::perf(None)?;
     }
     if env::var("XU_BENCH").ok().as_deref() == Some("1") {
-        bench::run_bench_suite()?;
+        bench::run_bench_suite(None)?;
+    }
+    if env::var("XU_WASM").ok().as_deref() == Some("1") {
+        wasm_build()?;
     }
     Ok(())
 }
@@ -6,32 +6,87 @@ use std::process::Output;
 use crate::bench;
 use crate::process::{format_output, run_args};
 
+/// Cargo feature of `xu_runtime` that swaps the `match` dispatch loop for
+/// the table-driven one.
+const THREADED_DISPATCH: &str = "threaded-dispatch";
+
 pub fn perf(mode: Option<&str>) -> Result<(), String> {
+    if mode == Some("dispatch") {
+        return perf_dispatch();
+    }
     let update_baseline = matches!(mode, Some("update-baseline"))
         || env::var("XU_PERF_UPDATE").ok().as_deref() == Some("1");
 
+    let baseline_path = PathBuf::from("perf").join("baseline.txt");
+    let baseline = read_baseline(&baseline_path)?;
+
+    let best = run_best_of(None)?;
+    for (k, v) in sorted(&best) {
+        eprintln!("PERF_RESULT {k}={v}");
+    }
+
+    if update_baseline {
+        write_baseline(&baseline_path, &best)?;
+        return Ok(());
+    }
+
+    let failures = gate_failures(&baseline, &best, "");
+    if !failures.is_empty() {
+        return Err(format!(
+            "perf gate failed (set XU_PERF_UPDATE=1 to refresh baseline):\n{}",
+            failures.join("\n")
+        ));
+    }
+
+    if env::var("XU_BENCH_REPORT").ok().as_deref() == Some("1") {
+        let scales = env::var("XU_BENCH_SCALES").ok();
+        bench::bench_report(scales.as_deref())?;
+    }
+
+    Ok(())
+}
+
+/// Runs the suite with both VM dispatchers, gates each against the
+/// baseline and prints the table-driven timings relative to `match`.
+fn perf_dispatch() -> Result<(), String> {
+    let baseline = read_baseline(&PathBuf::from("perf").join("baseline.txt"))?;
+    let matched = run_best_of(None)?;
+    let threaded = run_best_of(Some(THREADED_DISPATCH))?;
+
+    eprintln!("{:<64} {:>8} {:>9} {:>7}", "key", "match", "threaded", "ratio");
+    for (k, m) in sorted(&matched) {
+        let Some(t) = threaded.get(&k).copied() else {
+            continue;
+        };
+        let ratio = if m == 0 { "-".to_string() } else { format!("{:.2}", t as f64 / m as f64) };
+        eprintln!("{k:<64} {m:>6}ms {t:>7}ms {ratio:>7}");
+    }
+    let total = |m: &std::collections::HashMap<String, u64>| m.values().sum::<u64>();
+    eprintln!(
+        "PERF_DISPATCH match={}ms threaded={}ms",
+        total(&matched),
+        total(&threaded)
+    );
+
+    let mut failures = gate_failures(&baseline, &matched, "match ");
+    failures.extend(gate_failures(&baseline, &threaded, "threaded "));
+    if !failures.is_empty() {
+        return Err(format!("perf gate failed:\n{}", failures.join("\n")));
+    }
+    Ok(())
+}
+
+/// Best timing per key over `XU_PERF_RUNS` runs of the suite.
+fn run_best_of(feature: Option<&str>) -> Result<std::collections::HashMap<String, u64>, String> {
     let runs: usize = env::var("XU_PERF_RUNS")
         .ok()
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(3)
         .max(1);
 
-    let ratio: f64 = env::var("XU_PERF_MAX_RATIO")
-        .ok()
-        .and_then(|s| s.parse::<f64>().ok())
-        .unwrap_or(1.5);
-
-    let abs_ms: u64 = env::var("XU_PERF_MAX_ABS_MS")
-        .ok()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(20);
-
-    let baseline_path = PathBuf::from("perf").join("baseline.txt");
-    let baseline = read_baseline(&baseline_path)?;
-
     let mut best: std::collections::HashMap<String, u64> = std::collections::HashMap::new();
     for _ in 0..runs {
-        let mut m = run_perf_suite()?;
+        let mut m = run_perf_suite(feature)?;
         for (k, v) in m.drain() {
             best.entry(k)
                 .and_modify(|cur| {
@@ -42,77 +97,68 @@ pub fn perf(mode: Option<&str>) -> Result<(), String> {
                 .or_insert(v);
         }
     }
+    Ok(best)
+}
 
-    let mut keys: Vec<_> = best.keys().cloned().collect();
-    keys.sort();
-    for k in keys {
-        let v = best.get(&k).copied().unwrap_or(0);
-        eprintln!("PERF_RESULT {k}={v}");
-    }
+fn gate_failures(
+    baseline: &std::collections::HashMap<String, u64>,
+    best: &std::collections::HashMap<String, u64>,
+    label: &str,
+) -> Vec<String> {
+    let ratio: f64 = env::var("XU_PERF_MAX_RATIO")
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .unwrap_or(1.5);
 
-    if update_baseline {
-        write_baseline(&baseline_path, &best)?;
-        return Ok(());
-    }
+    let abs_ms: u64 = env::var("XU_PERF_MAX_ABS_MS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(20);
 
     let mut failures = Vec::new();
-    for (k, measured) in best.iter() {
-        let Some(base) = baseline.get(k).copied() else {
+    for (k, measured) in sorted(best) {
+        let Some(base) = baseline.get(&k).copied() else {
             continue;
         };
         if base == 0 {
             continue;
         }
         let allowed = ((base as f64) * ratio).ceil() as u64 + abs_ms;
-        if *measured > allowed {
+        if measured > allowed {
             failures.push(format!(
-                "{k}: measured={measured}ms baseline={base}ms allowed<={allowed}ms"
+                "{label}{k}: measured={measured}ms baseline={base}ms allowed<={allowed}ms"
             ));
         }
     }
-    if !failures.is_empty() {
-        return Err(format!(
-            "perf gate failed (set XU_PERF_UPDATE=1 to refresh baseline):\n{}",
-            failures.join("\n")
-        ));
-    }
-
-    if env::var("XU_BENCH_REPORT").ok().as_deref() == Some("1") {
-        let scales = env::var("XU_BENCH_SCALES").ok();
-        bench::bench_report(scales.as_deref())?;
-    }
+    failures
+}
 
-    Ok(())
+fn sorted(values: &std::collections::HashMap<String, u64>) -> Vec<(String, u64)> {
+    let mut out: Vec<_> = values.iter().map(|(k, v)| (k.clone(), *v)).collect();
+    out.sort();
+    out
 }
 
-fn run_perf_suite() -> Result<std::collections::HashMap<String, u64>, String> {
+fn run_perf_suite(feature: Option<&str>) -> Result<std::collections::HashMap<String, u64>, String> {
     let mut out = std::collections::HashMap::new();
-    run_perf_test(&mut out, "perf_lexer_parser")?;
-    run_perf_test(&mut out, "perf_runtime_exec")?;
-    run_perf_test(&mut out, "perf_benchmarks")?;
-    run_perf_test(&mut out, "perf_vm_long_interpolation")?;
+    run_perf_test(&mut out, "perf_lexer_parser", feature)?;
+    run_perf_test(&mut out, "perf_runtime_exec", feature)?;
+    run_perf_test(&mut out, "perf_benchmarks", feature)?;
+    run_perf_test(&mut out, "perf_vm_long_interpolation", feature)?;
     Ok(out)
 }
 
 fn run_perf_test(
     out: &mut std::collections::HashMap<String, u64>,
     test_name: &str,
+    feature: Option<&str>,
 ) -> Result<(), String> {
-    let o = run_args(
-        "cargo",
-        &[
-            "test",
-            "-q",
-            "-p",
-            "xu_runtime",
-            "--test",
-            test_name,
-            "--release",
-            "--",
-            "--ignored",
-            "--nocapture",
-        ],
-    )?;
+    let mut args = vec!["test", "-q", "-p", "xu_runtime", "--test", test_name, "--release"];
+    if let Some(feature) = feature {
+        args.extend(["--features", feature]);
+    }
+    args.extend(["--", "--ignored", "--nocapture"]);
+    let o = run_args("cargo", &args)?;
     if !o.status.success() {
         return Err(format!("{test_name} failed:\n{}", format_output(&o)));
     }
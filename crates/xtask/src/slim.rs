@@ -46,7 +46,7 @@ pub(crate) fn slim_baseline() -> Result<(), String> {
     Ok(())
 }
 
-fn xu_release_bin_path() -> PathBuf {
+pub(crate) fn xu_release_bin_path() -> PathBuf {
     let mut p = PathBuf::from("target");
     p.push("release");
     if cfg!(windows) {
@@ -0,0 +1,56 @@
+// Embeds the interpreter: defines functions in a script and calls them.
+//
+//     cargo build -p xu_capi
+//     cc crates/xu_capi/examples/embed.c -Icrates/xu_capi/include target/debug/libxu_capi.a -lpthread -ldl -lm
+
+#include <stdio.h>
+
+#include "xu.h"
+
+static const char *SCRIPT =
+    "func add(a: int, b: int) -> int { return a + b }\n"
+    "func greet(names: [string]) -> string {\n"
+    "    println(\"greeting \" + names.length.to_string())\n"
+    "    return \"hi \" + names.join(\" and \")\n"
+    "}\n";
+
+int main(void) {
+    XuRuntime *rt = xu_runtime_new();
+    if (xu_eval(rt, SCRIPT) != XU_OK) {
+        fprintf(stderr, "%s\n", xu_last_error(rt));
+        return 1;
+    }
+
+    const XuValue *args[] = { xu_value_int(2), xu_value_int(40) };
+    XuValue *sum = xu_call(rt, "add", args, 2);
+    int64_t n = 0;
+    if (sum == NULL || !xu_value_as_int(sum, &n)) {
+        fprintf(stderr, "add: %s\n", xu_last_error(rt));
+        return 1;
+    }
+    printf("add: %lld\n", (long long)n);
+
+    const XuValue *names[] = { xu_value_str("ada"), xu_value_str("grace") };
+    XuValue *list = xu_value_list(names, 2);
+    const XuValue *greet_args[] = { list };
+    XuValue *greeting = xu_call(rt, "greet", greet_args, 1);
+    char *text = xu_value_as_str(greeting);
+    char *printed = xu_take_output(rt);
+    printf("greet: %s (printed \"%s\")\n", text, printed);
+    xu_string_free(text);
+    xu_string_free(printed);
+
+    if (xu_call(rt, "missing", NULL, 0) == NULL) {
+        printf("missing: %s\n", xu_last_error(rt));
+    }
+
+    for (int i = 0; i < 2; i++) {
+        xu_value_free((XuValue *)args[i]);
+        xu_value_free((XuValue *)names[i]);
+    }
+    xu_value_free(sum);
+    xu_value_free(list);
+    xu_value_free(greeting);
+    xu_runtime_free(rt);
+    return 0;
+}
@@ -0,0 +1,212 @@
+#ifndef XU_H
+#define XU_H
+
+/* Generated by cbindgen from crates/xu_capi; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of calls that return no value.
+typedef enum XuStatus {
+  XU_OK = 0,
+  XU_ERROR = 1,
+} XuStatus;
+
+// What an [`XuValue`] holds. Ranges, enums and structs are `XU_OTHER`.
+typedef enum XuValueKind {
+  XU_UNIT = 0,
+  XU_BOOL = 1,
+  XU_INT = 2,
+  XU_FLOAT = 3,
+  XU_STR = 4,
+  XU_LIST = 5,
+  XU_TUPLE = 6,
+  XU_DICT = 7,
+  XU_OPTION = 8,
+  XU_OTHER = 9,
+} XuValueKind;
+
+// An interpreter with the output and error left by its last calls.
+typedef struct XuRuntime XuRuntime;
+
+// A copy of a script value, owned by the caller.
+typedef struct XuValue XuValue;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Calls the top-level function `name` of the last program run by
+// [`xu_eval`] with copies of the `nargs` values in `args`, which stay owned
+// by the caller. Returns the result, or `NULL` on error.
+//
+// # Safety
+//
+// `rt` is a live runtime, `name` a NUL-terminated string and `args` points
+// to `nargs` live values (it may be NULL when `nargs` is 0).
+struct XuValue *xu_call(struct XuRuntime *rt,
+                        const char *name,
+                        const struct XuValue *const *args,
+                        uintptr_t nargs);
+
+// Compiles and runs `source` as a program, with strict checks.
+//
+// Each call is a new program: it replaces the globals of the previous one,
+// and its functions are the ones [`xu_call`] finds afterwards. What the
+// program prints is kept for [`xu_take_output`]. Compile errors are
+// reported as rendered diagnostics.
+//
+// # Safety
+//
+// `rt` is a live runtime and `source` a NUL-terminated string.
+enum XuStatus xu_eval(struct XuRuntime *rt, const char *source);
+
+// The message of the last failed call on `rt`, or `NULL` if the last call
+// succeeded. The string belongs to the runtime and is valid until its next
+// call.
+//
+// # Safety
+//
+// `rt` is a live runtime.
+const char *xu_last_error(const struct XuRuntime *rt);
+
+// Frees a runtime. `NULL` is ignored.
+//
+// # Safety
+//
+// `rt` is NULL or a runtime from [`xu_runtime_new`] not yet freed.
+void xu_runtime_free(struct XuRuntime *rt);
+
+// Creates a runtime with strict variable checks and the embedded stdlib.
+// Free it with [`xu_runtime_free`].
+struct XuRuntime *xu_runtime_new(void);
+
+// Frees a string returned by this library. `NULL` is ignored.
+//
+// # Safety
+//
+// `s` is NULL or a string from this library not yet freed.
+void xu_string_free(char *s);
+
+// Everything printed since the last call to this function. Free the result
+// with [`xu_string_free`].
+//
+// # Safety
+//
+// `rt` is a live runtime.
+char *xu_take_output(struct XuRuntime *rt);
+
+// Stores the bool in `out` and returns true if `v` is a bool.
+//
+// # Safety
+//
+// `v` is a live value and `out` writable.
+bool xu_value_as_bool(const struct XuValue *v, bool *out);
+
+// Stores the number in `out` and returns true if `v` is a float or an int.
+//
+// # Safety
+//
+// `v` is a live value and `out` writable.
+bool xu_value_as_float(const struct XuValue *v, double *out);
+
+// Stores the int in `out` and returns true if `v` is an int.
+//
+// # Safety
+//
+// `v` is a live value and `out` writable.
+bool xu_value_as_int(const struct XuValue *v, int64_t *out);
+
+// A copy of the string in `v`, or `NULL` if `v` is not a string or contains
+// a NUL. Free it with `xu_string_free`.
+//
+// # Safety
+//
+// `v` is a live value.
+char *xu_value_as_str(const struct XuValue *v);
+
+// A copy of element `i` of a list or tuple, the value of entry `i` of a
+// dict, or the value of a present option (`i` = 0). `NULL` if there is no
+// such element.
+//
+// # Safety
+//
+// `v` is a live value.
+struct XuValue *xu_value_at(const struct XuValue *v, uintptr_t i);
+
+// A bool value.
+struct XuValue *xu_value_bool(bool b);
+
+// A dict mapping copies of `keys[i]` to copies of `values[i]`, or `NULL` if
+// one of them is NULL. Keys must be hashable (strings, ints, bools, ...)
+// when the dict is passed to the runtime.
+//
+// # Safety
+//
+// `keys` and `values` each point to `n` values (they may be NULL when `n`
+// is 0).
+struct XuValue *xu_value_dict(const struct XuValue *const *keys,
+                              const struct XuValue *const *values,
+                              uintptr_t n);
+
+// A float value.
+struct XuValue *xu_value_float(double f);
+
+// Frees a value. `NULL` is ignored.
+//
+// # Safety
+//
+// `v` is NULL or a value from this library not yet freed.
+void xu_value_free(struct XuValue *v);
+
+// An int value. Calls taking it fail unless it fits in 48 bits, between
+// -2^47 and 2^47 - 1.
+struct XuValue *xu_value_int(int64_t i);
+
+// A copy of the key of entry `i` of a dict, or `NULL`.
+//
+// # Safety
+//
+// `v` is a live value.
+struct XuValue *xu_value_key_at(const struct XuValue *v, uintptr_t i);
+
+// What `v` holds; `NULL` is `XU_OTHER`.
+//
+// # Safety
+//
+// `v` is a live value.
+enum XuValueKind xu_value_kind(const struct XuValue *v);
+
+// The number of elements of a list or tuple, entries of a dict, or values
+// of an option (0 or 1). Other values have none.
+//
+// # Safety
+//
+// `v` is a live value.
+uintptr_t xu_value_len(const struct XuValue *v);
+
+// A list of copies of the `n` values in `items`, or `NULL` if one of them
+// is NULL.
+//
+// # Safety
+//
+// `items` points to `n` values (it may be NULL when `n` is 0).
+struct XuValue *xu_value_list(const struct XuValue *const *items, uintptr_t n);
+
+// A string value copied from `s`, or `NULL` if `s` is NULL or not UTF-8.
+//
+// # Safety
+//
+// `s` is NULL or a NUL-terminated string.
+struct XuValue *xu_value_str(const char *s);
+
+// The unit value `()`.
+struct XuValue *xu_value_unit(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* XU_H */
@@ -0,0 +1,257 @@
+//! C API for embedding the Xu interpreter.
+//!
+//! The header `include/xu.h` declares everything exported here. A host
+//! creates a runtime with [`xu_runtime_new`], runs source with [`xu_eval`]
+//! and calls the functions that source defined with [`xu_call`]:
+//!
+//! ```c
+//! XuRuntime *rt = xu_runtime_new();
+//! if (xu_eval(rt, "func add(a: int, b: int) -> int { return a + b }") != XU_OK) {
+//!     fprintf(stderr, "%s\n", xu_last_error(rt));
+//! }
+//! const XuValue *args[] = { xu_value_int(2), xu_value_int(3) };
+//! XuValue *sum = xu_call(rt, "add", args, 2);
+//! ```
+//!
+//! `examples/embed.c` is a complete host.
+//!
+//! Values are [`XuValue`] handles owning a copy of a script value, not
+//! references into the runtime's heap: they stay valid after the runtime is
+//! freed, and every handle returned to the caller is freed with
+//! [`xu_value_free`]. Strings returned as `char *` are freed with
+//! [`xu_string_free`].
+//!
+//! Failing calls return [`XuStatus::XU_ERROR`] or `NULL` and leave a message
+//! for [`xu_last_error`]. Panics inside the interpreter are caught at the
+//! boundary and reported the same way.
+
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use xu_driver::Driver;
+use xu_runtime::{HostValue, Runtime};
+use xu_syntax::Severity;
+
+mod value;
+
+pub use value::{
+    XuValue, XuValueKind, xu_value_as_bool, xu_value_as_float, xu_value_as_int, xu_value_as_str, xu_value_at,
+    xu_value_bool, xu_value_dict, xu_value_float, xu_value_free, xu_value_int, xu_value_key_at, xu_value_kind,
+    xu_value_len, xu_value_list, xu_value_str, xu_value_unit,
+};
+
+/// An interpreter with the output and error left by its last calls.
+pub struct XuRuntime {
+    rt: Runtime,
+    driver: Driver,
+    output: String,
+    error: Option<CString>,
+}
+
+/// Result of calls that return no value.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XuStatus {
+    XU_OK = 0,
+    XU_ERROR = 1,
+}
+
+impl XuRuntime {
+    fn fail(&mut self, message: String) {
+        self.error = Some(to_c_string(message));
+    }
+
+    /// Runs `f`, turning a panic into an error. The runtime may be left
+    /// mid-run by a panic; later calls still work but see its state.
+    fn guard<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        self.error = None;
+        match catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(res) => res,
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".into());
+                Err(format!("Internal error: {message}"))
+            }
+        }
+    }
+
+    fn eval(&mut self, source: &str) -> Result<(), String> {
+        let compiled = self.driver.compile_text("<eval>", source, true)?;
+        let errors: Vec<_> = compiled
+            .diagnostics
+            .iter()
+            .filter(|d| matches!(d.severity, Severity::Error))
+            .cloned()
+            .collect();
+        if !errors.is_empty() {
+            return Err(xu_syntax::render_diagnostics(&compiled.source, &errors));
+        }
+        match self.rt.exec_executable(&compiled.executable) {
+            Ok(res) => {
+                self.output.push_str(&res.output);
+                Ok(())
+            }
+            Err(e) => {
+                let output = self.rt.take_output();
+                self.output.push_str(&output);
+                Err(e)
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, args: Vec<HostValue>) -> Result<HostValue, String> {
+        let res = self.rt.call_global_host(name, args);
+        let output = self.rt.take_output();
+        self.output.push_str(&output);
+        res
+    }
+}
+
+/// Converts to a C string, cutting `s` at an interior NUL.
+fn to_c_string(mut s: String) -> CString {
+    if let Some(nul) = s.find('\0') {
+        s.truncate(nul);
+    }
+    CString::new(s).expect("no interior NUL")
+}
+
+/// Reads a NUL-terminated UTF-8 argument.
+///
+/// # Safety
+///
+/// `s` is NULL or points to a NUL-terminated string.
+unsafe fn arg_str<'a>(s: *const c_char, what: &str) -> Result<&'a str, String> {
+    if s.is_null() {
+        return Err(format!("{what} is NULL"));
+    }
+    unsafe { CStr::from_ptr(s) }
+        .to_str()
+        .map_err(|_| format!("{what} is not valid UTF-8"))
+}
+
+/// Creates a runtime with strict variable checks and the embedded stdlib.
+/// Free it with [`xu_runtime_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn xu_runtime_new() -> *mut XuRuntime {
+    let mut rt = Runtime::new();
+    rt.set_strict_vars(true);
+    rt.set_frontend(Box::new(Driver::new()));
+    Box::into_raw(Box::new(XuRuntime { rt, driver: Driver::new(), output: String::new(), error: None }))
+}
+
+/// Frees a runtime. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `rt` is NULL or a runtime from [`xu_runtime_new`] not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_runtime_free(rt: *mut XuRuntime) {
+    if !rt.is_null() {
+        drop(unsafe { Box::from_raw(rt) });
+    }
+}
+
+/// Compiles and runs `source` as a program, with strict checks.
+///
+/// Each call is a new program: it replaces the globals of the previous one,
+/// and its functions are the ones [`xu_call`] finds afterwards. What the
+/// program prints is kept for [`xu_take_output`]. Compile errors are
+/// reported as rendered diagnostics.
+///
+/// # Safety
+///
+/// `rt` is a live runtime and `source` a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_eval(rt: *mut XuRuntime, source: *const c_char) -> XuStatus {
+    let Some(rt) = (unsafe { rt.as_mut() }) else {
+        return XuStatus::XU_ERROR;
+    };
+    let res = rt.guard(|rt| {
+        let source = unsafe { arg_str(source, "source") }?;
+        rt.eval(source)
+    });
+    match res {
+        Ok(()) => XuStatus::XU_OK,
+        Err(e) => {
+            rt.fail(e);
+            XuStatus::XU_ERROR
+        }
+    }
+}
+
+/// Calls the top-level function `name` of the last program run by
+/// [`xu_eval`] with copies of the `nargs` values in `args`, which stay owned
+/// by the caller. Returns the result, or `NULL` on error.
+///
+/// # Safety
+///
+/// `rt` is a live runtime, `name` a NUL-terminated string and `args` points
+/// to `nargs` live values (it may be NULL when `nargs` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_call(
+    rt: *mut XuRuntime,
+    name: *const c_char,
+    args: *const *const XuValue,
+    nargs: usize,
+) -> *mut XuValue {
+    let Some(rt) = (unsafe { rt.as_mut() }) else {
+        return std::ptr::null_mut();
+    };
+    let res = rt.guard(|rt| {
+        let name = unsafe { arg_str(name, "function name") }?;
+        let args = unsafe { value::values(args, nargs) }.ok_or_else(|| "argument list is NULL".to_string())?;
+        rt.call(name, args)
+    });
+    match res {
+        Ok(v) => XuValue::into_raw(v),
+        Err(e) => {
+            rt.fail(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// The message of the last failed call on `rt`, or `NULL` if the last call
+/// succeeded. The string belongs to the runtime and is valid until its next
+/// call.
+///
+/// # Safety
+///
+/// `rt` is a live runtime.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_last_error(rt: *const XuRuntime) -> *const c_char {
+    match unsafe { rt.as_ref() }.and_then(|rt| rt.error.as_ref()) {
+        Some(e) => e.as_ptr(),
+        None => std::ptr::null(),
+    }
+}
+
+/// Everything printed since the last call to this function. Free the result
+/// with [`xu_string_free`].
+///
+/// # Safety
+///
+/// `rt` is a live runtime.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_take_output(rt: *mut XuRuntime) -> *mut c_char {
+    match unsafe { rt.as_mut() } {
+        Some(rt) => to_c_string(std::mem::take(&mut rt.output)).into_raw(),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Frees a string returned by this library. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `s` is NULL or a string from this library not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
@@ -0,0 +1,276 @@
+//! Building and reading [`XuValue`] handles.
+
+use std::ffi::{CStr, c_char};
+
+use xu_runtime::HostValue;
+
+/// A copy of a script value, owned by the caller.
+pub struct XuValue(HostValue);
+
+/// What an [`XuValue`] holds. Ranges, enums and structs are `XU_OTHER`.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XuValueKind {
+    XU_UNIT = 0,
+    XU_BOOL = 1,
+    XU_INT = 2,
+    XU_FLOAT = 3,
+    XU_STR = 4,
+    XU_LIST = 5,
+    XU_TUPLE = 6,
+    XU_DICT = 7,
+    XU_OPTION = 8,
+    XU_OTHER = 9,
+}
+
+impl XuValue {
+    pub(crate) fn into_raw(v: HostValue) -> *mut XuValue {
+        Box::into_raw(Box::new(XuValue(v)))
+    }
+
+    /// Elements of lists and tuples, the value of a present option, or the
+    /// values of a dict.
+    fn items(&self) -> Option<Vec<&HostValue>> {
+        Some(match &self.0 {
+            HostValue::List(items) | HostValue::Tuple(items) => items.iter().collect(),
+            HostValue::Option(inner) => inner.iter().map(|b| b.as_ref()).collect(),
+            HostValue::Dict(entries) => entries.iter().map(|(_, v)| v).collect(),
+            _ => return None,
+        })
+    }
+}
+
+/// Copies `n` values, or `None` if `values` is NULL while `n` is not 0.
+///
+/// # Safety
+///
+/// `values` points to `n` live values.
+pub(crate) unsafe fn values(values: *const *const XuValue, n: usize) -> Option<Vec<HostValue>> {
+    if n == 0 {
+        return Some(Vec::new());
+    }
+    if values.is_null() {
+        return None;
+    }
+    unsafe { std::slice::from_raw_parts(values, n) }
+        .iter()
+        .map(|v| unsafe { v.as_ref() }.map(|v| v.0.clone()))
+        .collect()
+}
+
+/// The unit value `()`.
+#[unsafe(no_mangle)]
+pub extern "C" fn xu_value_unit() -> *mut XuValue {
+    XuValue::into_raw(HostValue::Unit)
+}
+
+/// A bool value.
+#[unsafe(no_mangle)]
+pub extern "C" fn xu_value_bool(b: bool) -> *mut XuValue {
+    XuValue::into_raw(HostValue::Bool(b))
+}
+
+/// An int value. Calls taking it fail unless it fits in 48 bits, between
+/// -2^47 and 2^47 - 1.
+#[unsafe(no_mangle)]
+pub extern "C" fn xu_value_int(i: i64) -> *mut XuValue {
+    XuValue::into_raw(HostValue::Int(i))
+}
+
+/// A float value.
+#[unsafe(no_mangle)]
+pub extern "C" fn xu_value_float(f: f64) -> *mut XuValue {
+    XuValue::into_raw(HostValue::Float(f))
+}
+
+/// A string value copied from `s`, or `NULL` if `s` is NULL or not UTF-8.
+///
+/// # Safety
+///
+/// `s` is NULL or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_str(s: *const c_char) -> *mut XuValue {
+    if s.is_null() {
+        return std::ptr::null_mut();
+    }
+    match unsafe { CStr::from_ptr(s) }.to_str() {
+        Ok(s) => XuValue::into_raw(HostValue::Str(s.to_string())),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// A list of copies of the `n` values in `items`, or `NULL` if one of them
+/// is NULL.
+///
+/// # Safety
+///
+/// `items` points to `n` values (it may be NULL when `n` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_list(items: *const *const XuValue, n: usize) -> *mut XuValue {
+    match unsafe { values(items, n) } {
+        Some(items) => XuValue::into_raw(HostValue::List(items)),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// A dict mapping copies of `keys[i]` to copies of `values[i]`, or `NULL` if
+/// one of them is NULL. Keys must be hashable (strings, ints, bools, ...)
+/// when the dict is passed to the runtime.
+///
+/// # Safety
+///
+/// `keys` and `values` each point to `n` values (they may be NULL when `n`
+/// is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_dict(
+    keys: *const *const XuValue,
+    values: *const *const XuValue,
+    n: usize,
+) -> *mut XuValue {
+    match (unsafe { self::values(keys, n) }, unsafe { self::values(values, n) }) {
+        (Some(keys), Some(values)) => XuValue::into_raw(HostValue::Dict(keys.into_iter().zip(values).collect())),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Frees a value. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `v` is NULL or a value from this library not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_free(v: *mut XuValue) {
+    if !v.is_null() {
+        drop(unsafe { Box::from_raw(v) });
+    }
+}
+
+/// What `v` holds; `NULL` is `XU_OTHER`.
+///
+/// # Safety
+///
+/// `v` is a live value.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_kind(v: *const XuValue) -> XuValueKind {
+    let Some(v) = (unsafe { v.as_ref() }) else {
+        return XuValueKind::XU_OTHER;
+    };
+    match v.0 {
+        HostValue::Unit => XuValueKind::XU_UNIT,
+        HostValue::Bool(_) => XuValueKind::XU_BOOL,
+        HostValue::Int(_) => XuValueKind::XU_INT,
+        HostValue::Float(_) => XuValueKind::XU_FLOAT,
+        HostValue::Str(_) => XuValueKind::XU_STR,
+        HostValue::List(_) => XuValueKind::XU_LIST,
+        HostValue::Tuple(_) => XuValueKind::XU_TUPLE,
+        HostValue::Dict(_) => XuValueKind::XU_DICT,
+        HostValue::Option(_) => XuValueKind::XU_OPTION,
+        HostValue::Range(..) | HostValue::Enum { .. } | HostValue::Struct { .. } => XuValueKind::XU_OTHER,
+    }
+}
+
+/// Stores the bool in `out` and returns true if `v` is a bool.
+///
+/// # Safety
+///
+/// `v` is a live value and `out` writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_as_bool(v: *const XuValue, out: *mut bool) -> bool {
+    match unsafe { v.as_ref() } {
+        Some(XuValue(HostValue::Bool(b))) if !out.is_null() => {
+            unsafe { *out = *b };
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Stores the int in `out` and returns true if `v` is an int.
+///
+/// # Safety
+///
+/// `v` is a live value and `out` writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_as_int(v: *const XuValue, out: *mut i64) -> bool {
+    match unsafe { v.as_ref() } {
+        Some(XuValue(HostValue::Int(i))) if !out.is_null() => {
+            unsafe { *out = *i };
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Stores the number in `out` and returns true if `v` is a float or an int.
+///
+/// # Safety
+///
+/// `v` is a live value and `out` writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_as_float(v: *const XuValue, out: *mut f64) -> bool {
+    let f = match unsafe { v.as_ref() } {
+        Some(XuValue(HostValue::Float(f))) => *f,
+        Some(XuValue(HostValue::Int(i))) => *i as f64,
+        _ => return false,
+    };
+    if out.is_null() {
+        return false;
+    }
+    unsafe { *out = f };
+    true
+}
+
+/// A copy of the string in `v`, or `NULL` if `v` is not a string or contains
+/// a NUL. Free it with `xu_string_free`.
+///
+/// # Safety
+///
+/// `v` is a live value.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_as_str(v: *const XuValue) -> *mut c_char {
+    match unsafe { v.as_ref() } {
+        Some(XuValue(HostValue::Str(s))) => match std::ffi::CString::new(s.as_str()) {
+            Ok(s) => s.into_raw(),
+            Err(_) => std::ptr::null_mut(),
+        },
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// The number of elements of a list or tuple, entries of a dict, or values
+/// of an option (0 or 1). Other values have none.
+///
+/// # Safety
+///
+/// `v` is a live value.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_len(v: *const XuValue) -> usize {
+    unsafe { v.as_ref() }.and_then(XuValue::items).map_or(0, |items| items.len())
+}
+
+/// A copy of element `i` of a list or tuple, the value of entry `i` of a
+/// dict, or the value of a present option (`i` = 0). `NULL` if there is no
+/// such element.
+///
+/// # Safety
+///
+/// `v` is a live value.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_at(v: *const XuValue, i: usize) -> *mut XuValue {
+    let item = unsafe { v.as_ref() }.and_then(XuValue::items).and_then(|items| items.get(i).map(|v| (*v).clone()));
+    item.map_or(std::ptr::null_mut(), XuValue::into_raw)
+}
+
+/// A copy of the key of entry `i` of a dict, or `NULL`.
+///
+/// # Safety
+///
+/// `v` is a live value.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xu_value_key_at(v: *const XuValue, i: usize) -> *mut XuValue {
+    match unsafe { v.as_ref() } {
+        Some(XuValue(HostValue::Dict(entries))) => entries.get(i).map_or(std::ptr::null_mut(), |(k, _)| XuValue::into_raw(k.clone())),
+        _ => std::ptr::null_mut(),
+    }
+}
@@ -0,0 +1,192 @@
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use xu_capi::*;
+
+struct Rt(*mut XuRuntime);
+
+impl Rt {
+    fn new() -> Self {
+        Rt(xu_runtime_new())
+    }
+
+    fn eval(&self, src: &str) -> Result<(), String> {
+        let src = CString::new(src).unwrap();
+        match unsafe { xu_eval(self.0, src.as_ptr()) } {
+            XuStatus::XU_OK => Ok(()),
+            XuStatus::XU_ERROR => Err(self.error().expect("error message")),
+        }
+    }
+
+    fn call(&self, name: &str, args: &[*mut XuValue]) -> Result<*mut XuValue, String> {
+        let name = CString::new(name).unwrap();
+        let args: Vec<*const XuValue> = args.iter().map(|a| *a as *const _).collect();
+        let v = unsafe { xu_call(self.0, name.as_ptr(), args.as_ptr(), args.len()) };
+        if v.is_null() { Err(self.error().expect("error message")) } else { Ok(v) }
+    }
+
+    fn error(&self) -> Option<String> {
+        let e = unsafe { xu_last_error(self.0) };
+        (!e.is_null()).then(|| unsafe { CStr::from_ptr(e) }.to_str().unwrap().to_string())
+    }
+
+    fn output(&self) -> String {
+        unsafe { take_string(xu_take_output(self.0)) }.unwrap()
+    }
+}
+
+impl Drop for Rt {
+    fn drop(&mut self) {
+        unsafe { xu_runtime_free(self.0) };
+    }
+}
+
+unsafe fn take_string(s: *mut std::ffi::c_char) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+    let text = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+    unsafe { xu_string_free(s) };
+    Some(text)
+}
+
+fn int(v: *const XuValue) -> Option<i64> {
+    let mut out = 0;
+    unsafe { xu_value_as_int(v, &mut out) }.then_some(out)
+}
+
+fn str_value(s: &str) -> *mut XuValue {
+    let s = CString::new(s).unwrap();
+    unsafe { xu_value_str(s.as_ptr()) }
+}
+
+fn free(values: &[*mut XuValue]) {
+    for v in values {
+        unsafe { xu_value_free(*v) };
+    }
+}
+
+#[test]
+fn eval_defines_functions_to_call() {
+    let rt = Rt::new();
+    rt.eval("println(\"loaded\")\nfunc add(a: int, b: int) -> int { return a + b }\nfunc shout(s: string) -> string {\n  println(s)\n  return s + \"!\"\n}\n")
+        .unwrap();
+    assert_eq!(rt.error(), None);
+    assert_eq!(rt.output(), "loaded\n");
+
+    let args = [xu_value_int(2), xu_value_int(40)];
+    let sum = rt.call("add", &args).unwrap();
+    assert_eq!(unsafe { xu_value_kind(sum) }, XuValueKind::XU_INT);
+    assert_eq!(int(sum), Some(42));
+
+    let word = str_value("hey");
+    let loud = rt.call("shout", &[word]).unwrap();
+    assert_eq!(unsafe { take_string(xu_value_as_str(loud)) }.as_deref(), Some("hey!"));
+    assert_eq!(rt.output(), "hey\n");
+    assert_eq!(rt.output(), "");
+
+    // A new program replaces the functions of the last one.
+    rt.eval("func add(a: int, b: int) -> int { return a * b }\n").unwrap();
+    let product = rt.call("add", &args).unwrap();
+    assert_eq!(int(product), Some(80));
+    assert!(rt.call("shout", &[word]).unwrap_err().contains("shout"));
+    free(&[args[0], args[1], sum, word, loud, product]);
+}
+
+#[test]
+fn failures_leave_an_error_message() {
+    let rt = Rt::new();
+    let err = rt.eval("let = 1\n").unwrap_err();
+    assert!(err.contains("<eval>"), "{err}");
+
+    let err = rt.eval("println(\"before\")\nlet z = 0\nprintln(1 / z)\n").unwrap_err();
+    assert!(err.contains("zero"), "{err}");
+    assert_eq!(rt.output(), "before\n");
+
+    rt.eval("func f(x: int) -> float { return 10 / x }\n").unwrap();
+    assert_eq!(rt.error(), None);
+    assert!(rt.call("g", &[]).unwrap_err().contains("g"));
+    let zero = xu_value_int(0);
+    assert!(rt.call("f", &[zero]).unwrap_err().contains("zero"));
+    let one = xu_value_int(1);
+    let ten = rt.call("f", &[one]).unwrap();
+    assert_eq!(rt.error(), None);
+
+    assert_eq!(unsafe { xu_eval(rt.0, ptr::null()) }, XuStatus::XU_ERROR);
+    assert!(rt.error().unwrap().contains("NULL"));
+    assert_eq!(unsafe { xu_eval(ptr::null_mut(), ptr::null()) }, XuStatus::XU_ERROR);
+    assert!(unsafe { xu_call(rt.0, ptr::null(), ptr::null(), 0) }.is_null());
+    let name = CString::new("f").unwrap();
+    assert!(unsafe { xu_call(rt.0, name.as_ptr(), ptr::null(), 1) }.is_null());
+    assert!(rt.error().unwrap().contains("NULL"));
+    let huge = xu_value_int(1 << 50);
+    assert!(rt.call("f", &[huge]).unwrap_err().contains("48 bits"));
+    free(&[zero, one, ten, huge]);
+}
+
+#[test]
+fn values_convert_both_ways() {
+    let rt = Rt::new();
+    rt.eval(
+        "P has {\n  x: int\n  y: int\n}\n\
+         func echo(v: any) -> any { return v }\n\
+         func stats(xs: [int], d: {string: float}) -> (int, float) { return (xs.length, d[\"pi\"]) }\n\
+         func find(xs: [int], x: int) -> Option[int] { for i in 0..xs.length { if xs[i] == x { return Option#some(i) } }\n return Option#none }\n\
+         func point() -> P { return P{x: 1, y: 2} }\n",
+    )
+    .unwrap();
+
+    let items = [xu_value_int(3), xu_value_int(5), xu_value_int(8)];
+    let list_items: Vec<*const XuValue> = items.iter().map(|v| *v as *const _).collect();
+    let list = unsafe { xu_value_list(list_items.as_ptr(), 3) };
+    let key = str_value("pi");
+    let pi = xu_value_float(3.5);
+    let dict = unsafe { xu_value_dict(&(key as *const _), &(pi as *const _), 1) };
+
+    let pair = rt.call("stats", &[list, dict]).unwrap();
+    assert_eq!(unsafe { xu_value_kind(pair) }, XuValueKind::XU_TUPLE);
+    assert_eq!(unsafe { xu_value_len(pair) }, 2);
+    let (n, f) = unsafe { (xu_value_at(pair, 0), xu_value_at(pair, 1)) };
+    assert_eq!(int(n), Some(3));
+    let mut out = 0.0;
+    assert!(unsafe { xu_value_as_float(f, &mut out) });
+    assert_eq!(out, 3.5);
+    assert!(unsafe { xu_value_as_float(n, &mut out) });
+    assert_eq!(out, 3.0);
+    assert!(unsafe { xu_value_at(pair, 2) }.is_null());
+
+    let echoed = rt.call("echo", &[dict]).unwrap();
+    assert_eq!(unsafe { xu_value_kind(echoed) }, XuValueKind::XU_DICT);
+    assert_eq!(unsafe { xu_value_len(echoed) }, 1);
+    let (k, v) = unsafe { (xu_value_key_at(echoed, 0), xu_value_at(echoed, 0)) };
+    assert_eq!(unsafe { take_string(xu_value_as_str(k)) }.as_deref(), Some("pi"));
+    assert!(unsafe { xu_value_as_float(v, &mut out) });
+
+    let five = xu_value_int(5);
+    let found = rt.call("find", &[list, five]).unwrap();
+    assert_eq!(unsafe { xu_value_kind(found) }, XuValueKind::XU_OPTION);
+    assert_eq!(unsafe { xu_value_len(found) }, 1);
+    let index = unsafe { xu_value_at(found, 0) };
+    assert_eq!(int(index), Some(1));
+    let four = xu_value_int(4);
+    let missing = rt.call("find", &[list, four]).unwrap();
+    assert_eq!(unsafe { xu_value_len(missing) }, 0);
+
+    let p = rt.call("point", &[]).unwrap();
+    assert_eq!(unsafe { xu_value_kind(p) }, XuValueKind::XU_OTHER);
+    // Handles are copies: they outlive the runtime.
+    drop(rt);
+    assert_eq!(unsafe { xu_value_len(list) }, 3);
+
+    let (unit, yes) = (xu_value_unit(), xu_value_bool(true));
+    assert_eq!(unsafe { xu_value_kind(unit) }, XuValueKind::XU_UNIT);
+    let mut b = false;
+    assert!(unsafe { xu_value_as_bool(yes, &mut b) } && b);
+    assert!(!unsafe { xu_value_as_bool(unit, &mut b) });
+    assert_eq!(int(yes), None);
+    assert!(unsafe { xu_value_as_str(yes) }.is_null());
+    assert!(unsafe { xu_value_str([0xffu8, 0].as_ptr().cast()) }.is_null());
+    assert!(unsafe { xu_value_list(&ptr::null(), 1) }.is_null());
+    free(&items);
+    free(&[list, key, pi, dict, pair, n, f, echoed, k, v, five, found, index, four, missing, p, unit, yes]);
+}
@@ -0,0 +1,40 @@
+//! `include/xu.h` is generated by cbindgen, which is not run by the build;
+//! this keeps it from falling behind the Rust definitions.
+
+use std::path::Path;
+
+fn read(path: &str) -> String {
+    std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap()
+}
+
+#[test]
+fn header_declares_every_exported_item() {
+    let header = read("include/xu.h");
+    let sources = read("src/lib.rs") + &read("src/value.rs");
+    let exported: Vec<&str> = sources
+        .lines()
+        .filter_map(|line| line.split_once("extern \"C\" fn ").map(|(_, rest)| rest))
+        .map(|rest| &rest[..rest.find('(').unwrap()])
+        .collect();
+    let declared: Vec<&str> = header
+        .lines()
+        .filter(|line| !line.starts_with("//"))
+        .filter_map(|line| line.find("xu_").map(|i| &line[i..]))
+        .filter_map(|rest| rest.find('(').map(|end| &rest[..end]))
+        .collect();
+    assert!(exported.len() >= 20, "found only {} functions", exported.len());
+    for name in &exported {
+        assert!(declared.contains(name), "xu.h lacks {name}");
+    }
+    for name in &declared {
+        assert!(exported.contains(name), "xu.h declares {name}, which is gone");
+    }
+
+    for item in ["XuRuntime", "XuValue", "XuStatus", "XuValueKind"] {
+        assert!(header.contains(&format!("}} {item};")) || header.contains(&format!("typedef struct {item} {item};")));
+    }
+    for line in sources.lines().map(str::trim).filter(|l| l.starts_with("XU_")) {
+        let variant = line.trim_end_matches(',');
+        assert!(header.contains(&format!("  {variant},")), "xu.h lacks {variant}");
+    }
+}
@@ -0,0 +1,90 @@
+//! `xu bench`: prints the timings of a script's `bench` blocks and compares
+//! them with a baseline saved by an earlier run.
+
+use xu_runtime::BenchResult;
+
+/// The results as a JSON object; also the baseline file format.
+pub(crate) fn to_json(results: &[BenchResult]) -> String {
+    let benchmarks: Vec<serde_json::Value> = results
+        .iter()
+        .map(|r| {
+            serde_json::json!({
+                "name": r.name,
+                "iterations": r.iterations,
+                "mean_ms": r.mean_ms,
+                "median_ms": r.median_ms,
+                "p99_ms": r.p99_ms,
+                "allocations": r.allocations,
+            })
+        })
+        .collect();
+    serde_json::json!({ "benchmarks": benchmarks }).to_string()
+}
+
+/// Reads a baseline written by [`to_json`].
+pub(crate) fn load_baseline(path: &str) -> Result<Vec<BenchResult>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| format!("{path}: {e}"))?;
+    let malformed = || format!("{path}: not a bench baseline");
+    let entries = json["benchmarks"].as_array().ok_or_else(malformed)?;
+    entries
+        .iter()
+        .map(|e| {
+            Some(BenchResult {
+                name: e["name"].as_str()?.to_string(),
+                iterations: e["iterations"].as_u64()? as usize,
+                mean_ms: e["mean_ms"].as_f64()?,
+                median_ms: e["median_ms"].as_f64()?,
+                p99_ms: e["p99_ms"].as_f64()?,
+                allocations: e["allocations"].as_u64()?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(malformed)
+}
+
+/// Change of the mean time relative to the baseline, in percent.
+fn change(result: &BenchResult, baseline: &[BenchResult]) -> Option<f64> {
+    let base = baseline.iter().find(|b| b.name == result.name)?;
+    (base.mean_ms > 0.0).then(|| (result.mean_ms - base.mean_ms) / base.mean_ms * 100.0)
+}
+
+/// One row per benchmark; with a baseline, a last column with the change of
+/// the mean time (`new` for benchmarks the baseline lacks).
+pub(crate) fn report(results: &[BenchResult], baseline: Option<&[BenchResult]>) -> String {
+    let width = results.iter().map(|r| r.name.chars().count()).max().unwrap_or(0).max(5);
+    let mut out = format!(
+        "{:<width$} {:>10} {:>10} {:>10} {:>10}",
+        "bench", "mean ms", "median ms", "p99 ms", "allocs"
+    );
+    if baseline.is_some() {
+        out.push_str(&format!(" {:>12}", "vs baseline"));
+    }
+    out.push('\n');
+    for r in results {
+        out.push_str(&format!(
+            "{:<width$} {:>10.3} {:>10.3} {:>10.3} {:>10}",
+            r.name, r.mean_ms, r.median_ms, r.p99_ms, r.allocations
+        ));
+        if let Some(baseline) = baseline {
+            let cell = match change(r, baseline) {
+                Some(pct) => format!("{pct:+.1}%"),
+                None => "new".to_string(),
+            };
+            out.push_str(&format!(" {cell:>12}"));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Benchmarks whose mean time grew by more than `threshold` percent.
+pub(crate) fn regressions(results: &[BenchResult], baseline: &[BenchResult], threshold: f64) -> Vec<String> {
+    results
+        .iter()
+        .filter_map(|r| {
+            let pct = change(r, baseline)?;
+            (pct > threshold).then(|| format!("{} is {pct:.1}% slower than the baseline", r.name))
+        })
+        .collect()
+}
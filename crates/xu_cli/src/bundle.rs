@@ -0,0 +1,76 @@
+//! `xu bundle`: writes a copy of the running `xu` binary with a compiled
+//! program appended, and runs that program when such a copy starts.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use xu_ir::bundle::{self, Bundle, TRAILER_LEN, TRAILER_MAGIC};
+use xu_runtime::Runtime;
+
+/// The bundle appended to the running binary, if it has one. Only the
+/// trailer is read from binaries without a bundle.
+pub(crate) fn attached() -> Option<Result<Bundle, String>> {
+    let exe = std::env::current_exe().ok()?;
+    let mut file = std::fs::File::open(&exe).ok()?;
+    file.seek(SeekFrom::End(-(TRAILER_LEN as i64))).ok()?;
+    let mut trailer = [0u8; TRAILER_LEN];
+    file.read_exact(&mut trailer).ok()?;
+    if !trailer.ends_with(TRAILER_MAGIC) {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    let read = file.rewind().and_then(|_| file.read_to_end(&mut bytes));
+    Some(match read {
+        Ok(_) => bundle::decode(&bytes).map_err(|e| format!("{}: {e}", exe.display())),
+        Err(e) => Err(format!("{}: {e}", exe.display())),
+    })
+}
+
+/// Runs the program of an attached bundle with `args` as its arguments and
+/// exits with its status.
+pub(crate) fn run(bundle: Bundle, args: Vec<String>) -> ! {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_args(args);
+    let entry = match rt.load_bundle(bundle) {
+        Ok(entry) => entry,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+    rt.set_output_streams(Box::new(xu_runtime::StdStreams { merged: false }));
+    rt.set_terminal(Box::new(xu_runtime::StdTerminal::detect()));
+    rt.set_signal_source(Box::new(xu_runtime::OsSignals));
+    match rt.exec_executable(&entry) {
+        Ok(res) => std::process::exit(res.exit_code.unwrap_or(0)),
+        Err(e) if rt.panicked() => {
+            eprintln!("{e}");
+            std::process::exit(101);
+        }
+        Err(e) => {
+            eprintln!("RuntimeError: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Writes the running binary, without any bundle it already carries, plus
+/// `bundle` to `out` and makes it executable. Returns the size written.
+pub(crate) fn write(bundle: &Bundle, out: &Path) -> Result<usize, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Cannot locate the xu binary: {e}"))?;
+    let mut bytes = std::fs::read(&exe).map_err(|e| format!("{}: {e}", exe.display()))?;
+    if bundle::has_bundle(&bytes) {
+        let info = bundle::verify(&bytes).map_err(|e| format!("{}: {e}", exe.display()))?;
+        bytes.truncate(info.runtime_len);
+    }
+    bytes.extend(bundle::encode(bundle));
+    std::fs::write(out, &bytes).map_err(|e| format!("{}: {e}", out.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(out, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("{}: {e}", out.display()))?;
+    }
+    Ok(bytes.len())
+}
@@ -0,0 +1,298 @@
+//! Semantics conformance suite: programs with recorded output, run on every
+//! way a Xu program can execute and summarized as a parity matrix.
+//!
+//! A case is a `<name>.xu` file with the standard output it must print in
+//! `<name>.expected` next to it; other files (such as modules the cases
+//! import) live in subdirectories. Each case runs on:
+//!
+//! ```text
+//! ast   the AST executor
+//! vm    the bytecode VM, replaying the inputs the AST run read
+//! lua   `xu codegen lua`, run with `lua`
+//! c     `xu codegen c`, built with `cc` and run
+//! ```
+//!
+//! A backend whose tool is not installed is skipped, and a program a code
+//! generator rejects is reported as unsupported rather than failed, so the
+//! matrix also shows which language features the generators lack. Setting
+//! `XU_UPDATE_CONFORMANCE=1` (or passing `update` to [`run_all`]) rewrites
+//! the expected files from the AST executor instead of comparing.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use xu_driver::{CodegenOptions, CodegenTarget, Driver};
+use xu_ir::Executable;
+use xu_runtime::{EngineRun, Runtime};
+use xu_syntax::Severity;
+
+/// Extension of the file holding a case's expected output.
+pub const EXPECTED_EXT: &str = "expected";
+
+/// Environment variable that switches the suite to update mode.
+pub const UPDATE_ENV: &str = "XU_UPDATE_CONFORMANCE";
+
+/// One way of executing a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Ast,
+    Vm,
+    Lua,
+    C,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 4] = [Backend::Ast, Backend::Vm, Backend::Lua, Backend::C];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Ast => "ast",
+            Backend::Vm => "vm",
+            Backend::Lua => "lua",
+            Backend::C => "c",
+        }
+    }
+}
+
+/// How one case fared on one backend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    /// Printed something else, failed, or could not be built.
+    Fail(String),
+    /// The code generator rejected the program.
+    Unsupported(String),
+    /// The tool the backend needs is not installed.
+    Skipped,
+}
+
+/// The results of one case, in the order of [`Backend::ALL`].
+#[derive(Clone, Debug)]
+pub struct CaseResult {
+    pub name: String,
+    pub statuses: Vec<(Backend, Status)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub cases: Vec<CaseResult>,
+}
+
+impl Report {
+    /// Every failed case and backend with what went wrong.
+    pub fn failures(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for case in &self.cases {
+            for (backend, status) in &case.statuses {
+                if let Status::Fail(why) = status {
+                    out.push(format!("{} [{}]: {why}", case.name, backend.name()));
+                }
+            }
+        }
+        out
+    }
+
+    /// The parity matrix: one row per case, one column per backend, and a
+    /// last row counting the cases each backend passed.
+    pub fn matrix(&self) -> String {
+        let width = self
+            .cases
+            .iter()
+            .map(|c| c.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(6);
+        let mut rows = vec![row("case", width, Backend::ALL.map(Backend::name))];
+        for case in &self.cases {
+            let cells = case.statuses.iter().map(|(_, status)| match status {
+                Status::Pass => "ok",
+                Status::Fail(_) => "FAIL",
+                Status::Unsupported(_) => "-",
+                Status::Skipped => "skip",
+            });
+            rows.push(row(&case.name, width, cells));
+        }
+        let passed = (0..Backend::ALL.len()).map(|i| {
+            let n = self
+                .cases
+                .iter()
+                .filter(|c| c.statuses[i].1 == Status::Pass)
+                .count();
+            format!("{n}/{}", self.cases.len())
+        });
+        rows.push(row("passed", width, passed));
+        rows.concat()
+    }
+}
+
+fn row<S: AsRef<str>>(label: &str, width: usize, cells: impl IntoIterator<Item = S>) -> String {
+    let mut out = format!("{label:width$}");
+    for cell in cells {
+        out.push_str(&format!("  {:7}", cell.as_ref()));
+    }
+    format!("{}\n", out.trim_end())
+}
+
+/// Case programs directly under `root`, sorted by name.
+pub fn case_files(root: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(root).map_err(|e| format!("{}: {e}", root.display()))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "xu"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Runs every case under `root` on every backend. In update mode (`update`
+/// or `XU_UPDATE_CONFORMANCE=1`) the expected files are rewritten from the
+/// AST executor first, so the report shows how the others compare.
+pub fn run_all(root: &Path, update: bool) -> Result<Report, String> {
+    let update = update || std::env::var(UPDATE_ENV).is_ok_and(|v| v == "1");
+    let scratch = std::env::temp_dir().join(format!("xu_conformance_{}", std::process::id()));
+    fs::create_dir_all(&scratch).map_err(|e| format!("{}: {e}", scratch.display()))?;
+    let mut report = Report::default();
+    let result = case_files(root).and_then(|files| {
+        for file in files {
+            report.cases.push(run_case(&file, &scratch, update)?);
+        }
+        Ok(())
+    });
+    let _ = fs::remove_dir_all(&scratch);
+    result.map(|()| report)
+}
+
+/// Runs the case `file`, using `scratch` for generated programs.
+pub fn run_case(file: &Path, scratch: &Path, update: bool) -> Result<CaseResult, String> {
+    let name = file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (ast, vm) = run_engines(file)?;
+    let expected_path = file.with_extension(EXPECTED_EXT);
+    if update {
+        if let Some(e) = &ast.error {
+            return Err(format!("{}: the AST executor failed: {e}", file.display()));
+        }
+        fs::write(&expected_path, &ast.output)
+            .map_err(|e| format!("{}: {e}", expected_path.display()))?;
+    }
+    let expected = fs::read_to_string(&expected_path)
+        .map_err(|e| format!("{}: {e}", expected_path.display()))?;
+    let mut statuses = Vec::with_capacity(Backend::ALL.len());
+    for backend in Backend::ALL {
+        let status = match backend {
+            Backend::Ast => engine_status(&ast, &expected),
+            Backend::Vm => engine_status(&vm, &expected),
+            Backend::Lua => generated_status(file, scratch, CodegenTarget::Lua, &expected),
+            Backend::C => generated_status(file, scratch, CodegenTarget::C, &expected),
+        };
+        statuses.push((backend, status));
+    }
+    Ok(CaseResult { name, statuses })
+}
+
+fn run_engines(file: &Path) -> Result<(EngineRun, EngineRun), String> {
+    let path = file.to_string_lossy();
+    let compiled = Driver::new().compile_file(&path, true)?;
+    let errors: Vec<_> = compiled
+        .diagnostics
+        .iter()
+        .filter(|d| matches!(d.severity, Severity::Error))
+        .cloned()
+        .collect();
+    if !errors.is_empty() {
+        return Err(xu_syntax::render_diagnostics(&compiled.source, &errors));
+    }
+    let Executable::Bytecode(program) = &compiled.executable else {
+        return Err(format!("{path}: no bytecode to run on the VM"));
+    };
+    let comparison = xu_runtime::compare_engines(program, || {
+        let mut rt = Runtime::new();
+        rt.set_frontend(Box::new(Driver::new()));
+        rt.set_entry_path(&path).expect("set entry path");
+        rt
+    });
+    Ok((comparison.ast, comparison.vm))
+}
+
+fn engine_status(run: &EngineRun, expected: &str) -> Status {
+    match &run.error {
+        Some(e) => Status::Fail(format!("RuntimeError: {e}")),
+        None => output_status(&run.output, expected),
+    }
+}
+
+fn output_status(actual: &str, expected: &str) -> Status {
+    if actual == expected {
+        return Status::Pass;
+    }
+    // The first differing line is usually the one that explains the rest.
+    let (mut a, mut e) = (actual.lines(), expected.lines());
+    for line in 1.. {
+        match (e.next(), a.next()) {
+            (x, y) if x == y && x.is_some() => continue,
+            (x, y) => {
+                let show =
+                    |l: Option<&str>| l.map_or("<end of output>".to_string(), |l| format!("{l:?}"));
+                return Status::Fail(format!(
+                    "line {line}: expected {}, got {}",
+                    show(x),
+                    show(y)
+                ));
+            }
+        }
+    }
+    unreachable!("outputs differ, so some line does")
+}
+
+fn generated_status(file: &Path, scratch: &Path, target: CodegenTarget, expected: &str) -> Status {
+    let opts = CodegenOptions {
+        inject_runtime: true,
+    };
+    let code = match Driver::new().codegen(&file.to_string_lossy(), true, target, &opts) {
+        Ok(code) => code,
+        Err(e) => return Status::Unsupported(e),
+    };
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let source = scratch.join(format!("{stem}.{}", target.name()));
+    if let Err(e) = fs::write(&source, code) {
+        return Status::Fail(format!("{}: {e}", source.display()));
+    }
+    let run = match target {
+        CodegenTarget::Lua => Command::new("lua").arg(&source).output(),
+        CodegenTarget::C => {
+            let exe = scratch.join(stem.as_ref());
+            let build = Command::new("cc")
+                .args(["-std=c99", "-O1", "-o"])
+                .arg(&exe)
+                .arg(&source)
+                .arg("-lm")
+                .output();
+            match build {
+                Ok(b) if !b.status.success() => {
+                    return Status::Fail(format!(
+                        "cc failed:\n{}",
+                        String::from_utf8_lossy(&b.stderr)
+                    ));
+                }
+                Ok(_) => Command::new(&exe).output(),
+                Err(e) => Err(e),
+            }
+        }
+    };
+    match run {
+        Err(e) if e.kind() == ErrorKind::NotFound => Status::Skipped,
+        Err(e) => Status::Fail(e.to_string()),
+        Ok(out) if !out.status.success() => Status::Fail(
+            String::from_utf8_lossy(&out.stderr)
+                .lines()
+                .next()
+                .unwrap_or("failed")
+                .to_string(),
+        ),
+        Ok(out) => output_status(&String::from_utf8_lossy(&out.stdout), expected),
+    }
+}
@@ -0,0 +1,218 @@
+//! Fixture-based CLI tests: run the real `xu` binary and compare its
+//! behavior with files recorded next to the program.
+//!
+//! A fixture is a directory:
+//!
+//! ```text
+//! <name>/
+//!   main.xu                    program
+//!   args                       optional: command and flags, default `run`
+//!   expected.stdout            expected standard output
+//!   expected.stderr            expected standard error
+//!   expected.exit              expected exit code
+//!   expected.diagnostics.json  optional: `xu check --json` output
+//! ```
+//!
+//! The binary runs with the fixture directory as working directory and
+//! `main.xu` as a relative path, so recorded output does not depend on where
+//! the checkout lives. Setting `XU_UPDATE_FIXTURES=1` (or
+//! [`Harness::update`]) rewrites the expected files from the actual run
+//! instead of comparing.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Program file run in each fixture.
+pub const PROGRAM: &str = "main.xu";
+
+/// Environment variable that switches the harness to update mode.
+pub const UPDATE_ENV: &str = "XU_UPDATE_FIXTURES";
+
+/// What one run of the binary produced (or is expected to produce).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    /// `xu check --json` output; `None` when the fixture does not record it.
+    pub diagnostics: Option<String>,
+}
+
+/// Runs fixtures against one `xu` binary.
+#[derive(Clone, Debug)]
+pub struct Harness {
+    bin: PathBuf,
+    update: bool,
+}
+
+impl Harness {
+    /// A harness for the binary at `bin`. Update mode follows `XU_UPDATE_FIXTURES`.
+    pub fn new(bin: impl Into<PathBuf>) -> Self {
+        Self {
+            bin: bin.into(),
+            update: std::env::var(UPDATE_ENV).is_ok_and(|v| v == "1"),
+        }
+    }
+
+    /// Forces update mode on or off.
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Runs one fixture directory. In update mode the expected files are
+    /// rewritten; otherwise mismatches are reported as an error.
+    pub fn run(&self, dir: &Path) -> Result<(), String> {
+        let args = read_args(dir)?;
+        let record_diags = self.update || dir.join("expected.diagnostics.json").exists();
+        let actual = self.execute(dir, &args, record_diags)?;
+        if self.update {
+            return write_expected(dir, &actual);
+        }
+        let expected = read_expected(dir)?;
+        let mut diffs = Vec::new();
+        if actual.exit_code != expected.exit_code {
+            diffs.push(format!(
+                "exit code: expected {}, got {}",
+                expected.exit_code, actual.exit_code
+            ));
+        }
+        for (what, want, got) in [
+            ("stdout", &expected.stdout, &actual.stdout),
+            ("stderr", &expected.stderr, &actual.stderr),
+        ] {
+            if want != got {
+                diffs.push(format!("{what} mismatch:\n--- expected\n{want}--- actual\n{got}"));
+            }
+        }
+        if let (Some(want), Some(got)) = (&expected.diagnostics, &actual.diagnostics) {
+            if want != got {
+                diffs.push(format!(
+                    "diagnostics mismatch:\n--- expected\n{want}--- actual\n{got}"
+                ));
+            }
+        }
+        if diffs.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("fixture {} failed:\n{}", dir.display(), diffs.join("\n")))
+        }
+    }
+
+    /// Runs every fixture directly under `root` (directories containing
+    /// `main.xu`, in name order) and returns how many ran. All fixtures run
+    /// even when some fail; the error lists every failure.
+    pub fn run_all(&self, root: &Path) -> Result<usize, String> {
+        let dirs = fixture_dirs(root)?;
+        let failures: Vec<String> = dirs.iter().filter_map(|d| self.run(d).err()).collect();
+        if failures.is_empty() {
+            Ok(dirs.len())
+        } else {
+            Err(format!(
+                "{} of {} fixtures failed\n\n{}",
+                failures.len(),
+                dirs.len(),
+                failures.join("\n\n")
+            ))
+        }
+    }
+
+    fn execute(&self, dir: &Path, args: &[String], diags: bool) -> Result<Outcome, String> {
+        let output = Command::new(&self.bin)
+            .args(args)
+            .arg(PROGRAM)
+            .current_dir(dir)
+            .output()
+            .map_err(|e| format!("Failed to run {}: {e}", self.bin.display()))?;
+        let diagnostics = if diags {
+            // Same flags as the fixture, with `check --json` as the command.
+            let flags = args.iter().skip(1).filter(|a| a.starts_with("--"));
+            let out = Command::new(&self.bin)
+                .args(["check", "--json"])
+                .args(flags)
+                .arg(PROGRAM)
+                .current_dir(dir)
+                .output()
+                .map_err(|e| format!("Failed to run {}: {e}", self.bin.display()))?;
+            Some(normalize(&out.stdout))
+        } else {
+            None
+        };
+        Ok(Outcome {
+            stdout: normalize(&output.stdout),
+            stderr: normalize(&output.stderr),
+            exit_code: output.status.code().unwrap_or(-1),
+            diagnostics,
+        })
+    }
+}
+
+/// Fixture directories directly under `root`, sorted by name.
+pub fn fixture_dirs(root: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(root).map_err(|e| format!("{}: {e}", root.display()))?;
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.join(PROGRAM).is_file())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn read_args(dir: &Path) -> Result<Vec<String>, String> {
+    let path = dir.join("args");
+    if !path.exists() {
+        return Ok(vec!["run".to_string()]);
+    }
+    let text = read(&path)?;
+    let args: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+    if args.is_empty() {
+        return Err(format!("{}: empty args file", path.display()));
+    }
+    Ok(args)
+}
+
+fn read_expected(dir: &Path) -> Result<Outcome, String> {
+    let exit = read(&dir.join("expected.exit"))?;
+    let exit_code = exit
+        .trim()
+        .parse()
+        .map_err(|_| format!("{}: invalid exit code {exit:?}", dir.join("expected.exit").display()))?;
+    let diags = dir.join("expected.diagnostics.json");
+    Ok(Outcome {
+        stdout: read(&dir.join("expected.stdout"))?,
+        stderr: read(&dir.join("expected.stderr"))?,
+        exit_code,
+        diagnostics: if diags.exists() { Some(read(&diags)?) } else { None },
+    })
+}
+
+fn write_expected(dir: &Path, o: &Outcome) -> Result<(), String> {
+    let write = |name: &str, text: &str| {
+        let path = dir.join(name);
+        fs::write(&path, text).map_err(|e| format!("{}: {e}", path.display()))
+    };
+    write("expected.stdout", &o.stdout)?;
+    write("expected.stderr", &o.stderr)?;
+    write("expected.exit", &format!("{}\n", o.exit_code))?;
+    match &o.diagnostics {
+        Some(d) if !d.is_empty() => write("expected.diagnostics.json", d),
+        _ => {
+            let path = dir.join("expected.diagnostics.json");
+            if path.exists() {
+                fs::remove_file(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map(|s| s.replace("\r\n", "\n"))
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn normalize(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).replace("\r\n", "\n")
+}
@@ -0,0 +1,132 @@
+//! `xu init`: project scaffolding.
+//!
+//! Creates the standard project layout:
+//!
+//! ```text
+//! <name>/
+//!   xu.toml
+//!   .gitignore
+//!   src/main.xu      (app / cli)   src/lib.xu (lib)
+//!   tests/
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Template {
+    App,
+    Lib,
+    Cli,
+}
+
+impl Template {
+    fn kind(self) -> &'static str {
+        match self {
+            Template::App => "app",
+            Template::Lib => "lib",
+            Template::Cli => "cli",
+        }
+    }
+
+    fn entry(self) -> &'static str {
+        match self {
+            Template::Lib => "src/lib.xu",
+            Template::App | Template::Cli => "src/main.xu",
+        }
+    }
+}
+
+const GITIGNORE: &str = "/target/\n*.xuc\n.DS_Store\n";
+
+const APP_MAIN: &str = r#"func main() {
+    println("Hello from {{name}}!")
+}
+"#;
+
+const CLI_MAIN: &str = r#"func main() {
+    let args = os_args()
+    if args.length < 2 {
+        println("usage: {{name}} <name>")
+    } else {
+        println("Hello, {args[1]}!")
+    }
+}
+"#;
+
+const LIB_SRC: &str = r#"pub func greet(name: string) -> string {
+    return "Hello, {name}!"
+}
+"#;
+
+const LIB_TEST: &str = r#"use "../src/lib.xu" as lib
+
+func main() {
+    __builtin_assert(lib.greet("xu") == "Hello, xu!", "greet")
+    println("ok")
+}
+"#;
+
+const APP_TEST: &str = r#"func main() {
+    __builtin_assert(1 + 1 == 2, "arithmetic")
+    println("ok")
+}
+"#;
+
+/// Creates a new project at `dir`. Fails if `dir` exists and is not empty.
+pub fn init_project(dir: &Path, template: Template) -> Result<(), String> {
+    if dir.exists()
+        && fs::read_dir(dir)
+            .map_err(|e| format!("{}: {e}", dir.display()))?
+            .next()
+            .is_some()
+    {
+        return Err(format!("Destination is not empty: {}", dir.display()));
+    }
+    let name = project_name(dir)?;
+
+    let manifest = format!(
+        "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nkind = \"{}\"\nentry = \"{}\"\n# prelude = \"src/prelude.xu\"\n\n[dependencies]\n",
+        template.kind(),
+        template.entry()
+    );
+    let (src, test) = match template {
+        Template::App => (APP_MAIN, APP_TEST),
+        Template::Cli => (CLI_MAIN, APP_TEST),
+        Template::Lib => (LIB_SRC, LIB_TEST),
+    };
+    let test_name = match template {
+        Template::Lib => "tests/lib_test.xu",
+        Template::App | Template::Cli => "tests/main_test.xu",
+    };
+
+    let files = [
+        ("xu.toml", manifest),
+        (".gitignore", GITIGNORE.to_string()),
+        (template.entry(), src.replace("{{name}}", &name)),
+        (test_name, test.to_string()),
+    ];
+    for (rel, content) in &files {
+        let path = dir.join(rel);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+        }
+        fs::write(&path, content).map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn project_name(dir: &Path) -> Result<String, String> {
+    let abs = if dir.is_absolute() {
+        dir.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map_err(|e| e.to_string())?
+            .join(dir)
+    };
+    abs.components()
+        .next_back()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .filter(|n| !n.is_empty() && n != "." && n != "..")
+        .ok_or_else(|| format!("Cannot infer project name from {}", dir.display()))
+}
@@ -0,0 +1,9 @@
+//! Library side of the `xu` command line tool.
+//!
+//! The binary lives in `main.rs`; this crate root exposes helpers meant for
+//! tests and packagers, such as the fixture harness that runs a built `xu`
+//! binary against directories of expected behavior, and the conformance
+//! suite that checks every backend prints the same.
+
+pub mod conformance;
+pub mod fixture;
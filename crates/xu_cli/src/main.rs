@@ -1,8 +1,13 @@
-use std::io::Write;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
 
 use xu_driver::Driver;
 use xu_runtime::Runtime;
-use xu_syntax::{TokenKind, render_diagnostic};
+use xu_syntax::{RenderOptions, TokenKind, render_diagnostic_with_options};
+
+mod bench;
+mod bundle;
+mod init;
 
 // Use mimalloc for better memory management (returns memory to OS more aggressively)
 #[cfg(not(target_env = "msvc"))]
@@ -10,9 +15,20 @@ use xu_syntax::{TokenKind, render_diagnostic};
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
 const USAGE: &str =
-    "Usage: xu <tokens|check|ast|run> [--nonstrict] [--timing] [--no-diags] <args>";
+    "Usage: xu <tokens|check|ast|run|bench|graph|codegen|init|snapshot|bundle|verify-bundle> [--nonstrict] [--release] [--timing] [--gc-stats] [--heap-profile] [--ic-stats] [--record <trace>] [--replay <trace>] [--allow <capability>] [--verify] [--no-cache] [--no-diags] [--no-color] [--merge-stderr] [--json] [--lang <en|zh-CN>] [--explain <code>] <args>";
 
 fn main() {
+    // A binary written by `xu bundle` runs its program, passing every
+    // argument on to it.
+    if let Some(attached) = bundle::attached() {
+        match attached {
+            Ok(b) => bundle::run(b, std::env::args().skip(1).collect()),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        }
+    }
     let mut argv: Vec<String> = std::env::args().skip(1).collect();
     let Some(cmd) = argv.first().cloned() else {
         eprintln!("{USAGE}");
@@ -22,7 +38,24 @@ fn main() {
     let mut strict = true;
     let mut timing = false;
     let mut no_diags = false;
+    let mut no_color = false;
+    let mut release = false;
+    let mut json = false;
+    let mut gc_stats = false;
+    let mut heap_profile = false;
+    let mut ic_stats = false;
+    let mut explain: Option<String> = None;
+    let mut record: Option<String> = None;
+    let mut replay: Option<String> = None;
+    let mut verify = false;
+    let mut no_cache = false;
+    let mut merge_stderr = false;
+    let mut allow: Vec<String> = Vec::new();
     let mut positional: Vec<String> = Vec::new();
+    // `XU_LANG` picks the diagnostic language; `--lang` overrides it.
+    if let Some(lang) = xu_syntax::catalog::lang_from_env() {
+        xu_syntax::catalog::set_lang(lang);
+    }
 
     let mut i = 0;
     while i < argv.len() {
@@ -33,15 +66,109 @@ fn main() {
             strict = false;
         } else if a == "--timing" {
             timing = true;
+        } else if a == "--gc-stats" {
+            // `run` prints a garbage collector summary on exit.
+            gc_stats = true;
+        } else if a == "--heap-profile" {
+            // `run` prints a heap snapshot on exit; JSON with `--json`.
+            heap_profile = true;
+        } else if a == "--ic-stats" {
+            // `run` prints inline cache hit rates and the busiest
+            // polymorphic sites on exit; JSON with `--json`.
+            ic_stats = true;
         } else if a == "--no-diags" {
             no_diags = true;
+        } else if a == "--no-color" {
+            no_color = true;
+        } else if a == "--no-cache" {
+            no_cache = true;
+        } else if a == "--merge-stderr" {
+            // `run` writes the script's standard error, and the report of
+            // a runtime error, to stdout too, in order with its output.
+            merge_stderr = true;
+        } else if a == "--json" {
+            // `check` prints diagnostics as JSON lines on stdout, `run
+            // --heap-profile` and `--ic-stats` their reports as JSON.
+            json = true;
+        } else if a == "--release" {
+            // Release builds strip contract checks.
+            release = true;
+        } else if a == "--explain" || a.starts_with("--explain=") {
+            // `check --explain <code>` prints the long explanation of a
+            // diagnostic code instead of checking a file.
+            explain = Some(match a.strip_prefix("--explain=") {
+                Some(c) => c.to_string(),
+                None => {
+                    i += 1;
+                    argv.get(i).cloned().unwrap_or_default()
+                }
+            });
+        } else if a == "--verify" {
+            // `run --verify` runs the script on both the AST executor and the
+            // VM and reports where their output, errors or globals differ.
+            verify = true;
+        } else if a == "--record" || a == "--replay" {
+            // `run --record <trace>` saves the script's clock reads, random
+            // draws, file reads and environment variables; `--replay
+            // <trace>` feeds them back to reproduce the run.
+            i += 1;
+            let Some(trace) = argv.get(i).cloned() else {
+                eprintln!("Missing <trace> after {a}");
+                std::process::exit(2);
+            };
+            if a == "--record" {
+                record = Some(trace);
+            } else {
+                replay = Some(trace);
+            }
+        } else if a == "--allow" {
+            // `run --allow <capability>` approves the script's
+            // `with_capability` requests for it; repeat for several.
+            i += 1;
+            let Some(cap) = argv.get(i).cloned() else {
+                eprintln!("Missing <capability> after --allow");
+                std::process::exit(2);
+            };
+            allow.push(cap);
+        } else if a == "--lang" || a.starts_with("--lang=") {
+            let tag = match a.strip_prefix("--lang=") {
+                Some(t) => t.to_string(),
+                None => {
+                    i += 1;
+                    argv.get(i).cloned().unwrap_or_default()
+                }
+            };
+            match xu_syntax::catalog::Lang::parse(&tag) {
+                Some(lang) => xu_syntax::catalog::set_lang(lang),
+                None => {
+                    eprintln!("Unknown language: {tag}");
+                    std::process::exit(2);
+                }
+            }
         } else {
             positional.push(a.clone());
         }
         i += 1;
     }
 
-    let driver = Driver::new();
+    let driver = Driver::new().with_contracts(!release);
+    // `run` and `bench` reuse compiled scripts from the project's
+    // `target/.xu_cache` (or `XU_CACHE_DIR`) unless `--no-cache` is given.
+    let driver = match (cmd.as_str(), positional.first()) {
+        ("run" | "bench", Some(path)) if !no_cache => match exec_cache_dir(path) {
+            Some(dir) => driver.with_cache_dir(dir),
+            None => driver,
+        },
+        _ => driver,
+    };
+    // Builtins of linked plugins are known to the analysis like the std ones.
+    #[cfg(feature = "plugins")]
+    driver.add_predefs(xu_runtime::plugin::builtin_names());
+    // Diagnostics are colored on a terminal unless `--no-color` or `NO_COLOR`
+    // asks otherwise.
+    let render_opts = RenderOptions {
+        color: !no_color && std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal(),
+    };
 
     match cmd.as_str() {
         "tokens" => {
@@ -75,11 +202,28 @@ fn main() {
             }
         }
         "check" => {
+            if let Some(code) = &explain {
+                match xu_syntax::explain::explain(code) {
+                    Some(e) => print!("{}", e.to_text()),
+                    None => {
+                        eprintln!("Unknown diagnostic code: {code}");
+                        std::process::exit(2);
+                    }
+                }
+                return;
+            }
             if positional.len() != 1 {
                 eprintln!("Missing <file>");
                 std::process::exit(2);
             }
             let path = positional[0].as_str();
+            let prelude = manifest_prelude(path);
+            if let Some(p) = &prelude {
+                if let Err(e) = driver.set_prelude(&p.to_string_lossy()) {
+                    eprintln!("{e}");
+                    std::process::exit(2);
+                }
+            }
             let parsed = match driver.parse_file(path, strict) {
                 Ok(v) => v,
                 Err(e) => {
@@ -88,7 +232,14 @@ fn main() {
                 }
             };
             for d in &parsed.diagnostics {
-                eprintln!("{}", render_diagnostic(&parsed.source, d));
+                if json {
+                    println!("{}", diagnostic_json(&parsed.source, d));
+                } else {
+                    eprintln!("{}", render_diagnostic_with_options(&parsed.source, d, render_opts));
+                }
+            }
+            if !json {
+                print_explain_hint(&parsed.diagnostics);
             }
             if parsed
                 .diagnostics
@@ -135,7 +286,7 @@ fn main() {
                 )
             };
             for d in &parsed.diagnostics {
-                eprintln!("{}", render_diagnostic(&parsed.source, d));
+                eprintln!("{}", render_diagnostic_with_options(&parsed.source, d, render_opts));
             }
             if parsed
                 .diagnostics
@@ -169,74 +320,429 @@ fn main() {
                 std::process::exit(2);
             }
             let path = positional[0].as_str();
-            let compiled = match driver.compile_file(path, strict) {
-                Ok(v) => v,
-                Err(e) => {
-                    eprintln!("{e}");
+            let (compiled, prelude) = compile_entry(&driver, path, strict, no_diags, render_opts);
+            if verify {
+                let xu_ir::Executable::Bytecode(program) = &compiled.executable else {
+                    eprintln!("{path}: no bytecode to compare with the AST executor");
                     std::process::exit(2);
+                };
+                let comparison = xu_runtime::compare_engines(program, || {
+                    let mut rt = entry_runtime(path, prelude.as_deref(), strict, release);
+                    rt.set_args(positional.clone());
+                    rt
+                });
+                print!("{}", comparison.vm.output);
+                if !comparison.divergences.is_empty() {
+                    eprintln!("verify: the AST executor and the VM diverged");
+                    for d in &comparison.divergences {
+                        eprintln!("{d}");
+                    }
+                    std::process::exit(1);
                 }
-            };
-            if !no_diags {
-                for d in &compiled.diagnostics {
-                    eprintln!("{}", render_diagnostic(&compiled.source, d));
+                if let Some(e) = &comparison.vm.error {
+                    eprintln!("RuntimeError: {e}");
+                    std::process::exit(1);
                 }
+                return;
             }
-            // 如果有错误级别的诊断，退出
-            if compiled
-                .diagnostics
-                .iter()
-                .any(|d| matches!(d.severity, xu_syntax::Severity::Error))
-            {
-                std::process::exit(1);
+            let mut rt = entry_runtime(path, prelude.as_deref(), strict, release);
+            rt.set_ic_stats(ic_stats);
+            rt.set_args(positional.clone());
+            if !allow.is_empty() {
+                let allow = allow.clone();
+                rt.set_capability_policy(Box::new(move |c: &str| allow.iter().any(|a| a == c)));
             }
+            if let Some(trace_path) = &replay {
+                let trace = std::fs::read_to_string(trace_path)
+                    .map_err(|e| format!("{trace_path}: {e}"))
+                    .and_then(|text| xu_runtime::Trace::parse(&text).map_err(|e| format!("{trace_path}: {e}")));
+                match trace {
+                    Ok(trace) => rt.replay_trace(trace),
+                    Err(e) => {
+                        eprintln!("{e}");
+                        std::process::exit(2);
+                    }
+                }
+            }
+            if record.is_some() {
+                rt.start_recording();
+            }
+            // Stream output as it is written instead of buffering it.
+            rt.set_output_streams(Box::new(xu_runtime::StdStreams { merged: merge_stderr }));
+            rt.set_terminal(Box::new(xu_runtime::StdTerminal::detect()));
+            rt.set_signal_source(Box::new(xu_runtime::OsSignals));
 
-            let mut rt = Runtime::new();
-            rt.set_strict_vars(strict);
-            rt.set_frontend(Box::new(Driver::new()));
-            rt.set_entry_path(path).expect("set entry path");
+            let result = rt.exec_executable(&compiled.executable);
+            if let (Some(trace_path), Some(trace)) = (&record, rt.recorded_trace()) {
+                if let Err(e) = std::fs::write(trace_path, trace.to_text()) {
+                    eprintln!("{trace_path}: {e}");
+                    std::process::exit(2);
+                }
+            }
+            let misses = rt.replay_misses();
+            if !misses.is_empty() {
+                eprintln!(
+                    "warning: replay diverged: {} inputs were not in the trace, first: {}",
+                    misses.len(),
+                    misses[0]
+                );
+            }
+            if gc_stats {
+                eprintln!("{}", gc_summary(&rt.gc_stats()));
+            }
+            if heap_profile {
+                let snapshot = rt.heap_snapshot();
+                if json {
+                    eprintln!("{}", snapshot.to_json());
+                } else {
+                    eprint!("{}", snapshot.to_text());
+                }
+            }
+            if ic_stats {
+                let stats = rt.ic_stats();
+                if json {
+                    eprintln!("{}", stats.to_json());
+                } else {
+                    eprint!("{}", stats.to_text(10));
+                }
+            }
 
-            // Set stdlib path
-            if let Ok(exe_path) = std::env::current_exe() {
-                if let Some(project_root) = exe_path
-                    .parent()
-                    .and_then(|p| p.parent())
-                    .and_then(|p| p.parent())
-                {
-                    let stdlib = project_root.join("stdlib");
-                    if stdlib.exists() {
-                        rt.set_stdlib_path(stdlib.to_string_lossy().to_string());
+            match result {
+                Ok(res) => {
+                    if let Some(code) = res.exit_code {
+                        let _ = std::io::stdout().flush();
+                        std::process::exit(code);
                     }
                 }
+                Err(e) => {
+                    // A `panic` is reported as such and exits like a Rust panic.
+                    let (kind, code) = if rt.panicked() { ("", 101) } else { ("RuntimeError: ", 1) };
+                    let report = format!("{kind}{e}\n{}", render_trace(rt.error_trace(), &compiled.source));
+                    if merge_stderr {
+                        print!("{report}");
+                    } else {
+                        eprint!("{report}");
+                    }
+                    std::process::exit(code);
+                }
             }
-            // If current_exe logic fails, try relative to CWD
-            if std::path::Path::new("stdlib").exists() {
-                rt.set_stdlib_path(
-                    std::env::current_dir()
-                        .unwrap()
-                        .join("stdlib")
-                        .to_string_lossy()
-                        .to_string(),
+        }
+        "bench" => {
+            // `xu bench <file>` runs the script with its `bench` blocks
+            // enabled and prints their timings. `--save <file>` writes them
+            // as a baseline, `--baseline <file>` compares with one and
+            // `--threshold <pct>` fails if a mean time grew by more than that.
+            let mut config = xu_runtime::BenchConfig::default();
+            let mut save: Option<String> = None;
+            let mut baseline_path: Option<String> = None;
+            let mut threshold: Option<f64> = None;
+            let mut files: Vec<String> = Vec::new();
+            let mut args = positional.iter();
+            while let Some(a) = args.next() {
+                if !matches!(a.as_str(), "--warmup" | "--iterations" | "--save" | "--baseline" | "--threshold") {
+                    files.push(a.clone());
+                    continue;
+                }
+                let Some(value) = args.next() else {
+                    eprintln!("Missing value after {a}");
+                    std::process::exit(2);
+                };
+                match a.as_str() {
+                    "--warmup" => config.warmup = flag_value(a, value),
+                    "--iterations" => config.iterations = flag_value(a, value),
+                    "--threshold" => threshold = Some(flag_value(a, value)),
+                    "--save" => save = Some(value.clone()),
+                    _ => baseline_path = Some(value.clone()),
+                }
+            }
+            if files.is_empty() || (threshold.is_some() && baseline_path.is_none()) {
+                eprintln!(
+                    "Usage: xu bench [--warmup <n>] [--iterations <n>] [--save <file>] [--baseline <file> [--threshold <pct>]] [--json] <file>"
                 );
+                std::process::exit(2);
             }
+            let baseline = match baseline_path.as_deref().map(bench::load_baseline) {
+                Some(Err(e)) => {
+                    eprintln!("{e}");
+                    std::process::exit(2);
+                }
+                b => b.map(Result::unwrap_or_default),
+            };
 
-            rt.set_args(positional.clone());
-
+            let path = files[0].as_str();
+            let (compiled, prelude) = compile_entry(&driver, path, strict, no_diags, render_opts);
+            let mut rt = entry_runtime(path, prelude.as_deref(), strict, release);
+            rt.set_args(files.clone());
+            rt.set_bench(Some(config));
             let result = rt.exec_executable(&compiled.executable);
-            let output = match &result {
-                Ok(res) => res.output.clone(),
-                Err(_) => rt.take_output(),
+            let (output, error_output) = match &result {
+                Ok(res) => (res.output.clone(), res.error_output.clone()),
+                Err(_) => (rt.take_output(), rt.take_error_output()),
             };
+            // The script's own output stays off stdout when it carries JSON.
+            if json {
+                eprint!("{output}");
+            } else {
+                print!("{output}");
+            }
+            eprint!("{error_output}");
+            if let Err(e) = result {
+                eprintln!("RuntimeError: {e}");
+                eprint!("{}", render_trace(rt.error_trace(), &compiled.source));
+                std::process::exit(1);
+            }
 
-            let mut stdout = std::io::stdout().lock();
-            let _ = write!(stdout, "{}", output);
-
-            match result {
-                Ok(_) => {}
+            let results = rt.bench_results();
+            if results.is_empty() {
+                eprintln!("warning: {path} has no bench blocks");
+            }
+            if json {
+                println!("{}", bench::to_json(results));
+            } else {
+                print!("{}", bench::report(results, baseline.as_deref()));
+            }
+            if let Some(save) = &save {
+                if let Err(e) = std::fs::write(save, bench::to_json(results) + "\n") {
+                    eprintln!("{save}: {e}");
+                    std::process::exit(2);
+                }
+            }
+            if let (Some(baseline), Some(threshold)) = (&baseline, threshold) {
+                let regressed = bench::regressions(results, baseline, threshold);
+                for r in &regressed {
+                    eprintln!("error: {r}");
+                }
+                if !regressed.is_empty() {
+                    std::process::exit(1);
+                }
+            }
+        }
+        "snapshot" => {
+            let mut rt = Runtime::new();
+            rt.set_frontend(Box::new(Driver::new()));
+            set_stdlib_path(&mut rt);
+            let out = match positional.first() {
+                Some(p) => std::path::PathBuf::from(p),
+                None => match rt.stdlib_path() {
+                    Some(dir) => std::path::Path::new(dir).join(SNAPSHOT_FILE),
+                    None => {
+                        eprintln!("Usage: xu snapshot [<out>] [<prelude files>...]");
+                        std::process::exit(2);
+                    }
+                },
+            };
+            let mut imports: Vec<&str> = xu_runtime::embedded_std_modules().collect();
+            imports.extend(positional.iter().skip(1).map(|s| s.as_str()));
+            let report = rt.build_snapshot(&imports);
+            for (path, e) in &report.skipped {
+                let first = e.lines().next().unwrap_or_default();
+                eprintln!("skipped {path}: {first}");
+            }
+            if let Err(e) = std::fs::write(&out, &report.bytes) {
+                eprintln!("{}: {e}", out.display());
+                std::process::exit(1);
+            }
+            println!(
+                "Wrote {} modules to {}",
+                report.modules.len(),
+                out.display()
+            );
+        }
+        "bundle" => {
+            // `xu bundle <entry>` writes a self-contained executable: this
+            // binary with the compiled program and every module it imports
+            // appended. `-o <file>` names it; the default is the entry's
+            // file stem in the current directory.
+            let mut out: Option<std::path::PathBuf> = None;
+            let mut files: Vec<&str> = Vec::new();
+            let mut args = positional.iter();
+            while let Some(a) = args.next() {
+                if a == "-o" || a == "--output" {
+                    let Some(value) = args.next() else {
+                        eprintln!("Missing value after {a}");
+                        std::process::exit(2);
+                    };
+                    out = Some(value.into());
+                } else {
+                    files.push(a.as_str());
+                }
+            }
+            if files.len() != 1 {
+                eprintln!("Usage: xu bundle [-o <file>] <entry>");
+                std::process::exit(2);
+            }
+            let path = files[0];
+            let (compiled, prelude) = compile_entry(&driver, path, strict, no_diags, render_opts);
+            let mut rt = entry_runtime(path, prelude.as_deref(), strict, release);
+            let program = match rt.build_bundle(&compiled.executable) {
+                Ok(b) => b,
                 Err(e) => {
-                    eprintln!("RuntimeError: {e}");
+                    eprintln!("{e}");
                     std::process::exit(1);
                 }
+            };
+            let out = out.unwrap_or_else(|| {
+                let stem = std::path::Path::new(path).file_stem().unwrap_or_default().to_string_lossy();
+                format!("{stem}{}", std::env::consts::EXE_SUFFIX).into()
+            });
+            match bundle::write(&program, &out) {
+                Ok(size) => println!(
+                    "Wrote {} ({} modules, {size} bytes)",
+                    out.display(),
+                    program.modules.len()
+                ),
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        "verify-bundle" => {
+            if positional.len() != 1 {
+                eprintln!("Missing <file>");
+                std::process::exit(2);
+            }
+            let path = positional[0].as_str();
+            let bytes = match std::fs::read(path) {
+                Ok(b) => b,
+                Err(e) => {
+                    eprintln!("{path}: {e}");
+                    std::process::exit(2);
+                }
+            };
+            let info = match xu_ir::bundle::verify(&bytes) {
+                Ok(info) => info,
+                Err(e) => {
+                    eprintln!("{path}: {e}");
+                    std::process::exit(1);
+                }
+            };
+            println!("checksum: ok ({:016x})", info.checksum);
+            println!("runtime: {} bytes", info.runtime_len);
+            println!("section: {} bytes", info.section_len);
+            println!("built by: xu {} (image v{})", info.lang_version, info.image_version);
+            if !info.decoded {
+                println!(
+                    "note: this xu reads image v{}; module code was not decoded",
+                    xu_ir::image::IMAGE_VERSION
+                );
+            }
+            println!("entry: {}", info.entry);
+            println!("modules: {}", info.modules.len());
+            for m in &info.modules {
+                println!("  {m}");
+            }
+        }
+        "graph" => {
+            // `xu graph <entry>` prints the import graph as DOT, `--calls`
+            // the call graph instead and `--json` both as JSON.
+            let mut calls = false;
+            let mut files: Vec<&str> = Vec::new();
+            for a in &positional {
+                match a.as_str() {
+                    "--calls" => calls = true,
+                    _ => files.push(a.as_str()),
+                }
+            }
+            if files.len() != 1 {
+                eprintln!("Usage: xu graph [--calls] [--json] <entry>");
+                std::process::exit(2);
+            }
+            let graph = match driver.graph(files[0]) {
+                Ok(g) => g,
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(2);
+                }
+            };
+            if json {
+                println!("{}", graph.to_json());
+                return;
+            }
+            for cycle in graph.import_cycles() {
+                let mut names: Vec<&str> = cycle.iter().map(|&m| graph.modules[m].path.as_str()).collect();
+                names.push(names[0]);
+                eprintln!("warning: import cycle: {}", names.join(" -> "));
+            }
+            if calls {
+                for cycle in graph.call_cycles() {
+                    let mut names: Vec<String> = cycle.iter().map(|&f| graph.function_label(f)).collect();
+                    names.push(names[0].clone());
+                    eprintln!("warning: recursion: {}", names.join(" -> "));
+                }
+                print!("{}", graph.calls_to_dot());
+            } else {
+                print!("{}", graph.imports_to_dot());
+            }
+        }
+        "codegen" => {
+            // `xu codegen <entry> lua [<out>]` translates the program to Lua,
+            // which loads its runtime with `require("xu_runtime")` unless
+            // `--inject-runtime` includes it; `xu codegen --runtime lua
+            // [<out>]` writes that runtime. `c` does the same with the
+            // header `xu_runtime.h`.
+            let mut opts = xu_driver::CodegenOptions::default();
+            let mut runtime_only = false;
+            let mut args: Vec<&str> = Vec::new();
+            for a in &positional {
+                match a.as_str() {
+                    "--inject-runtime" => opts.inject_runtime = true,
+                    "--runtime" => runtime_only = true,
+                    _ => args.push(a.as_str()),
+                }
+            }
+            let usage =
+                "Usage: xu codegen <entry> <lua|c> [<out>] [--inject-runtime]\n       xu codegen --runtime <lua|c> [<out>]";
+            let (entry, rest) =
+                if runtime_only { (None, &args[..]) } else { (args.first().copied(), args.get(1..).unwrap_or_default()) };
+            let (Some(target), out) = (rest.first().and_then(|t| xu_driver::CodegenTarget::parse(t)), rest.get(1)) else {
+                eprintln!("{usage}");
+                std::process::exit(2);
+            };
+            if rest.len() > 2 || (!runtime_only && entry.is_none()) {
+                eprintln!("{usage}");
+                std::process::exit(2);
+            }
+            let code = match entry {
+                None => target.runtime().to_string(),
+                Some(entry) => match driver.codegen(entry, strict, target, &opts) {
+                    Ok(code) => code,
+                    Err(e) => {
+                        eprintln!("{e}");
+                        std::process::exit(1);
+                    }
+                },
+            };
+            match out {
+                Some(out) => {
+                    if let Err(e) = std::fs::write(out, code) {
+                        eprintln!("{out}: {e}");
+                        std::process::exit(1);
+                    }
+                }
+                None => print!("{code}"),
+            }
+        }
+        "init" => {
+            let mut template = init::Template::App;
+            let mut dirs: Vec<&str> = Vec::new();
+            for a in &positional {
+                match a.as_str() {
+                    "--lib" => template = init::Template::Lib,
+                    "--cli" => template = init::Template::Cli,
+                    _ => dirs.push(a.as_str()),
+                }
+            }
+            if dirs.len() > 1 {
+                eprintln!("Usage: xu init [--lib|--cli] [<dir>]");
+                std::process::exit(2);
+            }
+            let dir = std::path::Path::new(dirs.first().copied().unwrap_or("."));
+            if let Err(e) = init::init_project(dir, template) {
+                eprintln!("{e}");
+                std::process::exit(1);
             }
+            println!("Created project at {}", dir.display());
         }
         _ => {
             eprintln!("Unknown command: {cmd}");
@@ -245,6 +751,243 @@ fn main() {
     }
 }
 
+/// After diagnostics, points at `xu check --explain` for the error codes
+/// that have long explanations.
+/// Compiles the script `run` and `bench` execute, printing its diagnostics
+/// and exiting on errors. Also returns the manifest prelude, if any.
+fn compile_entry(
+    driver: &Driver,
+    path: &str,
+    strict: bool,
+    no_diags: bool,
+    render_opts: RenderOptions,
+) -> (xu_driver::CompiledFile, Option<std::path::PathBuf>) {
+    let prelude = manifest_prelude(path);
+    if let Some(p) = &prelude {
+        if let Err(e) = driver.set_prelude(&p.to_string_lossy()) {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    }
+    let compiled = match driver.compile_file(path, strict) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+    if !no_diags {
+        for d in &compiled.diagnostics {
+            eprintln!("{}", render_diagnostic_with_options(&compiled.source, d, render_opts));
+        }
+        print_explain_hint(&compiled.diagnostics);
+    }
+    // 如果有错误级别的诊断，退出
+    if compiled
+        .diagnostics
+        .iter()
+        .any(|d| matches!(d.severity, xu_syntax::Severity::Error))
+    {
+        std::process::exit(1);
+    }
+    (compiled, prelude)
+}
+
+/// Parses the value of a numeric flag, exiting if it is malformed.
+fn flag_value<T: std::str::FromStr>(flag: &str, value: &str) -> T {
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("Invalid {flag}: {value}");
+        std::process::exit(2);
+    })
+}
+
+/// A runtime set up to execute the script at `path`.
+fn entry_runtime(path: &str, prelude: Option<&std::path::Path>, strict: bool, release: bool) -> Runtime {
+    let mut rt = Runtime::new();
+    rt.set_strict_vars(strict);
+    rt.set_frontend(Box::new(Driver::new().with_contracts(!release)));
+    rt.set_entry_path(path).expect("set entry path");
+    if let Some(p) = prelude {
+        rt.set_prelude(&p.to_string_lossy());
+    }
+    set_stdlib_path(&mut rt);
+    load_snapshot(&mut rt);
+    rt
+}
+
+fn print_explain_hint(diagnostics: &[xu_syntax::Diagnostic]) {
+    let codes = diagnostics
+        .iter()
+        .filter(|d| matches!(d.severity, xu_syntax::Severity::Error))
+        .filter_map(|d| d.code);
+    if let Some(hint) = xu_syntax::explain::explain_hint(codes) {
+        eprintln!("{hint}");
+    }
+}
+
+/// Points the runtime at the stdlib next to the binary's project root, or in
+/// the current directory.
+/// One diagnostic as a JSON object: severity, code, message, byte span and
+/// 1-based line/column of the span start.
+fn diagnostic_json(source: &xu_syntax::SourceFile, d: &xu_syntax::Diagnostic) -> String {
+    let severity = match d.severity {
+        xu_syntax::Severity::Error => "error",
+        xu_syntax::Severity::Warning => "warning",
+        xu_syntax::Severity::Info => "info",
+    };
+    let (span, line, column) = match d.span {
+        Some(s) => {
+            let (line, col) = source.text.line_col(s.start.0);
+            (
+                serde_json::json!({ "start": s.start.0, "end": s.end.0 }),
+                serde_json::json!(line + 1),
+                serde_json::json!(col + 1),
+            )
+        }
+        None => Default::default(),
+    };
+    serde_json::json!({
+        "severity": severity,
+        "code": d.code,
+        "message": d.message,
+        "file": source.name,
+        "span": span,
+        "line": line,
+        "column": column,
+    })
+    .to_string()
+}
+
+fn set_stdlib_path(rt: &mut Runtime) {
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(project_root) = exe_path
+            .parent()
+            .and_then(|p| p.parent())
+            .and_then(|p| p.parent())
+        {
+            let stdlib = project_root.join("stdlib");
+            if stdlib.exists() {
+                rt.set_stdlib_path(stdlib.to_string_lossy().to_string());
+            }
+        }
+    }
+    // If current_exe logic fails, try relative to CWD
+    if std::path::Path::new("stdlib").exists() {
+        rt.set_stdlib_path(
+            std::env::current_dir()
+                .unwrap()
+                .join("stdlib")
+                .to_string_lossy()
+                .to_string(),
+        );
+    }
+}
+
+/// The `xu.toml` closest to `script`, searching its ancestors.
+fn project_manifest(script: &str) -> Option<std::path::PathBuf> {
+    let script = std::path::Path::new(script).canonicalize().ok()?;
+    script.ancestors().skip(1).map(|d| d.join("xu.toml")).find(|m| m.is_file())
+}
+
+/// Where compiled scripts are cached: `XU_CACHE_DIR`, or `target/.xu_cache`
+/// next to the project's `xu.toml`. Scripts outside a project are not cached.
+fn exec_cache_dir(script: &str) -> Option<std::path::PathBuf> {
+    if let Some(dir) = std::env::var_os("XU_CACHE_DIR") {
+        return Some(dir.into());
+    }
+    Some(project_manifest(script)?.parent()?.join("target").join(".xu_cache"))
+}
+
+/// Finds the `xu.toml` closest to `script` and returns its `prelude` entry
+/// (from `[package]`), resolved against the manifest's directory.
+fn manifest_prelude(script: &str) -> Option<std::path::PathBuf> {
+    let manifest = project_manifest(script)?;
+    let text = std::fs::read_to_string(&manifest).ok()?;
+    let mut in_package = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() == "prelude" {
+            let value = value.trim().trim_matches('"');
+            return Some(manifest.parent()?.join(value));
+        }
+    }
+    None
+}
+
+/// Default snapshot location inside the stdlib directory.
+const SNAPSHOT_FILE: &str = "std.xusnap";
+
+/// Loads the startup snapshot named by `XU_SNAPSHOT`, or the default one in
+/// the stdlib directory if it exists.
+fn load_snapshot(rt: &mut Runtime) {
+    let path = match std::env::var_os("XU_SNAPSHOT") {
+        Some(p) => std::path::PathBuf::from(p),
+        None => match rt.stdlib_path() {
+            Some(dir) => std::path::Path::new(dir).join(SNAPSHOT_FILE),
+            None => return,
+        },
+    };
+    let Ok(bytes) = std::fs::read(&path) else {
+        return;
+    };
+    if let Err(e) = rt.load_snapshot(&bytes) {
+        eprintln!("warning: ignoring snapshot {}: {e}", path.display());
+    }
+}
+
+/// `  at file:line:col in func` per frame of a runtime error, innermost
+/// first. Frames from other files are resolved by reading them, once per
+/// file.
+fn render_trace(frames: &[xu_runtime::TraceFrame], entry: &xu_syntax::SourceFile) -> String {
+    let mut loaded: HashMap<&str, Option<xu_syntax::SourceText>> = HashMap::new();
+    let mut out = String::new();
+    for f in frames {
+        let text = if f.file == entry.name {
+            Some(&entry.text)
+        } else {
+            loaded
+                .entry(f.file.as_str())
+                .or_insert_with(|| std::fs::read_to_string(&f.file).ok().map(xu_syntax::SourceText::new))
+                .as_ref()
+        };
+        out.push_str(&format!("  at {}", f.file));
+        if let Some(text) = text {
+            let (line, col) = text.line_col(f.span.start.0);
+            out.push_str(&format!(":{}:{}", line + 1, col + 1));
+        }
+        if let Some(func) = &f.function {
+            out.push_str(&format!(" in {func}"));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// One-line summary for `--gc-stats`.
+fn gc_summary(s: &xu_runtime::GcStats) -> String {
+    let kib = |bytes: u64| bytes as f64 / 1024.0;
+    format!(
+        "gc: {} collections, {} objects ({:.1} KiB) freed, {} live ({:.1} KiB), pauses {:.3} ms total, {:.3} ms max",
+        s.collections,
+        s.objects_freed,
+        kib(s.bytes_freed),
+        s.live_objects,
+        kib(s.live_bytes as u64),
+        s.total_pause.as_secs_f64() * 1000.0,
+        s.max_pause.as_secs_f64() * 1000.0,
+    )
+}
+
 fn escape_visible(s: &str) -> String {
     let mut out = String::new();
     for c in s.chars() {
@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use xu_cli::conformance;
+
+#[test]
+fn backends_agree_on_the_conformance_suite() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/conformance");
+    let report = conformance::run_all(&root, false).unwrap_or_else(|e| panic!("{e}"));
+    assert!(
+        !report.cases.is_empty(),
+        "no cases under {}",
+        root.display()
+    );
+    let failures = report.failures();
+    assert!(
+        failures.is_empty(),
+        "{}\n{}",
+        report.matrix(),
+        failures.join("\n")
+    );
+}
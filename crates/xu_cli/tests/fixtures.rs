@@ -0,0 +1,12 @@
+use std::path::Path;
+
+use xu_cli::fixture::Harness;
+
+#[test]
+fn cli_fixtures() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    match Harness::new(env!("CARGO_BIN_EXE_xu")).run_all(&root) {
+        Ok(n) => assert!(n > 0, "no fixtures under {}", root.display()),
+        Err(e) => panic!("{e}"),
+    }
+}
@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn run_xu(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_xu"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn fresh_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join("xu_cli_init_tests").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn run_file(path: &Path, extra: &[&str]) -> String {
+    let p = path.to_string_lossy().to_string();
+    let mut args = vec!["run", p.as_str()];
+    args.extend_from_slice(extra);
+    let out = run_xu(&args);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(out.status.code(), Some(0), "stderr was: {stderr}");
+    String::from_utf8_lossy(&out.stdout).trim_end().to_string()
+}
+
+#[test]
+fn init_creates_app_project() {
+    let dir = fresh_dir("hello_app");
+    let out = run_xu(&["init", dir.to_string_lossy().as_ref()]);
+    assert_eq!(out.status.code(), Some(0));
+
+    let manifest = fs::read_to_string(dir.join("xu.toml")).unwrap();
+    assert!(manifest.contains("name = \"hello_app\""), "{manifest}");
+    assert!(manifest.contains("entry = \"src/main.xu\""), "{manifest}");
+    assert!(dir.join(".gitignore").is_file());
+    assert!(dir.join("tests").is_dir());
+
+    assert_eq!(run_file(&dir.join("src/main.xu"), &[]), "Hello from hello_app!");
+    assert_eq!(run_file(&dir.join("tests/main_test.xu"), &[]), "ok");
+}
+
+#[test]
+fn init_lib_template() {
+    let dir = fresh_dir("greeter");
+    let out = run_xu(&["init", "--lib", dir.to_string_lossy().as_ref()]);
+    assert_eq!(out.status.code(), Some(0));
+
+    let manifest = fs::read_to_string(dir.join("xu.toml")).unwrap();
+    assert!(manifest.contains("kind = \"lib\""), "{manifest}");
+    assert!(dir.join("src/lib.xu").is_file());
+    assert!(!dir.join("src/main.xu").exists());
+    assert_eq!(run_file(&dir.join("tests/lib_test.xu"), &[]), "ok");
+}
+
+#[test]
+fn init_cli_template() {
+    let dir = fresh_dir("greet_cli");
+    let out = run_xu(&["init", dir.to_string_lossy().as_ref(), "--cli"]);
+    assert_eq!(out.status.code(), Some(0));
+
+    let main = dir.join("src/main.xu");
+    assert_eq!(run_file(&main, &[]), "usage: greet_cli <name>");
+    assert_eq!(run_file(&main, &["Bob"]), "Hello, Bob!");
+}
+
+#[test]
+fn init_refuses_non_empty_directory() {
+    let dir = fresh_dir("occupied");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("keep.txt"), "x").unwrap();
+
+    let out = run_xu(&["init", dir.to_string_lossy().as_ref()]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Destination is not empty"));
+    assert!(!dir.join("xu.toml").exists());
+}
@@ -0,0 +1,69 @@
+use std::fs;
+use std::process::Command;
+
+fn xu(args: &[&str]) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_xu"));
+    cmd.args(args).env_remove("XU_LANG");
+    cmd
+}
+
+fn run_xu(args: &[&str]) -> std::process::Output {
+    xu(args).output().unwrap()
+}
+
+#[test]
+fn lang_flag_selects_diagnostic_language() {
+    let dir = std::env::temp_dir().join("xu_cli_lang_tests");
+    let _ = fs::create_dir_all(&dir);
+    let main = dir.join("main.xu");
+    fs::write(&main, "println(missing_name)\n").unwrap();
+    let main = main.to_string_lossy().to_string();
+
+    let out = run_xu(&["check", &main]);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Undefined identifier: missing_name"));
+
+    for flag in [&["--lang", "zh-CN"][..], &["--lang=zh_CN"][..]] {
+        let mut args = vec!["check"];
+        args.extend_from_slice(flag);
+        args.push(&main);
+        let out = run_xu(&args);
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert_eq!(out.status.code(), Some(1), "{stderr}");
+        assert!(stderr.contains("未定义的标识符"), "{stderr}");
+        assert!(stderr.contains("missing_name"), "{stderr}");
+    }
+
+    let out = run_xu(&["check", "--lang", "klingon", &main]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Unknown language"));
+}
+
+#[test]
+fn xu_lang_selects_the_language_and_the_flag_overrides_it() {
+    let dir = std::env::temp_dir().join("xu_cli_lang_env_tests");
+    let _ = fs::create_dir_all(&dir);
+    let main = dir.join("main.xu");
+    fs::write(&main, "@cached\nfunc f() {\n}\nprintln(missing_name)\n").unwrap();
+    let main = main.to_string_lossy().to_string();
+
+    let out = xu(&["check", &main]).env("XU_LANG", "zh-CN").output().unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("未知的注解：@cached"), "{stderr}");
+    assert!(stderr.contains("未定义的标识符：missing_name"), "{stderr}");
+    // Codes stay the same in every language.
+    assert!(stderr.contains("[E0001]"), "{stderr}");
+    assert!(stderr.contains("运行 `xu check --explain E0001`"), "{stderr}");
+
+    let out = xu(&["check", "--lang", "en", &main]).env("XU_LANG", "zh-CN").output().unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Unknown annotation: @cached"), "{stderr}");
+
+    let out = xu(&["check", "--json", &main]).env("XU_LANG", "zh").output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("\"code\":\"E0001\""), "{stdout}");
+    assert!(stdout.contains("未定义的标识符"), "{stdout}");
+
+    // An unrecognised value is ignored rather than failing the command.
+    let out = xu(&["check", &main]).env("XU_LANG", "klingon").output().unwrap();
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Undefined identifier"));
+}
@@ -73,3 +73,242 @@ if true {
     assert!(stderr.contains("Error:"));
     assert!(stderr.contains("Expected"), "{stderr}");
 }
+
+#[test]
+fn run_gc_stats_prints_a_summary() {
+    let path = write_temp_xu("run_gc_stats_prints_a_summary", "gc()\nprintln(\"hi\")\n");
+    let out = run_xu(&["run", "--gc-stats", path.to_string_lossy().as_ref()]);
+    let _ = std::fs::remove_file(&path);
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "hi\n");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.starts_with("gc: 1 collections,"), "{stderr}");
+}
+
+#[test]
+fn run_heap_profile_reports_retainers() {
+    let src = "var cache: {string: string} = {}\nfor i in 0..50 {\n    cache[\"k{i}\"] = \"value {i}\"\n}\nprintln(\"hi\")\n";
+    let path = write_temp_xu("run_heap_profile_reports_retainers", src);
+    let text = run_xu(&["run", "--heap-profile", path.to_string_lossy().as_ref()]);
+    let json = run_xu(&["run", "--heap-profile", "--json", path.to_string_lossy().as_ref()]);
+    let _ = std::fs::remove_file(&path);
+    assert!(text.status.success());
+    assert_eq!(String::from_utf8_lossy(&text.stdout), "hi\n");
+    let stderr = String::from_utf8_lossy(&text.stderr);
+    assert!(stderr.starts_with("heap: "), "{stderr}");
+    assert!(stderr.contains("global cache"), "{stderr}");
+
+    assert!(json.status.success());
+    let stderr = String::from_utf8_lossy(&json.stderr);
+    let report: serde_json::Value = serde_json::from_str(stderr.trim()).expect("json report");
+    assert_eq!(report["largest"][0]["path"][0], "global cache");
+}
+
+#[test]
+fn run_ic_stats_reports_polymorphic_sites() {
+    let src = "A has {\n    n: int\n\n    func get() {\n        return self.n\n    }\n}\nB has {\n    n: int\n\n    func get() {\n        return self.n + 1\n    }\n}\nlet items = [A{ n: 1 }, B{ n: 1 }]\nvar total = 0\nfor i in 0..10 {\n    total += items[i % 2].get()\n}\nprintln(total)\n";
+    let path = write_temp_xu("run_ic_stats_reports_polymorphic_sites", src);
+    let text = run_xu(&["run", "--ic-stats", path.to_string_lossy().as_ref()]);
+    let json = run_xu(&["run", "--ic-stats", "--json", path.to_string_lossy().as_ref()]);
+    let _ = std::fs::remove_file(&path);
+    assert!(text.status.success());
+    assert_eq!(String::from_utf8_lossy(&text.stdout), "15\n");
+    let stderr = String::from_utf8_lossy(&text.stderr);
+    assert!(stderr.starts_with("inline caches: "), "{stderr}");
+    assert!(stderr.contains("method get (slot "), "{stderr}");
+    assert!(stderr.contains("A, B"), "{stderr}");
+
+    assert!(json.status.success());
+    let stderr = String::from_utf8_lossy(&json.stderr);
+    let report: serde_json::Value = serde_json::from_str(stderr.trim()).expect("json report");
+    assert_eq!(report["sites"][0]["name"], "get");
+    assert_eq!(report["sites"][0]["shapes"][1], "B");
+}
+
+#[test]
+fn run_error_prints_a_traceback() {
+    let src = "func inner(xs) {\n    let n = xs.length\n    return xs[n]\n}\nfunc outer() {\n    println(\"calling\")\n    return inner([1, 2])\n}\nouter()\n";
+    let path = write_temp_xu("run_error_prints_a_traceback", src);
+    let out = run_xu(&["run", path.to_string_lossy().as_ref()]);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let file = path.to_string_lossy();
+    let expected = format!(
+        "RuntimeError: Index out of range\n  at {file}:3:5 in inner\n  at {file}:7:5 in outer\n  at {file}:9:1\n"
+    );
+    assert!(stderr.ends_with(&expected), "{stderr}");
+}
+
+#[test]
+fn check_explain_prints_the_explanation() {
+    let out = run_xu(&["check", "--explain", "e1002"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.starts_with("E1002: Argument count mismatch\n"), "{stdout}");
+    assert!(stdout.contains("Erroneous code example:\n\n    func add("), "{stdout}");
+
+    let out = run_xu(&["check", "--explain=E9999"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Unknown diagnostic code: E9999"));
+}
+
+#[test]
+fn check_errors_point_at_explain() {
+    let path = write_temp_xu("check_errors_point_at_explain", "println(undef)\n");
+    let out = run_xu(&["check", path.to_string_lossy().as_ref()]);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.ends_with("For more information about this error, try `xu check --explain E0001`.\n"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn check_reports_errors_after_a_syntax_error() {
+    let src = "Point has {\n    x int\n}\nlet a = (1 +\nfunc f() {\n    let v = [1, 2\n    println(undef1)\n}\nprintln(undef2)\n";
+    let path = write_temp_xu("check_reports_errors_after_a_syntax_error", src);
+    let out = run_xu(&["check", "--no-color", path.to_string_lossy().as_ref()]);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    for expected in [":2:7: ", "Unclosed '('", "Unclosed '['", "undef1", "undef2"] {
+        assert!(stderr.contains(expected), "missing {expected:?} in {stderr}");
+    }
+    assert!(!stderr.contains("Undefined identifier: x"), "{stderr}");
+}
+
+#[test]
+fn graph_prints_dot_and_warns_about_recursion() {
+    let path = write_temp_xu("graph_prints_dot", "func f(n: int) { g(n) }\nfunc g(n: int) { f(n) }\nf(1)\n");
+    let file = path.to_string_lossy().to_string();
+    let imports = run_xu(&["graph", &file]);
+    let calls = run_xu(&["graph", "--calls", &file]);
+    let json = run_xu(&["graph", "--json", &file]);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(imports.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&imports.stdout).starts_with("digraph imports {"));
+    let stdout = String::from_utf8_lossy(&calls.stdout);
+    assert!(stdout.contains("f0 -> f1 [color=red];"), "{stdout}");
+    let stderr = String::from_utf8_lossy(&calls.stderr);
+    assert!(stderr.contains("warning: recursion: ") && stderr.contains(":f -> "), "{stderr}");
+    let json: serde_json::Value = serde_json::from_slice(&json.stdout).expect("json");
+    assert_eq!(json["call_cycles"], serde_json::json!([[0, 1]]));
+    assert_eq!(run_xu(&["graph"]).status.code(), Some(2));
+}
+
+#[test]
+fn run_replay_reproduces_a_recorded_run() {
+    let path = write_temp_xu("run_replay", "println(time_millis(), rand(1000000))\n");
+    let file = path.to_string_lossy().to_string();
+    let trace = std::env::temp_dir().join(format!("xu_run_replay_{}.trace", std::process::id()));
+    let trace = trace.to_string_lossy().to_string();
+    let recorded = run_xu(&["run", "--record", &trace, &file]);
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let replayed = run_xu(&["run", "--replay", &trace, &file]);
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&trace);
+    assert_eq!(recorded.status.code(), Some(0));
+    assert!(!recorded.stdout.is_empty());
+    assert_eq!(replayed.stdout, recorded.stdout);
+    assert!(replayed.stderr.is_empty(), "{}", String::from_utf8_lossy(&replayed.stderr));
+    assert_eq!(run_xu(&["run", "--replay", "/nonexistent.trace", &file]).status.code(), Some(2));
+}
+
+#[test]
+fn run_verify_compares_the_engines() {
+    let path = write_temp_xu("run_verify", "let r = rand(1000000)\nprintln(time_millis() > 0, r >= 0)\n");
+    let file = path.to_string_lossy().to_string();
+    let out = run_xu(&["run", "--verify", &file]);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "true\ntrue\n");
+}
+
+#[test]
+fn run_caches_compiled_scripts_in_projects() {
+    let dir = std::env::temp_dir().join(format!("xu_run_cache_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("xu.toml"), "[package]\nname = \"cached\"\n").unwrap();
+    let script = dir.join("src").join("main.xu");
+    std::fs::write(&script, "println(\"cached\")\n").unwrap();
+    let file = script.to_string_lossy().to_string();
+    let cache = dir.join("target").join(".xu_cache");
+
+    let uncached = run_xu(&["run", "--no-cache", &file]);
+    let created = cache.exists();
+    let first = run_xu(&["run", &file]);
+    let entries = std::fs::read_dir(&cache).map(|d| d.count()).unwrap_or(0);
+    let second = run_xu(&["run", &file]);
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(uncached.stdout, b"cached\n");
+    assert!(!created);
+    assert_eq!(entries, 1);
+    assert_eq!(first.stdout, b"cached\n");
+    assert_eq!(second.stdout, b"cached\n");
+}
+
+#[test]
+fn bench_reports_and_compares_with_a_baseline() {
+    let path = write_temp_xu("bench_reports", "println(\"setup\")\nbench \"sum\" {\n    var s = 0\n    for i in [0..99] { s += i }\n}\n");
+    let file = path.to_string_lossy().to_string();
+    let base = std::env::temp_dir().join(format!("xu_bench_baseline_{}.json", std::process::id()));
+    let base = base.to_string_lossy().to_string();
+    let saved = run_xu(&["bench", "--iterations", "3", "--save", &base, &file]);
+    let compared = run_xu(&["bench", "--warmup", "0", "--baseline", &base, "--threshold", "1000000", &file]);
+    let json = run_xu(&["bench", "--json", "--iterations", "2", &file]);
+    let _ = std::fs::remove_file(&path);
+    let baseline = std::fs::read_to_string(&base).unwrap_or_default();
+    let _ = std::fs::remove_file(&base);
+
+    assert_eq!(saved.status.code(), Some(0), "{}", String::from_utf8_lossy(&saved.stderr));
+    let stdout = String::from_utf8_lossy(&saved.stdout);
+    assert!(stdout.starts_with("setup\nbench "), "{stdout}");
+    assert!(stdout.lines().any(|l| l.starts_with("sum ")), "{stdout}");
+    let baseline: serde_json::Value = serde_json::from_str(&baseline).expect("baseline");
+    assert_eq!(baseline["benchmarks"][0]["name"], "sum");
+    assert_eq!(baseline["benchmarks"][0]["iterations"], 3);
+
+    assert_eq!(compared.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&compared.stdout).contains("vs baseline"));
+    let json_out: serde_json::Value = serde_json::from_slice(&json.stdout).expect("json");
+    assert_eq!(json_out["benchmarks"][0]["iterations"], 2);
+    assert_eq!(String::from_utf8_lossy(&json.stderr), "setup\n");
+    assert_eq!(run_xu(&["bench", "--threshold", "5", &file]).status.code(), Some(2));
+}
+
+#[test]
+fn run_keeps_stderr_apart_unless_merged() {
+    let p = write_temp_xu("stderr", "println(\"a\")\neprint(\"b\")\nprintln(\"c\")\nlet x = [1][3]\n");
+    let out = run_xu(&["run", p.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "a\nc\n");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.starts_with("b\nRuntimeError: "));
+
+    let out = run_xu(&["run", "--merge-stderr", p.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stderr.is_empty());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.starts_with("a\nb\nc\nRuntimeError: "));
+    let _ = std::fs::remove_file(&p);
+}
+
+#[test]
+fn run_exits_with_the_scripts_code() {
+    let p = write_temp_xu("exit", "println(\"bye\")\nexit(7)\nprintln(\"never\")\n");
+    let out = run_xu(&["run", p.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(7));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "bye\n");
+    let _ = std::fs::remove_file(&p);
+
+    let p = write_temp_xu("panic", "panic(\"broken invariant\")\n");
+    let out = run_xu(&["run", p.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(101));
+    assert!(String::from_utf8_lossy(&out.stderr).starts_with("panic: broken invariant"));
+    let _ = std::fs::remove_file(&p);
+}
@@ -0,0 +1,44 @@
+use std::fs;
+use std::process::Command;
+
+fn run_xu(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_xu"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn manifest_prelude_is_used_by_check_and_run() {
+    let dir = std::env::temp_dir().join("xu_cli_prelude_tests");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("xu.toml"),
+        "[package]\nname = \"demo\"\nprelude = \"src/prelude.xu\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/prelude.xu"),
+        "pub func shout(s: string) -> string { return s + \"!\" }\n",
+    )
+    .unwrap();
+    let main = dir.join("src/main.xu");
+    fs::write(&main, "println(shout(\"hello\"))\n").unwrap();
+    let main = main.to_string_lossy().to_string();
+
+    let out = run_xu(&["check", &main]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(out.status.code(), Some(0), "{stderr}");
+    assert!(!stderr.contains("Undefined identifier"), "{stderr}");
+
+    let out = run_xu(&["run", &main]);
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim_end(), "hello!");
+
+    // Without the manifest entry the name is undefined again.
+    fs::write(dir.join("xu.toml"), "[package]\nname = \"demo\"\n").unwrap();
+    let out = run_xu(&["check", &main]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Undefined identifier"));
+}
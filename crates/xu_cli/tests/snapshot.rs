@@ -0,0 +1,45 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn run_uses_snapshot_built_by_cli() {
+    let dir = std::env::temp_dir().join("xu_cli_snapshot_tests");
+    let _ = fs::create_dir_all(&dir);
+    let lib = dir.join("prelude.xu");
+    let main = dir.join("main.xu");
+    let image = dir.join("app.xusnap");
+    fs::write(&lib, "pub func hi() { return \"hi from snapshot\"; }\n").unwrap();
+    fs::write(&main, format!("use \"{}\" as p;\nprintln(p.hi());\n", lib.display())).unwrap();
+
+    let exe = env!("CARGO_BIN_EXE_xu");
+    let out = Command::new(exe)
+        .arg("snapshot")
+        .arg(&image)
+        .arg(&lib)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("Wrote "));
+
+    let out = Command::new(exe)
+        .arg("run")
+        .arg(&main)
+        .env("XU_SNAPSHOT", &image)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(out.status.code(), Some(0), "{stderr}");
+    assert!(!stderr.contains("ignoring snapshot"), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim_end(), "hi from snapshot");
+
+    fs::write(&image, b"garbage").unwrap();
+    let out = Command::new(exe)
+        .arg("run")
+        .arg(&main)
+        .env("XU_SNAPSHOT", &image)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("ignoring snapshot"));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim_end(), "hi from snapshot");
+}
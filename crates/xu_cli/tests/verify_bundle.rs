@@ -0,0 +1,100 @@
+use std::process::Command;
+
+use xu_ir::Frontend;
+use xu_ir::bundle::{self, Bundle};
+
+fn bundled_binary() -> Vec<u8> {
+    let driver = xu_driver::Driver::new();
+    let main = driver
+        .compile_text_no_analyze("main.xu", "use \"util\"\nprintln(util.greet())\n")
+        .unwrap();
+    let util = driver
+        .compile_text_no_analyze("util.xu", "pub func greet() -> string { return \"hi\" }\n")
+        .unwrap();
+    let mut bytes = b"\x7fELF fake runtime".to_vec();
+    bytes.extend(bundle::encode(&Bundle {
+        lang_version: "9.9.9".to_string(),
+        entry: "main.xu".to_string(),
+        modules: vec![
+            ("main.xu".to_string(), main.executable),
+            ("util.xu".to_string(), util.executable),
+        ],
+    }));
+    bytes
+}
+
+fn verify(bytes: &[u8]) -> std::process::Output {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tool");
+    std::fs::write(&path, bytes).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_xu"))
+        .arg("verify-bundle")
+        .arg(&path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn verify_bundle_reports_version_and_modules() {
+    let bytes = bundled_binary();
+    let info = bundle::verify(&bytes).unwrap();
+    assert_eq!(info.runtime_len, 17);
+    assert!(info.decoded);
+
+    let out = verify(&bytes);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{stdout}");
+    assert!(stdout.contains("checksum: ok"), "{stdout}");
+    assert!(stdout.contains("built by: xu 9.9.9"), "{stdout}");
+    assert!(stdout.contains("entry: main.xu"), "{stdout}");
+    assert!(stdout.contains("modules: 2\n  main.xu\n  util.xu\n"), "{stdout}");
+
+    let decoded = bundle::decode(&bytes).unwrap();
+    assert_eq!(decoded.modules.len(), 2);
+}
+
+#[test]
+fn verify_bundle_rejects_tampered_section() {
+    let mut bytes = bundled_binary();
+    let i = bytes.len() - bundle::TRAILER_LEN - 3;
+    bytes[i] ^= 0xff;
+    let out = verify(&bytes);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Bundle checksum mismatch"), "{stderr}");
+}
+
+#[test]
+fn verify_bundle_rejects_plain_binaries() {
+    let out = verify(b"\x7fELF no bundle here");
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("No bundle section found"), "{stderr}");
+}
+
+#[test]
+fn bundle_writes_a_self_contained_executable() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("src");
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(src.join("greet.xu"), "pub func greet(n: string) -> string { return \"hi \" + n }\n").unwrap();
+    std::fs::write(src.join("main.xu"), "use \"greet.xu\" as g\nprintln(g.greet(os_args().join(\" \")))\n").unwrap();
+    let tool = dir.path().join("tool");
+    let out = Command::new(env!("CARGO_BIN_EXE_xu"))
+        .args(["bundle", "-o"])
+        .arg(&tool)
+        .arg(src.join("main.xu"))
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("2 modules"));
+
+    std::fs::remove_dir_all(&src).unwrap();
+    let run = Command::new(&tool).args(["ada", "--lovelace"]).current_dir(dir.path()).output().unwrap();
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "hi ada --lovelace\n");
+
+    let info = bundle::verify(&std::fs::read(&tool).unwrap()).unwrap();
+    assert_eq!(info.entry, "main.xu");
+    assert_eq!(info.modules, ["main.xu", "greet.xu"]);
+}
@@ -0,0 +1,36 @@
+//! Embeds the sources of the standard library (`stdlib/*.xu`) so labeled
+//! calls of `std/...` functions resolve against the parameters the modules
+//! declare, wherever the script is compiled.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let stdlib_dir = manifest_dir.join("../../stdlib");
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("std_sources.rs");
+
+    println!("cargo:rerun-if-changed={}", stdlib_dir.display());
+
+    let mut files: Vec<PathBuf> = match fs::read_dir(&stdlib_dir) {
+        Ok(rd) => {
+            rd.filter_map(|e| e.ok().map(|e| e.path())).filter(|p| p.extension().is_some_and(|e| e == "xu")).collect()
+        }
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+
+    let mut code = String::from("static STD_SOURCES: &[(&str, &str)] = &[\n");
+    for path in &files {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let stem = path.file_stem().unwrap().to_string_lossy();
+        let abs = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        code.push_str(&format!(
+            "    (\"std/{stem}\", include_str!({:?})),\n",
+            abs.to_string_lossy().replace('\\', "/")
+        ));
+    }
+    code.push_str("];\n");
+    fs::write(out, code).unwrap();
+}
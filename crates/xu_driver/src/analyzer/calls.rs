@@ -0,0 +1,372 @@
+//! 调用检查：内置函数、内置类型方法与结构体字面量
+//!
+//! 调用内置函数时按 [`xu_syntax::builtin_signature`] 检查参数个数；能推断出
+//! 类型的参数还要符合签名中的参数类型。文件中重新绑定过的内置函数名指向
+//! 别的值，不做检查。接收者能推断为 text、list 等内置类型时，方法名须是
+//! [`xu_syntax::builtin_methods`] 中的一个。结构体字面量中能推断出类型的
+//! 字段值须符合字段声明的类型。
+
+use std::collections::{HashMap, HashSet};
+use xu_parser::{Expr, FuncDef, MethodCallExpr, Stmt, StructInitItem};
+use xu_syntax::{
+    BuiltinSig, Diagnostic, DiagnosticKind, DiagnosticsFormatter, Type, TypeId, TypeInterner, builtin_methods,
+    builtin_signature, codes, find_best_match,
+};
+
+use super::StructMap;
+use super::types::{collect_all_func_sigs, empty_container_literal_ok, infer_type, type_mismatch_id, typeref_to_typeid};
+use super::utils::{Finder, collect_pattern_binds};
+
+type FuncSigs = HashMap<String, (Vec<Option<TypeId>>, Option<TypeId>)>;
+
+struct CallChecker<'a, 'b> {
+    func_sigs: FuncSigs,
+    structs: &'a StructMap,
+    interner: TypeInterner,
+    /// 参数与局部变量的类型，未声明类型的参数为 any
+    type_env: Vec<HashMap<String, TypeId>>,
+    rebound: &'a HashSet<String>,
+    finder: &'a mut Finder<'b>,
+    out: &'a mut Vec<Diagnostic>,
+}
+
+pub fn analyze_calls(
+    module: &xu_parser::Module,
+    structs: &StructMap,
+    rebound: &HashSet<String>,
+    finder: &mut Finder<'_>,
+    out: &mut Vec<Diagnostic>,
+) {
+    let mut interner = TypeInterner::new();
+    let mut func_sigs = HashMap::new();
+    collect_all_func_sigs(&module.stmts, &mut func_sigs, &mut interner);
+    let mut checker =
+        CallChecker { func_sigs, structs, interner, type_env: vec![HashMap::new()], rebound, finder, out };
+    checker.stmts(&module.stmts);
+}
+
+impl CallChecker<'_, '_> {
+    fn func(&mut self, def: &FuncDef) {
+        let mut params = HashMap::new();
+        for p in def.params.iter() {
+            let ty = match &p.ty {
+                Some(t) => typeref_to_typeid(&mut self.interner, t),
+                None => self.interner.intern(Type::Any),
+            };
+            params.insert(p.name.clone(), ty);
+            if let Some(d) = &p.default {
+                self.expr(d);
+            }
+        }
+        self.type_env.push(params);
+        self.stmts(&def.body);
+        self.type_env.pop();
+    }
+
+    /// 在新作用域中检查 `body`，`names` 绑定为 any
+    fn scoped(&mut self, names: Vec<String>, body: &[Stmt]) {
+        let any = self.interner.intern(Type::Any);
+        self.type_env.push(names.into_iter().map(|n| (n, any)).collect());
+        self.stmts(body);
+        self.type_env.pop();
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) {
+        for s in stmts {
+            self.stmt(s);
+        }
+    }
+
+    fn stmt(&mut self, s: &Stmt) {
+        match s {
+            Stmt::StructDef(def) => {
+                // 越过声明中的字段名，使字面量的字段定位到字面量上
+                self.finder.find_name(&def.name);
+                for f in def.fields.iter() {
+                    self.finder.find_name(&f.name);
+                }
+                for f in def.static_fields.iter() {
+                    self.expr(&f.default);
+                }
+                for f in def.fields.iter() {
+                    if let Some(d) = &f.default {
+                        self.expr(d);
+                    }
+                }
+                for m in def.methods.iter() {
+                    self.func(m);
+                }
+            }
+            Stmt::FuncDef(def) => self.func(def),
+            Stmt::DoesBlock(def) => {
+                for f in def.funcs.iter() {
+                    self.func(f);
+                }
+            }
+            Stmt::If(s) => {
+                for (cond, body) in s.branches.iter() {
+                    self.expr(cond);
+                    self.scoped(Vec::new(), body);
+                }
+                if let Some(body) = &s.else_branch {
+                    self.scoped(Vec::new(), body);
+                }
+            }
+            Stmt::While(s) => {
+                self.expr(&s.cond);
+                self.scoped(Vec::new(), &s.body);
+            }
+            Stmt::ForEach(s) => {
+                self.expr(&s.iter);
+                self.scoped(vec![s.var.clone()], &s.body);
+            }
+            Stmt::Match(s) => {
+                self.expr(&s.expr);
+                for (pat, body) in s.arms.iter() {
+                    let mut binds = Vec::new();
+                    collect_pattern_binds(pat, &mut binds);
+                    self.scoped(binds, body);
+                }
+                if let Some(body) = &s.else_branch {
+                    self.scoped(Vec::new(), body);
+                }
+            }
+            Stmt::Block(stmts) => self.scoped(Vec::new(), stmts),
+            Stmt::Return(Some(e)) | Stmt::Expr(e) => self.expr(e),
+            Stmt::Assign(s) => {
+                self.expr(&s.target);
+                self.expr(&s.value);
+                if let (Some(_), Expr::Ident(name, _)) = (s.decl, &s.target) {
+                    let ty = match &s.ty {
+                        Some(t) => Some(typeref_to_typeid(&mut self.interner, t)),
+                        None => infer_type(&s.value, &self.func_sigs, self.structs, &self.type_env, &mut self.interner),
+                    };
+                    let ty = ty.unwrap_or_else(|| self.interner.intern(Type::Any));
+                    self.type_env.last_mut().expect("type_env should not be empty").insert(name.clone(), ty);
+                }
+            }
+            Stmt::EnumDef(_) | Stmt::Use(_) | Stmt::Return(None) | Stmt::Break | Stmt::Continue | Stmt::Error(_) => {}
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) {
+        for e in exprs {
+            self.expr(e);
+        }
+    }
+
+    fn expr(&mut self, e: &Expr) {
+        match e {
+            Expr::Call(c) => {
+                if let Expr::Ident(name, _) = c.callee.as_ref() {
+                    if let Some(sig) = builtin_signature(name).filter(|_| !self.rebound.contains(name)) {
+                        self.check(sig, &c.args);
+                    }
+                }
+                self.expr(&c.callee);
+                self.exprs(&c.args);
+            }
+            Expr::InterpolatedString(items) | Expr::List(items) | Expr::Tuple(items) => self.exprs(items),
+            Expr::Range(r) => {
+                self.expr(&r.start);
+                self.expr(&r.end);
+            }
+            Expr::IfExpr(i) => {
+                self.expr(&i.cond);
+                self.expr(&i.then_expr);
+                self.expr(&i.else_expr);
+            }
+            Expr::Match(m) => {
+                self.expr(&m.expr);
+                for (_, arm) in m.arms.iter() {
+                    self.expr(arm);
+                }
+                if let Some(e) = &m.else_expr {
+                    self.expr(e);
+                }
+            }
+            Expr::FuncLit(def) => self.func(def),
+            Expr::Dict(entries) => {
+                for (_, v) in entries.iter() {
+                    self.expr(v);
+                }
+            }
+            Expr::StructInit(s) => {
+                if let Some(m) = &s.module {
+                    self.expr(m);
+                }
+                if s.module.is_none() && self.structs.contains_key(&s.ty) {
+                    self.finder.find_name(&s.ty);
+                }
+                for item in s.items.iter() {
+                    match item {
+                        StructInitItem::Spread(e) => self.expr(e),
+                        StructInitItem::Field(name, e) => {
+                            if s.module.is_none() {
+                                self.field(&s.ty, name, e);
+                            }
+                            self.expr(e);
+                        }
+                    }
+                }
+            }
+            Expr::EnumCtor { module, args, .. } => {
+                if let Some(m) = module {
+                    self.expr(m);
+                }
+                self.exprs(args);
+            }
+            Expr::Member(m) => self.expr(&m.object),
+            Expr::Index(i) => {
+                self.expr(&i.object);
+                self.expr(&i.index);
+            }
+            Expr::MethodCall(m) => {
+                self.expr(&m.receiver);
+                self.method(m);
+                self.exprs(&m.args);
+            }
+            Expr::Unary { expr, .. }
+            | Expr::Group(expr)
+            | Expr::Try(expr)
+            | Expr::OptChain(expr)
+            | Expr::OptLink(expr) => self.expr(expr),
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Compare(c) => {
+                self.expr(&c.first);
+                for (_, e) in c.links.iter() {
+                    self.expr(e);
+                }
+            }
+            Expr::Ident(..) | Expr::Int(_) | Expr::Float(_) | Expr::Str(_) | Expr::Bool(_) | Expr::Error(_) => {}
+        }
+    }
+
+    /// 检查一处内置函数调用；先于参数检查，使 finder 停在函数名上
+    fn check(&mut self, sig: &BuiltinSig, args: &[Expr]) {
+        let span = self.finder.find_name(sig.name);
+        let max = sig.max.unwrap_or(usize::MAX);
+        if args.len() < sig.min || args.len() > max {
+            self.out.push(
+                Diagnostic::error_kind(
+                    DiagnosticKind::ArgumentCountMismatch {
+                        expected_min: sig.min,
+                        expected_max: max,
+                        actual: args.len(),
+                    },
+                    span,
+                )
+                .with_code(codes::ARGUMENT_COUNT_MISMATCH),
+            );
+            return;
+        }
+        for (idx, (arg, &param)) in args.iter().zip(sig.params).enumerate() {
+            // 带标签的调用以 () 占住跳过的可选参数
+            let skipped = idx >= sig.min && matches!(arg, Expr::Tuple(items) if items.is_empty());
+            if param == "any" || skipped {
+                continue;
+            }
+            let Some(actual) = infer_type(arg, &self.func_sigs, self.structs, &self.type_env, &mut self.interner)
+            else {
+                continue;
+            };
+            let accepted = match self.interner.get(actual) {
+                Type::Any => true,
+                // 签名中的 func 指任意函数，不限参数与返回类型
+                Type::Function(..) => param == "func",
+                _ => {
+                    let expected = self.interner.parse_type_str(param);
+                    !type_mismatch_id(&self.interner, expected, actual)
+                }
+            };
+            if !accepted {
+                self.out.push(
+                    Diagnostic::error_kind(
+                        DiagnosticKind::TypeMismatch {
+                            expected: param.to_string(),
+                            actual: self.interner.name(actual),
+                        },
+                        span,
+                    )
+                    .with_code(codes::TYPE_MISMATCH)
+                    .with_help(DiagnosticsFormatter::format(&DiagnosticKind::ArgumentHasWrongType(idx + 1))),
+                );
+            }
+        }
+    }
+
+    /// 检查内置类型上的方法调用，未知的方法名给出最接近的方法作为建议
+    fn method(&mut self, m: &MethodCallExpr) {
+        let Some(recv) = infer_type(&m.receiver, &self.func_sigs, self.structs, &self.type_env, &mut self.interner)
+        else {
+            return;
+        };
+        let ty = match self.interner.get(recv) {
+            Type::Text => "text",
+            Type::List(_) => "list",
+            Type::Dict(..) => "dict",
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::Bool => "bool",
+            _ => return,
+        };
+        let Some(methods) = builtin_methods(ty) else {
+            return;
+        };
+        let span = self.finder.find_name(&m.method);
+        if methods.contains(&m.method.as_str()) {
+            return;
+        }
+        let mut diag = Diagnostic::error_kind(
+            DiagnosticKind::UnsupportedMethod { method: m.method.clone(), ty: ty.to_string() },
+            span,
+        )
+        .with_code(codes::UNSUPPORTED_METHOD);
+        if let Some(suggested) = find_best_match(&m.method, methods.iter().copied()) {
+            diag = diag
+                .with_suggestion(DiagnosticsFormatter::format(&DiagnosticKind::DidYouMean(suggested.to_string())));
+        }
+        self.out.push(diag);
+    }
+
+    /// 检查结构体字面量中的一个字段值；字段类型是未声明的类型名（如类型参数）时不检查
+    fn field(&mut self, ty: &str, name: &str, value: &Expr) {
+        let Some(declared) = self.structs.get(ty).and_then(|fields| fields.get(name)) else {
+            return;
+        };
+        let span = self.finder.find_name(name);
+        let expected = self.interner.parse_type_str(declared);
+        if let Type::Struct(n) = self.interner.get(expected) {
+            if !self.structs.contains_key(n) {
+                return;
+            }
+        }
+        let Some(actual) = infer_type(value, &self.func_sigs, self.structs, &self.type_env, &mut self.interner) else {
+            return;
+        };
+        let accepted = match (self.interner.get(expected), self.interner.get(actual)) {
+            (_, Type::Any) | (Type::Function(..), Type::Function(..)) => true,
+            _ => {
+                !type_mismatch_id(&self.interner, expected, actual)
+                    || empty_container_literal_ok(&self.interner, expected, value)
+            }
+        };
+        if !accepted {
+            self.out.push(
+                Diagnostic::error_kind(
+                    DiagnosticKind::FieldTypeMismatch {
+                        ty: ty.to_string(),
+                        field: name.to_string(),
+                        expected: declared.clone(),
+                        actual: self.interner.name(actual),
+                    },
+                    span,
+                )
+                .with_code(codes::TYPE_MISMATCH),
+            );
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 use xu_syntax::{Diagnostic, DiagnosticKind, codes, DiagnosticsFormatter, find_best_match};
 use xu_parser::{Expr, StructInitItem};
 use super::utils::{Finder, collect_pattern_binds};
@@ -76,6 +77,7 @@ pub fn analyze_expr(
             }
         }
         Expr::FuncLit(def) => {
+            let def = Rc::make_mut(def);
             // Re-use logic from stmt analysis for local function bodies
             // Note: Since we are splitting files, we might need to handle this recursion
             // by passing a callback or exposing analyze_stmts.
@@ -172,7 +174,14 @@ pub fn analyze_expr(
             analyze_expr(left, funcs, scope, finder, out);
             analyze_expr(right, funcs, scope, finder, out);
         }
+        Expr::Compare(c) => {
+            analyze_expr(&mut c.first, funcs, scope, finder, out);
+            for (_, e) in c.links.iter_mut() {
+                analyze_expr(e, funcs, scope, finder, out);
+            }
+        }
         Expr::Group(e) => analyze_expr(e, funcs, scope, finder, out),
+        Expr::Try(e) | Expr::OptChain(e) | Expr::OptLink(e) => analyze_expr(e, funcs, scope, finder, out),
         Expr::InterpolatedString(parts) => {
             for p in parts {
                 analyze_expr(p, funcs, scope, finder, out);
@@ -3,7 +3,7 @@
 //! Responsible for semantic analysis, type checking, and scope management.
 //! Refactored in v1.1 to enforce strict mode and remove legacy features.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
@@ -11,26 +11,78 @@ use std::sync::{Arc, RwLock};
 use xu_lexer::Lexer;
 use xu_parser::Parser;
 use xu_syntax::{
-    Diagnostic, DiagnosticKind, codes, SourceFile,
+    Diagnostic, SourceFile,
     BUILTIN_NAMES,
 };
 
+use crate::const_eval::{ConstMap, Consts};
+
 mod utils;
 mod expr;
 mod stmt;
 mod types;
+mod try_expr;
+mod calls;
 
 pub use types::type_to_string;
 // Re-export StructMap for submodules
 pub(crate) type StructMap = HashMap<String, HashMap<String, String>>;
 
-use utils::Finder;
+pub(crate) use utils::Finder;
 use stmt::analyze_stmts;
 use types::analyze_types;
+use try_expr::analyze_try;
+use calls::analyze_calls;
 
 #[derive(Clone, Default, Debug)]
 pub struct ImportCache {
-    pub modules: HashMap<PathBuf, (Vec<String>, StructMap)>,
+    pub modules: HashMap<PathBuf, (Vec<String>, StructMap, ConstMap)>,
+    /// Exports of the prelude module, predefined in every analyzed module.
+    pub prelude: Option<PreludeExports>,
+    /// Global names the runtime defines besides the builtins, such as those
+    /// of plugins, predefined in every analyzed module.
+    pub predefs: Vec<String>,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct PreludeExports {
+    pub path: PathBuf,
+    pub names: Vec<String>,
+    pub structs: StructMap,
+}
+
+/// Collects the public names of a prelude module, matching what the runtime
+/// exports from it.
+pub(crate) fn collect_prelude_exports(path: PathBuf, module: &xu_parser::Module) -> PreludeExports {
+    let mut names = Vec::new();
+    let mut structs = HashMap::new();
+    for s in &module.stmts {
+        match s {
+            xu_parser::Stmt::FuncDef(def) if def.vis == xu_parser::Visibility::Public => {
+                names.push(def.name.clone());
+            }
+            xu_parser::Stmt::EnumDef(def) if def.vis == xu_parser::Visibility::Public => {
+                names.push(def.name.clone());
+            }
+            xu_parser::Stmt::StructDef(def) if def.vis == xu_parser::Visibility::Public => {
+                let mut fields = HashMap::new();
+                for f in &def.fields {
+                    fields.insert(f.name.clone(), type_to_string(&f.ty));
+                }
+                for sf in &def.static_fields {
+                    fields.insert(format!("static:{}", sf.name), type_to_string(&sf.ty));
+                }
+                structs.insert(def.name.clone(), fields);
+            }
+            xu_parser::Stmt::Assign(a) if a.decl.is_some() && a.vis == xu_parser::Visibility::Public => {
+                if let xu_parser::Expr::Ident(name, _) = &a.target {
+                    names.push(name.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    PreludeExports { path, names, structs }
 }
 
 pub(crate) fn analyze_module(
@@ -85,15 +137,30 @@ pub(crate) fn analyze_module(
         }
     }
 
+    let (prelude, predefs) = {
+        let cache = cache.read().unwrap();
+        (cache.prelude.clone(), cache.predefs.clone())
+    };
+    let prelude = prelude.filter(|p| p.path != current_path);
+    if let Some(p) = &prelude {
+        for (name, fields) in &p.structs {
+            structs.entry(name.clone()).or_insert_with(|| fields.clone());
+        }
+    }
+
     let mut scope: Vec<HashMap<String, usize>> = vec![HashMap::new()];
     let mut def_spans: Vec<HashMap<String, xu_syntax::Span>> = vec![HashMap::new()];
     for builtin in BUILTIN_NAMES {
         let idx = scope.last().expect("scope stack should not be empty").len();
         scope.last_mut().expect("scope stack should not be empty").insert(builtin.to_string(), idx);
     }
-    for name in extra_predefs {
+    for name in extra_predefs.iter().copied().chain(predefs.iter().map(String::as_str)) {
         let idx = scope.last().expect("scope stack should not be empty").len();
-        scope.last_mut().expect("scope stack should not be empty").insert((*name).to_string(), idx);
+        scope.last_mut().expect("scope stack should not be empty").insert(name.to_string(), idx);
+    }
+    for name in prelude.iter().flat_map(|p| p.names.iter()) {
+        let idx = scope.last().expect("scope stack should not be empty").len();
+        scope.last_mut().expect("scope stack should not be empty").insert(name.clone(), idx);
     }
     for name in funcs.keys() {
         let idx = scope.last().expect("scope stack should not be empty").len();
@@ -125,6 +192,16 @@ pub(crate) fn analyze_module(
     let mut type_finder = Finder::new(source, tokens);
     analyze_types(module, &structs, &mut type_finder, &mut out);
 
+    let mut try_finder = Finder::new(source, tokens);
+    analyze_try(module, &structs, &mut try_finder, &mut out);
+
+    let mut rebound: HashSet<String> =
+        extra_predefs.iter().map(|n| n.to_string()).chain(predefs).chain(prelude.into_iter().flat_map(|p| p.names)).collect();
+    let resolved = crate::resolve::resolve(source.text.as_str(), tokens);
+    rebound.extend(resolved.decls.keys().map(|&i| resolved.name(i).to_string()));
+    let mut call_finder = Finder::new(source, tokens);
+    analyze_calls(module, &structs, &rebound, &mut call_finder, &mut out);
+
     import_stack.pop();
     out
 }
@@ -134,22 +211,13 @@ pub(crate) fn process_import(
     base_dir: &Path,
     cache: Arc<RwLock<ImportCache>>,
     out: &mut Vec<Diagnostic>,
-    span: Option<xu_syntax::Span>,
     import_stack: &mut Vec<PathBuf>,
-) -> (Vec<String>, StructMap) {
+) -> (Vec<String>, StructMap, ConstMap) {
     if let Ok(abs_path) = resolve_import_path(base_dir, path) {
-        // Check for circular import
+        // Circular imports are allowed; the runtime hands back the partially
+        // initialized module, so there is nothing to merge into scope here.
         if import_stack.contains(&abs_path) {
-            let mut chain: Vec<String> = import_stack
-                .iter()
-                .map(|p| p.to_string_lossy().into_owned())
-                .collect();
-            chain.push(abs_path.to_string_lossy().into_owned());
-            out.push(
-                Diagnostic::error_kind(DiagnosticKind::CircularImport(chain), span)
-                    .with_code(codes::CIRCULAR_IMPORT),
-            );
-            return (Vec::new(), HashMap::new());
+            return (Vec::new(), HashMap::new(), HashMap::new());
         }
 
         if let Some(cached) = cache.read().unwrap().modules.get(&abs_path) {
@@ -160,6 +228,7 @@ pub(crate) fn process_import(
             let lex = Lexer::new(&input).lex();
             let bump = bumpalo::Bump::new();
             let mut parse = Parser::new(&input, &lex.tokens, &bump).parse();
+            crate::labels::resolve_labels(&mut parse.module, &abs_path.to_string_lossy(), &mut parse.diagnostics);
 
             let source = SourceFile::new(
                 xu_syntax::SourceId(0),
@@ -204,12 +273,55 @@ pub(crate) fn process_import(
                 }
             }
 
-            let res = (func_exports, struct_exports);
+            // Like other bindings, only public constants are exported.
+            let dir = abs_path.parent().unwrap_or(Path::new("."));
+            let mut const_exports = module_consts(&parse.module.stmts, dir, &cache).own;
+            const_exports.retain(|name, _| {
+                parse.module.stmts.iter().any(|s| match s {
+                    xu_parser::Stmt::Assign(a) => {
+                        a.vis == xu_parser::Visibility::Public
+                            && matches!(&a.target, xu_parser::Expr::Ident(n, _) if n == name)
+                    }
+                    _ => false,
+                })
+            });
+
+            let res = (func_exports, struct_exports, const_exports);
             cache.write().unwrap().modules.insert(abs_path, res.clone());
             return res;
         }
     }
-    (Vec::new(), HashMap::new())
+    (Vec::new(), HashMap::new(), HashMap::new())
+}
+
+/// The constants a module declares at its top level, folded in order, and
+/// the public constants of the modules it imports, taken from `cache`.
+pub(crate) fn module_consts(stmts: &[xu_parser::Stmt], base_dir: &Path, cache: &RwLock<ImportCache>) -> Consts {
+    let mut consts = Consts::default();
+    for s in stmts {
+        match s {
+            xu_parser::Stmt::Use(u) => {
+                let Ok(abs_path) = resolve_import_path(base_dir, &u.path) else { continue };
+                if let Some((_, _, exported)) = cache.read().unwrap().modules.get(&abs_path) {
+                    let alias = u.alias.clone().unwrap_or_else(|| infer_module_alias(&u.path));
+                    consts.modules.insert(alias, exported.clone());
+                }
+            }
+            xu_parser::Stmt::Assign(a) if a.decl.is_some() => {
+                let xu_parser::Expr::Ident(name, _) = &a.target else { continue };
+                let value = match a.decl {
+                    Some(xu_parser::DeclKind::Const) => consts.eval(&a.value),
+                    _ => None,
+                };
+                match value {
+                    Some(v) => consts.own.insert(name.clone(), v),
+                    None => consts.own.remove(name),
+                };
+            }
+            _ => {}
+        }
+    }
+    consts
 }
 
 pub(crate) fn infer_module_alias(path: &str) -> String {
@@ -224,7 +336,7 @@ pub(crate) fn infer_module_alias(path: &str) -> String {
     last.strip_suffix(".xu").unwrap_or(last).to_string()
 }
 
-fn resolve_import_path(base_dir: &Path, path: &str) -> Result<PathBuf, ()> {
+pub(crate) fn resolve_import_path(base_dir: &Path, path: &str) -> Result<PathBuf, ()> {
     let p = Path::new(path);
     if p.is_absolute() {
         return Ok(p.to_path_buf());
@@ -1,12 +1,14 @@
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::{Arc, RwLock};
-use xu_syntax::{Diagnostic, DiagnosticKind, codes, find_best_match, DiagnosticsFormatter, BUILTIN_NAMES};
-use xu_parser::{Stmt, Expr, FuncDef, Param};
+use xu_syntax::{Diagnostic, DiagnosticKind, TokenKind, codes, find_best_match, DiagnosticsFormatter, BUILTIN_NAMES};
+use xu_parser::{DeclKind, Stmt, Expr, FuncDef, Param};
 use super::utils::{Finder, report_shadowing, collect_pattern_binds};
 use super::expr::analyze_expr;
 use super::{ImportCache, process_import, infer_module_alias};
+use crate::const_eval::Consts;
 
 /// 返回不应触发遮蔽警告的内置函数名集合
 fn builtin_names_set() -> HashSet<&'static str> {
@@ -26,6 +28,10 @@ struct AnalyzeContext<'a, 'b> {
     cache: Arc<RwLock<ImportCache>>,
     import_stack: &'a mut Vec<PathBuf>,
     builtins: HashSet<&'static str>,
+    /// 已声明的模块常量与导入模块的公开常量
+    consts: Consts,
+    /// 正在分析的语句列表的嵌套层数，模块顶层为 1
+    depth: usize,
 }
 
 impl<'a, 'b> AnalyzeContext<'a, 'b> {
@@ -64,6 +70,7 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
 
     /// 分析语句列表的内部实现
     fn analyze_stmts_impl(&mut self, stmts: &mut [Stmt]) -> bool {
+        self.depth += 1;
         let mut terminated = false;
         for s in stmts {
             if terminated {
@@ -76,9 +83,9 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
                 );
             }
             match s {
-                Stmt::StructDef(def) => self.analyze_struct_def(def),
+                Stmt::StructDef(def) => self.analyze_struct_def(Rc::make_mut(def)),
                 Stmt::EnumDef(_) => {}
-                Stmt::FuncDef(def) => self.analyze_func_def(def),
+                Stmt::FuncDef(def) => self.analyze_func_def(Rc::make_mut(def)),
                 Stmt::DoesBlock(def) => self.analyze_does_block(def),
                 Stmt::Use(u) => self.analyze_use_stmt(u),
                 Stmt::If(s) => {
@@ -114,12 +121,14 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
                 Stmt::Error(_) => {}
             }
         }
+        self.depth -= 1;
         terminated
     }
 
     /// 分析结构体定义
     fn analyze_struct_def(&mut self, def: &mut xu_parser::StructDef) {
         for method in def.methods.iter_mut() {
+            let method = Rc::make_mut(method);
             let idx = self.scope.last().expect("scope stack should not be empty").len();
             self.scope.last_mut().expect("scope stack should not be empty").insert(method.name.clone(), idx);
             self.scope.push(HashMap::new());
@@ -139,6 +148,7 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
     /// 分析 does 块
     fn analyze_does_block(&mut self, def: &mut xu_parser::DoesBlock) {
         for func_def in def.funcs.iter_mut() {
+            let func_def = Rc::make_mut(func_def);
             let idx = self.scope.last().expect("scope stack should not be empty").len();
             self.scope.last_mut().expect("scope stack should not be empty").insert(func_def.name.clone(), idx);
             self.scope.push(HashMap::new());
@@ -152,12 +162,13 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
 
     /// 分析 use 语句
     fn analyze_use_stmt(&mut self, u: &xu_parser::UseStmt) {
-        let (new_funcs, new_structs) = process_import(
+        // Advance past the `use` keyword so the alias lookup below starts after it.
+        self.finder.find_name_or_next("use");
+        let (new_funcs, new_structs, new_consts) = process_import(
             &u.path,
             self.base_dir,
             self.cache.clone(),
             self.out,
-            self.finder.find_name_or_next("use"),
             self.import_stack,
         );
         for name in new_funcs {
@@ -179,6 +190,7 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
         {
             report_shadowing(&alias, self.finder, self.out);
         }
+        self.consts.modules.insert(alias.clone(), new_consts);
         let idx = self.scope.last().expect("scope stack should not be empty").len();
         self.scope.last_mut().expect("scope stack should not be empty").insert(alias.clone(), idx);
         if let Some(sp) = self.finder.find_name_or_next(&alias) {
@@ -272,6 +284,9 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
 
     /// 分析赋值语句
     fn analyze_assign_stmt(&mut self, s: &mut xu_parser::AssignStmt) {
+        if s.decl == Some(DeclKind::Const) {
+            self.analyze_const_decl(s);
+        }
         analyze_expr(&mut s.value, self.funcs, self.scope, self.finder, self.out);
 
         // 检查 unit 赋值
@@ -291,8 +306,8 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
                     _ => false,
                 };
                 if needs_annot {
-                    self.out.push(Diagnostic::error(
-                        "Type annotation required for empty container literal",
+                    self.out.push(Diagnostic::error_kind(
+                        DiagnosticKind::EmptyContainerNeedsAnnotation,
                         self.finder.find_name_or_next(name),
                     ));
                 }
@@ -309,6 +324,21 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
             if s.decl.is_some() && resolved.is_some() {
                 report_shadowing(name, self.finder, self.out);
             }
+            if s.decl.is_some() && s.decl != Some(DeclKind::Const) && self.depth == 1 {
+                self.consts.own.remove(name.as_str());
+            }
+            // 常量只在声明处赋值一次
+            let module_level = resolved.is_some_and(|(depth, _)| depth as usize + 1 == self.scope.len());
+            if s.decl.is_none() && module_level && self.consts.own.contains_key(name.as_str()) {
+                self.out.push(Diagnostic::error_kind(
+                    DiagnosticKind::ConstReassignment(name.clone()),
+                    self.finder.find_name_or_next(name),
+                ));
+            }
+            // 声明遮蔽外层绑定（含内置函数）时在当前作用域分配新槽位
+            if s.decl.is_some() && resolved.is_some_and(|(depth, _)| depth != 0) {
+                resolved = None;
+            }
 
             // 严格模式下检查未定义标识符
             if self.strict && s.ty.is_none() && s.decl.is_none() {
@@ -351,6 +381,26 @@ impl<'a, 'b> AnalyzeContext<'a, 'b> {
     }
 }
 
+impl AnalyzeContext<'_, '_> {
+    /// 检查 `const` 声明：只能位于模块顶层，值须能在编译期求出
+    fn analyze_const_decl(&mut self, s: &xu_parser::AssignStmt) {
+        let Expr::Ident(name, _) = &s.target else { return };
+        let span = self.finder.find_kw_or_next(TokenKind::KwConst);
+        if self.depth > 1 {
+            self.out.push(Diagnostic::error_kind(DiagnosticKind::ConstNotAtModuleLevel, span));
+            return;
+        }
+        match self.consts.eval(&s.value) {
+            Some(v) => {
+                self.consts.own.insert(name.clone(), v);
+            }
+            None => {
+                self.out.push(Diagnostic::error_kind(DiagnosticKind::ConstNotEvaluable(name.clone()), span));
+            }
+        }
+    }
+}
+
 /// 检查表达式是否为 unit 字面量（空元组）
 fn is_unit_expr(expr: &Expr) -> bool {
     match expr {
@@ -387,6 +437,8 @@ pub fn analyze_stmts(
         cache,
         import_stack,
         builtins: builtin_names_set(),
+        consts: Consts::default(),
+        depth: 0,
     };
     ctx.analyze_stmts_impl(stmts)
 }
@@ -448,6 +500,12 @@ pub(crate) fn analyze_local_stmts_shim(
             }
             Stmt::Return(None) => {}
             Stmt::Assign(a) => {
+                if a.decl == Some(DeclKind::Const) {
+                    out.push(Diagnostic::error_kind(
+                        DiagnosticKind::ConstNotAtModuleLevel,
+                        finder.find_kw_or_next(TokenKind::KwConst),
+                    ));
+                }
                 analyze_expr(&mut a.value, funcs, scope, finder, out);
                 if let Expr::Ident(name, slot) = &mut a.target {
                     let mut resolved = None;
@@ -0,0 +1,239 @@
+//! `?` 运算符检查
+//!
+//! `expr?` 会从所在函数提前返回 err/none，因此只能出现在函数内，且函数
+//! 若声明了返回类型，必须是 Result 或 Option；操作数的类型能推断出来时
+//! 也必须是 Result 或 Option。
+
+use std::collections::HashMap;
+use xu_parser::{Expr, FuncDef, Stmt, StructInitItem};
+use xu_syntax::{Diagnostic, DiagnosticKind, TokenKind, Type, TypeId, TypeInterner, codes};
+
+use super::StructMap;
+use super::types::{collect_all_func_sigs, infer_type, type_to_string, typeref_to_typeid};
+use super::utils::Finder;
+
+type FuncSigs = HashMap<String, (Vec<Option<TypeId>>, Option<TypeId>)>;
+
+/// `?` 所在的位置
+#[derive(Clone, Copy)]
+enum Context<'m> {
+    TopLevel,
+    Func(&'m FuncDef),
+}
+
+struct TryChecker<'a, 'b, 'm> {
+    func_sigs: FuncSigs,
+    structs: &'a StructMap,
+    interner: TypeInterner,
+    /// 当前函数参数的声明类型
+    type_env: Vec<HashMap<String, TypeId>>,
+    finder: &'a mut Finder<'b>,
+    out: &'a mut Vec<Diagnostic>,
+    ctx: Context<'m>,
+}
+
+pub fn analyze_try(module: &xu_parser::Module, structs: &StructMap, finder: &mut Finder<'_>, out: &mut Vec<Diagnostic>) {
+    let mut interner = TypeInterner::new();
+    let mut func_sigs = HashMap::new();
+    collect_all_func_sigs(&module.stmts, &mut func_sigs, &mut interner);
+    let mut checker = TryChecker {
+        func_sigs,
+        structs,
+        interner,
+        type_env: vec![HashMap::new()],
+        finder,
+        out,
+        ctx: Context::TopLevel,
+    };
+    checker.stmts(&module.stmts);
+}
+
+impl<'m> TryChecker<'_, '_, 'm> {
+    fn func(&mut self, def: &'m FuncDef) {
+        let outer = std::mem::replace(&mut self.ctx, Context::Func(def));
+        let mut params = HashMap::new();
+        for p in def.params.iter() {
+            if let Some(t) = &p.ty {
+                params.insert(p.name.clone(), typeref_to_typeid(&mut self.interner, t));
+            }
+            if let Some(d) = &p.default {
+                self.expr(d);
+            }
+        }
+        self.type_env.push(params);
+        self.stmts(&def.body);
+        self.type_env.pop();
+        self.ctx = outer;
+    }
+
+    fn stmts(&mut self, stmts: &'m [Stmt]) {
+        for s in stmts {
+            self.stmt(s);
+        }
+    }
+
+    fn stmt(&mut self, s: &'m Stmt) {
+        match s {
+            Stmt::StructDef(def) => {
+                for f in def.static_fields.iter() {
+                    self.expr(&f.default);
+                }
+                for f in def.fields.iter() {
+                    if let Some(d) = &f.default {
+                        self.expr(d);
+                    }
+                }
+                for m in def.methods.iter() {
+                    self.func(m);
+                }
+            }
+            Stmt::FuncDef(def) => self.func(def),
+            Stmt::DoesBlock(def) => {
+                for f in def.funcs.iter() {
+                    self.func(f);
+                }
+            }
+            Stmt::If(s) => {
+                for (cond, body) in s.branches.iter() {
+                    self.expr(cond);
+                    self.stmts(body);
+                }
+                if let Some(body) = &s.else_branch {
+                    self.stmts(body);
+                }
+            }
+            Stmt::While(s) => {
+                self.expr(&s.cond);
+                self.stmts(&s.body);
+            }
+            Stmt::ForEach(s) => {
+                self.expr(&s.iter);
+                self.stmts(&s.body);
+            }
+            Stmt::Match(s) => {
+                self.expr(&s.expr);
+                for (_, body) in s.arms.iter() {
+                    self.stmts(body);
+                }
+                if let Some(body) = &s.else_branch {
+                    self.stmts(body);
+                }
+            }
+            Stmt::Block(stmts) => self.stmts(stmts),
+            Stmt::Return(Some(e)) | Stmt::Expr(e) => self.expr(e),
+            Stmt::Assign(s) => {
+                self.expr(&s.target);
+                self.expr(&s.value);
+            }
+            Stmt::EnumDef(_) | Stmt::Use(_) | Stmt::Return(None) | Stmt::Break | Stmt::Continue | Stmt::Error(_) => {}
+        }
+    }
+
+    fn exprs(&mut self, exprs: &'m [Expr]) {
+        for e in exprs {
+            self.expr(e);
+        }
+    }
+
+    fn expr(&mut self, e: &'m Expr) {
+        match e {
+            Expr::Try(inner) => {
+                self.expr(inner);
+                self.check(inner);
+            }
+            Expr::InterpolatedString(items) | Expr::List(items) | Expr::Tuple(items) => self.exprs(items),
+            Expr::Range(r) => {
+                self.expr(&r.start);
+                self.expr(&r.end);
+            }
+            Expr::IfExpr(i) => {
+                self.expr(&i.cond);
+                self.expr(&i.then_expr);
+                self.expr(&i.else_expr);
+            }
+            Expr::Match(m) => {
+                self.expr(&m.expr);
+                for (_, arm) in m.arms.iter() {
+                    self.expr(arm);
+                }
+                if let Some(e) = &m.else_expr {
+                    self.expr(e);
+                }
+            }
+            Expr::FuncLit(def) => self.func(def),
+            Expr::Dict(entries) => {
+                for (_, v) in entries.iter() {
+                    self.expr(v);
+                }
+            }
+            Expr::StructInit(s) => {
+                if let Some(m) = &s.module {
+                    self.expr(m);
+                }
+                for item in s.items.iter() {
+                    match item {
+                        StructInitItem::Spread(e) | StructInitItem::Field(_, e) => self.expr(e),
+                    }
+                }
+            }
+            Expr::EnumCtor { module, args, .. } => {
+                if let Some(m) = module {
+                    self.expr(m);
+                }
+                self.exprs(args);
+            }
+            Expr::Member(m) => self.expr(&m.object),
+            Expr::Index(i) => {
+                self.expr(&i.object);
+                self.expr(&i.index);
+            }
+            Expr::Call(c) => {
+                self.expr(&c.callee);
+                self.exprs(&c.args);
+            }
+            Expr::MethodCall(m) => {
+                self.expr(&m.receiver);
+                self.exprs(&m.args);
+            }
+            Expr::Unary { expr, .. } | Expr::Group(expr) | Expr::OptChain(expr) | Expr::OptLink(expr) => {
+                self.expr(expr)
+            }
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Compare(c) => {
+                self.expr(&c.first);
+                for (_, e) in c.links.iter() {
+                    self.expr(e);
+                }
+            }
+            Expr::Ident(..) | Expr::Int(_) | Expr::Float(_) | Expr::Str(_) | Expr::Bool(_) | Expr::Error(_) => {}
+        }
+    }
+
+    /// 检查一处 `operand?`；每处都要消耗一个 `?` token，使后面的位置对得上
+    fn check(&mut self, operand: &Expr) {
+        let span = self.finder.find_kw(TokenKind::Question);
+        let kind = match self.ctx {
+            Context::TopLevel => Some(DiagnosticKind::TryOutsideFunction),
+            Context::Func(def) => match &def.return_ty {
+                Some(t) if !matches!(t.name.as_str(), "Result" | "Option" | "any") => {
+                    Some(DiagnosticKind::TryInNonResultFunction(type_to_string(t)))
+                }
+                _ => None,
+            },
+        };
+        let kind = kind.or_else(|| {
+            let actual = infer_type(operand, &self.func_sigs, self.structs, &self.type_env, &mut self.interner)?;
+            match self.interner.get(actual) {
+                Type::Any | Type::Enum(_) => None,
+                Type::Struct(name) if name == "Result" || name == "Option" => None,
+                _ => Some(DiagnosticKind::TryOnNonResult(self.interner.name(actual))),
+            }
+        });
+        if let Some(kind) = kind {
+            self.out.push(Diagnostic::error_kind(kind, span).with_code(codes::INVALID_TRY));
+        }
+    }
+}
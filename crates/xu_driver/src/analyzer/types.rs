@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use xu_syntax::{Diagnostic, DiagnosticKind, codes, Type, TypeId, TypeInterner, TokenKind, Span};
+use std::rc::Rc;
+use xu_syntax::{Diagnostic, DiagnosticKind, DiagnosticsFormatter, codes, Type, TypeId, TypeInterner, TokenKind, Span};
 use xu_parser::{Stmt, Expr, TypeRef, UnaryOp, BinaryOp, ReceiverType};
 use super::utils::Finder;
 use super::{StructMap, infer_module_alias};
@@ -18,14 +19,14 @@ fn make_type_mismatch_diag(
     expected: TypeId,
     actual: TypeId,
     primary: Option<Span>,
-    label_msg: Option<&str>,
+    label: Option<DiagnosticKind>,
 ) -> Diagnostic {
     let mut d = Diagnostic::error_kind(
         DiagnosticKind::TypeMismatch { expected: interner.name(expected), actual: interner.name(actual) },
         primary,
     ).with_code(codes::TYPE_MISMATCH);
-    if let (Some(msg), Some(sp)) = (label_msg, primary) {
-        d = d.with_label(msg, sp);
+    if let (Some(label), Some(sp)) = (label, primary) {
+        d = d.with_label(DiagnosticsFormatter::format(&label), sp);
     }
     d
 }
@@ -51,7 +52,7 @@ pub fn analyze_types(
 }
 
 /// 递归收集所有函数签名（包括嵌套函数）
-fn collect_all_func_sigs(
+pub(super) fn collect_all_func_sigs(
     stmts: &[Stmt],
     func_sigs: &mut HashMap<String, (Vec<Option<TypeId>>, Option<TypeId>)>,
     interner: &mut TypeInterner,
@@ -126,7 +127,7 @@ fn analyze_type_stmts(
         match s {
             Stmt::StructDef(def) => {
                 for method in def.methods.iter() {
-                    analyze_type_stmts(&[Stmt::FuncDef(Box::new(method.clone()))], func_sigs, structs, type_env, finder, None, interner, out);
+                    analyze_type_stmts(&[Stmt::FuncDef(Rc::clone(method))], func_sigs, structs, type_env, finder, None, interner, out);
                 }
             }
             Stmt::EnumDef(_) => {}
@@ -137,7 +138,7 @@ fn analyze_type_stmts(
                         let tid = typeref_to_typeid(interner, t);
                         env_last(type_env).insert(p.name.clone(), tid);
                         if let Some(d) = &p.default {
-                            check_type_match(d, tid, func_sigs, structs, type_env, finder, interner, out, Some("Variable is defined here"));
+                            check_type_match(d, tid, func_sigs, structs, type_env, finder, interner, out, Some(DiagnosticKind::VariableDefinedHere));
                         }
                     }
                 }
@@ -147,7 +148,7 @@ fn analyze_type_stmts(
             }
             Stmt::DoesBlock(def) => {
                 for f in def.funcs.iter() {
-                    analyze_type_stmts(&[Stmt::FuncDef(Box::new(f.clone()))], func_sigs, structs, type_env, finder, None, interner, out);
+                    analyze_type_stmts(&[Stmt::FuncDef(Rc::clone(f))], func_sigs, structs, type_env, finder, None, interner, out);
                 }
             }
             Stmt::Use(u) => {
@@ -195,7 +196,7 @@ fn analyze_type_stmts(
                         let any_id = interner.intern(Type::Any);
                         if actual != any_id && type_mismatch_id(interner, expected, actual) && !empty_container_literal_ok(interner, expected, e) {
                             out.push(make_type_mismatch_diag(interner, expected, actual, finder.find_kw_or_next(TokenKind::KwReturn), None)
-                                .with_help("Function return type is declared at definition"));
+                                .with_help(DiagnosticsFormatter::format(&DiagnosticKind::ReturnTypeDeclaredHere)));
                         }
                     }
                 }
@@ -227,12 +228,12 @@ fn check_type_match(
     finder: &mut Finder<'_>,
     interner: &mut TypeInterner,
     out: &mut Vec<Diagnostic>,
-    label_msg: Option<&str>,
+    label: Option<DiagnosticKind>,
 ) {
     if let Some(actual) = infer_type(expr, func_sigs, structs, type_env, interner) {
         if type_mismatch_id(interner, expected, actual) && !empty_container_literal_ok(interner, expected, expr) {
             let primary = finder.next_significant_span();
-            out.push(make_type_mismatch_diag(interner, expected, actual, primary, label_msg));
+            out.push(make_type_mismatch_diag(interner, expected, actual, primary, label));
         }
     }
 }
@@ -252,7 +253,7 @@ fn analyze_assign_stmt(
         if let Some(actual) = infer_type(&s.value, func_sigs, structs, type_env, interner) {
             if type_mismatch_id(interner, expected_id, actual) && !empty_container_literal_ok(interner, expected_id, &s.value) {
                 let primary = match &s.target { Expr::Ident(name, _) => finder.find_name_or_next(name), _ => finder.next_significant_span() };
-                out.push(make_type_mismatch_diag(interner, expected_id, actual, primary, Some("Variable is defined here")));
+                out.push(make_type_mismatch_diag(interner, expected_id, actual, primary, Some(DiagnosticKind::VariableDefinedHere)));
             }
         }
         if let Expr::Ident(name, _) = &s.target {
@@ -270,7 +271,7 @@ fn analyze_assign_stmt(
                 let any_id = interner.intern(Type::Any);
                 if actual != any_id && type_mismatch_id(interner, expected, actual) {
                     let primary = finder.find_name_or_next(name);
-                    out.push(make_type_mismatch_diag(interner, expected, actual, primary, Some("Variable is defined here")));
+                    out.push(make_type_mismatch_diag(interner, expected, actual, primary, Some(DiagnosticKind::VariableDefinedHere)));
                 }
             }
         }
@@ -369,14 +370,16 @@ pub fn infer_type(
             }
         }
         Expr::Range(_) => Some(interner.intern(Type::Range)),
+        Expr::Compare(_) => Some(interner.intern(Type::Bool)),
         Expr::StructInit(s) => Some(interner.parse_type_str(&s.ty)),
         Expr::EnumCtor { ty, .. } => Some(interner.parse_type_str(ty)),
-        Expr::Error(_) => None,
+        Expr::Error(_) | Expr::Try(_) | Expr::OptChain(_) | Expr::OptLink(_) => None,
         Expr::Ident(name, _) => type_env.iter().rev().find_map(|m| m.get(name).cloned()),
         Expr::Group(e) => infer_type(e, func_sigs, structs, type_env, interner),
         Expr::Unary { op, expr } => match op {
             UnaryOp::Not => Some(interner.intern(Type::Bool)),
             UnaryOp::Neg => infer_type(expr, func_sigs, structs, type_env, interner),
+            UnaryOp::BitNot => Some(interner.intern(Type::Int)),
         },
         Expr::Binary { op, left, right } => {
             let lt = infer_type(left, func_sigs, structs, type_env, interner);
@@ -389,7 +392,8 @@ pub fn infer_type(
                 | BinaryOp::Gt
                 | BinaryOp::Lt
                 | BinaryOp::Ge
-                | BinaryOp::Le => Some(interner.intern(Type::Bool)),
+                | BinaryOp::Le
+                | BinaryOp::In => Some(interner.intern(Type::Bool)),
                 BinaryOp::Add => {
                     let text = interner.intern(Type::Text);
                     let float = interner.intern(Type::Float);
@@ -413,6 +417,21 @@ pub fn infer_type(
                     }
                 }
                 BinaryOp::Div => Some(interner.intern(Type::Float)),
+                // int ** int is a float when the exponent is negative
+                BinaryOp::Pow => {
+                    let float = interner.intern(Type::Float);
+                    if lt == Some(float) || rt == Some(float) { Some(float) } else { None }
+                }
+                BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::Shl | BinaryOp::Shr => {
+                    Some(interner.intern(Type::Int))
+                }
+                // The left side may be an Option that is unwrapped, so only
+                // a plain value on both sides has a known type.
+                BinaryOp::Coalesce => {
+                    let plain =
+                        lt.is_some_and(|l| matches!(interner.get(l), Type::Int | Type::Float | Type::Text | Type::Bool));
+                    if plain && lt == rt { lt } else { None }
+                }
             }
         }
         Expr::Member(m) => {
@@ -443,6 +462,9 @@ pub fn infer_type(
                         let text = interner.intern(Type::Text);
                         return Some(interner.list(text));
                     }
+                    (Type::Dict(kid, _), "keys_sorted") => {
+                        return Some(interner.list(*kid));
+                    }
                     (Type::Dict(_, vid), "values") => {
                         return Some(interner.list(*vid));
                     }
@@ -544,7 +566,13 @@ pub fn infer_type(
             match (ot.map(|id| interner.get(id)), m.method.as_str()) {
                 (Some(Type::List(_)), "contains") => Some(interner.intern(Type::Bool)),
                 (Some(Type::List(_)), "add") => None, // Unit
-                (Some(Type::Dict(_, _)), "contains") => Some(interner.intern(Type::Bool)),
+                (Some(Type::List(_)), "sorted" | "take" | "drop") => ot,
+                (Some(Type::List(_)), "any" | "all") => Some(interner.intern(Type::Bool)),
+                (Some(Type::List(_)), "chunk") => ot.map(|tid| interner.list(tid)),
+                (Some(Type::List(_)), "to_string") => Some(interner.intern(Type::Text)),
+                (Some(Type::Dict(_, _)), "contains" | "remove") => Some(interner.intern(Type::Bool)),
+                (Some(Type::Dict(_, _)), "update") => None, // Unit
+                (Some(Type::Dict(_, vid)), "entry_or_insert") => Some(*vid),
                 (Some(Type::Dict(_, _)), "get") => {
                     // dict.get() returns Option[V], but we simplify to Option (struct type)
                     // since we don't have a proper Option[T] generic type in the type system
@@ -552,10 +580,21 @@ pub fn infer_type(
                 }
                 (Some(Type::Struct(s)), "read") if s == "file" => Some(interner.intern(Type::Text)),
                 (Some(Type::Struct(s)), "close") if s == "file" => None, // Unit
-                (Some(Type::Text), "split") => {
+                (Some(Type::Struct(s)), "write") if s == "file" => None, // Unit
+                (Some(Type::Struct(s)), "recv" | "try_recv") if s == "channel" => {
+                    Some(interner.intern(Type::Struct("Option".to_string())))
+                }
+                (Some(Type::Struct(s)), "send" | "close") if s == "channel" => None, // Unit
+                (Some(Type::Text), "split" | "chars" | "graphemes") => {
                     let text = interner.intern(Type::Text);
                     Some(interner.list(text))
                 }
+                (Some(Type::Text), "code_points" | "to_bytes") => {
+                    let int = interner.intern(Type::Int);
+                    Some(interner.list(int))
+                }
+                (Some(Type::Text), "byte_length" | "char_length") => Some(interner.intern(Type::Int)),
+                (Some(Type::Text), "normalize") => ot,
                 _ => None,
             }
         }
@@ -574,15 +613,20 @@ fn unify_types_id(interner: &mut TypeInterner, a: TypeId, b: TypeId) -> TypeId {
     interner.intern(Type::Any)
 }
 
-fn empty_container_literal_ok(interner: &TypeInterner, expected: TypeId, expr: &Expr) -> bool {
+pub(super) fn empty_container_literal_ok(interner: &TypeInterner, expected: TypeId, expr: &Expr) -> bool {
     match interner.get(expected) {
         Type::List(_) => matches!(expr, Expr::List(items) if items.is_empty()),
-        Type::Dict(_, _) => matches!(expr, Expr::Dict(entries) if entries.is_empty()),
+        Type::Dict(_, _) => match expr {
+            Expr::Dict(entries) => entries.is_empty(),
+            // dict() and dict(capacity: n) are empty dicts of any key/value type
+            Expr::Call(c) => matches!(c.callee.as_ref(), Expr::Ident(name, _) if name == "dict"),
+            _ => false,
+        },
         _ => false,
     }
 }
 
-fn type_mismatch_id(interner: &TypeInterner, expected: TypeId, actual: TypeId) -> bool {
+pub(super) fn type_mismatch_id(interner: &TypeInterner, expected: TypeId, actual: TypeId) -> bool {
     !type_compatible_id(interner, expected, actual)
 }
 
@@ -598,7 +642,7 @@ fn type_compatible_id(interner: &TypeInterner, expected: TypeId, actual: TypeId)
     }
 }
 
-fn typeref_to_typeid(interner: &mut TypeInterner, t: &TypeRef) -> TypeId {
+pub(super) fn typeref_to_typeid(interner: &mut TypeInterner, t: &TypeRef) -> TypeId {
     if t.params.is_empty() {
         if let Some(id) = interner.builtin_by_name(&t.name) {
             id
@@ -672,7 +716,7 @@ fn check_closure_call_args(
                                     finder.next_significant_span(),
                                 )
                                 .with_code(codes::TYPE_MISMATCH)
-                                .with_help(format!("Argument {} has wrong type", idx + 1));
+                                .with_help(DiagnosticsFormatter::format(&DiagnosticKind::ArgumentHasWrongType(idx + 1)));
                                 out.push(d);
                             }
                         }
@@ -685,7 +729,13 @@ fn check_closure_call_args(
             check_closure_call_args(left, func_sigs, structs, type_env, finder, interner, out);
             check_closure_call_args(right, func_sigs, structs, type_env, finder, interner, out);
         }
-        Expr::Unary { expr, .. } => {
+        Expr::Compare(c) => {
+            check_closure_call_args(&c.first, func_sigs, structs, type_env, finder, interner, out);
+            for (_, e) in c.links.iter() {
+                check_closure_call_args(e, func_sigs, structs, type_env, finder, interner, out);
+            }
+        }
+        Expr::Unary { expr, .. } | Expr::Try(expr) | Expr::OptChain(expr) | Expr::OptLink(expr) => {
             check_closure_call_args(expr, func_sigs, structs, type_env, finder, interner, out);
         }
         Expr::Member(m) => {
@@ -57,6 +57,17 @@ impl<'a> Finder<'a> {
     pub fn find_kw_or_next(&mut self, kind: TokenKind) -> Option<Span> {
         self.find_kw(kind).or_else(|| self.next_significant_span())
     }
+
+    pub fn find_any_kw(&mut self, kinds: &[TokenKind]) -> Option<Span> {
+        for idx in self.i..self.tokens.len() {
+            let t = &self.tokens[idx];
+            if kinds.contains(&t.kind) {
+                self.i = idx + 1;
+                return Some(t.span);
+            }
+        }
+        None
+    }
 }
 
 pub fn report_shadowing(name: &str, finder: &mut Finder<'_>, out: &mut Vec<Diagnostic>) {
@@ -3,21 +3,69 @@
 //!
 //!
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use xu_ir::{
-    AssignOp, AssignStmt, BinaryOp, Bytecode, BytecodeFunction, Expr, IfStmt, Module, Op, Pattern,
-    ReceiverType, Stmt, UnaryOp, infer_module_alias,
+    AssignOp, AssignStmt, BinaryOp, Bytecode, BytecodeFunction, DeclKind, Expr, IfStmt, Module, Op, Pattern,
+    ReceiverType, Stmt, SwitchTable, UnaryOp, infer_module_alias,
 };
-
-pub fn compile_module(module: &Module) -> Option<Bytecode> {
+use xu_syntax::{SourceFile, Span, Token, TokenKind};
+
+use crate::analyzer::Finder;
+use crate::const_eval::{ConstMap, Consts};
+use crate::resolve::{DeclRole, resolve};
+
+/// 编译模块；`source` 与 `tokens` 用于生成字节码到源码位置的映射，
+/// `imports` 是导入模块的公开常量（按别名）
+pub fn compile_module(
+    module: &Module,
+    source: &SourceFile,
+    tokens: &[Token],
+    imports: HashMap<String, ConstMap>,
+) -> Option<Bytecode> {
     let mut c = Compiler::new();
-    for s in &module.stmts {
-        c.compile_stmt(s)?;
+    c.bc.spans.file = source.name.clone();
+    c.finder = Some(Finder::new(source, tokens));
+    c.struct_fields = Rc::new(collect_struct_fields(&module.stmts));
+    let declares_consts = module.stmts.iter().any(|s| matches!(s, Stmt::Assign(a) if a.decl == Some(DeclKind::Const)));
+    if declares_consts || !imports.is_empty() {
+        c.rebound = Rc::new(rebound_names(source, tokens));
+        c.consts.modules = imports.into_iter().filter(|(alias, _)| !c.rebound.contains(alias)).collect();
     }
+    c.compile_stmts(&module.stmts)?;
     c.bc.ops.push(Op::Halt);
     Some(c.bc)
 }
 
+/// 文件中除 `const` 声明与 `use` 别名外绑定过的名字。这些名字可能在某处
+/// 指向别的值，对它们的读取不能替换为常量
+fn rebound_names(source: &SourceFile, tokens: &[Token]) -> HashSet<String> {
+    let resolved = resolve(source.text.as_str(), tokens);
+    resolved
+        .decls
+        .iter()
+        .filter(|&(&i, &role)| match role {
+            DeclRole::Module => false,
+            DeclRole::Constant => i == 0 || resolved.kind(i - 1) != TokenKind::KwConst,
+            _ => true,
+        })
+        .map(|(&i, _)| resolved.name(i).to_string())
+        .collect()
+}
+
+/// Field names and declared type names of the structs a module defines, in
+/// layout order, for resolving field offsets at compile time.
+fn collect_struct_fields(stmts: &[Stmt]) -> StructFields {
+    let mut out = StructFields::new();
+    for s in stmts {
+        if let Stmt::StructDef(def) = s {
+            let fields = def.fields.iter().map(|f| (f.name.clone(), f.ty.name.clone())).collect();
+            out.insert(def.name.clone(), fields);
+        }
+    }
+    out
+}
+
 /// Check if an expression is a to_text(expr) call and return the inner expression
 fn extract_to_text_arg(expr: &Expr) -> Option<&Expr> {
     if let Expr::Call(c) = expr {
@@ -30,6 +78,129 @@ fn extract_to_text_arg(expr: &Expr) -> Option<&Expr> {
     None
 }
 
+/// The list a `for` loop fills, when it is worth pre-sizing: every iteration
+/// appends to `xs` with `push`/`add`, and an earlier statement of the same block set
+/// `xs` to a list literal, so `xs` is defined by the time the loop starts.
+fn presize_target<'a>(before: &[Stmt], stmt: &'a xu_ir::ForEachStmt) -> Option<&'a str> {
+    let target = appended_list(&stmt.body)?;
+    if target == stmt.var {
+        return None;
+    }
+    let declared = before.iter().any(|s| match s {
+        Stmt::Assign(a) => {
+            a.op == AssignOp::Set
+                && matches!(&a.target, Expr::Ident(n, _) if n == target)
+                && matches!(a.value, Expr::List(_))
+        }
+        _ => false,
+    });
+    declared.then_some(target)
+}
+
+/// Identifier appended to by `push`/`add` on every iteration: a top-level
+/// statement of `body` (or of a plain block in it) with no earlier statement
+/// that can skip it. Appends under an `if` or after a `continue` run only
+/// for some items, so those loops are not pre-sized.
+fn appended_list(body: &[Stmt]) -> Option<&str> {
+    for s in body {
+        match s {
+            Stmt::Expr(Expr::MethodCall(m)) if matches!(m.method.as_str(), "push" | "add") && m.args.len() == 1 => {
+                return match m.receiver.as_ref() {
+                    Expr::Ident(name, _) => Some(name.as_str()),
+                    _ => None,
+                };
+            }
+            Stmt::Block(b) => {
+                if let Some(target) = appended_list(b) {
+                    return Some(target);
+                }
+            }
+            _ => {}
+        }
+        if may_skip_rest(s, true) {
+            return None;
+        }
+    }
+    None
+}
+
+/// Whether `s` can leave the rest of the loop body unexecuted; `loop_exits`
+/// is false inside a nested loop, where `break`/`continue` stay local.
+fn may_skip_rest(s: &Stmt, loop_exits: bool) -> bool {
+    let any = |b: &[Stmt], exits: bool| b.iter().any(|s| may_skip_rest(s, exits));
+    match s {
+        Stmt::Return(_) => true,
+        Stmt::Break | Stmt::Continue => loop_exits,
+        Stmt::Block(b) => any(b, loop_exits),
+        Stmt::If(i) => {
+            i.branches.iter().any(|(_, b)| any(b, loop_exits))
+                || i.else_branch.as_deref().is_some_and(|b| any(b, loop_exits))
+        }
+        Stmt::Match(m) => {
+            m.arms.iter().any(|(_, b)| any(b, loop_exits))
+                || m.else_branch.as_deref().is_some_and(|b| any(b, loop_exits))
+        }
+        Stmt::While(w) => any(&w.body, false),
+        Stmt::ForEach(f) => any(&f.body, false),
+        _ => false,
+    }
+}
+
+/// 表达式最左侧的名字（或 `self`）所在的记号，作为语句的起始位置
+fn find_leading(finder: &mut Finder<'_>, e: &Expr) -> Option<Span> {
+    match e {
+        Expr::Ident(name, _) if name == "self" => finder.find_kw(TokenKind::KwSelf),
+        Expr::Ident(name, _) => finder.find_name(name),
+        Expr::Member(m) => find_leading(finder, &m.object),
+        Expr::Index(i) => find_leading(finder, &i.object),
+        Expr::Call(c) => find_leading(finder, &c.callee),
+        Expr::MethodCall(m) => find_leading(finder, &m.receiver),
+        Expr::Binary { left, .. } => find_leading(finder, left),
+        Expr::Compare(c) => find_leading(finder, &c.first),
+        Expr::Try(e) | Expr::OptChain(e) | Expr::OptLink(e) => find_leading(finder, e),
+        _ => None,
+    }
+}
+
+/// 复合赋值 `x op= y` 在读出 `x` 后执行的二元运算指令
+fn assign_opcode(op: AssignOp) -> Op {
+    match op {
+        AssignOp::Add => Op::Add,
+        AssignOp::Sub => Op::Sub,
+        AssignOp::Mul => Op::Mul,
+        AssignOp::Div => Op::Div,
+        AssignOp::Mod => Op::Mod,
+        AssignOp::Pow => Op::Pow,
+        AssignOp::BitAnd => Op::BitAnd,
+        AssignOp::BitOr => Op::BitOr,
+        AssignOp::BitXor => Op::BitXor,
+        AssignOp::Shl => Op::Shl,
+        AssignOp::Shr => Op::Shr,
+        AssignOp::Set => unreachable!("plain assignment has no operator"),
+    }
+}
+
+/// 字面量分支至少有这么多个时，match 才编译为跳转表
+const SWITCH_MIN_ARMS: usize = 3;
+
+/// match 的分支全是整数字面量或全是字符串字面量（最后可以有一个 `_`）时，
+/// 可以用 `Op::Switch` 一次跳到目标分支
+fn is_switch<'a>(pats: impl Iterator<Item = &'a Pattern>) -> bool {
+    let (mut ints, mut strs, mut rest) = (0, 0, false);
+    for p in pats {
+        if rest {
+            return false;
+        }
+        match p {
+            Pattern::Int(_) => ints += 1,
+            Pattern::Str(_) => strs += 1,
+            Pattern::Wildcard => rest = true,
+            _ => return false,
+        }
+    }
+    (ints == 0 || strs == 0) && ints + strs >= SWITCH_MIN_ARMS
+}
+
 /// Collect all binding names from a pattern in order
 fn collect_pattern_bindings(pat: &Pattern) -> Vec<String> {
     let mut bindings = Vec::new();
@@ -62,31 +233,61 @@ struct LoopCtx {
 
 struct Scope {
     locals: Vec<String>,
+    /// 静态类型已知的变量 -> 结构体名
+    types: HashMap<String, String>,
 }
 
-struct Compiler {
+type StructFields = HashMap<String, Vec<(String, String)>>;
+
+struct Compiler<'a> {
     bc: Bytecode,
     loops: Vec<LoopCtx>,
     scopes: Vec<Scope>,
-    next_ic_slot: usize,
+    next_ic_slot: u32,
+    /// 常量池去重索引：字符串、整数与浮点数（按位比较）常量到池下标
+    str_consts: HashMap<String, u32>,
+    int_consts: HashMap<i64, u32>,
+    float_consts: HashMap<u64, u32>,
     known_types: HashSet<String>,
+    /// 本模块定义的结构体布局，函数体编译时共享
+    struct_fields: Rc<StructFields>,
     in_function: bool,  // Track if we're inside a function body
+    /// 按编译顺序在记号流中向前查找语句位置，与分析器的做法相同；
+    /// 闭包体内为 None，沿用所在语句的位置
+    finder: Option<Finder<'a>>,
+    /// 当前语句的位置
+    span: Option<Span>,
+    /// 正在编译的 `?.` 链中各 OptLink 的位置，链结束时统一修补到链尾
+    opt_links: Vec<usize>,
+    /// 读取时直接加载值的常量：本模块已声明的和导入模块的
+    consts: Consts,
+    /// 见 [`rebound_names`]
+    rebound: Rc<HashSet<String>>,
 }
 
-impl Compiler {
+impl<'a> Compiler<'a> {
     fn new() -> Self {
         Self {
             bc: Bytecode::default(),
             loops: Vec::new(),
-            scopes: vec![Scope { locals: Vec::new() }],
+            scopes: vec![Scope { locals: Vec::new(), types: HashMap::new() }],
             next_ic_slot: 0,
+            str_consts: HashMap::new(),
+            int_consts: HashMap::new(),
+            float_consts: HashMap::new(),
             known_types: HashSet::new(),
+            struct_fields: Rc::default(),
             in_function: false,
+            finder: None,
+            span: None,
+            opt_links: Vec::new(),
+            consts: Consts::default(),
+            rebound: Rc::default(),
         }
     }
 
     fn push_scope(&mut self) {
-        self.scopes.push(Scope { locals: Vec::new() });
+        self.scopes.push(Scope { locals: Vec::new(), types: HashMap::new() });
     }
 
     // ==================== 辅助方法 ====================
@@ -95,7 +296,15 @@ impl Compiler {
     #[inline]
     fn patch_jump(&mut self, pos: usize, target: usize) -> Option<()> {
         match &mut self.bc.ops[pos] {
-            Op::Jump(to) | Op::JumpIfFalse(to) | Op::JumpIfTrue(to) => { *to = target; Some(()) }
+            Op::Jump(to)
+            | Op::JumpIfFalse(to)
+            | Op::JumpIfTrue(to)
+            | Op::OptLink(to)
+            | Op::Coalesce(to)
+            | Op::CmpLink(_, to) => {
+                *to = target as u32;
+                Some(())
+            }
             _ => None,
         }
     }
@@ -112,10 +321,47 @@ impl Compiler {
     /// 编译语句列表
     #[inline]
     fn compile_stmts(&mut self, stmts: &[Stmt]) -> Option<()> {
-        for s in stmts { self.compile_stmt(s)?; }
+        for (i, s) in stmts.iter().enumerate() {
+            self.locate_stmt(s);
+            match s {
+                Stmt::ForEach(f) => self.compile_foreach(f, presize_target(&stmts[..i], f))?,
+                _ => self.compile_stmt(s)?,
+            }
+        }
         Some(())
     }
 
+    /// 在源码映射中记录语句的位置。找不到起始记号的语句（如解析器生成的
+    /// 契约检查）沿用上一条语句的位置
+    fn locate_stmt(&mut self, stmt: &Stmt) {
+        let Some(finder) = self.finder.as_mut() else { return };
+        let span = match stmt {
+            Stmt::StructDef(def) => finder.find_name(&def.name),
+            Stmt::EnumDef(def) => finder.find_name(&def.name),
+            Stmt::Use(_) => finder.find_kw(TokenKind::KwUse),
+            Stmt::If(_) => finder.find_kw(TokenKind::KwIf),
+            Stmt::While(_) => finder.find_kw(TokenKind::KwWhile),
+            Stmt::ForEach(_) => finder.find_kw(TokenKind::KwFor),
+            // `when` 绑定会被解析为 match
+            Stmt::Match(_) => finder.find_any_kw(&[TokenKind::KwMatch, TokenKind::KwWhen]),
+            Stmt::Return(_) => finder.find_kw(TokenKind::KwReturn),
+            Stmt::Break => finder.find_kw(TokenKind::KwBreak),
+            Stmt::Continue => finder.find_kw(TokenKind::KwContinue),
+            Stmt::Assign(a) => find_leading(finder, &a.target),
+            Stmt::Expr(e) => find_leading(finder, e),
+            // 函数在 compile_func_def 中定位
+            Stmt::FuncDef(_) | Stmt::DoesBlock(_) | Stmt::Block(_) | Stmt::Error(_) => None,
+        };
+        if let Some(span) = span {
+            self.mark_span(span);
+        }
+    }
+
+    fn mark_span(&mut self, span: Span) {
+        self.span = Some(span);
+        self.bc.spans.mark(self.bc.ops.len() as u32, span);
+    }
+
     /// 编译表达式列表
     #[inline]
     fn compile_exprs(&mut self, exprs: &[Expr]) -> Option<()> {
@@ -131,7 +377,7 @@ impl Compiler {
         pos
     }
 
-    fn resolve_local(&self, name: &str) -> Option<usize> {
+    fn resolve_local(&self, name: &str) -> Option<u32> {
         // Only use local variables inside functions
         if !self.in_function || self.scopes.len() <= 1 {
             return None; // Top-level variables are globals
@@ -140,14 +386,14 @@ impl Compiler {
         for i in 1..self.scopes.len() {
             let scope = &self.scopes[i];
             if let Some(pos) = scope.locals.iter().position(|l| l == name) {
-                return Some(offset + pos);
+                return Some((offset + pos) as u32);
             }
             offset += scope.locals.len();
         }
         None
     }
 
-    fn define_local(&mut self, name: &str) -> usize {
+    fn define_local(&mut self, name: &str) -> u32 {
         if self.scopes.len() <= 1 {
             // This should not be called for top-level if we want globals
             // But if it is, we return a dummy index
@@ -163,24 +409,103 @@ impl Compiler {
         for i in 1..self.scopes.len() - 1 {
             offset += self.scopes[i].locals.len();
         }
-        offset + pos
+        (offset + pos) as u32
     }
 
-    fn alloc_ic_slot(&mut self) -> usize {
+    /// 变量所在作用域：函数内为定义它的局部作用域，顶层为全局作用域；
+    /// 函数内引用的全局变量可能被任意修改，不跟踪其类型
+    fn type_scope(&self, name: &str) -> Option<usize> {
+        if !self.in_function {
+            return Some(0);
+        }
+        (1..self.scopes.len()).find(|&i| self.scopes[i].locals.iter().any(|l| l == name))
+    }
+
+    /// 记录赋值后变量的静态结构体类型；未知时清除旧记录
+    fn set_static_type(&mut self, name: &str, ty: Option<String>) {
+        let Some(i) = self.type_scope(name) else { return };
+        let types = &mut self.scopes[i].types;
+        match ty.filter(|t| self.struct_fields.contains_key(t)) {
+            Some(t) => {
+                types.insert(name.to_string(), t);
+            }
+            None => {
+                types.remove(name);
+            }
+        }
+    }
+
+    /// 表达式的静态结构体类型（能确定时）
+    fn static_type(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Ident(name, _) => self.scopes[self.type_scope(name)?].types.get(name).cloned(),
+            Expr::StructInit(s) if s.module.is_none() => {
+                self.struct_fields.contains_key(&s.ty).then(|| s.ty.clone())
+            }
+            Expr::Member(m) => {
+                let ty = self.static_type(&m.object)?;
+                let (_, field_ty) = self.struct_fields[&ty].iter().find(|(f, _)| *f == m.field)?;
+                self.struct_fields.contains_key(field_ty).then(|| field_ty.clone())
+            }
+            Expr::Group(e) => self.static_type(e),
+            _ => None,
+        }
+    }
+
+    /// 静态类型已知时，字段在布局中的偏移
+    fn field_offset(&self, object: &Expr, field: &str) -> Option<u32> {
+        let ty = self.static_type(object)?;
+        self.struct_fields[&ty].iter().position(|(f, _)| f == field).map(|i| i as u32)
+    }
+
+    fn alloc_ic_slot(&mut self) -> u32 {
         let s = self.next_ic_slot;
         self.next_ic_slot += 1;
         s
     }
 
+    /// 添加常量并返回池下标，相同常量只保存一份。
+    /// 字符串与数字走哈希索引，其余常量很少且很少重复，线性查找
     fn add_constant(&mut self, c: xu_ir::Constant) -> u32 {
-        if let Some(pos) = self.bc.constants.iter().position(|x| x == &c) {
-            return pos as u32;
-        }
         let pos = self.bc.constants.len() as u32;
+        let index = match &c {
+            xu_ir::Constant::Str(s) => self.str_consts.get(s.as_str()),
+            xu_ir::Constant::Int(i) => self.int_consts.get(i),
+            xu_ir::Constant::Float(f) => self.float_consts.get(&f.to_bits()),
+            _ => {
+                if let Some(found) = self.bc.constants.iter().position(|x| x == &c) {
+                    return found as u32;
+                }
+                None
+            }
+        };
+        if let Some(&found) = index {
+            return found;
+        }
+        match &c {
+            xu_ir::Constant::Str(s) => {
+                self.str_consts.insert(s.clone(), pos);
+            }
+            xu_ir::Constant::Int(i) => {
+                self.int_consts.insert(*i, pos);
+            }
+            xu_ir::Constant::Float(f) => {
+                self.float_consts.insert(f.to_bits(), pos);
+            }
+            _ => {}
+        }
         self.bc.constants.push(c);
         pos
     }
 
+    /// 字符串常量的加载指令；空串不占常量池
+    fn str_const_op(&mut self, s: String) -> Op {
+        if s.is_empty() {
+            return Op::ConstEmptyStr;
+        }
+        Op::Const(self.add_constant(xu_ir::Constant::Str(s)))
+    }
+
     fn is_const_expr(&self, expr: &Expr) -> bool {
         match expr {
             Expr::Int(_) | Expr::Float(_) | Expr::Bool(_) | Expr::Str(_) => true,
@@ -197,8 +522,8 @@ impl Compiler {
     fn compile_interp_part(&mut self, expr: &Expr) -> Option<()> {
         match expr {
             Expr::Str(s) => {
-                let s_idx = self.add_constant(xu_ir::Constant::Str(s.clone()));
-                self.bc.ops.push(Op::Const(s_idx));
+                let op = self.str_const_op(s.clone());
+                self.bc.ops.push(op);
             }
             _ => {
                 self.compile_expr(expr)?;
@@ -213,7 +538,7 @@ impl Compiler {
             Stmt::Error(_) => None,
             Stmt::StructDef(def) => {
                 self.known_types.insert(def.name.clone());
-                let idx = self.add_constant(xu_ir::Constant::Struct((**def).clone()));
+                let idx = self.add_constant(xu_ir::Constant::Struct(Rc::clone(def)));
                 self.bc.ops.push(Op::DefineStruct(idx));
 
                 // Compile static field initializations
@@ -234,7 +559,7 @@ impl Compiler {
             }
             Stmt::EnumDef(def) => {
                 self.known_types.insert(def.name.clone());
-                let idx = self.add_constant(xu_ir::Constant::Enum((**def).clone()));
+                let idx = self.add_constant(xu_ir::Constant::Enum(Rc::clone(def)));
                 self.bc.ops.push(Op::DefineEnum(idx));
                 Some(())
             }
@@ -247,7 +572,7 @@ impl Compiler {
             }
             Stmt::If(s) => self.compile_if(s),
             Stmt::While(s) => self.compile_while(s),
-            Stmt::ForEach(s) => self.compile_foreach(s),
+            Stmt::ForEach(s) => self.compile_foreach(s, None),
             Stmt::Use(u) => {
                 let path_idx = self.add_constant(xu_ir::Constant::Str(u.path.clone()));
                 let alias = u
@@ -268,9 +593,7 @@ impl Compiler {
                     self.bc.ops.push(Op::EnvPush);
                 }
                 self.push_scope();
-                for stmt in stmts.iter() {
-                    self.compile_stmt(stmt)?;
-                }
+                self.compile_stmts(stmts)?;
                 self.scopes.pop();
                 if !self.in_function {
                     self.bc.ops.push(Op::EnvPop);
@@ -280,6 +603,7 @@ impl Compiler {
             Stmt::Return(v) => self.compile_return(v.as_ref()),
             Stmt::Break => self.compile_break(),
             Stmt::Continue => self.compile_continue(),
+            Stmt::Assign(s) if s.decl == Some(DeclKind::Const) => self.compile_const(s),
             Stmt::Assign(s) => self.compile_assign(s),
             Stmt::Expr(e) => {
                 if self.is_const_expr(e) {
@@ -297,7 +621,7 @@ impl Compiler {
             return None;
         };
         let pos = self.bc.ops.len();
-        self.bc.ops.push(Op::Break(usize::MAX));
+        self.bc.ops.push(Op::Break(u32::MAX));
         ctx.break_ops.push(pos);
         Some(())
     }
@@ -307,7 +631,7 @@ impl Compiler {
             return None;
         };
         let pos = self.bc.ops.len();
-        self.bc.ops.push(Op::Continue(usize::MAX));
+        self.bc.ops.push(Op::Continue(u32::MAX));
         ctx.continue_ops.push(pos);
         Some(())
     }
@@ -317,43 +641,65 @@ impl Compiler {
             let Op::Break(to) = &mut self.bc.ops[pos] else {
                 return None;
             };
-            *to = break_to;
+            *to = break_to as u32;
         }
         for pos in ctx.continue_ops {
             let Op::Continue(to) = &mut self.bc.ops[pos] else {
                 return None;
             };
-            *to = cont_to;
+            *to = cont_to as u32;
         }
         Some(())
     }
 
-    fn compile_func_def(&mut self, def: &xu_ir::FuncDef) -> Option<()> {
-        let f_idx = self.compile_func_body(def)?;
+    fn compile_func_def(&mut self, def: &Rc<xu_ir::FuncDef>) -> Option<()> {
+        if let Some(span) = self.finder.as_mut().and_then(|f| f.find_kw(TokenKind::KwFunc)) {
+            self.mark_span(span);
+        }
+        let f_idx = self.compile_func_body(def, true)?;
         self.bc.ops.push(Op::MakeFunction(f_idx));
         let n_idx = self.add_constant(xu_ir::Constant::Str(def.name.clone()));
         self.bc.ops.push(Op::StoreName(n_idx));
         Some(())
     }
 
-    /// Compile function body and return constant index
-    fn compile_func_body(&mut self, def: &xu_ir::FuncDef) -> Option<u32> {
+    /// Compile function body and return constant index. `locate` hands the
+    /// token finder to the body; closures keep the enclosing statement's span.
+    fn compile_func_body(&mut self, def: &Rc<xu_ir::FuncDef>, locate: bool) -> Option<u32> {
         let mut inner = Compiler::new();
         inner.in_function = true;  // Mark that we're inside a function
+        inner.bc.spans.file = self.bc.spans.file.clone();
+        if let Some(span) = self.span {
+            inner.mark_span(span);
+        }
+        if locate {
+            inner.finder = self.finder.take();
+        }
+        // IC 槽在整个编译单元内编号，避免不同函数的访问点共用同一个槽
+        inner.next_ic_slot = self.next_ic_slot;
+        inner.struct_fields = self.struct_fields.clone();
+        inner.consts = self.consts.clone();
+        inner.rebound = self.rebound.clone();
         inner.push_scope();
         for p in &def.params {
             inner.define_local(&p.name);
+            inner.set_static_type(&p.name, p.ty.as_ref().map(|t| t.name.clone()));
         }
-        for s in &def.body {
-            inner.compile_stmt(s)?;
+        let compiled = inner.compile_stmts(&def.body);
+        if locate {
+            self.finder = inner.finder.take();
         }
+        compiled?;
         inner.bc.ops.push(Op::ConstNull);
         inner.bc.ops.push(Op::Return);
+        self.next_ic_slot = inner.next_ic_slot;
         let locals_count = inner.scopes.iter().map(|s| s.locals.len()).sum();
+        let needs_env_frame = inner.bc.ops.iter().any(|op| matches!(op, Op::MakeFunction(_)));
         let fun = BytecodeFunction {
-            def: def.clone(),
-            bytecode: std::rc::Rc::new(inner.bc),
+            def: Rc::clone(def),
+            bytecode: Rc::new(inner.bc),
             locals_count,
+            needs_env_frame,
         };
         Some(self.add_constant(xu_ir::Constant::Func(fun)))
     }
@@ -362,9 +708,9 @@ impl Compiler {
         let mut end_jumps: Vec<usize> = Vec::new();
         for (cond, body) in &stmt.branches {
             self.compile_expr(cond)?;
-            let jfalse_pos = self.emit_jump(Op::JumpIfFalse(usize::MAX));
+            let jfalse_pos = self.emit_jump(Op::JumpIfFalse(u32::MAX));
             self.compile_stmts(body)?;
-            end_jumps.push(self.emit_jump(Op::Jump(usize::MAX)));
+            end_jumps.push(self.emit_jump(Op::Jump(u32::MAX)));
             self.patch_jump(jfalse_pos, self.bc.ops.len())?;
         }
         if let Some(body) = &stmt.else_branch {
@@ -374,6 +720,11 @@ impl Compiler {
     }
 
     fn compile_match_stmt(&mut self, stmt: &xu_ir::MatchStmt) -> Option<()> {
+        if is_switch(stmt.arms.iter().map(|(p, _)| p)) {
+            return self.compile_switch(&stmt.expr, &stmt.arms, stmt.else_branch.as_ref(), |c, body| {
+                c.compile_stmts(body)
+            });
+        }
         self.compile_expr(&stmt.expr)?;
         let mut arm_end_jumps: Vec<usize> = Vec::new();
 
@@ -397,13 +748,13 @@ impl Compiler {
                     self.bc.ops.push(Op::Pop);
                     self.compile_stmts(body)?;
                 }
-                arm_end_jumps.push(self.emit_jump(Op::Jump(usize::MAX)));
+                arm_end_jumps.push(self.emit_jump(Op::Jump(u32::MAX)));
                 self.patch_jump(jpos, self.bc.ops.len())?;
             } else {
                 self.bc.ops.push(Op::Pop);
                 self.compile_stmts(body)?;
                 if !is_last {
-                    arm_end_jumps.push(self.emit_jump(Op::Jump(usize::MAX)));
+                    arm_end_jumps.push(self.emit_jump(Op::Jump(u32::MAX)));
                 }
             }
         }
@@ -415,6 +766,49 @@ impl Compiler {
         self.patch_jumps(&arm_end_jumps, self.bc.ops.len())
     }
 
+    /// 编译字面量 match：`Op::Switch` 查表直接跳到分支，不逐个测试模式。
+    /// 与逐个测试时一样，值在分支开头弹出；没有分支匹配且没有 `_` 或 else 时
+    /// 值留在栈上
+    fn compile_switch<B>(
+        &mut self,
+        subject: &Expr,
+        arms: &[(Pattern, B)],
+        else_body: Option<&B>,
+        mut body: impl FnMut(&mut Self, &B) -> Option<()>,
+    ) -> Option<()> {
+        self.compile_expr(subject)?;
+        let switch_pos = self.bc.ops.len();
+        self.bc.ops.push(Op::Switch(u32::MAX));
+        let (mut ints, mut strs) = (Vec::new(), Vec::new());
+        let mut end_jumps = Vec::new();
+        let mut default = None;
+        for (pat, b) in arms {
+            let target = self.bc.ops.len() as u32;
+            match pat {
+                Pattern::Int(v) => ints.push((*v, target)),
+                Pattern::Str(s) => strs.push((s.clone(), target)),
+                _ => default = Some(target),
+            }
+            self.bc.ops.push(Op::Pop);
+            body(self, b)?;
+            if default.is_none() {
+                end_jumps.push(self.emit_jump(Op::Jump(u32::MAX)));
+            }
+        }
+        if let (None, Some(b)) = (default, else_body) {
+            default = Some(self.bc.ops.len() as u32);
+            self.bc.ops.push(Op::Pop);
+            body(self, b)?;
+        }
+        let end = self.bc.ops.len();
+        self.patch_jumps(&end_jumps, end)?;
+        let default = default.unwrap_or(end as u32);
+        let table = if strs.is_empty() { SwitchTable::ints(&ints, default) } else { SwitchTable::strs(&strs, default) };
+        let table_idx = self.add_constant(xu_ir::Constant::Switch(table));
+        self.bc.ops[switch_pos] = Op::Switch(table_idx);
+        Some(())
+    }
+
     /// 编译模式检查，返回需要修补的跳转位置（通配符返回 None）
     fn compile_match_pattern_check(&mut self, pat: &Pattern) -> Option<Option<usize>> {
         if matches!(pat, Pattern::Wildcard) {
@@ -422,24 +816,31 @@ impl Compiler {
         }
         let pat_idx = self.add_constant(xu_ir::Constant::Pattern(pat.clone()));
         self.bc.ops.push(Op::MatchPattern(pat_idx));
-        Some(Some(self.emit_jump(Op::JumpIfFalse(usize::MAX))))
+        Some(Some(self.emit_jump(Op::JumpIfFalse(u32::MAX))))
     }
 
     fn compile_while(&mut self, stmt: &xu_ir::WhileStmt) -> Option<()> {
         let loop_start = self.bc.ops.len();
         self.compile_expr(&stmt.cond)?;
-        let jfalse_pos = self.emit_jump(Op::JumpIfFalse(usize::MAX));
+        let jfalse_pos = self.emit_jump(Op::JumpIfFalse(u32::MAX));
         self.loops.push(LoopCtx { break_ops: Vec::new(), continue_ops: Vec::new() });
         self.compile_stmts(&stmt.body)?;
         let ctx = self.loops.pop()?;
-        self.bc.ops.push(Op::Jump(loop_start));
+        self.bc.ops.push(Op::Jump(loop_start as u32));
         let end = self.bc.ops.len();
         self.patch_jump(jfalse_pos, end)?;
         self.patch_loop(ctx, end, loop_start)
     }
 
-    fn compile_foreach(&mut self, stmt: &xu_ir::ForEachStmt) -> Option<()> {
+    /// `presize` names a list the body appends to; it is given room for one
+    /// element per iteration before the loop starts.
+    fn compile_foreach(&mut self, stmt: &xu_ir::ForEachStmt, presize: Option<&str>) -> Option<()> {
         self.compile_expr(&stmt.iter)?;
+        if let Some(list) = presize {
+            self.bc.ops.push(Op::Dup);
+            self.compile_expr_ident(list)?;
+            self.bc.ops.push(Op::ListReserveFor);
+        }
         // Only use local variables inside functions, not in top-level blocks
         let var_idx = if self.in_function && self.scopes.len() > 1 {
             Some(self.define_local(&stmt.var))
@@ -447,22 +848,22 @@ impl Compiler {
             None
         };
         let n_idx = self.add_constant(xu_ir::Constant::Str(stmt.var.clone()));
-        let init_pos = self.emit_jump(Op::ForEachInit(n_idx, var_idx, usize::MAX));
+        let init_pos = self.emit_jump(Op::ForEachInit(n_idx, var_idx, u32::MAX));
         self.loops.push(LoopCtx { break_ops: Vec::new(), continue_ops: Vec::new() });
         let body_start = self.bc.ops.len();
         self.compile_stmts(&stmt.body)?;
-        let next_pos = self.emit_jump(Op::ForEachNext(n_idx, var_idx, body_start, usize::MAX));
+        let next_pos = self.emit_jump(Op::ForEachNext(n_idx, var_idx, body_start as u32, u32::MAX));
         let break_cleanup = self.bc.ops.len();
         self.bc.ops.push(Op::IterPop);
-        let j_to_end = self.emit_jump(Op::Jump(usize::MAX));
+        let j_to_end = self.emit_jump(Op::Jump(u32::MAX));
         let end = self.bc.ops.len();
         // 修补 ForEachInit 和 ForEachNext 的结束地址
         match &mut self.bc.ops[init_pos] {
-            Op::ForEachInit(_, _, end_ip) => *end_ip = end,
+            Op::ForEachInit(_, _, end_ip) => *end_ip = end as u32,
             _ => return None,
         }
         match &mut self.bc.ops[next_pos] {
-            Op::ForEachNext(_, _, _, end_ip) => *end_ip = end,
+            Op::ForEachNext(_, _, _, end_ip) => *end_ip = end as u32,
             _ => return None,
         }
         self.patch_jump(j_to_end, end)?;
@@ -480,6 +881,20 @@ impl Compiler {
         Some(())
     }
 
+    /// `const NAME = expr`：在编译期求值，此后对 NAME 的读取直接加载该值；
+    /// 求不出值时交给 AST 执行器
+    fn compile_const(&mut self, stmt: &AssignStmt) -> Option<()> {
+        let Expr::Ident(name, _) = &stmt.target else {
+            return None;
+        };
+        let value = self.consts.eval(&stmt.value)?;
+        self.compile_assign(&AssignStmt { value: value.to_expr(), ..stmt.clone() })?;
+        if !self.rebound.contains(name) {
+            self.consts.own.insert(name.clone(), value);
+        }
+        Some(())
+    }
+
     fn compile_assign(&mut self, stmt: &AssignStmt) -> Option<()> {
         match &stmt.target {
             Expr::Ident(name, _) => match stmt.op {
@@ -501,9 +916,14 @@ impl Compiler {
                         let n_idx = self.add_constant(xu_ir::Constant::Str(name.clone()));
                         self.bc.ops.push(Op::StoreName(n_idx));
                     }
+                    let ty = match &stmt.ty {
+                        Some(t) => Some(t.name.clone()),
+                        None => self.static_type(&stmt.value),
+                    };
+                    self.set_static_type(name, ty);
                     Some(())
                 }
-                AssignOp::Add | AssignOp::Sub | AssignOp::Mul | AssignOp::Div => {
+                _ => {
                     match stmt.op {
                         AssignOp::Add => {
                             if let Some(idx) = self.resolve_local(name) {
@@ -527,7 +947,8 @@ impl Compiler {
                                 self.bc.ops.push(Op::AddAssignName(n_idx));
                             }
                         }
-                        AssignOp::Sub | AssignOp::Mul | AssignOp::Div => {
+                        AssignOp::Set => unreachable!(),
+                        _ => {
                             if let Some(idx) = self.resolve_local(name) {
                                 self.bc.ops.push(Op::LoadLocal(idx));
                             } else {
@@ -535,12 +956,7 @@ impl Compiler {
                                 self.bc.ops.push(Op::LoadName(n_idx));
                             }
                             self.compile_expr(&stmt.value)?;
-                            self.bc.ops.push(match stmt.op {
-                                AssignOp::Sub => Op::Sub,
-                                AssignOp::Mul => Op::Mul,
-                                AssignOp::Div => Op::Div,
-                                _ => unreachable!(),
-                            });
+                            self.bc.ops.push(assign_opcode(stmt.op));
                             if let Some(idx) = self.resolve_local(name) {
                                 self.bc.ops.push(Op::StoreLocal(idx));
                             } else {
@@ -548,7 +964,6 @@ impl Compiler {
                                 self.bc.ops.push(Op::StoreName(n_idx));
                             }
                         }
-                        AssignOp::Set => unreachable!(),
                     }
                     Some(())
                 }
@@ -573,13 +988,7 @@ impl Compiler {
                             // Compile the RHS
                             self.compile_expr(&stmt.value)?;
                             // Apply the operation
-                            self.bc.ops.push(match stmt.op {
-                                AssignOp::Add => Op::Add,
-                                AssignOp::Sub => Op::Sub,
-                                AssignOp::Mul => Op::Mul,
-                                AssignOp::Div => Op::Div,
-                                AssignOp::Set => unreachable!(),
-                            });
+                            self.bc.ops.push(assign_opcode(stmt.op));
                             // Store back
                             self.bc.ops.push(Op::SetStaticField(t_idx, f_idx));
                         }
@@ -589,7 +998,10 @@ impl Compiler {
                 self.compile_expr(&stmt.value)?;
                 self.compile_expr(&m.object)?;
                 let n_idx = self.add_constant(xu_ir::Constant::Str(m.field.clone()));
-                self.bc.ops.push(Op::AssignMember(n_idx, stmt.op));
+                match self.field_offset(&m.object, &m.field) {
+                    Some(offset) => self.bc.ops.push(Op::AssignMemberAt(n_idx, offset, stmt.op)),
+                    None => self.bc.ops.push(Op::AssignMember(n_idx, stmt.op)),
+                }
                 Some(())
             }
             Expr::Index(ix) => {
@@ -606,20 +1018,24 @@ impl Compiler {
     fn try_fold_unary(&self, op: UnaryOp, expr: &Expr) -> Option<Op> {
         match (op, expr) {
             (UnaryOp::Not, Expr::Bool(v)) => Some(Op::ConstBool(!*v)),
-            (UnaryOp::Neg, Expr::Int(v)) => Some(Op::ConstInt(-*v)),
+            (UnaryOp::Neg, Expr::Int(v)) => v.checked_neg().map(Op::ConstInt),
             (UnaryOp::Neg, Expr::Float(v)) => Some(Op::ConstFloat(-*v)),
+            (UnaryOp::BitNot, Expr::Int(v)) => Some(Op::ConstInt(!*v)),
             _ => None,
         }
     }
 
     fn try_fold_binary(&mut self, op: BinaryOp, left: &Expr, right: &Expr) -> Option<Op> {
         match (op, left, right) {
-            // Arithmetic
-            (BinaryOp::Add, Expr::Int(a), Expr::Int(b)) => Some(Op::ConstInt(a + b)),
-            (BinaryOp::Sub, Expr::Int(a), Expr::Int(b)) => Some(Op::ConstInt(a - b)),
-            (BinaryOp::Mul, Expr::Int(a), Expr::Int(b)) => Some(Op::ConstInt(a * b)),
-            (BinaryOp::Div, Expr::Int(a), Expr::Int(b)) if *b != 0 => Some(Op::ConstInt(a / b)),
-            (BinaryOp::Mod, Expr::Int(a), Expr::Int(b)) if *b != 0 => Some(Op::ConstInt(a % b)),
+            // Arithmetic saturates as at runtime; what would fail there is left to it
+            (BinaryOp::Add, Expr::Int(a), Expr::Int(b)) => Some(Op::ConstInt(a.saturating_add(*b))),
+            (BinaryOp::Sub, Expr::Int(a), Expr::Int(b)) => Some(Op::ConstInt(a.saturating_sub(*b))),
+            (BinaryOp::Mul, Expr::Int(a), Expr::Int(b)) => Some(Op::ConstInt(a.saturating_mul(*b))),
+            (BinaryOp::Div, Expr::Int(a), Expr::Int(b)) => a.checked_div(*b).map(Op::ConstInt),
+            (BinaryOp::Mod, Expr::Int(a), Expr::Int(b)) => a.checked_rem(*b).map(Op::ConstInt),
+            (BinaryOp::BitAnd, Expr::Int(a), Expr::Int(b)) => Some(Op::ConstInt(a & b)),
+            (BinaryOp::BitOr, Expr::Int(a), Expr::Int(b)) => Some(Op::ConstInt(a | b)),
+            (BinaryOp::BitXor, Expr::Int(a), Expr::Int(b)) => Some(Op::ConstInt(a ^ b)),
 
             (BinaryOp::Add, Expr::Float(a), Expr::Float(b)) => Some(Op::ConstFloat(a + b)),
             (BinaryOp::Sub, Expr::Float(a), Expr::Float(b)) => Some(Op::ConstFloat(a - b)),
@@ -628,8 +1044,7 @@ impl Compiler {
 
             // String concatenation (simple case)
             (BinaryOp::Add, Expr::Str(a), Expr::Str(b)) => {
-                let idx = self.add_constant(xu_ir::Constant::Str(format!("{}{}", a, b)));
-                Some(Op::Const(idx))
+                Some(self.str_const_op(format!("{}{}", a, b)))
             }
 
             // Comparison
@@ -664,6 +1079,7 @@ impl Compiler {
             // 一元和二元运算
             Expr::Unary { op, expr } => self.compile_expr_unary(*op, expr),
             Expr::Binary { op, left, right } => self.compile_expr_binary(*op, left, right),
+            Expr::Compare(c) => self.compile_expr_compare(c),
             // 字符串插值
             Expr::InterpolatedString(parts) => self.compile_expr_interpolated_string(parts),
             // 集合类型
@@ -686,9 +1102,29 @@ impl Compiler {
             Expr::Member(m) => self.compile_expr_member(m),
             Expr::Index(ix) => self.compile_expr_index(ix),
             Expr::Group(e) => self.compile_expr(e),
+            Expr::Try(e) => {
+                self.compile_expr(e)?;
+                self.bc.ops.push(Op::Try);
+                Some(())
+            }
+            Expr::OptChain(e) => self.compile_expr_opt_chain(e),
+            Expr::OptLink(e) => {
+                self.compile_expr(e)?;
+                let pos = self.emit_jump(Op::OptLink(0));
+                self.opt_links.push(pos);
+                Some(())
+            }
         }
     }
 
+    /// 编译 `?.` / `?[` 链：任一环节为 none 时跳到链尾，整条链的值为 none
+    fn compile_expr_opt_chain(&mut self, e: &Expr) -> Option<()> {
+        let start = self.opt_links.len();
+        self.compile_expr(e)?;
+        let links = self.opt_links.split_off(start);
+        self.patch_jumps(&links, self.bc.ops.len())
+    }
+
     // ==================== 字面量表达式编译 ====================
 
     /// 编译标识符表达式
@@ -696,6 +1132,9 @@ impl Compiler {
     fn compile_expr_ident(&mut self, name: &str) -> Option<()> {
         if let Some(idx) = self.resolve_local(name) {
             self.bc.ops.push(Op::LoadLocal(idx));
+        } else if let Some(v) = self.consts.own.get(name) {
+            let v = v.to_expr();
+            self.compile_expr(&v)?;
         } else {
             let n_idx = self.add_constant(xu_ir::Constant::Str(name.to_string()));
             self.bc.ops.push(Op::LoadName(n_idx));
@@ -727,8 +1166,8 @@ impl Compiler {
     /// 编译字符串字面量
     #[inline]
     fn compile_expr_str(&mut self, s: &str) -> Option<()> {
-        let s_idx = self.add_constant(xu_ir::Constant::Str(s.to_string()));
-        self.bc.ops.push(Op::Const(s_idx));
+        let op = self.str_const_op(s.to_string());
+        self.bc.ops.push(op);
         Some(())
     }
 
@@ -752,6 +1191,11 @@ impl Compiler {
                 self.bc.ops.push(Op::Sub);
                 Some(())
             }
+            UnaryOp::BitNot => {
+                self.compile_expr(expr)?;
+                self.bc.ops.push(Op::BitNot);
+                Some(())
+            }
         }
     }
 
@@ -779,6 +1223,19 @@ impl Compiler {
         if op == BinaryOp::Or {
             return self.compile_short_circuit_or(left, right);
         }
+        if op == BinaryOp::Coalesce {
+            return self.compile_coalesce(left, right);
+        }
+        // 区间字面量直接比较边界，不构造区间
+        if op == BinaryOp::In {
+            if let Expr::Range(r) = right {
+                self.compile_expr(left)?;
+                self.compile_expr(&r.start)?;
+                self.compile_expr(&r.end)?;
+                self.bc.ops.push(Op::InRange(r.inclusive));
+                return Some(());
+            }
+        }
         self.compile_expr(left)?;
         self.compile_expr(right)?;
         self.bc.ops.push(match op {
@@ -787,18 +1244,46 @@ impl Compiler {
             BinaryOp::Mul => Op::Mul,
             BinaryOp::Div => Op::Div,
             BinaryOp::Mod => Op::Mod,
+            BinaryOp::Pow => Op::Pow,
+            BinaryOp::BitAnd => Op::BitAnd,
+            BinaryOp::BitOr => Op::BitOr,
+            BinaryOp::BitXor => Op::BitXor,
+            BinaryOp::Shl => Op::Shl,
+            BinaryOp::Shr => Op::Shr,
             BinaryOp::Eq => Op::Eq,
             BinaryOp::Ne => Op::Ne,
             BinaryOp::And => Op::And,  // 由于上面的短路求值，不会到达这里
             BinaryOp::Or => Op::Or,    // 由于上面的短路求值，不会到达这里
+            BinaryOp::Coalesce => return None, // 同上，由 compile_coalesce 处理
             BinaryOp::Gt => Op::Gt,
             BinaryOp::Lt => Op::Lt,
             BinaryOp::Ge => Op::Ge,
             BinaryOp::Le => Op::Le,
+            BinaryOp::In => Op::In,
         });
         Some(())
     }
 
+    /// 编译链式比较 `a < b <= c`：每个操作数只求值一次，某一环不成立时跳到链尾，值为 false
+    fn compile_expr_compare(&mut self, c: &xu_ir::CompareExpr) -> Option<()> {
+        self.compile_expr(&c.first)?;
+        let mut jumps = Vec::with_capacity(c.links.len());
+        for (i, (op, e)) in c.links.iter().enumerate() {
+            self.compile_expr(e)?;
+            if i + 1 < c.links.len() {
+                jumps.push(self.emit_jump(Op::CmpLink(*op, 0)));
+            } else {
+                self.bc.ops.push(match op {
+                    BinaryOp::Gt => Op::Gt,
+                    BinaryOp::Lt => Op::Lt,
+                    BinaryOp::Ge => Op::Ge,
+                    _ => Op::Le,
+                });
+            }
+        }
+        self.patch_jumps(&jumps, self.bc.ops.len())
+    }
+
     /// 编译短路与运算 (&&)
     fn compile_short_circuit_and(&mut self, left: &Expr, right: &Expr) -> Option<()> {
         self.compile_expr(left)?;
@@ -809,6 +1294,14 @@ impl Compiler {
         self.patch_jump(jump_idx, self.bc.ops.len())
     }
 
+    /// 编译 `??`：左值不是 none 时跳过右值
+    fn compile_coalesce(&mut self, left: &Expr, right: &Expr) -> Option<()> {
+        self.compile_expr(left)?;
+        let jump_idx = self.emit_jump(Op::Coalesce(0));
+        self.compile_expr(right)?;
+        self.patch_jump(jump_idx, self.bc.ops.len())
+    }
+
     /// 编译短路或运算 (||)
     fn compile_short_circuit_or(&mut self, left: &Expr, right: &Expr) -> Option<()> {
         self.compile_expr(left)?;
@@ -856,7 +1349,7 @@ impl Compiler {
         }
         let use_builder = parts.len() >= 8 || const_cap >= 128 || generic >= 3 || non_const >= 6;
         if use_builder {
-            self.bc.ops.push(Op::BuilderNewCap(const_cap));
+            self.bc.ops.push(Op::BuilderNewCap(const_cap as u32));
             for p in parts {
                 self.compile_expr(p)?;
                 self.bc.ops.push(Op::BuilderAppend);
@@ -872,20 +1365,19 @@ impl Compiler {
                     s.push_str(&text);
                 }
             }
-            let s_idx = self.add_constant(xu_ir::Constant::Str(s));
-            self.bc.ops.push(Op::Const(s_idx));
+            let op = self.str_const_op(s);
+            self.bc.ops.push(op);
             return Some(());
         }
         if let Some(Expr::Str(first)) = parts.first() {
-            let f_idx = self.add_constant(xu_ir::Constant::Str(first.clone()));
-            self.bc.ops.push(Op::Const(f_idx));
+            let op = self.str_const_op(first.clone());
+            self.bc.ops.push(op);
             for p in &parts[1..] {
                 self.compile_interp_part(p)?;
             }
             return Some(());
         }
-        let empty_idx = self.add_constant(xu_ir::Constant::Str(String::new()));
-        self.bc.ops.push(Op::Const(empty_idx));
+        self.bc.ops.push(Op::ConstEmptyStr);
         for p in parts {
             self.compile_interp_part(p)?;
         }
@@ -897,14 +1389,14 @@ impl Compiler {
     /// 编译列表表达式
     fn compile_expr_list(&mut self, items: &[Expr]) -> Option<()> {
         self.compile_exprs(items)?;
-        self.bc.ops.push(Op::ListNew(items.len()));
+        self.bc.ops.push(Op::ListNew(items.len() as u32));
         Some(())
     }
 
     /// 编译元组表达式
     fn compile_expr_tuple(&mut self, items: &[Expr]) -> Option<()> {
         self.compile_exprs(items)?;
-        self.bc.ops.push(Op::TupleNew(items.len()));
+        self.bc.ops.push(Op::TupleNew(items.len() as u32));
         Some(())
     }
 
@@ -915,7 +1407,7 @@ impl Compiler {
             self.bc.ops.push(Op::Const(k_idx));
             self.compile_expr(v)?;
         }
-        self.bc.ops.push(Op::DictNew(entries.len()));
+        self.bc.ops.push(Op::DictNew(entries.len() as u32));
         Some(())
     }
 
@@ -932,9 +1424,9 @@ impl Compiler {
     /// 编译 if 表达式
     fn compile_expr_if(&mut self, e: &xu_ir::IfExpr) -> Option<()> {
         self.compile_expr(&e.cond)?;
-        let j_if = self.emit_jump(Op::JumpIfFalse(usize::MAX));
+        let j_if = self.emit_jump(Op::JumpIfFalse(u32::MAX));
         self.compile_expr(&e.then_expr)?;
-        let j_end = self.emit_jump(Op::Jump(usize::MAX));
+        let j_end = self.emit_jump(Op::Jump(u32::MAX));
         self.patch_jump(j_if, self.bc.ops.len())?;
         self.compile_expr(&e.else_expr)?;
         self.patch_jump(j_end, self.bc.ops.len())
@@ -942,6 +1434,9 @@ impl Compiler {
 
     /// 编译 match 表达式
     fn compile_expr_match(&mut self, m: &xu_ir::MatchExpr) -> Option<()> {
+        if is_switch(m.arms.iter().map(|(p, _)| p)) {
+            return self.compile_switch(&m.expr, &m.arms, m.else_expr.as_deref(), |c, body| c.compile_expr(body));
+        }
         self.compile_expr(&m.expr)?;
         let mut arm_end_jumps: Vec<usize> = Vec::new();
 
@@ -965,13 +1460,13 @@ impl Compiler {
                     self.bc.ops.push(Op::Pop);
                     self.compile_expr(body)?;
                 }
-                arm_end_jumps.push(self.emit_jump(Op::Jump(usize::MAX)));
+                arm_end_jumps.push(self.emit_jump(Op::Jump(u32::MAX)));
                 self.patch_jump(jpos, self.bc.ops.len())?;
             } else {
                 self.bc.ops.push(Op::Pop);
                 self.compile_expr(body)?;
                 if !is_last {
-                    arm_end_jumps.push(self.emit_jump(Op::Jump(usize::MAX)));
+                    arm_end_jumps.push(self.emit_jump(Op::Jump(u32::MAX)));
                 }
             }
         }
@@ -986,8 +1481,8 @@ impl Compiler {
     // ==================== 函数相关编译 ====================
 
     /// 编译函数字面量表达式
-    fn compile_expr_func_lit(&mut self, def: &xu_ir::FuncDef) -> Option<()> {
-        let f_idx = self.compile_func_body(def)?;
+    fn compile_expr_func_lit(&mut self, def: &Rc<xu_ir::FuncDef>) -> Option<()> {
+        let f_idx = self.compile_func_body(def, false)?;
         self.bc.ops.push(Op::MakeFunction(f_idx));
         Some(())
     }
@@ -1005,7 +1500,7 @@ impl Compiler {
         }
         self.compile_expr(&c.callee)?;
         self.compile_exprs(&c.args)?;
-        self.bc.ops.push(Op::Call(c.args.len()));
+        self.bc.ops.push(Op::Call(c.args.len() as u32));
         Some(())
     }
 
@@ -1018,7 +1513,7 @@ impl Compiler {
         if mname == "add" && recv_ty == Some(ReceiverType::List) {
             self.compile_expr(&m.receiver)?;
             self.compile_exprs(&m.args)?;
-            self.bc.ops.push(Op::ListAppend(m.args.len()));
+            self.bc.ops.push(Op::ListAppend(m.args.len() as u32));
             return Some(());
         }
         // 只有当接收者确认为字典时才生成 DictMerge
@@ -1051,17 +1546,17 @@ impl Compiler {
                 // 它是局部变量 - 使用常规 CallMethod
                 self.compile_expr(&m.receiver)?;
                 self.compile_exprs(&m.args)?;
-                self.bc.ops.push(Op::CallMethod(m_idx, method_hash, m.args.len(), Some(slot)));
+                self.bc.ops.push(Op::CallMethod(m_idx, method_hash, m.args.len() as u32, Some(slot)));
             } else {
                 // 不是局部变量 - 可能是全局变量或类型名，生成 CallStaticOrMethod
                 self.compile_exprs(&m.args)?;
                 let type_idx = self.add_constant(xu_ir::Constant::Str(name.clone()));
-                self.bc.ops.push(Op::CallStaticOrMethod(type_idx, m_idx, method_hash, m.args.len(), Some(slot)));
+                self.bc.ops.push(Op::CallStaticOrMethod(type_idx, m_idx, m.args.len() as u32, Some(slot)));
             }
         } else {
             self.compile_expr(&m.receiver)?;
             self.compile_exprs(&m.args)?;
-            self.bc.ops.push(Op::CallMethod(m_idx, method_hash, m.args.len(), Some(slot)));
+            self.bc.ops.push(Op::CallMethod(m_idx, method_hash, m.args.len() as u32, Some(slot)));
         }
         Some(())
     }
@@ -1132,7 +1627,7 @@ impl Compiler {
         self.bc.ops.push(if args.is_empty() {
             Op::EnumCtor(t_idx, v_idx)
         } else {
-            Op::EnumCtorN(t_idx, v_idx, args.len())
+            Op::EnumCtorN(t_idx, v_idx, args.len() as u32)
         });
         Some(())
     }
@@ -1141,6 +1636,13 @@ impl Compiler {
     fn compile_expr_member(&mut self, m: &xu_ir::MemberExpr) -> Option<()> {
         // 只有当标识符是已知类型名时才使用静态字段访问
         if let Expr::Ident(name, _) = m.object.as_ref() {
+            // 导入模块的常量 `alias.NAME`
+            if let Some(v) = self.consts.modules.get(name).and_then(|c| c.get(&m.field)) {
+                if self.resolve_local(name).is_none() {
+                    let v = v.to_expr();
+                    return self.compile_expr(&v);
+                }
+            }
             // 检查是否是已知的类型名（结构体或枚举）
             if self.known_types.contains(name) {
                 let t_idx = self.add_constant(xu_ir::Constant::Str(name.clone()));
@@ -1149,11 +1651,15 @@ impl Compiler {
                 return Some(());
             }
         }
-        // 所有其他情况：编译对象表达式并使用 GetMember
+        // 所有其他情况：编译对象表达式并使用 GetMember；
+        // 静态类型已知时直接按偏移读取，布局不符再回退到 IC 路径
         self.compile_expr(&m.object)?;
         let slot = self.alloc_ic_slot();
         let n_idx = self.add_constant(xu_ir::Constant::Str(m.field.clone()));
-        self.bc.ops.push(Op::GetMember(n_idx, Some(slot)));
+        match self.field_offset(&m.object, &m.field) {
+            Some(offset) => self.bc.ops.push(Op::GetMemberAt(n_idx, offset, Some(slot))),
+            None => self.bc.ops.push(Op::GetMember(n_idx, Some(slot))),
+        }
         Some(())
     }
 
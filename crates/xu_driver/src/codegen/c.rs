@@ -0,0 +1,1665 @@
+//! C output of `xu codegen`.
+//!
+//! The program becomes one C99 file. Every module's top level is a load
+//! function run on the module's first `use`, and the entry's runs from
+//! `main`. Functions and methods become C functions taking their arguments
+//! as an array; nested functions are hoisted, and the variables they capture
+//! live in cells they share with the enclosing function. Expressions are split
+//! into temporaries so they evaluate left to right. Values use the
+//! representation described in `c_runtime.h`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::rc::Rc;
+
+use xu_parser::{AssignOp, BinaryOp, Expr, FuncDef, Pattern, Stmt, StructInitItem, UnaryOp};
+
+use super::{BUILTINS, CodegenOptions, ModuleInfo, SourceModule, TypeInfo, method_name, method_owner};
+
+pub(super) const RUNTIME: &str = include_str!("c_runtime.h");
+
+pub(super) fn emit(modules: &[SourceModule], opts: &CodegenOptions) -> Result<String, String> {
+    if let Some(m) = modules.iter().find(|m| m.imports.values().any(|&to| to == 0)) {
+        return Err(format!("{}: importing the entry module is not supported in C output", m.key));
+    }
+    let infos: Vec<ModuleInfo> = modules.iter().map(|m| ModuleInfo::collect(&m.module)).collect();
+    let mut e = Emitter::new(modules, &infos);
+    for idx in (1..modules.len()).chain([0]) {
+        e.module = idx;
+        e.load()?;
+    }
+    let mut out = String::new();
+    if opts.inject_runtime {
+        out.push_str(RUNTIME);
+    } else {
+        out.push_str("#include \"xu_runtime.h\"\n");
+    }
+    for part in [&e.protos, &e.data, &e.funcs, &e.type_descriptors()] {
+        if !part.is_empty() {
+            out.push('\n');
+            out.push_str(part.trim_end_matches('\n'));
+            out.push('\n');
+        }
+    }
+    out.push_str("\nint main(int argc, char **argv) {\n    xu_init(argc, argv);\n    m0_load();\n    return 0;\n}\n");
+    Ok(out)
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Value,
+    /// A top-level function, called directly.
+    Func(String),
+    /// A struct or enum; the key of its `TypeDesc`.
+    Type(String),
+    /// The alias of an imported module.
+    Module(usize),
+}
+
+#[derive(Clone, Debug)]
+struct Binding {
+    c: String,
+    kind: Kind,
+    /// Holds a `xu_value *` as closures capture the variable.
+    cell: bool,
+    /// A module-level variable, reachable from every function.
+    global: bool,
+}
+
+impl Binding {
+    fn access(&self) -> String {
+        if self.cell { format!("(*{})", self.c) } else { self.c.clone() }
+    }
+}
+
+struct Scope {
+    names: HashMap<String, Binding>,
+    /// Index of the frame the scope's variables belong to.
+    frame: usize,
+}
+
+/// A C function being emitted.
+struct Frame {
+    /// Only closures can capture variables of enclosing functions.
+    closure: bool,
+    /// Captured names and their cells as the enclosing function reaches
+    /// them, in the order of `xu_self->caps`.
+    caps: Vec<(String, String)>,
+    /// Names used by functions nested in this one; its variables of these
+    /// names are cells.
+    cells: HashSet<String>,
+    /// A module's load function, which returns nothing.
+    void: bool,
+}
+
+/// A struct or enum, emitted as the `xu_type` `ty{key}`.
+struct TypeDesc {
+    name: String,
+    is_enum: bool,
+    info: TypeInfo,
+    /// The function computing each field's default, if it has one.
+    defaults: Vec<Option<String>>,
+    /// Methods emitted so far and their C functions.
+    methods: Vec<(String, String)>,
+}
+
+/// A member resolved without looking at the value.
+enum Static {
+    /// A static field or a field of `self`, as an lvalue.
+    Field(String),
+    /// A static method's C function.
+    Method(String),
+}
+
+/// Where an assignment stores its value.
+enum Place {
+    Var(String),
+    Prop(String, String),
+    Index(String, String),
+}
+
+struct Emitter<'a> {
+    modules: &'a [SourceModule],
+    infos: &'a [ModuleInfo],
+    module: usize,
+    /// Each module's top-level names.
+    globals: Vec<HashMap<String, Binding>>,
+    types: HashMap<String, TypeDesc>,
+    type_order: Vec<String>,
+    strings: HashMap<String, String>,
+    /// Functions with a prototype, and the static closures of those without
+    /// captures.
+    fns: HashSet<String>,
+    closures: HashSet<String>,
+    undeclared: HashSet<String>,
+    protos: String,
+    data: String,
+    funcs: String,
+    out: String,
+    indent: usize,
+    scopes: Vec<Scope>,
+    frames: Vec<Frame>,
+    next_tmp: usize,
+    loops: usize,
+    /// The blocks opened by the `?.` links of the chain being emitted.
+    open_links: usize,
+    /// The key of the struct whose method is being emitted.
+    self_ty: Option<String>,
+}
+
+impl<'a> Emitter<'a> {
+    fn new(modules: &'a [SourceModule], infos: &'a [ModuleInfo]) -> Self {
+        let mut e = Emitter {
+            modules,
+            infos,
+            module: 0,
+            globals: Vec::new(),
+            types: HashMap::new(),
+            type_order: Vec::new(),
+            strings: HashMap::new(),
+            fns: HashSet::new(),
+            closures: HashSet::new(),
+            undeclared: HashSet::new(),
+            protos: String::new(),
+            data: String::new(),
+            funcs: String::new(),
+            out: String::new(),
+            indent: 0,
+            scopes: Vec::new(),
+            frames: Vec::new(),
+            next_tmp: 0,
+            loops: 0,
+            open_links: 0,
+            self_ty: None,
+        };
+        for m in 0..modules.len() {
+            let globals = e.top_names(m);
+            e.globals.push(globals);
+        }
+        e
+    }
+
+    fn key(&self) -> &str {
+        &self.modules[self.module].key
+    }
+
+    fn line(&mut self, s: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(s);
+        self.out.push('\n');
+    }
+
+    fn tmp(&mut self) -> String {
+        self.next_tmp += 1;
+        format!("t{}", self.next_tmp)
+    }
+
+    /// Stores `v` in a new temporary.
+    fn temp(&mut self, v: &str) -> String {
+        let t = self.tmp();
+        self.line(&format!("xu_value {t} = {v};"));
+        t
+    }
+
+    /// Emits what `f` writes one level deeper into a separate string.
+    fn nested<R>(&mut self, f: impl FnOnce(&mut Self) -> Result<R, String>) -> Result<(String, R), String> {
+        let saved = std::mem::take(&mut self.out);
+        self.indent += 1;
+        let r = f(self);
+        self.indent -= 1;
+        let body = std::mem::replace(&mut self.out, saved);
+        r.map(|r| (body, r))
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Scope { names: HashMap::new(), frame: self.frames.len() - 1 });
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|s| s.names.get(name))
+    }
+
+    /// The C expression reading `name`, capturing it into the closures
+    /// between its function and the current one.
+    fn var(&mut self, name: &str) -> Result<Option<(String, Kind)>, String> {
+        let Some(si) = self.scopes.iter().rposition(|s| s.names.contains_key(name)) else {
+            return Ok(None);
+        };
+        let b = self.scopes[si].names[name].clone();
+        let (frame, here) = (self.scopes[si].frame, self.frames.len() - 1);
+        if b.global || frame == here || !matches!(b.kind, Kind::Value) {
+            return Ok(Some((b.access(), b.kind)));
+        }
+        if !b.cell {
+            return Err(format!("{}: cannot capture `{name}` in C output", self.key()));
+        }
+        let cell = self.capture(here, frame, name, &b.c)?;
+        Ok(Some((format!("(*{cell})"), b.kind)))
+    }
+
+    /// The cell of `name`, declared in frame `target`, as frame `frame`
+    /// reaches it.
+    fn capture(&mut self, frame: usize, target: usize, name: &str, cell: &str) -> Result<String, String> {
+        if frame == target {
+            return Ok(cell.to_string());
+        }
+        if let Some(i) = self.frames[frame].caps.iter().position(|(n, _)| n == name) {
+            return Ok(format!("xu_self->caps[{i}]"));
+        }
+        if !self.frames[frame].closure {
+            return Err(format!(
+                "{}: `{name}` is used by a method or field default of a type declared in a function, \
+                 which is not supported in C output",
+                self.key()
+            ));
+        }
+        let outer = self.capture(frame - 1, target, name, cell)?;
+        self.frames[frame].caps.push((name.to_string(), outer));
+        Ok(format!("xu_self->caps[{}]", self.frames[frame].caps.len() - 1))
+    }
+
+    /// Declares `name` in the innermost scope with the value `v` and returns
+    /// how to reach it; declaring a name again in the same scope assigns the
+    /// existing variable.
+    fn define(&mut self, name: &str, v: &str) -> String {
+        let scope = self.scopes.last().expect("scope");
+        if let Some(b) = scope.names.get(name).filter(|b| matches!(b.kind, Kind::Value)) {
+            let access = b.access();
+            self.line(&format!("{access} = {v};"));
+            return access;
+        }
+        let c = if self.lookup(name).is_some() {
+            self.next_tmp += 1;
+            format!("v_{}_{}", c_name(name), self.next_tmp)
+        } else {
+            format!("v_{}", c_name(name))
+        };
+        let cell = self.frames.last().expect("frame").cells.contains(name);
+        if cell {
+            self.line(&format!("xu_value *{c} = xu_cell({v});"));
+        } else {
+            self.line(&format!("xu_value {c} = {v};"));
+        }
+        let b = Binding { c, kind: Kind::Value, cell, global: false };
+        let access = b.access();
+        self.scopes.last_mut().expect("scope").names.insert(name.to_string(), b);
+        access
+    }
+
+    /// The top-level names of module `m`, declaring its globals and types.
+    fn top_names(&mut self, m: usize) -> HashMap<String, Binding> {
+        let source = &self.modules[m];
+        let info = &self.infos[m];
+        let mut names: HashMap<String, Binding> = HashMap::new();
+        let mut order = Vec::new();
+        for s in source.module.stmts.iter() {
+            let (name, kind) = match s {
+                Stmt::FuncDef(def) if method_owner(&def.name).is_some_and(|ty| info.types.contains_key(ty)) => continue,
+                Stmt::FuncDef(def) => (def.name.clone(), Kind::Func(format!("f{m}_{}", c_name(&def.name)))),
+                Stmt::StructDef(def) => {
+                    let key = self.register_type(format!("{m}_{}", c_name(&def.name)), &def.name, false, || {
+                        info.types[&def.name].clone()
+                    });
+                    (def.name.clone(), Kind::Type(key))
+                }
+                Stmt::EnumDef(def) => {
+                    let key = self.register_type(format!("{m}_{}", c_name(&def.name)), &def.name, true, || {
+                        info.types[&def.name].clone()
+                    });
+                    (def.name.clone(), Kind::Type(key))
+                }
+                Stmt::Use(u) => (
+                    u.alias.clone().unwrap_or_else(|| xu_ir::infer_module_alias(&u.path)),
+                    Kind::Module(source.imports[&u.path]),
+                ),
+                Stmt::Assign(a) if a.decl.is_some() => match &a.target {
+                    Expr::Ident(name, _) => (name.clone(), Kind::Value),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            // A name declared both as a function and a variable is a variable.
+            let kind = match (names.get(&name).map(|b| &b.kind), kind) {
+                (Some(Kind::Value), Kind::Func(_)) | (Some(Kind::Func(_)), Kind::Value) => Kind::Value,
+                (_, kind) => kind,
+            };
+            let c = match &kind {
+                Kind::Func(f) => f.clone(),
+                _ => format!("g{m}_{}", c_name(&name)),
+            };
+            if !names.contains_key(&name) {
+                order.push(name.clone());
+            }
+            names.insert(name, Binding { c, kind, cell: false, global: true });
+        }
+        for name in order {
+            let b = &names[&name];
+            if matches!(b.kind, Kind::Value) {
+                let _ = writeln!(self.data, "XU_DATA xu_value {};", b.c);
+            }
+        }
+        names
+    }
+
+    fn register_type(&mut self, key: String, name: &str, is_enum: bool, info: impl FnOnce() -> TypeInfo) -> String {
+        if self.types.contains_key(&key) {
+            return key;
+        }
+        let info = info();
+        let _ = writeln!(self.data, "XU_DATA const xu_type ty{key};");
+        let mut statics: Vec<&String> = info.static_fields.iter().collect();
+        statics.sort();
+        for f in statics {
+            let _ = writeln!(self.data, "XU_DATA xu_value g{key}__{};", c_name(f));
+        }
+        self.type_order.push(key.clone());
+        let defaults = vec![None; info.fields.len()];
+        self.types
+            .insert(key.clone(), TypeDesc { name: name.to_string(), is_enum, info, defaults, methods: Vec::new() });
+        key
+    }
+
+    /// The key of the struct or enum declared by the statement being
+    /// emitted, declaring it when not at the top level.
+    fn type_key(&mut self, name: &str, is_enum: bool, info: impl FnOnce() -> TypeInfo) -> String {
+        if self.scopes.len() == 1 {
+            if let Some(Binding { kind: Kind::Type(key), .. }) = self.lookup(name) {
+                return key.clone();
+            }
+        }
+        self.next_tmp += 1;
+        let key = format!("{}_{}_{}", self.module, c_name(name), self.next_tmp);
+        self.register_type(key.clone(), name, is_enum, info);
+        let b = Binding { c: String::new(), kind: Kind::Type(key.clone()), cell: false, global: true };
+        self.scopes.last_mut().expect("scope").names.insert(name.to_string(), b);
+        key
+    }
+
+    fn type_descriptors(&self) -> String {
+        let mut out = String::new();
+        for key in &self.type_order {
+            let t = &self.types[key];
+            let mut fields = "NULL".to_string();
+            if !t.info.fields.is_empty() {
+                let names: Vec<String> = t.info.fields.iter().map(|f| c_str(f)).collect();
+                let _ = writeln!(out, "static const char *const tyf{key}[] = {{{}}};", names.join(", "));
+                fields = format!("tyf{key}");
+            }
+            let mut defaults = "NULL".to_string();
+            if t.defaults.iter().any(Option::is_some) {
+                let fns: Vec<&str> = t.defaults.iter().map(|d| d.as_deref().unwrap_or("NULL")).collect();
+                let _ = writeln!(out, "static xu_value (*const tyd{key}[])(void) = {{{}}};", fns.join(", "));
+                defaults = format!("tyd{key}");
+            }
+            let mut methods = "NULL".to_string();
+            if !t.methods.is_empty() {
+                let items: Vec<String> = t.methods.iter().map(|(m, f)| format!("{{{}, {f}}}", c_str(m))).collect();
+                let _ = writeln!(out, "static const xu_method tym{key}[] = {{{}}};", items.join(", "));
+                methods = format!("tym{key}");
+            }
+            let _ = writeln!(
+                out,
+                "XU_DATA const xu_type ty{key} = {{{}, {}, {}, {fields}, {defaults}, {}, {methods}}};",
+                c_str(&t.name),
+                t.is_enum as i32,
+                t.info.fields.len(),
+                t.methods.len()
+            );
+        }
+        out
+    }
+
+    fn load(&mut self) -> Result<(), String> {
+        let modules = self.modules;
+        let m = self.module;
+        let stmts = &modules[m].module.stmts;
+        self.frames.push(Frame { closure: false, caps: Vec::new(), cells: captured(stmts), void: true });
+        self.scopes.push(Scope { names: self.globals[m].clone(), frame: 0 });
+        self.indent = 1;
+        self.line(&format!("if (m{m}_loaded) return;"));
+        self.line(&format!("m{m}_loaded = 1;"));
+        let mut r = stmts.iter().try_for_each(|s| self.stmt(s));
+        if r.is_ok() && m == 0 && stmts.iter().any(|s| matches!(s, Stmt::FuncDef(def) if def.name == "main")) {
+            match self.lookup("main").map(|b| (b.c.clone(), b.kind.clone())) {
+                Some((f, Kind::Func(_))) => self.line(&format!("if (!xu_main_invoked) {f}(NULL, 0, NULL);")),
+                Some((g, _)) => self.line(&format!("if (!xu_main_invoked) xu_call({g}, 0, NULL);")),
+                None => r = Err(format!("{}: `main` is not declared", self.key())),
+            }
+        }
+        self.scopes.pop();
+        self.frames.pop();
+        r?;
+        let body = std::mem::take(&mut self.out);
+        let _ = writeln!(self.protos, "static void m{m}_load(void);");
+        let _ = writeln!(self.data, "static int m{m}_loaded;");
+        let _ = write!(self.funcs, "static void m{m}_load(void) {{\n{body}}}\n\n");
+        Ok(())
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) -> Result<(), String> {
+        self.push_scope();
+        for s in stmts.iter() {
+            if let Stmt::FuncDef(def) = s {
+                self.define(&def.name, "xu_unit()");
+            }
+        }
+        let r = stmts.iter().try_for_each(|s| self.stmt(s));
+        self.scopes.pop();
+        r
+    }
+
+    fn indented_block(&mut self, stmts: &[Stmt]) -> Result<(), String> {
+        self.indent += 1;
+        let r = self.block(stmts);
+        self.indent -= 1;
+        r
+    }
+
+    fn stmt(&mut self, s: &Stmt) -> Result<(), String> {
+        match s {
+            Stmt::FuncDef(def) if self.scopes.len() == 1 && self.owner_type(&def.name).is_some() => {
+                let (ty, key) = self.owner_type(&def.name).expect("method");
+                self.methods(&key, &ty, std::slice::from_ref(def))?;
+            }
+            Stmt::FuncDef(def) => {
+                let top = self.scopes.len() == 1;
+                let main = self.module == 0 && top && def.name == "main";
+                let f = if top {
+                    format!("f{}_{}", self.module, c_name(&def.name))
+                } else {
+                    self.next_tmp += 1;
+                    format!("fl{}_{}", self.next_tmp, c_name(&def.name))
+                };
+                if !self.fns.insert(f.clone()) {
+                    return Err(format!(
+                        "{}: function `{}` is defined twice, which is not supported in C output",
+                        self.key(),
+                        def.name
+                    ));
+                }
+                let caps = self.function(def, &f, !top, main)?;
+                if !matches!(self.lookup(&def.name).map(|b| &b.kind), Some(Kind::Func(_))) {
+                    let v = self.func_value(&f, &caps);
+                    let (access, _) = self.var(&def.name)?.expect("declared");
+                    self.line(&format!("{access} = {v};"));
+                }
+            }
+            Stmt::StructDef(def) => {
+                let key = self.type_key(&def.name, false, || TypeInfo::of_struct(def));
+                for (i, f) in def.fields.iter().enumerate() {
+                    if let Some(d) = &f.default {
+                        let name = format!("d{key}__{}", c_name(&f.name));
+                        self.thunk(&name, d)?;
+                        self.types.get_mut(&key).expect("type").defaults[i] = Some(name);
+                    }
+                }
+                for f in def.static_fields.iter() {
+                    let v = self.expr(&f.default)?;
+                    self.line(&format!("g{key}__{} = {v};", c_name(&f.name)));
+                }
+                self.methods(&key, &def.name, &def.methods)?;
+            }
+            Stmt::EnumDef(def) => {
+                self.type_key(&def.name, true, || TypeInfo { variants: def.variants.to_vec(), ..TypeInfo::default() });
+            }
+            Stmt::DoesBlock(block) => {
+                let key = match self.lookup(&block.target) {
+                    Some(Binding { kind: Kind::Type(key), .. }) => key.clone(),
+                    _ => return Err(format!("{}: cannot add methods to {} in C output", self.key(), block.target)),
+                };
+                self.methods(&key, &block.target, &block.funcs)?;
+            }
+            Stmt::Use(u) => {
+                let Some(&to) = self.modules[self.module].imports.get(&u.path) else {
+                    return Err(format!("{}: `use` inside a block is not supported in C output", self.key()));
+                };
+                if self.scopes.len() > 1 {
+                    let alias = u.alias.clone().unwrap_or_else(|| xu_ir::infer_module_alias(&u.path));
+                    let b = Binding { c: String::new(), kind: Kind::Module(to), cell: false, global: true };
+                    self.scopes.last_mut().expect("scope").names.insert(alias, b);
+                }
+                self.line(&format!("m{to}_load();"));
+            }
+            Stmt::If(s) => self.if_stmt(s)?,
+            Stmt::While(s) => {
+                let (prelude, c) = self.nested(|e| e.cond(&s.cond))?;
+                if prelude.is_empty() {
+                    self.line(&format!("while ({c}) {{"));
+                } else {
+                    self.line("for (;;) {");
+                    self.out.push_str(&prelude);
+                    self.indent += 1;
+                    self.line(&format!("if (!{c}) break;"));
+                    self.indent -= 1;
+                }
+                self.loop_body(&s.body, |_| Ok(()))?;
+                self.line("}");
+            }
+            Stmt::ForEach(s) => self.for_each(s)?,
+            Stmt::Match(s) => {
+                let v = self.expr(&s.expr)?;
+                self.line("{");
+                self.indent += 1;
+                let subject = self.temp(&v);
+                for (i, (pat, body)) in s.arms.iter().enumerate() {
+                    let cond = self.pattern_cond(pat, &subject);
+                    self.line(&format!("{}if ({cond}) {{", if i == 0 { "" } else { "} else " }));
+                    self.indent += 1;
+                    self.push_scope();
+                    self.bind_pattern(pat, &subject);
+                    let r = self.block(body);
+                    self.scopes.pop();
+                    r?;
+                    self.indent -= 1;
+                }
+                if let Some(body) = &s.else_branch {
+                    if s.arms.is_empty() {
+                        self.block(body)?;
+                    } else {
+                        self.line("} else {");
+                        self.indented_block(body)?;
+                    }
+                }
+                if !s.arms.is_empty() {
+                    self.line("}");
+                }
+                self.indent -= 1;
+                self.line("}");
+            }
+            Stmt::Block(body) => {
+                self.line("{");
+                self.indented_block(body)?;
+                self.line("}");
+            }
+            Stmt::Return(v) => {
+                let void = self.frames.last().expect("frame").void;
+                match v {
+                    Some(e) => {
+                        let v = self.expr(e)?;
+                        if void {
+                            self.line(&format!("(void){v};"));
+                            self.line("return;");
+                        } else {
+                            self.line(&format!("return {v};"));
+                        }
+                    }
+                    None => self.line(if void { "return;" } else { "return xu_unit();" }),
+                }
+            }
+            Stmt::Break | Stmt::Continue if self.loops == 0 => {
+                let what = if matches!(s, Stmt::Break) { "break" } else { "continue" };
+                return Err(format!("{}: `{what}` outside of a loop", self.key()));
+            }
+            Stmt::Break => self.line("break;"),
+            Stmt::Continue => self.line("continue;"),
+            Stmt::Assign(a) => self.assign(a)?,
+            Stmt::Expr(e) => {
+                let v = self.expr(e)?;
+                if matches!(e, Expr::Call(_) | Expr::MethodCall(_)) {
+                    self.line(&format!("{v};"));
+                } else {
+                    self.line(&format!("(void){v};"));
+                }
+            }
+            Stmt::Error(_) => {
+                return Err(format!("{}: cannot translate a statement with syntax errors", self.key()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The type a top-level `func (self: T) m()` belongs to and its key, if
+    /// `T` is a struct or enum of this module.
+    fn owner_type(&self, name: &str) -> Option<(String, String)> {
+        let ty = method_owner(name)?;
+        if !self.infos[self.module].types.contains_key(ty) {
+            return None;
+        }
+        match self.scopes.first()?.names.get(ty) {
+            Some(Binding { kind: Kind::Type(key), .. }) => Some((ty.to_string(), key.clone())),
+            _ => None,
+        }
+    }
+
+    fn methods(&mut self, key: &str, ty: &str, defs: &[Rc<FuncDef>]) -> Result<(), String> {
+        for def in defs {
+            let Some((is_static, name)) = method_name(ty, &def.name) else {
+                continue;
+            };
+            let f = format!("f{key}__{}_{}", if is_static { "s" } else { "m" }, c_name(name));
+            if !self.fns.insert(f.clone()) {
+                return Err(format!(
+                    "{}: method `{ty}.{name}` is defined twice, which is not supported in C output",
+                    self.key()
+                ));
+            }
+            let saved = std::mem::replace(&mut self.self_ty, (!is_static).then(|| key.to_string()));
+            let r = self.function(def, &f, false, false);
+            self.self_ty = saved;
+            r?;
+            let desc = self.types.get_mut(key).expect("type");
+            desc.info.add_methods(ty, std::slice::from_ref(def));
+            if !is_static {
+                desc.methods.push((name.to_string(), f));
+            }
+        }
+        Ok(())
+    }
+
+    /// Emits what `f` writes as the body of a new C function.
+    fn in_function(
+        &mut self,
+        frame: Frame,
+        f: impl FnOnce(&mut Self) -> Result<(), String>,
+    ) -> Result<(String, Frame), String> {
+        self.frames.push(frame);
+        let out = std::mem::take(&mut self.out);
+        let indent = std::mem::replace(&mut self.indent, 1);
+        let loops = std::mem::take(&mut self.loops);
+        self.push_scope();
+        let r = f(self);
+        self.scopes.pop();
+        let frame = self.frames.pop().expect("frame");
+        let body = std::mem::replace(&mut self.out, out);
+        self.indent = indent;
+        self.loops = loops;
+        r.map(|()| (body, frame))
+    }
+
+    /// Emits `def` as the C function `name` and returns the cells it
+    /// captures, as the enclosing function reaches them. The entry's `main`
+    /// records that it ran so it is not run again after the top level.
+    fn function(&mut self, def: &FuncDef, name: &str, closure: bool, main: bool) -> Result<Vec<String>, String> {
+        let frame = Frame { closure, caps: Vec::new(), cells: captured(&def.body), void: false };
+        let (body, frame) = self.in_function(frame, |e| {
+            for (i, p) in def.params.iter().enumerate() {
+                let v = e.define(&p.name, &format!("argc > {i} ? argv[{i}] : xu_unit()"));
+                if let Some(d) = &p.default {
+                    e.line(&format!("if (argc <= {i}) {{"));
+                    e.indent += 1;
+                    let d = e.expr(d)?;
+                    e.line(&format!("{v} = {d};"));
+                    e.indent -= 1;
+                    e.line("}");
+                }
+            }
+            if main {
+                e.line("xu_main_invoked = 1;");
+            }
+            e.block(&def.body)
+        })?;
+        let signature = format!("XU_FN xu_value {name}(xu_closure *xu_self, int argc, xu_value *argv)");
+        let _ = writeln!(self.protos, "{signature};");
+        let _ = writeln!(self.funcs, "{signature} {{");
+        if frame.caps.is_empty() {
+            self.funcs.push_str("    (void)xu_self;\n");
+        }
+        if def.params.is_empty() {
+            self.funcs.push_str("    (void)argc;\n    (void)argv;\n");
+        }
+        self.funcs.push_str(&body);
+        self.funcs.push_str("    return xu_unit();\n}\n\n");
+        Ok(frame.caps.into_iter().map(|(_, cell)| cell).collect())
+    }
+
+    /// Emits `static xu_value name(void)` computing `e`, for field defaults.
+    fn thunk(&mut self, name: &str, e: &Expr) -> Result<(), String> {
+        let frame = Frame { closure: false, caps: Vec::new(), cells: HashSet::new(), void: false };
+        let (body, _) = self.in_function(frame, |em| {
+            let v = em.expr(e)?;
+            em.line(&format!("return {v};"));
+            Ok(())
+        })?;
+        let _ = writeln!(self.protos, "XU_FN xu_value {name}(void);");
+        let _ = write!(self.funcs, "XU_FN xu_value {name}(void) {{\n{body}}}\n\n");
+        Ok(())
+    }
+
+    /// The value of the function `f` closing over `caps`.
+    fn func_value(&mut self, f: &str, caps: &[String]) -> String {
+        if caps.is_empty() {
+            return self.static_closure(f);
+        }
+        format!("xu_closure_new({f}, {}, (xu_value *[]){{{}}})", caps.len(), caps.join(", "))
+    }
+
+    fn static_closure(&mut self, f: &str) -> String {
+        if self.closures.insert(f.to_string()) {
+            let _ = writeln!(self.data, "static xu_closure c{f} = {{{f}, 0, NULL}};");
+        }
+        format!("xu_func(&c{f})")
+    }
+
+    /// A builtin used as a value, wrapped in a function.
+    fn builtin_value(&mut self, name: &str) -> String {
+        let f = format!("fb_{name}");
+        if self.fns.insert(f.clone()) {
+            let signature = format!("XU_FN xu_value {f}(xu_closure *xu_self, int argc, xu_value *argv)");
+            let _ = writeln!(self.protos, "{signature};");
+            let _ =
+                write!(self.funcs, "{signature} {{\n    (void)xu_self;\n    return xu_b_{name}(argc, argv);\n}}\n\n");
+        }
+        self.static_closure(&f)
+    }
+
+    fn string(&mut self, s: &str) -> String {
+        if let Some(name) = self.strings.get(s) {
+            return format!("xu_str_v(&{name})");
+        }
+        let name = format!("s{}", self.strings.len());
+        let _ = writeln!(self.data, "static const xu_string {name} = {{{}, {}}};", s.len(), c_str(s));
+        self.strings.insert(s.to_string(), name.clone());
+        format!("xu_str_v(&{name})")
+    }
+
+    fn undeclared_enum(&mut self, ty: &str) -> String {
+        let name = format!("tyu_{}", c_name(ty));
+        if self.undeclared.insert(name.clone()) {
+            let _ = writeln!(self.data, "XU_DATA const xu_type {name} = {{{}, 1, 0, NULL, NULL, 0, NULL}};", c_str(ty));
+        }
+        format!("&{name}")
+    }
+
+    fn if_stmt(&mut self, s: &xu_ir::IfStmt) -> Result<(), String> {
+        let mut open = 0;
+        for (i, (cond, body)) in s.branches.iter().enumerate() {
+            if i == 0 {
+                let c = self.cond(cond)?;
+                self.line(&format!("if ({c}) {{"));
+            } else {
+                let (prelude, c) = self.nested(|e| e.cond(cond))?;
+                if prelude.is_empty() {
+                    self.line(&format!("}} else if ({c}) {{"));
+                } else {
+                    // The condition needs statements of its own.
+                    self.line("} else {");
+                    self.indent += 1;
+                    open += 1;
+                    self.out.push_str(&prelude);
+                    self.line(&format!("if ({c}) {{"));
+                }
+            }
+            self.indented_block(body)?;
+        }
+        if let Some(body) = &s.else_branch {
+            self.line("} else {");
+            self.indented_block(body)?;
+        }
+        self.line("}");
+        for _ in 0..open {
+            self.indent -= 1;
+            self.line("}");
+        }
+        Ok(())
+    }
+
+    fn loop_body(&mut self, body: &[Stmt], bind: impl FnOnce(&mut Self) -> Result<(), String>) -> Result<(), String> {
+        self.loops += 1;
+        self.indent += 1;
+        self.push_scope();
+        let r = bind(self).and_then(|()| self.block(body));
+        self.scopes.pop();
+        self.indent -= 1;
+        self.loops -= 1;
+        r
+    }
+
+    fn for_each(&mut self, s: &xu_ir::ForEachStmt) -> Result<(), String> {
+        if let Expr::Range(r) = &s.iter {
+            if let (Expr::Int(a), Expr::Int(b)) = (r.start.as_ref(), r.end.as_ref()) {
+                self.next_tmp += 1;
+                let i = format!("i{}", self.next_tmp);
+                let (a, b) = (*a, *b);
+                let (test, step) = match (a <= b, r.inclusive) {
+                    (true, true) => ("<=", "++"),
+                    (true, false) => ("<", "++"),
+                    (false, true) => (">=", "--"),
+                    (false, false) => (">", "--"),
+                };
+                self.line(&format!("for (int64_t {i} = {}; {i} {test} {}; {i}{step}) {{", c_int(a), c_int(b)));
+                self.loop_body(&s.body, |e| {
+                    e.define(&s.var, &format!("xu_int({i})"));
+                    Ok(())
+                })?;
+                self.line("}");
+                return Ok(());
+            }
+        }
+        let v = match &s.iter {
+            Expr::Range(r) => {
+                let (a, b) = (self.operand(&r.start)?, self.operand(&r.end)?);
+                format!("xu_range_of({a}, {b}, {})", r.inclusive as i32)
+            }
+            iter => self.expr(iter)?,
+        };
+        let kv = s.var.starts_with("__tmp_foreach_");
+        self.next_tmp += 1;
+        let (it, x) = (format!("it{}", self.next_tmp), format!("x{}", self.next_tmp));
+        self.line("{");
+        self.indent += 1;
+        self.line(&format!("xu_iter {it};"));
+        self.line(&format!("xu_value {x};"));
+        self.line(&format!("xu_iter_init(&{it}, {v}, {});", kv as i32));
+        self.line(&format!("while (xu_iter_next(&{it}, &{x})) {{"));
+        self.loop_body(&s.body, |e| {
+            e.define(&s.var, &x);
+            Ok(())
+        })?;
+        self.line("}");
+        self.indent -= 1;
+        self.line("}");
+        Ok(())
+    }
+
+    fn bind_pattern(&mut self, pat: &Pattern, access: &str) {
+        match pat {
+            Pattern::Bind(name) => {
+                self.define(name, access);
+            }
+            Pattern::Tuple(items) | Pattern::EnumVariant { args: items, .. } => {
+                for (i, p) in items.iter().enumerate() {
+                    self.bind_pattern(p, &format!("xu_part({access}, {i})"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The condition under which `pat` matches the value `access`.
+    fn pattern_cond(&mut self, pat: &Pattern, access: &str) -> String {
+        let mut conds = Vec::new();
+        self.collect_conds(pat, access, &mut conds);
+        if conds.is_empty() { "1".to_string() } else { conds.join(" && ") }
+    }
+
+    fn collect_conds(&mut self, pat: &Pattern, access: &str, out: &mut Vec<String>) {
+        match pat {
+            Pattern::Wildcard | Pattern::Bind(_) => {}
+            Pattern::Int(i) => out.push(format!("xu_equal({access}, xu_int({}))", c_int(*i))),
+            Pattern::Float(f) => out.push(format!("xu_equal({access}, xu_float({}))", c_float(*f))),
+            Pattern::Str(s) => {
+                let s = self.string(s);
+                out.push(format!("xu_equal({access}, {s})"));
+            }
+            Pattern::Bool(b) => out.push(format!("xu_equal({access}, xu_bool({}))", *b as i32)),
+            Pattern::Tuple(items) => {
+                out.push(format!("xu_is_tuple({access}, {})", items.len()));
+                for (i, p) in items.iter().enumerate() {
+                    self.collect_conds(p, &format!("xu_part({access}, {i})"), out);
+                }
+            }
+            Pattern::EnumVariant { ty, variant, args } => {
+                out.push(format!("xu_is_variant({access}, {}, {}, {})", c_str(ty), c_str(variant), args.len()));
+                for (i, p) in args.iter().enumerate() {
+                    self.collect_conds(p, &format!("xu_part({access}, {i})"), out);
+                }
+            }
+        }
+    }
+
+    fn assign(&mut self, a: &xu_ir::AssignStmt) -> Result<(), String> {
+        if let (Expr::Ident(name, _), Some(_)) = (&a.target, a.decl) {
+            // A function can call itself through the name it is bound to.
+            let declared = self.scopes.last().expect("scope").names.contains_key(name);
+            if matches!(a.value, Expr::FuncLit(_)) && !declared {
+                let access = self.define(name, "xu_unit()");
+                let v = self.expr(&a.value)?;
+                self.line(&format!("{access} = {v};"));
+                return Ok(());
+            }
+            let v = self.expr(&a.value)?;
+            self.define(name, &v);
+            return Ok(());
+        }
+        let place = match &a.target {
+            Expr::Ident(name, _) => match self.var(name)? {
+                Some((access, Kind::Value)) => Place::Var(access),
+                Some(_) => return Err(format!("{}: cannot assign to `{name}` in C output", self.key())),
+                None => {
+                    // Assigning an unknown name declares it.
+                    let v = self.expr(&a.value)?;
+                    self.define(name, &v);
+                    return Ok(());
+                }
+            },
+            Expr::Member(m) => match self.static_member(&m.object, &m.field)? {
+                Some(Static::Field(field)) => Place::Var(field),
+                Some(Static::Method(_)) => {
+                    return Err(format!("{}: cannot assign to method `{}` in C output", self.key(), m.field));
+                }
+                None => Place::Prop(self.operand(&m.object)?, m.field.clone()),
+            },
+            Expr::Index(ix) => {
+                let obj = self.operand(&ix.object)?;
+                let idx = self.operand(&ix.index)?;
+                Place::Index(obj, idx)
+            }
+            _ => return Err(format!("{}: invalid assignment target", self.key())),
+        };
+        let op = match a.op {
+            AssignOp::Set => None,
+            AssignOp::Add => Some("xu_add"),
+            AssignOp::Sub => Some("xu_sub"),
+            AssignOp::Mul => Some("xu_mul"),
+            AssignOp::Div => Some("xu_div"),
+            AssignOp::Mod => Some("xu_mod"),
+            AssignOp::Pow => Some("xu_power"),
+            AssignOp::BitAnd => Some("xu_band"),
+            AssignOp::BitOr => Some("xu_bor"),
+            AssignOp::BitXor => Some("xu_bxor"),
+            AssignOp::Shl => Some("xu_shl"),
+            AssignOp::Shr => Some("xu_shr"),
+        };
+        let v = match op {
+            None => self.expr(&a.value)?,
+            Some(op) => {
+                let v = self.operand(&a.value)?;
+                let current = match &place {
+                    Place::Var(v) => v.clone(),
+                    Place::Prop(o, f) => format!("xu_prop({o}, {})", c_str(f)),
+                    Place::Index(o, i) => format!("xu_index({o}, {i})"),
+                };
+                format!("{op}({current}, {v})")
+            }
+        };
+        match place {
+            Place::Var(target) => self.line(&format!("{target} = {v};")),
+            Place::Prop(o, f) => self.line(&format!("xu_setprop({o}, {}, {v});", c_str(&f))),
+            Place::Index(o, i) => self.line(&format!("xu_setindex({o}, {i}, {v});")),
+        }
+        Ok(())
+    }
+
+    /// `Type.member`, `alias.Type.member` and `self.field` with the member
+    /// known to exist.
+    fn static_member(&mut self, object: &Expr, field: &str) -> Result<Option<Static>, String> {
+        let key = match object {
+            Expr::Ident(name, _) => match self.lookup(name) {
+                Some(Binding { kind: Kind::Type(key), .. }) => key.clone(),
+                Some(Binding { kind: Kind::Value, .. }) if name == "self" => {
+                    let Some(key) = &self.self_ty else {
+                        return Ok(None);
+                    };
+                    let Some(i) = self.types[key].info.fields.iter().position(|f| f == field) else {
+                        return Ok(None);
+                    };
+                    let (access, _) = self.var(name)?.expect("self");
+                    return Ok(Some(Static::Field(format!("XU_STRUCT({access})->fields[{i}]"))));
+                }
+                _ => return Ok(None),
+            },
+            Expr::Member(m) => match self.module_alias(&m.object).and_then(|to| self.module_type(to, &m.field)) {
+                Some(key) => key,
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let info = &self.types[&key].info;
+        Ok(if info.static_fields.contains(field) {
+            Some(Static::Field(format!("g{key}__{}", c_name(field))))
+        } else if info.statics.contains(field) {
+            Some(Static::Method(format!("f{key}__s_{}", c_name(field))))
+        } else {
+            None
+        })
+    }
+
+    fn module_alias(&self, e: &Expr) -> Option<usize> {
+        match e {
+            Expr::Ident(name, _) => match self.lookup(name)? {
+                Binding { kind: Kind::Module(m), .. } => Some(*m),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn module_type(&self, module: usize, ty: &str) -> Option<String> {
+        let info = &self.infos[module];
+        let exported = info.exports.iter().any(|n| n == ty);
+        (exported && info.types.contains_key(ty)).then(|| format!("{module}_{}", c_name(ty)))
+    }
+
+    fn not_exported(&self, module: usize, name: &str) -> String {
+        format!("{}: {} does not export `{name}`", self.key(), self.modules[module].key)
+    }
+
+    /// The exported name `name` of module `module` as a value.
+    fn export(&mut self, module: usize, name: &str) -> Result<String, String> {
+        match self.globals[module].get(name).map(|b| (b.c.clone(), b.kind.clone())) {
+            Some((c, Kind::Value)) => Ok(c),
+            Some((f, Kind::Func(_))) => Ok(self.static_closure(&f)),
+            _ => Err(format!("{}: `{name}` of {} is not a value in C output", self.key(), self.modules[module].key)),
+        }
+    }
+
+    /// `e` as an expression that can be evaluated after the ones emitted
+    /// next without changing the order of their effects.
+    fn operand(&mut self, e: &Expr) -> Result<String, String> {
+        let v = self.expr(e)?;
+        if is_atom(e) {
+            return Ok(v);
+        }
+        Ok(self.temp(&v))
+    }
+
+    fn operands(&mut self, es: &[Expr]) -> Result<Vec<String>, String> {
+        es.iter().map(|e| self.operand(e)).collect()
+    }
+
+    /// The `argc, argv` of a call with arguments `es`.
+    fn args(&mut self, es: &[Expr]) -> Result<String, String> {
+        let vs = self.operands(es)?;
+        Ok(array(&vs))
+    }
+
+    fn cond(&mut self, e: &Expr) -> Result<String, String> {
+        Ok(match e {
+            Expr::Bool(b) => (*b as i32).to_string(),
+            e => format!("xu_truthy({})", self.expr(e)?),
+        })
+    }
+
+    /// Emits `t = e` for a branch of a conditional expression.
+    fn branch_value(&mut self, t: &str, e: &Expr) -> Result<(), String> {
+        self.indent += 1;
+        let r = self.expr(e).map(|v| self.line(&format!("{t} = {v};")));
+        self.indent -= 1;
+        r
+    }
+
+    fn expr(&mut self, e: &Expr) -> Result<String, String> {
+        Ok(match e {
+            Expr::Ident(name, _) => match self.var(name)? {
+                Some((access, Kind::Value)) => access,
+                Some((_, Kind::Func(f))) => self.static_closure(&f),
+                Some(_) => return Err(format!("{}: `{name}` is not a value in C output", self.key())),
+                None if BUILTINS.contains(&name.as_str()) => self.builtin_value(name),
+                None if xu_syntax::BUILTIN_NAMES.contains(&name.as_str()) => {
+                    return Err(format!("{}: builtin `{name}` is not available in C output", self.key()));
+                }
+                None => format!("xu_undefined({})", c_str(name)),
+            },
+            Expr::Int(i) => format!("xu_int({})", c_int(*i)),
+            Expr::Float(f) => format!("xu_float({})", c_float(*f)),
+            Expr::Str(s) => self.string(s),
+            Expr::Bool(b) => format!("xu_bool({})", *b as i32),
+            Expr::InterpolatedString(parts) => {
+                let parts: Vec<&Expr> = parts.iter().filter(|p| !matches!(p, Expr::Str(s) if s.is_empty())).collect();
+                match parts.as_slice() {
+                    [] => self.string(""),
+                    [Expr::Str(s)] => self.string(s),
+                    _ => {
+                        let mut vs = Vec::with_capacity(parts.len());
+                        for p in parts {
+                            vs.push(self.operand(p)?);
+                        }
+                        format!("xu_concat({})", array(&vs))
+                    }
+                }
+            }
+            Expr::List(items) if items.is_empty() => "xu_list_new(0)".to_string(),
+            Expr::List(items) => format!("xu_list_of({})", self.args(items)?),
+            Expr::Tuple(items) => format!("xu_tuple_of({})", self.args(items)?),
+            Expr::Dict(entries) if entries.is_empty() => "xu_dict_new()".to_string(),
+            Expr::Dict(entries) => {
+                let mut parts = Vec::with_capacity(entries.len() * 2);
+                for (k, v) in entries.iter() {
+                    parts.push(self.string(k));
+                    parts.push(self.operand(v)?);
+                }
+                format!("xu_dict_of({}, (xu_value[]){{{}}})", entries.len(), parts.join(", "))
+            }
+            Expr::Range(r) => {
+                let (a, b) = (self.operand(&r.start)?, self.operand(&r.end)?);
+                format!("xu_range_of({a}, {b}, {})", r.inclusive as i32)
+            }
+            Expr::IfExpr(ie) => {
+                let c = self.cond(&ie.cond)?;
+                let t = self.temp("xu_unit()");
+                self.line(&format!("if ({c}) {{"));
+                self.branch_value(&t, &ie.then_expr)?;
+                self.line("} else {");
+                self.branch_value(&t, &ie.else_expr)?;
+                self.line("}");
+                t
+            }
+            Expr::Match(m) => {
+                let v = self.expr(&m.expr)?;
+                let subject = self.temp(&v);
+                let t = self.temp("xu_unit()");
+                for (i, (pat, arm)) in m.arms.iter().enumerate() {
+                    let cond = self.pattern_cond(pat, &subject);
+                    self.line(&format!("{}if ({cond}) {{", if i == 0 { "" } else { "} else " }));
+                    self.indent += 1;
+                    self.push_scope();
+                    self.bind_pattern(pat, &subject);
+                    let r = self.expr(arm).map(|v| self.line(&format!("{t} = {v};")));
+                    self.scopes.pop();
+                    r?;
+                    self.indent -= 1;
+                }
+                if let Some(else_expr) = &m.else_expr {
+                    if m.arms.is_empty() {
+                        let v = self.expr(else_expr)?;
+                        self.line(&format!("{t} = {v};"));
+                    } else {
+                        self.line("} else {");
+                        self.branch_value(&t, else_expr)?;
+                    }
+                }
+                if !m.arms.is_empty() {
+                    self.line("}");
+                }
+                t
+            }
+            Expr::FuncLit(def) => {
+                self.next_tmp += 1;
+                let f = format!("fl{}", self.next_tmp);
+                let caps = self.function(def, &f, true, false)?;
+                self.func_value(&f, &caps)
+            }
+            Expr::StructInit(si) => self.struct_init(si)?,
+            Expr::EnumCtor { module, ty, variant, args } => self.enum_ctor(module.as_deref(), ty, variant, args)?,
+            Expr::Member(m) => match self.static_member(&m.object, &m.field)? {
+                Some(Static::Field(field)) => field,
+                Some(Static::Method(f)) => self.static_closure(&f),
+                None => match self.module_alias(&m.object) {
+                    Some(module) if self.infos[module].exports.contains(&m.field) => self.export(module, &m.field)?,
+                    Some(module) => return Err(self.not_exported(module, &m.field)),
+                    None => format!("xu_prop({}, {})", self.operand(&m.object)?, c_str(&m.field)),
+                },
+            },
+            Expr::Index(ix) => {
+                let (o, i) = (self.operand(&ix.object)?, self.operand(&ix.index)?);
+                format!("xu_index({o}, {i})")
+            }
+            Expr::Call(c) => self.call(c)?,
+            Expr::MethodCall(m) => self.method_call(m)?,
+            Expr::Unary { op: UnaryOp::Neg, expr } => match expr.as_ref() {
+                Expr::Int(i) => format!("xu_int({})", c_int(i.wrapping_neg())),
+                Expr::Float(f) => format!("xu_float({})", c_float(-f)),
+                e => format!("xu_neg({})", self.expr(e)?),
+            },
+            Expr::Unary { op: UnaryOp::Not, expr } => format!("xu_not({})", self.expr(expr)?),
+            Expr::Unary { op: UnaryOp::BitNot, expr } => format!("xu_bnot({})", self.expr(expr)?),
+            Expr::Binary { op: op @ (BinaryOp::And | BinaryOp::Or), left, right } => {
+                let l = self.expr(left)?;
+                let t = self.temp(&format!("xu_bool(xu_truthy({l}))"));
+                self.line(&format!("if ({}{t}.as.b) {{", if *op == BinaryOp::And { "" } else { "!" }));
+                self.indent += 1;
+                let r = self.expr(right).map(|r| self.line(&format!("{t} = xu_bool(xu_truthy({r}));")));
+                self.indent -= 1;
+                r?;
+                self.line("}");
+                t
+            }
+            Expr::Binary { op: BinaryOp::Coalesce, left, right } => {
+                let l = self.expr(left)?;
+                let t = self.temp(&l);
+                self.line(&format!("if (!xu_present(&{t})) {{"));
+                self.indent += 1;
+                let r = self.expr(right).map(|r| self.line(&format!("{t} = {r};")));
+                self.indent -= 1;
+                r?;
+                self.line("}");
+                t
+            }
+            Expr::Binary { op: BinaryOp::In, left, right } => {
+                let x = self.operand(left)?;
+                match right.as_ref() {
+                    Expr::Range(r) => {
+                        let (a, b) = (self.operand(&r.start)?, self.operand(&r.end)?);
+                        format!("xu_in_range({x}, {a}, {b}, {})", r.inclusive as i32)
+                    }
+                    coll => format!("xu_is_in({x}, {})", self.operand(coll)?),
+                }
+            }
+            // Each link that holds opens a block that the next one is
+            // emitted in, so later operands are only evaluated while the
+            // chain still holds.
+            Expr::Compare(c) => {
+                let res = self.temp("xu_bool(0)");
+                let mut prev = self.operand(&c.first)?;
+                let mut open = 0;
+                let mut r = Ok(());
+                for (i, (op, e)) in c.links.iter().enumerate() {
+                    let cur = match self.operand(e) {
+                        Ok(cur) => cur,
+                        Err(err) => {
+                            r = Err(err);
+                            break;
+                        }
+                    };
+                    let f = match op {
+                        BinaryOp::Gt => "xu_gt",
+                        BinaryOp::Lt => "xu_lt",
+                        BinaryOp::Ge => "xu_ge",
+                        _ => "xu_le",
+                    };
+                    if i + 1 == c.links.len() {
+                        self.line(&format!("{res} = {f}({prev}, {cur});"));
+                    } else {
+                        self.line(&format!("if ({f}({prev}, {cur}).as.b) {{"));
+                        self.indent += 1;
+                        open += 1;
+                    }
+                    prev = cur;
+                }
+                for _ in 0..open {
+                    self.indent -= 1;
+                    self.line("}");
+                }
+                r?;
+                res
+            }
+            Expr::Binary { op, left, right } => {
+                let (l, r) = (self.operand(left)?, self.operand(right)?);
+                let f = match op {
+                    BinaryOp::Add => "xu_add",
+                    BinaryOp::Sub => "xu_sub",
+                    BinaryOp::Mul => "xu_mul",
+                    BinaryOp::Div => "xu_div",
+                    BinaryOp::Mod => "xu_mod",
+                    BinaryOp::Pow => "xu_power",
+                    BinaryOp::BitAnd => "xu_band",
+                    BinaryOp::BitOr => "xu_bor",
+                    BinaryOp::BitXor => "xu_bxor",
+                    BinaryOp::Shl => "xu_shl",
+                    BinaryOp::Shr => "xu_shr",
+                    BinaryOp::Gt => "xu_gt",
+                    BinaryOp::Lt => "xu_lt",
+                    BinaryOp::Ge => "xu_ge",
+                    BinaryOp::Le => "xu_le",
+                    BinaryOp::Eq => "xu_eq",
+                    BinaryOp::Ne => "xu_ne",
+                    BinaryOp::And | BinaryOp::Or | BinaryOp::Coalesce | BinaryOp::In => {
+                        unreachable!("short-circuit operators and `in` are handled above")
+                    }
+                };
+                format!("{f}({l}, {r})")
+            }
+            Expr::Group(e) => self.expr(e)?,
+            Expr::Try(_) => {
+                return Err(format!("{}: the `?` operator is not supported in C output", self.key()));
+            }
+            // Each link opens a block that the rest of the chain is emitted
+            // in; the chain's value stays `none` unless all of them are entered.
+            Expr::OptChain(e) => {
+                let res = self.temp("xu_none()");
+                let outer = std::mem::take(&mut self.open_links);
+                let r = self.expr(e).map(|v| self.line(&format!("{res} = {v};")));
+                for _ in 0..self.open_links {
+                    self.indent -= 1;
+                    self.line("}");
+                }
+                self.open_links = outer;
+                r?;
+                res
+            }
+            Expr::OptLink(e) => {
+                let v = self.expr(e)?;
+                let t = self.temp(&v);
+                self.line(&format!("if (xu_present(&{t})) {{"));
+                self.indent += 1;
+                self.open_links += 1;
+                t
+            }
+            Expr::Error(_) => {
+                return Err(format!("{}: cannot translate an expression with syntax errors", self.key()));
+            }
+        })
+    }
+
+    fn struct_init(&mut self, si: &xu_ir::StructInitExpr) -> Result<String, String> {
+        let key = match &si.module {
+            Some(m) => match self.module_alias(m) {
+                Some(to) => Some(self.module_type(to, &si.ty).ok_or_else(|| self.not_exported(to, &si.ty))?),
+                None => None,
+            },
+            None => match self.lookup(&si.ty) {
+                Some(Binding { kind: Kind::Type(key), .. }) => Some(key.clone()),
+                _ => None,
+            },
+        };
+        let Some(key) = key.filter(|key| !self.types[key].is_enum) else {
+            return Err(format!("{}: unknown struct `{}`", self.key(), si.ty));
+        };
+        let mut spreads = Vec::new();
+        let mut fields = Vec::new();
+        for item in si.items.iter() {
+            match item {
+                StructInitItem::Spread(v) => spreads.push(self.operand(v)?),
+                StructInitItem::Field(name, v) => {
+                    let Some(i) = self.types[&key].info.fields.iter().position(|f| f == name) else {
+                        return Err(format!("{}: struct `{}` has no field `{name}`", self.key(), si.ty));
+                    };
+                    fields.push((i, self.operand(v)?));
+                }
+            }
+        }
+        let t = self.temp(&format!("xu_struct_new(&ty{key})"));
+        for base in spreads {
+            self.line(&format!("xu_struct_spread({t}, {base});"));
+        }
+        for (i, v) in fields {
+            self.line(&format!("XU_STRUCT({t})->fields[{i}] = {v};"));
+        }
+        self.line(&format!("xu_struct_fill({t});"));
+        Ok(t)
+    }
+
+    fn enum_ctor(&mut self, module: Option<&Expr>, ty: &str, variant: &str, args: &[Expr]) -> Result<String, String> {
+        if module.is_none() && self.lookup(ty).is_none() {
+            let f = match (ty, variant, args.len()) {
+                ("Option", "none", 0) => return Ok("xu_none()".to_string()),
+                ("Option", "some", 1) => Some("xu_some"),
+                ("Result", "ok", 1) => Some("xu_ok"),
+                ("Result", "err", 1) => Some("xu_err"),
+                _ => None,
+            };
+            if let Some(f) = f {
+                return Ok(format!("{f}({})", self.expr(&args[0])?));
+            }
+        }
+        let desc = match module {
+            Some(m) => match self.module_alias(m) {
+                Some(to) => format!("&ty{}", self.module_type(to, ty).ok_or_else(|| self.not_exported(to, ty))?),
+                None => return Err(format!("{}: unknown enum `{ty}`", self.key())),
+            },
+            None => match self.lookup(ty) {
+                Some(Binding { kind: Kind::Type(key), .. }) => format!("&ty{key}"),
+                _ => self.undeclared_enum(ty),
+            },
+        };
+        let args = self.operands(args)?;
+        Ok(format!("xu_variant({desc}, {}, {})", c_str(variant), array(&args)))
+    }
+
+    fn call(&mut self, c: &xu_ir::CallExpr) -> Result<String, String> {
+        let direct = match c.callee.as_ref() {
+            Expr::Ident(name, _) => match self.lookup(name).map(|b| &b.kind) {
+                Some(Kind::Func(f)) => Some(format!("{f}(NULL, ")),
+                None if BUILTINS.contains(&name.as_str()) => Some(format!("xu_b_{name}(")),
+                _ => None,
+            },
+            Expr::Member(m) => match self.module_alias(&m.object) {
+                Some(to) if self.infos[to].exports.contains(&m.field) => match self.globals[to].get(&m.field) {
+                    Some(Binding { kind: Kind::Func(f), .. }) => Some(format!("{f}(NULL, ")),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(f) = direct {
+            return Ok(format!("{f}{})", self.args(&c.args)?));
+        }
+        let f = self.operand(&c.callee)?;
+        Ok(format!("xu_call({f}, {})", self.args(&c.args)?))
+    }
+
+    fn method_call(&mut self, m: &xu_ir::MethodCallExpr) -> Result<String, String> {
+        match self.static_member(&m.receiver, &m.method)? {
+            Some(Static::Method(f)) => return Ok(format!("{f}(NULL, {})", self.args(&m.args)?)),
+            Some(Static::Field(v)) => {
+                let f = self.temp(&v);
+                return Ok(format!("xu_call({f}, {})", self.args(&m.args)?));
+            }
+            None => {}
+        }
+        if let Some(module) = self.module_alias(&m.receiver) {
+            if !self.infos[module].exports.contains(&m.method) {
+                return Err(self.not_exported(module, &m.method));
+            }
+            if let Some(Binding { kind: Kind::Func(f), .. }) = self.globals[module].get(&m.method) {
+                let f = f.clone();
+                return Ok(format!("{f}(NULL, {})", self.args(&m.args)?));
+            }
+            let f = self.export(module, &m.method)?;
+            let f = self.temp(&f);
+            return Ok(format!("xu_call({f}, {})", self.args(&m.args)?));
+        }
+        if let (Expr::Ident(name, _), Some(key)) = (m.receiver.as_ref(), &self.self_ty) {
+            let known = self.types[key].methods.iter().rev().find(|(n, _)| *n == m.method).map(|(_, f)| f.clone());
+            if let (true, Some(f)) = (name == "self", known) {
+                let (recv, _) = self.var(name)?.expect("self");
+                let mut vs = vec![recv];
+                vs.extend(self.operands(&m.args)?);
+                return Ok(format!("{f}(NULL, {})", array(&vs)));
+            }
+        }
+        let recv = self.operand(&m.receiver)?;
+        Ok(format!("xu_call_method({recv}, {}, {})", c_str(&m.method), self.args(&m.args)?))
+    }
+}
+
+/// Expressions whose C form can be evaluated later without reordering
+/// effects.
+fn is_atom(e: &Expr) -> bool {
+    match e {
+        Expr::Int(_) | Expr::Float(_) | Expr::Str(_) | Expr::Bool(_) | Expr::Ident(..) => true,
+        Expr::Group(e) => is_atom(e),
+        _ => false,
+    }
+}
+
+/// `n, (xu_value[]){...}` for the `argc, argv` of runtime functions.
+fn array(vs: &[String]) -> String {
+    if vs.is_empty() { "0, NULL".to_string() } else { format!("{}, (xu_value[]){{{}}}", vs.len(), vs.join(", ")) }
+}
+
+/// Names used inside the functions nested in `body`. A variable of such a
+/// name is kept in a cell, so the closures share it instead of copying it.
+fn captured(body: &[Stmt]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    scan_stmts(body, false, &mut names);
+    names
+}
+
+fn scan_func(def: &FuncDef, out: &mut HashSet<String>) {
+    for p in def.params.iter() {
+        if let Some(d) = &p.default {
+            scan_expr(d, true, out);
+        }
+    }
+    scan_stmts(&def.body, true, out);
+}
+
+fn scan_stmts(stmts: &[Stmt], nested: bool, out: &mut HashSet<String>) {
+    for s in stmts {
+        scan_stmt(s, nested, out);
+    }
+}
+
+fn scan_stmt(s: &Stmt, nested: bool, out: &mut HashSet<String>) {
+    match s {
+        Stmt::StructDef(def) => {
+            for f in def.fields.iter() {
+                if let Some(d) = &f.default {
+                    scan_expr(d, true, out);
+                }
+            }
+            for f in def.static_fields.iter() {
+                scan_expr(&f.default, nested, out);
+            }
+            for m in def.methods.iter() {
+                scan_func(m, out);
+            }
+        }
+        Stmt::FuncDef(def) => scan_func(def, out),
+        Stmt::DoesBlock(block) => {
+            for f in block.funcs.iter() {
+                scan_func(f, out);
+            }
+        }
+        Stmt::If(s) => {
+            for (cond, body) in s.branches.iter() {
+                scan_expr(cond, nested, out);
+                scan_stmts(body, nested, out);
+            }
+            if let Some(body) = &s.else_branch {
+                scan_stmts(body, nested, out);
+            }
+        }
+        Stmt::While(s) => {
+            scan_expr(&s.cond, nested, out);
+            scan_stmts(&s.body, nested, out);
+        }
+        Stmt::ForEach(s) => {
+            scan_expr(&s.iter, nested, out);
+            scan_stmts(&s.body, nested, out);
+        }
+        Stmt::Match(s) => {
+            scan_expr(&s.expr, nested, out);
+            for (_, body) in s.arms.iter() {
+                scan_stmts(body, nested, out);
+            }
+            if let Some(body) = &s.else_branch {
+                scan_stmts(body, nested, out);
+            }
+        }
+        Stmt::Block(body) => scan_stmts(body, nested, out),
+        Stmt::Return(Some(e)) | Stmt::Expr(e) => scan_expr(e, nested, out),
+        Stmt::Assign(a) => {
+            scan_expr(&a.target, nested, out);
+            scan_expr(&a.value, nested, out);
+        }
+        Stmt::EnumDef(_) | Stmt::Use(_) | Stmt::Return(None) | Stmt::Break | Stmt::Continue | Stmt::Error(_) => {}
+    }
+}
+
+fn scan_expr(e: &Expr, nested: bool, out: &mut HashSet<String>) {
+    let each = |es: &[Expr], out: &mut HashSet<String>| es.iter().for_each(|e| scan_expr(e, nested, out));
+    match e {
+        Expr::Ident(name, _) => {
+            if nested {
+                out.insert(name.clone());
+            }
+        }
+        Expr::FuncLit(def) => scan_func(def, out),
+        Expr::InterpolatedString(items) | Expr::List(items) | Expr::Tuple(items) => each(items, out),
+        Expr::Range(r) => {
+            scan_expr(&r.start, nested, out);
+            scan_expr(&r.end, nested, out);
+        }
+        Expr::IfExpr(ie) => {
+            scan_expr(&ie.cond, nested, out);
+            scan_expr(&ie.then_expr, nested, out);
+            scan_expr(&ie.else_expr, nested, out);
+        }
+        Expr::Match(m) => {
+            scan_expr(&m.expr, nested, out);
+            for (_, arm) in m.arms.iter() {
+                scan_expr(arm, nested, out);
+            }
+            if let Some(e) = &m.else_expr {
+                scan_expr(e, nested, out);
+            }
+        }
+        Expr::Dict(entries) => {
+            for (_, v) in entries.iter() {
+                scan_expr(v, nested, out);
+            }
+        }
+        Expr::StructInit(si) => {
+            if let Some(m) = &si.module {
+                scan_expr(m, nested, out);
+            }
+            for item in si.items.iter() {
+                match item {
+                    StructInitItem::Spread(v) | StructInitItem::Field(_, v) => scan_expr(v, nested, out),
+                }
+            }
+        }
+        Expr::EnumCtor { module, args, .. } => {
+            if let Some(m) = module {
+                scan_expr(m, nested, out);
+            }
+            each(args, out);
+        }
+        Expr::Member(m) => scan_expr(&m.object, nested, out),
+        Expr::Index(ix) => {
+            scan_expr(&ix.object, nested, out);
+            scan_expr(&ix.index, nested, out);
+        }
+        Expr::Call(c) => {
+            scan_expr(&c.callee, nested, out);
+            each(&c.args, out);
+        }
+        Expr::MethodCall(m) => {
+            scan_expr(&m.receiver, nested, out);
+            each(&m.args, out);
+        }
+        Expr::Unary { expr, .. } | Expr::Group(expr) | Expr::Try(expr) | Expr::OptChain(expr) | Expr::OptLink(expr) => {
+            scan_expr(expr, nested, out)
+        }
+        Expr::Binary { left, right, .. } => {
+            scan_expr(left, nested, out);
+            scan_expr(right, nested, out);
+        }
+        Expr::Compare(c) => {
+            scan_expr(&c.first, nested, out);
+            for (_, e) in c.links.iter() {
+                scan_expr(e, nested, out);
+            }
+        }
+        Expr::Int(_) | Expr::Float(_) | Expr::Str(_) | Expr::Bool(_) | Expr::Error(_) => {}
+    }
+}
+
+/// A fragment of a C identifier for the Xu name `name`; generated names
+/// always have a prefix, so keywords and leading digits need no care.
+fn c_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            out.push(c);
+        } else {
+            let _ = write!(out, "_u{:x}_", c as u32);
+        }
+    }
+    out
+}
+
+fn c_int(i: i64) -> String {
+    match i {
+        i64::MIN => "INT64_MIN".to_string(),
+        i if i32::try_from(i).is_ok() => i.to_string(),
+        i => format!("INT64_C({i})"),
+    }
+}
+
+fn c_float(f: f64) -> String {
+    if f.is_nan() {
+        "NAN".to_string()
+    } else if f.is_infinite() {
+        if f > 0.0 { "HUGE_VAL".to_string() } else { "-HUGE_VAL".to_string() }
+    } else {
+        format!("{f:?}")
+    }
+}
+
+fn c_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for b in s.bytes() {
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            // Keeps `??` sequences from reading as trigraphs.
+            b'?' => out.push_str("\\?"),
+            0x20..=0x7e => out.push(b as char),
+            b => {
+                let _ = write!(out, "\\{b:03o}");
+            }
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,2484 @@
+/*
+ * Runtime support for C generated by `xu codegen <file> c`.
+ *
+ * Portable C99 with no dependencies besides the C library. Values are
+ * tagged unions; every object lives in an arena that is only released when
+ * the program exits, so there is no garbage collector to port. A program
+ * includes this file exactly once.
+ *
+ * Configuration, as macros defined before the include:
+ *   XU_ARENA_SIZE     bytes of a static arena; without it chunks of
+ *                     XU_ARENA_CHUNK bytes are taken from malloc
+ *   XU_WRITE(p, n)    writes program output (default: stdout)
+ *   XU_WRITE_ERR(p, n) writes the error report (default: stderr)
+ *   XU_EXIT(code)     ends the program after a runtime error
+ */
+#ifndef XU_RUNTIME_H
+#define XU_RUNTIME_H
+
+#include <inttypes.h>
+#include <math.h>
+#include <stdarg.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+
+/* XU_FN and XU_DATA declare functions and data a program may not use. */
+#if defined(__GNUC__)
+#define XU_FN static __attribute__((unused))
+#define XU_DATA static __attribute__((unused))
+#define XU_NORETURN __attribute__((noreturn))
+#else
+#define XU_FN static
+#define XU_DATA static
+#define XU_NORETURN
+#endif
+
+#ifndef XU_WRITE
+#define XU_WRITE(p, n) fwrite((p), 1, (n), stdout)
+#endif
+#ifndef XU_WRITE_ERR
+#define XU_WRITE_ERR(p, n) fwrite((p), 1, (n), stderr)
+#endif
+#ifndef XU_EXIT
+#define XU_EXIT(code) (fflush(stdout), exit(code))
+#endif
+#ifndef XU_ARENA_CHUNK
+#define XU_ARENA_CHUNK ((size_t)1 << 20)
+#endif
+
+/* ------------------------------------------------------------------------
+ * Values
+ */
+
+typedef enum {
+    XU_UNIT,
+    XU_BOOL,
+    XU_INT,
+    XU_FLOAT,
+    XU_STR,
+    XU_LIST,
+    XU_TUPLE,
+    XU_DICT,
+    XU_RANGE,
+    XU_STRUCT,
+    XU_ENUM,
+    XU_FUNC,
+    XU_BUILDER
+} xu_tag;
+
+typedef struct {
+    xu_tag tag;
+    union {
+        int b;
+        int64_t i;
+        double f;
+        void *p;
+    } as;
+} xu_value;
+
+/* Strings are immutable UTF-8; literals point at static data. */
+typedef struct {
+    size_t len;
+    const char *data;
+} xu_string;
+
+/* Lists and tuples. */
+typedef struct {
+    size_t len, cap;
+    xu_value *items;
+} xu_list;
+
+typedef struct {
+    xu_value key, val;
+    uint64_t hash;
+} xu_entry;
+
+/* Dicts keep insertion order in `entries`; `index` maps hashes to entries. */
+typedef struct {
+    size_t len, cap;
+    xu_entry *entries;
+    size_t slots;
+    int32_t *index;
+} xu_dict;
+
+typedef struct {
+    int64_t start, end;
+    int inclusive;
+} xu_range;
+
+typedef struct xu_closure xu_closure;
+typedef xu_value (*xu_fn)(xu_closure *self, int argc, xu_value *argv);
+
+/* A function value: the C function and the cells of the variables it
+ * captured. Methods get their receiver as the first argument. */
+struct xu_closure {
+    xu_fn fn;
+    int ncaps;
+    xu_value **caps;
+};
+
+typedef struct {
+    const char *name;
+    xu_fn fn;
+} xu_method;
+
+/* A struct or enum type. `defaults` holds a function per field computing
+ * its default, or NULL. */
+typedef struct {
+    const char *name;
+    int is_enum;
+    int nfields;
+    const char *const *fields;
+    xu_value (*const *defaults)(void);
+    int nmethods;
+    const xu_method *methods;
+} xu_type;
+
+typedef struct {
+    const xu_type *type;
+    xu_value *fields;
+} xu_struct;
+
+typedef struct {
+    const xu_type *type;
+    const char *tag;
+    int n;
+    xu_value *payload;
+} xu_enum;
+
+typedef struct {
+    size_t len, cap;
+    char *data;
+} xu_builder;
+
+XU_FN XU_NORETURN void xu_fail(const char *fmt, ...);
+
+/* ------------------------------------------------------------------------
+ * Arena
+ */
+
+typedef union {
+    int64_t i;
+    double f;
+    void *p;
+} xu_align;
+
+#ifdef XU_ARENA_SIZE
+static xu_align xu_arena_mem[(XU_ARENA_SIZE + sizeof(xu_align) - 1) / sizeof(xu_align)];
+static size_t xu_arena_used;
+#else
+static unsigned char *xu_arena_ptr;
+static size_t xu_arena_left;
+#endif
+
+XU_FN void *xu_alloc(size_t n) {
+    void *p;
+    n = (n + sizeof(xu_align) - 1) / sizeof(xu_align) * sizeof(xu_align);
+    if (n == 0) n = sizeof(xu_align);
+#ifdef XU_ARENA_SIZE
+    if (n > sizeof(xu_arena_mem) - xu_arena_used) xu_fail("Out of memory");
+    p = (unsigned char *)xu_arena_mem + xu_arena_used;
+    xu_arena_used += n;
+#else
+    if (n > xu_arena_left) {
+        size_t size = n > XU_ARENA_CHUNK ? n : XU_ARENA_CHUNK;
+        xu_arena_ptr = (unsigned char *)malloc(size);
+        if (!xu_arena_ptr) xu_fail("Out of memory");
+        xu_arena_left = size;
+    }
+    p = xu_arena_ptr;
+    xu_arena_ptr += n;
+    xu_arena_left -= n;
+#endif
+    return p;
+}
+
+/* Growing copies the data; the old block stays in the arena. */
+XU_FN void *xu_grow(void *old, size_t old_size, size_t new_size) {
+    void *p = xu_alloc(new_size);
+    if (old_size) memcpy(p, old, old_size);
+    return p;
+}
+
+/* ------------------------------------------------------------------------
+ * Constructors and accessors
+ */
+
+static const xu_value xu_unit_value = {XU_UNIT, {0}};
+
+XU_FN xu_value xu_unit(void) { return xu_unit_value; }
+
+XU_FN xu_value xu_bool(int b) {
+    xu_value v;
+    v.tag = XU_BOOL;
+    v.as.b = b != 0;
+    return v;
+}
+
+XU_FN xu_value xu_int(int64_t i) {
+    xu_value v;
+    v.tag = XU_INT;
+    v.as.i = i;
+    return v;
+}
+
+XU_FN xu_value xu_float(double f) {
+    xu_value v;
+    v.tag = XU_FLOAT;
+    v.as.f = f;
+    return v;
+}
+
+XU_FN xu_value xu_obj(xu_tag tag, const void *p) {
+    xu_value v;
+    v.tag = tag;
+    v.as.p = (void *)p;
+    return v;
+}
+
+#define XU_STR(v) ((xu_string *)(v).as.p)
+#define XU_LIST(v) ((xu_list *)(v).as.p)
+#define XU_DICT(v) ((xu_dict *)(v).as.p)
+#define XU_RANGE(v) ((xu_range *)(v).as.p)
+#define XU_STRUCT(v) ((xu_struct *)(v).as.p)
+#define XU_ENUM(v) ((xu_enum *)(v).as.p)
+#define XU_FUNC(v) ((xu_closure *)(v).as.p)
+#define XU_BUILDER(v) ((xu_builder *)(v).as.p)
+
+XU_FN xu_value xu_str_v(const xu_string *s) { return xu_obj(XU_STR, s); }
+
+XU_FN xu_value xu_str_new(const char *data, size_t len) {
+    xu_string *s = (xu_string *)xu_alloc(sizeof(xu_string));
+    char *copy = (char *)xu_alloc(len + 1);
+    if (len) memcpy(copy, data, len);
+    copy[len] = 0;
+    s->len = len;
+    s->data = copy;
+    return xu_obj(XU_STR, s);
+}
+
+XU_FN xu_value xu_cstr(const char *s) { return xu_str_new(s, strlen(s)); }
+
+XU_FN xu_value xu_list_new(size_t cap) {
+    xu_list *l = (xu_list *)xu_alloc(sizeof(xu_list));
+    l->len = 0;
+    l->cap = cap;
+    l->items = cap ? (xu_value *)xu_alloc(cap * sizeof(xu_value)) : NULL;
+    return xu_obj(XU_LIST, l);
+}
+
+XU_FN void xu_list_push(xu_value list, xu_value v) {
+    xu_list *l = XU_LIST(list);
+    if (l->len == l->cap) {
+        size_t cap = l->cap ? l->cap * 2 : 4;
+        l->items = (xu_value *)xu_grow(l->items, l->len * sizeof(xu_value), cap * sizeof(xu_value));
+        l->cap = cap;
+    }
+    l->items[l->len++] = v;
+}
+
+XU_FN xu_value xu_list_of(int n, const xu_value *items) {
+    xu_value l = xu_list_new((size_t)n);
+    if (n) memcpy(XU_LIST(l)->items, items, (size_t)n * sizeof(xu_value));
+    XU_LIST(l)->len = (size_t)n;
+    return l;
+}
+
+XU_FN xu_value xu_tuple_of(int n, const xu_value *items) {
+    xu_value t = xu_list_of(n, items);
+    t.tag = XU_TUPLE;
+    return t;
+}
+
+XU_FN xu_value xu_pair(xu_value a, xu_value b) {
+    xu_value items[2];
+    items[0] = a;
+    items[1] = b;
+    return xu_tuple_of(2, items);
+}
+
+XU_FN xu_value xu_range_of(xu_value a, xu_value b, int inclusive) {
+    xu_range *r;
+    if (a.tag != XU_INT || b.tag != XU_INT) xu_fail("Range bounds must be int");
+    r = (xu_range *)xu_alloc(sizeof(xu_range));
+    r->start = a.as.i;
+    r->end = b.as.i;
+    r->inclusive = inclusive;
+    return xu_obj(XU_RANGE, r);
+}
+
+/* A function without captures, such as a top-level function. */
+XU_FN xu_value xu_func(xu_closure *c) { return xu_obj(XU_FUNC, c); }
+
+XU_FN xu_value xu_closure_new(xu_fn fn, int ncaps, xu_value **caps) {
+    xu_closure *c = (xu_closure *)xu_alloc(sizeof(xu_closure));
+    c->fn = fn;
+    c->ncaps = ncaps;
+    c->caps = NULL;
+    if (ncaps) {
+        c->caps = (xu_value **)xu_alloc((size_t)ncaps * sizeof(xu_value *));
+        memcpy(c->caps, caps, (size_t)ncaps * sizeof(xu_value *));
+    }
+    return xu_obj(XU_FUNC, c);
+}
+
+/* A variable captured by a closure. */
+XU_FN xu_value *xu_cell(xu_value v) {
+    xu_value *c = (xu_value *)xu_alloc(sizeof(xu_value));
+    *c = v;
+    return c;
+}
+
+XU_FN const char *xu_type_name(xu_value v) {
+    switch (v.tag) {
+    case XU_UNIT: return "unit";
+    case XU_BOOL: return "bool";
+    case XU_INT: return "int";
+    case XU_FLOAT: return "float";
+    case XU_STR: return "string";
+    case XU_LIST: return "list";
+    case XU_TUPLE: return "tuple";
+    case XU_DICT: return "dict";
+    case XU_RANGE: return "range";
+    case XU_STRUCT: return XU_STRUCT(v)->type->name;
+    case XU_ENUM: return XU_ENUM(v)->type->name;
+    case XU_FUNC: return "function";
+    case XU_BUILDER: return "builder";
+    }
+    return "unknown";
+}
+
+XU_FN const xu_string *xu_as_str(xu_value v, const char *what) {
+    if (v.tag != XU_STR) xu_fail("%s expects string, got %s", what, xu_type_name(v));
+    return XU_STR(v);
+}
+
+XU_FN int64_t xu_as_int(xu_value v, const char *what) {
+    if (v.tag != XU_INT) xu_fail("%s expects int, got %s", what, xu_type_name(v));
+    return v.as.i;
+}
+
+XU_FN double xu_as_num(xu_value v, const char *what) {
+    if (v.tag == XU_INT) return (double)v.as.i;
+    if (v.tag != XU_FLOAT) xu_fail("%s expects number, got %s", what, xu_type_name(v));
+    return v.as.f;
+}
+
+XU_FN xu_list *xu_as_list(xu_value v, const char *what) {
+    if (v.tag != XU_LIST) xu_fail("%s expects a list, got %s", what, xu_type_name(v));
+    return XU_LIST(v);
+}
+
+XU_FN xu_value xu_undefined(const char *name) { xu_fail("Undefined identifier: %s", name); }
+
+/* Conditions must be bools. */
+XU_FN int xu_truthy(xu_value v) {
+    if (v.tag != XU_BOOL) xu_fail("Condition must be bool, got %s", xu_type_name(v));
+    return v.as.b;
+}
+
+/* ------------------------------------------------------------------------
+ * Structs and enums
+ */
+
+XU_FN xu_value xu_struct_new(const xu_type *t) {
+    xu_struct *s = (xu_struct *)xu_alloc(sizeof(xu_struct));
+    int i;
+    s->type = t;
+    s->fields = (xu_value *)xu_alloc((size_t)(t->nfields ? t->nfields : 1) * sizeof(xu_value));
+    for (i = 0; i < t->nfields; i++) s->fields[i] = xu_unit();
+    return xu_obj(XU_STRUCT, s);
+}
+
+XU_FN int xu_field_index(const xu_type *t, const char *name) {
+    int i;
+    for (i = 0; i < t->nfields; i++) {
+        if (strcmp(t->fields[i], name) == 0) return i;
+    }
+    return -1;
+}
+
+/* `T{ ..base }`: copies the fields `base` has of the same name. */
+XU_FN void xu_struct_spread(xu_value dst, xu_value base) {
+    xu_struct *d = XU_STRUCT(dst);
+    const xu_struct *b;
+    int i;
+    if (base.tag != XU_STRUCT) xu_fail("Cannot spread %s into %s", xu_type_name(base), d->type->name);
+    b = XU_STRUCT(base);
+    for (i = 0; i < b->type->nfields; i++) {
+        int j = xu_field_index(d->type, b->type->fields[i]);
+        if (j >= 0) d->fields[j] = b->fields[i];
+    }
+}
+
+/* Gives the fields still unset their defaults. */
+XU_FN void xu_struct_fill(xu_value s) {
+    const xu_type *t = XU_STRUCT(s)->type;
+    int i;
+    for (i = 0; t->defaults && i < t->nfields; i++) {
+        if (t->defaults[i] && XU_STRUCT(s)->fields[i].tag == XU_UNIT) XU_STRUCT(s)->fields[i] = t->defaults[i]();
+    }
+}
+
+XU_FN xu_value xu_variant(const xu_type *t, const char *tag, int n, const xu_value *payload) {
+    xu_enum *e = (xu_enum *)xu_alloc(sizeof(xu_enum));
+    e->type = t;
+    e->tag = tag;
+    e->n = n;
+    e->payload = NULL;
+    if (n) {
+        e->payload = (xu_value *)xu_alloc((size_t)n * sizeof(xu_value));
+        memcpy(e->payload, payload, (size_t)n * sizeof(xu_value));
+    }
+    return xu_obj(XU_ENUM, e);
+}
+
+static const xu_type xu_option_type = {"Option", 1, 0, NULL, NULL, 0, NULL};
+static const xu_type xu_result_type = {"Result", 1, 0, NULL, NULL, 0, NULL};
+static const xu_enum xu_none_value = {&xu_option_type, "none", 0, NULL};
+
+XU_FN xu_value xu_none(void) { return xu_obj(XU_ENUM, &xu_none_value); }
+XU_FN xu_value xu_some(xu_value v) { return xu_variant(&xu_option_type, "some", 1, &v); }
+XU_FN xu_value xu_ok(xu_value v) { return xu_variant(&xu_result_type, "ok", 1, &v); }
+XU_FN xu_value xu_err(xu_value v) { return xu_variant(&xu_result_type, "err", 1, &v); }
+
+/* Whether `v` is variant `tag` of the enum named `ty` with at least `n`
+ * payload values. */
+XU_FN int xu_is_variant(xu_value v, const char *ty, const char *tag, int n) {
+    return v.tag == XU_ENUM && strcmp(XU_ENUM(v)->type->name, ty) == 0 && strcmp(XU_ENUM(v)->tag, tag) == 0 &&
+           XU_ENUM(v)->n >= n;
+}
+
+XU_FN int xu_is_tuple(xu_value v, size_t n) { return v.tag == XU_TUPLE && XU_LIST(v)->len == n; }
+
+/* For `?.`, `?[` and `??`: whether `*v` is not `Option#none`, unwrapping
+ * `Option#some` in place. */
+XU_FN int xu_present(xu_value *v) {
+    if (xu_is_variant(*v, "Option", "none", 0)) return 0;
+    if (xu_is_variant(*v, "Option", "some", 1)) *v = XU_ENUM(*v)->payload[0];
+    return 1;
+}
+
+/* Payload item or tuple item `i` of a value a pattern matched. */
+XU_FN xu_value xu_part(xu_value v, int i) {
+    if (v.tag == XU_ENUM) return XU_ENUM(v)->payload[i];
+    return XU_LIST(v)->items[i];
+}
+
+/* ------------------------------------------------------------------------
+ * Text
+ */
+
+typedef struct {
+    char *data;
+    size_t len, cap;
+} xu_buf;
+
+XU_FN void xu_buf_put(xu_buf *b, const char *p, size_t n) {
+    if (b->len + n + 1 > b->cap) {
+        size_t cap = b->cap ? b->cap * 2 : 32;
+        while (cap < b->len + n + 1) cap *= 2;
+        b->data = (char *)xu_grow(b->data, b->len, cap);
+        b->cap = cap;
+    }
+    if (n) memcpy(b->data + b->len, p, n);
+    b->len += n;
+    b->data[b->len] = 0;
+}
+
+XU_FN void xu_buf_puts(xu_buf *b, const char *s) { xu_buf_put(b, s, strlen(s)); }
+
+XU_FN void xu_buf_putc(xu_buf *b, char c) { xu_buf_put(b, &c, 1); }
+
+XU_FN xu_value xu_buf_str(xu_buf *b) {
+    xu_string *s = (xu_string *)xu_alloc(sizeof(xu_string));
+    xu_buf_put(b, "", 0);
+    s->len = b->len;
+    s->data = b->data;
+    return xu_obj(XU_STR, s);
+}
+
+XU_FN void xu_buf_int(xu_buf *b, int64_t i) {
+    char tmp[24];
+    snprintf(tmp, sizeof tmp, "%" PRId64, i);
+    xu_buf_puts(b, tmp);
+}
+
+/* Floats print like the interpreter: shortest round trip, positional for
+ * decimal exponents in -6..20 (always with `plain`), without a trailing
+ * `.0`. */
+XU_FN void xu_buf_float(xu_buf *b, double f, int plain) {
+    char s[40], digits[24];
+    int p, exp, nd = 0, i;
+    const char *m;
+    if (f != f) {
+        xu_buf_puts(b, "NaN");
+        return;
+    }
+    if (f == HUGE_VAL || f == -HUGE_VAL) {
+        xu_buf_puts(b, f > 0 ? "inf" : "-inf");
+        return;
+    }
+    if (f == 0) {
+        xu_buf_puts(b, "0");
+        return;
+    }
+    for (p = 0; p <= 16; p++) {
+        snprintf(s, sizeof s, "%.*e", p, f);
+        if (strtod(s, NULL) == f) break;
+    }
+    m = s;
+    if (*m == '-') {
+        xu_buf_putc(b, '-');
+        m++;
+    }
+    for (; *m && *m != 'e'; m++) {
+        if (*m != '.') digits[nd++] = *m;
+    }
+    exp = atoi(m + 1);
+    while (nd > 1 && digits[nd - 1] == '0') nd--;
+    if (!plain && (exp < -6 || exp >= 21)) {
+        xu_buf_putc(b, digits[0]);
+        if (nd > 1) {
+            xu_buf_putc(b, '.');
+            xu_buf_put(b, digits + 1, (size_t)nd - 1);
+        }
+        xu_buf_putc(b, 'e');
+        xu_buf_int(b, exp);
+    } else if (exp >= 0) {
+        if (nd <= exp + 1) {
+            xu_buf_put(b, digits, (size_t)nd);
+            for (i = nd; i < exp + 1; i++) xu_buf_putc(b, '0');
+        } else {
+            xu_buf_put(b, digits, (size_t)exp + 1);
+            xu_buf_putc(b, '.');
+            xu_buf_put(b, digits + exp + 1, (size_t)(nd - exp - 1));
+        }
+    } else {
+        xu_buf_puts(b, "0.");
+        for (i = 0; i < -exp - 1; i++) xu_buf_putc(b, '0');
+        xu_buf_put(b, digits, (size_t)nd);
+    }
+}
+
+/* Containers being printed, to cut cycles. */
+typedef struct xu_path {
+    const void *obj;
+    const struct xu_path *up;
+} xu_path;
+
+XU_FN int xu_on_path(const xu_path *path, const void *obj) {
+    for (; path; path = path->up) {
+        if (path->obj == obj) return 1;
+    }
+    return 0;
+}
+
+XU_FN void xu_buf_dict_key(xu_buf *b, xu_value k, const xu_path *path);
+
+/* The `println` form of a value. */
+XU_FN void xu_buf_value(xu_buf *b, xu_value v, const xu_path *path) {
+    xu_path here;
+    size_t i;
+    int j;
+    here.obj = v.as.p;
+    here.up = path;
+    switch (v.tag) {
+    case XU_UNIT: xu_buf_puts(b, "()"); return;
+    case XU_BOOL: xu_buf_puts(b, v.as.b ? "true" : "false"); return;
+    case XU_INT: xu_buf_int(b, v.as.i); return;
+    case XU_FLOAT: xu_buf_float(b, v.as.f, 0); return;
+    case XU_STR: xu_buf_put(b, XU_STR(v)->data, XU_STR(v)->len); return;
+    case XU_FUNC: xu_buf_puts(b, "function"); return;
+    case XU_BUILDER: xu_buf_put(b, XU_BUILDER(v)->data, XU_BUILDER(v)->len); return;
+    case XU_RANGE:
+        xu_buf_putc(b, '[');
+        xu_buf_int(b, XU_RANGE(v)->start);
+        xu_buf_puts(b, XU_RANGE(v)->inclusive ? "..=" : "..");
+        xu_buf_int(b, XU_RANGE(v)->end);
+        xu_buf_putc(b, ']');
+        return;
+    case XU_LIST:
+    case XU_TUPLE:
+        if (xu_on_path(path, v.as.p)) {
+            xu_buf_puts(b, v.tag == XU_LIST ? "[...]" : "(...)");
+            return;
+        }
+        xu_buf_putc(b, v.tag == XU_LIST ? '[' : '(');
+        for (i = 0; i < XU_LIST(v)->len; i++) {
+            if (i) xu_buf_putc(b, ',');
+            xu_buf_value(b, XU_LIST(v)->items[i], &here);
+        }
+        xu_buf_putc(b, v.tag == XU_LIST ? ']' : ')');
+        return;
+    case XU_DICT:
+        if (xu_on_path(path, v.as.p)) {
+            xu_buf_puts(b, "{...}");
+            return;
+        }
+        xu_buf_putc(b, '{');
+        for (i = 0; i < XU_DICT(v)->len; i++) {
+            if (i) xu_buf_putc(b, ',');
+            xu_buf_dict_key(b, XU_DICT(v)->entries[i].key, &here);
+            xu_buf_putc(b, ':');
+            xu_buf_value(b, XU_DICT(v)->entries[i].val, &here);
+        }
+        xu_buf_putc(b, '}');
+        return;
+    case XU_STRUCT:
+        if (xu_on_path(path, v.as.p)) {
+            xu_buf_puts(b, "{...}");
+            return;
+        }
+        xu_buf_puts(b, XU_STRUCT(v)->type->name);
+        xu_buf_putc(b, '{');
+        for (j = 0; j < XU_STRUCT(v)->type->nfields; j++) {
+            if (j) xu_buf_putc(b, ',');
+            xu_buf_puts(b, XU_STRUCT(v)->type->fields[j]);
+            xu_buf_putc(b, ':');
+            xu_buf_value(b, XU_STRUCT(v)->fields[j], &here);
+        }
+        xu_buf_putc(b, '}');
+        return;
+    case XU_ENUM:
+        if (XU_ENUM(v)->type == &xu_option_type && strcmp(XU_ENUM(v)->tag, "some") == 0) {
+            xu_buf_value(b, XU_ENUM(v)->payload[0], path);
+            return;
+        }
+        xu_buf_puts(b, XU_ENUM(v)->type->name);
+        xu_buf_putc(b, '#');
+        xu_buf_puts(b, XU_ENUM(v)->tag);
+        return;
+    }
+}
+
+XU_FN void xu_buf_dict_key(xu_buf *b, xu_value k, const xu_path *path) {
+    xu_buf_putc(b, '"');
+    xu_buf_value(b, k, path);
+    xu_buf_putc(b, '"');
+}
+
+XU_FN xu_value xu_to_str(xu_value v) {
+    xu_buf b = {NULL, 0, 0};
+    if (v.tag == XU_STR) return v;
+    xu_buf_value(&b, v, NULL);
+    return xu_buf_str(&b);
+}
+
+/* Joins the parts of an interpolated string. */
+XU_FN xu_value xu_concat(int n, const xu_value *parts) {
+    xu_buf b = {NULL, 0, 0};
+    int i;
+    for (i = 0; i < n; i++) xu_buf_value(&b, parts[i], NULL);
+    return xu_buf_str(&b);
+}
+
+XU_FN void xu_buf_quote(xu_buf *b, const char *p, size_t n) {
+    size_t i;
+    xu_buf_putc(b, '"');
+    for (i = 0; i < n; i++) {
+        unsigned char c = (unsigned char)p[i];
+        if (c == '"') xu_buf_puts(b, "\\\"");
+        else if (c == '\\') xu_buf_puts(b, "\\\\");
+        else if (c == '\n') xu_buf_puts(b, "\\n");
+        else if (c == '\r') xu_buf_puts(b, "\\r");
+        else if (c == '\t') xu_buf_puts(b, "\\t");
+        else if (c < 0x20 || c == 0x7f) {
+            char tmp[12];
+            snprintf(tmp, sizeof tmp, "\\u{%x}", c);
+            xu_buf_puts(b, tmp);
+        } else xu_buf_putc(b, (char)c);
+    }
+    xu_buf_putc(b, '"');
+}
+
+/* Number of characters of UTF-8 text. */
+XU_FN size_t xu_utf8_len(const char *p, size_t n) {
+    size_t i, count = 0;
+    for (i = 0; i < n; i++) {
+        if (((unsigned char)p[i] & 0xC0) != 0x80) count++;
+    }
+    return count;
+}
+
+/* Byte offset of character `i`, or `n` past the end. */
+XU_FN size_t xu_utf8_offset(const char *p, size_t n, size_t i) {
+    size_t off = 0;
+    while (off < n && i > 0) {
+        off++;
+        while (off < n && ((unsigned char)p[off] & 0xC0) == 0x80) off++;
+        i--;
+    }
+    return off;
+}
+
+/* ------------------------------------------------------------------------
+ * `inspect`: a value becomes a tree of leaves and groups, laid out on one
+ * line or, in pretty mode, one item per line.
+ */
+
+#define XU_INSPECT_INDENT 2
+#define XU_INSPECT_WIDTH 80
+
+typedef struct xu_doc xu_doc;
+typedef struct {
+    xu_buf prefix;
+    xu_doc *doc;
+} xu_doc_item;
+
+struct xu_doc {
+    xu_buf text; /* the leaf text, or the group's opening */
+    const char *close; /* NULL for leaves */
+    int n;
+    xu_doc_item *items;
+};
+
+typedef struct {
+    int64_t max_depth, max_items;
+} xu_inspect_opts;
+
+XU_FN void xu_doc_flat(const xu_doc *d, xu_buf *out) {
+    int i;
+    xu_buf_put(out, d->text.data, d->text.len);
+    if (!d->close) return;
+    for (i = 0; i < d->n; i++) {
+        if (i) xu_buf_puts(out, ", ");
+        xu_buf_put(out, d->items[i].prefix.data, d->items[i].prefix.len);
+        xu_doc_flat(d->items[i].doc, out);
+    }
+    xu_buf_puts(out, d->close);
+}
+
+XU_FN void xu_doc_layout(const xu_doc *d, xu_buf *out, int level, size_t column, int pretty) {
+    xu_buf flat = {NULL, 0, 0};
+    int i, j;
+    xu_doc_flat(d, &flat);
+    if (!d->close || !pretty || d->n == 0 || column + xu_utf8_len(flat.data, flat.len) <= XU_INSPECT_WIDTH) {
+        xu_buf_put(out, flat.data, flat.len);
+        return;
+    }
+    xu_buf_put(out, d->text.data, d->text.len);
+    xu_buf_putc(out, '\n');
+    for (i = 0; i < d->n; i++) {
+        size_t inner = (size_t)(level + 1) * XU_INSPECT_INDENT;
+        for (j = 0; j < (int)inner; j++) xu_buf_putc(out, ' ');
+        xu_buf_put(out, d->items[i].prefix.data, d->items[i].prefix.len);
+        xu_doc_layout(d->items[i].doc, out, level + 1,
+                      inner + xu_utf8_len(d->items[i].prefix.data, d->items[i].prefix.len), pretty);
+        xu_buf_puts(out, ",\n");
+    }
+    for (j = 0; j < level * XU_INSPECT_INDENT; j++) xu_buf_putc(out, ' ');
+    xu_buf_puts(out, d->close);
+}
+
+XU_FN xu_doc *xu_doc_leaf(void) {
+    xu_doc *d = (xu_doc *)xu_alloc(sizeof(xu_doc));
+    memset(d, 0, sizeof(xu_doc));
+    return d;
+}
+
+XU_FN xu_doc *xu_inspect_doc(xu_value v, int64_t depth, const xu_inspect_opts *opts, const xu_path *path);
+
+/* A container's group; `keys` are the dict keys or NULL, `names` the
+ * struct field names or NULL. */
+XU_FN xu_doc *xu_doc_group(xu_value v, int64_t depth, const xu_inspect_opts *opts, const xu_path *path,
+                           xu_buf open, const char *close, size_t n, const xu_value *vals, const xu_value *keys,
+                           const char *const *names, size_t key_stride) {
+    xu_doc *d = xu_doc_leaf();
+    xu_path here;
+    size_t i, shown;
+    if (xu_on_path(path, v.as.p)) {
+        xu_buf_puts(&d->text, "<cycle>");
+        return d;
+    }
+    if (depth >= opts->max_depth && n > 0) {
+        xu_buf_put(&d->text, open.data, open.len);
+        xu_buf_puts(&d->text, "...");
+        xu_buf_puts(&d->text, close);
+        return d;
+    }
+    here.obj = v.as.p;
+    here.up = path;
+    d->text = open;
+    d->close = close;
+    shown = (int64_t)n > opts->max_items ? (size_t)opts->max_items : n;
+    d->items = (xu_doc_item *)xu_alloc((shown + 1) * sizeof(xu_doc_item));
+    for (i = 0; i < shown; i++) {
+        xu_doc_item *item = &d->items[i];
+        memset(&item->prefix, 0, sizeof(xu_buf));
+        if (keys) {
+            /* Keys are hashable, so they cannot form cycles */
+            xu_inspect_opts flat = {INT64_MAX, INT64_MAX};
+            xu_doc_flat(xu_inspect_doc(*(const xu_value *)((const char *)keys + i * key_stride), depth + 1, &flat,
+                                       NULL),
+                        &item->prefix);
+            xu_buf_puts(&item->prefix, ": ");
+        } else if (names) {
+            xu_buf_puts(&item->prefix, names[i]);
+            xu_buf_puts(&item->prefix, ": ");
+        }
+        item->doc = xu_inspect_doc(*(const xu_value *)((const char *)vals + i * key_stride), depth + 1, opts, &here);
+    }
+    d->n = (int)shown;
+    if (n > shown) {
+        xu_doc_item *item = &d->items[d->n++];
+        char tmp[48];
+        memset(&item->prefix, 0, sizeof(xu_buf));
+        item->doc = xu_doc_leaf();
+        snprintf(tmp, sizeof tmp, "... (%lu more)", (unsigned long)(n - shown));
+        xu_buf_puts(&item->doc->text, tmp);
+    }
+    return d;
+}
+
+XU_FN xu_doc *xu_inspect_doc(xu_value v, int64_t depth, const xu_inspect_opts *opts, const xu_path *path) {
+    xu_doc *d;
+    xu_buf open = {NULL, 0, 0};
+    switch (v.tag) {
+    case XU_STR:
+        d = xu_doc_leaf();
+        xu_buf_quote(&d->text, XU_STR(v)->data, XU_STR(v)->len);
+        return d;
+    case XU_BUILDER:
+        d = xu_doc_leaf();
+        xu_buf_puts(&d->text, "builder(");
+        xu_buf_quote(&d->text, XU_BUILDER(v)->data, XU_BUILDER(v)->len);
+        xu_buf_putc(&d->text, ')');
+        return d;
+    case XU_LIST:
+    case XU_TUPLE:
+        xu_buf_puts(&open, v.tag == XU_LIST ? "[" : "(");
+        return xu_doc_group(v, depth, opts, path, open, v.tag == XU_LIST ? "]" : ")", XU_LIST(v)->len,
+                            XU_LIST(v)->items, NULL, NULL, sizeof(xu_value));
+    case XU_DICT:
+        xu_buf_puts(&open, "{");
+        if (XU_DICT(v)->len == 0) {
+            d = xu_doc_leaf();
+            xu_buf_puts(&d->text, "{}");
+            return d;
+        }
+        return xu_doc_group(v, depth, opts, path, open, "}", XU_DICT(v)->len, &XU_DICT(v)->entries[0].val,
+                            &XU_DICT(v)->entries[0].key, NULL, sizeof(xu_entry));
+    case XU_STRUCT:
+        xu_buf_puts(&open, XU_STRUCT(v)->type->name);
+        xu_buf_puts(&open, "{");
+        return xu_doc_group(v, depth, opts, path, open, "}", (size_t)XU_STRUCT(v)->type->nfields,
+                            XU_STRUCT(v)->fields, NULL, XU_STRUCT(v)->type->fields, sizeof(xu_value));
+    case XU_ENUM:
+        xu_buf_puts(&open, XU_ENUM(v)->type->name);
+        xu_buf_putc(&open, '#');
+        xu_buf_puts(&open, XU_ENUM(v)->tag);
+        if (XU_ENUM(v)->n == 0) {
+            d = xu_doc_leaf();
+            d->text = open;
+            return d;
+        }
+        xu_buf_putc(&open, '(');
+        return xu_doc_group(v, depth, opts, path, open, ")", (size_t)XU_ENUM(v)->n, XU_ENUM(v)->payload, NULL, NULL,
+                            sizeof(xu_value));
+    default:
+        d = xu_doc_leaf();
+        xu_buf_value(&d->text, v, NULL);
+        return d;
+    }
+}
+
+/* The one-line form without limits, also used for dict keys. */
+XU_FN void xu_buf_inspect(xu_buf *b, xu_value v) {
+    xu_inspect_opts opts = {INT64_MAX, INT64_MAX};
+    xu_doc_flat(xu_inspect_doc(v, 0, &opts, NULL), b);
+}
+
+/* ------------------------------------------------------------------------
+ * Equality, hashing and dicts
+ */
+
+typedef struct xu_pair_path {
+    const void *a, *b;
+    const struct xu_pair_path *up;
+} xu_pair_path;
+
+/* Deep equality; with `keys`, ints and floats are never equal and NaN
+ * equals itself, as dict keys need. */
+XU_FN int xu_eq_at(xu_value a, xu_value b, int keys, const xu_pair_path *path) {
+    xu_pair_path here;
+    const xu_pair_path *p;
+    size_t i;
+    int j;
+    if (a.tag == XU_INT && b.tag == XU_FLOAT) return !keys && (double)a.as.i == b.as.f;
+    if (a.tag == XU_FLOAT && b.tag == XU_INT) return !keys && a.as.f == (double)b.as.i;
+    if (a.tag != b.tag) return 0;
+    switch (a.tag) {
+    case XU_UNIT: return 1;
+    case XU_BOOL: return a.as.b == b.as.b;
+    case XU_INT: return a.as.i == b.as.i;
+    case XU_FLOAT: return a.as.f == b.as.f || (keys && a.as.f != a.as.f && b.as.f != b.as.f);
+    case XU_STR:
+        return XU_STR(a)->len == XU_STR(b)->len && memcmp(XU_STR(a)->data, XU_STR(b)->data, XU_STR(a)->len) == 0;
+    case XU_FUNC:
+    case XU_BUILDER: return a.as.p == b.as.p;
+    case XU_RANGE:
+        return XU_RANGE(a)->start == XU_RANGE(b)->start && XU_RANGE(a)->end == XU_RANGE(b)->end &&
+               XU_RANGE(a)->inclusive == XU_RANGE(b)->inclusive;
+    default: break;
+    }
+    if (a.as.p == b.as.p) return 1;
+    for (p = path; p; p = p->up) {
+        if (p->a == a.as.p && p->b == b.as.p) return 1;
+    }
+    here.a = a.as.p;
+    here.b = b.as.p;
+    here.up = path;
+    switch (a.tag) {
+    case XU_LIST:
+    case XU_TUPLE:
+        if (XU_LIST(a)->len != XU_LIST(b)->len) return 0;
+        for (i = 0; i < XU_LIST(a)->len; i++) {
+            if (!xu_eq_at(XU_LIST(a)->items[i], XU_LIST(b)->items[i], keys, &here)) return 0;
+        }
+        return 1;
+    case XU_DICT: {
+        const xu_dict *da = XU_DICT(a), *db = XU_DICT(b);
+        if (da->len != db->len) return 0;
+        for (i = 0; i < da->len; i++) {
+            size_t k;
+            int found = 0;
+            for (k = 0; k < db->len && !found; k++) {
+                if (xu_eq_at(da->entries[i].key, db->entries[k].key, 1, NULL)) {
+                    if (!xu_eq_at(da->entries[i].val, db->entries[k].val, keys, &here)) return 0;
+                    found = 1;
+                }
+            }
+            if (!found) return 0;
+        }
+        return 1;
+    }
+    case XU_STRUCT:
+        if (XU_STRUCT(a)->type != XU_STRUCT(b)->type) return 0;
+        for (j = 0; j < XU_STRUCT(a)->type->nfields; j++) {
+            if (!xu_eq_at(XU_STRUCT(a)->fields[j], XU_STRUCT(b)->fields[j], keys, &here)) return 0;
+        }
+        return 1;
+    case XU_ENUM:
+        /* Like the interpreter, two `Option#some` values are never equal. */
+        if (strcmp(XU_ENUM(a)->type->name, XU_ENUM(b)->type->name) != 0) return 0;
+        if ((!keys && XU_ENUM(a)->type == &xu_option_type) || strcmp(XU_ENUM(a)->tag, XU_ENUM(b)->tag) != 0 ||
+            XU_ENUM(a)->n != XU_ENUM(b)->n) {
+            return 0;
+        }
+        for (j = 0; j < XU_ENUM(a)->n; j++) {
+            if (!xu_eq_at(XU_ENUM(a)->payload[j], XU_ENUM(b)->payload[j], keys, &here)) return 0;
+        }
+        return 1;
+    default: return 0;
+    }
+}
+
+XU_FN int xu_equal(xu_value a, xu_value b) { return xu_eq_at(a, b, 0, NULL); }
+
+XU_FN uint64_t xu_hash_bytes(uint64_t h, const void *p, size_t n) {
+    const unsigned char *s = (const unsigned char *)p;
+    size_t i;
+    for (i = 0; i < n; i++) {
+        h ^= s[i];
+        h *= 1099511628211ULL;
+    }
+    return h;
+}
+
+XU_FN uint64_t xu_hash_at(uint64_t h, xu_value v, int depth) {
+    size_t i;
+    int j;
+    unsigned char tag = (unsigned char)v.tag;
+    h = xu_hash_bytes(h, &tag, 1);
+    if (depth > 32) return h;
+    switch (v.tag) {
+    case XU_UNIT: return h;
+    case XU_BOOL: return xu_hash_bytes(h, &v.as.b, sizeof v.as.b);
+    case XU_INT: return xu_hash_bytes(h, &v.as.i, sizeof v.as.i);
+    case XU_FLOAT: {
+        double f = v.as.f;
+        if (f != f) return xu_hash_bytes(h, "NaN", 3);
+        if (f == 0) f = 0.0;
+        return xu_hash_bytes(h, &f, sizeof f);
+    }
+    case XU_STR: return xu_hash_bytes(h, XU_STR(v)->data, XU_STR(v)->len);
+    case XU_RANGE: return xu_hash_bytes(h, XU_RANGE(v), sizeof(xu_range));
+    case XU_LIST:
+    case XU_TUPLE:
+        for (i = 0; i < XU_LIST(v)->len; i++) h = xu_hash_at(h, XU_LIST(v)->items[i], depth + 1);
+        return h;
+    case XU_STRUCT:
+        h = xu_hash_bytes(h, XU_STRUCT(v)->type->name, strlen(XU_STRUCT(v)->type->name));
+        for (j = 0; j < XU_STRUCT(v)->type->nfields; j++) h = xu_hash_at(h, XU_STRUCT(v)->fields[j], depth + 1);
+        return h;
+    case XU_ENUM:
+        h = xu_hash_bytes(h, XU_ENUM(v)->tag, strlen(XU_ENUM(v)->tag));
+        for (j = 0; j < XU_ENUM(v)->n; j++) h = xu_hash_at(h, XU_ENUM(v)->payload[j], depth + 1);
+        return h;
+    default: return xu_hash_bytes(h, &v.as.p, sizeof v.as.p);
+    }
+}
+
+XU_FN uint64_t xu_hash(xu_value v) { return xu_hash_at(14695981039346656037ULL, v, 0); }
+
+XU_FN xu_value xu_dict_new(void) {
+    xu_dict *d = (xu_dict *)xu_alloc(sizeof(xu_dict));
+    memset(d, 0, sizeof(xu_dict));
+    return xu_obj(XU_DICT, d);
+}
+
+XU_FN void xu_dict_reindex(xu_dict *d, size_t slots) {
+    size_t i;
+    d->slots = slots;
+    d->index = (int32_t *)xu_alloc(slots * sizeof(int32_t));
+    for (i = 0; i < slots; i++) d->index[i] = -1;
+    for (i = 0; i < d->len; i++) {
+        size_t s = (size_t)d->entries[i].hash & (slots - 1);
+        while (d->index[s] >= 0) s = (s + 1) & (slots - 1);
+        d->index[s] = (int32_t)i;
+    }
+}
+
+/* Slot of `key` in the index: the slot holding it, or the empty slot it
+ * would go in. */
+XU_FN size_t xu_dict_slot(const xu_dict *d, xu_value key, uint64_t h) {
+    size_t s = (size_t)h & (d->slots - 1);
+    while (d->index[s] >= 0) {
+        const xu_entry *e = &d->entries[d->index[s]];
+        if (e->hash == h && xu_eq_at(e->key, key, 1, NULL)) break;
+        s = (s + 1) & (d->slots - 1);
+    }
+    return s;
+}
+
+XU_FN xu_entry *xu_dict_find(xu_value dict, xu_value key) {
+    xu_dict *d = XU_DICT(dict);
+    size_t s;
+    if (d->len == 0) return NULL;
+    s = xu_dict_slot(d, key, xu_hash(key));
+    return d->index[s] >= 0 ? &d->entries[d->index[s]] : NULL;
+}
+
+/* Struct keys are copied so later changes to the struct leave the key
+ * alone. */
+XU_FN xu_value xu_freeze(xu_value k) {
+    xu_value copy;
+    int i;
+    if (k.tag != XU_STRUCT) return k;
+    copy = xu_struct_new(XU_STRUCT(k)->type);
+    for (i = 0; i < XU_STRUCT(k)->type->nfields; i++) XU_STRUCT(copy)->fields[i] = xu_freeze(XU_STRUCT(k)->fields[i]);
+    return copy;
+}
+
+XU_FN void xu_dict_set(xu_value dict, xu_value key, xu_value val) {
+    xu_dict *d = XU_DICT(dict);
+    uint64_t h = xu_hash(key);
+    size_t s;
+    if ((d->len + 1) * 2 > d->slots) xu_dict_reindex(d, d->slots ? d->slots * 2 : 8);
+    s = xu_dict_slot(d, key, h);
+    if (d->index[s] >= 0) {
+        d->entries[d->index[s]].val = val;
+        return;
+    }
+    if (d->len == d->cap) {
+        size_t cap = d->cap ? d->cap * 2 : 4;
+        d->entries = (xu_entry *)xu_grow(d->entries, d->len * sizeof(xu_entry), cap * sizeof(xu_entry));
+        d->cap = cap;
+    }
+    d->entries[d->len].key = xu_freeze(key);
+    d->entries[d->len].val = val;
+    d->entries[d->len].hash = h;
+    d->index[s] = (int32_t)d->len;
+    d->len++;
+}
+
+/* Removes `key`, keeping the order of the other entries. */
+XU_FN int xu_dict_take(xu_value dict, xu_value key, xu_value *out) {
+    xu_dict *d = XU_DICT(dict);
+    xu_entry *e = xu_dict_find(dict, key);
+    size_t i;
+    if (!e) return 0;
+    i = (size_t)(e - d->entries);
+    if (out) *out = e->val;
+    memmove(&d->entries[i], &d->entries[i + 1], (d->len - i - 1) * sizeof(xu_entry));
+    d->len--;
+    xu_dict_reindex(d, d->slots);
+    return 1;
+}
+
+/* A dict literal from `n` keys and values, alternating. */
+XU_FN xu_value xu_dict_of(int n, const xu_value *kv) {
+    xu_value d = xu_dict_new();
+    int i;
+    for (i = 0; i + 1 < 2 * n; i += 2) xu_dict_set(d, kv[i], kv[i + 1]);
+    return d;
+}
+
+/* ------------------------------------------------------------------------
+ * Operators
+ */
+
+XU_FN xu_value xu_add(xu_value a, xu_value b) {
+    if (a.tag == XU_STR || b.tag == XU_STR) {
+        xu_buf buf = {NULL, 0, 0};
+        xu_buf_value(&buf, a, NULL);
+        xu_buf_value(&buf, b, NULL);
+        return xu_buf_str(&buf);
+    }
+    if (a.tag == XU_INT && b.tag == XU_INT) return xu_int((int64_t)((uint64_t)a.as.i + (uint64_t)b.as.i));
+    if ((a.tag == XU_INT || a.tag == XU_FLOAT) && (b.tag == XU_INT || b.tag == XU_FLOAT)) {
+        return xu_float(xu_as_num(a, "+") + xu_as_num(b, "+"));
+    }
+    xu_fail("Cannot add %s and %s", xu_type_name(a), xu_type_name(b));
+}
+
+XU_FN xu_value xu_sub(xu_value a, xu_value b) {
+    if (a.tag == XU_INT && b.tag == XU_INT) return xu_int((int64_t)((uint64_t)a.as.i - (uint64_t)b.as.i));
+    return xu_float(xu_as_num(a, "-") - xu_as_num(b, "-"));
+}
+
+XU_FN xu_value xu_mul(xu_value a, xu_value b) {
+    if (a.tag == XU_INT && b.tag == XU_INT) return xu_int((int64_t)((uint64_t)a.as.i * (uint64_t)b.as.i));
+    return xu_float(xu_as_num(a, "*") * xu_as_num(b, "*"));
+}
+
+/* Integer division and remainder truncate toward zero. */
+XU_FN xu_value xu_div(xu_value a, xu_value b) {
+    if (a.tag == XU_INT && b.tag == XU_INT) {
+        if (b.as.i == 0) xu_fail("Division by zero");
+        if (b.as.i == -1) return xu_int((int64_t)(0 - (uint64_t)a.as.i));
+        return xu_int(a.as.i / b.as.i);
+    }
+    if (xu_as_num(b, "/") == 0) xu_fail("Division by zero");
+    return xu_float(xu_as_num(a, "/") / xu_as_num(b, "/"));
+}
+
+XU_FN xu_value xu_mod(xu_value a, xu_value b) {
+    if (a.tag == XU_INT && b.tag == XU_INT) {
+        if (b.as.i == 0) xu_fail("Division by zero");
+        if (b.as.i == -1) return xu_int(0);
+        return xu_int(a.as.i % b.as.i);
+    }
+    if (xu_as_num(b, "%") == 0) xu_fail("Division by zero");
+    return xu_float(fmod(xu_as_num(a, "%"), xu_as_num(b, "%")));
+}
+
+/* `**` stays an int for int operands and a non-negative exponent. */
+XU_FN xu_value xu_power(xu_value a, xu_value b) {
+    if (a.tag == XU_INT && b.tag == XU_INT && b.as.i >= 0) {
+        uint64_t r = 1, x = (uint64_t)a.as.i;
+        int64_t e = b.as.i;
+        while (e > 0) {
+            if (e & 1) r *= x;
+            x *= x;
+            e >>= 1;
+        }
+        return xu_int((int64_t)r);
+    }
+    return xu_float(pow(xu_as_num(a, "**"), xu_as_num(b, "**")));
+}
+
+XU_FN void xu_ints(const char *op, xu_value a, xu_value b) {
+    if (a.tag != XU_INT || b.tag != XU_INT) {
+        xu_fail("Bitwise operator '%s' requires int operands, got %s and %s", op, xu_type_name(a),
+                xu_type_name(b));
+    }
+}
+
+XU_FN void xu_shift_amount(int64_t b) {
+    if (b < 0 || b > 63) xu_fail("Shift amount must be from 0 to 63, got %" PRId64, b);
+}
+
+XU_FN xu_value xu_band(xu_value a, xu_value b) {
+    xu_ints("&", a, b);
+    return xu_int(a.as.i & b.as.i);
+}
+
+XU_FN xu_value xu_bor(xu_value a, xu_value b) {
+    xu_ints("|", a, b);
+    return xu_int(a.as.i | b.as.i);
+}
+
+XU_FN xu_value xu_bxor(xu_value a, xu_value b) {
+    xu_ints("^", a, b);
+    return xu_int(a.as.i ^ b.as.i);
+}
+
+XU_FN xu_value xu_shl(xu_value a, xu_value b) {
+    xu_ints("<<", a, b);
+    xu_shift_amount(b.as.i);
+    return xu_int((int64_t)((uint64_t)a.as.i << b.as.i));
+}
+
+/* Right shifts keep the sign, which C leaves to the implementation. */
+XU_FN xu_value xu_shr(xu_value a, xu_value b) {
+    xu_ints(">>", a, b);
+    xu_shift_amount(b.as.i);
+    if (a.as.i >= 0) return xu_int(a.as.i >> b.as.i);
+    return xu_int(~(~a.as.i >> b.as.i));
+}
+
+XU_FN xu_value xu_bnot(xu_value a) {
+    if (a.tag != XU_INT) xu_fail("Unary operator '~' expects int type");
+    return xu_int(~a.as.i);
+}
+
+XU_FN xu_value xu_neg(xu_value a) {
+    if (a.tag == XU_INT) return xu_int((int64_t)(0 - (uint64_t)a.as.i));
+    return xu_float(-xu_as_num(a, "-"));
+}
+
+XU_FN xu_value xu_not(xu_value a) { return xu_bool(!xu_truthy(a)); }
+
+/* -1, 0 or 1 for numbers and strings. */
+XU_FN int xu_compare(xu_value a, xu_value b) {
+    if (a.tag == XU_INT && b.tag == XU_INT) return a.as.i < b.as.i ? -1 : a.as.i > b.as.i;
+    if ((a.tag == XU_INT || a.tag == XU_FLOAT) && (b.tag == XU_INT || b.tag == XU_FLOAT)) {
+        double x = xu_as_num(a, "<"), y = xu_as_num(b, "<");
+        return x < y ? -1 : x > y;
+    }
+    if (a.tag == XU_STR && b.tag == XU_STR) {
+        size_t n = XU_STR(a)->len < XU_STR(b)->len ? XU_STR(a)->len : XU_STR(b)->len;
+        int c = memcmp(XU_STR(a)->data, XU_STR(b)->data, n);
+        if (c) return c < 0 ? -1 : 1;
+        return XU_STR(a)->len < XU_STR(b)->len ? -1 : XU_STR(a)->len > XU_STR(b)->len;
+    }
+    xu_fail("Cannot compare %s and %s", xu_type_name(a), xu_type_name(b));
+}
+
+XU_FN xu_value xu_lt(xu_value a, xu_value b) { return xu_bool(xu_compare(a, b) < 0); }
+XU_FN xu_value xu_le(xu_value a, xu_value b) {
+    if (a.tag == XU_FLOAT && a.as.f != a.as.f) return xu_bool(0);
+    return xu_bool(xu_compare(a, b) <= 0);
+}
+XU_FN xu_value xu_gt(xu_value a, xu_value b) { return xu_bool(xu_compare(a, b) > 0); }
+XU_FN xu_value xu_ge(xu_value a, xu_value b) {
+    if (a.tag == XU_FLOAT && a.as.f != a.as.f) return xu_bool(0);
+    return xu_bool(xu_compare(a, b) >= 0);
+}
+XU_FN xu_value xu_eq(xu_value a, xu_value b) { return xu_bool(xu_equal(a, b)); }
+XU_FN xu_value xu_ne(xu_value a, xu_value b) { return xu_bool(!xu_equal(a, b)); }
+
+/* `x in a..b`: ints, and integral floats, within the bounds. */
+XU_FN int xu_range_has(xu_value x, int64_t a, int64_t b, int inclusive) {
+    if (x.tag == XU_INT) return x.as.i >= a && (inclusive ? x.as.i <= b : x.as.i < b);
+    if (x.tag != XU_FLOAT || x.as.f != floor(x.as.f)) return 0;
+    return x.as.f >= (double)a && (inclusive ? x.as.f <= (double)b : x.as.f < (double)b);
+}
+
+XU_FN xu_value xu_in_range(xu_value x, xu_value a, xu_value b, int inclusive) {
+    if (a.tag != XU_INT || b.tag != XU_INT) xu_fail("Range bounds must be int");
+    return xu_bool(xu_range_has(x, a.as.i, b.as.i, inclusive));
+}
+
+/* ------------------------------------------------------------------------
+ * Calls
+ */
+
+XU_FN xu_value xu_call(xu_value f, int argc, xu_value *argv) {
+    if (f.tag != XU_FUNC) xu_fail("Not callable: %s", xu_type_name(f));
+    return XU_FUNC(f)->fn(XU_FUNC(f), argc, argv);
+}
+
+XU_FN xu_value xu_call1(xu_value f, xu_value a) { return xu_call(f, 1, &a); }
+
+XU_FN xu_value xu_call2(xu_value f, xu_value a, xu_value b) {
+    xu_value args[2];
+    args[0] = a;
+    args[1] = b;
+    return xu_call(f, 2, args);
+}
+
+/* Calls `f` for a condition, which has to be a bool. */
+XU_FN int xu_test(xu_value f, xu_value a) {
+    xu_value r = xu_call1(f, a);
+    if (r.tag != XU_BOOL) xu_fail("Condition must be bool, got %s", xu_type_name(r));
+    return r.as.b;
+}
+
+#define XU_ARG(i) ((i) < argc ? argv[i] : xu_unit())
+
+/* ------------------------------------------------------------------------
+ * Indexing and members
+ */
+
+XU_FN size_t xu_list_index(const xu_list *l, xu_value i) {
+    if (i.tag != XU_INT || i.as.i < 0 || (uint64_t)i.as.i >= l->len) xu_fail("Index out of range");
+    return (size_t)i.as.i;
+}
+
+XU_FN void xu_slice_bounds(const xu_range *r, size_t n, size_t *a, size_t *b) {
+    int64_t s = r->start, e = r->inclusive ? r->end + 1 : r->end;
+    if (s < 0) s = 0;
+    if (e > (int64_t)n) e = (int64_t)n;
+    if (e < s) e = s;
+    *a = (size_t)s;
+    *b = (size_t)e;
+}
+
+XU_FN xu_value xu_str_index(const xu_string *s, xu_value i) {
+    size_t p, q;
+    if (i.tag == XU_RANGE) {
+        size_t a, b;
+        xu_slice_bounds(XU_RANGE(i), xu_utf8_len(s->data, s->len), &a, &b);
+        p = xu_utf8_offset(s->data, s->len, a);
+        q = xu_utf8_offset(s->data, s->len, b);
+        return xu_str_new(s->data + p, q - p);
+    }
+    if (i.tag != XU_INT || i.as.i < 0) return xu_unit();
+    p = xu_utf8_offset(s->data, s->len, (size_t)i.as.i);
+    if (p >= s->len) return xu_unit();
+    q = xu_utf8_offset(s->data, s->len, (size_t)i.as.i + 1);
+    return xu_str_new(s->data + p, q - p);
+}
+
+XU_FN xu_value xu_index(xu_value o, xu_value i) {
+    xu_entry *e;
+    switch (o.tag) {
+    case XU_LIST:
+        if (i.tag == XU_RANGE) {
+            size_t a, b;
+            xu_slice_bounds(XU_RANGE(i), XU_LIST(o)->len, &a, &b);
+            return xu_list_of((int)(b - a), XU_LIST(o)->items + a);
+        }
+        return XU_LIST(o)->items[xu_list_index(XU_LIST(o), i)];
+    case XU_TUPLE: return XU_LIST(o)->items[xu_list_index(XU_LIST(o), i)];
+    case XU_DICT:
+        e = xu_dict_find(o, i);
+        if (!e) {
+            xu_value k = xu_to_str(i);
+            xu_fail("Key not found: %s", XU_STR(k)->data);
+        }
+        return e->val;
+    case XU_STR: {
+        xu_value c = xu_str_index(XU_STR(o), i);
+        if (c.tag == XU_UNIT) xu_fail("Index out of range");
+        return c;
+    }
+    default: xu_fail("Cannot index %s", xu_type_name(o));
+    }
+}
+
+XU_FN void xu_setindex(xu_value o, xu_value i, xu_value v) {
+    if (o.tag == XU_LIST) XU_LIST(o)->items[xu_list_index(XU_LIST(o), i)] = v;
+    else if (o.tag == XU_DICT) xu_dict_set(o, i, v);
+    else xu_fail("Cannot assign to an index of %s", xu_type_name(o));
+}
+
+XU_FN xu_value xu_prop(xu_value o, const char *f) {
+    xu_entry *e;
+    int i;
+    switch (o.tag) {
+    case XU_STRUCT:
+        i = xu_field_index(XU_STRUCT(o)->type, f);
+        if (i < 0) xu_fail("Unknown member: %s", f);
+        return XU_STRUCT(o)->fields[i];
+    case XU_ENUM:
+        if (strcmp(f, "name") == 0) return xu_cstr(XU_ENUM(o)->tag);
+        if (strcmp(f, "type_name") == 0) return xu_cstr(XU_ENUM(o)->type->name);
+        if (XU_ENUM(o)->type == &xu_option_type && strcmp(f, "has") == 0) {
+            return xu_bool(strcmp(XU_ENUM(o)->tag, "some") == 0);
+        }
+        if (XU_ENUM(o)->type == &xu_option_type && strcmp(f, "none") == 0) {
+            return xu_bool(strcmp(XU_ENUM(o)->tag, "none") == 0);
+        }
+        break;
+    case XU_TUPLE: {
+        char *end;
+        long n;
+        if (strcmp(f, "length") == 0) return xu_int((int64_t)XU_LIST(o)->len);
+        n = strtol(f, &end, 10);
+        if (*f && !*end) return XU_LIST(o)->items[xu_list_index(XU_LIST(o), xu_int(n))];
+        break;
+    }
+    case XU_LIST:
+        if (strcmp(f, "length") == 0) return xu_int((int64_t)XU_LIST(o)->len);
+        break;
+    case XU_DICT:
+        if (strcmp(f, "length") == 0) return xu_int((int64_t)XU_DICT(o)->len);
+        e = xu_dict_find(o, xu_cstr(f));
+        if (!e) xu_fail("Key not found: %s", f);
+        return e->val;
+    case XU_STR:
+        if (strcmp(f, "length") == 0) return xu_int((int64_t)xu_utf8_len(XU_STR(o)->data, XU_STR(o)->len));
+        break;
+    default: break;
+    }
+    xu_fail("Cannot access member %s of %s", f, xu_type_name(o));
+}
+
+XU_FN void xu_setprop(xu_value o, const char *f, xu_value v) {
+    if (o.tag == XU_STRUCT) {
+        int i = xu_field_index(XU_STRUCT(o)->type, f);
+        if (i < 0) xu_fail("Unknown member: %s", f);
+        XU_STRUCT(o)->fields[i] = v;
+    } else if (o.tag == XU_DICT) {
+        xu_dict_set(o, xu_cstr(f), v);
+    } else {
+        xu_fail("Cannot assign to member %s of %s", f, xu_type_name(o));
+    }
+}
+
+/* ------------------------------------------------------------------------
+ * Iteration: lists and dicts are iterated as they were when the loop
+ * started; with `kv` dicts yield their entries as tuples.
+ */
+
+typedef struct {
+    xu_value v;
+    size_t i, n;
+    xu_value *items;
+    xu_entry *entries;
+    int64_t cur, end;
+    int done, kv;
+} xu_iter;
+
+XU_FN void xu_iter_init(xu_iter *it, xu_value v, int kv) {
+    memset(it, 0, sizeof *it);
+    it->v = v;
+    it->kv = kv;
+    switch (v.tag) {
+    case XU_LIST:
+        it->n = XU_LIST(v)->len;
+        it->items = (xu_value *)xu_alloc((it->n ? it->n : 1) * sizeof(xu_value));
+        if (it->n) memcpy(it->items, XU_LIST(v)->items, it->n * sizeof(xu_value));
+        break;
+    case XU_DICT:
+        it->n = XU_DICT(v)->len;
+        it->entries = (xu_entry *)xu_alloc((it->n ? it->n : 1) * sizeof(xu_entry));
+        if (it->n) memcpy(it->entries, XU_DICT(v)->entries, it->n * sizeof(xu_entry));
+        break;
+    case XU_RANGE:
+        it->cur = XU_RANGE(v)->start;
+        it->end = XU_RANGE(v)->end;
+        break;
+    default: xu_fail("Cannot iterate over %s (expected list, range or dict)", xu_type_name(v));
+    }
+}
+
+XU_FN int xu_iter_next(xu_iter *it, xu_value *out) {
+    if (it->v.tag == XU_RANGE) {
+        int64_t x = it->cur;
+        if (it->done || (!XU_RANGE(it->v)->inclusive && x == it->end)) return 0;
+        if (x == it->end) it->done = 1;
+        else it->cur = x < it->end ? x + 1 : x - 1;
+        *out = xu_int(x);
+        return 1;
+    }
+    if (it->i >= it->n) return 0;
+    if (it->v.tag == XU_LIST) {
+        *out = it->items[it->i++];
+    } else {
+        xu_entry *e = &it->entries[it->i++];
+        *out = it->kv ? xu_pair(e->key, e->val) : e->key;
+    }
+    return 1;
+}
+
+/* ------------------------------------------------------------------------
+ * Methods
+ */
+
+XU_FN xu_value xu_opt(int found, xu_value v) { return found ? xu_some(v) : xu_none(); }
+
+/* Default sort order: numbers by value, strings by bytes, false before
+ * true; anything else keeps its place. */
+XU_FN int xu_default_after(xu_value a, xu_value b) {
+    int an = a.tag == XU_INT || a.tag == XU_FLOAT, bn = b.tag == XU_INT || b.tag == XU_FLOAT;
+    if ((an && bn) || (a.tag == XU_STR && b.tag == XU_STR)) return xu_compare(b, a) < 0;
+    if (a.tag == XU_BOOL && b.tag == XU_BOOL) return a.as.b && !b.as.b;
+    return 0;
+}
+
+typedef struct {
+    xu_value cmp;
+    int by_key;
+} xu_sort_by;
+
+XU_FN int xu_after(const xu_sort_by *by, xu_value a, xu_value b) {
+    xu_value r;
+    if (by->by_key) return xu_default_after(XU_LIST(a)->items[0], XU_LIST(b)->items[0]);
+    if (by->cmp.tag == XU_UNIT) return xu_default_after(a, b);
+    r = xu_call2(by->cmp, b, a);
+    if (r.tag == XU_INT) return r.as.i < 0;
+    if (r.tag == XU_BOOL) return r.as.b;
+    xu_fail("sort comparator must return int or bool, got %s", xu_type_name(r));
+}
+
+/* Stable bottom-up merge sort. */
+XU_FN void xu_sort_items(xu_value *items, size_t n, const xu_sort_by *by) {
+    xu_value *src = items, *dst = (xu_value *)xu_alloc((n ? n : 1) * sizeof(xu_value)), *t;
+    size_t width, start;
+    for (width = 1; width < n; width *= 2) {
+        size_t k = 0;
+        for (start = 0; start < n; start += 2 * width) {
+            size_t mid = start + width < n ? start + width : n;
+            size_t stop = start + 2 * width < n ? start + 2 * width : n;
+            size_t i = start, j = mid;
+            while (i < mid && j < stop) dst[k++] = xu_after(by, src[i], src[j]) ? src[j++] : src[i++];
+            while (i < mid) dst[k++] = src[i++];
+            while (j < stop) dst[k++] = src[j++];
+        }
+        t = src;
+        src = dst;
+        dst = t;
+    }
+    if (src != items) memcpy(items, src, n * sizeof(xu_value));
+}
+
+XU_FN xu_value xu_list_method(xu_value self, const char *m, int argc, xu_value *argv) {
+    xu_list *l = XU_LIST(self);
+    xu_value out;
+    size_t i;
+#define M(name) (strcmp(m, name) == 0)
+    if (M("length")) return xu_int((int64_t)l->len);
+    if (M("get") || M("get_int")) {
+        xu_value i = XU_ARG(0);
+        if (i.tag != XU_INT || i.as.i < 0 || (uint64_t)i.as.i >= l->len) return xu_none();
+        return xu_some(l->items[i.as.i]);
+    }
+    if (M("first")) return xu_opt(l->len > 0, l->len ? l->items[0] : xu_unit());
+    if (M("push")) {
+        xu_list_push(self, XU_ARG(0));
+        return xu_unit();
+    }
+    if (M("pop")) return l->len ? l->items[--l->len] : xu_unit();
+    if (M("remove")) {
+        size_t j = xu_list_index(l, XU_ARG(0));
+        out = l->items[j];
+        memmove(&l->items[j], &l->items[j + 1], (l->len - j - 1) * sizeof(xu_value));
+        l->len--;
+        return out;
+    }
+    if (M("insert")) {
+        xu_value at = XU_ARG(0);
+        size_t j;
+        if (at.tag != XU_INT || at.as.i < 0 || (uint64_t)at.as.i > l->len) xu_fail("Index out of range");
+        j = (size_t)at.as.i;
+        xu_list_push(self, xu_unit());
+        memmove(&l->items[j + 1], &l->items[j], (l->len - j - 1) * sizeof(xu_value));
+        l->items[j] = XU_ARG(1);
+        return xu_unit();
+    }
+    if (M("clear")) {
+        l->len = 0;
+        return xu_unit();
+    }
+    if (M("reverse")) {
+        for (i = 0; i < l->len / 2; i++) {
+            xu_value t = l->items[i];
+            l->items[i] = l->items[l->len - 1 - i];
+            l->items[l->len - 1 - i] = t;
+        }
+        return xu_unit();
+    }
+    if (M("reserve")) return xu_unit();
+    if (M("contains")) {
+        for (i = 0; i < l->len; i++) {
+            if (xu_equal(l->items[i], XU_ARG(0))) return xu_bool(1);
+        }
+        return xu_bool(0);
+    }
+    if (M("join")) {
+        xu_buf b = {NULL, 0, 0};
+        const xu_string *sep = xu_as_str(XU_ARG(0), "join");
+        for (i = 0; i < l->len; i++) {
+            if (i) xu_buf_put(&b, sep->data, sep->len);
+            xu_buf_value(&b, l->items[i], NULL);
+        }
+        return xu_buf_str(&b);
+    }
+    if (M("map")) {
+        out = xu_list_new(l->len);
+        for (i = 0; i < l->len; i++) xu_list_push(out, xu_call1(XU_ARG(0), l->items[i]));
+        return out;
+    }
+    if (M("filter")) {
+        out = xu_list_new(0);
+        for (i = 0; i < l->len; i++) {
+            if (xu_test(XU_ARG(0), l->items[i])) xu_list_push(out, l->items[i]);
+        }
+        return out;
+    }
+    if (M("each")) {
+        for (i = 0; i < l->len; i++) xu_call1(XU_ARG(0), l->items[i]);
+        return xu_unit();
+    }
+    if (M("any") || M("all")) {
+        int all = M("all");
+        for (i = 0; i < l->len; i++) {
+            if (xu_test(XU_ARG(0), l->items[i]) != all) return xu_bool(!all);
+        }
+        return xu_bool(all);
+    }
+    if (M("flat_map")) {
+        out = xu_list_new(0);
+        for (i = 0; i < l->len; i++) {
+            xu_value r = xu_call1(XU_ARG(0), l->items[i]);
+            size_t j;
+            if (r.tag != XU_LIST) {
+                xu_list_push(out, r);
+                continue;
+            }
+            for (j = 0; j < XU_LIST(r)->len; j++) xu_list_push(out, XU_LIST(r)->items[j]);
+        }
+        return out;
+    }
+    if (M("zip")) {
+        xu_list *other = xu_as_list(XU_ARG(0), "zip");
+        size_t n = l->len < other->len ? l->len : other->len;
+        out = xu_list_new(n);
+        for (i = 0; i < n; i++) xu_list_push(out, xu_pair(l->items[i], other->items[i]));
+        return out;
+    }
+    if (M("enumerate")) {
+        out = xu_list_new(l->len);
+        for (i = 0; i < l->len; i++) xu_list_push(out, xu_pair(xu_int((int64_t)i), l->items[i]));
+        return out;
+    }
+    if (M("take") || M("drop")) {
+        int64_t n = xu_as_int(XU_ARG(0), m);
+        size_t k = n < 0 ? 0 : (uint64_t)n > l->len ? l->len : (size_t)n;
+        if (M("take")) return xu_list_of((int)k, l->items);
+        return xu_list_of((int)(l->len - k), l->items + k);
+    }
+    if (M("chunk")) {
+        int64_t size = xu_as_int(XU_ARG(0), "chunk");
+        if (size <= 0) xu_fail("chunk size must be positive");
+        out = xu_list_new(0);
+        for (i = 0; i < l->len; i += (size_t)size) {
+            size_t n = l->len - i < (size_t)size ? l->len - i : (size_t)size;
+            xu_list_push(out, xu_list_of((int)n, l->items + i));
+        }
+        return out;
+    }
+    if (M("reduce")) {
+        xu_value acc = XU_ARG(1);
+        for (i = 0; i < l->len; i++) acc = xu_call2(XU_ARG(0), acc, l->items[i]);
+        return acc;
+    }
+    if (M("find") || M("find_index") || M("find_or")) {
+        for (i = 0; i < l->len; i++) {
+            if (!xu_test(XU_ARG(0), l->items[i])) continue;
+            if (M("find_index")) return xu_some(xu_int((int64_t)i));
+            return M("find") ? xu_some(l->items[i]) : l->items[i];
+        }
+        return M("find_or") ? XU_ARG(1) : xu_none();
+    }
+    if (M("repeat")) {
+        int64_t times = xu_as_int(XU_ARG(0), "repeat"), t;
+        out = xu_list_new(0);
+        for (t = 0; t < times; t++) {
+            for (i = 0; i < l->len; i++) xu_list_push(out, l->items[i]);
+        }
+        return out;
+    }
+    if (M("sort") || M("sorted")) {
+        xu_sort_by by;
+        by.cmp = XU_ARG(0);
+        by.by_key = 0;
+        out = M("sort") ? self : xu_list_of((int)l->len, l->items);
+        xu_sort_items(XU_LIST(out)->items, l->len, &by);
+        return M("sort") ? xu_unit() : out;
+    }
+    if (M("sort_by_key")) {
+        xu_sort_by by;
+        xu_value *keyed = (xu_value *)xu_alloc((l->len ? l->len : 1) * sizeof(xu_value));
+        by.cmp = xu_unit();
+        by.by_key = 1;
+        for (i = 0; i < l->len; i++) keyed[i] = xu_pair(xu_call1(XU_ARG(0), l->items[i]), l->items[i]);
+        xu_sort_items(keyed, l->len, &by);
+        for (i = 0; i < l->len; i++) l->items[i] = XU_LIST(keyed[i])->items[1];
+        return xu_unit();
+    }
+#undef M
+    xu_fail("Unknown method %s for list", m);
+}
+
+/* Bools, then ints, floats and strings, each by value; compound keys last
+ * in insertion order. */
+XU_FN int xu_key_rank(xu_value k) {
+    switch (k.tag) {
+    case XU_BOOL: return 0;
+    case XU_INT: return 1;
+    case XU_FLOAT: return 2;
+    case XU_STR: return 3;
+    default: return 4;
+    }
+}
+
+XU_FN int xu_key_after(const xu_sort_by *by, xu_value a, xu_value b) {
+    int ra = xu_key_rank(a), rb = xu_key_rank(b);
+    (void)by;
+    if (ra != rb) return ra > rb;
+    if (ra == 0) return a.as.b && !b.as.b;
+    if (ra == 4) return 0;
+    return xu_compare(b, a) < 0;
+}
+
+XU_FN xu_value xu_dict_method(xu_value self, const char *m, int argc, xu_value *argv) {
+    xu_dict *d = XU_DICT(self);
+    xu_entry *e;
+    xu_value out;
+    size_t i;
+#define M(name) (strcmp(m, name) == 0)
+    if (M("length")) return xu_int((int64_t)d->len);
+    if (M("get") || M("get_int")) {
+        e = xu_dict_find(self, XU_ARG(0));
+        return e ? xu_some(e->val) : xu_none();
+    }
+    if (M("insert") || M("insert_int")) {
+        xu_dict_set(self, XU_ARG(0), XU_ARG(1));
+        return xu_unit();
+    }
+    if (M("contains") || M("has")) return xu_bool(xu_dict_find(self, XU_ARG(0)) != NULL);
+    if (M("remove")) return xu_bool(xu_dict_take(self, XU_ARG(0), NULL));
+    if (M("pop")) {
+        if (xu_dict_take(self, XU_ARG(0), &out)) return out;
+        if (argc > 1) return argv[1];
+        out = xu_to_str(XU_ARG(0));
+        xu_fail("Key not found: %s", XU_STR(out)->data);
+    }
+    if (M("clear")) {
+        d->len = 0;
+        if (d->slots) xu_dict_reindex(d, d->slots);
+        return xu_unit();
+    }
+    if (M("keys") || M("values") || M("items")) {
+        out = xu_list_new(d->len);
+        for (i = 0; i < d->len; i++) {
+            xu_entry *en = &d->entries[i];
+            xu_list_push(out, M("keys") ? en->key : M("values") ? en->val : xu_pair(en->key, en->val));
+        }
+        return out;
+    }
+    if (M("merge") || M("update")) {
+        xu_value other = XU_ARG(0);
+        if (other.tag != XU_DICT) xu_fail("%s expects a dict, got %s", m, xu_type_name(other));
+        for (i = 0; i < XU_DICT(other)->len; i++) {
+            xu_dict_set(self, XU_DICT(other)->entries[i].key, XU_DICT(other)->entries[i].val);
+        }
+        return xu_unit();
+    }
+    if (M("get_or_default")) {
+        e = xu_dict_find(self, XU_ARG(0));
+        return e ? e->val : XU_ARG(1);
+    }
+    if (M("entry_or_insert")) {
+        e = xu_dict_find(self, XU_ARG(0));
+        if (e) return e->val;
+        out = xu_call(XU_ARG(1), 0, NULL);
+        xu_dict_set(self, XU_ARG(0), out);
+        return out;
+    }
+    if (M("keys_sorted")) {
+        xu_value *src, *dst, *t;
+        size_t width, start, n = d->len;
+        out = xu_list_new(n);
+        for (i = 0; i < n; i++) xu_list_push(out, d->entries[i].key);
+        src = XU_LIST(out)->items;
+        dst = (xu_value *)xu_alloc((n ? n : 1) * sizeof(xu_value));
+        for (width = 1; width < n; width *= 2) {
+            size_t k = 0;
+            for (start = 0; start < n; start += 2 * width) {
+                size_t mid = start + width < n ? start + width : n;
+                size_t stop = start + 2 * width < n ? start + 2 * width : n;
+                size_t a = start, b = mid;
+                while (a < mid && b < stop) dst[k++] = xu_key_after(NULL, src[a], src[b]) ? src[b++] : src[a++];
+                while (a < mid) dst[k++] = src[a++];
+                while (b < stop) dst[k++] = src[b++];
+            }
+            t = src;
+            src = dst;
+            dst = t;
+        }
+        XU_LIST(out)->items = src;
+        return out;
+    }
+#undef M
+    xu_fail("Unknown method %s for dict", m);
+}
+
+XU_FN int xu_is_space(char c) { return c == ' ' || c == '\t' || c == '\n' || c == '\v' || c == '\f' || c == '\r'; }
+
+XU_FN xu_value xu_trim(const xu_string *s, int start, int end) {
+    size_t a = 0, b = s->len;
+    while (start && a < b && xu_is_space(s->data[a])) a++;
+    while (end && b > a && xu_is_space(s->data[b - 1])) b--;
+    return xu_str_new(s->data + a, b - a);
+}
+
+/* Byte offset of `sub` in `s` from `from`, or -1. */
+XU_FN long xu_find(const xu_string *s, const char *sub, size_t n, size_t from) {
+    size_t i;
+    if (n == 0) return (long)from;
+    for (i = from; i + n <= s->len; i++) {
+        if (memcmp(s->data + i, sub, n) == 0) return (long)i;
+    }
+    return -1;
+}
+
+/* `x in coll`: range bounds, list or tuple elements, dict keys, substrings. */
+XU_FN xu_value xu_is_in(xu_value x, xu_value coll) {
+    size_t i;
+    switch (coll.tag) {
+    case XU_RANGE:
+        return xu_bool(xu_range_has(x, XU_RANGE(coll)->start, XU_RANGE(coll)->end, XU_RANGE(coll)->inclusive));
+    case XU_LIST:
+    case XU_TUPLE:
+        for (i = 0; i < XU_LIST(coll)->len; i++) {
+            if (xu_equal(XU_LIST(coll)->items[i], x)) return xu_bool(1);
+        }
+        return xu_bool(0);
+    case XU_DICT: return xu_bool(xu_dict_find(coll, x) != NULL);
+    case XU_STR:
+        if (x.tag != XU_STR) xu_fail("'in' on a string needs a string, got %s", xu_type_name(x));
+        return xu_bool(xu_find(XU_STR(coll), XU_STR(x)->data, XU_STR(x)->len, 0) >= 0);
+    default: xu_fail("'in' expects a range, list, tuple, dict or string, got %s", xu_type_name(coll));
+    }
+}
+
+XU_FN int xu_parse_int(const xu_string *s, int64_t *out) {
+    xu_value t = xu_trim(s, 1, 1);
+    const char *p = XU_STR(t)->data;
+    size_t i = 0, n = XU_STR(t)->len;
+    uint64_t v = 0;
+    int neg = 0;
+    if (i < n && (p[i] == '+' || p[i] == '-')) neg = p[i++] == '-';
+    if (i == n) return 0;
+    for (; i < n; i++) {
+        if (p[i] < '0' || p[i] > '9') return 0;
+        if (v > (UINT64_MAX - 9) / 10) return 0;
+        v = v * 10 + (uint64_t)(p[i] - '0');
+    }
+    if (v > (uint64_t)INT64_MAX + (uint64_t)neg) return 0;
+    *out = neg ? (int64_t)(0 - v) : (int64_t)v;
+    return 1;
+}
+
+XU_FN int xu_parse_float(const xu_string *s, double *out) {
+    xu_value t = xu_trim(s, 1, 1);
+    const char *p = XU_STR(t)->data, *q;
+    size_t i, n = XU_STR(t)->len;
+    int digit = 0;
+    char *end;
+    q = p + (n && (p[0] == '+' || p[0] == '-'));
+    for (i = 0; i < n; i++) {
+        char c = (char)(p[i] | 0x20);
+        if (p[i] >= '0' && p[i] <= '9') digit = 1;
+        else if (!(c == 'e' || p[i] == '.' || p[i] == '+' || p[i] == '-')) break;
+    }
+    if (i < n) {
+        char low[16];
+        size_t k, m = (size_t)(p + n - q);
+        if (m >= sizeof low) return 0;
+        for (k = 0; k < m; k++) low[k] = (char)(q[k] | 0x20);
+        low[m] = 0;
+        if (strcmp(low, "inf") == 0 || strcmp(low, "infinity") == 0) *out = p[0] == '-' ? -HUGE_VAL : HUGE_VAL;
+        else if (strcmp(low, "nan") == 0) *out = NAN;
+        else return 0;
+        return 1;
+    }
+    if (!digit) return 0;
+    *out = strtod(p, &end);
+    return end == p + n;
+}
+
+XU_FN xu_value xu_str_method(xu_value self, const char *m, int argc, xu_value *argv) {
+    const xu_string *s = XU_STR(self);
+    xu_value out;
+    size_t i;
+#define M(name) (strcmp(m, name) == 0)
+    if (M("length") || M("char_length")) return xu_int((int64_t)xu_utf8_len(s->data, s->len));
+    if (M("byte_length")) return xu_int((int64_t)s->len);
+    if (M("get") || M("get_int")) {
+        out = xu_str_index(s, XU_ARG(0));
+        return out.tag == XU_UNIT ? xu_none() : xu_some(out);
+    }
+    if (M("split") || M("split_lazy")) {
+        const xu_string *sep = xu_as_str(XU_ARG(0), "split");
+        size_t start = 0;
+        long p;
+        out = xu_list_new(0);
+        if (sep->len == 0) {
+            xu_list_push(out, xu_str_new("", 0));
+            for (i = 0; i < s->len;) {
+                size_t j = i + 1;
+                while (j < s->len && ((unsigned char)s->data[j] & 0xC0) == 0x80) j++;
+                xu_list_push(out, xu_str_new(s->data + i, j - i));
+                i = j;
+            }
+            xu_list_push(out, xu_str_new("", 0));
+            return out;
+        }
+        while ((p = xu_find(s, sep->data, sep->len, start)) >= 0) {
+            xu_list_push(out, xu_str_new(s->data + start, (size_t)p - start));
+            start = (size_t)p + sep->len;
+        }
+        xu_list_push(out, xu_str_new(s->data + start, s->len - start));
+        return out;
+    }
+    if (M("chars") || M("graphemes") || M("code_points")) {
+        out = xu_list_new(0);
+        for (i = 0; i < s->len;) {
+            const unsigned char *c = (const unsigned char *)s->data + i;
+            size_t j = i + 1;
+            while (j < s->len && ((unsigned char)s->data[j] & 0xC0) == 0x80) j++;
+            if (M("code_points")) {
+                int64_t cp = c[0] < 0x80 ? c[0] : c[0] < 0xE0 ? c[0] & 0x1F : c[0] < 0xF0 ? c[0] & 0x0F : c[0] & 0x07;
+                size_t k;
+                for (k = 1; k < j - i; k++) cp = (cp << 6) | (c[k] & 0x3F);
+                xu_list_push(out, xu_int(cp));
+            } else {
+                xu_list_push(out, xu_str_new(s->data + i, j - i));
+            }
+            i = j;
+        }
+        return out;
+    }
+    if (M("to_int") || M("try_to_int")) {
+        int64_t v = 0;
+        int ok = xu_parse_int(s, &v);
+        if (M("try_to_int")) return xu_opt(ok, xu_int(v));
+        if (!ok) xu_fail("Invalid integer: %s", s->data);
+        return xu_int(v);
+    }
+    if (M("to_float") || M("try_to_float")) {
+        double v = 0;
+        int ok = xu_parse_float(s, &v);
+        if (M("try_to_float")) return xu_opt(ok, xu_float(v));
+        if (!ok) xu_fail("Invalid float: %s", s->data);
+        return xu_float(v);
+    }
+    if (M("contains") || M("starts_with") || M("ends_with") || M("find")) {
+        const xu_string *sub = xu_as_str(XU_ARG(0), m);
+        long p;
+        if (M("starts_with")) return xu_bool(sub->len <= s->len && memcmp(s->data, sub->data, sub->len) == 0);
+        if (M("ends_with")) {
+            return xu_bool(sub->len <= s->len && memcmp(s->data + s->len - sub->len, sub->data, sub->len) == 0);
+        }
+        p = xu_find(s, sub->data, sub->len, 0);
+        if (M("contains")) return xu_bool(p >= 0);
+        return xu_opt(p >= 0, xu_int((int64_t)xu_utf8_len(s->data, p < 0 ? 0 : (size_t)p)));
+    }
+    if (M("replace")) {
+        const xu_string *from = xu_as_str(XU_ARG(0), "replace"), *to = xu_as_str(XU_ARG(1), "replace");
+        long p = xu_find(s, from->data, from->len, 0);
+        xu_buf b = {NULL, 0, 0};
+        if (p < 0) return self;
+        xu_buf_put(&b, s->data, (size_t)p);
+        xu_buf_put(&b, to->data, to->len);
+        xu_buf_put(&b, s->data + p + from->len, s->len - (size_t)p - from->len);
+        return xu_buf_str(&b);
+    }
+    if (M("trim")) return xu_trim(s, 1, 1);
+    if (M("trim_start")) return xu_trim(s, 1, 0);
+    if (M("trim_end")) return xu_trim(s, 0, 1);
+    if (M("to_upper") || M("to_lower")) {
+        char *p;
+        out = xu_str_new(s->data, s->len);
+        p = (char *)XU_STR(out)->data;
+        for (i = 0; i < s->len; i++) {
+            if (M("to_upper") && p[i] >= 'a' && p[i] <= 'z') p[i] = (char)(p[i] - 32);
+            if (M("to_lower") && p[i] >= 'A' && p[i] <= 'Z') p[i] = (char)(p[i] + 32);
+        }
+        return out;
+    }
+    if (M("substr")) {
+        int64_t start = xu_as_int(XU_ARG(0), "substr"), n = xu_as_int(XU_ARG(1), "substr");
+        size_t p, q;
+        if (start < 0 || n <= 0) return xu_str_new("", 0);
+        p = xu_utf8_offset(s->data, s->len, (size_t)start);
+        q = xu_utf8_offset(s->data, s->len, (size_t)(start + n));
+        return xu_str_new(s->data + p, q - p);
+    }
+    if (M("format")) {
+        xu_value d = XU_ARG(0), text = self;
+        if (d.tag != XU_DICT) xu_fail("format expects a dict, got %s", xu_type_name(d));
+        for (i = 0; i < XU_DICT(d)->len; i++) {
+            xu_buf needle = {NULL, 0, 0}, b = {NULL, 0, 0};
+            xu_value repl = xu_to_str(XU_DICT(d)->entries[i].val);
+            const xu_string *t = XU_STR(text);
+            size_t start = 0;
+            long p;
+            xu_buf_putc(&needle, '{');
+            xu_buf_value(&needle, XU_DICT(d)->entries[i].key, NULL);
+            xu_buf_putc(&needle, '}');
+            while ((p = xu_find(t, needle.data, needle.len, start)) >= 0) {
+                xu_buf_put(&b, t->data + start, (size_t)p - start);
+                xu_buf_put(&b, XU_STR(repl)->data, XU_STR(repl)->len);
+                start = (size_t)p + needle.len;
+            }
+            xu_buf_put(&b, t->data + start, t->len - start);
+            text = xu_buf_str(&b);
+        }
+        return text;
+    }
+#undef M
+    xu_fail("Unknown method %s for string", m);
+}
+
+/* Rounds half away from zero and stays a float. */
+XU_FN double xu_round(double f) { return f >= 0 ? floor(f + 0.5) : ceil(f - 0.5); }
+
+XU_FN int64_t xu_float_to_int(double f) {
+    if (f != f || f >= 9223372036854775808.0 || f < -9223372036854775808.0) return 0;
+    return (int64_t)f;
+}
+
+XU_FN xu_value xu_num_method(xu_value self, const char *m, int argc, xu_value *argv) {
+#define M(name) (strcmp(m, name) == 0)
+    if (M("to_string")) {
+        xu_buf b = {NULL, 0, 0};
+        if (self.tag == XU_INT) xu_buf_int(&b, self.as.i);
+        else xu_buf_float(&b, self.as.f, 1);
+        return xu_buf_str(&b);
+    }
+    if (M("abs")) {
+        if (self.tag == XU_INT) return self.as.i < 0 ? xu_neg(self) : self;
+        return xu_float(fabs(self.as.f));
+    }
+    if (self.tag == XU_INT) {
+        if (M("is_even")) return xu_bool(self.as.i % 2 == 0);
+        if (M("is_odd")) return xu_bool(self.as.i % 2 != 0);
+        if (M("to_base")) {
+            int64_t base = xu_as_int(XU_ARG(0), "to_base");
+            uint64_t n = self.as.i < 0 ? 0 - (uint64_t)self.as.i : (uint64_t)self.as.i;
+            char tmp[72];
+            int k = 71;
+            if (base < 2 || base > 36) xu_fail("Base must be between 2 and 36");
+            tmp[k] = 0;
+            do {
+                tmp[--k] = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ"[n % (uint64_t)base];
+                n /= (uint64_t)base;
+            } while (n);
+            if (self.as.i < 0) tmp[--k] = '-';
+            return xu_cstr(tmp + k);
+        }
+    } else {
+        if (M("to_int")) return xu_int(xu_float_to_int(self.as.f));
+        if (M("round")) {
+            double factor;
+            if (argc == 0 || argv[0].tag == XU_UNIT) return xu_float(xu_round(self.as.f));
+            factor = pow(10.0, xu_as_num(argv[0], "round"));
+            return xu_float(xu_round(self.as.f * factor) / factor);
+        }
+        if (M("floor")) return xu_float(floor(self.as.f));
+        if (M("ceil")) return xu_float(ceil(self.as.f));
+    }
+#undef M
+    xu_fail("Unknown method %s for %s", m, xu_type_name(self));
+}
+
+XU_FN int xu_success(xu_value e) {
+    const xu_enum *en = XU_ENUM(e);
+    if (en->type == &xu_option_type) return strcmp(en->tag, "some") == 0;
+    if (en->type == &xu_result_type) return strcmp(en->tag, "ok") == 0;
+    xu_fail("Unsupported method for %s", en->type->name);
+}
+
+/* Methods of every enum, and the Option and Result combinators. */
+XU_FN xu_value xu_enum_method(xu_value self, const char *m, int argc, xu_value *argv) {
+    const xu_enum *e = XU_ENUM(self);
+    int option = e->type == &xu_option_type;
+#define M(name) (strcmp(m, name) == 0)
+    if (M("name")) return xu_cstr(e->tag);
+    if (M("type_name")) return xu_cstr(e->type->name);
+    if (M("to_string")) {
+        xu_buf b = {NULL, 0, 0};
+        if (option && e->n == 1) {
+            xu_buf_puts(&b, "Option#some(");
+            xu_buf_value(&b, e->payload[0], NULL);
+            xu_buf_putc(&b, ')');
+        } else {
+            xu_buf_puts(&b, e->type->name);
+            xu_buf_putc(&b, '#');
+            xu_buf_puts(&b, e->tag);
+        }
+        return xu_buf_str(&b);
+    }
+    if (M("has") || M("get") || M("filter")) {
+        if (!option) xu_fail("Unsupported method %s for %s", m, e->type->name);
+        if (M("has")) return xu_bool(e->n == 1);
+        if (M("get")) {
+            if (e->n != 1) xu_fail("Called get() on None value");
+            return e->payload[0];
+        }
+        if (e->n == 1) {
+            xu_value r = xu_call1(XU_ARG(0), e->payload[0]);
+            if (r.tag == XU_BOOL && r.as.b) return self;
+        }
+        return xu_none();
+    }
+    if (M("or")) return xu_success(self) ? e->payload[0] : XU_ARG(0);
+    if (M("or_else")) return xu_success(self) ? e->payload[0] : xu_call(XU_ARG(0), 0, NULL);
+    if (M("map")) {
+        xu_value v;
+        if (!xu_success(self)) return self;
+        v = xu_call1(XU_ARG(0), e->payload[0]);
+        return xu_variant(e->type, e->tag, 1, &v);
+    }
+    if (M("map_err")) {
+        if (e->type != &xu_result_type) xu_fail("Unsupported method map_err for %s", e->type->name);
+        if (xu_success(self)) return self;
+        return xu_err(xu_call1(XU_ARG(0), e->payload[0]));
+    }
+    if (M("then")) return xu_success(self) ? xu_call1(XU_ARG(0), e->payload[0]) : self;
+    if (M("each")) {
+        if (xu_success(self)) xu_call1(XU_ARG(0), e->payload[0]);
+        return xu_unit();
+    }
+#undef M
+    xu_fail("Unknown method %s for %s", m, e->type->name);
+}
+
+/* Calls method `m` of `o`: the struct's or enum's own methods first, then
+ * a function stored in a field of that name, then the builtin methods. */
+XU_FN xu_value xu_call_method(xu_value o, const char *m, int argc, xu_value *argv) {
+    const xu_type *t = NULL;
+    int i;
+    if (o.tag == XU_STRUCT) t = XU_STRUCT(o)->type;
+    if (o.tag == XU_ENUM) t = XU_ENUM(o)->type;
+    if (t) {
+        for (i = 0; i < t->nmethods; i++) {
+            if (strcmp(t->methods[i].name, m) == 0) {
+                xu_value small[8], *args = argc < 8 ? small : (xu_value *)xu_alloc((size_t)(argc + 1) * sizeof(xu_value));
+                args[0] = o;
+                if (argc) memcpy(args + 1, argv, (size_t)argc * sizeof(xu_value));
+                return t->methods[i].fn(NULL, argc + 1, args);
+            }
+        }
+    }
+    switch (o.tag) {
+    case XU_STRUCT:
+        i = xu_field_index(t, m);
+        if (i >= 0 && XU_STRUCT(o)->fields[i].tag == XU_FUNC) return xu_call(XU_STRUCT(o)->fields[i], argc, argv);
+        break;
+    case XU_ENUM: return xu_enum_method(o, m, argc, argv);
+    case XU_LIST: return xu_list_method(o, m, argc, argv);
+    case XU_DICT: return xu_dict_method(o, m, argc, argv);
+    case XU_STR: return xu_str_method(o, m, argc, argv);
+    case XU_INT:
+    case XU_FLOAT: return xu_num_method(o, m, argc, argv);
+    case XU_BOOL:
+        if (strcmp(m, "not") == 0) return xu_bool(!o.as.b);
+        if (strcmp(m, "to_string") == 0) return xu_cstr(o.as.b ? "true" : "false");
+        break;
+    case XU_TUPLE:
+        if (strcmp(m, "length") == 0) return xu_int((int64_t)XU_LIST(o)->len);
+        break;
+    default: break;
+    }
+    xu_fail("Unknown method %s for %s", m, xu_type_name(o));
+}
+
+/* ------------------------------------------------------------------------
+ * Builtins, called with their arguments as an array
+ */
+
+static int xu_argc;
+static char **xu_argv;
+static int64_t xu_next_id = 1;
+static uint64_t xu_rand_state;
+
+/* Like `println`, `print` writes each argument on a line of its own. */
+XU_FN xu_value xu_b_print(int argc, xu_value *argv) {
+    int i;
+    for (i = 0; i < argc; i++) {
+        xu_buf b = {NULL, 0, 0};
+        xu_buf_value(&b, argv[i], NULL);
+        xu_buf_putc(&b, '\n');
+        XU_WRITE(b.data, b.len);
+    }
+    return xu_unit();
+}
+
+XU_FN xu_value xu_b_println(int argc, xu_value *argv) { return xu_b_print(argc, argv); }
+
+XU_FN xu_value xu_b_to_text(int argc, xu_value *argv) { return xu_to_str(XU_ARG(0)); }
+
+XU_FN xu_value xu_b_inspect(int argc, xu_value *argv) {
+    xu_inspect_opts opts;
+    xu_buf b = {NULL, 0, 0};
+    opts.max_depth = argc > 2 && argv[2].tag == XU_INT ? argv[2].as.i : 8;
+    opts.max_items = argc > 3 && argv[3].tag == XU_INT ? argv[3].as.i : 100;
+    xu_doc_layout(xu_inspect_doc(XU_ARG(0), 0, &opts, NULL), &b, 0, 0, argc > 1 && argv[1].tag == XU_BOOL && argv[1].as.b);
+    return xu_buf_str(&b);
+}
+
+XU_FN xu_value xu_b_abs(int argc, xu_value *argv) { return xu_num_method(XU_ARG(0), "abs", 0, NULL); }
+
+XU_FN xu_value xu_minmax(const char *name, xu_value a, xu_value b, int pick_a) {
+    if (a.tag == XU_INT && b.tag == XU_INT) return pick_a ? a : b;
+    xu_as_num(a, name);
+    xu_as_num(b, name);
+    return xu_float(xu_as_num(pick_a ? a : b, name));
+}
+
+XU_FN xu_value xu_b_max(int argc, xu_value *argv) {
+    return xu_minmax("max", XU_ARG(0), XU_ARG(1), xu_compare(XU_ARG(0), XU_ARG(1)) >= 0);
+}
+
+XU_FN xu_value xu_b_min(int argc, xu_value *argv) {
+    return xu_minmax("min", XU_ARG(0), XU_ARG(1), xu_compare(XU_ARG(0), XU_ARG(1)) <= 0);
+}
+
+XU_FN xu_value xu_b_clamp(int argc, xu_value *argv) {
+    xu_value x = XU_ARG(0), lo = XU_ARG(1), hi = XU_ARG(2), v;
+    if (xu_compare(lo, hi) > 0) xu_fail("clamp: lower bound is greater than upper bound");
+    v = xu_compare(x, lo) < 0 ? lo : xu_compare(x, hi) > 0 ? hi : x;
+    if (x.tag == XU_INT && lo.tag == XU_INT && hi.tag == XU_INT) return v;
+    return xu_float(xu_as_num(v, "clamp"));
+}
+
+#define XU_FLOAT_FN(name, expr)                                                                                        \
+    XU_FN xu_value xu_b_##name(int argc, xu_value *argv) {                                                             \
+        double x = xu_as_num(XU_ARG(0), #name);                                                                        \
+        return xu_float(expr);                                                                                         \
+    }
+XU_FLOAT_FN(sin, sin(x))
+XU_FLOAT_FN(cos, cos(x))
+XU_FLOAT_FN(tan, tan(x))
+XU_FLOAT_FN(sqrt, sqrt(x))
+XU_FLOAT_FN(log, log(x))
+XU_FLOAT_FN(exp, exp(x))
+XU_FLOAT_FN(log2, log(x) / log(2.0))
+#undef XU_FLOAT_FN
+
+XU_FN xu_value xu_b_pow(int argc, xu_value *argv) {
+    return xu_float(pow(xu_as_num(XU_ARG(0), "pow"), xu_as_num(XU_ARG(1), "pow")));
+}
+
+XU_FN int64_t xu_gcd(int64_t a, int64_t b) {
+    a = a < 0 ? -a : a;
+    b = b < 0 ? -b : b;
+    while (b) {
+        int64_t t = a % b;
+        a = b;
+        b = t;
+    }
+    return a;
+}
+
+XU_FN xu_value xu_b_gcd(int argc, xu_value *argv) {
+    return xu_int(xu_gcd(xu_as_int(XU_ARG(0), "gcd"), xu_as_int(XU_ARG(1), "gcd")));
+}
+
+XU_FN xu_value xu_b_lcm(int argc, xu_value *argv) {
+    int64_t a = xu_as_int(XU_ARG(0), "lcm"), b = xu_as_int(XU_ARG(1), "lcm");
+    if (a == 0 || b == 0) return xu_int(0);
+    a = a < 0 ? -a : a;
+    b = b < 0 ? -b : b;
+    return xu_int(a / xu_gcd(a, b) * b);
+}
+
+XU_FN xu_value xu_b_parse_int(int argc, xu_value *argv) {
+    xu_value v = XU_ARG(0);
+    int64_t i;
+    double f;
+    if (v.tag == XU_INT) return v;
+    if (v.tag == XU_FLOAT) return xu_int(xu_float_to_int(v.as.f));
+    if (v.tag != XU_STR) xu_fail("parse_int expects text or number");
+    if (xu_parse_int(XU_STR(v), &i)) return xu_int(i);
+    if (xu_parse_float(XU_STR(v), &f)) return xu_int(xu_float_to_int(f));
+    xu_fail("parse_int expects numeric text");
+}
+
+XU_FN xu_value xu_b_parse_float(int argc, xu_value *argv) {
+    xu_value v = XU_ARG(0);
+    double f;
+    if (v.tag == XU_INT || v.tag == XU_FLOAT) return xu_float(xu_as_num(v, "parse_float"));
+    if (v.tag != XU_STR) xu_fail("parse_float expects text or number");
+    if (!xu_parse_float(XU_STR(v), &f)) xu_fail("parse_float expects numeric text");
+    return xu_float(f);
+}
+
+XU_FN xu_value xu_b_contains(int argc, xu_value *argv) {
+    return xu_str_method(XU_ARG(0), "contains", argc - 1, argv + 1);
+}
+
+XU_FN xu_value xu_b_starts_with(int argc, xu_value *argv) {
+    return xu_str_method(XU_ARG(0), "starts_with", argc - 1, argv + 1);
+}
+
+XU_FN xu_value xu_b_ends_with(int argc, xu_value *argv) {
+    return xu_str_method(XU_ARG(0), "ends_with", argc - 1, argv + 1);
+}
+
+XU_FN xu_value xu_b_time_unix(int argc, xu_value *argv) {
+    (void)argc;
+    (void)argv;
+    return xu_int((int64_t)time(NULL));
+}
+
+XU_FN xu_value xu_b_time_millis(int argc, xu_value *argv) {
+    (void)argc;
+    (void)argv;
+    return xu_int((int64_t)time(NULL) * 1000);
+}
+
+XU_FN xu_value xu_b_mono_micros(int argc, xu_value *argv) {
+    (void)argc;
+    (void)argv;
+    return xu_int((int64_t)((double)clock() * 1e6 / CLOCKS_PER_SEC));
+}
+
+XU_FN xu_value xu_b_mono_nanos(int argc, xu_value *argv) {
+    (void)argc;
+    (void)argv;
+    return xu_int((int64_t)((double)clock() * 1e9 / CLOCKS_PER_SEC));
+}
+
+XU_FN xu_value xu_b_rand(int argc, xu_value *argv) {
+    uint64_t x;
+    if (!xu_rand_state) xu_rand_state = (uint64_t)time(NULL) * 2654435761ULL | 1;
+    xu_rand_state ^= xu_rand_state >> 12;
+    xu_rand_state ^= xu_rand_state << 25;
+    xu_rand_state ^= xu_rand_state >> 27;
+    x = (xu_rand_state * 2685821657736338717ULL) >> 1;
+    if (argc == 0 || argv[0].tag == XU_UNIT) return xu_int((int64_t)x);
+    {
+        int64_t upper = argv[0].tag == XU_FLOAT ? xu_float_to_int(argv[0].as.f) : xu_as_int(argv[0], "rand");
+        if (upper <= 0) xu_fail("rand upper bound must be > 0");
+        return xu_int((int64_t)(x % (uint64_t)upper));
+    }
+}
+
+XU_FN xu_value xu_b_gen_id(int argc, xu_value *argv) {
+    (void)argc;
+    (void)argv;
+    return xu_int(xu_next_id++);
+}
+
+XU_FN xu_value xu_b_os_args(int argc, xu_value *argv) {
+    xu_value out = xu_list_new(0);
+    int i;
+    (void)argc;
+    (void)argv;
+    for (i = 0; i < xu_argc; i++) xu_list_push(out, xu_cstr(xu_argv[i]));
+    return out;
+}
+
+XU_FN xu_value xu_b_env_get(int argc, xu_value *argv) {
+    const char *v = getenv(xu_as_str(XU_ARG(0), "env_get")->data);
+    return xu_cstr(v ? v : "");
+}
+
+XU_FN xu_value xu_b_builder_new(int argc, xu_value *argv) {
+    xu_builder *b = (xu_builder *)xu_alloc(sizeof(xu_builder));
+    (void)argc;
+    (void)argv;
+    memset(b, 0, sizeof(xu_builder));
+    return xu_obj(XU_BUILDER, b);
+}
+
+XU_FN xu_value xu_b_builder_new_cap(int argc, xu_value *argv) { return xu_b_builder_new(argc, argv); }
+
+XU_FN xu_builder *xu_as_builder(xu_value v, const char *what) {
+    if (v.tag != XU_BUILDER) xu_fail("%s expects a builder, got %s", what, xu_type_name(v));
+    return XU_BUILDER(v);
+}
+
+XU_FN xu_value xu_b_builder_push(int argc, xu_value *argv) {
+    xu_builder *b = xu_as_builder(XU_ARG(0), "builder_push");
+    xu_buf buf;
+    buf.data = b->data;
+    buf.len = b->len;
+    buf.cap = b->cap;
+    xu_buf_value(&buf, XU_ARG(1), NULL);
+    b->data = buf.data;
+    b->len = buf.len;
+    b->cap = buf.cap;
+    return xu_unit();
+}
+
+XU_FN xu_value xu_b_builder_finalize(int argc, xu_value *argv) {
+    xu_builder *b = xu_as_builder(XU_ARG(0), "builder_finalize");
+    xu_value s = xu_str_new(b->data ? b->data : "", b->len);
+    b->len = 0;
+    return s;
+}
+
+XU_FN xu_value xu_b___builtin_assert(int argc, xu_value *argv) {
+    xu_value c = XU_ARG(0);
+    if (c.tag != XU_BOOL) xu_fail("__builtin_assert expects bool");
+    if (!c.as.b) {
+        if (argc > 1) xu_fail("%s", XU_STR(xu_to_str(argv[1]))->data);
+        xu_fail("Assertion failed");
+    }
+    return xu_unit();
+}
+
+XU_FN xu_value xu_b___builtin_assert_eq(int argc, xu_value *argv) {
+    if (!xu_equal(XU_ARG(0), XU_ARG(1))) {
+        if (argc > 2) xu_fail("%s", XU_STR(xu_to_str(argv[2]))->data);
+        xu_fail("Assertion failed: %s != %s", XU_STR(xu_to_str(XU_ARG(0)))->data,
+                XU_STR(xu_to_str(XU_ARG(1)))->data);
+    }
+    return xu_unit();
+}
+
+XU_FN xu_value xu_b___set_from_list(int argc, xu_value *argv) {
+    xu_list *l = xu_as_list(XU_ARG(0), "set");
+    xu_value d = xu_dict_new();
+    size_t i;
+    for (i = 0; i < l->len; i++) {
+        if (l->items[i].tag != XU_INT && l->items[i].tag != XU_STR) xu_fail("Set items must be int or string");
+        xu_dict_set(d, l->items[i], xu_unit());
+    }
+    return d;
+}
+
+XU_FN xu_value xu_b_dict(int argc, xu_value *argv) {
+    (void)argc;
+    (void)argv;
+    return xu_dict_new();
+}
+
+/* Nothing to collect: the arena is released at exit. */
+XU_FN xu_value xu_b_gc(int argc, xu_value *argv) {
+    (void)argc;
+    (void)argv;
+    return xu_unit();
+}
+
+/* `bench` blocks only run under `xu bench`. */
+XU_FN xu_value xu_b___bench(int argc, xu_value *argv) {
+    (void)argc;
+    (void)argv;
+    return xu_unit();
+}
+
+/* ------------------------------------------------------------------------
+ * Entry
+ */
+
+static int xu_main_invoked;
+
+/* Reports a script error like `xu run`: on stderr, with exit status 1. */
+XU_FN XU_NORETURN void xu_fail(const char *fmt, ...) {
+    char msg[512];
+    va_list ap;
+    va_start(ap, fmt);
+    vsnprintf(msg, sizeof msg, fmt, ap);
+    va_end(ap);
+    fflush(stdout);
+    XU_WRITE_ERR("RuntimeError: ", 14);
+    XU_WRITE_ERR(msg, strlen(msg));
+    XU_WRITE_ERR("\n", 1);
+    XU_EXIT(1);
+    abort();
+}
+
+XU_FN void xu_init(int argc, char **argv) {
+    xu_argc = argc;
+    xu_argv = argv;
+    xu_main_invoked = 0;
+}
+
+#endif
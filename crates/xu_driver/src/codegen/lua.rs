@@ -0,0 +1,1205 @@
+//! Lua output of `xu codegen`.
+//!
+//! Every imported module becomes a function in `xu.modules` that runs the
+//! module's top level and returns its exports; the entry's top level runs
+//! under `xu.run`. Top-level names are declared up front so functions can
+//! refer to names defined after them, as they can in Xu. Values use the
+//! representation described in `lua_runtime.lua`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::rc::Rc;
+
+use xu_parser::{AssignOp, BinaryOp, DeclKind, Expr, FuncDef, Pattern, Stmt, StructInitItem, TypeRef, UnaryOp};
+
+use super::{BUILTINS, CodegenOptions, ModuleInfo, SourceModule, method_name, method_owner};
+
+pub(super) const RUNTIME: &str = include_str!("lua_runtime.lua");
+
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in", "local", "nil",
+    "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Names the generated code itself uses.
+const RESERVED: &[&str] = &["xu", "_ENV", TOP_TABLE];
+
+/// Holds the top-level names of a module with more than [`MAX_TOP_LOCALS`],
+/// as Lua allows 200 locals per function.
+const TOP_TABLE: &str = "__top";
+const MAX_TOP_LOCALS: usize = 150;
+
+pub(super) fn emit(modules: &[SourceModule], opts: &CodegenOptions) -> Result<String, String> {
+    if let Some(m) = modules.iter().find(|m| m.imports.values().any(|&to| to == 0)) {
+        return Err(format!("{}: importing the entry module is not supported in Lua output", m.key));
+    }
+    let infos: Vec<ModuleInfo> = modules.iter().map(|m| ModuleInfo::collect(&m.module)).collect();
+    let mut out = String::new();
+    for idx in (1..modules.len()).chain([0]) {
+        let mut e = Emitter::new(modules, &infos, idx);
+        if idx == 0 {
+            e.line("xu.run(function()");
+        } else {
+            e.line(&format!("xu.modules[{}] = function()", lua_str(&modules[idx].key)));
+        }
+        e.indent += 1;
+        e.top_level()?;
+        e.indent -= 1;
+        e.line(if idx == 0 { "end)" } else { "end" });
+        out.push('\n');
+        out.push_str(&e.out);
+    }
+    let head = if opts.inject_runtime {
+        format!("local xu = (function()\n{}end)()\n", super::shake::lua_runtime(RUNTIME, &out))
+    } else {
+        "local xu = require(\"xu_runtime\")\n".to_string()
+    };
+    Ok(head + &out)
+}
+
+/// What the emitter knows of a value statically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Ty {
+    Any,
+    Num,
+    Str,
+    Bool,
+}
+
+impl Ty {
+    fn of_ref(ty: Option<&TypeRef>) -> Ty {
+        match ty.map(|t| t.name.as_str()) {
+            Some("int" | "float") => Ty::Num,
+            Some("string") => Ty::Str,
+            Some("bool") => Ty::Bool,
+            _ => Ty::Any,
+        }
+    }
+
+    /// Values Lua compares with `==` the way Xu does.
+    fn is_primitive(self) -> bool {
+        self != Ty::Any
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Value(Ty),
+    /// A struct or enum declared in `module`.
+    Type {
+        module: usize,
+        name: String,
+    },
+    /// The alias of an imported module.
+    Module(usize),
+}
+
+#[derive(Clone, Debug)]
+struct Binding {
+    lua: String,
+    kind: Kind,
+}
+
+/// Where an assignment stores its value.
+enum Place {
+    Var(String),
+    /// A struct field known to exist, or a static.
+    Field(String),
+    Prop(String, String),
+    Index(String, String),
+}
+
+struct Emitter<'a> {
+    modules: &'a [SourceModule],
+    infos: &'a [ModuleInfo],
+    module: usize,
+    out: String,
+    indent: usize,
+    scopes: Vec<HashMap<String, Binding>>,
+    next_tmp: usize,
+    /// The label `continue` jumps to in each enclosing loop.
+    loops: Vec<Option<String>>,
+    /// The struct whose method is being emitted.
+    self_ty: Option<String>,
+}
+
+impl<'a> Emitter<'a> {
+    fn new(modules: &'a [SourceModule], infos: &'a [ModuleInfo], module: usize) -> Self {
+        Emitter {
+            modules,
+            infos,
+            module,
+            out: String::new(),
+            indent: 0,
+            scopes: Vec::new(),
+            next_tmp: 0,
+            loops: Vec::new(),
+            self_ty: None,
+        }
+    }
+
+    fn key(&self) -> &str {
+        &self.modules[self.module].key
+    }
+
+    fn line(&mut self, s: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("  ");
+        }
+        // Keeps Lua from reading the parenthesis as a call of the previous line.
+        if s.starts_with('(') {
+            self.out.push(';');
+        }
+        self.out.push_str(s);
+        self.out.push('\n');
+    }
+
+    fn pad(&self) -> String {
+        "  ".repeat(self.indent)
+    }
+
+    fn tmp(&mut self) -> String {
+        self.next_tmp += 1;
+        format!("__t{}", self.next_tmp)
+    }
+
+    /// Emits what `f` writes one level deeper into a separate string.
+    fn nested(&mut self, f: impl FnOnce(&mut Self) -> Result<(), String>) -> Result<String, String> {
+        let saved = std::mem::take(&mut self.out);
+        self.indent += 1;
+        let r = f(self);
+        self.indent -= 1;
+        let body = std::mem::replace(&mut self.out, saved);
+        r.map(|()| body)
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|s| s.get(name))
+    }
+
+    fn bind(&mut self, name: &str, lua: String, kind: Kind) {
+        self.scopes.last_mut().expect("scope").insert(name.to_string(), Binding { lua, kind });
+    }
+
+    /// Declares `name` in the innermost scope and returns its Lua name and
+    /// whether it needs a `local`; declaring a name again in the same scope
+    /// reuses its local.
+    fn declare(&mut self, name: &str, kind: Kind) -> (String, bool) {
+        let scope = self.scopes.last_mut().expect("scope");
+        if let Some(b) = scope.get_mut(name) {
+            b.kind = kind;
+            return (b.lua.clone(), false);
+        }
+        let lua = lua_name(name);
+        scope.insert(name.to_string(), Binding { lua: lua.clone(), kind });
+        (lua, true)
+    }
+
+    fn top_level(&mut self) -> Result<(), String> {
+        let modules = self.modules;
+        let infos = self.infos;
+        let m = &modules[self.module];
+        let stmts = &m.module.stmts;
+        let mut names: Vec<(String, Kind)> = Vec::new();
+        let mut lets: HashMap<&str, usize> = HashMap::new();
+        for s in stmts.iter() {
+            let (name, kind) = match s {
+                Stmt::FuncDef(def)
+                    if method_owner(&def.name).is_some_and(|ty| infos[self.module].types.contains_key(ty)) =>
+                {
+                    continue;
+                }
+                Stmt::FuncDef(def) => (def.name.clone(), Kind::Value(Ty::Any)),
+                Stmt::StructDef(def) => (def.name.clone(), Kind::Type { module: self.module, name: def.name.clone() }),
+                Stmt::EnumDef(def) => (def.name.clone(), Kind::Type { module: self.module, name: def.name.clone() }),
+                Stmt::Use(u) => (
+                    u.alias.clone().unwrap_or_else(|| xu_ir::infer_module_alias(&u.path)),
+                    Kind::Module(m.imports[&u.path]),
+                ),
+                Stmt::Assign(a) if a.decl.is_some() => match &a.target {
+                    Expr::Ident(name, _) => {
+                        *lets.entry(name).or_default() += 1;
+                        (name.clone(), Kind::Value(Ty::Any))
+                    }
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            match names.iter_mut().find(|(n, _)| *n == name) {
+                Some(entry) => entry.1 = kind,
+                None => names.push((name, kind)),
+            }
+        }
+        self.scopes.push(HashMap::new());
+        let in_table = names.len() > MAX_TOP_LOCALS;
+        if in_table {
+            self.line(&format!("local {TOP_TABLE} = {{}}"));
+        }
+        let mut locals = Vec::new();
+        for (name, kind) in names {
+            let lua = if in_table { dot(TOP_TABLE, &lua_name(&name)) } else { lua_name(&name) };
+            locals.push(lua.clone());
+            self.bind(&name, lua, kind);
+        }
+        if !in_table {
+            for chunk in locals.chunks(10) {
+                self.line(&format!("local {}", chunk.join(", ")));
+            }
+        }
+        // A name declared once keeps the type of its value in functions
+        // emitted before the declaration too.
+        let single: HashSet<String> = lets.into_iter().filter(|&(_, n)| n == 1).map(|(k, _)| k.to_string()).collect();
+        for (i, s) in stmts.iter().enumerate() {
+            match s {
+                Stmt::Assign(a) if a.decl.is_some() => {
+                    let Expr::Ident(name, _) = &a.target else {
+                        self.stmt(s, false)?;
+                        continue;
+                    };
+                    let lua = self.lookup(name).expect("declared").lua.clone();
+                    let value = self.assigned_value(a.op, &lua, Ty::Any, &a.value)?;
+                    self.line(&format!("{lua} = {value}"));
+                    if single.contains(name) {
+                        let ty = self.decl_ty(a.decl, a.ty.as_ref(), &a.value);
+                        self.bind(name, lua, Kind::Value(ty));
+                    }
+                }
+                _ => self.stmt(s, i + 1 == stmts.len())?,
+            }
+        }
+        if self.module == 0 {
+            let has_main = stmts.iter().any(|s| matches!(s, Stmt::FuncDef(def) if def.name == "main"));
+            if has_main {
+                let main = self.lookup("main").expect("declared").lua.clone();
+                self.line(&format!("if not xu.main_invoked then {main}() end"));
+            }
+        } else {
+            let mut items = Vec::new();
+            for name in &infos[self.module].exports {
+                let lua = self.lookup(name).expect("declared").lua.clone();
+                items.push(format!("{} = {lua}", table_key(name)));
+            }
+            self.line(&format!("return {{ {} }}", items.join(", ")));
+        }
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn decl_ty(&self, decl: Option<DeclKind>, ty: Option<&TypeRef>, value: &Expr) -> Ty {
+        match Ty::of_ref(ty) {
+            Ty::Any if matches!(decl, Some(DeclKind::Let | DeclKind::Const)) => self.ty(value),
+            ty => ty,
+        }
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) -> Result<(), String> {
+        self.scopes.push(HashMap::new());
+        let funcs: Vec<String> = stmts
+            .iter()
+            .filter_map(|s| match s {
+                Stmt::FuncDef(def) => Some(def.name.clone()),
+                _ => None,
+            })
+            .collect();
+        if !funcs.is_empty() {
+            let mut locals = Vec::new();
+            for name in &funcs {
+                let (lua, fresh) = self.declare(name, Kind::Value(Ty::Any));
+                if fresh {
+                    locals.push(lua);
+                }
+            }
+            self.line(&format!("local {}", locals.join(", ")));
+        }
+        let r = stmts.iter().enumerate().try_for_each(|(i, s)| self.stmt(s, i + 1 == stmts.len()));
+        self.scopes.pop();
+        r
+    }
+
+    fn stmt(&mut self, s: &Stmt, last: bool) -> Result<(), String> {
+        match s {
+            Stmt::FuncDef(def) if self.scopes.len() == 1 && self.owner_type(&def.name).is_some() => {
+                let (ty, lua) = self.owner_type(&def.name).expect("method");
+                self.methods(&lua, &ty, std::slice::from_ref(def))?;
+            }
+            Stmt::FuncDef(def) => {
+                let lua = self.lookup(&def.name).expect("declared").lua.clone();
+                let main = self.module == 0 && self.scopes.len() == 1 && def.name == "main";
+                let f = self.function(def, main)?;
+                self.line(&format!("{lua} = {f}"));
+            }
+            Stmt::StructDef(def) => {
+                let lua = self.type_binding(&def.name);
+                let fields: Vec<String> = def.fields.iter().map(|f| lua_str(&f.name)).collect();
+                self.line(&format!("{lua} = xu.struct({}, {{{}}})", lua_str(&def.name), fields.join(", ")));
+                for f in def.fields.iter() {
+                    if let Some(d) = &f.default {
+                        let v = self.expr(d)?;
+                        self.line(&format!("{} = function() return {v} end", dot(&format!("{lua}.defaults"), &f.name)));
+                    }
+                }
+                for f in def.static_fields.iter() {
+                    let v = self.expr(&f.default)?;
+                    self.line(&format!("{} = {v}", dot(&format!("{lua}.statics"), &f.name)));
+                }
+                self.methods(&lua, &def.name, &def.methods)?;
+            }
+            Stmt::EnumDef(def) => {
+                let lua = self.type_binding(&def.name);
+                let variants: Vec<String> = def.variants.iter().map(|v| lua_str(v)).collect();
+                self.line(&format!("{lua} = xu.enum({}, {{{}}})", lua_str(&def.name), variants.join(", ")));
+            }
+            Stmt::DoesBlock(block) => {
+                let lua = match self.lookup(&block.target) {
+                    Some(Binding { lua, kind: Kind::Type { .. } }) => lua.clone(),
+                    _ => {
+                        return Err(format!("{}: cannot add methods to {} in Lua output", self.key(), block.target));
+                    }
+                };
+                self.methods(&lua, &block.target, &block.funcs)?;
+            }
+            Stmt::Use(u) => {
+                let Some(&to) = self.modules[self.module].imports.get(&u.path) else {
+                    return Err(format!("{}: `use` inside a block is not supported in Lua output", self.key()));
+                };
+                let alias = u.alias.clone().unwrap_or_else(|| xu_ir::infer_module_alias(&u.path));
+                let key = lua_str(&self.modules[to].key);
+                if self.scopes.len() == 1 {
+                    let lua = self.lookup(&alias).expect("declared").lua.clone();
+                    self.line(&format!("{lua} = xu.import({key})"));
+                } else {
+                    let (lua, fresh) = self.declare(&alias, Kind::Module(to));
+                    self.line(&format!("{}{lua} = xu.import({key})", if fresh { "local " } else { "" }));
+                }
+            }
+            Stmt::If(s) => {
+                for (i, (cond, body)) in s.branches.iter().enumerate() {
+                    let c = self.expr(cond)?;
+                    self.line(&format!("{} {c} then", if i == 0 { "if" } else { "elseif" }));
+                    self.indent += 1;
+                    self.block(body)?;
+                    self.indent -= 1;
+                }
+                if let Some(body) = &s.else_branch {
+                    self.line("else");
+                    self.indent += 1;
+                    self.block(body)?;
+                    self.indent -= 1;
+                }
+                self.line("end");
+            }
+            Stmt::While(s) => {
+                let c = self.expr(&s.cond)?;
+                self.line(&format!("while {c} do"));
+                self.loop_body(&s.body, |_| Ok(()))?;
+                self.line("end");
+            }
+            Stmt::ForEach(s) => self.for_each(s)?,
+            Stmt::Match(s) => {
+                let v = self.expr(&s.expr)?;
+                let tmp = self.tmp();
+                self.line("do");
+                self.indent += 1;
+                self.line(&format!("local {tmp} = {v}"));
+                for (i, (pat, body)) in s.arms.iter().enumerate() {
+                    let cond = pattern_cond(pat, &tmp);
+                    self.line(&format!("{} {cond} then", if i == 0 { "if" } else { "elseif" }));
+                    self.indent += 1;
+                    self.scopes.push(HashMap::new());
+                    self.bind_pattern(pat, &tmp);
+                    let r = self.block(body);
+                    self.scopes.pop();
+                    r?;
+                    self.indent -= 1;
+                }
+                if let Some(body) = &s.else_branch {
+                    if s.arms.is_empty() {
+                        self.block(body)?;
+                    } else {
+                        self.line("else");
+                        self.indent += 1;
+                        self.block(body)?;
+                        self.indent -= 1;
+                    }
+                }
+                if !s.arms.is_empty() {
+                    self.line("end");
+                }
+                self.indent -= 1;
+                self.line("end");
+            }
+            Stmt::Block(body) => {
+                self.line("do");
+                self.indent += 1;
+                self.block(body)?;
+                self.indent -= 1;
+                self.line("end");
+            }
+            Stmt::Return(v) => {
+                let ret = match v {
+                    Some(e) => format!("return {}", self.expr(e)?),
+                    None => "return".to_string(),
+                };
+                // `return` has to end a Lua block.
+                if last { self.line(&ret) } else { self.line(&format!("do {ret} end")) }
+            }
+            Stmt::Break => self.line("break"),
+            Stmt::Continue => match self.loops.last() {
+                Some(Some(label)) => {
+                    let label = label.clone();
+                    self.line(&format!("goto {label}"));
+                }
+                _ => return Err(format!("{}: `continue` outside of a loop", self.key())),
+            },
+            Stmt::Assign(a) => self.assign(a)?,
+            Stmt::Expr(e) => {
+                let v = self.expr(e)?;
+                if matches!(e, Expr::Call(_) | Expr::MethodCall(_)) {
+                    self.line(&v);
+                } else {
+                    self.line(&format!("local _ = {v}"));
+                }
+            }
+            Stmt::Error(_) => {
+                return Err(format!("{}: cannot translate a statement with syntax errors", self.key()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The type a top-level `func (self: T) m()` belongs to and its Lua name,
+    /// if `T` is a struct or enum of this module.
+    fn owner_type(&self, name: &str) -> Option<(String, String)> {
+        let ty = method_owner(name)?;
+        if !self.infos[self.module].types.contains_key(ty) {
+            return None;
+        }
+        match self.scopes.first()?.get(ty) {
+            Some(Binding { lua, kind: Kind::Type { .. } }) => Some((ty.to_string(), lua.clone())),
+            _ => None,
+        }
+    }
+
+    /// The Lua name of a struct or enum declared by the statement being
+    /// emitted, declaring it when not at the top level.
+    fn type_binding(&mut self, name: &str) -> String {
+        let kind = Kind::Type { module: self.module, name: name.to_string() };
+        if self.scopes.len() == 1 {
+            return self.lookup(name).expect("declared").lua.clone();
+        }
+        let (lua, fresh) = self.declare(name, kind);
+        if fresh {
+            self.line(&format!("local {lua}"));
+        }
+        lua
+    }
+
+    fn methods(&mut self, ty_lua: &str, ty: &str, defs: &[Rc<FuncDef>]) -> Result<(), String> {
+        for def in defs {
+            let Some((is_static, name)) = method_name(ty, &def.name) else {
+                continue;
+            };
+            let saved = std::mem::replace(&mut self.self_ty, (!is_static).then(|| ty.to_string()));
+            let f = self.function(def, false);
+            self.self_ty = saved;
+            let table = if is_static { "statics" } else { "methods" };
+            self.line(&format!("{} = {}", dot(&format!("{ty_lua}.{table}"), name), f?));
+        }
+        Ok(())
+    }
+
+    /// A function expression for `def`; the entry's `main` records that it
+    /// ran so it is not run again after the top level.
+    fn function(&mut self, def: &FuncDef, main: bool) -> Result<String, String> {
+        let loops = std::mem::take(&mut self.loops);
+        self.scopes.push(HashMap::new());
+        let mut params = Vec::new();
+        for p in def.params.iter() {
+            let (lua, _) = self.declare(&p.name, Kind::Value(Ty::of_ref(p.ty.as_ref())));
+            params.push(lua);
+        }
+        let body = self.nested(|e| {
+            for (p, lua) in def.params.iter().zip(&params) {
+                if let Some(d) = &p.default {
+                    let v = e.expr(d)?;
+                    e.line(&format!("if {lua} == nil then {lua} = {v} end"));
+                }
+            }
+            if main {
+                e.line("xu.main_invoked = true");
+            }
+            e.block(&def.body)
+        });
+        self.scopes.pop();
+        self.loops = loops;
+        Ok(format!("function({})\n{}{}end", params.join(", "), body?, self.pad()))
+    }
+
+    fn loop_body(&mut self, body: &[Stmt], bind: impl FnOnce(&mut Self) -> Result<(), String>) -> Result<(), String> {
+        let label = has_continue(body).then(|| {
+            self.next_tmp += 1;
+            format!("continue_{}", self.next_tmp)
+        });
+        self.loops.push(label.clone());
+        self.indent += 1;
+        self.scopes.push(HashMap::new());
+        let r = bind(self).and_then(|()| match &label {
+            // The label follows a block so no local is in scope at it.
+            Some(label) => {
+                self.line("do");
+                self.indent += 1;
+                self.block(body)?;
+                self.indent -= 1;
+                self.line("end");
+                self.line(&format!("::{label}::"));
+                Ok(())
+            }
+            None => self.block(body),
+        });
+        self.scopes.pop();
+        self.indent -= 1;
+        self.loops.pop();
+        r
+    }
+
+    fn for_each(&mut self, s: &xu_ir::ForEachStmt) -> Result<(), String> {
+        let var = lua_name(&s.var);
+        let mut ty = Ty::Any;
+        let header = match &s.iter {
+            Expr::Range(r) => {
+                ty = Ty::Num;
+                match (r.start.as_ref(), r.end.as_ref()) {
+                    (Expr::Int(a), Expr::Int(b)) => {
+                        let (a, b) = (*a, *b);
+                        match (a <= b, r.inclusive) {
+                            (true, true) => format!("for {var} = {a}, {b} do"),
+                            (true, false) => format!("for {var} = {a}, {} do", b - 1),
+                            (false, true) => format!("for {var} = {a}, {b}, -1 do"),
+                            (false, false) => format!("for {var} = {a}, {}, -1 do", b + 1),
+                        }
+                    }
+                    (start, end) => {
+                        let (a, b) = (self.expr(start)?, self.expr(end)?);
+                        format!("for {var} in xu.range_iter({a}, {b}, {}) do", r.inclusive)
+                    }
+                }
+            }
+            iter => {
+                let v = self.expr(iter)?;
+                if s.var.starts_with("__tmp_foreach_") {
+                    format!("for _, {var} in xu.each({v}, true) do")
+                } else {
+                    format!("for _, {var} in xu.each({v}) do")
+                }
+            }
+        };
+        self.line(&header);
+        self.loop_body(&s.body, |e| {
+            e.bind(&s.var, var.clone(), Kind::Value(ty));
+            Ok(())
+        })?;
+        self.line("end");
+        Ok(())
+    }
+
+    fn bind_pattern(&mut self, pat: &Pattern, access: &str) {
+        match pat {
+            Pattern::Bind(name) => {
+                let (lua, _) = self.declare(name, Kind::Value(Ty::Any));
+                self.line(&format!("local {lua} = {access}"));
+            }
+            Pattern::Tuple(items) | Pattern::EnumVariant { args: items, .. } => {
+                for (i, p) in items.iter().enumerate() {
+                    self.bind_pattern(p, &format!("{access}[{}]", i + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn assign(&mut self, a: &xu_ir::AssignStmt) -> Result<(), String> {
+        if let (Expr::Ident(name, _), Some(decl)) = (&a.target, a.decl) {
+            let ty = self.decl_ty(Some(decl), a.ty.as_ref(), &a.value);
+            // A function can call itself through the name it is bound to.
+            if matches!(a.value, Expr::FuncLit(_)) {
+                let (lua, fresh) = self.declare(name, Kind::Value(ty));
+                if fresh {
+                    self.line(&format!("local {lua}"));
+                }
+                let v = self.expr(&a.value)?;
+                self.line(&format!("{lua} = {v}"));
+                return Ok(());
+            }
+            let v = self.expr(&a.value)?;
+            let (lua, fresh) = self.declare(name, Kind::Value(ty));
+            self.line(&format!("{}{lua} = {v}", if fresh { "local " } else { "" }));
+            return Ok(());
+        }
+        let mut setup = Vec::new();
+        let (place, ty) = match &a.target {
+            Expr::Ident(name, _) => match self.lookup(name) {
+                Some(b) => {
+                    let ty = match b.kind {
+                        Kind::Value(ty) => ty,
+                        _ => Ty::Any,
+                    };
+                    (Place::Var(b.lua.clone()), ty)
+                }
+                None => {
+                    // Assigning an unknown name declares it.
+                    let v = self.expr(&a.value)?;
+                    let (lua, _) = self.declare(name, Kind::Value(Ty::Any));
+                    self.line(&format!("local {lua} = {v}"));
+                    return Ok(());
+                }
+            },
+            Expr::Member(m) => match self.static_member(&m.object, &m.field) {
+                Some(field) => (Place::Field(field), Ty::Any),
+                None => {
+                    let obj = self.simple(&m.object, a.op, &mut setup)?;
+                    (Place::Prop(obj, m.field.clone()), Ty::Any)
+                }
+            },
+            Expr::Index(ix) => {
+                let obj = self.simple(&ix.object, a.op, &mut setup)?;
+                let idx = self.simple(&ix.index, a.op, &mut setup)?;
+                (Place::Index(obj, idx), Ty::Any)
+            }
+            _ => return Err(format!("{}: invalid assignment target", self.key())),
+        };
+        let current = match &place {
+            Place::Var(v) | Place::Field(v) => v.clone(),
+            Place::Prop(o, f) => format!("xu.prop({o}, {})", lua_str(f)),
+            Place::Index(o, i) => format!("xu.index({o}, {i})"),
+        };
+        let v = self.assigned_value(a.op, &current, ty, &a.value)?;
+        let store = match place {
+            Place::Var(v_) | Place::Field(v_) => format!("{v_} = {v}"),
+            Place::Prop(o, f) => format!("xu.setprop({o}, {}, {v})", lua_str(&f)),
+            Place::Index(o, i) => format!("xu.setindex({o}, {i}, {v})"),
+        };
+        if setup.is_empty() {
+            self.line(&store);
+        } else {
+            self.line("do");
+            self.indent += 1;
+            for s in setup {
+                self.line(&s);
+            }
+            self.line(&store);
+            self.indent -= 1;
+            self.line("end");
+        }
+        Ok(())
+    }
+
+    /// `e` as an expression that can be evaluated twice, for compound
+    /// assignments.
+    fn simple(&mut self, e: &Expr, op: AssignOp, setup: &mut Vec<String>) -> Result<String, String> {
+        let v = self.expr(e)?;
+        if op == AssignOp::Set || matches!(e, Expr::Ident(..) | Expr::Int(_) | Expr::Str(_)) {
+            return Ok(v);
+        }
+        let tmp = self.tmp();
+        setup.push(format!("local {tmp} = {v}"));
+        Ok(tmp)
+    }
+
+    fn assigned_value(&mut self, op: AssignOp, current: &str, ty: Ty, value: &Expr) -> Result<String, String> {
+        let v = self.expr(value)?;
+        let vt = self.ty(value);
+        Ok(match op {
+            AssignOp::Set => v,
+            AssignOp::Add => add(current, ty, &v, vt),
+            AssignOp::Sub => format!("{current} - {v}"),
+            AssignOp::Mul => format!("{current} * {v}"),
+            AssignOp::Div => format!("xu.div({current}, {v})"),
+            AssignOp::Mod => format!("xu.mod({current}, {v})"),
+            AssignOp::Pow => format!("xu.power({current}, {v})"),
+            AssignOp::BitAnd => format!("xu.band({current}, {v})"),
+            AssignOp::BitOr => format!("xu.bor({current}, {v})"),
+            AssignOp::BitXor => format!("xu.bxor({current}, {v})"),
+            AssignOp::Shl => format!("xu.shl({current}, {v})"),
+            AssignOp::Shr => format!("xu.shr({current}, {v})"),
+        })
+    }
+
+    /// `Type.field`, `alias.Type.field` and `self.field` with `field` known
+    /// to exist, as a Lua field access.
+    fn static_member(&self, object: &Expr, field: &str) -> Option<String> {
+        match object {
+            Expr::Ident(name, _) => match self.lookup(name)? {
+                Binding { lua, kind: Kind::Type { module, name: ty } } => {
+                    let info = self.infos[*module].types.get(ty)?;
+                    info.statics.contains(field).then(|| dot(&format!("{lua}.statics"), field))
+                }
+                Binding { lua, kind: Kind::Value(_) } if name == "self" => {
+                    let ty = self.self_ty.as_ref()?;
+                    let info = self.infos[self.module].types.get(ty)?;
+                    info.fields.iter().any(|f| f == field).then(|| dot(lua, field))
+                }
+                _ => None,
+            },
+            Expr::Member(m) => {
+                let (lua, module) = self.module_alias(&m.object)?;
+                let info = self.infos[module].types.get(&m.field)?;
+                info.statics.contains(field).then(|| dot(&format!("{}.statics", dot(&lua, &m.field)), field))
+            }
+            _ => None,
+        }
+    }
+
+    fn module_alias(&self, e: &Expr) -> Option<(String, usize)> {
+        match e {
+            Expr::Ident(name, _) => match self.lookup(name)? {
+                Binding { lua, kind: Kind::Module(m) } => Some((lua.clone(), *m)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn ty(&self, e: &Expr) -> Ty {
+        match e {
+            Expr::Int(_) | Expr::Float(_) => Ty::Num,
+            Expr::Str(_) | Expr::InterpolatedString(_) => Ty::Str,
+            Expr::Bool(_) => Ty::Bool,
+            Expr::Ident(name, _) => match self.lookup(name) {
+                Some(Binding { kind: Kind::Value(ty), .. }) => *ty,
+                _ => Ty::Any,
+            },
+            Expr::Group(e) => self.ty(e),
+            Expr::Unary { op: UnaryOp::Neg, expr } if self.ty(expr) == Ty::Num => Ty::Num,
+            Expr::Unary { op: UnaryOp::Not, .. } => Ty::Bool,
+            Expr::Unary { op: UnaryOp::BitNot, .. } => Ty::Num,
+            Expr::Binary { op, left, right } => {
+                let (l, r) = (self.ty(left), self.ty(right));
+                match op {
+                    BinaryOp::Add if l == Ty::Str || r == Ty::Str => Ty::Str,
+                    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod | BinaryOp::Pow
+                        if l == Ty::Num && r == Ty::Num =>
+                    {
+                        Ty::Num
+                    }
+                    BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::Shl | BinaryOp::Shr => Ty::Num,
+                    BinaryOp::Gt
+                    | BinaryOp::Lt
+                    | BinaryOp::Ge
+                    | BinaryOp::Le
+                    | BinaryOp::Eq
+                    | BinaryOp::Ne
+                    | BinaryOp::And
+                    | BinaryOp::Or
+                    | BinaryOp::In => Ty::Bool,
+                    _ => Ty::Any,
+                }
+            }
+            Expr::Compare(_) => Ty::Bool,
+            _ => Ty::Any,
+        }
+    }
+
+    fn exprs(&mut self, es: &[Expr]) -> Result<String, String> {
+        let mut parts = Vec::with_capacity(es.len());
+        for e in es {
+            parts.push(self.expr(e)?);
+        }
+        Ok(parts.join(", "))
+    }
+
+    fn expr(&mut self, e: &Expr) -> Result<String, String> {
+        Ok(match e {
+            Expr::Ident(name, _) => match self.lookup(name) {
+                Some(b) => b.lua.clone(),
+                None if BUILTINS.contains(&name.as_str()) => format!("xu.{name}"),
+                None if xu_syntax::BUILTIN_NAMES.contains(&name.as_str()) => {
+                    return Err(format!("{}: builtin `{name}` is not available in Lua output", self.key()));
+                }
+                None => format!("xu.undefined({})", lua_str(name)),
+            },
+            Expr::Int(i) => lua_int(*i),
+            Expr::Float(f) => lua_float(*f),
+            Expr::Str(s) => lua_str(s),
+            Expr::Bool(b) => b.to_string(),
+            Expr::InterpolatedString(parts) => {
+                let mut out = Vec::with_capacity(parts.len());
+                for p in parts.iter() {
+                    match p {
+                        Expr::Str(s) if s.is_empty() => {}
+                        Expr::Str(s) => out.push(lua_str(s)),
+                        e if self.ty(e) == Ty::Str => out.push(self.expr(e)?),
+                        e => out.push(format!("xu.str({})", self.expr(e)?)),
+                    }
+                }
+                match out.len() {
+                    0 => "\"\"".to_string(),
+                    1 => out.remove(0),
+                    _ => format!("({})", out.join(" .. ")),
+                }
+            }
+            Expr::List(items) => format!("xu.list({})", self.exprs(items)?),
+            Expr::Tuple(items) => format!("xu.tuple({})", self.exprs(items)?),
+            Expr::Dict(entries) => {
+                let mut parts = Vec::with_capacity(entries.len() * 2);
+                for (k, v) in entries.iter() {
+                    parts.push(lua_str(k));
+                    parts.push(self.expr(v)?);
+                }
+                format!("xu.dict_of({})", parts.join(", "))
+            }
+            Expr::Range(r) => {
+                let (a, b) = (self.expr(&r.start)?, self.expr(&r.end)?);
+                format!("xu.range({a}, {b}, {})", r.inclusive)
+            }
+            Expr::IfExpr(ie) => {
+                let c = self.expr(&ie.cond)?;
+                let (t, f) = (self.expr(&ie.then_expr)?, self.expr(&ie.else_expr)?);
+                if surely_truthy(&ie.then_expr) {
+                    format!("({c} and {t} or {f})")
+                } else {
+                    let pad = self.pad();
+                    format!("(function()\n{pad}  if {c} then return {t} end\n{pad}  return {f}\n{pad}end)()")
+                }
+            }
+            Expr::Match(m) => {
+                let v = self.expr(&m.expr)?;
+                let body = self.nested(|e| {
+                    let tmp = e.tmp();
+                    e.line(&format!("local {tmp} = {v}"));
+                    for (pat, arm) in m.arms.iter() {
+                        e.line(&format!("if {} then", pattern_cond(pat, &tmp)));
+                        e.indent += 1;
+                        e.scopes.push(HashMap::new());
+                        e.bind_pattern(pat, &tmp);
+                        let r = e.expr(arm);
+                        e.scopes.pop();
+                        let r = r?;
+                        e.line(&format!("return {r}"));
+                        e.indent -= 1;
+                        e.line("end");
+                    }
+                    if let Some(else_expr) = &m.else_expr {
+                        let r = e.expr(else_expr)?;
+                        e.line(&format!("return {r}"));
+                    }
+                    Ok(())
+                })?;
+                format!("(function()\n{body}{}end)()", self.pad())
+            }
+            Expr::FuncLit(def) => self.function(def, false)?,
+            Expr::StructInit(si) => {
+                let ty = match &si.module {
+                    Some(m) => dot(&self.expr(m)?, &si.ty),
+                    None => self.expr(&Expr::Ident(si.ty.clone(), Default::default()))?,
+                };
+                let mut fields = Vec::new();
+                let mut spreads = Vec::new();
+                for item in si.items.iter() {
+                    match item {
+                        StructInitItem::Field(name, v) => {
+                            fields.push(format!("{} = {}", table_key(name), self.expr(v)?))
+                        }
+                        StructInitItem::Spread(v) => spreads.push(format!(", {}", self.expr(v)?)),
+                    }
+                }
+                format!("xu.new({ty}, {{{}}}{})", fields.join(", "), spreads.concat())
+            }
+            Expr::EnumCtor { module, ty, variant, args } => self.enum_ctor(module.as_deref(), ty, variant, args)?,
+            Expr::Member(m) => match self.static_member(&m.object, &m.field) {
+                Some(field) => field,
+                None => match self.module_alias(&m.object) {
+                    Some((lua, module)) if self.infos[module].exports.contains(&m.field) => dot(&lua, &m.field),
+                    _ => format!("xu.prop({}, {})", self.expr(&m.object)?, lua_str(&m.field)),
+                },
+            },
+            Expr::Index(ix) => format!("xu.index({}, {})", self.expr(&ix.object)?, self.expr(&ix.index)?),
+            Expr::Call(c) => {
+                let f = self.expr(&c.callee)?;
+                let args = self.exprs(&c.args)?;
+                match c.callee.as_ref() {
+                    Expr::Ident(..) | Expr::Member(_) | Expr::Index(_) | Expr::Call(_) | Expr::MethodCall(_) => {
+                        format!("{f}({args})")
+                    }
+                    _ => format!("({f})({args})"),
+                }
+            }
+            Expr::MethodCall(m) => self.method_call(m)?,
+            Expr::Unary { op: UnaryOp::Neg, expr } => format!("(-{})", self.expr(expr)?),
+            Expr::Unary { op: UnaryOp::Not, expr } => format!("(not {})", self.expr(expr)?),
+            Expr::Unary { op: UnaryOp::BitNot, expr } => format!("xu.bnot({})", self.expr(expr)?),
+            Expr::Binary { op, left, right } => {
+                let (l, r) = (self.expr(left)?, self.expr(right)?);
+                let (lt, rt) = (self.ty(left), self.ty(right));
+                let prim = lt.is_primitive() || rt.is_primitive();
+                match op {
+                    BinaryOp::Add => format!("({})", add(&l, lt, &r, rt)),
+                    BinaryOp::Sub => format!("({l} - {r})"),
+                    BinaryOp::Mul => format!("({l} * {r})"),
+                    BinaryOp::Div => format!("xu.div({l}, {r})"),
+                    BinaryOp::Mod => format!("xu.mod({l}, {r})"),
+                    BinaryOp::Pow => format!("xu.power({l}, {r})"),
+                    BinaryOp::BitAnd => format!("xu.band({l}, {r})"),
+                    BinaryOp::BitOr => format!("xu.bor({l}, {r})"),
+                    BinaryOp::BitXor => format!("xu.bxor({l}, {r})"),
+                    BinaryOp::Shl => format!("xu.shl({l}, {r})"),
+                    BinaryOp::Shr => format!("xu.shr({l}, {r})"),
+                    BinaryOp::Gt => format!("({l} > {r})"),
+                    BinaryOp::Lt => format!("({l} < {r})"),
+                    BinaryOp::Ge => format!("({l} >= {r})"),
+                    BinaryOp::Le => format!("({l} <= {r})"),
+                    BinaryOp::Eq if prim => format!("({l} == {r})"),
+                    BinaryOp::Eq => format!("xu.eq({l}, {r})"),
+                    BinaryOp::Ne if prim => format!("({l} ~= {r})"),
+                    BinaryOp::Ne => format!("(not xu.eq({l}, {r}))"),
+                    BinaryOp::And => format!("({l} and {r})"),
+                    BinaryOp::Or => format!("({l} or {r})"),
+                    BinaryOp::Coalesce => format!("xu.coalesce({l}, function() return {r} end)"),
+                    BinaryOp::In => match right.as_ref() {
+                        Expr::Range(rg) => {
+                            let (a, b) = (self.expr(&rg.start)?, self.expr(&rg.end)?);
+                            format!("xu.in_range({l}, {a}, {b}, {})", rg.inclusive)
+                        }
+                        _ => format!("xu.is_in({l}, {r})"),
+                    },
+                }
+            }
+            Expr::Compare(c) => {
+                // 每个操作数只求值一次，某一环不成立即为 false
+                let mut prev = self.tmp();
+                let mut body = format!("local {prev} = {}", self.expr(&c.first)?);
+                for (i, (op, e)) in c.links.iter().enumerate() {
+                    let cur = self.tmp();
+                    let sym = match op {
+                        BinaryOp::Gt => ">",
+                        BinaryOp::Lt => "<",
+                        BinaryOp::Ge => ">=",
+                        _ => "<=",
+                    };
+                    body.push_str(&format!(" local {cur} = {}", self.expr(e)?));
+                    if i + 1 < c.links.len() {
+                        body.push_str(&format!(" if not ({prev} {sym} {cur}) then return false end"));
+                    } else {
+                        body.push_str(&format!(" return {prev} {sym} {cur}"));
+                    }
+                    prev = cur;
+                }
+                format!("(function() {body} end)()")
+            }
+            Expr::Group(e) => format!("({})", self.expr(e)?),
+            Expr::Try(_) => {
+                return Err(format!("{}: the `?` operator is not supported in Lua output", self.key()));
+            }
+            Expr::OptChain(e) => format!("xu.chain(function() return {} end)", self.expr(e)?),
+            Expr::OptLink(e) => format!("xu.link({})", self.expr(e)?),
+            Expr::Error(_) => {
+                return Err(format!("{}: cannot translate an expression with syntax errors", self.key()));
+            }
+        })
+    }
+
+    fn enum_ctor(&mut self, module: Option<&Expr>, ty: &str, variant: &str, args: &[Expr]) -> Result<String, String> {
+        let args_lua = self.exprs(args)?;
+        if module.is_none() && self.lookup(ty).is_none() {
+            match (ty, variant, args.len()) {
+                ("Option", "none", 0) => return Ok("xu.none".to_string()),
+                ("Option", "some", 1) => return Ok(format!("xu.some({args_lua})")),
+                ("Result", "ok", 1) => return Ok(format!("xu.ok({args_lua})")),
+                ("Result", "err", 1) => return Ok(format!("xu.err({args_lua})")),
+                _ => {}
+            }
+        }
+        let (lua, info) = match module {
+            Some(m) => {
+                let info = self.module_alias(m).and_then(|(_, module)| self.infos[module].types.get(ty));
+                (dot(&self.expr(m)?, ty), info)
+            }
+            None => match self.lookup(ty) {
+                Some(Binding { lua, kind: Kind::Type { module, name } }) => {
+                    (lua.clone(), self.infos[*module].types.get(name))
+                }
+                _ => {
+                    let sep = if args.is_empty() { "" } else { ", " };
+                    return Ok(format!("xu.tag({}, {}{sep}{args_lua})", lua_str(ty), lua_str(variant)));
+                }
+            },
+        };
+        if args.is_empty() && info.is_some_and(|t| t.variants.iter().any(|v| v == variant)) {
+            return Ok(dot(&format!("{lua}.variants"), variant));
+        }
+        let sep = if args.is_empty() { "" } else { ", " };
+        Ok(format!("xu.variant({lua}, {}{sep}{args_lua})", lua_str(variant)))
+    }
+
+    fn method_call(&mut self, m: &xu_ir::MethodCallExpr) -> Result<String, String> {
+        let args = self.exprs(&m.args)?;
+        let sep = if m.args.is_empty() { "" } else { ", " };
+        if let Some(f) = self.static_member(&m.receiver, &m.method) {
+            return Ok(format!("{f}({args})"));
+        }
+        if let Some((lua, module)) = self.module_alias(&m.receiver) {
+            if self.infos[module].exports.contains(&m.method) {
+                return Ok(format!("{}({args})", dot(&lua, &m.method)));
+            }
+        }
+        if let (Expr::Ident(name, _), Some(ty)) = (m.receiver.as_ref(), &self.self_ty) {
+            let known = self.infos[self.module].types.get(ty).is_some_and(|t| t.methods.contains(&m.method));
+            if name == "self" && known {
+                if let Some(Binding { lua, kind: Kind::Type { .. } }) = self.lookup(ty) {
+                    let f = dot(&format!("{lua}.methods"), &m.method);
+                    return Ok(format!("{f}(self{sep}{args})"));
+                }
+            }
+        }
+        let recv = self.expr(&m.receiver)?;
+        Ok(format!("xu.call({recv}, {}{sep}{args})", lua_str(&m.method)))
+    }
+}
+
+fn add(l: &str, lt: Ty, r: &str, rt: Ty) -> String {
+    match (lt, rt) {
+        (Ty::Num, Ty::Num) => format!("{l} + {r}"),
+        (Ty::Str, Ty::Str) => format!("{l} .. {r}"),
+        _ => format!("xu.add({l}, {r})"),
+    }
+}
+
+/// Whether `e` is never `false` or unit, so `c and e or f` picks it.
+fn surely_truthy(e: &Expr) -> bool {
+    match e {
+        Expr::Bool(b) => *b,
+        Expr::Int(_)
+        | Expr::Float(_)
+        | Expr::Str(_)
+        | Expr::InterpolatedString(_)
+        | Expr::List(_)
+        | Expr::Tuple(_)
+        | Expr::Dict(_)
+        | Expr::Range(_)
+        | Expr::StructInit(_)
+        | Expr::EnumCtor { .. }
+        | Expr::FuncLit(_) => true,
+        Expr::Binary { op: BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul, .. } => true,
+        Expr::Unary { op: UnaryOp::Neg, .. } => true,
+        Expr::Group(e) => surely_truthy(e),
+        _ => false,
+    }
+}
+
+/// Whether `body` continues the loop it is the body of.
+fn has_continue(body: &[Stmt]) -> bool {
+    body.iter().any(|s| match s {
+        Stmt::Continue => true,
+        Stmt::If(s) => {
+            s.branches.iter().any(|(_, b)| has_continue(b)) || s.else_branch.as_deref().is_some_and(has_continue)
+        }
+        Stmt::Match(s) => {
+            s.arms.iter().any(|(_, b)| has_continue(b)) || s.else_branch.as_deref().is_some_and(has_continue)
+        }
+        Stmt::Block(b) => has_continue(b),
+        _ => false,
+    })
+}
+
+/// The condition under which `pat` matches the value `access`.
+fn pattern_cond(pat: &Pattern, access: &str) -> String {
+    let mut conds = Vec::new();
+    collect_conds(pat, access, &mut conds);
+    if conds.is_empty() { "true".to_string() } else { conds.join(" and ") }
+}
+
+fn collect_conds(pat: &Pattern, access: &str, out: &mut Vec<String>) {
+    match pat {
+        Pattern::Wildcard | Pattern::Bind(_) => {}
+        Pattern::Int(i) => out.push(format!("{access} == {}", lua_int(*i))),
+        Pattern::Float(f) => out.push(format!("{access} == {}", lua_float(*f))),
+        Pattern::Str(s) => out.push(format!("{access} == {}", lua_str(s))),
+        Pattern::Bool(b) => out.push(format!("{access} == {b}")),
+        Pattern::Tuple(items) => {
+            out.push(format!("xu.is_tuple({access}, {})", items.len()));
+            for (i, p) in items.iter().enumerate() {
+                collect_conds(p, &format!("{access}[{}]", i + 1), out);
+            }
+        }
+        Pattern::EnumVariant { ty, variant, args } => {
+            out.push(format!("xu.is_variant({access}, {}, {})", lua_str(ty), lua_str(variant)));
+            for (i, p) in args.iter().enumerate() {
+                collect_conds(p, &format!("{access}[{}]", i + 1), out);
+            }
+        }
+    }
+}
+
+/// A Lua identifier for the Xu name `name`.
+fn lua_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            out.push(c);
+        } else {
+            let _ = write!(out, "_u{:x}_", c as u32);
+        }
+    }
+    if out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    if KEYWORDS.contains(&out.as_str()) || RESERVED.contains(&out.as_str()) {
+        out.push('_');
+    }
+    out
+}
+
+fn is_lua_ident(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+/// The field `name` of the table `obj`.
+fn dot(obj: &str, name: &str) -> String {
+    if is_lua_ident(name) { format!("{obj}.{name}") } else { format!("{obj}[{}]", lua_str(name)) }
+}
+
+/// `name` as the key of a table constructor entry.
+fn table_key(name: &str) -> String {
+    if is_lua_ident(name) { name.to_string() } else { format!("[{}]", lua_str(name)) }
+}
+
+fn lua_int(i: i64) -> String {
+    match i {
+        i64::MIN => "math.mininteger".to_string(),
+        i if i < 0 => format!("({i})"),
+        i => i.to_string(),
+    }
+}
+
+fn lua_float(f: f64) -> String {
+    if f.is_nan() {
+        "(0/0)".to_string()
+    } else if f.is_infinite() {
+        if f > 0.0 { "math.huge".to_string() } else { "(-math.huge)".to_string() }
+    } else if f < 0.0 {
+        format!("({f:?})")
+    } else {
+        format!("{f:?}")
+    }
+}
+
+fn lua_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\x7f' => {
+                let _ = write!(out, "\\{:03}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,1538 @@
+-- Runtime support for Lua generated by `xu codegen <file> lua`.
+--
+-- Needs Lua 5.3 or later (integer subtype, utf8 library, goto). Values map
+-- to Lua as follows: unit is nil, ints and floats are integers and floats,
+-- strings are Lua strings indexed by character, functions are functions.
+-- Lists, tuples, dicts, ranges, struct instances and enum values are tables
+-- told apart by their metatable.
+
+local xu = {}
+
+local mtype, floor, ceil, huge = math.type, math.floor, math.ceil, math.huge
+local concat, unpack = table.concat, table.unpack
+local ulen, uoffset, uchar, ucodes = utf8.len, utf8.offset, utf8.char, utf8.codes
+
+local List = { __name = "list" }
+local Tuple = { __name = "tuple" }
+local Dict = { __name = "dict" }
+local Range = { __name = "range" }
+local Module = { __name = "module" }
+local Builder = { __name = "builder" }
+xu.List, xu.Tuple, xu.Dict, xu.Range, xu.Module = List, Tuple, Dict, Range, Module
+
+-- Raises a script error; `xu.run` reports it like the interpreter does.
+local function fail(msg)
+  error(msg, 0)
+end
+xu.fail = fail
+
+function xu.undefined(name)
+  fail("Undefined identifier: " .. name)
+end
+
+local function type_name(v)
+  local t = type(v)
+  if t == "nil" then return "unit" end
+  if t == "number" then return mtype(v) == "integer" and "int" or "float" end
+  if t == "boolean" then return "bool" end
+  if t == "string" then return "string" end
+  if t == "function" then return "function" end
+  local mt = getmetatable(v)
+  if mt == List then return "list" end
+  if mt == Tuple then return "tuple" end
+  if mt == Dict then return "dict" end
+  if mt == Range then return "range" end
+  if mt == Module then return "module" end
+  if mt == Builder then return "builder" end
+  if mt and (mt.__xu_struct or mt.__xu_enum) then return mt.name end
+  return "unknown"
+end
+xu.type_name = type_name
+
+---------------------------------------------------------------------------
+-- Constructors
+
+function xu.list(...)
+  return setmetatable(table.pack(...), List)
+end
+
+local function list_of(items, n)
+  items.n = n or #items
+  return setmetatable(items, List)
+end
+xu.list_of = list_of
+
+function xu.tuple(...)
+  return setmetatable(table.pack(...), Tuple)
+end
+
+function xu.range(a, b, inclusive)
+  return setmetatable({ a, b, inclusive }, Range)
+end
+
+-- Dicts keep insertion order: `keys` holds the keys in order, `pos` and
+-- `vals` are indexed by the storage key (compound keys are serialized,
+-- floats too so that `1.0` differs from `1` and NaN can be a key).
+local inspect
+
+local function dkey(k)
+  if type(k) == "table" then return "\0" .. inspect(k) end
+  if mtype(k) == "float" then
+    if k ~= k then return "\1NaN" end
+    if k == 0 then k = 0.0 end
+    return "\1" .. string.format("%a", k)
+  end
+  return k
+end
+
+-- Struct keys are copied so later changes to the struct leave the key alone.
+local function freeze(k)
+  local mt = getmetatable(k)
+  if not (mt and mt.__xu_struct) then return k end
+  local copy = {}
+  for _, f in ipairs(mt.fields) do copy[f] = freeze(rawget(k, f)) end
+  return setmetatable(copy, mt)
+end
+
+local function new_dict()
+  return setmetatable({ keys = {}, pos = {}, vals = {}, len = 0 }, Dict)
+end
+
+local function dict_set(d, k, v)
+  local kk = dkey(k)
+  if d.pos[kk] == nil then
+    d.len = d.len + 1
+    d.keys[d.len] = freeze(k)
+    d.pos[kk] = d.len
+  end
+  d.vals[kk] = v
+end
+
+local function dict_get(d, k)
+  local kk = dkey(k)
+  if d.pos[kk] == nil then return false end
+  return true, d.vals[kk]
+end
+
+local function dict_take(d, k)
+  local kk = dkey(k)
+  local i = d.pos[kk]
+  if i == nil then return false end
+  local v = d.vals[kk]
+  table.remove(d.keys, i)
+  d.pos[kk], d.vals[kk] = nil, nil
+  d.len = d.len - 1
+  for j = i, d.len do
+    d.pos[dkey(d.keys[j])] = j
+  end
+  return true, v
+end
+
+function xu.dict_of(...)
+  local d = new_dict()
+  for i = 1, select("#", ...), 2 do
+    local k, v = select(i, ...)
+    dict_set(d, k, v)
+  end
+  return d
+end
+
+-- Struct types hold their field names in order, default thunks, methods
+-- and static members; instances store their fields directly.
+function xu.struct(name, fields)
+  local has = {}
+  for _, f in ipairs(fields) do has[f] = true end
+  return { __xu_struct = true, name = name, fields = fields, has = has, defaults = {}, methods = {}, statics = {} }
+end
+
+function xu.new(T, init, ...)
+  local o = {}
+  for i = 1, select("#", ...) do
+    local base = select(i, ...)
+    for _, f in ipairs(T.fields) do
+      local v = rawget(base, f)
+      if v ~= nil then o[f] = v end
+    end
+  end
+  for k, v in pairs(init) do o[k] = v end
+  for _, f in ipairs(T.fields) do
+    if o[f] == nil and T.defaults[f] then o[f] = T.defaults[f]() end
+  end
+  return setmetatable(o, T)
+end
+
+-- Enum values are `{tag = variant, n = #payload, ...payload}`; variants
+-- without payload are shared.
+function xu.enum(name, variants)
+  local T = { __xu_enum = true, name = name, methods = {}, statics = {}, variants = {} }
+  for _, v in ipairs(variants) do
+    T.variants[v] = setmetatable({ tag = v, n = 0 }, T)
+  end
+  return T
+end
+
+function xu.variant(T, tag, ...)
+  local v = table.pack(...)
+  v.tag = tag
+  return setmetatable(v, T)
+end
+
+local Option = xu.enum("Option", { "none" })
+local Result = xu.enum("Result", {})
+xu.Option, xu.Result = Option, Result
+local none = Option.variants.none
+xu.none = none
+
+local function some(v)
+  return setmetatable({ tag = "some", n = 1, v }, Option)
+end
+xu.some = some
+
+function xu.ok(v)
+  return setmetatable({ tag = "ok", n = 1, v }, Result)
+end
+
+function xu.err(e)
+  return setmetatable({ tag = "err", n = 1, e }, Result)
+end
+
+-- `?.` and `?[`: a link unwraps `Option#some`, and at `none` ends its
+-- chain, which then evaluates to `none`.
+local chain_end = {}
+
+function xu.link(v)
+  if v == none then error(chain_end, 0) end
+  if getmetatable(v) == Option then return v[1] end
+  return v
+end
+
+function xu.chain(f)
+  local ok, v = pcall(f)
+  if ok then return v end
+  if v == chain_end then return none end
+  error(v, 0)
+end
+
+-- `a ?? b`: `b` is only evaluated when `a` is `none`.
+function xu.coalesce(v, f)
+  if v == none then return f() end
+  if getmetatable(v) == Option then return v[1] end
+  return v
+end
+
+-- Values of enums used without a declaration, like `Color#red`.
+local undeclared = {}
+function xu.tag(ty, tag, ...)
+  local T = undeclared[ty]
+  if not T then
+    T = xu.enum(ty, {})
+    undeclared[ty] = T
+  end
+  return xu.variant(T, tag, ...)
+end
+
+function xu.is_variant(v, ty, tag)
+  local mt = getmetatable(v)
+  return mt ~= nil and mt.__xu_enum == true and mt.name == ty and v.tag == tag
+end
+
+function xu.is_tuple(v, n)
+  return getmetatable(v) == Tuple and v.n == n
+end
+
+---------------------------------------------------------------------------
+-- Text
+
+-- Floats print like the interpreter: shortest round trip, positional for
+-- decimal exponents in -6..20 (always with `plain`), without a trailing `.0`.
+local function fmt_float(f, plain)
+  if f ~= f then return "NaN" end
+  if f == huge then return "inf" end
+  if f == -huge then return "-inf" end
+  if f == 0 then return "0" end
+  local s
+  for p = 0, 16 do
+    s = string.format("%." .. p .. "e", f)
+    if tonumber(s) == f then break end
+  end
+  local neg = s:sub(1, 1) == "-"
+  if neg then s = s:sub(2) end
+  local mant, exp = s:match("^([^e]+)e([-+]%d+)$")
+  exp = tonumber(exp)
+  local digits = mant:gsub("%.", ""):gsub("0+$", "")
+  if digits == "" then digits = "0" end
+  local out
+  if not plain and (exp < -6 or exp >= 21) then
+    out = digits:sub(1, 1)
+    if #digits > 1 then out = out .. "." .. digits:sub(2) end
+    out = out .. "e" .. exp
+  elseif exp >= 0 then
+    if #digits <= exp + 1 then
+      out = digits .. string.rep("0", exp + 1 - #digits)
+    else
+      out = digits:sub(1, exp + 1) .. "." .. digits:sub(exp + 2)
+    end
+  else
+    out = "0." .. string.rep("0", -exp - 1) .. digits
+  end
+  return neg and "-" .. out or out
+end
+
+local function str(v, seen)
+  local t = type(v)
+  if t == "string" then return v end
+  if t == "number" then
+    if mtype(v) == "integer" then return tostring(v) end
+    return fmt_float(v)
+  end
+  if t == "nil" then return "()" end
+  if t == "boolean" then return v and "true" or "false" end
+  if t == "function" then return "function" end
+  local mt = getmetatable(v)
+  seen = seen or {}
+  if mt == List or mt == Tuple then
+    local open, close = "[", "]"
+    if mt == Tuple then open, close = "(", ")" end
+    if seen[v] then return open .. "..." .. close end
+    seen[v] = true
+    local parts = {}
+    for i = 1, v.n do parts[i] = str(v[i], seen) end
+    return open .. concat(parts, ",") .. close
+  elseif mt == Dict then
+    if seen[v] then return "{...}" end
+    seen[v] = true
+    local parts = {}
+    for i = 1, v.len do
+      local k = v.keys[i]
+      parts[i] = '"' .. str(k, seen) .. '":' .. str(v.vals[dkey(k)], seen)
+    end
+    return "{" .. concat(parts, ",") .. "}"
+  elseif mt == Range then
+    return "[" .. v[1] .. (v[3] and "..=" or "..") .. v[2] .. "]"
+  elseif mt == Module then
+    return "module"
+  elseif mt == Builder then
+    return concat(v)
+  elseif mt and mt.__xu_struct then
+    if seen[v] then return "{...}" end
+    seen[v] = true
+    local parts = {}
+    for i, f in ipairs(mt.fields) do parts[i] = f .. ":" .. str(rawget(v, f), seen) end
+    return mt.name .. "{" .. concat(parts, ",") .. "}"
+  elseif mt and mt.__xu_enum then
+    if mt == Option and v.tag == "some" then return str(v[1], seen) end
+    return mt.name .. "#" .. v.tag
+  end
+  return "unknown"
+end
+xu.str = str
+
+local function quote(s)
+  return '"' .. s:gsub('[%c"\\]', function(c)
+    if c == '"' then return '\\"' end
+    if c == "\\" then return "\\\\" end
+    if c == "\n" then return "\\n" end
+    if c == "\r" then return "\\r" end
+    if c == "\t" then return "\\t" end
+    return string.format("\\u{%x}", c:byte())
+  end) .. '"'
+end
+
+-- The `inspect` builtin, rendered like the interpreter: a value becomes a
+-- tree of text leaves and groups { open, items, close } whose items are
+-- { prefix, doc }, laid out on one line or, in pretty mode, one item per line.
+local INSPECT_INDENT, INSPECT_WIDTH = 2, 80
+
+local function flat(doc, out)
+  if type(doc) == "string" then
+    out[#out + 1] = doc
+    return out
+  end
+  out[#out + 1] = doc.open
+  for i, item in ipairs(doc.items) do
+    if i > 1 then out[#out + 1] = ", " end
+    out[#out + 1] = item[1]
+    flat(item[2], out)
+  end
+  out[#out + 1] = doc.close
+  return out
+end
+
+local function layout(doc, out, level, column, pretty)
+  local one = concat(flat(doc, {}))
+  if type(doc) == "string" or not pretty or #doc.items == 0 or column + utf8.len(one) <= INSPECT_WIDTH then
+    out[#out + 1] = one
+    return
+  end
+  local inner = string.rep(" ", (level + 1) * INSPECT_INDENT)
+  out[#out + 1] = doc.open .. "\n"
+  for _, item in ipairs(doc.items) do
+    out[#out + 1] = inner .. item[1]
+    layout(item[2], out, level + 1, #inner + utf8.len(item[1]), pretty)
+    out[#out + 1] = ",\n"
+  end
+  out[#out + 1] = string.rep(" ", level * INSPECT_INDENT) .. doc.close
+end
+
+local function inspect_doc(v, depth, opts, path)
+  local t = type(v)
+  if t == "string" then return quote(v) end
+  if t ~= "table" then return str(v) end
+  local mt = getmetatable(v)
+  local function group(open, close, entries)
+    if path[v] then return "<cycle>" end
+    if depth >= opts.max_depth and #entries > 0 then return open .. "..." .. close end
+    path[v] = true
+    local items = {}
+    for i = 1, math.min(#entries, opts.max_items) do
+      items[i] = { entries[i][1], inspect_doc(entries[i][2], depth + 1, opts, path) }
+    end
+    if #entries > opts.max_items then
+      items[#items + 1] = { "", "... (" .. (#entries - opts.max_items) .. " more)" }
+    end
+    path[v] = nil
+    return { open = open, items = items, close = close }
+  end
+  local function seq(from, n)
+    local entries = {}
+    for i = 1, n do entries[i] = { "", from[i] } end
+    return entries
+  end
+  if mt == List then return group("[", "]", seq(v, v.n)) end
+  if mt == Tuple then return group("(", ")", seq(v, v.n)) end
+  if mt == Dict then
+    local entries = {}
+    for i = 1, v.len do
+      local k = v.keys[i]
+      -- Keys are hashable, so they cannot form cycles
+      local key = concat(flat(inspect_doc(k, depth + 1, opts, {}), {}))
+      entries[i] = { key .. ": ", v.vals[dkey(k)] }
+    end
+    return group("{", "}", entries)
+  end
+  if mt == Builder then return "builder(" .. quote(concat(v)) .. ")" end
+  if mt and mt.__xu_struct then
+    local entries = {}
+    for i, f in ipairs(mt.fields) do entries[i] = { f .. ": ", rawget(v, f) } end
+    return group(mt.name .. "{", "}", entries)
+  end
+  if mt and mt.__xu_enum then
+    if v.n == 0 then return mt.name .. "#" .. v.tag end
+    return group(mt.name .. "#" .. v.tag .. "(", ")", seq(v, v.n))
+  end
+  return str(v)
+end
+
+local INSPECT_FLAT = { max_depth = huge, max_items = huge }
+
+-- The one-line form without limits, also used for dict storage keys.
+function inspect(v)
+  return concat(flat(inspect_doc(v, 0, INSPECT_FLAT, {}), {}))
+end
+
+-- A left-out option is nil, or `()` when a later one was given by label.
+local function given(v)
+  if xu.is_tuple(v, 0) then return nil end
+  return v
+end
+
+function xu.inspect(v, pretty, max_depth, max_items)
+  local opts = { max_depth = given(max_depth) or 8, max_items = given(max_items) or 100 }
+  local out = {}
+  layout(inspect_doc(v, 0, opts, {}), out, 0, 0, given(pretty))
+  return concat(out)
+end
+
+---------------------------------------------------------------------------
+-- Operators
+
+function xu.add(a, b)
+  if type(a) == "string" or type(b) == "string" then return str(a) .. str(b) end
+  if type(a) == "number" and type(b) == "number" then return a + b end
+  fail("Cannot add " .. type_name(a) .. " and " .. type_name(b))
+end
+
+-- Integer division and remainder truncate toward zero.
+local function idiv(a, b)
+  local q = a // b
+  if q < 0 and q * b ~= a then q = q + 1 end
+  return q
+end
+
+function xu.div(a, b)
+  if b == 0 then fail("Division by zero") end
+  if mtype(a) == "integer" and mtype(b) == "integer" then return idiv(a, b) end
+  return a / b
+end
+
+function xu.mod(a, b)
+  if b == 0 then fail("Division by zero") end
+  if mtype(a) == "integer" and mtype(b) == "integer" then
+    return a - idiv(a, b) * b
+  end
+  return math.fmod(a, b)
+end
+
+-- `**` stays an int for int operands and a non-negative exponent.
+function xu.power(a, b)
+  if mtype(a) == "integer" and mtype(b) == "integer" and b >= 0 then
+    local r = 1
+    while b > 0 do
+      if b % 2 == 1 then r = r * a end
+      a, b = a * a, b // 2
+    end
+    return r
+  end
+  return (a + 0.0) ^ b
+end
+
+-- Lua's own bitwise operators accept integral floats; Xu's take ints only.
+local function ints(op, a, b)
+  if mtype(a) ~= "integer" or mtype(b) ~= "integer" then
+    fail("Bitwise operator '" .. op .. "' requires int operands, got " .. type_name(a) .. " and " .. type_name(b))
+  end
+end
+
+local function shift_amount(b)
+  if b < 0 or b > 63 then fail("Shift amount must be from 0 to 63, got " .. b) end
+end
+
+function xu.band(a, b) ints("&", a, b) return a & b end
+function xu.bor(a, b) ints("|", a, b) return a | b end
+function xu.bxor(a, b) ints("^", a, b) return a ~ b end
+function xu.shl(a, b) ints("<<", a, b) shift_amount(b) return a << b end
+
+-- Lua's `>>` is a logical shift; Xu's keeps the sign.
+function xu.shr(a, b)
+  ints(">>", a, b)
+  shift_amount(b)
+  if a >= 0 then return a >> b end
+  return ~(~a >> b)
+end
+
+function xu.bnot(a)
+  if mtype(a) ~= "integer" then fail("Unary operator '~' expects int type") end
+  return ~a
+end
+
+local function eq(a, b, seen)
+  if a == b then return true end
+  if type(a) ~= "table" or type(b) ~= "table" then return false end
+  local mt = getmetatable(a)
+  if mt ~= getmetatable(b) or mt == nil then return false end
+  seen = seen or {}
+  local pairs_seen = seen[a]
+  if pairs_seen and pairs_seen[b] then return true end
+  if not pairs_seen then
+    pairs_seen = {}
+    seen[a] = pairs_seen
+  end
+  pairs_seen[b] = true
+  if mt == List then
+    if a.n ~= b.n then return false end
+    for i = 1, a.n do
+      if not eq(a[i], b[i], seen) then return false end
+    end
+    return true
+  elseif mt == Dict then
+    if a.len ~= b.len then return false end
+    for i = 1, a.len do
+      local kk = dkey(a.keys[i])
+      if b.pos[kk] == nil or not eq(a.vals[kk], b.vals[kk], seen) then return false end
+    end
+    return true
+  elseif mt == Range then
+    return a[1] == b[1] and a[2] == b[2] and a[3] == b[3]
+  elseif mt.__xu_enum then
+    -- Like the interpreter, two `Option#some` values are never equal.
+    if mt == Option or a.tag ~= b.tag or a.n ~= b.n then return false end
+    for i = 1, a.n do
+      if not eq(a[i], b[i], seen) then return false end
+    end
+    return true
+  elseif mt.__xu_struct then
+    for _, f in ipairs(mt.fields) do
+      if not eq(rawget(a, f), rawget(b, f), seen) then return false end
+    end
+    return true
+  end
+  return false
+end
+xu.eq = eq
+
+-- `x in a..b` holds for ints, and integral floats, within the bounds.
+local function in_range(x, a, b, inclusive)
+  if type(x) ~= "number" or x ~= math.floor(x) or x < a then return false end
+  if inclusive then return x <= b end
+  return x < b
+end
+xu.in_range = in_range
+
+-- `x in coll`: range bounds, list or tuple elements, dict keys, substrings.
+function xu.is_in(x, coll)
+  if type(coll) == "string" then
+    if type(x) ~= "string" then fail("'in' on a string needs a string, got " .. type_name(x)) end
+    return string.find(coll, x, 1, true) ~= nil
+  end
+  local mt = getmetatable(coll)
+  if mt == Range then return in_range(x, coll[1], coll[2], coll[3]) end
+  if mt == List or mt == Tuple then
+    for i = 1, coll.n do
+      if eq(coll[i], x) then return true end
+    end
+    return false
+  end
+  if mt == Dict then return coll.pos[dkey(x)] ~= nil end
+  fail("'in' expects a range, list, tuple, dict or string, got " .. type_name(coll))
+end
+
+---------------------------------------------------------------------------
+-- Strings
+
+local function char_len(s)
+  return ulen(s) or #s
+end
+
+-- Byte range of the characters `i .. i + n - 1` (0-based), clamped.
+local function char_span(s, i, n)
+  local p = uoffset(s, i + 1)
+  if not p or p > #s then return nil end
+  local q = uoffset(s, i + n + 1)
+  return p, (q or #s + 1) - 1
+end
+
+local function char_at(s, i)
+  if mtype(i) ~= "integer" or i < 0 then return nil end
+  local p, q = char_span(s, i, 1)
+  if not p then return nil end
+  return s:sub(p, q)
+end
+
+local function plain_find(s, sub, init)
+  return string.find(s, sub, init, true)
+end
+
+local function split(s, sep)
+  local out = {}
+  if sep == "" then
+    out[1] = ""
+    for _, c in ucodes(s) do out[#out + 1] = uchar(c) end
+    out[#out + 1] = ""
+    return list_of(out)
+  end
+  local start = 1
+  while true do
+    local p, q = plain_find(s, sep, start)
+    if not p then break end
+    out[#out + 1] = s:sub(start, p - 1)
+    start = q + 1
+  end
+  out[#out + 1] = s:sub(start)
+  return list_of(out)
+end
+
+local function trim(s)
+  return (s:gsub("^%s+", ""):gsub("%s+$", ""))
+end
+
+local function parse_int(s)
+  s = trim(s)
+  if not s:match("^[+-]?%d+$") then return nil end
+  return math.tointeger(tonumber(s))
+end
+
+local function parse_float(s)
+  s = trim(s)
+  local lower = s:lower()
+  if lower:match("^[+-]?inf$") or lower:match("^[+-]?infinity$") then
+    return lower:sub(1, 1) == "-" and -huge or huge
+  end
+  if lower:match("^[+-]?nan$") then return 0 / 0 end
+  if not s:match("^[+-]?%d*%.?%d*[eE]?[+-]?%d*$") or not s:match("%d") then return nil end
+  local v = tonumber(s)
+  return v and v + 0.0
+end
+
+---------------------------------------------------------------------------
+-- Indexing and members
+
+local function list_index(l, i)
+  if mtype(i) ~= "integer" or i < 0 or i >= l.n then fail("Index out of range") end
+  return i + 1
+end
+
+local function slice_bounds(r, n)
+  local a, b = r[1], r[2]
+  if r[3] then b = b + 1 end
+  if a < 0 then a = 0 end
+  if b > n then b = n end
+  return a, b
+end
+
+function xu.index(o, i)
+  local mt = getmetatable(o)
+  if mt == List then
+    if getmetatable(i) == Range then
+      local a, b = slice_bounds(i, o.n)
+      local out = {}
+      for j = a + 1, b do out[#out + 1] = o[j] end
+      return list_of(out, math.max(b - a, 0))
+    end
+    return o[list_index(o, i)]
+  elseif mt == Dict then
+    local found, v = dict_get(o, i)
+    if not found then fail("Key not found: " .. str(i)) end
+    return v
+  elseif mt == Tuple then
+    return o[list_index(o, i)]
+  elseif type(o) == "string" then
+    if getmetatable(i) == Range then
+      local a, b = slice_bounds(i, char_len(o))
+      if b <= a then return "" end
+      local p, q = char_span(o, a, b - a)
+      return p and o:sub(p, q) or ""
+    end
+    local c = char_at(o, i)
+    if c == nil then fail("Index out of range") end
+    return c
+  end
+  fail("Cannot index " .. type_name(o))
+end
+
+function xu.setindex(o, i, v)
+  local mt = getmetatable(o)
+  if mt == List then
+    o[list_index(o, i)] = v
+  elseif mt == Dict then
+    dict_set(o, i, v)
+  else
+    fail("Cannot assign to an index of " .. type_name(o))
+  end
+end
+
+function xu.prop(o, f)
+  local mt = getmetatable(o)
+  if mt and mt.__xu_struct then
+    if not mt.has[f] then fail("Unknown member: " .. f) end
+    return rawget(o, f)
+  elseif mt == Module then
+    local v = rawget(o, f)
+    if v == nil then fail("Unknown member: " .. f) end
+    return v
+  elseif mt and mt.__xu_enum then
+    if f == "name" then return o.tag end
+    if f == "type_name" then return mt.name end
+    if mt == Option and f == "has" then return o.tag == "some" end
+    if mt == Option and f == "none" then return o.tag == "none" end
+  elseif mt == Tuple then
+    if f == "length" then return o.n end
+    local i = math.tointeger(tonumber(f))
+    if i then return o[list_index(o, i)] end
+  elseif mt == List and f == "length" then
+    return o.n
+  elseif mt == Dict then
+    if f == "length" then return o.len end
+    local found, v = dict_get(o, f)
+    if not found then fail("Key not found: " .. f) end
+    return v
+  elseif type(o) == "string" and f == "length" then
+    return char_len(o)
+  end
+  fail("Cannot access member " .. f .. " of " .. type_name(o))
+end
+
+function xu.setprop(o, f, v)
+  local mt = getmetatable(o)
+  if mt and mt.__xu_struct then
+    if not mt.has[f] then fail("Unknown member: " .. f) end
+    rawset(o, f, v)
+  elseif mt == Dict then
+    dict_set(o, f, v)
+  else
+    fail("Cannot assign to member " .. f .. " of " .. type_name(o))
+  end
+end
+
+---------------------------------------------------------------------------
+-- Iteration: `for _, x in xu.each(v) do`. Lists and dicts are iterated as
+-- they were when the loop started; `kv` yields dict entries as tuples.
+
+local function range_iter(a, b, inclusive)
+  local step = a <= b and 1 or -1
+  local cur, done = a, false
+  return function()
+    if done or (not inclusive and cur == b) then return nil end
+    local x = cur
+    if cur == b then done = true else cur = cur + step end
+    return x, x
+  end
+end
+xu.range_iter = range_iter
+
+function xu.each(v, kv)
+  local mt = getmetatable(v)
+  if mt == List then
+    local n = v.n
+    local items = table.move(v, 1, n, 1, {})
+    local i = 0
+    return function()
+      i = i + 1
+      if i <= n then return i, items[i] end
+    end
+  elseif mt == Dict then
+    local n = v.len
+    local keys = table.move(v.keys, 1, n, 1, {})
+    local vals = {}
+    for i = 1, n do vals[i] = v.vals[dkey(keys[i])] end
+    local i = 0
+    return function()
+      i = i + 1
+      if i > n then return nil end
+      if kv then return i, xu.tuple(keys[i], vals[i]) end
+      return i, keys[i]
+    end
+  elseif mt == Range then
+    return range_iter(v[1], v[2], v[3])
+  end
+  fail("Cannot iterate over " .. type_name(v) .. " (expected list, range or dict)")
+end
+
+---------------------------------------------------------------------------
+-- Methods
+
+local function truthy_call(f, ...)
+  local r = f(...)
+  if type(r) ~= "boolean" then fail("Condition must be bool, got " .. type_name(r)) end
+  return r
+end
+
+local function opt(found, v)
+  if found then return some(v) end
+  return none
+end
+
+-- Default sort order: numbers by value, strings by bytes, false before
+-- true; anything else keeps its place.
+local function default_before(a, b)
+  local ta, tb = type(a), type(b)
+  if ta == "number" and tb == "number" then return b < a end
+  if ta == "string" and tb == "string" then return b < a end
+  if ta == "boolean" and tb == "boolean" then return a and not b end
+  return false
+end
+
+-- Stable bottom-up merge sort; `after(x, y)` tells whether `x` must come
+-- after `y`.
+local function merge_sort(items, n, after)
+  local src, dst = items, {}
+  local width = 1
+  while width < n do
+    local k = 1
+    local start = 1
+    while start <= n do
+      local mid = math.min(start + width, n + 1)
+      local stop = math.min(start + 2 * width, n + 1)
+      local i, j = start, mid
+      while i < mid and j < stop do
+        if after(src[i], src[j]) then
+          dst[k] = src[j]
+          j = j + 1
+        else
+          dst[k] = src[i]
+          i = i + 1
+        end
+        k = k + 1
+      end
+      while i < mid do
+        dst[k] = src[i]
+        i, k = i + 1, k + 1
+      end
+      while j < stop do
+        dst[k] = src[j]
+        j, k = j + 1, k + 1
+      end
+      start = stop
+    end
+    src, dst = dst, src
+    width = width * 2
+  end
+  return src
+end
+
+local function sorted_items(l, cmp)
+  local items = table.move(l, 1, l.n, 1, {})
+  local after = default_before
+  if cmp then
+    after = function(a, b)
+      local r = cmp(b, a)
+      if type(r) == "number" then return r < 0 end
+      if type(r) == "boolean" then return r end
+      fail("sort comparator must return int or bool, got " .. type_name(r))
+    end
+  end
+  return merge_sort(items, l.n, after)
+end
+
+local ListMethods = {}
+
+function ListMethods.length(l) return l.n end
+function ListMethods.get(l, i)
+  if mtype(i) ~= "integer" or i < 0 or i >= l.n then return none end
+  return some(l[i + 1])
+end
+ListMethods.get_int = ListMethods.get
+function ListMethods.first(l) return opt(l.n > 0, l[1]) end
+function ListMethods.push(l, v)
+  l.n = l.n + 1
+  l[l.n] = v
+end
+function ListMethods.pop(l)
+  if l.n == 0 then return nil end
+  local v = l[l.n]
+  l[l.n] = nil
+  l.n = l.n - 1
+  return v
+end
+function ListMethods.remove(l, i)
+  local j = list_index(l, i)
+  local v = l[j]
+  table.move(l, j + 1, l.n, j)
+  l[l.n] = nil
+  l.n = l.n - 1
+  return v
+end
+function ListMethods.insert(l, i, v)
+  if mtype(i) ~= "integer" or i < 0 or i > l.n then fail("Index out of range") end
+  table.move(l, i + 1, l.n, i + 2)
+  l[i + 1] = v
+  l.n = l.n + 1
+end
+function ListMethods.clear(l)
+  for i = 1, l.n do l[i] = nil end
+  l.n = 0
+end
+function ListMethods.reverse(l)
+  local i, j = 1, l.n
+  while i < j do
+    l[i], l[j] = l[j], l[i]
+    i, j = i + 1, j - 1
+  end
+end
+function ListMethods.reserve() end
+function ListMethods.contains(l, v)
+  for i = 1, l.n do
+    if eq(l[i], v) then return true end
+  end
+  return false
+end
+function ListMethods.join(l, sep)
+  local parts = {}
+  for i = 1, l.n do parts[i] = str(l[i]) end
+  return concat(parts, sep)
+end
+function ListMethods.map(l, f)
+  local out = {}
+  for i = 1, l.n do out[i] = f(l[i]) end
+  return list_of(out, l.n)
+end
+function ListMethods.filter(l, f)
+  local out, n = {}, 0
+  for i = 1, l.n do
+    if truthy_call(f, l[i]) then
+      n = n + 1
+      out[n] = l[i]
+    end
+  end
+  return list_of(out, n)
+end
+function ListMethods.each(l, f)
+  for i = 1, l.n do f(l[i]) end
+end
+function ListMethods.any(l, f)
+  for i = 1, l.n do
+    if truthy_call(f, l[i]) then return true end
+  end
+  return false
+end
+function ListMethods.all(l, f)
+  for i = 1, l.n do
+    if not truthy_call(f, l[i]) then return false end
+  end
+  return true
+end
+function ListMethods.flat_map(l, f)
+  local out, n = {}, 0
+  for i = 1, l.n do
+    local r = f(l[i])
+    if getmetatable(r) == List then
+      for j = 1, r.n do out[n + j] = r[j] end
+      n = n + r.n
+    else
+      n = n + 1
+      out[n] = r
+    end
+  end
+  return list_of(out, n)
+end
+function ListMethods.zip(l, other)
+  if getmetatable(other) ~= List then fail("zip expects a list, got " .. type_name(other)) end
+  local out = {}
+  local n = math.min(l.n, other.n)
+  for i = 1, n do out[i] = xu.tuple(l[i], other[i]) end
+  return list_of(out, n)
+end
+function ListMethods.enumerate(l)
+  local out = {}
+  for i = 1, l.n do out[i] = xu.tuple(i - 1, l[i]) end
+  return list_of(out, l.n)
+end
+function ListMethods.take(l, n)
+  n = math.min(math.max(n, 0), l.n)
+  return list_of(table.move(l, 1, n, 1, {}), n)
+end
+function ListMethods.drop(l, n)
+  n = math.min(math.max(n, 0), l.n)
+  return list_of(table.move(l, n + 1, l.n, 1, {}), l.n - n)
+end
+function ListMethods.chunk(l, size)
+  if size <= 0 then fail("chunk size must be positive") end
+  local out = {}
+  for i = 1, l.n, size do
+    local m = math.min(size, l.n - i + 1)
+    out[#out + 1] = list_of(table.move(l, i, i + m - 1, 1, {}), m)
+  end
+  return list_of(out)
+end
+function ListMethods.reduce(l, f, acc)
+  for i = 1, l.n do acc = f(acc, l[i]) end
+  return acc
+end
+function ListMethods.find(l, f)
+  for i = 1, l.n do
+    if truthy_call(f, l[i]) then return some(l[i]) end
+  end
+  return none
+end
+function ListMethods.find_index(l, f)
+  for i = 1, l.n do
+    if truthy_call(f, l[i]) then return some(i - 1) end
+  end
+  return none
+end
+function ListMethods.find_or(l, f, default)
+  for i = 1, l.n do
+    if truthy_call(f, l[i]) then return l[i] end
+  end
+  return default
+end
+function ListMethods.repeat_(l, times)
+  local out, n = {}, 0
+  for _ = 1, times do
+    for i = 1, l.n do out[n + i] = l[i] end
+    n = n + l.n
+  end
+  return list_of(out, n)
+end
+ListMethods["repeat"] = ListMethods.repeat_
+ListMethods.repeat_ = nil
+function ListMethods.sort(l, cmp)
+  local items = sorted_items(l, cmp)
+  table.move(items, 1, l.n, 1, l)
+end
+function ListMethods.sorted(l, cmp)
+  return list_of(sorted_items(l, cmp), l.n)
+end
+function ListMethods.sort_by_key(l, f)
+  local keyed = {}
+  for i = 1, l.n do keyed[i] = { f(l[i]), l[i] } end
+  local items = merge_sort(keyed, l.n, function(a, b) return default_before(a[1], b[1]) end)
+  for i = 1, l.n do l[i] = items[i][2] end
+end
+
+local DictMethods = {}
+
+function DictMethods.length(d) return d.len end
+function DictMethods.get(d, k) return opt(dict_get(d, k)) end
+DictMethods.get_int = DictMethods.get
+function DictMethods.insert(d, k, v) dict_set(d, k, v) end
+DictMethods.insert_int = DictMethods.insert
+function DictMethods.contains(d, k) return (dict_get(d, k)) end
+DictMethods.has = DictMethods.contains
+function DictMethods.remove(d, k) return (dict_take(d, k)) end
+function DictMethods.pop(d, k, ...)
+  local found, v = dict_take(d, k)
+  if found then return v end
+  if select("#", ...) > 0 then return (...) end
+  fail("Key not found: " .. str(k))
+end
+function DictMethods.clear(d)
+  d.keys, d.pos, d.vals, d.len = {}, {}, {}, 0
+end
+function DictMethods.keys(d)
+  return list_of(table.move(d.keys, 1, d.len, 1, {}), d.len)
+end
+function DictMethods.values(d)
+  local out = {}
+  for i = 1, d.len do out[i] = d.vals[dkey(d.keys[i])] end
+  return list_of(out, d.len)
+end
+function DictMethods.items(d)
+  local out = {}
+  for i = 1, d.len do
+    local k = d.keys[i]
+    out[i] = xu.tuple(k, d.vals[dkey(k)])
+  end
+  return list_of(out, d.len)
+end
+function DictMethods.merge(d, other)
+  for i = 1, other.len do
+    local k = other.keys[i]
+    dict_set(d, k, other.vals[dkey(k)])
+  end
+end
+DictMethods.update = DictMethods.merge
+function DictMethods.get_or_default(d, k, default)
+  local found, v = dict_get(d, k)
+  if found then return v end
+  return default
+end
+function DictMethods.entry_or_insert(d, k, f)
+  local found, v = dict_get(d, k)
+  if found then return v end
+  v = f()
+  dict_set(d, k, v)
+  return v
+end
+-- Bools, then ints, floats and strings, each by value; compound keys last
+-- in insertion order.
+function DictMethods.keys_sorted(d)
+  local function rank(k)
+    local t = type(k)
+    if t == "boolean" then return 0 end
+    if t == "number" then return mtype(k) == "integer" and 1 or 2 end
+    if t == "string" then return 3 end
+    return 4
+  end
+  local items = table.move(d.keys, 1, d.len, 1, {})
+  items = merge_sort(items, d.len, function(a, b)
+    local ra, rb = rank(a), rank(b)
+    if ra ~= rb then return ra > rb end
+    if ra == 0 then return a and not b end
+    if ra == 4 then return false end
+    return b < a
+  end)
+  return list_of(items, d.len)
+end
+
+local StrMethods = {}
+
+function StrMethods.length(s) return char_len(s) end
+StrMethods.char_length = StrMethods.length
+function StrMethods.byte_length(s) return #s end
+function StrMethods.get(s, i)
+  local c = char_at(s, i)
+  return opt(c ~= nil, c)
+end
+StrMethods.get_int = StrMethods.get
+StrMethods.split = split
+StrMethods.split_lazy = split
+function StrMethods.chars(s)
+  local out = {}
+  for _, c in ucodes(s) do out[#out + 1] = uchar(c) end
+  return list_of(out)
+end
+StrMethods.graphemes = StrMethods.chars
+function StrMethods.code_points(s)
+  local out = {}
+  for _, c in ucodes(s) do out[#out + 1] = c end
+  return list_of(out)
+end
+function StrMethods.to_int(s)
+  local v = parse_int(s)
+  if v == nil then fail("Invalid integer: " .. s) end
+  return v
+end
+function StrMethods.to_float(s)
+  local v = parse_float(s)
+  if v == nil then fail("Invalid float: " .. s) end
+  return v
+end
+function StrMethods.try_to_int(s)
+  local v = parse_int(s)
+  return opt(v ~= nil, v)
+end
+function StrMethods.try_to_float(s)
+  local v = parse_float(s)
+  return opt(v ~= nil, v)
+end
+function StrMethods.contains(s, sub) return plain_find(s, sub, 1) ~= nil end
+function StrMethods.starts_with(s, prefix) return s:sub(1, #prefix) == prefix end
+function StrMethods.ends_with(s, suffix) return suffix == "" or s:sub(-#suffix) == suffix end
+function StrMethods.find(s, sub)
+  local p = plain_find(s, sub, 1)
+  if not p then return none end
+  return some(char_len(s:sub(1, p - 1)))
+end
+function StrMethods.replace(s, from, to)
+  local p, q = plain_find(s, from, 1)
+  if not p then return s end
+  return s:sub(1, p - 1) .. to .. s:sub(q + 1)
+end
+StrMethods.trim = trim
+function StrMethods.trim_start(s) return (s:gsub("^%s+", "")) end
+function StrMethods.trim_end(s) return (s:gsub("%s+$", "")) end
+function StrMethods.to_upper(s) return s:upper() end
+function StrMethods.to_lower(s) return s:lower() end
+function StrMethods.substr(s, start, n)
+  if start < 0 or n < 0 then return "" end
+  local p, q = char_span(s, start, n)
+  if not p or n == 0 then return "" end
+  return s:sub(p, q)
+end
+function StrMethods.format(s, d)
+  for i = 1, d.len do
+    local k = d.keys[i]
+    local needle = "{" .. str(k) .. "}"
+    local repl = str(d.vals[dkey(k)])
+    local out, start = {}, 1
+    while true do
+      local p, q = plain_find(s, needle, start)
+      if not p then break end
+      out[#out + 1] = s:sub(start, p - 1)
+      out[#out + 1] = repl
+      start = q + 1
+    end
+    out[#out + 1] = s:sub(start)
+    s = concat(out)
+  end
+  return s
+end
+
+local IntMethods = {}
+function IntMethods.to_string(i) return tostring(i) end
+function IntMethods.abs(i) return math.abs(i) end
+function IntMethods.is_even(i) return i % 2 == 0 end
+function IntMethods.is_odd(i) return i % 2 ~= 0 end
+function IntMethods.to_base(i, base)
+  if base < 2 or base > 36 then fail("Base must be between 2 and 36") end
+  if i == 0 then return "0" end
+  local digits = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ"
+  local out = {}
+  local n = math.abs(i)
+  while n > 0 do
+    local d = n % base
+    table.insert(out, 1, digits:sub(d + 1, d + 1))
+    n = n // base
+  end
+  if i < 0 then table.insert(out, 1, "-") end
+  return concat(out)
+end
+
+-- Rounds half away from zero and stays a float.
+local function round(f)
+  if f >= 0 then return floor(f + 0.5) + 0.0 end
+  return ceil(f - 0.5) + 0.0
+end
+
+local FloatMethods = {}
+function FloatMethods.to_string(f) return fmt_float(f, true) end
+function FloatMethods.abs(f) return math.abs(f) end
+function FloatMethods.to_int(f)
+  if f ~= f or f == huge or f == -huge then return 0 end
+  return math.tointeger(f >= 0 and floor(f) or ceil(f))
+end
+function FloatMethods.round(f, digits)
+  if digits == nil then return round(f) end
+  local factor = 10.0 ^ digits
+  return round(f * factor) / factor
+end
+function FloatMethods.floor(f) return floor(f) + 0.0 end
+function FloatMethods.ceil(f) return ceil(f) + 0.0 end
+
+local BoolMethods = {}
+BoolMethods["not"] = function(b) return not b end
+function BoolMethods.to_string(b) return b and "true" or "false" end
+
+local TupleMethods = {}
+function TupleMethods.length(t) return t.n end
+
+-- Methods of every enum, and the Option and Result combinators.
+local EnumMethods = {}
+function EnumMethods.name(e) return e.tag end
+function EnumMethods.type_name(e) return getmetatable(e).name end
+
+local function success(e)
+  local ty = getmetatable(e)
+  if ty == Option then return e.tag == "some" end
+  if ty == Result then return e.tag == "ok" end
+  fail("Unsupported method for " .. ty.name)
+end
+
+function EnumMethods.to_string(e)
+  if getmetatable(e) == Option and e.tag == "some" then return "Option#some(" .. str(e[1]) .. ")" end
+  return getmetatable(e).name .. "#" .. e.tag
+end
+function EnumMethods.has(e)
+  if getmetatable(e) ~= Option then fail("Unsupported method has for " .. getmetatable(e).name) end
+  return e.tag == "some"
+end
+function EnumMethods.get(e)
+  if getmetatable(e) ~= Option then fail("Unsupported method get for " .. getmetatable(e).name) end
+  if e.tag ~= "some" then fail("Called get() on None value") end
+  return e[1]
+end
+EnumMethods["or"] = function(e, default)
+  if success(e) then return e[1] end
+  return default
+end
+function EnumMethods.or_else(e, f)
+  if success(e) then return e[1] end
+  return f()
+end
+function EnumMethods.map(e, f)
+  if not success(e) then return e end
+  return setmetatable({ tag = e.tag, n = 1, f(e[1]) }, getmetatable(e))
+end
+function EnumMethods.map_err(e, f)
+  if getmetatable(e) ~= Result then fail("Unsupported method map_err for " .. getmetatable(e).name) end
+  if e.tag == "ok" then return e end
+  return xu.err(f(e[1]))
+end
+EnumMethods["then"] = function(e, f)
+  if not success(e) then return e end
+  return f(e[1])
+end
+function EnumMethods.each(e, f)
+  if success(e) then f(e[1]) end
+end
+function EnumMethods.filter(e, f)
+  if getmetatable(e) ~= Option then fail("Unsupported method filter for " .. getmetatable(e).name) end
+  if e.tag == "some" and f(e[1]) == true then return e end
+  return none
+end
+
+-- Calls method `m` of `o`: the struct's or enum's own methods first, then
+-- a function stored in a field of that name, then the builtin methods.
+function xu.call(o, m, ...)
+  local t = type(o)
+  local methods
+  if t == "string" then
+    methods = StrMethods
+  elseif t == "number" then
+    methods = mtype(o) == "integer" and IntMethods or FloatMethods
+  elseif t == "boolean" then
+    methods = BoolMethods
+  elseif t == "table" then
+    local mt = getmetatable(o)
+    if mt == List then
+      methods = ListMethods
+    elseif mt == Dict then
+      methods = DictMethods
+    elseif mt == Tuple then
+      methods = TupleMethods
+    elseif mt == Module then
+      local f = rawget(o, m)
+      if f == nil then fail("Unknown member: " .. m) end
+      return f(...)
+    elseif mt and (mt.__xu_struct or mt.__xu_enum) then
+      local f = mt.methods[m]
+      if f then return f(o, ...) end
+      if mt.__xu_struct then
+        f = rawget(o, m)
+        if type(f) == "function" then return f(...) end
+      else
+        methods = EnumMethods
+      end
+    end
+  end
+  local f = methods and methods[m]
+  if f == nil then fail("Unknown method " .. m .. " for " .. type_name(o)) end
+  return f(o, ...)
+end
+
+---------------------------------------------------------------------------
+-- Builtins
+
+-- Like `println`, `print` writes each argument on a line of its own.
+function xu.print(...)
+  for i = 1, select("#", ...) do io.write(str((select(i, ...))), "\n") end
+end
+xu.println = xu.print
+
+function xu.to_text(v) return str(v) end
+
+function xu.abs(v)
+  if type(v) ~= "number" then fail("abs expects number") end
+  return math.abs(v)
+end
+
+local function minmax(name, a, b, pick_a)
+  if mtype(a) == "integer" and mtype(b) == "integer" then
+    return pick_a and a or b
+  end
+  if type(a) ~= "number" or type(b) ~= "number" then fail(name .. " expects numbers") end
+  return (pick_a and a or b) + 0.0
+end
+
+function xu.max(a, b) return minmax("max", a, b, a >= b) end
+function xu.min(a, b) return minmax("min", a, b, a <= b) end
+
+function xu.clamp(x, lo, hi)
+  if lo > hi then fail("clamp: lower bound is greater than upper bound") end
+  local all_int = mtype(x) == "integer" and mtype(lo) == "integer" and mtype(hi) == "integer"
+  local v = x < lo and lo or (x > hi and hi or x)
+  return all_int and v or v + 0.0
+end
+
+local function float_fn(name, f)
+  xu[name] = function(x)
+    if type(x) ~= "number" then fail(name .. " expects number") end
+    return f(x + 0.0)
+  end
+end
+float_fn("sin", math.sin)
+float_fn("cos", math.cos)
+float_fn("tan", math.tan)
+float_fn("sqrt", math.sqrt)
+float_fn("log", math.log)
+float_fn("exp", math.exp)
+float_fn("log2", function(x) return math.log(x, 2) end)
+
+function xu.pow(a, b) return (a + 0.0) ^ b end
+
+local function gcd(a, b)
+  a, b = math.abs(a), math.abs(b)
+  while b ~= 0 do a, b = b, a % b end
+  return a
+end
+function xu.gcd(a, b) return gcd(a, b) end
+function xu.lcm(a, b)
+  if a == 0 or b == 0 then return 0 end
+  a, b = math.abs(a), math.abs(b)
+  return a // gcd(a, b) * b
+end
+
+function xu.parse_int(v)
+  if mtype(v) == "integer" then return v end
+  if type(v) == "number" then return FloatMethods.to_int(v) end
+  if type(v) ~= "string" then fail("parse_int expects text or number") end
+  local i = parse_int(v)
+  if i then return i end
+  local f = parse_float(v)
+  if f then return FloatMethods.to_int(f) end
+  fail("parse_int expects numeric text")
+end
+
+function xu.parse_float(v)
+  if type(v) == "number" then return v + 0.0 end
+  if type(v) ~= "string" then fail("parse_float expects text or number") end
+  local f = parse_float(v)
+  if f == nil then fail("parse_float expects numeric text") end
+  return f
+end
+
+function xu.contains(s, sub) return StrMethods.contains(s, sub) end
+function xu.starts_with(s, prefix) return StrMethods.starts_with(s, prefix) end
+function xu.ends_with(s, suffix) return StrMethods.ends_with(s, suffix) end
+
+function xu.time_unix() return os.time() end
+function xu.time_millis() return os.time() * 1000 end
+function xu.mono_micros() return floor(os.clock() * 1e6) end
+function xu.mono_nanos() return floor(os.clock() * 1e9) end
+
+function xu.rand(upper)
+  if upper == nil then return math.random(0, math.maxinteger) end
+  if upper <= 0 then fail("rand upper bound must be > 0") end
+  return math.random(0, FloatMethods.to_int(upper + 0.0) - 1)
+end
+
+local next_id = 1
+function xu.gen_id()
+  local id = next_id
+  next_id = next_id + 1
+  return id
+end
+
+function xu.os_args()
+  local out = {}
+  for i = 0, #(arg or {}) do out[#out + 1] = arg[i] end
+  return list_of(out)
+end
+
+function xu.env_get(name) return os.getenv(name) or "" end
+
+function xu.builder_new() return setmetatable({}, Builder) end
+xu.builder_new_cap = xu.builder_new
+function xu.builder_push(b, v) b[#b + 1] = str(v) end
+function xu.builder_finalize(b)
+  local s = concat(b)
+  for i = #b, 1, -1 do b[i] = nil end
+  return s
+end
+
+function xu.__builtin_assert(cond, ...)
+  if type(cond) ~= "boolean" then fail("__builtin_assert expects bool") end
+  if not cond then
+    if select("#", ...) > 0 then fail(str((...))) end
+    fail("Assertion failed")
+  end
+end
+
+function xu.__builtin_assert_eq(a, b, ...)
+  if not eq(a, b) then
+    if select("#", ...) > 0 then fail(str((...))) end
+    fail("Assertion failed: " .. str(a) .. " != " .. str(b))
+  end
+end
+
+function xu.__set_from_list(l)
+  local d = new_dict()
+  for i = 1, l.n do
+    local v = l[i]
+    if mtype(v) ~= "integer" and type(v) ~= "string" then fail("Set items must be int or string") end
+    dict_set(d, v, nil)
+  end
+  return d
+end
+
+function xu.dict() return new_dict() end
+
+function xu.gc() collectgarbage() end
+
+-- `bench` blocks only run under `xu bench`.
+function xu.__bench() end
+
+---------------------------------------------------------------------------
+-- Modules and entry
+
+xu.modules = {}
+local loaded = {}
+
+-- Runs module `key` once and returns its exports. A module imported again
+-- while it runs (an import cycle) gets the table filled when it finishes.
+function xu.import(key)
+  local m = loaded[key]
+  if m then return m end
+  m = setmetatable({}, Module)
+  loaded[key] = m
+  for k, v in pairs(xu.modules[key]()) do rawset(m, k, v) end
+  return m
+end
+
+xu.main_invoked = false
+
+-- Runs the entry chunk, reporting a script error on stderr with exit
+-- status 1 like `xu run`.
+function xu.run(body)
+  local ok, e = xpcall(body, function(msg)
+    if type(msg) == "string" then return msg end
+    return str(msg)
+  end)
+  io.stdout:flush()
+  if not ok then
+    io.stderr:write("RuntimeError: ", e, "\n")
+    os.exit(1)
+  end
+end
+
+return xu
@@ -0,0 +1,307 @@
+//! Translation of Xu programs to other languages.
+//!
+//! `xu codegen <entry> lua` translates the entry and every module reachable
+//! through `use` into one Lua chunk. The chunk needs a small runtime, which
+//! is either `require`d as `xu_runtime` or injected into the chunk. Programs
+//! using builtins the runtime lacks (files, workers, channels, ...) or the
+//! standard library are rejected.
+//!
+//! `xu codegen <entry> c` does the same for a C99 file, whose runtime is the
+//! header `xu_runtime.h`. It needs only libc and libm.
+//!
+//! An injected Lua runtime is cut down to the parts the program can reach.
+
+mod c;
+mod lua;
+mod shake;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use xu_parser::{Expr, FuncDef, Stmt, Visibility};
+use xu_syntax::Severity;
+
+use crate::analyzer::resolve_import_path;
+use crate::frontend::{Driver, ParsedFile};
+
+/// Builtins the runtimes provide; calls to the others are rejected.
+const BUILTINS: &[&str] = &[
+    "print",
+    "println",
+    "gen_id",
+    "gc",
+    "time_unix",
+    "time_millis",
+    "mono_micros",
+    "mono_nanos",
+    "abs",
+    "max",
+    "min",
+    "rand",
+    "to_text",
+    "inspect",
+    "dict",
+    "parse_int",
+    "parse_float",
+    "builder_new",
+    "builder_new_cap",
+    "builder_push",
+    "builder_finalize",
+    "os_args",
+    "env_get",
+    "contains",
+    "starts_with",
+    "ends_with",
+    "sin",
+    "cos",
+    "tan",
+    "sqrt",
+    "log",
+    "pow",
+    "log2",
+    "exp",
+    "clamp",
+    "gcd",
+    "lcm",
+    "__builtin_assert",
+    "__builtin_assert_eq",
+    "__set_from_list",
+    "__bench",
+];
+
+/// A language `xu codegen` translates to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodegenTarget {
+    /// Lua 5.3 or later.
+    Lua,
+    /// C99, built with `cc out.c -lm`.
+    C,
+}
+
+impl CodegenTarget {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "lua" => Some(CodegenTarget::Lua),
+            "c" => Some(CodegenTarget::C),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CodegenTarget::Lua => "lua",
+            CodegenTarget::C => "c",
+        }
+    }
+
+    /// Source of the runtime generated programs use.
+    pub fn runtime(self) -> &'static str {
+        match self {
+            CodegenTarget::Lua => lua::RUNTIME,
+            CodegenTarget::C => c::RUNTIME,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CodegenOptions {
+    /// Include the runtime in the output instead of loading it.
+    pub inject_runtime: bool,
+}
+
+/// A parsed module of the program being translated.
+struct SourceModule {
+    /// Path relative to the entry's directory when below it.
+    key: String,
+    module: xu_ir::Module,
+    /// Module index of each `use` path.
+    imports: HashMap<String, usize>,
+}
+
+impl Driver {
+    /// Translates the program whose entry file is `entry` to `target`.
+    pub fn codegen(
+        &self,
+        entry: &str,
+        strict: bool,
+        target: CodegenTarget,
+        opts: &CodegenOptions,
+    ) -> Result<String, String> {
+        let entry_path = Path::new(entry).canonicalize().map_err(|e| format!("Failed to read file {entry}: {e}"))?;
+        let root = entry_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        // Analysis of the entry covers its imports.
+        let parsed = self.parse_file(entry, strict)?;
+        check_errors(&parsed)?;
+
+        let mut modules =
+            vec![SourceModule { key: display_key(&root, &entry_path), module: parsed.module, imports: HashMap::new() }];
+        let mut by_path: HashMap<PathBuf, usize> = HashMap::from([(entry_path.clone(), 0)]);
+        let mut queue = vec![(0, entry_path)];
+        while let Some((idx, path)) = queue.pop() {
+            let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            let uses: Vec<String> = modules[idx]
+                .module
+                .stmts
+                .iter()
+                .filter_map(|s| match s {
+                    Stmt::Use(u) => Some(u.path.clone()),
+                    _ => None,
+                })
+                .collect();
+            for use_path in uses {
+                if use_path.starts_with("std/") {
+                    return Err(format!(
+                        "{}: `use \"{use_path}\"`: the standard library is not available in generated code",
+                        modules[idx].key
+                    ));
+                }
+                let target_path = resolve_import_path(&base_dir, &use_path)
+                    .map_err(|_| format!("{}: cannot resolve import {use_path}", modules[idx].key))?;
+                let to = match by_path.get(&target_path) {
+                    Some(&to) => to,
+                    None => {
+                        let text = fs::read_to_string(&target_path)
+                            .map_err(|e| format!("Failed to read file {}: {e}", target_path.display()))?;
+                        let parsed = self.parse_text_no_analyze(&target_path.to_string_lossy(), &text)?;
+                        check_errors(&parsed)?;
+                        let to = modules.len();
+                        modules.push(SourceModule {
+                            key: display_key(&root, &target_path),
+                            module: parsed.module,
+                            imports: HashMap::new(),
+                        });
+                        by_path.insert(target_path.clone(), to);
+                        queue.push((to, target_path));
+                        to
+                    }
+                };
+                modules[idx].imports.insert(use_path, to);
+            }
+        }
+
+        match target {
+            CodegenTarget::Lua => lua::emit(&modules, opts),
+            CodegenTarget::C => c::emit(&modules, opts),
+        }
+    }
+}
+
+fn check_errors(parsed: &ParsedFile) -> Result<(), String> {
+    let errors: Vec<_> = parsed.diagnostics.iter().filter(|d| matches!(d.severity, Severity::Error)).cloned().collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(xu_syntax::render_diagnostics(&parsed.source, &errors))
+}
+
+fn display_key(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
+
+/// What other modules need to know of a module.
+#[derive(Default)]
+struct ModuleInfo {
+    types: HashMap<String, TypeInfo>,
+    /// Names `alias.name` reaches, in declaration order.
+    exports: Vec<String>,
+}
+
+#[derive(Clone, Default)]
+struct TypeInfo {
+    fields: Vec<String>,
+    variants: Vec<String>,
+    methods: HashSet<String>,
+    /// Static fields and static methods.
+    statics: HashSet<String>,
+    static_fields: HashSet<String>,
+}
+
+impl ModuleInfo {
+    fn collect(module: &xu_ir::Module) -> Self {
+        let mut info = ModuleInfo::default();
+        let mut export = |name: &str, vis: Visibility| {
+            if vis == Visibility::Public && !name.starts_with('_') && !info.exports.iter().any(|n| n == name) {
+                info.exports.push(name.to_string());
+            }
+        };
+        for s in &module.stmts {
+            match s {
+                Stmt::FuncDef(def) => export(&def.name, def.vis),
+                Stmt::StructDef(def) => export(&def.name, def.vis),
+                Stmt::EnumDef(def) => export(&def.name, def.vis),
+                Stmt::Assign(a) if a.decl.is_some() => {
+                    if let Expr::Ident(name, _) = &a.target {
+                        export(name, a.vis);
+                    }
+                }
+                _ => {}
+            }
+        }
+        for s in &module.stmts {
+            match s {
+                Stmt::StructDef(def) => {
+                    info.types.insert(def.name.clone(), TypeInfo::of_struct(def));
+                }
+                Stmt::EnumDef(def) => {
+                    info.types.entry(def.name.clone()).or_default().variants = def.variants.to_vec();
+                }
+                Stmt::DoesBlock(block) => {
+                    if let Some(ty) = info.types.get_mut(&block.target) {
+                        ty.add_methods(&block.target, &block.funcs);
+                    }
+                }
+                Stmt::FuncDef(def) => {
+                    let Some(owner) = method_owner(&def.name) else {
+                        continue;
+                    };
+                    if let Some(ty) = info.types.get_mut(owner) {
+                        ty.add_methods(owner, std::slice::from_ref(def));
+                    }
+                }
+                _ => {}
+            }
+        }
+        info
+    }
+}
+
+impl TypeInfo {
+    fn of_struct(def: &xu_ir::StructDef) -> Self {
+        let mut ty = TypeInfo {
+            fields: def.fields.iter().map(|f| f.name.clone()).collect(),
+            static_fields: def.static_fields.iter().map(|f| f.name.clone()).collect(),
+            ..TypeInfo::default()
+        };
+        ty.statics.extend(ty.static_fields.iter().cloned());
+        ty.add_methods(&def.name, &def.methods);
+        ty
+    }
+
+    fn add_methods(&mut self, ty: &str, defs: &[Rc<FuncDef>]) {
+        for def in defs {
+            match method_name(ty, &def.name) {
+                Some((true, name)) => self.statics.insert(name.to_string()),
+                Some((false, name)) => self.methods.insert(name.to_string()),
+                None => false,
+            };
+        }
+    }
+}
+
+/// Splits the parser's `__static__T__m` and `__method__T__m` into whether
+/// the method is static and its name.
+fn method_name<'a>(ty: &str, name: &'a str) -> Option<(bool, &'a str)> {
+    let of_ty = |rest: &'a str| rest.strip_prefix(ty).and_then(|r| r.strip_prefix("__"));
+    if let Some(m) = name.strip_prefix("__static__").and_then(of_ty) {
+        return Some((true, m));
+    }
+    name.strip_prefix("__method__").and_then(of_ty).map(|m| (false, m))
+}
+
+/// The type a top-level `func (self: T) m()` is a method of.
+fn method_owner(name: &str) -> Option<&str> {
+    let rest = name.strip_prefix("__method__").or_else(|| name.strip_prefix("__static__"))?;
+    rest.split_once("__").map(|(ty, _)| ty)
+}
@@ -0,0 +1,198 @@
+//! Removal of the runtime helpers a generated program cannot reach, for
+//! `--inject-runtime` Lua output.
+//!
+//! The runtime is split into its top-level statements. A statement defining
+//! names (`local function f`, `function xu.f`, `T.m = ...`) is kept when a
+//! kept part of the output refers to one of them; any other statement runs
+//! for its effect and is always kept. The generated program is the root.
+//! Builtin methods are looked up by name at run time, so `T.m` is also kept
+//! when a kept part mentions the string `"m"`, and metamethods (`T.__eq`)
+//! whenever `T` is. C output needs none of this, as the compiler already
+//! drops the runtime's unused `static` functions.
+
+use std::collections::{HashMap, HashSet};
+
+/// The statements of `runtime` that `program` can reach, in their order.
+pub(super) fn lua_runtime(runtime: &str, program: &str) -> String {
+    let chunks = chunks(runtime);
+    let defs: Vec<Vec<String>> = chunks.iter().map(|c| defines(c)).collect();
+    let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+    // Chunks defining `T.k` by key `k`, for lookups by name.
+    let mut by_key: HashMap<&str, Vec<(&str, usize)>> = HashMap::new();
+    for (i, names) in defs.iter().enumerate() {
+        for name in names {
+            by_name.entry(name).or_default().push(i);
+            if let Some((table, key)) = name.split_once('.') {
+                if table != "xu" {
+                    by_key.entry(key).or_default().push((table, i));
+                }
+            }
+        }
+    }
+
+    let mut kept = vec![false; chunks.len()];
+    let mut tables: HashSet<String> = HashSet::new();
+    let mut strings: HashSet<String> = HashSet::new();
+    let mut work = scan(program);
+    for (i, names) in defs.iter().enumerate() {
+        // Comment-only chunks other than the header are dropped.
+        let code = chunks[i].lines().any(|l| !l.trim().is_empty() && !l.starts_with("--"));
+        if names.is_empty() && (code || i == 0) {
+            kept[i] = true;
+            work.extend(scan(chunks[i]));
+        }
+    }
+    while let Some(r) = work.pop() {
+        let mut reach: Vec<usize> = Vec::new();
+        match r {
+            Ref::Name(name) => {
+                if let Some(is) = by_name.get(name.as_str()) {
+                    reach.extend(is);
+                }
+                if !name.contains('.') && tables.insert(name.clone()) {
+                    for (key, entries) in &by_key {
+                        let wanted = key.starts_with("__") || strings.contains(*key);
+                        reach.extend(entries.iter().filter(|(t, _)| wanted && *t == name).map(|&(_, i)| i));
+                    }
+                }
+            }
+            Ref::Str(s) => {
+                if let Some(entries) = by_key.get(s.as_str()) {
+                    reach.extend(entries.iter().filter(|(t, _)| tables.contains(*t)).map(|&(_, i)| i));
+                }
+                strings.insert(s);
+            }
+        }
+        for i in reach {
+            if !kept[i] {
+                kept[i] = true;
+                work.extend(scan(chunks[i]));
+            }
+        }
+    }
+    chunks.iter().zip(&kept).filter(|(_, k)| **k).map(|(c, _)| *c).collect()
+}
+
+/// The top-level statements of `src`, each with the comment lines right
+/// above it and the blank lines after it.
+fn chunks(src: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let (mut start, mut at) = (0, 0);
+    let mut prev_comment = false;
+    for line in src.split_inclusive('\n') {
+        let starts = line.starts_with(|c: char| !c.is_whitespace()) && !continues(line);
+        if starts && !prev_comment && at > start {
+            out.push(&src[start..at]);
+            start = at;
+        }
+        prev_comment = line.starts_with("--");
+        at += line.len();
+    }
+    if at > start {
+        out.push(&src[start..at]);
+    }
+    out
+}
+
+/// Whether a line at column 0 continues the statement before it.
+fn continues(line: &str) -> bool {
+    let word = line.split(|c: char| !c.is_ascii_alphanumeric() && c != '_').next().unwrap_or("");
+    matches!(word, "end" | "else" | "elseif" | "until") || line.starts_with(['}', ')', ']'])
+}
+
+/// The names a chunk's first statement defines.
+fn defines(chunk: &str) -> Vec<String> {
+    let Some(line) = chunk.lines().find(|l| !l.starts_with("--") && !l.trim().is_empty()) else {
+        return Vec::new();
+    };
+    if let Some(rest) = line.strip_prefix("local function ") {
+        return vec![path(rest)];
+    }
+    if let Some(rest) = line.strip_prefix("function ") {
+        return vec![path(rest)];
+    }
+    let line = line.strip_prefix("local ").unwrap_or(line);
+    let lhs = match line.split_once('=') {
+        Some((lhs, rhs)) if !rhs.starts_with('=') => lhs,
+        // `local x` declares without assigning.
+        _ if line.len() < 80 && line.trim_end().chars().all(|c| is_ident(c) || c == ',' || c == ' ') => line,
+        _ => return Vec::new(),
+    };
+    let names: Vec<String> = lhs.split(',').map(|n| n.trim().replace("[\"", ".").replace("\"]", "")).collect();
+    if names.iter().all(|n| !n.is_empty() && n.chars().all(|c| is_ident(c) || c == '.')) { names } else { Vec::new() }
+}
+
+/// The dotted name at the start of `s`, with `T:m` spelled `T.m`.
+fn path(s: &str) -> String {
+    s.chars().take_while(|&c| is_ident(c) || c == '.' || c == ':').map(|c| if c == ':' { '.' } else { c }).collect()
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+enum Ref {
+    /// `a` or `a.b`, for `a.b.c` and `a:b`.
+    Name(String),
+    Str(String),
+}
+
+/// The names and string literals in Lua code, skipping comments.
+fn scan(code: &str) -> Vec<Ref> {
+    let b = code.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < b.len() {
+        match b[i] {
+            b'-' if b.get(i + 1) == Some(&b'-') => {
+                if code[i + 2..].starts_with("[[") {
+                    i = code[i..].find("]]").map_or(b.len(), |e| i + e + 2);
+                } else {
+                    i = code[i..].find('\n').map_or(b.len(), |e| i + e);
+                }
+            }
+            b'[' if b.get(i + 1) == Some(&b'[') => {
+                i = code[i..].find("]]").map_or(b.len(), |e| i + e + 2);
+            }
+            q @ (b'"' | b'\'') => {
+                let mut s = String::new();
+                i += 1;
+                while i < b.len() && b[i] != q {
+                    if b[i] == b'\\' {
+                        i += 1;
+                    }
+                    if i < b.len() {
+                        s.push(b[i] as char);
+                    }
+                    i += 1;
+                }
+                i += 1;
+                out.push(Ref::Str(s));
+            }
+            c if c.is_ascii_digit() => {
+                while i < b.len() && (is_ident(b[i] as char) || b[i] == b'.') {
+                    i += 1;
+                }
+            }
+            c if is_ident(c as char) => {
+                let start = i;
+                while i < b.len() && is_ident(b[i] as char) {
+                    i += 1;
+                }
+                let head = &code[start..i];
+                out.push(Ref::Name(head.to_string()));
+                if matches!(b.get(i), Some(b'.' | b':')) && b.get(i + 1).is_some_and(|&c| is_ident(c as char)) {
+                    let field_start = i + 1;
+                    let mut j = field_start;
+                    while j < b.len() && is_ident(b[j] as char) {
+                        j += 1;
+                    }
+                    out.push(Ref::Name(format!("{head}.{}", &code[field_start..j])));
+                    i = j;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    out
+}
@@ -0,0 +1,177 @@
+//! Compile-time evaluation of `const` declarations.
+//!
+//! A constant's initializer may use literals, other constants of the module,
+//! public constants of imported modules (`alias.NAME`) and the operators on
+//! them. It folds to the value the runtime would compute, which the analyzer
+//! exports and the compiler loads in place of the name.
+
+use std::collections::HashMap;
+
+use xu_ir::{BinaryOp, Expr, UnaryOp};
+
+/// The value of a constant.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl ConstValue {
+    /// The literal expression of the value.
+    pub fn to_expr(&self) -> Expr {
+        match self {
+            ConstValue::Int(v) => Expr::Int(*v),
+            ConstValue::Float(v) => Expr::Float(*v),
+            ConstValue::Bool(v) => Expr::Bool(*v),
+            ConstValue::Str(v) => Expr::Str(v.clone()),
+        }
+    }
+}
+
+/// Values of constants by name.
+pub(crate) type ConstMap = HashMap<String, ConstValue>;
+
+/// The constants visible to a module.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Consts {
+    /// Constants the module has declared so far.
+    pub own: ConstMap,
+    /// Public constants of imported modules, by the alias of the import.
+    pub modules: HashMap<String, ConstMap>,
+}
+
+impl Consts {
+    /// The value of the constant `expr` names: `NAME` or `alias.NAME`.
+    pub fn get(&self, expr: &Expr) -> Option<&ConstValue> {
+        match expr {
+            Expr::Ident(name, _) => self.own.get(name),
+            Expr::Member(m) => match m.object.as_ref() {
+                Expr::Ident(alias, _) => self.modules.get(alias)?.get(&m.field),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Folds `expr` to its value, or `None` if it is not a constant
+    /// expression or its evaluation would fail at runtime.
+    pub fn eval(&self, expr: &Expr) -> Option<ConstValue> {
+        match expr {
+            Expr::Int(v) => Some(ConstValue::Int(*v)),
+            Expr::Float(v) => Some(ConstValue::Float(*v)),
+            Expr::Bool(v) => Some(ConstValue::Bool(*v)),
+            Expr::Str(v) => Some(ConstValue::Str(v.clone())),
+            Expr::Ident(..) | Expr::Member(_) => self.get(expr).cloned(),
+            Expr::Group(e) => self.eval(e),
+            Expr::Unary { op, expr } => unary(*op, self.eval(expr)?),
+            Expr::Binary { op, left, right } => binary(*op, self.eval(left)?, self.eval(right)?),
+            Expr::Compare(c) => {
+                let mut left = self.eval(&c.first)?;
+                let mut all = true;
+                for (op, e) in c.links.iter() {
+                    let right = self.eval(e)?;
+                    // 与运行时一样，首个不成立的比较之后不再比较
+                    all = all && matches!(binary(*op, left, right.clone())?, ConstValue::Bool(true));
+                    left = right;
+                }
+                Some(ConstValue::Bool(all))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn unary(op: UnaryOp, v: ConstValue) -> Option<ConstValue> {
+    match (op, v) {
+        (UnaryOp::Neg, ConstValue::Int(a)) => a.checked_neg().map(ConstValue::Int),
+        (UnaryOp::Neg, ConstValue::Float(a)) => Some(ConstValue::Float(-a)),
+        (UnaryOp::Not, ConstValue::Bool(a)) => Some(ConstValue::Bool(!a)),
+        (UnaryOp::BitNot, ConstValue::Int(a)) => Some(ConstValue::Int(!a)),
+        _ => None,
+    }
+}
+
+/// Int arithmetic saturates and mixed operands compute in float, as at
+/// runtime; division by zero and out-of-range shifts do not fold.
+fn binary(op: BinaryOp, left: ConstValue, right: ConstValue) -> Option<ConstValue> {
+    use BinaryOp::*;
+    match (left, right) {
+        (ConstValue::Int(a), ConstValue::Int(b)) => Some(match op {
+            Add => ConstValue::Int(a.saturating_add(b)),
+            Sub => ConstValue::Int(a.saturating_sub(b)),
+            Mul => ConstValue::Int(a.saturating_mul(b)),
+            Div => ConstValue::Int(a.checked_div(b)?),
+            Mod => ConstValue::Int(a.checked_rem(b)?),
+            Pow if b >= 0 => {
+                let exp = u32::try_from(b).unwrap_or(u32::MAX - 1 + (b & 1) as u32);
+                ConstValue::Int(a.saturating_pow(exp))
+            }
+            Pow => ConstValue::Float((a as f64).powf(b as f64)),
+            BitAnd => ConstValue::Int(a & b),
+            BitOr => ConstValue::Int(a | b),
+            BitXor => ConstValue::Int(a ^ b),
+            Shl if (0..64).contains(&b) => ConstValue::Int(a << b),
+            Shr if (0..64).contains(&b) => ConstValue::Int(a >> b),
+            Eq => ConstValue::Bool(a == b),
+            Ne => ConstValue::Bool(a != b),
+            Gt => ConstValue::Bool(a > b),
+            Lt => ConstValue::Bool(a < b),
+            Ge => ConstValue::Bool(a >= b),
+            Le => ConstValue::Bool(a <= b),
+            _ => return None,
+        }),
+        (ConstValue::Float(a), ConstValue::Float(b)) => float(op, a, b),
+        (ConstValue::Int(a), ConstValue::Float(b)) => float(op, a as f64, b),
+        (ConstValue::Float(a), ConstValue::Int(b)) => float(op, a, b as f64),
+        (ConstValue::Bool(a), ConstValue::Bool(b)) => Some(ConstValue::Bool(match op {
+            And => a && b,
+            Or => a || b,
+            Eq => a == b,
+            Ne => a != b,
+            _ => return None,
+        })),
+        (ConstValue::Str(a), ConstValue::Str(b)) => Some(match op {
+            Add => ConstValue::Str(a + &b),
+            Eq => ConstValue::Bool(a == b),
+            Ne => ConstValue::Bool(a != b),
+            Gt => ConstValue::Bool(a > b),
+            Lt => ConstValue::Bool(a < b),
+            Ge => ConstValue::Bool(a >= b),
+            Le => ConstValue::Bool(a <= b),
+            _ => return None,
+        }),
+        // `+` with one string operand concatenates the other's text.
+        (ConstValue::Str(a), b) if op == Add => Some(ConstValue::Str(a + &text(&b)?)),
+        (a, ConstValue::Str(b)) if op == Add => Some(ConstValue::Str(text(&a)? + &b)),
+        _ => None,
+    }
+}
+
+fn float(op: BinaryOp, a: f64, b: f64) -> Option<ConstValue> {
+    use BinaryOp::*;
+    Some(match op {
+        Add => ConstValue::Float(a + b),
+        Sub => ConstValue::Float(a - b),
+        Mul => ConstValue::Float(a * b),
+        Div if b != 0.0 => ConstValue::Float(a / b),
+        Mod if b != 0.0 => ConstValue::Float(a % b),
+        Pow => ConstValue::Float(a.powf(b)),
+        Gt => ConstValue::Bool(a > b),
+        Lt => ConstValue::Bool(a < b),
+        Ge => ConstValue::Bool(a >= b),
+        Le => ConstValue::Bool(a <= b),
+        _ => return None,
+    })
+}
+
+/// How a concatenated int or bool prints; floats are left to the runtime's
+/// formatting.
+fn text(v: &ConstValue) -> Option<String> {
+    match v {
+        ConstValue::Int(a) => Some(a.to_string()),
+        ConstValue::Bool(a) => Some(a.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,104 @@
+//! On-disk cache of compiled files, keyed by content hash.
+//!
+//! With [`Driver::with_cache_dir`](crate::Driver::with_cache_dir),
+//! [`Driver::compile_file`](crate::Driver::compile_file) keeps each compiled
+//! file in `<dir>/<hash>.xbc`. The hash covers the source text, its path, the
+//! compile options, the prelude, predefined names and the build of `xu`. An entry also lists
+//! the files the analysis read (imports and the prelude) with a hash of their
+//! contents, and is only used while all of them are unchanged. Only compiles
+//! without diagnostics are stored, so a hit has nothing to report.
+//!
+//! Entry layout, in the [image](xu_ir::image) encoding:
+//!
+//! ```text
+//! MAGIC IMAGE_VERSION pkg_version [(path, content_hash)] Executable
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use xu_ir::image::{IMAGE_VERSION, ImageReader, ImageWriter};
+use xu_ir::{Executable, stable_hash64_bytes};
+
+const MAGIC: &[u8; 8] = b"XUXBC\0\0\0";
+
+/// Extension of cache entries.
+pub const CACHE_EXTENSION: &str = "xbc";
+
+/// Hash identifying one compile of `input` as `path`.
+pub(crate) fn key(
+    path: &str,
+    input: &str,
+    strict: bool,
+    contracts: bool,
+    prelude: Option<&Path>,
+    predefs: &[String],
+) -> u64 {
+    let mut w = ImageWriter::new();
+    w.put(&IMAGE_VERSION);
+    w.put(&env!("CARGO_PKG_VERSION").to_string());
+    w.put(&path.to_string());
+    w.put(&strict);
+    w.put(&contracts);
+    w.put(&prelude.map(|p| p.to_string_lossy().into_owned()));
+    w.put(&predefs.to_vec());
+    w.bytes(input.as_bytes());
+    stable_hash64_bytes(&w.finish())
+}
+
+pub(crate) fn entry_path(dir: &Path, key: u64) -> PathBuf {
+    dir.join(format!("{key:016x}.{CACHE_EXTENSION}"))
+}
+
+fn content_hash(path: &str) -> Option<u64> {
+    fs::read(path).ok().map(|bytes| stable_hash64_bytes(&bytes))
+}
+
+/// The cached executable for `key`, if there is one and every file it
+/// depends on is unchanged. Unreadable or stale entries count as misses.
+pub(crate) fn load(dir: &Path, key: u64) -> Option<Executable> {
+    let bytes = fs::read(entry_path(dir, key)).ok()?;
+    let mut r = ImageReader::new(&bytes);
+    if r.bytes(MAGIC.len()).ok()? != &MAGIC[..] {
+        return None;
+    }
+    let version: u32 = r.get().ok()?;
+    let pkg: String = r.get().ok()?;
+    if version != IMAGE_VERSION || pkg != env!("CARGO_PKG_VERSION") {
+        return None;
+    }
+    let deps: Vec<(String, u64)> = r.get().ok()?;
+    if deps.iter().any(|(path, hash)| content_hash(path) != Some(*hash)) {
+        return None;
+    }
+    let executable: Executable = r.get().ok()?;
+    r.is_empty().then_some(executable)
+}
+
+/// Writes the entry for `key`. The file is written under a temporary name
+/// and renamed, so concurrent runs never read a partial entry.
+pub(crate) fn store(dir: &Path, key: u64, deps: &[PathBuf], executable: &Executable) -> Result<(), String> {
+    let deps: Vec<(String, u64)> = deps
+        .iter()
+        .map(|p| {
+            let path = p.to_string_lossy().into_owned();
+            let hash = content_hash(&path).ok_or_else(|| format!("Failed to read {path}"))?;
+            Ok((path, hash))
+        })
+        .collect::<Result<_, String>>()?;
+    let mut w = ImageWriter::new();
+    w.bytes(MAGIC);
+    w.put(&IMAGE_VERSION);
+    w.put(&env!("CARGO_PKG_VERSION").to_string());
+    w.put(&deps);
+    w.put(executable);
+
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let path = entry_path(dir, key);
+    let tmp = path.with_extension(format!("{CACHE_EXTENSION}.{}.tmp", std::process::id()));
+    fs::write(&tmp, w.finish()).map_err(|e| format!("{}: {e}", tmp.display()))?;
+    fs::rename(&tmp, &path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("{}: {e}", path.display())
+    })
+}
@@ -3,18 +3,26 @@
 //!
 //!
 
+use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
 use xu_lexer::{Lexer, normalize_source};
 use xu_parser::Parser;
 use xu_syntax::{Diagnostic, SourceFile, SourceId, Token};
 
-use crate::analyzer::{ImportCache, analyze_module};
+use crate::analyzer::{ImportCache, analyze_module, collect_prelude_exports, module_consts};
 use crate::bytecode_compiler;
+use crate::exec_cache;
 
 pub struct Driver {
     pub cache: Arc<RwLock<ImportCache>>,
+    /// Whether `@requires` / `@ensures` are compiled to runtime checks.
+    pub contracts: bool,
+    /// Where [`Driver::compile_file`] caches compiled files; `None` disables
+    /// the cache.
+    pub cache_dir: Option<PathBuf>,
 }
 
 impl xu_ir::Frontend for Driver {
@@ -24,9 +32,9 @@ impl xu_ir::Frontend for Driver {
         input: &str,
     ) -> Result<xu_ir::CompiledUnit, String> {
         let parsed = self.parse_text_no_analyze(path, input)?;
-        let bc = bytecode_compiler::compile_module(&parsed.module);
+        let bc = bytecode_compiler::compile_module(&parsed.module, &parsed.source, &parsed.tokens, HashMap::new());
         let executable = xu_ir::Executable::Bytecode(xu_ir::Program {
-            module: parsed.module.clone(),
+            module: parsed.module,
             bytecode: bc,
         });
         Ok(xu_ir::CompiledUnit {
@@ -50,9 +58,47 @@ impl Driver {
     pub fn new() -> Self {
         Self {
             cache: Arc::new(RwLock::new(ImportCache::default())),
+            contracts: true,
+            cache_dir: None,
         }
     }
 
+    /// Checked builds (the default) compile contract annotations to
+    /// assertions; release builds strip them.
+    pub fn with_contracts(mut self, enabled: bool) -> Self {
+        self.contracts = enabled;
+        self
+    }
+
+    /// Caches what [`Driver::compile_file`] compiles in `dir`, usually a
+    /// project's `target/.xu_cache`, and reuses it while the file and
+    /// everything its analysis read are unchanged.
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Makes the public names of the module at `path` predefined in every
+    /// module analyzed by this driver, mirroring `Runtime::set_prelude`.
+    pub fn set_prelude(&self, path: &str) -> Result<(), String> {
+        let abs = std::path::Path::new(path)
+            .canonicalize()
+            .map_err(|e| format!("Failed to read prelude {path}: {e}"))?;
+        let input = fs::read_to_string(&abs)
+            .map_err(|e| format!("Failed to read prelude {path}: {e}"))?;
+        let parsed = self.parse_text_no_analyze(path, &input)?;
+        let exports = collect_prelude_exports(abs, &parsed.module);
+        self.cache.write().unwrap().prelude = Some(exports);
+        Ok(())
+    }
+
+    /// Makes `names` predefined in every module analyzed by this driver, for
+    /// globals the runtime defines besides the builtins, such as
+    /// `Runtime::extra_builtin_names`.
+    pub fn add_predefs<S: Into<String>>(&self, names: impl IntoIterator<Item = S>) {
+        self.cache.write().unwrap().predefs.extend(names.into_iter().map(Into::into));
+    }
+
     pub fn lex_file(&self, path: &str) -> Result<LexedFile, String> {
         let input =
             fs::read_to_string(path).map_err(|e| format!("Failed to read file {path}: {e}"))?;
@@ -86,28 +132,31 @@ impl Driver {
         ),
         String,
     > {
-        let t1 = std::time::Instant::now();
+        let t1 = Stamp::now();
         let normalized = normalize_source(input);
-        let t2 = std::time::Instant::now();
+        let t2 = Stamp::now();
         let source = SourceFile::new(SourceId(0), path.to_string(), normalized.text);
         let lex = Lexer::new(source.text.as_str()).lex();
-        let t3 = std::time::Instant::now();
+        let t3 = Stamp::now();
         let bump = bumpalo::Bump::new();
-        let parse = Parser::new(source.text.as_str(), &lex.tokens, &bump).parse();
-        let t4 = std::time::Instant::now();
-
+        let parse = Parser::new(source.text.as_str(), &lex.tokens, &bump)
+            .with_contracts(self.contracts)
+            .parse();
         let mut diagnostics = lex.diagnostics;
         diagnostics.extend(parse.diagnostics);
+        let mut module = parse.module;
+        crate::labels::resolve_labels(&mut module, path, &mut diagnostics);
+        let t4 = Stamp::now();
 
         Ok((
             source,
             lex.tokens,
-            parse.module,
+            module,
             diagnostics,
             Timings {
-                normalize_us: (t2 - t1).as_micros(),
-                lex_us: (t3 - t2).as_micros(),
-                parse_us: (t4 - t3).as_micros(),
+                normalize_us: t2.micros_since(t1),
+                lex_us: t3.micros_since(t2),
+                parse_us: t4.micros_since(t3),
                 analyze_us: 0,
             },
         ))
@@ -120,14 +169,71 @@ impl Driver {
     }
 
     pub fn compile_file(&self, path: &str, strict: bool) -> Result<CompiledFile, String> {
+        let input =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read file {path}: {e}"))?;
+        let Some(dir) = &self.cache_dir else {
+            return self.compile_text(path, &input, strict);
+        };
+        let key = self.cache_key(path, &input, strict);
+        if let Some(executable) = exec_cache::load(dir, key) {
+            let lexed = self.lex_text(path, &input)?;
+            return Ok(CompiledFile {
+                path: lexed.path,
+                source: lexed.source,
+                tokens: lexed.tokens,
+                executable,
+                diagnostics: Vec::new(),
+            });
+        }
+        let compiled = self.compile_text(path, &input, strict)?;
+        if compiled.diagnostics.is_empty() {
+            let cache = self.cache.read().unwrap();
+            let prelude = cache.prelude.as_ref().map(|p| p.path.clone());
+            let deps: Vec<PathBuf> = cache.modules.keys().cloned().chain(prelude).collect();
+            // A cache that cannot be written only costs the next run a compile.
+            let _ = exec_cache::store(dir, key, &deps, &compiled.executable);
+        }
+        Ok(compiled)
+    }
+
+    /// The file [`Driver::compile_file`] would cache `input` in, compiled
+    /// as `path`, or `None` without a cache directory.
+    pub fn cache_entry_path(&self, path: &str, input: &str, strict: bool) -> Option<PathBuf> {
+        let dir = self.cache_dir.as_ref()?;
+        Some(exec_cache::entry_path(dir, self.cache_key(path, input, strict)))
+    }
+
+    fn cache_key(&self, path: &str, input: &str, strict: bool) -> u64 {
+        let cache = self.cache.read().unwrap();
+        let prelude = cache.prelude.as_ref().map(|p| p.path.as_path());
+        exec_cache::key(path, input, strict, self.contracts, prelude, &cache.predefs)
+    }
+
+    /// Like [`Driver::compile_file`], for source text already in memory.
+    pub fn compile_text(&self, path: &str, input: &str, strict: bool) -> Result<CompiledFile, String> {
+        self.compile_text_with_predefs(path, input, strict, &[])
+    }
+
+    /// [`Driver::compile_text`] with `extra_predefs` known to the analysis as
+    /// global names, such as functions a host defines in the runtime.
+    pub fn compile_text_with_predefs(
+        &self,
+        path: &str,
+        input: &str,
+        strict: bool,
+        extra_predefs: &[&str],
+    ) -> Result<CompiledFile, String> {
         let ParsedFile {
             path,
             source,
             tokens,
             module,
             diagnostics,
-        } = self.parse_file(path.as_ref(), strict)?;
-        let bc = bytecode_compiler::compile_module(&module);
+        } = self.parse_text_with_predefs(path, input, strict, extra_predefs)?;
+        // The analysis cached the constants of the imported modules.
+        let base_dir = Path::new(&path).parent().unwrap_or(Path::new("."));
+        let imports = module_consts(&module.stmts, base_dir, &self.cache).modules;
+        let bc = bytecode_compiler::compile_module(&module, &source, &tokens, imports);
         Ok(CompiledFile {
             path,
             source,
@@ -200,7 +306,7 @@ impl Driver {
         let (source, tokens, mut module, mut diagnostics, mut tm) =
             self.lex_parse_inner(path, input)?;
         let mut import_stack = Vec::new();
-        let t4 = std::time::Instant::now();
+        let t4 = Stamp::now();
         let analysis = analyze_module(
             &source,
             &tokens,
@@ -210,7 +316,7 @@ impl Driver {
             &mut import_stack,
             &[],
         );
-        let t5 = std::time::Instant::now();
+        let t5 = Stamp::now();
         diagnostics.extend(analysis);
 
         let pf = ParsedFile {
@@ -220,7 +326,7 @@ impl Driver {
             module,
             diagnostics,
         };
-        tm.analyze_us = (t5 - t4).as_micros();
+        tm.analyze_us = t5.micros_since(t4);
         Ok((pf, tm))
     }
 }
@@ -253,3 +359,30 @@ pub struct Timings {
     pub parse_us: u128,
     pub analyze_us: u128,
 }
+
+/// A point in time for [`Timings`]. Without the `sys` feature there is no
+/// clock and every timing is zero.
+#[derive(Clone, Copy)]
+struct Stamp(#[cfg(feature = "sys")] std::time::Instant);
+
+impl Stamp {
+    #[cfg(feature = "sys")]
+    fn now() -> Self {
+        Stamp(std::time::Instant::now())
+    }
+
+    #[cfg(not(feature = "sys"))]
+    fn now() -> Self {
+        Stamp()
+    }
+
+    #[cfg(feature = "sys")]
+    fn micros_since(self, earlier: Stamp) -> u128 {
+        (self.0 - earlier.0).as_micros()
+    }
+
+    #[cfg(not(feature = "sys"))]
+    fn micros_since(self, _earlier: Stamp) -> u128 {
+        0
+    }
+}
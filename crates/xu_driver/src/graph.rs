@@ -0,0 +1,540 @@
+//! Module import graph and function call graph of a project.
+//!
+//! Starting from an entry file, every module reachable through `use` is
+//! parsed and its imports and functions recorded. Calls are resolved
+//! statically: `f()` to a function of the module or of one of its imports,
+//! `alias.f()` to a function of the imported module, `Type.f()` and
+//! `alias.Type.f()` to a static method and `self.f()` to a method of the
+//! enclosing type. Calls through any other receiver depend on runtime values
+//! and are left out, as are builtins.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use xu_parser::{Expr, FuncDef, Stmt, StructInitItem};
+
+use crate::analyzer::resolve_import_path;
+use crate::frontend::Driver;
+use crate::references::push_json_str;
+
+/// Name of the pseudo-function holding a module's top-level statements.
+pub const TOP_LEVEL: &str = "<top>";
+
+/// One module of the project.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModuleNode {
+    /// Path relative to the entry's directory when below it.
+    pub path: String,
+    /// `false` if the import could not be resolved or read.
+    pub found: bool,
+    pub lines: usize,
+    /// Functions and methods, not counting [`TOP_LEVEL`].
+    pub functions: usize,
+}
+
+/// One function, method (`Type.name`) or module top level.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionNode {
+    pub module: usize,
+    pub name: String,
+}
+
+/// The graphs of a project; the entry is module 0. Edges are index pairs,
+/// sorted and without duplicates.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProjectGraph {
+    pub modules: Vec<ModuleNode>,
+    pub imports: Vec<(usize, usize)>,
+    pub functions: Vec<FunctionNode>,
+    pub calls: Vec<(usize, usize)>,
+}
+
+impl Driver {
+    /// Builds the import and call graphs of the project whose entry file is
+    /// `entry`. Modules with syntax errors contribute what parses.
+    pub fn graph(&self, entry: &str) -> Result<ProjectGraph, String> {
+        let entry_path = Path::new(entry)
+            .canonicalize()
+            .map_err(|e| format!("Failed to read file {entry}: {e}"))?;
+        let root = entry_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let mut builder = Builder { graph: ProjectGraph::default(), by_path: HashMap::new(), root };
+        let mut modules = Vec::new();
+        let mut queue = vec![entry_path];
+        while let Some(path) = queue.pop() {
+            let idx = builder.module(&path);
+            let Ok(text) = fs::read_to_string(&path) else {
+                if idx == 0 {
+                    return Err(format!("Failed to read file {entry}"));
+                }
+                continue;
+            };
+            let parsed = self.parse_text_no_analyze(&path.to_string_lossy(), &text)?;
+            let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            let mut aliases = HashMap::new();
+            for s in &parsed.module.stmts {
+                let Stmt::Use(u) = s else { continue };
+                let target = resolve_import_path(&base_dir, &u.path).unwrap_or_else(|_| PathBuf::from(&u.path));
+                let known = builder.by_path.contains_key(&target);
+                let to = builder.module(&target);
+                if !known && target.is_file() {
+                    queue.push(target);
+                }
+                builder.graph.imports.push((idx, to));
+                let alias = u.alias.clone().unwrap_or_else(|| xu_ir::infer_module_alias(&u.path));
+                aliases.insert(alias, to);
+            }
+            let m = &mut builder.graph.modules[idx];
+            m.found = true;
+            m.lines = parsed.source.text.as_str().lines().count();
+            modules.push((idx, parsed.module, aliases));
+        }
+        modules.sort_by_key(|m| m.0);
+
+        // Declare every function before resolving calls, which may point
+        // into modules parsed later.
+        let mut funcs: Vec<HashMap<String, usize>> = vec![HashMap::new(); builder.graph.modules.len()];
+        let mut bodies: Vec<(usize, Option<String>, Vec<&[Stmt]>)> = Vec::new();
+        for (idx, module, _) in &modules {
+            let mut top = Vec::new();
+            for s in &module.stmts {
+                let defs: Vec<&Rc<FuncDef>> = match s {
+                    Stmt::FuncDef(def) => vec![def],
+                    Stmt::StructDef(def) => def.methods.iter().collect(),
+                    Stmt::DoesBlock(block) => block.funcs.iter().collect(),
+                    _ => {
+                        top.push(std::slice::from_ref(s));
+                        continue;
+                    }
+                };
+                for def in defs {
+                    let (owner, name) = demangle(&def.name);
+                    let display = match owner {
+                        Some(ty) => format!("{ty}.{name}"),
+                        None => name.to_string(),
+                    };
+                    let fid = builder.function(*idx, &display);
+                    funcs[*idx].insert(display, fid);
+                    bodies.push((fid, owner.map(str::to_string), vec![&def.body[..]]));
+                }
+            }
+            builder.graph.modules[*idx].functions = funcs[*idx].len();
+            let fid = builder.function(*idx, TOP_LEVEL);
+            bodies.push((fid, None, top));
+        }
+
+        let imports_of = |m: usize| builder.graph.imports.iter().filter(move |e| e.0 == m).map(|e| e.1);
+        let mut calls = BTreeSet::new();
+        for (fid, owner, stmts) in &bodies {
+            let m = builder.graph.functions[*fid].module;
+            let aliases = &modules.iter().find(|x| x.0 == m).expect("parsed module").2;
+            let mut callees = Vec::new();
+            for s in stmts.iter().flat_map(|b| b.iter()) {
+                collect_stmt(s, &mut callees);
+            }
+            for callee in callees {
+                let target = match callee {
+                    Callee::Free(name) => funcs[m].get(&name).copied().or_else(|| {
+                        imports_of(m)
+                            .filter(|_| !name.starts_with('_'))
+                            .find_map(|to| funcs[to].get(&name).copied())
+                    }),
+                    Callee::Qualified(recv, name) => match aliases.get(&recv) {
+                        Some(&to) => funcs[to].get(&name).copied(),
+                        None => std::iter::once(m)
+                            .chain(imports_of(m))
+                            .find_map(|to| funcs[to].get(&format!("{recv}.{name}")).copied()),
+                    },
+                    Callee::Static(alias, ty, name) => {
+                        aliases.get(&alias).and_then(|&to| funcs[to].get(&format!("{ty}.{name}")).copied())
+                    }
+                    Callee::SelfMethod(name) => {
+                        owner.as_ref().and_then(|ty| funcs[m].get(&format!("{ty}.{name}")).copied())
+                    }
+                };
+                if let Some(to) = target {
+                    calls.insert((*fid, to));
+                }
+            }
+        }
+        let mut graph = builder.graph;
+        graph.calls = calls.into_iter().collect();
+        graph.imports.sort_unstable();
+        graph.imports.dedup();
+        Ok(graph)
+    }
+}
+
+struct Builder {
+    graph: ProjectGraph,
+    by_path: HashMap<PathBuf, usize>,
+    root: PathBuf,
+}
+
+impl Builder {
+    fn module(&mut self, path: &Path) -> usize {
+        if let Some(&idx) = self.by_path.get(path) {
+            return idx;
+        }
+        let display = path.strip_prefix(&self.root).unwrap_or(path).to_string_lossy().into_owned();
+        let idx = self.graph.modules.len();
+        self.graph.modules.push(ModuleNode { path: display, found: false, lines: 0, functions: 0 });
+        self.by_path.insert(path.to_path_buf(), idx);
+        idx
+    }
+
+    fn function(&mut self, module: usize, name: &str) -> usize {
+        self.graph.functions.push(FunctionNode { module, name: name.to_string() });
+        self.graph.functions.len() - 1
+    }
+}
+
+/// Splits `__method__Type__name` and `__static__Type__name` into the type
+/// and the method name.
+fn demangle(name: &str) -> (Option<&str>, &str) {
+    let mangled = name.strip_prefix("__method__").or_else(|| name.strip_prefix("__static__"));
+    match mangled.and_then(|rest| rest.split_once("__")) {
+        Some((ty, method)) => (Some(ty), method),
+        None => (None, name),
+    }
+}
+
+/// A call as written.
+enum Callee {
+    /// `name()`
+    Free(String),
+    /// `recv.name()`: a module function or a static method.
+    Qualified(String, String),
+    /// `alias.Type.name()`
+    Static(String, String, String),
+    /// `self.name()`
+    SelfMethod(String),
+}
+
+fn collect_block(stmts: &[Stmt], out: &mut Vec<Callee>) {
+    for s in stmts {
+        collect_stmt(s, out);
+    }
+}
+
+fn collect_stmt(s: &Stmt, out: &mut Vec<Callee>) {
+    match s {
+        Stmt::If(s) => {
+            for (cond, body) in &s.branches {
+                collect_expr(cond, out);
+                collect_block(body, out);
+            }
+            collect_block(s.else_branch.as_deref().unwrap_or_default(), out);
+        }
+        Stmt::While(s) => {
+            collect_expr(&s.cond, out);
+            collect_block(&s.body, out);
+        }
+        Stmt::ForEach(s) => {
+            collect_expr(&s.iter, out);
+            collect_block(&s.body, out);
+        }
+        Stmt::Match(s) => {
+            collect_expr(&s.expr, out);
+            for (_, body) in &s.arms {
+                collect_block(body, out);
+            }
+            collect_block(s.else_branch.as_deref().unwrap_or_default(), out);
+        }
+        Stmt::Block(body) => collect_block(body, out),
+        Stmt::Return(Some(e)) | Stmt::Expr(e) => collect_expr(e, out),
+        Stmt::Assign(s) => {
+            collect_expr(&s.target, out);
+            collect_expr(&s.value, out);
+        }
+        Stmt::StructDef(def) => {
+            for f in &def.fields {
+                if let Some(d) = &f.default {
+                    collect_expr(d, out);
+                }
+            }
+            for f in &def.static_fields {
+                collect_expr(&f.default, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_exprs(exprs: &[Expr], out: &mut Vec<Callee>) {
+    for e in exprs {
+        collect_expr(e, out);
+    }
+}
+
+fn collect_expr(e: &Expr, out: &mut Vec<Callee>) {
+    match e {
+        Expr::Call(c) => {
+            match &*c.callee {
+                Expr::Ident(name, _) => out.push(Callee::Free(name.clone())),
+                callee => collect_expr(callee, out),
+            }
+            collect_exprs(&c.args, out);
+        }
+        Expr::MethodCall(m) => {
+            match &*m.receiver {
+                Expr::Ident(recv, _) if recv == "self" => out.push(Callee::SelfMethod(m.method.clone())),
+                Expr::Ident(recv, _) => out.push(Callee::Qualified(recv.clone(), m.method.clone())),
+                Expr::Member(member) => {
+                    if let Expr::Ident(alias, _) = &*member.object {
+                        out.push(Callee::Static(alias.clone(), member.field.clone(), m.method.clone()));
+                    }
+                    collect_expr(&m.receiver, out);
+                }
+                recv => collect_expr(recv, out),
+            }
+            collect_exprs(&m.args, out);
+        }
+        // Calls in a closure are made on behalf of the enclosing function.
+        Expr::FuncLit(def) => collect_block(&def.body, out),
+        Expr::InterpolatedString(items) | Expr::List(items) | Expr::Tuple(items) => collect_exprs(items, out),
+        Expr::Range(r) => {
+            collect_expr(&r.start, out);
+            collect_expr(&r.end, out);
+        }
+        Expr::IfExpr(x) => {
+            collect_expr(&x.cond, out);
+            collect_expr(&x.then_expr, out);
+            collect_expr(&x.else_expr, out);
+        }
+        Expr::Match(x) => {
+            collect_expr(&x.expr, out);
+            for (_, arm) in &x.arms {
+                collect_expr(arm, out);
+            }
+            if let Some(e) = &x.else_expr {
+                collect_expr(e, out);
+            }
+        }
+        Expr::Dict(items) => {
+            for (_, v) in items {
+                collect_expr(v, out);
+            }
+        }
+        Expr::StructInit(s) => {
+            for item in &s.items {
+                match item {
+                    StructInitItem::Spread(e) | StructInitItem::Field(_, e) => collect_expr(e, out),
+                }
+            }
+        }
+        Expr::EnumCtor { args, .. } => collect_exprs(args, out),
+        Expr::Member(m) => collect_expr(&m.object, out),
+        Expr::Index(x) => {
+            collect_expr(&x.object, out);
+            collect_expr(&x.index, out);
+        }
+        Expr::Unary { expr, .. } | Expr::Group(expr) | Expr::Try(expr) | Expr::OptChain(expr) | Expr::OptLink(expr) => {
+            collect_expr(expr, out)
+        }
+        Expr::Binary { left, right, .. } => {
+            collect_expr(left, out);
+            collect_expr(right, out);
+        }
+        Expr::Compare(c) => {
+            collect_expr(&c.first, out);
+            for (_, e) in c.links.iter() {
+                collect_expr(e, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl ProjectGraph {
+    /// Groups of modules that import each other, directly or transitively,
+    /// each in ascending order. A module importing itself is a group of one.
+    pub fn import_cycles(&self) -> Vec<Vec<usize>> {
+        cycles(self.modules.len(), &self.imports)
+    }
+
+    /// Groups of mutually recursive functions, like [`Self::import_cycles`].
+    pub fn call_cycles(&self) -> Vec<Vec<usize>> {
+        cycles(self.functions.len(), &self.calls)
+    }
+
+    /// `path:name` of function `f`.
+    pub fn function_label(&self, f: usize) -> String {
+        let func = &self.functions[f];
+        format!("{}:{}", self.modules[func.module].path, func.name)
+    }
+
+    /// The import graph in Graphviz DOT. Modules that could not be read are
+    /// dashed; edges inside an import cycle are red.
+    pub fn imports_to_dot(&self) -> String {
+        let in_cycle = cycle_members(self.modules.len(), &self.import_cycles());
+        let mut out = String::from("digraph imports {\n    node [shape=box];\n");
+        for (i, m) in self.modules.iter().enumerate() {
+            let label = if m.found {
+                format!("{}\\n{} lines, {} functions", dot_escape(&m.path), m.lines, m.functions)
+            } else {
+                dot_escape(&m.path)
+            };
+            let style = if m.found { "" } else { ", style=dashed" };
+            out.push_str(&format!("    m{i} [label=\"{label}\"{style}];\n"));
+        }
+        push_dot_edges(&mut out, 'm', &self.imports, &in_cycle);
+        out.push_str("}\n");
+        out
+    }
+
+    /// The call graph in Graphviz DOT, one cluster per module. Edges inside
+    /// a recursion cycle are red.
+    pub fn calls_to_dot(&self) -> String {
+        let in_cycle = cycle_members(self.functions.len(), &self.call_cycles());
+        let mut out = String::from("digraph calls {\n    node [shape=ellipse];\n");
+        for (i, m) in self.modules.iter().enumerate() {
+            out.push_str(&format!("    subgraph cluster_{i} {{\n        label=\"{}\";\n", dot_escape(&m.path)));
+            for (f, func) in self.functions.iter().enumerate().filter(|(_, func)| func.module == i) {
+                out.push_str(&format!("        f{f} [label=\"{}\"];\n", dot_escape(&func.name)));
+            }
+            out.push_str("    }\n");
+        }
+        push_dot_edges(&mut out, 'f', &self.calls, &in_cycle);
+        out.push_str("}\n");
+        out
+    }
+
+    /// Both graphs and their cycles as one JSON object; nodes are referred to
+    /// by index.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"modules\":[");
+        for (i, m) in self.modules.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"path\":");
+            push_json_str(&mut out, &m.path);
+            out.push_str(&format!(",\"found\":{},\"lines\":{},\"functions\":{}}}", m.found, m.lines, m.functions));
+        }
+        out.push_str("],\"imports\":");
+        push_json_edges(&mut out, &self.imports);
+        out.push_str(",\"functions\":[");
+        for (i, f) in self.functions.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&format!("{{\"module\":{},\"name\":", f.module));
+            push_json_str(&mut out, &f.name);
+            out.push('}');
+        }
+        out.push_str("],\"calls\":");
+        push_json_edges(&mut out, &self.calls);
+        let groups = |cycles: Vec<Vec<usize>>| {
+            let groups: Vec<String> = cycles
+                .iter()
+                .map(|c| format!("[{}]", c.iter().map(usize::to_string).collect::<Vec<_>>().join(",")))
+                .collect();
+            format!("[{}]", groups.join(","))
+        };
+        out.push_str(&format!(
+            ",\"import_cycles\":{},\"call_cycles\":{}}}",
+            groups(self.import_cycles()),
+            groups(self.call_cycles())
+        ));
+        out
+    }
+}
+
+/// Strongly connected components with a cycle, found with Tarjan's
+/// algorithm, ordered by their smallest node.
+fn cycles(n: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    struct Tarjan<'a> {
+        adj: Vec<Vec<usize>>,
+        edges: &'a [(usize, usize)],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        out: Vec<Vec<usize>>,
+    }
+    impl Tarjan<'_> {
+        fn visit(&mut self, v: usize) {
+            self.index[v] = Some(self.next);
+            self.low[v] = self.next;
+            self.next += 1;
+            self.stack.push(v);
+            self.on_stack[v] = true;
+            for k in 0..self.adj[v].len() {
+                let w = self.adj[v][k];
+                match self.index[w] {
+                    None => {
+                        self.visit(w);
+                        self.low[v] = self.low[v].min(self.low[w]);
+                    }
+                    Some(iw) if self.on_stack[w] => self.low[v] = self.low[v].min(iw),
+                    Some(_) => {}
+                }
+            }
+            if Some(self.low[v]) == self.index[v] {
+                let mut group = Vec::new();
+                while let Some(w) = self.stack.pop() {
+                    self.on_stack[w] = false;
+                    group.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                if group.len() > 1 || self.edges.contains(&(v, v)) {
+                    group.sort_unstable();
+                    self.out.push(group);
+                }
+            }
+        }
+    }
+    let mut adj = vec![Vec::new(); n];
+    for &(a, b) in edges {
+        adj[a].push(b);
+    }
+    let mut t = Tarjan {
+        adj,
+        edges,
+        index: vec![None; n],
+        low: vec![0; n],
+        on_stack: vec![false; n],
+        stack: Vec::new(),
+        next: 0,
+        out: Vec::new(),
+    };
+    for v in 0..n {
+        if t.index[v].is_none() {
+            t.visit(v);
+        }
+    }
+    t.out.sort();
+    t.out
+}
+
+/// The cycle group of each node, if any.
+fn cycle_members(n: usize, cycles: &[Vec<usize>]) -> Vec<Option<usize>> {
+    let mut member = vec![None; n];
+    for (g, group) in cycles.iter().enumerate() {
+        for &v in group {
+            member[v] = Some(g);
+        }
+    }
+    member
+}
+
+fn push_dot_edges(out: &mut String, prefix: char, edges: &[(usize, usize)], in_cycle: &[Option<usize>]) {
+    for &(a, b) in edges {
+        let color = if in_cycle[a].is_some() && in_cycle[a] == in_cycle[b] { " [color=red]" } else { "" };
+        out.push_str(&format!("    {prefix}{a} -> {prefix}{b}{color};\n"));
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn push_json_edges(out: &mut String, edges: &[(usize, usize)]) {
+    let edges: Vec<String> = edges.iter().map(|(a, b)| format!("[{a},{b}]")).collect();
+    out.push_str(&format!("[{}]", edges.join(",")));
+}
@@ -0,0 +1,348 @@
+//! 带标签的实参：把 `f(a, name: b)` 改写为按位置传参的调用
+//!
+//! 解析器只记下实参的标签，这里按被调函数声明的形参解析它们。被调者可以是
+//! 本模块顶层定义的函数、`use` 导入的模块中的公开函数（`alias.func(...)`），
+//! 或在 [`xu_syntax::BuiltinSig::names`] 中列出形参名的内置函数。标签须是
+//! 位置实参之后的形参名且不重复；最后一个标签之前未给出的形参取其默认值，
+//! 默认值须是常量，内置函数的可选形参取 `()`。之后的形参留给被调者补齐。
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use xu_lexer::{Lexer, normalize_source};
+use xu_parser::{CallExpr, Expr, FuncDef, MethodCallExpr, Module, Parser, Stmt, StructInitItem, UnaryOp, Visibility};
+use xu_syntax::{Diagnostic, DiagnosticKind, Span, builtin_signature};
+
+use crate::analyzer::resolve_import_path;
+use crate::std_sources::std_source;
+
+/// 被调函数的形参名与默认值
+type Params = Vec<(String, Option<Expr>)>;
+
+struct Resolver<'a> {
+    base_dir: PathBuf,
+    /// 本模块顶层定义的函数
+    funcs: HashMap<String, Params>,
+    /// `use` 绑定的模块别名到导入路径
+    modules: HashMap<String, String>,
+    /// 已读取的导入模块中的公开函数；读不到的模块为 None
+    imported: HashMap<String, Option<HashMap<String, Params>>>,
+    out: &'a mut Vec<Diagnostic>,
+}
+
+/// Rewrites the labeled calls in `module`, parsed from `path`, into
+/// positional ones, reporting labels that do not resolve to `out`.
+pub(crate) fn resolve_labels(module: &mut Module, path: &str, out: &mut Vec<Diagnostic>) {
+    let mut funcs = HashMap::new();
+    let mut modules = HashMap::new();
+    for s in module.stmts.iter() {
+        match s {
+            Stmt::FuncDef(def) => {
+                funcs.insert(def.name.clone(), params_of(def));
+            }
+            Stmt::Use(u) => {
+                let alias = u.alias.clone().unwrap_or_else(|| xu_ir::infer_module_alias(&u.path));
+                modules.insert(alias, u.path.clone());
+            }
+            _ => {}
+        }
+    }
+    let base_dir = Path::new(path).parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut resolver = Resolver { base_dir, funcs, modules, imported: HashMap::new(), out };
+    resolver.stmts(&mut module.stmts);
+}
+
+fn params_of(def: &FuncDef) -> Params {
+    def.params.iter().map(|p| (p.name.clone(), p.default.clone())).collect()
+}
+
+/// 模块源码中的公开函数
+fn public_funcs(input: &str) -> HashMap<String, Params> {
+    let normalized = normalize_source(input);
+    let lex = Lexer::new(&normalized.text).lex();
+    let bump = bumpalo::Bump::new();
+    let parse = Parser::new(&normalized.text, &lex.tokens, &bump).parse();
+    let mut funcs = HashMap::new();
+    for s in parse.module.stmts.iter() {
+        if let Stmt::FuncDef(def) = s {
+            if def.vis == Visibility::Public {
+                funcs.insert(def.name.clone(), params_of(def));
+            }
+        }
+    }
+    funcs
+}
+
+/// 默认值能否原样放到调用处求值
+fn is_constant(e: &Expr) -> bool {
+    match e {
+        Expr::Int(_) | Expr::Float(_) | Expr::Str(_) | Expr::Bool(_) => true,
+        Expr::List(items) | Expr::Tuple(items) => items.iter().all(is_constant),
+        Expr::Unary { op: UnaryOp::Neg, expr } | Expr::Group(expr) => is_constant(expr),
+        _ => false,
+    }
+}
+
+fn invalid_label(label: &str, func: &str, span: Span) -> Diagnostic {
+    Diagnostic::error_kind(
+        DiagnosticKind::InvalidArgumentLabel { label: label.to_string(), func: func.to_string() },
+        Some(span),
+    )
+}
+
+/// 按 `params` 把 `args` 末尾 `labels.len()` 个带标签的实参排到各自形参的位置
+fn apply(
+    args: &mut Box<[Expr]>,
+    labels: &[(String, Span)],
+    params: &[(String, Option<Expr>)],
+    func: &str,
+    out: &mut Vec<Diagnostic>,
+) {
+    let mut all = std::mem::take(args).into_vec();
+    let positional = all.len().saturating_sub(labels.len());
+    let labeled = all.split_off(positional);
+    let mut given: Vec<Option<Expr>> = vec![None; params.len()];
+    for ((label, span), arg) in labels.iter().zip(labeled) {
+        match params.iter().position(|(name, _)| name == label) {
+            Some(i) if i >= positional && given[i].is_none() => given[i] = Some(arg),
+            _ => out.push(invalid_label(label, func, *span)),
+        }
+    }
+    let Some(last) = given.iter().rposition(Option::is_some) else {
+        *args = all.into_boxed_slice();
+        return;
+    };
+    let span = labels[0].1;
+    for (i, arg) in given.into_iter().enumerate().take(last + 1).skip(positional) {
+        all.push(match (arg, &params[i].1) {
+            (Some(arg), _) => arg,
+            (None, Some(default)) if is_constant(default) => default.clone(),
+            (None, _) => {
+                out.push(Diagnostic::error_kind(
+                    DiagnosticKind::ExpectedToken(format!("{}:", params[i].0)),
+                    Some(span),
+                ));
+                Expr::Error(span)
+            }
+        });
+    }
+    *args = all.into_boxed_slice();
+}
+
+impl Resolver<'_> {
+    /// 读取 `path` 导入的模块：先按磁盘上的路径，`std/...` 再找内嵌的标准库
+    fn load(&mut self, path: &str) {
+        if self.imported.contains_key(path) {
+            return;
+        }
+        let text = resolve_import_path(&self.base_dir, path).ok().and_then(|p| fs::read_to_string(p).ok());
+        let text = text.or_else(|| std_source(path).map(str::to_string));
+        self.imported.insert(path.to_string(), text.map(|t| public_funcs(&t)));
+    }
+
+    fn call(&mut self, c: &mut CallExpr) {
+        let labels = std::mem::take(&mut c.labels);
+        let name = match c.callee.as_ref() {
+            Expr::Ident(name, _) => name.as_str(),
+            _ => "<function>",
+        };
+        if let Some(params) = self.funcs.get(name) {
+            apply(&mut c.args, &labels, params, name, self.out);
+        } else if let Some(sig) = builtin_signature(name).filter(|s| !s.names.is_empty()) {
+            // 内置函数的可选形参以 () 表示未给出
+            let params: Params = sig
+                .names
+                .iter()
+                .enumerate()
+                .map(|(i, n)| (n.to_string(), (i >= sig.min).then(|| Expr::Tuple(Box::new([])))))
+                .collect();
+            apply(&mut c.args, &labels, &params, name, self.out);
+        } else {
+            self.out.push(invalid_label(&labels[0].0, name, labels[0].1));
+        }
+    }
+
+    fn method_call(&mut self, m: &mut MethodCallExpr) {
+        let labels = std::mem::take(&mut m.labels);
+        let path = match m.receiver.as_ref() {
+            Expr::Ident(alias, _) => self.modules.get(alias).cloned(),
+            _ => None,
+        };
+        let func = match m.receiver.as_ref() {
+            Expr::Ident(alias, _) => format!("{alias}.{}", m.method),
+            _ => m.method.clone(),
+        };
+        if let Some(path) = path {
+            self.load(&path);
+            let params = self.imported.get(&path).and_then(|funcs| funcs.as_ref()?.get(&m.method));
+            if let Some(params) = params {
+                apply(&mut m.args, &labels, params, &func, self.out);
+                return;
+            }
+        }
+        self.out.push(invalid_label(&labels[0].0, &func, labels[0].1));
+    }
+
+    fn func(&mut self, def: &mut Rc<FuncDef>) {
+        let def = Rc::make_mut(def);
+        for p in def.params.iter_mut() {
+            if let Some(d) = &mut p.default {
+                self.expr(d);
+            }
+        }
+        self.stmts(&mut def.body);
+    }
+
+    fn stmts(&mut self, stmts: &mut [Stmt]) {
+        for s in stmts {
+            self.stmt(s);
+        }
+    }
+
+    fn stmt(&mut self, s: &mut Stmt) {
+        match s {
+            Stmt::StructDef(def) => {
+                let def = Rc::make_mut(def);
+                for f in def.static_fields.iter_mut() {
+                    self.expr(&mut f.default);
+                }
+                for f in def.fields.iter_mut() {
+                    if let Some(d) = &mut f.default {
+                        self.expr(d);
+                    }
+                }
+                for m in def.methods.iter_mut() {
+                    self.func(m);
+                }
+            }
+            Stmt::FuncDef(def) => self.func(def),
+            Stmt::DoesBlock(def) => {
+                for f in def.funcs.iter_mut() {
+                    self.func(f);
+                }
+            }
+            Stmt::If(s) => {
+                for (cond, body) in s.branches.iter_mut() {
+                    self.expr(cond);
+                    self.stmts(body);
+                }
+                if let Some(body) = &mut s.else_branch {
+                    self.stmts(body);
+                }
+            }
+            Stmt::While(s) => {
+                self.expr(&mut s.cond);
+                self.stmts(&mut s.body);
+            }
+            Stmt::ForEach(s) => {
+                self.expr(&mut s.iter);
+                self.stmts(&mut s.body);
+            }
+            Stmt::Match(s) => {
+                self.expr(&mut s.expr);
+                for (_, body) in s.arms.iter_mut() {
+                    self.stmts(body);
+                }
+                if let Some(body) = &mut s.else_branch {
+                    self.stmts(body);
+                }
+            }
+            Stmt::Block(stmts) => self.stmts(stmts),
+            Stmt::Return(Some(e)) | Stmt::Expr(e) => self.expr(e),
+            Stmt::Assign(s) => {
+                self.expr(&mut s.target);
+                self.expr(&mut s.value);
+            }
+            Stmt::EnumDef(_) | Stmt::Use(_) | Stmt::Return(None) | Stmt::Break | Stmt::Continue | Stmt::Error(_) => {}
+        }
+    }
+
+    fn exprs(&mut self, exprs: &mut [Expr]) {
+        for e in exprs {
+            self.expr(e);
+        }
+    }
+
+    fn expr(&mut self, e: &mut Expr) {
+        match e {
+            Expr::Call(c) => {
+                self.expr(&mut c.callee);
+                self.exprs(&mut c.args);
+                if !c.labels.is_empty() {
+                    self.call(c);
+                }
+            }
+            Expr::MethodCall(m) => {
+                self.expr(&mut m.receiver);
+                self.exprs(&mut m.args);
+                if !m.labels.is_empty() {
+                    self.method_call(m);
+                }
+            }
+            Expr::InterpolatedString(items) | Expr::List(items) | Expr::Tuple(items) => self.exprs(items),
+            Expr::Range(r) => {
+                self.expr(&mut r.start);
+                self.expr(&mut r.end);
+            }
+            Expr::IfExpr(i) => {
+                self.expr(&mut i.cond);
+                self.expr(&mut i.then_expr);
+                self.expr(&mut i.else_expr);
+            }
+            Expr::Match(m) => {
+                self.expr(&mut m.expr);
+                for (_, arm) in m.arms.iter_mut() {
+                    self.expr(arm);
+                }
+                if let Some(e) = &mut m.else_expr {
+                    self.expr(e);
+                }
+            }
+            Expr::FuncLit(def) => self.func(def),
+            Expr::Dict(entries) => {
+                for (_, v) in entries.iter_mut() {
+                    self.expr(v);
+                }
+            }
+            Expr::StructInit(s) => {
+                if let Some(m) = &mut s.module {
+                    self.expr(m);
+                }
+                for item in s.items.iter_mut() {
+                    match item {
+                        StructInitItem::Spread(e) | StructInitItem::Field(_, e) => self.expr(e),
+                    }
+                }
+            }
+            Expr::EnumCtor { module, args, .. } => {
+                if let Some(m) = module {
+                    self.expr(m);
+                }
+                self.exprs(args);
+            }
+            Expr::Member(m) => self.expr(&mut m.object),
+            Expr::Index(i) => {
+                self.expr(&mut i.object);
+                self.expr(&mut i.index);
+            }
+            Expr::Unary { expr, .. }
+            | Expr::Group(expr)
+            | Expr::Try(expr)
+            | Expr::OptChain(expr)
+            | Expr::OptLink(expr) => self.expr(expr),
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Compare(c) => {
+                self.expr(&mut c.first);
+                for (_, e) in c.links.iter_mut() {
+                    self.expr(e);
+                }
+            }
+            Expr::Ident(..) | Expr::Int(_) | Expr::Float(_) | Expr::Str(_) | Expr::Bool(_) | Expr::Error(_) => {}
+        }
+    }
+}
@@ -10,7 +10,24 @@
 
 mod analyzer;
 mod bytecode_compiler;
+mod codegen;
+mod const_eval;
+mod exec_cache;
 mod frontend;
+mod graph;
 mod analyzer_util;
+mod labels;
+mod references;
+mod resolve;
+mod semantic_tokens;
+mod std_sources;
 
-pub use frontend::{Driver, LexedFile, ParsedFile, Timings};
+pub use codegen::{CodegenOptions, CodegenTarget};
+pub use exec_cache::CACHE_EXTENSION;
+pub use frontend::{CompiledFile, Driver, LexedFile, ParsedFile, Timings};
+pub use graph::{FunctionNode, ModuleNode, ProjectGraph, TOP_LEVEL};
+pub use references::{Location, Position, TextEdit, WorkspaceEdit};
+pub use semantic_tokens::{
+    MOD_DECLARATION, MOD_DEFAULT_LIBRARY, MOD_READONLY, SEMANTIC_TOKEN_MODIFIERS, SEMANTIC_TOKEN_TYPES,
+    SemanticToken, SemanticTokenKind, SemanticTokens,
+};
@@ -0,0 +1,464 @@
+//! Find-all-references and rename.
+//!
+//! Identifiers are resolved per file by the lexical resolver (see
+//! `resolve`), then linked across files through `use` statements: a
+//! module-level name is shared by its declaring file, `alias.name` uses in
+//! files that import it under `alias`, and unqualified uses in files that
+//! import it (imported functions and types are in scope by name). The
+//! workspace is every `.xu` file under the nearest directory with an
+//! `xu.toml` above the file (or the file's own directory), plus everything
+//! those files import.
+//!
+//! Offsets and spans count bytes of the files as stored; positions are
+//! zero-based lines and UTF-16 code units, as LSP uses by default.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use xu_lexer::{Lexer, NormalizedSource, normalize_source};
+use xu_syntax::{ColumnUnit, LineIndex, Span, TokenKind, unquote};
+
+use crate::analyzer::resolve_import_path;
+use crate::frontend::Driver;
+use crate::resolve::{DeclRole, Target, resolve};
+
+/// A zero-based line and UTF-16 column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+/// A range of one file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// The canonical path of the file.
+    pub path: String,
+    /// Byte range in the file as stored.
+    pub span: Span,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Replaces the text at `location` by `new_text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    pub location: Location,
+    pub new_text: String,
+}
+
+/// The edits of a refactoring, grouped by file in workspace order and sorted
+/// by position within each file. Edits never overlap.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkspaceEdit {
+    pub edits: Vec<TextEdit>,
+}
+
+impl WorkspaceEdit {
+    /// The paths of the files the edit changes, in order.
+    pub fn paths(&self) -> Vec<&str> {
+        self.files().map(|edits| edits[0].location.path.as_str()).collect()
+    }
+
+    /// The edits of each file in turn.
+    fn files(&self) -> impl Iterator<Item = &[TextEdit]> {
+        self.edits.chunk_by(|a, b| a.location.path == b.location.path)
+    }
+
+    /// Applies the edits of the file at `path` to its stored `text`.
+    pub fn apply(&self, path: &str, text: &str) -> String {
+        match self.files().find(|edits| edits[0].location.path == path) {
+            Some(edits) => apply_edits(text, edits),
+            None => text.to_string(),
+        }
+    }
+
+    /// The edit as an LSP `WorkspaceEdit`: `{"changes":{uri:[TextEdit]}}`.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"changes\":{");
+        for (i, edits) in self.files().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            push_json_str(&mut out, &format!("file://{}", edits[0].location.path));
+            out.push_str(":[");
+            for (j, e) in edits.iter().enumerate() {
+                if j > 0 {
+                    out.push(',');
+                }
+                let (s, end) = (e.location.start, e.location.end);
+                out.push_str(&format!(
+                    "{{\"range\":{{\"start\":{{\"line\":{},\"character\":{}}},\"end\":{{\"line\":{},\"character\":{}}}}},\"newText\":",
+                    s.line, s.character, end.line, end.character
+                ));
+                push_json_str(&mut out, &e.new_text);
+                out.push('}');
+            }
+            out.push(']');
+        }
+        out.push_str("}}");
+        out
+    }
+}
+
+/// `text` with `edits` (sorted, non-overlapping) applied in one pass.
+fn apply_edits(text: &str, edits: &[TextEdit]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for e in edits {
+        out.push_str(&text[copied..e.location.span.start.0 as usize]);
+        out.push_str(&e.new_text);
+        copied = e.location.span.end.0 as usize;
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Appends `s` as a JSON string literal.
+pub(crate) fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl Driver {
+    /// Every occurrence, declaration included, of the name at byte `offset`
+    /// of the file at `path`, across the workspace.
+    pub fn find_references(&self, path: &str, offset: u32) -> Result<Vec<Location>, String> {
+        let ws = Workspace::load(Path::new(path), &HashMap::new())?;
+        let symbol = ws.symbol_at(offset)?;
+        Ok(ws.occurrences_of(&symbol).into_iter().map(|(file, span)| ws.location(file, span)).collect())
+    }
+
+    /// Renames the name at byte `offset` of the file at `path` to
+    /// `new_name` everywhere in the workspace. Fails, changing nothing, if
+    /// the name is not declared in the workspace or the new name is not an
+    /// identifier, is already declared next to it, or would change what
+    /// another name refers to.
+    pub fn rename(&self, path: &str, offset: u32, new_name: &str) -> Result<WorkspaceEdit, String> {
+        let lexed = Lexer::new(new_name).lex();
+        let significant: Vec<_> = lexed.tokens.iter().filter(|t| t.kind != TokenKind::Eof).collect();
+        if !lexed.diagnostics.is_empty()
+            || significant.len() != 1
+            || significant[0].kind != TokenKind::Ident
+            || significant[0].span.end.0 as usize != new_name.len()
+        {
+            return Err(format!("`{new_name}` is not a valid identifier"));
+        }
+
+        let ws = Workspace::load(Path::new(path), &HashMap::new())?;
+        let symbol = ws.symbol_at(offset)?;
+        let occurrences = ws.occurrences_of(&symbol);
+        let old_name = symbol.name(&ws);
+        if !occurrences.iter().any(|&(file, span)| ws.files[file].declares(span)) {
+            return Err(format!("`{old_name}` is not declared in this workspace"));
+        }
+        if let Symbol::Global(file, _) = &symbol {
+            let file = &ws.files[ws.by_path[file]];
+            if file.top_level.contains_key(new_name) || file.imports.iter().any(|(alias, _)| alias == new_name) {
+                return Err(format!("`{new_name}` is already declared in {}", file.path.display()));
+            }
+        }
+        let edit = WorkspaceEdit {
+            edits: occurrences
+                .iter()
+                .map(|&(file, span)| TextEdit { location: ws.location(file, span), new_text: new_name.to_string() })
+                .collect(),
+        };
+
+        // Re-resolve the renamed workspace: every name must still refer to
+        // what it referred to before, the renamed ones to the renamed symbol.
+        let mut overrides = HashMap::new();
+        for edits in edit.files() {
+            let file = &ws.files[ws.by_path[Path::new(&edits[0].location.path)]];
+            overrides.insert(file.path.clone(), apply_edits(&file.stored, edits));
+        }
+        let renamed = Workspace::load(Path::new(path), &overrides)?;
+        let renamed_symbol = match &symbol {
+            Symbol::Local(file, decl) => Symbol::Local(file.clone(), *decl),
+            Symbol::Global(file, _) => Symbol::Global(file.clone(), new_name.to_string()),
+        };
+        for (idx, file) in ws.files.iter().enumerate() {
+            let Some(&after_idx) = renamed.by_path.get(&file.path) else { continue };
+            let after = &renamed.files[after_idx];
+            if after.occurrences.len() != file.occurrences.len() {
+                return Err(format!("renaming `{old_name}` to `{new_name}` changes how {} parses", file.path.display()));
+            }
+            for (k, occ) in file.occurrences.iter().enumerate() {
+                let expected = match ws.resolve(idx, occ) {
+                    Ok(s) if s == symbol => Ok(renamed_symbol.clone()),
+                    other => other,
+                };
+                if renamed.resolve(after_idx, &after.occurrences[k]) != expected {
+                    let at = ws.location(idx, occ.span);
+                    return Err(format!(
+                        "renaming `{old_name}` to `{new_name}` would change what `{}` refers to at {}:{}:{}",
+                        occ.name,
+                        at.path,
+                        at.start.line + 1,
+                        at.start.character + 1
+                    ));
+                }
+            }
+        }
+        Ok(edit)
+    }
+}
+
+/// What a name refers to, workspace-wide.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Symbol {
+    /// A parameter or local, by the index of its declaring significant token.
+    Local(PathBuf, usize),
+    /// A module-level name of a file.
+    Global(PathBuf, String),
+}
+
+impl Symbol {
+    fn name(&self, ws: &Workspace) -> String {
+        match self {
+            Symbol::Local(file, decl) => {
+                let file = &ws.files[ws.by_path[file]];
+                file.occurrences.iter().find(|o| o.decl == Some(*decl)).map(|o| o.name.clone()).unwrap_or_default()
+            }
+            Symbol::Global(_, name) => name.clone(),
+        }
+    }
+}
+
+/// One identifier of a file.
+struct Occurrence {
+    span: Span,
+    name: String,
+    target: Target,
+    /// The significant-token index, if this occurrence declares its name.
+    decl: Option<usize>,
+    /// The identifier before the `.` of a member.
+    qualifier: Option<String>,
+}
+
+struct FileIndex {
+    path: PathBuf,
+    /// The file as stored.
+    stored: String,
+    source: NormalizedSource,
+    /// Lines of the normalized text.
+    lines: LineIndex,
+    /// Spans are in the normalized text, in token order; identifiers inside
+    /// string interpolations come last.
+    occurrences: Vec<Occurrence>,
+    top_level: HashMap<String, DeclRole>,
+    /// Module aliases, explicit or inferred, and the files they import.
+    imports: Vec<(String, PathBuf)>,
+}
+
+impl FileIndex {
+    fn build(path: PathBuf, stored: String) -> Self {
+        let source = normalize_source(&stored);
+        let text = source.text.as_str();
+        let tokens = Lexer::new(text).lex().tokens;
+        let resolved = resolve(text, &tokens);
+
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let mut imports = Vec::new();
+        for i in 0..resolved.tokens.len() {
+            if resolved.kind(i) != TokenKind::KwUse || resolved.kind(i + 1) != TokenKind::Str {
+                continue;
+            }
+            let module = unquote(resolved.name(i + 1));
+            let alias = if resolved.kind(i + 2) == TokenKind::KwAs && resolved.kind(i + 3) == TokenKind::Ident {
+                resolved.name(i + 3).to_string()
+            } else {
+                xu_ir::infer_module_alias(&module)
+            };
+            if let Ok(file) = resolve_import_path(&base_dir, &module) {
+                imports.push((alias, file));
+            }
+        }
+
+        let mut occurrences = Vec::new();
+        for (i, target) in resolved.targets.iter().enumerate() {
+            let Some(target) = *target else { continue };
+            let qualifier = (target == Target::Member
+                && i >= 2
+                && resolved.kind(i - 2) == TokenKind::Ident
+                && resolved.targets[i - 2] == Some(Target::Global)
+                && (i < 3 || resolved.kind(i - 3) != TokenKind::Dot))
+                .then(|| resolved.name(i - 2).to_string());
+            occurrences.push(Occurrence {
+                span: resolved.tokens[i].span,
+                name: resolved.name(i).to_string(),
+                target,
+                decl: resolved.decls.contains_key(&i).then_some(i),
+                qualifier,
+            });
+        }
+        for &(span, target) in &resolved.interpolated {
+            let name = text[span.start.0 as usize..span.end.0 as usize].to_string();
+            occurrences.push(Occurrence { span, name, target, decl: None, qualifier: None });
+        }
+        let top_level = resolved.top_level.iter().map(|(name, decl)| (name.clone(), resolved.decls[decl])).collect();
+        let lines = LineIndex::new(text);
+        FileIndex { path, stored, source, lines, occurrences, top_level, imports }
+    }
+
+    fn declares(&self, span: Span) -> bool {
+        self.occurrences.iter().any(|o| o.span == span && o.decl.is_some())
+    }
+
+    fn import(&self, alias: &str) -> Option<&Path> {
+        self.imports.iter().find(|(a, _)| a == alias).map(|(_, file)| file.as_path())
+    }
+}
+
+struct Workspace {
+    files: Vec<FileIndex>,
+    by_path: HashMap<PathBuf, usize>,
+}
+
+impl Workspace {
+    /// Indexes the workspace of `path`, which comes first. Files in
+    /// `overrides` are indexed with the given text instead of their own.
+    fn load(path: &Path, overrides: &HashMap<PathBuf, String>) -> Result<Self, String> {
+        let display = path.display();
+        let path = path.canonicalize().map_err(|e| format!("Failed to read file {display}: {e}"))?;
+        let root = path
+            .ancestors()
+            .skip(1)
+            .find(|d| d.join("xu.toml").is_file())
+            .or(path.parent())
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mut queue = VecDeque::from([path.clone()]);
+        let mut found = Vec::new();
+        collect_sources(&root, &mut found);
+        queue.extend(found);
+
+        let mut ws = Workspace { files: Vec::new(), by_path: HashMap::new() };
+        let mut seen = HashSet::new();
+        while let Some(file) = queue.pop_front() {
+            if !seen.insert(file.clone()) {
+                continue;
+            }
+            let stored = match overrides.get(&file) {
+                Some(text) => text.clone(),
+                None => match fs::read_to_string(&file) {
+                    Ok(text) => text,
+                    Err(e) if file == path => return Err(format!("Failed to read file {display}: {e}")),
+                    Err(_) => continue,
+                },
+            };
+            let index = FileIndex::build(file.clone(), stored);
+            queue.extend(index.imports.iter().map(|(_, f)| f.clone()));
+            ws.by_path.insert(file, ws.files.len());
+            ws.files.push(index);
+        }
+        Ok(ws)
+    }
+
+    /// The symbol of the identifier at `offset` of the first file.
+    fn symbol_at(&self, offset: u32) -> Result<Symbol, String> {
+        let file = &self.files[0];
+        let at = file.source.normalized_offset(offset);
+        let occ = file
+            .occurrences
+            .iter()
+            .find(|o| o.span.start.0 <= at && at <= o.span.end.0)
+            .ok_or_else(|| format!("No name at offset {offset} of {}", file.path.display()))?;
+        self.resolve(0, occ).map_err(|why| format!("`{}` {why}", occ.name))
+    }
+
+    /// What `occ` of the `file`th file refers to, or why it refers to
+    /// nothing that can be renamed.
+    fn resolve(&self, file: usize, occ: &Occurrence) -> Result<Symbol, &'static str> {
+        let f = &self.files[file];
+        let defined_in = |module: &Path, name: &str, imported: bool| {
+            let m = &self.files[*self.by_path.get(module)?];
+            match m.top_level.get(name)? {
+                DeclRole::Function if imported && name.starts_with('_') => None,
+                DeclRole::Function | DeclRole::Type => Some(Symbol::Global(m.path.clone(), name.to_string())),
+                _ if !imported => Some(Symbol::Global(m.path.clone(), name.to_string())),
+                _ => None,
+            }
+        };
+        match occ.target {
+            Target::Binding(decl) => Ok(Symbol::Local(f.path.clone(), decl)),
+            Target::Skip => Err("is a field name, label or enum variant"),
+            Target::Member => occ
+                .qualifier
+                .as_deref()
+                .filter(|q| matches!(f.top_level.get(*q), None | Some(DeclRole::Module)))
+                .and_then(|q| f.import(q))
+                .and_then(|module| defined_in(module, &occ.name, false))
+                .ok_or("is a field or method, which cannot be renamed yet"),
+            Target::Global => {
+                if f.top_level.contains_key(&occ.name) || f.import(&occ.name).is_some() {
+                    return Ok(Symbol::Global(f.path.clone(), occ.name.clone()));
+                }
+                f.imports
+                    .iter()
+                    .find_map(|(_, module)| defined_in(module, &occ.name, true))
+                    .ok_or("is a builtin or is not defined in this workspace")
+            }
+        }
+    }
+
+    /// Every occurrence of `symbol`, by file index and normalized span.
+    fn occurrences_of(&self, symbol: &Symbol) -> Vec<(usize, Span)> {
+        let mut out = Vec::new();
+        for (idx, file) in self.files.iter().enumerate() {
+            let mut spans: Vec<Span> = file
+                .occurrences
+                .iter()
+                .filter(|o| self.resolve(idx, o).as_ref() == Ok(symbol))
+                .map(|o| o.span)
+                .collect();
+            spans.sort_by_key(|s| s.start);
+            out.extend(spans.into_iter().map(|s| (idx, s)));
+        }
+        out
+    }
+
+    fn location(&self, file: usize, span: Span) -> Location {
+        let file = &self.files[file];
+        let text = file.source.text.as_str();
+        let position = |offset: u32| {
+            let (line, character) = file.lines.line_col(text, offset, ColumnUnit::Utf16);
+            Position { line, character }
+        };
+        Location {
+            path: file.path.to_string_lossy().into_owned(),
+            span: file.source.original_span(span),
+            start: position(span.start.0),
+            end: position(span.end.0),
+        }
+    }
+}
+
+/// The `.xu` files under `dir`, skipping hidden directories and `target`.
+fn collect_sources(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    let mut entries: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    entries.sort();
+    for entry in entries {
+        let name = entry.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if entry.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                collect_sources(&entry, out);
+            }
+        } else if entry.extension().is_some_and(|e| e == "xu") {
+            out.push(entry.canonicalize().unwrap_or(entry));
+        }
+    }
+}
@@ -0,0 +1,503 @@
+//! Lexical name resolution over the token stream.
+//!
+//! AST nodes carry no spans, so editor features resolve identifiers on the
+//! tokens instead: brace blocks and closure bodies are scopes, parameters,
+//! `let` / `var`, loop variables, match-arm bindings and local functions bind
+//! names in them, and everything else is a module-level name. Identifiers in
+//! string interpolations are resolved in the scope of their string.
+
+use std::collections::HashMap;
+
+use xu_lexer::Lexer;
+use xu_syntax::{InterpolationParser, InterpolationPiece, Span, Token, TokenKind};
+
+/// What a significant token declares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DeclRole {
+    Function,
+    /// A function inside a `has` or `does` block.
+    Method,
+    Type,
+    Module,
+    Param,
+    /// A module-level `let` or `const`.
+    Constant,
+    /// Any other `let` or `var`, and loop and pattern variables.
+    Variable,
+}
+
+/// What an identifier refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Target {
+    /// The parameter or local declared at this significant-token index.
+    Binding(usize),
+    /// A module-level name of the file, or a builtin.
+    Global,
+    /// A member after `.`, or a method declaration.
+    Member,
+    /// A field name, call label or enum variant.
+    Skip,
+}
+
+/// A name visible in a lexical scope.
+struct Binding {
+    name: String,
+    /// The declaring token; the binding is visible from there on.
+    from: usize,
+}
+
+/// A token range in which names are bound: a brace block, or the body of a
+/// closure written without braces.
+struct Scope {
+    /// The scope is active for tokens after this one...
+    start: usize,
+    /// ...up to and excluding this one.
+    end: usize,
+    /// The body of a `has` or `does` block.
+    members: bool,
+    bindings: Vec<Binding>,
+}
+
+/// The resolved identifiers of one file.
+pub(crate) struct Resolved<'a> {
+    pub text: &'a str,
+    /// Significant tokens: no newlines, comments or spaces.
+    pub tokens: Vec<&'a Token>,
+    /// Per significant token; `None` for everything but identifiers.
+    pub targets: Vec<Option<Target>>,
+    pub decls: HashMap<usize, DeclRole>,
+    /// The first module-level declaration of each name.
+    pub top_level: HashMap<String, usize>,
+    /// Identifiers inside string interpolations.
+    pub interpolated: Vec<(Span, Target)>,
+}
+
+impl<'a> Resolved<'a> {
+    pub fn kind(&self, i: usize) -> TokenKind {
+        self.tokens.get(i).map(|t| t.kind).unwrap_or(TokenKind::Eof)
+    }
+
+    pub fn name(&self, i: usize) -> &'a str {
+        let span = self.tokens[i].span;
+        &self.text[span.start.0 as usize..span.end.0 as usize]
+    }
+}
+
+struct Resolver<'a> {
+    text: &'a str,
+    tokens: Vec<&'a Token>,
+    /// For each significant token, whether it begins a source line.
+    line_start: Vec<bool>,
+    scopes: Vec<Scope>,
+    /// Scope index by the token that opens it.
+    scope_at: HashMap<usize, usize>,
+    targets: Vec<Option<Target>>,
+    decls: HashMap<usize, DeclRole>,
+    top_level: HashMap<String, usize>,
+}
+
+pub(crate) fn resolve<'a>(text: &'a str, all: &'a [Token]) -> Resolved<'a> {
+    let mut tokens = Vec::with_capacity(all.len());
+    let mut line_start = Vec::with_capacity(all.len());
+    let mut prev_end = 0;
+    for t in all {
+        if matches!(t.kind, TokenKind::Newline | TokenKind::Comment | TokenKind::Space | TokenKind::Eof) {
+            continue;
+        }
+        let start = t.span.start.0 as usize;
+        tokens.push(t);
+        line_start.push(prev_end == 0 || text[prev_end.min(start)..start].contains('\n'));
+        prev_end = t.span.end.0 as usize;
+    }
+    let n = tokens.len();
+    let mut r = Resolver {
+        text,
+        tokens,
+        line_start,
+        scopes: Vec::new(),
+        scope_at: HashMap::new(),
+        targets: vec![None; n],
+        decls: HashMap::new(),
+        top_level: HashMap::new(),
+    };
+    r.collect_brace_scopes();
+    r.collect_declarations();
+    let interpolated = r.resolve_uses();
+    Resolved {
+        text,
+        tokens: r.tokens,
+        targets: r.targets,
+        decls: r.decls,
+        top_level: r.top_level,
+        interpolated,
+    }
+}
+
+impl<'a> Resolver<'a> {
+    fn kind(&self, i: usize) -> TokenKind {
+        self.tokens.get(i).map(|t| t.kind).unwrap_or(TokenKind::Eof)
+    }
+
+    fn name(&self, i: usize) -> &'a str {
+        let span = self.tokens[i].span;
+        &self.text[span.start.0 as usize..span.end.0 as usize]
+    }
+
+    fn add_scope(&mut self, start: usize, end: usize) -> usize {
+        let idx = self.scopes.len();
+        let members = start > 0 && matches!(self.kind(start - 1), TokenKind::KwHas | TokenKind::KwDoes);
+        self.scopes.push(Scope { start, end, members, bindings: Vec::new() });
+        self.scope_at.insert(start, idx);
+        idx
+    }
+
+    /// One scope per brace pair; an unclosed brace scopes to the end.
+    fn collect_brace_scopes(&mut self) {
+        let mut open = Vec::new();
+        for i in 0..self.tokens.len() {
+            match self.kind(i) {
+                TokenKind::LBrace => open.push(i),
+                TokenKind::RBrace => {
+                    if let Some(start) = open.pop() {
+                        self.add_scope(start, i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let end = self.tokens.len();
+        for start in open {
+            self.add_scope(start, end);
+        }
+    }
+
+    /// Index after the group opened at `i`, or `i + 1` if `i` opens none.
+    fn skip_group(&self, i: usize) -> usize {
+        if !matches!(self.kind(i), TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace) {
+            return i + 1;
+        }
+        let mut depth = 0usize;
+        let mut j = i;
+        while j < self.tokens.len() {
+            match self.kind(j) {
+                TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => depth += 1,
+                TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return j + 1;
+                    }
+                }
+                _ => {}
+            }
+            j += 1;
+        }
+        j
+    }
+
+    /// Parameter names of the list opened at `open` (by `(` or `|`), and the
+    /// index after the list.
+    fn param_list(&self, open: usize) -> (Vec<usize>, usize) {
+        let close = if self.kind(open) == TokenKind::Pipe { TokenKind::Pipe } else { TokenKind::RParen };
+        let mut names = Vec::new();
+        let mut expect_name = true;
+        let mut j = open + 1;
+        while j < self.tokens.len() {
+            match self.kind(j) {
+                k if k == close => return (names, j + 1),
+                TokenKind::Comma => expect_name = true,
+                TokenKind::Ident | TokenKind::KwSelf if expect_name => {
+                    if self.kind(j) == TokenKind::Ident {
+                        names.push(j);
+                    }
+                    expect_name = false;
+                }
+                TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => {
+                    j = self.skip_group(j);
+                    continue;
+                }
+                TokenKind::RParen | TokenKind::RBrace | TokenKind::RBracket => return (names, j),
+                _ => {}
+            }
+            j += 1;
+        }
+        (names, j)
+    }
+
+    /// Skips an optional `-> T` return type starting at `i`.
+    fn skip_return_type(&self, i: usize) -> usize {
+        if self.kind(i) != TokenKind::Minus || self.kind(i + 1) != TokenKind::Gt {
+            return i;
+        }
+        let mut j = self.skip_group(i + 2);
+        while matches!(self.kind(j), TokenKind::LBracket | TokenKind::Lt | TokenKind::Question) {
+            j = if self.kind(j) == TokenKind::Lt {
+                (j..self.tokens.len()).find(|&k| self.kind(k) == TokenKind::Gt).map_or(j + 1, |k| k + 1)
+            } else {
+                self.skip_group(j)
+            };
+        }
+        j
+    }
+
+    /// Declares the token `at` with `role`: at module level as a module-level
+    /// name, elsewhere as a binding of the innermost enclosing scope.
+    fn declare(&mut self, at: usize, role: DeclRole, enclosing: Option<usize>) {
+        self.decls.insert(at, role);
+        match enclosing {
+            None => {
+                self.targets[at] = Some(Target::Global);
+                self.top_level.entry(self.name(at).to_string()).or_insert(at);
+            }
+            Some(scope) => self.bind(scope, at, role),
+        }
+    }
+
+    fn bind(&mut self, scope: usize, at: usize, role: DeclRole) {
+        let name = self.name(at).to_string();
+        self.scopes[scope].bindings.push(Binding { name, from: at });
+        self.decls.insert(at, role);
+        self.targets[at] = Some(Target::Binding(at));
+    }
+
+    /// `func name(params) -> T { ... }`, methods with a receiver list and
+    /// anonymous `func(params) { ... }`.
+    fn collect_func(&mut self, i: usize, enclosing: Option<usize>) {
+        let mut j = i + 1;
+        let mut params = Vec::new();
+        if self.kind(j) == TokenKind::LParen && self.kind(j + 1) != TokenKind::RParen {
+            // `func (p: Point) name(...)` or an anonymous function.
+            let (names, next) = self.param_list(j);
+            params.extend(names);
+            j = next;
+        }
+        let mut receiver = !params.is_empty();
+        if self.kind(j) == TokenKind::Ident {
+            receiver &= self.kind(j + 1) == TokenKind::LParen;
+            if receiver || enclosing.is_some_and(|s| self.scopes[s].members) {
+                self.decls.insert(j, DeclRole::Method);
+                self.targets[j] = Some(Target::Member);
+            } else {
+                self.declare(j, DeclRole::Function, enclosing);
+            }
+            j += 1;
+        }
+        if self.kind(j) == TokenKind::LParen {
+            let (names, next) = self.param_list(j);
+            params.extend(names);
+            j = next;
+        }
+        j = self.skip_return_type(j);
+        if let Some(&scope) = self.scope_at.get(&j) {
+            for p in params {
+                self.bind(scope, p, DeclRole::Param);
+            }
+        }
+    }
+
+    /// `|params| expr` and `|params| { ... }`. A `|` after an operand is an
+    /// alternative in a pattern or enum definition instead.
+    fn collect_closure(&mut self, i: usize) {
+        if i > 0
+            && matches!(
+                self.kind(i - 1),
+                TokenKind::Ident
+                    | TokenKind::Int
+                    | TokenKind::Float
+                    | TokenKind::Str
+                    | TokenKind::True
+                    | TokenKind::False
+                    | TokenKind::RParen
+                    | TokenKind::RBracket
+                    | TokenKind::RBrace
+                    | TokenKind::KwSelf
+            )
+        {
+            return;
+        }
+        let (params, next) = self.param_list(i);
+        if params.is_empty() {
+            return;
+        }
+        let body = self.skip_return_type(next);
+        let scope = match self.scope_at.get(&body) {
+            Some(&scope) => scope,
+            None => {
+                let end = self.expr_end(next);
+                self.add_scope(next - 1, end)
+            }
+        };
+        for p in params {
+            self.bind(scope, p, DeclRole::Param);
+        }
+    }
+
+    /// End of a closure body written as an expression: the first line break,
+    /// `,`, `;` or unmatched closer.
+    fn expr_end(&self, mut j: usize) -> usize {
+        while j < self.tokens.len() {
+            if j > 0 && self.line_start[j] {
+                return j;
+            }
+            match self.kind(j) {
+                TokenKind::Comma | TokenKind::StmtEnd | TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace => {
+                    return j;
+                }
+                TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => j = self.skip_group(j),
+                _ => j += 1,
+            }
+        }
+        j
+    }
+
+    /// The first `{` at nesting depth zero from `j` on.
+    fn next_block(&self, mut j: usize) -> usize {
+        while j < self.tokens.len() && self.kind(j) != TokenKind::LBrace {
+            j = if matches!(self.kind(j), TokenKind::LParen | TokenKind::LBracket) { self.skip_group(j) } else { j + 1 };
+        }
+        j
+    }
+
+    /// Loop variables of `for x in ... { }` are locals of the loop body.
+    fn collect_for(&mut self, i: usize) {
+        let Some(in_idx) = (i + 1..self.tokens.len()).take(8).find(|&j| self.kind(j) == TokenKind::KwIn) else {
+            return;
+        };
+        let names: Vec<usize> = (i + 1..in_idx).filter(|&j| self.kind(j) == TokenKind::Ident).collect();
+        if let Some(&scope) = self.scope_at.get(&self.next_block(in_idx + 1)) {
+            for n in names {
+                self.bind(scope, n, DeclRole::Variable);
+            }
+        }
+    }
+
+    /// A match arm `Type#variant(a, b) { ... }` binds `a` and `b` in its body.
+    fn collect_arm_bindings(&mut self, i: usize) {
+        if !self.line_start[i] || self.kind(i + 1) != TokenKind::Hash || self.kind(i + 3) != TokenKind::LParen {
+            return;
+        }
+        let close = self.skip_group(i + 3);
+        let Some(&scope) = self.scope_at.get(&close) else { return };
+        let names: Vec<usize> = (i + 4..close - 1)
+            .filter(|&j| {
+                self.kind(j) == TokenKind::Ident
+                    && matches!(self.kind(j - 1), TokenKind::LParen | TokenKind::Comma)
+                    && matches!(self.kind(j + 1), TokenKind::RParen | TokenKind::Comma)
+            })
+            .collect();
+        for n in names {
+            self.bind(scope, n, DeclRole::Variable);
+        }
+    }
+
+    fn collect_declarations(&mut self) {
+        let mut stack: Vec<usize> = Vec::new();
+        for i in 0..self.tokens.len() {
+            let enclosing = stack.last().copied();
+            match self.kind(i) {
+                TokenKind::LBrace => stack.extend(self.scope_at.get(&i).copied()),
+                TokenKind::RBrace => {
+                    stack.pop();
+                }
+                TokenKind::KwFunc => self.collect_func(i, enclosing),
+                TokenKind::Pipe => self.collect_closure(i),
+                TokenKind::KwFor => self.collect_for(i),
+                TokenKind::KwLet | TokenKind::KwVar | TokenKind::KwConst if self.kind(i + 1) == TokenKind::Ident => {
+                    let role = if enclosing.is_none() && self.kind(i) != TokenKind::KwVar {
+                        DeclRole::Constant
+                    } else {
+                        DeclRole::Variable
+                    };
+                    self.declare(i + 1, role, enclosing);
+                }
+                TokenKind::Ident => match self.kind(i + 1) {
+                    TokenKind::KwHas | TokenKind::KwWith
+                        if enclosing.is_none() && (self.line_start[i] || (i > 0 && self.kind(i - 1) == TokenKind::KwPub)) =>
+                    {
+                        self.declare(i, DeclRole::Type, None);
+                    }
+                    _ if enclosing.is_none() && i > 0 && self.kind(i - 1) == TokenKind::KwAs => {
+                        self.declare(i, DeclRole::Module, None);
+                    }
+                    _ => self.collect_arm_bindings(i),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn lookup(&self, active: &[usize], name: &str, at: usize) -> Target {
+        for &s in active.iter().rev() {
+            let found = self.scopes[s].bindings.iter().rev().find(|b| b.name == name && b.from <= at);
+            if let Some(b) = found {
+                return Target::Binding(b.from);
+            }
+        }
+        Target::Global
+    }
+
+    fn resolve_uses(&mut self) -> Vec<(Span, Target)> {
+        let mut interpolated = Vec::new();
+        let mut active: Vec<usize> = Vec::new();
+        let mut starts: Vec<usize> = (0..self.scopes.len()).collect();
+        starts.sort_by_key(|&s| (self.scopes[s].start, std::cmp::Reverse(self.scopes[s].end)));
+        let mut next_scope = starts.into_iter().peekable();
+        for i in 0..self.tokens.len() {
+            while active.last().is_some_and(|&s| self.scopes[s].end <= i) {
+                active.pop();
+            }
+            while let Some(s) = next_scope.next_if(|&s| self.scopes[s].start < i) {
+                active.push(s);
+            }
+            match self.kind(i) {
+                TokenKind::Ident if self.targets[i].is_none() => {}
+                TokenKind::Str => {
+                    for (span, name) in self.interpolated_names(i) {
+                        interpolated.push((span, self.lookup(&active, &name, i)));
+                    }
+                    continue;
+                }
+                _ => continue,
+            }
+            let prev = if i > 0 { self.kind(i - 1) } else { TokenKind::Newline };
+            let next = self.kind(i + 1);
+            let target = if prev == TokenKind::Dot {
+                Target::Member
+            } else if prev == TokenKind::Hash
+                || (next == TokenKind::Colon
+                    && (self.line_start[i] || matches!(prev, TokenKind::LBrace | TokenKind::Comma | TokenKind::LParen)))
+            {
+                Target::Skip
+            } else {
+                self.lookup(&active, self.name(i), i)
+            };
+            self.targets[i] = Some(target);
+        }
+        interpolated
+    }
+
+    /// Identifiers of the string token `i`'s interpolations that are not
+    /// members, with their spans in the file.
+    fn interpolated_names(&self, i: usize) -> Vec<(Span, String)> {
+        let span = self.tokens[i].span;
+        let raw = &self.text[span.start.0 as usize..span.end.0 as usize];
+        let quote = if raw.starts_with("\"\"\"") { 3 } else { 1 };
+        if raw.starts_with('r') || raw.len() < 2 * quote || !raw.contains('{') {
+            return Vec::new();
+        }
+        let inner = &raw[quote..raw.len() - quote];
+        let mut out = Vec::new();
+        InterpolationParser::new(inner).parse(|piece| {
+            let InterpolationPiece::Expr(expr) = piece else { return };
+            let base = span.start.0 as usize + quote + (expr.as_ptr() as usize - inner.as_ptr() as usize);
+            let lexed = Lexer::new(expr).lex().tokens;
+            for (k, t) in lexed.iter().enumerate() {
+                let after_dot = k > 0 && lexed[k - 1].kind == TokenKind::Dot;
+                if t.kind == TokenKind::Ident && !after_dot {
+                    let (s, e) = (t.span.start.0 as usize, t.span.end.0 as usize);
+                    out.push((Span::new((base + s) as u32, (base + e) as u32), expr[s..e].to_string()));
+                }
+            }
+        });
+        out
+    }
+}
@@ -0,0 +1,258 @@
+//! Semantic tokens for editor highlighting.
+//!
+//! Classifies identifier tokens as functions, types, parameters, constants
+//! and modules. Module-level names come from the analyzed AST; parameters,
+//! locals that shadow them and closure parameters are resolved lexically
+//! over the token stream (see `resolve`), because AST nodes carry no spans.
+//! The result is encoded in the Language Server Protocol semantic-token
+//! format.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use xu_parser::{DeclKind, Expr, Stmt};
+use xu_syntax::{BUILTIN_NAMES, ColumnUnit, LineIndex, Span, TokenKind};
+
+use crate::frontend::{Driver, ParsedFile};
+use crate::resolve::{DeclRole, Resolved, Target, resolve};
+
+/// Token type names of the legend, indexed by [`SemanticTokenKind::index`].
+/// Constants use the standard `variable` type plus the `readonly` modifier.
+pub const SEMANTIC_TOKEN_TYPES: &[&str] = &["function", "type", "parameter", "variable", "namespace"];
+
+/// Token modifier names of the legend; modifier `i` is bit `1 << i`.
+pub const SEMANTIC_TOKEN_MODIFIERS: &[&str] = &["declaration", "readonly", "defaultLibrary"];
+
+/// Modifier bit for the token that declares the name.
+pub const MOD_DECLARATION: u32 = 1;
+/// Modifier bit for names that cannot be reassigned.
+pub const MOD_READONLY: u32 = 1 << 1;
+/// Modifier bit for builtin functions and types.
+pub const MOD_DEFAULT_LIBRARY: u32 = 1 << 2;
+
+/// Type names that need no declaration.
+const BUILTIN_TYPES: &[&str] = &[
+    "any", "bool", "int", "float", "text", "str", "string", "func", "range", "list", "dict", "Option", "Result",
+];
+
+/// What an identifier token names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SemanticTokenKind {
+    Function,
+    Type,
+    Parameter,
+    Constant,
+    Module,
+}
+
+impl SemanticTokenKind {
+    /// Index of the kind's token type in [`SEMANTIC_TOKEN_TYPES`].
+    pub fn index(self) -> u32 {
+        match self {
+            SemanticTokenKind::Function => 0,
+            SemanticTokenKind::Type => 1,
+            SemanticTokenKind::Parameter => 2,
+            SemanticTokenKind::Constant => 3,
+            SemanticTokenKind::Module => 4,
+        }
+    }
+}
+
+/// One classified token. Positions are zero-based and count UTF-16 code
+/// units, as LSP does by default.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub span: Span,
+    pub line: u32,
+    pub start: u32,
+    pub length: u32,
+    pub kind: SemanticTokenKind,
+    /// `MOD_*` bits.
+    pub modifiers: u32,
+}
+
+/// The classified tokens of one file, in source order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SemanticTokens {
+    pub tokens: Vec<SemanticToken>,
+}
+
+impl SemanticTokens {
+    /// The LSP `data` array: five integers per token (line delta, start
+    /// delta, length, token type, modifier bits), each position relative to
+    /// the previous token.
+    pub fn encode(&self) -> Vec<u32> {
+        let mut data = Vec::with_capacity(self.tokens.len() * 5);
+        let (mut line, mut start) = (0, 0);
+        for t in &self.tokens {
+            let delta_start = if t.line == line { t.start - start } else { t.start };
+            data.extend([t.line - line, delta_start, t.length, t.kind.index(), t.modifiers]);
+            line = t.line;
+            start = t.start;
+        }
+        data
+    }
+
+    /// The tokens as an LSP `SemanticTokens` result: `{"data":[...]}`.
+    pub fn to_json(&self) -> String {
+        let data: Vec<String> = self.encode().iter().map(u32::to_string).collect();
+        format!("{{\"data\":[{}]}}", data.join(","))
+    }
+}
+
+impl Driver {
+    /// Classifies the identifiers of the file at `path` for syntax
+    /// highlighting. Files with errors are classified as far as they parse.
+    pub fn semantic_tokens(&self, path: &str) -> Result<SemanticTokens, String> {
+        let input =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read file {path}: {e}"))?;
+        self.semantic_tokens_text(path, &input)
+    }
+
+    /// Like [`Driver::semantic_tokens`], for source text already in memory.
+    pub fn semantic_tokens_text(&self, path: &str, input: &str) -> Result<SemanticTokens, String> {
+        let parsed = self.parse_text(path, input, false)?;
+        Ok(classify(&parsed))
+    }
+}
+
+/// Names declared at module level, by kind.
+#[derive(Default)]
+struct Globals {
+    kinds: HashMap<String, SemanticTokenKind>,
+    /// Module-level variables, which shadow builtin names.
+    vars: HashSet<String>,
+}
+
+fn collect_globals(stmts: &[Stmt]) -> Globals {
+    let mut g = Globals::default();
+    for s in stmts {
+        match s {
+            Stmt::FuncDef(def) => {
+                g.kinds.insert(def.name.clone(), SemanticTokenKind::Function);
+            }
+            Stmt::StructDef(def) => {
+                g.kinds.insert(def.name.clone(), SemanticTokenKind::Type);
+            }
+            Stmt::EnumDef(def) => {
+                g.kinds.insert(def.name.clone(), SemanticTokenKind::Type);
+            }
+            Stmt::Use(u) => {
+                let alias = u.alias.clone().unwrap_or_else(|| xu_ir::infer_module_alias(&u.path));
+                g.kinds.insert(alias, SemanticTokenKind::Module);
+            }
+            Stmt::Assign(a) => {
+                if let (Some(decl), Expr::Ident(name, _)) = (a.decl, &a.target) {
+                    match decl {
+                        DeclKind::Let | DeclKind::Const => {
+                            g.kinds.insert(name.clone(), SemanticTokenKind::Constant);
+                        }
+                        DeclKind::Var => {
+                            g.vars.insert(name.clone());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    g
+}
+
+/// The kind and modifiers of a declaring token, if it is highlighted.
+fn declaration(role: DeclRole) -> Option<(SemanticTokenKind, u32)> {
+    let kind = match role {
+        DeclRole::Function | DeclRole::Method => SemanticTokenKind::Function,
+        DeclRole::Type => SemanticTokenKind::Type,
+        DeclRole::Module => SemanticTokenKind::Module,
+        DeclRole::Param => SemanticTokenKind::Parameter,
+        DeclRole::Constant => return Some((SemanticTokenKind::Constant, MOD_DECLARATION | MOD_READONLY)),
+        DeclRole::Variable => return None,
+    };
+    Some((kind, MOD_DECLARATION))
+}
+
+/// The kind and modifiers of a use of `target` named `name`.
+fn reference(
+    resolved: &Resolved,
+    globals: &Globals,
+    target: Target,
+    name: &str,
+    call: bool,
+) -> Option<(SemanticTokenKind, u32)> {
+    match target {
+        Target::Binding(decl) => match resolved.decls.get(&decl)? {
+            DeclRole::Param => Some((SemanticTokenKind::Parameter, 0)),
+            DeclRole::Function => Some((SemanticTokenKind::Function, 0)),
+            _ => None,
+        },
+        Target::Member => call.then_some((SemanticTokenKind::Function, 0)),
+        Target::Skip => None,
+        Target::Global => match globals.kinds.get(name) {
+            Some(&SemanticTokenKind::Constant) => Some((SemanticTokenKind::Constant, MOD_READONLY)),
+            Some(&kind) => Some((kind, 0)),
+            None if globals.vars.contains(name) => None,
+            None if BUILTIN_NAMES.contains(&name) => Some((SemanticTokenKind::Function, MOD_DEFAULT_LIBRARY)),
+            None if BUILTIN_TYPES.contains(&name) => Some((SemanticTokenKind::Type, MOD_DEFAULT_LIBRARY)),
+            None => None,
+        },
+    }
+}
+
+pub(crate) fn classify(parsed: &ParsedFile) -> SemanticTokens {
+    let text = parsed.source.text.as_str();
+    let mut globals = collect_globals(&parsed.module.stmts);
+    let resolved = resolve(text, &parsed.tokens);
+    // Declarations that failed to parse are missing from the AST but still
+    // usable in the editor's eyes.
+    for (name, decl) in &resolved.top_level {
+        match resolved.decls[decl] {
+            DeclRole::Variable => {
+                if !globals.kinds.contains_key(name) {
+                    globals.vars.insert(name.clone());
+                }
+            }
+            role => {
+                if let Some((kind, _)) = declaration(role) {
+                    globals.kinds.entry(name.clone()).or_insert(kind);
+                }
+            }
+        }
+    }
+
+    let mut classified: Vec<(Span, SemanticTokenKind, u32)> = Vec::new();
+    for (i, target) in resolved.targets.iter().enumerate() {
+        let Some(target) = *target else { continue };
+        let span = resolved.tokens[i].span;
+        let class = match resolved.decls.get(&i) {
+            Some(&role) => declaration(role),
+            None => {
+                let call = resolved.kind(i + 1) == TokenKind::LParen;
+                reference(&resolved, &globals, target, resolved.name(i), call)
+            }
+        };
+        classified.extend(class.map(|(kind, mods)| (span, kind, mods)));
+    }
+    for &(span, target) in &resolved.interpolated {
+        let name = &text[span.start.0 as usize..span.end.0 as usize];
+        classified.extend(reference(&resolved, &globals, target, name, false).map(|(kind, mods)| (span, kind, mods)));
+    }
+    classified.sort_by_key(|c| c.0.start);
+
+    let lines = LineIndex::new(text);
+    let tokens = classified
+        .into_iter()
+        .map(|(span, kind, modifiers)| {
+            let (line, col) = lines.line_col(text, span.start.0, ColumnUnit::Utf16);
+            SemanticToken {
+                span,
+                line,
+                start: col,
+                length: text[span.start.0 as usize..span.end.0 as usize].encode_utf16().count() as u32,
+                kind,
+                modifiers,
+            }
+        })
+        .collect();
+    SemanticTokens { tokens }
+}
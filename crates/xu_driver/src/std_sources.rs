@@ -0,0 +1,10 @@
+//! Standard library sources embedded at build time (see `build.rs`).
+
+include!(concat!(env!("OUT_DIR"), "/std_sources.rs"));
+
+/// The source of the standard library module imported as `path`, such as
+/// `std/csv` or `std/csv.xu`.
+pub(crate) fn std_source(path: &str) -> Option<&'static str> {
+    let key = path.strip_suffix(".xu").unwrap_or(path);
+    STD_SOURCES.iter().find(|(k, _)| *k == key).map(|(_, src)| *src)
+}
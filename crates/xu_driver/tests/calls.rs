@@ -0,0 +1,108 @@
+use xu_driver::Driver;
+
+fn collect_errors(src: &str) -> Vec<String> {
+    let parsed = Driver::new().parse_text("<test>", src, true).unwrap();
+    parsed
+        .diagnostics
+        .iter()
+        .filter(|d| matches!(d.severity, xu_syntax::Severity::Error))
+        .map(|d| d.message.clone())
+        .collect()
+}
+
+#[test]
+fn builtin_calls_are_checked_against_their_signatures() {
+    let src = "let s = \"abc\"\nprintln(max(1, 2, 3))\nprintln(gen_id(1))\nprintln(sqrt(s))\nsleep(1.5)\n";
+    assert_eq!(
+        collect_errors(src),
+        vec![
+            "Argument count mismatch: expected 2 but got 3",
+            "Argument count mismatch: expected 0 but got 1",
+            "Type mismatch: expected float but got text",
+            "Type mismatch: expected int but got float",
+        ]
+    );
+}
+
+#[test]
+fn builtin_calls_accept_what_the_runtime_accepts() {
+    let src = r#"
+func apply(n: int) -> float {
+  let f = |x| x * 2
+  println(parallel_map([n], f).length, inspect([n], pretty: true), to_text(n))
+  println()
+  return sqrt(n) + pow(clamp(n, 0, 10), 2.5)
+}
+func g(contains) {
+  return contains(1, 2, 3)
+}
+println(apply(4), g(|a, b, c| a + b + c), open("x.txt", "w"))
+"#;
+    let errs = collect_errors(src);
+    assert!(errs.is_empty(), "{errs:?}");
+}
+
+#[test]
+fn unknown_methods_on_builtin_types_suggest_the_nearest_one() {
+    let src = "let s = \"abc\"\nprintln(s.to_upperr(), [1].lenght(), 2.5.flor())\n";
+    let parsed = Driver::new().parse_text("<test>", src, true).unwrap();
+    let found: Vec<_> =
+        parsed.diagnostics.iter().map(|d| (d.message.as_str(), d.suggestion.as_deref().unwrap_or(""))).collect();
+    assert_eq!(
+        found,
+        vec![
+            ("Unsupported method: to_upperr for type text", "Did you mean 'to_upper'?"),
+            ("Unsupported method: lenght for type list", "Did you mean 'length'?"),
+            ("Unsupported method: flor for type float", "Did you mean 'floor'?"),
+        ]
+    );
+    assert_eq!(parsed.diagnostics[0].code, Some(xu_syntax::codes::UNSUPPORTED_METHOD));
+}
+
+#[test]
+fn methods_are_only_checked_on_known_builtin_receivers() {
+    let src = r#"
+P has {
+  x: int
+}
+P does {
+  func lenght() -> int {
+    return self.x
+  }
+}
+func f(v) {
+  return v.anything()
+}
+let d = {"a": 1}
+println(P { x: 1 }.lenght(), f(1), d.get_or_default("a", 0), d.keys().length(), 3.to_base(2))
+var xs = [1]
+xs.add(2)
+"#;
+    let errs = collect_errors(src);
+    assert!(errs.is_empty(), "{errs:?}");
+}
+
+#[test]
+fn struct_literal_fields_are_checked_against_their_declared_types() {
+    let src = r#"
+Point has {
+  x: int
+  y: float
+  tags: list[text]
+  tag: T
+}
+let p = Point { x: "a", y: 2, tags: [], tag: 1 }
+let q = Point { x: 1, y: true, tags: [1], tag: "t" }
+func f(n) -> Point {
+  return Point { x: n, y: n, tags: ["a"], tag: n }
+}
+"#;
+    assert_eq!(
+        collect_errors(src),
+        vec![
+            "Type mismatch for field 'x' of struct Point: expected int but got text",
+            "Type mismatch for field 'y' of struct Point: expected float but got ?",
+            "Type mismatch for field 'tags' of struct Point: expected list[text] but got list[int]",
+        ]
+    );
+}
@@ -0,0 +1,229 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+use xu_driver::{CodegenOptions, CodegenTarget, Driver};
+
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xu_codegen_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, text) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+    dir
+}
+
+const LIB: &str = "Shape has {\n    w: int\n    h: int\n    func area() -> int { return self.w * self.h }\n    static func square(n: int) -> Shape { return Shape{ w: n, h: n } }\n}\npub func describe(s: Shape) -> string { return \"{s.w}x{s.h}\" }\npub func square(n: int) -> Shape { return Shape.square(n) }\n";
+
+const MAIN: &str = "use \"lib.xu\" as lib\nTone with [ low | high ]\nfunc label(t: Tone) -> string {\n    return match t {\n        Tone#low { \"low\" }\n        Tone#high { \"high\" }\n        _ { \"?\" }\n    }\n}\nlet s = lib.square(3)\nprintln(lib.describe(s), s.area())\nvar counts: {string: int} = {}\nfor w in [\"a\", \"b\", \"a\"] {\n    counts[w] = counts.get_or_default(w, 0) + 1\n}\nprintln(inspect(counts), label(Tone#high))\nvar total = 0\nfor i in [1..=4] {\n    if i == 2 { continue }\n    total += i\n}\nprintln(total, 7 / 2, 7.0 / 2)\n";
+
+#[test]
+fn imports_become_module_loaders() {
+    let dir = project("modules", &[("main.xu", MAIN), ("lib.xu", LIB)]);
+    let out = Driver::new().codegen(
+        dir.join("main.xu").to_str().unwrap(),
+        true,
+        CodegenTarget::Lua,
+        &CodegenOptions::default(),
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+    let out = out.expect("codegen");
+
+    assert!(out.starts_with("local xu = require(\"xu_runtime\")\n"));
+    assert_eq!(out.matches("xu.modules[\"lib.xu\"] = function()").count(), 1);
+    assert!(out.contains("xu.run(function()"));
+}
+
+#[test]
+fn generated_lua_prints_what_the_interpreter_prints() {
+    let dir = project("run", &[("main.xu", MAIN), ("lib.xu", LIB)]);
+    let opts = CodegenOptions { inject_runtime: true };
+    let code = Driver::new().codegen(dir.join("main.xu").to_str().unwrap(), true, CodegenTarget::Lua, &opts);
+    let code = code.expect("codegen");
+    let script = dir.join("main.lua");
+    std::fs::write(&script, code).unwrap();
+    let run = Command::new("lua").arg(&script).output();
+    let _ = std::fs::remove_dir_all(&dir);
+    let Ok(run) = run else {
+        eprintln!("skipping: lua not found");
+        return;
+    };
+
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "3x3\n9\n{\"a\": 2, \"b\": 1}\nhigh\n8\n3\n3.5\n");
+}
+
+#[test]
+fn injected_lua_runtime_keeps_only_what_is_used() {
+    let dir = project("shake", &[("main.xu", "var d: {string: int} = {}\nprintln(d.get_or_default(\"k\", 1))\n")]);
+    let opts = CodegenOptions { inject_runtime: true };
+    let code = Driver::new().codegen(dir.join("main.xu").to_str().unwrap(), true, CodegenTarget::Lua, &opts);
+    let _ = std::fs::remove_dir_all(&dir);
+    let code = code.expect("codegen");
+
+    assert!(code.len() < CodegenTarget::Lua.runtime().len() / 2, "{} bytes", code.len());
+    assert!(code.contains("function xu.print("));
+    assert!(code.contains("function DictMethods.get_or_default("));
+    assert!(!code.contains("function xu.builder_new("));
+    assert!(!code.contains("function ListMethods.sort("));
+}
+
+#[test]
+fn generated_c_prints_what_the_interpreter_prints() {
+    let dir = project("run_c", &[("main.xu", MAIN), ("lib.xu", LIB)]);
+    let code = Driver::new().codegen(
+        dir.join("main.xu").to_str().unwrap(),
+        true,
+        CodegenTarget::C,
+        &CodegenOptions::default(),
+    );
+    let code = code.expect("codegen");
+    assert!(code.starts_with("#include \"xu_runtime.h\"\n"));
+    std::fs::write(dir.join("main.c"), code).unwrap();
+    std::fs::write(dir.join("xu_runtime.h"), CodegenTarget::C.runtime()).unwrap();
+    let exe = dir.join("main");
+    let build = Command::new("cc").arg("-std=c99").arg("-o").arg(&exe).arg(dir.join("main.c")).arg("-lm").output();
+    let Ok(build) = build else {
+        let _ = std::fs::remove_dir_all(&dir);
+        eprintln!("skipping: cc not found");
+        return;
+    };
+    assert!(build.status.success(), "{}", String::from_utf8_lossy(&build.stderr));
+    let run = Command::new(&exe).output().expect("run");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "3x3\n9\n{\"a\": 2, \"b\": 1}\nhigh\n8\n3\n3.5\n");
+}
+
+#[test]
+fn unsupported_programs_are_rejected() {
+    let dir = project(
+        "unsupported",
+        &[("std.xu", "use \"std/random\" as random\n"), ("builtin.xu", "println(base64_encode(\"x\"))\n")],
+    );
+    let driver = Driver::new();
+    let codegen = |file: &str| {
+        driver.codegen(dir.join(file).to_str().unwrap(), false, CodegenTarget::Lua, &CodegenOptions::default())
+    };
+    let std_err = codegen("std.xu").unwrap_err();
+    let builtin_err = codegen("builtin.xu").unwrap_err();
+    let c_err = driver
+        .codegen(dir.join("builtin.xu").to_str().unwrap(), false, CodegenTarget::C, &CodegenOptions::default())
+        .unwrap_err();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(std_err.contains("the standard library is not available"), "{std_err}");
+    assert!(builtin_err.contains("builtin `base64_encode` is not available in Lua output"), "{builtin_err}");
+    assert!(c_err.contains("builtin `base64_encode` is not available in C output"), "{c_err}");
+}
+
+const OPTIONAL: &str = "User has {\n    name: string\n}\nfunc find(id: int) -> Option {\n    if id == 1 { return Option#some(User{ name: \"ann\" }) }\n    return Option#none\n}\nfunc loud(x: string) -> string {\n    println(\"eval {x}\")\n    return x\n}\nprintln(inspect(find(1)?.name.to_upper()), inspect(find(2)?.name.to_upper()))\nlet d = {\"a\": 1}\nprintln(d?[\"a\"], find(1)?.name ?? loud(\"x\"), find(2)?.name ?? loud(\"y\"))\n";
+
+#[test]
+fn optional_chaining_translates_to_lua_and_c() {
+    let dir = project("optional", &[("main.xu", OPTIONAL)]);
+    let entry = dir.join("main.xu");
+    let lua = Driver::new().codegen(
+        entry.to_str().unwrap(),
+        true,
+        CodegenTarget::Lua,
+        &CodegenOptions { inject_runtime: true },
+    );
+    let c = Driver::new().codegen(entry.to_str().unwrap(), true, CodegenTarget::C, &CodegenOptions::default());
+    std::fs::write(dir.join("main.lua"), lua.expect("lua codegen")).unwrap();
+    std::fs::write(dir.join("main.c"), c.expect("c codegen")).unwrap();
+    std::fs::write(dir.join("xu_runtime.h"), CodegenTarget::C.runtime()).unwrap();
+    let exe = dir.join("main");
+    let lua_run = Command::new("lua").arg(dir.join("main.lua")).output();
+    let build = Command::new("cc").arg("-std=c99").arg("-o").arg(&exe).arg(dir.join("main.c")).arg("-lm").output();
+    let c_run = build.as_ref().ok().filter(|b| b.status.success()).map(|_| Command::new(&exe).output().expect("run"));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let want = "\"ANN\"\nOption#none\neval y\n1\nann\ny\n";
+    match lua_run {
+        Ok(run) => assert_eq!(String::from_utf8_lossy(&run.stdout), want, "{}", String::from_utf8_lossy(&run.stderr)),
+        Err(_) => eprintln!("skipping: lua not found"),
+    }
+    match (build, c_run) {
+        (Ok(_), Some(run)) => assert_eq!(String::from_utf8_lossy(&run.stdout), want),
+        (Ok(build), None) => panic!("{}", String::from_utf8_lossy(&build.stderr)),
+        (Err(_), _) => eprintln!("skipping: cc not found"),
+    }
+}
+
+const INT_OPS: &str = "var x = 7\nx %= 4\nx **= 3\nx <<= 2\nx >>= 1\nx &= 62\nx |= 1\nx ^= 3\nprintln(x, 2 ** -1, -2 ** 2, ~5, -17 >> 2, 5 & 1 == 1)\nlet d = {\"n\": 3}\nd[\"n\"] **= 2\nprintln(d[\"n\"])\nprintln(1.5 & 1)\n";
+
+#[test]
+fn int_operators_translate_to_lua_and_c() {
+    let dir = project("int_ops", &[("main.xu", INT_OPS)]);
+    let entry = dir.join("main.xu");
+    let lua = Driver::new().codegen(
+        entry.to_str().unwrap(),
+        true,
+        CodegenTarget::Lua,
+        &CodegenOptions { inject_runtime: true },
+    );
+    let c = Driver::new().codegen(entry.to_str().unwrap(), true, CodegenTarget::C, &CodegenOptions::default());
+    std::fs::write(dir.join("main.lua"), lua.expect("lua codegen")).unwrap();
+    std::fs::write(dir.join("main.c"), c.expect("c codegen")).unwrap();
+    std::fs::write(dir.join("xu_runtime.h"), CodegenTarget::C.runtime()).unwrap();
+    let exe = dir.join("main");
+    let lua_run = Command::new("lua").arg(dir.join("main.lua")).output();
+    let build = Command::new("cc").arg("-std=c99").arg("-o").arg(&exe).arg(dir.join("main.c")).arg("-lm").output();
+    let c_run = build.as_ref().ok().filter(|b| b.status.success()).map(|_| Command::new(&exe).output().expect("run"));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let want = "52\n0.5\n-4\n-6\n-5\ntrue\n9\n";
+    let err = "Bitwise operator '&' requires int operands, got float and int";
+    match lua_run {
+        Ok(run) => {
+            assert_eq!(String::from_utf8_lossy(&run.stdout), want);
+            assert!(String::from_utf8_lossy(&run.stderr).contains(err), "{}", String::from_utf8_lossy(&run.stderr));
+        }
+        Err(_) => eprintln!("skipping: lua not found"),
+    }
+    match (build, c_run) {
+        (Ok(_), Some(run)) => {
+            assert_eq!(String::from_utf8_lossy(&run.stdout), want);
+            assert!(String::from_utf8_lossy(&run.stderr).contains(err), "{}", String::from_utf8_lossy(&run.stderr));
+        }
+        (Ok(build), None) => panic!("{}", String::from_utf8_lossy(&build.stderr)),
+        (Err(_), _) => eprintln!("skipping: cc not found"),
+    }
+}
+
+const MEMBERSHIP: &str = "var calls = 0\nfunc f(n: int) -> int {\n    calls += 1\n    return n\n}\nlet x = 5\nprintln(0 <= x < 10, f(3) < 2 < f(9), 1 < f(2) <= 2, calls)\nlet r = 1..=3\nprintln(x in 1..100, 100 in 1..100, 2.5 in 1..3, 3 in r)\nlet d = {\"a\": 1}\nprintln([2, 3] in [1, [2, 3]], \"a\" in d, 1 in d, \"ell\" in \"hello\", 3 in (1, 2))\n";
+
+#[test]
+fn chained_comparisons_and_in_translate_to_lua_and_c() {
+    let dir = project("membership", &[("main.xu", MEMBERSHIP)]);
+    let entry = dir.join("main.xu");
+    let lua = Driver::new().codegen(
+        entry.to_str().unwrap(),
+        true,
+        CodegenTarget::Lua,
+        &CodegenOptions { inject_runtime: true },
+    );
+    let c = Driver::new().codegen(entry.to_str().unwrap(), true, CodegenTarget::C, &CodegenOptions::default());
+    std::fs::write(dir.join("main.lua"), lua.expect("lua codegen")).unwrap();
+    std::fs::write(dir.join("main.c"), c.expect("c codegen")).unwrap();
+    std::fs::write(dir.join("xu_runtime.h"), CodegenTarget::C.runtime()).unwrap();
+    let exe = dir.join("main");
+    let lua_run = Command::new("lua").arg(dir.join("main.lua")).output();
+    let build = Command::new("cc").arg("-std=c99").arg("-o").arg(&exe).arg(dir.join("main.c")).arg("-lm").output();
+    let c_run = build.as_ref().ok().filter(|b| b.status.success()).map(|_| Command::new(&exe).output().expect("run"));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let want = "true\nfalse\ntrue\n2\ntrue\nfalse\nfalse\ntrue\ntrue\ntrue\nfalse\ntrue\nfalse\n";
+    match lua_run {
+        Ok(run) => assert_eq!(String::from_utf8_lossy(&run.stdout), want, "{}", String::from_utf8_lossy(&run.stderr)),
+        Err(_) => eprintln!("skipping: lua not found"),
+    }
+    match (build, c_run) {
+        (Ok(_), Some(run)) => assert_eq!(String::from_utf8_lossy(&run.stdout), want),
+        (Ok(build), None) => panic!("{}", String::from_utf8_lossy(&build.stderr)),
+        (Err(_), _) => eprintln!("skipping: cc not found"),
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use xu_driver::Driver;
+use xu_ir::Executable;
+
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xu_exec_cache_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, text) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+    dir
+}
+
+fn ops(e: &Executable) -> Vec<xu_ir::Op> {
+    match e {
+        Executable::Bytecode(p) => p.bytecode.as_ref().expect("bytecode").ops.clone(),
+        Executable::Ast(_) => panic!("expected bytecode"),
+    }
+}
+
+#[test]
+fn compiled_files_are_reused_while_sources_are_unchanged() {
+    let lib = "pub func f(x: int) -> int { return x + 1 }\n";
+    let main = "use \"lib.xu\" as lib\nprintln(lib.f(2))\n";
+    let dir = project("reuse", &[("main.xu", main), ("lib.xu", lib), ("other.xu", "println(7)\n")]);
+    let cache = dir.join("target").join(".xu_cache");
+    let driver = || Driver::new().with_cache_dir(&cache);
+    let path = dir.join("main.xu").to_string_lossy().into_owned();
+    let other = dir.join("other.xu").to_string_lossy().into_owned();
+
+    let first = driver().compile_file(&path, true).unwrap();
+    let entry = driver().cache_entry_path(&path, main, true).unwrap();
+    assert!(entry.is_file());
+    assert_eq!(entry.extension().unwrap(), xu_driver::CACHE_EXTENSION);
+    let stored = std::fs::read(&entry).unwrap();
+    let again = driver().compile_file(&path, true).unwrap();
+    assert_eq!(ops(&again.executable), ops(&first.executable));
+    assert_eq!(again.source.text.as_str(), main);
+    assert!(!again.tokens.is_empty());
+    assert_eq!(std::fs::read(&entry).unwrap(), stored);
+
+    // The entry is what a hit returns: serve main.xu's code for other.xu.
+    let other_first = driver().compile_file(&other, true).unwrap();
+    assert_ne!(ops(&other_first.executable), ops(&first.executable));
+    std::fs::copy(&entry, driver().cache_entry_path(&other, "println(7)\n", true).unwrap()).unwrap();
+    assert_eq!(ops(&driver().compile_file(&other, true).unwrap().executable), ops(&first.executable));
+
+    // Other options are other entries.
+    assert_ne!(driver().cache_entry_path(&path, main, false), Some(entry.clone()));
+    assert_ne!(driver().with_contracts(false).cache_entry_path(&path, main, true), Some(entry.clone()));
+    assert_eq!(Driver::new().cache_entry_path(&path, main, true), None);
+
+    // Changing an import invalidates the entry, which is then rewritten.
+    std::fs::write(dir.join("lib.xu"), "pub func f(x: int) -> int { return x + 2 }\n").unwrap();
+    driver().compile_file(&path, true).unwrap();
+    assert_ne!(std::fs::read(&entry).unwrap(), stored);
+
+    // Corrupt entries are ignored.
+    std::fs::write(&entry, b"garbage").unwrap();
+    assert_eq!(ops(&driver().compile_file(&path, true).unwrap().executable), ops(&first.executable));
+    assert_ne!(std::fs::read(&entry).unwrap(), b"garbage");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn files_with_diagnostics_are_not_cached() {
+    let main = "let x = \n";
+    let dir = project("diags", &[("main.xu", main)]);
+    let cache = dir.join("cache");
+    let driver = Driver::new().with_cache_dir(&cache);
+    let path = dir.join("main.xu").to_string_lossy().into_owned();
+    let compiled = driver.compile_file(&path, true).unwrap();
+    assert!(!compiled.diagnostics.is_empty());
+    assert!(!driver.cache_entry_path(&path, main, true).unwrap().exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
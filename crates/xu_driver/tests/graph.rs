@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use xu_driver::{Driver, ProjectGraph, TOP_LEVEL};
+
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xu_graph_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, text) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+    dir
+}
+
+/// Call edges as `caller -> callee` labels.
+fn calls(g: &ProjectGraph) -> Vec<String> {
+    g.calls.iter().map(|&(a, b)| format!("{} -> {}", g.function_label(a), g.function_label(b))).collect()
+}
+
+#[test]
+fn calls_are_resolved_through_imports_and_receivers() {
+    let lib = "Shape has {\n    w: int\n    func area() -> int { return scale(self.w) }\n    static func unit() -> Shape { return Shape{ w: 1 } }\n}\nShape does {\n    func double() -> int { return self.area() * 2 }\n}\nfunc scale(n: int) -> int { return n * 2 }\n";
+    let main = "use \"lib.xu\" as lib\nfunc run() {\n    let s = lib.Shape.unit()\n    println(lib.scale(s.double()), scale(1))\n    [1].map(|x| helper(x))\n}\nfunc helper(x: int) -> int { return x }\nrun()\n";
+    let dir = project("calls", &[("main.xu", main), ("lib.xu", lib)]);
+    let g = Driver::new().graph(dir.join("main.xu").to_str().unwrap()).expect("graph");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let modules: Vec<_> = g.modules.iter().map(|m| (m.path.as_str(), m.found, m.functions)).collect();
+    assert_eq!(modules, [("main.xu", true, 2), ("lib.xu", true, 4)]);
+    assert_eq!(g.imports, [(0, 1)]);
+    assert_eq!(g.function_label(g.functions.iter().position(|f| f.name == TOP_LEVEL).unwrap()), "main.xu:<top>");
+    assert_eq!(
+        calls(&g),
+        [
+            "main.xu:run -> main.xu:helper",
+            "main.xu:run -> lib.xu:Shape.unit",
+            "main.xu:run -> lib.xu:scale",
+            "main.xu:<top> -> main.xu:run",
+            "lib.xu:Shape.area -> lib.xu:scale",
+            "lib.xu:Shape.double -> lib.xu:Shape.area",
+        ]
+    );
+    assert!(g.import_cycles().is_empty() && g.call_cycles().is_empty());
+}
+
+#[test]
+fn cycles_and_missing_modules_are_reported() {
+    let a = "use \"b.xu\" as b\nuse \"missing.xu\" as gone\nfunc ping(n: int) { if n > 0 { b.pong(n - 1) } }\n";
+    let b = "use \"a.xu\" as a\nfunc pong(n: int) { a.ping(n) }\nfunc fact(n: int) -> int { return n * fact(n - 1) }\n";
+    let dir = project("cycles", &[("a.xu", a), ("b.xu", b)]);
+    let g = Driver::new().graph(dir.join("a.xu").to_str().unwrap()).expect("graph");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let missing = g.modules.iter().position(|m| !m.found).expect("missing module");
+    assert_eq!(g.modules[missing].path, "missing.xu");
+    assert_eq!(g.import_cycles(), [vec![0, 1]]);
+    let cycles: Vec<Vec<String>> =
+        g.call_cycles().iter().map(|c| c.iter().map(|&f| g.function_label(f)).collect()).collect();
+    assert_eq!(cycles, [vec!["a.xu:ping".to_string(), "b.xu:pong".into()], vec!["b.xu:fact".into()]]);
+
+    let dot = g.imports_to_dot();
+    assert!(dot.starts_with("digraph imports {"), "{dot}");
+    assert!(dot.contains("m0 -> m1 [color=red];"), "{dot}");
+    assert!(dot.contains("style=dashed"), "{dot}");
+    assert!(g.calls_to_dot().contains("subgraph cluster_2"));
+    let json = g.to_json();
+    assert!(json.contains("\"import_cycles\":[[0,1]]"), "{json}");
+    assert!(Driver::new().graph("/nonexistent/main.xu").is_err());
+}
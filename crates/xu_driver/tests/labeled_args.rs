@@ -0,0 +1,104 @@
+use xu_driver::Driver;
+use xu_ir::{Expr, Stmt};
+
+/// The last statement's expression after labels are resolved, and the
+/// errors reported.
+fn resolve(path: &str, src: &str) -> (Option<Expr>, Vec<String>) {
+    let parsed = Driver::new().parse_text_no_analyze(path, src).unwrap();
+    let errors = parsed
+        .diagnostics
+        .iter()
+        .filter(|d| matches!(d.severity, xu_syntax::Severity::Error))
+        .map(|d| d.message.clone())
+        .collect();
+    let last = match parsed.module.stmts.last() {
+        Some(Stmt::Expr(e)) => Some(e.clone()),
+        _ => None,
+    };
+    (last, errors)
+}
+
+fn args(e: &Option<Expr>) -> &[Expr] {
+    match e {
+        Some(Expr::Call(c)) => {
+            assert!(c.labels.is_empty(), "labels left: {:?}", c.labels);
+            &c.args
+        }
+        Some(Expr::MethodCall(m)) => {
+            assert!(m.labels.is_empty(), "labels left: {:?}", m.labels);
+            &m.args
+        }
+        other => panic!("not a call: {other:?}"),
+    }
+}
+
+#[test]
+fn labels_resolve_against_the_functions_own_parameters() {
+    let src = "func f(a, b: int = 2, c = [1, -3], d = \"x\") { return a }\nf(0, c: [], b: 5)\n";
+    let (call, errors) = resolve("<test>", src);
+    assert!(errors.is_empty(), "{errors:?}");
+    assert!(matches!(args(&call), [Expr::Int(0), Expr::Int(5), Expr::List(items)] if items.is_empty()));
+    // A skipped parameter takes its constant default; later ones are left to the callee.
+    let (call, errors) = resolve("<test>", "func f(a, b = -1, c = 0, d = 1) { return a }\nf(0, c: 7)\n");
+    assert!(errors.is_empty(), "{errors:?}");
+    match args(&call) {
+        [Expr::Int(0), Expr::Unary { expr, .. }, Expr::Int(7)] => assert!(matches!(expr.as_ref(), Expr::Int(1))),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn builtins_skip_optional_parameters_with_unit() {
+    let (call, errors) = resolve("<test>", "inspect([1], max_items: 3)\n");
+    assert!(errors.is_empty(), "{errors:?}");
+    assert!(matches!(
+        args(&call),
+        [Expr::List(_), Expr::Tuple(a), Expr::Tuple(b), Expr::Int(3)] if a.is_empty() && b.is_empty()
+    ));
+    let (call, errors) = resolve("<test>", "dict(capacity: 10)\n");
+    assert!(errors.is_empty(), "{errors:?}");
+    assert!(matches!(args(&call), [Expr::Int(10)]));
+}
+
+#[test]
+fn module_functions_resolve_through_their_alias() {
+    let (call, errors) = resolve("<test>", "use \"std/csv\" as table\ntable.parse(t, headers: false)\n");
+    assert!(errors.is_empty(), "{errors:?}");
+    assert!(matches!(args(&call), [Expr::Ident(..), Expr::Bool(false)]));
+
+    let dir = std::env::temp_dir().join(format!("xu_labeled_args_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("greet.xu"),
+        "pub func hello(name, greeting = \"hi\", punct = \"!\") {\n  return name\n}\n",
+    )
+    .unwrap();
+    let main = dir.join("main.xu");
+    let (call, errors) = resolve(main.to_str().unwrap(), "use \"greet\"\ngreet.hello(\"a\", punct: \"?\")\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(errors.is_empty(), "{errors:?}");
+    match args(&call) {
+        [Expr::Str(a), Expr::Str(b), Expr::Str(c)] => {
+            assert_eq!((a.as_str(), b.as_str(), c.as_str()), ("a", "hi", "?"))
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn labels_that_do_not_resolve_are_errors() {
+    let cases = [
+        ("inspect(v, depth: 1)\n", "Invalid argument label 'depth' in call to inspect"),
+        ("inspect(v, pretty: true, pretty: false)\n", "Invalid argument label 'pretty' in call to inspect"),
+        ("func f(a, b) { return a }\nf(1, a: 2)\n", "Invalid argument label 'a' in call to f"),
+        ("let g = 1\ng(x: 1)\n", "Invalid argument label 'x' in call to g"),
+        ("csv.parse(t, headers: false)\n", "Invalid argument label 'headers' in call to csv.parse"),
+        ("func f(a, b, c = 0) { return a }\nf(1, c: 2)\n", "Expected b:"),
+        ("func f(a, b = a, c = 0) { return a }\nf(1, c: 2)\n", "Expected b:"),
+        ("inspect(pretty: true)\n", "Expected value:"),
+    ];
+    for (src, expected) in cases {
+        let (_, errors) = resolve("<test>", src);
+        assert_eq!(errors, vec![expected], "{src}");
+    }
+}
@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+
+use xu_driver::Driver;
+
+/// A scratch workspace; removed on drop.
+struct Workspace(PathBuf);
+
+impl Workspace {
+    fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("xu_references_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (path, text) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, text).unwrap();
+        }
+        Workspace(dir.canonicalize().unwrap())
+    }
+
+    fn path(&self, file: &str) -> String {
+        self.0.join(file).to_str().unwrap().to_string()
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Byte offset of the `nth` occurrence of `needle` in `text`.
+fn offset(text: &str, needle: &str, nth: usize) -> u32 {
+    text.match_indices(needle).nth(nth).unwrap_or_else(|| panic!("no {needle} #{nth}")).0 as u32
+}
+
+/// `(file name, line, character)` per reference.
+fn references(driver: &Driver, path: &str, offset: u32) -> Vec<(String, u32, u32)> {
+    driver
+        .find_references(path, offset)
+        .expect("references")
+        .into_iter()
+        .map(|l| {
+            let name = Path::new(&l.path).file_name().unwrap().to_str().unwrap().to_string();
+            (name, l.start.line, l.start.character)
+        })
+        .collect()
+}
+
+const MAIN: &str = "func total(n: int) -> int {
+    let acc = n * 2
+    return acc + n
+}
+
+func other(n: int) -> int {
+    return n
+}
+
+println(\"{total(2)} and {other(1)}\")
+";
+
+#[test]
+fn references_follow_lexical_scopes() {
+    let ws = Workspace::new("scopes", &[("main.xu", MAIN)]);
+    let main = ws.path("main.xu");
+    let driver = Driver::new();
+    // The parameter `n` of `total`, not the one of `other`.
+    let n = references(&driver, &main, offset(MAIN, "n:", 0));
+    assert_eq!(n, [("main.xu".into(), 0, 11), ("main.xu".into(), 1, 14), ("main.xu".into(), 2, 17)]);
+    // Uses inside string interpolations count.
+    let total = references(&driver, &main, offset(MAIN, "total", 1));
+    assert_eq!(total, [("main.xu".into(), 0, 5), ("main.xu".into(), 9, 10)]);
+}
+
+#[test]
+fn rename_edits_every_file_that_uses_the_name() {
+    let lib = "func area(w: int, h: int) -> int {\n    return w * h\n}\n";
+    let main = "use \"lib.xu\" as shapes\nprintln(shapes.area(2, 3))\nprintln(area(1, 1))\n";
+    let ws = Workspace::new("imports", &[("lib.xu", lib), ("main.xu", main)]);
+    let (lib_path, main_path) = (ws.path("lib.xu"), ws.path("main.xu"));
+    let driver = Driver::new();
+
+    let edit = driver.rename(&main_path, offset(main, "area", 0), "surface").expect("rename");
+    assert_eq!(edit.paths(), [main_path.as_str(), lib_path.as_str()]);
+    assert_eq!(
+        edit.apply(&main_path, main),
+        "use \"lib.xu\" as shapes\nprintln(shapes.surface(2, 3))\nprintln(surface(1, 1))\n"
+    );
+    assert_eq!(edit.apply(&lib_path, lib), lib.replace("area", "surface"));
+    assert!(edit.to_json().starts_with(&format!(
+        "{{\"changes\":{{\"file://{main_path}\":[{{\"range\":{{\"start\":{{\"line\":1,\"character\":15}},\"end\":{{\"line\":1,\"character\":19}}}},\"newText\":\"surface\"}}"
+    )));
+
+    // Renaming from the declaring file finds the importer too.
+    let from_lib = references(&driver, &lib_path, offset(lib, "area", 0));
+    assert_eq!(from_lib.len(), 3, "{from_lib:?}");
+
+    // An explicit alias renames within its file only.
+    let alias = driver.rename(&main_path, offset(main, "shapes", 1), "geo").expect("rename alias");
+    assert_eq!(alias.paths(), [main_path.as_str()]);
+    assert_eq!(alias.edits.len(), 2);
+}
+
+#[test]
+fn rename_rejects_captures_conflicts_and_bad_names() {
+    let src = "let limit = 3\n\nfunc f(x: int) -> int {\n    let y = 1\n    return x + y + limit\n}\n\nfunc g() {}\n";
+    let ws = Workspace::new("conflicts", &[("main.xu", src)]);
+    let main = ws.path("main.xu");
+    let driver = Driver::new();
+    let x = offset(src, "x:", 0);
+
+    assert!(driver.rename(&main, x, "count").is_ok());
+    let capture = driver.rename(&main, x, "y").unwrap_err();
+    assert!(capture.contains("would change what"), "{capture}");
+    let shadow = driver.rename(&main, x, "limit").unwrap_err();
+    assert!(shadow.contains("would change what `limit` refers to"), "{shadow}");
+    let conflict = driver.rename(&main, offset(src, "g()", 0), "f").unwrap_err();
+    assert!(conflict.contains("already declared"), "{conflict}");
+    assert!(driver.rename(&main, x, "func").unwrap_err().contains("not a valid identifier"));
+    assert!(driver.rename(&main, x, "a b").unwrap_err().contains("not a valid identifier"));
+    let builtin = driver.rename(&ws.path("main.xu"), 0, "keep");
+    assert!(builtin.is_err());
+}
+
+#[test]
+fn fields_builtins_and_whitespace_are_not_symbols() {
+    let src = "Point has {\n    x: int\n}\n\nlet p = Point{ x: 1 }\nprintln(p.x)\n";
+    let ws = Workspace::new("unsupported", &[("main.xu", src)]);
+    let main = ws.path("main.xu");
+    let driver = Driver::new();
+    let field = driver.find_references(&main, offset(src, "x:", 0)).unwrap_err();
+    assert!(field.contains("field"), "{field}");
+    let member = driver.rename(&main, offset(src, "x)", 0), "y").unwrap_err();
+    assert!(member.contains("cannot be renamed"), "{member}");
+    let builtin = driver.find_references(&main, offset(src, "println", 0)).unwrap_err();
+    assert!(builtin.contains("builtin"), "{builtin}");
+    assert!(driver.find_references(&main, offset(src, "\n\n", 0) + 1).is_err());
+    assert_eq!(references(&driver, &main, offset(src, "Point", 1)).len(), 2);
+    assert!(driver.find_references("/nonexistent/main.xu", 0).is_err());
+}
+
+#[test]
+fn crlf_files_use_stored_offsets() {
+    let src = "let a = 1\r\nlet b = a\r\nprintln(a + b)\r\n";
+    let ws = Workspace::new("crlf", &[("main.xu", src)]);
+    let main = ws.path("main.xu");
+    let edit = Driver::new().rename(&main, offset(src, "a", 2), "alpha").expect("rename");
+    assert_eq!(edit.apply(&main, src), "let alpha = 1\r\nlet b = alpha\r\nprintln(alpha + b)\r\n");
+    let last = &edit.edits[2].location;
+    assert_eq!((last.start.line, last.start.character), (2, 8));
+}
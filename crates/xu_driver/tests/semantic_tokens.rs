@@ -0,0 +1,120 @@
+use xu_driver::{
+    Driver, MOD_DECLARATION, MOD_DEFAULT_LIBRARY, MOD_READONLY, SEMANTIC_TOKEN_TYPES, SemanticTokenKind,
+};
+
+/// `(line, text, kind, modifiers)` per classified token.
+fn classify(src: &str) -> Vec<(u32, String, SemanticTokenKind, u32)> {
+    let tokens = Driver::new().semantic_tokens_text("<mem>", src).expect("semantic tokens");
+    tokens
+        .tokens
+        .iter()
+        .map(|t| (t.line, src[t.span.start.0 as usize..t.span.end.0 as usize].to_string(), t.kind, t.modifiers))
+        .collect()
+}
+
+fn kinds_of(tokens: &[(u32, String, SemanticTokenKind, u32)], name: &str) -> Vec<(u32, SemanticTokenKind, u32)> {
+    tokens.iter().filter(|t| t.1 == name).map(|t| (t.0, t.2, t.3)).collect()
+}
+
+const SRC: &str = "use \"math\"
+let LIMIT = 10
+
+Point has {
+    x: int
+
+    func scaled(scale: int) -> int {
+        return self.x * scale
+    }
+}
+
+func add(a: int, b: int) -> int {
+    return a + b + LIMIT
+}
+
+func shadow(a: int) {
+    let a = 2
+    println(a)
+}
+
+let p = Point{ x: 1 }
+let ys = [1, 2].map(|v| v + add(v, 1))
+println(p.scaled(2), math.sqrt(4))
+";
+
+#[test]
+fn declarations_and_uses_are_classified() {
+    use SemanticTokenKind::*;
+    let tokens = classify(SRC);
+    assert_eq!(kinds_of(&tokens, "Point"), [(3, Type, MOD_DECLARATION), (20, Type, 0)]);
+    assert_eq!(kinds_of(&tokens, "add"), [(11, Function, MOD_DECLARATION), (21, Function, 0)]);
+    assert_eq!(kinds_of(&tokens, "scaled"), [(6, Function, MOD_DECLARATION), (22, Function, 0)]);
+    assert_eq!(kinds_of(&tokens, "scale"), [(6, Parameter, MOD_DECLARATION), (7, Parameter, 0)]);
+    assert_eq!(
+        kinds_of(&tokens, "LIMIT"),
+        [(1, Constant, MOD_DECLARATION | MOD_READONLY), (12, Constant, MOD_READONLY)]
+    );
+    assert_eq!(kinds_of(&tokens, "math"), [(22, Module, 0)]);
+    assert_eq!(kinds_of(&tokens, "sqrt"), [(22, Function, 0)]);
+    assert_eq!(kinds_of(&tokens, "println")[0], (17, Function, MOD_DEFAULT_LIBRARY));
+    assert_eq!(kinds_of(&tokens, "int")[0], (4, Type, MOD_DEFAULT_LIBRARY));
+    assert_eq!(kinds_of(&tokens, "v"), [(21, Parameter, MOD_DECLARATION), (21, Parameter, 0), (21, Parameter, 0)]);
+    assert_eq!(kinds_of(&tokens, "p"), [(20, Constant, MOD_DECLARATION | MOD_READONLY), (22, Constant, MOD_READONLY)]);
+    // Field names are left to the editor's grammar.
+    assert!(kinds_of(&tokens, "x").is_empty(), "{tokens:?}");
+}
+
+#[test]
+fn locals_shadow_parameters() {
+    use SemanticTokenKind::*;
+    let tokens = classify(SRC);
+    let a: Vec<_> = kinds_of(&tokens, "a").into_iter().filter(|t| t.0 >= 15).collect();
+    assert_eq!(a, [(15, Parameter, MOD_DECLARATION)]);
+}
+
+#[test]
+fn encodes_relative_positions_in_utf16() {
+    let driver = Driver::new();
+    let tokens = driver.semantic_tokens_text("<mem>", "let s = \"😀\"; let k = 1\nprintln(k)\n").expect("semantic tokens");
+    let (constant, function) = (SemanticTokenKind::Constant.index(), SemanticTokenKind::Function.index());
+    assert_eq!(
+        tokens.encode(),
+        [
+            0, 4, 1, constant, MOD_DECLARATION | MOD_READONLY,
+            0, 14, 1, constant, MOD_DECLARATION | MOD_READONLY,
+            1, 0, 7, function, MOD_DEFAULT_LIBRARY,
+            0, 8, 1, constant, MOD_READONLY,
+        ]
+    );
+    assert!(tokens.to_json().starts_with("{\"data\":[0,4,1,3,3,0,14,1,3,3,"), "{}", tokens.to_json());
+    assert_eq!(SEMANTIC_TOKEN_TYPES[SemanticTokenKind::Constant.index() as usize], "variable");
+}
+
+#[test]
+fn reads_files_and_classifies_files_with_errors() {
+    let dir = std::env::temp_dir().join(format!("xu_semantic_tokens_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.xu");
+    std::fs::write(&path, "func f(n: int) {\n    return n +\n}\nf(1)\n").unwrap();
+    let tokens = Driver::new().semantic_tokens(path.to_str().unwrap()).expect("semantic tokens");
+    let _ = std::fs::remove_dir_all(&dir);
+    let kinds: Vec<_> = tokens.tokens.iter().map(|t| (t.line, t.kind)).collect();
+    assert!(kinds.contains(&(0, SemanticTokenKind::Function)), "{kinds:?}");
+    assert!(kinds.contains(&(1, SemanticTokenKind::Parameter)), "{kinds:?}");
+    assert!(kinds.contains(&(3, SemanticTokenKind::Function)), "{kinds:?}");
+    assert!(Driver::new().semantic_tokens("/nonexistent/main.xu").is_err());
+}
+
+#[test]
+fn examples_are_classified_in_order() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+    let driver = Driver::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|e| e != "xu") {
+            continue;
+        }
+        let tokens = driver.semantic_tokens(path.to_str().unwrap()).expect("semantic tokens");
+        assert!(!tokens.tokens.is_empty(), "{}", path.display());
+        assert!(tokens.tokens.windows(2).all(|w| w[0].span.start < w[1].span.start), "{}", path.display());
+    }
+}
@@ -0,0 +1,28 @@
+use std::rc::Rc;
+
+use xu_driver::Driver;
+use xu_ir::{Constant, Executable, Stmt};
+
+#[test]
+fn compiled_functions_and_structs_share_their_parsed_definitions() {
+    let src = "P has {\n    x: int\n}\nfunc f(a: int) -> int { return a + 1 }\nprintln(f(1))\n";
+    let compiled = Driver::new().compile_text("main.xu", src, true).unwrap();
+    assert!(compiled.diagnostics.is_empty(), "{:?}", compiled.diagnostics);
+    let Executable::Bytecode(program) = &compiled.executable else { panic!("expected bytecode") };
+    let bytecode = program.bytecode.as_ref().expect("bytecode");
+    let (mut funcs, mut structs) = (0, 0);
+    for stmt in &program.module.stmts {
+        match stmt {
+            Stmt::FuncDef(def) => {
+                assert!(bytecode.constants.iter().any(|c| matches!(c, Constant::Func(f) if Rc::ptr_eq(&f.def, def))));
+                funcs += 1;
+            }
+            Stmt::StructDef(def) => {
+                assert!(bytecode.constants.iter().any(|c| matches!(c, Constant::Struct(s) if Rc::ptr_eq(s, def))));
+                structs += 1;
+            }
+            _ => {}
+        }
+    }
+    assert_eq!((funcs, structs), (1, 1));
+}
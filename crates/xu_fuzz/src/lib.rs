@@ -0,0 +1,119 @@
+//! Fuzzing harnesses for the Xu front end and VM.
+//!
+//! Each harness takes arbitrary bytes, must never panic, and returns normally
+//! whatever the input: malformed programs end in diagnostics or runtime
+//! errors. The `cargo fuzz` targets under `fuzz/` call these functions, and
+//! the crate's tests replay inputs that once crashed them.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use xu_driver::Driver;
+use xu_lexer::{Lexer, normalize_source};
+use xu_parser::Parser;
+use xu_runtime::{EnvVars, FileStat, FileSystem, Runtime};
+
+/// Wall-clock budget of one [`exec`] run.
+pub const EXEC_TIME_LIMIT: Duration = Duration::from_millis(200);
+
+/// Inputs longer than this are ignored by [`exec`], which is far slower per
+/// byte than the front end.
+pub const EXEC_MAX_INPUT: usize = 4096;
+
+/// Stack of the thread [`exec`] runs programs on: enough for the runtime's
+/// recursion limit even in unoptimized builds.
+const EXEC_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+fn text(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
+
+/// Lexes `data`, read as UTF-8 with invalid sequences replaced.
+pub fn lex(data: &[u8]) {
+    let normalized = normalize_source(&text(data));
+    let _ = Lexer::new(&normalized.text).lex();
+}
+
+/// Lexes and parses `data`.
+pub fn parse(data: &[u8]) {
+    let normalized = normalize_source(&text(data));
+    let lex = Lexer::new(&normalized.text).lex();
+    let bump = bumpalo::Bump::new();
+    let _ = Parser::new(&normalized.text, &lex.tokens, &bump).parse();
+}
+
+/// Compiles `data` and, if it has no errors, runs the bytecode with a time
+/// limit, an output limit, no file system access and an empty environment.
+/// Panics in the run are passed on to the caller.
+pub fn exec(data: &[u8]) {
+    if data.len() > EXEC_MAX_INPUT {
+        return;
+    }
+    let source = text(data);
+    let runner = std::thread::Builder::new()
+        .stack_size(EXEC_STACK_SIZE)
+        .spawn(move || exec_source(&source))
+        .expect("spawn exec thread");
+    if let Err(panic) = runner.join() {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+fn exec_source(source: &str) {
+    let driver = Driver::new();
+    let Ok(compiled) = driver.compile_text("fuzz.xu", source, false) else {
+        return;
+    };
+    if compiled.diagnostics.iter().any(|d| matches!(d.severity, xu_syntax::Severity::Error)) {
+        return;
+    }
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(Driver::new()));
+    rt.set_file_system(Box::new(NoFileSystem));
+    rt.set_env_vars(Box::new(NoEnvVars));
+    let discard: Box<dyn std::io::Write> = Box::new(std::io::sink());
+    rt.set_output_streams(Box::new(discard));
+
+    // The watchdog interrupts the run at the deadline unless dropping `done`
+    // wakes it first.
+    let interrupt = rt.interrupt_handle();
+    let (done, finished) = mpsc::channel::<()>();
+    let watchdog = std::thread::spawn(move || {
+        if finished.recv_timeout(EXEC_TIME_LIMIT) == Err(mpsc::RecvTimeoutError::Timeout) {
+            interrupt.interrupt();
+        }
+    });
+    let _ = rt.exec_executable(&compiled.executable);
+    drop(done);
+    let _ = watchdog.join();
+}
+
+/// Refuses every access, so fuzzed programs cannot touch the host.
+struct NoFileSystem;
+
+impl FileSystem for NoFileSystem {
+    fn metadata(&self, path: &str) -> Result<(), String> {
+        Err(format!("{path}: no file system while fuzzing"))
+    }
+
+    fn stat(&self, path: &str) -> Result<FileStat, String> {
+        Err(format!("{path}: no file system while fuzzing"))
+    }
+
+    fn canonicalize(&self, path: &str) -> Result<String, String> {
+        Err(format!("{path}: no file system while fuzzing"))
+    }
+
+    fn read_to_string(&self, path: &str) -> Result<String, String> {
+        Err(format!("{path}: no file system while fuzzing"))
+    }
+}
+
+/// Keeps runs independent of the environment the fuzzer was started in.
+struct NoEnvVars;
+
+impl EnvVars for NoEnvVars {
+    fn var(&self, _key: &str) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,78 @@
+//! Inputs that once crashed a harness, replayed through all three of them.
+
+use proptest::prelude::*;
+use proptest::test_runner::Config as ProptestConfig;
+
+const CRASHES: &[&str] = &[
+    // Contract annotations that never reached a function looped forever.
+    "@requires(n)\n@ensures(r",
+    "@requires(n)\n@",
+    // Builtins read unrelated memory for arguments that are not objects.
+    "println(recv_any(1.5))\n",
+    "println(parallel_map(-1, 0))\n",
+    "use \"std/csv\" as csv\nprintln(csv.write([1, 2]))\n",
+    // Capacities and repeat counts overflowed or aborted the allocator.
+    "println(builder_new_cap(1.0e300))\n",
+    "println([1, 2, 3].repeat(1.0e300))\n",
+    // Folding int literals overflowed at compile time.
+    "println(100000000000000 * 100000000000000)\n",
+    "println(9223372036854775807 + 1)\n",
+    "println(-9223372036854775807 - 10)\n",
+];
+
+fn run_all(input: &[u8]) {
+    xu_fuzz::lex(input);
+    xu_fuzz::parse(input);
+    xu_fuzz::exec(input);
+}
+
+#[test]
+fn recorded_crashes_no_longer_crash() {
+    for input in CRASHES {
+        run_all(input.as_bytes());
+    }
+}
+
+#[test]
+fn deep_nesting_is_rejected_before_it_exhausts_the_stack() {
+    let n = 20_000;
+    let inputs = [
+        format!("let a = {}1{}", "(".repeat(n), ")".repeat(n)),
+        format!("let a = {}", "[".repeat(n)),
+        format!("let a = {}1", "- ".repeat(n)),
+        format!("let a = 1{}", " + 1".repeat(n)),
+        format!("let a = b{}", ".c()".repeat(n)),
+        format!("let a = {}1", "|| ".repeat(n)),
+        format!("let a: {}int{} = []", "[".repeat(n), "]".repeat(n)),
+        format!("func f() {{ {}", "if true { ".repeat(n)),
+        format!("match 1 {{ {}1{} {{ }} }}", "(".repeat(n), ")".repeat(n)),
+    ];
+    for input in &inputs {
+        run_all(input.as_bytes());
+    }
+}
+
+#[test]
+fn runaway_programs_are_stopped() {
+    run_all(b"while true { }\n");
+    run_all(b"func f(n: int) -> int { return f(n + 1) }\nf(0)\n");
+    run_all(b"while true { println(\"spam\") }\n");
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig { cases: 64, .. ProptestConfig::default() })]
+
+    #[test]
+    fn arbitrary_bytes_do_not_panic(data in proptest::collection::vec(any::<u8>(), 0..256)) {
+        run_all(&data);
+    }
+
+    #[test]
+    fn token_soup_does_not_panic(parts in proptest::collection::vec(prop::sample::select(&[
+        "func f(", ")", "(", "{", "}", "[", "]", "\"{", "}\"", "let x = ", "x", "1", "+", ".", "..",
+        "|x| ", "match ", "if ", "else ", "for i in ", "while ", " has ", "@requires(", "\n", ":", ",",
+        "return ", "-> int ", "bench \"b\": ", "Option#some(", "?", "=", "*", "-", "9223372036854775807",
+    ][..]), 0..48)) {
+        run_all(parts.concat().as_bytes());
+    }
+}
@@ -1,3 +1,13 @@
+//! Syntax tree of a module.
+//!
+//! Definitions (functions, function literals, structs and enums) sit behind
+//! an `Rc`: the compiled code, the types the runtime registers and every
+//! closure created from a definition share its node instead of copying the
+//! body. The analyzer annotates the tree before anything shares it, so its
+//! `Rc::make_mut` finds each node unique and never copies.
+
+use std::rc::Rc;
+
 use xu_syntax::Span;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -7,9 +17,9 @@ pub struct Module {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Stmt {
-    StructDef(Box<StructDef>),
-    EnumDef(Box<EnumDef>),
-    FuncDef(Box<FuncDef>),
+    StructDef(Rc<StructDef>),
+    EnumDef(Rc<EnumDef>),
+    FuncDef(Rc<FuncDef>),
     DoesBlock(Box<DoesBlock>),
     Use(Box<UseStmt>),
     If(Box<IfStmt>),
@@ -37,7 +47,7 @@ pub struct StructDef {
     pub name: String,
     pub fields: Box<[StructField]>,
     pub static_fields: Box<[StaticField]>,
-    pub methods: Box<[FuncDef]>,
+    pub methods: Box<[Rc<FuncDef>]>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -68,13 +78,15 @@ pub struct FuncDef {
     pub params: Box<[Param]>,
     pub return_ty: Option<TypeRef>,
     pub body: Box<[Stmt]>,
+    /// Text of the `//` comment lines directly above the definition.
+    pub doc: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct DoesBlock {
     pub vis: Visibility,
     pub target: String,
-    pub funcs: Box<[FuncDef]>,
+    pub funcs: Box<[Rc<FuncDef>]>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -153,6 +165,9 @@ pub struct AssignStmt {
 pub enum DeclKind {
     Let,
     Var,
+    /// `const NAME = expr`: a module-level binding whose value the compiler
+    /// evaluates, so uses of it load the value directly.
+    Const,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -162,6 +177,34 @@ pub enum AssignOp {
     Sub,
     Mul,
     Div,
+    Mod,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+}
+
+impl AssignOp {
+    /// The binary operator a compound assignment applies (`x op= y` is
+    /// `x = x op y`); `None` for plain `=`.
+    pub fn binary_op(self) -> Option<BinaryOp> {
+        match self {
+            AssignOp::Set => None,
+            AssignOp::Add => Some(BinaryOp::Add),
+            AssignOp::Sub => Some(BinaryOp::Sub),
+            AssignOp::Mul => Some(BinaryOp::Mul),
+            AssignOp::Div => Some(BinaryOp::Div),
+            AssignOp::Mod => Some(BinaryOp::Mod),
+            AssignOp::Pow => Some(BinaryOp::Pow),
+            AssignOp::BitAnd => Some(BinaryOp::BitAnd),
+            AssignOp::BitOr => Some(BinaryOp::BitOr),
+            AssignOp::BitXor => Some(BinaryOp::BitXor),
+            AssignOp::Shl => Some(BinaryOp::Shl),
+            AssignOp::Shr => Some(BinaryOp::Shr),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -177,7 +220,7 @@ pub enum Expr {
     Range(Box<RangeExpr>),
     IfExpr(Box<IfExpr>),
     Match(Box<MatchExpr>),
-    FuncLit(Box<FuncDef>),
+    FuncLit(Rc<FuncDef>),
     Dict(Box<[(String, Expr)]>),
     StructInit(Box<StructInitExpr>),
     EnumCtor {
@@ -199,7 +242,20 @@ pub enum Expr {
         left: Box<Expr>,
         right: Box<Expr>,
     },
+    /// Two or more chained ordering comparisons, such as `0 <= x < 10`.
+    Compare(Box<CompareExpr>),
     Group(Box<Expr>),
+    /// `expr?`: the payload of `Result#ok` / `Option#some`; an error or
+    /// none is returned from the enclosing function as it is.
+    Try(Box<Expr>),
+    /// A postfix chain containing `?.` or `?[`, such as `user?.address.city`.
+    /// Evaluates to `Option#none` as soon as one of its [`Expr::OptLink`]s
+    /// meets none.
+    OptChain(Box<Expr>),
+    /// The receiver of a `?.` / `?[` link in an [`Expr::OptChain`]: the
+    /// payload of `Option#some`, any other value as it is, and for
+    /// `Option#none` the end of the whole chain.
+    OptLink(Box<Expr>),
     Error(Span),
 }
 
@@ -217,6 +273,15 @@ pub struct MatchExpr {
     pub else_expr: Option<Box<Expr>>,
 }
 
+/// `a < b <= c` holds when each link (`a < b`, `b <= c`) does. Operands are
+/// evaluated at most once, left to right, stopping at the first link that
+/// does not hold.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompareExpr {
+    pub first: Box<Expr>,
+    pub links: Box<[(BinaryOp, Expr)]>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct RangeExpr {
     pub start: Box<Expr>,
@@ -255,6 +320,10 @@ pub struct IndexExpr {
 pub struct CallExpr {
     pub callee: Box<Expr>,
     pub args: Box<[Expr]>,
+    /// Labels of the last `labels.len()` arguments, as in
+    /// `inspect(v, max_items: 3)`. The driver resolves them against the
+    /// parameters the callee declares before compiling, leaving this empty.
+    pub labels: Box<[(String, Span)]>,
 }
 
 /// Receiver type hint for method calls, used by the compiler to generate
@@ -272,6 +341,8 @@ pub struct MethodCallExpr {
     pub receiver: Box<Expr>,
     pub method: String,
     pub args: Box<[Expr]>,
+    /// Labels of the trailing arguments, as for [`CallExpr::labels`].
+    pub labels: Box<[(String, Span)]>,
     pub ic_slot: std::cell::Cell<Option<usize>>,
     pub receiver_ty: std::cell::Cell<Option<ReceiverType>>,
 }
@@ -280,6 +351,8 @@ pub struct MethodCallExpr {
 pub enum UnaryOp {
     Neg,
     Not,
+    /// `~x`: bitwise complement of an int.
+    BitNot,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -289,14 +362,36 @@ pub enum BinaryOp {
     Mul,
     Div,
     Mod,
+    /// `a ** b`: int when both are ints and `b >= 0`, float otherwise.
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    /// `a << b`; `b` must be from 0 to 63.
+    Shl,
+    /// `a >> b`: arithmetic (sign-preserving) shift.
+    Shr,
     Gt,
     Lt,
     Ge,
     Le,
+    /// `a in b`: whether the range, list, tuple, dict (by key) or string (as
+    /// a substring) `b` contains `a`.
+    In,
     Eq,
     Ne,
     And,
     Or,
+    /// `a ?? b`: the payload of `a` when it is `Option#some`, `b` when it is
+    /// `Option#none` (evaluated only then), and any other `a` as it is.
+    Coalesce,
+}
+
+impl BinaryOp {
+    /// `>`, `<`, `>=` and `<=`, the operators that chain.
+    pub fn is_ordering(self) -> bool {
+        matches!(self, BinaryOp::Gt | BinaryOp::Lt | BinaryOp::Ge | BinaryOp::Le)
+    }
 }
 
 impl Expr {
@@ -0,0 +1,178 @@
+//! Bundle sections: compiled modules appended to a copy of the `xu` binary.
+//!
+//! A bundled binary is the runtime executable followed by a section and a
+//! fixed-size trailer:
+//!
+//! ```text
+//! <runtime binary> <section> <section_len: u64 LE> <checksum: u64 LE> <TRAILER_MAGIC>
+//! ```
+//!
+//! The section is an [image](crate::image): [`SECTION_MAGIC`], the image
+//! version, the language version that built it, the entry module key, the
+//! module keys, then one [`Executable`] per key. The module list comes before
+//! the executables so it can be reported even when the IR encoding belongs to
+//! a different build. The checksum is [`stable_hash64_bytes`] over the section.
+
+use crate::Executable;
+use crate::image::{IMAGE_VERSION, ImageReader, ImageWriter};
+use crate::stable_hash64_bytes;
+
+pub const SECTION_MAGIC: &[u8; 8] = b"XUBUNDLE";
+pub const TRAILER_MAGIC: &[u8; 8] = b"XUBTRL01";
+/// `section_len`, `checksum` and [`TRAILER_MAGIC`].
+pub const TRAILER_LEN: usize = 24;
+
+/// The program stored in a bundle.
+#[derive(Clone, Debug)]
+pub struct Bundle {
+    /// Version of `xu` that compiled the modules.
+    pub lang_version: String,
+    /// Key of the module run on startup.
+    pub entry: String,
+    /// Module keys with their compiled code, entry included.
+    pub modules: Vec<(String, Executable)>,
+}
+
+/// What [`verify`] found in a bundled binary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BundleInfo {
+    /// Size of the runtime binary in front of the section.
+    pub runtime_len: usize,
+    pub section_len: usize,
+    pub checksum: u64,
+    pub image_version: u32,
+    pub lang_version: String,
+    pub entry: String,
+    pub modules: Vec<String>,
+    /// Whether the executables were decoded. Sections from a build with a
+    /// different [`IMAGE_VERSION`] pass the checksum but are not decoded.
+    pub decoded: bool,
+}
+
+/// Encodes `bundle` as a section plus trailer, ready to append to a binary.
+pub fn encode(bundle: &Bundle) -> Vec<u8> {
+    let mut w = ImageWriter::new();
+    w.bytes(SECTION_MAGIC);
+    w.put(&IMAGE_VERSION);
+    w.put(&bundle.lang_version);
+    w.put(&bundle.entry);
+    let keys: Vec<String> = bundle.modules.iter().map(|(k, _)| k.clone()).collect();
+    w.put(&keys);
+    for (_, executable) in &bundle.modules {
+        w.put(executable);
+    }
+    let mut out = w.finish();
+    let checksum = stable_hash64_bytes(&out);
+    let section_len = out.len() as u64;
+    out.extend_from_slice(&section_len.to_le_bytes());
+    out.extend_from_slice(&checksum.to_le_bytes());
+    out.extend_from_slice(TRAILER_MAGIC);
+    out
+}
+
+/// Whether `bytes` ends with a bundle trailer.
+pub fn has_bundle(bytes: &[u8]) -> bool {
+    bytes.len() >= TRAILER_LEN && bytes.ends_with(TRAILER_MAGIC)
+}
+
+/// Locates the section in a bundled binary and checks its checksum. Returns
+/// the runtime length, the section and the checksum.
+fn locate(bytes: &[u8]) -> Result<(usize, &[u8], u64), String> {
+    if !has_bundle(bytes) {
+        return Err("No bundle section found".into());
+    }
+    let trailer = &bytes[bytes.len() - TRAILER_LEN..];
+    let section_len = u64::from_le_bytes(trailer[..8].try_into().unwrap());
+    let checksum = u64::from_le_bytes(trailer[8..16].try_into().unwrap());
+    let body_len = bytes.len() - TRAILER_LEN;
+    let Some(start) = usize::try_from(section_len)
+        .ok()
+        .and_then(|n| body_len.checked_sub(n))
+    else {
+        return Err(format!(
+            "Bundle section length {section_len} exceeds the file size"
+        ));
+    };
+    let section = &bytes[start..body_len];
+    let actual = stable_hash64_bytes(section);
+    if actual != checksum {
+        return Err(format!(
+            "Bundle checksum mismatch: recorded {checksum:016x}, computed {actual:016x}"
+        ));
+    }
+    Ok((start, section, checksum))
+}
+
+struct Header {
+    image_version: u32,
+    lang_version: String,
+    entry: String,
+    keys: Vec<String>,
+}
+
+/// Reads the section header, leaving the reader at the first executable.
+fn read_header(section: &[u8]) -> Result<(ImageReader<'_>, Header), String> {
+    let mut r = ImageReader::new(section);
+    if r.bytes(SECTION_MAGIC.len()).ok() != Some(&SECTION_MAGIC[..]) {
+        return Err("Not a bundle section".into());
+    }
+    let header = Header {
+        image_version: r.get()?,
+        lang_version: r.get()?,
+        entry: r.get()?,
+        keys: r.get()?,
+    };
+    Ok((r, header))
+}
+
+fn read_modules(r: &mut ImageReader<'_>, keys: Vec<String>) -> Result<Vec<(String, Executable)>, String> {
+    let mut modules = Vec::with_capacity(keys.len());
+    for key in keys {
+        let executable: Executable = r.get()?;
+        modules.push((key, executable));
+    }
+    if !r.is_empty() {
+        return Err("Trailing data in bundle section".into());
+    }
+    Ok(modules)
+}
+
+/// Checks the integrity of a bundled binary and reports what it contains.
+pub fn verify(bytes: &[u8]) -> Result<BundleInfo, String> {
+    let (runtime_len, section, checksum) = locate(bytes)?;
+    let (mut r, h) = read_header(section)?;
+    if !h.keys.contains(&h.entry) {
+        return Err(format!("Bundle entry {} is not among its modules", h.entry));
+    }
+    let decoded = h.image_version == IMAGE_VERSION;
+    if decoded {
+        read_modules(&mut r, h.keys.clone())?;
+    }
+    Ok(BundleInfo {
+        runtime_len,
+        section_len: section.len(),
+        checksum,
+        image_version: h.image_version,
+        lang_version: h.lang_version,
+        entry: h.entry,
+        modules: h.keys,
+        decoded,
+    })
+}
+
+/// Decodes the program of a bundled binary built by this version.
+pub fn decode(bytes: &[u8]) -> Result<Bundle, String> {
+    let (_, section, _) = locate(bytes)?;
+    let (mut r, h) = read_header(section)?;
+    if h.image_version != IMAGE_VERSION {
+        return Err(format!(
+            "Bundle was built by xu {} (image v{}); rebuild it with this version",
+            h.lang_version, h.image_version
+        ));
+    }
+    Ok(Bundle {
+        lang_version: h.lang_version,
+        entry: h.entry,
+        modules: read_modules(&mut r, h.keys)?,
+    })
+}
@@ -2,13 +2,18 @@
 
 use std::rc::Rc;
 
-use crate::{AssignOp, EnumDef, FuncDef, Pattern, StructDef};
+use xu_syntax::Span;
+
+use crate::{AssignOp, BinaryOp, EnumDef, FuncDef, Pattern, StructDef};
 
 #[derive(Clone, Debug)]
 pub struct BytecodeFunction {
-    pub def: FuncDef,
+    pub def: Rc<FuncDef>,
     pub bytecode: Rc<Bytecode>,
     pub locals_count: usize,
+    /// Whether the body makes closures of its own, which then need the
+    /// call's bindings in an environment frame.
+    pub needs_env_frame: bool,
 }
 
 impl PartialEq for BytecodeFunction {
@@ -24,25 +29,109 @@ pub enum Constant {
     Str(String),
     Int(i64),
     Float(f64),
-    Struct(StructDef),
-    Enum(EnumDef),
+    Struct(Rc<StructDef>),
+    Enum(Rc<EnumDef>),
     Func(BytecodeFunction),
     Names(Vec<String>),
     Pattern(Pattern),
+    Switch(SwitchTable),
+}
+
+/// Jump table of a `match` whose arms are all int literals or all string
+/// literals, built so that dispatch does not test the arms one by one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwitchTable {
+    pub cases: SwitchCases,
+    /// Target when no case matches: the wildcard arm, the else branch or
+    /// the end of the match.
+    pub default: u32,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum SwitchCases {
+    /// Ints close together: the target of `v` is `targets[v - base]`, with
+    /// `default` in the gaps.
+    Dense { base: i64, targets: Box<[u32]> },
+    /// Sparse ints, sorted for binary search.
+    Ints(Box<[(i64, u32)]>),
+    /// Strings, sorted for binary search.
+    Strs(Box<[(String, u32)]>),
+}
+
+impl SwitchTable {
+    /// Builds the table for int cases, given first arm first; a repeated
+    /// value keeps the target of its first arm.
+    pub fn ints(cases: &[(i64, u32)], default: u32) -> Self {
+        let mut sorted = cases.to_vec();
+        sorted.sort_by_key(|&(v, _)| v);
+        sorted.dedup_by_key(|&mut (v, _)| v);
+        let (lo, hi) = match (sorted.first(), sorted.last()) {
+            (Some(&(lo, _)), Some(&(hi, _))) => (lo, hi),
+            _ => return SwitchTable { cases: SwitchCases::Ints(Box::new([])), default },
+        };
+        // 跨度不超过分支数的两倍时用直接索引
+        let span = hi.abs_diff(lo);
+        if span < 2 * sorted.len() as u64 {
+            let mut targets = vec![default; span as usize + 1];
+            for &(v, to) in &sorted {
+                targets[v.abs_diff(lo) as usize] = to;
+            }
+            return SwitchTable { cases: SwitchCases::Dense { base: lo, targets: targets.into() }, default };
+        }
+        SwitchTable { cases: SwitchCases::Ints(sorted.into()), default }
+    }
+
+    /// Builds the table for string cases, given first arm first.
+    pub fn strs(cases: &[(String, u32)], default: u32) -> Self {
+        let mut sorted = cases.to_vec();
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+        sorted.dedup_by(|a, b| a.0 == b.0);
+        SwitchTable { cases: SwitchCases::Strs(sorted.into()), default }
+    }
+
+    /// Target for an int subject.
+    pub fn int_target(&self, v: i64) -> u32 {
+        match &self.cases {
+            SwitchCases::Dense { base, targets } => match v.checked_sub(*base) {
+                Some(i) if i >= 0 && (i as u64) < targets.len() as u64 => targets[i as usize],
+                _ => self.default,
+            },
+            SwitchCases::Ints(cases) => match cases.binary_search_by_key(&v, |&(c, _)| c) {
+                Ok(i) => cases[i].1,
+                Err(_) => self.default,
+            },
+            SwitchCases::Strs(_) => self.default,
+        }
+    }
+
+    /// Target for a string subject.
+    pub fn str_target(&self, s: &str) -> u32 {
+        match &self.cases {
+            SwitchCases::Strs(cases) => match cases.binary_search_by(|(c, _)| c.as_str().cmp(s)) {
+                Ok(i) => cases[i].1,
+                Err(_) => self.default,
+            },
+            _ => self.default,
+        }
+    }
+}
+
+/// One VM instruction. Indices, counts and jump targets are `u32` so an op
+/// fits in 24 bytes.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Op {
     ConstInt(i64),
     ConstFloat(f64),
     ConstBool(bool),
     ConstNull,
+    /// The empty string, without a constant pool entry.
+    ConstEmptyStr,
     Const(u32), // Index into constant pool
     Pop,
     Dup,        // Duplicate top of stack
     Add,
     AddAssignName(u32), // Index into constant pool (String)
-    AddAssignLocal(usize),
+    AddAssignLocal(u32),
     Sub,
     Mul,
     Div,
@@ -57,13 +146,13 @@ pub enum Op {
     Ge,
     Le,
     Not,
-    Jump(usize),
-    JumpIfFalse(usize),
-    JumpIfTrue(usize),
+    Jump(u32),
+    JumpIfFalse(u32),
+    JumpIfTrue(u32),
     LoadName(u32), // Index into constant pool (String)
-    LoadLocal(usize),
+    LoadLocal(u32),
     StoreName(u32), // Index into constant pool (String)
-    StoreLocal(usize),
+    StoreLocal(u32),
     Use(u32, u32), // (path_str_idx, alias_str_idx)
     AssertType(u32),            // Index into constant pool (String)
     DefineStruct(u32),          // Index into constant pool (StructDef)
@@ -71,38 +160,48 @@ pub enum Op {
     StructInit(u32, u32),       // Index to String, Index to Names (Vec<String>)
     StructInitSpread(u32, u32), // Index to String (type), Index to Names (explicit fields) - stack: [spread_src, field_values...]
     EnumCtor(u32, u32),         // Index to String (Enum name), Index to String (Ctor name)
-    EnumCtorN(u32, u32, usize), // Index to String (Enum name), Index to String (Ctor name), args_count
+    EnumCtorN(u32, u32, u32), // Index to String (Enum name), Index to String (Ctor name), args_count
     MakeFunction(u32),          // Index into constant pool (BytecodeFunction)
-    Call(usize),
-    CallMethod(u32, u64, usize, Option<usize>), // Index to String (Method name), hash, args_count, slot
+    Call(u32),
+    CallMethod(u32, u64, u32, Option<u32>), // Index to String (Method name), hash, args_count, slot
     /// Try static method first, fall back to instance method
-    /// (type_name_idx, method_name_idx, method_hash, args_count, slot)
-    CallStaticOrMethod(u32, u32, u64, usize, Option<usize>),
-    IncLocal(usize),
+    /// (type_name_idx, method_name_idx, args_count, slot)
+    CallStaticOrMethod(u32, u32, u32, Option<u32>),
+    IncLocal(u32),
     MakeRange(bool),
-    GetMember(u32, Option<usize>), // Index to String (Member name), slot
-    GetIndex(Option<usize>),
+    GetMember(u32, Option<u32>), // Index to String (Member name), slot
+    GetIndex(Option<u32>),
     AssignMember(u32, AssignOp), // Index to String (Member name), op
+    /// Struct field read at the offset the compiler resolved from the static
+    /// type: (member name, offset, slot). Instances whose layout has another
+    /// field there take the `GetMember` path.
+    GetMemberAt(u32, u32, Option<u32>),
+    /// Struct field assignment at a compiler-resolved offset: (member name,
+    /// offset, op), with the same fallback as `GetMemberAt`.
+    AssignMemberAt(u32, u32, AssignOp),
     AssignIndex(AssignOp),
-    Break(usize),
-    Continue(usize),
+    Break(u32),
+    Continue(u32),
     Return,
     RunPending,
     // Builder specialized ops
-    BuilderNewCap(usize),
+    BuilderNewCap(u32),
     BuilderAppend,
     BuilderFinalize,
-    ForEachInit(u32, Option<usize>, usize), // Index to String, slot, local_idx
-    ForEachNext(u32, Option<usize>, usize, usize), // Index to String, slot, local_idx, jump_addr
+    ForEachInit(u32, Option<u32>, u32), // Index to String (var name), local_idx, end
+    ForEachNext(u32, Option<u32>, u32, u32), // Index to String (var name), local_idx, loop_start, end
     IterPop,
     EnvPush,
     EnvPop,
-    ListNew(usize),
-    TupleNew(usize),
-    DictNew(usize),
+    ListNew(u32),
+    TupleNew(u32),
+    DictNew(u32),
     DictInsert,
     DictMerge,
-    ListAppend(usize),
+    ListAppend(u32),
+    /// Pre-sizes the list a loop appends to. Stack: [iterable, list] -> [];
+    /// does nothing when either value is of another type.
+    ListReserveFor,
     Print,
     Halt,
     // Match pattern ops
@@ -116,10 +215,182 @@ pub enum Op {
     SetStaticField(u32, u32),
     /// Initialize static field: (type_name_idx, field_name_idx)
     InitStaticField(u32, u32),
+    /// `expr?`. Stack: [v] -> [payload] for `Result#ok` / `Option#some`;
+    /// returns `v` from the function for `Result#err` / `Option#none`.
+    Try,
+    /// `?.` / `?[` link. Stack: [v] -> [payload] for `Option#some`, [v] for
+    /// other values; for `Option#none` -> [none] and jumps to the end of the
+    /// chain.
+    OptLink(u32),
+    /// `??`. Stack: [v] -> [payload] for `Option#some`, [v] for other values,
+    /// jumping past the right operand; for `Option#none` -> [] and falls
+    /// through to it.
+    Coalesce(u32),
+    /// `**`, `&`, `|`, `^`, `<<`, `>>`. Stack: [a, b] -> [a op b].
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    /// `~`. Stack: [a] -> [!a].
+    BitNot,
+    /// A non-final link of a chained comparison such as `a < b <= c`, with
+    /// the link's ordering operator. Stack: [a, b] -> [b] when `a op b`
+    /// holds; otherwise [false] and jumps to the end of the chain.
+    CmpLink(BinaryOp, u32),
+    /// `in`. Stack: [x, coll] -> [x in coll].
+    In,
+    /// `in` against a range literal, without building the range; the flag
+    /// is `..=`. Stack: [x, start, end] -> [x in start..end].
+    InRange(bool),
+    /// `match` over int or string literals: jumps to the arm the
+    /// `Constant::Switch` table at the index gives for the value on top of
+    /// the stack, which stays there. Stack: [v] -> [v].
+    Switch(u32),
+}
+
+const _: () = assert!(std::mem::size_of::<Op>() <= 24);
+
+impl Op {
+    /// Number of distinct opcodes.
+    pub const COUNT: usize = 94;
+
+    /// Dense opcode number in `0..Op::COUNT`, in declaration order. Used by
+    /// table-driven dispatchers.
+    #[inline(always)]
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Op::ConstInt(_) => 0,
+            Op::ConstFloat(_) => 1,
+            Op::ConstBool(_) => 2,
+            Op::ConstNull => 3,
+            Op::ConstEmptyStr => 4,
+            Op::Const(_) => 5,
+            Op::Pop => 6,
+            Op::Dup => 7,
+            Op::Add => 8,
+            Op::AddAssignName(_) => 9,
+            Op::AddAssignLocal(_) => 10,
+            Op::Sub => 11,
+            Op::Mul => 12,
+            Op::Div => 13,
+            Op::Mod => 14,
+            Op::StrAppend => 15,
+            Op::Eq => 16,
+            Op::Ne => 17,
+            Op::And => 18,
+            Op::Or => 19,
+            Op::Gt => 20,
+            Op::Lt => 21,
+            Op::Ge => 22,
+            Op::Le => 23,
+            Op::Not => 24,
+            Op::Jump(_) => 25,
+            Op::JumpIfFalse(_) => 26,
+            Op::JumpIfTrue(_) => 27,
+            Op::LoadName(_) => 28,
+            Op::LoadLocal(_) => 29,
+            Op::StoreName(_) => 30,
+            Op::StoreLocal(_) => 31,
+            Op::Use(_, _) => 32,
+            Op::AssertType(_) => 33,
+            Op::DefineStruct(_) => 34,
+            Op::DefineEnum(_) => 35,
+            Op::StructInit(_, _) => 36,
+            Op::StructInitSpread(_, _) => 37,
+            Op::EnumCtor(_, _) => 38,
+            Op::EnumCtorN(_, _, _) => 39,
+            Op::MakeFunction(_) => 40,
+            Op::Call(_) => 41,
+            Op::CallMethod(_, _, _, _) => 42,
+            Op::CallStaticOrMethod(_, _, _, _) => 43,
+            Op::IncLocal(_) => 44,
+            Op::MakeRange(_) => 45,
+            Op::GetMember(_, _) => 46,
+            Op::GetIndex(_) => 47,
+            Op::AssignMember(_, _) => 48,
+            Op::GetMemberAt(_, _, _) => 49,
+            Op::AssignMemberAt(_, _, _) => 50,
+            Op::AssignIndex(_) => 51,
+            Op::Break(_) => 52,
+            Op::Continue(_) => 53,
+            Op::Return => 54,
+            Op::RunPending => 55,
+            Op::BuilderNewCap(_) => 56,
+            Op::BuilderAppend => 57,
+            Op::BuilderFinalize => 58,
+            Op::ForEachInit(_, _, _) => 59,
+            Op::ForEachNext(_, _, _, _) => 60,
+            Op::IterPop => 61,
+            Op::EnvPush => 62,
+            Op::EnvPop => 63,
+            Op::ListNew(_) => 64,
+            Op::TupleNew(_) => 65,
+            Op::DictNew(_) => 66,
+            Op::DictInsert => 67,
+            Op::DictMerge => 68,
+            Op::ListAppend(_) => 69,
+            Op::ListReserveFor => 70,
+            Op::Print => 71,
+            Op::Halt => 72,
+            Op::MatchPattern(_) => 73,
+            Op::MatchBindings(_) => 74,
+            Op::LocalsPush => 75,
+            Op::LocalsPop => 76,
+            Op::GetStaticField(_, _) => 77,
+            Op::SetStaticField(_, _) => 78,
+            Op::InitStaticField(_, _) => 79,
+            Op::Try => 80,
+            Op::OptLink(_) => 81,
+            Op::Coalesce(_) => 82,
+            Op::Pow => 83,
+            Op::BitAnd => 84,
+            Op::BitOr => 85,
+            Op::BitXor => 86,
+            Op::Shl => 87,
+            Op::Shr => 88,
+            Op::BitNot => 89,
+            Op::CmpLink(_, _) => 90,
+            Op::In => 91,
+            Op::InRange(_) => 92,
+            Op::Switch(_) => 93,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Bytecode {
     pub ops: Vec<Op>,
     pub constants: Vec<Constant>,
+    /// Where in the source each op came from.
+    pub spans: SourceMap,
+}
+
+/// Maps op indices back to source spans. Each entry gives the span of the
+/// statement that the ops from its index up to the next entry were compiled
+/// from; ops before the first entry have no position.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceMap {
+    /// The source file name, as given to the frontend.
+    pub file: String,
+    /// `(first op index, span)`, in increasing op order.
+    pub entries: Vec<(u32, Span)>,
+}
+
+impl SourceMap {
+    /// Attributes the ops from `op` onward to `span`.
+    pub fn mark(&mut self, op: u32, span: Span) {
+        match self.entries.last_mut() {
+            Some((_, last)) if *last == span => {}
+            Some((at, last)) if *at == op => *last = span,
+            _ => self.entries.push((op, span)),
+        }
+    }
+
+    /// The span op `op` was compiled from.
+    pub fn span_at(&self, op: usize) -> Option<Span> {
+        let i = self.entries.partition_point(|&(at, _)| at as usize <= op);
+        i.checked_sub(1).map(|i| self.entries[i].1)
+    }
 }
@@ -1,8 +1,13 @@
 pub fn stable_hash64(s: &str) -> u64 {
+    stable_hash64_bytes(s.as_bytes())
+}
+
+/// FNV-1a over raw bytes; [`stable_hash64`] is this applied to UTF-8 text.
+pub fn stable_hash64_bytes(bytes: &[u8]) -> u64 {
     const FNV_OFFSET: u64 = 14695981039346656037;
     const FNV_PRIME: u64 = 1099511628211;
     let mut h = FNV_OFFSET;
-    for &b in s.as_bytes() {
+    for &b in bytes {
         h ^= b as u64;
         h = h.wrapping_mul(FNV_PRIME);
     }
@@ -0,0 +1,1032 @@
+//! Compact binary encoding of the IR, used for startup snapshots.
+//!
+//! The format is private to a given build: readers reject images whose
+//! [`IMAGE_VERSION`] differs instead of attempting to migrate them.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use xu_syntax::Span;
+
+use crate::*;
+
+/// Bumped whenever the encoding of any IR type changes.
+pub const IMAGE_VERSION: u32 = 15;
+
+#[derive(Default)]
+pub struct ImageWriter {
+    buf: Vec<u8>,
+}
+
+impl ImageWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn put<T: ImageCodec>(&mut self, v: &T) {
+        v.encode(self);
+    }
+
+    pub fn bytes(&mut self, b: &[u8]) {
+        self.buf.extend_from_slice(b);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    fn uvar(&mut self, mut v: u64) {
+        loop {
+            let b = (v & 0x7f) as u8;
+            v >>= 7;
+            if v == 0 {
+                self.buf.push(b);
+                return;
+            }
+            self.buf.push(b | 0x80);
+        }
+    }
+}
+
+pub struct ImageReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ImageReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub fn get<T: ImageCodec>(&mut self) -> Result<T, String> {
+        T::decode(self)
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(n).filter(|&e| e <= self.data.len());
+        let Some(end) = end else {
+            return Err("Truncated image".into());
+        };
+        let out = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn uvar(&mut self) -> Result<u64, String> {
+        let mut out = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.u8()?;
+            if shift >= 64 {
+                return Err("Malformed image integer".into());
+            }
+            out |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(out);
+            }
+            shift += 7;
+        }
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        let n = self.uvar()? as usize;
+        // Every element takes at least one byte, which bounds allocations on
+        // corrupt input.
+        if n > self.data.len() - self.pos {
+            return Err("Truncated image".into());
+        }
+        Ok(n)
+    }
+}
+
+fn bad_tag(ty: &str, tag: u8) -> String {
+    format!("Malformed image: invalid {ty} tag {tag}")
+}
+
+/// Types that can be written to and read back from an image.
+pub trait ImageCodec: Sized {
+    fn encode(&self, w: &mut ImageWriter);
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String>;
+}
+
+impl ImageCodec for bool {
+    fn encode(&self, w: &mut ImageWriter) {
+        w.u8(*self as u8);
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        match r.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            t => Err(bad_tag("bool", t)),
+        }
+    }
+}
+
+impl ImageCodec for u32 {
+    fn encode(&self, w: &mut ImageWriter) {
+        w.uvar(u64::from(*self));
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        u32::try_from(r.uvar()?).map_err(|_| "Malformed image integer".to_string())
+    }
+}
+
+impl ImageCodec for u64 {
+    fn encode(&self, w: &mut ImageWriter) {
+        w.uvar(*self);
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        r.uvar()
+    }
+}
+
+impl ImageCodec for usize {
+    fn encode(&self, w: &mut ImageWriter) {
+        w.uvar(*self as u64);
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        usize::try_from(r.uvar()?).map_err(|_| "Malformed image integer".to_string())
+    }
+}
+
+impl ImageCodec for u128 {
+    fn encode(&self, w: &mut ImageWriter) {
+        w.bytes(&self.to_le_bytes());
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        let b = r.bytes(16)?;
+        Ok(u128::from_le_bytes(b.try_into().unwrap()))
+    }
+}
+
+impl ImageCodec for i64 {
+    fn encode(&self, w: &mut ImageWriter) {
+        // Zigzag so small negative numbers stay short.
+        w.uvar(((*self << 1) ^ (*self >> 63)) as u64);
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        let v = r.uvar()?;
+        Ok(((v >> 1) as i64) ^ -((v & 1) as i64))
+    }
+}
+
+impl ImageCodec for f64 {
+    fn encode(&self, w: &mut ImageWriter) {
+        w.bytes(&self.to_bits().to_le_bytes());
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        let b = r.bytes(8)?;
+        Ok(f64::from_bits(u64::from_le_bytes(b.try_into().unwrap())))
+    }
+}
+
+impl ImageCodec for String {
+    fn encode(&self, w: &mut ImageWriter) {
+        w.uvar(self.len() as u64);
+        w.bytes(self.as_bytes());
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        let n = r.len()?;
+        let b = r.bytes(n)?;
+        String::from_utf8(b.to_vec()).map_err(|_| "Malformed image string".to_string())
+    }
+}
+
+impl<T: ImageCodec> ImageCodec for Option<T> {
+    fn encode(&self, w: &mut ImageWriter) {
+        match self {
+            None => w.u8(0),
+            Some(v) => {
+                w.u8(1);
+                v.encode(w);
+            }
+        }
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        match r.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(r)?)),
+            t => Err(bad_tag("option", t)),
+        }
+    }
+}
+
+impl<T: ImageCodec> ImageCodec for Vec<T> {
+    fn encode(&self, w: &mut ImageWriter) {
+        w.uvar(self.len() as u64);
+        for v in self {
+            v.encode(w);
+        }
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        let n = r.len()?;
+        let mut out = Vec::with_capacity(n);
+        for _ in 0..n {
+            out.push(T::decode(r)?);
+        }
+        Ok(out)
+    }
+}
+
+impl<T: ImageCodec> ImageCodec for Box<[T]> {
+    fn encode(&self, w: &mut ImageWriter) {
+        w.uvar(self.len() as u64);
+        for v in self.iter() {
+            v.encode(w);
+        }
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(Vec::<T>::decode(r)?.into_boxed_slice())
+    }
+}
+
+impl<T: ImageCodec> ImageCodec for Box<T> {
+    fn encode(&self, w: &mut ImageWriter) {
+        (**self).encode(w);
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(Box::new(T::decode(r)?))
+    }
+}
+
+impl<T: ImageCodec> ImageCodec for Rc<T> {
+    fn encode(&self, w: &mut ImageWriter) {
+        (**self).encode(w);
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(Rc::new(T::decode(r)?))
+    }
+}
+
+impl<T: ImageCodec + Copy> ImageCodec for Cell<T> {
+    fn encode(&self, w: &mut ImageWriter) {
+        self.get().encode(w);
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(Cell::new(T::decode(r)?))
+    }
+}
+
+impl<A: ImageCodec, B: ImageCodec> ImageCodec for (A, B) {
+    fn encode(&self, w: &mut ImageWriter) {
+        self.0.encode(w);
+        self.1.encode(w);
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok((A::decode(r)?, B::decode(r)?))
+    }
+}
+
+impl ImageCodec for Span {
+    fn encode(&self, w: &mut ImageWriter) {
+        self.start.0.encode(w);
+        self.end.0.encode(w);
+    }
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(Span::new(r.get()?, r.get()?))
+    }
+}
+
+macro_rules! image_struct {
+    ($ty:ident { $($field:ident),* $(,)? }) => {
+        impl ImageCodec for $ty {
+            fn encode(&self, w: &mut ImageWriter) {
+                $(self.$field.encode(w);)*
+            }
+            fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+                Ok($ty { $($field: r.get()?,)* })
+            }
+        }
+    };
+}
+
+/// Field-less enums, encoded as their position in the list.
+macro_rules! image_unit_enum {
+    ($ty:ident { $($variant:ident),* $(,)? }) => {
+        impl ImageCodec for $ty {
+            fn encode(&self, w: &mut ImageWriter) {
+                const ORDER: &[$ty] = &[$($ty::$variant),*];
+                let tag = ORDER.iter().position(|v| v == self).unwrap();
+                w.u8(tag as u8);
+            }
+            fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+                const ORDER: &[$ty] = &[$($ty::$variant),*];
+                let tag = r.u8()?;
+                ORDER
+                    .get(tag as usize)
+                    .copied()
+                    .ok_or_else(|| bad_tag(stringify!($ty), tag))
+            }
+        }
+    };
+}
+
+image_unit_enum!(Visibility { Public, Inner });
+image_unit_enum!(DeclKind { Let, Var, Const });
+image_unit_enum!(AssignOp { Set, Add, Sub, Mul, Div, Mod, Pow, BitAnd, BitOr, BitXor, Shl, Shr });
+image_unit_enum!(ReceiverType { List, Dict, Struct, Other });
+image_unit_enum!(UnaryOp { Neg, Not, BitNot });
+image_unit_enum!(BinaryOp {
+    Add, Sub, Mul, Div, Mod, Pow, BitAnd, BitOr, BitXor, Shl, Shr, Gt, Lt, Ge, Le, In, Eq, Ne, And, Or, Coalesce
+});
+
+image_struct!(Module { stmts });
+image_struct!(StructDef { vis, name, fields, static_fields, methods });
+image_struct!(EnumDef { vis, name, variants });
+image_struct!(StructField { name, ty, default });
+image_struct!(StaticField { name, ty, default });
+image_struct!(FuncDef { vis, name, params, return_ty, body, doc });
+image_struct!(DoesBlock { vis, target, funcs });
+image_struct!(UseStmt { path, alias });
+image_struct!(Param { name, ty, default });
+image_struct!(TypeRef { name, params });
+image_struct!(IfStmt { branches, else_branch });
+image_struct!(WhileStmt { cond, body });
+image_struct!(ForEachStmt { iter, var, body });
+image_struct!(MatchStmt { expr, arms, else_branch });
+image_struct!(AssignStmt { vis, target, op, value, ty, slot, decl });
+image_struct!(IfExpr { cond, then_expr, else_expr });
+image_struct!(MatchExpr { expr, arms, else_expr });
+image_struct!(CompareExpr { first, links });
+image_struct!(RangeExpr { start, end, inclusive });
+image_struct!(StructInitExpr { module, ty, items });
+image_struct!(MemberExpr { object, field, ic_slot });
+image_struct!(IndexExpr { object, index, ic_slot });
+image_struct!(CallExpr { callee, args, labels });
+image_struct!(MethodCallExpr { receiver, method, args, labels, ic_slot, receiver_ty });
+image_struct!(BytecodeFunction { def, bytecode, locals_count, needs_env_frame });
+image_struct!(Bytecode { ops, constants, spans });
+image_struct!(SourceMap { file, entries });
+image_struct!(Program { module, bytecode });
+
+impl ImageCodec for Stmt {
+    fn encode(&self, w: &mut ImageWriter) {
+        match self {
+            Stmt::StructDef(v) => {
+                w.u8(0);
+                v.encode(w);
+            }
+            Stmt::EnumDef(v) => {
+                w.u8(1);
+                v.encode(w);
+            }
+            Stmt::FuncDef(v) => {
+                w.u8(2);
+                v.encode(w);
+            }
+            Stmt::DoesBlock(v) => {
+                w.u8(3);
+                v.encode(w);
+            }
+            Stmt::Use(v) => {
+                w.u8(4);
+                v.encode(w);
+            }
+            Stmt::If(v) => {
+                w.u8(5);
+                v.encode(w);
+            }
+            Stmt::While(v) => {
+                w.u8(6);
+                v.encode(w);
+            }
+            Stmt::ForEach(v) => {
+                w.u8(7);
+                v.encode(w);
+            }
+            Stmt::Match(v) => {
+                w.u8(8);
+                v.encode(w);
+            }
+            Stmt::Block(v) => {
+                w.u8(9);
+                v.encode(w);
+            }
+            Stmt::Return(v) => {
+                w.u8(10);
+                v.encode(w);
+            }
+            Stmt::Break => w.u8(11),
+            Stmt::Continue => w.u8(12),
+            Stmt::Assign(v) => {
+                w.u8(13);
+                v.encode(w);
+            }
+            Stmt::Expr(v) => {
+                w.u8(14);
+                v.encode(w);
+            }
+            Stmt::Error(v) => {
+                w.u8(15);
+                v.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(match r.u8()? {
+            0 => Stmt::StructDef(r.get()?),
+            1 => Stmt::EnumDef(r.get()?),
+            2 => Stmt::FuncDef(r.get()?),
+            3 => Stmt::DoesBlock(r.get()?),
+            4 => Stmt::Use(r.get()?),
+            5 => Stmt::If(r.get()?),
+            6 => Stmt::While(r.get()?),
+            7 => Stmt::ForEach(r.get()?),
+            8 => Stmt::Match(r.get()?),
+            9 => Stmt::Block(r.get()?),
+            10 => Stmt::Return(r.get()?),
+            11 => Stmt::Break,
+            12 => Stmt::Continue,
+            13 => Stmt::Assign(r.get()?),
+            14 => Stmt::Expr(r.get()?),
+            15 => Stmt::Error(r.get()?),
+            t => return Err(bad_tag("Stmt", t)),
+        })
+    }
+}
+
+impl ImageCodec for Pattern {
+    fn encode(&self, w: &mut ImageWriter) {
+        match self {
+            Pattern::Wildcard => w.u8(0),
+            Pattern::Bind(v) => {
+                w.u8(1);
+                v.encode(w);
+            }
+            Pattern::Tuple(v) => {
+                w.u8(2);
+                v.encode(w);
+            }
+            Pattern::Int(v) => {
+                w.u8(3);
+                v.encode(w);
+            }
+            Pattern::Float(v) => {
+                w.u8(4);
+                v.encode(w);
+            }
+            Pattern::Str(v) => {
+                w.u8(5);
+                v.encode(w);
+            }
+            Pattern::Bool(v) => {
+                w.u8(6);
+                v.encode(w);
+            }
+            Pattern::EnumVariant { ty, variant, args } => {
+                w.u8(7);
+                ty.encode(w);
+                variant.encode(w);
+                args.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(match r.u8()? {
+            0 => Pattern::Wildcard,
+            1 => Pattern::Bind(r.get()?),
+            2 => Pattern::Tuple(r.get()?),
+            3 => Pattern::Int(r.get()?),
+            4 => Pattern::Float(r.get()?),
+            5 => Pattern::Str(r.get()?),
+            6 => Pattern::Bool(r.get()?),
+            7 => Pattern::EnumVariant {
+                ty: r.get()?,
+                variant: r.get()?,
+                args: r.get()?,
+            },
+            t => return Err(bad_tag("Pattern", t)),
+        })
+    }
+}
+
+impl ImageCodec for StructInitItem {
+    fn encode(&self, w: &mut ImageWriter) {
+        match self {
+            StructInitItem::Spread(e) => {
+                w.u8(0);
+                e.encode(w);
+            }
+            StructInitItem::Field(name, e) => {
+                w.u8(1);
+                name.encode(w);
+                e.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(match r.u8()? {
+            0 => StructInitItem::Spread(r.get()?),
+            1 => StructInitItem::Field(r.get()?, r.get()?),
+            t => return Err(bad_tag("StructInitItem", t)),
+        })
+    }
+}
+
+impl ImageCodec for Expr {
+    fn encode(&self, w: &mut ImageWriter) {
+        match self {
+            Expr::Ident(name, slot) => {
+                w.u8(0);
+                name.encode(w);
+                slot.encode(w);
+            }
+            Expr::Int(v) => {
+                w.u8(1);
+                v.encode(w);
+            }
+            Expr::Float(v) => {
+                w.u8(2);
+                v.encode(w);
+            }
+            Expr::Str(v) => {
+                w.u8(3);
+                v.encode(w);
+            }
+            Expr::InterpolatedString(v) => {
+                w.u8(4);
+                v.encode(w);
+            }
+            Expr::Bool(v) => {
+                w.u8(5);
+                v.encode(w);
+            }
+            Expr::List(v) => {
+                w.u8(6);
+                v.encode(w);
+            }
+            Expr::Tuple(v) => {
+                w.u8(7);
+                v.encode(w);
+            }
+            Expr::Range(v) => {
+                w.u8(8);
+                v.encode(w);
+            }
+            Expr::IfExpr(v) => {
+                w.u8(9);
+                v.encode(w);
+            }
+            Expr::Match(v) => {
+                w.u8(10);
+                v.encode(w);
+            }
+            Expr::FuncLit(v) => {
+                w.u8(11);
+                v.encode(w);
+            }
+            Expr::Dict(v) => {
+                w.u8(12);
+                v.encode(w);
+            }
+            Expr::StructInit(v) => {
+                w.u8(13);
+                v.encode(w);
+            }
+            Expr::EnumCtor {
+                module,
+                ty,
+                variant,
+                args,
+            } => {
+                w.u8(14);
+                module.encode(w);
+                ty.encode(w);
+                variant.encode(w);
+                args.encode(w);
+            }
+            Expr::Member(v) => {
+                w.u8(15);
+                v.encode(w);
+            }
+            Expr::Index(v) => {
+                w.u8(16);
+                v.encode(w);
+            }
+            Expr::Call(v) => {
+                w.u8(17);
+                v.encode(w);
+            }
+            Expr::MethodCall(v) => {
+                w.u8(18);
+                v.encode(w);
+            }
+            Expr::Unary { op, expr } => {
+                w.u8(19);
+                op.encode(w);
+                expr.encode(w);
+            }
+            Expr::Binary { op, left, right } => {
+                w.u8(20);
+                op.encode(w);
+                left.encode(w);
+                right.encode(w);
+            }
+            Expr::Group(v) => {
+                w.u8(21);
+                v.encode(w);
+            }
+            Expr::Error(v) => {
+                w.u8(22);
+                v.encode(w);
+            }
+            Expr::Try(v) => {
+                w.u8(23);
+                v.encode(w);
+            }
+            Expr::OptChain(v) => {
+                w.u8(24);
+                v.encode(w);
+            }
+            Expr::OptLink(v) => {
+                w.u8(25);
+                v.encode(w);
+            }
+            Expr::Compare(v) => {
+                w.u8(26);
+                v.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(match r.u8()? {
+            0 => Expr::Ident(r.get()?, r.get()?),
+            1 => Expr::Int(r.get()?),
+            2 => Expr::Float(r.get()?),
+            3 => Expr::Str(r.get()?),
+            4 => Expr::InterpolatedString(r.get()?),
+            5 => Expr::Bool(r.get()?),
+            6 => Expr::List(r.get()?),
+            7 => Expr::Tuple(r.get()?),
+            8 => Expr::Range(r.get()?),
+            9 => Expr::IfExpr(r.get()?),
+            10 => Expr::Match(r.get()?),
+            11 => Expr::FuncLit(r.get()?),
+            12 => Expr::Dict(r.get()?),
+            13 => Expr::StructInit(r.get()?),
+            14 => Expr::EnumCtor {
+                module: r.get()?,
+                ty: r.get()?,
+                variant: r.get()?,
+                args: r.get()?,
+            },
+            15 => Expr::Member(r.get()?),
+            16 => Expr::Index(r.get()?),
+            17 => Expr::Call(r.get()?),
+            18 => Expr::MethodCall(r.get()?),
+            19 => Expr::Unary {
+                op: r.get()?,
+                expr: r.get()?,
+            },
+            20 => Expr::Binary {
+                op: r.get()?,
+                left: r.get()?,
+                right: r.get()?,
+            },
+            21 => Expr::Group(r.get()?),
+            22 => Expr::Error(r.get()?),
+            23 => Expr::Try(r.get()?),
+            24 => Expr::OptChain(r.get()?),
+            25 => Expr::OptLink(r.get()?),
+            26 => Expr::Compare(r.get()?),
+            t => return Err(bad_tag("Expr", t)),
+        })
+    }
+}
+
+image_struct!(SwitchTable { cases, default });
+
+impl ImageCodec for SwitchCases {
+    fn encode(&self, w: &mut ImageWriter) {
+        match self {
+            SwitchCases::Dense { base, targets } => {
+                w.u8(0);
+                base.encode(w);
+                targets.encode(w);
+            }
+            SwitchCases::Ints(v) => {
+                w.u8(1);
+                v.encode(w);
+            }
+            SwitchCases::Strs(v) => {
+                w.u8(2);
+                v.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(match r.u8()? {
+            0 => SwitchCases::Dense { base: r.get()?, targets: r.get()? },
+            1 => SwitchCases::Ints(r.get()?),
+            2 => SwitchCases::Strs(r.get()?),
+            t => return Err(bad_tag("SwitchCases", t)),
+        })
+    }
+}
+
+impl ImageCodec for Constant {
+    fn encode(&self, w: &mut ImageWriter) {
+        match self {
+            Constant::Str(v) => {
+                w.u8(0);
+                v.encode(w);
+            }
+            Constant::Int(v) => {
+                w.u8(1);
+                v.encode(w);
+            }
+            Constant::Float(v) => {
+                w.u8(2);
+                v.encode(w);
+            }
+            Constant::Struct(v) => {
+                w.u8(3);
+                v.encode(w);
+            }
+            Constant::Enum(v) => {
+                w.u8(4);
+                v.encode(w);
+            }
+            Constant::Func(v) => {
+                w.u8(5);
+                v.encode(w);
+            }
+            Constant::Names(v) => {
+                w.u8(6);
+                v.encode(w);
+            }
+            Constant::Pattern(v) => {
+                w.u8(7);
+                v.encode(w);
+            }
+            Constant::Switch(v) => {
+                w.u8(8);
+                v.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(match r.u8()? {
+            0 => Constant::Str(r.get()?),
+            1 => Constant::Int(r.get()?),
+            2 => Constant::Float(r.get()?),
+            3 => Constant::Struct(r.get()?),
+            4 => Constant::Enum(r.get()?),
+            5 => Constant::Func(r.get()?),
+            6 => Constant::Names(r.get()?),
+            7 => Constant::Pattern(r.get()?),
+            8 => Constant::Switch(r.get()?),
+            t => return Err(bad_tag("Constant", t)),
+        })
+    }
+}
+
+/// Writes an op tag followed by its operands.
+macro_rules! put_op {
+    ($w:expr, $tag:expr $(, $arg:expr)*) => {{
+        $w.u8($tag);
+        $($arg.encode($w);)*
+    }};
+}
+
+impl ImageCodec for Op {
+    fn encode(&self, w: &mut ImageWriter) {
+        match self {
+            Op::ConstInt(a) => put_op!(w, 0, a),
+            Op::ConstFloat(a) => put_op!(w, 1, a),
+            Op::ConstBool(a) => put_op!(w, 2, a),
+            Op::ConstNull => put_op!(w, 3),
+            Op::Const(a) => put_op!(w, 4, a),
+            Op::Pop => put_op!(w, 5),
+            Op::Dup => put_op!(w, 6),
+            Op::Add => put_op!(w, 7),
+            Op::AddAssignName(a) => put_op!(w, 8, a),
+            Op::AddAssignLocal(a) => put_op!(w, 9, a),
+            Op::Sub => put_op!(w, 10),
+            Op::Mul => put_op!(w, 11),
+            Op::Div => put_op!(w, 12),
+            Op::Mod => put_op!(w, 13),
+            Op::StrAppend => put_op!(w, 14),
+            Op::Eq => put_op!(w, 15),
+            Op::Ne => put_op!(w, 16),
+            Op::And => put_op!(w, 17),
+            Op::Or => put_op!(w, 18),
+            Op::Gt => put_op!(w, 19),
+            Op::Lt => put_op!(w, 20),
+            Op::Ge => put_op!(w, 21),
+            Op::Le => put_op!(w, 22),
+            Op::Not => put_op!(w, 23),
+            Op::Jump(a) => put_op!(w, 24, a),
+            Op::JumpIfFalse(a) => put_op!(w, 25, a),
+            Op::JumpIfTrue(a) => put_op!(w, 26, a),
+            Op::LoadName(a) => put_op!(w, 27, a),
+            Op::LoadLocal(a) => put_op!(w, 28, a),
+            Op::StoreName(a) => put_op!(w, 29, a),
+            Op::StoreLocal(a) => put_op!(w, 30, a),
+            Op::Use(a, b) => put_op!(w, 31, a, b),
+            Op::AssertType(a) => put_op!(w, 32, a),
+            Op::DefineStruct(a) => put_op!(w, 33, a),
+            Op::DefineEnum(a) => put_op!(w, 34, a),
+            Op::StructInit(a, b) => put_op!(w, 35, a, b),
+            Op::StructInitSpread(a, b) => put_op!(w, 36, a, b),
+            Op::EnumCtor(a, b) => put_op!(w, 37, a, b),
+            Op::EnumCtorN(a, b, c) => put_op!(w, 38, a, b, c),
+            Op::MakeFunction(a) => put_op!(w, 39, a),
+            Op::Call(a) => put_op!(w, 40, a),
+            Op::CallMethod(a, b, c, d) => put_op!(w, 41, a, b, c, d),
+            Op::CallStaticOrMethod(a, b, c, d) => put_op!(w, 42, a, b, c, d),
+            Op::IncLocal(a) => put_op!(w, 43, a),
+            Op::MakeRange(a) => put_op!(w, 44, a),
+            Op::GetMember(a, b) => put_op!(w, 45, a, b),
+            Op::GetIndex(a) => put_op!(w, 46, a),
+            Op::AssignMember(a, b) => put_op!(w, 47, a, b),
+            Op::AssignIndex(a) => put_op!(w, 48, a),
+            Op::Break(a) => put_op!(w, 49, a),
+            Op::Continue(a) => put_op!(w, 50, a),
+            Op::Return => put_op!(w, 51),
+            Op::RunPending => put_op!(w, 52),
+            Op::BuilderNewCap(a) => put_op!(w, 53, a),
+            Op::BuilderAppend => put_op!(w, 54),
+            Op::BuilderFinalize => put_op!(w, 55),
+            Op::ForEachInit(a, b, c) => put_op!(w, 56, a, b, c),
+            Op::ForEachNext(a, b, c, d) => put_op!(w, 57, a, b, c, d),
+            Op::IterPop => put_op!(w, 58),
+            Op::EnvPush => put_op!(w, 59),
+            Op::EnvPop => put_op!(w, 60),
+            Op::ListNew(a) => put_op!(w, 61, a),
+            Op::TupleNew(a) => put_op!(w, 62, a),
+            Op::DictNew(a) => put_op!(w, 63, a),
+            Op::DictInsert => put_op!(w, 64),
+            Op::DictMerge => put_op!(w, 65),
+            Op::ListAppend(a) => put_op!(w, 66, a),
+            Op::Print => put_op!(w, 67),
+            Op::Halt => put_op!(w, 68),
+            Op::MatchPattern(a) => put_op!(w, 69, a),
+            Op::MatchBindings(a) => put_op!(w, 70, a),
+            Op::LocalsPush => put_op!(w, 71),
+            Op::LocalsPop => put_op!(w, 72),
+            Op::GetStaticField(a, b) => put_op!(w, 73, a, b),
+            Op::SetStaticField(a, b) => put_op!(w, 74, a, b),
+            Op::InitStaticField(a, b) => put_op!(w, 75, a, b),
+            Op::ListReserveFor => put_op!(w, 76),
+            Op::GetMemberAt(a, b, c) => put_op!(w, 77, a, b, c),
+            Op::AssignMemberAt(a, b, c) => put_op!(w, 78, a, b, c),
+            Op::ConstEmptyStr => put_op!(w, 79),
+            Op::Try => put_op!(w, 80),
+            Op::OptLink(a) => put_op!(w, 81, a),
+            Op::Coalesce(a) => put_op!(w, 82, a),
+            Op::Pow => put_op!(w, 83),
+            Op::BitAnd => put_op!(w, 84),
+            Op::BitOr => put_op!(w, 85),
+            Op::BitXor => put_op!(w, 86),
+            Op::Shl => put_op!(w, 87),
+            Op::Shr => put_op!(w, 88),
+            Op::BitNot => put_op!(w, 89),
+            Op::CmpLink(a, b) => put_op!(w, 90, a, b),
+            Op::In => put_op!(w, 91),
+            Op::InRange(a) => put_op!(w, 92, a),
+            Op::Switch(a) => put_op!(w, 93, a),
+        }
+    }
+
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(match r.u8()? {
+            0 => Op::ConstInt(r.get()?),
+            1 => Op::ConstFloat(r.get()?),
+            2 => Op::ConstBool(r.get()?),
+            3 => Op::ConstNull,
+            4 => Op::Const(r.get()?),
+            5 => Op::Pop,
+            6 => Op::Dup,
+            7 => Op::Add,
+            8 => Op::AddAssignName(r.get()?),
+            9 => Op::AddAssignLocal(r.get()?),
+            10 => Op::Sub,
+            11 => Op::Mul,
+            12 => Op::Div,
+            13 => Op::Mod,
+            14 => Op::StrAppend,
+            15 => Op::Eq,
+            16 => Op::Ne,
+            17 => Op::And,
+            18 => Op::Or,
+            19 => Op::Gt,
+            20 => Op::Lt,
+            21 => Op::Ge,
+            22 => Op::Le,
+            23 => Op::Not,
+            24 => Op::Jump(r.get()?),
+            25 => Op::JumpIfFalse(r.get()?),
+            26 => Op::JumpIfTrue(r.get()?),
+            27 => Op::LoadName(r.get()?),
+            28 => Op::LoadLocal(r.get()?),
+            29 => Op::StoreName(r.get()?),
+            30 => Op::StoreLocal(r.get()?),
+            31 => Op::Use(r.get()?, r.get()?),
+            32 => Op::AssertType(r.get()?),
+            33 => Op::DefineStruct(r.get()?),
+            34 => Op::DefineEnum(r.get()?),
+            35 => Op::StructInit(r.get()?, r.get()?),
+            36 => Op::StructInitSpread(r.get()?, r.get()?),
+            37 => Op::EnumCtor(r.get()?, r.get()?),
+            38 => Op::EnumCtorN(r.get()?, r.get()?, r.get()?),
+            39 => Op::MakeFunction(r.get()?),
+            40 => Op::Call(r.get()?),
+            41 => Op::CallMethod(r.get()?, r.get()?, r.get()?, r.get()?),
+            42 => Op::CallStaticOrMethod(r.get()?, r.get()?, r.get()?, r.get()?),
+            43 => Op::IncLocal(r.get()?),
+            44 => Op::MakeRange(r.get()?),
+            45 => Op::GetMember(r.get()?, r.get()?),
+            46 => Op::GetIndex(r.get()?),
+            47 => Op::AssignMember(r.get()?, r.get()?),
+            48 => Op::AssignIndex(r.get()?),
+            49 => Op::Break(r.get()?),
+            50 => Op::Continue(r.get()?),
+            51 => Op::Return,
+            52 => Op::RunPending,
+            53 => Op::BuilderNewCap(r.get()?),
+            54 => Op::BuilderAppend,
+            55 => Op::BuilderFinalize,
+            56 => Op::ForEachInit(r.get()?, r.get()?, r.get()?),
+            57 => Op::ForEachNext(r.get()?, r.get()?, r.get()?, r.get()?),
+            58 => Op::IterPop,
+            59 => Op::EnvPush,
+            60 => Op::EnvPop,
+            61 => Op::ListNew(r.get()?),
+            62 => Op::TupleNew(r.get()?),
+            63 => Op::DictNew(r.get()?),
+            64 => Op::DictInsert,
+            65 => Op::DictMerge,
+            66 => Op::ListAppend(r.get()?),
+            67 => Op::Print,
+            68 => Op::Halt,
+            69 => Op::MatchPattern(r.get()?),
+            70 => Op::MatchBindings(r.get()?),
+            71 => Op::LocalsPush,
+            72 => Op::LocalsPop,
+            73 => Op::GetStaticField(r.get()?, r.get()?),
+            74 => Op::SetStaticField(r.get()?, r.get()?),
+            75 => Op::InitStaticField(r.get()?, r.get()?),
+            76 => Op::ListReserveFor,
+            77 => Op::GetMemberAt(r.get()?, r.get()?, r.get()?),
+            78 => Op::AssignMemberAt(r.get()?, r.get()?, r.get()?),
+            79 => Op::ConstEmptyStr,
+            80 => Op::Try,
+            81 => Op::OptLink(r.get()?),
+            82 => Op::Coalesce(r.get()?),
+            83 => Op::Pow,
+            84 => Op::BitAnd,
+            85 => Op::BitOr,
+            86 => Op::BitXor,
+            87 => Op::Shl,
+            88 => Op::Shr,
+            89 => Op::BitNot,
+            90 => Op::CmpLink(r.get()?, r.get()?),
+            91 => Op::In,
+            92 => Op::InRange(r.get()?),
+            93 => Op::Switch(r.get()?),
+            t => return Err(bad_tag("Op", t)),
+        })
+    }
+}
+
+impl ImageCodec for Executable {
+    fn encode(&self, w: &mut ImageWriter) {
+        match self {
+            Executable::Ast(m) => {
+                w.u8(0);
+                m.encode(w);
+            }
+            Executable::Bytecode(p) => {
+                w.u8(1);
+                p.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut ImageReader<'_>) -> Result<Self, String> {
+        Ok(match r.u8()? {
+            0 => Executable::Ast(r.get()?),
+            1 => Executable::Bytecode(r.get()?),
+            t => return Err(bad_tag("Executable", t)),
+        })
+    }
+}
@@ -1,10 +1,12 @@
 //! Intermediate representation for Xu bytecode compilation.
 
 mod ast;
+pub mod bundle;
 mod bytecode;
 mod executable;
 mod frontend;
 mod hash;
+pub mod image;
 mod program;
 
 pub use ast::*;
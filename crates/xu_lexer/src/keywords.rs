@@ -1,6 +1,9 @@
 use phf::phf_map;
 use xu_syntax::TokenKind;
 
+/// Length of the longest keyword; longer identifiers skip the lookup.
+pub(crate) const KEYWORD_MAX_LEN: usize = 8;
+
 pub(crate) static KEYWORDS_EN: phf::Map<&'static str, TokenKind> = phf_map! {
     "true" => TokenKind::True,
     "false" => TokenKind::False,
@@ -14,6 +17,7 @@ pub(crate) static KEYWORDS_EN: phf::Map<&'static str, TokenKind> = phf_map! {
     "continue" => TokenKind::KwContinue,
     "let" => TokenKind::KwLet,
     "var" => TokenKind::KwVar,
+    "const" => TokenKind::KwConst,
     "is" => TokenKind::KwIs,
     "with" => TokenKind::KwWith,
     "has" => TokenKind::KwHas,
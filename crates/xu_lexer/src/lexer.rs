@@ -4,9 +4,11 @@
 //! tokens (indentation/newlines), and collects diagnostics.
 //!
 //! Design: single linear pass, delimiter stack + indentation stack, minimal allocations.
+//! Runs of spaces, identifiers, comments and string bodies are skipped over as bytes,
+//! the latter two with `memchr`, rather than decoded char by char.
 //!
 //! Related: `LexResult`, `xu_syntax` (tokens/diagnostics).
-use crate::keywords::KEYWORDS_EN;
+use crate::keywords::{KEYWORD_MAX_LEN, KEYWORDS_EN};
 use xu_syntax::{
     Diagnostic, DiagnosticKind, Span, Token, TokenKind, is_ident_continue, is_ident_start,
 };
@@ -17,6 +19,17 @@ pub struct LexResult {
     pub diagnostics: Vec<Diagnostic>,
 }
 
+/// An open delimiter on the lexer's stack.
+struct OpenDelim {
+    ch: char,
+    span: Span,
+    /// Newlines dropped because this `(` or `[` was the innermost open
+    /// delimiter, as (token index, span). They are put back if the delimiter
+    /// turns out to be unclosed, so the parser can resynchronize on the lines
+    /// after it.
+    held_newlines: Vec<(usize, Span)>,
+}
+
 /// Xu lexer.
 pub struct Lexer<'a> {
     input: &'a str,
@@ -25,8 +38,9 @@ pub struct Lexer<'a> {
     diagnostics: Vec<Diagnostic>,
     tokens: Vec<Token>,
     at_line_start: bool,
-    delim_depth: u32,
-    delim_stack: Vec<char>,
+    delim_stack: Vec<OpenDelim>,
+    /// Held newlines of unclosed delimiters, inserted when lexing finishes.
+    restored_newlines: Vec<(usize, Span)>,
     last_sig_kind: Option<TokenKind>,
 }
 
@@ -40,8 +54,8 @@ impl<'a> Lexer<'a> {
             diagnostics: Vec::new(),
             tokens: Vec::new(),
             at_line_start: true,
-            delim_depth: 0,
             delim_stack: Vec::new(),
+            restored_newlines: Vec::new(),
             last_sig_kind: None,
         }
     }
@@ -52,26 +66,58 @@ impl<'a> Lexer<'a> {
     #[inline]
     fn handle_open_delim(&mut self, start: usize, ch: char, kind: TokenKind) {
         self.i += 1;
-        self.delim_depth = self.delim_depth.saturating_add(1);
-        self.delim_stack.push(ch);
+        self.delim_stack.push(OpenDelim {
+            ch,
+            span: Span::new(start as u32, self.i as u32),
+            held_newlines: Vec::new(),
+        });
         self.push(kind, start, self.i);
     }
 
-    /// 处理闭分隔符
+    /// 处理闭分隔符：与栈中更深处的开分隔符匹配时，其间的开分隔符视为未闭合；
+    /// 栈中没有对应的开分隔符时报告多余的闭分隔符，栈保持不变
     #[inline]
     fn handle_close_delim(&mut self, start: usize, expected: char, close_ch: char, kind: TokenKind) {
         self.i += 1;
-        if let Some(top) = self.delim_stack.pop() {
-            if top != expected {
+        match self.delim_stack.iter().rposition(|d| d.ch == expected) {
+            Some(pos) => {
+                while self.delim_stack.len() > pos + 1 {
+                    let open = self.delim_stack.pop().unwrap();
+                    self.report_unclosed(open);
+                }
+                self.delim_stack.pop();
+            }
+            None => {
                 self.diagnostics.push(Diagnostic::error_kind(DiagnosticKind::UnmatchedDelimiter(close_ch), Some(Span::new(start as u32, self.i as u32))));
             }
-        } else {
-            self.diagnostics.push(Diagnostic::error_kind(DiagnosticKind::UnmatchedDelimiter(close_ch), Some(Span::new(start as u32, self.i as u32))));
         }
-        self.delim_depth = self.delim_depth.saturating_sub(1);
         self.push(kind, start, self.i);
     }
 
+    /// 报告未闭合的分隔符，并恢复因它而省略的换行
+    fn report_unclosed(&mut self, open: OpenDelim) {
+        self.diagnostics.push(Diagnostic::error_kind(DiagnosticKind::UnclosedDelimiter(open.ch), Some(open.span)));
+        self.restored_newlines.extend(open.held_newlines);
+    }
+
+    /// 处理换行：按上下文输出 Newline，或记在最内层的 `(` / `[` 上
+    fn handle_newline(&mut self, start: usize) {
+        if !self.ends_statement() {
+            self.at_line_start = false;
+            return;
+        }
+        match self.delim_stack.last_mut() {
+            Some(open) if open.ch != '{' => {
+                open.held_newlines.push((self.tokens.len(), Span::new(start as u32, self.i as u32)));
+                self.at_line_start = false;
+            }
+            _ => {
+                self.push(TokenKind::Newline, start, self.i);
+                self.at_line_start = true;
+            }
+        }
+    }
+
     /// 处理可能带 = 后缀的运算符
     #[inline]
     fn handle_op_with_eq(&mut self, start: usize, base: TokenKind, with_eq: TokenKind) {
@@ -89,20 +135,23 @@ impl<'a> Lexer<'a> {
         let start = self.i;
         self.i += 1;
         while self.i < self.bytes.len() {
-            let ch = self.peek_char().unwrap();
-            if ch == '\n' || ch == '\r' { break; }
-            if ch == quote {
-                self.i += 1;
+            let Some(at) = memchr::memchr3(quote as u8, b'\\', b'\n', &self.bytes[self.i..]) else {
+                self.i = self.line_end(self.bytes.len());
+                break;
+            };
+            let stop = self.i + at;
+            self.i = self.line_end(stop);
+            if self.i < stop || self.bytes[stop] == b'\n' {
+                break;
+            }
+            self.i += 1;
+            if self.bytes[stop] == quote as u8 {
                 self.push(TokenKind::Str, start, self.i);
                 return;
             }
-            if ch == '\\' {
-                self.i += 1;
-                if self.i >= self.bytes.len() { break; }
-                self.i += self.peek_char().unwrap().len_utf8();
-                continue;
-            }
-            self.i += ch.len_utf8();
+            // 反斜杠之后的字符（包括换行）属于转义
+            if self.i >= self.bytes.len() { break; }
+            self.i += self.peek_char().unwrap().len_utf8();
         }
         self.diagnostics.push(Diagnostic::error_kind(DiagnosticKind::UnterminatedString, Some(Span::new(start as u32, self.i as u32))));
     }
@@ -128,21 +177,11 @@ impl<'a> Lexer<'a> {
                     if self.peek_char() == Some('\n') {
                         self.i += 1;
                     }
-                    if self.should_emit_newline() {
-                        self.push(TokenKind::Newline, start, self.i);
-                        self.at_line_start = true;
-                    } else {
-                        self.at_line_start = false;
-                    }
+                    self.handle_newline(start);
                 }
                 Some('\n') => {
                     self.i += 1;
-                    if self.should_emit_newline() {
-                        self.push(TokenKind::Newline, start, self.i);
-                        self.at_line_start = true;
-                    } else {
-                        self.at_line_start = false;
-                    }
+                    self.handle_newline(start);
                 }
                 Some('\t') => {
                     self.i += 1;
@@ -159,35 +198,35 @@ impl<'a> Lexer<'a> {
                     ));
                 }
                 Some(' ') => {
-                    self.i += 1;
+                    let rest = &self.bytes[self.i..];
+                    self.i += rest.iter().position(|&b| b != b' ').unwrap_or(rest.len());
                 }
                 Some('/') => {
                     if self.peek_str("//") {
                         self.i += 2;
-                        while let Some(ch) = self.peek_char() {
-                            if ch == '\n' {
-                                break;
-                            }
-                            self.i += ch.len_utf8();
-                        }
+                        let rest = &self.bytes[self.i..];
+                        self.i += memchr::memchr(b'\n', rest).unwrap_or(rest.len());
                     } else if self.peek_str("/*") {
                         self.i += 2;
                         let mut terminated = false;
-                        while self.i < self.bytes.len() {
+                        while let Some(at) = memchr::memchr2(b'*', b'\n', &self.bytes[self.i..]) {
+                            self.i += at;
                             if self.peek_str("*/") {
                                 self.i += 2;
                                 terminated = true;
                                 break;
                             }
-                            let ch = self.peek_char().unwrap();
-                            if ch == '\n' {
+                            if self.bytes[self.i] == b'\n' {
                                 let nl_start = self.i;
                                 self.i += 1;
                                 self.push(TokenKind::Newline, nl_start, self.i);
                                 self.at_line_start = true;
                                 continue;
                             }
-                            self.i += ch.len_utf8();
+                            self.i += 1;
+                        }
+                        if !terminated {
+                            self.i = self.bytes.len();
                         }
                         if !terminated {
                             self.diagnostics.push(Diagnostic::error_kind(
@@ -243,20 +282,36 @@ impl<'a> Lexer<'a> {
                 }
                 Some('+') => self.handle_op_with_eq(start, TokenKind::Plus, TokenKind::PlusEq),
                 Some('-') => self.handle_op_with_eq(start, TokenKind::Minus, TokenKind::MinusEq),
-                Some('*') => self.handle_op_with_eq(start, TokenKind::Star, TokenKind::StarEq),
-                Some('%') => {
+                Some('*') => {
+                    if self.peek_str("**") {
+                        self.i += 1;
+                        self.handle_op_with_eq(start, TokenKind::StarStar, TokenKind::StarStarEq);
+                    } else {
+                        self.handle_op_with_eq(start, TokenKind::Star, TokenKind::StarEq);
+                    }
+                }
+                Some('%') => self.handle_op_with_eq(start, TokenKind::Percent, TokenKind::PercentEq),
+                Some('^') => self.handle_op_with_eq(start, TokenKind::Caret, TokenKind::CaretEq),
+                Some('~') => {
                     self.i += 1;
-                    self.push(TokenKind::Percent, start, self.i);
+                    self.push(TokenKind::Tilde, start, self.i);
                 }
                 Some('#') => {
                     self.i += 1;
                     self.push(TokenKind::Hash, start, self.i);
                 }
+                Some('@') => {
+                    self.i += 1;
+                    self.push(TokenKind::At, start, self.i);
+                }
                 Some('|') => {
                     self.i += 1;
                     if self.peek_char() == Some('|') {
                         self.i += 1;
                         self.push(TokenKind::PipePipe, start, self.i);
+                    } else if self.peek_char() == Some('=') {
+                        self.i += 1;
+                        self.push(TokenKind::PipeEq, start, self.i);
                     } else {
                         self.push(TokenKind::Pipe, start, self.i);
                     }
@@ -266,20 +321,42 @@ impl<'a> Lexer<'a> {
                     if self.peek_char() == Some('&') {
                         self.i += 1;
                         self.push(TokenKind::AmpAmp, start, self.i);
+                    } else if self.peek_char() == Some('=') {
+                        self.i += 1;
+                        self.push(TokenKind::AmpEq, start, self.i);
+                    } else {
+                        self.push(TokenKind::Amp, start, self.i);
+                    }
+                }
+                Some('>') => {
+                    if self.peek_str(">>") {
+                        self.i += 1;
+                        self.handle_op_with_eq(start, TokenKind::Shr, TokenKind::ShrEq);
                     } else {
-                        self.diagnostics.push(Diagnostic::error_kind(
-                            DiagnosticKind::UnexpectedChar('&'),
-                            Some(Span::new(start as u32, self.i as u32)),
-                        ));
+                        self.handle_op_with_eq(start, TokenKind::Gt, TokenKind::Ge);
+                    }
+                }
+                Some('<') => {
+                    if self.peek_str("<<") {
+                        self.i += 1;
+                        self.handle_op_with_eq(start, TokenKind::Shl, TokenKind::ShlEq);
+                    } else {
+                        self.handle_op_with_eq(start, TokenKind::Lt, TokenKind::Le);
                     }
                 }
-                Some('>') => self.handle_op_with_eq(start, TokenKind::Gt, TokenKind::Ge),
-                Some('<') => self.handle_op_with_eq(start, TokenKind::Lt, TokenKind::Le),
                 Some('=') => self.handle_op_with_eq(start, TokenKind::Eq, TokenKind::EqEq),
                 Some('!') => self.handle_op_with_eq(start, TokenKind::Bang, TokenKind::Ne),
                 Some('?') => {
-                    self.i += 1;
-                    self.push(TokenKind::Question, start, self.i);
+                    if self.peek_str("??") {
+                        self.i += 2;
+                        self.push(TokenKind::QuestionQuestion, start, self.i);
+                    } else if self.peek_str("?.") && !self.peek_str("?..") {
+                        self.i += 2;
+                        self.push(TokenKind::QuestionDot, start, self.i);
+                    } else {
+                        self.i += 1;
+                        self.push(TokenKind::Question, start, self.i);
+                    }
                 }
                 Some('r') => {
                     if self.peek_str("r\"") {
@@ -309,13 +386,11 @@ impl<'a> Lexer<'a> {
             kind: TokenKind::Eof,
             span: Span::new(self.i as u32, self.i as u32),
         });
-        if !self.delim_stack.is_empty() {
-            for ch in self.delim_stack.iter().rev() {
-                self.diagnostics.push(Diagnostic::error_kind(
-                    DiagnosticKind::UnclosedDelimiter(*ch),
-                    Some(Span::new(self.i as u32, self.i as u32)),
-                ));
-            }
+        while let Some(open) = self.delim_stack.pop() {
+            self.report_unclosed(open);
+        }
+        if !self.restored_newlines.is_empty() {
+            self.restore_newlines();
         }
 
         LexResult {
@@ -334,13 +409,24 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn should_emit_newline(&self) -> bool {
-        if self.delim_depth > 0 {
-            match self.delim_stack.last() {
-                Some('{') => {}
-                _ => return false,
+    /// 把未闭合分隔符之后的换行按原位置插回记号流
+    fn restore_newlines(&mut self) {
+        let mut restored = std::mem::take(&mut self.restored_newlines);
+        restored.sort_by_key(|(idx, _)| *idx);
+        let mut tokens = Vec::with_capacity(self.tokens.len() + restored.len());
+        let mut next = restored.into_iter().peekable();
+        for (idx, t) in std::mem::take(&mut self.tokens).into_iter().enumerate() {
+            while let Some((_, span)) = next.next_if(|(at, _)| *at == idx) {
+                tokens.push(Token { kind: TokenKind::Newline, span });
             }
+            tokens.push(t);
         }
+        self.tokens = tokens;
+    }
+
+    /// 换行是否结束当前语句（不考虑括号）：行尾为运算符或下一行以运算符、
+    /// 点号或闭分隔符开头时为续行
+    fn ends_statement(&self) -> bool {
         if self.last_sig_kind.is_some_and(|k| {
             matches!(
                 k,
@@ -350,6 +436,11 @@ impl<'a> Lexer<'a> {
                     | TokenKind::Minus
                     | TokenKind::Star
                     | TokenKind::Slash
+                    | TokenKind::StarStar
+                    | TokenKind::Amp
+                    | TokenKind::Caret
+                    | TokenKind::Shl
+                    | TokenKind::Shr
                     | TokenKind::Eq
                     | TokenKind::LParen
                     | TokenKind::LBracket
@@ -365,7 +456,7 @@ impl<'a> Lexer<'a> {
         if matches!(next, '.' | ')' | ']' | '}') {
             return false;
         }
-        if matches!(next, '+' | '-' | '*' | '/' | '%' | '=' | '>' | '<' | '!') {
+        if matches!(next, '+' | '-' | '*' | '/' | '%' | '&' | '^' | '=' | '>' | '<' | '!') {
             return false;
         }
         true
@@ -528,17 +619,15 @@ impl<'a> Lexer<'a> {
     fn lex_raw_string(&mut self) {
         let start = self.i;
         self.i += 2;
-        while self.i < self.bytes.len() {
-            let ch = self.peek_char().unwrap();
-            if ch == '\n' || ch == '\r' {
-                break;
-            }
-            if ch == '"' {
+        if let Some(at) = memchr::memchr3(b'"', b'\n', b'\r', &self.bytes[self.i..]) {
+            self.i += at;
+            if self.bytes[self.i] == b'"' {
                 self.i += 1;
                 self.push(TokenKind::Str, start, self.i);
                 return;
             }
-            self.i += ch.len_utf8();
+        } else {
+            self.i = self.bytes.len();
         }
         self.diagnostics.push(Diagnostic::error_kind(
             DiagnosticKind::UnterminatedString,
@@ -549,15 +638,16 @@ impl<'a> Lexer<'a> {
     fn lex_triple_string(&mut self) {
         let start = self.i;
         self.i += 3;
-        while self.i < self.bytes.len() {
+        while let Some(at) = memchr::memchr(b'"', &self.bytes[self.i..]) {
+            self.i += at;
             if self.peek_str("\"\"\"") {
                 self.i += 3;
                 self.push(TokenKind::Str, start, self.i);
                 return;
             }
-            let ch = self.peek_char().unwrap();
-            self.i += ch.len_utf8();
+            self.i += 1;
         }
+        self.i = self.bytes.len();
         self.diagnostics.push(Diagnostic::error_kind(
             DiagnosticKind::UnterminatedString,
             Some(Span::new(start as u32, self.i as u32)),
@@ -569,22 +659,39 @@ impl<'a> Lexer<'a> {
         let start = self.i;
         self.i += self.peek_char().unwrap().len_utf8();
         while self.i < self.bytes.len() {
-            let ch = self.peek_char().unwrap();
-            if is_ident_continue(ch) {
-                self.i += ch.len_utf8();
-            } else {
-                break;
+            let b = self.bytes[self.i];
+            if b.is_ascii_alphanumeric() || b == b'_' {
+                self.i += 1;
+                continue;
+            }
+            match self.peek_char() {
+                Some(ch) if !ch.is_ascii() && is_ident_continue(ch) => self.i += ch.len_utf8(),
+                _ => break,
             }
         }
 
         let s = &self.input[start..self.i];
-        let kind = KEYWORDS_EN.get(s).cloned().unwrap_or(TokenKind::Ident);
+        let kind = if s.len() <= KEYWORD_MAX_LEN {
+            KEYWORDS_EN.get(s).cloned().unwrap_or(TokenKind::Ident)
+        } else {
+            TokenKind::Ident
+        };
 
         self.push(kind, start, self.i);
     }
 
     fn peek_char(&self) -> Option<char> {
-        self.input[self.i..].chars().next()
+        match self.bytes.get(self.i) {
+            Some(&b) if b.is_ascii() => Some(b as char),
+            Some(_) => self.input[self.i..].chars().next(),
+            None => None,
+        }
+    }
+
+    /// `end`, or the first `\r` before it on the current line: literals stop
+    /// at either line terminator.
+    fn line_end(&self, end: usize) -> usize {
+        memchr::memchr(b'\r', &self.bytes[self.i..end]).map_or(end, |at| self.i + at)
     }
 
     fn peek_str(&self, s: &str) -> bool {
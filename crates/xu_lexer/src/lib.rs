@@ -7,4 +7,4 @@ mod lexer;
 mod normalize;
 
 pub use lexer::{LexResult, Lexer};
-pub use normalize::normalize_source;
+pub use normalize::{LineEnding, NormalizedSource, normalize_source};
@@ -1,46 +1,130 @@
-use xu_syntax::{Diagnostic, Span};
+use xu_syntax::{Diagnostic, DiagnosticKind, Span};
+
+/// A line terminator as written in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
 
 pub struct NormalizedSource {
     pub text: String,
     pub diagnostics: Vec<Diagnostic>,
+    /// Offsets (in `text`) of every `\n` that was `\r\n` or `\r` in the input,
+    /// with its original form. Plain `\n` endings are not recorded.
+    pub line_endings: Vec<(u32, LineEnding)>,
+    /// Offsets (in `text`) of the `\n`s that were `\r\n`, ascending: each
+    /// one shifts the rest of the text by a byte.
+    crlf_at: Vec<u32>,
+}
+
+impl NormalizedSource {
+    /// Maps an offset in the normalized text back to the input.
+    pub fn original_offset(&self, offset: u32) -> u32 {
+        offset + self.crlf_at.partition_point(|&at| at < offset) as u32
+    }
+
+    /// Maps an offset in the input to the normalized text. An offset inside
+    /// a `\r\n` maps to its `\n`.
+    pub fn normalized_offset(&self, offset: u32) -> u32 {
+        // The k-th `\r\n` starts at `crlf_at[k] + k` in the input.
+        let (mut lo, mut hi) = (0, self.crlf_at.len());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if self.crlf_at[mid] + (mid as u32) < offset {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        offset - lo as u32
+    }
+
+    /// Maps a span in the normalized text back to the input.
+    pub fn original_span(&self, span: Span) -> Span {
+        Span::new(self.original_offset(span.start.0), self.original_offset(span.end.0))
+    }
+
+    /// The most common line ending of the input; `Lf` when there are none.
+    pub fn dominant_line_ending(&self) -> LineEnding {
+        let newlines = memchr::memchr_iter(b'\n', self.text.as_bytes()).count();
+        let crlf = self.crlf_at.len();
+        let cr = self.line_endings.len() - crlf;
+        let lf = newlines - self.line_endings.len();
+        if crlf > lf && crlf >= cr {
+            LineEnding::CrLf
+        } else if cr > lf && cr > crlf {
+            LineEnding::Cr
+        } else {
+            LineEnding::Lf
+        }
+    }
 }
 
+/// UTF-8 encoding of U+3000 (ideographic space).
+const FULL_WIDTH_SPACE: &[u8] = "\u{3000}".as_bytes();
+
 pub fn normalize_source(input: &str) -> NormalizedSource {
     let mut diagnostics = Vec::new();
+    let mut line_endings = Vec::new();
+    let mut crlf_at = Vec::new();
 
     let mut out = String::with_capacity(input.len());
-    let mut chars = input.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        match c {
-            '\r' => {
-                if chars.peek() == Some(&'\n') {
-                    chars.next();
+    let bytes = input.as_bytes();
+    // 只有 `\r`、`\t` 与全角空格需要改写，其余内容整段拷贝
+    let mut copied = 0;
+    for i in memchr::memchr3_iter(b'\r', b'\t', FULL_WIDTH_SPACE[0], bytes) {
+        let next = match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => i + 2,
+            b'\r' | b'\t' => i + 1,
+            _ if bytes[i..].starts_with(FULL_WIDTH_SPACE) => i + FULL_WIDTH_SPACE.len(),
+            _ => continue,
+        };
+        out.push_str(&input[copied..i]);
+        let start = out.len() as u32;
+        match bytes[i] {
+            b'\r' => {
+                let ending = if next == i + 2 { LineEnding::CrLf } else { LineEnding::Cr };
+                line_endings.push((start, ending));
+                if ending == LineEnding::CrLf {
+                    crlf_at.push(start);
                 }
                 out.push('\n');
             }
-            '\t' => {
-                let start = out.len() as u32;
-                diagnostics.push(Diagnostic::error(
-                    "Tab is not allowed; use ASCII spaces",
+            b'\t' => {
+                diagnostics.push(Diagnostic::error_kind(
+                    DiagnosticKind::TabNotAllowed,
                     Some(Span::new(start, start.saturating_add(1))),
                 ));
                 out.push(' ');
             }
-            '\u{3000}' => {
-                let start = out.len() as u32;
-                diagnostics.push(Diagnostic::error(
-                    "Full-width space is not allowed; use ASCII spaces",
+            _ => {
+                diagnostics.push(Diagnostic::error_kind(
+                    DiagnosticKind::FullWidthSpaceNotAllowed,
                     Some(Span::new(start, start.saturating_add(1))),
                 ));
                 out.push(' ');
             }
-            _ => out.push(c),
         }
+        copied = next;
     }
+    out.push_str(&input[copied..]);
 
     NormalizedSource {
         text: out,
         diagnostics,
+        line_endings,
+        crlf_at,
     }
 }
@@ -0,0 +1,45 @@
+use xu_lexer::{LineEnding, normalize_source};
+use xu_syntax::Span;
+
+#[test]
+fn crlf_and_cr_are_recorded_and_normalized() {
+    let normalized = normalize_source("a\r\nb\rc\nd");
+    assert_eq!(normalized.text, "a\nb\nc\nd");
+    assert_eq!(
+        normalized.line_endings,
+        vec![(1, LineEnding::CrLf), (3, LineEnding::Cr)]
+    );
+}
+
+#[test]
+fn spans_map_back_to_original_offsets() {
+    let src = "let a = 1\r\nlet b = 2\r\n";
+    let normalized = normalize_source(src);
+    let start = normalized.text.find("let b").unwrap() as u32;
+    let span = normalized.original_span(Span::new(start, start + 5));
+    assert_eq!(&src[span.start.0 as usize..span.end.0 as usize], "let b");
+    assert_eq!(normalized.original_offset(0), 0);
+}
+
+#[test]
+fn offsets_round_trip_between_input_and_normalized_text() {
+    let src = "a\r\nb\rc\r\n\r\nd\ne\r\n";
+    let normalized = normalize_source(src);
+    for i in 0..=normalized.text.len() as u32 {
+        assert_eq!(normalized.normalized_offset(normalized.original_offset(i)), i);
+    }
+    // `d` is at 7 after four newlines, three of them `\r\n`.
+    assert_eq!(normalized.original_offset(7), 10);
+    // Both bytes of a `\r\n` map to its `\n`.
+    assert_eq!(normalized.normalized_offset(1), 1);
+    assert_eq!(normalized.normalized_offset(2), 1);
+}
+
+#[test]
+fn dominant_line_ending_reflects_input() {
+    assert_eq!(normalize_source("a\r\nb\r\nc\n").dominant_line_ending(), LineEnding::CrLf);
+    assert_eq!(normalize_source("a\nb\r\nc\n").dominant_line_ending(), LineEnding::Lf);
+    assert_eq!(normalize_source("a\rb\r").dominant_line_ending(), LineEnding::Cr);
+    assert_eq!(normalize_source("no newline").dominant_line_ending(), LineEnding::Lf);
+    assert_eq!(LineEnding::CrLf.as_str(), "\r\n");
+}
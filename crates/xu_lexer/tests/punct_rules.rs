@@ -49,3 +49,26 @@ fn ascii_dot_is_access() {
             .any(|t| matches!(t.kind, xu_syntax::TokenKind::Dot))
     );
 }
+
+#[test]
+fn operators_take_the_longest_match() {
+    use xu_syntax::TokenKind::*;
+    let src = "a ** b **= c % d %= e & f &= g | h |= i ^ j ^= ~k << l <<= m >> n >>= o <= p >= q && r || s";
+    let lex = Lexer::new(src).lex();
+    assert!(lex.diagnostics.is_empty(), "diagnostics={:?}", lex.diagnostics);
+    let ops: Vec<_> = lex.tokens.iter().map(|t| t.kind).filter(|k| !matches!(k, Ident | Eof)).collect();
+    assert_eq!(
+        ops,
+        [
+            StarStar, StarStarEq, Percent, PercentEq, Amp, AmpEq, Pipe, PipeEq, Caret, CaretEq, Tilde, Shl, ShlEq,
+            Shr, ShrEq, Le, Ge, AmpAmp, PipePipe
+        ]
+    );
+}
+
+#[test]
+fn trailing_bit_operator_continues_the_line() {
+    let lex = Lexer::new("x = a &\n    b\ny = c\n    << 2").lex();
+    let newlines = lex.tokens.iter().filter(|t| matches!(t.kind, xu_syntax::TokenKind::Newline)).count();
+    assert_eq!(newlines, 1);
+}
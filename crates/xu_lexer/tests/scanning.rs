@@ -0,0 +1,65 @@
+use xu_lexer::{Lexer, normalize_source};
+use xu_syntax::TokenKind;
+
+/// Kinds and texts of the tokens of `src`, without the trailing `Eof`.
+fn tokens(src: &str) -> Vec<(TokenKind, &str)> {
+    let lex = Lexer::new(src).lex();
+    assert!(lex.diagnostics.is_empty(), "{:?}", lex.diagnostics);
+    let mut out: Vec<_> =
+        lex.tokens.iter().map(|t| (t.kind, &src[t.span.start.0 as usize..t.span.end.0 as usize])).collect();
+    assert_eq!(out.pop().map(|(k, _)| k), Some(TokenKind::Eof));
+    out
+}
+
+#[test]
+fn literals_and_comments_end_where_they_should() {
+    let src = "x = \"a \\\" 名 \\\\\" + 'b\\'c' + r\"C:\\d\" /* one * two\n ** */ + \"\"\"x\"y\n\"\"\" // \"tail\n";
+    let kinds: Vec<_> = tokens(src).into_iter().filter(|(k, _)| *k != TokenKind::Plus).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (TokenKind::Ident, "x"),
+            (TokenKind::Eq, "="),
+            (TokenKind::Str, "\"a \\\" 名 \\\\\""),
+            (TokenKind::Str, "'b\\'c'"),
+            (TokenKind::Str, "r\"C:\\d\""),
+            (TokenKind::Newline, "\n"),
+            (TokenKind::Str, "\"\"\"x\"y\n\"\"\""),
+            (TokenKind::Newline, "\n"),
+        ]
+    );
+}
+
+#[test]
+fn identifiers_mix_ascii_and_cjk_and_keywords_are_found_by_length() {
+    let src = "continue continued 名称_2x _值 a名b  when";
+    assert_eq!(
+        tokens(src),
+        vec![
+            (TokenKind::KwContinue, "continue"),
+            (TokenKind::Ident, "continued"),
+            (TokenKind::Ident, "名称_2x"),
+            (TokenKind::Ident, "_值"),
+            (TokenKind::Ident, "a名b"),
+            (TokenKind::KwWhen, "when"),
+        ]
+    );
+}
+
+#[test]
+fn unterminated_literals_stop_at_the_line_end() {
+    for (src, end) in [("\"abc\r\nx", 4), ("'abc\\", 5), ("r\"abc\ny", 5), ("\"\"\"abc\n", 7), ("/* abc \n", 8)] {
+        let lex = Lexer::new(src).lex();
+        let d = lex.diagnostics.first().unwrap_or_else(|| panic!("no diagnostic for {src:?}"));
+        assert!(d.message.starts_with("Unterminated"), "{src:?}: {d:?}");
+        assert_eq!(d.span.map(|s| (s.start.0, s.end.0)), Some((0, end)), "{src:?}");
+    }
+}
+
+#[test]
+fn normalizing_rewrites_only_line_endings_and_forbidden_spaces() {
+    let normalized = normalize_source("名\r\nb\u{3000}ã\tc\r");
+    assert_eq!(normalized.text, "名\nb ã c\n");
+    let found: Vec<_> = normalized.diagnostics.iter().map(|d| (&d.message[..4], d.span.unwrap().start.0)).collect();
+    assert_eq!(found, vec![("Full", 5), ("Tab ", 8)]);
+}
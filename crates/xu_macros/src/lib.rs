@@ -0,0 +1,197 @@
+//! Procedural macros for writing Xu builtins in Rust.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Expr, FnArg, ItemFn, ItemStruct, Lit, Meta, Pat, Type, parse_macro_input};
+
+/// Turns ordinary Rust functions into builtins.
+///
+/// ```ignore
+/// xu_builtin! {
+///     /// Installs `hypot` and `repeat`.
+///     pub struct Shapes;
+///
+///     pub fn hypot(a: f64, b: f64) -> f64 {
+///         a.hypot(b)
+///     }
+///
+///     #[name = "__repeat"]
+///     pub fn repeat(rt: &mut Runtime, s: String, n: Option<i64>) -> Result<String, String> {
+///         ...
+///     }
+/// }
+/// ```
+///
+/// Each function `f` gets a wrapper `builtin_f` with the signature of a
+/// `BuiltinFn`: it checks the argument count, converts each argument with
+/// `xu_runtime::native::FromValue`, calls `f` and converts the result with
+/// `IntoValue`. A first parameter of type `&mut Runtime` or `&Runtime` is
+/// passed the runtime instead of an argument; trailing `Option` parameters
+/// may be left out by the caller. The builtin is named after the function
+/// unless `#[name = "..."]` says otherwise. The optional unit struct at the
+/// top becomes a `BuiltinProvider` registering every function of the block.
+#[proc_macro]
+pub fn xu_builtin(input: TokenStream) -> TokenStream {
+    let block = parse_macro_input!(input as Block);
+    match expand(block) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+struct Block {
+    provider: Option<ItemStruct>,
+    funcs: Vec<ItemFn>,
+}
+
+impl Parse for Block {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let provider = if input.fork().parse::<ItemStruct>().is_ok() { Some(input.parse()?) } else { None };
+        let mut funcs = Vec::new();
+        while !input.is_empty() {
+            funcs.push(input.parse()?);
+        }
+        Ok(Block { provider, funcs })
+    }
+}
+
+/// The builtin name set with `#[name = "..."]`, removed from `attrs`.
+fn take_name(attrs: &mut Vec<Attribute>) -> syn::Result<Option<String>> {
+    let mut name = None;
+    let mut err = None;
+    attrs.retain(|a| {
+        if !a.path().is_ident("name") {
+            return true;
+        }
+        match &a.meta {
+            Meta::NameValue(nv) => match &nv.value {
+                Expr::Lit(l) => match &l.lit {
+                    Lit::Str(s) => name = Some(s.value()),
+                    other => err = Some(syn::Error::new_spanned(other, "expected a string")),
+                },
+                other => err = Some(syn::Error::new_spanned(other, "expected a string")),
+            },
+            _ => err = Some(syn::Error::new_spanned(a, "expected #[name = \"...\"]")),
+        }
+        false
+    });
+    match err {
+        Some(e) => Err(e),
+        None => Ok(name),
+    }
+}
+
+/// Whether `ty` is `&Runtime` or `&mut Runtime`, and which.
+fn runtime_ref(ty: &Type) -> Option<bool> {
+    let Type::Reference(r) = ty else {
+        return None;
+    };
+    let Type::Path(p) = &*r.elem else {
+        return None;
+    };
+    (p.path.segments.last()?.ident == "Runtime").then_some(r.mutability.is_some())
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p.path.segments.last().is_some_and(|s| s.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// `"expects 1 argument"`, `"expects 1 or 2 arguments"`, `"expects 1 to 3
+/// arguments"`, as the hand-written builtins say it.
+fn arity_message(name: &str, min: usize, max: usize) -> String {
+    match max - min {
+        0 if max == 1 => format!("{name} expects 1 argument"),
+        0 => format!("{name} expects {max} arguments"),
+        1 => format!("{name} expects {min} or {max} arguments"),
+        _ => format!("{name} expects {min} to {max} arguments"),
+    }
+}
+
+fn expand(block: Block) -> syn::Result<proc_macro2::TokenStream> {
+    let mut out = proc_macro2::TokenStream::new();
+    let mut entries = Vec::new();
+    for mut func in block.funcs {
+        let name = take_name(&mut func.attrs)?.unwrap_or_else(|| func.sig.ident.to_string());
+        let ident = func.sig.ident.clone();
+        let wrapper = format_ident!("builtin_{}", ident);
+        let vis = func.vis.clone();
+        let docs: Vec<_> = func.attrs.iter().filter(|a| a.path().is_ident("doc")).cloned().collect();
+        func.attrs.retain(|a| !a.path().is_ident("doc"));
+        func.vis = syn::Visibility::Inherited;
+
+        let mut params = func.sig.inputs.iter().peekable();
+        let mut pass_rt = None;
+        if let Some(FnArg::Typed(first)) = params.peek() {
+            if let Some(mutable) = runtime_ref(&first.ty) {
+                pass_rt = Some(mutable);
+                params.next();
+            }
+        }
+        let mut types = Vec::new();
+        for p in params {
+            match p {
+                FnArg::Typed(t) => {
+                    if !matches!(&*t.pat, Pat::Ident(_) | Pat::Wild(_)) {
+                        return Err(syn::Error::new_spanned(&t.pat, "xu_builtin parameters must be plain names"));
+                    }
+                    if runtime_ref(&t.ty).is_some() {
+                        return Err(syn::Error::new_spanned(&t.ty, "the runtime can only be the first parameter"));
+                    }
+                    types.push((*t.ty).clone());
+                }
+                FnArg::Receiver(r) => return Err(syn::Error::new_spanned(r, "xu_builtin functions take no self")),
+            }
+        }
+        let max = types.len();
+        let min = max - types.iter().rev().take_while(|t| is_option(t)).count();
+        if let Some(t) = types[..min].iter().find(|t| is_option(t)) {
+            return Err(syn::Error::new_spanned(t, "only trailing parameters can be optional"));
+        }
+        let arity = arity_message(&name, min, max);
+        let arity_check = if min == max {
+            quote! { if args.len() != #max { return ::core::result::Result::Err(#arity.into()); } }
+        } else {
+            quote! { if args.len() < #min || args.len() > #max { return ::core::result::Result::Err(#arity.into()); } }
+        };
+        let vars: Vec<_> = (0..max).map(|i| format_ident!("arg{}", i)).collect();
+        let converts = types.iter().zip(&vars).enumerate().map(|(i, (ty, var))| {
+            quote! { let #var = <#ty as ::xu_runtime::native::FromValue>::from_arg(rt, args.get(#i), #name)?; }
+        });
+        let rt_arg = match pass_rt {
+            Some(true) => quote! { rt, },
+            Some(false) => quote! { &*rt, },
+            None => quote! {},
+        };
+        out.extend(quote! {
+            #(#docs)*
+            #vis fn #wrapper(
+                rt: &mut ::xu_runtime::Runtime,
+                args: &[::xu_runtime::Value],
+            ) -> ::core::result::Result<::xu_runtime::Value, ::std::string::String> {
+                #func
+                #arity_check
+                #(#converts)*
+                let result = #ident(#rt_arg #(#vars),*);
+                ::xu_runtime::native::IntoValue::into_value(result, rt)
+            }
+        });
+        entries.push(quote! { registry.register(#name, #wrapper); });
+    }
+    if let Some(provider) = block.provider {
+        let ident = &provider.ident;
+        out.extend(quote! {
+            #provider
+
+            impl ::xu_runtime::BuiltinProvider for #ident {
+                fn install(&self, registry: &mut ::xu_runtime::BuiltinRegistry) {
+                    #(#entries)*
+                }
+            }
+        });
+    }
+    Ok(out)
+}
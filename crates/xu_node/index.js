@@ -0,0 +1,12 @@
+// Loads the native module built by `napi build --platform`.
+const { platform, arch } = process;
+
+let suffix = `${platform}-${arch}`;
+if (platform === "linux") {
+  const { glibcVersionRuntime } = process.report.getReport().header;
+  suffix += glibcVersionRuntime ? "-gnu" : "-musl";
+} else if (platform === "win32") {
+  suffix += "-msvc";
+}
+
+module.exports = require(`./xuscript.${suffix}.node`);
@@ -0,0 +1,331 @@
+//! Node.js bindings for the Xu interpreter, published as the `xuscript`
+//! package.
+//!
+//! ```js
+//! const xu = require("xuscript");
+//!
+//! xu.run('println("hi")');  // "hi\n"
+//!
+//! const rt = new xu.Runtime({ allowedRoots: ["./rules"], env: { REGION: "eu" } });
+//! rt.register("discount", (total) => (total > 100 ? total * 0.9 : total));
+//! rt.run("func price(total: float) -> float { return discount(total) + 5.0 }");
+//! rt.call("price", [120.5]);  // 113.45
+//! ```
+//!
+//! Values cross the boundary as copies through [`Js`]: `null`/`undefined`,
+//! booleans, numbers, strings, arrays and plain objects map to the script
+//! values of the same shape.
+
+use std::collections::HashMap;
+use std::ptr;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsValue, ValueType, sys};
+use napi_derive::napi;
+
+use xu_driver::Driver;
+use xu_runtime::{EnvVars, FileStat, FileSystem, HostValue};
+use xu_syntax::Severity;
+
+/// Conversion nesting limit, which also stops objects that contain themselves.
+const MAX_DEPTH: usize = 256;
+
+/// Whole numbers in this range become script ints; others stay floats.
+const INT_RANGE: std::ops::RangeInclusive<f64> = -(1i64 << 47) as f64..=((1i64 << 47) - 1) as f64;
+
+/// How a runtime may reach outside the script.
+#[napi(object)]
+#[derive(Default)]
+pub struct RuntimeOptions {
+    /// Directories whose files scripts may read and write. Without any,
+    /// scripts have no file access.
+    pub allowed_roots: Option<Vec<String>>,
+    /// Capabilities approved when a script asks with `with_capability`,
+    /// such as `"fs.read"`; other requests are denied.
+    pub capabilities: Option<Vec<String>>,
+    /// Environment variables scripts see; none by default.
+    pub env: Option<HashMap<String, String>>,
+}
+
+/// An interpreter whose programs can call functions registered from
+/// JavaScript.
+#[napi]
+pub struct Runtime {
+    rt: xu_runtime::Runtime,
+    driver: Driver,
+}
+
+#[napi]
+impl Runtime {
+    #[napi(constructor)]
+    pub fn new(options: Option<RuntimeOptions>) -> Result<Self> {
+        let options = options.unwrap_or_default();
+        let mut rt = xu_runtime::Runtime::new();
+        rt.set_strict_vars(true);
+        rt.set_frontend(Box::new(Driver::new()));
+        match options.allowed_roots {
+            Some(roots) if !roots.is_empty() => {
+                for root in &roots {
+                    rt.add_allowed_root(root).map_err(Error::from_reason)?;
+                }
+            }
+            _ => rt.set_file_system(Box::new(NoFiles)),
+        }
+        let approved = options.capabilities.unwrap_or_default();
+        rt.set_capability_policy(Box::new(move |c: &str| approved.iter().any(|a| a == c)));
+        rt.set_env_vars(Box::new(Vars(options.env.unwrap_or_default())));
+        Ok(Runtime { rt, driver: Driver::new() })
+    }
+
+    /// Compiles and runs `source` as a program and returns what it printed.
+    /// Each call is a new program, whose functions `call` finds afterwards.
+    #[napi]
+    pub fn run(&mut self, source: String) -> Result<String> {
+        let names = self.rt.host_function_names();
+        let compiled = self
+            .driver
+            .compile_text_with_predefs("<node>", &source, true, &names)
+            .map_err(Error::from_reason)?;
+        let errors: Vec<_> = compiled
+            .diagnostics
+            .iter()
+            .filter(|d| matches!(d.severity, Severity::Error))
+            .cloned()
+            .collect();
+        if !errors.is_empty() {
+            return Err(Error::from_reason(xu_syntax::render_diagnostics(&compiled.source, &errors)));
+        }
+        match self.rt.exec_executable(&compiled.executable) {
+            Ok(res) => Ok(res.output),
+            Err(e) => {
+                self.rt.take_output();
+                Err(Error::from_reason(e))
+            }
+        }
+    }
+
+    /// Calls the top-level function `name` of the last program with copies
+    /// of `args`. What the function prints goes to `process.stdout`.
+    #[napi(ts_return_type = "unknown")]
+    pub fn call(&mut self, env: Env, name: String, args: Option<Vec<Js>>) -> Result<Js> {
+        let args = args.unwrap_or_default().into_iter().map(|a| a.0).collect();
+        let res = self.rt.call_global_host(&name, args);
+        let output = self.rt.take_output();
+        if !output.is_empty() {
+            let stdout: Object = env.get_global()?.get_named_property::<Object>("process")?.get_named_property("stdout")?;
+            let write: Function<String, Unknown> = stdout.get_named_property("write")?;
+            write.apply(stdout, output)?;
+        }
+        res.map(Js).map_err(Error::from_reason)
+    }
+
+    /// Defines the global function `name` for programs run from now on.
+    /// Scripts call `func` with copies of their arguments; an exception it
+    /// throws becomes a runtime error in the script.
+    #[napi(ts_args_type = "name: string, func: (...args: any[]) => unknown")]
+    pub fn register(&mut self, env: Env, name: String, func: Function<JsArgs, Js>) -> Result<()> {
+        let func = func.create_ref()?;
+        let raw_env = env.raw();
+        let fname = name.clone();
+        self.rt.define_host_function(&name, move |args| {
+            let env = Env::from_raw(raw_env);
+            func.borrow_back(&env)
+                .and_then(|f| f.call(JsArgs(args.to_vec())))
+                .map(|r| r.0)
+                .map_err(|e| format!("{fname}: {}", e.reason))
+        });
+        Ok(())
+    }
+}
+
+/// Runs `source` in a new runtime built with `options`, with the functions
+/// of `functions` defined under their keys, and returns what it printed.
+#[napi(ts_args_type = "source: string, functions?: Record<string, (...args: any[]) => unknown>, options?: RuntimeOptions")]
+pub fn run(
+    env: Env,
+    source: String,
+    functions: Option<HashMap<String, Function<JsArgs, Js>>>,
+    options: Option<RuntimeOptions>,
+) -> Result<String> {
+    let mut rt = Runtime::new(options)?;
+    for (name, func) in functions.unwrap_or_default() {
+        rt.register(env, name, func)?;
+    }
+    rt.run(source)
+}
+
+/// A value copied between JavaScript and a script.
+///
+/// From JavaScript, `null` and `undefined` become unit and whole numbers
+/// within the script int range ints; arrays become lists and other objects
+/// dicts with string keys. Back in JavaScript, options become their value
+/// or `null`, tuples arrays and structs objects of their fields; ranges and
+/// other enums have no JavaScript form.
+pub struct Js(HostValue);
+
+impl TypeName for Js {
+    fn type_name() -> &'static str {
+        "unknown"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::Unknown
+    }
+}
+
+impl ValidateNapiValue for Js {}
+
+impl FromNapiValue for Js {
+    unsafe fn from_napi_value(env: sys::napi_env, value: sys::napi_value) -> Result<Self> {
+        let value = unsafe { Unknown::from_raw_unchecked(env, value) };
+        from_js(&value, 0).map(Js)
+    }
+}
+
+impl ToNapiValue for Js {
+    unsafe fn to_napi_value(env: sys::napi_env, value: Self) -> Result<sys::napi_value> {
+        unsafe { to_js(env, value.0) }
+    }
+}
+
+/// The arguments of a host function call, passed to the JavaScript function
+/// as separate arguments.
+pub struct JsArgs(Vec<HostValue>);
+
+impl JsValuesTupleIntoVec for JsArgs {
+    // napi calls this with the environment of the current call.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    fn into_vec(self, env: sys::napi_env) -> Result<Vec<sys::napi_value>> {
+        self.0.into_iter().map(|v| unsafe { to_js(env, v) }).collect()
+    }
+}
+
+fn from_js(value: &Unknown<'_>, depth: usize) -> Result<HostValue> {
+    if depth > MAX_DEPTH {
+        return Err(Error::from_reason("value is nested too deeply (or contains itself) to pass to Xu"));
+    }
+    let env = value.value().env;
+    let raw = value.raw();
+    Ok(match value.get_type()? {
+        ValueType::Undefined | ValueType::Null => HostValue::Unit,
+        ValueType::Boolean => HostValue::Bool(unsafe { bool::from_napi_value(env, raw)? }),
+        ValueType::Number => {
+            let n = unsafe { f64::from_napi_value(env, raw)? };
+            if n.fract() == 0.0 && INT_RANGE.contains(&n) {
+                HostValue::Int(n as i64)
+            } else {
+                HostValue::Float(n)
+            }
+        }
+        ValueType::String => HostValue::Str(unsafe { String::from_napi_value(env, raw)? }),
+        ValueType::Object if value.is_array()? => {
+            let array = unsafe { Array::from_napi_value(env, raw)? };
+            let mut items = Vec::with_capacity(array.len() as usize);
+            for i in 0..array.len() {
+                let item: Unknown = array.get(i)?.expect("index within length");
+                items.push(from_js(&item, depth + 1)?);
+            }
+            HostValue::List(items)
+        }
+        ValueType::Object => {
+            let object = unsafe { Object::from_napi_value(env, raw)? };
+            let mut entries = Vec::new();
+            for key in Object::keys(&object)? {
+                let item: Unknown = object.get_named_property(&key)?;
+                entries.push((HostValue::Str(key), from_js(&item, depth + 1)?));
+            }
+            HostValue::Dict(entries)
+        }
+        other => {
+            let kind = other.to_string().to_lowercase();
+            return Err(Error::new(Status::InvalidArg, format!("cannot pass a {kind} to Xu")));
+        }
+    })
+}
+
+/// # Safety
+///
+/// `env` is the environment of the current call.
+unsafe fn to_js(env: sys::napi_env, value: HostValue) -> Result<sys::napi_value> {
+    let list = |items: Vec<HostValue>| -> Result<sys::napi_value> {
+        let mut array = ptr::null_mut();
+        check_status!(unsafe { sys::napi_create_array_with_length(env, items.len(), &mut array) })?;
+        for (i, item) in items.into_iter().enumerate() {
+            let item = unsafe { to_js(env, item)? };
+            check_status!(unsafe { sys::napi_set_element(env, array, i as u32, item) })?;
+        }
+        Ok(array)
+    };
+    let object = |entries: Vec<(String, HostValue)>| -> Result<sys::napi_value> {
+        let mut object = Object::new(&Env::from_raw(env))?;
+        for (key, item) in entries {
+            object.set(key, Js(item))?;
+        }
+        unsafe { Object::to_napi_value(env, object) }
+    };
+    unsafe {
+        match value {
+            HostValue::Unit | HostValue::Option(None) => Null::to_napi_value(env, Null),
+            HostValue::Bool(b) => bool::to_napi_value(env, b),
+            HostValue::Int(i) => f64::to_napi_value(env, i as f64),
+            HostValue::Float(f) => f64::to_napi_value(env, f),
+            HostValue::Str(s) => String::to_napi_value(env, s),
+            HostValue::List(items) | HostValue::Tuple(items) => list(items),
+            HostValue::Dict(entries) => {
+                let entries = entries
+                    .into_iter()
+                    .map(|(k, v)| match k {
+                        HostValue::Str(k) => Ok((k, v)),
+                        HostValue::Int(i) => Ok((i.to_string(), v)),
+                        other => Err(Error::from_reason(format!("cannot use {other:?} as a JavaScript object key"))),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                object(entries)
+            }
+            HostValue::Option(Some(inner)) => to_js(env, *inner),
+            HostValue::Struct { fields, .. } => object(fields),
+            HostValue::Range(..) => Err(Error::from_reason("cannot pass a range to JavaScript")),
+            HostValue::Enum { ty, variant, .. } => {
+                Err(Error::from_reason(format!("cannot pass {ty}#{variant} to JavaScript")))
+            }
+        }
+    }
+}
+
+/// The file system of runtimes without allowed roots: every access fails.
+struct NoFiles;
+
+impl FileSystem for NoFiles {
+    fn metadata(&self, path: &str) -> std::result::Result<(), String> {
+        Err(no_files(path))
+    }
+
+    fn stat(&self, path: &str) -> std::result::Result<FileStat, String> {
+        Err(no_files(path))
+    }
+
+    fn canonicalize(&self, path: &str) -> std::result::Result<String, String> {
+        Err(no_files(path))
+    }
+
+    fn read_to_string(&self, path: &str) -> std::result::Result<String, String> {
+        Err(no_files(path))
+    }
+
+    fn write(&self, path: &str, _contents: &str) -> std::result::Result<(), String> {
+        Err(no_files(path))
+    }
+}
+
+fn no_files(path: &str) -> String {
+    format!("{path}: file access is not allowed")
+}
+
+/// The environment variables given in [`RuntimeOptions::env`].
+struct Vars(HashMap<String, String>);
+
+impl EnvVars for Vars {
+    fn var(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+}
@@ -0,0 +1,81 @@
+// Run with `npm test` after `npm run build`.
+const test = require("node:test");
+const assert = require("node:assert");
+
+const xu = require("..");
+
+test("run returns what the program printed", () => {
+  assert.strictEqual(xu.run('println("hi")\nprintln(1 + 2)\n'), "hi\n3\n");
+});
+
+test("compile and runtime errors throw", () => {
+  assert.throws(() => xu.run("println(missing)\n"), /missing/);
+  assert.throws(() => xu.run("let xs = [1]\nprintln(xs[5])\n"), /out of range/);
+});
+
+test("run defines host functions", () => {
+  const out = xu.run('println(greet("xu"))\n', { greet: (name) => "hello " + name });
+  assert.strictEqual(out, "hello xu\n");
+});
+
+test("values round-trip", () => {
+  const rt = new xu.Runtime();
+  rt.register("echo", (v) => v);
+  rt.run("func pass(v: any) -> any { return echo(v) }\n");
+  for (const v of [null, true, 7, -(2 ** 40), 2.5, "s", [1, [2.5, "x"]], { k: [1, 2], n: { m: null } }]) {
+    assert.deepStrictEqual(rt.call("pass", [v]), v);
+  }
+  assert.strictEqual(rt.call("pass", [undefined]), null);
+});
+
+test("script values", () => {
+  const rt = new xu.Runtime();
+  rt.run(
+    "P has {\n    x: int\n    y: int\n}\n" +
+      "func pt() -> P { return P{ x: 1, y: 2 } }\n" +
+      "func opt(b: bool) -> Option[int] { if b { return Option#some(4) } return Option#none }\n"
+  );
+  assert.deepStrictEqual(rt.call("pt"), { x: 1, y: 2 });
+  assert.strictEqual(rt.call("opt", [true]), 4);
+  assert.strictEqual(rt.call("opt", [false]), null);
+});
+
+test("unconvertible values throw", () => {
+  const rt = new xu.Runtime();
+  rt.run("func id(v: any) -> any { return v }\nfunc r() -> any { return 1..3 }\n");
+  assert.throws(() => rt.call("id", [() => 1]), /function/);
+  assert.throws(() => rt.call("r"), /range/);
+  const cyclic = {};
+  cyclic.self = cyclic;
+  assert.throws(() => rt.call("id", [cyclic]), /nested too deeply/);
+});
+
+test("callback exceptions become script errors", () => {
+  const rt = new xu.Runtime();
+  rt.register("check", () => {
+    throw new Error("no rules match");
+  });
+  assert.throws(() => rt.run("check(1)\n"), /no rules match/);
+  // The runtime stays usable.
+  assert.strictEqual(rt.run("println(2)\n"), "2\n");
+});
+
+test("the sandbox limits files, capabilities and environment", () => {
+  const fs = require("node:fs");
+  const os = require("node:os");
+  const path = require("node:path");
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), "xu-node-"));
+  const file = path.join(dir, "data.txt");
+  fs.writeFileSync(file, "inside");
+  const read = `let f = open(${JSON.stringify(file)})\nprintln(f.read())\n`;
+
+  assert.throws(() => new xu.Runtime().run(read), /not allowed/);
+  assert.strictEqual(new xu.Runtime({ allowedRoots: [dir] }).run(read), "inside\n");
+
+  const env = 'println(env_get("REGION"))\nprintln(env_get("HOME"))\n';
+  assert.strictEqual(new xu.Runtime({ env: { REGION: "eu" } }).run(env), "eu\n\n");
+
+  const ask = 'with_capability("fs.read", || { println("granted") })\n';
+  assert.throws(() => new xu.Runtime().run(ask), /denied|not granted/i);
+  assert.strictEqual(new xu.Runtime({ capabilities: ["fs.read"] }).run(ask), "granted\n");
+});
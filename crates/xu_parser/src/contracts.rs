@@ -0,0 +1,311 @@
+//! Function contracts: `@requires(cond[, msg])` and `@ensures(cond[, msg])`.
+//!
+//! Contract annotations precede a function definition and are lowered into
+//! ordinary statements while the function is parsed: `requires` clauses become
+//! prologue assertions, `ensures` clauses become assertions before every return
+//! (with the returned value bound to `result`). The assertions call the same
+//! `__builtin_assert` primitive as `std/assert`. When contracts are disabled
+//! (release builds) the clauses are still parsed, then dropped.
+use std::cell::Cell;
+
+use crate::{AssignOp, AssignStmt, BinaryOp, CallExpr, DeclKind, Expr, FuncDef, Stmt, Visibility};
+use xu_syntax::{Diagnostic, DiagnosticKind, Span, TokenKind};
+
+use super::Parser;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ContractKind {
+    Requires,
+    Ensures,
+}
+
+/// A parsed contract clause awaiting its function.
+#[derive(Clone, Debug)]
+pub(crate) struct Contract {
+    kind: ContractKind,
+    cond: Expr,
+    message: Option<Expr>,
+    /// Span of the condition, for the violation message.
+    span: Span,
+    /// Whether the condition or message names `result`.
+    uses_result: bool,
+}
+
+/// Name the returned value is bound to inside `ensures` clauses.
+const RESULT_NAME: &str = "result";
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a, 'b> Parser<'a, 'b> {
+    /// Parses consecutive contract annotations into `pending_contracts`.
+    /// The next function definition takes them.
+    pub(crate) fn parse_contract_annotations(&mut self) {
+        while self.at(TokenKind::At) {
+            // Each annotation recovers on its own; resyncing from an earlier
+            // one would land back on this `@`.
+            self.begin_stmt();
+            let at_span = self.cur_span();
+            self.bump();
+            let kind = match self.peek_kind() {
+                TokenKind::Ident => {
+                    let t = self.bumped();
+                    match self.token_text(&t) {
+                        "requires" => Some(ContractKind::Requires),
+                        "ensures" => Some(ContractKind::Ensures),
+                        other => {
+                            self.diagnostics.push(Diagnostic::error_kind(
+                                DiagnosticKind::UnknownAnnotation(other.to_string()),
+                                Some(t.span),
+                            ));
+                            None
+                        }
+                    }
+                }
+                _ => {
+                    self.diagnostics.push(Diagnostic::error_kind(
+                        DiagnosticKind::ExpectedToken("requires or ensures".to_string()),
+                        Some(self.cur_span()),
+                    ));
+                    None
+                }
+            };
+            match kind.and_then(|kind| self.parse_contract_args(kind)) {
+                Some(c) => self.pending_contracts.push(c),
+                None => {
+                    self.recover_stmt();
+                }
+            }
+            self.skip_trivia();
+            if !self.at(TokenKind::At) && !self.at_func_def() {
+                self.diagnostics.push(Diagnostic::error_kind(
+                    DiagnosticKind::ContractWithoutFunction,
+                    Some(at_span),
+                ));
+                self.pending_contracts.clear();
+                return;
+            }
+        }
+    }
+
+    /// `(cond[, message])` after `@requires` / `@ensures`.
+    fn parse_contract_args(&mut self, kind: ContractKind) -> Option<Contract> {
+        self.expect(TokenKind::LParen)?;
+        self.skip_layout();
+        let start = self.cur_span();
+        let first = self.i;
+        let cond = self.parse_expr(0)?;
+        let end = self.tokens[self.i - 1].span;
+        self.skip_layout();
+        let message = if self.at(TokenKind::Comma) {
+            self.bump();
+            self.skip_layout();
+            Some(self.parse_expr(0)?)
+        } else {
+            None
+        };
+        self.skip_layout();
+        let uses_result = self.tokens[first..self.i]
+            .iter()
+            .any(|t| t.kind == TokenKind::Ident && self.token_text(t) == RESULT_NAME);
+        self.expect(TokenKind::RParen)?;
+        Some(Contract {
+            kind,
+            cond,
+            message,
+            span: Span::new(start.start.0, end.end.0),
+            uses_result,
+        })
+    }
+
+    /// Whether the next tokens start a function definition, allowing the
+    /// `pub` / `static` modifiers in front of `func`.
+    fn at_func_def(&self) -> bool {
+        let mut n = 0;
+        while matches!(
+            self.peek_kind_n(n),
+            Some(TokenKind::KwPub | TokenKind::KwStatic)
+        ) {
+            n += 1;
+        }
+        self.peek_kind_n(n) == Some(TokenKind::KwFunc)
+    }
+
+    /// Lowers `contracts` into assertions in `f`'s body. `display` names the
+    /// function in violation messages.
+    ///
+    /// An `ensures` clause that uses `result` is reported, and dropped, when
+    /// the function can end without a value: by falling off the end of its
+    /// body or by a bare `return`.
+    pub(crate) fn apply_contracts(&mut self, f: &mut FuncDef, display: &str, mut contracts: Vec<Contract>) {
+        if contracts.iter().any(|c| c.uses_result) && (!always_returns(&f.body) || has_bare_return(&f.body)) {
+            contracts.retain(|c| {
+                let keep = c.kind == ContractKind::Requires || !c.uses_result;
+                if !keep {
+                    self.diagnostics.push(Diagnostic::error_kind(
+                        DiagnosticKind::EnsuresMayNotReturn(display.to_string()),
+                        Some(c.span),
+                    ));
+                }
+                keep
+            });
+        }
+        if contracts.is_empty() || !self.contracts_enabled {
+            return;
+        }
+        let (requires, ensures): (Vec<_>, Vec<_>) =
+            contracts.into_iter().partition(|c| c.kind == ContractKind::Requires);
+
+        let mut body: Vec<Stmt> = requires
+            .iter()
+            .map(|c| self.contract_assert(c, display))
+            .collect();
+        if ensures.is_empty() {
+            body.extend(f.body.iter().cloned());
+        } else {
+            let checks: Vec<Stmt> = ensures
+                .iter()
+                .map(|c| self.contract_assert(c, display))
+                .collect();
+            let falls_through = !always_returns(&f.body);
+            body.extend(f.body.iter().cloned().map(|s| with_postconditions(s, &checks)));
+            if falls_through {
+                body.push(Stmt::Block(postcondition_block(None, &checks, false)));
+            }
+        }
+        f.body = body.into_boxed_slice();
+    }
+
+    /// `__builtin_assert(cond, "...")` for one clause.
+    fn contract_assert(&self, c: &Contract, display: &str) -> Stmt {
+        let src = &self.input[c.span.start.0 as usize..c.span.end.0 as usize];
+        let (line, col) = self.line_col(c.span.start.0 as usize);
+        let (verb, blame) = match c.kind {
+            ContractKind::Requires => ("requires", "caller"),
+            ContractKind::Ensures => ("ensures", display),
+        };
+        let text = format!(
+            "Contract violated: {display} {verb} `{src}` at {line}:{col} (blame: {blame})"
+        );
+        let message = match &c.message {
+            None => Expr::Str(text),
+            Some(Expr::Str(m)) => Expr::Str(format!("{text}: {m}")),
+            Some(m) => Expr::Binary {
+                op: BinaryOp::Add,
+                left: Box::new(Expr::Str(format!("{text}: "))),
+                right: Box::new(m.clone()),
+            },
+        };
+        Stmt::Expr(Expr::Call(Box::new(CallExpr {
+            callee: Box::new(Expr::Ident("__builtin_assert".to_string(), Cell::new(None))),
+            args: vec![c.cond.clone(), message].into_boxed_slice(),
+            labels: Box::default(),
+        })))
+    }
+
+    /// 1-based line and column of a byte offset.
+    fn line_col(&self, offset: usize) -> (usize, usize) {
+        let before = &self.input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (line, before[line_start..].chars().count() + 1)
+    }
+}
+
+/// Rewrites every `return` reachable in `s` (nested functions excluded) to
+/// check the postconditions first.
+fn with_postconditions(s: Stmt, checks: &[Stmt]) -> Stmt {
+    let body = |b: Box<[Stmt]>| -> Box<[Stmt]> {
+        b.into_vec().into_iter().map(|s| with_postconditions(s, checks)).collect()
+    };
+    match s {
+        Stmt::Return(v) => Stmt::Block(postcondition_block(v, checks, true)),
+        Stmt::If(mut x) => {
+            x.branches = x
+                .branches
+                .into_vec()
+                .into_iter()
+                .map(|(c, b)| (c, body(b)))
+                .collect();
+            x.else_branch = x.else_branch.map(body);
+            Stmt::If(x)
+        }
+        Stmt::While(mut x) => {
+            x.body = body(x.body);
+            Stmt::While(x)
+        }
+        Stmt::ForEach(mut x) => {
+            x.body = body(x.body);
+            Stmt::ForEach(x)
+        }
+        Stmt::Match(mut x) => {
+            x.arms = x.arms.into_vec().into_iter().map(|(p, b)| (p, body(b))).collect();
+            x.else_branch = x.else_branch.map(body);
+            Stmt::Match(x)
+        }
+        Stmt::Block(b) => Stmt::Block(body(b)),
+        other => other,
+    }
+}
+
+/// `let result = value; <checks>; return result`. Without a value (a bare
+/// `return`, or `ret` false for the implicit end of a function) only the checks
+/// run before returning.
+fn postcondition_block(value: Option<Expr>, checks: &[Stmt], ret: bool) -> Box<[Stmt]> {
+    let result = || Expr::Ident(RESULT_NAME.to_string(), Cell::new(None));
+    let mut stmts = Vec::with_capacity(checks.len() + 2);
+    let Some(value) = value else {
+        stmts.extend(checks.iter().cloned());
+        if ret {
+            stmts.push(Stmt::Return(None));
+        }
+        return stmts.into_boxed_slice();
+    };
+    stmts.push(Stmt::Assign(Box::new(AssignStmt {
+        vis: Visibility::Inner,
+        target: result(),
+        op: AssignOp::Set,
+        value,
+        ty: None,
+        slot: None,
+        decl: Some(DeclKind::Let),
+    })));
+    stmts.extend(checks.iter().cloned());
+    stmts.push(Stmt::Return(Some(result())));
+    stmts.into_boxed_slice()
+}
+
+/// Whether every path through `stmts` ends in a `return`.
+fn always_returns(stmts: &[Stmt]) -> bool {
+    match stmts.last() {
+        Some(Stmt::Return(_)) => true,
+        Some(Stmt::Block(b)) => always_returns(b),
+        Some(Stmt::If(x)) => {
+            x.else_branch.as_deref().is_some_and(always_returns)
+                && x.branches.iter().all(|(_, b)| always_returns(b))
+        }
+        Some(Stmt::Match(x)) => {
+            x.else_branch.as_deref().is_some_and(always_returns)
+                && x.arms.iter().all(|(_, b)| always_returns(b))
+        }
+        _ => false,
+    }
+}
+
+/// Whether a `return` without a value is reachable in `stmts` (nested
+/// functions excluded).
+fn has_bare_return(stmts: &[Stmt]) -> bool {
+    stmts.iter().any(|s| match s {
+        Stmt::Return(v) => v.is_none(),
+        Stmt::Block(b) => has_bare_return(b),
+        Stmt::If(x) => {
+            x.branches.iter().any(|(_, b)| has_bare_return(b))
+                || x.else_branch.as_deref().is_some_and(has_bare_return)
+        }
+        Stmt::While(x) => has_bare_return(&x.body),
+        Stmt::ForEach(x) => has_bare_return(&x.body),
+        Stmt::Match(x) => {
+            x.arms.iter().any(|(_, b)| has_bare_return(b))
+                || x.else_branch.as_deref().is_some_and(has_bare_return)
+        }
+        _ => false,
+    })
+}
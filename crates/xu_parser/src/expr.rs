@@ -1,23 +1,28 @@
+use std::rc::Rc;
+
 use super::Parser;
 use crate::parser::{infix_binding_power, prefix_binding_power, BraceContent};
 
-use xu_syntax::{Diagnostic, DiagnosticKind, TokenKind, unquote};
+use xu_syntax::{Diagnostic, DiagnosticKind, Span, TokenKind, unquote};
 
 use crate::{
-    BinaryOp, CallExpr, Expr, FuncDef, IndexExpr, MatchExpr, MemberExpr, MethodCallExpr, Pattern,
+    BinaryOp, CallExpr, CompareExpr, Expr, FuncDef, IndexExpr, MatchExpr, MemberExpr, MethodCallExpr, Pattern,
     RangeExpr, Stmt, StructInitExpr, StructInitItem, UnaryOp, Visibility,
 };
 
+/// 调用的实参，以及末尾带标签实参的标签
+type Args = (Vec<Expr>, Vec<(String, Span)>);
+
 #[allow(clippy::needless_lifetimes)]
 impl<'a, 'b> Parser<'a, 'b> {
     // ==================== 辅助方法 ====================
 
     /// 创建匿名函数定义
     #[inline]
-    fn make_func_lit(&mut self, params: Vec<crate::Param>, return_ty: Option<crate::TypeRef>, body: Box<[Stmt]>) -> Expr {
+    pub(super) fn make_func_lit(&mut self, params: Vec<crate::Param>, return_ty: Option<crate::TypeRef>, body: Box<[Stmt]>) -> Expr {
         let name = format!("__anon_func_{}", self.tmp_counter);
         self.tmp_counter += 1;
-        Expr::FuncLit(Box::new(FuncDef { vis: Visibility::Inner, name, params: params.into_boxed_slice(), return_ty, body }))
+        Expr::FuncLit(Rc::new(FuncDef { vis: Visibility::Inner, name, params: params.into_boxed_slice(), return_ty, body, doc: None }))
     }
 
     /// 解析冒号后的表达式或块表达式
@@ -32,8 +37,10 @@ impl<'a, 'b> Parser<'a, 'b> {
     }
 
     pub(super) fn parse_expr(&mut self, min_bp: u8) -> Option<Expr> {
-        let lhs = self.parse_prefix()?;
-        self.parse_expr_from_prefix(lhs, min_bp)
+        self.nested(|p| {
+            let lhs = p.parse_prefix()?;
+            p.parse_expr_from_prefix(lhs, min_bp)
+        })
     }
 
     pub(super) fn parse_expr_no_struct_init(&mut self, min_bp: u8) -> Option<Expr> {
@@ -45,6 +52,7 @@ impl<'a, 'b> Parser<'a, 'b> {
         mut lhs: Expr,
         min_bp: u8,
     ) -> Option<Expr> {
+        let mut links = 0;
         loop {
             if self.at(TokenKind::Newline) || self.at(TokenKind::StmtEnd) || self.at(TokenKind::Eof)
             {
@@ -56,6 +64,7 @@ impl<'a, 'b> Parser<'a, 'b> {
                 if l_bp < min_bp {
                     break;
                 }
+                self.chain_link(&mut links)?;
                 self.bump();
                 let rhs = self.parse_expr(r_bp)?;
                 lhs = Expr::Range(Box::new(RangeExpr {
@@ -74,11 +83,19 @@ impl<'a, 'b> Parser<'a, 'b> {
                 TokenKind::Lt => BinaryOp::Lt,
                 TokenKind::Ge => BinaryOp::Ge,
                 TokenKind::Le => BinaryOp::Le,
+                TokenKind::KwIn => BinaryOp::In,
                 TokenKind::Plus => BinaryOp::Add,
                 TokenKind::Minus => BinaryOp::Sub,
                 TokenKind::Star => BinaryOp::Mul,
                 TokenKind::Slash => BinaryOp::Div,
                 TokenKind::Percent => BinaryOp::Mod,
+                TokenKind::StarStar => BinaryOp::Pow,
+                TokenKind::Amp => BinaryOp::BitAnd,
+                TokenKind::Pipe => BinaryOp::BitOr,
+                TokenKind::Caret => BinaryOp::BitXor,
+                TokenKind::Shl => BinaryOp::Shl,
+                TokenKind::Shr => BinaryOp::Shr,
+                TokenKind::QuestionQuestion => BinaryOp::Coalesce,
                 _ => break,
             };
 
@@ -86,17 +103,41 @@ impl<'a, 'b> Parser<'a, 'b> {
             if l_bp < min_bp {
                 break;
             }
+            self.chain_link(&mut links)?;
             self.bump();
-            let rhs = self.parse_expr(r_bp)?;
-            lhs = Expr::Binary {
-                op,
-                left: Box::new(lhs),
-                right: Box::new(rhs),
-            };
+            let mut rhs = self.parse_expr(r_bp)?;
+            // `x in a..b` 检查的是整个范围，而范围的优先级低于 `in`
+            if op == BinaryOp::In && (self.at(TokenKind::DotDot) || self.at(TokenKind::DotDotEq)) {
+                let inclusive = self.at(TokenKind::DotDotEq);
+                self.bump();
+                let end = self.parse_expr(r_bp)?;
+                rhs = Expr::Range(Box::new(RangeExpr { start: Box::new(rhs), end: Box::new(end), inclusive }));
+            }
+            lhs = Self::make_binary(op, lhs, rhs);
         }
         Some(lhs)
     }
 
+    /// `a < b < c` 连写时合并为一个比较链，而不是 `(a < b) < c`
+    fn make_binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Expr {
+        if !op.is_ordering() {
+            return Expr::Binary { op, left: Box::new(lhs), right: Box::new(rhs) };
+        }
+        match lhs {
+            Expr::Binary { op: prev, left, right } if prev.is_ordering() => Expr::Compare(Box::new(CompareExpr {
+                first: left,
+                links: vec![(prev, *right), (op, rhs)].into_boxed_slice(),
+            })),
+            Expr::Compare(mut c) => {
+                let mut links = std::mem::take(&mut c.links).into_vec();
+                links.push((op, rhs));
+                c.links = links.into_boxed_slice();
+                Expr::Compare(c)
+            }
+            lhs => Expr::Binary { op, left: Box::new(lhs), right: Box::new(rhs) },
+        }
+    }
+
     fn parse_prefix(&mut self) -> Option<Expr> {
         self.skip_trivia();
         match self.peek_kind() {
@@ -116,12 +157,23 @@ impl<'a, 'b> Parser<'a, 'b> {
                     expr: Box::new(expr),
                 })
             }
+            TokenKind::Tilde => {
+                self.bump();
+                let expr = self.parse_expr(prefix_binding_power())?;
+                Some(Expr::Unary {
+                    op: UnaryOp::BitNot,
+                    expr: Box::new(expr),
+                })
+            }
             _ => self.parse_postfix_expr(),
         }
     }
 
     fn parse_postfix_expr(&mut self) -> Option<Expr> {
         let mut expr = self.parse_primary()?;
+        let mut links = 0;
+        // 出现过 `?.` / `?[` 时，整条链包在 OptChain 中
+        let mut optional = false;
         loop {
             if self.at(TokenKind::Newline) || self.at(TokenKind::StmtEnd) || self.at(TokenKind::Eof)
             {
@@ -156,8 +208,12 @@ impl<'a, 'b> Parser<'a, 'b> {
                         _ => break,
                     }
                 }
-                TokenKind::Dot => {
-                    self.bump();
+                TokenKind::Dot | TokenKind::QuestionDot => {
+                    self.chain_link(&mut links)?;
+                    if self.bumped().kind == TokenKind::QuestionDot {
+                        expr = Expr::OptLink(Box::new(expr));
+                        optional = true;
+                    }
                     let field = if self.at(TokenKind::Ident) {
                         self.expect_ident()?
                     } else if self.peek_kind().is_keyword() {
@@ -181,7 +237,19 @@ impl<'a, 'b> Parser<'a, 'b> {
                         ic_slot: std::cell::Cell::new(None),
                     }));
                 }
+                TokenKind::Question if self.peek_kind_n(1) == Some(TokenKind::LBracket) => {
+                    // `?[`：下一轮按普通下标解析
+                    self.bump();
+                    expr = Expr::OptLink(Box::new(expr));
+                    optional = true;
+                }
+                TokenKind::Question => {
+                    self.chain_link(&mut links)?;
+                    self.bump();
+                    expr = Expr::Try(Box::new(expr));
+                }
                 TokenKind::LBracket => {
+                    self.chain_link(&mut links)?;
                     self.bump();
                     let start = self.parse_expr(3)?;
                     self.skip_trivia();
@@ -204,8 +272,15 @@ impl<'a, 'b> Parser<'a, 'b> {
                     }));
                 }
                 TokenKind::LParen => {
-                    let args = self.parse_args()?;
+                    self.chain_link(&mut links)?;
+                    let (args, labels) = self.parse_args()?;
                     if let Expr::EnumCtor { module, ty, variant, .. } = expr {
+                        if let Some((label, span)) = labels.first() {
+                            self.diagnostics.push(Diagnostic::error_kind(
+                                DiagnosticKind::InvalidArgumentLabel { label: label.clone(), func: format!("{ty}#{variant}") },
+                                Some(*span),
+                            ));
+                        }
                         expr = Expr::EnumCtor {
                             module,
                             ty,
@@ -218,6 +293,7 @@ impl<'a, 'b> Parser<'a, 'b> {
                             receiver: m.object,
                             method: m.field,
                             args: args.into_boxed_slice(),
+                            labels: labels.into_boxed_slice(),
                             ic_slot: std::cell::Cell::new(None),
                             receiver_ty: std::cell::Cell::new(None),
                         }));
@@ -225,18 +301,20 @@ impl<'a, 'b> Parser<'a, 'b> {
                         expr = Expr::Call(Box::new(CallExpr {
                             callee: Box::new(expr),
                             args: args.into_boxed_slice(),
+                            labels: labels.into_boxed_slice(),
                         }));
                     }
                 }
                 TokenKind::Ident if self.peek_kind_n(1) == Some(TokenKind::LParen) => {
                     let t = self.bumped();
                     let method = self.token_text(&t).to_string();
-                    let args = self.parse_args()?;
+                    let (args, labels) = self.parse_args()?;
                     // Always generate MethodCall, let runtime decide if it's static or instance
                     expr = Expr::MethodCall(Box::new(MethodCallExpr {
                         receiver: Box::new(expr),
                         method,
                         args: args.into_boxed_slice(),
+                        labels: labels.into_boxed_slice(),
                         ic_slot: std::cell::Cell::new(None),
                         receiver_ty: std::cell::Cell::new(None),
                     }));
@@ -255,6 +333,7 @@ impl<'a, 'b> Parser<'a, 'b> {
                                         std::cell::Cell::new(None),
                                     )),
                                     args: Box::new([list_expr]),
+                                    labels: Box::default(),
                                 }));
                                 continue;
                             }
@@ -292,6 +371,9 @@ impl<'a, 'b> Parser<'a, 'b> {
                 _ => break,
             }
         }
+        if optional {
+            expr = Expr::OptChain(Box::new(expr));
+        }
         Some(expr)
     }
 
@@ -490,11 +572,16 @@ impl<'a, 'b> Parser<'a, 'b> {
     }
 
     fn parse_if_expr(&mut self) -> Option<Expr> {
-        self.expect(TokenKind::KwIf)?;
+        let if_span = self.expect(TokenKind::KwIf)?.span;
         let cond = self.parse_expr_no_struct_init(0)?;
         let then_expr = self.parse_expr_after_colon()?;
         self.skip_trivia();
-        self.expect(TokenKind::KwElse)?;
+        // 作为表达式必须有值，缺少 else 时在 `if` 处报错
+        if !self.at(TokenKind::KwElse) {
+            self.diagnostics.push(Diagnostic::error_kind(DiagnosticKind::IfExprWithoutElse, Some(if_span)));
+            return None;
+        }
+        self.bump();
         self.skip_trivia();
         let else_expr = if self.at(TokenKind::KwIf) {
             self.parse_if_expr()?
@@ -679,13 +766,17 @@ impl<'a, 'b> Parser<'a, 'b> {
         Some(entries)
     }
 
-    fn parse_args(&mut self) -> Option<Vec<Expr>> {
+    /// Parses a parenthesized argument list. Trailing arguments may carry
+    /// labels, as in `inspect(v, max_items: 3)`; they are returned alongside
+    /// the arguments and resolved by the driver.
+    fn parse_args(&mut self) -> Option<Args> {
         self.expect(TokenKind::LParen)?;
         let mut args: Vec<Expr> = Vec::with_capacity(4);
+        let mut labels: Vec<(String, Span)> = Vec::new();
         self.skip_layout();
         if self.at(TokenKind::RParen) {
             self.bump();
-            return Some(args);
+            return Some((args, labels));
         }
         // 在括号内允许结构体字面量，因为括号提供了明确的边界
         self.with_struct_init(true, |p| {
@@ -694,6 +785,20 @@ impl<'a, 'b> Parser<'a, 'b> {
                 if p.at(TokenKind::RParen) {
                     break;
                 }
+                let is_label = (p.at(TokenKind::Ident) || p.peek_kind().is_keyword())
+                    && p.peek_kind_n(1) == Some(TokenKind::Colon);
+                if is_label {
+                    let span = p.cur_span();
+                    let t = p.bumped();
+                    labels.push((p.token_text(&t).to_string(), span));
+                    p.bump();
+                } else if !labels.is_empty() {
+                    // 带标签的参数之后不能再有位置参数
+                    p.diagnostics.push(Diagnostic::error_kind(
+                        DiagnosticKind::ExpectedToken("argument label".to_string()),
+                        Some(p.cur_span()),
+                    ));
+                }
                 args.push(p.parse_expr(0)?);
                 p.skip_layout();
                 if p.at(TokenKind::Comma) {
@@ -706,6 +811,6 @@ impl<'a, 'b> Parser<'a, 'b> {
         })?;
         self.skip_layout();
         self.expect(TokenKind::RParen)?;
-        Some(args)
+        Some((args, labels))
     }
 }
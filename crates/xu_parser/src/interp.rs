@@ -61,6 +61,7 @@ impl<'a, 'b> Parser<'a, 'b> {
             let normalized = normalize_source(&expr_str_with_term);
             let lex = Lexer::new(&normalized.text).lex();
             let mut p = Parser::new(&normalized.text, &lex.tokens, self.bump);
+            p.depth = self.depth;
             p.skip_trivia();
             let expr = p.parse_expr(0).unwrap_or(Expr::Tuple(Box::new([])));
             p.skip_trivia();
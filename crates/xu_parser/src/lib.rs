@@ -3,6 +3,7 @@
 #![allow(clippy::collapsible_if)]
 #![allow(clippy::collapsible_else_if)]
 
+mod contracts;
 mod expr;
 mod interp;
 pub mod mangling;
@@ -10,7 +11,7 @@ mod parser;
 mod stmt;
 mod types;
 
-pub use parser::{ParseResult, Parser};
+pub use parser::{MAX_CHAIN, MAX_NESTING, ParseResult, Parser};
 
 // Re-export all xu_ir types for internal use and public API
 // Note: This is intentional as xu_parser is the primary interface for AST types
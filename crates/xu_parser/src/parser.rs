@@ -29,6 +29,17 @@ pub enum BraceContent {
 
 
 
+/// Deepest nesting of expressions, blocks, types and patterns the parser
+/// accepts. Deeper input is reported rather than parsed, so that neither the
+/// parser nor the passes walking its tree can run out of stack, even on a
+/// 2 MiB thread in an unoptimized build.
+pub const MAX_NESTING: usize = 128;
+
+/// Longest chain of operators or postfix accesses (`a + b + c`, `a.b.c`)
+/// the parser accepts, counting the nesting the chain appears in. Each link
+/// deepens the tree by one level but costs less stack than a nested group.
+pub const MAX_CHAIN: usize = 256;
+
 /// Parse result.
 pub struct ParseResult {
     pub module: Module,
@@ -47,6 +58,16 @@ pub struct Parser<'a, 'b> {
     pub allow_comma_terminator: bool,
     pub inline_stmt_mode: bool, // 内联多语句模式：分号不被 expect_stmt_terminator 消费
     pub struct_init_allowed: bool,
+    /// Lower `@requires` / `@ensures` into assertions; when false they are
+    /// parsed and dropped.
+    pub contracts_enabled: bool,
+    pub(crate) pending_contracts: Vec<crate::contracts::Contract>,
+    /// Token index where the innermost statement being parsed starts.
+    pub(crate) stmt_start: usize,
+    /// Number of diagnostics reported before that statement started.
+    pub(crate) stmt_diags: usize,
+    /// Nesting levels currently open, bounded by [`MAX_NESTING`].
+    pub(crate) depth: usize,
     pub bump: &'b bumpalo::Bump,
 }
 
@@ -76,10 +97,21 @@ impl<'a, 'b> Parser<'a, 'b> {
             allow_comma_terminator: false,
             inline_stmt_mode: false,
             struct_init_allowed: true,
+            contracts_enabled: true,
+            pending_contracts: Vec::new(),
+            stmt_start: 0,
+            stmt_diags: 0,
+            depth: 0,
             bump,
         }
     }
 
+    /// Enables or disables contract checks (see [`Parser::contracts_enabled`]).
+    pub fn with_contracts(mut self, enabled: bool) -> Self {
+        self.contracts_enabled = enabled;
+        self
+    }
+
     /// Parse the full input and return a module plus diagnostics.
     pub fn parse(mut self) -> ParseResult {
         let mut stmts: Vec<Stmt> = Vec::with_capacity(8);
@@ -107,6 +139,10 @@ impl<'a, 'b> Parser<'a, 'b> {
     }
 
     pub fn parse_block(&mut self) -> Option<Box<[Stmt]>> {
+        self.nested(Self::parse_block_inner)
+    }
+
+    fn parse_block_inner(&mut self) -> Option<Box<[Stmt]>> {
         while self.at(TokenKind::Newline) {
             self.bump();
         }
@@ -181,8 +217,29 @@ impl<'a, 'b> Parser<'a, 'b> {
         Some(stmts.into_boxed_slice())
     }
 
+    /// Marks the current token as the start of a statement (or struct member,
+    /// method or match arm) for [`Parser::recover_stmt`].
+    pub(crate) fn begin_stmt(&mut self) {
+        self.stmt_start = self.i;
+        self.stmt_diags = self.diagnostics.len();
+    }
+
+    /// Skips the rest of a statement that failed to parse and returns a
+    /// `Stmt::Error` placeholder for it.
+    ///
+    /// Only the first error of the statement is kept; the rest are usually
+    /// follow-on errors of the same mistake. If the statement ran on into a
+    /// following line (an unclosed `(` does that), parsing resumes at the
+    /// start of that line, otherwise after the end of the current line.
     pub fn recover_stmt(&mut self) -> Stmt {
-        let start_span = self.cur_span();
+        self.diagnostics.truncate(self.stmt_diags + 1);
+        let start = self.stmt_start.min(self.i);
+        let start_span = self.tokens.get(start).map(|t| t.span).unwrap_or_else(|| self.cur_span());
+        if let Some(resume) = self.resync_point(start) {
+            self.i = resume;
+            self.stmt_diags = self.diagnostics.len();
+            return Stmt::Error(Span::new(start_span.start.0, self.tokens[resume - 1].span.start.0));
+        }
         let mut brace_depth = 0;
         while !self.at(TokenKind::Eof) {
             if self.at(TokenKind::LBrace) {
@@ -208,9 +265,46 @@ impl<'a, 'b> Parser<'a, 'b> {
         if self.at(TokenKind::StmtEnd) || self.at(TokenKind::Newline) {
             self.bump();
         }
+        self.stmt_diags = self.diagnostics.len();
         Stmt::Error(Span::new(start_span.start.0, self.cur_span().end.0))
     }
 
+    /// The first line start after `start` and up to the current token that
+    /// is outside any brace the statement opened. Inside an unclosed `(` or
+    /// after a trailing operator there is no newline token, so a `let`,
+    /// `var`, `return` or named `func` that begins a source line counts as a
+    /// line start too.
+    fn resync_point(&self, start: usize) -> Option<usize> {
+        let mut depth = 0usize;
+        for idx in start + 1..=self.i.min(self.tokens.len() - 1) {
+            match self.tokens[idx - 1].kind {
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace if depth == 0 => return None,
+                TokenKind::RBrace => depth -= 1,
+                _ => {}
+            }
+            if depth > 0 {
+                continue;
+            }
+            let next = self.tokens[idx].kind;
+            let line_start = match self.tokens[idx - 1].kind {
+                TokenKind::Newline => !matches!(next, TokenKind::Newline | TokenKind::KwElse | TokenKind::RBrace),
+                _ => {
+                    let func_def = next == TokenKind::KwFunc
+                        && self.tokens.get(idx + 1).is_some_and(|t| t.kind == TokenKind::Ident);
+                    let decl = matches!(next, TokenKind::KwLet | TokenKind::KwVar | TokenKind::KwConst);
+                    (func_def || decl || next == TokenKind::KwReturn)
+                        && self.input[self.tokens[idx - 1].span.end.0 as usize..self.tokens[idx].span.start.0 as usize]
+                            .contains('\n')
+                }
+            };
+            if line_start {
+                return Some(idx);
+            }
+        }
+        None
+    }
+
     pub fn expect_ident(&mut self) -> Option<String> {
         self.skip_trivia();
         // Check if current token is a keyword that cannot be used as identifier
@@ -392,6 +486,35 @@ impl<'a, 'b> Parser<'a, 'b> {
 
     /// Execute a closure with struct_init_allowed temporarily set to a specific value.
     #[inline]
+    /// Runs `f` one nesting level deeper, or reports
+    /// [`DiagnosticKind::NestingTooDeep`] past [`MAX_NESTING`] levels.
+    pub(crate) fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        self.check_depth(self.depth, MAX_NESTING)?;
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Counts one more link of an operator or postfix chain, or reports
+    /// [`DiagnosticKind::NestingTooDeep`] past [`MAX_CHAIN`] levels.
+    pub(crate) fn chain_link(&mut self, links: &mut usize) -> Option<()> {
+        self.check_depth(self.depth + *links, MAX_CHAIN)?;
+        *links += 1;
+        Some(())
+    }
+
+    fn check_depth(&mut self, depth: usize, limit: usize) -> Option<()> {
+        if depth >= limit {
+            self.diagnostics.push(Diagnostic::error_kind(
+                DiagnosticKind::NestingTooDeep,
+                Some(self.cur_span()),
+            ));
+            return None;
+        }
+        Some(())
+    }
+
     pub fn with_struct_init<T>(&mut self, allowed: bool, f: impl FnOnce(&mut Self) -> T) -> T {
         let old = self.struct_init_allowed;
         self.struct_init_allowed = allowed;
@@ -411,6 +534,28 @@ impl<'a, 'b> Parser<'a, 'b> {
         &self.input[t.span.start.0 as usize..t.span.end.0 as usize]
     }
 
+    /// Doc comment of a definition on the line containing byte `pos`: the
+    /// `//` lines directly above it, minus the markers. Contract annotations
+    /// between the comment and the definition are skipped.
+    pub fn doc_comment_before(&self, pos: usize) -> Option<String> {
+        let line_start = self.input[..pos].rfind('\n').map_or(0, |i| i + 1);
+        let mut lines = Vec::new();
+        for line in self.input[..line_start].lines().rev() {
+            let t = line.trim();
+            if let Some(text) = t.strip_prefix("//") {
+                let text = text.strip_prefix('/').unwrap_or(text);
+                lines.push(text.strip_prefix(' ').unwrap_or(text).trim_end());
+            } else if !(lines.is_empty() && t.starts_with('@')) {
+                break;
+            }
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+
     pub fn parse_params(&mut self) -> Option<Vec<crate::Param>> {
         let mut params: Vec<crate::Param> = Vec::new();
         if self.at(TokenKind::RParen) {
@@ -448,6 +593,10 @@ impl<'a, 'b> Parser<'a, 'b> {
     }
 
     pub fn parse_pattern(&mut self) -> Option<Pattern> {
+        self.nested(Self::parse_pattern_inner)
+    }
+
+    fn parse_pattern_inner(&mut self) -> Option<Pattern> {
         self.skip_trivia();
         match self.peek_kind() {
             TokenKind::LParen => {
@@ -482,6 +631,19 @@ impl<'a, 'b> Parser<'a, 'b> {
                 if name == "_" {
                     return Some(Pattern::Wildcard);
                 }
+                // `ok(v)` / `err(e)` match what the `ok` and `err` builtins build
+                if matches!(name.as_str(), "ok" | "err") && self.at(TokenKind::LParen) {
+                    self.bump();
+                    self.skip_trivia();
+                    let inner = self.parse_pattern()?;
+                    self.skip_trivia();
+                    self.expect(TokenKind::RParen)?;
+                    return Some(Pattern::EnumVariant {
+                        ty: "Result".to_string(),
+                        variant: name,
+                        args: Box::new([inner]),
+                    });
+                }
                 if self.at(TokenKind::Hash) {
                     self.bump();
                     let variant = self.expect_ident()?;
@@ -558,14 +720,24 @@ pub fn infix_binding_power(op: crate::BinaryOp) -> (u8, u8) {
         crate::BinaryOp::Or => (1, 2),
         crate::BinaryOp::And => (3, 4),
         crate::BinaryOp::Eq | crate::BinaryOp::Ne => (5, 6),
-        crate::BinaryOp::Gt | crate::BinaryOp::Lt | crate::BinaryOp::Ge | crate::BinaryOp::Le => (7, 8),
-        crate::BinaryOp::Add | crate::BinaryOp::Sub => (9, 10),
-        crate::BinaryOp::Mul | crate::BinaryOp::Div | crate::BinaryOp::Mod => (11, 12),
+        crate::BinaryOp::Gt | crate::BinaryOp::Lt | crate::BinaryOp::Ge | crate::BinaryOp::Le | crate::BinaryOp::In => {
+            (7, 8)
+        }
+        // 右结合：`a ?? b ?? c` 为 `a ?? (b ?? c)`
+        crate::BinaryOp::Coalesce => (10, 9),
+        crate::BinaryOp::BitOr => (11, 12),
+        crate::BinaryOp::BitXor => (13, 14),
+        crate::BinaryOp::BitAnd => (15, 16),
+        crate::BinaryOp::Shl | crate::BinaryOp::Shr => (17, 18),
+        crate::BinaryOp::Add | crate::BinaryOp::Sub => (19, 20),
+        crate::BinaryOp::Mul | crate::BinaryOp::Div | crate::BinaryOp::Mod => (21, 22),
+        // 右结合且高于一元运算：`-2 ** 2` 为 `-(2 ** 2)`，`2 ** 3 ** 2` 为 `2 ** (3 ** 2)`
+        crate::BinaryOp::Pow => (25, 24),
     }
 }
 
 pub fn prefix_binding_power() -> u8 {
-    13
+    23
 }
 
 pub fn fast_interpolation_expr(key: &str) -> Option<Expr> {
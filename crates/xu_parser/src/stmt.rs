@@ -1,14 +1,23 @@
 use std::cell::Cell;
+use std::rc::Rc;
 use crate::mangling::{method_name, static_name, METHOD_PREFIX};
 use crate::parser::BraceContent;
 use crate::{
-    AssignOp, AssignStmt, DeclKind, DoesBlock, EnumDef, Expr, ForEachStmt, FuncDef, IfStmt,
+    AssignOp, AssignStmt, CallExpr, DeclKind, DoesBlock, EnumDef, Expr, ForEachStmt, FuncDef, IfStmt,
     MemberExpr, MatchStmt, Param, Pattern, StaticField, Stmt, StructDef, StructField, TypeRef, UseStmt, Visibility,
     WhileStmt,
 };
 use xu_syntax::{Diagnostic, DiagnosticKind, TokenKind, unquote};
 use super::Parser;
 
+/// The members of a struct body collected so far.
+#[derive(Default)]
+struct StructMembers {
+    fields: Vec<StructField>,
+    static_fields: Vec<StaticField>,
+    methods: Vec<Rc<FuncDef>>,
+}
+
 #[allow(clippy::needless_lifetimes)]
 impl<'a, 'b> Parser<'a, 'b> {
     // ==================== 辅助方法 ====================
@@ -71,6 +80,8 @@ impl<'a, 'b> Parser<'a, 'b> {
     /// Parse a single statement.
     pub(super) fn parse_stmt(&mut self) -> Option<Stmt> {
         self.skip_trivia();
+        self.begin_stmt();
+        self.parse_contract_annotations();
         let mut vis = Visibility::Inner;
         if self.at(TokenKind::KwPub) {
             self.bump();
@@ -78,7 +89,7 @@ impl<'a, 'b> Parser<'a, 'b> {
             self.skip_trivia();
         }
         match self.peek_kind() {
-            TokenKind::KwFunc => self.parse_func_def(vis).map(|x| Stmt::FuncDef(Box::new(x))),
+            TokenKind::KwFunc => self.parse_func_def(vis).map(|x| Stmt::FuncDef(Rc::new(x))),
             TokenKind::KwIf => self.parse_if().map(|x| Stmt::If(Box::new(x))),
             TokenKind::KwWhile => self.parse_while().map(|x| Stmt::While(Box::new(x))),
             TokenKind::KwFor => self.parse_foreach().map(|x| Stmt::ForEach(Box::new(x))),
@@ -90,7 +101,7 @@ impl<'a, 'b> Parser<'a, 'b> {
                 self.parse_simple_kw_stmt(TokenKind::KwContinue, Stmt::Continue)
             }
             TokenKind::KwUse => self.parse_use_stmt(),
-            TokenKind::KwLet | TokenKind::KwVar => self.parse_let_var_decl(vis),
+            TokenKind::KwLet | TokenKind::KwVar | TokenKind::KwConst => self.parse_let_var_decl(vis),
             TokenKind::LBrace => {
                 // Check if this is a block statement or a dict literal
                 if self.is_block_stmt() {
@@ -100,17 +111,22 @@ impl<'a, 'b> Parser<'a, 'b> {
                 }
             }
             TokenKind::Ident => {
-                if self.is_does_block_start() {
+                if self.is_bench_start() {
+                    self.parse_bench()
+                } else if self.is_does_block_start() {
                     self.parse_does_block(vis)
                         .map(|x| Stmt::DoesBlock(Box::new(x)))
                 } else if self.is_type_def_start() {
-                    if self.peek_kind_n(2) == Some(TokenKind::LBrace)
-                        && self.braced_type_def_is_struct()
+                    // `has` always introduces a struct, even one whose
+                    // fields are malformed.
+                    if self.peek_kind_n(1) == Some(TokenKind::KwHas)
+                        || (self.peek_kind_n(2) == Some(TokenKind::LBrace)
+                            && self.braced_type_def_is_struct())
                     {
                         self.parse_struct_def(vis)
-                            .map(|x| Stmt::StructDef(Box::new(x)))
+                            .map(|x| Stmt::StructDef(Rc::new(x)))
                     } else {
-                        self.parse_enum_def(vis).map(|x| Stmt::EnumDef(Box::new(x)))
+                        self.parse_enum_def(vis).map(|x| Stmt::EnumDef(Rc::new(x)))
                     }
                 } else {
                     self.parse_assign_or_expr_stmt()
@@ -137,16 +153,18 @@ impl<'a, 'b> Parser<'a, 'b> {
         Some(Stmt::Block(stmts))
     }
 
-    /// Parse a `let` / `var` declaration.
+    /// Parse a `let` / `var` / `const` declaration.
     fn parse_let_var_decl(&mut self, vis: Visibility) -> Option<Stmt> {
-        let kw_kind = self.peek_kind();
-        if kw_kind != TokenKind::KwLet && kw_kind != TokenKind::KwVar {
-            return None;
-        }
+        let decl = match self.peek_kind() {
+            TokenKind::KwLet => Some(DeclKind::Let),
+            TokenKind::KwVar => Some(DeclKind::Var),
+            TokenKind::KwConst => Some(DeclKind::Const),
+            _ => return None,
+        };
         self.bump();
         self.skip_trivia();
-        // variable name or tuple destructure
-        let tuple_names = if self.at(TokenKind::LParen) {
+        // variable name or tuple destructure; a constant names a single value
+        let tuple_names = if self.at(TokenKind::LParen) && decl != Some(DeclKind::Const) {
             self.bump();
             let mut names: Vec<String> = Vec::with_capacity(4);
             self.skip_layout();
@@ -196,11 +214,6 @@ impl<'a, 'b> Parser<'a, 'b> {
         // initializer expression
         let value = self.parse_expr(0)?;
         self.expect_stmt_terminator()?;
-        let decl = if kw_kind == TokenKind::KwLet {
-            Some(DeclKind::Let)
-        } else {
-            Some(DeclKind::Var)
-        };
         if let Some(names) = tuple_names {
             let tmp = format!("__tmp_destructure_{}", self.tmp_counter);
             self.tmp_counter += 1;
@@ -281,75 +294,19 @@ impl<'a, 'b> Parser<'a, 'b> {
         }
         self.expect(TokenKind::LBrace)?;
 
-        let mut fields: Vec<StructField> = Vec::with_capacity(4);
-        let mut static_fields: Vec<StaticField> = Vec::with_capacity(2);
-        let mut methods: Vec<FuncDef> = Vec::with_capacity(4);
+        let mut members = StructMembers::default();
         while !self.at(TokenKind::RBrace) && !self.at(TokenKind::Eof) {
             self.skip_layout();
             if self.at(TokenKind::RBrace) {
                 break;
             }
-
-            let mut item_vis = Visibility::Inner;
-            if self.at(TokenKind::KwPub) {
-                self.bump();
-                item_vis = Visibility::Public;
-                self.skip_trivia();
-            }
-
-            let is_static = self.at(TokenKind::KwStatic);
-            if is_static {
-                self.bump();
-                self.skip_trivia();
-            }
-
-            if self.at(TokenKind::KwFunc) {
-                let mut f = self.parse_func_def(item_vis)?;
-                self.process_method(&mut f, &name, is_static);
-                methods.push(f);
-                continue;
-            }
-
-            let field_name = self.expect_field_name()?;
-            self.expect(TokenKind::Colon)?;
-            let field_ty = self.parse_type_ref()?;
-            self.skip_layout();
-
-            if is_static {
-                // Static fields require a default value
-                if !self.at(TokenKind::Eq) {
-                    self.diagnostics.push(Diagnostic::error_kind(
-                        DiagnosticKind::Raw("Static field requires a default value".to_string()),
-                        Some(self.cur_span()),
-                    ));
-                    return None;
-                }
-                self.bump();
-                let default = self.parse_expr(0)?;
-                static_fields.push(StaticField {
-                    name: field_name,
-                    ty: field_ty,
-                    default,
-                });
-            } else {
-                // Instance fields have optional default
-                let default = if self.at(TokenKind::Eq) {
-                    self.bump();
-                    Some(self.parse_expr(0)?)
-                } else {
-                    None
-                };
-                fields.push(StructField {
-                    name: field_name,
-                    ty: field_ty,
-                    default,
-                });
-            }
-            self.skip_layout();
-            if self.at(TokenKind::Comma) {
-                self.bump();
+            // A malformed member is skipped so the following ones are still checked.
+            self.begin_stmt();
+            if self.parse_struct_member(&name, &mut members).is_none() {
+                self.recover_stmt();
             }
         }
+        let StructMembers { fields, static_fields, methods } = members;
 
         self.expect(TokenKind::RBrace)?;
         self.expect_stmt_terminator()?;
@@ -362,6 +319,71 @@ impl<'a, 'b> Parser<'a, 'b> {
         })
     }
 
+    /// Parses one field, static field or method of a struct body.
+    fn parse_struct_member(&mut self, name: &str, members: &mut StructMembers) -> Option<()> {
+        self.parse_contract_annotations();
+        let mut item_vis = Visibility::Inner;
+        if self.at(TokenKind::KwPub) {
+            self.bump();
+            item_vis = Visibility::Public;
+            self.skip_trivia();
+        }
+
+        let is_static = self.at(TokenKind::KwStatic);
+        if is_static {
+            self.bump();
+            self.skip_trivia();
+        }
+
+        if self.at(TokenKind::KwFunc) {
+            let mut f = self.parse_func_def(item_vis)?;
+            self.process_method(&mut f, name, is_static);
+            members.methods.push(Rc::new(f));
+            return Some(());
+        }
+
+        let field_name = self.expect_field_name()?;
+        self.expect(TokenKind::Colon)?;
+        let field_ty = self.parse_type_ref()?;
+        self.skip_layout();
+
+        if is_static {
+            // Static fields require a default value
+            if !self.at(TokenKind::Eq) {
+                self.diagnostics.push(Diagnostic::error_kind(
+                    DiagnosticKind::StaticFieldWithoutDefault,
+                    Some(self.cur_span()),
+                ));
+                return None;
+            }
+            self.bump();
+            let default = self.parse_expr(0)?;
+            members.static_fields.push(StaticField {
+                name: field_name,
+                ty: field_ty,
+                default,
+            });
+        } else {
+            // Instance fields have optional default
+            let default = if self.at(TokenKind::Eq) {
+                self.bump();
+                Some(self.parse_expr(0)?)
+            } else {
+                None
+            };
+            members.fields.push(StructField {
+                name: field_name,
+                ty: field_ty,
+                default,
+            });
+        }
+        self.skip_layout();
+        if self.at(TokenKind::Comma) {
+            self.bump();
+        }
+        Some(())
+    }
+
     fn parse_enum_def(&mut self, vis: Visibility) -> Option<EnumDef> {
         let name = self.expect_ident()?;
         self.expect(TokenKind::KwWith)?;
@@ -408,7 +430,10 @@ impl<'a, 'b> Parser<'a, 'b> {
     }
 
     fn parse_func_def(&mut self, vis: Visibility) -> Option<FuncDef> {
+        let doc = self.doc_comment_before(self.cur_span().start.0 as usize);
         self.expect(TokenKind::KwFunc)?;
+        // 先取走注解，避免函数体内的嵌套函数误用
+        let contracts = std::mem::take(&mut self.pending_contracts);
         let name = if self.at(TokenKind::LParen) {
             self.bump();
             let receiver_name = if self.at(TokenKind::KwSelf) {
@@ -443,13 +468,16 @@ impl<'a, 'b> Parser<'a, 'b> {
                 None
             };
             let body = self.parse_block()?;
-            return Some(FuncDef {
+            let mut f = FuncDef {
                 vis,
                 name: internal,
                 params: params.into_boxed_slice(),
                 return_ty,
                 body,
-            });
+                doc,
+            };
+            self.apply_contracts(&mut f, &method, contracts);
+            return Some(f);
         } else {
             self.expect_ident()?
         };
@@ -466,13 +494,17 @@ impl<'a, 'b> Parser<'a, 'b> {
             None
         };
         let body = self.parse_block()?;
-        Some(FuncDef {
+        let mut f = FuncDef {
             vis,
             name,
             params: params.into_boxed_slice(),
             return_ty,
             body,
-        })
+            doc,
+        };
+        let display = f.name.clone();
+        self.apply_contracts(&mut f, &display, contracts);
+        Some(f)
     }
 
     fn parse_does_block(&mut self, vis: Visibility) -> Option<DoesBlock> {
@@ -493,19 +525,21 @@ impl<'a, 'b> Parser<'a, 'b> {
                 | "dict"
         ) {
             self.diagnostics.push(Diagnostic::error_kind(
-                DiagnosticKind::Raw(format!("cannot extend builtin type: {}", target)),
+                DiagnosticKind::CannotExtendBuiltin(target.to_string()),
                 Some(t.span),
             ));
         }
         self.expect(TokenKind::KwDoes)?;
         self.skip_trivia();
         self.expect(TokenKind::LBrace)?;
-        let mut funcs: Vec<FuncDef> = Vec::with_capacity(4);
+        let mut funcs: Vec<Rc<FuncDef>> = Vec::with_capacity(4);
         while !self.at(TokenKind::RBrace) && !self.at(TokenKind::Eof) {
             self.skip_trivia();
             if self.at(TokenKind::RBrace) {
                 break;
             }
+            self.begin_stmt();
+            self.parse_contract_annotations();
             let mut fvis = Visibility::Inner;
             if self.at(TokenKind::KwPub) {
                 self.bump();
@@ -519,9 +553,15 @@ impl<'a, 'b> Parser<'a, 'b> {
                 self.skip_trivia();
             }
 
-            let mut f = self.parse_func_def(fvis)?;
-            self.process_method(&mut f, &target, is_static);
-            funcs.push(f);
+            match self.parse_func_def(fvis) {
+                Some(mut f) => {
+                    self.process_method(&mut f, &target, is_static);
+                    funcs.push(Rc::new(f));
+                }
+                None => {
+                    self.recover_stmt();
+                }
+            }
         }
         self.expect(TokenKind::RBrace)?;
         self.expect_stmt_terminator()?;
@@ -538,12 +578,19 @@ impl<'a, 'b> Parser<'a, 'b> {
         self.skip_trivia();
         self.expect(TokenKind::LBrace)?;
         let mut arms: Vec<(Pattern, Box<[Stmt]>)> = Vec::with_capacity(4);
+        let mut arm_failed = false;
         while !self.at(TokenKind::RBrace) && !self.at(TokenKind::Eof) {
             self.skip_trivia();
             if self.at(TokenKind::RBrace) { break; }
-            let pat = self.parse_pattern()?;
-            let body = self.parse_body_after_colon(true)?;
-            arms.push((pat, body));
+            self.begin_stmt();
+            let arm = self.parse_pattern().and_then(|pat| Some((pat, self.parse_body_after_colon(true)?)));
+            match arm {
+                Some(arm) => arms.push(arm),
+                None => {
+                    arm_failed = true;
+                    self.recover_stmt();
+                }
+            }
             self.skip_trivia();
             if self.at(TokenKind::Comma) { self.bump(); }
         }
@@ -551,6 +598,9 @@ impl<'a, 'b> Parser<'a, 'b> {
             let body = body.clone();
             arms.pop();
             Some(body)
+        } else if arm_failed {
+            // The default arm may be the one that failed to parse.
+            None
         } else {
             self.diagnostics.push(Diagnostic::error_kind(
                 DiagnosticKind::MissingMatchDefault,
@@ -572,9 +622,7 @@ impl<'a, 'b> Parser<'a, 'b> {
             let next = self.peek_kind_n(1);
             if next == Some(TokenKind::LBrace) || next == Some(TokenKind::Newline) {
                 self.diagnostics.push(Diagnostic::error_kind(
-                    DiagnosticKind::Raw(
-                        "Use 'match' for pattern matching. 'when' is for optional binding: when x = expr { ... } else { ... }".to_string()
-                    ),
+                    DiagnosticKind::WhenIsNotMatch,
                     Some(self.cur_span()),
                 ));
                 return None;
@@ -587,9 +635,7 @@ impl<'a, 'b> Parser<'a, 'b> {
             self.skip_layout();
             if !self.at(TokenKind::Eq) {
                 self.diagnostics.push(Diagnostic::error_kind(
-                    DiagnosticKind::Raw(
-                        "Expected '=' after identifier in 'when' binding. Use 'match' for pattern matching.".to_string()
-                    ),
+                    DiagnosticKind::WhenBindingExpectsEq,
                     Some(self.cur_span()),
                 ));
                 return None;
@@ -625,6 +671,29 @@ impl<'a, 'b> Parser<'a, 'b> {
         outer_stmt
     }
 
+    /// `bench "name": ...` or `bench "name" { ... }`; `bench` is not a
+    /// keyword, so it stays usable as a name elsewhere.
+    fn is_bench_start(&self) -> bool {
+        self.tokens.get(self.i).is_some_and(|t| self.token_text(t) == "bench")
+            && self.peek_kind_n(1) == Some(TokenKind::Str)
+            && matches!(self.peek_kind_n(2), Some(TokenKind::Colon | TokenKind::LBrace))
+    }
+
+    /// 基准块脱糖为 `__bench("name", || { ... })`，由运行时在 bench 模式下反复调用
+    fn parse_bench(&mut self) -> Option<Stmt> {
+        self.bump();
+        let t = self.bumped();
+        let raw = self.token_text(&t).to_string();
+        let name = self.parse_interpolated_string(raw.as_str())?;
+        let body = self.parse_body_after_colon(false)?;
+        let func = self.make_func_lit(Vec::new(), None, body);
+        Some(Stmt::Expr(Expr::Call(Box::new(CallExpr {
+            callee: Box::new(Expr::Ident("__bench".to_string(), Cell::new(None))),
+            args: vec![name, func].into_boxed_slice(),
+            labels: Box::default(),
+        }))))
+    }
+
     fn is_does_block_start(&self) -> bool {
         self.peek_kind() == TokenKind::Ident && self.peek_kind_n(1) == Some(TokenKind::KwDoes)
     }
@@ -734,6 +803,13 @@ impl<'a, 'b> Parser<'a, 'b> {
             TokenKind::MinusEq => Some(AssignOp::Sub),
             TokenKind::StarEq => Some(AssignOp::Mul),
             TokenKind::SlashEq => Some(AssignOp::Div),
+            TokenKind::PercentEq => Some(AssignOp::Mod),
+            TokenKind::StarStarEq => Some(AssignOp::Pow),
+            TokenKind::AmpEq => Some(AssignOp::BitAnd),
+            TokenKind::PipeEq => Some(AssignOp::BitOr),
+            TokenKind::CaretEq => Some(AssignOp::BitXor),
+            TokenKind::ShlEq => Some(AssignOp::Shl),
+            TokenKind::ShrEq => Some(AssignOp::Shr),
             _ => None,
         } {
             self.bump();
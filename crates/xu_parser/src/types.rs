@@ -5,6 +5,10 @@ use xu_syntax::{Diagnostic, DiagnosticKind, TokenKind};
 #[allow(clippy::needless_lifetimes)]
 impl<'a, 'b> Parser<'a, 'b> {
     pub(super) fn parse_type_ref(&mut self) -> Option<TypeRef> {
+        self.nested(Self::parse_type_ref_inner)
+    }
+
+    fn parse_type_ref_inner(&mut self) -> Option<TypeRef> {
         if self.at(TokenKind::LBracket) {
             self.bump();
             self.skip_layout();
@@ -0,0 +1,46 @@
+use xu_lexer::{Lexer, normalize_source};
+use xu_parser::{Parser, Stmt};
+
+fn func_docs(src: &str) -> Vec<(String, Option<String>)> {
+    let normalized = normalize_source(src);
+    let lex = Lexer::new(&normalized.text).lex();
+    assert!(lex.diagnostics.is_empty(), "{:?}", lex.diagnostics);
+    let bump = bumpalo::Bump::new();
+    let parse = Parser::new(&normalized.text, &lex.tokens, &bump).parse();
+    let errors: Vec<_> = parse
+        .diagnostics
+        .iter()
+        .filter(|d| matches!(d.severity, xu_syntax::Severity::Error))
+        .collect();
+    assert!(errors.is_empty(), "{errors:?}");
+    parse
+        .module
+        .stmts
+        .iter()
+        .filter_map(|s| match s {
+            Stmt::FuncDef(f) => Some((f.name.clone(), f.doc.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn doc_comment_attaches_to_following_func() {
+    let src = "// File header.\n\n// Adds one.\n///   Keeps inner indent.\npub func inc(x: int) -> int {\n    return x + 1\n}\n\nfunc bare() {\n}\n";
+    assert_eq!(
+        func_docs(src),
+        vec![
+            ("inc".to_string(), Some("Adds one.\n  Keeps inner indent.".to_string())),
+            ("bare".to_string(), None),
+        ]
+    );
+}
+
+#[test]
+fn doc_comment_skips_contract_annotations() {
+    let src = "// Positive only.\n@requires(x > 0)\nfunc f(x: int) -> int {\n    return x\n}\n";
+    assert_eq!(
+        func_docs(src),
+        vec![("f".to_string(), Some("Positive only.".to_string()))]
+    );
+}
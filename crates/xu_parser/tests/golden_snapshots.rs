@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use xu_lexer::{Lexer, normalize_source};
 use xu_parser::Parser;
 use xu_syntax::{Severity, SourceFile, SourceId, TokenKind};
+use xu_testkit::Golden;
 
 fn input_path(name: &str) -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -70,33 +71,8 @@ fn format_diagnostics(source: &SourceFile, mut diagnostics: Vec<xu_syntax::Diagn
     s
 }
 
-fn golden_path(kind: &str, name: &str) -> PathBuf {
-    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("tests")
-        .join("golden")
-        .join(kind)
-        .join(format!("{name}.txt"))
-}
-
-fn golden_update_enabled() -> bool {
-    let v = std::env::var("XU_UPDATE_GOLDEN").ok();
-    if v.as_deref().is_some_and(|v| v == "1" || v == "true") {
-        return true;
-    }
-    std::env::var("HAOSCRIPT_UPDATE_GOLDEN").is_ok_and(|v| v == "1" || v == "true")
-}
-
-fn assert_or_update(path: PathBuf, actual: &str) {
-    let update = golden_update_enabled();
-    if update {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).unwrap();
-        }
-        fs::write(&path, actual).unwrap();
-        return;
-    }
-    let expected = fs::read_to_string(&path).unwrap();
-    assert_eq!(actual.trim_end(), expected.trim_end());
+fn golden(kind: &str) -> Golden {
+    Golden::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(kind))
 }
 
 fn normalize_ast_snapshot(s: String) -> String {
@@ -136,7 +112,7 @@ fn golden_ast_01_basics() {
         .collect();
     assert!(errors.is_empty());
     let actual = normalize_ast_snapshot(format!("{:#?}", parse.module));
-    assert_or_update(golden_path("ast", "01_basics"), &actual);
+    golden("ast").assert("01_basics", &actual);
 }
 
 #[test]
@@ -154,7 +130,7 @@ fn golden_ast_02_control_flow() {
         .collect();
     assert!(errors.is_empty());
     let actual = normalize_ast_snapshot(format!("{:#?}", parse.module));
-    assert_or_update(golden_path("ast", "02_control_flow"), &actual);
+    golden("ast").assert("02_control_flow", &actual);
 }
 
 #[test]
@@ -163,7 +139,7 @@ fn golden_tokens_01_basics() {
     let (source, _) = load_source(&path);
     let lex = Lexer::new(source.text.as_str()).lex();
     let actual = format_tokens(&source, &lex.tokens);
-    assert_or_update(golden_path("tokens", "01_basics"), &actual);
+    golden("tokens").assert("01_basics", &actual);
 }
 
 #[test]
@@ -172,7 +148,7 @@ fn golden_tokens_02_control_flow() {
     let (source, _) = load_source(&path);
     let lex = Lexer::new(source.text.as_str()).lex();
     let actual = format_tokens(&source, &lex.tokens);
-    assert_or_update(golden_path("tokens", "02_control_flow"), &actual);
+    golden("tokens").assert("02_control_flow", &actual);
 }
 
 #[test]
@@ -186,5 +162,5 @@ fn golden_diagnostics_08_error_mixed_punct() {
     diags.extend(lex.diagnostics);
     diags.extend(parse.diagnostics);
     let actual = format_diagnostics(&source, diags);
-    assert_or_update(golden_path("diagnostics", "08_error_mixed_punct"), &actual);
+    golden("diagnostics").assert("08_error_mixed_punct", &actual);
 }
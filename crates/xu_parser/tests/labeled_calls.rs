@@ -0,0 +1,86 @@
+use xu_lexer::{Lexer, normalize_source};
+use xu_parser::{Expr, Parser, Stmt};
+
+fn parse_assign(src: &str) -> (Vec<String>, Option<Expr>) {
+    let normalized = normalize_source(src);
+    assert!(
+        normalized.diagnostics.is_empty(),
+        "{:?}",
+        normalized.diagnostics
+    );
+    let lex = Lexer::new(&normalized.text).lex();
+    assert!(lex.diagnostics.is_empty(), "{:?}", lex.diagnostics);
+    let bump = bumpalo::Bump::new();
+    let parse = Parser::new(&normalized.text, &lex.tokens, &bump).parse();
+    let errors = parse
+        .diagnostics
+        .iter()
+        .filter(|d| matches!(d.severity, xu_syntax::Severity::Error))
+        .map(|d| d.message.clone())
+        .collect();
+    let value = parse.module.stmts.iter().find_map(|s| match s {
+        Stmt::Assign(s) => Some(s.value.clone()),
+        _ => None,
+    });
+    (errors, value)
+}
+
+fn labels(labels: &[(String, xu_syntax::Span)]) -> Vec<&str> {
+    labels.iter().map(|(l, _)| l.as_str()).collect()
+}
+
+#[test]
+fn labeled_arguments_are_kept_in_call_order() {
+    let (errors, value) = parse_assign("x = inspect(v, max_items: 3, pretty: true);\n");
+    assert!(errors.is_empty(), "{errors:?}");
+    match value {
+        Some(Expr::Call(c)) => {
+            assert!(matches!(c.callee.as_ref(), Expr::Ident(name, _) if name == "inspect"));
+            assert!(matches!(
+                c.args.as_ref(),
+                [Expr::Ident(_, _), Expr::Int(3), Expr::Bool(true)]
+            ));
+            assert_eq!(labels(&c.labels), ["max_items", "pretty"]);
+        }
+        other => panic!("unexpected assign value: {other:?}"),
+    }
+}
+
+#[test]
+fn any_call_takes_labels() {
+    let (errors, value) = parse_assign("x = greet(\"a\", default: 1);\n");
+    assert!(errors.is_empty(), "{errors:?}");
+    match value {
+        Some(Expr::Call(c)) => assert_eq!(labels(&c.labels), ["default"]),
+        other => panic!("unexpected assign value: {other:?}"),
+    }
+    let (errors, value) = parse_assign("x = csv.parse(t, headers: false);\n");
+    assert!(errors.is_empty(), "{errors:?}");
+    match value {
+        Some(Expr::MethodCall(m)) => {
+            assert!(matches!(m.receiver.as_ref(), Expr::Ident(name, _) if name == "csv"));
+            assert_eq!(m.method, "parse");
+            assert!(matches!(m.args.as_ref(), [Expr::Ident(_, _), Expr::Bool(false)]));
+            assert_eq!(labels(&m.labels), ["headers"]);
+        }
+        other => panic!("unexpected assign value: {other:?}"),
+    }
+}
+
+#[test]
+fn positional_arguments_cannot_follow_labeled_ones() {
+    let (errors, _) = parse_assign("x = f(a: 1, 2);\n");
+    assert!(
+        errors.iter().any(|e| e.contains("argument label")),
+        "{errors:?}"
+    );
+}
+
+#[test]
+fn enum_constructors_take_no_labels() {
+    let (errors, _) = parse_assign("x = Shape#circle(r: 1);\n");
+    assert!(
+        errors.iter().any(|e| e.contains("Invalid argument label 'r'")),
+        "{errors:?}"
+    );
+}
@@ -0,0 +1,134 @@
+use xu_lexer::{Lexer, normalize_source};
+use xu_parser::{Parser, Stmt};
+
+/// Lexer and parser errors as `line: message`, plus the parsed top-level
+/// statements.
+fn parse(src: &str, inspect: impl FnOnce(&[Stmt])) -> Vec<String> {
+    let normalized = normalize_source(src);
+    let text = normalized.text.as_str();
+    let lex = Lexer::new(text).lex();
+    let bump = bumpalo::Bump::new();
+    let parse = Parser::new(text, &lex.tokens, &bump).parse();
+    inspect(&parse.module.stmts);
+    lex.diagnostics
+        .iter()
+        .chain(&parse.diagnostics)
+        .map(|d| {
+            let line = d.span.map(|s| text[..s.start.0 as usize].matches('\n').count() + 1).unwrap_or(0);
+            format!("{line}: {}", d.message)
+        })
+        .collect()
+}
+
+fn is_let(stmt: &Stmt, name: &str) -> bool {
+    matches!(stmt, Stmt::Assign(a) if matches!(&a.target, xu_parser::Expr::Ident(n, _) if n.as_str() == name))
+}
+
+#[test]
+fn unclosed_paren_is_reported_at_the_opener_and_the_next_line_still_parses() {
+    let errors = parse("let a = (1 +\nlet b = 2\nlet c = b\n", |stmts| {
+        assert!(matches!(stmts[0], Stmt::Error(_)), "{stmts:?}");
+        assert!(is_let(&stmts[1], "b"), "{stmts:?}");
+        assert!(is_let(&stmts[2], "c"), "{stmts:?}");
+    });
+    assert_eq!(errors, ["1: Unclosed '('", "2: Expected expression"]);
+}
+
+#[test]
+fn a_closer_of_the_outer_block_closes_an_inner_unclosed_bracket() {
+    let src = "func f() {\n    let v = [1, 2\n    let w = 3\n}\nlet x = 4\n";
+    let errors = parse(src, |stmts| {
+        let Stmt::FuncDef(f) = &stmts[0] else { panic!("{stmts:?}") };
+        assert!(matches!(f.body[0], Stmt::Error(_)), "{:?}", f.body);
+        assert!(is_let(&f.body[1], "w"), "{:?}", f.body);
+        assert!(is_let(&stmts[1], "x"), "{stmts:?}");
+    });
+    assert_eq!(errors, ["2: Unclosed '['", "3: Expected RBracket"]);
+}
+
+#[test]
+fn a_stray_closer_does_not_unbalance_the_enclosing_block() {
+    let errors = parse("func f() {\n    let v = 1)\n}\nlet x = 2\n", |stmts| {
+        assert!(matches!(stmts[0], Stmt::FuncDef(_)), "{stmts:?}");
+        assert!(is_let(&stmts[1], "x"), "{stmts:?}");
+    });
+    assert_eq!(errors[0], "2: Unmatched ')'");
+    assert!(!errors.iter().any(|e| e.contains("'{'")), "{errors:?}");
+}
+
+#[test]
+fn malformed_struct_members_are_skipped_one_at_a_time() {
+    let src = "Point has {\n    x int\n    y: int\n    z\n    func norm() {\n        return self.y\n    }\n}\nlet p = 1\n";
+    let errors = parse(src, |stmts| {
+        let Stmt::StructDef(def) = &stmts[0] else { panic!("{stmts:?}") };
+        let fields: Vec<_> = def.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(fields, ["y"]);
+        assert_eq!(def.methods.len(), 1);
+        assert!(is_let(&stmts[1], "p"), "{stmts:?}");
+    });
+    assert_eq!(errors, ["2: Expected Colon", "5: Expected Colon"]);
+}
+
+#[test]
+fn every_broken_statement_in_a_block_is_reported_once() {
+    let src = "func f() {\n    let a = \n    let b = (2 *\n    let c = 3 +\n    return c\n}\n";
+    let errors = parse(src, |stmts| {
+        let Stmt::FuncDef(f) = &stmts[0] else { panic!("{stmts:?}") };
+        assert!(matches!(f.body.last(), Some(Stmt::Return(_))), "{:?}", f.body);
+    });
+    assert_eq!(
+        errors,
+        ["3: Unclosed '('", "3: Expected expression", "4: Expected expression", "5: Expected expression"]
+    );
+}
+
+#[test]
+fn a_broken_match_arm_keeps_the_other_arms() {
+    let src = "let n = 1\nmatch n {\n    1 { println(1) }\n    2 => { println(2) }\n    _ { println(0) }\n}\nlet after = 2\n";
+    let errors = parse(src, |stmts| {
+        assert!(matches!(stmts[1], Stmt::Match(_)), "{stmts:?}");
+        assert!(is_let(&stmts[2], "after"), "{stmts:?}");
+    });
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert!(errors[0].starts_with("4: "), "{errors:?}");
+}
+
+#[test]
+fn broken_contract_annotations_end_in_diagnostics() {
+    for src in ["@requires(n)\n@ensures(r", "@requires(n)\n@", "@requires(\n@requires(\nlet x = 1\n"] {
+        let errors = parse(src, |_| {});
+        assert!(!errors.is_empty(), "{src:?}");
+    }
+}
+
+#[test]
+fn nesting_past_the_limit_is_reported_once_per_statement() {
+    let deep = xu_parser::MAX_NESTING + 1;
+    let long = xu_parser::MAX_CHAIN + 1;
+    let sources = [
+        format!("let a = {}1{}\nlet b = 2\n", "(".repeat(deep), ")".repeat(deep)),
+        format!("let a = {}1{}\nlet b = 2\n", "[".repeat(deep), "]".repeat(deep)),
+        format!("let a = 1{}\nlet b = 2\n", " + 1".repeat(long)),
+        format!("let a = b{}\nlet b = 2\n", ".c".repeat(long)),
+        format!("let a: {}int{} = []\nlet b = 2\n", "[".repeat(deep), "]".repeat(deep)),
+        format!("func f() {{ {}1{} }}\nlet b = 2\n", "if true { ".repeat(deep), " }".repeat(deep)),
+    ];
+    for src in &sources {
+        let errors = parse(src, |stmts| {
+            assert!(is_let(stmts.last().unwrap(), "b"), "{stmts:?}");
+        });
+        assert_eq!(errors, ["1: Expression or block is nested too deeply"], "{}", &src[..40]);
+    }
+
+    let (within, long) = (xu_parser::MAX_NESTING - 2, xu_parser::MAX_CHAIN - 2);
+    let src = format!("let a = {}1{}\nlet b = 1{}\n", "(".repeat(within), ")".repeat(within), " + 1".repeat(long));
+    assert!(parse(&src, |_| {}).is_empty());
+}
+
+#[test]
+fn an_if_expression_without_else_is_reported_at_the_if() {
+    let errors = parse("let a = if b: 1\nlet c = 2\n", |stmts| {
+        assert!(is_let(&stmts[1], "c"), "{stmts:?}");
+    });
+    assert_eq!(errors, ["1: An if expression requires an else branch"]);
+}
@@ -0,0 +1,246 @@
+//! Python bindings for the Xu interpreter, built as the `xu` extension
+//! module of the `xuscript` package.
+//!
+//! ```python
+//! import xu
+//!
+//! print(xu.run('println("hi")'), end="")
+//!
+//! rt = xu.Runtime()
+//! rt.register("discount", lambda total: total * 0.9 if total > 100 else total)
+//! rt.run("func price(total: float) -> float { return discount(total) + 5.0 }")
+//! rt.call("price", 120.0)  # 113.0
+//! ```
+//!
+//! Values cross the boundary as copies, converted by [`to_host`] and
+//! [`to_py`]: `None`, `bool`, `int`, `float`, `str`, `list`, `tuple` and
+//! `dict` map to the script values of the same shape.
+
+use pyo3::IntoPyObjectExt;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyOverflowError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+
+use xu_driver::Driver;
+use xu_runtime::{HostValue, INT_RANGE};
+use xu_syntax::Severity;
+
+create_exception!(
+    xu,
+    XuError,
+    PyException,
+    "A script failed to compile or run."
+);
+
+/// Conversion nesting limit, which also stops self-containing lists and dicts.
+const MAX_DEPTH: usize = 256;
+
+/// An interpreter whose programs can call functions registered from Python.
+#[pyclass(unsendable, module = "xu")]
+struct Runtime {
+    rt: xu_runtime::Runtime,
+    driver: Driver,
+}
+
+#[pymethods]
+impl Runtime {
+    #[new]
+    fn new() -> Self {
+        let mut rt = xu_runtime::Runtime::new();
+        rt.set_strict_vars(true);
+        rt.set_frontend(Box::new(Driver::new()));
+        Runtime {
+            rt,
+            driver: Driver::new(),
+        }
+    }
+
+    /// Compiles and runs `source` as a program and returns what it printed.
+    /// Each call is a new program, whose functions `call` finds afterwards.
+    fn run(&mut self, source: &str) -> PyResult<String> {
+        let names = self.rt.host_function_names();
+        let compiled = self
+            .driver
+            .compile_text_with_predefs("<python>", source, true, &names)
+            .map_err(XuError::new_err)?;
+        let errors: Vec<_> = compiled
+            .diagnostics
+            .iter()
+            .filter(|d| matches!(d.severity, Severity::Error))
+            .cloned()
+            .collect();
+        if !errors.is_empty() {
+            return Err(XuError::new_err(xu_syntax::render_diagnostics(
+                &compiled.source,
+                &errors,
+            )));
+        }
+        match self.rt.exec_executable(&compiled.executable) {
+            Ok(res) => Ok(res.output),
+            Err(e) => {
+                self.rt.take_output();
+                Err(XuError::new_err(e))
+            }
+        }
+    }
+
+    /// Calls the top-level function `name` of the last program with copies
+    /// of `args`. What the function prints goes to `sys.stdout`.
+    #[pyo3(signature = (name, *args))]
+    fn call(
+        &mut self,
+        py: Python<'_>,
+        name: &str,
+        args: &Bound<'_, PyTuple>,
+    ) -> PyResult<PyObject> {
+        let args = args
+            .iter()
+            .map(|a| to_host(&a, 0))
+            .collect::<PyResult<Vec<_>>>()?;
+        let res = self.rt.call_global_host(name, args);
+        let output = self.rt.take_output();
+        if !output.is_empty() {
+            py.import("sys")?
+                .getattr("stdout")?
+                .call_method1("write", (output,))?;
+        }
+        Ok(to_py(py, res.map_err(XuError::new_err)?)?.unbind())
+    }
+
+    /// Defines the global function `name` for programs run from now on.
+    /// Scripts call `func` with copies of their arguments; an exception it
+    /// raises becomes a runtime error in the script.
+    fn register(&mut self, name: &str, func: Bound<'_, PyAny>) -> PyResult<()> {
+        if !func.is_callable() {
+            return Err(PyTypeError::new_err(format!(
+                "register: {name} is not callable"
+            )));
+        }
+        let func: Py<PyAny> = func.unbind();
+        let fname = name.to_string();
+        self.rt.define_host_function(name, move |args| {
+            Python::with_gil(|py| {
+                let args = args
+                    .iter()
+                    .map(|a| to_py(py, a.clone()))
+                    .collect::<PyResult<Vec<_>>>()?;
+                let result = func.bind(py).call1(PyTuple::new(py, args)?)?;
+                to_host(&result, 0)
+            })
+            .map_err(|e| format!("{fname}: {e}"))
+        });
+        Ok(())
+    }
+}
+
+/// Runs `source` in a new runtime with the callables of `functions` defined
+/// under their keys, and returns what it printed.
+#[pyfunction]
+#[pyo3(signature = (source, functions = None))]
+fn run(source: &str, functions: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+    let mut rt = Runtime::new();
+    for (name, func) in functions.into_iter().flatten() {
+        rt.register(name.downcast::<PyString>()?.to_str()?, func)?;
+    }
+    rt.run(source)
+}
+
+/// Copies a Python object into a script value.
+fn to_host(obj: &Bound<'_, PyAny>, depth: usize) -> PyResult<HostValue> {
+    if depth > MAX_DEPTH {
+        return Err(PyValueError::new_err(
+            "value is nested too deeply (or contains itself) to pass to Xu",
+        ));
+    }
+    let items = |seq: Bound<'_, PyAny>| -> PyResult<Vec<HostValue>> {
+        seq.try_iter()?.map(|x| to_host(&x?, depth + 1)).collect()
+    };
+    Ok(if obj.is_none() {
+        HostValue::Unit
+    } else if let Ok(b) = obj.downcast::<PyBool>() {
+        HostValue::Bool(b.is_true())
+    } else if obj.is_instance_of::<PyInt>() {
+        let i: i64 = obj.extract()?;
+        if !INT_RANGE.contains(&i) {
+            return Err(PyOverflowError::new_err(format!(
+                "int {i} does not fit in Xu's 48-bit ints"
+            )));
+        }
+        HostValue::Int(i)
+    } else if let Ok(f) = obj.downcast::<PyFloat>() {
+        HostValue::Float(f.value())
+    } else if let Ok(s) = obj.downcast::<PyString>() {
+        HostValue::Str(s.to_str()?.to_string())
+    } else if obj.is_instance_of::<PyList>() {
+        HostValue::List(items(obj.clone())?)
+    } else if obj.is_instance_of::<PyTuple>() {
+        HostValue::Tuple(items(obj.clone())?)
+    } else if let Ok(d) = obj.downcast::<PyDict>() {
+        let mut entries = Vec::with_capacity(d.len());
+        for (k, v) in d.iter() {
+            entries.push((to_host(&k, depth + 1)?, to_host(&v, depth + 1)?));
+        }
+        HostValue::Dict(entries)
+    } else {
+        return Err(PyTypeError::new_err(format!(
+            "cannot pass a {} to Xu",
+            obj.get_type().name()?
+        )));
+    })
+}
+
+/// Builds the Python object for a script value. Options become their value
+/// or `None`, ranges `range` objects and structs dicts of their fields;
+/// other enums have no Python form.
+fn to_py(py: Python<'_>, value: HostValue) -> PyResult<Bound<'_, PyAny>> {
+    let all = |items: Vec<HostValue>| {
+        items
+            .into_iter()
+            .map(|x| to_py(py, x))
+            .collect::<PyResult<Vec<_>>>()
+    };
+    Ok(match value {
+        HostValue::Unit | HostValue::Option(None) => py.None().into_bound(py),
+        HostValue::Bool(b) => b.into_bound_py_any(py)?,
+        HostValue::Int(i) => i.into_bound_py_any(py)?,
+        HostValue::Float(f) => f.into_bound_py_any(py)?,
+        HostValue::Str(s) => s.into_bound_py_any(py)?,
+        HostValue::List(items) => PyList::new(py, all(items)?)?.into_any(),
+        HostValue::Tuple(items) => PyTuple::new(py, all(items)?)?.into_any(),
+        HostValue::Dict(entries) => {
+            let d = PyDict::new(py);
+            for (k, v) in entries {
+                d.set_item(to_py(py, k)?, to_py(py, v)?)?;
+            }
+            d.into_any()
+        }
+        HostValue::Range(start, end, inclusive) => {
+            let end = if inclusive { end + 1 } else { end };
+            py.import("builtins")?
+                .getattr("range")?
+                .call1((start, end))?
+        }
+        HostValue::Option(Some(inner)) => to_py(py, *inner)?,
+        HostValue::Struct { fields, .. } => {
+            let d = PyDict::new(py);
+            for (name, v) in fields {
+                d.set_item(name, to_py(py, v)?)?;
+            }
+            d.into_any()
+        }
+        HostValue::Enum { ty, variant, .. } => {
+            return Err(PyTypeError::new_err(format!(
+                "cannot pass {ty}#{variant} to Python"
+            )));
+        }
+    })
+}
+
+#[pymodule]
+fn xu(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("XuError", m.py().get_type::<XuError>())?;
+    m.add_class::<Runtime>()?;
+    m.add_function(wrap_pyfunction!(run, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,84 @@
+"""Tests for the xu extension module; run with `python -m unittest` after
+building it (`maturin develop` in crates/xu_py)."""
+
+import unittest
+
+import xu
+
+
+class RunTest(unittest.TestCase):
+    def test_run_returns_output(self):
+        self.assertEqual(xu.run('println("hi")\nprintln(1 + 2)\n'), "hi\n3\n")
+
+    def test_errors_raise_xu_error(self):
+        with self.assertRaises(xu.XuError) as cm:
+            xu.run("println(missing)\n")
+        self.assertIn("missing", str(cm.exception))
+        with self.assertRaises(xu.XuError):
+            xu.run("let xs = [1]\nprintln(xs[5])\n")
+
+    def test_host_functions(self):
+        out = xu.run('println(greet("xu"))\n', {"greet": lambda name: "hello " + name})
+        self.assertEqual(out, "hello xu\n")
+
+
+class RuntimeTest(unittest.TestCase):
+    def test_values_round_trip(self):
+        rt = xu.Runtime()
+        rt.register("echo", lambda v: v)
+        rt.run("func pass(v: any) -> any { return echo(v) }\n")
+        for v in [None, True, 7, -2**40, 2.5, "s", [1, [2.0, "x"]], (1, "a"), {"k": [1, 2]}, {3: False}]:
+            self.assertEqual(rt.call("pass", v), v)
+
+    def test_script_values(self):
+        rt = xu.Runtime()
+        rt.run(
+            "P has {\n    x: int\n    y: int\n}\n"
+            "func pt() -> P { return P{ x: 1, y: 2 } }\n"
+            "func r() -> any { return 1..=3 }\n"
+            "func opt(b: bool) -> Option[int] { if b { return Option#some(4) } return Option#none }\n"
+        )
+        self.assertEqual(rt.call("pt"), {"x": 1, "y": 2})
+        self.assertEqual(rt.call("r"), range(1, 4))
+        self.assertEqual(rt.call("opt", True), 4)
+        self.assertIsNone(rt.call("opt", False))
+
+    def test_unconvertible_values(self):
+        rt = xu.Runtime()
+        rt.run("func id(v: any) -> any { return v }\n")
+        with self.assertRaises(TypeError):
+            rt.call("id", object())
+        with self.assertRaises(OverflowError):
+            rt.call("id", 2**64)
+        with self.assertRaises(OverflowError):
+            rt.call("id", 2**47)
+        with self.assertRaises(OverflowError):
+            rt.call("id", -(2**47) - 1)
+        self.assertEqual(rt.call("id", 2**47 - 1), 2**47 - 1)
+        self.assertEqual(rt.call("id", -(2**47)), -(2**47))
+        xs = []
+        xs.append(xs)
+        with self.assertRaises(ValueError):
+            rt.call("id", xs)
+        with self.assertRaises(TypeError):
+            rt.register("f", 3)
+
+    def test_host_exceptions_are_script_errors(self):
+        def fail(_):
+            raise ValueError("no rules match")
+
+        rt = xu.Runtime()
+        rt.register("check", fail)
+        with self.assertRaises(xu.XuError) as cm:
+            rt.run("check(1)\n")
+        self.assertIn("no rules match", str(cm.exception))
+
+    def test_call_unknown_function(self):
+        rt = xu.Runtime()
+        rt.run("let x = 1\n")
+        with self.assertRaises(xu.XuError):
+            rt.call("nope")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,63 @@
+//! Embeds the standard library (`stdlib/*.xu`) into the runtime so installed
+//! binaries can import `std/...` without a stdlib directory on disk. Each
+//! module is compiled here and stored as a serialized `Executable` next to
+//! its source, so importing it at run time skips the frontend.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use xu_ir::Frontend;
+use xu_ir::image::ImageWriter;
+use xu_syntax::Severity;
+
+/// Must match `EMBEDDED_PREFIX` in `src/modules/std_bundle.rs`: modules are
+/// compiled under their import key so spans name the same file.
+const EMBEDDED_PREFIX: &str = "<embedded>/";
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let stdlib_dir = manifest_dir.join("../../stdlib");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let out = out_dir.join("std_bundle.rs");
+
+    println!("cargo:rerun-if-changed={}", stdlib_dir.display());
+
+    let mut files: Vec<PathBuf> = match fs::read_dir(&stdlib_dir) {
+        Ok(rd) => rd
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "xu"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+
+    let driver = xu_driver::Driver::new();
+    let mut code = String::from("pub(crate) static STD_BUNDLE: &[(&str, &str, Option<&[u8]>)] = &[\n");
+    for path in &files {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let name = path.file_name().unwrap().to_string_lossy();
+        let key = format!("{EMBEDDED_PREFIX}std/{name}");
+        let source = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        let abs = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        // A module that does not compile is embedded as source only, so
+        // importing it reports its errors as a module on disk would.
+        let image = match driver.compile_text_no_analyze(&key, &source) {
+            Ok(compiled) if !compiled.diagnostics.iter().any(|d| matches!(d.severity, Severity::Error)) => {
+                let mut w = ImageWriter::new();
+                w.put(&compiled.executable);
+                let image = out_dir.join(format!("std_{}.img", path.file_stem().unwrap().to_string_lossy()));
+                fs::write(&image, w.finish()).unwrap();
+                format!("Some(include_bytes!({:?}))", display(&image))
+            }
+            _ => "None".to_string(),
+        };
+        code.push_str(&format!("    (\"std/{}\", include_str!({:?}), {}),\n", name, display(&abs), image));
+    }
+    code.push_str("];\n");
+    fs::write(out, code).unwrap();
+}
+
+fn display(p: &Path) -> String {
+    p.to_string_lossy().into_owned()
+}
@@ -3,6 +3,7 @@ use crate::Value;
 use crate::core::value::DictKey;
 
 use crate::Runtime;
+use crate::runtime::{IcKind, record_ic};
 
 impl Runtime {
     pub(crate) fn get_member_with_ic(
@@ -31,13 +32,14 @@ impl Runtime {
                 if idx < self.caches.ic_slots.len() {
                     let c = &self.caches.ic_slots[idx];
                     if c.id == id.0 && c.ver == cur_ver && c.key_hash == key_hash {
+                        record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Field, idx, field, obj, true);
                         return Ok(c.value);
                     }
                 }
             }
 
             let v = if let crate::core::heap::ManagedObject::Dict(me) = self.heap.get(id) {
-                Self::dict_get_by_str_with_hash(me, field, key_hash)
+                Self::dict_get_by_str_with_hash(&self.heap, me, field, key_hash)
             } else {
                 None
             }
@@ -65,6 +67,7 @@ impl Runtime {
                 value: v,
                 ..Default::default()
             };
+            record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Field, idx, field, obj, false);
 
             Ok(v)
         } else {
@@ -76,6 +79,33 @@ impl Runtime {
         self.get_member_with_ic_raw(obj, field, None)
     }
 
+    /// Read of a struct field at an offset the compiler resolved from the
+    /// static type. The layout check makes a wrong guess safe: instances of
+    /// another type or an older layout take the inline cache path.
+    #[inline(always)]
+    pub(crate) fn get_member_at(
+        &mut self,
+        obj: Value,
+        field: &str,
+        offset: u32,
+        slot_idx: Option<u32>,
+    ) -> Result<Value, String> {
+        let offset = offset as usize;
+        let slot_idx = slot_idx.map(|s| s as usize);
+        if obj.get_tag() == crate::core::value::TAG_STRUCT {
+            if let crate::core::heap::ManagedObject::Struct(s) = self.heap.get(obj.as_obj_id()) {
+                if s.field_names.get(offset).is_some_and(|f| f == field) {
+                    let v = s.fields[offset];
+                    if let Some(slot) = slot_idx {
+                        record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Field, slot, field, obj, true);
+                    }
+                    return Ok(v);
+                }
+            }
+        }
+        self.get_member_with_ic_raw(obj, field, slot_idx)
+    }
+
     pub(crate) fn get_member_with_ic_raw(
         &mut self,
         obj: Value,
@@ -89,19 +119,23 @@ impl Runtime {
                 if let Some(idx) = slot_idx {
                     if idx < self.caches.ic_slots.len() {
                         let c = &self.caches.ic_slots[idx];
-                        if c.struct_ty_hash == s.ty_hash && c.key_hash == xu_ir::stable_hash64(field)
+                        if c.struct_ty_hash == s.ty_hash
+                            && c.key_hash == xu_ir::stable_hash64(field)
+                            && c.layout_id == s.layout_id()
+                            && c.generation == self.caches.ic_generation
                         {
                             if let Some(offset) = c.field_offset {
+                                record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Field, idx, field, obj, true);
                                 return Ok(s.fields[offset]);
                             }
                         }
                     }
+                    record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Field, idx, field, obj, false);
                 }
 
-                let layout = self.types.struct_layouts.get(&s.ty).ok_or_else(|| {
-                    self.error(xu_syntax::DiagnosticKind::UnknownStruct(s.ty.clone()))
-                })?;
-                let pos = layout.iter().position(|f| f == field).ok_or_else(|| {
+                // Read through the instance's own layout: instances created
+                // before a struct redefinition keep their original field order.
+                let pos = s.field_names.iter().position(|f| f == field).ok_or_else(|| {
                     self.error(xu_syntax::DiagnosticKind::UnknownMember(field.to_string()))
                 })?;
 
@@ -113,6 +147,8 @@ impl Runtime {
                         struct_ty_hash: s.ty_hash,
                         key_hash: xu_ir::stable_hash64(field),
                         field_offset: Some(pos),
+                        layout_id: s.layout_id(),
+                        generation: self.caches.ic_generation,
                         ..Default::default()
                     };
                 }
@@ -214,7 +250,7 @@ impl Runtime {
             let id = obj.as_obj_id();
             if let crate::core::heap::ManagedObject::Dict(me) = self.heap.get(id) {
                 let key_hash = Self::hash_bytes(me.map.hasher(), field.as_bytes());
-                Self::dict_get_by_str_with_hash(me, field, key_hash)
+                Self::dict_get_by_str_with_hash(&self.heap, me, field, key_hash)
                     .ok_or_else(|| self.error(xu_syntax::DiagnosticKind::KeyNotFound(field.to_string())))
             } else {
                 Err(self.error(xu_syntax::DiagnosticKind::Raw(NOT_A_DICT.into())))
@@ -247,7 +283,10 @@ impl Runtime {
         slot_cell: &std::cell::Cell<Option<usize>>,
     ) -> Result<Value, String> {
         let tag = obj.get_tag();
-        if tag == crate::core::value::TAG_DICT {
+        if tag == crate::core::value::TAG_DICT && !index.is_int() && index.get_tag() != crate::core::value::TAG_STR {
+            // Bool, float, tuple and struct keys bypass the inline cache
+            self.get_index_with_ic_raw(obj, index, None)
+        } else if tag == crate::core::value::TAG_DICT {
             let id = obj.as_obj_id();
             let (cur_ver, key_hash, key) = if let crate::core::heap::ManagedObject::Dict(me) = self.heap.get(id)
             {
@@ -273,6 +312,7 @@ impl Runtime {
                 if idx < self.caches.ic_slots.len() {
                     let c = &self.caches.ic_slots[idx];
                     if c.id == id.0 && c.ver == cur_ver && c.key_hash == key_hash {
+                        record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Index, idx, "[]", obj, true);
                         return Ok(c.value);
                     }
                 }
@@ -304,6 +344,7 @@ impl Runtime {
                 value: v,
                 ..Default::default()
             };
+            record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Index, idx, "[]", obj, false);
             Ok(v)
         } else {
             self.get_index_with_ic_raw(obj, index, slot_cell.get())
@@ -332,6 +373,17 @@ impl Runtime {
             } else {
                 Err(self.error(xu_syntax::DiagnosticKind::Raw(NOT_A_LIST.into())))
             }
+        } else if tag == crate::core::value::TAG_DICT && !index.is_int() && index.get_tag() != crate::core::value::TAG_STR {
+            let key = crate::methods::get_dict_key_from_value(self, &index)?;
+            let found = if let crate::core::heap::ManagedObject::Dict(me) = self.heap.get(obj.as_obj_id()) {
+                me.get_entry(&key)
+            } else {
+                return Err(self.error(xu_syntax::DiagnosticKind::Raw(NOT_A_DICT.into())));
+            };
+            found.ok_or_else(|| {
+                let key = crate::util::value_to_string(&index, &self.heap);
+                self.error(xu_syntax::DiagnosticKind::KeyNotFound(key))
+            })
         } else if tag == crate::core::value::TAG_DICT {
             let id = obj.as_obj_id();
             if let crate::core::heap::ManagedObject::Dict(me) = self.heap.get(id) {
@@ -347,11 +399,13 @@ impl Runtime {
                         if idx < self.caches.ic_slots.len() {
                             let c = &self.caches.ic_slots[idx];
                             if c.id == id.0 && c.ver == me.ver && c.key_hash == key_hash {
+                                record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Index, idx, "[]", obj, true);
                                 return Ok(c.value);
                             }
                         }
+                        record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Index, idx, "[]", obj, false);
                     }
-                    let out_val = Self::dict_get_by_str_with_hash(me, &key, key_hash)
+                    let out_val = Self::dict_get_by_str_with_hash(&self.heap, me, &key, key_hash)
                         .ok_or_else(|| self.error(xu_syntax::DiagnosticKind::KeyNotFound(key.clone())))?;
                     if let Some(idx) = slot_idx {
                         while self.caches.ic_slots.len() <= idx {
@@ -373,14 +427,14 @@ impl Runtime {
                         if idx < self.caches.ic_slots.len() {
                             let c = &self.caches.ic_slots[idx];
                             if c.id == id.0 && c.ver == me.ver && c.key_hash == key_hash {
+                                record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Index, idx, "[]", obj, true);
                                 return Ok(c.value);
                             }
                         }
+                        record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Index, idx, "[]", obj, false);
                     }
                     let out_val = me
-                        .map
-                        .get(&DictKey::Int(key))
-                        .cloned()
+                        .get_entry(&DictKey::Int(key))
                         .ok_or_else(|| {
                             self.error(xu_syntax::DiagnosticKind::KeyNotFound(key.to_string()))
                         })?;
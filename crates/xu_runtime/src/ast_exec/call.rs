@@ -11,8 +11,8 @@ use crate::util::type_matches;
 use crate::runtime::type_check::{compute_type_signature, should_use_type_ic, type_sig_matches};
 
 /// 函数调用上下文，用于保存和恢复状态
+/// 调用方的环境在调用期间存放于 `suspended_envs`，以便 GC 把它作为根
 struct CallContext {
-    saved_env: Option<crate::Env>,
     saved_func: Option<String>,
     saved_param_bindings: Option<Vec<(String, usize)>>,
     saved_frame_depth: usize,
@@ -23,8 +23,8 @@ impl CallContext {
         let mut call_env = rt.pools.env_pool.pop().unwrap_or_default();
         call_env.reset_for_call_from(fun_env);
         let saved_env = std::mem::replace(&mut rt.env, call_env);
+        rt.suspended_envs.push(saved_env);
         Self {
-            saved_env: Some(saved_env),
             saved_func: rt.current_func.take(),
             saved_param_bindings: rt.current_param_bindings.take(),
             saved_frame_depth: rt.func_entry_frame_depth,
@@ -33,7 +33,8 @@ impl CallContext {
 
     fn restore(self, rt: &mut Runtime) {
         rt.pop_locals();
-        let call_env = std::mem::replace(&mut rt.env, self.saved_env.expect("saved_env was set"));
+        let saved_env = rt.suspended_envs.pop().expect("caller env was suspended");
+        let call_env = std::mem::replace(&mut rt.env, saved_env);
         rt.pools.env_pool.push(call_env);
         rt.current_func = self.saved_func;
         rt.current_param_bindings = self.saved_param_bindings;
@@ -57,6 +58,7 @@ impl Runtime {
 
         match func_obj {
             Function::Builtin(fun) => fun(self, args),
+            Function::Host(fun) => fun(self, args),
             Function::User(fun) => {
                 if fun.def.name == "main" {
                     self.main_invoked = true;
@@ -105,7 +107,12 @@ impl Runtime {
                 self.check_param_types(&fun.def.name, &fun.def.params, args)?;
                 if use_type_ic { fun.type_sig_ic.set(Some(type_sig)); }
             }
-            if let Some(res) = crate::vm::run_bytecode_fast_params_only(self, &fun.bytecode, &fun.def.params, args) {
+            #[cfg(feature = "jit")]
+            if let Some(v) = self.call_jit(fun, args) {
+                self.check_return_type(&fun.def.return_ty, &v)?;
+                return Ok(v);
+            }
+            if let Some(res) = crate::vm::run_bytecode_fast_params_only(self, &fun.bytecode, &fun.def, args) {
                 let v = res?;
                 self.check_return_type(&fun.def.return_ty, &v)?;
                 return Ok(v);
@@ -337,6 +344,7 @@ impl Runtime {
             }
             Flow::None => Ok(Value::UNIT),
             Flow::Throw(v) => Err(self.format_throw(&v)),
+            Flow::Exit(code) => Err(crate::runtime::exit_message(code)),
             Flow::Break | Flow::Continue => Err(self.error(
                 xu_syntax::DiagnosticKind::UnexpectedControlFlowInFunction("break or continue"),
             )),
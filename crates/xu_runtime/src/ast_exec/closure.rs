@@ -113,8 +113,11 @@ pub(super) fn params_all_slotted(stmts: &[Stmt], params: &[xu_ir::Param]) -> boo
             Expr::Index(m) => check_expr(&m.object, names) && check_expr(&m.index, names),
             Expr::Call(c) => check_expr(&c.callee, names) && c.args.iter().all(|a| check_expr(a, names)),
             Expr::MethodCall(m) => check_expr(&m.receiver, names) && m.args.iter().all(|a| check_expr(a, names)),
-            Expr::Unary { expr, .. } => check_expr(expr, names),
+            Expr::Unary { expr, .. } | Expr::Try(expr) | Expr::OptChain(expr) | Expr::OptLink(expr) => {
+                check_expr(expr, names)
+            }
             Expr::Binary { left, right, .. } => check_expr(left, names) && check_expr(right, names),
+            Expr::Compare(c) => check_expr(&c.first, names) && c.links.iter().all(|(_, e)| check_expr(e, names)),
             _ => true,
         }
     }
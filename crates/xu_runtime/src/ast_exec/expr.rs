@@ -37,7 +37,10 @@ impl Runtime {
                         // depth >= max_valid_depth means the variable is outside current function
                         // Fall through to env lookup
                     } else if let Some(func_name) = self.current_func.as_deref() {
-                        if let Some(idxmap) = self.compiled_locals_idx.get(func_name) {
+                        // The compiled indices address the function's own frame; inside a
+                        // nested block frame fall back to the by-name lookup below.
+                        let nested = self.locals.maps.len() > self.func_entry_frame_depth;
+                        if let Some(idxmap) = self.compiled_locals_idx.get(func_name).filter(|_| !nested) {
                             if let Some(&idx) = idxmap.get(s) {
                                 slot.set(Some((0, idx as u32)));
                                 if let Some(v) = self.get_local_by_depth_index(0, idx) {
@@ -108,6 +111,16 @@ impl Runtime {
                             }))
                         }
                     }
+                    UnaryOp::BitNot => {
+                        if v.is_int() {
+                            Ok(Value::from_i64(!v.as_i64()))
+                        } else {
+                            Err(self.error(xu_syntax::DiagnosticKind::InvalidUnaryOperand {
+                                op: '~',
+                                expected: "int".to_string(),
+                            }))
+                        }
+                    }
                 }
             }
             Expr::Binary { op, left, right } => {
@@ -119,8 +132,9 @@ impl Runtime {
                                     let av = self.eval_expr(&c.args[0])?;
                                     let digits = if av.is_int() {
                                         Some(i64_to_text_fast(av.as_i64()))
-                                    } else if av.is_f64() && av.as_f64().fract() == 0.0 {
-                                        Some(i64_to_text_fast(av.as_f64() as i64))
+                                    } else if av.is_f64() {
+                                        let digits = crate::util::FloatBuf::new(av.as_f64(), &self.heap.float_format);
+                                        Some(digits.as_str().into())
                                     } else if av.get_tag() == crate::core::value::TAG_STR {
                                         if let crate::core::heap::ManagedObject::Str(s) =
                                             self.heap.get(av.as_obj_id())
@@ -144,6 +158,24 @@ impl Runtime {
                         }
                     }
                 }
+                if *op == BinaryOp::In {
+                    let x = self.eval_expr(left)?;
+                    // 区间字面量直接比较边界，不分配区间对象
+                    if let Expr::Range(r) = right.as_ref() {
+                        let start = to_i64(&self.eval_expr(&r.start)?)?;
+                        let end = to_i64(&self.eval_expr(&r.end)?)?;
+                        return Ok(Value::from_bool(crate::runtime::in_range(x, start, end, r.inclusive)));
+                    }
+                    let coll = self.eval_expr(right)?;
+                    return self.eval_binary(BinaryOp::In, x, coll);
+                }
+                if *op == BinaryOp::Coalesce {
+                    let a = self.eval_expr(left)?;
+                    return match self.present_value(a) {
+                        Some(v) => Ok(v),
+                        None => self.eval_expr(right),
+                    };
+                }
                 // Short-circuit evaluation for && and ||
                 if *op == BinaryOp::And {
                     let a = self.eval_expr(left)?;
@@ -191,18 +223,30 @@ impl Runtime {
                 let b = self.eval_expr(right)?;
                 self.eval_binary(*op, a, b)
             }
+            Expr::Compare(c) => {
+                let mut a = self.eval_expr(&c.first)?;
+                for (op, e) in c.links.iter() {
+                    let b = self.eval_expr(e)?;
+                    let holds = self.eval_binary(*op, a, b)?;
+                    if !holds.as_bool() {
+                        return Ok(holds);
+                    }
+                    a = b;
+                }
+                Ok(Value::from_bool(true))
+            }
             Expr::List(items) => {
-                let mut v = Vec::with_capacity(items.len());
+                let mut v = self.heap.take_list(items.len());
                 for e in items {
                     v.push(self.eval_expr(e)?);
                 }
-                Ok(Value::list(self.alloc(crate::core::heap::ManagedObject::List(v))))
+                Ok(Value::list(self.alloc(crate::core::heap::ManagedObject::List(v.into()))))
             }
             Expr::Tuple(items) => {
                 if items.is_empty() {
                     return Ok(Value::UNIT);
                 }
-                let mut v = Vec::with_capacity(items.len());
+                let mut v = self.heap.take_list(items.len());
                 for e in items {
                     v.push(self.eval_expr(e)?);
                 }
@@ -265,7 +309,7 @@ impl Runtime {
             Expr::FuncLit(def) => {
                 // Check if we need to capture any variables
                 let has_locals = self.locals.is_active() && self.locals.has_bindings();
-                let has_params = self.current_param_bindings.as_ref().map_or(false, |b| !b.is_empty());
+                let has_params = self.current_param_bindings.as_ref().is_some_and(|b| !b.is_empty());
                 let needs_capture = has_locals || has_params;
 
                 // Only create a new frame and capture if needed
@@ -304,7 +348,7 @@ impl Runtime {
                 let needs_env_frame = needs_env_frame(&def.body);
                 let skip_local_map = false;
                 let func = UserFunction {
-                    def: (**def).clone(),
+                    def: Rc::clone(def),
                     env: captured_env,
                     needs_env_frame,
                     fast_param_indices: None,
@@ -317,7 +361,7 @@ impl Runtime {
                 )))
             }
             Expr::Dict(entries) => {
-                let mut map: Dict = crate::core::value::dict_with_capacity(entries.len());
+                let mut map: Dict = self.heap.take_dict(entries.len());
                 for (k, v) in entries {
                     // Allocate string key on heap
                     let key = DictKey::from_str_alloc(k, &mut self.heap);
@@ -387,6 +431,7 @@ impl Runtime {
                                         }
                                         let hash = Self::hash_bytes(db.map.hasher(), fname.as_bytes());
                                         if let Some(v) = Self::dict_get_by_str_with_hash(
+                                            &self.heap,
                                             db,
                                             fname.as_str(),
                                             hash,
@@ -403,6 +448,7 @@ impl Runtime {
                         }
                     }
                 }
+                self.check_struct_fields(&s.ty, &values)?;
                 Ok(Value::struct_obj(self.alloc(
                     crate::core::heap::ManagedObject::Struct(Box::new(StructInstance {
                         ty: s.ty.clone(),
@@ -498,6 +544,32 @@ impl Runtime {
                     slot_idx,
                 )
             }
+            Expr::Try(e) => {
+                let v = self.eval_expr(e)?;
+                match self.try_payload(v)? {
+                    Some(inner) => Ok(inner),
+                    None => {
+                        // 以错误展开到所在语句，由 throw_err 转为返回 v
+                        self.try_return = Some(v);
+                        Err(String::new())
+                    }
+                }
+            }
+            Expr::OptChain(e) => match self.eval_expr(e) {
+                Err(_) if std::mem::take(&mut self.opt_chain_none) => Ok(self.option_none()),
+                res => res,
+            },
+            Expr::OptLink(e) => {
+                let v = self.eval_expr(e)?;
+                match self.present_value(v) {
+                    Some(v) => Ok(v),
+                    None => {
+                        // 以错误展开到所在的 OptChain，由其转为 none
+                        self.opt_chain_none = true;
+                        Err(String::new())
+                    }
+                }
+            }
             _ => Err(self.error(xu_syntax::DiagnosticKind::ExpectedExpression)),
         }
     }
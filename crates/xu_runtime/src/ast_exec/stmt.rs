@@ -4,7 +4,6 @@ use xu_ir::{AssignOp, AssignStmt, BinaryOp, Expr, Stmt};
 
 use crate::errors::messages::NOT_A_STRING;
 use crate::Value;
-use crate::core::heap::ObjectId;
 use crate::core::value::{DictKey, Function, UserFunction, ValueExt};
 
 use super::closure::{has_ident_assign, needs_env_frame, params_all_slotted};
@@ -14,20 +13,20 @@ use crate::{Flow, Runtime};
 
 /// 将 DictKey 转换为 Value
 #[inline]
-fn dict_key_to_value(_rt: &mut Runtime, k: &DictKey) -> Value {
-    match k {
-        DictKey::StrRef { obj_id, .. } => {
-            // Directly use the ObjectId - no string copy!
-            Value::str(ObjectId(*obj_id))
-        }
-        DictKey::Int(i) => Value::from_i64(*i),
-    }
+fn dict_key_to_value(rt: &mut Runtime, k: &DictKey) -> Value {
+    k.to_script_value(&mut rt.heap)
 }
 
 impl Runtime {
     /// 创建错误值
     #[inline]
     fn throw_err(&mut self, e: String) -> Flow {
+        if let Some(code) = self.exit_request {
+            return Flow::Exit(code);
+        }
+        if let Some(v) = self.try_return.take() {
+            return Flow::Return(v);
+        }
         Flow::Throw(Value::str(self.alloc(crate::core::heap::ManagedObject::Str(e.into()))))
     }
 
@@ -43,6 +42,10 @@ impl Runtime {
     /// 执行循环体并处理 flow
     #[inline]
     fn exec_loop_body(&mut self, body: &[Stmt]) -> Option<Flow> {
+        // 循环每轮都是中断和信号的安全点
+        if let Err(e) = self.check_interrupt().and_then(|_| self.check_signals()) {
+            return Some(self.throw_err(e));
+        }
         match self.exec_stmts(body) {
             Flow::None | Flow::Continue => None,
             Flow::Break => Some(Flow::Break),
@@ -75,10 +78,12 @@ impl Runtime {
 
     /// 执行函数定义列表
     #[inline]
-    fn exec_func_defs(&mut self, funcs: &[xu_ir::FuncDef]) -> Flow {
+    fn exec_func_defs(&mut self, funcs: &[Rc<xu_ir::FuncDef>]) -> Flow {
         for f in funcs {
-            let s = Stmt::FuncDef(Box::new(f.clone()));
-            if let other @ (Flow::Return(_) | Flow::Throw(_) | Flow::Break | Flow::Continue) = self.exec_stmt(&s) {
+            let s = Stmt::FuncDef(Rc::clone(f));
+            if let other @ (Flow::Return(_) | Flow::Throw(_) | Flow::Exit(_) | Flow::Break | Flow::Continue) =
+                self.exec_stmt(&s)
+            {
                 return other;
             }
         }
@@ -98,9 +103,10 @@ impl Runtime {
     fn exec_stmt(&mut self, stmt: &Stmt) -> Flow {
         match stmt {
             Stmt::StructDef(def) => {
-                self.types.structs.insert(def.name.clone(), (**def).clone());
-                let layout = def.fields.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
-                self.types.struct_layouts.insert(def.name.clone(), std::rc::Rc::from(layout));
+                self.types.structs.insert(def.name.clone(), Rc::clone(def));
+                if self.types.define_struct_layout(&def.name, &def.fields) {
+                    self.caches.invalidate_code_caches();
+                }
 
                 for sf in def.static_fields.iter() {
                     let value = match self.eval_expr(&sf.default) {
@@ -118,7 +124,7 @@ impl Runtime {
             Stmt::FuncDef(def) => {
                 // Check if we need to capture any local variables
                 let has_locals = self.locals.is_active() && self.locals.has_bindings();
-                let has_params = self.current_param_bindings.as_ref().map_or(false, |b| !b.is_empty());
+                let has_params = self.current_param_bindings.as_ref().is_some_and(|b| !b.is_empty());
                 let needs_capture = has_locals || has_params;
 
                 let captured_env = if needs_capture {
@@ -168,7 +174,7 @@ impl Runtime {
                     && !has_ident_assign(&def.body)
                     && params_all_slotted(&def.body, &def.params);
                 let func = UserFunction {
-                    def: (**def).clone(),
+                    def: Rc::clone(def),
                     env: captured_env,
                     needs_env_frame,
                     fast_param_indices,
@@ -231,7 +237,7 @@ impl Runtime {
                 if tag == crate::core::value::TAG_LIST {
                     let id = iter.as_obj_id();
                     let items = if let crate::core::heap::ManagedObject::List(list) = self.heap.get(id) {
-                        list.clone()
+                        list.to_vec()
                     } else {
                         Vec::new()
                     };
@@ -284,7 +290,7 @@ impl Runtime {
                         }
                     } else {
                         let raw_keys = if let crate::core::heap::ManagedObject::Dict(dict) = self.heap.get(id) {
-                            dict.map.keys().cloned().collect::<Vec<_>>()
+                            dict.entries().map(|(k, _)| k).collect::<Vec<_>>()
                         } else {
                             Vec::new()
                         };
@@ -408,23 +414,13 @@ impl Runtime {
         }
     }
 
-    /// 收集字典键值对
+    /// 收集字典键值对（插入顺序）
     fn collect_dict_pairs(&mut self, id: crate::core::ObjectId, include_all: bool) -> Vec<(DictKey, Value)> {
         let mut pairs = Vec::new();
         // First collect shape keys as strings (to avoid borrow issues)
         let shape_keys: Vec<(String, Value)> = if let crate::core::heap::ManagedObject::Dict(dict) = self.heap.get(id) {
-            for (k, v) in dict.map.iter() {
-                pairs.push((*k, *v));
-            }
+            pairs.extend(dict.entries());
             if include_all {
-                // Collect elements
-                if let Some(elements) = dict.elements() {
-                    for (i, v) in elements.iter().enumerate() {
-                        if v.get_tag() != crate::core::value::TAG_UNIT {
-                            pairs.push((DictKey::Int(i as i64), *v));
-                        }
-                    }
-                }
                 // Collect shape keys
                 if let Some(sid) = dict.shape {
                     if let crate::core::heap::ManagedObject::Shape(shape) = self.heap.get(sid) {
@@ -470,7 +466,7 @@ impl Runtime {
                             }));
                         }
                     }
-                    let immutable = matches!(stmt.decl, Some(xu_ir::DeclKind::Let));
+                    let immutable = matches!(stmt.decl, Some(xu_ir::DeclKind::Let | xu_ir::DeclKind::Const));
                     if self.locals.is_active() {
                         // 如果变量不存在或者是不可变声明，都需要定义新变量
                         if !self.set_local(name, rhs) || immutable {
@@ -613,9 +609,10 @@ impl Runtime {
                 v.bin_op_assign(BinaryOp::Add, rhs, &mut self.heap)?;
                 Ok(v)
             }
-            AssignOp::Sub => cur.unwrap_or(Value::from_i64(0)).bin_op(BinaryOp::Sub, rhs),
-            AssignOp::Mul => cur.unwrap_or(Value::from_i64(0)).bin_op(BinaryOp::Mul, rhs),
-            AssignOp::Div => cur.unwrap_or(Value::from_i64(0)).bin_op(BinaryOp::Div, rhs),
+            _ => {
+                let bop = op.binary_op().expect("compound assignment");
+                cur.unwrap_or(Value::from_i64(0)).bin_op(bop, rhs)
+            }
         }
     }
 
@@ -631,15 +628,13 @@ impl Runtime {
             let mut prev = None;
             let mut pos = 0;
             if let crate::core::heap::ManagedObject::Struct(s) = self.heap.get(id) {
-                let layout = self.types.struct_layouts.get(&s.ty).ok_or_else(|| {
-                    self.error(xu_syntax::DiagnosticKind::UnknownStruct(s.ty.clone()))
-                })?;
-                pos = layout.iter().position(|f| f == field).ok_or_else(|| {
+                pos = s.field_names.iter().position(|f| f == field).ok_or_else(|| {
                     self.error(xu_syntax::DiagnosticKind::UnknownMember(field.to_string()))
                 })?;
                 prev = Some(s.fields[pos]);
             }
             let v = self.apply_assign_op(prev, op, rhs)?;
+            self.check_assigned_field(obj, field, &v)?;
             if let crate::core::heap::ManagedObject::Struct(s) = self.heap_get_mut(id) {
                 s.fields[pos] = v;
             }
@@ -652,6 +647,87 @@ impl Runtime {
         }
     }
 
+    /// Assignment to a struct field at an offset the compiler resolved;
+    /// instances with another field there go through `assign_member`.
+    pub(crate) fn assign_member_at(
+        &mut self,
+        obj: Value,
+        field: &str,
+        offset: usize,
+        op: AssignOp,
+        rhs: Value,
+    ) -> Result<(), String> {
+        if obj.get_tag() == crate::core::value::TAG_STRUCT {
+            let id = obj.as_obj_id();
+            if let crate::core::heap::ManagedObject::Struct(s) = self.heap.get(id) {
+                if s.field_names.get(offset).is_some_and(|f| f == field) {
+                    let prev = s.fields[offset];
+                    let v = self.apply_assign_op(Some(prev), op, rhs)?;
+                    self.check_assigned_field(obj, field, &v)?;
+                    if let crate::core::heap::ManagedObject::Struct(s) = self.heap_get_mut(id) {
+                        s.fields[offset] = v;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+        self.assign_member(obj, field, op, rhs)
+    }
+
+    /// strict_fields 开启时，检查新建的 `ty` 实例中已有值（非 unit）的字段是否符合
+    /// 声明的类型；`values` 按结构体布局排列
+    pub(crate) fn check_struct_fields(&self, ty: &str, values: &[Value]) -> Result<(), String> {
+        if !self.config.strict_fields {
+            return Ok(());
+        }
+        let Some(def) = self.types.structs.get(ty) else {
+            return Ok(());
+        };
+        for (f, v) in def.fields.iter().zip(values) {
+            if !v.is_unit() {
+                self.check_field_type(ty, f, v)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// strict_fields 开启时，检查赋给结构体 `obj` 字段 `field` 的值
+    fn check_assigned_field(&self, obj: Value, field: &str, v: &Value) -> Result<(), String> {
+        if !self.config.strict_fields {
+            return Ok(());
+        }
+        if let crate::core::heap::ManagedObject::Struct(s) = self.heap.get(obj.as_obj_id()) {
+            if let Some(def) = self.types.structs.get(s.ty.as_str()) {
+                if let Some(f) = def.fields.iter().find(|f| f.name == field) {
+                    return self.check_field_type(&s.ty, f, v);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 字段声明的类型不是运行时能识别的类型（如类型参数）时不检查
+    fn check_field_type(&self, ty: &str, f: &xu_ir::StructField, v: &Value) -> Result<(), String> {
+        let expected = f.ty.name.as_str();
+        let known = matches!(
+            expected,
+            "int" | "float" | "string" | "text" | "str" | "bool" | "?" | "list" | "dict" | "tuple" | "range" | "file"
+                | "channel" | "unit" | "Option"
+        ) || self.types.structs.contains_key(expected)
+            || self.types.enums.contains_key(expected);
+        if !known || type_matches(expected, v, &self.heap) {
+            return Ok(());
+        }
+        // 与检查阶段的报错一致，文本类型统一叫 text
+        let name = |t: &str| if matches!(t, "string" | "str") { "text".to_string() } else { t.to_string() };
+        Err(self.error(xu_syntax::DiagnosticKind::FieldTypeMismatch {
+            ty: ty.to_string(),
+            field: f.name.clone(),
+            expected: name(expected),
+            actual: name(v.type_name()),
+        }))
+    }
+
     pub(crate) fn assign_index(
         &mut self,
         obj: Value,
@@ -715,18 +791,18 @@ impl Runtime {
                 if let Some((key_hash, map_hash)) = cached_hash {
                     // Cache hit - use cached hash, skip string hash computation
                     if let crate::core::heap::ManagedObject::Dict(me) = self.heap_get_mut(id) {
-                        use indexmap::map::RawEntryApiV1;
-                        match me.map.raw_entry_mut_v1().from_hash(map_hash, |dk| {
+                        use crate::core::dict_map::DictEntry;
+                        match me.map.entry_by_hash(map_hash, |dk| {
                             if let DictKey::StrRef { hash: h, .. } = dk {
                                 *h == key_hash
                             } else {
                                 false
                             }
                         }) {
-                            indexmap::map::raw_entry_v1::RawEntryMut::Occupied(mut o) => {
-                                *o.get_mut() = rhs;
+                            DictEntry::Occupied(slot) => {
+                                *slot = rhs;
                             }
-                            indexmap::map::raw_entry_v1::RawEntryMut::Vacant(vac) => {
+                            DictEntry::Vacant(vac) => {
                                 let key = DictKey::from_str_obj(key_id, key_hash);
                                 vac.insert(key, rhs);
                             }
@@ -744,7 +820,7 @@ impl Runtime {
                 } else {
                     return Err(NOT_A_STRING.to_string());
                 };
-                let key_hash = DictKey::hash_str(&key_str);
+                let key_hash = DictKey::hash_str(&key_str, &self.heap);
 
                 // Compute map hash
                 let map_hash = if let crate::core::heap::ManagedObject::Dict(me) = self.heap.get(id) {
@@ -775,7 +851,7 @@ impl Runtime {
             let key = if idx.get_tag() == crate::core::value::TAG_STR {
                 let key_id = idx.as_obj_id();
                 let hash = if let crate::core::heap::ManagedObject::Str(s) = self.heap.get(key_id) {
-                    DictKey::hash_str(s.as_str())
+                    DictKey::hash_str(s.as_str(), &self.heap)
                 } else {
                     return Err(NOT_A_STRING.to_string());
                 };
@@ -784,13 +860,13 @@ impl Runtime {
             } else if idx.is_int() {
                 DictKey::Int(idx.as_i64())
             } else {
-                return Err(self.error(xu_syntax::DiagnosticKind::DictKeyRequired));
+                crate::methods::get_dict_key_from_value(self, &idx)?
             };
 
             // Fast path for simple assignment
             if op == AssignOp::Set {
                 if let crate::core::heap::ManagedObject::Dict(me) = self.heap_get_mut(id) {
-                    me.map.insert(key, rhs);
+                    me.insert_entry(key, rhs);
                     me.ver += 1;
                     self.caches.dict_version_last = Some((id.0, me.ver));
                 }
@@ -800,14 +876,15 @@ impl Runtime {
             // Compound assignment needs old value
             let mut prev = None;
             if let crate::core::heap::ManagedObject::Dict(me) = self.heap.get(id) {
-                prev = me.map.get(&key).cloned();
+                prev = me.get_entry(&key);
             }
 
             let v = self.apply_assign_op(prev, op, rhs)?;
 
             if let crate::core::heap::ManagedObject::Dict(me) = self.heap_get_mut(id) {
-                let prev = me.map.insert(key, v);
-                if prev.as_ref() != Some(&v) {
+                let old = me.get_entry(&key);
+                me.insert_entry(key, v);
+                if old != Some(v) {
                     me.ver += 1;
                     self.caches.dict_version_last = Some((id.0, me.ver));
                 }
@@ -0,0 +1,58 @@
+//! `channel(capacity)` and `recv_any`: queues of copied values that can be
+//! sent to workers, so any runtime holding one can send and receive.
+//!
+//! Methods are in `methods/channel.rs`; the queue itself is
+//! [`crate::core::channel::Channel`].
+
+use std::sync::Arc;
+
+use super::super::Runtime;
+use crate::Value;
+use crate::core::channel::Channel;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{TAG_CHANNEL, TAG_LIST};
+
+pub fn builtin_channel(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    let capacity = match args {
+        [] => None,
+        [n] if n.is_int() && n.as_i64() >= 1 => Some(n.as_i64() as usize),
+        [n] => return Err(format!("channel: capacity must be a positive int, got {}", crate::util::value_to_string(n, &rt.heap))),
+        _ => return Err("channel expects 0 or 1 arguments".into()),
+    };
+    Ok(Value::channel(rt.alloc(ManagedObject::Channel(Arc::new(Channel::new(capacity))))))
+}
+
+/// Waits for a message on any of the channels and returns
+/// `Option#some((index, value))`, taking from the first ready channel in
+/// list order; none once all of them are closed and drained.
+pub fn builtin_recv_any(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("recv_any expects 1 argument".into());
+    }
+    let items = match (args[0].get_tag(), rt.heap.object(args[0])) {
+        (TAG_LIST, Some(ManagedObject::List(items))) => items.to_vec(),
+        _ => return Err(format!("recv_any expects a list of channels, got {}", args[0].type_name())),
+    };
+    let mut channels = Vec::with_capacity(items.len());
+    for v in &items {
+        match (v.get_tag(), rt.heap.object(*v)) {
+            (TAG_CHANNEL, Some(ManagedObject::Channel(ch))) => channels.push(ch.clone()),
+            _ => return Err(format!("recv_any expects a list of channels, got a {}", v.type_name())),
+        }
+    }
+    if channels.is_empty() {
+        return Ok(rt.option_none());
+    }
+    // As in `recv`: if no other thread holds any of them, waiting never ends.
+    if !channels.iter().any(|ch| ch.is_ready() || Arc::strong_count(ch) > 2) {
+        return Err("recv_any: every channel is empty and no other thread can send to them".into());
+    }
+    match Channel::recv_any(&channels) {
+        Some((i, msg)) => {
+            let v = msg.into_value(rt)?;
+            let pair = Value::tuple(rt.alloc(ManagedObject::Tuple(vec![Value::from_i64(i as i64), v])));
+            Ok(rt.option_some(pair))
+        }
+        None => Ok(rt.option_none()),
+    }
+}
@@ -83,18 +83,18 @@ pub fn builtin_set_from_list(rt: &mut Runtime, args: &[Value]) -> Result<Value,
         return Err("__set_from_list expects list".into());
     }
     let items = if let crate::core::heap::ManagedObject::List(items) = rt.heap.get(list.as_obj_id()) {
-        items.clone()
+        items.to_vec()
     } else {
         return Err("__set_from_list expects list".into());
     };
 
-    let mut dict = crate::core::value::dict_with_capacity(items.len());
+    let mut dict = crate::core::value::dict_with_capacity(items.len(), &rt.heap);
     for item in items {
         let key = if item.get_tag() == crate::core::value::TAG_STR {
             let key_id = item.as_obj_id();
             if let crate::core::heap::ManagedObject::Str(s) = rt.heap.get(key_id) {
                 // Use ObjectId directly - no string copy!
-                crate::core::value::DictKey::from_str_obj(key_id, crate::core::value::DictKey::hash_str(s.as_str()))
+                crate::core::value::DictKey::from_str_obj(key_id, crate::core::value::DictKey::hash_str(s.as_str(), &rt.heap))
             } else {
                 return Err("Invalid set item".into());
             }
@@ -108,3 +108,19 @@ pub fn builtin_set_from_list(rt: &mut Runtime, args: &[Value]) -> Result<Value,
 
     Ok(Value::dict(rt.alloc(crate::core::heap::ManagedObject::Dict(dict))))
 }
+
+/// Largest capacity hint honored by `dict(capacity: n)`; larger hints are clamped
+const DICT_CAPACITY_HINT_MAX: i64 = 1 << 24;
+
+/// `dict(capacity?)`: an empty dict with room for `capacity` entries
+pub fn builtin_dict(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() > 1 {
+        return Err("dict expects at most 1 argument".into());
+    }
+    let n = args.first().copied().unwrap_or(Value::from_i64(0));
+    if !n.is_int() || n.as_i64() < 0 {
+        return Err("dict(capacity:) expects a non-negative int".into());
+    }
+    let dict = crate::core::value::dict_with_capacity(n.as_i64().min(DICT_CAPACITY_HINT_MAX) as usize, &rt.heap);
+    Ok(Value::dict(rt.alloc(crate::core::heap::ManagedObject::Dict(dict))))
+}
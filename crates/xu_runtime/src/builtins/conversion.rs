@@ -83,14 +83,35 @@ pub fn builtin_to_text(rt: &mut Runtime, args: &[Value]) -> Result<Value, String
             "false".into()
         }
     } else if v.is_f64() {
-        let f = v.as_f64();
-        if f.fract() == 0.0 {
-            i64_to_text_fast(f as i64)
-        } else {
-            f.to_string().into()
-        }
+        crate::util::FloatBuf::new(v.as_f64(), &rt.heap.float_format).as_str().into()
     } else {
         super::super::util::value_to_string(v, &rt.heap).into()
     };
     Ok(Value::str(rt.alloc(crate::core::heap::ManagedObject::Str(s))))
 }
+
+/// `inspect(v, pretty, max_depth, max_items)`; options left out, or passed
+/// as `()` by a labeled call that skips them, keep their defaults.
+pub fn builtin_inspect(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 4 {
+        return Err("inspect expects 1 to 4 arguments".into());
+    }
+    let mut opts = crate::util::PrettyOptions::default();
+    if let Some(p) = args.get(1).filter(|p| !p.is_unit()) {
+        if !p.is_bool() {
+            return Err("inspect(pretty:) expects a bool".into());
+        }
+        opts.pretty = p.as_bool();
+    }
+    for (i, slot) in [(2, &mut opts.max_depth), (3, &mut opts.max_items)] {
+        if let Some(n) = args.get(i).filter(|n| !n.is_unit()) {
+            if !n.is_int() || n.as_i64() < 0 {
+                let label = if i == 2 { "max_depth" } else { "max_items" };
+                return Err(format!("inspect({label}:) expects a non-negative int"));
+            }
+            *slot = n.as_i64() as usize;
+        }
+    }
+    let s = rt.inspect(&args[0], &opts);
+    Ok(Value::str(rt.alloc(crate::core::heap::ManagedObject::Str(s.into()))))
+}
@@ -1,5 +1,3 @@
-use std::io::BufRead;
-
 use super::super::Runtime;
 use super::super::util::value_to_string;
 use crate::Value;
@@ -11,6 +9,59 @@ pub fn builtin_print(rt: &mut Runtime, args: &[Value]) -> Result<Value, String>
     Ok(Value::UNIT)
 }
 
+/// `eprint(...)` writes each value on its own line to standard error.
+pub fn builtin_eprint(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    for a in args {
+        let text = value_to_string(a, &rt.heap);
+        rt.write_error(&text);
+    }
+    Ok(Value::UNIT)
+}
+
+/// `exit(code = 0)` ends the program with `code`. It unwinds as an error
+/// that only the top of the program turns back into the code, so running
+/// callers and modules stop where they are.
+pub fn builtin_exit(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() > 1 {
+        return Err("exit expects 0 or 1 argument".into());
+    }
+    let code = match args.first() {
+        None => 0,
+        Some(v) if v.is_int() && (0..=255).contains(&v.as_i64()) => v.as_i64() as i32,
+        Some(v) if v.is_int() => return Err(format!("exit: code must be from 0 to 255, got {}", v.as_i64())),
+        Some(v) => return Err(format!("exit expects an int code, got {}", v.type_name())),
+    };
+    rt.exit_request = Some(code);
+    Err(crate::runtime::exit_message(code))
+}
+
+/// `panic(message)` fails the program with `message`, reported as a panic
+/// rather than a runtime error.
+pub fn builtin_panic(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("panic expects 1 argument".into());
+    }
+    rt.panicked = true;
+    Err(format!("panic: {}", value_to_string(&args[0], &rt.heap)))
+}
+
+/// `ok(value = ())` builds `Result#ok(value)`.
+pub fn builtin_ok(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() > 1 {
+        return Err("ok expects 0 or 1 argument".into());
+    }
+    let value = args.first().copied().unwrap_or(Value::UNIT);
+    rt.enum_new_checked("Result", "ok", Box::new([value]))
+}
+
+/// `err(error)` builds `Result#err(error)`.
+pub fn builtin_err(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("err expects 1 argument".into());
+    }
+    rt.enum_new_checked("Result", "err", Box::new([args[0]]))
+}
+
 pub fn builtin_gen_id(rt: &mut Runtime, _args: &[Value]) -> Result<Value, String> {
     let id = rt.types.next_id;
     rt.types.next_id = rt.types.next_id.saturating_add(1);
@@ -20,19 +71,30 @@ pub fn builtin_gen_id(rt: &mut Runtime, _args: &[Value]) -> Result<Value, String
 pub fn builtin_gc(rt: &mut Runtime, _args: &[Value]) -> Result<Value, String> {
     rt.gc(&[]);
     // Try to release memory back to OS
-    #[cfg(target_os = "linux")]
+    #[cfg(all(feature = "sys", target_os = "linux"))]
     unsafe {
         libc::malloc_trim(0);
     }
     // Force a thread yield to allow OS to reclaim memory
+    #[cfg(feature = "sys")]
     std::thread::sleep(std::time::Duration::from_millis(10));
     Ok(Value::UNIT)
 }
 
+/// `open(path)` opens for reading; `open(path, "w")` for writing, creating or
+/// truncating the file on the first `write`.
 pub fn builtin_open(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err("open expects 1 argument".into());
+    if args.is_empty() || args.len() > 2 {
+        return Err("open expects 1 or 2 arguments".into());
     }
+    let writable = match args.get(1) {
+        None => false,
+        Some(mode) => match text_arg(rt, mode).as_deref() {
+            Some("r") => false,
+            Some("w") => true,
+            _ => return Err("open mode must be \"r\" or \"w\"".into()),
+        },
+    };
     let path = if args[0].get_tag() == crate::core::value::TAG_STR {
         if let crate::core::heap::ManagedObject::Str(s) = rt.heap.get(args[0].as_obj_id()) {
             s.to_string()
@@ -42,27 +104,132 @@ pub fn builtin_open(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
     } else {
         return Err("open expects text".into());
     };
-    rt.fs_metadata(&path)?;
+    if !writable {
+        rt.fs_metadata(&path)?;
+    }
     Ok(Value::file(rt.alloc(crate::core::heap::ManagedObject::File(
         Box::new(crate::core::value::FileHandle {
             path,
             open: true,
-            content: "".to_string(),
+            writable,
+            written: false,
         }),
     ))))
 }
 
-pub fn builtin_input(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
-    if args.len() > 1 {
-        return Err("input expects 0 or 1 argument".into());
+fn text_arg(rt: &Runtime, v: &Value) -> Option<String> {
+    if v.get_tag() != crate::core::value::TAG_STR {
+        return None;
     }
-    if let Some(prompt) = args.first() {
-        rt.write_output(&value_to_string(prompt, &rt.heap));
+    match rt.heap.get(v.as_obj_id()) {
+        crate::core::heap::ManagedObject::Str(s) => Some(s.to_string()),
+        _ => None,
     }
-    let mut line = String::new();
-    let mut stdin = std::io::stdin().lock();
-    let _ = stdin.read_line(&mut line);
-    Ok(Value::str(rt.alloc(crate::core::heap::ManagedObject::Str(
-        line.trim_end_matches(['\n', '\r']).to_string().into(),
-    ))))
+}
+
+/// `help(name)` prints the signature and doc comment of a builtin, or of a
+/// function given by value or by (`module.`)name.
+pub fn builtin_help(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("help expects 1 argument".into());
+    }
+    let text = if let Some(name) = text_arg(rt, &args[0]) {
+        match xu_syntax::builtin_doc(&name) {
+            Some(d) => format_help(d.signature, Some(d.doc)),
+            None => match lookup_path(rt, &name) {
+                Some(v) => describe_function(rt, v, &name)?,
+                None => format!("No help for '{name}'"),
+            },
+        }
+    } else if args[0].get_tag() == crate::core::value::TAG_FUNC {
+        describe_function(rt, args[0], "function")?
+    } else {
+        return Err("help expects a name or a function".into());
+    };
+    rt.write_output(&text);
+    Ok(Value::UNIT)
+}
+
+/// Resolves `name` or `module.name` in the global scope.
+fn lookup_path(rt: &Runtime, path: &str) -> Option<Value> {
+    let mut parts = path.split('.');
+    let mut v = rt.env.get(parts.next()?)?;
+    for part in parts {
+        if v.get_tag() != crate::core::value::TAG_MODULE {
+            return None;
+        }
+        let crate::core::heap::ManagedObject::Module(m) = rt.heap.get(v.as_obj_id()) else {
+            return None;
+        };
+        v = *m.exports.map.get(part)?;
+    }
+    Some(v)
+}
+
+fn describe_function(rt: &Runtime, v: Value, name: &str) -> Result<String, String> {
+    if v.get_tag() != crate::core::value::TAG_FUNC {
+        return Ok(format!("'{name}' is not a function ({})", v.type_name()));
+    }
+    let def = match rt.heap.get(v.as_obj_id()) {
+        crate::core::heap::ManagedObject::Function(crate::core::value::Function::User(f)) => &f.def,
+        crate::core::heap::ManagedObject::Function(crate::core::value::Function::Bytecode(f)) => &f.def,
+        _ => {
+            // Builtins are not named by their value; find the global bound to it.
+            let builtin = xu_syntax::BUILTIN_NAMES.iter().find(|n| rt.env.get(n) == Some(v));
+            return Ok(match builtin.and_then(|n| xu_syntax::builtin_doc(n)) {
+                Some(d) => format_help(d.signature, Some(d.doc)),
+                None => format!("{name}: builtin without documentation"),
+            });
+        }
+    };
+    Ok(format_help(&func_signature(def), def.doc.as_deref()))
+}
+
+fn format_help(signature: &str, doc: Option<&str>) -> String {
+    let mut out = signature.to_string();
+    for line in doc.unwrap_or("(no documentation)").lines() {
+        out.push_str("\n  ");
+        out.push_str(line);
+    }
+    out
+}
+
+/// `func name(a: int, b: text = "x") -> int`, written the way it is declared.
+fn func_signature(def: &xu_ir::FuncDef) -> String {
+    fn ty(t: &xu_ir::TypeRef) -> String {
+        if t.params.is_empty() {
+            t.name.clone()
+        } else {
+            let inner: Vec<String> = t.params.iter().map(ty).collect();
+            format!("{}[{}]", t.name, inner.join(", "))
+        }
+    }
+    let params: Vec<String> = def
+        .params
+        .iter()
+        .map(|p| {
+            let mut s = p.name.clone();
+            if let Some(t) = &p.ty {
+                s.push_str(": ");
+                s.push_str(&ty(t));
+            }
+            if let Some(d) = &p.default {
+                s.push_str(" = ");
+                s.push_str(&match d {
+                    xu_ir::Expr::Int(i) => i.to_string(),
+                    xu_ir::Expr::Float(f) => format!("{f:?}"),
+                    xu_ir::Expr::Bool(b) => b.to_string(),
+                    xu_ir::Expr::Str(s) => format!("{s:?}"),
+                    _ => "...".to_string(),
+                });
+            }
+            s
+        })
+        .collect();
+    let mut sig = format!("func {}({})", def.name, params.join(", "));
+    if let Some(r) = &def.return_ty {
+        sig.push_str(" -> ");
+        sig.push_str(&ty(r));
+    }
+    sig
 }
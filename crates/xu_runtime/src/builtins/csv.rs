@@ -0,0 +1,333 @@
+//! Hooks behind `std/csv`: RFC 4180 records with `"` quoting.
+//!
+//! Fields are separated by `,` and records by `\n`, `\r\n` or `\r`. A quoted
+//! field may contain separators, line breaks and `""` for a literal quote;
+//! blank lines are skipped. A reader parses its file a chunk at a time.
+//! Writing quotes exactly the fields that need it and ends every record with
+//! `\n`.
+
+use super::super::Runtime;
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{DictKey, TAG_DICT, TAG_LIST, TAG_STR, TAG_STRUCT};
+
+/// Outcome of [`parse_record`].
+enum Record {
+    /// The fields and the position after the record.
+    Fields(Vec<String>, usize),
+    /// No record is left.
+    End,
+    /// The text ends inside a record, or may go on with the next chunk.
+    More,
+}
+
+/// Parses the record starting at byte `pos`, skipping blank lines first.
+/// `at_end` tells whether `text` is all the input; if not, a record that
+/// reaches the end of `text` is [`Record::More`]. `line` is the line number
+/// of the start of `text`, for errors.
+fn parse_record(text: &str, mut pos: usize, at_end: bool, line: usize) -> Result<Record, String> {
+    let bytes = text.as_bytes();
+    while pos < bytes.len() && (bytes[pos] == b'\n' || bytes[pos] == b'\r') {
+        pos += 1;
+    }
+    if pos >= bytes.len() {
+        return Ok(if at_end { Record::End } else { Record::More });
+    }
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut i = pos;
+    loop {
+        if quoted {
+            let Some(rel) = text[i..].find('"') else {
+                if !at_end {
+                    return Ok(Record::More);
+                }
+                let line = line + text[..pos].matches('\n').count();
+                return Err(format!("csv: unterminated quoted field in the record on line {line}"));
+            };
+            field.push_str(&text[i..i + rel]);
+            i += rel + 1;
+            if bytes.get(i) == Some(&b'"') {
+                field.push('"');
+                i += 1;
+            } else if i == bytes.len() && !at_end {
+                // The next chunk may start with the second quote of `""`.
+                return Ok(Record::More);
+            } else {
+                quoted = false;
+            }
+            continue;
+        }
+        match bytes.get(i) {
+            None if !at_end => return Ok(Record::More),
+            None => {
+                fields.push(field);
+                return Ok(Record::Fields(fields, i));
+            }
+            Some(b'\n') | Some(b'\r') => {
+                fields.push(field);
+                if bytes[i] == b'\r' && bytes.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                return Ok(Record::Fields(fields, i + 1));
+            }
+            Some(b',') => {
+                fields.push(std::mem::take(&mut field));
+                i += 1;
+            }
+            Some(b'"') if field.is_empty() => {
+                quoted = true;
+                i += 1;
+            }
+            Some(_) => {
+                // Up to the next separator; quotes inside an unquoted field
+                // and text after a closing quote are kept as they are.
+                let rel = text[i..].find([',', '\n', '\r']).unwrap_or(text.len() - i);
+                field.push_str(&text[i..i + rel]);
+                i += rel;
+            }
+        }
+    }
+}
+
+fn text_of(rt: &Runtime, v: &Value) -> Option<String> {
+    if v.get_tag() == TAG_STR {
+        if let ManagedObject::Str(s) = rt.heap.get(v.as_obj_id()) {
+            return Some(s.as_str().to_string());
+        }
+    }
+    None
+}
+
+fn str_value(rt: &mut Runtime, s: &str) -> Value {
+    Value::str(rt.alloc(ManagedObject::Str(s.into())))
+}
+
+fn list_value(rt: &mut Runtime, fields: &[String]) -> Value {
+    let items = fields.iter().map(|f| str_value(rt, f)).collect();
+    Value::list(rt.alloc(ManagedObject::List(items)))
+}
+
+/// A record as a dict keyed by `header`, in header order. Missing trailing
+/// fields are empty; extra fields are an error.
+fn dict_value(rt: &mut Runtime, header: &[Value], fields: &[String], record: usize) -> Result<Value, String> {
+    if fields.len() > header.len() {
+        return Err(format!(
+            "csv: record {record} has {} fields, the header has {}",
+            fields.len(),
+            header.len()
+        ));
+    }
+    let mut dict = crate::core::value::dict_with_capacity(header.len(), &rt.heap);
+    for (i, name) in header.iter().enumerate() {
+        let key = match rt.heap.get(name.as_obj_id()) {
+            ManagedObject::Str(s) => DictKey::from_str_obj(name.as_obj_id(), DictKey::hash_str(s.as_str(), &rt.heap)),
+            _ => return Err("csv: header fields must be text".into()),
+        };
+        let value = str_value(rt, fields.get(i).map_or("", String::as_str));
+        dict.insert_entry(key, value);
+    }
+    Ok(Value::dict(rt.alloc(ManagedObject::Dict(dict))))
+}
+
+fn list_items(rt: &Runtime, v: &Value) -> Option<Vec<Value>> {
+    if v.get_tag() == TAG_LIST {
+        if let ManagedObject::List(items) = rt.heap.get(v.as_obj_id()) {
+            return Some(items.to_vec());
+        }
+    }
+    None
+}
+
+pub fn builtin_csv_parse(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("csv.parse expects 2 arguments".into());
+    }
+    let text = text_of(rt, &args[0]).ok_or_else(|| format!("csv.parse expects text, got {}", args[0].type_name()))?;
+    let headers = args[1].is_bool() && args[1].as_bool();
+    let mut pos = 0;
+    let mut header: Option<Vec<Value>> = None;
+    let mut rows = Vec::new();
+    while let Record::Fields(fields, next) = parse_record(&text, pos, true, 1)? {
+        pos = next;
+        if !headers {
+            rows.push(list_value(rt, &fields));
+        } else if let Some(header) = &header {
+            rows.push(dict_value(rt, header, &fields, rows.len() + 2)?);
+        } else {
+            header = Some(fields.iter().map(|f| str_value(rt, f)).collect());
+        }
+    }
+    Ok(Value::list(rt.alloc(ManagedObject::List(rows.into()))))
+}
+
+/// Index and value of the field `name` of a struct instance.
+fn struct_field(rt: &Runtime, v: &Value, name: &str) -> Result<(usize, Value), String> {
+    if v.get_tag() == TAG_STRUCT {
+        if let ManagedObject::Struct(s) = rt.heap.get(v.as_obj_id()) {
+            if let Some(i) = s.field_names.iter().position(|n| n == name) {
+                return Ok((i, s.fields[i]));
+            }
+        }
+    }
+    Err(format!("csv reader: missing field {name}"))
+}
+
+fn set_struct_field(rt: &mut Runtime, v: &Value, index: usize, value: Value) {
+    let id = v.as_obj_id();
+    if let ManagedObject::Struct(s) = rt.heap_get_mut(id) {
+        s.fields[index] = value;
+    }
+    rt.write_barrier(id);
+}
+
+/// Bytes a reader asks its file for at a time.
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Reads the next record of a `csv.Reader`. The reader keeps the text read
+/// from its file but not yet parsed in `buf` from `pos` on, and reads the
+/// next chunk at byte `offset` of the file when a record runs past the end
+/// of it, so a record may span any number of chunks and lines. With
+/// `headers` the first record fills `header` and rows are dicts.
+pub fn builtin_csv_next(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("csv reader expects 1 argument".into());
+    }
+    let reader = args[0];
+    let (_, file) = struct_field(rt, &reader, "file")?;
+    let path = match rt.heap.object(file) {
+        Some(ManagedObject::File(h)) if h.open => h.path.clone(),
+        Some(ManagedObject::File(_)) => return Err("csv reader: the file is closed".into()),
+        _ => return Err(format!("csv reader: file must be a file, got {}", file.type_name())),
+    };
+    let (buf_index, mut buf) = struct_field(rt, &reader, "buf")?;
+    let (pos_index, pos) = struct_field(rt, &reader, "pos")?;
+    let (offset_index, offset) = struct_field(rt, &reader, "offset")?;
+    let (line_index, line) = struct_field(rt, &reader, "line")?;
+    let (eof_index, eof) = struct_field(rt, &reader, "eof")?;
+    let (_, headers) = struct_field(rt, &reader, "headers")?;
+    let (header_index, header) = struct_field(rt, &reader, "header")?;
+    let (count_index, count) = struct_field(rt, &reader, "count")?;
+    if buf.get_tag() != TAG_STR {
+        return Err("csv reader: buf must be text".into());
+    }
+    let mut pos = usize::try_from(pos.as_i64()).unwrap_or(0);
+    let mut offset = u64::try_from(offset.as_i64()).unwrap_or(0);
+    let mut line = usize::try_from(line.as_i64()).unwrap_or(1);
+    let mut eof = eof.is_bool() && eof.as_bool();
+    let mut header = list_items(rt, &header).unwrap_or_default();
+    let headers = headers.is_bool() && headers.as_bool();
+    // Parse in place: copying the buffer on every call would make reading
+    // quadratic in the file size.
+    let next_record = |rt: &Runtime, buf: Value, pos: usize, eof: bool, line: usize| match rt.heap.get(buf.as_obj_id()) {
+        ManagedObject::Str(s) => parse_record(s.as_str(), pos.min(s.as_str().len()), eof, line),
+        _ => Ok(Record::End),
+    };
+    let fields = loop {
+        match next_record(rt, buf, pos, eof, line)? {
+            Record::Fields(fields, next) => {
+                pos = next;
+                if headers && header.is_empty() {
+                    let value = list_value(rt, &fields);
+                    header = list_items(rt, &value).unwrap_or_default();
+                    set_struct_field(rt, &reader, header_index, value);
+                    continue;
+                }
+                break Some(fields);
+            }
+            Record::End => break None,
+            Record::More => {
+                // 丢弃已解析的部分，把未完成的记录与下一块拼接
+                let chunk = rt.fs_read_chunk(&path, offset, READ_CHUNK)?;
+                let ManagedObject::Str(s) = rt.heap.get(buf.as_obj_id()) else {
+                    break None;
+                };
+                let rest = &s.as_str()[pos.min(s.as_str().len())..];
+                line += s.as_str()[..s.as_str().len() - rest.len()].matches('\n').count();
+                let mut text = String::with_capacity(rest.len() + chunk.len());
+                text.push_str(rest);
+                text.push_str(&chunk);
+                buf = str_value(rt, &text);
+                pos = 0;
+                offset += chunk.len() as u64;
+                eof = chunk.is_empty();
+            }
+        }
+    };
+    set_struct_field(rt, &reader, buf_index, buf);
+    set_struct_field(rt, &reader, pos_index, Value::from_i64(pos as i64));
+    set_struct_field(rt, &reader, offset_index, Value::from_i64(offset as i64));
+    set_struct_field(rt, &reader, line_index, Value::from_i64(line as i64));
+    set_struct_field(rt, &reader, eof_index, Value::from_bool(eof));
+    let Some(fields) = fields else {
+        return Ok(rt.option_none());
+    };
+    let count = count.as_i64() + 1;
+    let row = if headers {
+        dict_value(rt, &header, &fields, count as usize + 1)?
+    } else {
+        list_value(rt, &fields)
+    };
+    set_struct_field(rt, &reader, count_index, Value::from_i64(count));
+    Ok(rt.option_some(row))
+}
+
+fn push_field(out: &mut String, field: &str) {
+    if field.contains([',', '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&field.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(field);
+    }
+}
+
+fn push_record(out: &mut String, fields: &[String]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_field(out, field);
+    }
+    out.push('\n');
+}
+
+/// Text of a field value: texts as they are, anything else as `println`
+/// shows it.
+fn field_text(rt: &Runtime, v: &Value) -> String {
+    text_of(rt, v).unwrap_or_else(|| crate::util::value_to_string(v, &rt.heap))
+}
+
+pub fn builtin_csv_write(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("csv.write expects 1 argument".into());
+    }
+    let rows = list_items(rt, &args[0]).ok_or_else(|| format!("csv.write expects a list of rows, got {}", args[0].type_name()))?;
+    let mut out = String::new();
+    // Rows that are dicts share the keys of the first one as header.
+    let mut header: Option<Vec<(DictKey, String)>> = None;
+    for row in &rows {
+        match (row.get_tag(), rt.heap.object(*row)) {
+            (TAG_LIST, Some(ManagedObject::List(items))) => {
+                let fields: Vec<String> = items.iter().map(|v| field_text(rt, v)).collect();
+                push_record(&mut out, &fields);
+            }
+            (TAG_DICT, Some(ManagedObject::Dict(d))) => {
+                let keys = header.get_or_insert_with(|| {
+                    let keys: Vec<_> = d.entries().map(|(k, _)| (k, field_text(rt, &k.to_value(&rt.heap)))).collect();
+                    let names: Vec<String> = keys.iter().map(|(_, name)| name.clone()).collect();
+                    push_record(&mut out, &names);
+                    keys
+                });
+                let fields: Vec<String> = keys
+                    .iter()
+                    .map(|(k, _)| d.get_entry(k).map(|v| field_text(rt, &v)).unwrap_or_default())
+                    .collect();
+                push_record(&mut out, &fields);
+            }
+            _ => return Err(format!("csv.write: rows must be lists or dicts, got {}", row.type_name())),
+        }
+    }
+    Ok(str_value(rt, &out))
+}
@@ -0,0 +1,243 @@
+//! Text encodings and binary-to-text helpers.
+//!
+//! Byte strings are lists of ints in `0..=255`. `text.to_bytes(encoding)` and
+//! `bytes.to_string(encoding)` convert between text and bytes; the
+//! `base64_*` and `hex_*` builtins encode bytes (or the UTF-8 bytes of a
+//! text) as text and back.
+
+use super::super::Runtime;
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{TAG_LIST, TAG_STR};
+
+/// A text encoding accepted by `to_bytes` and `to_string`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Ascii,
+}
+
+impl Encoding {
+    /// Parses an encoding name; case, `-` and `_` are ignored, so `"UTF-8"`,
+    /// `"utf8"` and `"utf_8"` are the same.
+    pub(crate) fn parse(name: &str) -> Result<Self, String> {
+        let key: String = name
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        match key.as_str() {
+            "utf8" => Ok(Self::Utf8),
+            "utf16" | "utf16le" => Ok(Self::Utf16Le),
+            "utf16be" => Ok(Self::Utf16Be),
+            "latin1" | "iso88591" => Ok(Self::Latin1),
+            "ascii" | "usascii" => Ok(Self::Ascii),
+            _ => Err(format!(
+                "Unknown encoding: {name} (expected utf-8, utf-16le, utf-16be, latin1 or ascii)"
+            )),
+        }
+    }
+
+    pub(crate) fn encode(self, s: &str) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Utf8 => Ok(s.as_bytes().to_vec()),
+            Self::Utf16Le => Ok(s.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Self::Utf16Be => Ok(s.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+            Self::Latin1 | Self::Ascii => {
+                let limit = if self == Self::Latin1 { 0xFF } else { 0x7F };
+                s.chars()
+                    .map(|c| {
+                        u8::try_from(c as u32)
+                            .ok()
+                            .filter(|b| u32::from(*b) <= limit)
+                            .ok_or_else(|| format!("Character {c:?} cannot be encoded as {}", self.name()))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub(crate) fn decode(self, bytes: &[u8]) -> Result<String, String> {
+        match self {
+            Self::Utf8 => String::from_utf8(bytes.to_vec())
+                .map_err(|e| format!("Invalid utf-8 at byte {}", e.utf8_error().valid_up_to())),
+            Self::Utf16Le | Self::Utf16Be => {
+                if bytes.len() % 2 != 0 {
+                    return Err(format!("Odd number of bytes for {}", self.name()));
+                }
+                let units = bytes.chunks_exact(2).map(|p| {
+                    let pair = [p[0], p[1]];
+                    if self == Self::Utf16Le {
+                        u16::from_le_bytes(pair)
+                    } else {
+                        u16::from_be_bytes(pair)
+                    }
+                });
+                char::decode_utf16(units)
+                    .map(|r| r.map_err(|e| format!("Unpaired surrogate {:#06x} in {}", e.unpaired_surrogate(), self.name())))
+                    .collect()
+            }
+            Self::Latin1 => Ok(bytes.iter().map(|&b| char::from(b)).collect()),
+            Self::Ascii => bytes
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii() {
+                        Ok(char::from(b))
+                    } else {
+                        Err(format!("Byte {b} is not ascii"))
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Latin1 => "latin1",
+            Self::Ascii => "ascii",
+        }
+    }
+}
+
+/// The bytes of a byte list, or the UTF-8 bytes of a text.
+pub(crate) fn bytes_arg(rt: &Runtime, v: &Value, what: &str) -> Result<Vec<u8>, String> {
+    if v.get_tag() == TAG_STR {
+        if let ManagedObject::Str(s) = rt.heap.get(v.as_obj_id()) {
+            return Ok(s.as_str().as_bytes().to_vec());
+        }
+    }
+    if v.get_tag() == TAG_LIST {
+        if let ManagedObject::List(items) = rt.heap.get(v.as_obj_id()) {
+            return items
+                .iter()
+                .map(|item| {
+                    if item.is_int() {
+                        u8::try_from(item.as_i64()).map_err(|_| format!("{what}: byte {} out of range 0..255", item.as_i64()))
+                    } else {
+                        Err(format!("{what}: bytes must be ints, got {}", item.type_name()))
+                    }
+                })
+                .collect();
+        }
+    }
+    Err(format!("{what} expects text or a byte list, got {}", v.type_name()))
+}
+
+pub(crate) fn bytes_value(rt: &mut Runtime, bytes: &[u8]) -> Value {
+    let items = bytes.iter().map(|&b| Value::from_i64(i64::from(b))).collect();
+    Value::list(rt.alloc(ManagedObject::List(items)))
+}
+
+fn text_arg(rt: &Runtime, v: &Value, what: &str) -> Result<String, String> {
+    if v.get_tag() == TAG_STR {
+        if let ManagedObject::Str(s) = rt.heap.get(v.as_obj_id()) {
+            return Ok(s.as_str().to_string());
+        }
+    }
+    Err(format!("{what} expects text, got {}", v.type_name()))
+}
+
+fn text_value(rt: &mut Runtime, s: String) -> Value {
+    Value::str(rt.alloc(ManagedObject::Str(s.into())))
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn builtin_base64_encode(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("base64_encode expects 1 argument".into());
+    }
+    let bytes = bytes_arg(rt, &args[0], "base64_encode")?;
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (u32::from(b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(BASE64_ALPHABET[((n >> (18 - 6 * i)) & 0x3F) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    Ok(text_value(rt, out))
+}
+
+pub fn builtin_base64_decode(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("base64_decode expects 1 argument".into());
+    }
+    let text = text_arg(rt, &args[0], "base64_decode")?;
+    // Line breaks are allowed, as in MIME; padding is optional.
+    let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    let data = match digits.iter().position(|&b| b == b'=') {
+        Some(pad) => {
+            if digits.len() % 4 != 0 || digits.len() - pad > 2 || digits[pad..].iter().any(|&b| b != b'=') {
+                return Err("base64_decode: invalid padding".into());
+            }
+            &digits[..pad]
+        }
+        None => &digits[..],
+    };
+    if data.len() % 4 == 1 {
+        return Err("base64_decode: truncated input".into());
+    }
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut n = 0u32;
+        for (i, &b) in chunk.iter().enumerate() {
+            let d = BASE64_ALPHABET
+                .iter()
+                .position(|&a| a == b)
+                .ok_or_else(|| format!("base64_decode: invalid character {:?}", char::from(b)))?;
+            n |= (d as u32) << (18 - 6 * i);
+        }
+        out.extend_from_slice(&n.to_be_bytes()[1..chunk.len()]);
+    }
+    Ok(bytes_value(rt, &out))
+}
+
+pub fn builtin_hex_encode(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("hex_encode expects 1 argument".into());
+    }
+    let bytes = bytes_arg(rt, &args[0], "hex_encode")?;
+    Ok(text_value(rt, hex_string(&bytes)))
+}
+
+/// Lowercase hex digits of `bytes`.
+pub(crate) fn hex_string(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        out.push(char::from(DIGITS[usize::from(b >> 4)]));
+        out.push(char::from(DIGITS[usize::from(b & 0xF)]));
+    }
+    out
+}
+
+pub fn builtin_hex_decode(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("hex_decode expects 1 argument".into());
+    }
+    let text = text_arg(rt, &args[0], "hex_decode")?;
+    if text.len() % 2 != 0 {
+        return Err("hex_decode: odd number of digits".into());
+    }
+    let digit = |b: u8| {
+        char::from(b)
+            .to_digit(16)
+            .ok_or_else(|| format!("hex_decode: invalid digit {:?}", char::from(b)))
+    };
+    let bytes = text
+        .as_bytes()
+        .chunks_exact(2)
+        .map(|p| Ok(((digit(p[0])? << 4) | digit(p[1])?) as u8))
+        .collect::<Result<Vec<u8>, String>>()?;
+    Ok(bytes_value(rt, &bytes))
+}
@@ -0,0 +1,357 @@
+//! Hooks behind `std/ffi`: calls into functions of native shared libraries.
+//!
+//! Only built with the `ffi` feature, on Unix for x86-64 and AArch64. There
+//! the C calling conventions pass integer and floating-point arguments in
+//! two separate banks of registers, so every signature is called through
+//! one function type taking six integers and eight doubles: a callee reads
+//! the registers it declares and ignores the rest. Variadic functions and
+//! structs passed by value are not supported.
+//!
+//! Every hook but `close` needs an active `ffi` grant and fails outright in
+//! a runtime with allowed roots. A signature that does not match the
+//! native function is undefined behavior, like in C.
+//!
+//! Library ids index the runtime's table from 1.
+
+use std::ffi::CString;
+
+use super::super::Runtime;
+use super::encoding::bytes_arg;
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{TAG_LIST, TAG_STR};
+
+/// Integer-class and floating-point arguments a call can pass.
+const MAX_INTS: usize = 6;
+const MAX_FLOATS: usize = 8;
+
+/// A type in a signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CType {
+    I32,
+    I64,
+    F32,
+    F64,
+    /// An address, as an int.
+    Ptr,
+    /// A NUL-terminated copy of a text.
+    Str,
+    /// A pointer to a copy of a byte list, copied back after the call.
+    Bytes,
+    Void,
+}
+
+impl CType {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "f32" => Self::F32,
+            "f64" => Self::F64,
+            "ptr" => Self::Ptr,
+            "string" => Self::Str,
+            "bytes" => Self::Bytes,
+            "void" => Self::Void,
+            _ => return None,
+        })
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+}
+
+struct Signature {
+    params: Vec<CType>,
+    ret: CType,
+}
+
+/// The libraries a runtime opened; closed ones are `None`.
+#[derive(Default)]
+pub(crate) struct Libraries {
+    libs: Vec<Option<Library>>,
+}
+
+struct Library {
+    path: String,
+    handle: native::Handle,
+}
+
+impl Runtime {
+    fn ffi_library(&self, id: i64) -> Result<&Library, String> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|i| i.checked_sub(1))
+            .and_then(|i| self.ffi.libs.get(i))
+            .and_then(Option::as_ref)
+            .ok_or_else(|| format!("ffi: no open library with id {id}"))
+    }
+}
+
+fn text_arg(rt: &Runtime, v: &Value, what: &str) -> Result<String, String> {
+    if v.get_tag() == TAG_STR {
+        if let ManagedObject::Str(s) = rt.heap.get(v.as_obj_id()) {
+            return Ok(s.as_str().to_string());
+        }
+    }
+    Err(format!("{what} expects text, got {}", v.type_name()))
+}
+
+fn list_arg(rt: &Runtime, v: &Value, what: &str) -> Result<Vec<Value>, String> {
+    if v.get_tag() == TAG_LIST {
+        if let ManagedObject::List(items) = rt.heap.get(v.as_obj_id()) {
+            return Ok(items.to_vec());
+        }
+    }
+    Err(format!("{what} expects a list, got {}", v.type_name()))
+}
+
+fn id_arg(v: &Value, what: &str) -> Result<i64, String> {
+    if !v.is_int() {
+        return Err(format!("{what}: library id must be an int, got {}", v.type_name()));
+    }
+    Ok(v.as_i64())
+}
+
+/// Parses the parameter and return types of `name`.
+fn signature(rt: &Runtime, name: &str, params: &Value, ret: &Value) -> Result<Signature, String> {
+    let what = format!("ffi: {name}");
+    let ctype = |v: &Value| -> Result<CType, String> {
+        let t = text_arg(rt, v, &what)?;
+        CType::parse(&t).ok_or_else(|| format!("{what}: unknown type \"{t}\""))
+    };
+    let params = list_arg(rt, params, &what)?.iter().map(ctype).collect::<Result<Vec<_>, _>>()?;
+    let ret = ctype(ret)?;
+    if params.contains(&CType::Void) {
+        return Err(format!("{what}: \"void\" is only a return type"));
+    }
+    if ret == CType::Bytes {
+        return Err(format!("{what}: \"bytes\" is only a parameter type; return a \"ptr\""));
+    }
+    let floats = params.iter().filter(|t| t.is_float()).count();
+    if params.len() - floats > MAX_INTS || floats > MAX_FLOATS {
+        return Err(format!("{what}: at most {MAX_INTS} integer and {MAX_FLOATS} float parameters are supported"));
+    }
+    Ok(Signature { params, ret })
+}
+
+/// Opens the library at `path` and returns its id.
+pub fn builtin_ffi_open(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("ffi.open expects 1 argument".into());
+    }
+    let path = text_arg(rt, &args[0], "ffi.open")?;
+    rt.check_ffi_grant(path.clone())?;
+    let handle = native::Handle::open(&path)?;
+    rt.ffi.libs.push(Some(Library { path, handle }));
+    Ok(Value::from_i64(rt.ffi.libs.len() as i64))
+}
+
+/// Checks a signature and that the library has the function, so mistakes
+/// show where the function is declared rather than where it is called.
+pub fn builtin_ffi_symbol(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 4 {
+        return Err("ffi.symbol expects 4 arguments".into());
+    }
+    let id = id_arg(&args[0], "ffi.symbol")?;
+    let name = text_arg(rt, &args[1], "ffi.symbol")?;
+    signature(rt, &name, &args[2], &args[3])?;
+    let lib = rt.ffi_library(id)?;
+    let target = format!("{}:{name}", lib.path);
+    rt.check_ffi_grant(target)?;
+    rt.ffi_library(id)?.handle.symbol(&name)?;
+    Ok(Value::UNIT)
+}
+
+/// Calls the function `name` of a library with a list of arguments.
+pub fn builtin_ffi_call(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 5 {
+        return Err("ffi.call expects 5 arguments".into());
+    }
+    let id = id_arg(&args[0], "ffi.call")?;
+    let name = text_arg(rt, &args[1], "ffi.call")?;
+    let sig = signature(rt, &name, &args[2], &args[3])?;
+    let values = list_arg(rt, &args[4], &format!("ffi: {name}"))?;
+    if values.len() != sig.params.len() {
+        return Err(format!("ffi: {name} expects {} arguments, got {}", sig.params.len(), values.len()));
+    }
+    let lib = rt.ffi_library(id)?;
+    let target = format!("{}:{name}", lib.path);
+    rt.check_ffi_grant(target)?;
+    let addr = rt.ffi_library(id)?.handle.symbol(&name)?;
+
+    let mut ints = [0i64; MAX_INTS];
+    let mut floats = [0f64; MAX_FLOATS];
+    let (mut n_ints, mut n_floats) = (0, 0);
+    // Kept alive until the call returns.
+    let mut strings: Vec<CString> = Vec::new();
+    let mut buffers: Vec<(Value, Vec<u8>)> = Vec::new();
+    for (i, (ty, v)) in sig.params.iter().zip(&values).enumerate() {
+        let what = format!("ffi: {name}: argument {}", i + 1);
+        let int = |v: &Value| {
+            if v.is_int() { Ok(v.as_i64()) } else { Err(format!("{what} must be an int, got {}", v.type_name())) }
+        };
+        let float =
+            |v: &Value| super::common::to_f64(v).map_err(|_| format!("{what} must be a number, got {}", v.type_name()));
+        match ty {
+            CType::F32 => floats[n_floats] = f64::from_bits(u64::from((float(v)? as f32).to_bits())),
+            CType::F64 => floats[n_floats] = float(v)?,
+            CType::I32 => {
+                let n = int(v)?;
+                ints[n_ints] = i64::from(i32::try_from(n).map_err(|_| format!("{what}: {n} does not fit in an i32"))?);
+            }
+            CType::I64 | CType::Ptr => ints[n_ints] = int(v)?,
+            CType::Str => {
+                let s =
+                    CString::new(text_arg(rt, v, &what)?).map_err(|_| format!("{what} contains a NUL character"))?;
+                ints[n_ints] = s.as_ptr() as i64;
+                strings.push(s);
+            }
+            CType::Bytes => {
+                let mut bytes = bytes_arg(rt, v, &what)?;
+                ints[n_ints] = bytes.as_mut_ptr() as i64;
+                buffers.push((*v, bytes));
+            }
+            CType::Void => unreachable!("rejected by signature"),
+        }
+        if ty.is_float() {
+            n_floats += 1;
+        } else {
+            n_ints += 1;
+        }
+    }
+
+    // SAFETY: the script declared the signature under the `ffi` grant; the
+    // arguments it names are in place and the buffers outlive the call.
+    let bits = unsafe { native::call(addr, ints, floats, sig.ret.is_float()) };
+
+    for (list, bytes) in buffers {
+        if list.get_tag() == TAG_LIST {
+            let id = list.as_obj_id();
+            if let ManagedObject::List(items) = rt.heap_get_mut(id) {
+                for (item, b) in items.iter_mut().zip(bytes) {
+                    *item = Value::from_i64(i64::from(b));
+                }
+            }
+            rt.write_barrier(id);
+        }
+    }
+    Ok(match sig.ret {
+        CType::Void => Value::UNIT,
+        CType::I32 => Value::from_i64(i64::from(bits as u32 as i32)),
+        CType::I64 | CType::Ptr => Value::from_i64(bits as i64),
+        CType::F32 => Value::from_f64(f64::from(f32::from_bits(bits as u32))),
+        CType::F64 => Value::from_f64(f64::from_bits(bits)),
+        CType::Str => {
+            if bits == 0 {
+                return Err(format!("ffi: {name} returned a null string"));
+            }
+            // SAFETY: the signature says the result is a NUL-terminated string.
+            let s = unsafe { std::ffi::CStr::from_ptr(bits as usize as *const std::ffi::c_char) };
+            let s = s.to_string_lossy().into_owned();
+            Value::str(rt.alloc(ManagedObject::Str(s.into())))
+        }
+        CType::Bytes => unreachable!("rejected by signature"),
+    })
+}
+
+/// Closes a library; functions declared from it can no longer be called.
+pub fn builtin_ffi_close(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("ffi.close expects 1 argument".into());
+    }
+    let id = id_arg(&args[0], "ffi.close")?;
+    rt.ffi_library(id)?;
+    rt.ffi.libs[id as usize - 1] = None;
+    Ok(Value::UNIT)
+}
+
+#[cfg(all(feature = "ffi", unix, any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod native {
+    use std::ffi::{CStr, CString, c_void};
+
+    pub(super) struct Handle(*mut c_void);
+
+    fn last_error() -> String {
+        // SAFETY: dlerror returns null or a NUL-terminated message.
+        let e = unsafe { libc::dlerror() };
+        if e.is_null() {
+            return "unknown error".into();
+        }
+        unsafe { CStr::from_ptr(e) }.to_string_lossy().into_owned()
+    }
+
+    impl Handle {
+        pub(super) fn open(path: &str) -> Result<Self, String> {
+            let c = CString::new(path).map_err(|_| "ffi.open: the path contains a NUL character".to_string())?;
+            // SAFETY: `c` is NUL-terminated; loading runs the library's
+            // initializers, which the `ffi` grant allows.
+            let handle = unsafe { libc::dlopen(c.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+            if handle.is_null() {
+                return Err(format!("ffi.open: {}", last_error()));
+            }
+            Ok(Self(handle))
+        }
+
+        pub(super) fn symbol(&self, name: &str) -> Result<usize, String> {
+            let c = CString::new(name).map_err(|_| format!("ffi: {name:?} contains a NUL character"))?;
+            // SAFETY: the handle is open and `c` is NUL-terminated.
+            let addr = unsafe { libc::dlsym(self.0, c.as_ptr()) };
+            if addr.is_null() {
+                return Err(format!("ffi: {}", last_error()));
+            }
+            Ok(addr as usize)
+        }
+    }
+
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            // SAFETY: the handle came from dlopen and is closed once.
+            unsafe {
+                libc::dlclose(self.0);
+            }
+        }
+    }
+
+    type Native<R> = unsafe extern "C" fn(i64, i64, i64, i64, i64, i64, f64, f64, f64, f64, f64, f64, f64, f64) -> R;
+
+    /// Calls the function at `addr` and returns the bits of its result,
+    /// taken from the floating-point register when `float_ret`.
+    pub(super) unsafe fn call(addr: usize, ints: [i64; 6], floats: [f64; 8], float_ret: bool) -> u64 {
+        let [a, b, c, d, e, f] = ints;
+        let [g, h, i, j, k, l, m, n] = floats;
+        // SAFETY: the caller vouches for `addr` being a function taking at
+        // most these registers.
+        unsafe {
+            if float_ret {
+                let fun: Native<f64> = std::mem::transmute(addr);
+                fun(a, b, c, d, e, f, g, h, i, j, k, l, m, n).to_bits()
+            } else {
+                let fun: Native<i64> = std::mem::transmute(addr);
+                fun(a, b, c, d, e, f, g, h, i, j, k, l, m, n) as u64
+            }
+        }
+    }
+}
+
+#[cfg(not(all(feature = "ffi", unix, any(target_arch = "x86_64", target_arch = "aarch64"))))]
+mod native {
+    const UNAVAILABLE: &str =
+        "ffi: not available in this build (needs the `ffi` feature, on Unix for x86-64 or AArch64)";
+
+    pub(super) struct Handle;
+
+    impl Handle {
+        pub(super) fn open(_path: &str) -> Result<Self, String> {
+            Err(UNAVAILABLE.into())
+        }
+
+        pub(super) fn symbol(&self, _name: &str) -> Result<usize, String> {
+            Err(UNAVAILABLE.into())
+        }
+    }
+
+    pub(super) unsafe fn call(_addr: usize, _ints: [i64; 6], _floats: [f64; 8], _float_ret: bool) -> u64 {
+        unreachable!("no library can be opened")
+    }
+}
@@ -0,0 +1,308 @@
+//! The hooks behind `std/fs`: `glob` patterns and the `Walker` that lists a
+//! directory tree one entry at a time. Both read directories through the
+//! runtime's [`FileSystem`](crate::FileSystem), so allowed roots and
+//! `fs.read` grants apply to every directory they open.
+
+use super::super::Runtime;
+use crate::Value;
+use crate::core::ListItems;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{TAG_LIST, TAG_STR, TAG_STRUCT, TAG_TUPLE};
+use crate::util::DirEntry;
+
+fn str_value(rt: &mut Runtime, s: &str) -> Value {
+    Value::str(rt.alloc(ManagedObject::Str(s.into())))
+}
+
+fn text(rt: &Runtime, v: &Value) -> Option<String> {
+    match (v.get_tag() == TAG_STR).then(|| rt.heap.get(v.as_obj_id())) {
+        Some(ManagedObject::Str(s)) => Some(s.as_str().to_string()),
+        _ => None,
+    }
+}
+
+fn join(dir: &str, name: &str) -> String {
+    match dir {
+        "" | "." => name.to_string(),
+        d if d.ends_with('/') => format!("{d}{name}"),
+        d => format!("{d}/{name}"),
+    }
+}
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+/// The entries of `dir` sorted by name; none when it cannot be read.
+fn sorted_entries(rt: &mut Runtime, dir: &str) -> Result<Vec<DirEntry>, String> {
+    let path = if dir.is_empty() { "." } else { dir };
+    let mut entries = rt.fs_read_dir(path)?.unwrap_or_default();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Whether `name` matches one component of a glob pattern: `*` matches any
+/// run of characters, `?` one character and `[...]` one of a set, with
+/// ranges such as `a-z` and `!` or `^` to negate.
+fn match_component(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Where to resume after the last `*` when a later part fails to match.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(p + 1),
+            Some('[') => match_class(pattern, p, name[n]),
+            Some(c) if *c == name[n] => Some(p + 1),
+            _ => None,
+        };
+        match (step, star) {
+            (Some(next), _) => {
+                p = next;
+                n += 1;
+            }
+            (None, Some((sp, sn))) => {
+                p = sp;
+                n = sn + 1;
+                star = Some((sp, sn + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Matches `c` against the class opening at `pattern[start]`; the position
+/// after the class on a match. An unclosed `[` matches itself.
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<usize> {
+    let mut i = start + 1;
+    let negate = matches!(pattern.get(i), Some('!' | '^'));
+    if negate {
+        i += 1;
+    }
+    let mut found = false;
+    let mut first = true;
+    while i < pattern.len() && (first || pattern[i] != ']') {
+        first = false;
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|e| *e != ']') {
+            found |= pattern[i] <= c && c <= pattern[i + 2];
+            i += 3;
+        } else {
+            found |= pattern[i] == c;
+            i += 1;
+        }
+    }
+    if i >= pattern.len() {
+        return (c == '[').then_some(start + 1);
+    }
+    (found != negate).then_some(i + 1)
+}
+
+/// Whether `path` names a file or directory. Denied access is an error.
+fn exists(rt: &mut Runtime, path: &str) -> Result<bool, String> {
+    Ok(rt.fs_read_dir(path)?.is_ok() || rt.fs_metadata(path).is_ok())
+}
+
+fn has_wildcard(component: &str) -> bool {
+    component.contains(['*', '?', '['])
+}
+
+/// Adds to `out` the paths below `dir` matching `components`; `**` stands
+/// for any number of directories.
+fn expand(rt: &mut Runtime, dir: &str, components: &[String], out: &mut Vec<String>) -> Result<(), String> {
+    let Some((first, rest)) = components.split_first() else {
+        if !dir.is_empty() {
+            out.push(dir.to_string());
+        }
+        return Ok(());
+    };
+    if first == "**" {
+        expand(rt, dir, rest, out)?;
+        for entry in sorted_entries(rt, dir)? {
+            if entry.is_dir && !is_hidden(&entry.name) {
+                expand(rt, &join(dir, &entry.name), components, out)?;
+            }
+        }
+        return Ok(());
+    }
+    if !has_wildcard(first) {
+        let path = join(dir, first);
+        if !rest.is_empty() {
+            return expand(rt, &path, rest, out);
+        }
+        if exists(rt, &path)? {
+            out.push(path);
+        }
+        return Ok(());
+    }
+    let pattern: Vec<char> = first.chars().collect();
+    for entry in sorted_entries(rt, dir)? {
+        if is_hidden(&entry.name) && !first.starts_with('.') {
+            continue;
+        }
+        let name: Vec<char> = entry.name.chars().collect();
+        if !match_component(&pattern, &name) || (!rest.is_empty() && !entry.is_dir) {
+            continue;
+        }
+        expand(rt, &join(dir, &entry.name), rest, out)?;
+    }
+    Ok(())
+}
+
+/// The paths matching a glob pattern, sorted. Names starting with `.` only
+/// match components that start with `.` too, and `**` does not descend into
+/// them.
+pub fn builtin_fs_glob(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("fs.glob expects 1 argument".into());
+    }
+    let Some(pattern) = text(rt, &args[0]) else {
+        return Err(format!("fs.glob expects text, got {}", args[0].type_name()));
+    };
+    let (root, pattern) = match pattern.strip_prefix('/') {
+        Some(rest) => ("/", rest),
+        None => ("", pattern.as_str()),
+    };
+    let components: Vec<String> = pattern.split('/').filter(|c| !c.is_empty()).map(str::to_string).collect();
+    // Literal leading components are joined without listing their parents.
+    let literal = components.iter().take_while(|c| !has_wildcard(c) && *c != "**").count();
+    let mut out = Vec::new();
+    if literal == components.len() {
+        let path = format!("{root}{}", components.join("/"));
+        if !path.is_empty() && exists(rt, &path)? {
+            out.push(path);
+        }
+    } else {
+        let base = format!("{root}{}", components[..literal].join("/"));
+        expand(rt, &base, &components[literal..], &mut out)?;
+    }
+    out.sort();
+    out.dedup();
+    let items = out.iter().map(|p| str_value(rt, p)).collect();
+    Ok(Value::list(rt.alloc(ManagedObject::List(items))))
+}
+
+/// The fields of a `fs.Walker`, by name.
+struct Walker {
+    id: crate::core::heap::ObjectId,
+    names: Vec<String>,
+    fields: Vec<Value>,
+}
+
+impl Walker {
+    fn new(rt: &Runtime, v: &Value) -> Result<Walker, String> {
+        match (v.get_tag() == TAG_STRUCT).then(|| rt.heap.get(v.as_obj_id())) {
+            Some(ManagedObject::Struct(s)) => {
+                Ok(Walker { id: v.as_obj_id(), names: s.field_names.to_vec(), fields: s.fields.to_vec() })
+            }
+            _ => Err("fs walker expects a Walker".into()),
+        }
+    }
+
+    fn index(&self, name: &str) -> Result<usize, String> {
+        self.names.iter().position(|n| n == name).ok_or_else(|| format!("fs walker: missing field {name}"))
+    }
+
+    fn get(&self, name: &str) -> Result<Value, String> {
+        Ok(self.fields[self.index(name)?])
+    }
+
+    fn flag(&self, name: &str) -> Result<bool, String> {
+        let v = self.get(name)?;
+        Ok(v.is_bool() && v.as_bool())
+    }
+
+    fn set(&self, rt: &mut Runtime, name: &str, value: Value) -> Result<(), String> {
+        let index = self.index(name)?;
+        if let ManagedObject::Struct(s) = rt.heap_get_mut(self.id) {
+            s.fields[index] = value;
+        }
+        rt.write_barrier(self.id);
+        Ok(())
+    }
+}
+
+/// Pushes the entries of `dir` onto the walker's stack of entries to visit,
+/// last name first so they come off in order. Each is a
+/// `(path, depth, is_dir)` tuple.
+fn push_children(rt: &mut Runtime, stack: Value, dir: &str, depth: i64, hidden: bool) -> Result<(), String> {
+    let mut entries = match rt.fs_read_dir(dir)? {
+        Ok(entries) => entries,
+        Err(e) => return Err(format!("fs.walk: cannot read {dir}: {e}")),
+    };
+    entries.sort_by(|a, b| b.name.cmp(&a.name));
+    for entry in entries {
+        if is_hidden(&entry.name) && !hidden {
+            continue;
+        }
+        let path = str_value(rt, &join(dir, &entry.name));
+        let tuple = vec![path, Value::from_i64(depth), Value::from_bool(entry.is_dir)];
+        let tuple = Value::tuple(rt.alloc(ManagedObject::Tuple(tuple)));
+        if let ManagedObject::List(items) = rt.heap_get_mut(stack.as_obj_id()) {
+            items.make_mut().push(tuple);
+        }
+        rt.write_barrier(stack.as_obj_id());
+    }
+    Ok(())
+}
+
+/// The next path of a `fs.Walker`, depth first in name order, or none when
+/// the tree is done. Directories come before their contents.
+pub fn builtin_fs_walk_next(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("fs walker expects 1 argument".into());
+    }
+    let walker = Walker::new(rt, &args[0])?;
+    let hidden = walker.flag("hidden")?;
+    let dirs = walker.flag("dirs")?;
+    let max_depth = walker.get("max_depth")?.as_i64();
+    let ext = text(rt, &walker.get("ext")?).unwrap_or_default();
+    let skip = walker.get("skip")?;
+    let skip: Vec<String> = match (skip.get_tag() == TAG_LIST).then(|| rt.heap.get(skip.as_obj_id())) {
+        Some(ManagedObject::List(items)) => items.iter().filter_map(|v| text(rt, v)).collect(),
+        _ => Vec::new(),
+    };
+    let mut stack = walker.get("pending")?;
+    if !walker.flag("started")? {
+        let root = text(rt, &walker.get("root")?).unwrap_or_default();
+        stack = Value::list(rt.alloc(ManagedObject::List(ListItems::new())));
+        walker.set(rt, "pending", stack)?;
+        walker.set(rt, "started", Value::from_bool(true))?;
+        push_children(rt, stack, &root, 1, hidden)?;
+    }
+    if stack.get_tag() != TAG_LIST {
+        return Err("fs walker: pending must be a list".into());
+    }
+    loop {
+        let next = match rt.heap_get_mut(stack.as_obj_id()) {
+            ManagedObject::List(items) => items.make_mut().pop(),
+            _ => None,
+        };
+        let Some(next) = next else {
+            return Ok(rt.option_none());
+        };
+        let (path, depth, is_dir) = match (next.get_tag() == TAG_TUPLE).then(|| rt.heap.get(next.as_obj_id())) {
+            Some(ManagedObject::Tuple(t)) if t.len() == 3 => (t[0], t[1].as_i64(), t[2].as_bool()),
+            _ => continue,
+        };
+        let path_text = text(rt, &path).unwrap_or_default();
+        let name = path_text.rsplit('/').next().unwrap_or("");
+        if is_dir {
+            if skip.iter().any(|s| s == name) {
+                continue;
+            }
+            if max_depth < 0 || depth < max_depth {
+                push_children(rt, stack, &path_text, depth + 1, hidden)?;
+            }
+            if dirs {
+                return Ok(rt.option_some(path));
+            }
+        } else if ext.is_empty() || name.strip_suffix(ext.as_str()).is_some_and(|stem| stem.ends_with('.')) {
+            return Ok(rt.option_some(path));
+        }
+    }
+}
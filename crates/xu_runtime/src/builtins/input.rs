@@ -0,0 +1,155 @@
+//! `input` and its typed variants. Answers are read through the runtime's
+//! [`Terminal`](crate::Terminal), so embedders and tests can script them.
+//!
+//! Each takes `(prompt?, default?, validate?)`, with `()` standing for a
+//! left-out argument as labeled calls such as `input_int("Age? ", default: 30)`
+//! pass it. An empty answer gives `default` when there is one; an answer
+//! that does not parse, or that `validate` turns down, is asked again.
+
+use super::super::Runtime;
+use super::super::util::value_to_string;
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{TAG_FUNC, TAG_STR};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Text,
+    Secret,
+    Int,
+    Float,
+}
+
+/// The value of one answer, or the message to show before asking again.
+fn parse(rt: &mut Runtime, kind: Kind, answer: &str) -> Result<Value, &'static str> {
+    match kind {
+        Kind::Text | Kind::Secret => Ok(Value::str(rt.alloc(ManagedObject::Str(answer.into())))),
+        Kind::Int => answer.trim().parse().map(Value::from_i64).map_err(|_| "please enter a whole number"),
+        Kind::Float => match answer.trim().parse::<f64>() {
+            Ok(f) if f.is_finite() => Ok(Value::from_f64(f)),
+            _ => Err("please enter a number"),
+        },
+    }
+}
+
+/// `default` checked against what `name` returns; ints are widened for
+/// `input_float`.
+fn check_default(rt: &Runtime, name: &str, kind: Kind, default: Value) -> Result<Option<Value>, String> {
+    if default.is_unit() {
+        return Ok(None);
+    }
+    let ok = match kind {
+        Kind::Text | Kind::Secret => {
+            matches!(
+                (default.get_tag() == TAG_STR).then(|| rt.heap.get(default.as_obj_id())),
+                Some(ManagedObject::Str(_))
+            )
+        }
+        Kind::Int => default.is_int(),
+        Kind::Float if default.is_int() => return Ok(Some(Value::from_f64(default.as_i64() as f64))),
+        Kind::Float => default.is_f64(),
+    };
+    let expected = match kind {
+        Kind::Text | Kind::Secret => "text",
+        Kind::Int => "int",
+        Kind::Float => "float",
+    };
+    if !ok {
+        return Err(format!("{name}: default must be {expected}, got {}", default.type_name()));
+    }
+    Ok(Some(default))
+}
+
+/// Asks until an answer is accepted; `None` at the end of input.
+fn ask(rt: &mut Runtime, name: &str, kind: Kind, args: &[Value]) -> Result<Option<Value>, String> {
+    if args.len() > 3 {
+        return Err(format!("{name} expects 0 to 3 arguments"));
+    }
+    let prompt = args.first().filter(|p| !p.is_unit()).map(|p| value_to_string(p, &rt.heap)).unwrap_or_default();
+    let default = match args.get(1) {
+        Some(d) => check_default(rt, name, kind, *d)?,
+        None => None,
+    };
+    let validate = args.get(2).copied().filter(|v| !v.is_unit());
+    if let Some(f) = validate {
+        if f.get_tag() != TAG_FUNC {
+            return Err(format!("{name}: validate must be a function, got {}", f.type_name()));
+        }
+    }
+    loop {
+        rt.write_output_raw(&prompt);
+        rt.flush_output();
+        let line = if kind == Kind::Secret { rt.terminal_read_secret() } else { rt.terminal_read_line() };
+        let Some(line) = line else {
+            return Ok(None);
+        };
+        if let (true, Some(d)) = (line.trim().is_empty(), default) {
+            return Ok(Some(d));
+        }
+        let value = match parse(rt, kind, &line) {
+            Ok(v) => v,
+            Err(message) => {
+                rt.write_output(message);
+                continue;
+            }
+        };
+        let Some(f) = validate else {
+            return Ok(Some(value));
+        };
+        let verdict = rt.call_function(f, &[value])?;
+        if verdict.is_bool() {
+            if verdict.as_bool() {
+                return Ok(Some(value));
+            }
+            rt.write_output("invalid answer, please try again");
+            continue;
+        }
+        match (verdict.get_tag() == TAG_STR).then(|| rt.heap.get(verdict.as_obj_id())) {
+            Some(ManagedObject::Str(s)) => {
+                let message = s.as_str().to_string();
+                rt.write_output(&message);
+            }
+            _ => return Err(format!("{name}: validate must return bool or text, got {}", verdict.type_name())),
+        }
+    }
+}
+
+/// `input(prompt?)` reads one line without its line ending; at the end of
+/// input it gives `default`, or `""`.
+pub fn builtin_input(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    match ask(rt, "input", Kind::Text, args)? {
+        Some(v) => Ok(v),
+        None => match args.get(1).filter(|d| !d.is_unit()) {
+            Some(d) => Ok(*d),
+            None => Ok(Value::str(rt.alloc(ManagedObject::Str("".into())))),
+        },
+    }
+}
+
+fn some_or_none(rt: &mut Runtime, answer: Option<Value>) -> Value {
+    match answer {
+        Some(v) => rt.option_some(v),
+        None => rt.option_none(),
+    }
+}
+
+/// `input_int(prompt?)` asks until a whole number is typed; none at the end
+/// of input.
+pub fn builtin_input_int(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    let answer = ask(rt, "input_int", Kind::Int, args)?;
+    Ok(some_or_none(rt, answer))
+}
+
+/// `input_float(prompt?)` asks until a number is typed; none at the end of
+/// input.
+pub fn builtin_input_float(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    let answer = ask(rt, "input_float", Kind::Float, args)?;
+    Ok(some_or_none(rt, answer))
+}
+
+/// `input_secret(prompt?)` reads a line without showing it as it is typed;
+/// none at the end of input.
+pub fn builtin_input_secret(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    let answer = ask(rt, "input_secret", Kind::Secret, args)?;
+    Ok(some_or_none(rt, answer))
+}
@@ -1,6 +1,7 @@
 use super::common::to_f64; use super::common::to_f64_pair;
 use super::super::Runtime;
 use crate::Value;
+use crate::xu_builtin;
 
 pub fn builtin_abs(_rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
     if args.len() != 1 {
@@ -40,50 +41,83 @@ pub fn builtin_min(_rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
     }
 }
 
-pub fn builtin_sin(_rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err("sin expects 1 argument".into());
+/// Stays int when all three arguments are ints, like `min`/`max`.
+pub fn builtin_clamp(_rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 3 {
+        return Err("clamp expects 3 arguments".into());
+    }
+    if args.iter().all(|v| v.is_int()) {
+        let (x, lo, hi) = (args[0].as_i64(), args[1].as_i64(), args[2].as_i64());
+        if lo > hi {
+            return Err("clamp: lower bound is greater than upper bound".into());
+        }
+        Ok(Value::from_i64(x.clamp(lo, hi)))
+    } else {
+        let x = to_f64(&args[0])?;
+        let (lo, hi) = to_f64_pair(&args[1], &args[2])?;
+        if lo > hi || lo.is_nan() || hi.is_nan() {
+            return Err("clamp: lower bound is greater than upper bound".into());
+        }
+        Ok(Value::from_f64(x.clamp(lo, hi)))
     }
-    let v = to_f64(&args[0])?;
-    Ok(Value::from_f64(v.sin()))
 }
 
-pub fn builtin_cos(_rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err("cos expects 1 argument".into());
+fn gcd_u64(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
     }
-    let v = to_f64(&args[0])?;
-    Ok(Value::from_f64(v.cos()))
+    a
 }
 
-pub fn builtin_tan(_rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err("tan expects 1 argument".into());
+xu_builtin! {
+    /// Builtins taking and returning one kind of number. Installed by
+    /// [`StdBuiltinProvider`](crate::StdBuiltinProvider).
+    pub struct MathBuiltinProvider;
+
+    pub fn sin(x: f64) -> f64 {
+        x.sin()
     }
-    let v = to_f64(&args[0])?;
-    Ok(Value::from_f64(v.tan()))
-}
 
-pub fn builtin_sqrt(_rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err("sqrt expects 1 argument".into());
+    pub fn cos(x: f64) -> f64 {
+        x.cos()
     }
-    let v = to_f64(&args[0])?;
-    Ok(Value::from_f64(v.sqrt()))
-}
 
-pub fn builtin_log(_rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err("log expects 1 argument".into());
+    pub fn tan(x: f64) -> f64 {
+        x.tan()
     }
-    let v = to_f64(&args[0])?;
-    Ok(Value::from_f64(v.ln()))
-}
 
-pub fn builtin_pow(_rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
-    if args.len() != 2 {
-        return Err("pow expects 2 arguments".into());
+    pub fn sqrt(x: f64) -> f64 {
+        x.sqrt()
+    }
+
+    pub fn log(x: f64) -> f64 {
+        x.ln()
+    }
+
+    pub fn pow(base: f64, exp: f64) -> f64 {
+        base.powf(exp)
+    }
+
+    pub fn log2(x: f64) -> f64 {
+        x.log2()
+    }
+
+    pub fn exp(x: f64) -> f64 {
+        x.exp()
+    }
+
+    pub fn gcd(a: i64, b: i64) -> Result<i64, String> {
+        i64::try_from(gcd_u64(a.unsigned_abs(), b.unsigned_abs())).map_err(|_| "gcd: integer overflow".to_string())
+    }
+
+    pub fn lcm(a: i64, b: i64) -> Result<i64, String> {
+        if a == 0 || b == 0 {
+            return Ok(0);
+        }
+        let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+        (a / gcd_u64(a, b))
+            .checked_mul(b)
+            .and_then(|l| i64::try_from(l).ok())
+            .ok_or_else(|| "lcm: integer overflow".to_string())
     }
-    let (base, exp) = to_f64_pair(&args[0], &args[1])?;
-    Ok(Value::from_f64(base.powf(exp)))
 }
@@ -3,6 +3,20 @@ pub(super) mod conversion;
 pub(super) mod math;
 pub(super) mod collection;
 pub(super) mod system;
+pub(super) mod random;
+pub(crate) mod encoding;
+pub(super) mod crypto;
+pub(super) mod csv;
+pub(super) mod fs;
+pub(super) mod input;
+pub(crate) mod term;
+pub(crate) mod worker;
+pub(crate) mod ffi;
+pub(crate) mod reflect;
+pub(super) mod serialize;
+pub(super) mod schema;
+pub(crate) mod template;
+pub(super) mod channel;
 pub(super) mod common;
 
 pub use core::*;
@@ -10,3 +24,17 @@ pub use conversion::*;
 pub use math::*;
 pub use collection::*;
 pub use system::*;
+pub use random::*;
+pub use crypto::*;
+pub use csv::*;
+pub use reflect::*;
+pub use term::TermBuiltinProvider;
+pub use channel::*;
+pub use worker::{builtin_parallel_map, builtin_worker_close, builtin_worker_join, builtin_worker_recv, builtin_worker_send, builtin_worker_spawn};
+pub use ffi::{builtin_ffi_call, builtin_ffi_close, builtin_ffi_open, builtin_ffi_symbol};
+pub use schema::builtin_validate;
+pub use template::builtin_template_render;
+pub use fs::{builtin_fs_glob, builtin_fs_walk_next};
+pub use input::{builtin_input, builtin_input_float, builtin_input_int, builtin_input_secret};
+pub use serialize::{builtin_deserialize, builtin_serialize};
+pub use encoding::{builtin_base64_decode, builtin_base64_encode, builtin_hex_decode, builtin_hex_encode};
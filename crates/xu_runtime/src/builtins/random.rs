@@ -0,0 +1,112 @@
+//! Hooks behind `std/random`. Every draw goes through the runtime's
+//! `RngAlgorithm` capability, so a seeded runtime is fully reproducible.
+
+use super::common::to_f64;
+use super::super::Runtime;
+use crate::Value;
+use crate::core::heap::ManagedObject;
+
+/// Uniform float in `[0, 1)` built from the top 53 bits of a draw.
+fn next_f64(rt: &mut Runtime) -> f64 {
+    (rt.rng_next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Uniform integer in `[0, span)`, using the high bits of a draw.
+fn next_below(rt: &mut Runtime, span: u64) -> u64 {
+    ((rt.rng_next_u64() as u128 * span as u128) >> 64) as u64
+}
+
+fn expect_int(v: &Value, what: &str) -> Result<i64, String> {
+    if v.is_int() {
+        Ok(v.as_i64())
+    } else {
+        Err(format!("{what} expects int, got {}", v.type_name()))
+    }
+}
+
+fn list_items(rt: &Runtime, v: &Value, what: &str) -> Result<Vec<Value>, String> {
+    if v.get_tag() == crate::core::value::TAG_LIST {
+        if let ManagedObject::List(items) = rt.heap.get(v.as_obj_id()) {
+            return Ok(items.to_vec());
+        }
+    }
+    Err(format!("{what} expects list, got {}", v.type_name()))
+}
+
+pub fn builtin_random_seed(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("random.seed expects 1 argument".into());
+    }
+    let seed = expect_int(&args[0], "random.seed")?;
+    rt.set_rng_seed(seed as u64);
+    Ok(Value::UNIT)
+}
+
+pub fn builtin_random_int(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("random.int expects 2 arguments".into());
+    }
+    let lo = expect_int(&args[0], "random.int")?;
+    let hi = expect_int(&args[1], "random.int")?;
+    if hi < lo {
+        return Err(format!("random.int: empty range {lo}..{hi}"));
+    }
+    let span = (hi as i128 - lo as i128 + 1) as u128;
+    let offset = if span > u64::MAX as u128 {
+        rt.rng_next_u64()
+    } else {
+        next_below(rt, span as u64)
+    };
+    Ok(Value::from_i64((lo as i128 + offset as i128) as i64))
+}
+
+pub fn builtin_random_float(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("random.float expects 0 arguments".into());
+    }
+    Ok(Value::from_f64(next_f64(rt)))
+}
+
+pub fn builtin_random_gauss(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("random.gauss expects 2 arguments".into());
+    }
+    let mean = to_f64(&args[0])?;
+    let stddev = to_f64(&args[1])?;
+    if stddev < 0.0 {
+        return Err("random.gauss: stddev must be >= 0".into());
+    }
+    // Box-Muller; `1 - u` keeps the logarithm away from zero.
+    let u1 = 1.0 - next_f64(rt);
+    let u2 = next_f64(rt);
+    let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+    Ok(Value::from_f64(mean + stddev * z))
+}
+
+pub fn builtin_random_choice(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("random.choice expects 1 argument".into());
+    }
+    let items = list_items(rt, &args[0], "random.choice")?;
+    if items.is_empty() {
+        return Err("random.choice: list is empty".into());
+    }
+    let i = next_below(rt, items.len() as u64) as usize;
+    Ok(items[i])
+}
+
+pub fn builtin_random_shuffle(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("random.shuffle expects 1 argument".into());
+    }
+    let mut items = list_items(rt, &args[0], "random.shuffle")?;
+    // Fisher-Yates, shuffling in place.
+    for i in (1..items.len()).rev() {
+        let j = next_below(rt, i as u64 + 1) as usize;
+        items.swap(i, j);
+    }
+    if let ManagedObject::List(list) = rt.heap.get_mut(args[0].as_obj_id()) {
+        *list = items.into();
+    }
+    Ok(Value::UNIT)
+}
@@ -0,0 +1,135 @@
+//! Builtins looking at values and types at runtime, for serializers and
+//! object mappers written in Xu.
+
+use super::super::Runtime;
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{DictKey, StructInstance, TAG_DICT, TAG_ENUM, TAG_OPTION, TAG_STRUCT};
+use crate::xu_builtin;
+
+/// The text of a string dict key.
+pub(crate) fn key_text(rt: &Runtime, key: &DictKey) -> Option<String> {
+    match key {
+        DictKey::StrRef { obj_id, .. } => match rt.heap.get(crate::core::heap::ObjectId(*obj_id)) {
+            ManagedObject::Str(s) => Some(s.as_str().to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// What `type_of` says of `v`.
+pub(crate) fn type_name_of(rt: &Runtime, v: &Value) -> String {
+    match v.get_tag() {
+        TAG_STRUCT => match rt.heap.get(v.as_obj_id()) {
+            ManagedObject::Struct(s) => s.ty.clone(),
+            _ => v.type_name().to_string(),
+        },
+        TAG_ENUM => match rt.heap.get(v.as_obj_id()) {
+            ManagedObject::Enum(e) => e.0.as_str().to_string(),
+            _ => v.type_name().to_string(),
+        },
+        TAG_OPTION => "Option".to_string(),
+        _ => v.type_name().to_string(),
+    }
+}
+
+/// Names of the methods of `ty` defined in the global scope or exported by a
+/// loaded module.
+fn method_names(rt: &Runtime, ty: &str) -> Vec<String> {
+    let prefix = format!("__method__{ty}__");
+    let mut names: Vec<String> = Vec::new();
+    let frame = rt.env.global_frame();
+    names.extend(frame.borrow().names.keys().filter_map(|k| k.strip_prefix(&prefix)).map(str::to_string));
+    for module in rt.loaded_modules.values() {
+        if let ManagedObject::Module(m) = rt.heap.get(module.as_obj_id()) {
+            names.extend(m.exports.map.keys().filter_map(|k| k.strip_prefix(&prefix)).map(str::to_string));
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+xu_builtin! {
+    /// Reflection builtins. Installed by [`StdBuiltinProvider`](crate::StdBuiltinProvider).
+    pub struct ReflectBuiltinProvider;
+
+    /// The struct or enum name of `v`, or the name of its builtin type.
+    pub fn type_of(rt: &Runtime, v: Value) -> String {
+        type_name_of(rt, &v)
+    }
+
+    /// The fields of a struct value as a dict, in declaration order.
+    pub fn fields_of(rt: &mut Runtime, v: Value) -> Result<Value, String> {
+        if v.get_tag() != TAG_STRUCT {
+            return Err(format!("fields_of expects a struct, got {}", v.type_name()));
+        }
+        let (names, values) = match rt.heap.get(v.as_obj_id()) {
+            ManagedObject::Struct(s) => (s.field_names.clone(), s.fields.clone()),
+            _ => return Err(format!("fields_of expects a struct, got {}", v.type_name())),
+        };
+        let mut dict = crate::core::value::dict_with_capacity(names.len(), &rt.heap);
+        for (name, value) in names.iter().zip(values.iter()) {
+            let key = DictKey::from_str_alloc(name, &mut rt.heap);
+            dict.insert_entry(key, *value);
+        }
+        Ok(Value::dict(rt.alloc(ManagedObject::Dict(dict))))
+    }
+
+    /// The sorted method names of the struct or enum `ty`; other types have
+    /// none.
+    pub fn methods_of(rt: &Runtime, ty: String) -> Vec<String> {
+        method_names(rt, &ty)
+    }
+
+    /// A `ty` value with the fields in `fields`, keyed by name. Fields left
+    /// out take their defaults; unknown or missing fields are errors.
+    pub fn struct_from_dict(rt: &mut Runtime, ty: String, fields: Value) -> Result<Value, String> {
+        let Some(layout) = rt.types.struct_layouts.get(&ty).cloned() else {
+            return Err(rt.error(xu_syntax::DiagnosticKind::UnknownStruct(ty)));
+        };
+        if fields.get_tag() != TAG_DICT {
+            return Err(format!("struct_from_dict expects a dict, got {}", fields.type_name()));
+        }
+        let entries: Vec<(Option<String>, Value)> = match rt.heap.get(fields.as_obj_id()) {
+            ManagedObject::Dict(d) => d.entries().map(|(k, v)| (key_text(rt, &k), v)).collect(),
+            _ => return Err(format!("struct_from_dict expects a dict, got {}", fields.type_name())),
+        };
+        let mut values: Vec<Option<Value>> = vec![None; layout.len()];
+        for (key, value) in entries {
+            match key.as_deref().and_then(|k| layout.iter().position(|f| f == k)) {
+                Some(pos) => values[pos] = Some(value),
+                None => {
+                    let key = key.unwrap_or_else(|| "(non-text key)".to_string());
+                    return Err(format!("struct_from_dict: {ty} has no field {key}"));
+                }
+            }
+        }
+        let defaults: Vec<_> = match rt.types.structs.get(&ty) {
+            Some(def) => def.fields.iter().map(|f| f.default.clone()).collect(),
+            None => Vec::new(),
+        };
+        let mut fields = Vec::with_capacity(layout.len());
+        for (i, value) in values.into_iter().enumerate() {
+            let value = match (value, defaults.get(i).cloned().flatten()) {
+                (Some(v), _) => v,
+                (None, Some(d)) => rt.eval_expr(&d)?,
+                (None, None) => return Err(format!("struct_from_dict: missing field {} of {ty}", layout[i])),
+            };
+            fields.push(value);
+        }
+        Ok(Value::struct_obj(rt.alloc(ManagedObject::Struct(Box::new(StructInstance {
+            ty_hash: xu_ir::stable_hash64(&ty),
+            ty,
+            fields: fields.into_boxed_slice(),
+            field_names: layout,
+        })))))
+    }
+
+    /// Calls the method `method` of `obj`, as `obj.method(...args)` would.
+    pub fn call_by_name(rt: &mut Runtime, obj: Value, method: String, args: Option<Vec<Value>>) -> Result<Value, String> {
+        let args = args.unwrap_or_default();
+        rt.call_method_with_ic_raw(obj, &method, xu_ir::stable_hash64(&method), &args, None)
+    }
+}
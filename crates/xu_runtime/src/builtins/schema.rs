@@ -0,0 +1,312 @@
+//! `validate(value, schema)`: checks a value, typically parsed JSON, against
+//! a schema and lists every violation with its path.
+//!
+//! A schema is a type name, or a dict of constraints:
+//!
+//! | Key | Applies to | Meaning |
+//! |-----|------------|---------|
+//! | `type` | any | A type name or a list of them: the names `type_of` gives, `"number"` for int or float, `"any"` |
+//! | `min`, `max` | numbers | Inclusive bounds |
+//! | `min_length`, `max_length` | strings, lists, dicts, tuples | Inclusive bounds on the length in characters or items |
+//! | `pattern` | strings | A regex that must match somewhere in the string |
+//! | `one_of` | any | The allowed values |
+//! | `items` | lists | Schema of every item |
+//! | `keys` | dicts, structs | Schema of each named key or field |
+//! | `required` | dicts | Keys that must be present |
+//! | `values` | dicts | Schema of the values of keys not in `keys` |
+//! | `extra` | dicts | `false` to reject keys not in `keys` |
+//!
+//! Paths start at `$` and add `.key` or `["key"]` for dict keys and struct
+//! fields, `[i]` for list items. A value of the wrong type gets no further
+//! checks. Mistakes in the schema itself are runtime errors.
+
+use regex::Regex;
+
+use super::super::Runtime;
+use super::reflect::{key_text, type_name_of};
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{TAG_DICT, TAG_LIST, TAG_STR, TAG_STRUCT, TAG_TUPLE};
+use crate::util::PrettyOptions;
+
+const BUILTIN_TYPES: &[&str] = &[
+    "any", "number", "int", "float", "bool", "string", "list", "dict", "tuple", "range", "unit", "function", "Option",
+];
+
+pub fn builtin_validate(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("validate expects 2 arguments".into());
+    }
+    let schema = Schema::parse(rt, &args[1])?;
+    let mut violations = Vec::new();
+    schema.check(rt, &args[0], "$", &mut violations);
+    if violations.is_empty() {
+        return Ok(result(rt, "ok", args[0]));
+    }
+    let items = violations.into_iter().map(|v| Value::str(rt.alloc(ManagedObject::Str(v.into())))).collect();
+    let list = Value::list(rt.alloc(ManagedObject::List(items)));
+    Ok(result(rt, "err", list))
+}
+
+fn result(rt: &mut Runtime, variant: &str, payload: Value) -> Value {
+    Value::enum_obj(rt.alloc(ManagedObject::Enum(Box::new(("Result".into(), variant.into(), Box::new([payload]))))))
+}
+
+#[derive(Default)]
+struct Schema {
+    /// Allowed type names; empty allows any.
+    types: Vec<String>,
+    min: Option<f64>,
+    max: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+    one_of: Option<Vec<Value>>,
+    items: Option<Box<Schema>>,
+    keys: Vec<(String, Schema)>,
+    required: Vec<String>,
+    values: Option<Box<Schema>>,
+    extra: bool,
+}
+
+fn text(rt: &Runtime, v: &Value) -> Option<String> {
+    if v.get_tag() != TAG_STR {
+        return None;
+    }
+    match rt.heap.get(v.as_obj_id()) {
+        ManagedObject::Str(s) => Some(s.as_str().to_string()),
+        _ => None,
+    }
+}
+
+fn list(rt: &Runtime, v: &Value) -> Option<Vec<Value>> {
+    if v.get_tag() != TAG_LIST {
+        return None;
+    }
+    match rt.heap.get(v.as_obj_id()) {
+        ManagedObject::List(items) => Some(items.to_vec()),
+        _ => None,
+    }
+}
+
+/// The entries of a dict with their keys as text; other keys are `None`.
+fn dict_entries(rt: &Runtime, v: &Value) -> Option<Vec<(Option<String>, Value)>> {
+    if v.get_tag() != TAG_DICT {
+        return None;
+    }
+    match rt.heap.get(v.as_obj_id()) {
+        ManagedObject::Dict(d) => Some(d.entries().map(|(k, v)| (key_text(rt, &k), v)).collect()),
+        _ => None,
+    }
+}
+
+fn number(v: &Value) -> Option<f64> {
+    if v.is_int() {
+        Some(v.as_i64() as f64)
+    } else if v.is_f64() {
+        Some(v.as_f64())
+    } else {
+        None
+    }
+}
+
+impl Schema {
+    fn parse(rt: &Runtime, v: &Value) -> Result<Self, String> {
+        let mut schema = Schema { extra: true, ..Schema::default() };
+        if let Some(name) = text(rt, v) {
+            schema.types.push(Self::type_name(rt, name)?);
+            return Ok(schema);
+        }
+        let entries = dict_entries(rt, v)
+            .ok_or_else(|| format!("validate: a schema is a type name or a dict, got {}", v.type_name()))?;
+        for (key, value) in entries {
+            let Some(key) = key else {
+                return Err("validate: schema keys must be text".into());
+            };
+            let bad = |what: &str| format!("validate: schema key \"{key}\" must be {what}");
+            match key.as_str() {
+                "type" => {
+                    let names = match (text(rt, &value), list(rt, &value)) {
+                        (Some(name), _) => vec![name],
+                        (None, Some(items)) => items
+                            .iter()
+                            .map(|n| text(rt, n).ok_or_else(|| bad("a type name or a list of them")))
+                            .collect::<Result<_, _>>()?,
+                        _ => return Err(bad("a type name or a list of them")),
+                    };
+                    for name in names {
+                        schema.types.push(Self::type_name(rt, name)?);
+                    }
+                }
+                "min" => schema.min = Some(number(&value).ok_or_else(|| bad("a number"))?),
+                "max" => schema.max = Some(number(&value).ok_or_else(|| bad("a number"))?),
+                "min_length" | "max_length" => {
+                    let n = if value.is_int() { usize::try_from(value.as_i64()).ok() } else { None };
+                    let n = n.ok_or_else(|| bad("a non-negative int"))?;
+                    if key == "min_length" {
+                        schema.min_length = Some(n);
+                    } else {
+                        schema.max_length = Some(n);
+                    }
+                }
+                "pattern" => {
+                    let p = text(rt, &value).ok_or_else(|| bad("a regex"))?;
+                    schema.pattern = Some(Regex::new(&p).map_err(|e| format!("validate: invalid pattern: {e}"))?);
+                }
+                "one_of" => schema.one_of = Some(list(rt, &value).ok_or_else(|| bad("a list"))?),
+                "items" => schema.items = Some(Box::new(Self::parse(rt, &value)?)),
+                "values" => schema.values = Some(Box::new(Self::parse(rt, &value)?)),
+                "keys" => {
+                    for (name, sub) in dict_entries(rt, &value).ok_or_else(|| bad("a dict"))? {
+                        let name = name.ok_or_else(|| bad("a dict with text keys"))?;
+                        schema.keys.push((name, Self::parse(rt, &sub)?));
+                    }
+                }
+                "required" => {
+                    let items = list(rt, &value).ok_or_else(|| bad("a list of keys"))?;
+                    schema.required = items
+                        .iter()
+                        .map(|k| text(rt, k).ok_or_else(|| bad("a list of keys")))
+                        .collect::<Result<_, _>>()?;
+                }
+                "extra" => {
+                    if !value.is_bool() {
+                        return Err(bad("a bool"));
+                    }
+                    schema.extra = value.as_bool();
+                }
+                _ => return Err(format!("validate: unknown schema key \"{key}\"")),
+            }
+        }
+        Ok(schema)
+    }
+
+    /// `name`, if it names a builtin type or a declared struct or enum.
+    fn type_name(rt: &Runtime, name: String) -> Result<String, String> {
+        let known = BUILTIN_TYPES.contains(&name.as_str())
+            || rt.types.structs.contains_key(&name)
+            || rt.types.struct_layouts.contains_key(&name)
+            || rt.types.enums.contains_key(&name);
+        if known { Ok(name) } else { Err(format!("validate: unknown type \"{name}\" in schema")) }
+    }
+
+    fn matches_type(&self, actual: &str, v: &Value) -> bool {
+        self.types.is_empty()
+            || self.types.iter().any(|t| match t.as_str() {
+                "any" => true,
+                "number" => v.is_int() || v.is_f64(),
+                t => t == actual,
+            })
+    }
+
+    fn check(&self, rt: &Runtime, v: &Value, path: &str, out: &mut Vec<String>) {
+        let actual = type_name_of(rt, v);
+        if !self.matches_type(&actual, v) {
+            out.push(format!("{path}: expected {}, got {actual}", self.types.join(" or ")));
+            return;
+        }
+        let show = |v: &Value| rt.inspect(v, &PrettyOptions::default());
+        if let Some(allowed) = &self.one_of {
+            if !allowed.iter().any(|a| rt.values_equal(a, v)) {
+                let allowed: Vec<String> = allowed.iter().map(show).collect();
+                out.push(format!("{path}: {} is not one of {}", show(v), allowed.join(", ")));
+            }
+        }
+        if let Some(n) = number(v) {
+            if let Some(min) = self.min {
+                if n < min {
+                    out.push(format!("{path}: {} is less than the minimum {}", show(v), show(&bound(min))));
+                }
+            }
+            if let Some(max) = self.max {
+                if n > max {
+                    out.push(format!("{path}: {} is more than the maximum {}", show(v), show(&bound(max))));
+                }
+            }
+        }
+        let tag = v.get_tag();
+        let obj = if matches!(tag, TAG_STR | TAG_LIST | TAG_DICT | TAG_TUPLE | TAG_STRUCT) {
+            Some(rt.heap.get(v.as_obj_id()))
+        } else {
+            None
+        };
+        let len = match obj {
+            Some(ManagedObject::Str(s)) => Some(s.char_count()),
+            Some(obj @ (ManagedObject::List(_) | ManagedObject::Tuple(_))) => obj.elements().map(|items| items.len()),
+            Some(ManagedObject::Dict(d)) => Some(d.len()),
+            _ => None,
+        };
+        if let Some(len) = len {
+            if let Some(min) = self.min_length {
+                if len < min {
+                    out.push(format!("{path}: length {len} is less than the minimum {min}"));
+                }
+            }
+            if let Some(max) = self.max_length {
+                if len > max {
+                    out.push(format!("{path}: length {len} is more than the maximum {max}"));
+                }
+            }
+        }
+        match obj {
+            Some(ManagedObject::Str(s)) => {
+                if let Some(re) = &self.pattern {
+                    if !re.is_match(s.as_str()) {
+                        out.push(format!("{path}: {} does not match {}", show(v), re.as_str()));
+                    }
+                }
+            }
+            Some(ManagedObject::List(items)) => {
+                if let Some(item_schema) = &self.items {
+                    for (i, item) in items.iter().enumerate() {
+                        item_schema.check(rt, item, &format!("{path}[{i}]"), out);
+                    }
+                }
+            }
+            Some(ManagedObject::Dict(d)) => {
+                let entries: Vec<_> = d.entries().map(|(k, value)| (k, key_text(rt, &k), value)).collect();
+                for key in &self.required {
+                    if !entries.iter().any(|(_, name, _)| name.as_deref() == Some(key.as_str())) {
+                        out.push(format!("{}: missing required key", key_path(path, key)));
+                    }
+                }
+                for (key, name, value) in &entries {
+                    let sub_path = match name {
+                        Some(name) => key_path(path, name),
+                        None => format!("{path}[{}]", show(&key.to_value(&rt.heap))),
+                    };
+                    match name.as_deref().and_then(|n| self.keys.iter().find(|(k, _)| k == n)) {
+                        Some((_, sub)) => sub.check(rt, value, &sub_path, out),
+                        None => {
+                            if !self.extra {
+                                out.push(format!("{sub_path}: unknown key"));
+                            } else if let Some(values) = &self.values {
+                                values.check(rt, value, &sub_path, out);
+                            }
+                        }
+                    }
+                }
+            }
+            Some(ManagedObject::Struct(s)) => {
+                for (name, sub) in &self.keys {
+                    if let Some(i) = s.field_names.iter().position(|f| f == name) {
+                        sub.check(rt, &s.fields[i], &key_path(path, name), out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A bound as the value it is shown as: ints stay ints.
+fn bound(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 9.0e15 { Value::from_i64(n as i64) } else { Value::from_f64(n) }
+}
+
+/// `path.key` for keys that read as names, `path["key"]` for others.
+fn key_path(path: &str, key: &str) -> String {
+    let is_name = key.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if is_name { format!("{path}.{key}") } else { format!("{path}[{key:?}]") }
+}
@@ -0,0 +1,391 @@
+//! `serialize` and `deserialize`: a compact binary encoding of values, for
+//! handing structured state to workers, other processes or files.
+//!
+//! The encoding is the magic `XU`, a version byte, then one value. A value
+//! is a tag byte and its payload; counts and lengths are LEB128 varints and
+//! ints are zigzag varints:
+//!
+//! | Tag | Value | Payload |
+//! |-----|-------|---------|
+//! | 0 | unit | |
+//! | 1, 2 | `false`, `true` | |
+//! | 3 | int | varint |
+//! | 4 | float | 8 bytes, little-endian |
+//! | 5 | text | length, UTF-8 bytes |
+//! | 6, 7 | list, tuple | count, items |
+//! | 8 | dict | count, key and value of each entry |
+//! | 9 | range | start, end, inclusive byte |
+//! | 10 | `Option#some` | the inner value |
+//! | 11 | enum | type name, variant name, count, payload |
+//! | 12 | struct | type name, count, name and value of each field |
+//! | 13 | back-reference | index |
+//!
+//! Every heap value (tags 5 to 12) is numbered in the order it starts; a
+//! value met again, including one that contains itself, is written as a
+//! back-reference, so shared and cyclic structure comes back as it was.
+//! Names are written as text payloads without a tag. Functions, files,
+//! modules, builders and channels cannot be serialized.
+
+use std::collections::HashMap;
+
+use super::super::Runtime;
+use super::encoding::{bytes_arg, bytes_value};
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{
+    DictKey, StructInstance, TAG_DICT, TAG_ENUM, TAG_LIST, TAG_OPTION, TAG_RANGE, TAG_STR, TAG_STRUCT, TAG_TUPLE,
+};
+
+const MAGIC: &[u8; 2] = b"XU";
+const VERSION: u8 = 1;
+
+/// Nesting limit, so that hostile input cannot overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+const T_UNIT: u8 = 0;
+const T_FALSE: u8 = 1;
+const T_TRUE: u8 = 2;
+const T_INT: u8 = 3;
+const T_FLOAT: u8 = 4;
+const T_STR: u8 = 5;
+const T_LIST: u8 = 6;
+const T_TUPLE: u8 = 7;
+const T_DICT: u8 = 8;
+const T_RANGE: u8 = 9;
+const T_SOME: u8 = 10;
+const T_ENUM: u8 = 11;
+const T_STRUCT: u8 = 12;
+const T_REF: u8 = 13;
+
+pub fn builtin_serialize(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("serialize expects 1 argument".into());
+    }
+    let mut w = Writer { rt, out: MAGIC.to_vec(), seen: HashMap::new() };
+    w.out.push(VERSION);
+    w.value(&args[0], 0)?;
+    let out = w.out;
+    Ok(bytes_value(rt, &out))
+}
+
+pub fn builtin_deserialize(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("deserialize expects 1 argument".into());
+    }
+    let bytes = bytes_arg(rt, &args[0], "deserialize")?;
+    if bytes.len() < 3 || &bytes[..2] != MAGIC {
+        return Err("deserialize: not serialized data".into());
+    }
+    if bytes[2] != VERSION {
+        return Err(format!("deserialize: unsupported version {}", bytes[2]));
+    }
+    let mut r = Reader { rt, bytes: &bytes, pos: 3, table: Vec::new() };
+    let v = r.value(0)?;
+    if r.pos != bytes.len() {
+        return Err("deserialize: trailing bytes after the value".into());
+    }
+    Ok(v)
+}
+
+struct Writer<'a> {
+    rt: &'a Runtime,
+    out: Vec<u8>,
+    /// Index of each heap object written so far.
+    seen: HashMap<usize, usize>,
+}
+
+impl Writer<'_> {
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.out.push((n as u8) | 0x80);
+            n >>= 7;
+        }
+        self.out.push(n as u8);
+    }
+
+    fn int(&mut self, i: i64) {
+        self.varint(((i << 1) ^ (i >> 63)) as u64);
+    }
+
+    fn text(&mut self, s: &str) {
+        self.varint(s.len() as u64);
+        self.out.extend_from_slice(s.as_bytes());
+    }
+
+    fn value(&mut self, v: &Value, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err("serialize: value is nested too deeply".into());
+        }
+        if v.is_unit() {
+            self.out.push(T_UNIT);
+            return Ok(());
+        }
+        if v.is_bool() {
+            self.out.push(if v.as_bool() { T_TRUE } else { T_FALSE });
+            return Ok(());
+        }
+        if v.is_int() {
+            self.out.push(T_INT);
+            self.int(v.as_i64());
+            return Ok(());
+        }
+        if v.is_f64() {
+            self.out.push(T_FLOAT);
+            self.out.extend_from_slice(&v.as_f64().to_le_bytes());
+            return Ok(());
+        }
+        let tag = v.get_tag();
+        if !matches!(tag, TAG_STR | TAG_LIST | TAG_TUPLE | TAG_DICT | TAG_RANGE | TAG_OPTION | TAG_ENUM | TAG_STRUCT) {
+            return Err(format!("serialize: cannot encode a {}", v.type_name()));
+        }
+        let id = v.as_obj_id();
+        if let Some(&index) = self.seen.get(&id.0) {
+            self.out.push(T_REF);
+            self.varint(index as u64);
+            return Ok(());
+        }
+        self.seen.insert(id.0, self.seen.len());
+        let rt = self.rt;
+        match rt.heap.get(id) {
+            ManagedObject::Str(s) => {
+                self.out.push(T_STR);
+                self.text(s.as_str());
+            }
+            obj @ (ManagedObject::List(_) | ManagedObject::Tuple(_)) => {
+                let items = obj.elements().unwrap_or_default();
+                self.out.push(if tag == TAG_LIST { T_LIST } else { T_TUPLE });
+                self.varint(items.len() as u64);
+                for item in items.iter() {
+                    self.value(item, depth + 1)?;
+                }
+            }
+            ManagedObject::Dict(d) => {
+                self.out.push(T_DICT);
+                self.varint(d.len() as u64);
+                for (k, value) in d.entries() {
+                    self.value(&k.to_value(&rt.heap), depth + 1)?;
+                    self.value(&value, depth + 1)?;
+                }
+            }
+            ManagedObject::Range(start, end, inclusive) => {
+                self.out.push(T_RANGE);
+                self.int(*start);
+                self.int(*end);
+                self.out.push(u8::from(*inclusive));
+            }
+            ManagedObject::OptionSome(inner) => {
+                self.out.push(T_SOME);
+                self.value(inner, depth + 1)?;
+            }
+            ManagedObject::Enum(e) => {
+                let (ty, variant, payload) = e.as_ref();
+                self.out.push(T_ENUM);
+                self.text(ty.as_str());
+                self.text(variant.as_str());
+                self.varint(payload.len() as u64);
+                for item in payload.iter() {
+                    self.value(item, depth + 1)?;
+                }
+            }
+            ManagedObject::Struct(s) => {
+                self.out.push(T_STRUCT);
+                self.text(&s.ty);
+                self.varint(s.fields.len() as u64);
+                for (name, field) in s.field_names.iter().zip(s.fields.iter()) {
+                    self.text(name);
+                    self.value(field, depth + 1)?;
+                }
+            }
+            _ => return Err(format!("serialize: cannot encode a {}", v.type_name())),
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    rt: &'a mut Runtime,
+    bytes: &'a [u8],
+    pos: usize,
+    /// Heap values read so far, by index; containers are entered before
+    /// their items are read, so that back-references to them resolve.
+    table: Vec<Value>,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, String> {
+        let b = *self.bytes.get(self.pos).ok_or("deserialize: truncated data")?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            n |= u64::from(b & 0x7F) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err("deserialize: varint too long".into())
+    }
+
+    fn int(&mut self) -> Result<i64, String> {
+        let n = self.varint()?;
+        Ok(((n >> 1) as i64) ^ -((n & 1) as i64))
+    }
+
+    /// A count of items that each take at least one byte.
+    fn count(&mut self) -> Result<usize, String> {
+        let n = self.varint()?;
+        if n > (self.bytes.len() - self.pos) as u64 {
+            return Err("deserialize: truncated data".into());
+        }
+        Ok(n as usize)
+    }
+
+    fn text(&mut self) -> Result<String, String> {
+        let len = self.count()?;
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "deserialize: text is not valid UTF-8".to_string())
+    }
+
+    fn enter(&mut self, v: Value) -> Value {
+        self.table.push(v);
+        v
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err("deserialize: value is nested too deeply".into());
+        }
+        let tag = self.byte()?;
+        Ok(match tag {
+            T_UNIT => Value::UNIT,
+            T_FALSE => Value::from_bool(false),
+            T_TRUE => Value::from_bool(true),
+            T_INT => Value::from_i64(self.int()?),
+            T_FLOAT => {
+                let end = self.pos + 8;
+                let bytes = self.bytes.get(self.pos..end).ok_or("deserialize: truncated data")?;
+                let f = f64::from_le_bytes(bytes.try_into().expect("8 bytes"));
+                self.pos = end;
+                Value::from_f64(f)
+            }
+            T_STR => {
+                let s = self.text()?;
+                let v = Value::str(self.rt.alloc(ManagedObject::Str(s.into())));
+                self.enter(v)
+            }
+            T_LIST | T_TUPLE => {
+                let n = self.count()?;
+                let v = if tag == T_LIST {
+                    Value::list(self.rt.alloc(ManagedObject::List(Vec::with_capacity(n).into())))
+                } else {
+                    Value::tuple(self.rt.alloc(ManagedObject::Tuple(Vec::with_capacity(n))))
+                };
+                self.enter(v);
+                for _ in 0..n {
+                    let item = self.value(depth + 1)?;
+                    match self.rt.heap_get_mut(v.as_obj_id()) {
+                        ManagedObject::List(items) => items.make_mut().push(item),
+                        ManagedObject::Tuple(items) => items.push(item),
+                        _ => {}
+                    }
+                }
+                v
+            }
+            T_DICT => {
+                let n = self.count()?;
+                let dict = crate::core::value::dict_with_capacity(n, &self.rt.heap);
+                let v = Value::dict(self.rt.alloc(ManagedObject::Dict(dict)));
+                self.enter(v);
+                for _ in 0..n {
+                    let key = self.value(depth + 1)?;
+                    let key = DictKey::from_value(key, &mut self.rt.heap)
+                        .ok_or_else(|| format!("deserialize: a {} cannot be a dict key", key.type_name()))?;
+                    let value = self.value(depth + 1)?;
+                    if let ManagedObject::Dict(d) = self.rt.heap_get_mut(v.as_obj_id()) {
+                        d.insert_entry(key, value);
+                    }
+                }
+                v
+            }
+            T_RANGE => {
+                let start = self.int()?;
+                let end = self.int()?;
+                let inclusive = self.byte()? != 0;
+                let v = Value::range(self.rt.alloc(ManagedObject::Range(start, end, inclusive)));
+                self.enter(v)
+            }
+            T_SOME => {
+                let v = Value::option_some(self.rt.alloc(ManagedObject::OptionSome(Value::UNIT)));
+                self.enter(v);
+                let inner = self.value(depth + 1)?;
+                *self.rt.heap_get_mut(v.as_obj_id()) = ManagedObject::OptionSome(inner);
+                v
+            }
+            T_ENUM => {
+                let ty = self.text()?;
+                let variant = self.text()?;
+                let n = self.count()?;
+                if ty == "Option" && variant == "none" && n == 0 {
+                    let v = self.rt.option_none();
+                    return Ok(self.enter(v));
+                }
+                let v = Value::enum_obj(self.rt.alloc(ManagedObject::Enum(Box::new((
+                    ty.into(),
+                    variant.into(),
+                    Box::new([]),
+                )))));
+                self.enter(v);
+                let mut payload = Vec::with_capacity(n);
+                for _ in 0..n {
+                    payload.push(self.value(depth + 1)?);
+                }
+                if let ManagedObject::Enum(e) = self.rt.heap_get_mut(v.as_obj_id()) {
+                    e.2 = payload.into_boxed_slice();
+                }
+                v
+            }
+            T_STRUCT => {
+                let ty = self.text()?;
+                let n = self.count()?;
+                let mut names = Vec::with_capacity(n);
+                let v = Value::struct_obj(self.rt.alloc(ManagedObject::Struct(Box::new(StructInstance {
+                    ty_hash: xu_ir::stable_hash64(&ty),
+                    ty: ty.clone(),
+                    fields: vec![Value::UNIT; n].into_boxed_slice(),
+                    field_names: Vec::new().into(),
+                }))));
+                self.enter(v);
+                for i in 0..n {
+                    names.push(self.text()?);
+                    let field = self.value(depth + 1)?;
+                    if let ManagedObject::Struct(s) = self.rt.heap_get_mut(v.as_obj_id()) {
+                        s.fields[i] = field;
+                    }
+                }
+                // Share the registered layout when it matches, as a struct
+                // literal would.
+                let layout = match self.rt.types.struct_layouts.get(&ty) {
+                    Some(l) if l[..] == names[..] => l.clone(),
+                    _ => names.into(),
+                };
+                if let ManagedObject::Struct(s) = self.rt.heap_get_mut(v.as_obj_id()) {
+                    s.field_names = layout;
+                }
+                v
+            }
+            T_REF => {
+                let index = self.varint()?;
+                *usize::try_from(index)
+                    .ok()
+                    .and_then(|i| self.table.get(i))
+                    .ok_or("deserialize: back-reference to a value not yet read")?
+            }
+            other => return Err(format!("deserialize: unknown tag {other}")),
+        })
+    }
+}
@@ -1,5 +1,6 @@
 use super::super::Runtime;
 use crate::Value;
+#[cfg(feature = "sys")]
 use libc::{getrusage, rusage, RUSAGE_SELF};
 
 pub fn builtin_time_unix(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
@@ -30,6 +31,32 @@ pub fn builtin_mono_nanos(rt: &mut Runtime, args: &[Value]) -> Result<Value, Str
     Ok(Value::from_i64(rt.clock_mono_nanos()))
 }
 
+/// Longest single wait of `sleep`, so interrupts and signal handlers are not
+/// held up by a long sleep.
+const SLEEP_SLICE_MS: u64 = 50;
+
+pub fn builtin_sleep(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("sleep expects 1 argument".into());
+    }
+    if !args[0].is_int() {
+        return Err(format!("sleep expects an int number of milliseconds, got {}", args[0].type_name()));
+    }
+    let ms = args[0].as_i64();
+    if ms < 0 {
+        return Err(format!("sleep: milliseconds must not be negative, got {ms}"));
+    }
+    let mut left = ms as u64;
+    while left > 0 {
+        let slice = left.min(SLEEP_SLICE_MS);
+        rt.clock_sleep_millis(slice);
+        left -= slice;
+        rt.check_interrupt()?;
+        rt.check_signals()?;
+    }
+    Ok(Value::UNIT)
+}
+
 pub fn builtin_rand(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
     if args.len() > 1 {
         return Err("rand expects 0 or 1 argument".into());
@@ -67,7 +94,7 @@ pub fn builtin_os_args(rt: &mut Runtime, args: &[Value]) -> Result<Value, String
         })
         .collect::<Vec<_>>();
     Ok(Value::list(
-        rt.alloc(crate::core::heap::ManagedObject::List(items)),
+        rt.alloc(crate::core::heap::ManagedObject::List(items.into())),
     ))
 }
 
@@ -84,12 +111,12 @@ pub fn builtin_env_get(rt: &mut Runtime, args: &[Value]) -> Result<Value, String
     } else {
         return Err("env_get expects string".into());
     };
-    match std::env::var(&key) {
-        Ok(val) => Ok(Value::str(
+    match rt.env_var(&key) {
+        Some(val) => Ok(Value::str(
             rt.heap
                 .alloc(crate::core::heap::ManagedObject::Str(val.into())),
         )),
-        Err(_) => Ok(Value::str(
+        None => Ok(Value::str(
             rt.heap
                 .alloc(crate::core::heap::ManagedObject::Str("".into())),
         )),
@@ -97,6 +124,11 @@ pub fn builtin_env_get(rt: &mut Runtime, args: &[Value]) -> Result<Value, String
 }
 
 pub fn builtin_process_rss(_rt: &mut Runtime, _args: &[Value]) -> Result<Value, String> {
+    Ok(Value::from_i64(peak_rss()))
+}
+
+#[cfg(feature = "sys")]
+fn peak_rss() -> i64 {
     let mut usage = rusage {
         ru_utime: libc::timeval { tv_sec: 0, tv_usec: 0 },
         ru_stime: libc::timeval { tv_sec: 0, tv_usec: 0 },
@@ -118,8 +150,61 @@ pub fn builtin_process_rss(_rt: &mut Runtime, _args: &[Value]) -> Result<Value,
     unsafe {
         let _ = getrusage(RUSAGE_SELF, &mut usage);
     }
-    let rss = usage.ru_maxrss;
-    Ok(Value::from_i64(rss))
+    usage.ru_maxrss
+}
+
+/// Without the `sys` feature there are no process statistics.
+#[cfg(not(feature = "sys"))]
+fn peak_rss() -> i64 {
+    0
+}
+
+pub fn builtin_with_capability(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("with_capability expects 2 arguments".into());
+    }
+    let capability = if args[0].get_tag() == crate::core::value::TAG_STR {
+        if let crate::core::heap::ManagedObject::Str(s) = rt.heap.get(args[0].as_obj_id()) {
+            s.to_string()
+        } else {
+            return Err("with_capability expects text".into());
+        }
+    } else {
+        return Err("with_capability expects text".into());
+    };
+    if args[1].get_tag() != crate::core::value::TAG_FUNC {
+        return Err("with_capability expects a function".into());
+    }
+    if !super::super::util::KNOWN_CAPABILITIES.contains(&capability.as_str()) {
+        return Err(format!("with_capability: unknown capability '{capability}'"));
+    }
+    if !rt.request_capability(&capability) {
+        return Err(rt.error(xu_syntax::DiagnosticKind::CapabilityDenied(capability)));
+    }
+    let res = rt.call_function(args[1], &[]);
+    rt.release_capability(&capability);
+    res
+}
+
+/// `on_signal(name, handler)` runs `handler()` when the signal `name`
+/// (`"int"`, `"term"` or `"hup"`) arrives. Needs the `signal` capability.
+pub fn builtin_on_signal(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("on_signal expects 2 arguments".into());
+    }
+    let name = match (args[0].get_tag() == crate::core::value::TAG_STR).then(|| rt.heap.get(args[0].as_obj_id())) {
+        Some(crate::core::heap::ManagedObject::Str(s)) => s.to_string(),
+        _ => return Err("on_signal expects text".into()),
+    };
+    let Some(signal) = crate::Signal::parse(&name) else {
+        return Err(format!("on_signal: unknown signal '{name}' (expected int, term or hup)"));
+    };
+    if args[1].get_tag() != crate::core::value::TAG_FUNC {
+        return Err("on_signal expects a function".into());
+    }
+    rt.check_signal_grant(&name)?;
+    rt.set_signal_handler(signal, args[1]);
+    Ok(Value::UNIT)
 }
 
 pub fn builtin_heap_stats(rt: &mut Runtime, _args: &[Value]) -> Result<Value, String> {
@@ -137,6 +222,54 @@ pub fn builtin_heap_stats(rt: &mut Runtime, _args: &[Value]) -> Result<Value, St
     Ok(Value::UNIT)
 }
 
+/// `std/gc` `stats()`: the runtime's `gc_stats` as a dict, pauses in
+/// milliseconds.
+pub fn builtin_gc_stats(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("gc.stats expects 0 arguments".into());
+    }
+    let stats = rt.gc_stats();
+    let fields = [
+        ("collections", Value::from_i64(stats.collections as i64)),
+        ("objects_freed", Value::from_i64(stats.objects_freed as i64)),
+        ("bytes_freed", Value::from_i64(stats.bytes_freed as i64)),
+        ("live_objects", Value::from_i64(stats.live_objects as i64)),
+        ("live_bytes", Value::from_i64(stats.live_bytes as i64)),
+        ("total_pause_ms", Value::from_f64(stats.total_pause.as_secs_f64() * 1000.0)),
+        ("max_pause_ms", Value::from_f64(stats.max_pause.as_secs_f64() * 1000.0)),
+        ("pool_reuses", Value::from_i64(stats.pool_reuses as i64)),
+    ];
+    let mut dict = crate::core::value::dict_with_capacity(fields.len(), &rt.heap);
+    for (name, value) in fields {
+        let id = rt.alloc(crate::core::heap::ManagedObject::Str(name.into()));
+        let key = crate::core::value::DictKey::from_str_obj(id, crate::core::value::DictKey::hash_str(name, &rt.heap));
+        dict.insert_entry(key, value);
+    }
+    Ok(Value::dict(rt.alloc(crate::core::heap::ManagedObject::Dict(dict))))
+}
+
+/// `bench "name": ...` blocks: timed runs of the block in bench mode,
+/// skipped otherwise.
+pub fn builtin_bench(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("__bench expects 2 arguments".into());
+    }
+    let name = if args[0].get_tag() == crate::core::value::TAG_STR {
+        if let crate::core::heap::ManagedObject::Str(s) = rt.heap.get(args[0].as_obj_id()) {
+            s.to_string()
+        } else {
+            return Err("bench expects a text name".into());
+        }
+    } else {
+        return Err("bench expects a text name".into());
+    };
+    if args[1].get_tag() != crate::core::value::TAG_FUNC {
+        return Err("__bench expects a function".into());
+    }
+    rt.run_bench(name, args[1])?;
+    Ok(Value::UNIT)
+}
+
 pub fn builtin_assert(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
     if args.is_empty() || args.len() > 2 {
         return Err("__builtin_assert expects 1 or 2 arguments".into());
@@ -186,6 +319,9 @@ pub fn builtin_builder_new(rt: &mut Runtime, args: &[Value]) -> Result<Value, St
 }
 
 
+/// The capacity given to `builder_new_cap` is only a hint; larger ones are
+/// clamped rather than allocated up front.
+const BUILDER_CAPACITY_MAX: usize = 1 << 24;
 
 pub fn builtin_builder_new_with_capacity(
     rt: &mut Runtime,
@@ -203,7 +339,7 @@ pub fn builtin_builder_new_with_capacity(
         return Err("builder_new_cap expects non-negative number".into());
     };
     Ok(Value::builder(rt.alloc(
-        crate::core::heap::ManagedObject::Builder(String::with_capacity(cap)),
+        crate::core::heap::ManagedObject::Builder(String::with_capacity(cap.min(BUILDER_CAPACITY_MAX))),
     )))
 }
 
@@ -234,15 +370,9 @@ pub fn builtin_builder_push(rt: &mut Runtime, args: &[Value]) -> Result<Value, S
             sb.push_str(digits);
         }
     } else if v.is_f64() {
-        let f = v.as_f64();
-        let s = if f.fract() == 0.0 {
-            let mut buf = itoa::Buffer::new();
-            buf.format(f as i64).to_string()
-        } else {
-            f.to_string()
-        };
+        let format = rt.heap.float_format;
         if let crate::core::heap::ManagedObject::Builder(sb) = rt.heap_get_mut(id) {
-            sb.push_str(&s);
+            crate::util::write_f64(sb, v.as_f64(), &format);
         }
     } else if v.get_tag() == crate::core::value::TAG_STR {
         // Optimize: get string pointer and length first, then push
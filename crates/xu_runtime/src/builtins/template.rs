@@ -0,0 +1,400 @@
+//! The hook behind `std/template`: text templates with `{{ name }}`
+//! substitutions, `{% if %}` conditionals and `{% for %}` loops.
+//!
+//! Templates are compiled to a list of [`Node`]s on first use and cached by
+//! their text, so rendering the same template in a loop parses it once.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::super::Runtime;
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{TAG_DICT, TAG_ENUM, TAG_LIST, TAG_STR, TAG_STRUCT, TAG_TUPLE};
+use crate::util::value_to_string;
+
+/// Compiled templates kept at most; the cache starts over when full.
+const CACHE_LIMIT: usize = 128;
+
+/// Compiled templates of a runtime, by template text.
+#[derive(Default)]
+pub(crate) struct TemplateCache {
+    compiled: HashMap<String, Rc<[Node]>>,
+}
+
+/// A dotted name such as `user.address.city` or `rows.0`, with the line it
+/// is used on for error messages.
+#[derive(Debug)]
+struct Path {
+    parts: Vec<String>,
+    line: usize,
+}
+
+impl Path {
+    fn show(&self) -> String {
+        self.parts.join(".")
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Filter {
+    Escape,
+    Upper,
+    Lower,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Var(Path, Vec<Filter>),
+    /// Branches of `if` / `elif`, each a test and whether it is negated,
+    /// then the `else` body.
+    If(Vec<(Path, bool, Vec<Node>)>, Vec<Node>),
+    For(String, Path, Vec<Node>),
+}
+
+pub fn builtin_template_render(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("__template_render expects 2 arguments".into());
+    }
+    let text = match (args[0].get_tag() == TAG_STR).then(|| rt.heap.get(args[0].as_obj_id())) {
+        Some(ManagedObject::Str(s)) => s.as_str().to_string(),
+        _ => return Err(format!("template.render expects text, got {}", args[0].type_name())),
+    };
+    let nodes = match rt.templates.compiled.get(&text) {
+        Some(nodes) => nodes.clone(),
+        None => {
+            let nodes: Rc<[Node]> = compile(&text)?.into();
+            if rt.templates.compiled.len() >= CACHE_LIMIT {
+                rt.templates.compiled.clear();
+            }
+            rt.templates.compiled.insert(text, nodes.clone());
+            nodes
+        }
+    };
+    let mut out = String::new();
+    let mut scope = Scope { data: args[1], locals: Vec::new() };
+    render(rt, &nodes, &mut scope, &mut out)?;
+    Ok(Value::str(rt.alloc(ManagedObject::Str(out.into()))))
+}
+
+enum Tag {
+    If(Path, bool),
+    Elif(Path, bool),
+    Else,
+    EndIf,
+    For(String, Path),
+    EndFor,
+}
+
+/// Parses `{% ... %}` contents.
+fn parse_tag(body: &str, line: usize) -> Result<Tag, String> {
+    let words: Vec<&str> = body.split_whitespace().collect();
+    let test = |rest: &[&str]| -> Result<(Path, bool), String> {
+        match rest {
+            [name] => Ok((parse_path(name, line)?, false)),
+            ["not", name] => Ok((parse_path(name, line)?, true)),
+            _ => Err(format!("template: line {line}: expected a name or `not` and a name after if")),
+        }
+    };
+    Ok(match words.as_slice() {
+        ["if", rest @ ..] => {
+            let (path, negate) = test(rest)?;
+            Tag::If(path, negate)
+        }
+        ["elif", rest @ ..] => {
+            let (path, negate) = test(rest)?;
+            Tag::Elif(path, negate)
+        }
+        ["else"] => Tag::Else,
+        ["endif"] => Tag::EndIf,
+        ["for", var, "in", list] => {
+            if !is_name(var) || *var == "loop" {
+                return Err(format!("template: line {line}: bad loop variable {var}"));
+            }
+            Tag::For(var.to_string(), parse_path(list, line)?)
+        }
+        ["endfor"] => Tag::EndFor,
+        _ => return Err(format!("template: line {line}: unknown tag {{% {} %}}", body.trim())),
+    })
+}
+
+fn is_name(s: &str) -> bool {
+    s.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn parse_path(s: &str, line: usize) -> Result<Path, String> {
+    let parts: Vec<String> = s.split('.').map(str::to_string).collect();
+    let ok = is_name(&parts[0]) && parts[1..].iter().all(|p| is_name(p) || p.bytes().all(|b| b.is_ascii_digit()));
+    if !ok || s.is_empty() {
+        return Err(format!("template: line {line}: bad name {s:?}"));
+    }
+    Ok(Path { parts, line })
+}
+
+/// Compiles a template; blocks are built on a stack of open `if` and `for`
+/// tags.
+fn compile(text: &str) -> Result<Vec<Node>, String> {
+    enum Open {
+        If { branches: Vec<(Path, bool, Vec<Node>)>, test: (Path, bool), in_else: bool, line: usize },
+        For { var: String, list: Path, line: usize },
+    }
+    let mut stack: Vec<(Open, Vec<Node>)> = Vec::new();
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut rest = text;
+    let mut line = 1;
+    while !rest.is_empty() {
+        // A `{` not starting a tag is text.
+        let bytes = rest.as_bytes();
+        let Some(start) =
+            (0..bytes.len()).find(|&i| bytes[i] == b'{' && matches!(bytes.get(i + 1), Some(b'{' | b'%' | b'#')))
+        else {
+            nodes.push(Node::Text(rest.to_string()));
+            break;
+        };
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+            line += rest[..start].matches('\n').count();
+        }
+        let kind = rest.as_bytes()[start + 1];
+        let close = match kind {
+            b'{' => "}}",
+            b'%' => "%}",
+            _ => "#}",
+        };
+        let body_start = start + 2;
+        let Some(len) = rest[body_start..].find(close) else {
+            return Err(format!("template: line {line}: unclosed {}", &rest[start..body_start]));
+        };
+        let body = &rest[body_start..body_start + len];
+        rest = &rest[body_start + len + 2..];
+        let tag_line = line;
+        line += body.matches('\n').count();
+        match kind {
+            b'{' => {
+                let mut pieces = body.split('|');
+                let path = parse_path(pieces.next().unwrap_or("").trim(), tag_line)?;
+                let filters = pieces
+                    .map(|f| match f.trim() {
+                        "escape" => Ok(Filter::Escape),
+                        "upper" => Ok(Filter::Upper),
+                        "lower" => Ok(Filter::Lower),
+                        other => Err(format!("template: line {tag_line}: unknown filter {other}")),
+                    })
+                    .collect::<Result<_, _>>()?;
+                nodes.push(Node::Var(path, filters));
+            }
+            b'%' => match parse_tag(body, tag_line)? {
+                Tag::If(path, negate) => {
+                    let open = Open::If { branches: Vec::new(), test: (path, negate), in_else: false, line: tag_line };
+                    stack.push((open, std::mem::take(&mut nodes)));
+                }
+                Tag::Elif(path, negate) => match stack.last_mut() {
+                    Some((Open::If { branches, test, in_else: false, .. }, _)) => {
+                        let (done, negated) = std::mem::replace(test, (path, negate));
+                        branches.push((done, negated, std::mem::take(&mut nodes)));
+                    }
+                    _ => return Err(format!("template: line {tag_line}: elif outside if")),
+                },
+                Tag::Else => match stack.last_mut() {
+                    Some((Open::If { branches, test, in_else, .. }, _)) if !*in_else => {
+                        let (done, negated) = std::mem::replace(test, (Path { parts: Vec::new(), line: 0 }, false));
+                        branches.push((done, negated, std::mem::take(&mut nodes)));
+                        *in_else = true;
+                    }
+                    _ => return Err(format!("template: line {tag_line}: else outside if")),
+                },
+                Tag::EndIf => match stack.pop() {
+                    Some((Open::If { mut branches, test, in_else, .. }, outer)) => {
+                        let body = std::mem::replace(&mut nodes, outer);
+                        let otherwise = if in_else {
+                            body
+                        } else {
+                            branches.push((test.0, test.1, body));
+                            Vec::new()
+                        };
+                        nodes.push(Node::If(branches, otherwise));
+                    }
+                    _ => return Err(format!("template: line {tag_line}: endif without if")),
+                },
+                Tag::For(var, list) => {
+                    stack.push((Open::For { var, list, line: tag_line }, std::mem::take(&mut nodes)));
+                }
+                Tag::EndFor => match stack.pop() {
+                    Some((Open::For { var, list, .. }, outer)) => {
+                        let body = std::mem::replace(&mut nodes, outer);
+                        nodes.push(Node::For(var, list, body));
+                    }
+                    _ => return Err(format!("template: line {tag_line}: endfor without for")),
+                },
+            },
+            _ => {}
+        }
+    }
+    match stack.pop() {
+        Some((Open::If { line, .. }, _)) => Err(format!("template: line {line}: if without endif")),
+        Some((Open::For { line, .. }, _)) => Err(format!("template: line {line}: for without endfor")),
+        None => Ok(nodes),
+    }
+}
+
+/// What a loop binds: its variable, and `loop` with `index` (from 1),
+/// `first` and `last`.
+enum Local<'a> {
+    Var(&'a str, Value),
+    Loop { index: usize, len: usize },
+}
+
+struct Scope<'a> {
+    data: Value,
+    locals: Vec<Local<'a>>,
+}
+
+/// The value of `path`, or `None` when some part of it does not exist.
+fn lookup(rt: &Runtime, scope: &Scope<'_>, path: &Path) -> Option<Value> {
+    let first = path.parts[0].as_str();
+    let mut rest = &path.parts[1..];
+    let mut v = None;
+    for local in scope.locals.iter().rev() {
+        match local {
+            Local::Var(name, value) if *name == first => {
+                v = Some(*value);
+                break;
+            }
+            Local::Loop { index, len } if first == "loop" => {
+                let field = rest.first()?;
+                rest = &rest[1..];
+                v = Some(match field.as_str() {
+                    "index" => Value::from_i64(*index as i64 + 1),
+                    "first" => Value::from_bool(*index == 0),
+                    "last" => Value::from_bool(*index + 1 == *len),
+                    _ => return None,
+                });
+                break;
+            }
+            _ => {}
+        }
+    }
+    let mut v = match v {
+        Some(v) => v,
+        None => member(rt, scope.data, first)?,
+    };
+    for part in rest {
+        v = member(rt, v, part)?;
+    }
+    Some(v)
+}
+
+/// `v.name`: a dict key, struct field or list index.
+fn member(rt: &Runtime, v: Value, name: &str) -> Option<Value> {
+    match v.get_tag() {
+        TAG_DICT => match rt.heap.get(v.as_obj_id()) {
+            ManagedObject::Dict(d) => d
+                .entries()
+                .find_map(|(k, value)| (super::reflect::key_text(rt, &k).as_deref() == Some(name)).then_some(value)),
+            _ => None,
+        },
+        TAG_STRUCT => match rt.heap.get(v.as_obj_id()) {
+            ManagedObject::Struct(s) => s.field_names.iter().position(|f| f == name).map(|i| s.fields[i]),
+            _ => None,
+        },
+        TAG_LIST | TAG_TUPLE => rt.heap.get(v.as_obj_id()).elements()?.get(name.parse::<usize>().ok()?).copied(),
+        _ => None,
+    }
+}
+
+/// `false`, `()`, `0`, empty text and containers, and `Option#none` are
+/// false; everything else is true.
+fn truthy(rt: &Runtime, v: Value) -> bool {
+    if v.is_unit() {
+        return false;
+    }
+    if v.is_bool() {
+        return v.as_bool();
+    }
+    if v.is_int() {
+        return v.as_i64() != 0;
+    }
+    if v.is_f64() {
+        return v.as_f64() != 0.0;
+    }
+    let (TAG_STR | TAG_LIST | TAG_DICT | TAG_ENUM) = v.get_tag() else {
+        return true;
+    };
+    match rt.heap.get(v.as_obj_id()) {
+        ManagedObject::Str(s) => !s.as_str().is_empty(),
+        ManagedObject::List(items) => !items.is_empty(),
+        ManagedObject::Dict(d) => d.len() > 0,
+        ManagedObject::Enum(e) => !(e.0.as_str() == "Option" && e.1.as_str() == "none"),
+        _ => true,
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn render<'a>(rt: &Runtime, nodes: &'a [Node], scope: &mut Scope<'a>, out: &mut String) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(path, filters) => {
+                let v = lookup(rt, scope, path)
+                    .ok_or_else(|| format!("template: line {}: {} is not defined", path.line, path.show()))?;
+                let mut text = value_to_string(&v, &rt.heap);
+                for filter in filters {
+                    text = match filter {
+                        Filter::Escape => escape_html(&text),
+                        Filter::Upper => text.to_uppercase(),
+                        Filter::Lower => text.to_lowercase(),
+                    };
+                }
+                out.push_str(&text);
+            }
+            Node::If(branches, otherwise) => {
+                let taken = branches.iter().find(|(path, negate, _)| {
+                    let on = lookup(rt, scope, path).is_some_and(|v| truthy(rt, v));
+                    on != *negate
+                });
+                let body = taken.map_or(&otherwise[..], |(_, _, body)| &body[..]);
+                render(rt, body, scope, out)?;
+            }
+            Node::For(var, path, body) => {
+                let v = lookup(rt, scope, path)
+                    .ok_or_else(|| format!("template: line {}: {} is not defined", path.line, path.show()))?;
+                let items = match (v.get_tag() == TAG_LIST).then(|| rt.heap.get(v.as_obj_id())) {
+                    Some(ManagedObject::List(items)) => items.to_vec(),
+                    _ => {
+                        return Err(format!(
+                            "template: line {}: {} is not a list but {}",
+                            path.line,
+                            path.show(),
+                            v.type_name()
+                        ));
+                    }
+                };
+                let len = items.len();
+                for (index, item) in items.into_iter().enumerate() {
+                    scope.locals.push(Local::Loop { index, len });
+                    scope.locals.push(Local::Var(var, item));
+                    let result = render(rt, body, scope, out);
+                    scope.locals.truncate(scope.locals.len() - 2);
+                    result?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
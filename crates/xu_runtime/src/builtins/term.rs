@@ -0,0 +1,153 @@
+//! The hooks behind `std/term`. Whether output goes to a terminal comes from
+//! the runtime's [`Terminal`](crate::Terminal), so embedders capturing the
+//! output get plain text and scripted answers.
+
+use super::super::Runtime;
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{TAG_DICT, TAG_LIST};
+use crate::util::value_to_string;
+use crate::xu_builtin;
+
+/// The cells of one table row: a list's items, or a dict's values.
+fn row_cells(rt: &Runtime, row: &Value) -> Result<Vec<Value>, String> {
+    let heap_row = matches!(row.get_tag(), TAG_LIST | TAG_DICT);
+    match heap_row.then(|| rt.heap.get(row.as_obj_id())) {
+        Some(ManagedObject::List(items)) => Ok(items.to_vec()),
+        Some(ManagedObject::Dict(d)) => Ok(d.entries().map(|(_, v)| v).collect()),
+        _ => Err(format!("term.table: a row must be a list or a dict, got {}", row.type_name())),
+    }
+}
+
+fn colors_on(rt: &Runtime) -> bool {
+    rt.terminal_is_tty() && rt.env_var("NO_COLOR").is_none() && rt.env_var("TERM").as_deref() != Some("dumb")
+}
+
+/// Terminal columns taken by `s`, leaving out ANSI escape sequences.
+fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut rest = s;
+    while let Some(start) = rest.find('\x1b') {
+        width += xu_syntax::display_width(&rest[..start]);
+        rest = &rest[start + 1..];
+        if let Some(body) = rest.strip_prefix('[') {
+            let end = body.find(|c: char| c.is_ascii_alphabetic()).map_or(body.len(), |i| i + 1);
+            rest = &body[end..];
+        }
+    }
+    width + xu_syntax::display_width(rest)
+}
+
+xu_builtin! {
+    /// Hooks behind `std/term`. Installed by [`StdBuiltinProvider`](crate::StdBuiltinProvider).
+    pub struct TermBuiltinProvider;
+
+    /// Whether output is shown on an interactive terminal.
+    #[name = "__term_is_tty"]
+    pub fn is_tty(rt: &Runtime) -> bool {
+        rt.terminal_is_tty()
+    }
+
+    /// Whether to color output: on a terminal, unless `NO_COLOR` is set or
+    /// `TERM` is `dumb`.
+    #[name = "__term_colors"]
+    pub fn colors(rt: &Runtime) -> bool {
+        colors_on(rt)
+    }
+
+    /// `text` wrapped in the ANSI style `code` (such as `31` or `1;4`) when
+    /// colors are on.
+    #[name = "__term_style"]
+    pub fn style(rt: &Runtime, text: String, code: String) -> String {
+        if colors_on(rt) { format!("\x1b[{code}m{text}\x1b[0m") } else { text }
+    }
+
+    /// Columns of the terminal from `COLUMNS`, 80 when unset.
+    #[name = "__term_width"]
+    pub fn width(rt: &Runtime) -> i64 {
+        rt.env_var("COLUMNS").and_then(|c| c.trim().parse().ok()).filter(|c| *c > 0).unwrap_or(80)
+    }
+
+    /// Writes `text` and shows the output so far right away.
+    #[name = "__term_write"]
+    pub fn write(rt: &mut Runtime, text: String) {
+        rt.write_output_raw(&text);
+        rt.flush_output();
+    }
+
+    /// Shows `prompt` and reads the answer; none at the end of input.
+    #[name = "__term_read_line"]
+    pub fn read_line(rt: &mut Runtime, prompt: String) -> Option<String> {
+        rt.write_output_raw(&prompt);
+        rt.flush_output();
+        rt.terminal_read_line()
+    }
+
+    /// One line of a progress bar `width` characters wide.
+    #[name = "__term_bar"]
+    pub fn bar(current: i64, total: i64, width: i64, label: String) -> String {
+        let total = total.max(1);
+        let current = current.clamp(0, total);
+        let width = width.max(1);
+        let filled = (current * width / total) as usize;
+        let percent = current * 100 / total;
+        let mut line = format!("[{}{}] {percent:>3}% {current}/{total}", "#".repeat(filled), "-".repeat(width as usize - filled));
+        if !label.is_empty() {
+            line.push(' ');
+            line.push_str(&label);
+        }
+        line
+    }
+
+    /// `rows` laid out in columns two spaces apart, without a final line
+    /// break. With `header` the first row (or, for dict rows, the keys of
+    /// the first) is underlined. Numbers are aligned to the right, other
+    /// cells to the left.
+    #[name = "__term_table"]
+    pub fn table(rt: &Runtime, rows: Vec<Value>, header: bool) -> Result<String, String> {
+        let mut lines: Vec<Vec<(String, bool)>> = Vec::new();
+        if let (true, Some(first)) = (header, rows.first()) {
+            if first.get_tag() == TAG_DICT {
+                if let ManagedObject::Dict(d) = rt.heap.get(first.as_obj_id()) {
+                    let keys = d.entries().map(|(k, _)| super::reflect::key_text(rt, &k).unwrap_or_default());
+                    lines.push(keys.map(|k| (k, false)).collect());
+                }
+            }
+        }
+        for row in &rows {
+            let cells = row_cells(rt, row)?;
+            lines.push(cells.iter().map(|c| (value_to_string(c, &rt.heap), c.is_int() || c.is_f64())).collect());
+        }
+        let columns = lines.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for line in &lines {
+            for (i, (cell, _)) in line.iter().enumerate() {
+                widths[i] = widths[i].max(visible_width(cell));
+            }
+        }
+        let mut out = Vec::with_capacity(lines.len() + 1);
+        for (n, line) in lines.iter().enumerate() {
+            let mut text = String::new();
+            for (i, width) in widths.iter().enumerate() {
+                let (cell, number) = line.get(i).map_or(("", false), |(c, n)| (c.as_str(), *n));
+                let pad = " ".repeat(width - visible_width(cell));
+                if i > 0 {
+                    text.push_str("  ");
+                }
+                if number {
+                    text.push_str(&pad);
+                    text.push_str(cell);
+                } else {
+                    text.push_str(cell);
+                    text.push_str(&pad);
+                }
+            }
+            out.push(text.trim_end().to_string());
+            if header && n == 0 {
+                let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                out.push(rule.join("  "));
+            }
+        }
+        Ok(out.join("\n"))
+    }
+}
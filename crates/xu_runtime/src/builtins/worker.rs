@@ -0,0 +1,568 @@
+//! Hooks behind `std/worker`, and `parallel_map`: scripts running on other
+//! threads.
+//!
+//! A runtime is single-threaded, so a worker is a fresh [`Runtime`] on its
+//! own thread with its own heap, sharing the spawner's capabilities (file
+//! system, allowed roots, environment, clock, policy and any recording or
+//! replay; grants stay with the spawner). It loads the declarations of the entry
+//! script (imports, types and functions, but no top-level statements and no
+//! `main`) and calls the spawned function by name. The two sides talk over a
+//! pair of channels carrying [`Transfer`] values, deep copies that own no
+//! heap objects; nothing is shared.
+//!
+//! `parallel_map` runs a pool of such runtimes that each take the next item
+//! until the list is done.
+//!
+//! Worker ids index the spawning runtime's handle table from 1; id 0 is the
+//! channel to the parent inside a worker.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread::JoinHandle;
+
+use super::super::Runtime;
+use crate::InterruptHandle;
+use crate::Value;
+use crate::core::ListItems;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{
+    DictKey, Function, TAG_CHANNEL, TAG_DICT, TAG_ENUM, TAG_FUNC, TAG_LIST, TAG_OPTION, TAG_RANGE, TAG_STR, TAG_STRUCT,
+    TAG_TUPLE,
+};
+
+/// Nesting limit for sent values; also stops cyclic lists and dicts.
+const MAX_DEPTH: usize = 256;
+
+/// A value copied out of one runtime's heap, to be rebuilt in another's.
+#[derive(Clone, Debug)]
+pub(crate) enum Transfer {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Transfer>),
+    Tuple(Vec<Transfer>),
+    Dict(Vec<(Transfer, Transfer)>),
+    Range(i64, i64, bool),
+    Some(Box<Transfer>),
+    /// Enum type, variant and payload; `Option#none` is one of these.
+    Enum(String, String, Vec<Transfer>),
+    /// Struct type, field names and field values.
+    Struct(String, Vec<String>, Vec<Transfer>),
+    /// Channels are shared rather than copied.
+    Channel(Arc<crate::core::channel::Channel>),
+}
+
+impl Transfer {
+    /// Deep-copies `v` out of `rt`'s heap; channels inside it stay shared.
+    /// Functions, files, modules and builders cannot be sent.
+    pub(crate) fn from_value(rt: &Runtime, v: &Value) -> Result<Self, String> {
+        Self::copy_out(rt, v, 0)
+    }
+
+    fn copy_out(rt: &Runtime, v: &Value, depth: usize) -> Result<Self, String> {
+        if depth > MAX_DEPTH {
+            return Err("worker: value is nested too deeply (or contains itself) to send".into());
+        }
+        if v.is_unit() {
+            return Ok(Self::Unit);
+        }
+        if v.is_bool() {
+            return Ok(Self::Bool(v.as_bool()));
+        }
+        if v.is_int() {
+            return Ok(Self::Int(v.as_i64()));
+        }
+        if v.is_f64() {
+            return Ok(Self::Float(v.as_f64()));
+        }
+        let items = |values: &[Value]| -> Result<Vec<Self>, String> {
+            values.iter().map(|x| Self::copy_out(rt, x, depth + 1)).collect()
+        };
+        match (v.get_tag(), rt.heap.get(v.as_obj_id())) {
+            (TAG_STR, ManagedObject::Str(s)) => Ok(Self::Str(s.as_str().to_string())),
+            (TAG_LIST, ManagedObject::List(values)) => Ok(Self::List(items(values)?)),
+            (TAG_TUPLE, ManagedObject::Tuple(values)) => Ok(Self::Tuple(items(values)?)),
+            (TAG_DICT, ManagedObject::Dict(d)) => {
+                let mut entries = Vec::with_capacity(d.len());
+                for (k, value) in d.entries() {
+                    let key = Self::copy_out(rt, &k.to_value(&rt.heap), depth + 1)?;
+                    entries.push((key, Self::copy_out(rt, &value, depth + 1)?));
+                }
+                Ok(Self::Dict(entries))
+            }
+            (TAG_RANGE, ManagedObject::Range(start, end, inclusive)) => Ok(Self::Range(*start, *end, *inclusive)),
+            (TAG_OPTION, ManagedObject::OptionSome(inner)) => Ok(Self::Some(Box::new(Self::copy_out(rt, inner, depth + 1)?))),
+            (TAG_ENUM, ManagedObject::Enum(e)) => {
+                let (ty, variant, payload) = e.as_ref();
+                Ok(Self::Enum(ty.as_str().to_string(), variant.as_str().to_string(), items(payload)?))
+            }
+            (TAG_CHANNEL, ManagedObject::Channel(ch)) => Ok(Self::Channel(ch.clone())),
+            (TAG_STRUCT, ManagedObject::Struct(s)) => Ok(Self::Struct(
+                s.ty.clone(),
+                s.field_names.to_vec(),
+                items(&s.fields)?,
+            )),
+            _ => Err(format!("worker: cannot send a {} to another thread", v.type_name())),
+        }
+    }
+
+    /// Rebuilds the value in `rt`'s heap.
+    pub(crate) fn into_value(self, rt: &mut Runtime) -> Result<Value, String> {
+        Ok(match self {
+            Self::Unit => Value::UNIT,
+            Self::Bool(b) => Value::from_bool(b),
+            Self::Int(i) => Value::from_i64(i),
+            Self::Float(f) => Value::from_f64(f),
+            Self::Str(s) => Value::str(rt.alloc(ManagedObject::Str(s.into()))),
+            Self::List(items) => {
+                let values = Self::all_into(items, rt)?;
+                Value::list(rt.alloc(ManagedObject::List(values.into())))
+            }
+            Self::Tuple(items) => {
+                let values = Self::all_into(items, rt)?;
+                Value::tuple(rt.alloc(ManagedObject::Tuple(values)))
+            }
+            Self::Dict(entries) => {
+                let mut dict = crate::core::value::dict_with_capacity(entries.len(), &rt.heap);
+                for (k, v) in entries {
+                    let key = k.into_value(rt)?;
+                    let key = DictKey::from_value(key, &mut rt.heap)
+                        .ok_or_else(|| "worker: received a dict key that cannot be hashed".to_string())?;
+                    let value = v.into_value(rt)?;
+                    dict.insert_entry(key, value);
+                }
+                Value::dict(rt.alloc(ManagedObject::Dict(dict)))
+            }
+            Self::Range(start, end, inclusive) => Value::range(rt.alloc(ManagedObject::Range(start, end, inclusive))),
+            Self::Some(inner) => {
+                let v = inner.into_value(rt)?;
+                rt.option_some(v)
+            }
+            Self::Enum(ty, variant, payload) => {
+                if ty == "Option" && variant == "none" && payload.is_empty() {
+                    return Ok(rt.option_none());
+                }
+                let payload = Self::all_into(payload, rt)?.into_boxed_slice();
+                Value::enum_obj(rt.alloc(ManagedObject::Enum(Box::new((ty.into(), variant.into(), payload)))))
+            }
+            Self::Channel(ch) => Value::channel(rt.alloc(ManagedObject::Channel(ch))),
+            Self::Struct(ty, names, fields) => {
+                let fields = Self::all_into(fields, rt)?.into_boxed_slice();
+                Value::struct_obj(rt.alloc(ManagedObject::Struct(Box::new(crate::core::value::StructInstance {
+                    ty_hash: xu_ir::stable_hash64(&ty),
+                    ty,
+                    fields,
+                    field_names: names.into(),
+                }))))
+            }
+        })
+    }
+
+    fn all_into(items: Vec<Self>, rt: &mut Runtime) -> Result<Vec<Value>, String> {
+        items.into_iter().map(|item| item.into_value(rt)).collect()
+    }
+}
+
+/// How a worker's function ended, with everything it printed and its
+/// capability audit log.
+struct WorkerExit {
+    result: Result<Transfer, String>,
+    output: String,
+    error_output: String,
+    audit_log: Vec<crate::CapabilityAuditEntry>,
+}
+
+/// The spawning side of a worker.
+struct WorkerHandle {
+    /// `None` once closed, which ends the worker's `recv` loop.
+    to_worker: Option<Sender<Transfer>>,
+    from_worker: Receiver<Transfer>,
+    thread: Option<JoinHandle<WorkerExit>>,
+}
+
+/// The worker side: the channels to the runtime that spawned it.
+struct ParentPort {
+    to_parent: Sender<Transfer>,
+    from_parent: Receiver<Transfer>,
+}
+
+/// Worker state of a runtime: the workers it spawned and, in a worker, the
+/// channel to its parent.
+#[derive(Default)]
+pub(crate) struct Workers {
+    handles: Vec<WorkerHandle>,
+    parent: Option<ParentPort>,
+}
+
+/// What a thread needs to rebuild the script in a fresh runtime and call one
+/// of its functions; all of it is `Send`.
+#[derive(Clone)]
+struct WorkerSetup {
+    frontend: Arc<dyn xu_ir::Frontend>,
+    entry_path: String,
+    stdlib_path: Option<String>,
+    args: Vec<String>,
+    config: crate::RuntimeConfig,
+    constants: Vec<(String, String)>,
+    caps: crate::util::SharedCapabilities,
+    tap: Option<crate::util::Tap>,
+    func: String,
+    params: usize,
+}
+
+impl WorkerSetup {
+    /// Checks that `f` is a named function the entry script can provide and
+    /// captures the host configuration and capabilities of `rt`.
+    fn new(rt: &Runtime, f: Value, what: &str) -> Result<Self, String> {
+        let def = match (f.get_tag(), rt.heap.object(f)) {
+            (TAG_FUNC, Some(ManagedObject::Function(Function::User(u)))) => &u.def,
+            (TAG_FUNC, Some(ManagedObject::Function(Function::Bytecode(b)))) => &b.def,
+            _ => return Err(format!("{what} expects a function, got {}", f.type_name())),
+        };
+        if def.name.starts_with("__anon_func_") {
+            return Err(format!("{what} expects a top-level function, not an anonymous one"));
+        }
+        let frontend = rt
+            .frontend
+            .clone()
+            .ok_or_else(|| format!("{what}: the runtime has no frontend to load the script with"))?;
+        let entry_path = rt
+            .entry_path
+            .clone()
+            .ok_or_else(|| format!("{what}: workers need the script to be run from a file"))?;
+        let (caps, tap) = rt.worker_capabilities();
+        Ok(Self {
+            frontend,
+            entry_path,
+            stdlib_path: rt.stdlib_path.clone(),
+            args: rt.args.clone(),
+            config: rt.config,
+            constants: rt.predefined_constants.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            caps,
+            tap,
+            func: def.name.clone(),
+            params: def.params.len(),
+        })
+    }
+
+    /// A fresh runtime with the spawner's configuration and capabilities.
+    fn start(&self) -> Runtime {
+        let mut rt = Runtime::with_config(self.config);
+        rt.install_worker_capabilities(self.caps.clone(), self.tap.clone());
+        rt.frontend = Some(self.frontend.clone());
+        rt.entry_path = Some(self.entry_path.clone());
+        rt.stdlib_path = self.stdlib_path.clone();
+        rt.args = self.args.clone();
+        for (name, value) in &self.constants {
+            rt.define_global_constant(name, value);
+        }
+        rt
+    }
+
+    /// Loads the declarations of the entry script into `rt`.
+    fn load(&self, rt: &mut Runtime) -> Result<(), String> {
+        let path = &self.entry_path;
+        let source = rt.fs_read_entry(path)?;
+        let compiled = self.frontend.compile_text_no_analyze(path, &source)?;
+        if let Some(err) = compiled
+            .diagnostics
+            .iter()
+            .find(|d| matches!(d.severity, xu_syntax::Severity::Error))
+        {
+            return Err(crate::util::render_parse_error(path, compiled.text, err));
+        }
+        let module = match compiled.executable {
+            xu_ir::Executable::Ast(module) => module,
+            xu_ir::Executable::Bytecode(program) => program.module,
+        };
+        rt.load_declarations(&module)?;
+        if rt.env.get(&self.func).is_none_or(|f| f.get_tag() != TAG_FUNC) {
+            return Err(format!("{} is not a top-level function of {path}", self.func));
+        }
+        Ok(())
+    }
+}
+
+fn run_worker(setup: WorkerSetup, port: ParentPort) -> WorkerExit {
+    let mut rt = setup.start();
+    rt.workers.parent = Some(port);
+    let result = call_in_worker(&mut rt, &setup);
+    // Closing the channel tells a parent blocked in `recv` that no more
+    // messages are coming.
+    rt.workers.parent = None;
+    WorkerExit {
+        result,
+        output: rt.take_output(),
+        error_output: rt.take_error_output(),
+        audit_log: rt.take_capability_audit_log(),
+    }
+}
+
+fn call_in_worker(rt: &mut Runtime, setup: &WorkerSetup) -> Result<Transfer, String> {
+    setup.load(rt)?;
+    let args = if setup.params == 1 {
+        let module = crate::modules::import_path(rt, "std/worker")?;
+        let parent = match rt.heap.get(module.as_obj_id()) {
+            ManagedObject::Module(m) => m.exports.map.get("parent").copied(),
+            _ => None,
+        };
+        let parent = parent.ok_or_else(|| "std/worker has no parent()".to_string())?;
+        vec![rt.call_function(parent, &[])?]
+    } else {
+        Vec::new()
+    };
+    let value = rt.call_global(&setup.func, &args)?;
+    Transfer::from_value(rt, &value)
+}
+
+impl Runtime {
+    fn worker_handle(&mut self, id: i64) -> Result<&mut WorkerHandle, String> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|i| i.checked_sub(1))
+            .and_then(|i| self.workers.handles.get_mut(i))
+            .ok_or_else(|| format!("worker: no worker with id {id}"))
+    }
+
+    fn parent_port(&self) -> Result<&ParentPort, String> {
+        self.workers
+            .parent
+            .as_ref()
+            .ok_or_else(|| "worker: parent() is only connected inside a worker".to_string())
+    }
+}
+
+fn id_arg(args: &[Value], n: usize, what: &str) -> Result<i64, String> {
+    if args.len() != n {
+        return Err(format!("{what} expects {n} argument{}", if n == 1 { "" } else { "s" }));
+    }
+    if !args[0].is_int() {
+        return Err(format!("{what}: worker id must be an int, got {}", args[0].type_name()));
+    }
+    Ok(args[0].as_i64())
+}
+
+pub fn builtin_worker_spawn(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("worker.spawn expects 1 argument".into());
+    }
+    let setup = WorkerSetup::new(rt, args[0], "worker.spawn")?;
+    if setup.params > 1 {
+        return Err(format!("worker.spawn: {} must take no parameters or one (the parent channel)", setup.func));
+    }
+    let (to_worker, from_parent) = channel();
+    let (to_parent, from_worker) = channel();
+    let port = ParentPort { to_parent, from_parent };
+    let thread = std::thread::Builder::new()
+        .name(format!("xu-worker-{}", setup.func))
+        .spawn(move || run_worker(setup, port))
+        .map_err(|e| format!("worker.spawn: cannot start a thread: {e}"))?;
+    rt.workers.handles.push(WorkerHandle {
+        to_worker: Some(to_worker),
+        from_worker,
+        thread: Some(thread),
+    });
+    Ok(Value::from_i64(rt.workers.handles.len() as i64))
+}
+
+pub fn builtin_worker_send(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("worker.send expects 2 arguments".into());
+    }
+    let id = id_arg(&args[..1], 1, "worker.send")?;
+    let message = Transfer::from_value(rt, &args[1])?;
+    let sent = if id == 0 {
+        rt.parent_port()?.to_parent.send(message).is_ok()
+    } else {
+        match &rt.worker_handle(id)?.to_worker {
+            Some(tx) => tx.send(message).is_ok(),
+            None => return Err(format!("worker.send: worker {id} is closed")),
+        }
+    };
+    if !sent {
+        return Err("worker.send: the other side has stopped".into());
+    }
+    Ok(Value::UNIT)
+}
+
+/// Blocks for the next message; none once the other side has finished or
+/// closed the channel and every message sent before has been received.
+pub fn builtin_worker_recv(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    let id = id_arg(args, 1, "worker.recv")?;
+    let message = if id == 0 {
+        rt.parent_port()?.from_parent.recv().ok()
+    } else {
+        rt.worker_handle(id)?.from_worker.recv().ok()
+    };
+    match message {
+        Some(m) => {
+            let v = m.into_value(rt)?;
+            Ok(rt.option_some(v))
+        }
+        None => Ok(rt.option_none()),
+    }
+}
+
+/// Closes the channel to a worker, so its `recv` returns none once it has
+/// read what was already sent.
+pub fn builtin_worker_close(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    let id = id_arg(args, 1, "worker.close")?;
+    if id == 0 {
+        return Err("worker.close: the parent channel closes when the worker returns".into());
+    }
+    rt.worker_handle(id)?.to_worker = None;
+    Ok(Value::UNIT)
+}
+
+/// Closes the channel, waits for the worker and returns what its function
+/// returned; its output, error output and audit log are appended to this
+/// runtime's.
+pub fn builtin_worker_join(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    let id = id_arg(args, 1, "worker.join")?;
+    if id == 0 {
+        return Err("worker.join: a worker cannot join its parent".into());
+    }
+    let handle = rt.worker_handle(id)?;
+    handle.to_worker = None;
+    let thread = handle
+        .thread
+        .take()
+        .ok_or_else(|| format!("worker.join: worker {id} was already joined"))?;
+    let exit = thread
+        .join()
+        .map_err(|_| format!("worker.join: worker {id} panicked"))?;
+    rt.write_output_raw(&exit.output);
+    rt.write_error_raw(&exit.error_output);
+    rt.extend_capability_audit_log(exit.audit_log);
+    match exit.result {
+        Ok(v) => v.into_value(rt),
+        Err(e) => Err(format!("worker {id} failed: {e}")),
+    }
+}
+
+/// What one `parallel_map` thread produced: results by item index, the
+/// first error it hit, its output and its capability audit log.
+struct MapBatch {
+    mapped: Vec<(usize, Transfer)>,
+    error: Option<(Option<usize>, String)>,
+    output: String,
+    error_output: String,
+    audit_log: Vec<crate::CapabilityAuditEntry>,
+}
+
+/// Maps items on one thread, taking the next unclaimed index each time so
+/// uneven items balance out across threads.
+fn map_batch(setup: &WorkerSetup, items: &[Transfer], next: &AtomicUsize, interrupt: &InterruptHandle) -> MapBatch {
+    let mut rt = setup.start();
+    let mut batch = MapBatch {
+        mapped: Vec::new(),
+        error: None,
+        output: String::new(),
+        error_output: String::new(),
+        audit_log: Vec::new(),
+    };
+    if let Err(e) = setup.load(&mut rt) {
+        next.fetch_max(items.len(), Ordering::Relaxed);
+        batch.error = Some((None, e));
+    }
+    while batch.error.is_none() {
+        // An interrupt of the calling runtime stops new items from starting.
+        if interrupt.is_interrupted() {
+            break;
+        }
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        if i >= items.len() {
+            break;
+        }
+        let mapped = items[i]
+            .clone()
+            .into_value(&mut rt)
+            .and_then(|v| rt.call_global(&setup.func, &[v]))
+            .and_then(|v| Transfer::from_value(&rt, &v));
+        match mapped {
+            Ok(t) => batch.mapped.push((i, t)),
+            Err(e) => {
+                // Stop the other threads from starting new items.
+                next.fetch_max(items.len(), Ordering::Relaxed);
+                batch.error = Some((Some(i), e));
+            }
+        }
+    }
+    batch.output = rt.take_output();
+    batch.error_output = rt.take_error_output();
+    batch.audit_log = rt.take_capability_audit_log();
+    batch
+}
+
+/// `parallel_map(list, f)`: `f` applied to each item on a pool of threads,
+/// each with a fresh runtime that shares this one's capabilities and loaded
+/// the entry script. Items and results are deep copies; results keep the
+/// order of the items.
+pub fn builtin_parallel_map(rt: &mut Runtime, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("parallel_map expects 2 arguments".into());
+    }
+    let items: Vec<Transfer> = match (args[0].get_tag(), rt.heap.object(args[0])) {
+        (TAG_LIST, Some(ManagedObject::List(values))) => {
+            values.iter().map(|v| Transfer::from_value(rt, v)).collect::<Result<_, _>>()?
+        }
+        _ => return Err(format!("parallel_map expects a list, got {}", args[0].type_name())),
+    };
+    let setup = WorkerSetup::new(rt, args[1], "parallel_map")?;
+    if setup.params != 1 {
+        return Err(format!("parallel_map: {} must take one parameter", setup.func));
+    }
+    if items.is_empty() {
+        return Ok(Value::list(rt.alloc(ManagedObject::List(ListItems::new()))));
+    }
+    let next = AtomicUsize::new(0);
+    let interrupt = rt.interrupt_handle();
+    #[cfg(feature = "sys")]
+    let batches: Vec<Option<MapBatch>> = {
+        let threads = match rt.config.parallel_workers {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        }
+        .min(items.len());
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|_| scope.spawn(|| map_batch(&setup, &items, &next, &interrupt)))
+                .collect();
+            handles.into_iter().map(|h| h.join().ok()).collect()
+        })
+    };
+    // Without threads, one batch on this thread maps every item.
+    #[cfg(not(feature = "sys"))]
+    let batches: Vec<Option<MapBatch>> = vec![Some(map_batch(&setup, &items, &next, &interrupt))];
+    let mut results: Vec<Option<Transfer>> = vec![None; items.len()];
+    let mut first_error: Option<(Option<usize>, String)> = None;
+    for batch in batches {
+        let batch = batch.ok_or_else(|| "parallel_map: a worker thread panicked".to_string())?;
+        rt.write_output_raw(&batch.output);
+        rt.write_error_raw(&batch.error_output);
+        rt.extend_capability_audit_log(batch.audit_log);
+        for (i, t) in batch.mapped {
+            results[i] = Some(t);
+        }
+        if let Some(e) = batch.error {
+            let earlier = first_error.as_ref().is_none_or(|(j, _)| e.0 < *j);
+            if earlier {
+                first_error = Some(e);
+            }
+        }
+    }
+    rt.check_interrupt()?;
+    match first_error {
+        Some((Some(i), e)) => return Err(format!("parallel_map: item {i}: {e}")),
+        Some((None, e)) => return Err(format!("parallel_map: {e}")),
+        None => {}
+    }
+    let values = results
+        .into_iter()
+        .map(|t| t.expect("every item is mapped without an error").into_value(rt))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Value::list(rt.alloc(ManagedObject::List(values.into()))))
+}
@@ -50,21 +50,43 @@ impl BuiltinProvider for StdBuiltinProvider {
     fn install(&self, registry: &mut BuiltinRegistry) {
         registry.register("print", builtins::builtin_print);
         registry.register("println", builtins::builtin_print);
+        registry.register("eprint", builtins::builtin_eprint);
+        registry.register("eprintln", builtins::builtin_eprint);
+        registry.register("exit", builtins::builtin_exit);
+        registry.register("panic", builtins::builtin_panic);
+        registry.register("ok", builtins::builtin_ok);
+        registry.register("err", builtins::builtin_err);
         registry.register("gen_id", builtins::builtin_gen_id);
         registry.register("gc", builtins::builtin_gc);
         registry.register("open", builtins::builtin_open);
         registry.register("input", builtins::builtin_input);
+        registry.register("input_int", builtins::builtin_input_int);
+        registry.register("input_float", builtins::builtin_input_float);
+        registry.register("input_secret", builtins::builtin_input_secret);
         registry.register("time_unix", builtins::builtin_time_unix);
         registry.register("time_millis", builtins::builtin_time_millis);
         registry.register("mono_micros", builtins::builtin_mono_micros);
         registry.register("mono_nanos", builtins::builtin_mono_nanos);
+        registry.register("sleep", builtins::builtin_sleep);
         registry.register("abs", builtins::builtin_abs);
         registry.register("max", builtins::builtin_max);
         registry.register("min", builtins::builtin_min);
         registry.register("rand", builtins::builtin_rand);
         registry.register("to_text", builtins::builtin_to_text);
+        registry.register("inspect", builtins::builtin_inspect);
+        registry.register("help", builtins::builtin_help);
         registry.register("parse_int", builtins::builtin_parse_int);
         registry.register("parse_float", builtins::builtin_parse_float);
+        registry.register("base64_encode", builtins::builtin_base64_encode);
+        registry.register("base64_decode", builtins::builtin_base64_decode);
+        registry.register("hex_encode", builtins::builtin_hex_encode);
+        registry.register("hex_decode", builtins::builtin_hex_decode);
+        registry.register("serialize", builtins::builtin_serialize);
+        registry.register("deserialize", builtins::builtin_deserialize);
+        registry.register("validate", builtins::builtin_validate);
+        registry.register("parallel_map", builtins::builtin_parallel_map);
+        registry.register("channel", builtins::builtin_channel);
+        registry.register("recv_any", builtins::builtin_recv_any);
         // builder
         registry.register(
             "builder_new_cap",
@@ -80,15 +102,122 @@ impl BuiltinProvider for StdBuiltinProvider {
         registry.register("starts_with", builtins::builtin_starts_with);
         registry.register("ends_with", builtins::builtin_ends_with);
         registry.register("process_rss", builtins::builtin_process_rss);
-        registry.register("sin", builtins::builtin_sin);
-        registry.register("cos", builtins::builtin_cos);
-        registry.register("tan", builtins::builtin_tan);
-        registry.register("sqrt", builtins::builtin_sqrt);
-        registry.register("log", builtins::builtin_log);
-        registry.register("pow", builtins::builtin_pow);
+        registry.register("with_capability", builtins::builtin_with_capability);
+        registry.register("on_signal", builtins::builtin_on_signal);
+        builtins::ReflectBuiltinProvider.install(registry);
+        builtins::MathBuiltinProvider.install(registry);
+        registry.register("clamp", builtins::builtin_clamp);
         registry.register("__builtin_assert", builtins::builtin_assert);
         registry.register("__builtin_assert_eq", builtins::builtin_assert_eq);
         registry.register("__set_from_list", builtins::builtin_set_from_list);
+        registry.register("dict", builtins::builtin_dict);
         registry.register("__heap_stats", builtins::builtin_heap_stats);
+        registry.register("__gc_collect", builtins::builtin_gc);
+        registry.register("__gc_stats", builtins::builtin_gc_stats);
+        registry.register("__bench", builtins::builtin_bench);
+        RandomBuiltinProvider.install(registry);
+        CryptoBuiltinProvider.install(registry);
+        CsvBuiltinProvider.install(registry);
+        FsBuiltinProvider.install(registry);
+        TimeBuiltinProvider.install(registry);
+        builtins::TermBuiltinProvider.install(registry);
+        TemplateBuiltinProvider.install(registry);
+        WorkerBuiltinProvider.install(registry);
+        FfiBuiltinProvider.install(registry);
+    }
+}
+
+/// Hooks behind `std/random`. Installed by [`StdBuiltinProvider`].
+pub struct RandomBuiltinProvider;
+
+impl BuiltinProvider for RandomBuiltinProvider {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("__random_seed", builtins::builtin_random_seed);
+        registry.register("__random_int", builtins::builtin_random_int);
+        registry.register("__random_float", builtins::builtin_random_float);
+        registry.register("__random_gauss", builtins::builtin_random_gauss);
+        registry.register("__random_choice", builtins::builtin_random_choice);
+        registry.register("__random_shuffle", builtins::builtin_random_shuffle);
+    }
+}
+
+/// Hooks behind `std/crypto`. Installed by [`StdBuiltinProvider`].
+pub struct CryptoBuiltinProvider;
+
+impl BuiltinProvider for CryptoBuiltinProvider {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("__crypto_sha256", builtins::builtin_crypto_sha256);
+        registry.register("__crypto_sha1", builtins::builtin_crypto_sha1);
+        registry.register("__crypto_md5", builtins::builtin_crypto_md5);
+        registry.register("__crypto_hmac_sha256", builtins::builtin_crypto_hmac_sha256);
+        registry.register("__crypto_crc32", builtins::builtin_crypto_crc32);
+    }
+}
+
+/// Hooks behind `std/csv`. Installed by [`StdBuiltinProvider`].
+pub struct CsvBuiltinProvider;
+
+impl BuiltinProvider for CsvBuiltinProvider {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("__csv_parse", builtins::builtin_csv_parse);
+        registry.register("__csv_next", builtins::builtin_csv_next);
+        registry.register("__csv_write", builtins::builtin_csv_write);
+    }
+}
+
+/// Hooks behind `std/time`. Installed by [`StdBuiltinProvider`].
+pub struct TimeBuiltinProvider;
+
+impl BuiltinProvider for TimeBuiltinProvider {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("__unix_time", builtins::builtin_time_unix);
+        registry.register("__unix_millis", builtins::builtin_time_millis);
+        registry.register("__mono_micros", builtins::builtin_mono_micros);
+        registry.register("__mono_nanos", builtins::builtin_mono_nanos);
+    }
+}
+
+/// Hooks behind `std/fs`. Installed by [`StdBuiltinProvider`].
+pub struct FsBuiltinProvider;
+
+impl BuiltinProvider for FsBuiltinProvider {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("__fs_glob", builtins::builtin_fs_glob);
+        registry.register("__fs_walk_next", builtins::builtin_fs_walk_next);
+    }
+}
+
+/// Hooks behind `std/template`. Installed by [`StdBuiltinProvider`].
+pub struct TemplateBuiltinProvider;
+
+impl BuiltinProvider for TemplateBuiltinProvider {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("__template_render", builtins::builtin_template_render);
+    }
+}
+
+/// Hooks behind `std/worker`. Installed by [`StdBuiltinProvider`].
+pub struct WorkerBuiltinProvider;
+
+impl BuiltinProvider for WorkerBuiltinProvider {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("__worker_spawn", builtins::builtin_worker_spawn);
+        registry.register("__worker_send", builtins::builtin_worker_send);
+        registry.register("__worker_recv", builtins::builtin_worker_recv);
+        registry.register("__worker_close", builtins::builtin_worker_close);
+        registry.register("__worker_join", builtins::builtin_worker_join);
+    }
+}
+
+/// Hooks behind `std/ffi`. Installed by [`StdBuiltinProvider`]; without the
+/// `ffi` feature they fail on use.
+pub struct FfiBuiltinProvider;
+
+impl BuiltinProvider for FfiBuiltinProvider {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("__ffi_open", builtins::builtin_ffi_open);
+        registry.register("__ffi_symbol", builtins::builtin_ffi_symbol);
+        registry.register("__ffi_call", builtins::builtin_ffi_call);
+        registry.register("__ffi_close", builtins::builtin_ffi_close);
     }
 }
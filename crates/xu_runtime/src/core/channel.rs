@@ -0,0 +1,169 @@
+//! Channels: queues of copied values shared between runtimes.
+//!
+//! A channel value in a heap holds an `Arc` to the queue, so sending the
+//! channel itself to a worker gives both threads the same queue. Messages
+//! are deep copies (see [`Transfer`]) and never reference a heap.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use crate::builtins::worker::Transfer;
+
+/// A FIFO queue with an optional capacity; `send` blocks while it is full
+/// and `recv` while it is empty.
+pub struct Channel {
+    capacity: Option<usize>,
+    state: Mutex<State>,
+    /// Signalled when a message arrives or the channel closes.
+    readable: Condvar,
+    /// Signalled when a message is taken or the channel closes.
+    writable: Condvar,
+}
+
+struct State {
+    queue: VecDeque<Transfer>,
+    closed: bool,
+    /// `recv_any` calls waiting on this channel among others.
+    selectors: Vec<Arc<Signal>>,
+}
+
+/// Wakes one `recv_any` when any of its channels changes.
+#[derive(Default)]
+struct Signal {
+    fired: Mutex<bool>,
+    cv: Condvar,
+}
+
+impl Signal {
+    fn fire(&self) {
+        *self.fired.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.cv.notify_one();
+    }
+
+    fn wait(&self) {
+        let mut fired = self.fired.lock().unwrap_or_else(|e| e.into_inner());
+        while !*fired {
+            fired = self.cv.wait(fired).unwrap_or_else(|e| e.into_inner());
+        }
+        *fired = false;
+    }
+}
+
+impl std::fmt::Debug for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Channel").field("capacity", &self.capacity).finish_non_exhaustive()
+    }
+}
+
+impl Channel {
+    /// A channel holding up to `capacity` messages, or any number for `None`.
+    pub(crate) fn new(capacity: Option<usize>) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(State { queue: VecDeque::new(), closed: false, selectors: Vec::new() }),
+            readable: Condvar::new(),
+            writable: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // A panic elsewhere cannot leave the queue half-updated.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queues `msg`, waiting for room; gives it back if the channel is closed.
+    pub(crate) fn send(&self, msg: Transfer) -> Result<(), Transfer> {
+        let mut st = self.lock();
+        while !st.closed && self.capacity.is_some_and(|cap| st.queue.len() >= cap) {
+            st = self.writable.wait(st).unwrap_or_else(|e| e.into_inner());
+        }
+        if st.closed {
+            return Err(msg);
+        }
+        st.queue.push_back(msg);
+        self.readable.notify_one();
+        for s in &st.selectors {
+            s.fire();
+        }
+        Ok(())
+    }
+
+    /// The next message, waiting for one; `None` once the channel is closed
+    /// and drained.
+    pub(crate) fn recv(&self) -> Option<Transfer> {
+        let mut st = self.lock();
+        loop {
+            if let Some(msg) = st.queue.pop_front() {
+                self.writable.notify_one();
+                return Some(msg);
+            }
+            if st.closed {
+                return None;
+            }
+            st = self.readable.wait(st).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// The next message if one is queued.
+    pub(crate) fn try_recv(&self) -> Option<Transfer> {
+        let msg = self.lock().queue.pop_front();
+        if msg.is_some() {
+            self.writable.notify_one();
+        }
+        msg
+    }
+
+    /// Whether a `recv` would return without waiting.
+    pub(crate) fn is_ready(&self) -> bool {
+        let st = self.lock();
+        st.closed || !st.queue.is_empty()
+    }
+
+    /// Stops further sends; queued messages can still be received.
+    pub(crate) fn close(&self) {
+        let mut st = self.lock();
+        st.closed = true;
+        self.readable.notify_all();
+        self.writable.notify_all();
+        for s in st.selectors.drain(..) {
+            s.fire();
+        }
+    }
+
+    /// The first message available on any of `channels`, checked in order,
+    /// with the index of its channel; waits until one has a message. `None`
+    /// once every channel is closed and drained.
+    pub(crate) fn recv_any(channels: &[Arc<Channel>]) -> Option<(usize, Transfer)> {
+        let signal = Arc::new(Signal::default());
+        loop {
+            // Register before looking, so a send in between still wakes us.
+            for ch in channels {
+                ch.lock().selectors.push(signal.clone());
+            }
+            let mut open = false;
+            let mut found = None;
+            for (i, ch) in channels.iter().enumerate() {
+                let mut st = ch.lock();
+                if let Some(msg) = st.queue.pop_front() {
+                    ch.writable.notify_one();
+                    found = Some((i, msg));
+                    break;
+                }
+                open |= !st.closed;
+            }
+            if found.is_none() && open {
+                signal.wait();
+            }
+            for ch in channels {
+                ch.lock().selectors.retain(|s| !Arc::ptr_eq(s, &signal));
+            }
+            if found.is_some() || !open {
+                return found;
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Dict key hashing policy.
+//!
+//! String and composite keys carry a 64-bit content hash that doubles as
+//! their identity (see [`DictKey`](super::DictKey)), and every dict map hashes
+//! its keys once more into buckets. With [`DictHashing::Keyed`] both hashes are
+//! keyed with seeds drawn per runtime — SipHash-1-3 for key contents, a seeded aHash
+//! for buckets — so scripts fed untrusted keys cannot be driven into
+//! collisions. [`DictHashing::Fixed`] uses constant seeds for deterministic,
+//! slightly faster hashing.
+//!
+//! The seeds live in the runtime's [`Heap`](super::heap::Heap), next to the
+//! keys hashed with them, so runtimes sharing a thread never mix them up.
+
+use std::hash::{BuildHasher, Hasher};
+
+use ahash::RandomState;
+
+/// How dict keys are hashed; see [`RuntimeConfig::dict_hashing`](crate::RuntimeConfig::dict_hashing).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DictHashing {
+    /// Hashes keyed with random per-runtime seeds.
+    #[default]
+    Keyed,
+    /// Constant seeds: the same keys hash identically in every run.
+    Fixed,
+}
+
+/// Seeds of one runtime.
+#[derive(Clone)]
+pub(crate) struct DictHashState {
+    /// SipHash keys for string contents; `None` for fixed hashing.
+    str_keys: Option<std::hash::RandomState>,
+    map_seeds: [u64; 4],
+}
+
+impl DictHashState {
+    pub(crate) fn new(mode: DictHashing) -> Self {
+        match mode {
+            DictHashing::Fixed => Self {
+                str_keys: None,
+                map_seeds: [0; 4],
+            },
+            DictHashing::Keyed => {
+                let str_keys = std::hash::RandomState::new();
+                // Every std RandomState draws fresh keys, so hashing a
+                // counter gives independent seeds.
+                let seeder = std::hash::RandomState::new();
+                let map_seeds = std::array::from_fn(|i| seeder.hash_one(i));
+                Self {
+                    str_keys: Some(str_keys),
+                    map_seeds,
+                }
+            }
+        }
+    }
+
+    /// Hasher for key contents under these seeds.
+    #[inline]
+    pub(crate) fn content_hasher(&self) -> ContentHasher {
+        match &self.str_keys {
+            Some(keys) => ContentHasher::Keyed(keys.build_hasher()),
+            None => ContentHasher::Fixed(ahash::AHasher::default()),
+        }
+    }
+
+    /// Content hash of a string key.
+    #[inline]
+    pub(crate) fn hash_str(&self, s: &str) -> u64 {
+        let mut h = self.content_hasher();
+        h.write(s.as_bytes());
+        h.finish()
+    }
+
+    /// Bucket hasher for a new dict map.
+    #[inline]
+    pub(crate) fn map_hasher(&self) -> RandomState {
+        let [k0, k1, k2, k3] = self.map_seeds;
+        RandomState::with_seeds(k0, k1, k2, k3)
+    }
+}
+
+/// Hasher for key contents: strings and the components of composite keys.
+pub(crate) enum ContentHasher {
+    Keyed(std::hash::DefaultHasher),
+    Fixed(ahash::AHasher),
+}
+
+impl Hasher for ContentHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        match self {
+            ContentHasher::Keyed(h) => h.write(bytes),
+            ContentHasher::Fixed(h) => h.write(bytes),
+        }
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        match self {
+            ContentHasher::Keyed(h) => h.finish(),
+            ContentHasher::Fixed(h) => h.finish(),
+        }
+    }
+}
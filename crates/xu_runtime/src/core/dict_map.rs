@@ -0,0 +1,258 @@
+//! The ordered hash map behind dicts.
+//!
+//! Entries live in a vector in insertion order and a hash table maps each
+//! key's hash to its position there, as in `IndexMap`. Unlike `IndexMap`, a
+//! large table is not rebuilt in one go when it fills up: a table twice the
+//! size is allocated and the positions are moved into it a few at a time on
+//! each later insertion, while lookups consult both tables. Building a dict
+//! with millions of entries then never stops for a whole-table rehash.
+
+use ahash::RandomState;
+use hashbrown::HashTable;
+
+use super::value::DictKey;
+use crate::Value;
+
+/// 索引表容量达到此值后改为渐进扩容；更小的表整表重建的停顿可以忽略
+pub const INCREMENTAL_REHASH_MIN: usize = 1 << 16;
+
+/// 每次插入从旧表迁出的位置数。新表容量是旧表的两倍，迁完旧表的 n 个
+/// 位置前最多再插入 n / 2 个键，新表不会在迁移中途再次装满
+const MIGRATE_PER_INSERT: usize = 2;
+
+struct Bucket {
+    hash: u64,
+    key: DictKey,
+    value: Value,
+}
+
+/// 渐进扩容中被替换的旧索引表：`entries[next..end]` 的位置仍只在这里
+struct Migration {
+    table: HashTable<usize>,
+    next: usize,
+    end: usize,
+}
+
+/// Insertion-ordered map from [`DictKey`] to [`Value`] that grows large
+/// tables incrementally.
+pub struct DictMap {
+    entries: Vec<Bucket>,
+    indices: HashTable<usize>,
+    migration: Option<Box<Migration>>,
+    hasher: RandomState,
+}
+
+/// A slot found by [`DictMap::entry_by_hash`].
+pub enum DictEntry<'a> {
+    Occupied(&'a mut Value),
+    Vacant(VacantDictEntry<'a>),
+}
+
+/// A key that is not in the map yet, with its hash.
+pub struct VacantDictEntry<'a> {
+    map: &'a mut DictMap,
+    hash: u64,
+}
+
+impl VacantDictEntry<'_> {
+    /// Appends `key`, which must hash to the hash this entry was looked up with.
+    pub fn insert(self, key: DictKey, value: Value) {
+        self.map.push(self.hash, key, value);
+    }
+}
+
+impl DictMap {
+    pub fn with_hasher(hasher: RandomState) -> Self {
+        Self::with_capacity_and_hasher(0, hasher)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: RandomState) -> Self {
+        DictMap {
+            entries: Vec::with_capacity(capacity),
+            indices: HashTable::with_capacity(capacity),
+            migration: None,
+            hasher,
+        }
+    }
+
+    #[inline]
+    pub fn hasher(&self) -> &RandomState {
+        &self.hasher
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries the map holds without reallocating its entry vector.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    /// Whether positions are still being moved into a grown table.
+    pub fn is_migrating(&self) -> bool {
+        self.migration.is_some()
+    }
+
+    /// Makes room for `additional` more entries. An explicit request, so any
+    /// pending migration is finished and the table resized at once.
+    pub fn reserve(&mut self, additional: usize) {
+        self.finish_migration();
+        self.entries.reserve(additional);
+        let entries = &self.entries;
+        self.indices.reserve(additional, |&i| entries[i].hash);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+        self.migration = None;
+    }
+
+    /// 先查新表，再查旧表中尚未迁出的位置
+    #[inline]
+    fn find_index(&self, hash: u64, mut eq: impl FnMut(&DictKey) -> bool) -> Option<usize> {
+        let entries = &self.entries;
+        if let Some(&i) = self.indices.find(hash, |&i| eq(&entries[i].key)) {
+            return Some(i);
+        }
+        let m = self.migration.as_deref()?;
+        m.table.find(hash, |&i| i >= m.next && eq(&entries[i].key)).copied()
+    }
+
+    #[inline]
+    pub fn get(&self, key: &DictKey) -> Option<&Value> {
+        let hash = self.hasher.hash_one(key);
+        self.get_by_hash(hash, |k| k == key).map(|(_, v)| v)
+    }
+
+    /// Looks up the entry whose key hashes to `hash` and satisfies `eq`.
+    #[inline]
+    pub fn get_by_hash(&self, hash: u64, eq: impl FnMut(&DictKey) -> bool) -> Option<(&DictKey, &Value)> {
+        let b = &self.entries[self.find_index(hash, eq)?];
+        Some((&b.key, &b.value))
+    }
+
+    /// The slot of the key that hashes to `hash` and satisfies `eq`, for
+    /// updating it or appending it.
+    #[inline]
+    pub fn entry_by_hash(&mut self, hash: u64, eq: impl FnMut(&DictKey) -> bool) -> DictEntry<'_> {
+        match self.find_index(hash, eq) {
+            Some(i) => DictEntry::Occupied(&mut self.entries[i].value),
+            None => DictEntry::Vacant(VacantDictEntry { map: self, hash }),
+        }
+    }
+
+    /// Sets `key` to `value`, returning the previous value. New keys go last.
+    pub fn insert(&mut self, key: DictKey, value: Value) -> Option<Value> {
+        let hash = self.hasher.hash_one(key);
+        match self.entry_by_hash(hash, |k| *k == key) {
+            DictEntry::Occupied(v) => Some(std::mem::replace(v, value)),
+            DictEntry::Vacant(vac) => {
+                vac.insert(key, value);
+                None
+            }
+        }
+    }
+
+    /// Removes `key`, keeping the order of the other entries. Like
+    /// `IndexMap::shift_remove` this takes time proportional to the map.
+    pub fn shift_remove(&mut self, key: &DictKey) -> Option<Value> {
+        self.finish_migration();
+        let hash = self.hasher.hash_one(key);
+        let entries = &self.entries;
+        let found = self.indices.find_entry(hash, |&i| entries[i].key == *key).ok()?;
+        let (i, _) = found.remove();
+        for pos in self.indices.iter_mut() {
+            if *pos > i {
+                *pos -= 1;
+            }
+        }
+        Some(self.entries.remove(i).value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&DictKey, &Value)> {
+        self.entries.iter().map(|b| (&b.key, &b.value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &DictKey> {
+        self.entries.iter().map(|b| &b.key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|b| &b.value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.entries.iter_mut().map(|b| &mut b.value)
+    }
+
+    /// Keys and values for rewriting object ids after the heap moved
+    /// objects. A key must keep the hash it was inserted with.
+    pub(crate) fn iter_mut_with_keys(&mut self) -> impl Iterator<Item = (&mut DictKey, &mut Value)> {
+        self.entries.iter_mut().map(|b| (&mut b.key, &mut b.value))
+    }
+
+    fn push(&mut self, hash: u64, key: DictKey, value: Value) {
+        self.migrate(MIGRATE_PER_INSERT);
+        if self.indices.len() == self.indices.capacity() {
+            self.grow();
+        }
+        let i = self.entries.len();
+        self.entries.push(Bucket { hash, key, value });
+        let entries = &self.entries;
+        self.indices.insert_unique(hash, i, |&j| entries[j].hash);
+    }
+
+    /// 索引表已满：小表整表扩容；大表换上两倍容量的空表，旧表留待逐步迁移
+    fn grow(&mut self) {
+        let capacity = self.indices.capacity();
+        if capacity < INCREMENTAL_REHASH_MIN || self.migration.is_some() {
+            self.finish_migration();
+            let entries = &self.entries;
+            self.indices.reserve(1, |&i| entries[i].hash);
+            return;
+        }
+        let table = std::mem::replace(&mut self.indices, HashTable::with_capacity(capacity * 2));
+        self.migration = Some(Box::new(Migration { table, next: 0, end: self.entries.len() }));
+    }
+
+    /// 把旧表中至多 `steps` 个位置迁入新表；哈希取自条目，无需重新计算
+    fn migrate(&mut self, steps: usize) {
+        let Some(m) = self.migration.as_deref_mut() else {
+            return;
+        };
+        let stop = m.end.min(m.next + steps);
+        let entries = &self.entries;
+        for i in m.next..stop {
+            self.indices.insert_unique(entries[i].hash, i, |&j| entries[j].hash);
+        }
+        m.next = stop;
+        if stop == m.end {
+            self.migration = None;
+        }
+    }
+
+    fn finish_migration(&mut self) {
+        if let Some(m) = self.migration.as_deref() {
+            self.migrate(m.end - m.next);
+        }
+    }
+}
+
+impl Clone for DictMap {
+    fn clone(&self) -> Self {
+        let mut map = DictMap::with_capacity_and_hasher(self.len(), self.hasher.clone());
+        for b in &self.entries {
+            map.push(b.hash, b.key, b.value);
+        }
+        map
+    }
+}
@@ -1,6 +1,9 @@
 //! Garbage collection and heap management.
 
+use std::rc::Rc;
+
 use super::value::{Dict, DictStr, FileHandle, Function, ModuleInstance, StructInstance, Value};
+use super::list::ListItems;
 use super::text::Text;
 
 /// Handle to a heap-allocated object.
@@ -9,7 +12,7 @@ pub struct ObjectId(pub usize);
 
 #[derive(Clone)]
 pub enum ManagedObject {
-    List(Vec<Value>),
+    List(ListItems),
     Tuple(Vec<Value>),
     Dict(Dict),
     DictStr(DictStr),
@@ -25,6 +28,8 @@ pub enum ManagedObject {
     Shape(Box<super::value::Shape>),
     /// Lazy string split iterator - stores source string and separator
     SplitIter(Box<SplitIterData>),
+    /// Queue shared with other runtimes; holds no heap references
+    Channel(std::sync::Arc<super::channel::Channel>),
 }
 
 /// Data for lazy string split iterator
@@ -37,6 +42,16 @@ pub struct SplitIterData {
 }
 
 impl ManagedObject {
+    /// The elements of a list or tuple.
+    #[inline]
+    pub fn elements(&self) -> Option<&[Value]> {
+        match self {
+            ManagedObject::List(v) => Some(v),
+            ManagedObject::Tuple(v) => Some(v),
+            _ => None,
+        }
+    }
+
     #[inline]
     pub fn size(&self) -> usize {
         match self {
@@ -50,7 +65,7 @@ impl ManagedObject {
             ManagedObject::DictStr(d) => 64 + d.map.capacity() * 48,
             ManagedObject::Builder(s) => 32 + s.capacity(),
             ManagedObject::Struct(s) => 64 + s.fields.len() * 8,
-            ManagedObject::Str(s) => 32 + s.as_str().len(),
+            ManagedObject::Str(s) => 32 + s.len(),
             ManagedObject::Function(_) => 256,
             ManagedObject::Enum(e) => 64 + e.2.len() * 8,
             ManagedObject::Module(_) => 256,
@@ -59,6 +74,56 @@ impl ManagedObject {
             ManagedObject::Range(_, _, _) => 32,
             ManagedObject::OptionSome(_) => 16,
             ManagedObject::SplitIter(s) => 64 + s.source.len() + s.separator.len(),
+            ManagedObject::Channel(_) => 128,
+        }
+    }
+}
+
+/// Largest buffers kept for reuse: list and tuple slots, dict entries and
+/// text bytes. Larger ones are released with their object.
+const POOLED_LIST_CAP: usize = 64;
+const POOLED_DICT_CAP: usize = 32;
+const POOLED_TEXT_CAP: usize = 1024;
+
+/// How many freed buffers the heap keeps per kind. When a sweep frees a
+/// small list or tuple, dict or heap text, its buffer backs the next
+/// allocation of that kind instead of going back to the allocator, which
+/// takes the churn out of loops building short-lived values. 0 turns a pool
+/// off; the default keeps up to 4096 of each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolLimits {
+    pub lists: usize,
+    pub dicts: usize,
+    pub texts: usize,
+}
+
+impl PoolLimits {
+    /// No pooling: every allocation comes from the allocator.
+    pub const NONE: Self = Self { lists: 0, dicts: 0, texts: 0 };
+}
+
+impl Default for PoolLimits {
+    fn default() -> Self {
+        Self { lists: 4096, dicts: 4096, texts: 4096 }
+    }
+}
+
+/// 回收的缓冲区；在 sweep 中填充，由 take_* 取用
+#[derive(Default)]
+struct Pools {
+    limits: PoolLimits,
+    lists: Vec<Vec<Value>>,
+    dicts: Vec<Dict>,
+    texts: Vec<Rc<String>>,
+    /// 由池满足的分配次数
+    reused: u64,
+}
+
+impl Pools {
+    fn put_list(&mut self, mut items: Vec<Value>) {
+        if items.capacity() > 0 && items.capacity() <= POOLED_LIST_CAP && self.lists.len() < self.limits.lists {
+            items.clear();
+            self.lists.push(items);
         }
     }
 }
@@ -71,6 +136,137 @@ pub struct Heap {
     pub(crate) gc_threshold: usize,
     pub(crate) alloc_bytes: usize,
     pub(crate) gc_threshold_bytes: usize,
+    /// Number of completed sweeps.
+    pub(crate) gc_cycles: u64,
+    /// Objects allocated since the heap was created; unlike `alloc_count`,
+    /// never reset by a sweep.
+    allocations: u64,
+    /// Object count between collections set by the host; `None` adapts it
+    /// to the live heap after each sweep.
+    fixed_threshold: Option<usize>,
+    /// Incremented whenever compaction moves objects; an `ObjectId` obtained
+    /// under an older epoch may no longer be valid.
+    epoch: u64,
+    pools: Pools,
+    /// Seeds for the dict keys hashed on this heap.
+    pub(crate) dict_hash: super::dict_hash::DictHashState,
+    /// How floats stored on this heap are rendered as text.
+    pub(crate) float_format: crate::util::FloatFormat,
+}
+
+/// What one [`Heap::sweep`] freed and kept. Byte counts are the estimates of
+/// [`ManagedObject::size`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sweep {
+    pub freed: usize,
+    pub freed_bytes: usize,
+    pub live: usize,
+    pub live_bytes: usize,
+}
+
+/// Old slot → new slot mapping produced by [`Heap::compact`].
+pub(crate) struct Forwarding {
+    table: Vec<usize>,
+    /// Objects that changed slot.
+    pub(crate) moved: usize,
+    /// Scopes already rewritten (closures share scopes through `Rc`).
+    seen_scopes: std::collections::HashSet<*const std::cell::RefCell<super::Scope>>,
+}
+
+impl Forwarding {
+    #[inline]
+    pub(crate) fn id(&self, id: usize) -> usize {
+        match self.table.get(id) {
+            Some(&new) if new != usize::MAX => new,
+            // Dangling before compaction; leave it as it was.
+            _ => id,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn value(&self, v: Value) -> Value {
+        if v.is_obj() {
+            v.with_obj_id(ObjectId(self.id(v.as_obj_id().0)))
+        } else {
+            v
+        }
+    }
+
+    pub(crate) fn values(&self, values: &mut [Value]) {
+        for v in values {
+            *v = self.value(*v);
+        }
+    }
+
+    pub(crate) fn env(&mut self, env: &mut super::Env) {
+        self.values(&mut env.stack);
+        for frame in &env.frames {
+            if self.seen_scopes.insert(std::rc::Rc::as_ptr(&frame.scope)) {
+                let mut scope = frame.scope.borrow_mut();
+                self.values(&mut scope.values);
+            }
+        }
+    }
+
+    fn object(&mut self, obj: &mut ManagedObject) {
+        match obj {
+            // 共享的元素先拷出，各自改写
+            ManagedObject::List(items) => self.values(items),
+            ManagedObject::Tuple(items) => self.values(items),
+            ManagedObject::Dict(dict) => {
+                for (key, value) in dict.map.iter_mut_with_keys() {
+                    // The key's hash is that of the string or composite
+                    // content, so moving the object does not change where the
+                    // entry lives.
+                    if let super::value::DictKey::StrRef { obj_id, .. }
+                    | super::value::DictKey::Composite { obj_id, .. } = key
+                    {
+                        *obj_id = self.id(*obj_id);
+                    }
+                    *value = self.value(*value);
+                }
+                if let Some(elements) = &mut dict.elements {
+                    self.values(elements);
+                }
+                if let Some(pv) = &mut dict.prop_values {
+                    self.values(pv);
+                }
+                if let Some(shape) = &mut dict.shape {
+                    *shape = ObjectId(self.id(shape.0));
+                }
+            }
+            ManagedObject::DictStr(dict) => {
+                for value in dict.map.values_mut() {
+                    *value = self.value(*value);
+                }
+            }
+            ManagedObject::Module(m) => {
+                for value in m.exports.map.values_mut() {
+                    *value = self.value(*value);
+                }
+            }
+            ManagedObject::Struct(s) => self.values(&mut s.fields),
+            ManagedObject::Enum(e) => self.values(&mut e.2),
+            ManagedObject::OptionSome(v) => *v = self.value(*v),
+            ManagedObject::Shape(shape) => {
+                if let Some(parent) = &mut shape.parent {
+                    *parent = ObjectId(self.id(parent.0));
+                }
+                for target in shape.transitions.values_mut() {
+                    *target = ObjectId(self.id(target.0));
+                }
+            }
+            ManagedObject::Function(Function::User(f)) => self.env(&mut std::rc::Rc::make_mut(f).env),
+            ManagedObject::Function(Function::Bytecode(f)) => self.env(&mut std::rc::Rc::make_mut(f).env),
+            ManagedObject::Function(Function::Builtin(_) | Function::Host(_))
+            | ManagedObject::Str(_)
+            | ManagedObject::Builder(_)
+            | ManagedObject::File(_)
+            | ManagedObject::Range(_, _, _)
+            | ManagedObject::SplitIter(_)
+            | ManagedObject::Channel(_) => {}
+        }
+    }
 }
 
 impl Heap {
@@ -83,12 +279,141 @@ impl Heap {
             gc_threshold: 100_000,  // 10万对象触发GC（平衡内存和性能）
             alloc_bytes: 0,
             gc_threshold_bytes: 128 * 1024 * 1024,  // 128MB触发GC
+            gc_cycles: 0,
+            allocations: 0,
+            fixed_threshold: None,
+            epoch: 0,
+            pools: Pools::default(),
+            dict_hash: super::dict_hash::DictHashState::new(super::dict_hash::DictHashing::Keyed),
+            float_format: crate::util::FloatFormat::default(),
+        }
+    }
+
+    /// Draws new dict hash seeds. Only valid while the heap holds no dicts.
+    pub(crate) fn set_dict_hashing(&mut self, mode: super::dict_hash::DictHashing) {
+        self.dict_hash = super::dict_hash::DictHashState::new(mode);
+    }
+
+    /// Sets how many freed buffers are kept per kind, releasing any beyond
+    /// the new limits.
+    pub fn set_pool_limits(&mut self, limits: PoolLimits) {
+        let pools = &mut self.pools;
+        pools.limits = limits;
+        pools.lists.truncate(limits.lists);
+        pools.dicts.truncate(limits.dicts);
+        pools.texts.truncate(limits.texts);
+    }
+
+    /// Objects allocated so far, including ones already freed.
+    pub fn allocations(&self) -> u64 {
+        self.allocations
+    }
+
+    /// Allocations served from the pools so far.
+    pub fn pool_reuses(&self) -> u64 {
+        self.pools.reused
+    }
+
+    /// An empty vector for a list or tuple of `cap` items, reusing a freed
+    /// buffer when one is pooled.
+    #[inline]
+    pub fn take_list(&mut self, cap: usize) -> Vec<Value> {
+        if cap <= POOLED_LIST_CAP {
+            if let Some(mut items) = self.pools.lists.pop() {
+                self.pools.reused += 1;
+                items.reserve(cap);
+                return items;
+            }
+        }
+        Vec::with_capacity(cap)
+    }
+
+    /// An empty dict for `cap` entries, reusing a freed one when pooled.
+    #[inline]
+    pub fn take_dict(&mut self, cap: usize) -> Dict {
+        if cap <= POOLED_DICT_CAP {
+            if let Some(mut dict) = self.pools.dicts.pop() {
+                self.pools.reused += 1;
+                dict.map.reserve(cap);
+                return dict;
+            }
+        }
+        super::value::dict_with_capacity(cap, self)
+    }
+
+    /// `s` as a text, in a pooled buffer when it does not fit inline.
+    #[inline]
+    pub fn take_text(&mut self, s: &str) -> Text {
+        if s.len() > super::text::INLINE_CAP && s.len() <= POOLED_TEXT_CAP {
+            if let Some(buf) = self.pools.texts.pop() {
+                self.pools.reused += 1;
+                return Text::from_buffer(buf, &[s]);
+            }
+        }
+        Text::from_str(s)
+    }
+
+    /// The texts `a` and `b` joined, in a pooled buffer when the result does
+    /// not fit inline; `None` unless both are texts.
+    #[inline]
+    pub fn concat_texts(&mut self, a: ObjectId, b: ObjectId) -> Option<Text> {
+        let (Some(ManagedObject::Str(ta)), Some(ManagedObject::Str(tb))) =
+            (self.objects.get(a.0)?.as_ref(), self.objects.get(b.0)?.as_ref())
+        else {
+            return None;
+        };
+        let total = ta.len() + tb.len();
+        if total > super::text::INLINE_CAP && total <= POOLED_TEXT_CAP {
+            if let Some(buf) = self.pools.texts.pop() {
+                self.pools.reused += 1;
+                return Some(Text::from_buffer(buf, &[ta.as_str(), tb.as_str()]));
+            }
+        }
+        Some(Text::concat2(ta, tb))
+    }
+
+    /// 释放对象；小缓冲区放入对应的池
+    fn recycle(&mut self, obj: ManagedObject) {
+        let pools = &mut self.pools;
+        match obj {
+            // 仍与其它列表共享的元素不入池
+            ManagedObject::List(items) if !items.is_shared() && items.capacity() <= POOLED_LIST_CAP => {
+                pools.put_list(items.into_vec());
+            }
+            ManagedObject::Tuple(items) => pools.put_list(items),
+            ManagedObject::Dict(mut dict)
+                if dict.map.capacity() <= POOLED_DICT_CAP && pools.dicts.len() < pools.limits.dicts =>
+            {
+                dict.map.clear();
+                dict.elements = None;
+                dict.shape = None;
+                dict.prop_values = None;
+                dict.ver = 0;
+                pools.dicts.push(dict);
+            }
+            ManagedObject::Str(text) if pools.texts.len() < pools.limits.texts => {
+                if let Some(buf) = text.into_buffer(POOLED_TEXT_CAP) {
+                    pools.texts.push(buf);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Collects after every `threshold` allocations instead of adapting the
+    /// count to the live heap; 0 restores the adaptive threshold. The
+    /// byte-based trigger applies either way.
+    pub fn set_gc_threshold(&mut self, threshold: usize) {
+        self.fixed_threshold = (threshold > 0).then_some(threshold);
+        if let Some(n) = self.fixed_threshold {
+            self.gc_threshold = n;
         }
     }
 
     #[inline]
     pub fn alloc(&mut self, obj: ManagedObject) -> ObjectId {
         self.alloc_count += 1;
+        self.allocations += 1;
         self.alloc_bytes += obj.size();
 
         if let Some(id) = self.free_list.pop() {
@@ -112,6 +437,12 @@ impl Heap {
         self.free_list.len()
     }
 
+    /// 值所引用的堆对象；数字、bool 等非对象值为 `None`
+    #[inline]
+    pub fn object(&self, v: Value) -> Option<&ManagedObject> {
+        v.is_obj().then(|| self.get(v.as_obj_id()))
+    }
+
     #[inline]
     pub fn get(&self, id: ObjectId) -> &ManagedObject {
         unsafe { self.objects.get_unchecked(id.0).as_ref().unwrap_unchecked() }
@@ -215,9 +546,9 @@ impl Heap {
                         }
                     }
                     ManagedObject::Dict(dict) => {
-                        // Mark string keys (DictKey::StrRef stores ObjectId)
+                        // Mark string and composite keys (they store an ObjectId)
                         for key in dict.map.keys() {
-                            if let Some(obj_id) = key.str_obj_id() {
+                            if let Some(obj_id) = key.heap_ref() {
                                 stack.push(obj_id.0);
                             }
                         }
@@ -282,7 +613,7 @@ impl Heap {
                                     }
                                 }
                             }
-                            Function::Builtin(_) => {}
+                            Function::Builtin(_) | Function::Host(_) => {}
                         }
                     }
                     ManagedObject::Str(_) |
@@ -290,29 +621,34 @@ impl Heap {
                     ManagedObject::File(_) |
                     ManagedObject::Range(_, _, _) |
                     ManagedObject::Shape(_) |
-                    ManagedObject::SplitIter(_) => {}
+                    ManagedObject::SplitIter(_) |
+                    ManagedObject::Channel(_) => {}
                 }
             }
         }
     }
 
     /// Sweep phase - free unreachable objects and aggressively reclaim memory
-    pub fn sweep(&mut self) {
+    pub fn sweep(&mut self) -> Sweep {
         let mut live_count = 0usize;
         let mut live_bytes = 0usize;
+        let mut freed = 0usize;
+        let mut freed_bytes = 0usize;
         let mut last_live_idx = 0usize;
 
         // First pass: free dead objects and count live ones
         for i in 0..self.objects.len() {
-            if let Some(ref obj) = self.objects[i] {
-                if self.is_marked_idx(i) {
+            if self.is_marked_idx(i) {
+                if let Some(ref obj) = self.objects[i] {
                     live_count += 1;
                     live_bytes += obj.size();
                     last_live_idx = i;
-                } else {
-                    // Free the object
-                    self.objects[i] = None;
                 }
+            } else if let Some(obj) = self.objects[i].take() {
+                // Free the object
+                freed += 1;
+                freed_bytes += obj.size();
+                self.recycle(obj);
             }
         }
 
@@ -357,15 +693,59 @@ impl Heap {
 
         self.alloc_count = 0;
         self.alloc_bytes = 0;
+        self.gc_cycles += 1;
 
         // Set next GC threshold based on live data
         // Use moderate growth factors with reasonable upper limits
         let growth = if live_count > 50000 { 1.5 } else { 2.0 };
         const MAX_THRESHOLD: usize = 500_000;  // 最大50万对象（恢复原值）
         const MAX_THRESHOLD_BYTES: usize = 256 * 1024 * 1024;  // 最大256MB（恢复原值）
-        self.gc_threshold = ((live_count as f64 * growth) as usize).clamp(16384, MAX_THRESHOLD);
+        self.gc_threshold = self
+            .fixed_threshold
+            .unwrap_or_else(|| ((live_count as f64 * growth) as usize).clamp(16384, MAX_THRESHOLD));
         self.gc_threshold_bytes =
             ((live_bytes as f64 * growth) as usize).clamp(16 * 1024 * 1024, MAX_THRESHOLD_BYTES);
+
+        Sweep { freed, freed_bytes, live: live_count, live_bytes }
+    }
+
+    /// The current compaction epoch.
+    #[inline]
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Slides live objects down to the lowest slots, keeping their relative
+    /// order, and rewrites the references between heap objects. References
+    /// held outside the heap must be rewritten by the caller with the returned
+    /// table, so this may only run when no native frame holds a `Value`.
+    pub(crate) fn compact(&mut self) -> Forwarding {
+        let mut table = vec![usize::MAX; self.objects.len()];
+        let mut next = 0usize;
+        let mut moved = 0usize;
+        for (old, slot) in table.iter_mut().enumerate() {
+            if self.objects[old].is_some() {
+                if old != next {
+                    self.objects.swap(old, next);
+                    moved += 1;
+                }
+                *slot = next;
+                next += 1;
+            }
+        }
+        self.objects.truncate(next);
+        self.objects.shrink_to(next.max(1024));
+        self.free_list.clear();
+        self.marks.clear();
+
+        let mut fwd = Forwarding { table, moved, seen_scopes: Default::default() };
+        if moved > 0 {
+            self.epoch += 1;
+            for obj in self.objects.iter_mut().flatten() {
+                fwd.object(obj);
+            }
+        }
+        fwd
     }
 
     pub fn memory_stats(&self) -> String {
@@ -389,6 +769,7 @@ impl Heap {
                     ManagedObject::Module(_) => 11,
                     ManagedObject::Shape(_) => 12,
                     ManagedObject::SplitIter(_) => 13,
+                    ManagedObject::Channel(_) => 14,
                 };
                 counts[idx] += 1;
             }
@@ -424,13 +805,13 @@ impl Heap {
     pub fn free_objects(&mut self, ids: &[usize]) {
         for &id in ids {
             if id < self.objects.len() {
-                if let Some(ref obj) = self.objects[id] {
+                if let Some(obj) = self.objects[id].take() {
                     let size = obj.size();
                     if self.alloc_bytes >= size {
                         self.alloc_bytes -= size;
                     }
+                    self.recycle(obj);
                 }
-                self.objects[id] = None;
                 self.free_list.push(id);
             }
         }
@@ -447,8 +828,8 @@ impl Heap {
         }
         if let Some(obj) = &self.objects[id] {
             match obj {
-                ManagedObject::List(list) | ManagedObject::Tuple(list) => {
-                    for item in list {
+                ManagedObject::List(_) | ManagedObject::Tuple(_) => {
+                    for item in obj.elements().unwrap_or_default() {
                         if item.is_obj() {
                             out.push(item.as_obj_id().0);
                         }
@@ -456,7 +837,7 @@ impl Heap {
                 }
                 ManagedObject::Dict(dict) => {
                     for key in dict.map.keys() {
-                        if let Some(obj_id) = key.str_obj_id() {
+                        if let Some(obj_id) = key.heap_ref() {
                             out.push(obj_id.0);
                         }
                     }
@@ -522,7 +903,7 @@ impl Heap {
                                 }
                             }
                         }
-                        Function::Builtin(_) => {}
+                        Function::Builtin(_) | Function::Host(_) => {}
                     }
                 }
                 ManagedObject::Module(m) => {
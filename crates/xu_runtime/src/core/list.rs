@@ -0,0 +1,138 @@
+//! Storage of list elements.
+//!
+//! A list owns its elements until a slice of it is taken. Long slices are
+//! views: the parent's elements move behind an `Rc` that both share, which
+//! keeps them alive for as long as either needs them. Whichever side is
+//! mutated first copies its own elements out, so neither sees the other's
+//! changes.
+
+use std::ops::{Deref, DerefMut, Range};
+use std::rc::Rc;
+
+use super::value::Value;
+
+/// Fewest elements [`ListItems::slice`] shares instead of copying.
+pub(crate) const SLICE_MIN: usize = 16;
+
+#[derive(Clone)]
+pub struct ListItems(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Owned(Vec<Value>),
+    /// `data[start..end]`, possibly shared with other lists.
+    Shared {
+        data: Rc<Vec<Value>>,
+        start: usize,
+        end: usize,
+    },
+}
+
+impl ListItems {
+    pub fn new() -> Self {
+        Self(Repr::Owned(Vec::new()))
+    }
+
+    /// The elements to mutate, copied out first if they are shared.
+    pub fn make_mut(&mut self) -> &mut Vec<Value> {
+        if let Repr::Shared { data, start, end } = &mut self.0 {
+            let owned = match Rc::get_mut(data) {
+                Some(v) if *start == 0 && *end == v.len() => std::mem::take(v),
+                _ => data[*start..*end].to_vec(),
+            };
+            self.0 = Repr::Owned(owned);
+        }
+        match &mut self.0 {
+            Repr::Owned(v) => v,
+            Repr::Shared { .. } => unreachable!(),
+        }
+    }
+
+    /// The elements of `range`. Short results are copied; longer ones share
+    /// the elements with `self`.
+    pub fn slice(&mut self, range: Range<usize>) -> ListItems {
+        if range.len() < SLICE_MIN {
+            return Self::from(self[range].to_vec());
+        }
+        if let Repr::Owned(v) = &mut self.0 {
+            let end = v.len();
+            self.0 = Repr::Shared { data: Rc::new(std::mem::take(v)), start: 0, end };
+        }
+        match &self.0 {
+            Repr::Shared { data, start, .. } => {
+                ListItems(Repr::Shared { data: data.clone(), start: start + range.start, end: start + range.end })
+            }
+            Repr::Owned(_) => unreachable!(),
+        }
+    }
+
+    /// Whether the elements are shared with another list.
+    pub fn is_shared(&self) -> bool {
+        matches!(&self.0, Repr::Shared { data, .. } if Rc::strong_count(data) > 1)
+    }
+
+    /// Slots allocated for elements; a view counts only its own.
+    pub fn capacity(&self) -> usize {
+        match &self.0 {
+            Repr::Owned(v) => v.capacity(),
+            Repr::Shared { start, end, .. } => end - start,
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<Value> {
+        match self.0 {
+            Repr::Owned(v) => v,
+            Repr::Shared { data, start, end } => match Rc::try_unwrap(data) {
+                Ok(v) if start == 0 && end == v.len() => v,
+                Ok(v) => v[start..end].to_vec(),
+                Err(data) => data[start..end].to_vec(),
+            },
+        }
+    }
+}
+
+impl Default for ListItems {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Vec<Value>> for ListItems {
+    fn from(v: Vec<Value>) -> Self {
+        Self(Repr::Owned(v))
+    }
+}
+
+impl Deref for ListItems {
+    type Target = [Value];
+
+    #[inline]
+    fn deref(&self) -> &[Value] {
+        match &self.0 {
+            Repr::Owned(v) => v,
+            Repr::Shared { data, start, end } => &data[*start..*end],
+        }
+    }
+}
+
+impl DerefMut for ListItems {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [Value] {
+        self.make_mut()
+    }
+}
+
+impl FromIterator<Value> for ListItems {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Self(Repr::Owned(iter.into_iter().collect()))
+    }
+}
+
+impl<'a> IntoIterator for &'a ListItems {
+    type Item = &'a Value;
+    type IntoIter = std::slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -5,11 +5,19 @@
 //! - `Heap` and GC - Garbage collection and memory management
 //! - `Env` and `Scope` - Environment and scope management
 //! - `Text` - Optimized string type
+//! - `ListItems` - List elements, shared between a list and its slices
 //! - `LocalSlots` - Local variable slot allocation
+//! - `dict_hash` - Dict key hashing policy
+//! - `DictMap` - Insertion-ordered dict storage that grows incrementally
+//! - `Channel` - Queues shared between runtimes on different threads
 
 pub mod heap;
+pub mod channel;
 pub mod text;
+pub mod list;
 pub mod value;
+pub(crate) mod dict_hash;
+pub mod dict_map;
 pub mod env;
 pub(crate) mod slot_allocator;
 
@@ -19,6 +27,7 @@ pub mod generational_heap;
 pub use value::*;
 pub use heap::ObjectId;
 pub use text::Text;
+pub use list::ListItems;
 pub use env::{Env, Scope};
 
 // Type alias for the active heap implementation
@@ -1,19 +1,63 @@
 //! Optimized string type with small string optimization.
-
-use std::cell::Cell;
+//!
+//! Substrings of long heap texts are views that share the parent's buffer;
+//! the shared `Rc` keeps it alive for as long as a view needs it, and the
+//! first append to a view copies its bytes out.
+//!
+//! Long texts built by concatenation are ropes: a prefix of a buffer that
+//! each concatenation extends in place, so accumulating a text piece by piece
+//! copies every piece once. A rope is joined into one string the first time
+//! it is read.
+
+use std::cell::{Cell, OnceCell, RefCell};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::rc::Rc;
 use std::str;
 
-const INLINE_CAP: usize = 22;
+pub(crate) const INLINE_CAP: usize = 22;
 const CHAR_COUNT_UNKNOWN: u32 = u32::MAX;
+/// Shortest substring [`Text::slice`] shares instead of copying.
+pub(crate) const SLICE_MIN: usize = 64;
+/// Shortest concatenation [`Text::append`] makes a rope.
+pub(crate) const ROPE_MIN: usize = 4096;
 
 #[derive(Clone)]
 pub enum Text {
     Inline { len: u8, buf: [u8; INLINE_CAP] },
     Heap { data: Rc<String>, char_count: Cell<u32> },
+    /// `len` bytes of `data` from byte `start`, both on char boundaries.
+    Slice { data: Rc<String>, start: u32, len: u32, char_count: Cell<u32> },
+    Rope(Rc<Rope>),
+}
+
+/// A text built by concatenation: the first `len` bytes of `buf`, which the
+/// texts concatenated from it go on appending to.
+pub struct Rope {
+    buf: Rc<RefCell<String>>,
+    len: usize,
+    /// The text as one string, joined on first read.
+    flat: OnceCell<Rc<String>>,
+    char_count: Cell<u32>,
+}
+
+impl Rope {
+    fn new(buf: Rc<RefCell<String>>, len: usize) -> Self {
+        Self { buf, len, flat: OnceCell::new(), char_count: Cell::new(CHAR_COUNT_UNKNOWN) }
+    }
+
+    fn flat(&self) -> &Rc<String> {
+        self.flat.get_or_init(|| Rc::new(self.buf.borrow()[..self.len].to_string()))
+    }
+
+    /// Runs `f` on the text without joining it.
+    fn with_str<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        match self.flat.get() {
+            Some(flat) => f(flat),
+            None => f(&self.buf.borrow()[..self.len]),
+        }
+    }
 }
 
 impl Text {
@@ -31,6 +75,8 @@ impl Text {
                 unsafe { str::from_utf8_unchecked(s) }
             }
             Text::Heap { data, .. } => data.as_str(),
+            Text::Slice { data, start, len, .. } => &data[*start as usize..(*start + *len) as usize],
+            Text::Rope(rope) => rope.flat(),
         }
     }
 
@@ -38,6 +84,8 @@ impl Text {
         match self {
             Text::Inline { len, .. } => *len as usize,
             Text::Heap { data, .. } => data.len(),
+            Text::Slice { len, .. } => *len as usize,
+            Text::Rope(rope) => rope.len,
         }
     }
 
@@ -59,19 +107,63 @@ impl Text {
                     s.chars().count()
                 }
             }
-            Text::Heap { data, char_count } => {
+            Text::Heap { char_count, .. } | Text::Slice { char_count, .. } => {
                 let cached = char_count.get();
                 if cached != CHAR_COUNT_UNKNOWN {
                     cached as usize
                 } else {
-                    let count = data.chars().count() as u32;
+                    let count = self.as_str().chars().count() as u32;
                     char_count.set(count);
                     count as usize
                 }
             }
+            Text::Rope(rope) => {
+                let cached = rope.char_count.get();
+                if cached != CHAR_COUNT_UNKNOWN {
+                    cached as usize
+                } else {
+                    let count = rope.with_str(|s| s.chars().count()) as u32;
+                    rope.char_count.set(count);
+                    count as usize
+                }
+            }
+        }
+    }
+
+    /// The bytes of `range`, which must lie on char boundaries. Short results
+    /// are copied; longer ones share the buffer of a heap text.
+    pub fn slice(&self, range: std::ops::Range<usize>) -> Text {
+        let s = &self.as_str()[range.clone()];
+        if s.len() < SLICE_MIN {
+            return Text::from_str(s);
+        }
+        let (data, base) = match self {
+            Text::Heap { data, .. } => (data, 0),
+            Text::Slice { data, start, .. } => (data, *start as usize),
+            Text::Rope(rope) => (rope.flat(), 0),
+            Text::Inline { .. } => unreachable!("inline texts are shorter than SLICE_MIN"),
+        };
+        match (u32::try_from(base + range.start), u32::try_from(s.len())) {
+            (Ok(start), Ok(len)) if data.len() <= u32::MAX as usize => {
+                Text::Slice { data: data.clone(), start, len, char_count: Cell::new(CHAR_COUNT_UNKNOWN) }
+            }
+            _ => Text::from_str(s),
         }
     }
 
+    /// The part of `self` that `sub` borrows from it, sharing the buffer as
+    /// [`Text::slice`] does.
+    pub fn slice_of(&self, sub: &str) -> Text {
+        let start = sub.as_ptr() as usize - self.as_str().as_ptr() as usize;
+        self.slice(start..start + sub.len())
+    }
+
+    /// Whether this text is a view into a buffer it shares with another.
+    #[inline]
+    pub fn is_slice(&self) -> bool {
+        matches!(self, Text::Slice { .. })
+    }
+
     pub fn from_str(s: &str) -> Self {
         if s.len() <= INLINE_CAP {
             let mut buf = [0u8; INLINE_CAP];
@@ -96,6 +188,33 @@ impl Text {
         Self::Heap { data: Rc::new(s), char_count: Cell::new(CHAR_COUNT_UNKNOWN) }
     }
 
+    /// Takes the buffer of a heap text nobody else holds, emptied, for
+    /// [`Text::from_buffer`]; `None` for inline, shared or larger than `max`
+    /// bytes.
+    pub(crate) fn into_buffer(self, max: usize) -> Option<Rc<String>> {
+        let Text::Heap { mut data, .. } = self else {
+            return None;
+        };
+        let buf = Rc::get_mut(&mut data)?;
+        if buf.capacity() > max {
+            return None;
+        }
+        buf.clear();
+        Some(data)
+    }
+
+    /// `parts` joined in a buffer from [`Text::into_buffer`].
+    pub(crate) fn from_buffer(mut data: Rc<String>, parts: &[&str]) -> Self {
+        if parts.iter().map(|s| s.len()).sum::<usize>() <= INLINE_CAP {
+            return Self::concat_many(parts);
+        }
+        match Rc::get_mut(&mut data) {
+            Some(buf) => parts.iter().for_each(|s| buf.push_str(s)),
+            None => data = Rc::new(parts.concat()),
+        }
+        Self::Heap { data, char_count: Cell::new(CHAR_COUNT_UNKNOWN) }
+    }
+
     pub fn into_string(self) -> String {
         match self {
             Text::Inline { len, buf } => {
@@ -107,9 +226,34 @@ impl Text {
                 Ok(s) => s,
                 Err(r) => (*r).clone(),
             },
+            Text::Slice { .. } | Text::Rope(_) => self.as_str().to_string(),
         }
     }
 
+    /// `a` followed by `s`. Results of at least [`ROPE_MIN`] bytes are
+    /// ropes; appending to the longest rope of a buffer extends the buffer
+    /// instead of copying `a`.
+    pub fn append(a: &Text, s: &str) -> Text {
+        let total = a.len() + s.len();
+        if total < ROPE_MIN {
+            return Text::concat_many(&[a.as_str(), s]);
+        }
+        if let Text::Rope(rope) = a {
+            let mut buf = rope.buf.borrow_mut();
+            if buf.len() == rope.len {
+                buf.push_str(s);
+                return Text::Rope(Rc::new(Rope::new(rope.buf.clone(), total)));
+            }
+        }
+        let mut buf = String::with_capacity(total * 2);
+        match a {
+            Text::Rope(rope) => rope.with_str(|prefix| buf.push_str(prefix)),
+            _ => buf.push_str(a.as_str()),
+        }
+        buf.push_str(s);
+        Text::Rope(Rc::new(Rope::new(Rc::new(RefCell::new(buf)), total)))
+    }
+
     pub fn push_str(&mut self, s: &str) {
         if s.is_empty() {
             return;
@@ -128,23 +272,27 @@ impl Text {
                 out.push_str(s);
                 *self = Text::Heap { data: Rc::new(out), char_count: Cell::new(CHAR_COUNT_UNKNOWN) };
             }
-            Text::Heap { data, char_count } => {
+            Text::Heap { data, char_count } if Rc::strong_count(data) == 1 || data.len() + s.len() < ROPE_MIN => {
                 let hm = Rc::make_mut(data);
                 hm.reserve(s.len());
                 hm.push_str(s);
                 // Invalidate cached char count
                 char_count.set(CHAR_COUNT_UNKNOWN);
             }
+            // 共享的长文本、视图与 rope：追加出新的文本，不改动共享的字节
+            _ => *self = Text::append(self, s),
         }
     }
 
     /// Check if this Text can be modified in-place (has unique ownership).
     /// Returns true for Inline variants (always owned) or Heap variants with Rc::strong_count == 1.
+    /// Slices and ropes never are.
     #[inline]
     pub fn is_unique(&self) -> bool {
         match self {
             Text::Inline { .. } => true,
             Text::Heap { data, .. } => Rc::strong_count(data) == 1,
+            Text::Slice { .. } | Text::Rope(_) => false,
         }
     }
 
@@ -184,6 +332,7 @@ impl Text {
                     false
                 }
             }
+            Text::Slice { .. } | Text::Rope(_) => false,
         }
     }
 
@@ -211,14 +360,8 @@ impl Text {
 
     /// Try to append an f64 in-place. Returns true if successful.
     #[inline]
-    pub fn try_push_f64_in_place(&mut self, f: f64) -> bool {
-        if f.fract() == 0.0 {
-            self.try_push_i64_in_place(f as i64)
-        } else {
-            let mut buf = ryu::Buffer::new();
-            let s = buf.format(f);
-            self.try_push_str_in_place(s)
-        }
+    pub fn try_push_f64_in_place(&mut self, f: f64, format: &crate::util::FloatFormat) -> bool {
+        self.try_push_str_in_place(crate::util::FloatBuf::new(f, format).as_str())
     }
 
     pub fn push_i64(&mut self, i: i64) {
@@ -242,10 +385,7 @@ impl Text {
             };
         }
 
-        let mut out = String::with_capacity(total);
-        out.push_str(a.as_str());
-        out.push_str(b.as_str());
-        Text::Heap { data: Rc::new(out), char_count: Cell::new(CHAR_COUNT_UNKNOWN) }
+        Text::append(a, b.as_str())
     }
 
     /// Concatenate a string with an integer efficiently (avoids cloning)
@@ -266,11 +406,8 @@ impl Text {
             };
         }
 
-        let mut out = String::with_capacity(total);
-        out.push_str(a.as_str());
         // SAFETY: digits is valid UTF-8 (ASCII digits)
-        out.push_str(unsafe { str::from_utf8_unchecked(digits) });
-        Text::Heap { data: Rc::new(out), char_count: Cell::new(CHAR_COUNT_UNKNOWN) }
+        Text::append(a, unsafe { str::from_utf8_unchecked(digits) })
     }
 
     /// Concatenate an integer with a string efficiently (avoids cloning)
@@ -315,10 +452,7 @@ impl Text {
             };
         }
 
-        let mut out = String::with_capacity(total);
-        out.push_str(a.as_str());
-        out.push_str(suffix);
-        Text::Heap { data: Rc::new(out), char_count: Cell::new(CHAR_COUNT_UNKNOWN) }
+        Text::append(a, suffix)
     }
 
     /// Concatenate a string with "()" efficiently (avoids cloning)
@@ -337,21 +471,14 @@ impl Text {
             };
         }
 
-        let mut out = String::with_capacity(total);
-        out.push_str(a.as_str());
-        out.push_str("()");
-        Text::Heap { data: Rc::new(out), char_count: Cell::new(CHAR_COUNT_UNKNOWN) }
+        Text::append(a, "()")
     }
 
     /// Concatenate a string with a float efficiently (avoids cloning)
     #[inline]
-    pub fn concat_str_float(a: &Text, f: f64) -> Text {
-        // For whole numbers, use integer formatting
-        if f.fract() == 0.0 {
-            return Self::concat_str_int(a, f as i64);
-        }
-        let mut float_buf = ryu::Buffer::new();
-        let digits = float_buf.format(f);
+    pub fn concat_str_float(a: &Text, f: f64, format: &crate::util::FloatFormat) -> Text {
+        let digits = crate::util::FloatBuf::new(f, format);
+        let digits = digits.as_str();
         let al = a.len();
         let bl = digits.len();
         let total = al + bl;
@@ -365,10 +492,7 @@ impl Text {
             };
         }
 
-        let mut out = String::with_capacity(total);
-        out.push_str(a.as_str());
-        out.push_str(digits);
-        Text::Heap { data: Rc::new(out), char_count: Cell::new(CHAR_COUNT_UNKNOWN) }
+        Text::append(a, digits)
     }
 
     /// Concatenate multiple strings efficiently by pre-calculating total length
@@ -402,6 +526,8 @@ impl Text {
         match self {
             Text::Inline { len, buf } => buf[..*len as usize].iter().all(|&b| b < 128),
             Text::Heap { data, .. } => data.is_ascii(),
+            Text::Slice { .. } => self.as_str().is_ascii(),
+            Text::Rope(rope) => rope.with_str(str::is_ascii),
         }
     }
 }
@@ -484,6 +610,13 @@ impl fmt::Debug for Text {
     }
 }
 
+impl fmt::Write for Text {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
 impl fmt::Display for Text {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_str())
@@ -29,6 +29,9 @@ pub const ELEMENTS_MAX: i64 = 8_000_000;
 /// Compact dict key representation using heap references.
 /// String keys store only the ObjectId reference (no string copy).
 /// This dramatically improves dict insertion performance.
+///
+/// Keys of different kinds never compare equal: `1`, `1.0` and `true` are
+/// three distinct keys.
 #[derive(Clone, Copy)]
 pub enum DictKey {
     /// String key - stores hash and ObjectId reference to heap string
@@ -36,8 +39,18 @@ pub enum DictKey {
     StrRef { hash: u64, obj_id: usize },
     /// Integer key
     Int(i64),
+    /// Boolean key
+    Bool(bool),
+    /// Float key, stored as canonical bits (see [`DictKey::from_f64`])
+    Float(u64),
+    /// Tuple or struct key - stores its [`ValueExt::stable_hash`] and the
+    /// ObjectId of a frozen copy (see [`DictKey::from_value`])
+    Composite { hash: u64, obj_id: usize },
 }
 
+/// Nesting limit for tuple and struct keys; also stops self-referencing structs.
+const MAX_KEY_DEPTH: usize = 64;
+
 impl fmt::Debug for DictKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -45,6 +58,11 @@ impl fmt::Debug for DictKey {
                 f.debug_struct("StrRef").field("hash", hash).field("obj_id", obj_id).finish()
             }
             DictKey::Int(i) => f.debug_tuple("Int").field(i).finish(),
+            DictKey::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+            DictKey::Float(bits) => f.debug_tuple("Float").field(&f64::from_bits(*bits)).finish(),
+            DictKey::Composite { hash, obj_id } => {
+                f.debug_struct("Composite").field("hash", hash).field("obj_id", obj_id).finish()
+            }
         }
     }
 }
@@ -67,7 +85,7 @@ impl DictKey {
     /// Create a string key by allocating a new string on the heap
     #[inline]
     pub fn from_str_alloc(s: &str, heap: &mut Heap) -> Self {
-        let hash = Self::hash_str(s);
+        let hash = Self::hash_str(s, heap);
         let obj_id = heap.alloc(ManagedObject::Str(Text::from_str(s)));
         DictKey::StrRef { hash, obj_id: obj_id.0 }
     }
@@ -75,18 +93,87 @@ impl DictKey {
     /// Create a string key from Text by allocating on heap
     #[inline]
     pub fn from_text_alloc(t: Text, heap: &mut Heap) -> Self {
-        let hash = Self::hash_str(t.as_str());
+        let hash = Self::hash_str(t.as_str(), heap);
         let obj_id = heap.alloc(ManagedObject::Str(t));
         DictKey::StrRef { hash, obj_id: obj_id.0 }
     }
 
-    /// Compute hash for a string (used for fast equality comparison)
+    /// Compute hash for a string (used for fast equality comparison) with
+    /// the seeds of `heap`, which keys of its dicts must share.
+    #[inline]
+    pub fn hash_str(s: &str, heap: &Heap) -> u64 {
+        heap.dict_hash.hash_str(s)
+    }
+
+    /// Create a float key. `-0.0` and `0.0` are the same key, and every NaN
+    /// is one key equal to itself, so `d[nan]` finds what was stored under NaN.
+    #[inline]
+    pub fn from_f64(f: f64) -> Self {
+        let f = if f == 0.0 { 0.0 } else if f.is_nan() { f64::NAN } else { f };
+        DictKey::Float(f.to_bits())
+    }
+
+    /// Create a key from any hashable value: text, int, float, bool, or a
+    /// tuple or struct whose members are hashable. Returns `None` for other
+    /// values.
+    ///
+    /// Composite keys hold a frozen copy of the value, so mutating a struct
+    /// after using it as a key does not move or corrupt the entry.
+    pub fn from_value(value: Value, heap: &mut Heap) -> Option<Self> {
+        if value.is_int() {
+            return Some(DictKey::Int(value.as_i64()));
+        }
+        if value.is_f64() {
+            return Some(Self::from_f64(value.as_f64()));
+        }
+        if value.is_bool() {
+            return Some(DictKey::Bool(value.as_bool()));
+        }
+        match value.get_tag() {
+            TAG_STR => {
+                let id = value.as_obj_id();
+                let ManagedObject::Str(s) = heap.get(id) else { return None; };
+                Some(Self::from_str_obj(id, Self::hash_str(s.as_str(), heap)))
+            }
+            TAG_TUPLE | TAG_STRUCT => {
+                let hash = value.stable_hash(heap).ok()?;
+                let frozen = freeze_key(value, heap);
+                Some(DictKey::Composite { hash, obj_id: frozen.as_obj_id().0 })
+            }
+            _ => None,
+        }
+    }
+
+    /// The key as a script value. Composite keys yield the dict's frozen
+    /// copy; callers handing it to scripts should copy structs first (see
+    /// [`DictKey::to_script_value`]).
+    pub fn to_value(&self, heap: &Heap) -> Value {
+        match self {
+            DictKey::StrRef { obj_id, .. } => Value::str(ObjectId(*obj_id)),
+            DictKey::Int(i) => Value::from_i64(*i),
+            DictKey::Bool(b) => Value::from_bool(*b),
+            DictKey::Float(bits) => Value::from_f64(f64::from_bits(*bits)),
+            DictKey::Composite { obj_id, .. } => match heap.get(ObjectId(*obj_id)) {
+                ManagedObject::Struct(_) => Value::struct_obj(ObjectId(*obj_id)),
+                _ => Value::tuple(ObjectId(*obj_id)),
+            },
+        }
+    }
+
+    /// The key as a value scripts may mutate: structs inside composite keys
+    /// are copied so the frozen key stays intact.
+    pub fn to_script_value(&self, heap: &mut Heap) -> Value {
+        let v = self.to_value(heap);
+        if matches!(self, DictKey::Composite { .. }) { freeze_key(v, heap) } else { v }
+    }
+
+    /// Heap object the key references, if any (for the GC)
     #[inline]
-    pub fn hash_str(s: &str) -> u64 {
-        use std::hash::Hasher;
-        let mut hasher = ahash::AHasher::default();
-        hasher.write(s.as_bytes());
-        hasher.finish()
+    pub fn heap_ref(&self) -> Option<ObjectId> {
+        match self {
+            DictKey::StrRef { obj_id, .. } | DictKey::Composite { obj_id, .. } => Some(ObjectId(*obj_id)),
+            _ => None,
+        }
     }
 
     /// Get the string content by looking up in heap
@@ -101,7 +188,7 @@ impl DictKey {
                     None
                 }
             }
-            DictKey::Int(_) => None,
+            _ => None,
         }
     }
 
@@ -110,7 +197,7 @@ impl DictKey {
     pub fn str_obj_id(&self) -> Option<ObjectId> {
         match self {
             DictKey::StrRef { obj_id, .. } => Some(ObjectId(*obj_id)),
-            DictKey::Int(_) => None,
+            _ => None,
         }
     }
 
@@ -118,13 +205,20 @@ impl DictKey {
     #[inline]
     pub fn get_hash(&self) -> u64 {
         match self {
-            DictKey::StrRef { hash, .. } => *hash,
+            DictKey::StrRef { hash, .. } | DictKey::Composite { hash, .. } => *hash,
             DictKey::Int(i) => {
                 use std::hash::Hasher;
                 let mut hasher = ahash::AHasher::default();
                 hasher.write_i64(*i);
                 hasher.finish()
             }
+            DictKey::Bool(b) => *b as u64,
+            DictKey::Float(bits) => {
+                use std::hash::Hasher;
+                let mut hasher = ahash::AHasher::default();
+                hasher.write_u64(*bits);
+                hasher.finish()
+            }
         }
     }
 
@@ -157,8 +251,10 @@ impl DictKey {
                     false
                 }
             }
-            (DictKey::Int(a), DictKey::Int(b)) => a == b,
-            _ => false,
+            (DictKey::Composite { hash: h1, obj_id: id1 }, DictKey::Composite { hash: h2, obj_id: id2 }) => {
+                id1 == id2 || (h1 == h2 && key_values_eq(self.to_value(heap), other.to_value(heap), heap))
+            }
+            _ => self == other,
         }
     }
 
@@ -176,7 +272,7 @@ impl DictKey {
                     false
                 }
             }
-            DictKey::Int(_) => false,
+            _ => false,
         }
     }
 
@@ -187,7 +283,7 @@ impl DictKey {
     }
 }
 
-/// PartialEq implementation - compares by hash for string keys
+/// PartialEq implementation - compares by hash for string and composite keys
 /// This assumes hash collisions are rare (ahash is high quality)
 impl PartialEq for DictKey {
     fn eq(&self, other: &Self) -> bool {
@@ -201,6 +297,11 @@ impl PartialEq for DictKey {
                 h1 == h2
             }
             (DictKey::Int(a), DictKey::Int(b)) => a == b,
+            (DictKey::Bool(a), DictKey::Bool(b)) => a == b,
+            (DictKey::Float(a), DictKey::Float(b)) => a == b,
+            (DictKey::Composite { hash: h1, obj_id: id1 }, DictKey::Composite { hash: h2, obj_id: id2 }) => {
+                id1 == id2 || h1 == h2
+            }
             _ => false,
         }
     }
@@ -219,6 +320,18 @@ impl Hash for DictKey {
                 state.write_u8(1);
                 i.hash(state);
             }
+            DictKey::Bool(b) => {
+                state.write_u8(2);
+                b.hash(state);
+            }
+            DictKey::Float(bits) => {
+                state.write_u8(3);
+                state.write_u64(*bits);
+            }
+            DictKey::Composite { hash, .. } => {
+                state.write_u8(4);
+                state.write_u64(*hash);
+            }
         }
     }
 }
@@ -228,10 +341,97 @@ impl fmt::Display for DictKey {
         match self {
             DictKey::StrRef { obj_id, .. } => write!(f, "<str@{}>", obj_id),
             DictKey::Int(i) => write!(f, "{}", i),
+            DictKey::Bool(b) => write!(f, "{}", b),
+            DictKey::Float(bits) => write!(f, "{}", f64::from_bits(*bits)),
+            DictKey::Composite { obj_id, .. } => write!(f, "<key@{}>", obj_id),
+        }
+    }
+}
+
+/// Copy of a hashable value that later mutation cannot reach: structs are
+/// copied, tuples only when they contain a struct. Strings are shared.
+fn freeze_key(value: Value, heap: &mut Heap) -> Value {
+    match value.get_tag() {
+        TAG_TUPLE => {
+            let ManagedObject::Tuple(items) = heap.get(value.as_obj_id()) else { return value; };
+            let items = items.clone();
+            let frozen: Vec<Value> = items.iter().map(|v| freeze_key(*v, heap)).collect();
+            if frozen == items {
+                value
+            } else {
+                Value::tuple(heap.alloc(ManagedObject::Tuple(frozen)))
+            }
+        }
+        TAG_STRUCT => {
+            let ManagedObject::Struct(s) = heap.get(value.as_obj_id()) else { return value; };
+            let mut copy = s.clone();
+            copy.fields = copy.fields.iter().map(|v| freeze_key(*v, heap)).collect();
+            Value::struct_obj(heap.alloc(ManagedObject::Struct(copy)))
         }
+        _ => value,
     }
 }
 
+/// Structural equality of two hashable values, matching [`ValueExt::stable_hash`].
+fn key_values_eq(a: Value, b: Value, heap: &Heap) -> bool {
+    if a.is_f64() && b.is_f64() {
+        return DictKey::from_f64(a.as_f64()) == DictKey::from_f64(b.as_f64());
+    }
+    if a.get_tag() != b.get_tag() {
+        return false;
+    }
+    match (a.get_tag(), heap.object(a), heap.object(b)) {
+        (TAG_STR, Some(ManagedObject::Str(x)), Some(ManagedObject::Str(y))) => x.as_str() == y.as_str(),
+        (TAG_TUPLE, Some(ManagedObject::Tuple(x)), Some(ManagedObject::Tuple(y))) => {
+            x.len() == y.len() && x.iter().zip(y.iter()).all(|(p, q)| key_values_eq(*p, *q, heap))
+        }
+        (TAG_STRUCT, Some(ManagedObject::Struct(x)), Some(ManagedObject::Struct(y))) => {
+            x.ty_hash == y.ty_hash
+                && x.fields.len() == y.fields.len()
+                && x.fields.iter().zip(y.fields.iter()).all(|(p, q)| key_values_eq(*p, *q, heap))
+        }
+        _ => a == b,
+    }
+}
+
+fn stable_hash_at(value: Value, heap: &Heap, depth: usize) -> Result<u64, String> {
+    if depth > MAX_KEY_DEPTH {
+        return Err("Dict key is nested too deeply".into());
+    }
+    let mut h = heap.dict_hash.content_hasher();
+    if value.is_int() {
+        h.write_u8(1);
+        h.write_i64(value.as_i64());
+    } else if value.is_f64() {
+        let DictKey::Float(bits) = DictKey::from_f64(value.as_f64()) else { unreachable!() };
+        h.write_u8(3);
+        h.write_u64(bits);
+    } else if value.is_bool() {
+        h.write_u8(2);
+        h.write_u8(value.as_bool() as u8);
+    } else {
+        match heap.get(value.as_obj_id()) {
+            ManagedObject::Str(s) if value.get_tag() == TAG_STR => return Ok(DictKey::hash_str(s.as_str(), heap)),
+            ManagedObject::Tuple(items) if value.get_tag() == TAG_TUPLE => {
+                h.write_u8(4);
+                h.write_usize(items.len());
+                for item in items {
+                    h.write_u64(stable_hash_at(*item, heap, depth + 1)?);
+                }
+            }
+            ManagedObject::Struct(s) if value.get_tag() == TAG_STRUCT => {
+                h.write_u8(5);
+                h.write_u64(s.ty_hash);
+                for field in s.fields.iter() {
+                    h.write_u64(stable_hash_at(*field, heap, depth + 1)?);
+                }
+            }
+            _ => return Err(format!("Unhashable value of type {}", value.type_name())),
+        }
+    }
+    Ok(h.finish())
+}
+
 // ============================================================================
 // IndexMap and Dict types (IndexMap preserves insertion order)
 // ============================================================================
@@ -242,8 +442,16 @@ pub type FastHashMap<K, V> = IndexMap<K, V, RandomState>;
 /// 超过此值的整数键将使用 map 存储
 pub const ELEMENTS_DENSE_MAX: usize = 1024;
 
+/// `reserve` 单次最多预留的列表槽位数，更大的请求按此截断
+pub const LIST_RESERVE_MAX: usize = 1 << 24;
+
+/// 字典实例
+///
+/// 迭代顺序即插入顺序：先 elements（按下标），再 map（DictMap 保序）。
+/// 为保证这一点，新的小整数键只有在 map 为空且下标大于已有下标时才追加到
+/// elements（见 `try_set_element`），否则写入 map。
 pub struct DictInstance {
-    pub map: FastHashMap<DictKey, Value>,
+    pub map: super::dict_map::DictMap,
     /// 延迟分配的整数键数组 (0 到 ELEMENTS_MAX-1)
     /// 使用 Option<Box<>> 避免为不使用整数键的 dict 分配内存
     pub elements: Option<Box<Vec<Value>>>,
@@ -303,6 +511,62 @@ impl DictInstance {
         was_unit
     }
 
+    /// 按插入顺序规则写入小整数键：已有的键原地覆盖，新键仅在不破坏
+    /// 插入顺序时追加到 elements。返回 `Some(是否新键)`；返回 `None` 表示
+    /// 调用方应改写入 map。
+    #[inline]
+    pub fn try_set_element(&mut self, idx: usize, value: Value) -> Option<bool> {
+        if let Some(slot) = self.elements_mut().and_then(|e| e.get_mut(idx)) {
+            if slot.get_tag() != TAG_UNIT {
+                *slot = value;
+                return Some(false);
+            }
+        }
+        let len = self.elements().map_or(0, |e| e.len());
+        if self.map.is_empty() && idx >= len {
+            return Some(self.set_element(idx, value));
+        }
+        None
+    }
+
+    /// 按插入顺序遍历 elements 与 map 中的条目（不含 Shape 属性）
+    pub fn entries(&self) -> impl Iterator<Item = (DictKey, Value)> + '_ {
+        let elements = self.elements().into_iter().flat_map(|e| {
+            e.iter()
+                .enumerate()
+                .filter(|(_, v)| v.get_tag() != TAG_UNIT)
+                .map(|(i, v)| (DictKey::Int(i as i64), *v))
+        });
+        elements.chain(self.map.iter().map(|(k, v)| (*k, *v)))
+    }
+
+    /// 查找任意键，小整数键优先查 elements
+    #[inline]
+    pub fn get_entry(&self, key: &DictKey) -> Option<Value> {
+        if let DictKey::Int(i) = *key {
+            if (0..ELEMENTS_MAX).contains(&i) {
+                if let Some(v) = self.get_element(i as usize) {
+                    return Some(v);
+                }
+            }
+        }
+        self.map.get(key).copied()
+    }
+
+    /// 插入任意键，小整数键按 `try_set_element` 规则选择存储位置
+    /// 返回是否是新键
+    #[inline]
+    pub fn insert_entry(&mut self, key: DictKey, value: Value) -> bool {
+        if let DictKey::Int(i) = key {
+            if (0..ELEMENTS_MAX).contains(&i) {
+                if let Some(was_unit) = self.try_set_element(i as usize, value) {
+                    return was_unit;
+                }
+            }
+        }
+        self.map.insert(key, value).is_none()
+    }
+
     /// 获取 prop_values 数组的引用（如果存在）
     #[inline]
     pub fn prop_values(&self) -> Option<&Vec<Value>> {
@@ -374,12 +638,8 @@ impl DictInstance {
 
 impl Clone for DictInstance {
     fn clone(&self) -> Self {
-        let mut map = fast_map_with_capacity(self.map.len());
-        for (k, v) in self.map.iter() {
-            map.insert(*k, *v);
-        }
         Self {
-            map,
+            map: self.map.clone(),
             elements: self.elements.as_ref().map(|e| Box::new(e.as_ref().clone())),
             shape: self.shape,
             prop_values: self.prop_values.as_ref().map(|pv| Box::new(pv.as_ref().clone())),
@@ -404,7 +664,7 @@ pub struct DictStrInstance {
 
 impl Clone for DictStrInstance {
     fn clone(&self) -> Self {
-        let mut map = fast_map_with_capacity(self.map.len());
+        let mut map = IndexMap::with_capacity_and_hasher(self.map.len(), self.map.hasher().clone());
         for (k, v) in self.map.iter() {
             map.insert(k.clone(), *v);
         }
@@ -426,9 +686,10 @@ pub fn fast_map_with_capacity<K: Eq + Hash, V>(cap: usize) -> FastHashMap<K, V>
     IndexMap::with_capacity_and_hasher(cap, fast_hasher())
 }
 
-pub fn dict_with_capacity(cap: usize) -> Dict {
+/// An empty dict for keys hashed on `heap`.
+pub fn dict_with_capacity(cap: usize, heap: &Heap) -> Dict {
     Box::new(DictInstance {
-        map: fast_map_with_capacity(cap),
+        map: super::dict_map::DictMap::with_capacity_and_hasher(cap, heap.dict_hash.map_hasher()),
         elements: None,  // 延迟分配
         shape: None,
         prop_values: None,  // 延迟分配
@@ -436,9 +697,9 @@ pub fn dict_with_capacity(cap: usize) -> Dict {
     })
 }
 
-pub fn dict_str_new() -> DictStr {
+pub fn dict_str_new(heap: &Heap) -> DictStr {
     Box::new(DictStrInstance {
-        map: fast_map_new(),
+        map: IndexMap::with_hasher(heap.dict_hash.map_hasher()),
         ver: 0,
     })
 }
@@ -491,6 +752,7 @@ pub const TAG_BUILDER: u64 = 0x000d;
 pub const TAG_TUPLE: u64 = 0x000e;
 pub const TAG_OPTION: u64 = 0x000f;
 pub const TAG_SPLIT_ITER: u64 = 0x0010;
+pub const TAG_CHANNEL: u64 = 0x0011;
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct Value(u64);
@@ -538,6 +800,12 @@ impl Value {
         Self(TAG_BASE | (tag << 48) | (id.0 as u64 & PAYLOAD_MASK))
     }
 
+    /// The same kind of reference, pointing at `id` instead.
+    #[inline(always)]
+    pub(crate) fn with_obj_id(self, id: ObjectId) -> Self {
+        Self::from_obj(self.get_tag(), id)
+    }
+
     pub fn list(id: ObjectId) -> Self {
         Self::from_obj(TAG_LIST, id)
     }
@@ -577,6 +845,9 @@ impl Value {
     pub fn split_iter(id: ObjectId) -> Self {
         Self::from_obj(TAG_SPLIT_ITER, id)
     }
+    pub fn channel(id: ObjectId) -> Self {
+        Self::from_obj(TAG_CHANNEL, id)
+    }
 
     #[inline(always)]
     pub fn is_f64(&self) -> bool {
@@ -626,6 +897,19 @@ impl Value {
         ObjectId((self.0 & PAYLOAD_MASK) as usize)
     }
 
+    /// 原始的 NaN-boxing 位，JIT 代码直接操作这种表示
+    #[cfg(feature = "jit")]
+    #[inline(always)]
+    pub(crate) fn to_bits(self) -> u64 {
+        self.0
+    }
+
+    #[cfg(feature = "jit")]
+    #[inline(always)]
+    pub(crate) fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
     pub fn get_tag(&self) -> u64 {
         if self.is_f64() {
             0
@@ -658,6 +942,7 @@ impl Value {
                 TAG_BUILDER => "builder",
                 TAG_TUPLE => "tuple",
                 TAG_SPLIT_ITER => "split_iterator",
+                TAG_CHANNEL => "channel",
                 _ => "unknown",
             }
         }
@@ -688,6 +973,7 @@ impl fmt::Debug for Value {
                 TAG_RANGE => write!(f, "Range(id={:?})", id),
                 TAG_ENUM => write!(f, "Enum(id={:?})", id),
                 TAG_BUILDER => write!(f, "Builder(id={:?})", id),
+                TAG_CHANNEL => write!(f, "Channel(id={:?})", id),
                 _ => write!(f, "Unknown(tag={}, id={:?})", tag, id),
             }
         }
@@ -711,11 +997,22 @@ pub struct StructInstance {
     pub field_names: std::rc::Rc<[String]>,
 }
 
+impl StructInstance {
+    /// Identity of the shared field layout, used to key inline caches.
+    #[inline(always)]
+    pub fn layout_id(&self) -> usize {
+        std::rc::Rc::as_ptr(&self.field_names) as *const String as usize
+    }
+}
+
 #[derive(Clone)]
 pub struct FileHandle {
     pub path: String,
     pub open: bool,
-    pub content: String,
+    /// Opened with mode `"w"`.
+    pub writable: bool,
+    /// A `write` created or truncated the file; later ones append to it.
+    pub written: bool,
 }
 
 // ============================================================================
@@ -727,11 +1024,13 @@ pub enum Function {
     User(Rc<UserFunction>),
     Builtin(BuiltinFunction),
     Bytecode(Rc<BytecodeFunction>),
+    /// 宿主经 [`crate::Runtime::define_host_function`] 注册的函数
+    Host(HostFunction),
 }
 
 #[derive(Clone)]
 pub struct UserFunction {
-    pub def: FuncDef,
+    pub def: Rc<FuncDef>,
     pub env: super::Env,
     pub needs_env_frame: bool,
     pub fast_param_indices: Option<Box<[usize]>>,
@@ -742,7 +1041,7 @@ pub struct UserFunction {
 
 #[derive(Clone)]
 pub struct BytecodeFunction {
-    pub def: FuncDef,
+    pub def: Rc<FuncDef>,
     pub bytecode: Rc<Bytecode>,
     pub env: super::Env,
     pub needs_env_frame: bool,
@@ -752,6 +1051,8 @@ pub struct BytecodeFunction {
 
 pub type BuiltinFunction = fn(&mut crate::Runtime, &[Value]) -> Result<Value, String>;
 
+pub type HostFunction = Rc<dyn Fn(&mut crate::Runtime, &[Value]) -> Result<Value, String>>;
+
 // ============================================================================
 // ValueExt trait for heap-dependent operations
 // ============================================================================
@@ -762,6 +1063,11 @@ pub trait ValueExt {
     fn bin_op(&self, op: BinaryOp, other: Value) -> Result<Value, String>;
     fn bin_op_assign(&mut self, op: BinaryOp, other: Value, heap: &mut Heap) -> Result<(), String>;
     fn to_string_lossy(&self, heap: &Heap) -> String;
+    /// Content hash of a hashable value (text, int, float, bool, and tuples
+    /// and structs of those); values equal as dict keys hash the same. Keyed
+    /// like string keys, so stable within a runtime and, with
+    /// [`DictHashing::Fixed`](super::dict_hash::DictHashing::Fixed), across runs.
+    fn stable_hash(&self, heap: &Heap) -> Result<u64, String>;
 }
 
 impl ValueExt for Value {
@@ -781,10 +1087,20 @@ impl ValueExt for Value {
             BinaryOp::Mul => mul(*self, other),
             BinaryOp::Div => div(*self, other),
             BinaryOp::Mod => rem(*self, other),
+            BinaryOp::Pow => pow(*self, other),
+            BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor
+            | BinaryOp::Shl
+            | BinaryOp::Shr => bits(*self, op, other),
             BinaryOp::Eq => Ok(Value::from_bool(self == &other)),
             BinaryOp::Ne => Ok(Value::from_bool(self != &other)),
             BinaryOp::And => and(*self, other),
             BinaryOp::Or => or(*self, other),
+            // 需要查看 Option，由执行器在求右操作数之前处理
+            BinaryOp::Coalesce => Err("'??' cannot be applied to evaluated operands".into()),
+            // 需要访问堆，由 Runtime::contains 处理
+            BinaryOp::In => Err("'in' needs the runtime heap".into()),
             BinaryOp::Gt
             | BinaryOp::Lt
             | BinaryOp::Ge
@@ -846,8 +1162,7 @@ impl ValueExt for Value {
         if self.is_bool() { return if self.as_bool() { "true" } else { "false" }.to_string(); }
         if self.is_int() { return i64_to_string_fast(self.as_i64()); }
         if self.is_f64() {
-            let f = self.as_f64();
-            return if f.fract() == 0.0 { format!("{}", f as i64) } else { f.to_string() };
+            return crate::util::format_f64_with(self.as_f64(), &heap.float_format);
         }
         if self.get_tag() == TAG_STR {
             if let ManagedObject::Str(s) = heap.get(self.as_obj_id()) {
@@ -856,6 +1171,10 @@ impl ValueExt for Value {
         }
         format!("{:?}", self)
     }
+
+    fn stable_hash(&self, heap: &Heap) -> Result<u64, String> {
+        stable_hash_at(*self, heap, 0)
+    }
 }
 
 // ============================================================================
@@ -922,6 +1241,46 @@ fn rem(a: Value, b: Value) -> Result<Value, String> {
     Ok(Value::from_f64(x % y))
 }
 
+fn pow(a: Value, b: Value) -> Result<Value, String> {
+    if a.is_int() && b.is_int() && b.as_i64() >= 0 {
+        // 超出 u32 的指数保持奇偶性即可：底数为 0/1/-1 时结果只取决于奇偶，其余必然饱和
+        let bv = b.as_i64();
+        let exp = u32::try_from(bv).unwrap_or(u32::MAX - 1 + (bv & 1) as u32);
+        return Ok(Value::from_i64(a.as_i64().saturating_pow(exp)));
+    }
+    let (x, y) = coerce_f64(a, b)?;
+    Ok(Value::from_f64(x.powf(y)))
+}
+
+fn bits(a: Value, op: BinaryOp, b: Value) -> Result<Value, String> {
+    let sym = match op {
+        BinaryOp::BitAnd => "&", BinaryOp::BitOr => "|", BinaryOp::BitXor => "^",
+        BinaryOp::Shl => "<<", BinaryOp::Shr => ">>",
+        _ => unreachable!(),
+    };
+    if !a.is_int() || !b.is_int() {
+        return Err(format!(
+            "Bitwise operator '{}' requires int operands, got {} and {}",
+            sym,
+            a.type_name(),
+            b.type_name()
+        ));
+    }
+    let (av, bv) = (a.as_i64(), b.as_i64());
+    let res = match op {
+        BinaryOp::BitAnd => av & bv,
+        BinaryOp::BitOr => av | bv,
+        BinaryOp::BitXor => av ^ bv,
+        _ => {
+            if !(0..64).contains(&bv) {
+                return Err(format!("Shift amount must be from 0 to 63, got {}", bv));
+            }
+            if op == BinaryOp::Shl { av << bv } else { av >> bv }
+        }
+    };
+    Ok(Value::from_i64(res))
+}
+
 fn and(a: Value, b: Value) -> Result<Value, String> {
     if a.is_bool() && b.is_bool() { Ok(Value::from_bool(a.as_bool() && b.as_bool())) }
     else { Err("Logical AND requires both operands to be of type ?".to_string()) }
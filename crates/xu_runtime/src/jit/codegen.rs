@@ -0,0 +1,561 @@
+//! 字节码到 Cranelift IR 的翻译
+//! 机器码里的值仍是 NaN-boxing 的 64 位表示。每个运算先检查操作数的标签，
+//! 整数与浮点按解释器的规则计算；其余情形（字符串、对象、除以零、乘法溢出）
+//! 一律返回 `BAIL`，由解释器从头重新执行这次调用。
+
+use cranelift_codegen::ir::condcodes::{FloatCC, IntCC};
+use cranelift_codegen::ir::{self, AbiParam, Block, InstBuilder, MemFlags, Signature, types};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use xu_ir::{Bytecode, Constant, Op};
+
+use crate::core::Value;
+use crate::core::value::{PAYLOAD_MASK, QNAN, TAG_BASE, TAG_BOOL, TAG_INT, TAG_MASK, TAG_UNIT};
+
+/// 函数返回，返回值已写入输出槽
+pub(super) const RETURNED: i32 = 0;
+/// 交还解释器
+pub(super) const BAIL: i32 = 1;
+
+const HIGH16: i64 = 0xffff_0000_0000_0000u64 as i64;
+const INT_BITS: i64 = (TAG_BASE | (TAG_INT << 48)) as i64;
+const BOOL_BITS: i64 = (TAG_BASE | (TAG_BOOL << 48)) as i64;
+const TRUE_BITS: i64 = BOOL_BITS | 1;
+/// 操作数栈的深度上限，超出的函数不编译
+const MAX_STACK: usize = 64;
+
+/// `(args, out, interrupt, signals) -> status`：参数按位排成的数组、返回值的
+/// 输出槽、中断标志和待处理信号的位集
+pub(super) fn signature(ptr: ir::Type, call_conv: cranelift_codegen::isa::CallConv) -> Signature {
+    let mut sig = Signature::new(call_conv);
+    sig.params.extend([AbiParam::new(ptr); 4]);
+    sig.returns.push(AbiParam::new(types::I32));
+    sig
+}
+
+/// 每个操作的出栈、入栈个数；JIT 不支持的操作返回 None
+fn stack_effect(op: &Op, bc: &Bytecode, locals: usize) -> Option<(usize, usize)> {
+    let local = |idx: &u32| (*idx as usize) < locals;
+    Some(match op {
+        Op::ConstInt(_) | Op::ConstFloat(_) | Op::ConstBool(_) | Op::ConstNull => (0, 1),
+        Op::Const(idx) if matches!(bc.constants.get(*idx as usize), Some(Constant::Int(_) | Constant::Float(_))) => {
+            (0, 1)
+        }
+        Op::LoadLocal(idx) if local(idx) => (0, 1),
+        Op::StoreLocal(idx) | Op::AddAssignLocal(idx) if local(idx) => (1, 0),
+        Op::IncLocal(idx) if local(idx) => (0, 0),
+        Op::Dup => (1, 2),
+        Op::Pop | Op::JumpIfFalse(_) | Op::JumpIfTrue(_) | Op::Return => (1, 0),
+        Op::Not => (1, 1),
+        Op::Add
+        | Op::Sub
+        | Op::Mul
+        | Op::Div
+        | Op::Mod
+        | Op::Eq
+        | Op::Ne
+        | Op::And
+        | Op::Or
+        | Op::Gt
+        | Op::Lt
+        | Op::Ge
+        | Op::Le => (2, 1),
+        Op::Jump(_) => (0, 0),
+        _ => return None,
+    })
+}
+
+/// 每个可达操作执行前的栈深度。含不支持的操作、各路径深度不一致或会
+/// 越过末尾的函数返回 None
+fn stack_depths(bc: &Bytecode, locals: usize) -> Option<Vec<Option<usize>>> {
+    let n = bc.ops.len();
+    let mut depths = vec![None; n];
+    let mut work = vec![(0usize, 0usize)];
+    while let Some((ip, depth)) = work.pop() {
+        if ip >= n {
+            return None;
+        }
+        match depths[ip] {
+            Some(seen) if seen == depth => continue,
+            Some(_) => return None,
+            None => depths[ip] = Some(depth),
+        }
+        let op = &bc.ops[ip];
+        let (pops, pushes) = stack_effect(op, bc, locals)?;
+        if depth < pops || depth - pops + pushes > MAX_STACK {
+            return None;
+        }
+        let depth = depth - pops + pushes;
+        match op {
+            Op::Jump(to) => work.push((*to as usize, depth)),
+            Op::JumpIfFalse(to) | Op::JumpIfTrue(to) => {
+                work.push((*to as usize, depth));
+                work.push((ip + 1, depth));
+            }
+            Op::Return => {}
+            _ => work.push((ip + 1, depth)),
+        }
+    }
+    Some(depths)
+}
+
+#[derive(Clone, Copy)]
+enum Arith {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+struct Emitter<'a> {
+    b: FunctionBuilder<'a>,
+    bail: Block,
+    interrupt: ir::Value,
+    signals: ir::Value,
+}
+
+impl Emitter<'_> {
+    fn bits(&mut self, v: Value) -> ir::Value {
+        self.b.ins().iconst(types::I64, v.to_bits() as i64)
+    }
+
+    /// `cond` 为真时交还解释器
+    fn bail_if(&mut self, cond: ir::Value) {
+        let cont = self.b.create_block();
+        self.b.ins().brif(cond, self.bail, &[], cont, &[]);
+        self.b.switch_to_block(cont);
+    }
+
+    fn bail_unless(&mut self, cond: ir::Value) {
+        let cond = self.b.ins().bxor_imm(cond, 1);
+        self.bail_if(cond);
+    }
+
+    /// 循环回边上的安全点：有中断或待处理信号时交还解释器，由它来处理
+    fn safepoint(&mut self) {
+        let flags = MemFlags::trusted();
+        let interrupted = self.b.ins().atomic_load(types::I8, flags, self.interrupt);
+        let signalled = self.b.ins().atomic_load(types::I32, flags, self.signals);
+        let interrupted = self.b.ins().icmp_imm(IntCC::NotEqual, interrupted, 0);
+        let signalled = self.b.ins().icmp_imm(IntCC::NotEqual, signalled, 0);
+        let stop = self.b.ins().bor(interrupted, signalled);
+        self.bail_if(stop);
+    }
+
+    fn is_int(&mut self, v: ir::Value) -> ir::Value {
+        let high = self.b.ins().band_imm(v, HIGH16);
+        self.b.ins().icmp_imm(IntCC::Equal, high, INT_BITS)
+    }
+
+    fn is_f64(&mut self, v: ir::Value) -> ir::Value {
+        let base = self.b.ins().band_imm(v, TAG_BASE as i64);
+        self.b.ins().icmp_imm(IntCC::NotEqual, base, TAG_BASE as i64)
+    }
+
+    fn is_bool(&mut self, v: ir::Value) -> ir::Value {
+        let high = self.b.ins().band_imm(v, HIGH16);
+        self.b.ins().icmp_imm(IntCC::Equal, high, BOOL_BITS)
+    }
+
+    fn is_obj(&mut self, v: ir::Value) -> ir::Value {
+        let boxed = self.is_f64(v);
+        let boxed = self.b.ins().bxor_imm(boxed, 1);
+        let tag = self.b.ins().band_imm(v, TAG_MASK as i64);
+        let tag = self.b.ins().ushr_imm(tag, 48);
+        let heap = self.b.ins().icmp_imm(IntCC::UnsignedGreaterThan, tag, TAG_UNIT as i64);
+        self.b.ins().band(boxed, heap)
+    }
+
+    fn is_num(&mut self, v: ir::Value) -> ir::Value {
+        let int = self.is_int(v);
+        let float = self.is_f64(v);
+        self.b.ins().bor(int, float)
+    }
+
+    /// 整数的 48 位负载，符号扩展到 64 位
+    fn int_payload(&mut self, v: ir::Value) -> ir::Value {
+        let shifted = self.b.ins().ishl_imm(v, 16);
+        self.b.ins().sshr_imm(shifted, 16)
+    }
+
+    fn box_int(&mut self, x: ir::Value) -> ir::Value {
+        let payload = self.b.ins().band_imm(x, PAYLOAD_MASK as i64);
+        self.b.ins().bor_imm(payload, INT_BITS)
+    }
+
+    fn box_bool(&mut self, cond: ir::Value) -> ir::Value {
+        let bit = self.b.ins().uextend(types::I64, cond);
+        self.b.ins().bor_imm(bit, BOOL_BITS)
+    }
+
+    /// 整数或浮点转为 f64；其余值的结果无意义，调用方须先检查
+    fn as_f64(&mut self, v: ir::Value) -> ir::Value {
+        let int = self.is_int(v);
+        let payload = self.int_payload(v);
+        let converted = self.b.ins().fcvt_from_sint(types::F64, payload);
+        let raw = self.b.ins().bitcast(types::F64, MemFlags::new(), v);
+        self.b.ins().select(int, converted, raw)
+    }
+
+    /// 与 `Value::from_f64` 一样把 NaN 规范为 `QNAN`
+    fn box_f64(&mut self, f: ir::Value) -> ir::Value {
+        let raw = self.b.ins().bitcast(types::I64, MemFlags::new(), f);
+        let nan = self.b.ins().fcmp(FloatCC::Unordered, f, f);
+        let qnan = self.b.ins().iconst(types::I64, QNAN as i64);
+        self.b.ins().select(nan, qnan, raw)
+    }
+
+    fn arith(&mut self, op: Arith, a: ir::Value, b: ir::Value) -> ir::Value {
+        let int_block = self.b.create_block();
+        let other = self.b.create_block();
+        let done = self.b.create_block();
+        self.b.append_block_param(done, types::I64);
+        let a_int = self.is_int(a);
+        let b_int = self.is_int(b);
+        let ints = self.b.ins().band(a_int, b_int);
+        self.b.ins().brif(ints, int_block, &[], other, &[]);
+
+        // 48 位的操作数相加减不会溢出 i64，截断到 48 位与解释器相同
+        self.b.switch_to_block(int_block);
+        let x = self.int_payload(a);
+        let y = self.int_payload(b);
+        let r = match op {
+            Arith::Add => self.b.ins().iadd(x, y),
+            Arith::Sub => self.b.ins().isub(x, y),
+            Arith::Mul => {
+                // 溢出 i64 时解释器先饱和再截断，交给它
+                let low = self.b.ins().imul(x, y);
+                let high = self.b.ins().smulhi(x, y);
+                let sign = self.b.ins().sshr_imm(low, 63);
+                let overflow = self.b.ins().icmp(IntCC::NotEqual, high, sign);
+                self.bail_if(overflow);
+                low
+            }
+            Arith::Div | Arith::Mod => {
+                let zero = self.b.ins().icmp_imm(IntCC::Equal, y, 0);
+                self.bail_if(zero);
+                if matches!(op, Arith::Div) { self.b.ins().sdiv(x, y) } else { self.b.ins().srem(x, y) }
+            }
+        };
+        let r = self.box_int(r);
+        self.b.ins().jump(done, &[r]);
+
+        self.b.switch_to_block(other);
+        if matches!(op, Arith::Mod) {
+            // Cranelift 没有浮点取余
+            self.b.ins().jump(self.bail, &[]);
+        } else {
+            let a_num = self.is_num(a);
+            let b_num = self.is_num(b);
+            let nums = self.b.ins().band(a_num, b_num);
+            self.bail_unless(nums);
+            let x = self.as_f64(a);
+            let y = self.as_f64(b);
+            let r = match op {
+                Arith::Add => self.b.ins().fadd(x, y),
+                Arith::Sub => self.b.ins().fsub(x, y),
+                Arith::Mul => self.b.ins().fmul(x, y),
+                _ => {
+                    let zero = self.b.ins().f64const(0.0);
+                    let is_zero = self.b.ins().fcmp(FloatCC::Equal, y, zero);
+                    self.bail_if(is_zero);
+                    self.b.ins().fdiv(x, y)
+                }
+            };
+            let r = self.box_f64(r);
+            self.b.ins().jump(done, &[r]);
+        }
+
+        self.b.switch_to_block(done);
+        self.b.block_params(done)[0]
+    }
+
+    fn compare(&mut self, int_cc: IntCC, float_cc: FloatCC, a: ir::Value, b: ir::Value) -> ir::Value {
+        let int_block = self.b.create_block();
+        let other = self.b.create_block();
+        let done = self.b.create_block();
+        self.b.append_block_param(done, types::I64);
+        let a_int = self.is_int(a);
+        let b_int = self.is_int(b);
+        let ints = self.b.ins().band(a_int, b_int);
+        self.b.ins().brif(ints, int_block, &[], other, &[]);
+
+        self.b.switch_to_block(int_block);
+        let x = self.int_payload(a);
+        let y = self.int_payload(b);
+        let holds = self.b.ins().icmp(int_cc, x, y);
+        let r = self.box_bool(holds);
+        self.b.ins().jump(done, &[r]);
+
+        // 字符串之间的比较交给解释器
+        self.b.switch_to_block(other);
+        let a_num = self.is_num(a);
+        let b_num = self.is_num(b);
+        let nums = self.b.ins().band(a_num, b_num);
+        self.bail_unless(nums);
+        let x = self.as_f64(a);
+        let y = self.as_f64(b);
+        let holds = self.b.ins().fcmp(float_cc, x, y);
+        let r = self.box_bool(holds);
+        self.b.ins().jump(done, &[r]);
+
+        self.b.switch_to_block(done);
+        self.b.block_params(done)[0]
+    }
+
+    /// 同 `Runtime::values_equal`：位相同即相等，整数与浮点按数值比较，
+    /// 其余标签不同或同为非对象的值不等；两个不同的对象要深比较，交给解释器
+    fn equal(&mut self, a: ir::Value, b: ir::Value) -> ir::Value {
+        let same = self.b.ins().icmp(IntCC::Equal, a, b);
+        let a_obj = self.is_obj(a);
+        let b_obj = self.is_obj(b);
+        let objs = self.b.ins().band(a_obj, b_obj);
+        let differ = self.b.ins().bxor_imm(same, 1);
+        let deep = self.b.ins().band(objs, differ);
+        self.bail_if(deep);
+        let a_int = self.is_int(a);
+        let b_int = self.is_int(b);
+        let a_f64 = self.is_f64(a);
+        let b_f64 = self.is_f64(b);
+        let int_float = self.b.ins().band(a_int, b_f64);
+        let float_int = self.b.ins().band(a_f64, b_int);
+        let mixed = self.b.ins().bor(int_float, float_int);
+        let x = self.as_f64(a);
+        let y = self.as_f64(b);
+        let num_eq = self.b.ins().fcmp(FloatCC::Equal, x, y);
+        let num_eq = self.b.ins().band(mixed, num_eq);
+        self.b.ins().bor(same, num_eq)
+    }
+
+    fn logic(&mut self, and: bool, a: ir::Value, b: ir::Value) -> ir::Value {
+        let a_bool = self.is_bool(a);
+        let b_bool = self.is_bool(b);
+        let bools = self.b.ins().band(a_bool, b_bool);
+        self.bail_unless(bools);
+        if and { self.b.ins().band(a, b) } else { self.b.ins().bor(a, b) }
+    }
+}
+
+/// 把 `bc` 翻译进 `func`（签名已设好）。`params` 个参数占局部变量的前几个槽，
+/// 其余槽从 `()` 开始。函数中有 JIT 不支持的操作时返回 false
+pub(super) fn translate(
+    func: &mut ir::Function,
+    fctx: &mut FunctionBuilderContext,
+    bc: &Bytecode,
+    params: usize,
+    locals: usize,
+) -> bool {
+    if params > locals {
+        return false;
+    }
+    let Some(depths) = stack_depths(bc, locals) else {
+        return false;
+    };
+    let ops = &bc.ops;
+    let n = ops.len();
+
+    let mut b = FunctionBuilder::new(func, fctx);
+    let entry = b.create_block();
+    b.append_block_params_for_function_params(entry);
+    b.switch_to_block(entry);
+    let &[args, out, interrupt, signals] = b.block_params(entry) else {
+        unreachable!("JIT signature has four parameters")
+    };
+    let vars: Vec<Variable> = (0..locals as u32).map(Variable::from_u32).collect();
+    for (i, var) in vars.iter().enumerate() {
+        b.declare_var(*var, types::I64);
+        let v = if i < params {
+            b.ins().load(types::I64, MemFlags::trusted(), args, (i * 8) as i32)
+        } else {
+            b.ins().iconst(types::I64, Value::UNIT.to_bits() as i64)
+        };
+        b.def_var(*var, v);
+    }
+
+    // 基本块从跳转目标、跳转与返回之后的操作开始；块参数是进入时栈上的值
+    let mut starts = vec![false; n];
+    starts[0] = true;
+    for (ip, op) in ops.iter().enumerate() {
+        if let Op::Jump(to) | Op::JumpIfFalse(to) | Op::JumpIfTrue(to) = op {
+            if let Some(start) = starts.get_mut(*to as usize) {
+                *start = true;
+            }
+        }
+        if matches!(op, Op::Jump(_) | Op::JumpIfFalse(_) | Op::JumpIfTrue(_) | Op::Return) && ip + 1 < n {
+            starts[ip + 1] = true;
+        }
+    }
+    let mut blocks: Vec<Option<Block>> = vec![None; n];
+    for ip in 0..n {
+        let Some(depth) = depths[ip] else { continue };
+        if starts[ip] {
+            let block = b.create_block();
+            for _ in 0..depth {
+                b.append_block_param(block, types::I64);
+            }
+            blocks[ip] = Some(block);
+        }
+    }
+    let bail = b.create_block();
+    let first = blocks[0].expect("op 0 starts a block");
+    b.ins().jump(first, &[]);
+
+    let mut e = Emitter { b, bail, interrupt, signals };
+    let mut stack: Vec<ir::Value> = Vec::with_capacity(MAX_STACK);
+    let mut open = false;
+    for (ip, op) in ops.iter().enumerate() {
+        if depths[ip].is_none() {
+            continue;
+        }
+        if let Some(block) = blocks[ip] {
+            if open {
+                e.b.ins().jump(block, &stack);
+            }
+            e.b.switch_to_block(block);
+            stack.clear();
+            stack.extend_from_slice(e.b.block_params(block));
+        }
+        open = true;
+        match op {
+            Op::ConstInt(i) => {
+                let v = e.bits(Value::from_i64(*i));
+                stack.push(v);
+            }
+            Op::ConstFloat(f) => {
+                let v = e.bits(Value::from_f64(*f));
+                stack.push(v);
+            }
+            Op::Const(idx) => {
+                let v = match &bc.constants[*idx as usize] {
+                    Constant::Int(i) => Value::from_i64(*i),
+                    Constant::Float(f) => Value::from_f64(*f),
+                    _ => unreachable!("checked by stack_effect"),
+                };
+                let v = e.bits(v);
+                stack.push(v);
+            }
+            Op::ConstBool(v) => {
+                let v = e.bits(Value::from_bool(*v));
+                stack.push(v);
+            }
+            Op::ConstNull => {
+                let v = e.bits(Value::UNIT);
+                stack.push(v);
+            }
+            Op::Pop => {
+                stack.pop();
+            }
+            Op::Dup => {
+                let v = *stack.last().expect("checked by stack_depths");
+                stack.push(v);
+            }
+            Op::LoadLocal(idx) => {
+                let v = e.b.use_var(vars[*idx as usize]);
+                stack.push(v);
+            }
+            Op::StoreLocal(idx) => {
+                let v = stack.pop().expect("checked by stack_depths");
+                e.b.def_var(vars[*idx as usize], v);
+            }
+            Op::IncLocal(idx) | Op::AddAssignLocal(idx) => {
+                let rhs = match op {
+                    Op::IncLocal(_) => e.bits(Value::from_i64(1)),
+                    _ => stack.pop().expect("checked by stack_depths"),
+                };
+                let cur = e.b.use_var(vars[*idx as usize]);
+                let v = e.arith(Arith::Add, cur, rhs);
+                e.b.def_var(vars[*idx as usize], v);
+            }
+            Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Mod => {
+                let rhs = stack.pop().expect("checked by stack_depths");
+                let lhs = stack.pop().expect("checked by stack_depths");
+                let kind = match op {
+                    Op::Add => Arith::Add,
+                    Op::Sub => Arith::Sub,
+                    Op::Mul => Arith::Mul,
+                    Op::Div => Arith::Div,
+                    _ => Arith::Mod,
+                };
+                let v = e.arith(kind, lhs, rhs);
+                stack.push(v);
+            }
+            Op::Gt | Op::Lt | Op::Ge | Op::Le => {
+                let rhs = stack.pop().expect("checked by stack_depths");
+                let lhs = stack.pop().expect("checked by stack_depths");
+                let (int_cc, float_cc) = match op {
+                    Op::Gt => (IntCC::SignedGreaterThan, FloatCC::GreaterThan),
+                    Op::Lt => (IntCC::SignedLessThan, FloatCC::LessThan),
+                    Op::Ge => (IntCC::SignedGreaterThanOrEqual, FloatCC::GreaterThanOrEqual),
+                    _ => (IntCC::SignedLessThanOrEqual, FloatCC::LessThanOrEqual),
+                };
+                let v = e.compare(int_cc, float_cc, lhs, rhs);
+                stack.push(v);
+            }
+            Op::Eq | Op::Ne => {
+                let rhs = stack.pop().expect("checked by stack_depths");
+                let lhs = stack.pop().expect("checked by stack_depths");
+                let mut holds = e.equal(lhs, rhs);
+                if matches!(op, Op::Ne) {
+                    holds = e.b.ins().bxor_imm(holds, 1);
+                }
+                let v = e.box_bool(holds);
+                stack.push(v);
+            }
+            Op::And | Op::Or => {
+                let rhs = stack.pop().expect("checked by stack_depths");
+                let lhs = stack.pop().expect("checked by stack_depths");
+                let v = e.logic(matches!(op, Op::And), lhs, rhs);
+                stack.push(v);
+            }
+            Op::Not => {
+                let v = stack.pop().expect("checked by stack_depths");
+                let is_bool = e.is_bool(v);
+                e.bail_unless(is_bool);
+                let v = e.b.ins().bxor_imm(v, 1);
+                stack.push(v);
+            }
+            Op::Jump(to) => {
+                if *to as usize <= ip {
+                    e.safepoint();
+                }
+                let target = blocks[*to as usize].expect("jump targets start blocks");
+                e.b.ins().jump(target, &stack);
+                open = false;
+            }
+            Op::JumpIfFalse(to) | Op::JumpIfTrue(to) => {
+                let v = stack.pop().expect("checked by stack_depths");
+                if *to as usize <= ip {
+                    e.safepoint();
+                }
+                let is_true = e.b.ins().icmp_imm(IntCC::Equal, v, TRUE_BITS);
+                let target = blocks[*to as usize].expect("jump targets start blocks");
+                let next = blocks[ip + 1].expect("ops after branches start blocks");
+                if matches!(op, Op::JumpIfFalse(_)) {
+                    // 条件不是布尔值时解释器报错
+                    let is_bool = e.is_bool(v);
+                    e.bail_unless(is_bool);
+                    e.b.ins().brif(is_true, next, &stack, target, &stack);
+                } else {
+                    e.b.ins().brif(is_true, target, &stack, next, &stack);
+                }
+                open = false;
+            }
+            Op::Return => {
+                let v = stack.pop().expect("checked by stack_depths");
+                e.b.ins().store(MemFlags::trusted(), v, out, 0);
+                let status = e.b.ins().iconst(types::I32, RETURNED as i64);
+                e.b.ins().return_(&[status]);
+                open = false;
+            }
+            _ => unreachable!("checked by stack_effect"),
+        }
+    }
+    debug_assert!(!open, "stack_depths rejects falling off the end");
+
+    e.b.switch_to_block(bail);
+    let status = e.b.ins().iconst(types::I32, BAIL as i64);
+    e.b.ins().return_(&[status]);
+    e.b.seal_all_blocks();
+    e.b.finalize();
+    true
+}
@@ -0,0 +1,196 @@
+//! 基线 JIT（`jit` 特性）
+//! 字节码函数按调用次数计热度；达到 `RuntimeConfig::jit_threshold` 次、且只用
+//! 整数/浮点运算、比较、局部变量、跳转与返回的函数，用 Cranelift 编译为机器码。
+//! 这些操作没有副作用，所以机器码遇到不支持的情形时可以直接交还解释器，由它
+//! 从头重新执行这次调用（去优化）；经常交还的函数此后一直由解释器执行。
+
+mod codegen;
+
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU32};
+
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::FunctionBuilderContext;
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::Module;
+use smallvec::SmallVec;
+use xu_ir::Bytecode;
+
+use crate::Runtime;
+use crate::core::Value;
+use crate::core::value::{BytecodeFunction, FastHashMap, fast_map_new};
+
+type NativeFn = unsafe extern "C" fn(*const u64, *mut u64, *const AtomicBool, *const AtomicU32) -> i32;
+
+/// 交还次数达到这个数、且超过机器码执行次数的八分之一时去优化
+const BAIL_LIMIT: u32 = 32;
+
+/// Activity of the JIT tier since the runtime was created or reset, from
+/// [`Runtime::jit_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JitStats {
+    /// Functions compiled to native code.
+    pub compiled: usize,
+    /// Hot functions left to the interpreter because they use operations
+    /// the JIT does not compile.
+    pub rejected: usize,
+    /// Calls native code ran to the end.
+    pub native_calls: u64,
+    /// Calls native code handed back to the interpreter, which ran them
+    /// again from the start: an operand that is not a number (or a bool
+    /// where one is expected), a division by zero, an int product beyond 64
+    /// bits, or a pending interrupt or signal.
+    pub bailouts: u64,
+    /// Compiled functions that bailed out so often they were sent back to
+    /// the interpreter for good.
+    pub deoptimized: usize,
+}
+
+enum State {
+    Counting,
+    Compiled(NativeFn),
+    Interpreted,
+}
+
+struct Entry {
+    /// 持有字节码，使作为键的地址不会被复用
+    _bytecode: Rc<Bytecode>,
+    calls: u32,
+    runs: u64,
+    bails: u32,
+    state: State,
+}
+
+struct Compiler {
+    module: JITModule,
+    ctx: cranelift_codegen::Context,
+    fctx: FunctionBuilderContext,
+}
+
+impl Compiler {
+    /// 本机不受 Cranelift 支持时返回 None
+    fn new() -> Option<Self> {
+        let mut flags = settings::builder();
+        flags.set("opt_level", "speed").ok()?;
+        flags.set("use_colocated_libcalls", "false").ok()?;
+        flags.set("is_pic", "false").ok()?;
+        let isa = cranelift_native::builder().ok()?.finish(settings::Flags::new(flags)).ok()?;
+        let module = JITModule::new(JITBuilder::with_isa(isa, cranelift_module::default_libcall_names()));
+        let ctx = module.make_context();
+        Some(Self { module, ctx, fctx: FunctionBuilderContext::new() })
+    }
+
+    fn compile(&mut self, bc: &Bytecode, params: usize, locals: usize) -> Option<NativeFn> {
+        let isa = self.module.isa();
+        self.ctx.func.signature = codegen::signature(isa.pointer_type(), isa.default_call_conv());
+        let compiled = codegen::translate(&mut self.ctx.func, &mut self.fctx, bc, params, locals)
+            .then(|| self.module.declare_anonymous_function(&self.ctx.func.signature).ok())
+            .flatten()
+            .filter(|id| self.module.define_function(*id, &mut self.ctx).is_ok());
+        self.module.clear_context(&mut self.ctx);
+        let id = compiled?;
+        self.module.finalize_definitions().ok()?;
+        let code = self.module.get_finalized_function(id);
+        // SAFETY: 函数按 `codegen::signature` 生成，与 NativeFn 一致
+        Some(unsafe { std::mem::transmute::<*const u8, NativeFn>(code) })
+    }
+}
+
+/// 调用计数与已编译的函数，以字节码的地址为键
+pub(crate) struct Jit {
+    compiler: Option<Compiler>,
+    entries: FastHashMap<usize, Entry>,
+    stats: JitStats,
+}
+
+impl Drop for Jit {
+    fn drop(&mut self) {
+        self.entries.clear();
+        if let Some(compiler) = self.compiler.take() {
+            // SAFETY: 机器码的地址只存在 entries 中，已随之清空
+            unsafe { compiler.module.free_memory() };
+        }
+    }
+}
+
+impl Jit {
+    pub(crate) fn new() -> Self {
+        Self { compiler: None, entries: fast_map_new(), stats: JitStats::default() }
+    }
+
+    /// 计一次调用；函数已编译或刚好变热并编译成功时返回机器码
+    fn native_code(&mut self, fun: &BytecodeFunction, threshold: u32) -> Option<NativeFn> {
+        let key = Rc::as_ptr(&fun.bytecode) as usize;
+        let entry = self.entries.entry(key).or_insert_with(|| Entry {
+            _bytecode: fun.bytecode.clone(),
+            calls: 0,
+            runs: 0,
+            bails: 0,
+            state: State::Counting,
+        });
+        match entry.state {
+            State::Compiled(code) => return Some(code),
+            State::Interpreted => return None,
+            State::Counting => {}
+        }
+        entry.calls += 1;
+        if entry.calls < threshold {
+            return None;
+        }
+        if self.compiler.is_none() {
+            self.compiler = Compiler::new();
+        }
+        let code =
+            self.compiler.as_mut().and_then(|c| c.compile(&fun.bytecode, fun.def.params.len(), fun.locals_count));
+        match code {
+            Some(code) => {
+                entry.state = State::Compiled(code);
+                self.stats.compiled += 1;
+            }
+            None => {
+                entry.state = State::Interpreted;
+                self.stats.rejected += 1;
+            }
+        }
+        code
+    }
+
+    fn record(&mut self, fun: &BytecodeFunction, bailed: bool) {
+        let Some(entry) = self.entries.get_mut(&(Rc::as_ptr(&fun.bytecode) as usize)) else { return };
+        entry.runs += 1;
+        if !bailed {
+            self.stats.native_calls += 1;
+            return;
+        }
+        self.stats.bailouts += 1;
+        entry.bails += 1;
+        if entry.bails >= BAIL_LIMIT && u64::from(entry.bails) * 8 > entry.runs {
+            entry.state = State::Interpreted;
+            self.stats.deoptimized += 1;
+        }
+    }
+}
+
+impl Runtime {
+    /// 用机器码执行一次调用；返回 None 时由解释器执行。调用方已检查过参数个数
+    /// 与类型，且函数不需要环境帧
+    pub(crate) fn call_jit(&mut self, fun: &BytecodeFunction, args: &[Value]) -> Option<Value> {
+        let threshold = self.config.jit_threshold;
+        if threshold == 0 {
+            return None;
+        }
+        let code = self.jit.native_code(fun, threshold)?;
+        let args: SmallVec<[u64; 8]> = args.iter().map(|v| v.to_bits()).collect();
+        let mut out = Value::UNIT.to_bits();
+        // SAFETY: 参数个数与编译时相同；机器码只读参数与两个标志，只写 out
+        let status = unsafe { code(args.as_ptr(), &mut out, self.interrupt.flag(), self.signals.pending_flags()) };
+        let bailed = status == codegen::BAIL;
+        self.jit.record(fun, bailed);
+        (status == codegen::RETURNED).then(|| Value::from_bits(out))
+    }
+
+    /// Activity of the JIT tier since the runtime was created or reset.
+    pub fn jit_stats(&self) -> JitStats {
+        self.jit.stats
+    }
+}
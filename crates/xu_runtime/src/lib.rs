@@ -7,6 +7,9 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::manual_range_contains)]
 
+// `xu_builtin!` expansions name the crate by path, here too.
+extern crate self as xu_runtime;
+
 // Reorganized module structure
 pub mod core;
 pub mod vm;
@@ -19,34 +22,69 @@ mod util;
 mod runtime;
 mod builtins;
 pub mod builtins_registry;
+pub mod native;
+#[cfg(feature = "plugins")]
+pub mod plugin;
 mod methods;
+#[cfg(feature = "jit")]
+mod jit;
 
 // Re-exports from core/
 pub use core::heap;
+pub use core::heap::PoolLimits;
 pub use core::text;
 pub use core::text::Text;
 pub use core::value::Value;
 pub use core::value::ValueExt;
 pub use core::env::{Env, Scope};
+pub use core::dict_hash::DictHashing;
 
 // Re-exports from vm/
 pub use vm::VM;
 
 // Re-exports from modules/
-pub use modules::{ImportStamp, ModuleLoader, StdModuleLoader};
+pub use modules::{
+    ImportStamp, ModuleLoader, SnapshotReport, StdModuleLoader, embedded_std_modules,
+};
 
 // Re-exports from util/
 pub use util::Appendable;
-pub use util::{Capabilities, Clock, FileStat, FileSystem, RngAlgorithm};
+pub use util::{FloatFormat, format_f64_with, write_f64};
+pub use util::PrettyOptions;
+pub use util::{
+    CapabilityAuditEntry, CapabilityPolicy, Capabilities, Clock, ClockSource, DenyAllPolicy,
+    DirEntry, EnvVars, FileStat, FileSystem, HostSignals, OsSignals, RngAlgorithm, SignalSource, StdTerminal, Terminal, Trace, TraceEvent,
+};
 
 // Re-exports from other modules
-pub use builtins_registry::{BuiltinProvider, BuiltinRegistry, StdBuiltinProvider};
+pub use builtins_registry::{
+    BuiltinProvider, BuiltinRegistry, CryptoBuiltinProvider, CsvBuiltinProvider, FfiBuiltinProvider, FsBuiltinProvider,
+    RandomBuiltinProvider, StdBuiltinProvider, TemplateBuiltinProvider, TimeBuiltinProvider, WorkerBuiltinProvider,
+};
 pub use xu_ir::{Bytecode, Op};
+pub use xu_macros::xu_builtin;
+pub use builtins::{MathBuiltinProvider, ReflectBuiltinProvider, TermBuiltinProvider};
 
 // Runtime structs and enums
+pub use runtime::{BenchConfig, BenchResult};
+pub use runtime::{Divergence, EngineComparison, EngineRun, compare_engines};
 pub use runtime::ExecResult;
+pub use runtime::GcStats;
+pub use runtime::{HostValue, INT_RANGE};
+pub use runtime::HeapCompaction;
+pub use runtime::HeapSnapshot;
+pub use runtime::{IcKind, IcKindSummary, IcSite, IcStats};
+pub use runtime::InterruptHandle;
+#[cfg(feature = "jit")]
+pub use jit::JitStats;
+pub use runtime::{Signal, SignalHandle};
+pub use runtime::KindSummary;
 pub use runtime::Runtime;
 pub use runtime::ICSlot;
 pub use runtime::MethodICSlot;
+pub use runtime::OutputOverflow;
+pub use runtime::{OutputSink, OutputStream, StdStreams};
+pub use runtime::RetainedObject;
 pub use runtime::RuntimeConfig;
+pub use runtime::TraceFrame;
 pub use runtime::Flow;
@@ -0,0 +1,68 @@
+use crate::Value;
+use crate::builtins::worker::Transfer;
+
+use super::common::*;
+use super::{MethodKind, Runtime};
+
+pub(super) fn dispatch(
+    rt: &mut Runtime,
+    recv: Value,
+    kind: MethodKind,
+    args: &[Value],
+    method: &str,
+) -> Result<Value, String> {
+    let ch = match rt.heap.get(recv.as_obj_id()) {
+        crate::core::heap::ManagedObject::Channel(ch) => ch.clone(),
+        _ => return Err(err(rt, xu_syntax::DiagnosticKind::Raw("Not a channel".into()))),
+    };
+    match kind {
+        MethodKind::ChanSend => {
+            validate_arity(rt, method, args.len(), 1, 1)?;
+            let msg = Transfer::from_value(rt, &args[0]).map_err(|e| err(rt, xu_syntax::DiagnosticKind::Raw(e)))?;
+            if ch.send(msg).is_err() {
+                return Err(err(rt, xu_syntax::DiagnosticKind::Raw("send on a closed channel".into())));
+            }
+            Ok(Value::UNIT)
+        }
+        MethodKind::ChanRecv => {
+            validate_arity(rt, method, args.len(), 0, 0)?;
+            // 只有本运行时持有该通道时（堆里一份、这里一份），没有别的线程能发送，
+            // 在空通道上等待会永远阻塞
+            if !ch.is_ready() && std::sync::Arc::strong_count(&ch) <= 2 {
+                return Err(err(
+                    rt,
+                    xu_syntax::DiagnosticKind::Raw("recv on an empty channel no other thread can send to".into()),
+                ));
+            }
+            let msg = ch.recv();
+            option_of(rt, msg)
+        }
+        MethodKind::ChanTryRecv => {
+            validate_arity(rt, method, args.len(), 0, 0)?;
+            let msg = ch.try_recv();
+            option_of(rt, msg)
+        }
+        MethodKind::Close => {
+            validate_arity(rt, method, args.len(), 0, 0)?;
+            ch.close();
+            Ok(Value::UNIT)
+        }
+        _ => Err(err(
+            rt,
+            xu_syntax::DiagnosticKind::UnsupportedMethod {
+                method: method.to_string(),
+                ty: "channel".to_string(),
+            },
+        )),
+    }
+}
+
+fn option_of(rt: &mut Runtime, msg: Option<Transfer>) -> Result<Value, String> {
+    match msg {
+        Some(m) => {
+            let v = m.into_value(rt).map_err(|e| err(rt, xu_syntax::DiagnosticKind::Raw(e)))?;
+            Ok(rt.option_some(v))
+        }
+        None => Ok(rt.option_none()),
+    }
+}
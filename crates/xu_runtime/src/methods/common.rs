@@ -22,7 +22,7 @@ pub fn err(rt: &Runtime, kind: xu_syntax::DiagnosticKind) -> String {
 }
 
 /// 验证值是否为列表类型
-pub fn expect_list(rt: &Runtime, value: Value) -> Result<&Vec<Value>, String> {
+pub fn expect_list(rt: &Runtime, value: Value) -> Result<&[Value], String> {
     let id = value.as_obj_id();
     let obj = rt.heap.get(id);
     if let crate::core::heap::ManagedObject::List(list) = obj {
@@ -32,7 +32,7 @@ pub fn expect_list(rt: &Runtime, value: Value) -> Result<&Vec<Value>, String> {
     }
 }
 
-/// 验证值是否为可变列表类型
+/// 验证值是否为可变列表类型；与其它列表共享的元素先拷出
 pub fn expect_list_mut(rt: &mut Runtime, value: Value) -> Result<&mut Vec<Value>, String> {
     // 先使用不可变引用检查类型
     {
@@ -48,7 +48,7 @@ pub fn expect_list_mut(rt: &mut Runtime, value: Value) -> Result<&mut Vec<Value>
     let obj = rt.heap_get_mut(id);
     // 由于前面已经检查过类型，这里可以安全地使用unwrap
     match obj {
-        crate::core::heap::ManagedObject::List(list) => Ok(list),
+        crate::core::heap::ManagedObject::List(list) => Ok(list.make_mut()),
         _ => unreachable!(),
     }
 }
@@ -122,22 +122,13 @@ pub fn get_str_from_value(rt: &Runtime, value: &Value) -> Result<String, String>
 
 /// 从Value中获取字典键的辅助函数
 pub fn get_dict_key_from_value(rt: &mut Runtime, value: &Value) -> Result<crate::core::value::DictKey, String> {
-    if value.get_tag() == crate::core::value::TAG_STR {
-        let key_id = value.as_obj_id();
-        let hash = if let crate::core::heap::ManagedObject::Str(s) = rt.heap.get(key_id) {
-            crate::core::value::DictKey::hash_str(s.as_str())
-        } else {
-            return Err("Not a string".into());
-        };
-        // Use ObjectId directly - no string copy!
-        Ok(crate::core::value::DictKey::from_str_obj(key_id, hash))
-    } else if value.is_int() {
-        Ok(crate::core::value::DictKey::Int(value.as_i64()))
-    } else {
-        Err(rt.error(xu_syntax::DiagnosticKind::TypeMismatch {
-            expected: "text or int".to_string(),
+    // Strings use the ObjectId directly - no string copy!
+    match crate::core::value::DictKey::from_value(*value, &mut rt.heap) {
+        Some(key) => Ok(key),
+        None => Err(rt.error(xu_syntax::DiagnosticKind::TypeMismatch {
+            expected: "hashable key (text, int, float, bool, tuple or struct)".to_string(),
             actual: value.type_name().to_string(),
-        }))
+        })),
     }
 }
 
@@ -153,9 +144,23 @@ pub fn create_str_value(rt: &mut Runtime, s: &str) -> Value {
     }
 }
 
+/// 创建子串Value的辅助函数；共享父串缓冲区的视图直接入堆
+pub fn create_text_value(rt: &mut Runtime, text: crate::Text) -> Value {
+    if text.is_slice() {
+        Value::str(rt.alloc(crate::core::heap::ManagedObject::Str(text)))
+    } else {
+        create_str_value(rt, text.as_str())
+    }
+}
+
 /// 创建列表Value的辅助函数
 pub fn create_list_value(rt: &mut Runtime, items: Vec<Value>) -> Value {
-    Value::list(rt.alloc(crate::core::heap::ManagedObject::List(items)))
+    Value::list(rt.alloc(crate::core::heap::ManagedObject::List(items.into())))
+}
+
+/// 创建元组Value的辅助函数
+pub fn create_tuple_value(rt: &mut Runtime, items: Vec<Value>) -> Value {
+    Value::tuple(rt.alloc(crate::core::heap::ManagedObject::Tuple(items)))
 }
 
 /// 验证参数是否为字符串类型
@@ -188,3 +193,16 @@ pub fn safe_get_from_list(_rt: &Runtime, list: &[Value], index: i64) -> Option<V
         Some(list[index as usize])
     }
 }
+
+/// 解析编码参数，缺省为 utf-8
+pub fn encoding_arg(
+    rt: &Runtime, arg: Option<&Value>,
+) -> Result<crate::builtins::encoding::Encoding, String> {
+    let Some(v) = arg else {
+        return Ok(crate::builtins::encoding::Encoding::Utf8);
+    };
+    validate_str_param(rt, v, "encoding")?;
+    let name = get_str_from_value(rt, v)?;
+    crate::builtins::encoding::Encoding::parse(&name)
+        .map_err(|e| err(rt, xu_syntax::DiagnosticKind::Raw(e)))
+}
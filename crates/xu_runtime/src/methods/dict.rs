@@ -1,10 +1,8 @@
 use std::rc::Rc;
 
-use indexmap::map::RawEntryApiV1;
-use indexmap::map::raw_entry_v1::RawEntryMut;
-
 use crate::Value;
 use crate::util::to_i64;
+use crate::core::dict_map::DictEntry;
 use crate::core::value::DictKey;
 
 use super::super::runtime::{DictCacheIntLast, DictCacheLast};
@@ -15,6 +13,8 @@ use super::common::*;
 enum TempKey {
     Str(Rc<String>),
     Int(i64),
+    /// 布尔、浮点与元组/结构体键
+    Other(DictKey),
 }
 
 impl TempKey {
@@ -22,6 +22,18 @@ impl TempKey {
         match self {
             TempKey::Str(s) => create_str_value(rt, &s),
             TempKey::Int(i) => Value::from_i64(i),
+            TempKey::Other(k) => k.to_script_value(&mut rt.heap),
+        }
+    }
+
+    /// keys_sorted 的分组顺序：布尔、整数、浮点、字符串、复合键
+    fn rank(&self) -> u8 {
+        match self {
+            TempKey::Other(DictKey::Bool(_)) => 0,
+            TempKey::Int(_) => 1,
+            TempKey::Other(DictKey::Float(_)) => 2,
+            TempKey::Str(_) => 3,
+            TempKey::Other(_) => 4,
         }
     }
 }
@@ -30,13 +42,21 @@ pub(super) fn dispatch(
     rt: &mut Runtime, recv: Value, kind: MethodKind, args: &[Value], method: &str,
 ) -> Result<Value, String> {
     match kind {
-        MethodKind::DictMerge => {
+        MethodKind::DictMerge | MethodKind::DictUpdate => {
             validate_arity(rt, method, args.len(), 1, 1)?;
             validate_dict_param(rt, &args[0], "other")?;
 
-            let entries: Vec<_> = {
+            // 按 other 的迭代顺序（先 elements 后 map）合并
+            let entries: Vec<(DictKey, Value)> = {
                 let other = expect_dict(rt, args[0])?;
-                other.map.iter().map(|(k, v)| (*k, *v)).collect()
+                let mut entries: Vec<_> = other.elements().map_or(Vec::new(), |e| {
+                    e.iter().enumerate()
+                        .filter(|(_, v)| v.get_tag() != crate::core::value::TAG_UNIT)
+                        .map(|(i, v)| (DictKey::Int(i as i64), *v))
+                        .collect()
+                });
+                entries.extend(other.map.iter().map(|(k, v)| (*k, *v)));
+                entries
             };
 
             let id = recv.as_obj_id().0;
@@ -45,11 +65,7 @@ pub(super) fn dispatch(
             me.map.reserve(entries.len());
 
             for (k, v) in entries {
-                let h = me.map.hasher().hash_one(k);
-                match me.map.raw_entry_mut_v1().from_hash(h, |kk| kk == &k) {
-                    RawEntryMut::Occupied(mut o) => { *o.get_mut() = v; }
-                    RawEntryMut::Vacant(vac) => { vac.insert(k, v); changed = true; }
-                }
+                changed |= me.insert_entry(k, v);
             }
 
             if changed {
@@ -69,12 +85,13 @@ pub(super) fn dispatch(
                 if i >= 0 && i < crate::core::value::ELEMENTS_MAX {
                     let idx = i as usize;
                     let me = expect_dict_mut(rt, recv)?;
-                    let was_unit = me.set_element(idx, value);
-                    if was_unit {
-                        me.ver += 1;
-                        rt.caches.dict_version_last = Some((id, me.ver));
+                    if let Some(was_unit) = me.try_set_element(idx, value) {
+                        if was_unit {
+                            me.ver += 1;
+                            rt.caches.dict_version_last = Some((id, me.ver));
+                        }
+                        return Ok(Value::UNIT);
                     }
-                    return Ok(Value::UNIT);
                 }
             }
 
@@ -83,7 +100,7 @@ pub(super) fn dispatch(
                 let key_id = args[0].as_obj_id();
                 let dict_key_hash = {
                     let key_str = expect_str(rt, args[0])?;
-                    DictKey::hash_str(key_str.as_str())
+                    DictKey::hash_str(key_str.as_str(), &rt.heap)
                 };
 
                 // Compute HashMap hash from DictKey hash
@@ -94,15 +111,15 @@ pub(super) fn dispatch(
 
                 let me = expect_dict_mut(rt, recv)?;
 
-                match me.map.raw_entry_mut_v1().from_hash(hash, |k| {
+                match me.map.entry_by_hash(hash, |k| {
                     if let DictKey::StrRef { hash: h, .. } = k {
                         *h == dict_key_hash
                     } else {
                         false
                     }
                 }) {
-                    RawEntryMut::Occupied(mut o) => { *o.get_mut() = value; }
-                    RawEntryMut::Vacant(vac) => {
+                    DictEntry::Occupied(slot) => { *slot = value; }
+                    DictEntry::Vacant(vac) => {
                         // Use ObjectId directly - no string copy!
                         vac.insert(DictKey::from_str_obj(key_id, dict_key_hash), value);
                         me.ver += 1;
@@ -117,9 +134,9 @@ pub(super) fn dispatch(
             let me = expect_dict_mut(rt, recv)?;
             let h = me.map.hasher().hash_one(key);
 
-            match me.map.raw_entry_mut_v1().from_hash(h, |kk| kk == &key) {
-                RawEntryMut::Occupied(mut o) => { *o.get_mut() = value; }
-                RawEntryMut::Vacant(vac) => {
+            match me.map.entry_by_hash(h, |kk| kk == &key) {
+                DictEntry::Occupied(slot) => { *slot = value; }
+                DictEntry::Vacant(vac) => {
                     vac.insert(key, value);
                     me.ver += 1;
                     rt.caches.dict_version_last = Some((id, me.ver));
@@ -137,12 +154,13 @@ pub(super) fn dispatch(
             if i >= 0 && i < crate::core::value::ELEMENTS_MAX {
                 let idx = i as usize;
                 let me = expect_dict_mut(rt, recv)?;
-                let was_unit = me.set_element(idx, value);
-                if was_unit {
-                    me.ver += 1;
-                    rt.caches.dict_version_last = Some((id, me.ver));
+                if let Some(was_unit) = me.try_set_element(idx, value) {
+                    if was_unit {
+                        me.ver += 1;
+                        rt.caches.dict_version_last = Some((id, me.ver));
+                    }
+                    return Ok(Value::UNIT);
                 }
-                return Ok(Value::UNIT);
             }
 
             // 大整数键
@@ -150,9 +168,9 @@ pub(super) fn dispatch(
             let h = Runtime::hash_dict_key_int(me.map.hasher(), i);
             let key = DictKey::Int(i);
 
-            match me.map.raw_entry_mut_v1().from_hash(h, |kk| kk == &key) {
-                RawEntryMut::Occupied(mut o) => { *o.get_mut() = value; }
-                RawEntryMut::Vacant(vac) => {
+            match me.map.entry_by_hash(h, |kk| kk == &key) {
+                DictEntry::Occupied(slot) => { *slot = value; }
+                DictEntry::Vacant(vac) => {
                     vac.insert(key, value);
                     me.ver += 1;
                     rt.caches.dict_version_last = Some((id, me.ver));
@@ -180,7 +198,7 @@ pub(super) fn dispatch(
             let (v, cur_ver) = {
                 let me = expect_dict(rt, recv)?;
                 let hash = Runtime::hash_bytes(me.map.hasher(), key_str.as_bytes());
-                (Runtime::dict_get_by_str_with_hash(me, key_str, hash), me.ver)
+                (Runtime::dict_get_by_str_with_hash(&rt.heap, me, key_str, hash), me.ver)
             };
 
             rt.caches.dict_version_last = Some((id, cur_ver));
@@ -235,7 +253,7 @@ pub(super) fn dispatch(
             if args[0].get_tag() == crate::core::value::TAG_STR {
                 let (key_str_ptr, key_str_len, dict_key_hash) = {
                     let key_str = expect_str(rt, args[0])?;
-                    (key_str.as_str().as_ptr(), key_str.len(), DictKey::hash_str(key_str.as_str()))
+                    (key_str.as_str().as_ptr(), key_str.len(), DictKey::hash_str(key_str.as_str(), &rt.heap))
                 };
                 let me = expect_dict(rt, recv)?;
 
@@ -256,7 +274,7 @@ pub(super) fn dispatch(
                 h.write_u8(0);
                 h.write_u64(dict_key_hash);
                 let hash = h.finish();
-                let found = me.map.raw_entry_v1().from_hash(hash, |k| {
+                let found = me.map.get_by_hash(hash, |k| {
                     if let DictKey::StrRef { hash: kh, .. } = k {
                         *kh == dict_key_hash
                     } else {
@@ -264,29 +282,34 @@ pub(super) fn dispatch(
                     }
                 }).is_some();
                 Ok(Value::from_bool(found))
-            } else if args[0].is_int() {
-                let me = expect_dict(rt, recv)?;
-                Ok(Value::from_bool(me.map.contains_key(&DictKey::Int(args[0].as_i64()))))
             } else {
-                Err(err(rt, xu_syntax::DiagnosticKind::TypeMismatch {
-                    expected: "text or int".to_string(),
-                    actual: args[0].type_name().to_string(),
-                }))
+                Ok(Value::from_bool(lookup_entry(rt, recv, args[0])?.is_some()))
             }
         }
         MethodKind::Remove => {
             validate_arity(rt, method, args.len(), 1, 1)?;
-            let key = get_dict_key_from_value(rt, &args[0])?;
-            let id = recv.as_obj_id().0;
-            let me = expect_dict_mut(rt, recv)?;
-
-            if me.map.shift_remove(&key).is_some() {
-                me.ver += 1;
-                rt.caches.dict_version_last = Some((id, me.ver));
-                Ok(Value::from_bool(true))
-            } else {
-                Ok(Value::from_bool(false))
+            Ok(Value::from_bool(take_entry(rt, recv, args[0])?.is_some()))
+        }
+        MethodKind::Pop => {
+            validate_arity(rt, method, args.len(), 1, 2)?;
+            match take_entry(rt, recv, args[0])? {
+                Some(v) => Ok(v),
+                None if args.len() == 2 => Ok(args[1]),
+                None => {
+                    let key = key_text(rt, args[0])?;
+                    Err(err(rt, xu_syntax::DiagnosticKind::KeyNotFound(key)))
+                }
+            }
+        }
+        MethodKind::DictEntryOrInsert => {
+            validate_arity(rt, method, args.len(), 2, 2)?;
+            if let Some(v) = lookup_entry(rt, recv, args[0])? {
+                return Ok(v);
             }
+            // 键不存在时才调用 default_fn，结果写回字典
+            let v = rt.call_function(args[1], &[])?;
+            dispatch(rt, recv, MethodKind::Insert, &[args[0], v], "insert")?;
+            Ok(v)
         }
         MethodKind::Clear => {
             validate_arity(rt, method, args.len(), 0, 0)?;
@@ -303,6 +326,22 @@ pub(super) fn dispatch(
             let result: Vec<_> = keys.into_iter().map(|k| k.into_value(rt)).collect();
             Ok(create_list_value(rt, result))
         }
+        MethodKind::DictKeysSorted => {
+            validate_arity(rt, method, args.len(), 0, 0)?;
+            let mut keys = collect_dict_keys(rt, recv)?;
+            // 按类型分组（见 TempKey::rank），组内按值排序；复合键保持插入顺序
+            keys.sort_by(|a, b| match (a, b) {
+                (TempKey::Int(x), TempKey::Int(y)) => x.cmp(y),
+                (TempKey::Str(x), TempKey::Str(y)) => x.cmp(y),
+                (TempKey::Other(DictKey::Bool(x)), TempKey::Other(DictKey::Bool(y))) => x.cmp(y),
+                (TempKey::Other(DictKey::Float(x)), TempKey::Other(DictKey::Float(y))) => {
+                    f64::from_bits(*x).total_cmp(&f64::from_bits(*y))
+                }
+                _ => a.rank().cmp(&b.rank()),
+            });
+            let result: Vec<_> = keys.into_iter().map(|k| k.into_value(rt)).collect();
+            Ok(create_list_value(rt, result))
+        }
         MethodKind::DictValues => {
             validate_arity(rt, method, args.len(), 0, 0)?;
             let me = expect_dict(rt, recv)?;
@@ -320,16 +359,14 @@ pub(super) fn dispatch(
         }
         MethodKind::GetOrDefault => {
             validate_arity(rt, method, args.len(), 2, 2)?;
-            let key = get_dict_key_from_value(rt, &args[0])?;
-            let me = expect_dict(rt, recv)?;
-            Ok(me.map.get(&key).copied().unwrap_or(args[1]))
+            Ok(lookup_entry(rt, recv, args[0])?.unwrap_or(args[1]))
         }
         MethodKind::DictItems => {
             validate_arity(rt, method, args.len(), 0, 0)?;
             let items_data = collect_dict_items(rt, recv)?;
             let items: Vec<_> = items_data.into_iter().map(|(k, v)| {
                 let key_val = k.into_value(rt);
-                create_list_value(rt, vec![key_val, v])
+                create_tuple_value(rt, vec![key_val, v])
             }).collect();
             Ok(create_list_value(rt, items))
         }
@@ -342,6 +379,54 @@ pub(super) fn dispatch(
     }
 }
 
+/// 查找键对应的值，小整数键优先查 elements
+fn lookup_entry(rt: &mut Runtime, recv: Value, key: Value) -> Result<Option<Value>, String> {
+    let dict_key = get_dict_key_from_value(rt, &key)?;
+    let me = expect_dict(rt, recv)?;
+    if let DictKey::Int(i) = dict_key {
+        if i >= 0 && i < crate::core::value::ELEMENTS_MAX {
+            if let Some(v) = me.get_element(i as usize) {
+                return Ok(Some(v));
+            }
+        }
+    }
+    Ok(me.map.get(&dict_key).copied())
+}
+
+/// 移除键对应的条目并返回被移除的值
+fn take_entry(rt: &mut Runtime, recv: Value, key: Value) -> Result<Option<Value>, String> {
+    let dict_key = get_dict_key_from_value(rt, &key)?;
+    let id = recv.as_obj_id().0;
+    let me = expect_dict_mut(rt, recv)?;
+
+    let mut removed = None;
+    if let DictKey::Int(i) = dict_key {
+        if i >= 0 && i < crate::core::value::ELEMENTS_MAX {
+            removed = me.get_element(i as usize);
+            if removed.is_some() {
+                me.set_element(i as usize, Value::UNIT);
+            }
+        }
+    }
+    if removed.is_none() {
+        removed = me.map.shift_remove(&dict_key);
+    }
+
+    if removed.is_some() {
+        me.ver += 1;
+        rt.caches.dict_version_last = Some((id, me.ver));
+    }
+    Ok(removed)
+}
+
+/// 键的文本形式，用于错误信息
+fn key_text(rt: &Runtime, key: Value) -> Result<String, String> {
+    if key.get_tag() == crate::core::value::TAG_STR {
+        return Ok(expect_str(rt, key)?.as_str().to_string());
+    }
+    Ok(crate::util::value_to_string(&key, &rt.heap))
+}
+
 fn collect_dict_keys(rt: &Runtime, recv: Value) -> Result<Vec<TempKey>, String> {
     let me = expect_dict(rt, recv)?;
     let elements_len = me.elements().map_or(0, |e| e.len());
@@ -364,6 +449,7 @@ fn collect_dict_keys(rt: &Runtime, recv: Value) -> Result<Vec<TempKey>, String>
                 }
             }
             DictKey::Int(i) => keys.push(TempKey::Int(*i)),
+            other => keys.push(TempKey::Other(*other)),
         }
     }
     Ok(keys)
@@ -391,6 +477,7 @@ fn collect_dict_items(rt: &Runtime, recv: Value) -> Result<Vec<(TempKey, Value)>
                 }
             }
             DictKey::Int(i) => items.push((TempKey::Int(*i), *v)),
+            other => items.push((TempKey::Other(*other), *v)),
         }
     }
     Ok(items)
@@ -87,6 +87,28 @@ pub(super) fn dispatch(
             validate_arity(rt, method, args.len(), 0, 0)?;
             Ok(Value::from_bool(is_success))
         }
+        MethodKind::IsOk | MethodKind::IsErr => {
+            if !is_result {
+                return Err(unsupported_method(rt, method, &ty));
+            }
+            validate_arity(rt, method, args.len(), 0, 0)?;
+            Ok(Value::from_bool(if kind == MethodKind::IsOk { is_success } else { is_fail }))
+        }
+        MethodKind::Expect => {
+            validate_arity(rt, method, args.len(), 1, 1)?;
+            if is_success {
+                return get_payload(rt, &payload, ty.as_str(), var);
+            }
+            if !is_fail {
+                return Err(unsupported_method(rt, method, &ty));
+            }
+            let msg = crate::util::value_to_string(&args[0], &rt.heap);
+            let msg = match payload.first() {
+                Some(e) => format!("{msg}: {}", crate::util::value_to_string(e, &rt.heap)),
+                None => msg,
+            };
+            Err(rt.error(xu_syntax::DiagnosticKind::Raw(msg)))
+        }
         MethodKind::Or => {
             validate_arity(rt, method, args.len(), 1, 1)?;
             if is_success {
@@ -2,16 +2,49 @@ use crate::Value;
 
 use super::{MethodKind, Runtime};
 
+/// Replaces `\r\n` and lone `\r` with `\n`.
+fn normalize_newlines(s: &str) -> String {
+    if !s.contains('\r') {
+        return s.to_string();
+    }
+    s.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Line terminator named by a `newline` argument: `"lf"`, `"crlf"`, `"cr"`
+/// or `"native"` (the platform's).
+fn newline_for(name: &str) -> Option<&'static str> {
+    match name {
+        "lf" => Some("\n"),
+        "crlf" => Some("\r\n"),
+        "cr" => Some("\r"),
+        "native" => Some(if cfg!(windows) { "\r\n" } else { "\n" }),
+        _ => None,
+    }
+}
+
+fn text_arg(rt: &Runtime, args: &[Value], i: usize, what: &str) -> Result<Option<String>, String> {
+    let Some(v) = args.get(i) else {
+        return Ok(None);
+    };
+    if v.get_tag() == crate::core::value::TAG_STR {
+        if let crate::core::heap::ManagedObject::Str(s) = rt.heap.get(v.as_obj_id()) {
+            return Ok(Some(s.to_string()));
+        }
+    }
+    Err(rt.error(xu_syntax::DiagnosticKind::Raw(format!("{what} expects text"))))
+}
+
 pub(super) fn dispatch(
     rt: &mut Runtime,
     recv: Value,
     kind: MethodKind,
-    _args: &[Value],
+    args: &[Value],
     method: &str,
 ) -> Result<Value, String> {
     let id = recv.as_obj_id();
     match kind {
         MethodKind::FileRead => {
+            super::validate_arity(rt, method, args.len(), 0, 1)?;
             let (open, path) = if let crate::core::heap::ManagedObject::File(h) = rt.heap.get(id) {
                 (h.open, h.path.clone())
             } else {
@@ -20,12 +53,71 @@ pub(super) fn dispatch(
             if !open {
                 return Err(rt.error(xu_syntax::DiagnosticKind::FileClosed));
             }
-            let content = rt.fs_read_to_string(&path)?;
+            // `read()` keeps line endings as stored; `read("lf")` normalizes them.
+            let normalize = match text_arg(rt, args, 0, "read")?.as_deref() {
+                None | Some("keep") => false,
+                Some("lf") => true,
+                Some(other) => {
+                    return Err(rt.error(xu_syntax::DiagnosticKind::Raw(format!(
+                        "read: unknown newline mode \"{other}\" (expected \"keep\" or \"lf\")"
+                    ))));
+                }
+            };
+            let mut content = rt.fs_read_to_string(&path)?;
+            if normalize {
+                content = normalize_newlines(&content);
+            }
             Ok(Value::str(rt.alloc(crate::core::heap::ManagedObject::Str(
                 content.trim_end_matches(['\n', '\r']).to_string().into(),
             ))))
         }
-        MethodKind::FileClose => {
+        MethodKind::FileWrite => {
+            super::validate_arity(rt, method, args.len(), 1, 2)?;
+            let (open, writable, path) =
+                if let crate::core::heap::ManagedObject::File(h) = rt.heap.get(id) {
+                    (h.open, h.writable, h.path.clone())
+                } else {
+                    return Err(rt.error(xu_syntax::DiagnosticKind::Raw("Not a file".into())));
+                };
+            if !open {
+                return Err(rt.error(xu_syntax::DiagnosticKind::FileClosed));
+            }
+            if !writable {
+                return Err(rt.error(xu_syntax::DiagnosticKind::Raw(
+                    "File was not opened for writing; use open(path, \"w\")".into(),
+                )));
+            }
+            let text = match args.first() {
+                Some(v) if v.get_tag() == crate::core::value::TAG_STR => {
+                    text_arg(rt, args, 0, "write")?.unwrap_or_default()
+                }
+                Some(v) => crate::util::value_to_string(v, &rt.heap),
+                None => String::new(),
+            };
+            // Text is written with `\n` turned into the requested ending
+            // (default `"lf"`), whatever endings it already had.
+            let newline_name = text_arg(rt, args, 1, "write")?.unwrap_or_else(|| "lf".into());
+            let Some(newline) = newline_for(&newline_name) else {
+                return Err(rt.error(xu_syntax::DiagnosticKind::Raw(format!(
+                    "write: unknown newline \"{newline_name}\" (expected \"lf\", \"crlf\", \"cr\" or \"native\")"
+                ))));
+            };
+            let mut text = normalize_newlines(&text);
+            if newline != "\n" {
+                text = text.replace('\n', newline);
+            }
+            let written = matches!(rt.heap.get(id), crate::core::heap::ManagedObject::File(h) if h.written);
+            if written {
+                rt.fs_append(&path, &text)?;
+            } else {
+                rt.fs_write(&path, &text)?;
+                if let crate::core::heap::ManagedObject::File(h) = rt.heap_get_mut(id) {
+                    h.written = true;
+                }
+            }
+            Ok(Value::UNIT)
+        }
+        MethodKind::Close => {
             if let crate::core::heap::ManagedObject::File(h) = rt.heap_get_mut(id) {
                 h.open = false;
             }
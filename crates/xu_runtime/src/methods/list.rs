@@ -53,7 +53,7 @@ pub(super) fn dispatch(
             }
             Ok(Value::from_bool(found))
         }
-        MethodKind::ListPop => {
+        MethodKind::Pop => {
             validate_arity(rt, method, args.len(), 0, 0)?;
             
             let list = expect_list_mut(rt, recv)?;
@@ -150,6 +150,121 @@ pub(super) fn dispatch(
 
             Ok(create_list_value(rt, out))
         }
+        MethodKind::Each => {
+            validate_arity(rt, method, args.len(), 1, 1)?;
+
+            let f = args[0];
+            let items = expect_list(rt, recv)?.to_vec();
+            for item in items {
+                rt.call_function(f, &[item])?;
+            }
+            Ok(Value::UNIT)
+        }
+        MethodKind::ListAny | MethodKind::ListAll => {
+            // any 遇到第一个 true 即返回，all 遇到第一个 false 即返回
+            validate_arity(rt, method, args.len(), 1, 1)?;
+
+            let f = args[0];
+            let want = kind == MethodKind::ListAny;
+            let items = expect_list(rt, recv)?.to_vec();
+            for item in items {
+                if predicate(rt, f, item)? == want {
+                    return Ok(Value::from_bool(want));
+                }
+            }
+            Ok(Value::from_bool(!want))
+        }
+        MethodKind::ListFlatMap => {
+            validate_arity(rt, method, args.len(), 1, 1)?;
+
+            let f = args[0];
+            let items = expect_list(rt, recv)?.to_vec();
+            // 回调可能触发 GC，已生成的结果需要作为临时根
+            let roots_base = rt.gc_temp_roots.len();
+            let mut out: Vec<Value> = Vec::with_capacity(items.len());
+            for item in items {
+                let mapped = match rt.call_function(f, &[item]) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        rt.gc_temp_roots.truncate(roots_base);
+                        return Err(e);
+                    }
+                };
+                let start = out.len();
+                if mapped.get_tag() == crate::core::value::TAG_LIST {
+                    out.extend_from_slice(expect_list(rt, mapped)?);
+                } else {
+                    out.push(mapped);
+                }
+                rt.gc_temp_roots.extend_from_slice(&out[start..]);
+            }
+            rt.gc_temp_roots.truncate(roots_base);
+            Ok(create_list_value(rt, out))
+        }
+        MethodKind::ListZip => {
+            // zip(other) - 按位置配对，长度取较短者
+            validate_arity(rt, method, args.len(), 1, 1)?;
+
+            let other = if args[0].get_tag() == crate::core::value::TAG_LIST {
+                expect_list(rt, args[0])?.to_vec()
+            } else {
+                return Err(err(rt, xu_syntax::DiagnosticKind::TypeMismatch {
+                    expected: "list".to_string(),
+                    actual: args[0].type_name().to_string(),
+                }));
+            };
+            let items = expect_list(rt, recv)?.to_vec();
+            let out = items
+                .into_iter()
+                .zip(other)
+                .map(|(a, b)| create_tuple_value(rt, vec![a, b]))
+                .collect();
+            Ok(create_list_value(rt, out))
+        }
+        MethodKind::ListEnumerate => {
+            // enumerate() - [(index, item), ...]
+            validate_arity(rt, method, args.len(), 0, 0)?;
+
+            let items = expect_list(rt, recv)?.to_vec();
+            let out = items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| create_tuple_value(rt, vec![Value::from_i64(i as i64), item]))
+                .collect();
+            Ok(create_list_value(rt, out))
+        }
+        MethodKind::ListTake | MethodKind::ListDrop => {
+            // take(n) 取前 n 个，drop(n) 跳过前 n 个；n 为负时按 0 处理
+            validate_arity(rt, method, args.len(), 1, 1)?;
+
+            let n = to_i64(&args[0])?.max(0) as usize;
+            let len = expect_list(rt, recv)?.len();
+            let n = n.min(len);
+            let range = if kind == MethodKind::ListTake { 0..n } else { n..len };
+            // 长切片与原列表共享元素，任一方被修改时才拷贝
+            let out = match rt.heap_get_mut(recv.as_obj_id()) {
+                crate::core::heap::ManagedObject::List(items) => items.slice(range),
+                _ => unreachable!(),
+            };
+            Ok(Value::list(rt.alloc(crate::core::heap::ManagedObject::List(out))))
+        }
+        MethodKind::ListChunk => {
+            // chunk(n) - 按 n 个一组切分，最后一组可能不足 n 个
+            validate_arity(rt, method, args.len(), 1, 1)?;
+
+            let n = to_i64(&args[0])?;
+            if n <= 0 {
+                return Err(err(rt, xu_syntax::DiagnosticKind::Raw(
+                    "chunk size must be positive".into(),
+                )));
+            }
+            let items = expect_list(rt, recv)?.to_vec();
+            let out = items
+                .chunks(n as usize)
+                .map(|c| create_list_value(rt, c.to_vec()))
+                .collect();
+            Ok(create_list_value(rt, out))
+        }
         MethodKind::Insert => {
             validate_arity(rt, method, args.len(), 2, 2)?;
 
@@ -164,21 +279,64 @@ pub(super) fn dispatch(
             list.insert(index, value);
             Ok(Value::UNIT)
         }
-        MethodKind::ListSort => {
-            validate_arity(rt, method, args.len(), 0, 0)?;
-            
+        MethodKind::ListSort | MethodKind::ListSorted => {
+            // sort() / sort(cmp)：cmp(a, b) 返回 int（<0、0、>0）或 bool（a 是否排在 b 前）
+            validate_arity(rt, method, args.len(), 0, 1)?;
+
+            let items = expect_list(rt, recv)?.to_vec();
+            let sorted = match args.first() {
+                None => {
+                    let mut items = items;
+                    items.sort_by(|a, b| default_order(rt, a, b));
+                    items
+                }
+                Some(&f) => {
+                    let roots_base = rt.gc_temp_roots.len();
+                    rt.gc_temp_roots.extend_from_slice(&items);
+                    rt.gc_temp_roots.push(f);
+                    let sorted = merge_sort(rt, items, |rt, a, b| {
+                        let r = rt.call_function(f, &[b, a])?;
+                        comparator_result(rt, r)
+                    });
+                    rt.gc_temp_roots.truncate(roots_base);
+                    sorted?
+                }
+            };
+
+            if kind == MethodKind::ListSorted {
+                return Ok(create_list_value(rt, sorted));
+            }
             let list = expect_list_mut(rt, recv)?;
-            list.sort_by(|a, b| {
-                if a.is_int() && b.is_int() {
-                    a.as_i64().cmp(&b.as_i64())
-                } else if a.is_f64() && b.is_f64() {
-                    a.as_f64()
-                        .partial_cmp(&b.as_f64())
-                        .unwrap_or(std::cmp::Ordering::Equal)
-                } else {
-                    std::cmp::Ordering::Equal
+            *list = sorted;
+            Ok(Value::UNIT)
+        }
+        MethodKind::ListSortByKey => {
+            // sort_by_key(f)：每个元素只调用一次 f，按键的默认顺序稳定排序
+            validate_arity(rt, method, args.len(), 1, 1)?;
+
+            let f = args[0];
+            let items = expect_list(rt, recv)?.to_vec();
+            let roots_base = rt.gc_temp_roots.len();
+            rt.gc_temp_roots.extend_from_slice(&items);
+            rt.gc_temp_roots.push(f);
+            let mut keyed: Vec<(Value, Value)> = Vec::with_capacity(items.len());
+            for item in items {
+                match rt.call_function(f, &[item]) {
+                    Ok(key) => {
+                        rt.gc_temp_roots.push(key);
+                        keyed.push((key, item));
+                    }
+                    Err(e) => {
+                        rt.gc_temp_roots.truncate(roots_base);
+                        return Err(e);
+                    }
                 }
-            });
+            }
+            keyed.sort_by(|a, b| default_order(rt, &a.0, &b.0));
+            rt.gc_temp_roots.truncate(roots_base);
+
+            let list = expect_list_mut(rt, recv)?;
+            *list = keyed.into_iter().map(|(_, item)| item).collect();
             Ok(Value::UNIT)
         }
         MethodKind::ListReduce => {
@@ -283,9 +441,13 @@ pub(super) fn dispatch(
                 return Err("Expected list".into());
             };
 
-            // Pre-allocate result with exact capacity
-            let total_len = len * n;
-            let mut result = Vec::with_capacity(total_len);
+            let Some(total_len) = len.checked_mul(n) else {
+                return Err(err(rt, xu_syntax::DiagnosticKind::Raw(
+                    "repeated list is too large".into(),
+                )));
+            };
+            let mut result =
+                Vec::with_capacity(total_len.min(crate::core::value::LIST_RESERVE_MAX));
 
             // Copy elements n times
             for _ in 0..n {
@@ -296,9 +458,133 @@ pub(super) fn dispatch(
 
             Ok(create_list_value(rt, result))
         }
+        MethodKind::ListReserve => {
+            // list.reserve(n) - 预留至少 n 个追加槽位，不改变内容
+            validate_arity(rt, method, args.len(), 1, 1)?;
+            let n = to_i64(&args[0])?;
+            if n < 0 {
+                return Err(err(rt, xu_syntax::DiagnosticKind::Raw(
+                    "reserve count must not be negative".into(),
+                )));
+            }
+            let n = (n as usize).min(crate::core::value::LIST_RESERVE_MAX);
+            let list = expect_list_mut(rt, recv)?;
+            list.reserve(n);
+            Ok(Value::UNIT)
+        }
+        MethodKind::ToString => {
+            // bytes.to_string(encoding) - 把字节列表按编码解码为字符串
+            validate_arity(rt, method, args.len(), 0, 1)?;
+            let encoding = encoding_arg(rt, args.first())?;
+            let bytes = crate::builtins::encoding::bytes_arg(rt, &recv, "to_string")
+                .map_err(|e| err(rt, xu_syntax::DiagnosticKind::Raw(e)))?;
+            let s = encoding
+                .decode(&bytes)
+                .map_err(|e| err(rt, xu_syntax::DiagnosticKind::Raw(e)))?;
+            Ok(create_str_value(rt, &s))
+        }
         _ => Err(rt.error(xu_syntax::DiagnosticKind::UnknownListMethod(
             method.to_string(),
         ))),
 
     }
 }
+
+/// 调用谓词并要求返回 bool。
+fn predicate(rt: &mut Runtime, f: Value, item: Value) -> Result<bool, String> {
+    let r = rt.call_function(f, &[item])?;
+    if !r.is_bool() {
+        return Err(err(rt, xu_syntax::DiagnosticKind::InvalidConditionType(
+            r.type_name().to_string(),
+        )));
+    }
+    Ok(r.as_bool())
+}
+
+/// 默认顺序：数字按数值（int 与 float 可混合）、文本按字典序、bool 中 false 在前；
+/// 其余视为相等，保持原有相对次序。
+fn default_order(rt: &Runtime, a: &Value, b: &Value) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    if a.is_int() && b.is_int() {
+        return a.as_i64().cmp(&b.as_i64());
+    }
+    let num = |v: &Value| {
+        if v.is_int() {
+            Some(v.as_i64() as f64)
+        } else if v.is_f64() {
+            Some(v.as_f64())
+        } else {
+            None
+        }
+    };
+    if let (Some(x), Some(y)) = (num(a), num(b)) {
+        return x.partial_cmp(&y).unwrap_or(Ordering::Equal);
+    }
+    if a.is_bool() && b.is_bool() {
+        return a.as_bool().cmp(&b.as_bool());
+    }
+    if a.get_tag() == crate::core::value::TAG_STR && b.get_tag() == crate::core::value::TAG_STR {
+        if let (
+            crate::core::heap::ManagedObject::Str(x),
+            crate::core::heap::ManagedObject::Str(y),
+        ) = (rt.heap.get(a.as_obj_id()), rt.heap.get(b.as_obj_id()))
+        {
+            return x.as_str().cmp(y.as_str());
+        }
+    }
+    Ordering::Equal
+}
+
+/// 比较函数以 `(b, a)` 调用，返回值转为 `b` 是否应排在 `a` 之前。
+fn comparator_result(rt: &Runtime, r: Value) -> Result<bool, String> {
+    if r.is_int() {
+        Ok(r.as_i64() < 0)
+    } else if r.is_f64() {
+        Ok(r.as_f64() < 0.0)
+    } else if r.is_bool() {
+        Ok(r.as_bool())
+    } else {
+        Err(err(rt, xu_syntax::DiagnosticKind::Raw(format!(
+            "sort comparator must return int or bool, got {}",
+            r.type_name()
+        ))))
+    }
+}
+
+/// 稳定的自底向上归并排序。比较函数可能回调脚本代码（并触发 GC），
+/// 因此先在副本上排序，调用方负责把元素加入 GC 临时根。
+fn merge_sort(
+    rt: &mut Runtime,
+    items: Vec<Value>,
+    mut after: impl FnMut(&mut Runtime, Value, Value) -> Result<bool, String>,
+) -> Result<Vec<Value>, String> {
+    let len = items.len();
+    let mut src = items;
+    let mut dst = Vec::with_capacity(len);
+    let mut width = 1;
+    while width < len {
+        dst.clear();
+        let mut start = 0;
+        while start < len {
+            let mid = (start + width).min(len);
+            let end = (start + 2 * width).min(len);
+            let (mut i, mut j) = (start, mid);
+            while i < mid && j < end {
+                // 仅当左侧严格排在右侧之后才取右侧元素，保证稳定性
+                if after(rt, src[i], src[j])? {
+                    dst.push(src[j]);
+                    j += 1;
+                } else {
+                    dst.push(src[i]);
+                    i += 1;
+                }
+            }
+            dst.extend_from_slice(&src[i..mid]);
+            dst.extend_from_slice(&src[j..end]);
+            start = end;
+        }
+        std::mem::swap(&mut src, &mut dst);
+        width *= 2;
+    }
+    Ok(src)
+}
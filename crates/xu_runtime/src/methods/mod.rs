@@ -3,6 +3,7 @@ use crate::Value;
 use crate::Runtime;
 
 mod bool;
+mod channel;
 mod common;
 mod dict;
 mod enum_;
@@ -15,6 +16,7 @@ mod str;
 mod tuple;
 
 use common::*;
+pub(crate) use common::get_dict_key_from_value;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum MethodKind {
@@ -30,32 +32,53 @@ pub(crate) enum MethodKind {
     Len,      // list.length(), str.length(), dict.length(), tuple.length()
     Clear,    // list.clear(), dict.clear()
     Remove,   // list.remove(i), dict.remove(k)
+    Pop,      // list.pop(), dict.pop(k, default)
     ToString, // int.to_string(), float.to_string(), bool.to_string(), option.to_string()
     Abs,      // int.abs(), float.abs()
+    Each,     // list.each(f), option.each(f)
+    Close,    // file.close(), channel.close()
 
     // List 专用方法
     ListPush,
-    ListPop,
     ListReverse,
     ListJoin,
     ListSort,
+    ListSorted,
+    ListSortByKey,
+    ListAny,
+    ListAll,
+    ListFlatMap,
+    ListZip,
+    ListEnumerate,
+    ListTake,
+    ListDrop,
+    ListChunk,
     ListReduce,
     ListFindIndex,
     ListFindOr,
     ListFirst,
     ListRepeat,
+    ListReserve,
 
     // Dict 专用方法
     DictMerge,
     DictInsertInt,
     DictKeys,
+    DictKeysSorted,
     DictValues,
     DictItems,
+    DictUpdate,
+    DictEntryOrInsert,
     GetOrDefault,
 
     // File 专用方法
     FileRead,
-    FileClose,
+    FileWrite,
+
+    // Channel 专用方法
+    ChanSend,
+    ChanRecv,
+    ChanTryRecv,
 
     // String 专用方法
     StrFormat,
@@ -75,6 +98,13 @@ pub(crate) enum MethodKind {
     StrEndsWith,
     StrSubstr,
     StrMatch,
+    StrChars,
+    StrCodePoints,
+    StrGraphemes,
+    StrNormalize,
+    StrByteLength,
+    StrCharLength,
+    StrToBytes,
 
     // Int 专用方法
     IntToBase,
@@ -93,8 +123,10 @@ pub(crate) enum MethodKind {
     Or,
     OrElse,
     Then,
-    Each,
     MapErr,
+    IsOk,
+    IsErr,
+    Expect,
 
     // Enum 专用方法
     EnumName,
@@ -124,32 +156,53 @@ impl MethodKind {
             "length" => Self::Len,
             "clear" => Self::Clear,
             "remove" => Self::Remove,
+            "pop" => Self::Pop,
             "to_string" => Self::ToString,
             "abs" => Self::Abs,
+            "each" => Self::Each,
 
             // List 专用
             "push" => Self::ListPush,
-            "pop" => Self::ListPop,
             "reverse" => Self::ListReverse,
             "join" => Self::ListJoin,
             "sort" => Self::ListSort,
+            "sorted" => Self::ListSorted,
+            "sort_by_key" => Self::ListSortByKey,
+            "any" => Self::ListAny,
+            "all" => Self::ListAll,
+            "flat_map" => Self::ListFlatMap,
+            "zip" => Self::ListZip,
+            "enumerate" => Self::ListEnumerate,
+            "take" => Self::ListTake,
+            "drop" => Self::ListDrop,
+            "chunk" => Self::ListChunk,
             "reduce" => Self::ListReduce,
             "find_index" => Self::ListFindIndex,
             "find_or" => Self::ListFindOr,
             "first" => Self::ListFirst,
             "repeat" => Self::ListRepeat,
+            "reserve" => Self::ListReserve,
 
             // Dict 专用
             "merge" => Self::DictMerge,
             "insert_int" => Self::DictInsertInt,
             "keys" => Self::DictKeys,
+            "keys_sorted" => Self::DictKeysSorted,
             "values" => Self::DictValues,
             "items" => Self::DictItems,
+            "update" => Self::DictUpdate,
+            "entry_or_insert" => Self::DictEntryOrInsert,
             "get_or_default" => Self::GetOrDefault,
 
             // File 专用
             "read" => Self::FileRead,
-            "close" => Self::FileClose,
+            "write" => Self::FileWrite,
+            "close" => Self::Close,
+
+            // Channel 专用
+            "send" => Self::ChanSend,
+            "recv" => Self::ChanRecv,
+            "try_recv" => Self::ChanTryRecv,
 
             // String 专用
             "format" => Self::StrFormat,
@@ -169,6 +222,13 @@ impl MethodKind {
             "ends_with" => Self::StrEndsWith,
             "substr" => Self::StrSubstr,
             "match" => Self::StrMatch,
+            "chars" => Self::StrChars,
+            "code_points" => Self::StrCodePoints,
+            "graphemes" => Self::StrGraphemes,
+            "normalize" => Self::StrNormalize,
+            "byte_length" => Self::StrByteLength,
+            "char_length" => Self::StrCharLength,
+            "to_bytes" => Self::StrToBytes,
 
             // Int 专用
             "to_base" => Self::IntToBase,
@@ -184,11 +244,13 @@ impl MethodKind {
             "not" => Self::BoolNot,
 
             // Option/Result 专用
-            "or" => Self::Or,
+            "or" | "unwrap_or" => Self::Or,
             "or_else" => Self::OrElse,
-            "then" => Self::Then,
-            "each" => Self::Each,
+            "then" | "and_then" => Self::Then,
             "map_err" => Self::MapErr,
+            "is_ok" => Self::IsOk,
+            "is_err" => Self::IsErr,
+            "expect" => Self::Expect,
 
             // Enum 专用
             "name" => Self::EnumName,
@@ -209,6 +271,7 @@ pub(super) fn dispatch_builtin_method(
         crate::core::value::TAG_LIST => list::dispatch(rt, recv, kind, args, method),
         crate::core::value::TAG_DICT => dict::dispatch(rt, recv, kind, args, method),
         crate::core::value::TAG_FILE => file::dispatch(rt, recv, kind, args, method),
+        crate::core::value::TAG_CHANNEL => channel::dispatch(rt, recv, kind, args, method),
         crate::core::value::TAG_STR => str::dispatch(rt, recv, kind, args, method),
         crate::core::value::TAG_ENUM => enum_::dispatch(rt, recv, kind, args, method),
         crate::core::value::TAG_OPTION => option::dispatch(rt, recv, kind, args, method),
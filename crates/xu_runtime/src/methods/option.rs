@@ -50,6 +50,10 @@ pub(super) fn dispatch(
             validate_arity(rt, method, args.len(), 0, 0)?;
             Ok(inner)
         }
+        MethodKind::Expect => {
+            validate_arity(rt, method, args.len(), 1, 1)?;
+            Ok(inner)
+        }
         MethodKind::ToString => {
             validate_arity(rt, method, args.len(), 0, 0)?;
             let inner_str = crate::util::value_to_string(&inner, &rt.heap);
@@ -58,20 +58,21 @@ pub(super) fn dispatch(
             {
                 let dict = expect_dict(rt, args[0])?;
 
-                for (k, v) in dict.map.iter() {
+                for (k, v) in dict.entries() {
                     let key = match k {
                         crate::core::value::DictKey::StrRef { obj_id, .. } => {
                             // Get string from heap
-                            if let crate::core::heap::ManagedObject::Str(s) = rt.heap.get(crate::core::heap::ObjectId(*obj_id)) {
+                            if let crate::core::heap::ManagedObject::Str(s) = rt.heap.get(crate::core::heap::ObjectId(obj_id)) {
                                 crate::Text::from_str(s.as_str())
                             } else {
                                 continue;
                             }
                         }
-                        crate::core::value::DictKey::Int(i) => crate::core::value::i64_to_text_fast(*i),
+                        crate::core::value::DictKey::Int(i) => crate::core::value::i64_to_text_fast(i),
+                        _ => crate::Text::from_str(&value_to_string(&k.to_value(&rt.heap), &rt.heap)),
                     };
                     let needle = format!("{{{}}}", key);
-                    let repl = value_to_string(v, &rt.heap);
+                    let repl = value_to_string(&v, &rt.heap);
                     out = out.as_str().replace(&needle, &repl).into();
                 }
             }
@@ -85,14 +86,9 @@ pub(super) fn dispatch(
             let sep = get_str_from_value(rt, &args[0])?;
             let s = expect_str(rt, recv)?;
 
-            // Clone source string to avoid borrow conflict
-            let source = s.as_str().to_string();
-
-            // Collect split results
-            let mut items = Vec::new();
-            for part in source.split(&sep) {
-                items.push(create_str_value(rt, part));
-            }
+            // 长片段是源串的视图，不拷贝
+            let parts: Vec<crate::Text> = s.as_str().split(&sep).map(|part| s.slice_of(part)).collect();
+            let items = parts.into_iter().map(|part| create_text_value(rt, part)).collect();
 
             Ok(create_list_value(rt, items))
         }
@@ -190,8 +186,8 @@ pub(super) fn dispatch(
         MethodKind::StrTrim => {
             validate_arity(rt, method, args.len(), 0, 0)?;
             let s = expect_str(rt, recv)?;
-            let result = s.as_str().trim().to_string();
-            Ok(create_str_value(rt, &result))
+            let result = s.slice_of(s.as_str().trim());
+            Ok(create_text_value(rt, result))
         }
         MethodKind::StrReplace => {
             validate_arity(rt, method, args.len(), 2, 2)?;
@@ -207,14 +203,14 @@ pub(super) fn dispatch(
         MethodKind::StrTrimStart => {
             validate_arity(rt, method, args.len(), 0, 0)?;
             let s = expect_str(rt, recv)?;
-            let result = s.as_str().trim_start().to_string();
-            Ok(create_str_value(rt, &result))
+            let result = s.slice_of(s.as_str().trim_start());
+            Ok(create_text_value(rt, result))
         }
         MethodKind::StrTrimEnd => {
             validate_arity(rt, method, args.len(), 0, 0)?;
             let s = expect_str(rt, recv)?;
-            let result = s.as_str().trim_end().to_string();
-            Ok(create_str_value(rt, &result))
+            let result = s.slice_of(s.as_str().trim_end());
+            Ok(create_text_value(rt, result))
         }
         MethodKind::Find => {
             validate_arity(rt, method, args.len(), 1, 1)?;
@@ -223,7 +219,11 @@ pub(super) fn dispatch(
             let sub = get_str_from_value(rt, &args[0])?;
             let s = expect_str(rt, recv)?;
 
-            match s.as_str().find(&sub) {
+            // 与 substr/get 一致，返回字符下标而非字节偏移
+            let found = s.as_str().find(&sub).map(|idx| {
+                if s.is_ascii() { idx } else { s.as_str()[..idx].chars().count() }
+            });
+            match found {
                 Some(idx) => Ok(rt.option_some(Value::from_i64(idx as i64))),
                 None => Ok(rt.option_none()),
             }
@@ -243,30 +243,83 @@ pub(super) fn dispatch(
             let length_idx = length as usize;
             let str_ref = s.as_str();
 
-            let result = if s.is_ascii() {
-                if start_idx >= str_ref.len() {
-                    "".to_string()
-                } else {
-                    let end_idx = std::cmp::min(start_idx + length_idx, str_ref.len());
-                    str_ref[start_idx..end_idx].to_string()
-                }
+            // 按字符下标换算成字节区间；长子串是源串的视图
+            let range = if s.is_ascii() {
+                let begin = start_idx.min(str_ref.len());
+                begin..begin.saturating_add(length_idx).min(str_ref.len())
             } else {
-                let chars: Vec<char> = str_ref.chars().collect();
-                if start_idx >= chars.len() {
-                    "".to_string()
-                } else {
-                    let end_idx = std::cmp::min(start_idx + length_idx, chars.len());
-                    chars[start_idx..end_idx].iter().collect()
-                }
+                let byte_at = |from: usize, n: usize| {
+                    str_ref[from..].char_indices().nth(n).map_or(str_ref.len(), |(i, _)| from + i)
+                };
+                let begin = byte_at(0, start_idx);
+                begin..byte_at(begin, length_idx)
             };
-            
-            Ok(create_str_value(rt, &result))
+            let result = s.slice(range);
+
+            Ok(create_text_value(rt, result))
         }
-        MethodKind::Len => {
+        MethodKind::Len | MethodKind::StrCharLength => {
             validate_arity(rt, method, args.len(), 0, 0)?;
             let s = expect_str(rt, recv)?;
             Ok(Value::from_i64(s.char_count() as i64))
         }
+        MethodKind::StrByteLength => {
+            validate_arity(rt, method, args.len(), 0, 0)?;
+            let s = expect_str(rt, recv)?;
+            Ok(Value::from_i64(s.len() as i64))
+        }
+        MethodKind::StrChars => {
+            validate_arity(rt, method, args.len(), 0, 0)?;
+            let source = expect_str(rt, recv)?.as_str().to_string();
+            let mut buf = [0u8; 4];
+            let items = source
+                .chars()
+                .map(|c| create_str_value(rt, c.encode_utf8(&mut buf)))
+                .collect();
+            Ok(create_list_value(rt, items))
+        }
+        MethodKind::StrCodePoints => {
+            validate_arity(rt, method, args.len(), 0, 0)?;
+            let s = expect_str(rt, recv)?;
+            let items = s.as_str().chars().map(|c| Value::from_i64(c as i64)).collect();
+            Ok(create_list_value(rt, items))
+        }
+        MethodKind::StrGraphemes => {
+            validate_arity(rt, method, args.len(), 0, 0)?;
+            let source = expect_str(rt, recv)?.as_str().to_string();
+            let items = xu_syntax::graphemes(&source)
+                .map(|g| create_str_value(rt, g))
+                .collect();
+            Ok(create_list_value(rt, items))
+        }
+        MethodKind::StrToBytes => {
+            validate_arity(rt, method, args.len(), 0, 1)?;
+            let encoding = encoding_arg(rt, args.first())?;
+            let s = expect_str(rt, recv)?;
+            let bytes = encoding
+                .encode(s.as_str())
+                .map_err(|e| err(rt, xu_syntax::DiagnosticKind::Raw(e)))?;
+            Ok(crate::builtins::encoding::bytes_value(rt, &bytes))
+        }
+        MethodKind::StrNormalize => {
+            validate_arity(rt, method, args.len(), 0, 1)?;
+            let form = match args.first() {
+                Some(v) => {
+                    validate_str_param(rt, v, "form")?;
+                    let name = get_str_from_value(rt, v)?;
+                    xu_syntax::NormalizationForm::parse(&name).ok_or_else(|| {
+                        err(rt, xu_syntax::DiagnosticKind::Raw(format!(
+                            "Unsupported normalization form: {} (expected \"NFC\" or \"NFD\")",
+                            name
+                        )))
+                    })?
+                }
+                None => xu_syntax::NormalizationForm::Nfc,
+            };
+            let s = expect_str(rt, recv)?;
+            let result = xu_syntax::normalize(s.as_str(), form);
+            Ok(create_str_value(rt, &result))
+        }
         MethodKind::StrMatch => {
             validate_arity(rt, method, args.len(), 1, 1)?;
             validate_str_param(rt, &args[0], "pattern")?;
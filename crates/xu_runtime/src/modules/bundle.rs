@@ -0,0 +1,230 @@
+//! 打包：把入口程序和它直接或间接导入的模块收集成一个 [`Bundle`]，
+//! 并在打包后的程序里从 Bundle 加载模块。
+//!
+//! Bundle 中的模块键是相对路径：用户模块相对于所有用户模块的公共祖先目录，
+//! 标准库模块为 `std/<名字>.xu`。运行时按与磁盘相同的规则（相对导入方所在
+//! 目录）在这些键之间解析 `use` 路径；打包时逐条检查每个导入在 Bundle 中
+//! 解析到的模块与磁盘上的一致。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use xu_ir::Executable;
+use xu_ir::bundle::Bundle;
+
+use super::import::{ImportParseCacheEntry, ImportParseResult};
+use super::loader::{ImportStamp, ModuleLoader, StdModuleLoader};
+use super::std_bundle::EMBEDDED_PREFIX;
+use crate::Runtime;
+
+/// Bundle 模块没有源文件，所有条目共用这个标记
+const BUNDLE_STAMP: ImportStamp = ImportStamp { len: 0, modified_nanos: None, hash: 0 };
+
+impl Runtime {
+    /// Collects `entry`, the program compiled from the runtime's entry path,
+    /// and every module it imports through `use`, directly or not, into a
+    /// [`Bundle`]. Imports are resolved and compiled the way running the
+    /// program would; in the bundle, modules are keyed by their path relative
+    /// to the common directory of the program's files, and stdlib modules as
+    /// `std/<name>.xu`.
+    ///
+    /// Fails if an import cannot be resolved or compiled, uses an absolute
+    /// path, or can only be found relative to the working directory. Programs
+    /// with a prelude cannot be bundled.
+    pub fn build_bundle(&mut self, entry: &Executable) -> Result<Bundle, String> {
+        let entry_key = self
+            .entry_path
+            .clone()
+            .ok_or_else(|| "Bundling needs the entry path of the program".to_string())?;
+        if self.prelude.is_some() {
+            return Err("Programs with a prelude cannot be bundled".into());
+        }
+        let mut found: Vec<(String, Executable)> = vec![(entry_key.clone(), entry.clone())];
+        let mut edges: Vec<(String, String, String)> = Vec::new();
+        let mut i = 0;
+        while i < found.len() {
+            let importer = found[i].0.clone();
+            for raw in use_paths(&found[i].1) {
+                if Path::new(&raw).is_absolute() {
+                    return Err(format!("{importer}: absolute import path {raw} cannot be bundled"));
+                }
+                self.import_stack.push(importer.clone());
+                let key = self.module_loader.resolve_key(self, &raw);
+                self.import_stack.pop();
+                let key = key.map_err(|e| format!("{importer}: {e}"))?;
+                if !found.iter().any(|(k, _)| *k == key) {
+                    let parsed = self.parse_import_cached(&key).map_err(|e| format!("{key}: {e}"))?;
+                    found.push((key.clone(), parsed.executable));
+                }
+                edges.push((importer.clone(), raw, key));
+            }
+            i += 1;
+        }
+
+        let names = bundle_keys(found.iter().map(|(k, _)| k.as_str()), self.stdlib_path.as_deref());
+        let keys: HashSet<&str> = names.values().map(String::as_str).collect();
+        for (importer, raw, key) in &edges {
+            if resolve_in_bundle(&names[importer], raw, |k| keys.contains(k)).as_ref() != Some(&names[key]) {
+                return Err(format!(
+                    "{importer}: import {raw} cannot be bundled: it is only found relative to the working directory"
+                ));
+            }
+        }
+        Ok(Bundle {
+            lang_version: env!("CARGO_PKG_VERSION").to_string(),
+            entry: names[&entry_key].clone(),
+            modules: found.into_iter().map(|(k, e)| (names[&k].clone(), e)).collect(),
+        })
+    }
+
+    /// Makes `use` resolve to the modules of `bundle` and returns its entry
+    /// program, ready for [`Runtime::exec_executable`]. `std/...` imports the
+    /// bundle lacks fall back to the stdlib embedded in this build.
+    pub fn load_bundle(&mut self, bundle: Bundle) -> Result<Executable, String> {
+        let mut entry = None;
+        let mut keys = HashSet::new();
+        for (key, executable) in bundle.modules {
+            if key == bundle.entry {
+                entry = Some(executable.clone());
+            }
+            keys.insert(key.clone());
+            self.import_parse_cache.insert(
+                key,
+                ImportParseCacheEntry {
+                    stamp: BUNDLE_STAMP,
+                    result: Ok(ImportParseResult { executable }),
+                },
+            );
+        }
+        let entry = entry.ok_or_else(|| format!("Bundle entry {} is not among its modules", bundle.entry))?;
+        self.entry_path = Some(bundle.entry);
+        self.module_loader = Box::new(BundleModuleLoader { keys });
+        Ok(entry)
+    }
+}
+
+/// Resolves imports among the modules of a loaded bundle.
+struct BundleModuleLoader {
+    keys: HashSet<String>,
+}
+
+impl ModuleLoader for BundleModuleLoader {
+    fn resolve_key(&self, rt: &Runtime, raw: &str) -> Result<String, String> {
+        let importer = rt.import_stack.last().or(rt.entry_path.as_ref()).cloned().unwrap_or_default();
+        if let Some(key) = resolve_in_bundle(&importer, raw, |k| self.keys.contains(k)) {
+            return Ok(key);
+        }
+        if raw.starts_with("std/") {
+            return StdModuleLoader.resolve_key(rt, raw);
+        }
+        Err(format!("Import failed: {raw} is not in the bundle"))
+    }
+
+    fn load_text_and_stamp(&self, rt: &Runtime, key: &str) -> Result<(String, ImportStamp), String> {
+        if self.keys.contains(key) {
+            return Ok((String::new(), BUNDLE_STAMP));
+        }
+        StdModuleLoader.load_text_and_stamp(rt, key)
+    }
+}
+
+/// 与磁盘上相同的解析规则：`std/` 路径补全扩展名，其余相对导入方所在目录
+fn resolve_in_bundle(importer: &str, raw: &str, contains: impl Fn(&str) -> bool) -> Option<String> {
+    let key = if raw.starts_with("std/") {
+        if raw.ends_with(".xu") { raw.to_string() } else { format!("{raw}.xu") }
+    } else {
+        let base = Path::new(importer).parent().unwrap_or(Path::new(""));
+        key_string(normalize(&base.join(raw))?)?
+    };
+    contains(&key).then_some(key)
+}
+
+/// 把磁盘上的模块键映射为 Bundle 中的相对键
+fn bundle_keys<'a>(keys: impl Iterator<Item = &'a str>, stdlib: Option<&str>) -> HashMap<String, String> {
+    let stdlib = stdlib.and_then(|s| Path::new(s).canonicalize().ok());
+    let mut names = HashMap::new();
+    let mut user = Vec::new();
+    for key in keys {
+        if let Some(name) = key.strip_prefix(EMBEDDED_PREFIX) {
+            names.insert(key.to_string(), name.to_string());
+            continue;
+        }
+        let path = Path::new(key);
+        let std_rel = stdlib.as_ref().and_then(|s| path.strip_prefix(s.join("std")).ok());
+        match std_rel.and_then(key_string) {
+            Some(rel) => {
+                names.insert(key.to_string(), format!("std/{rel}"));
+            }
+            None => user.push(key),
+        }
+    }
+    let root = common_dir(user.iter().map(|k| Path::new(*k)));
+    for key in user {
+        let rel = Path::new(key).strip_prefix(&root).ok().and_then(key_string);
+        names.insert(key.to_string(), rel.unwrap_or_else(|| key.to_string()));
+    }
+    names
+}
+
+/// 所有文件共同的祖先目录
+fn common_dir<'a>(mut paths: impl Iterator<Item = &'a Path>) -> PathBuf {
+    let Some(first) = paths.next() else {
+        return PathBuf::new();
+    };
+    let mut root = first.parent().unwrap_or(Path::new("")).to_path_buf();
+    for p in paths {
+        while !p.starts_with(&root) {
+            if !root.pop() {
+                break;
+            }
+        }
+    }
+    root
+}
+
+/// 去掉 `.` 并消解 `..`；越出根目录的路径无法表示
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    return None;
+                }
+            }
+            Component::Normal(part) => out.push(part),
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(out)
+}
+
+/// 用 `/` 连接的相对键
+fn key_string(path: impl AsRef<Path>) -> Option<String> {
+    let parts: Vec<String> = path
+        .as_ref()
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// 模块顶层 `use` 语句的路径
+fn use_paths(executable: &Executable) -> Vec<String> {
+    let module = match executable {
+        Executable::Ast(m) => m,
+        Executable::Bytecode(p) => &p.module,
+    };
+    module
+        .stmts
+        .iter()
+        .filter_map(|s| match s {
+            xu_ir::Stmt::Use(u) => Some(u.path.clone()),
+            _ => None,
+        })
+        .collect()
+}
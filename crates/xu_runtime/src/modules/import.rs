@@ -9,13 +9,13 @@ pub(crate) use xu_ir::infer_module_alias;
 
 #[derive(Clone, Debug)]
 pub(crate) struct ImportParseCacheEntry {
-    stamp: ImportStamp,
-    result: Result<ImportParseResult, String>,
+    pub(crate) stamp: ImportStamp,
+    pub(crate) result: Result<ImportParseResult, String>,
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct ImportParseResult {
-    executable: xu_ir::Executable,
+    pub(crate) executable: xu_ir::Executable,
 }
 
 impl Runtime {
@@ -28,6 +28,10 @@ impl Runtime {
         }
 
         let result = (|| {
+            // 内嵌的标准库模块在构建时已编译
+            if let Some(executable) = super::std_bundle::embedded_executable(key) {
+                return executable.map(|executable| ImportParseResult { executable });
+            }
             let Some(frontend) = self.frontend.as_ref() else {
                 return Err(self.error(xu_syntax::DiagnosticKind::ImportFailed(
                     "Runtime frontend is not configured".into(),
@@ -58,8 +62,93 @@ impl Runtime {
 
         result
     }
+
+    /// Re-parses and re-executes an already imported module.
+    ///
+    /// Exported functions that exist in both versions are swapped in place, so
+    /// every existing reference (module aliases, captured closures, values
+    /// stored in containers) picks up the new code through the same ObjectId.
+    /// Other exports are replaced in the module's export table. Method and
+    /// member inline caches are invalidated. A module that was never imported
+    /// is simply imported.
+    pub fn reload_module(&mut self, path: &str) -> Result<Value, String> {
+        let key = self.module_loader.resolve_key(self, path)?;
+        let Some(old_module) = self.loaded_modules.shift_remove(&key) else {
+            return import_path(self, path);
+        };
+        self.import_parse_cache.shift_remove(&key);
+        self.gc_temp_roots.push(old_module);
+        let reloaded = import_path(self, path);
+        self.gc_temp_roots.pop();
+        let new_module = match reloaded {
+            Ok(v) => v,
+            Err(e) => {
+                self.loaded_modules.insert(key, old_module);
+                return Err(e);
+            }
+        };
+
+        let new_exports = match self.heap.get(new_module.as_obj_id()) {
+            crate::core::heap::ManagedObject::Module(m) => m.exports.map.clone(),
+            _ => return Err(self.error(xu_syntax::DiagnosticKind::Raw("Not a module".into()))),
+        };
+        let old_exports = match self.heap.get(old_module.as_obj_id()) {
+            crate::core::heap::ManagedObject::Module(m) => m.exports.map.clone(),
+            _ => return Err(self.error(xu_syntax::DiagnosticKind::Raw("Not a module".into()))),
+        };
+
+        let mut exports: DictStr = crate::core::value::dict_str_new(&self.heap);
+        for (name, new_v) in new_exports.iter() {
+            let mut v = *new_v;
+            if let Some(old_v) = old_exports.get(name) {
+                let tag = crate::core::value::TAG_FUNC;
+                if old_v.get_tag() == tag && new_v.get_tag() == tag {
+                    let func = self.heap.get(new_v.as_obj_id()).clone();
+                    *self.heap_get_mut(old_v.as_obj_id()) = func;
+                    v = *old_v;
+                }
+            }
+            exports.map.insert(name.clone(), v);
+        }
+        if let crate::core::heap::ManagedObject::Module(m) = self.heap_get_mut(old_module.as_obj_id()) {
+            m.exports = exports;
+        }
+        self.loaded_modules.insert(key, old_module);
+        self.caches.invalidate_code_caches();
+        Ok(old_module)
+    }
+}
+
+impl Runtime {
+    /// Imports the configured prelude and defines its exports in the current
+    /// (entry) scope. Modules imported afterwards receive them as well.
+    pub(crate) fn install_prelude(&mut self) -> Result<(), String> {
+        let Some(path) = self.prelude.clone() else {
+            return Ok(());
+        };
+        let module = import_path(self, &path)?;
+        let exports: Vec<(String, Value)> = match self.heap.get(module.as_obj_id()) {
+            crate::core::heap::ManagedObject::Module(m) => {
+                m.exports.map.iter().map(|(k, v)| (k.clone(), *v)).collect()
+            }
+            _ => return Err(self.error(xu_syntax::DiagnosticKind::Raw("Not a module".into()))),
+        };
+        for (name, v) in &exports {
+            self.env.define(name.clone(), *v);
+        }
+        self.prelude_exports = exports;
+        Ok(())
+    }
 }
 
+/// Imports a module, executing its top level once and caching the result.
+///
+/// Circular imports are allowed: the module object is registered before its
+/// body runs, so a module reached again through a cycle receives this partially
+/// initialized object. Its exports are filled in only when the body finishes,
+/// so code running during initialization must not read members of a module
+/// still on the import stack; referencing them inside top-level functions that
+/// are called later is safe.
 pub(crate) fn import_path(rt: &mut Runtime, path: &str) -> Result<Value, String> {
     let key = rt.module_loader.resolve_key(rt, path)?;
     #[cfg(test)]
@@ -78,13 +167,8 @@ pub(crate) fn import_path(rt: &mut Runtime, path: &str) -> Result<Value, String>
         return Ok(v);
     }
 
-    if let Some(pos) = rt.import_stack.iter().position(|p| p == &key) {
-        let mut chain: Vec<String> = rt.import_stack[pos..].to_vec();
-        chain.push(key.clone());
-        return Err(rt.error(xu_syntax::DiagnosticKind::CircularImport(chain)));
-    }
-
     rt.import_stack.push(key.clone());
+    let suspended = rt.suspended_envs.len();
     let result = (|| {
         let parsed = rt.parse_import_cached(&key)?;
         let (module, bytecode) = match parsed.executable {
@@ -93,6 +177,8 @@ pub(crate) fn import_path(rt: &mut Runtime, path: &str) -> Result<Value, String>
         };
 
         let saved_env = rt.env.clone();
+        // Keep the importer's bindings alive if the module body collects.
+        rt.suspended_envs.push(saved_env.clone());
         rt.env = Env::new();
         rt.install_builtins();
         let new_locals = Runtime::collect_func_locals(&module);
@@ -108,12 +194,26 @@ pub(crate) fn import_path(rt: &mut Runtime, path: &str) -> Result<Value, String>
             .keys()
             .cloned()
             .collect();
+        // Defined after collecting builtin names so a module that redefines a
+        // prelude name still exports its own definition.
+        for (name, v) in rt.prelude_exports.clone() {
+            rt.env.define(name, v);
+        }
 
         Runtime::precompile_module(&module)?;
+
+        // Register the module before running its body so cyclic imports
+        // resolve to this (still empty) instance instead of recursing.
+        let module_obj = Value::module(rt.heap.alloc(crate::core::heap::ManagedObject::Module(
+            Box::new(ModuleInstance { exports: crate::core::value::dict_str_new(&rt.heap) }),
+        )));
+        rt.loaded_modules.insert(key.clone(), module_obj);
+
         let exec_result = match bytecode.as_ref() {
             Some(bc) => match crate::vm::run_bytecode(rt, bc)? {
                 Flow::None | Flow::Return(_) => Ok(()),
                 Flow::Throw(v) => Err(rt.format_throw(&v)),
+                Flow::Exit(code) => Err(crate::runtime::exit_message(code)),
                 Flow::Break | Flow::Continue => {
                     Err(rt.error(xu_syntax::DiagnosticKind::TopLevelBreakContinue))
                 }
@@ -121,6 +221,7 @@ pub(crate) fn import_path(rt: &mut Runtime, path: &str) -> Result<Value, String>
             None => match rt.exec_stmts(&module.stmts) {
                 Flow::None | Flow::Return(_) => Ok(()),
                 Flow::Throw(v) => Err(rt.format_throw(&v)),
+                Flow::Exit(code) => Err(crate::runtime::exit_message(code)),
                 Flow::Break | Flow::Continue => {
                     Err(rt.error(xu_syntax::DiagnosticKind::TopLevelBreakContinue))
                 }
@@ -160,7 +261,7 @@ pub(crate) fn import_path(rt: &mut Runtime, path: &str) -> Result<Value, String>
             }
         }
 
-        let mut exports: DictStr = crate::core::value::dict_str_new();
+        let mut exports: DictStr = crate::core::value::dict_str_new(&rt.heap);
         let frame_rc = module_env.global_frame();
         let frame0 = frame_rc.borrow();
         for (k, idx) in frame0.names.iter() {
@@ -173,23 +274,31 @@ pub(crate) fn import_path(rt: &mut Runtime, path: &str) -> Result<Value, String>
             {
                 continue;
             }
-            if !public_names.contains(k) {
+            // Methods of a public struct are exported with it.
+            let owner = k
+                .strip_prefix("__method__")
+                .or_else(|| k.strip_prefix("__static__"))
+                .and_then(|rest| rest.split_once("__"))
+                .map(|(ty, _)| ty);
+            if !public_names.contains(k) && !owner.is_some_and(|ty| public_names.contains(ty)) {
                 continue;
             }
             if let Some(v) = frame0.values.get(*idx) {
                 exports.map.insert(k.clone(), *v);
             }
         }
-        let module_obj = Value::module(
-            rt.heap
-                .alloc(crate::core::heap::ManagedObject::Module(Box::new(ModuleInstance { exports }))),
-        );
-        rt.loaded_modules.insert(key.clone(), module_obj);
+        if let crate::core::heap::ManagedObject::Module(m) = rt.heap_get_mut(module_obj.as_obj_id()) {
+            m.exports = exports;
+        }
         if trace_import {
             eprintln!("import_done: {}", key);
         }
         Ok(module_obj)
     })();
+    rt.suspended_envs.truncate(suspended);
+    if result.is_err() {
+        rt.loaded_modules.shift_remove(&key);
+    }
     rt.import_stack.pop();
     result
 }
@@ -1,11 +1,22 @@
 use std::path::PathBuf;
 
+use super::std_bundle;
 use crate::Runtime;
 
+/// Identifies the version of a module's source a cached compile was made
+/// from. The content hash catches edits that keep the length and land
+/// within the file system's modification time granularity.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ImportStamp {
     pub len: u64,
     pub modified_nanos: Option<u128>,
+    pub hash: u64,
+}
+
+impl ImportStamp {
+    pub fn new(text: &str, modified_nanos: Option<u128>) -> Self {
+        ImportStamp { len: text.len() as u64, modified_nanos, hash: xu_ir::stable_hash64(text) }
+    }
 }
 
 pub trait ModuleLoader {
@@ -29,6 +40,9 @@ impl ModuleLoader for StdModuleLoader {
                     return Ok(path);
                 }
             }
+            if let Some(key) = std_bundle::embedded_key(raw) {
+                return Ok(key);
+            }
         }
 
         let raw_path = PathBuf::from(raw);
@@ -63,15 +77,13 @@ impl ModuleLoader for StdModuleLoader {
         rt: &Runtime,
         key: &str,
     ) -> Result<(String, ImportStamp), String> {
+        if let Some(text) = std_bundle::embedded_source(key) {
+            return Ok((text.to_string(), ImportStamp::new(text, None)));
+        }
         let stat = rt.fs_stat(key)?;
         let text = rt.fs_read_to_string_import(key)?;
-        Ok((
-            text,
-            ImportStamp {
-                len: stat.len,
-                modified_nanos: stat.modified_nanos,
-            },
-        ))
+        let stamp = ImportStamp::new(&text, stat.modified_nanos);
+        Ok((text, stamp))
     }
 }
 
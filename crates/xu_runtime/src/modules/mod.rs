@@ -1,7 +1,12 @@
 //! Module loading and import system.
 
+mod bundle;
 mod loader;
 mod import;
+mod snapshot;
+mod std_bundle;
 
 pub use loader::{ImportStamp, ModuleLoader, StdModuleLoader};
+pub use snapshot::SnapshotReport;
+pub use std_bundle::embedded_std_modules;
 pub(crate) use import::{import_path, infer_module_alias, ImportParseCacheEntry};
@@ -0,0 +1,112 @@
+//! Startup snapshots: pre-parsed/compiled modules serialized into one image.
+//!
+//! Loading an image seeds the import parse cache, so importing a snapshotted
+//! module skips the frontend entirely. Every entry records the stamp of the
+//! source it was built from; if the source has changed since, the stamp no
+//! longer matches and the module is re-parsed as usual. Keys are canonical
+//! paths, so an image is only useful on the machine (and stdlib location) it
+//! was built for.
+
+use xu_ir::image::{IMAGE_VERSION, ImageReader, ImageWriter};
+
+use super::import::{ImportParseCacheEntry, ImportParseResult};
+use super::loader::ImportStamp;
+use crate::Runtime;
+
+const MAGIC: &[u8; 8] = b"XUSNAP\0\0";
+
+/// Result of [`Runtime::build_snapshot`].
+#[derive(Clone, Debug, Default)]
+pub struct SnapshotReport {
+    /// The serialized image.
+    pub bytes: Vec<u8>,
+    /// Import keys stored in the image.
+    pub modules: Vec<String>,
+    /// Imports that failed to resolve or compile, with the error.
+    pub skipped: Vec<(String, String)>,
+}
+
+impl Runtime {
+    /// Compiles each import path (as written in a `use` statement) and
+    /// serializes the results into a snapshot image.
+    pub fn build_snapshot(&mut self, paths: &[&str]) -> SnapshotReport {
+        let mut report = SnapshotReport::default();
+        let mut entries: Vec<(String, ImportStamp, xu_ir::Executable)> = Vec::new();
+        for path in paths {
+            let compiled = self
+                .module_loader
+                .resolve_key(self, path)
+                .and_then(|key| {
+                    let (_, stamp) = self.module_loader.load_text_and_stamp(self, &key)?;
+                    let parsed = self.parse_import_cached(&key)?;
+                    Ok((key, stamp, parsed.executable))
+                });
+            match compiled {
+                Ok(entry) => {
+                    if !entries.iter().any(|(k, _, _)| *k == entry.0) {
+                        report.modules.push(entry.0.clone());
+                        entries.push(entry);
+                    }
+                }
+                Err(e) => report.skipped.push((path.to_string(), e)),
+            }
+        }
+
+        let mut w = ImageWriter::new();
+        w.bytes(MAGIC);
+        w.put(&IMAGE_VERSION);
+        w.put(&env!("CARGO_PKG_VERSION").to_string());
+        w.put(&entries.len());
+        for (key, stamp, executable) in &entries {
+            w.put(key);
+            w.put(&stamp.len);
+            w.put(&stamp.modified_nanos);
+            w.put(&stamp.hash);
+            w.put(executable);
+        }
+        report.bytes = w.finish();
+        report
+    }
+
+    /// Loads a snapshot image built by [`Runtime::build_snapshot`] and returns
+    /// the number of modules it provides. Images from a different build are
+    /// rejected.
+    pub fn load_snapshot(&mut self, bytes: &[u8]) -> Result<usize, String> {
+        let mut r = ImageReader::new(bytes);
+        if r.bytes(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err("Not a snapshot image".into());
+        }
+        let version: u32 = r.get()?;
+        let pkg: String = r.get()?;
+        if version != IMAGE_VERSION || pkg != env!("CARGO_PKG_VERSION") {
+            return Err(format!(
+                "Snapshot was built by xu {pkg} (image v{version}); rebuild it with this version"
+            ));
+        }
+        let count: usize = r.get()?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let key: String = r.get()?;
+            let stamp = ImportStamp {
+                len: r.get()?,
+                modified_nanos: r.get()?,
+                hash: r.get()?,
+            };
+            let executable: xu_ir::Executable = r.get()?;
+            entries.push((key, stamp, executable));
+        }
+        if !r.is_empty() {
+            return Err("Trailing data in snapshot image".into());
+        }
+        for (key, stamp, executable) in entries {
+            self.import_parse_cache.insert(
+                key,
+                ImportParseCacheEntry {
+                    stamp,
+                    result: Ok(ImportParseResult { executable }),
+                },
+            );
+        }
+        Ok(count)
+    }
+}
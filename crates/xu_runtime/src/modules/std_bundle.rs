@@ -0,0 +1,48 @@
+//! Standard library modules embedded at build time (see `build.rs`).
+//!
+//! Each module is embedded as its source and as the `Executable` the build
+//! compiled it to. Used as a fallback when `std/...` cannot be resolved on
+//! disk; importing an embedded module decodes its executable instead of
+//! compiling the source.
+
+use xu_ir::image::ImageReader;
+
+include!(concat!(env!("OUT_DIR"), "/std_bundle.rs"));
+
+/// Prefix of import keys that refer to embedded modules.
+pub(crate) const EMBEDDED_PREFIX: &str = "<embedded>/";
+
+/// Maps a `std/...` import path to the key of its embedded module, if any.
+pub(crate) fn embedded_key(raw: &str) -> Option<String> {
+    let name = if raw.ends_with(".xu") {
+        raw.to_string()
+    } else {
+        format!("{raw}.xu")
+    };
+    STD_BUNDLE
+        .iter()
+        .any(|(n, _, _)| *n == name)
+        .then(|| format!("{EMBEDDED_PREFIX}{name}"))
+}
+
+fn embedded(key: &str) -> Option<&'static (&'static str, &'static str, Option<&'static [u8]>)> {
+    let name = key.strip_prefix(EMBEDDED_PREFIX)?;
+    STD_BUNDLE.iter().find(|(n, _, _)| *n == name)
+}
+
+/// Returns the source of an embedded module by its import key.
+pub(crate) fn embedded_source(key: &str) -> Option<&'static str> {
+    embedded(key).map(|(_, source, _)| *source)
+}
+
+/// Decodes the precompiled executable of an embedded module by its import
+/// key. Modules that failed to compile at build time have none.
+pub(crate) fn embedded_executable(key: &str) -> Option<Result<xu_ir::Executable, String>> {
+    let image = embedded(key)?.2?;
+    Some(ImageReader::new(image).get().map_err(|e| format!("{key}: corrupt embedded module: {e}")))
+}
+
+/// Names (`std/<file>.xu`) of all embedded modules.
+pub fn embedded_std_modules() -> impl Iterator<Item = &'static str> {
+    STD_BUNDLE.iter().map(|(n, _, _)| *n)
+}
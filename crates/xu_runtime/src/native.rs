@@ -0,0 +1,180 @@
+//! Conversions between [`Value`]s and Rust types for builtins written as
+//! ordinary Rust functions with [`xu_builtin!`](crate::xu_builtin).
+//!
+//! ```ignore
+//! use xu_runtime::xu_builtin;
+//!
+//! xu_builtin! {
+//!     /// Installs `hypot`.
+//!     pub struct Geometry;
+//!
+//!     pub fn hypot(a: f64, b: f64) -> f64 {
+//!         a.hypot(b)
+//!     }
+//! }
+//! ```
+//!
+//! expands to a `builtin_hypot` with the [`BuiltinFn`](crate::builtins_registry::BuiltinFn)
+//! signature, which reports `hypot expects 2 arguments` or `hypot expects
+//! number, got string` for bad calls, and a [`BuiltinProvider`](crate::BuiltinProvider)
+//! `Geometry` that registers it. Parameters and results may use the types
+//! implementing [`FromValue`] and [`IntoValue`]; a leading `&mut Runtime`
+//! receives the runtime.
+
+use crate::Runtime;
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{TAG_LIST, TAG_STR};
+
+/// A Rust type a builtin argument converts to.
+pub trait FromValue: Sized {
+    /// Converts `v`, an argument of the builtin `builtin`.
+    fn from_value(rt: &Runtime, v: &Value, builtin: &str) -> Result<Self, String>;
+
+    /// Converts an argument the caller may have left out.
+    fn from_arg(rt: &Runtime, v: Option<&Value>, builtin: &str) -> Result<Self, String> {
+        match v {
+            Some(v) => Self::from_value(rt, v, builtin),
+            None => Err(format!("{builtin}: missing argument")),
+        }
+    }
+}
+
+/// A Rust type a builtin result converts from.
+pub trait IntoValue {
+    fn into_value(self, rt: &mut Runtime) -> Result<Value, String>;
+}
+
+impl FromValue for Value {
+    fn from_value(_rt: &Runtime, v: &Value, _builtin: &str) -> Result<Self, String> {
+        Ok(*v)
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(_rt: &Runtime, v: &Value, builtin: &str) -> Result<Self, String> {
+        if v.is_int() { Ok(v.as_i64()) } else { Err(format!("{builtin} expects int, got {}", v.type_name())) }
+    }
+}
+
+/// Ints are widened, as in arithmetic.
+impl FromValue for f64 {
+    fn from_value(_rt: &Runtime, v: &Value, builtin: &str) -> Result<Self, String> {
+        if v.is_int() {
+            Ok(v.as_i64() as f64)
+        } else if v.is_f64() {
+            Ok(v.as_f64())
+        } else {
+            Err(format!("{builtin} expects number, got {}", v.type_name()))
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(_rt: &Runtime, v: &Value, builtin: &str) -> Result<Self, String> {
+        if v.is_bool() { Ok(v.as_bool()) } else { Err(format!("{builtin} expects bool, got {}", v.type_name())) }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(rt: &Runtime, v: &Value, builtin: &str) -> Result<Self, String> {
+        if v.get_tag() == TAG_STR {
+            if let ManagedObject::Str(s) = rt.heap.get(v.as_obj_id()) {
+                return Ok(s.as_str().to_string());
+            }
+        }
+        Err(format!("{builtin} expects text, got {}", v.type_name()))
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(rt: &Runtime, v: &Value, builtin: &str) -> Result<Self, String> {
+        if v.get_tag() == TAG_LIST {
+            if let ManagedObject::List(items) = rt.heap.get(v.as_obj_id()) {
+                return items.iter().map(|item| T::from_value(rt, item, builtin)).collect();
+            }
+        }
+        Err(format!("{builtin} expects a list, got {}", v.type_name()))
+    }
+}
+
+/// An argument the caller may leave out; only trailing parameters can be
+/// optional.
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(rt: &Runtime, v: &Value, builtin: &str) -> Result<Self, String> {
+        T::from_value(rt, v, builtin).map(Some)
+    }
+
+    fn from_arg(rt: &Runtime, v: Option<&Value>, builtin: &str) -> Result<Self, String> {
+        v.map(|v| T::from_value(rt, v, builtin)).transpose()
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self, _rt: &mut Runtime) -> Result<Value, String> {
+        Ok(self)
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self, _rt: &mut Runtime) -> Result<Value, String> {
+        Ok(Value::UNIT)
+    }
+}
+
+impl IntoValue for i64 {
+    fn into_value(self, _rt: &mut Runtime) -> Result<Value, String> {
+        Ok(Value::from_i64(self))
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self, _rt: &mut Runtime) -> Result<Value, String> {
+        Ok(Value::from_f64(self))
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self, _rt: &mut Runtime) -> Result<Value, String> {
+        Ok(Value::from_bool(self))
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self, rt: &mut Runtime) -> Result<Value, String> {
+        Ok(Value::str(rt.alloc(ManagedObject::Str(self.into()))))
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self, rt: &mut Runtime) -> Result<Value, String> {
+        Ok(Value::str(rt.alloc(ManagedObject::Str(self.into()))))
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self, rt: &mut Runtime) -> Result<Value, String> {
+        let items = self.into_iter().map(|item| item.into_value(rt)).collect::<Result<_, _>>()?;
+        Ok(Value::list(rt.alloc(ManagedObject::List(items))))
+    }
+}
+
+/// `Option#some` or `Option#none`.
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self, rt: &mut Runtime) -> Result<Value, String> {
+        match self {
+            Some(v) => {
+                let v = v.into_value(rt)?;
+                Ok(rt.option_some(v))
+            }
+            None => Ok(rt.option_none()),
+        }
+    }
+}
+
+/// An `Err` is raised as a runtime error.
+impl<T: IntoValue> IntoValue for Result<T, String> {
+    fn into_value(self, rt: &mut Runtime) -> Result<Value, String> {
+        self?.into_value(rt)
+    }
+}
@@ -0,0 +1,69 @@
+//! Builtin packs shipped as separate crates.
+//!
+//! A plugin crate such as `xu-plugin-redis` depends on `xu_runtime` with the
+//! `plugins` feature, implements [`BuiltinProvider`] and registers it once:
+//!
+//! ```ignore
+//! use xu_runtime::{BuiltinProvider, BuiltinRegistry};
+//!
+//! pub struct Redis;
+//!
+//! impl BuiltinProvider for Redis {
+//!     fn install(&self, registry: &mut BuiltinRegistry) {
+//!         registry.register("redis_get", redis_get);
+//!     }
+//! }
+//!
+//! xu_runtime::xu_plugin!("redis", Redis);
+//! ```
+//!
+//! Every [`Runtime`](crate::Runtime) of a program linking the crate installs
+//! its builtins after the standard ones, including the runtimes of workers.
+//! Plugins are installed in name order, so when two register the same
+//! builtin the later name wins.
+
+use crate::builtins_registry::{BuiltinProvider, BuiltinRegistry};
+
+#[doc(hidden)]
+pub use inventory;
+
+/// A registered builtin pack.
+pub struct Plugin {
+    pub name: &'static str,
+    /// Version of the crate that registered the plugin.
+    pub version: &'static str,
+    pub provider: &'static (dyn BuiltinProvider + Sync),
+}
+
+inventory::collect!(Plugin);
+
+/// Registers the [`BuiltinProvider`] `provider`, a constant expression, as
+/// the plugin `name` of the calling crate.
+#[macro_export]
+macro_rules! xu_plugin {
+    ($name:expr, $provider:expr $(,)?) => {
+        $crate::plugin::inventory::submit! {
+            $crate::plugin::Plugin {
+                name: $name,
+                version: env!("CARGO_PKG_VERSION"),
+                provider: &$provider,
+            }
+        }
+    };
+}
+
+/// The plugins linked into this program, by name.
+pub fn plugins() -> Vec<&'static Plugin> {
+    let mut all: Vec<&'static Plugin> = inventory::iter::<Plugin>.into_iter().collect();
+    all.sort_by_key(|p| p.name);
+    all
+}
+
+/// Names of the builtins all plugins install, for strict analysis.
+pub fn builtin_names() -> Vec<String> {
+    let mut registry = BuiltinRegistry::new();
+    for plugin in plugins() {
+        plugin.provider.install(&mut registry);
+    }
+    registry.names()
+}
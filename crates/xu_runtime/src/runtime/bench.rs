@@ -0,0 +1,120 @@
+//! 基准测试子模块
+//! `bench "name": ...` 块由解析器脱糖为 `__bench(name, func)`。开启 bench 模式后，
+//! 每个块在出现的位置先预热、再计时运行若干次，结果按出现顺序收集；
+//! 未开启时块被跳过，`xu run` 不受影响。
+
+use crate::core::Value;
+
+use super::core::Runtime;
+
+/// How `bench` blocks run, set with [`Runtime::set_bench`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchConfig {
+    /// Untimed runs before measuring.
+    pub warmup: usize,
+    /// Timed runs; at least one is made.
+    pub iterations: usize,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self { warmup: 3, iterations: 20 }
+    }
+}
+
+/// Timings of one `bench` block, from [`Runtime::bench_results`]. Times come
+/// from the monotonic clock of the runtime's capabilities.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchResult {
+    pub name: String,
+    pub iterations: usize,
+    pub mean_ms: f64,
+    pub median_ms: f64,
+    /// Nearest-rank 99th percentile.
+    pub p99_ms: f64,
+    /// Heap allocations per timed run, rounded down.
+    pub allocations: u64,
+}
+
+impl BenchResult {
+    fn from_samples(name: String, samples_ns: &mut [i64], allocations: u64) -> Self {
+        samples_ns.sort_unstable();
+        let n = samples_ns.len();
+        let ms = |ns: f64| ns / 1_000_000.0;
+        let mean = samples_ns.iter().map(|&s| s as f64).sum::<f64>() / n as f64;
+        let median = if n % 2 == 1 {
+            samples_ns[n / 2] as f64
+        } else {
+            (samples_ns[n / 2 - 1] + samples_ns[n / 2]) as f64 / 2.0
+        };
+        let p99 = samples_ns[(n * 99).div_ceil(100) - 1] as f64;
+        Self {
+            name,
+            iterations: n,
+            mean_ms: ms(mean),
+            median_ms: ms(median),
+            p99_ms: ms(p99),
+            allocations: allocations / n as u64,
+        }
+    }
+}
+
+/// 开启 bench 模式时的配置与已完成的结果
+pub(crate) struct BenchRunner {
+    config: BenchConfig,
+    results: Vec<BenchResult>,
+}
+
+impl Runtime {
+    /// Runs `bench` blocks with `config` during later executions, or skips
+    /// them with `None` (the default).
+    pub fn set_bench(&mut self, config: Option<BenchConfig>) {
+        self.bench = config.map(|config| Box::new(BenchRunner { config, results: Vec::new() }));
+    }
+
+    /// Results of the `bench` blocks the current (or last) execution ran, in
+    /// the order they ran.
+    pub fn bench_results(&self) -> &[BenchResult] {
+        self.bench.as_ref().map_or(&[], |b| &b.results)
+    }
+
+    /// 新一次执行开始时清空上次的结果
+    pub(crate) fn reset_bench_results(&mut self) {
+        if let Some(b) = &mut self.bench {
+            b.results.clear();
+        }
+    }
+
+    /// `__bench` 的实现：未开启 bench 模式时什么都不做
+    pub(crate) fn run_bench(&mut self, name: String, func: Value) -> Result<(), String> {
+        let Some(config) = self.bench.as_ref().map(|b| b.config) else {
+            return Ok(());
+        };
+        // 迭代之间可能触发 GC，函数值需要作为临时根
+        let roots_base = self.gc_temp_roots.len();
+        self.gc_temp_roots.push(func);
+        let res = self.measure(name, func, config);
+        self.gc_temp_roots.truncate(roots_base);
+        let result = res?;
+        if let Some(b) = &mut self.bench {
+            b.results.push(result);
+        }
+        Ok(())
+    }
+
+    fn measure(&mut self, name: String, func: Value, config: BenchConfig) -> Result<BenchResult, String> {
+        for _ in 0..config.warmup {
+            self.call_function(func, &[])?;
+        }
+        let iterations = config.iterations.max(1);
+        let mut samples = Vec::with_capacity(iterations);
+        let allocations_before = self.heap.allocations();
+        for _ in 0..iterations {
+            let start = self.clock_mono_nanos();
+            self.call_function(func, &[])?;
+            samples.push(self.clock_mono_nanos() - start);
+        }
+        let allocations = self.heap.allocations() - allocations_before;
+        Ok(BenchResult::from_samples(name, &mut samples, allocations))
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! This module contains:
 //! - eval_binary: Evaluate binary operations
+//! - contains: The `in` membership test
 //! - values_equal: Check if two values are equal
 //! - values_equal_inner: Recursive equality check with cycle detection
 
@@ -10,10 +11,11 @@ use std::collections::HashSet;
 use xu_ir::BinaryOp;
 
 use crate::core::heap::ManagedObject;
-use crate::core::value::ValueExt;
+use crate::core::value::{TAG_DICT, TAG_LIST, TAG_RANGE, TAG_STR, TAG_TUPLE, ValueExt};
+use crate::methods::MethodKind;
 use crate::core::Value;
-use crate::runtime::Text;
-use crate::util::value_to_string;
+use crate::errors::messages::NOT_A_STRING;
+use crate::util::{Appendable, value_to_string};
 use crate::Runtime;
 
 impl Runtime {
@@ -30,24 +32,23 @@ impl Runtime {
         match op {
             BinaryOp::Eq => Ok(Value::from_bool(self.values_equal(&a, &b))),
             BinaryOp::Ne => Ok(Value::from_bool(!self.values_equal(&a, &b))),
+            BinaryOp::In => self.contains(b, a).map(Value::from_bool),
             BinaryOp::Add => {
                 let at = a.get_tag();
                 let bt = b.get_tag();
                 if at == crate::core::value::TAG_STR && bt == crate::core::value::TAG_STR {
-                    // Fast path: both are strings - use Text::concat2
-                    let ta = if let ManagedObject::Str(s) = self.heap.get(a.as_obj_id()) {
-                        s.clone()
-                    } else {
-                        Text::new()
-                    };
-                    let tb = if let ManagedObject::Str(s) = self.heap.get(b.as_obj_id()) {
-                        s.clone()
-                    } else {
-                        Text::new()
+                    // Fast path: both are strings
+                    let result = self.heap.concat_texts(a.as_obj_id(), b.as_obj_id()).unwrap_or_default();
+                    Ok(Value::str(self.alloc(ManagedObject::Str(result))))
+                } else if at == crate::core::value::TAG_STR {
+                    // 追加到原文本之后，长文本由此累积成 rope
+                    let mut result = match self.heap.get(a.as_obj_id()) {
+                        ManagedObject::Str(sa) => sa.clone(),
+                        _ => return Err(NOT_A_STRING.into()),
                     };
-                    let result = Text::concat2(&ta, &tb);
+                    result.append_value(&b, &self.heap);
                     Ok(Value::str(self.alloc(ManagedObject::Str(result))))
-                } else if at == crate::core::value::TAG_STR || bt == crate::core::value::TAG_STR {
+                } else if bt == crate::core::value::TAG_STR {
                     let sa = value_to_string(&a, &self.heap);
                     let sb = value_to_string(&b, &self.heap);
                     // Pre-allocate capacity to avoid intermediate allocations
@@ -95,6 +96,39 @@ impl Runtime {
         }
     }
 
+    /// `item in coll`: whether `coll` is a range holding `item`, a list or
+    /// tuple with an element equal to it, a dict with it as a key, or a
+    /// string with it as a substring.
+    pub(crate) fn contains(&mut self, coll: Value, item: Value) -> Result<bool, String> {
+        match coll.get_tag() {
+            TAG_RANGE => match self.heap.get(coll.as_obj_id()) {
+                ManagedObject::Range(start, end, inclusive) => Ok(in_range(item, *start, *end, *inclusive)),
+                _ => Ok(false),
+            },
+            TAG_LIST | TAG_TUPLE => match self.heap.get(coll.as_obj_id()).elements() {
+                Some(items) => Ok(items.iter().any(|v| self.values_equal(v, &item))),
+                None => Ok(false),
+            },
+            TAG_DICT => {
+                let found =
+                    crate::methods::dispatch_builtin_method(self, coll, MethodKind::Contains, &[item], "contains")?;
+                Ok(found.is_bool() && found.as_bool())
+            }
+            TAG_STR => {
+                if item.get_tag() != TAG_STR {
+                    return Err(format!("'in' on a string needs a string, got {}", item.type_name()));
+                }
+                match (self.heap.get(coll.as_obj_id()), self.heap.get(item.as_obj_id())) {
+                    (ManagedObject::Str(hay), ManagedObject::Str(needle)) => {
+                        Ok(hay.as_str().contains(needle.as_str()))
+                    }
+                    _ => Ok(false),
+                }
+            }
+            _ => Err(format!("'in' expects a range, list, tuple, dict or string, got {}", coll.type_name())),
+        }
+    }
+
     /// Check if two values are equal.
     #[inline(always)]
     pub(crate) fn values_equal(&self, a: &Value, b: &Value) -> bool {
@@ -151,14 +185,14 @@ impl Runtime {
                     true
                 }
                 (ManagedObject::Dict(a_dict), ManagedObject::Dict(b_dict)) => {
-                    if a_dict.map.len() != b_dict.map.len() {
+                    if a_dict.len() != b_dict.len() {
                         return false;
                     }
-                    for (k, av) in a_dict.map.iter() {
-                        let Some(bv) = b_dict.map.get(k) else {
+                    for (k, av) in a_dict.entries() {
+                        let Some(bv) = b_dict.get_entry(&k) else {
                             return false;
                         };
-                        if !self.values_equal_inner(av, bv, seen) {
+                        if !self.values_equal_inner(&av, &bv, seen) {
                             return false;
                         }
                     }
@@ -198,3 +232,18 @@ impl Runtime {
         }
     }
 }
+
+/// `x in start..end` (or `..=`): an int, or a float with an integral value,
+/// within the bounds. Anything else is not in an integer range.
+pub(crate) fn in_range(x: Value, start: i64, end: i64, inclusive: bool) -> bool {
+    let below_end = |v: f64| if inclusive { v <= end as f64 } else { v < end as f64 };
+    if x.is_int() {
+        let v = x.as_i64();
+        v >= start && if inclusive { v <= end } else { v < end }
+    } else if x.is_f64() {
+        let v = x.as_f64();
+        v.fract() == 0.0 && v >= start as f64 && below_end(v)
+    } else {
+        false
+    }
+}
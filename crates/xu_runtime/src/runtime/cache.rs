@@ -17,6 +17,10 @@ pub struct ICSlot {
     pub option_some_cached: Value,
     pub struct_ty_hash: u64,
     pub field_offset: Option<usize>,
+    /// Identity of the struct layout `field_offset` was computed from.
+    pub layout_id: usize,
+    /// `CacheManager::ic_generation` at the time the slot was filled.
+    pub generation: u64,
 }
 
 /// Inline cache slot for method call optimization.
@@ -29,6 +33,8 @@ pub struct MethodICSlot {
     pub cached_func: Value,
     pub cached_user: Option<std::rc::Rc<crate::core::value::UserFunction>>,
     pub cached_bytecode: Option<std::rc::Rc<crate::core::value::BytecodeFunction>>,
+    /// `CacheManager::ic_generation` at the time the slot was filled.
+    pub generation: u64,
 }
 
 /// Last dict cache entry for string keys.
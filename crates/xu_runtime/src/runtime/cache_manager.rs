@@ -5,6 +5,7 @@ use std::rc::Rc;
 use crate::core::Value;
 use crate::core::value::{FastHashMap, fast_map_new};
 use super::cache::{ICSlot, MethodICSlot, DictCacheLast, DictCacheIntLast, DictInsertCacheLast};
+use super::ic_stats::IcRecorder;
 
 type HashMap<K, V> = FastHashMap<K, V>;
 
@@ -32,6 +33,10 @@ pub struct CacheManager {
     pub ic_slots: Vec<ICSlot>,
     /// 方法内联缓存槽
     pub ic_method_slots: Vec<MethodICSlot>,
+    /// IC 代数：每次失效加一，代数不符的槽视为未命中
+    pub ic_generation: u64,
+    /// IC 命中统计（默认关闭）；GC 清空槽位时保留
+    pub(crate) ic_stats: Option<Box<IcRecorder>>,
     /// 字符串池（用于字符串驻留）
     pub string_pool: HashMap<String, Rc<String>>,
     /// 字节码字符串常量缓存
@@ -41,6 +46,8 @@ pub struct CacheManager {
     pub small_int_strings: Vec<Option<Value>>,
     /// 缓存的 Option::none 值
     pub cached_option_none: Option<Value>,
+    /// 缓存的空字符串值（Op::ConstEmptyStr）
+    pub cached_empty_str: Option<Value>,
     /// 字符串 Value 驻留缓存 (hash -> Value)
     /// 用于 split() 等操作产生的重复字符串
     pub string_value_intern: HashMap<u64, Value>,
@@ -57,10 +64,13 @@ impl CacheManager {
             dict_version_last: None,
             ic_slots: Vec::new(),
             ic_method_slots: Vec::new(),
+            ic_generation: 0,
+            ic_stats: None,
             string_pool: fast_map_new(),
             bytecode_string_cache: fast_map_new(),
             small_int_strings: Vec::new(),
             cached_option_none: None,
+            cached_empty_str: None,
             string_value_intern: fast_map_new(),
         }
     }
@@ -74,8 +84,23 @@ impl CacheManager {
         self.dict_version_last = None;
         self.ic_slots.clear();
         self.ic_method_slots.clear();
-        // 注意：string_pool, bytecode_string_cache, small_int_strings, string_value_intern 不重置
-        // 因为它们可以跨执行复用
+        if let Some(stats) = &mut self.ic_stats {
+            **stats = IcRecorder::new();
+        }
+        // 新的执行会重建堆，缓存的 Value 指向旧堆对象，必须一并清空
+        self.bytecode_string_cache.clear();
+        self.small_int_strings.clear();
+        self.cached_option_none = None;
+        self.cached_empty_str = None;
+        self.string_value_intern.clear();
+        // 注意：string_pool 只持有 Rc<String>，不依赖堆，可以跨执行复用
+    }
+
+    /// 使代码相关缓存失效（模块重载、结构体布局变化后调用）
+    /// 方法缓存与 IC 槽可能持有旧函数或旧结构体布局，递增代数即可让所有槽失效
+    pub fn invalidate_code_caches(&mut self) {
+        self.method_cache.clear();
+        self.ic_generation = self.ic_generation.wrapping_add(1);
     }
 }
 
@@ -1,23 +1,113 @@
 //! Runtime configuration and result types.
 
 use crate::core::Value;
+use crate::core::dict_hash::DictHashing;
+use crate::core::heap::PoolLimits;
+use crate::util::FloatFormat;
 
 /// Result of executing a program or module.
 #[derive(Debug)]
 pub struct ExecResult {
     pub value: Option<Value>,
     pub output: String,
+    /// What the script wrote to standard error, with `eprint`.
+    pub error_output: String,
+    /// Set when output exceeded the runtime's output limit; `output` is then empty.
+    pub overflow: Option<OutputOverflow>,
+    /// The code the script passed to `exit`, if it called it.
+    pub exit_code: Option<i32>,
+}
+
+/// Where output beyond the output limit went.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputOverflow {
+    /// Written to a temporary file holding the complete output.
+    Spilled {
+        path: std::path::PathBuf,
+        bytes: u64,
+    },
+    /// Could not be written anywhere and was discarded.
+    Truncated { dropped: u64 },
+}
+
+/// Outcome of [`Runtime::compact_heap`](crate::Runtime::compact_heap).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeapCompaction {
+    /// Objects alive after the collection.
+    pub live: usize,
+    /// Objects that moved to a lower slot.
+    pub moved: usize,
+    /// Heap slots before the collection.
+    pub slots_before: usize,
+    /// Heap slots after compaction.
+    pub slots_after: usize,
+}
+
+/// Garbage collector activity since the current (or last) execution
+/// started, from [`Runtime::gc_stats`](crate::Runtime::gc_stats). Byte counts
+/// are estimates of object sizes, not allocator totals.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// Collections run, automatic and explicit.
+    pub collections: u64,
+    pub objects_freed: u64,
+    pub bytes_freed: u64,
+    /// Objects alive after the last collection.
+    pub live_objects: usize,
+    pub live_bytes: usize,
+    /// Time spent collecting, in total and in the longest collection.
+    pub total_pause: std::time::Duration,
+    pub max_pause: std::time::Duration,
+    /// Allocations served from buffers that collections recycled (see
+    /// [`RuntimeConfig::pool_limits`]).
+    pub pool_reuses: u64,
 }
 
 /// Runtime configuration options.
 #[derive(Clone, Copy, Debug)]
 pub struct RuntimeConfig {
     pub strict_vars: bool,
+    /// Check values stored in struct fields against the fields' declared
+    /// types, when a struct is built and when a field is assigned.
+    pub strict_fields: bool,
+    /// How floats are printed by `print`, interpolation and `to_text`.
+    pub float_format: FloatFormat,
+    /// Compact the heap when `exec_*` or `call_global` returns, if a
+    /// collection since the last compaction left more free slots than live
+    /// objects. Values the host holds from earlier calls are then invalid.
+    pub heap_compaction: bool,
+    /// Dict key hashing. Keyed (the default) resists hash flooding from
+    /// untrusted keys; fixed hashing is deterministic across runs.
+    pub dict_hashing: DictHashing,
+    /// Threads `parallel_map` uses; 0 means one per available CPU.
+    pub parallel_workers: usize,
+    /// Allocations between collections. 0 (the default) starts at 100 000
+    /// and adapts to the live heap after each collection; a lower value
+    /// trades speed for a smaller heap. The byte-based trigger (128 MB at
+    /// first, then scaled to the live heap) applies either way.
+    pub gc_threshold: usize,
+    /// Freed buffers kept per kind for reuse by new lists, dicts and texts.
+    pub pool_limits: PoolLimits,
+    /// Calls after which a bytecode function is compiled to native code, if
+    /// the JIT handles every operation in it; 0 turns the JIT off.
+    #[cfg(feature = "jit")]
+    pub jit_threshold: u32,
 }
 
 impl Default for RuntimeConfig {
     fn default() -> Self {
-        Self { strict_vars: true }
+        Self {
+            strict_vars: true,
+            strict_fields: true,
+            float_format: FloatFormat::default(),
+            heap_compaction: false,
+            dict_hashing: DictHashing::default(),
+            parallel_workers: 0,
+            gc_threshold: 0,
+            pool_limits: PoolLimits::default(),
+            #[cfg(feature = "jit")]
+            jit_threshold: 1000,
+        }
     }
 }
 
@@ -28,4 +118,6 @@ pub enum Flow {
     Break,
     Continue,
     Throw(Value),
+    /// `exit(code)` unwinding to the top of the program.
+    Exit(i32),
 }
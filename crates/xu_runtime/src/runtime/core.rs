@@ -1,8 +1,6 @@
 use std::hash::{BuildHasher, Hash, Hasher};
 use std::rc::Rc;
-use std::time::{SystemTime, UNIX_EPOCH};
 
-use indexmap::map::RawEntryApiV1;
 use xu_ir::{Executable, Expr, Module, Stmt};
 
 use crate::core::Value;
@@ -37,9 +35,33 @@ pub struct Runtime {
     #[cfg(feature = "generational-gc")]
     pub(crate) gen_heap: crate::core::generational_heap::GenerationalHeap,
     caps: capabilities::Capabilities,
+    /// 录制或回放中时，包住 `caps` 输入能力的记录器
+    tap: Option<capabilities::Tap>,
+    /// bench 模式下的配置与结果；`None` 时跳过 `bench` 块
+    pub(crate) bench: Option<Box<super::bench::BenchRunner>>,
     pub(crate) output: String,
+    pub(crate) output_limit: Option<usize>,
+    pub(crate) output_spill: super::output::OutputSpill,
+    /// 标准错误输出缓冲（`eprint`）
+    pub(crate) error_output: String,
+    /// 设置后两路输出直接写入，不再缓冲
+    pub(crate) output_streams: Option<Box<dyn super::output::OutputSink>>,
     pub(crate) main_invoked: bool,
+    /// `exit` 请求的退出码；设置期间出现的错误都是在向顶层展开
+    pub(crate) exit_request: Option<i32>,
+    /// `?` 遇到 err/none 时要返回的值；设置期间出现的错误都是在向所在函数展开
+    pub(crate) try_return: Option<Value>,
+    /// `?.` / `?[` 遇到 none；设置期间出现的错误都是在向所在的 OptChain 展开
+    pub(crate) opt_chain_none: bool,
+    /// 本次执行是否因 `panic` 失败
+    pub(crate) panicked: bool,
     pub(crate) call_stack_depth: usize,
+    /// 最近一次运行时错误经过的字节码位置，由内向外
+    pub(crate) error_trace: Vec<super::traceback::TraceFrame>,
+    /// 宿主的中断请求，在安全点检查
+    pub(crate) interrupt: super::interrupt::InterruptHandle,
+    /// `on_signal` 登记的处理函数与待处理的信号
+    pub(crate) signals: super::signal::SignalHandlers,
     rng_state: u64,
     pub(crate) config: RuntimeConfig,
 
@@ -57,7 +79,7 @@ pub struct Runtime {
 
     // ==================== 模块系统 ====================
     pub(crate) module_loader: Box<dyn modules::ModuleLoader>,
-    pub(crate) frontend: Option<Box<dyn xu_ir::Frontend>>,
+    pub(crate) frontend: Option<std::sync::Arc<dyn xu_ir::Frontend>>,
     pub(crate) loaded_modules: HashMap<String, Value>,
     pub(crate) import_parse_cache: HashMap<String, modules::ImportParseCacheEntry>,
     pub(crate) import_stack: Vec<String>,
@@ -75,13 +97,39 @@ pub struct Runtime {
     // ==================== 其他配置 ====================
     pub(crate) stdlib_path: Option<String>,
     pub(crate) args: Vec<String>,
-    predefined_constants: HashMap<String, String>,
+    pub(crate) predefined_constants: HashMap<String, String>,
+    /// 宿主注册的函数，每次执行前与内置函数一起定义
+    pub(crate) host_functions: Vec<(String, crate::core::value::HostFunction)>,
+    /// 宿主添加的内置函数提供者，安装在标准库和插件之后
+    pub(crate) builtin_providers: Vec<Rc<dyn builtins_registry::BuiltinProvider>>,
+    /// 预导入模块路径，其导出注入每个模块的作用域
+    pub(crate) prelude: Option<String>,
+    pub(crate) prelude_exports: Vec<(String, Value)>,
 
     // ==================== GC 相关 ====================
     /// 临时 GC 根（用于正在求值的值）
     pub(crate) gc_temp_roots: Vec<Value>,
     /// 需要 GC 保护的活动 VM 栈
     pub(crate) active_vm_stacks: Vec<*const Vec<Value>>,
+    /// 被函数调用或模块导入暂时替换掉的环境（调用方的绑定仍需保活）
+    pub(crate) suspended_envs: Vec<Env>,
+    /// 正在执行的入口层数（exec_* 与 call_global）；非零时不能压缩堆
+    pub(crate) exec_depth: usize,
+    /// 上次压缩堆时的 GC 轮次
+    pub(crate) compacted_at_cycle: u64,
+    /// 本次执行以来的 GC 统计
+    pub(crate) gc_stats: super::config::GcStats,
+
+    // ==================== 工作线程 ====================
+    /// 本运行时启动的工作线程，以及（在工作线程中）通往父线程的通道
+    pub(crate) workers: crate::builtins::worker::Workers,
+    /// `std/ffi` 打开的本地库
+    pub(crate) ffi: crate::builtins::ffi::Libraries,
+    /// `std/template` 编译过的模板
+    pub(crate) templates: crate::builtins::template::TemplateCache,
+    /// 热函数的调用计数与机器码
+    #[cfg(feature = "jit")]
+    pub(crate) jit: crate::jit::Jit,
 }
 
 impl Runtime {
@@ -91,19 +139,29 @@ impl Runtime {
 
     pub fn with_config(config: RuntimeConfig) -> Self {
         let env = Env::new();
-        let seed = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_nanos() as u64)
-            .unwrap_or(1);
+        let seed = crate::util::time_seed();
         let mut rt = Self {
             env,
             heap: crate::core::heap::Heap::new(),
             #[cfg(feature = "generational-gc")]
             gen_heap: crate::core::generational_heap::GenerationalHeap::new(),
             caps: capabilities::Capabilities::default(),
+            tap: None,
+            bench: None,
             output: String::new(),
+            output_limit: None,
+            output_spill: Default::default(),
+            error_output: String::new(),
+            output_streams: None,
             main_invoked: false,
+            exit_request: None,
+            try_return: None,
+            opt_chain_none: false,
+            panicked: false,
             call_stack_depth: 0,
+            error_trace: Vec::new(),
+            interrupt: Default::default(),
+            signals: Default::default(),
             rng_state: seed,
             config,
             // 类型系统
@@ -130,10 +188,27 @@ impl Runtime {
             stdlib_path: None,
             args: Vec::new(),
             predefined_constants: fast_map_new(),
+            host_functions: Vec::new(),
+            builtin_providers: Vec::new(),
+            prelude: None,
+            prelude_exports: Vec::new(),
             // GC 相关
             gc_temp_roots: Vec::new(),
             active_vm_stacks: Vec::new(),
+            suspended_envs: Vec::new(),
+            exec_depth: 0,
+            compacted_at_cycle: 0,
+            gc_stats: Default::default(),
+            workers: Default::default(),
+            ffi: Default::default(),
+            templates: Default::default(),
+            #[cfg(feature = "jit")]
+            jit: crate::jit::Jit::new(),
         };
+        rt.heap.set_gc_threshold(config.gc_threshold);
+        rt.heap.set_pool_limits(config.pool_limits);
+        rt.heap.set_dict_hashing(config.dict_hashing);
+        rt.heap.float_format = config.float_format;
         rt.install_builtins();
         rt
     }
@@ -239,6 +314,16 @@ impl Runtime {
         v
     }
 
+    /// The shared empty string pushed by `Op::ConstEmptyStr`
+    pub(crate) fn empty_str(&mut self) -> Value {
+        if let Some(v) = self.caches.cached_empty_str {
+            return v;
+        }
+        let v = Value::str(self.alloc(crate::core::heap::ManagedObject::Str(crate::Text::from_str(""))));
+        self.caches.cached_empty_str = Some(v);
+        v
+    }
+
     /// Get cached string Value for small integers (0-99999)
     /// Returns None if the integer is out of range
     /// Uses lazy initialization to avoid upfront memory allocation
@@ -354,14 +439,17 @@ impl Runtime {
         h.finish()
     }
 
-    pub(crate) fn dict_get_by_str_with_hash(me: &Dict, key: &str, _key_hash: u64) -> Option<Value> {
-        let dict_key_hash = DictKey::hash_str(key);
+    pub(crate) fn dict_get_by_str_with_hash(
+        heap: &crate::core::heap::Heap,
+        me: &Dict,
+        key: &str,
+        _key_hash: u64,
+    ) -> Option<Value> {
+        let dict_key_hash = DictKey::hash_str(key, heap);
         // Compute HashMap hash from DictKey hash
         let hash = super::dict_helpers::compute_map_hash(me.map.hasher(), dict_key_hash);
 
-        me.map
-            .raw_entry_v1()
-            .from_hash(hash, |k| {
+        me.map.get_by_hash(hash, |k| {
                 // Compare by hash only - hash collision is rare
                 if let DictKey::StrRef { hash: kh, .. } = k {
                     *kh == dict_key_hash
@@ -418,16 +506,105 @@ impl Runtime {
         }
     }
 
+    /// `?` 的操作数：ok/some 时为其载荷，err/none 时为 `None`（由所在函数原样返回）
+    pub(crate) fn try_payload(&self, v: Value) -> Result<Option<Value>, String> {
+        use crate::core::heap::ManagedObject;
+        use crate::core::value::{TAG_ENUM, TAG_OPTION};
+        let tag = v.get_tag();
+        if tag == TAG_OPTION {
+            if let ManagedObject::OptionSome(inner) = self.heap.get(v.as_obj_id()) {
+                return Ok(Some(*inner));
+            }
+        } else if tag == TAG_ENUM {
+            if let ManagedObject::Enum(e) = self.heap.get(v.as_obj_id()) {
+                let (ty, variant, payload) = e.as_ref();
+                match (ty.as_str(), variant.as_str()) {
+                    ("Result", "ok") | ("Option", "some") => {
+                        return Ok(Some(payload.first().copied().unwrap_or(Value::UNIT)));
+                    }
+                    ("Result", "err") | ("Option", "none") => return Ok(None),
+                    _ => {}
+                }
+            }
+        }
+        Err(self.error(xu_syntax::DiagnosticKind::TryOnNonResult(v.type_name().to_string())))
+    }
+
+    /// `?.`、`?[` 与 `??` 看到的值：none 为 `None`，some 为其载荷，其他值原样
+    pub(crate) fn present_value(&self, v: Value) -> Option<Value> {
+        use crate::core::heap::ManagedObject;
+        match v.get_tag() {
+            crate::core::value::TAG_OPTION => match self.heap.get(v.as_obj_id()) {
+                ManagedObject::OptionSome(inner) => Some(*inner),
+                _ => Some(v),
+            },
+            crate::core::value::TAG_ENUM => match self.heap.get(v.as_obj_id()) {
+                ManagedObject::Enum(e) if e.0.as_str() == "Option" => match e.1.as_str() {
+                    "none" => None,
+                    _ => Some(e.2.first().copied().unwrap_or(Value::UNIT)),
+                },
+                _ => Some(v),
+            },
+            _ => Some(v),
+        }
+    }
+
     pub fn set_clock(&mut self, clock: Box<dyn capabilities::Clock>) {
-        self.caps.clock = clock;
+        self.caps.clock = clock.into();
     }
 
     pub fn set_file_system(&mut self, fs: Box<dyn capabilities::FileSystem>) {
-        self.caps.fs = fs;
+        self.caps.fs = fs.into();
     }
 
     pub fn set_rng_algorithm(&mut self, rng: Box<dyn capabilities::RngAlgorithm>) {
-        self.caps.rng = rng;
+        self.caps.rng = rng.into();
+    }
+
+    pub fn set_env_vars(&mut self, env: Box<dyn capabilities::EnvVars>) {
+        self.caps.env = env.into();
+    }
+
+    /// The terminal `std/term` colors output for, and `input` and
+    /// `std/term` read answers from.
+    pub fn set_terminal(&mut self, terminal: Box<dyn capabilities::Terminal>) {
+        self.caps.terminal = terminal;
+    }
+
+    /// Where the signals handled with `on_signal` come from besides
+    /// [`Runtime::signal_handle`]; `xu run` installs
+    /// [`OsSignals`](crate::OsSignals).
+    pub fn set_signal_source(&mut self, signals: Box<dyn capabilities::SignalSource>) {
+        self.caps.signals = signals;
+    }
+
+    /// Records every clock read, random draw, file operation and environment
+    /// variable the script receives from here on; see [`Runtime::recorded_trace`].
+    /// Install custom capabilities before starting, since the recording wraps
+    /// the ones in place.
+    pub fn start_recording(&mut self) {
+        self.tap = Some(capabilities::Tap::record(&mut self.caps));
+    }
+
+    /// The inputs recorded since [`Runtime::start_recording`], or `None` when
+    /// not recording.
+    pub fn recorded_trace(&self) -> Option<capabilities::Trace> {
+        match &self.tap {
+            Some(tap @ capabilities::Tap::Record(_)) => Some(tap.trace()),
+            _ => None,
+        }
+    }
+
+    /// Answers the script's inputs from `trace` instead of the installed
+    /// capabilities, which only serve inputs the trace lacks.
+    pub fn replay_trace(&mut self, trace: capabilities::Trace) {
+        self.tap = Some(capabilities::Tap::replay(&mut self.caps, trace));
+    }
+
+    /// Inputs the replayed trace had no answer for. Non-empty means the run
+    /// diverged from the recording.
+    pub fn replay_misses(&self) -> Vec<String> {
+        self.tap.as_ref().map(capabilities::Tap::misses).unwrap_or_default()
     }
 
     pub fn set_module_loader(&mut self, loader: Box<dyn modules::ModuleLoader>) {
@@ -435,7 +612,7 @@ impl Runtime {
     }
 
     pub fn set_frontend(&mut self, frontend: Box<dyn xu_ir::Frontend>) {
-        self.frontend = Some(frontend);
+        self.frontend = Some(std::sync::Arc::from(frontend));
     }
 
     pub fn clear_allowed_roots(&mut self) {
@@ -466,6 +643,161 @@ impl Runtime {
         false
     }
 
+    /// Installs the policy that approves or denies `with_capability` requests.
+    pub fn set_capability_policy(&mut self, policy: Box<dyn capabilities::CapabilityPolicy>) {
+        self.caps.policy = policy.into();
+    }
+
+    /// Capability requests and the accesses made under granted capabilities.
+    pub fn capability_audit_log(&self) -> &[capabilities::CapabilityAuditEntry] {
+        &self.caps.audit_log
+    }
+
+    pub fn take_capability_audit_log(&mut self) -> Vec<capabilities::CapabilityAuditEntry> {
+        std::mem::take(&mut self.caps.audit_log)
+    }
+
+    /// What a worker of this runtime starts with: the shared capabilities and
+    /// the recording or replay in progress.
+    pub(crate) fn worker_capabilities(&self) -> (capabilities::SharedCapabilities, Option<capabilities::Tap>) {
+        (self.caps.share(), self.tap.clone())
+    }
+
+    pub(crate) fn install_worker_capabilities(
+        &mut self,
+        shared: capabilities::SharedCapabilities,
+        tap: Option<capabilities::Tap>,
+    ) {
+        self.caps = capabilities::Capabilities::from_shared(shared);
+        self.tap = tap;
+    }
+
+    /// Adds what a finished worker requested and accessed to the audit log.
+    pub(crate) fn extend_capability_audit_log(&mut self, entries: Vec<capabilities::CapabilityAuditEntry>) {
+        self.caps.audit_log.extend(entries);
+    }
+
+    /// Asks the policy for `capability`; on approval the grant stays active
+    /// until the matching `release_capability`.
+    pub(crate) fn request_capability(&mut self, capability: &str) -> bool {
+        let granted = self.caps.policy.approve(capability);
+        self.caps.audit_log.push(capabilities::CapabilityAuditEntry {
+            capability: capability.to_string(),
+            target: None,
+            granted,
+        });
+        if granted {
+            self.caps.grants.push(capability.to_string());
+        }
+        granted
+    }
+
+    pub(crate) fn release_capability(&mut self, capability: &str) {
+        if let Some(pos) = self.caps.grants.iter().rposition(|g| g == capability) {
+            self.caps.grants.remove(pos);
+        }
+    }
+
+    /// Checks a file read against the allowed roots, falling back to an
+    /// active `fs.read` grant.
+    fn check_fs_read(&mut self, path: &str) -> Result<(), String> {
+        if self.caps.allowed_roots.is_empty() {
+            return Ok(());
+        }
+        let abs = self
+            .caps
+            .fs
+            .canonicalize(path)
+            .unwrap_or_else(|_| path.to_string());
+        self.check_fs_grant(abs, capabilities::FS_READ)
+    }
+
+    /// Like `check_fs_read`, for a file that may not exist yet. An existing
+    /// file is checked where its symbolic links lead, a new one by its
+    /// canonicalized parent directory. Writing through a dangling link would
+    /// create its target wherever it points, so that needs an `fs.write`
+    /// grant.
+    fn check_fs_write(&mut self, path: &str) -> Result<(), String> {
+        if self.caps.allowed_roots.is_empty() {
+            return Ok(());
+        }
+        if let Ok(abs) = self.caps.fs.canonicalize(path) {
+            return self.check_fs_grant(abs, capabilities::FS_WRITE);
+        }
+        if self.caps.fs.is_symlink(path) {
+            return self.require_fs_grant(path.to_string(), capabilities::FS_WRITE);
+        }
+        let p = std::path::Path::new(path);
+        let parent = match p.parent() {
+            Some(d) if !d.as_os_str().is_empty() => d.to_string_lossy().to_string(),
+            _ => ".".to_string(),
+        };
+        let abs = match (self.caps.fs.canonicalize(&parent), p.file_name()) {
+            (Ok(dir), Some(name)) => std::path::Path::new(&dir)
+                .join(name)
+                .to_string_lossy()
+                .to_string(),
+            _ => path.to_string(),
+        };
+        self.check_fs_grant(abs, capabilities::FS_WRITE)
+    }
+
+    fn check_fs_grant(&mut self, abs: String, capability: &str) -> Result<(), String> {
+        if self.path_allowed(&abs) {
+            return Ok(());
+        }
+        self.require_fs_grant(abs, capability)
+    }
+
+    fn require_fs_grant(&mut self, abs: String, capability: &str) -> Result<(), String> {
+        let granted = self.caps.is_granted(capability);
+        self.caps.audit_log.push(capabilities::CapabilityAuditEntry {
+            capability: capability.to_string(),
+            target: Some(abs),
+            granted,
+        });
+        if granted {
+            Ok(())
+        } else {
+            Err(self.error(xu_syntax::DiagnosticKind::PathNotAllowed))
+        }
+    }
+
+    /// Checks a foreign call into `target` (a library, or `library:function`):
+    /// refused in a runtime with allowed roots, else allowed under an active
+    /// `ffi` grant.
+    pub(crate) fn check_ffi_grant(&mut self, target: String) -> Result<(), String> {
+        if !self.caps.allowed_roots.is_empty() {
+            return Err(format!("ffi: native calls are disabled when file access is restricted ({target})"));
+        }
+        let granted = self.caps.is_granted(capabilities::FFI);
+        self.caps.audit_log.push(capabilities::CapabilityAuditEntry {
+            capability: capabilities::FFI.to_string(),
+            target: Some(target),
+            granted,
+        });
+        if granted {
+            Ok(())
+        } else {
+            Err(self.error(xu_syntax::DiagnosticKind::CapabilityDenied(capabilities::FFI.to_string())))
+        }
+    }
+
+    /// Checks `on_signal` against an active `signal` grant.
+    pub(crate) fn check_signal_grant(&mut self, signal: &str) -> Result<(), String> {
+        let granted = self.caps.is_granted(capabilities::SIGNAL);
+        self.caps.audit_log.push(capabilities::CapabilityAuditEntry {
+            capability: capabilities::SIGNAL.to_string(),
+            target: Some(signal.to_string()),
+            granted,
+        });
+        if granted {
+            Ok(())
+        } else {
+            Err(self.error(xu_syntax::DiagnosticKind::CapabilityDenied(capabilities::SIGNAL.to_string())))
+        }
+    }
+
     pub(crate) fn canonicalize_import_checked(&self, path: &str) -> Result<String, String> {
         let p = std::path::Path::new(path);
 
@@ -491,6 +823,13 @@ impl Runtime {
         self.stdlib_path = Some(path);
     }
 
+    /// Sets a prelude module, imported before each entry execution. Its exports
+    /// are defined in the scope of the entry module and of every imported
+    /// module, like builtins. The path is resolved like a `use` path.
+    pub fn set_prelude(&mut self, path: &str) {
+        self.prelude = Some(path.to_string());
+    }
+
     pub fn set_args(&mut self, args: Vec<String>) {
         self.args = args;
     }
@@ -503,10 +842,14 @@ impl Runtime {
         self.config.strict_vars = enabled;
     }
 
+    pub fn set_float_format(&mut self, format: crate::util::FloatFormat) {
+        self.config.float_format = format;
+        self.heap.float_format = format;
+    }
+
     pub fn set_entry_path(&mut self, path: &str) -> Result<(), String> {
-        let canonical =
-            std::fs::canonicalize(path).map_err(|e| format!("Failed to set entry path: {e}"))?;
-        self.entry_path = Some(canonical.to_string_lossy().to_string());
+        let canonical = self.caps.fs.canonicalize(path).map_err(|e| format!("Failed to set entry path: {e}"))?;
+        self.entry_path = Some(canonical);
         Ok(())
     }
 
@@ -514,6 +857,23 @@ impl Runtime {
         self.env.get(name).is_some()
     }
 
+    /// Calls a top-level function of the last executed program. Useful for
+    /// hosts that keep a runtime alive and drive script callbacks.
+    pub fn call_global(&mut self, name: &str, args: &[Value]) -> Result<Value, String> {
+        let Some(f) = self.env.get(name) else {
+            return Err(self.error(xu_syntax::DiagnosticKind::UndefinedIdentifier(name.to_string())));
+        };
+        if f.get_tag() != crate::core::value::TAG_FUNC {
+            return Err(self.error(xu_syntax::DiagnosticKind::NotCallable(name.to_string())));
+        }
+        self.exec_depth += 1;
+        let res = self.call_function(f, args);
+        self.exec_depth -= 1;
+        let mut value = res?;
+        self.maybe_compact_heap(std::slice::from_mut(&mut value));
+        Ok(value)
+    }
+
     pub(crate) fn get_constant<'a>(
         &self,
         idx: u32,
@@ -547,23 +907,64 @@ impl Runtime {
         match flow {
             Flow::None => {
                 self.invoke_main_if_present()?;
-                Ok(ExecResult { value: None, output: std::mem::take(&mut self.output) })
+                Ok(self.exec_result(None))
             }
-            Flow::Return(v) => Ok(ExecResult { value: Some(v), output: std::mem::take(&mut self.output) }),
+            Flow::Return(v) => Ok(self.exec_result(Some(v))),
             Flow::Throw(v) => Err(self.format_throw(&v)),
+            Flow::Exit(code) => Err(exit_message(code)),
             Flow::Break | Flow::Continue => Err(self.error(xu_syntax::DiagnosticKind::TopLevelBreakContinue)),
         }
     }
 
     pub fn exec_module(&mut self, module: &Module) -> Result<ExecResult, String> {
+        self.exec_depth += 1;
+        let res = self.exec_module_inner(module);
+        self.exec_depth -= 1;
+        self.finish_entry(res)
+    }
+
+    fn exec_module_inner(&mut self, module: &Module) -> Result<ExecResult, String> {
         self.reset_for_entry_execution();
         self.compiled_locals = Self::collect_func_locals(module);
         self.compiled_locals_idx = Self::index_func_locals(&self.compiled_locals);
+        self.install_prelude()?;
         Self::precompile_module(module)?;
         let flow = self.exec_stmts(&module.stmts);
         self.handle_exec_flow(flow)
     }
 
+    /// 只执行模块中的声明（use、结构体、枚举、函数及 does 块），
+    /// 不运行其他顶层语句，也不调用 main。工作线程用它载入入口脚本。
+    pub(crate) fn load_declarations(&mut self, module: &Module) -> Result<(), String> {
+        let stmts: Box<[Stmt]> = module
+            .stmts
+            .iter()
+            .filter(|s| {
+                matches!(
+                    s,
+                    Stmt::Use(_) | Stmt::StructDef(_) | Stmt::EnumDef(_) | Stmt::FuncDef(_) | Stmt::DoesBlock(_)
+                )
+            })
+            .cloned()
+            .collect();
+        let module = Module { stmts };
+        self.exec_depth += 1;
+        let res = (|| {
+            self.reset_for_entry_execution();
+            self.compiled_locals = Self::collect_func_locals(&module);
+            self.compiled_locals_idx = Self::index_func_locals(&self.compiled_locals);
+            self.install_prelude()?;
+            Self::precompile_module(&module)?;
+            match self.exec_stmts(&module.stmts) {
+                Flow::Throw(v) => Err(self.format_throw(&v)),
+                Flow::Exit(code) => Err(exit_message(code)),
+                _ => Ok(()),
+            }
+        })();
+        self.exec_depth -= 1;
+        res
+    }
+
     pub fn exec_executable(&mut self, executable: &Executable) -> Result<ExecResult, String> {
         match executable {
             Executable::Ast(module) => self.exec_module(module),
@@ -572,16 +973,56 @@ impl Runtime {
     }
 
     pub fn exec_program(&mut self, program: &xu_ir::Program) -> Result<ExecResult, String> {
+        self.exec_depth += 1;
+        let res = self.exec_program_inner(program);
+        self.exec_depth -= 1;
+        self.finish_entry(res)
+    }
+
+    fn exec_result(&mut self, value: Option<Value>) -> ExecResult {
+        let (output, overflow) = self.finish_output();
+        let error_output = std::mem::take(&mut self.error_output);
+        ExecResult { value, output, error_output, overflow, exit_code: None }
+    }
+
+    /// The code the last execution was ended with by `exit`, which may have
+    /// unwound through a host call such as [`Runtime::call_global`].
+    pub fn exit_requested(&self) -> Option<i32> {
+        self.exit_request
+    }
+
+    /// Whether the last execution failed by calling `panic`.
+    pub fn panicked(&self) -> bool {
+        self.panicked
+    }
+
+    /// Runs the post-execution heap compaction, keeping the result value valid.
+    /// An error unwinding from `exit` ends the run normally with its code.
+    fn finish_entry(&mut self, res: Result<ExecResult, String>) -> Result<ExecResult, String> {
+        let mut res = match (res, self.exit_request) {
+            (Err(_), Some(code)) => ExecResult { exit_code: Some(code), ..self.exec_result(None) },
+            (res, _) => res?,
+        };
+        self.error_trace.clear();
+        match res.value.as_mut() {
+            Some(v) => self.maybe_compact_heap(std::slice::from_mut(v)),
+            None => self.maybe_compact_heap(&mut []),
+        }
+        Ok(res)
+    }
+
+    fn exec_program_inner(&mut self, program: &xu_ir::Program) -> Result<ExecResult, String> {
         self.reset_for_entry_execution();
         self.compiled_locals = Self::collect_func_locals(&program.module);
         self.compiled_locals_idx = Self::index_func_locals(&self.compiled_locals);
         for s in &program.module.stmts {
             match s {
-                Stmt::StructDef(def) => { self.types.structs.insert(def.name.clone(), (**def).clone()); }
+                Stmt::StructDef(def) => { self.types.structs.insert(def.name.clone(), Rc::clone(def)); }
                 Stmt::EnumDef(def) => { self.types.enums.insert(def.name.clone(), def.variants.to_vec()); }
                 _ => {}
             }
         }
+        self.install_prelude()?;
         Self::precompile_module(&program.module)?;
         let flow = if let Some(bc) = program.bytecode.as_ref() {
             ir::run_bytecode(self, bc)?
@@ -593,14 +1034,29 @@ impl Runtime {
 
     pub(crate) fn reset_for_entry_execution(&mut self) {
         self.output.clear();
+        self.error_output.clear();
+        self.output_spill = Default::default();
         self.main_invoked = false;
+        self.exit_request = None;
+        self.try_return = None;
+        self.opt_chain_none = false;
+        self.panicked = false;
+        self.signals.reset();
         self.import_stack.clear();
         self.loaded_modules.clear();
         self.types.reset();
         self.locals.clear();
+        self.prelude_exports.clear();
 
         self.env = Env::new();
+        self.suspended_envs.clear();
         self.heap = crate::core::heap::Heap::new();
+        self.heap.set_gc_threshold(self.config.gc_threshold);
+        self.heap.set_pool_limits(self.config.pool_limits);
+        self.heap.set_dict_hashing(self.config.dict_hashing);
+        self.heap.float_format = self.config.float_format;
+        self.gc_stats = Default::default();
+        self.reset_bench_results();
         self.install_builtins();
         for (k, v) in &self.predefined_constants {
             let s = self
@@ -608,9 +1064,81 @@ impl Runtime {
                 .alloc(crate::core::heap::ManagedObject::Str(v.to_string().into()));
             self.env.define(k.clone(), Value::str(s));
         }
+        for (name, f) in &self.host_functions {
+            let id = self.heap.alloc(crate::core::heap::ManagedObject::Function(
+                crate::core::value::Function::Host(f.clone()),
+            ));
+            self.env.define(name.clone(), Value::function(id));
+        }
         self.caches.reset();
+        self.caps.grants.clear();
         self.current_param_bindings = None;
         self.call_stack_depth = 0;
+        self.error_trace.clear();
+        self.compacted_at_cycle = 0;
+    }
+
+    /// Returns the runtime to its just-constructed state: heap, env, module
+    /// state, inline caches, string caches and object pools are all dropped.
+    /// Host configuration (capabilities, loader, frontend, predefined
+    /// constants, stdlib path, args) is kept.
+    pub fn reset(&mut self) {
+        self.reset_impl(false);
+    }
+
+    /// Like [`Runtime::reset`], but keeps the parsed std modules in the import
+    /// cache so the next execution does not re-parse them. Modules are still
+    /// re-executed, since every execution starts from an empty heap.
+    pub fn reset_preserving_std(&mut self) {
+        self.reset_impl(true);
+    }
+
+    fn reset_impl(&mut self, keep_std: bool) {
+        let std_root = if keep_std {
+            self.stdlib_path
+                .as_deref()
+                .and_then(|p| self.caps.fs.canonicalize(p).ok())
+        } else {
+            None
+        };
+        match std_root {
+            Some(root) => self
+                .import_parse_cache
+                .retain(|k, _| std::path::Path::new(k).starts_with(&root)),
+            None => self.import_parse_cache.clear(),
+        }
+
+        let ic_stats = self.caches.ic_stats.is_some();
+        self.caches = CacheManager::new();
+        self.set_ic_stats(ic_stats);
+        self.pools = ObjectPools::new();
+        self.compiled_locals.clear();
+        self.compiled_locals_idx.clear();
+        self.current_func = None;
+        self.func_entry_frame_depth = 0;
+        self.gc_temp_roots.clear();
+        self.active_vm_stacks.clear();
+        #[cfg(feature = "generational-gc")]
+        {
+            self.gen_heap = crate::core::generational_heap::GenerationalHeap::new();
+        }
+        #[cfg(feature = "jit")]
+        {
+            self.jit = crate::jit::Jit::new();
+        }
+        self.reset_for_entry_execution();
+    }
+
+    /// Number of live objects on the heap.
+    pub fn heap_object_count(&self) -> usize {
+        self.heap.objects.len() - self.heap.free_slot_count()
+    }
+
+    /// Renders `v` for display, with strings quoted, truncation and cycle
+    /// markers. This is what the `inspect` builtin returns; hosts such as a
+    /// REPL use it to echo results.
+    pub fn inspect(&self, v: &Value, opts: &crate::util::PrettyOptions) -> String {
+        crate::util::inspect_value(v, &self.heap, opts)
     }
 
     fn invoke_main_if_present(&mut self) -> Result<(), String> {
@@ -635,6 +1163,13 @@ impl Runtime {
             &builtins_registry::StdBuiltinProvider,
             &mut registry,
         );
+        #[cfg(feature = "plugins")]
+        for plugin in crate::plugin::plugins() {
+            builtins_registry::BuiltinProvider::install(plugin.provider, &mut registry);
+        }
+        for provider in &self.builtin_providers {
+            builtins_registry::BuiltinProvider::install(provider.as_ref(), &mut registry);
+        }
         registry.install_into(&mut self.env, &mut self.heap);
     }
 
@@ -642,23 +1177,74 @@ impl Runtime {
     pub(crate) fn clock_unix_millis(&self) -> i64 { self.caps.clock.unix_millis() }
     pub(crate) fn clock_mono_micros(&self) -> i64 { self.caps.clock.mono_micros() }
     pub(crate) fn clock_mono_nanos(&self) -> i64 { self.caps.clock.mono_nanos() }
+    pub(crate) fn clock_sleep_millis(&self, ms: u64) { self.caps.clock.sleep_millis(ms) }
 
-    pub(crate) fn fs_metadata(&self, path: &str) -> Result<(), String> {
+    pub(crate) fn fs_metadata(&mut self, path: &str) -> Result<(), String> {
+        self.check_fs_read(path)?;
         self.caps.fs.metadata(path).map_err(|e| format!("Open failed: {e}"))
     }
 
-    pub(crate) fn fs_read_to_string(&self, path: &str) -> Result<String, String> {
+    pub(crate) fn fs_read_to_string(&mut self, path: &str) -> Result<String, String> {
+        self.check_fs_read(path)?;
         self.caps.fs.read_to_string(path).map_err(|e| format!("Read failed: {e}"))
     }
 
+    pub(crate) fn fs_read_chunk(&mut self, path: &str, offset: u64, max: usize) -> Result<String, String> {
+        self.check_fs_read(path)?;
+        self.caps.fs.read_chunk(path, offset, max).map_err(|e| format!("Read failed: {e}"))
+    }
+
+    pub(crate) fn fs_write(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        self.check_fs_write(path)?;
+        self.caps.fs.write(path, contents).map_err(|e| format!("Write failed: {e}"))
+    }
+
+    pub(crate) fn fs_append(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        self.check_fs_write(path)?;
+        self.caps.fs.append(path, contents).map_err(|e| format!("Write failed: {e}"))
+    }
+
+    /// Lists the directory `path`. The outer error is a denied access, the
+    /// inner one a failure of the file system, such as a missing directory.
+    pub(crate) fn fs_read_dir(&mut self, path: &str) -> Result<Result<Vec<capabilities::DirEntry>, String>, String> {
+        self.check_fs_read(path)?;
+        Ok(self.caps.fs.read_dir(path))
+    }
+
     pub(crate) fn fs_read_to_string_import(&self, path: &str) -> Result<String, String> {
         self.caps.fs.read_to_string(path).map_err(|e| format!("Import failed: {e}"))
     }
 
+    /// Reads the entry script again for a worker. Like imports, this is not
+    /// checked against the allowed roots.
+    pub(crate) fn fs_read_entry(&self, path: &str) -> Result<String, String> {
+        self.caps.fs.read_to_string(path).map_err(|e| format!("cannot read {path}: {e}"))
+    }
+
     pub(crate) fn fs_stat(&self, path: &str) -> Result<capabilities::FileStat, String> {
         self.caps.fs.stat(path).map_err(|e| format!("Import failed: {e}"))
     }
 
+    pub(crate) fn env_var(&self, key: &str) -> Option<String> {
+        self.caps.env.var(key)
+    }
+
+    pub(crate) fn terminal_is_tty(&self) -> bool {
+        self.caps.terminal.is_tty()
+    }
+
+    pub(crate) fn terminal_read_line(&self) -> Option<String> {
+        self.caps.terminal.read_line()
+    }
+
+    pub(crate) fn terminal_read_secret(&self) -> Option<String> {
+        self.caps.terminal.read_secret()
+    }
+
+    pub(crate) fn watch_signal(&self, signal: super::Signal) {
+        self.caps.signals.watch(signal, &self.signal_handle());
+    }
+
     pub(crate) fn rng_next_u64(&mut self) -> u64 {
         self.caps.rng.next_u64(&mut self.rng_state)
     }
@@ -766,10 +1352,51 @@ impl Runtime {
         std::mem::take(&mut self.output)
     }
 
+    /// Takes what the script wrote to standard error so far.
+    pub fn take_error_output(&mut self) -> String {
+        std::mem::take(&mut self.error_output)
+    }
+
     pub fn write_output(&mut self, s: &str) {
+        if self.stream_output(super::output::OutputStream::Stdout, s, true) {
+            return;
+        }
         self.output.push_str(s);
         self.output.push('\n');
+        self.check_output_limit();
     }
+
+    /// Appends `s` to the output as is, without a line break.
+    pub(crate) fn write_output_raw(&mut self, s: &str) {
+        if self.stream_output(super::output::OutputStream::Stdout, s, false) {
+            return;
+        }
+        self.output.push_str(s);
+        self.check_output_limit();
+    }
+
+    /// Writes the line `s` to standard error.
+    pub fn write_error(&mut self, s: &str) {
+        if self.stream_output(super::output::OutputStream::Stderr, s, true) {
+            return;
+        }
+        self.error_output.push_str(s);
+        self.error_output.push('\n');
+    }
+
+    /// Appends `s` to standard error as is, without a line break.
+    pub(crate) fn write_error_raw(&mut self, s: &str) {
+        if self.stream_output(super::output::OutputStream::Stderr, s, false) {
+            return;
+        }
+        self.error_output.push_str(s);
+    }
+}
+
+/// The error an `exit(code)` unwinds with until the top of the program
+/// turns it back into the code.
+pub(crate) fn exit_message(code: i32) -> String {
+    format!("exit({code})")
 }
 
 impl Default for Runtime {
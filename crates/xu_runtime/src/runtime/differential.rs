@@ -0,0 +1,141 @@
+//! 差分测试子模块
+//! 同一个程序分别交给 AST 执行器和字节码 VM 运行。AST 那次记录全部不确定输入，
+//! VM 那次按记录回放，两边读到的时钟、随机数、文件和环境变量完全相同；
+//! 输出、错误或顶层变量若仍有差异，就是两个引擎语义不一致的 bug。
+
+use std::fmt;
+
+use xu_ir::Program;
+
+use crate::core::value::TAG_FUNC;
+
+use super::core::Runtime;
+
+/// What one engine did with a program in [`compare_engines`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineRun {
+    /// Everything the program printed, including output before an error.
+    pub output: String,
+    /// The error the run ended with, if it failed.
+    pub error: Option<String>,
+    /// Top-level variables when the run ended, sorted by name, with their
+    /// values as `println` would show them. Functions are left out.
+    pub globals: Vec<(String, String)>,
+}
+
+/// One way the two runs in [`compare_engines`] differ.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// What differs, such as `output line 3` or `global x`.
+    pub what: String,
+    pub ast: String,
+    pub vm: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:\n  ast: {}\n  vm:  {}", self.what, self.ast, self.vm)
+    }
+}
+
+/// Both runs of a program and their differences, from [`compare_engines`].
+#[derive(Clone, Debug)]
+pub struct EngineComparison {
+    pub ast: EngineRun,
+    pub vm: EngineRun,
+    /// Empty when the engines agree.
+    pub divergences: Vec<Divergence>,
+}
+
+/// Runs `program` on the AST executor and on the bytecode VM, each in a
+/// runtime made by `runtime`, and compares what they printed, how they
+/// ended and the top-level variables they left behind.
+///
+/// The VM run replays the clock reads, random draws, file reads and
+/// environment variables recorded during the AST run, so both see the same
+/// inputs; the VM asking for inputs the AST run never read is reported as
+/// a divergence too. `runtime` must configure both runtimes identically and
+/// should not install an output sink, since streamed output is not compared.
+pub fn compare_engines(program: &Program, mut runtime: impl FnMut() -> Runtime) -> EngineComparison {
+    let mut ast_rt = runtime();
+    ast_rt.start_recording();
+    let res = ast_rt.exec_module(&program.module);
+    let ast = ast_rt.engine_run(res);
+    let trace = ast_rt.recorded_trace().unwrap_or_default();
+
+    let mut vm_rt = runtime();
+    vm_rt.replay_trace(trace);
+    let res = vm_rt.exec_program(program);
+    let vm = vm_rt.engine_run(res);
+
+    let mut divergences = diff_runs(&ast, &vm);
+    let misses = vm_rt.replay_misses();
+    if !misses.is_empty() {
+        divergences.push(Divergence {
+            what: "inputs".to_string(),
+            ast: "not read".to_string(),
+            vm: misses.join(", "),
+        });
+    }
+    EngineComparison { ast, vm, divergences }
+}
+
+fn diff_runs(ast: &EngineRun, vm: &EngineRun) -> Vec<Divergence> {
+    let mut out = Vec::new();
+    // 只报告第一处不同的输出行，之后的差异通常由它引起
+    let (mut a, mut v) = (ast.output.lines(), vm.output.lines());
+    for line in 1.. {
+        match (a.next(), v.next()) {
+            (None, None) => break,
+            (x, y) if x == y => continue,
+            (x, y) => {
+                let show = |l: Option<&str>| l.map_or("<end of output>".to_string(), |l| format!("{l:?}"));
+                out.push(Divergence { what: format!("output line {line}"), ast: show(x), vm: show(y) });
+                break;
+            }
+        }
+    }
+    if ast.error != vm.error {
+        let show = |e: &Option<String>| e.clone().unwrap_or_else(|| "<no error>".to_string());
+        out.push(Divergence { what: "error".to_string(), ast: show(&ast.error), vm: show(&vm.error) });
+    }
+    let mut names: Vec<&str> = ast.globals.iter().chain(&vm.globals).map(|(n, _)| n.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    for name in names {
+        let value = |run: &EngineRun| run.globals.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+        let (x, y) = (value(ast), value(vm));
+        if x != y {
+            let show = |v: Option<String>| v.unwrap_or_else(|| "<undefined>".to_string());
+            out.push(Divergence { what: format!("global {name}"), ast: show(x), vm: show(y) });
+        }
+    }
+    out
+}
+
+impl Runtime {
+    /// 收集一次执行的输出、错误和顶层变量
+    fn engine_run(&mut self, res: Result<super::ExecResult, String>) -> EngineRun {
+        let (output, error) = match res {
+            Ok(r) => (r.output, None),
+            Err(e) => (self.take_output(), Some(e)),
+        };
+        EngineRun { output, error, globals: self.global_values() }
+    }
+
+    /// 全局作用域中的非函数变量
+    fn global_values(&self) -> Vec<(String, String)> {
+        let global = self.env.global_frame();
+        let scope = global.borrow();
+        let mut out: Vec<(String, String)> = scope
+            .names
+            .iter()
+            .filter_map(|(name, &idx)| {
+                let v = *scope.values.get(idx)?;
+                (v.get_tag() != TAG_FUNC).then(|| (name.clone(), crate::util::value_to_string(&v, &self.heap)))
+            })
+            .collect();
+        out.sort();
+        out
+    }
+}
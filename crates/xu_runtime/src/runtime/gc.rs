@@ -3,11 +3,15 @@
 //! This module contains:
 //! - gc: Full garbage collection
 //! - maybe_gc_with_roots: Conditional GC with extra roots
+//! - compact_heap: Full GC followed by heap compaction
+//! - gc_stats: Collection counts, freed objects and pause times
 //! - Generational GC support (when feature enabled)
 
 use crate::core::Value;
 use crate::Runtime;
 
+use super::config::{GcStats, HeapCompaction};
+
 impl Runtime {
     /// Collect all GC roots from the runtime state
     fn collect_gc_roots(&self, extra_roots: &[Value]) -> Vec<Value> {
@@ -30,11 +34,12 @@ impl Runtime {
             roots.extend_from_slice(stack);
         }
 
-        roots.extend_from_slice(&self.env.stack);
-
-        for frame in &self.env.frames {
-            let scope = frame.scope.borrow();
-            roots.extend_from_slice(&scope.values);
+        for env in std::iter::once(&self.env).chain(&self.suspended_envs) {
+            roots.extend_from_slice(&env.stack);
+            for frame in &env.frames {
+                let scope = frame.scope.borrow();
+                roots.extend_from_slice(&scope.values);
+            }
         }
 
         for frame_values in &self.locals.values {
@@ -45,9 +50,13 @@ impl Runtime {
             roots.push(*val);
         }
 
+        roots.extend(self.loaded_modules.values().copied());
+        roots.extend(self.signals.handlers.iter().map(|(_, h)| *h));
+
         for val in self.caches.small_int_strings.iter().flatten() {
             roots.push(*val);
         }
+        roots.extend(self.caches.cached_empty_str);
         for cache_vec in self.caches.bytecode_string_cache.values() {
             for val in cache_vec.iter().flatten() {
                 roots.push(*val);
@@ -67,7 +76,6 @@ impl Runtime {
         self.caches.ic_method_slots.clear();
         self.pools.env_pool.clear();
         self.pools.vm_stack_pool.clear();
-        self.pools.small_list_pool.clear();
     }
 
     /// Clean up string intern cache after GC.
@@ -80,13 +88,39 @@ impl Runtime {
         });
     }
 
-    /// Perform a full garbage collection cycle.
-    #[cfg(not(feature = "generational-gc"))]
+    /// Mark from `roots` and sweep, adding the result to the GC statistics.
+    fn mark_and_sweep(&mut self, roots: &[Value]) {
+        self.heap.mark_all(roots, &[&self.env], &[&self.locals]);
+        let sweep = self.heap.sweep();
+        let stats = &mut self.gc_stats;
+        stats.objects_freed += sweep.freed as u64;
+        stats.bytes_freed += sweep.freed_bytes as u64;
+        stats.live_objects = sweep.live;
+        stats.live_bytes = sweep.live_bytes;
+    }
+
+    /// Perform a full garbage collection cycle, timing it for `gc_stats`.
     pub fn gc(&mut self, extra_roots: &[Value]) {
+        let started = crate::util::Stopwatch::start();
+        self.gc_cycle(extra_roots);
+        let pause = started.elapsed();
+        let stats = &mut self.gc_stats;
+        stats.collections += 1;
+        stats.total_pause += pause;
+        stats.max_pause = stats.max_pause.max(pause);
+    }
+
+    /// Garbage collector activity since the current (or last) execution
+    /// started.
+    pub fn gc_stats(&self) -> GcStats {
+        GcStats { pool_reuses: self.heap.pool_reuses(), ..self.gc_stats }
+    }
+
+    #[cfg(not(feature = "generational-gc"))]
+    fn gc_cycle(&mut self, extra_roots: &[Value]) {
         self.clear_caches_for_gc();
         let roots = self.collect_gc_roots(extra_roots);
-        self.heap.mark_all(&roots, &[&self.env], &[&self.locals]);
-        self.heap.sweep();
+        self.mark_and_sweep(&roots);
         self.cleanup_intern_cache();
 
         // If heap is still fragmented after sweep, clear small_int_strings and GC again
@@ -105,8 +139,7 @@ impl Runtime {
             self.caches.string_value_intern.clear();
             // Run GC again to collect the now-unreferenced strings
             let roots = self.collect_gc_roots(extra_roots);
-            self.heap.mark_all(&roots, &[&self.env], &[&self.locals]);
-            self.heap.sweep();
+            self.mark_and_sweep(&roots);
             self.cleanup_intern_cache();
         }
     }
@@ -114,7 +147,7 @@ impl Runtime {
     /// Perform garbage collection (generational GC version).
     /// Currently only uses full GC - young GC is disabled for stability.
     #[cfg(feature = "generational-gc")]
-    pub fn gc(&mut self, extra_roots: &[Value]) {
+    fn gc_cycle(&mut self, extra_roots: &[Value]) {
         self.clear_caches_for_gc();
         let roots = self.collect_gc_roots(extra_roots);
         self.full_gc(&roots);
@@ -139,8 +172,7 @@ impl Runtime {
     #[cfg(feature = "generational-gc")]
     fn full_gc(&mut self, roots: &[Value]) {
         // Standard mark-sweep
-        self.heap.mark_all(roots, &[&self.env], &[&self.locals]);
-        self.heap.sweep();
+        self.mark_and_sweep(roots);
 
         // Update gen_heap after full GC
         let max_heap_id = self.heap.objects.len();
@@ -162,4 +194,85 @@ impl Runtime {
             self.gc(roots);
         }
     }
+
+    /// Runs a full collection, then moves every live object down to the
+    /// lowest heap slots so long-lived runtimes do not keep a fragmented heap.
+    ///
+    /// Object ids change, so every `Value` the host still holds must be in
+    /// `extra_roots`, which are rewritten in place; `heap_epoch` advances
+    /// whenever objects move. Returns `None` without doing anything while a
+    /// script is executing, since native frames may then hold values.
+    pub fn compact_heap(&mut self, extra_roots: &mut [Value]) -> Option<HeapCompaction> {
+        if self.exec_depth > 0 || !self.active_vm_stacks.is_empty() {
+            return None;
+        }
+        let slots_before = self.heap.objects.len();
+
+        // Caches holding values are dropped rather than rewritten.
+        self.caches.small_int_strings.clear();
+        self.caches.bytecode_string_cache.clear();
+        self.caches.string_value_intern.clear();
+        self.caches.dict_insert_cache_last = None;
+        self.caches.cached_option_none = None;
+        self.caches.cached_empty_str = None;
+        self.pools.vm_iters_pool.clear();
+        self.gc(extra_roots);
+
+        let mut fwd = self.heap.compact();
+        if fwd.moved > 0 {
+            fwd.values(extra_roots);
+            fwd.values(&mut self.gc_temp_roots);
+            fwd.env(&mut self.env);
+            for frame_values in &mut self.locals.values {
+                fwd.values(frame_values);
+            }
+            for v in self.types.static_fields.values_mut() {
+                *v = fwd.value(*v);
+            }
+            for v in self.loaded_modules.values_mut() {
+                *v = fwd.value(*v);
+            }
+            for (_, v) in &mut self.prelude_exports {
+                *v = fwd.value(*v);
+            }
+            for (_, v) in &mut self.signals.handlers {
+                *v = fwd.value(*v);
+            }
+        }
+        #[cfg(feature = "generational-gc")]
+        self.gen_heap.after_full_gc(self.heap.objects.len());
+        self.compacted_at_cycle = self.heap.gc_cycles;
+
+        Some(HeapCompaction {
+            live: self.heap.objects.len(),
+            moved: fwd.moved,
+            slots_before,
+            slots_after: self.heap.objects.len(),
+        })
+    }
+
+    /// The heap's compaction epoch. It changes whenever `compact_heap` moves
+    /// objects, invalidating `Value`s the host kept outside its roots.
+    pub fn heap_epoch(&self) -> u64 {
+        self.heap.epoch()
+    }
+
+    /// Compacts at the end of a top-level execution when enabled and a
+    /// collection since the last compaction left the heap mostly free slots.
+    pub(crate) fn maybe_compact_heap(&mut self, roots: &mut [Value]) {
+        if !self.config.heap_compaction
+            || self.exec_depth > 0
+            || self.heap.gc_cycles == self.compacted_at_cycle
+        {
+            return;
+        }
+        let free = self.heap.free_slot_count();
+        let live = self.heap.objects.len().saturating_sub(free);
+        if free > live && free >= HEAP_COMPACTION_MIN_FREE {
+            self.compact_heap(roots);
+        }
+    }
 }
+
+/// Fewest free slots worth an automatic compaction.
+const HEAP_COMPACTION_MIN_FREE: usize = 4096;
@@ -0,0 +1,628 @@
+//! 堆快照子模块
+//! 从 GC 根出发遍历存活对象，按种类汇总浅大小与保留大小（支配树），并给出示例保留路径。
+//! 只读取堆，不触发回收。
+
+use crate::core::heap::{Heap, ManagedObject};
+use crate::core::value::{DictKey, Function};
+use crate::core::Value;
+
+use super::core::Runtime;
+
+/// Number of objects listed in [`HeapSnapshot::largest`].
+const LARGEST: usize = 10;
+/// Longer retention paths keep their start and end around a gap.
+const MAX_PATH: usize = 24;
+/// Longer dict keys in path steps are cut.
+const MAX_KEY: usize = 40;
+
+/// Live objects of a [`Runtime`] heap, from [`Runtime::heap_snapshot`].
+///
+/// Sizes are the heap's own estimates, the same the collector accounts. The
+/// retained size of an object is what collecting would free if the object
+/// became unreachable: itself and everything only reachable through it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HeapSnapshot {
+    /// Objects reachable from the roots.
+    pub objects: usize,
+    pub bytes: usize,
+    /// Garbage not collected yet.
+    pub unreachable_objects: usize,
+    pub unreachable_bytes: usize,
+    /// One entry per kind, largest retained size first.
+    pub kinds: Vec<KindSummary>,
+    /// The objects retaining the most besides themselves, largest first.
+    pub largest: Vec<RetainedObject>,
+}
+
+/// Live objects of one kind: struct and enum instances by type name
+/// (`Point`), functions by definition (`fn make_handler`), anything else by
+/// type (`list`, `dict`, `string`, ...).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KindSummary {
+    pub kind: String,
+    pub count: usize,
+    pub shallow_bytes: usize,
+    /// Retained by objects of this kind, counting objects nested in another
+    /// of the same kind once.
+    pub retained_bytes: usize,
+    /// A shortest path from a root to the member retaining the most.
+    pub path: Vec<String>,
+}
+
+/// One live object and how it is reached.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RetainedObject {
+    /// Heap slot of the object, stable until the next collection.
+    pub id: usize,
+    pub kind: String,
+    pub shallow_bytes: usize,
+    pub retained_bytes: usize,
+    /// A shortest path from a root: its label (`global cache`, `module
+    /// std/json`, ...) and then one step per reference (`["key"]`, `[3]`,
+    /// `.field`, `captured x`).
+    pub path: Vec<String>,
+}
+
+impl Runtime {
+    /// A snapshot of the live heap for finding what keeps memory alive, such
+    /// as a cache that is never trimmed or a closure capturing a large value.
+    /// Reads the heap without collecting it.
+    pub fn heap_snapshot(&self) -> HeapSnapshot {
+        let roots = self.labeled_roots();
+        let heap = &self.heap;
+        let graph = Graph::build(heap, &roots);
+        let n = graph.obj.len();
+
+        let idom = graph.dominators();
+        // 逆后序中支配者在前，倒序累加即得保留大小
+        let shallow: Vec<usize> = graph.obj.iter().map(|&id| object_size(heap, id)).collect();
+        let mut retained = shallow.clone();
+        for &v in graph.rpo.iter().skip(1).rev() {
+            let d = idom[v as usize] as usize;
+            retained[d] += retained[v as usize];
+        }
+
+        // 种类编号
+        let mut kind_names: Vec<String> = Vec::new();
+        let mut kind_index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        let mut kind = vec![usize::MAX; n];
+        for (k, &id) in kind.iter_mut().zip(&graph.obj).skip(1) {
+            let name = kind_of(heap, id);
+            let next = kind_names.len();
+            *k = *kind_index.entry(name.clone()).or_insert(next);
+            if *k == next {
+                kind_names.push(name);
+            }
+        }
+
+        let mut summaries: Vec<KindSummary> =
+            kind_names.iter().map(|k| KindSummary { kind: k.clone(), ..Default::default() }).collect();
+        let mut sample: Vec<Option<usize>> = vec![None; kind_names.len()];
+        for v in 1..n {
+            let s = &mut summaries[kind[v]];
+            s.count += 1;
+            s.shallow_bytes += shallow[v];
+            let best = &mut sample[kind[v]];
+            if best.is_none_or(|b| retained[v] > retained[b]) {
+                *best = Some(v);
+            }
+        }
+
+        // 在支配树上深度优先，只累加没有同种祖先的对象
+        let mut children: Vec<Vec<u32>> = vec![Vec::new(); n];
+        for &v in graph.rpo.iter().skip(1) {
+            children[idom[v as usize] as usize].push(v);
+        }
+        let mut active = vec![0usize; kind_names.len()];
+        let mut stack: Vec<(u32, bool)> = vec![(0, false)];
+        while let Some((v, exit)) = stack.pop() {
+            let k = kind[v as usize];
+            if exit {
+                active[k] -= 1;
+                continue;
+            }
+            if k != usize::MAX {
+                if active[k] == 0 {
+                    summaries[k].retained_bytes += retained[v as usize];
+                }
+                active[k] += 1;
+                stack.push((v, true));
+            }
+            stack.extend(children[v as usize].iter().map(|&c| (c, false)));
+        }
+
+        for (s, best) in summaries.iter_mut().zip(&sample) {
+            if let Some(v) = best {
+                s.path = graph.path(heap, &roots, *v);
+            }
+        }
+        summaries.sort_by(|a, b| b.retained_bytes.cmp(&a.retained_bytes).then_with(|| a.kind.cmp(&b.kind)));
+
+        let mut order: Vec<usize> = (1..n).filter(|&v| retained[v] > shallow[v]).collect();
+        order.sort_by(|&a, &b| retained[b].cmp(&retained[a]).then(a.cmp(&b)));
+        let largest = order
+            .into_iter()
+            .take(LARGEST)
+            .map(|v| RetainedObject {
+                id: graph.obj[v],
+                kind: kind_names[kind[v]].clone(),
+                shallow_bytes: shallow[v],
+                retained_bytes: retained[v],
+                path: graph.path(heap, &roots, v),
+            })
+            .collect();
+
+        let mut snapshot = HeapSnapshot {
+            objects: n - 1,
+            bytes: shallow.iter().sum(),
+            kinds: summaries,
+            largest,
+            ..Default::default()
+        };
+        for (id, obj) in heap.objects.iter().enumerate() {
+            if let Some(obj) = obj {
+                if graph.node[id] == u32::MAX {
+                    snapshot.unreachable_objects += 1;
+                    snapshot.unreachable_bytes += obj.size();
+                }
+            }
+        }
+        snapshot
+    }
+
+    /// 与 collect_gc_roots 相同的根，附带可读标签
+    fn labeled_roots(&self) -> Vec<(String, Value)> {
+        let mut roots: Vec<(String, Value)> = Vec::new();
+        let push_frames = |roots: &mut Vec<(String, Value)>, env: &crate::Env, local: &str| {
+            for (depth, frame) in env.frames.iter().enumerate() {
+                let scope = frame.scope.borrow();
+                let what = if depth == 0 { "global" } else { local };
+                // 名字排序，使同一对象的标签稳定
+                let mut names: Vec<_> = scope.names.iter().collect();
+                names.sort();
+                for (name, &i) in names {
+                    let v = if frame.attached { env.stack.get(frame.base + i) } else { scope.values.get(i) };
+                    if let Some(v) = v {
+                        roots.push((format!("{what} {name}"), *v));
+                    }
+                }
+            }
+            for v in &env.stack {
+                roots.push((format!("{local} (temporary)"), *v));
+            }
+        };
+        push_frames(&mut roots, &self.env, "local");
+        for env in &self.suspended_envs {
+            push_frames(&mut roots, env, "caller local");
+        }
+        for (names, values) in self.locals.maps.iter().zip(&self.locals.values) {
+            for (name, &i) in names {
+                if let Some(v) = values.get(i) {
+                    roots.push((format!("local {name}"), *v));
+                }
+            }
+        }
+        for ((ty, field), v) in &self.types.static_fields {
+            roots.push((format!("static {ty}.{field}"), *v));
+        }
+        for (key, v) in &self.loaded_modules {
+            roots.push((format!("module {key}"), *v));
+        }
+        for values in &self.locals.values {
+            roots.extend(values.iter().map(|v| ("local (unnamed)".to_string(), *v)));
+        }
+        for stack_ptr in &self.active_vm_stacks {
+            let stack = unsafe { &**stack_ptr };
+            roots.extend(stack.iter().map(|v| ("vm stack".to_string(), *v)));
+        }
+        roots.extend(self.gc_temp_roots.iter().map(|v| ("temporary".to_string(), *v)));
+        for v in self.caches.small_int_strings.iter().flatten() {
+            roots.push(("string cache".to_string(), *v));
+        }
+        for cache in self.caches.bytecode_string_cache.values() {
+            roots.extend(cache.iter().flatten().map(|v| ("string cache".to_string(), *v)));
+        }
+        roots
+    }
+}
+
+/// 可达对象图；节点 0 是连接所有根的虚拟根
+struct Graph {
+    /// 节点 -> 对象编号
+    obj: Vec<usize>,
+    /// 对象编号 -> 节点，不可达为 u32::MAX
+    node: Vec<u32>,
+    /// 广度优先树：(父节点, 父节点的第几条边或第几个根)
+    parent: Vec<(u32, u32)>,
+    succ: Vec<Vec<u32>>,
+    rpo: Vec<u32>,
+}
+
+impl Graph {
+    fn build(heap: &Heap, roots: &[(String, Value)]) -> Self {
+        let mut g = Graph {
+            obj: vec![usize::MAX],
+            node: vec![u32::MAX; heap.objects.len()],
+            parent: vec![(0, 0)],
+            succ: vec![Vec::new()],
+            rpo: Vec::new(),
+        };
+        let live = |id: usize| heap.objects.get(id).is_some_and(|o| o.is_some());
+        let visit = |g: &mut Graph, from: u32, edge: u32, id: usize| {
+            if !live(id) {
+                return;
+            }
+            if g.node[id] == u32::MAX {
+                g.node[id] = g.obj.len() as u32;
+                g.obj.push(id);
+                g.parent.push((from, edge));
+                g.succ.push(Vec::new());
+            }
+            let to = g.node[id];
+            g.succ[from as usize].push(to);
+        };
+        for (i, (_, v)) in roots.iter().enumerate() {
+            if v.is_obj() {
+                visit(&mut g, 0, i as u32, v.as_obj_id().0);
+            }
+        }
+        // 节点按发现顺序编号，因此顺序扫描就是广度优先
+        let mut next = 1;
+        let mut children = Vec::new();
+        while next < g.obj.len() {
+            children.clear();
+            for_each_edge(heap, g.obj[next], &mut |id, _| children.push(id));
+            for (k, &id) in children.iter().enumerate() {
+                visit(&mut g, next as u32, k as u32, id);
+            }
+            next += 1;
+        }
+
+        // 后序（迭代深度优先），再反转
+        let mut seen = vec![false; g.obj.len()];
+        let mut stack: Vec<(u32, usize)> = vec![(0, 0)];
+        seen[0] = true;
+        while let Some(top) = stack.last_mut() {
+            let (v, i) = *top;
+            if let Some(&w) = g.succ[v as usize].get(i) {
+                top.1 += 1;
+                if !seen[w as usize] {
+                    seen[w as usize] = true;
+                    stack.push((w, 0));
+                }
+            } else {
+                g.rpo.push(v);
+                stack.pop();
+            }
+        }
+        g.rpo.reverse();
+        g
+    }
+
+    /// 直接支配者（Cooper–Harvey–Kennedy 迭代算法）
+    fn dominators(&self) -> Vec<u32> {
+        let n = self.obj.len();
+        let mut order = vec![0u32; n];
+        for (i, &v) in self.rpo.iter().enumerate() {
+            order[v as usize] = i as u32;
+        }
+        let mut preds: Vec<Vec<u32>> = vec![Vec::new(); n];
+        for (v, succ) in self.succ.iter().enumerate() {
+            for &w in succ {
+                preds[w as usize].push(v as u32);
+            }
+        }
+        const UNDEF: u32 = u32::MAX;
+        let mut idom = vec![UNDEF; n];
+        idom[0] = 0;
+        let intersect = |idom: &[u32], mut a: u32, mut b: u32| {
+            while a != b {
+                while order[a as usize] > order[b as usize] {
+                    a = idom[a as usize];
+                }
+                while order[b as usize] > order[a as usize] {
+                    b = idom[b as usize];
+                }
+            }
+            a
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &v in self.rpo.iter().skip(1) {
+                let mut new = UNDEF;
+                for &p in &preds[v as usize] {
+                    if idom[p as usize] == UNDEF {
+                        continue;
+                    }
+                    new = if new == UNDEF { p } else { intersect(&idom, p, new) };
+                }
+                if idom[v as usize] != new {
+                    idom[v as usize] = new;
+                    changed = true;
+                }
+            }
+        }
+        idom
+    }
+
+    /// 沿广度优先树回到根的路径标签
+    fn path(&self, heap: &Heap, roots: &[(String, Value)], mut v: usize) -> Vec<String> {
+        let mut steps = Vec::new();
+        while v != 0 {
+            let (p, edge) = self.parent[v];
+            if p == 0 {
+                steps.push(roots[edge as usize].0.clone());
+            } else {
+                steps.push(edge_label(heap, self.obj[p as usize], edge as usize));
+            }
+            v = p as usize;
+        }
+        steps.reverse();
+        if steps.len() > MAX_PATH {
+            let tail = steps.split_off(steps.len() - MAX_PATH / 2);
+            let skipped = steps.len() - MAX_PATH / 2;
+            steps.truncate(MAX_PATH / 2);
+            steps.push(format!("... ({skipped} more)"));
+            steps.extend(tail);
+        }
+        steps
+    }
+}
+
+fn object_size(heap: &Heap, id: usize) -> usize {
+    heap.objects.get(id).and_then(|o| o.as_ref()).map_or(0, ManagedObject::size)
+}
+
+fn kind_of(heap: &Heap, id: usize) -> String {
+    let Some(Some(obj)) = heap.objects.get(id) else {
+        return "unknown".into();
+    };
+    match obj {
+        ManagedObject::List(_) => "list".into(),
+        ManagedObject::Tuple(_) => "tuple".into(),
+        ManagedObject::Dict(_) | ManagedObject::DictStr(_) => "dict".into(),
+        ManagedObject::File(_) => "file".into(),
+        ManagedObject::Builder(_) => "builder".into(),
+        ManagedObject::Struct(s) => s.ty.clone(),
+        ManagedObject::Module(_) => "module".into(),
+        ManagedObject::Range(..) => "range".into(),
+        ManagedObject::Enum(e) => e.0.as_str().to_string(),
+        ManagedObject::OptionSome(_) => "Option".into(),
+        ManagedObject::Function(Function::User(f)) => format!("fn {}", f.def.name),
+        ManagedObject::Function(Function::Bytecode(f)) => format!("fn {}", f.def.name),
+        ManagedObject::Function(Function::Builtin(_) | Function::Host(_)) => "builtin".into(),
+        ManagedObject::Str(_) => "string".into(),
+        ManagedObject::Shape(_) => "shape".into(),
+        ManagedObject::SplitIter(_) => "split_iterator".into(),
+        ManagedObject::Channel(_) => "channel".into(),
+    }
+}
+
+/// The `k`-th reference of an object, as a path step.
+fn edge_label(heap: &Heap, id: usize, k: usize) -> String {
+    let mut i = 0;
+    let mut label = String::new();
+    for_each_edge(heap, id, &mut |_, make| {
+        if i == k {
+            label = make();
+        }
+        i += 1;
+    });
+    label
+}
+
+fn key_text(heap: &Heap, key: &DictKey) -> String {
+    let text = crate::util::value_to_string(&key.to_value(heap), heap);
+    let quoted = matches!(key, DictKey::StrRef { .. });
+    let text: String = if text.chars().count() > MAX_KEY {
+        text.chars().take(MAX_KEY).chain("...".chars()).collect()
+    } else {
+        text
+    };
+    if quoted { format!("[\"{text}\"]") } else { format!("[{text}]") }
+}
+
+/// 按 Heap::mark_all 的顺序列出对象的引用；标签按需生成
+fn for_each_edge(heap: &Heap, id: usize, f: &mut dyn FnMut(usize, &dyn Fn() -> String)) {
+    let Some(Some(obj)) = heap.objects.get(id) else {
+        return;
+    };
+    if let ManagedObject::Dict(dict) = obj {
+        for key in dict.map.keys() {
+            if let Some(k) = key.heap_ref() {
+                f(k.0, &|| format!("key {}", key_text(heap, key)));
+            }
+        }
+    }
+    let mut value = |v: &Value, label: &dyn Fn() -> String| {
+        if v.is_obj() {
+            f(v.as_obj_id().0, label);
+        }
+    };
+    match obj {
+        ManagedObject::List(_) | ManagedObject::Tuple(_) => {
+            for (i, v) in obj.elements().unwrap_or_default().iter().enumerate() {
+                value(v, &|| format!("[{i}]"));
+            }
+        }
+        ManagedObject::Dict(dict) => {
+            for (key, v) in dict.map.iter() {
+                value(v, &|| key_text(heap, key));
+            }
+            if let Some(pv) = &dict.prop_values {
+                let shape = dict.shape.and_then(|s| match heap.objects.get(s.0) {
+                    Some(Some(ManagedObject::Shape(shape))) => Some(shape),
+                    _ => None,
+                });
+                for (i, v) in pv.iter().enumerate() {
+                    value(v, &|| {
+                        let name = shape.and_then(|s| s.prop_map.iter().find(|(_, j)| **j == i));
+                        format!("[\"{}\"]", name.map_or("?", |(n, _)| n.as_str()))
+                    });
+                }
+            }
+            if let Some(elements) = &dict.elements {
+                for (i, v) in elements.iter().enumerate() {
+                    value(v, &|| format!("[{i}]"));
+                }
+            }
+        }
+        ManagedObject::DictStr(dict) => {
+            for (key, v) in dict.map.iter() {
+                value(v, &|| format!("[\"{key}\"]"));
+            }
+        }
+        ManagedObject::Struct(s) => {
+            for (i, v) in s.fields.iter().enumerate() {
+                value(v, &|| format!(".{}", s.field_names.get(i).map_or("?", String::as_str)));
+            }
+        }
+        ManagedObject::Module(m) => {
+            for (name, v) in m.exports.map.iter() {
+                value(v, &|| format!(".{name}"));
+            }
+        }
+        ManagedObject::Enum(e) => {
+            for (i, v) in e.2.iter().enumerate() {
+                value(v, &|| format!("{}({i})", e.1.as_str()));
+            }
+        }
+        ManagedObject::OptionSome(v) => value(v, &|| "some".to_string()),
+        ManagedObject::Function(func) => {
+            let env = match func {
+                Function::User(uf) => &uf.env,
+                Function::Bytecode(bf) => &bf.env,
+                Function::Builtin(_) | Function::Host(_) => return,
+            };
+            for v in &env.stack {
+                value(v, &|| "captured (temporary)".to_string());
+            }
+            for frame in &env.frames {
+                let scope = frame.scope.borrow();
+                for (i, v) in scope.values.iter().enumerate() {
+                    value(v, &|| {
+                        let name = scope.names.iter().find(|(_, j)| **j == i);
+                        format!("captured {}", name.map_or("?", |(n, _)| n.as_str()))
+                    });
+                }
+            }
+        }
+        ManagedObject::Str(_) |
+        ManagedObject::Builder(_) |
+        ManagedObject::File(_) |
+        ManagedObject::Range(_, _, _) |
+        ManagedObject::Shape(_) |
+        ManagedObject::SplitIter(_) |
+        ManagedObject::Channel(_) => {}
+    }
+}
+
+fn kib(bytes: usize) -> String {
+    format!("{:.1} KiB", bytes as f64 / 1024.0)
+}
+
+pub(super) fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn push_json_path(out: &mut String, path: &[String]) {
+    out.push('[');
+    for (i, step) in path.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_json_str(out, step);
+    }
+    out.push(']');
+}
+
+impl HeapSnapshot {
+    /// A report for people: totals, a table of kinds and the largest
+    /// retainers, each with its retention path.
+    pub fn to_text(&self) -> String {
+        let mut out = format!("heap: {} live objects, {}", self.objects, kib(self.bytes));
+        if self.unreachable_objects > 0 {
+            out.push_str(&format!(
+                " ({} unreachable, {} awaiting collection)",
+                self.unreachable_objects,
+                kib(self.unreachable_bytes)
+            ));
+        }
+        out.push('\n');
+        let width = self.kinds.iter().map(|k| k.kind.len()).max().unwrap_or(0).max(4);
+        out.push_str(&format!("{:<width$}  {:>8}  {:>12}  {:>12}  path\n", "kind", "count", "shallow", "retained"));
+        for k in &self.kinds {
+            out.push_str(&format!(
+                "{:<width$}  {:>8}  {:>12}  {:>12}  {}\n",
+                k.kind,
+                k.count,
+                kib(k.shallow_bytes),
+                kib(k.retained_bytes),
+                k.path.join(" -> ")
+            ));
+        }
+        if !self.largest.is_empty() {
+            out.push_str("largest retainers:\n");
+            for o in &self.largest {
+                out.push_str(&format!(
+                    "  {:>12}  {} #{}  {}\n",
+                    kib(o.retained_bytes),
+                    o.kind,
+                    o.id,
+                    o.path.join(" -> ")
+                ));
+            }
+        }
+        out
+    }
+
+    /// The snapshot as one JSON object with the fields of this struct.
+    pub fn to_json(&self) -> String {
+        let mut out = format!(
+            "{{\"objects\":{},\"bytes\":{},\"unreachable_objects\":{},\"unreachable_bytes\":{},\"kinds\":[",
+            self.objects, self.bytes, self.unreachable_objects, self.unreachable_bytes
+        );
+        for (i, k) in self.kinds.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"kind\":");
+            push_json_str(&mut out, &k.kind);
+            out.push_str(&format!(
+                ",\"count\":{},\"shallow_bytes\":{},\"retained_bytes\":{},\"path\":",
+                k.count, k.shallow_bytes, k.retained_bytes
+            ));
+            push_json_path(&mut out, &k.path);
+            out.push('}');
+        }
+        out.push_str("],\"largest\":[");
+        for (i, o) in self.largest.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&format!("{{\"id\":{},\"kind\":", o.id));
+            push_json_str(&mut out, &o.kind);
+            out.push_str(&format!(
+                ",\"shallow_bytes\":{},\"retained_bytes\":{},\"path\":",
+                o.shallow_bytes, o.retained_bytes
+            ));
+            push_json_path(&mut out, &o.path);
+            out.push('}');
+        }
+        out.push_str("]}");
+        out
+    }
+}
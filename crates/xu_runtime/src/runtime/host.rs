@@ -0,0 +1,229 @@
+//! Values exchanged with embedding hosts.
+//!
+//! A [`HostValue`] is a deep copy of a script value that owns no heap
+//! objects, so a host can keep it across calls, garbage collections and
+//! resets of the runtime it came from.
+
+use crate::Runtime;
+use crate::builtins_registry::{BuiltinProvider, BuiltinRegistry};
+use crate::Value;
+use crate::core::heap::ManagedObject;
+use crate::core::value::{
+    DictKey, TAG_DICT, TAG_ENUM, TAG_LIST, TAG_OPTION, TAG_RANGE, TAG_STR, TAG_STRUCT, TAG_TUPLE,
+};
+
+/// 复制的嵌套上限，同时挡住自引用的列表和字典
+const MAX_DEPTH: usize = 256;
+
+/// Ints a script can hold, 48 bits wide. [`Runtime::from_host`] refuses
+/// ints and range ends outside it.
+pub const INT_RANGE: std::ops::RangeInclusive<i64> = -(1 << 47)..=(1 << 47) - 1;
+
+/// A script value copied out of a runtime's heap.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HostValue {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<HostValue>),
+    Tuple(Vec<HostValue>),
+    /// Entries in the dict's iteration order.
+    Dict(Vec<(HostValue, HostValue)>),
+    /// Start, end and whether the end is included.
+    Range(i64, i64, bool),
+    Option(Option<Box<HostValue>>),
+    Enum { ty: String, variant: String, payload: Vec<HostValue> },
+    /// Fields in declaration order.
+    Struct { ty: String, fields: Vec<(String, HostValue)> },
+}
+
+impl Runtime {
+    /// Copies `v` out of the heap. Functions, files, modules, channels and
+    /// builders have no host form.
+    pub fn to_host(&self, v: &Value) -> Result<HostValue, String> {
+        self.copy_to_host(v, 0)
+    }
+
+    fn copy_to_host(&self, v: &Value, depth: usize) -> Result<HostValue, String> {
+        if depth > MAX_DEPTH {
+            return Err("Value is nested too deeply (or contains itself) to pass to the host".into());
+        }
+        if v.is_unit() {
+            return Ok(HostValue::Unit);
+        }
+        if v.is_bool() {
+            return Ok(HostValue::Bool(v.as_bool()));
+        }
+        if v.is_int() {
+            return Ok(HostValue::Int(v.as_i64()));
+        }
+        if v.is_f64() {
+            return Ok(HostValue::Float(v.as_f64()));
+        }
+        let items = |values: &[Value]| -> Result<Vec<HostValue>, String> {
+            values.iter().map(|x| self.copy_to_host(x, depth + 1)).collect()
+        };
+        match (v.get_tag(), self.heap.get(v.as_obj_id())) {
+            (TAG_STR, ManagedObject::Str(s)) => Ok(HostValue::Str(s.as_str().to_string())),
+            (TAG_LIST, ManagedObject::List(values)) => Ok(HostValue::List(items(values)?)),
+            (TAG_TUPLE, ManagedObject::Tuple(values)) => Ok(HostValue::Tuple(items(values)?)),
+            (TAG_DICT, ManagedObject::Dict(d)) => {
+                let mut entries = Vec::with_capacity(d.len());
+                for (k, value) in d.entries() {
+                    let key = self.copy_to_host(&k.to_value(&self.heap), depth + 1)?;
+                    entries.push((key, self.copy_to_host(&value, depth + 1)?));
+                }
+                Ok(HostValue::Dict(entries))
+            }
+            (TAG_RANGE, ManagedObject::Range(start, end, inclusive)) => Ok(HostValue::Range(*start, *end, *inclusive)),
+            (TAG_OPTION, ManagedObject::OptionSome(inner)) => {
+                Ok(HostValue::Option(Some(Box::new(self.copy_to_host(inner, depth + 1)?))))
+            }
+            (TAG_ENUM, ManagedObject::Enum(e)) => {
+                let (ty, variant, payload) = e.as_ref();
+                if ty.as_str() == "Option" && variant.as_str() == "none" && payload.is_empty() {
+                    return Ok(HostValue::Option(None));
+                }
+                Ok(HostValue::Enum {
+                    ty: ty.as_str().to_string(),
+                    variant: variant.as_str().to_string(),
+                    payload: items(payload)?,
+                })
+            }
+            (TAG_STRUCT, ManagedObject::Struct(s)) => Ok(HostValue::Struct {
+                ty: s.ty.clone(),
+                fields: s.field_names.iter().cloned().zip(items(&s.fields)?).collect(),
+            }),
+            _ => Err(format!("Cannot pass a {} to the host", v.type_name())),
+        }
+    }
+
+    /// Builds `value` in the heap. Fails for ints outside [`INT_RANGE`] and
+    /// dict keys that cannot be hashed.
+    pub fn from_host(&mut self, value: HostValue) -> Result<Value, String> {
+        Ok(match value {
+            HostValue::Unit => Value::UNIT,
+            HostValue::Bool(b) => Value::from_bool(b),
+            HostValue::Int(i) => Value::from_i64(script_int(i)?),
+            HostValue::Float(f) => Value::from_f64(f),
+            HostValue::Str(s) => Value::str(self.alloc(ManagedObject::Str(s.into()))),
+            HostValue::List(items) => {
+                let values = self.all_from_host(items)?;
+                Value::list(self.alloc(ManagedObject::List(values.into())))
+            }
+            HostValue::Tuple(items) => {
+                let values = self.all_from_host(items)?;
+                Value::tuple(self.alloc(ManagedObject::Tuple(values)))
+            }
+            HostValue::Dict(entries) => {
+                let mut dict = crate::core::value::dict_with_capacity(entries.len(), &self.heap);
+                for (k, v) in entries {
+                    let key = self.from_host(k)?;
+                    let key = DictKey::from_value(key, &mut self.heap)
+                        .ok_or_else(|| "Dict keys from the host must be strings, ints or other hashable values".to_string())?;
+                    let value = self.from_host(v)?;
+                    dict.insert_entry(key, value);
+                }
+                Value::dict(self.alloc(ManagedObject::Dict(dict)))
+            }
+            HostValue::Range(start, end, inclusive) => {
+                let (start, end) = (script_int(start)?, script_int(end)?);
+                Value::range(self.alloc(ManagedObject::Range(start, end, inclusive)))
+            }
+            HostValue::Option(None) => self.option_none(),
+            HostValue::Option(Some(inner)) => {
+                let v = self.from_host(*inner)?;
+                self.option_some(v)
+            }
+            HostValue::Enum { ty, variant, payload } => {
+                let payload = self.all_from_host(payload)?.into_boxed_slice();
+                Value::enum_obj(self.alloc(ManagedObject::Enum(Box::new((ty.into(), variant.into(), payload)))))
+            }
+            HostValue::Struct { ty, fields } => {
+                let (names, values): (Vec<String>, Vec<HostValue>) = fields.into_iter().unzip();
+                let fields = self.all_from_host(values)?.into_boxed_slice();
+                Value::struct_obj(self.alloc(ManagedObject::Struct(Box::new(crate::core::value::StructInstance {
+                    ty_hash: xu_ir::stable_hash64(&ty),
+                    ty,
+                    fields,
+                    field_names: names.into(),
+                }))))
+            }
+        })
+    }
+
+    fn all_from_host(&mut self, items: Vec<HostValue>) -> Result<Vec<Value>, String> {
+        items.into_iter().map(|item| self.from_host(item)).collect()
+    }
+
+    /// Defines a global function `name` that scripts call like a builtin,
+    /// with copies of their arguments; an `Err` is raised in the script as a
+    /// runtime error. Like [`Runtime::define_global_constant`], it is defined
+    /// from the next execution on, and kept across resets. Defining a name
+    /// again replaces its function.
+    ///
+    /// Strict analysis rejects calls to names it does not know; pass
+    /// [`Runtime::host_function_names`] to the frontend as predefined names.
+    pub fn define_host_function(
+        &mut self,
+        name: &str,
+        f: impl Fn(&[HostValue]) -> Result<HostValue, String> + 'static,
+    ) {
+        let f: crate::core::value::HostFunction = std::rc::Rc::new(move |rt: &mut Runtime, args: &[Value]| {
+            let args = args.iter().map(|a| rt.to_host(a)).collect::<Result<Vec<_>, _>>()?;
+            let result = f(&args)?;
+            rt.from_host(result)
+        });
+        match self.host_functions.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = f,
+            None => self.host_functions.push((name.to_string(), f)),
+        }
+    }
+
+    /// Names of the functions defined with [`Runtime::define_host_function`].
+    pub fn host_function_names(&self) -> Vec<&str> {
+        self.host_functions.iter().map(|(n, _)| n.as_str()).collect()
+    }
+
+    /// Installs the builtins of `provider` after the standard ones and those
+    /// of [plugins](crate::plugin), from the next execution on; a name it
+    /// registers again replaces the earlier builtin. Workers started by the
+    /// script only get the standard builtins and plugins.
+    pub fn add_builtin_provider(&mut self, provider: impl BuiltinProvider + 'static) {
+        self.builtin_providers.push(std::rc::Rc::new(provider));
+    }
+
+    /// Names of the builtins installed besides the standard ones, from
+    /// plugins and [`Runtime::add_builtin_provider`]. Like host functions,
+    /// strict analysis only knows them when passed as predefined names.
+    pub fn extra_builtin_names(&self) -> Vec<String> {
+        let mut registry = BuiltinRegistry::new();
+        #[cfg(feature = "plugins")]
+        for plugin in crate::plugin::plugins() {
+            plugin.provider.install(&mut registry);
+        }
+        for provider in &self.builtin_providers {
+            provider.install(&mut registry);
+        }
+        registry.names()
+    }
+
+    /// [`Runtime::call_global`] with arguments and result copied to and from
+    /// the host.
+    pub fn call_global_host(&mut self, name: &str, args: Vec<HostValue>) -> Result<HostValue, String> {
+        let args = self.all_from_host(args)?;
+        let result = self.call_global(name, &args)?;
+        self.to_host(&result)
+    }
+}
+
+/// 超出 48 位的整数放进值里会被截断，直接拒绝
+fn script_int(i: i64) -> Result<i64, String> {
+    if INT_RANGE.contains(&i) {
+        Ok(i)
+    } else {
+        Err(format!("Int {i} from the host does not fit in 48 bits"))
+    }
+}
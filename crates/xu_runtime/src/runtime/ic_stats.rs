@@ -0,0 +1,305 @@
+//! 内联缓存统计子模块
+//! 按 (操作种类, 槽位) 记录 IC 命中与未命中次数，以及每个站点见过的接收者形状，
+//! 用于找出多态的热点站点。默认关闭，关闭时每次查找只多一次分支判断。
+
+use smallvec::SmallVec;
+
+use crate::core::heap::{Heap, ManagedObject};
+use crate::core::value::{FastHashMap, fast_map_new};
+use crate::core::Value;
+
+use super::core::Runtime;
+use super::heap_snapshot::push_json_str;
+
+/// Distinct receiver shapes remembered per site; a site that sees more is
+/// reported as megamorphic.
+const MAX_SHAPES: usize = 8;
+
+/// The operation an inline cache slot serves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IcKind {
+    /// `obj.field` on a struct or dict.
+    Field,
+    /// `dict[key]`.
+    Index,
+    /// `recv.method(...)`.
+    Method,
+    /// The `dict.get(key)` fast path.
+    DictGet,
+    /// The `dict.insert(key, value)` fast path.
+    DictInsert,
+}
+
+impl IcKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IcKind::Field => "field",
+            IcKind::Index => "index",
+            IcKind::Method => "method",
+            IcKind::DictGet => "dict.get",
+            IcKind::DictInsert => "dict.insert",
+        }
+    }
+}
+
+/// Lookups of one inline cache site.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IcSite {
+    pub kind: IcKind,
+    /// The cache slot, unique per kind within one program.
+    pub slot: usize,
+    /// The field or method name looked up.
+    pub name: String,
+    pub hits: u64,
+    pub misses: u64,
+    /// Receiver types seen, in order of first appearance: the struct or
+    /// enum name, or the type name for other values.
+    pub shapes: Vec<String>,
+    /// More than `shapes.len()` receiver types were seen.
+    pub megamorphic: bool,
+}
+
+impl IcSite {
+    pub fn lookups(&self) -> u64 {
+        self.hits + self.misses
+    }
+
+    /// The site saw receivers of more than one type.
+    pub fn is_polymorphic(&self) -> bool {
+        self.shapes.len() > 1 || self.megamorphic
+    }
+}
+
+/// Totals for one [`IcKind`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IcKindSummary {
+    pub kind: IcKind,
+    pub hits: u64,
+    pub misses: u64,
+    pub sites: usize,
+}
+
+/// Inline cache activity since the current (or last) execution started, from
+/// [`Runtime::ic_stats`]. Empty unless enabled with
+/// [`Runtime::set_ic_stats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IcStats {
+    /// One entry per kind that had lookups, in [`IcKind`] order.
+    pub kinds: Vec<IcKindSummary>,
+    /// Every site, busiest first.
+    pub sites: Vec<IcSite>,
+}
+
+#[derive(Default)]
+struct SiteCounters {
+    name: String,
+    hits: u64,
+    misses: u64,
+    shapes: SmallVec<[String; 2]>,
+    megamorphic: bool,
+}
+
+/// 运行期计数器，存放在 CacheManager 中，GC 清空槽位时保留
+pub(crate) struct IcRecorder {
+    sites: FastHashMap<(IcKind, usize), SiteCounters>,
+}
+
+impl IcRecorder {
+    pub(crate) fn new() -> Self {
+        Self { sites: fast_map_new() }
+    }
+
+    /// 记录一次查找
+    pub(crate) fn record(&mut self, heap: &Heap, kind: IcKind, slot: usize, name: &str, recv: Value, hit: bool) {
+        let site = self.sites.entry((kind, slot)).or_default();
+        if site.name.is_empty() {
+            site.name = name.to_string();
+        }
+        if hit {
+            site.hits += 1;
+        } else {
+            site.misses += 1;
+        }
+        if site.megamorphic {
+            return;
+        }
+        let shape = shape_of(heap, recv);
+        if site.shapes.iter().any(|s| s == shape) {
+            return;
+        }
+        if site.shapes.len() == MAX_SHAPES {
+            site.megamorphic = true;
+        } else {
+            site.shapes.push(shape.to_string());
+        }
+    }
+
+    fn stats(&self) -> IcStats {
+        let mut sites: Vec<IcSite> = self
+            .sites
+            .iter()
+            .map(|(&(kind, slot), c)| IcSite {
+                kind,
+                slot,
+                name: c.name.clone(),
+                hits: c.hits,
+                misses: c.misses,
+                shapes: c.shapes.to_vec(),
+                megamorphic: c.megamorphic,
+            })
+            .collect();
+        sites.sort_by(|a, b| {
+            b.lookups()
+                .cmp(&a.lookups())
+                .then(a.kind.cmp(&b.kind))
+                .then(a.slot.cmp(&b.slot))
+        });
+        let mut kinds: Vec<IcKindSummary> = Vec::new();
+        for s in &sites {
+            match kinds.iter_mut().find(|k| k.kind == s.kind) {
+                Some(k) => {
+                    k.hits += s.hits;
+                    k.misses += s.misses;
+                    k.sites += 1;
+                }
+                None => kinds.push(IcKindSummary { kind: s.kind, hits: s.hits, misses: s.misses, sites: 1 }),
+            }
+        }
+        kinds.sort_by_key(|k| k.kind);
+        IcStats { kinds, sites }
+    }
+}
+
+/// 统计开启时记录一次 IC 查找。直接接收 `caches.ic_stats` 字段，
+/// 调用方在持有 IC 槽或堆对象借用时也能记录
+#[inline]
+pub(crate) fn record_ic(
+    stats: &mut Option<Box<IcRecorder>>,
+    heap: &Heap,
+    kind: IcKind,
+    slot: usize,
+    name: &str,
+    recv: Value,
+    hit: bool,
+) {
+    if let Some(stats) = stats {
+        stats.record(heap, kind, slot, name, recv, hit);
+    }
+}
+
+/// 与各 IC 的守卫条件一致：结构体与枚举按类型名区分，其余按值类型
+fn shape_of(heap: &Heap, recv: Value) -> &str {
+    let tag = recv.get_tag();
+    if tag == crate::core::value::TAG_STRUCT {
+        if let ManagedObject::Struct(s) = heap.get(recv.as_obj_id()) {
+            return s.ty.as_str();
+        }
+    } else if tag == crate::core::value::TAG_ENUM {
+        if let ManagedObject::Enum(e) = heap.get(recv.as_obj_id()) {
+            return e.as_ref().0.as_str();
+        }
+    }
+    recv.type_name()
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 { 0.0 } else { part as f64 * 100.0 / total as f64 }
+}
+
+impl IcStats {
+    /// Sites that saw receivers of more than one type, busiest first.
+    pub fn polymorphic(&self) -> impl Iterator<Item = &IcSite> {
+        self.sites.iter().filter(|s| s.is_polymorphic())
+    }
+
+    /// A report for people: hit rates per kind, then up to `limit` of the
+    /// busiest polymorphic sites.
+    pub fn to_text(&self, limit: usize) -> String {
+        let hits: u64 = self.kinds.iter().map(|k| k.hits).sum();
+        let lookups: u64 = self.kinds.iter().map(|k| k.hits + k.misses).sum();
+        let mut out = format!(
+            "inline caches: {} lookups, {:.1}% hits, {} sites\n",
+            lookups,
+            percent(hits, lookups),
+            self.sites.len()
+        );
+        for k in &self.kinds {
+            out.push_str(&format!(
+                "  {:<12} {:>10} hits {:>10} misses {:>6.1}%  {} sites\n",
+                k.kind.as_str(),
+                k.hits,
+                k.misses,
+                percent(k.hits, k.hits + k.misses),
+                k.sites
+            ));
+        }
+        let polymorphic: Vec<&IcSite> = self.polymorphic().take(limit).collect();
+        if polymorphic.is_empty() {
+            out.push_str("polymorphic sites: none\n");
+            return out;
+        }
+        out.push_str("polymorphic sites:\n");
+        for s in polymorphic {
+            out.push_str(&format!(
+                "  {} {} (slot {}): {} lookups, {:.1}% hits, {}{}\n",
+                s.kind.as_str(),
+                s.name,
+                s.slot,
+                s.lookups(),
+                percent(s.hits, s.lookups()),
+                s.shapes.join(", "),
+                if s.megamorphic { ", ... (megamorphic)" } else { "" }
+            ));
+        }
+        out
+    }
+
+    /// The statistics as one JSON object with the fields of this struct.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"kinds\":[");
+        for (i, k) in self.kinds.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&format!(
+                "{{\"kind\":\"{}\",\"hits\":{},\"misses\":{},\"sites\":{}}}",
+                k.kind.as_str(),
+                k.hits,
+                k.misses,
+                k.sites
+            ));
+        }
+        out.push_str("],\"sites\":[");
+        for (i, s) in self.sites.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&format!("{{\"kind\":\"{}\",\"slot\":{},\"name\":", s.kind.as_str(), s.slot));
+            push_json_str(&mut out, &s.name);
+            out.push_str(&format!(",\"hits\":{},\"misses\":{},\"shapes\":[", s.hits, s.misses));
+            for (j, shape) in s.shapes.iter().enumerate() {
+                if j > 0 {
+                    out.push(',');
+                }
+                push_json_str(&mut out, shape);
+            }
+            out.push_str(&format!("],\"megamorphic\":{}}}", s.megamorphic));
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+impl Runtime {
+    /// Turns inline cache statistics on or off. Counting costs a hash map
+    /// update per cached lookup, so it is off by default.
+    pub fn set_ic_stats(&mut self, enabled: bool) {
+        self.caches.ic_stats = enabled.then(|| Box::new(IcRecorder::new()));
+    }
+
+    /// Inline cache hits and misses per site since the current (or last)
+    /// execution started.
+    pub fn ic_stats(&self) -> IcStats {
+        self.caches.ic_stats.as_ref().map(|r| r.stats()).unwrap_or_default()
+    }
+}
@@ -0,0 +1,55 @@
+//! 中断子模块
+//! 宿主线程可请求停止正在执行的脚本；执行在下一个安全点以 Interrupted 错误返回。
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::core::Runtime;
+
+/// A thread-safe token that stops the execution of the [`Runtime`] it came
+/// from, for example to enforce a timeout from another thread.
+///
+/// After [`interrupt`](Self::interrupt) the running script fails with an
+/// `Interrupted` error (code `E3007`) at its next safepoint: every loop
+/// iteration, and every 1024 bytecode instructions. A native call that is
+/// already blocking, such as `recv` on a channel, returns first. An
+/// interrupt requested while nothing runs stops the next execution.
+#[derive(Clone, Debug, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    /// Requests that the current execution stop.
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether an interrupt was requested and no safepoint has acted on it
+    /// yet.
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// 中断标志本身，JIT 代码在循环回边读取
+    #[cfg(feature = "jit")]
+    pub(crate) fn flag(&self) -> &AtomicBool {
+        &self.0
+    }
+}
+
+impl Runtime {
+    /// A handle that interrupts this runtime from any thread. All handles of
+    /// a runtime share one flag, which the safepoint that stops the script
+    /// clears, so each interrupt stops exactly one execution.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// 安全点：宿主请求中断时取走中断标志并返回 Interrupted 错误
+    #[inline]
+    pub(crate) fn check_interrupt(&self) -> Result<(), String> {
+        if self.interrupt.is_interrupted() && self.interrupt.0.swap(false, Ordering::Relaxed) {
+            return Err(self.error(xu_syntax::DiagnosticKind::Interrupted));
+        }
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@ use crate::methods;
 
 use super::cache::MethodICSlot;
 use super::core::Runtime;
+use super::ic_stats::{IcKind, record_ic};
 
 pub(crate) use crate::methods::MethodKind;
 
@@ -29,11 +30,15 @@ impl Runtime {
         if let Some(idx) = slot_idx {
             if idx < self.caches.ic_method_slots.len() {
                 let slot = &self.caches.ic_method_slots[idx];
-                if slot.tag == tag && slot.method_hash == method_hash {
+                if slot.tag == tag
+                    && slot.method_hash == method_hash
+                    && slot.generation == self.caches.ic_generation
+                {
                     if tag == crate::core::value::TAG_STRUCT {
                         let id = recv.as_obj_id();
                         if let crate::core::heap::ManagedObject::Struct(s) = self.heap.get(id) {
                             if slot.struct_ty_hash == s.ty_hash {
+                                record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Method, idx, method, recv, true);
                                 if let Some(f) = slot.cached_bytecode.as_ref() {
                                     if args.is_empty() {
                                         return self.call_bytecode_function(f.clone(), &[recv]);
@@ -61,6 +66,7 @@ impl Runtime {
                             let (ty, _variant, _payload) = e.as_ref();
                             let ty_hash = xu_ir::stable_hash64(ty.as_str());
                             if slot.struct_ty_hash == ty_hash {
+                                record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Method, idx, method, recv, true);
                                 if let Some(f) = slot.cached_bytecode.as_ref() {
                                     if args.is_empty() {
                                         return self.call_bytecode_function(f.clone(), &[recv]);
@@ -81,12 +87,14 @@ impl Runtime {
                             }
                         }
                     } else if slot.kind != MethodKind::Unknown {
+                        record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Method, idx, method, recv, true);
                         return methods::dispatch_builtin_method(
                             self, recv, slot.kind, args, method,
                         );
                     }
                 }
             }
+            record_ic(&mut self.caches.ic_stats, &self.heap, IcKind::Method, idx, method, recv, false);
         }
 
         if tag == crate::core::value::TAG_MODULE {
@@ -109,6 +117,7 @@ impl Runtime {
                 self.caches.ic_method_slots[idx] = MethodICSlot {
                     tag,
                     method_hash,
+                    generation: self.caches.ic_generation,
                     struct_ty_hash: 0,
                     kind: MethodKind::Unknown,
                     cached_func: callee,
@@ -193,6 +202,7 @@ impl Runtime {
                 self.caches.ic_method_slots[idx] = MethodICSlot {
                     tag,
                     method_hash,
+                    generation: self.caches.ic_generation,
                     struct_ty_hash: if let crate::core::heap::ManagedObject::Struct(s) = self.heap.get(id) {
                         s.ty_hash
                     } else {
@@ -290,6 +300,7 @@ impl Runtime {
                             self.caches.ic_method_slots[idx] = MethodICSlot {
                                 tag,
                                 method_hash,
+                                generation: self.caches.ic_generation,
                                 struct_ty_hash: 0,
                                 kind,
                                 cached_func: Value::UNIT,
@@ -308,6 +319,7 @@ impl Runtime {
                 self.caches.ic_method_slots[idx] = MethodICSlot {
                     tag,
                     method_hash,
+                    generation: self.caches.ic_generation,
                     struct_ty_hash: ty_hash,
                     kind: MethodKind::Unknown,
                     cached_func: callee,
@@ -353,6 +365,7 @@ impl Runtime {
                 self.caches.ic_method_slots[idx] = MethodICSlot {
                     tag,
                     method_hash,
+                    generation: self.caches.ic_generation,
                     struct_ty_hash: 0,
                     kind,
                     cached_func: Value::UNIT,
@@ -13,17 +13,35 @@ mod cache_manager;
 mod object_pools;
 mod locals;
 mod precompile;
+mod output;
+mod interrupt;
+mod signal;
+mod heap_snapshot;
+mod ic_stats;
+mod bench;
+mod differential;
+mod host;
 mod method_call;
+mod traceback;
 pub(crate) mod dict_helpers;
 
 // Re-export all public types
-pub use config::{ExecResult, Flow, RuntimeConfig};
+pub use config::{ExecResult, Flow, GcStats, HeapCompaction, OutputOverflow, RuntimeConfig};
 pub use cache::{ICSlot, MethodICSlot};
+pub use interrupt::InterruptHandle;
+pub use signal::{Signal, SignalHandle};
+pub use heap_snapshot::{HeapSnapshot, KindSummary, RetainedObject};
+pub use ic_stats::{IcKind, IcKindSummary, IcSite, IcStats};
+pub use bench::{BenchConfig, BenchResult};
+pub use differential::{Divergence, EngineComparison, EngineRun, compare_engines};
+pub use host::{HostValue, INT_RANGE};
+pub use traceback::TraceFrame;
+pub use output::{OutputSink, OutputStream, StdStreams};
+pub(crate) use ic_stats::record_ic;
+pub(crate) use binary::in_range;
 pub(crate) use cache::{DictCacheLast, DictCacheIntLast, DictInsertCacheLast};
 
-// Re-export Text for use in submodules
-pub use crate::core::text::Text;
-
 // The main Runtime implementation is in core.rs
 mod core;
 pub use self::core::Runtime;
+pub(crate) use self::core::exit_message;
@@ -5,9 +5,6 @@ use crate::core::Env;
 use crate::core::Value;
 use crate::vm::{IterState, Handler};
 
-/// 小列表池的最大容量
-const SMALL_LIST_CAP: usize = 8;
-
 /// 对象池管理器
 /// 包含所有对象池相关的字段：
 /// - env_pool: 环境对象池
@@ -15,7 +12,8 @@ const SMALL_LIST_CAP: usize = 8;
 /// - vm_iters_pool: 迭代器状态池
 /// - vm_handlers_pool: 异常处理器池
 /// - builder_pool: 字符串构建器池
-/// - small_list_pool: 小列表池 (容量 ≤8)
+///
+/// 列表、字典与文本的缓冲区由堆在回收时池化（见 Heap::take_list 等）。
 pub struct ObjectPools {
     /// 环境对象池
     pub env_pool: Vec<Env>,
@@ -27,8 +25,6 @@ pub struct ObjectPools {
     pub vm_handlers_pool: Vec<Vec<Handler>>,
     /// 字符串构建器池
     pub builder_pool: Vec<String>,
-    /// 小列表池 (容量 ≤8 的列表)
-    pub small_list_pool: Vec<Vec<Value>>,
 }
 
 impl ObjectPools {
@@ -40,7 +36,6 @@ impl ObjectPools {
             vm_iters_pool: Vec::new(),
             vm_handlers_pool: Vec::new(),
             builder_pool: Vec::new(),
-            small_list_pool: Vec::new(),
         }
     }
 
@@ -107,20 +102,6 @@ impl ObjectPools {
             self.builder_pool.push(s);
         }
     }
-
-    /// 从小列表池获取或创建新列表
-    /// 仅用于容量 ≤8 的列表
-    #[inline]
-    pub fn get_small_list(&mut self, cap: usize) -> Option<Vec<Value>> {
-        if cap <= SMALL_LIST_CAP {
-            self.small_list_pool.pop().map(|mut v| {
-                v.clear();
-                v
-            })
-        } else {
-            None
-        }
-    }
 }
 
 impl Default for ObjectPools {
@@ -0,0 +1,183 @@
+//! 输出缓冲子模块
+//! 输出超过上限后写入临时文件，避免超大输出占满内存。
+//! 设置了 [`OutputSink`] 时标准输出与标准错误直接流式写出，不经缓冲。
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::config::OutputOverflow;
+use super::core::Runtime;
+
+/// Which of the script's output streams a write goes to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Receives the script's output as it is written; see
+/// [`Runtime::set_output_streams`]. A boxed writer is a sink taking both
+/// streams in the order they are written.
+pub trait OutputSink {
+    fn write(&mut self, stream: OutputStream, text: &str) -> std::io::Result<()>;
+    /// Called when a prompt or progress bar must show right away.
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl OutputSink for Box<dyn Write> {
+    fn write(&mut self, _stream: OutputStream, text: &str) -> std::io::Result<()> {
+        self.write_all(text.as_bytes())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Write::flush(self)
+    }
+}
+
+/// The process's standard output and error. With `merged`, standard error
+/// goes to standard output as well, so both keep their order in one stream.
+pub struct StdStreams {
+    pub merged: bool,
+}
+
+impl OutputSink for StdStreams {
+    fn write(&mut self, stream: OutputStream, text: &str) -> std::io::Result<()> {
+        match stream {
+            OutputStream::Stderr if !self.merged => {
+                // Keep what was written to stdout before it in front.
+                std::io::stdout().flush()?;
+                std::io::stderr().write_all(text.as_bytes())
+            }
+            _ => std::io::stdout().write_all(text.as_bytes()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stdout().flush()
+    }
+}
+
+/// 单次执行期间的输出溢出状态
+#[derive(Default)]
+pub(crate) struct OutputSpill {
+    /// 临时文件
+    file: Option<(PathBuf, std::fs::File)>,
+    /// 已移出内存缓冲的字节数
+    flushed: u64,
+    /// 因写入失败而丢弃的字节数
+    dropped: u64,
+}
+
+impl Runtime {
+    /// Caps the in-memory output buffer at `limit` bytes. Output beyond the cap
+    /// is spilled to a temporary file. `None` removes the cap.
+    pub fn set_output_limit(&mut self, limit: Option<usize>) {
+        self.output_limit = limit;
+    }
+
+    /// Streams standard output and error to `sink` as the script writes
+    /// them, instead of buffering; [`ExecResult`](crate::ExecResult) then
+    /// carries no output. The output limit no longer applies.
+    pub fn set_output_streams(&mut self, sink: Box<dyn OutputSink>) {
+        self.output_streams = Some(sink);
+    }
+
+    /// Writes `s`, and a line break with `newline`, to the output streams;
+    /// `false` when none are set and the caller should buffer it.
+    pub(crate) fn stream_output(&mut self, stream: OutputStream, s: &str, newline: bool) -> bool {
+        let Some(sink) = self.output_streams.as_mut() else {
+            return false;
+        };
+        let _ = sink.write(stream, s);
+        if newline {
+            let _ = sink.write(stream, "\n");
+        }
+        true
+    }
+
+    pub(crate) fn check_output_limit(&mut self) {
+        if let Some(limit) = self.output_limit {
+            if self.output.len() > limit {
+                self.flush_output_overflow();
+            }
+        }
+    }
+
+    /// Flushes the output streams right away, for prompts and progress bars
+    /// that must show before the script goes on. Without them the output
+    /// stays buffered.
+    pub(crate) fn flush_output(&mut self) {
+        if let Some(sink) = self.output_streams.as_mut() {
+            let _ = sink.flush();
+        }
+    }
+
+    fn flush_output_overflow(&mut self) {
+        let bytes = self.output.len() as u64;
+        if self.output_spill.file.is_none() {
+            self.output_spill.file = open_spill_file();
+        }
+        let written = match self.output_spill.file.as_mut() {
+            Some((_, f)) => f.write_all(self.output.as_bytes()).is_ok(),
+            None => false,
+        };
+        if written {
+            self.output_spill.flushed += bytes;
+        } else {
+            self.output_spill.dropped += bytes;
+        }
+        self.output.clear();
+    }
+
+    /// Takes the buffered output at the end of an execution. If the limit was
+    /// exceeded, the remaining buffer is flushed as well so the spill file
+    /// holds the complete output.
+    pub(crate) fn finish_output(&mut self) -> (String, Option<OutputOverflow>) {
+        let spill = &self.output_spill;
+        if spill.flushed == 0 && spill.dropped == 0 {
+            return (std::mem::take(&mut self.output), None);
+        }
+        if !self.output.is_empty() {
+            self.flush_output_overflow();
+        }
+        let spill = std::mem::take(&mut self.output_spill);
+        let overflow = match spill.file {
+            Some((path, mut f)) if spill.dropped == 0 => {
+                let _ = f.flush();
+                OutputOverflow::Spilled {
+                    path,
+                    bytes: spill.flushed,
+                }
+            }
+            _ => OutputOverflow::Truncated {
+                dropped: spill.dropped,
+            },
+        };
+        (String::new(), Some(overflow))
+    }
+}
+
+/// 文件名带随机后缀且只新建不覆盖，别人预先放好的文件或链接不会被写入
+#[cfg(feature = "sys")]
+fn open_spill_file() -> Option<(PathBuf, std::fs::File)> {
+    use std::hash::BuildHasher;
+    let seeder = std::hash::RandomState::new();
+    for attempt in 0..16u32 {
+        let suffix = seeder.hash_one(attempt);
+        let path = std::env::temp_dir().join(format!("xu-output-{}-{suffix:016x}.txt", std::process::id()));
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(f) => return Some((path, f)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(_) => return None,
+        }
+    }
+    None
+}
+
+/// Without the `sys` feature there is no temporary directory to spill to.
+#[cfg(not(feature = "sys"))]
+fn open_spill_file() -> Option<(PathBuf, std::fs::File)> {
+    None
+}
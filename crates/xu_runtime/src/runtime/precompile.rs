@@ -143,7 +143,14 @@ impl Runtime {
                 Self::precompile_expr(left)?;
                 Self::precompile_expr(right)
             }
-            Expr::Group(e) => Self::precompile_expr(e),
+            Expr::Compare(c) => {
+                Self::precompile_expr(&c.first)?;
+                for (_, e) in c.links.iter() {
+                    Self::precompile_expr(e)?;
+                }
+                Ok(())
+            }
+            Expr::Group(e) | Expr::Try(e) | Expr::OptChain(e) | Expr::OptLink(e) => Self::precompile_expr(e),
             Expr::Ident(..) | Expr::Int(_) | Expr::Float(_) | Expr::Bool(_) => Ok(()),
             _ => Ok(()),
         }
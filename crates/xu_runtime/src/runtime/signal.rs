@@ -0,0 +1,135 @@
+//! 信号子模块
+//! 脚本用 `on_signal` 登记处理函数；宿主或操作系统发来的信号先记在
+//! [`SignalHandle`] 中，执行到下一个安全点时再调用处理函数。
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::core::Runtime;
+use crate::Value;
+
+/// A signal a script can handle with `on_signal`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    /// `"int"`: Ctrl-C.
+    Int,
+    /// `"term"`: a request to terminate.
+    Term,
+    /// `"hup"`: the terminal hung up, or a request to reload.
+    Hup,
+}
+
+impl Signal {
+    pub const ALL: [Signal; 3] = [Signal::Int, Signal::Term, Signal::Hup];
+
+    /// The name scripts use for the signal.
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::Int => "int",
+            Signal::Term => "term",
+            Signal::Hup => "hup",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Signal> {
+        Signal::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    pub(crate) fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// A thread-safe token that delivers signals to the [`Runtime`] it came
+/// from. Raising a signal only records it, so this is safe to call from an
+/// OS signal handler.
+///
+/// The script's handler for the signal runs at the next safepoint: every
+/// loop iteration, and every 1024 bytecode instructions. A signal the
+/// script has no handler for stops it with an `Interrupted` error, as
+/// [`InterruptHandle::interrupt`](crate::InterruptHandle::interrupt) does.
+#[derive(Clone, Debug, Default)]
+pub struct SignalHandle(Arc<AtomicU32>);
+
+impl SignalHandle {
+    pub fn raise(&self, signal: Signal) {
+        self.0.fetch_or(signal.bit(), Ordering::Relaxed);
+    }
+
+    /// The counter OS signal handlers set bits in.
+    #[cfg(all(feature = "sys", unix))]
+    pub(crate) fn pending(&self) -> &Arc<AtomicU32> {
+        &self.0
+    }
+}
+
+/// 已登记的处理函数与待处理的信号
+#[derive(Default)]
+pub(crate) struct SignalHandlers {
+    pending: SignalHandle,
+    pub(crate) handlers: Vec<(Signal, Value)>,
+    /// 正在运行处理函数，期间的信号留待其返回后处理
+    delivering: bool,
+}
+
+impl SignalHandlers {
+    /// 新一次执行开始：清空处理函数与未处理的信号
+    pub(crate) fn reset(&mut self) {
+        self.handlers.clear();
+        self.pending.0.store(0, Ordering::Relaxed);
+        self.delivering = false;
+    }
+
+    /// 待处理信号的位集，JIT 代码在循环回边读取
+    #[cfg(feature = "jit")]
+    pub(crate) fn pending_flags(&self) -> &AtomicU32 {
+        &self.pending.0
+    }
+}
+
+impl Runtime {
+    /// A handle that raises signals in this runtime from any thread. All
+    /// handles of a runtime share one set of pending signals, cleared when
+    /// an execution (`exec_*`) starts.
+    pub fn signal_handle(&self) -> SignalHandle {
+        self.signals.pending.clone()
+    }
+
+    /// Makes `handler` the script's handler for `signal`, replacing an
+    /// earlier one, and asks the [`SignalSource`](crate::SignalSource) to
+    /// forward the signal from the OS.
+    pub(crate) fn set_signal_handler(&mut self, signal: Signal, handler: Value) {
+        match self.signals.handlers.iter_mut().find(|(s, _)| *s == signal) {
+            Some(entry) => entry.1 = handler,
+            None => self.signals.handlers.push((signal, handler)),
+        }
+        self.watch_signal(signal);
+    }
+
+    /// 安全点：调用待处理信号的处理函数；没有处理函数的信号以 Interrupted 错误结束执行
+    #[inline]
+    pub(crate) fn check_signals(&mut self) -> Result<(), String> {
+        if self.signals.delivering || self.signals.pending.0.load(Ordering::Relaxed) == 0 {
+            return Ok(());
+        }
+        self.deliver_signals()
+    }
+
+    fn deliver_signals(&mut self) -> Result<(), String> {
+        let pending = self.signals.pending.0.swap(0, Ordering::Relaxed);
+        self.signals.delivering = true;
+        let mut res = Ok(());
+        for signal in Signal::ALL.into_iter().filter(|s| pending & s.bit() != 0) {
+            let handler = self.signals.handlers.iter().find(|(s, _)| *s == signal).map(|(_, h)| *h);
+            res = match handler {
+                Some(f) => self.call_function(f, &[]).map(|_| ()),
+                None => Err(self.error(xu_syntax::DiagnosticKind::Interrupted)),
+            };
+            if res.is_err() {
+                break;
+            }
+        }
+        self.signals.delivering = false;
+        res
+    }
+}
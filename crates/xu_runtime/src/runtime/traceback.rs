@@ -0,0 +1,61 @@
+//! 运行时错误的调用栈子模块
+//! 错误或未捕获的异常离开一段字节码时，按字节码的源码映射记录出错指令所在的语句，
+//! 由内向外逐帧累积。新的字节码开始执行时清空上一次已被处理的记录。
+
+use xu_ir::Bytecode;
+use xu_syntax::Span;
+
+use super::core::Runtime;
+
+/// Where a runtime error passed through bytecode, innermost first, from
+/// [`Runtime::error_trace`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceFrame {
+    /// The source file name the bytecode was compiled from.
+    pub file: String,
+    /// The running function, `Type.method` for methods and `<closure>` for
+    /// function literals; `None` for module top-level code.
+    pub function: Option<String>,
+    /// The statement that was executing.
+    pub span: Span,
+}
+
+/// `__method__T__m` 与 `__static__T__m` 显示为 `T.m`，闭包显示为 `<closure>`
+fn display_name(name: &str) -> String {
+    if name.starts_with("__anon_func_") {
+        return "<closure>".to_string();
+    }
+    let bare = name
+        .strip_prefix("__method__")
+        .or_else(|| name.strip_prefix("__static__"));
+    match bare.and_then(|b| b.split_once("__")) {
+        Some((ty, method)) => format!("{ty}.{method}"),
+        None => name.to_string(),
+    }
+}
+
+impl Runtime {
+    /// 字节码以错误或异常结束时记录一帧；`ip` 为出错的指令
+    pub(crate) fn record_trace_frame(&mut self, bc: &Bytecode, ip: usize) {
+        let func = self.current_func.take();
+        self.push_trace_frame(bc, ip, func.as_deref());
+        self.current_func = func;
+    }
+
+    /// 同上，函数名由调用方给出
+    pub(crate) fn push_trace_frame(&mut self, bc: &Bytecode, ip: usize, func: Option<&str>) {
+        let Some(span) = bc.spans.span_at(ip) else { return };
+        self.error_trace.push(TraceFrame {
+            file: bc.spans.file.clone(),
+            function: func.map(display_name),
+            span,
+        });
+    }
+
+    /// The bytecode statements the last runtime error or uncaught exception
+    /// passed through, innermost first. Empty after a successful execution
+    /// and for code without source positions.
+    pub fn error_trace(&self) -> &[TraceFrame] {
+        &self.error_trace
+    }
+}
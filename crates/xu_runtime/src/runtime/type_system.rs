@@ -17,7 +17,7 @@ type HashMap<K, V> = FastHashMap<K, V>;
 /// - next_id: 下一个唯一 ID
 pub struct TypeSystem {
     /// 结构体定义映射
-    pub structs: HashMap<String, StructDef>,
+    pub structs: HashMap<String, Rc<StructDef>>,
     /// 结构体字段布局缓存
     pub struct_layouts: HashMap<String, Rc<[String]>>,
     /// 枚举定义映射
@@ -40,6 +40,18 @@ impl TypeSystem {
         }
     }
 
+    /// 注册结构体字段布局，返回布局是否发生变化
+    /// 布局不变时保留原有 Rc，使已缓存的 IC 继续有效
+    pub fn define_struct_layout(&mut self, name: &str, fields: &[xu_ir::StructField]) -> bool {
+        if let Some(old) = self.struct_layouts.get(name) {
+            if old.len() == fields.len() && old.iter().zip(fields).all(|(a, f)| *a == f.name) {
+                return false;
+            }
+        }
+        let layout: Rc<[String]> = fields.iter().map(|f| f.name.clone()).collect();
+        self.struct_layouts.insert(name.to_string(), layout).is_some()
+    }
+
     /// 重置类型系统状态
     pub fn reset(&mut self) {
         self.structs.clear();
@@ -1,3 +1,4 @@
+use super::float_fmt::{FloatFormat, write_f64};
 use super::helpers::value_to_string;
 use crate::Text;
 use crate::Value;
@@ -6,7 +7,7 @@ use crate::core::heap::Heap;
 pub trait Appendable {
     fn append_str(&mut self, s: &str);
     fn append_i64(&mut self, i: i64);
-    fn append_f64(&mut self, f: f64);
+    fn append_f64(&mut self, f: f64, format: &FloatFormat);
     fn append_bool(&mut self, b: bool);
     fn append_null(&mut self);
     fn append_value(&mut self, v: &Value, heap: &Heap);
@@ -20,13 +21,8 @@ impl Appendable for String {
         let mut buf = itoa::Buffer::new();
         self.push_str(buf.format(i));
     }
-    fn append_f64(&mut self, f: f64) {
-        if f.fract() == 0.0 {
-            self.append_i64(f as i64);
-        } else {
-            use std::fmt::Write;
-            write!(self, "{}", f).ok();
-        }
+    fn append_f64(&mut self, f: f64, format: &FloatFormat) {
+        write_f64(self, f, format);
     }
     fn append_bool(&mut self, b: bool) {
         self.push_str(if b { "true" } else { "false" });
@@ -38,7 +34,7 @@ impl Appendable for String {
         if v.is_int() {
             self.append_i64(v.as_i64());
         } else if v.is_f64() {
-            self.append_f64(v.as_f64());
+            self.append_f64(v.as_f64(), &heap.float_format);
         } else if v.is_bool() {
             self.append_bool(v.as_bool());
         } else if v.is_unit() {
@@ -61,12 +57,8 @@ impl Appendable for Text {
         let mut buf = itoa::Buffer::new();
         self.push_str(buf.format(i));
     }
-    fn append_f64(&mut self, f: f64) {
-        if f.fract() == 0.0 {
-            self.append_i64(f as i64);
-        } else {
-            self.push_str(&f.to_string());
-        }
+    fn append_f64(&mut self, f: f64, format: &FloatFormat) {
+        write_f64(self, f, format);
     }
     fn append_bool(&mut self, b: bool) {
         self.push_str(if b { "true" } else { "false" });
@@ -78,7 +70,7 @@ impl Appendable for Text {
         if v.is_int() {
             self.append_i64(v.as_i64());
         } else if v.is_f64() {
-            self.append_f64(v.as_f64());
+            self.append_f64(v.as_f64(), &heap.float_format);
         } else if v.is_bool() {
             self.append_bool(v.as_bool());
         } else if v.is_unit() {
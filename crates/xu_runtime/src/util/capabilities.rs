@@ -1,17 +1,38 @@
 //! System capability traits for dependency injection.
+//!
+//! The clock, file system, random number generator, environment variables
+//! and capability policy are shared with the runtime's workers, so they must
+//! be `Send + Sync`.
 
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "sys")]
 use std::sync::OnceLock;
+#[cfg(feature = "sys")]
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-pub trait Clock {
+pub trait Clock: Send + Sync {
     fn unix_secs(&self) -> i64;
     fn unix_millis(&self) -> i64;
     fn mono_micros(&self) -> i64;
     fn mono_nanos(&self) -> i64;
+
+    /// Blocks for `ms` milliseconds; `sleep` goes through here, so a fake
+    /// clock can advance its time instead of waiting. Without the `sys`
+    /// feature this returns at once.
+    fn sleep_millis(&self, ms: u64) {
+        #[cfg(feature = "sys")]
+        std::thread::sleep(Duration::from_millis(ms));
+        #[cfg(not(feature = "sys"))]
+        let _ = ms;
+    }
 }
 
+/// The host's clock. Without the `sys` feature there is none and every
+/// reading is zero; embedders install their own with `Runtime::set_clock`.
 pub struct SystemClock;
 
+#[cfg(feature = "sys")]
 impl Clock for SystemClock {
     fn unix_secs(&self) -> i64 {
         SystemTime::now()
@@ -40,15 +61,114 @@ impl Clock for SystemClock {
     }
 }
 
-pub trait FileSystem {
+#[cfg(not(feature = "sys"))]
+impl Clock for SystemClock {
+    fn unix_secs(&self) -> i64 {
+        0
+    }
+
+    fn unix_millis(&self) -> i64 {
+        0
+    }
+
+    fn mono_micros(&self) -> i64 {
+        0
+    }
+
+    fn mono_nanos(&self) -> i64 {
+        0
+    }
+}
+
+/// Seed for the script random number generator: the time in nanoseconds,
+/// or a fixed seed without the `sys` feature.
+pub(crate) fn time_seed() -> u64 {
+    #[cfg(feature = "sys")]
+    {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1)
+    }
+    #[cfg(not(feature = "sys"))]
+    {
+        1
+    }
+}
+
+/// Measures how long runtime work takes, for statistics. Without the `sys`
+/// feature there is no clock and every measurement is zero.
+pub(crate) struct Stopwatch {
+    #[cfg(feature = "sys")]
+    started: Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Stopwatch {
+            #[cfg(feature = "sys")]
+            started: Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(feature = "sys")]
+        {
+            self.started.elapsed()
+        }
+        #[cfg(not(feature = "sys"))]
+        {
+            Duration::ZERO
+        }
+    }
+}
+
+pub trait FileSystem: Send + Sync {
     fn metadata(&self, path: &str) -> Result<(), String>;
     fn stat(&self, path: &str) -> Result<FileStat, String>;
     fn canonicalize(&self, path: &str) -> Result<String, String>;
+    /// Whether `path` itself is a symbolic link, whether or not its target
+    /// exists. File systems without links keep the default.
+    fn is_symlink(&self, _path: &str) -> bool {
+        false
+    }
     fn read_to_string(&self, path: &str) -> Result<String, String>;
+    /// At most `max` bytes (at least 4) of the text of `path` from byte
+    /// `offset` on, cut at a character boundary; empty at the end of the
+    /// file. The default reads the whole file through `read_to_string`.
+    fn read_chunk(&self, path: &str, offset: u64, max: usize) -> Result<String, String> {
+        let all = self.read_to_string(path)?;
+        let start = usize::try_from(offset).unwrap_or(usize::MAX).min(all.len());
+        let mut end = start.saturating_add(max).min(all.len());
+        while !all.is_char_boundary(end) {
+            end -= 1;
+        }
+        Ok(all[start..end].to_string())
+    }
+    /// Creates or truncates `path` with `contents`. Read-only file systems
+    /// keep the default, which refuses.
+    fn write(&self, path: &str, _contents: &str) -> Result<(), String> {
+        Err(format!("{path}: file system is read-only"))
+    }
+    /// Adds `contents` to the end of the existing file `path`. The default
+    /// rewrites the whole file through `read_to_string` and `write`.
+    fn append(&self, path: &str, contents: &str) -> Result<(), String> {
+        let mut all = self.read_to_string(path)?;
+        all.push_str(contents);
+        self.write(path, &all)
+    }
+    /// The entries of the directory `path`, in no particular order. File
+    /// systems without directories keep the default, which refuses.
+    fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, String> {
+        Err(format!("{path}: file system cannot list directories"))
+    }
 }
 
+/// The host's file system through `std::fs`. Without the `sys` feature
+/// every access fails.
 pub struct StdFileSystem;
 
+#[cfg(feature = "sys")]
 impl FileSystem for StdFileSystem {
     fn metadata(&self, path: &str) -> Result<(), String> {
         std::fs::metadata(path)
@@ -74,12 +194,94 @@ impl FileSystem for StdFileSystem {
         Ok(canonical.to_string_lossy().to_string())
     }
 
+    fn is_symlink(&self, path: &str) -> bool {
+        std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+    }
+
     fn read_to_string(&self, path: &str) -> Result<String, String> {
         std::fs::read_to_string(path).map_err(|e| e.to_string())
     }
+
+    fn read_chunk(&self, path: &str, offset: u64, max: usize) -> Result<String, String> {
+        use std::io::{Read, Seek, SeekFrom};
+        let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+        file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+        let mut bytes = Vec::with_capacity(max);
+        file.take(max as u64).read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+        // 末尾被截断的多字节字符留给下一块
+        let valid = match std::str::from_utf8(&bytes) {
+            Ok(_) => bytes.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err("stream did not contain valid UTF-8".to_string()),
+        };
+        bytes.truncate(valid);
+        Ok(String::from_utf8(bytes).expect("validated above"))
+    }
+
+    fn write(&self, path: &str, contents: &str) -> Result<(), String> {
+        std::fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    fn append(&self, path: &str, contents: &str) -> Result<(), String> {
+        use std::io::Write;
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).map_err(|e| e.to_string())?;
+        file.write_all(contents.as_bytes()).map_err(|e| e.to_string())
+    }
+
+    fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, String> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(path).map_err(|e| e.to_string())? {
+            let entry = entry.map_err(|e| e.to_string())?;
+            // Symbolic links are not followed, so a link to a parent cannot
+            // make a walk endless.
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            entries.push(DirEntry { name: entry.file_name().to_string_lossy().to_string(), is_dir });
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(not(feature = "sys"))]
+impl FileSystem for StdFileSystem {
+    fn metadata(&self, path: &str) -> Result<(), String> {
+        Err(no_file_system(path))
+    }
+
+    fn stat(&self, path: &str) -> Result<FileStat, String> {
+        Err(no_file_system(path))
+    }
+
+    fn canonicalize(&self, path: &str) -> Result<String, String> {
+        Err(no_file_system(path))
+    }
+
+    fn read_to_string(&self, path: &str) -> Result<String, String> {
+        Err(no_file_system(path))
+    }
+
+    fn read_chunk(&self, path: &str, _offset: u64, _max: usize) -> Result<String, String> {
+        Err(no_file_system(path))
+    }
+
+    fn write(&self, path: &str, _contents: &str) -> Result<(), String> {
+        Err(no_file_system(path))
+    }
+
+    fn append(&self, path: &str, _contents: &str) -> Result<(), String> {
+        Err(no_file_system(path))
+    }
+
+    fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, String> {
+        Err(no_file_system(path))
+    }
 }
 
-pub trait RngAlgorithm {
+#[cfg(not(feature = "sys"))]
+fn no_file_system(path: &str) -> String {
+    format!("{path}: no file system in this build")
+}
+
+pub trait RngAlgorithm: Send + Sync {
     fn next_u64(&self, state: &mut u64) -> u64;
 }
 
@@ -92,20 +294,292 @@ impl RngAlgorithm for Lcg64 {
     }
 }
 
+pub trait EnvVars: Send + Sync {
+    /// The value of the environment variable `key`, if set and valid Unicode.
+    fn var(&self, key: &str) -> Option<String>;
+}
+
+pub struct StdEnvVars;
+
+impl EnvVars for StdEnvVars {
+    fn var(&self, key: &str) -> Option<String> {
+        std::env::var(key).ok()
+    }
+}
+
+/// The terminal a script talks to through `std/term`: whether its output is
+/// shown on one, and the lines the user types.
+pub trait Terminal {
+    /// Whether output is shown on an interactive terminal that understands
+    /// ANSI escape sequences.
+    fn is_tty(&self) -> bool;
+    /// The next line typed by the user, without its line break; `None` at
+    /// the end of input.
+    fn read_line(&self) -> Option<String>;
+    /// Like [`Terminal::read_line`], without showing what is typed. Defaults
+    /// to `read_line`.
+    fn read_secret(&self) -> Option<String> {
+        self.read_line()
+    }
+}
+
+/// Standard input, with output shown on a terminal only when `tty` is set.
+/// The runtime defaults to `tty: false`, as embedders usually capture the
+/// output; the CLI installs [`StdTerminal::detect`].
+pub struct StdTerminal {
+    pub tty: bool,
+}
+
+impl StdTerminal {
+    /// A terminal that is a TTY when the process's standard output is one.
+    pub fn detect() -> Self {
+        #[cfg(feature = "sys")]
+        {
+            use std::io::IsTerminal;
+            StdTerminal { tty: std::io::stdout().is_terminal() }
+        }
+        #[cfg(not(feature = "sys"))]
+        {
+            StdTerminal { tty: false }
+        }
+    }
+}
+
+impl Terminal for StdTerminal {
+    fn is_tty(&self) -> bool {
+        self.tty
+    }
+
+    fn read_line(&self) -> Option<String> {
+        let mut line = String::new();
+        match std::io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+        }
+    }
+
+    #[cfg(all(feature = "sys", unix))]
+    fn read_secret(&self) -> Option<String> {
+        use std::io::IsTerminal;
+        use std::os::fd::AsRawFd;
+        let stdin = std::io::stdin();
+        if !stdin.is_terminal() {
+            return self.read_line();
+        }
+        let fd = stdin.as_raw_fd();
+        // SAFETY: `termios` is plain data filled in by `tcgetattr` before use.
+        let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut saved) } != 0 {
+            return self.read_line();
+        }
+        let mut quiet = saved;
+        quiet.c_lflag &= !libc::ECHO;
+        quiet.c_lflag |= libc::ECHONL;
+        unsafe { libc::tcsetattr(fd, libc::TCSANOW, &quiet) };
+        let line = self.read_line();
+        unsafe { libc::tcsetattr(fd, libc::TCSANOW, &saved) };
+        line
+    }
+}
+
+/// Where the signals handled with `on_signal` come from, besides the
+/// runtime's [`SignalHandle`](crate::SignalHandle).
+pub trait SignalSource {
+    /// Starts forwarding `signal` to `pending` once the script handles it.
+    fn watch(&self, signal: crate::Signal, pending: &crate::SignalHandle);
+}
+
+/// Only the signals the host raises through
+/// [`Runtime::signal_handle`](crate::Runtime::signal_handle); the process's
+/// own signals are left alone. The runtime's default.
+pub struct HostSignals;
+
+impl SignalSource for HostSignals {
+    fn watch(&self, _signal: crate::Signal, _pending: &crate::SignalHandle) {}
+}
+
+/// The process's signals (`SIGINT`, `SIGTERM`, `SIGHUP`), which stop going
+/// to their default action once the script handles them. Only one runtime
+/// of a process receives them: the last to start watching. Off Unix, or
+/// without the `sys` feature, like [`HostSignals`].
+pub struct OsSignals;
+
+#[cfg(all(feature = "sys", unix))]
+mod os_signals {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+
+    use crate::Signal;
+
+    /// The pending signals of the runtime receiving the process's signals.
+    /// Each one installed stays allocated, as a handler may be reading it.
+    static PENDING: AtomicPtr<AtomicU32> = AtomicPtr::new(std::ptr::null_mut());
+
+    extern "C" fn forward(signum: libc::c_int) {
+        let signal = match signum {
+            libc::SIGINT => Signal::Int,
+            libc::SIGTERM => Signal::Term,
+            _ => Signal::Hup,
+        };
+        let pending = PENDING.load(Ordering::Acquire);
+        // SAFETY: non-null pointers come from `Arc::into_raw` and are never freed.
+        if let Some(pending) = unsafe { pending.as_ref() } {
+            pending.fetch_or(signal.bit(), Ordering::Relaxed);
+        }
+    }
+
+    pub(super) fn watch(signal: Signal, pending: &Arc<AtomicU32>) {
+        let ptr = Arc::as_ptr(pending) as *mut AtomicU32;
+        if PENDING.load(Ordering::Acquire) != ptr {
+            PENDING.store(Arc::into_raw(pending.clone()) as *mut AtomicU32, Ordering::Release);
+        }
+        let signum = match signal {
+            Signal::Int => libc::SIGINT,
+            Signal::Term => libc::SIGTERM,
+            Signal::Hup => libc::SIGHUP,
+        };
+        // SAFETY: `forward` only touches atomics, which is async-signal-safe.
+        unsafe { libc::signal(signum, forward as extern "C" fn(libc::c_int) as libc::sighandler_t) };
+    }
+}
+
+impl SignalSource for OsSignals {
+    fn watch(&self, signal: crate::Signal, pending: &crate::SignalHandle) {
+        #[cfg(all(feature = "sys", unix))]
+        os_signals::watch(signal, pending.pending());
+        #[cfg(not(all(feature = "sys", unix)))]
+        let _ = (signal, pending);
+    }
+}
+
+/// Capability name governing file reads outside the allowed roots.
+pub const FS_READ: &str = "fs.read";
+
+/// Capability name governing file writes outside the allowed roots.
+pub const FS_WRITE: &str = "fs.write";
+
+/// Capability name governing `std/ffi` calls into native libraries, which
+/// are never allowed in a runtime with allowed roots.
+pub const FFI: &str = "ffi";
+
+/// Capability name governing `on_signal`, which takes signals such as
+/// Ctrl-C away from their default action.
+pub const SIGNAL: &str = "signal";
+
+/// Capabilities a script may request through `with_capability`.
+pub const KNOWN_CAPABILITIES: &[&str] = &[FS_READ, FS_WRITE, FFI, SIGNAL];
+
+/// Host-side decision point for `with_capability` requests.
+///
+/// Consulted once per request; returning `false` denies the grant.
+pub trait CapabilityPolicy: Send + Sync {
+    fn approve(&self, capability: &str) -> bool;
+}
+
+impl<F: Fn(&str) -> bool + Send + Sync> CapabilityPolicy for F {
+    fn approve(&self, capability: &str) -> bool {
+        self(capability)
+    }
+}
+
+/// Denies every request. Used when the embedder has not installed a policy.
+pub struct DenyAllPolicy;
+
+impl CapabilityPolicy for DenyAllPolicy {
+    fn approve(&self, _capability: &str) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapabilityAuditEntry {
+    pub capability: String,
+    /// The resource accessed under the grant, or `None` for the request itself.
+    pub target: Option<String>,
+    pub granted: bool,
+}
+
 pub struct Capabilities {
-    pub clock: Box<dyn Clock>,
-    pub fs: Box<dyn FileSystem>,
-    pub rng: Box<dyn RngAlgorithm>,
+    pub clock: Arc<dyn Clock>,
+    pub fs: Arc<dyn FileSystem>,
+    pub rng: Arc<dyn RngAlgorithm>,
+    pub env: Arc<dyn EnvVars>,
+    pub terminal: Box<dyn Terminal>,
+    pub signals: Box<dyn SignalSource>,
     pub allowed_roots: Vec<String>,
+    pub policy: Arc<dyn CapabilityPolicy>,
+    pub grants: Vec<String>,
+    pub audit_log: Vec<CapabilityAuditEntry>,
+}
+
+/// What a worker thread takes over from the capabilities of the runtime that
+/// started it. Grants, the audit log, the terminal and the signal source are
+/// the worker's own.
+#[derive(Clone)]
+pub(crate) struct SharedCapabilities {
+    clock: Arc<dyn Clock>,
+    fs: Arc<dyn FileSystem>,
+    rng: Arc<dyn RngAlgorithm>,
+    env: Arc<dyn EnvVars>,
+    allowed_roots: Vec<String>,
+    policy: Arc<dyn CapabilityPolicy>,
+}
+
+impl Capabilities {
+    pub fn is_granted(&self, capability: &str) -> bool {
+        self.grants.iter().any(|g| g == capability)
+    }
+
+    /// Takes the capabilities that supply the script's inputs, leaving the
+    /// defaults in their place.
+    pub(crate) fn take_inputs(&mut self) -> Inputs {
+        Inputs {
+            clock: std::mem::replace(&mut self.clock, Arc::new(SystemClock)),
+            fs: std::mem::replace(&mut self.fs, Arc::new(StdFileSystem)),
+            rng: std::mem::replace(&mut self.rng, Arc::new(Lcg64)),
+            env: std::mem::replace(&mut self.env, Arc::new(StdEnvVars)),
+        }
+    }
+
+    pub(crate) fn share(&self) -> SharedCapabilities {
+        SharedCapabilities {
+            clock: self.clock.clone(),
+            fs: self.fs.clone(),
+            rng: self.rng.clone(),
+            env: self.env.clone(),
+            allowed_roots: self.allowed_roots.clone(),
+            policy: self.policy.clone(),
+        }
+    }
+
+    /// Capabilities of a worker started with `shared`.
+    pub(crate) fn from_shared(shared: SharedCapabilities) -> Self {
+        let SharedCapabilities { clock, fs, rng, env, allowed_roots, policy } = shared;
+        Self { clock, fs, rng, env, allowed_roots, policy, ..Self::default() }
+    }
+}
+
+/// The capabilities returned by [`Capabilities::take_inputs`].
+pub(crate) struct Inputs {
+    pub clock: Arc<dyn Clock>,
+    pub fs: Arc<dyn FileSystem>,
+    pub rng: Arc<dyn RngAlgorithm>,
+    pub env: Arc<dyn EnvVars>,
 }
 
 impl Default for Capabilities {
     fn default() -> Self {
         Self {
-            clock: Box::new(SystemClock),
-            fs: Box::new(StdFileSystem),
-            rng: Box::new(Lcg64),
+            clock: Arc::new(SystemClock),
+            fs: Arc::new(StdFileSystem),
+            rng: Arc::new(Lcg64),
+            env: Arc::new(StdEnvVars),
+            terminal: Box::new(StdTerminal { tty: false }),
+            signals: Box::new(HostSignals),
             allowed_roots: Vec::new(),
+            policy: Arc::new(DenyAllPolicy),
+            grants: Vec::new(),
+            audit_log: Vec::new(),
         }
     }
 }
@@ -115,3 +589,10 @@ pub struct FileStat {
     pub len: u64,
     pub modified_nanos: Option<u128>,
 }
+
+/// One entry of a directory listed by [`FileSystem::read_dir`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
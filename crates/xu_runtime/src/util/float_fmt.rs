@@ -0,0 +1,140 @@
+//! Float display policy shared by `print`, interpolation, `to_text` and
+//! string concatenation.
+//!
+//! Each runtime keeps its format on its heap (see `Runtime::set_float_format`),
+//! so runtimes on one thread can use different formats.
+
+use smallvec::SmallVec;
+use std::fmt::{self, Write};
+
+/// How floats are rendered as text.
+///
+/// Digits are laid out positionally unless the decimal exponent falls outside
+/// `sci_min_exp..sci_max_exp`, in which case scientific notation (`1.5e21`,
+/// `2e-8`) is used. The defaults match JavaScript's `Number#toString`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FloatFormat {
+    /// Round to this many significant digits. `None` prints the shortest
+    /// representation that round-trips.
+    pub significant_digits: Option<u8>,
+    /// Smallest decimal exponent printed positionally.
+    pub sci_min_exp: i32,
+    /// Decimal exponent from which scientific notation is used.
+    pub sci_max_exp: i32,
+}
+
+impl Default for FloatFormat {
+    fn default() -> Self {
+        Self {
+            significant_digits: None,
+            sci_min_exp: -6,
+            sci_max_exp: 21,
+        }
+    }
+}
+
+/// A float formatted on the stack, for callers that need its length before
+/// copying it into place.
+pub(crate) struct FloatBuf(SmallVec<[u8; 32]>);
+
+impl FloatBuf {
+    pub(crate) fn new(f: f64, format: &FloatFormat) -> Self {
+        let mut buf = FloatBuf(SmallVec::new());
+        write_f64(&mut buf, f, format);
+        buf
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        // SAFETY: only whole `&str`s are ever written.
+        unsafe { std::str::from_utf8_unchecked(&self.0) }
+    }
+}
+
+impl Write for FloatBuf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Formats `f` according to `format`.
+pub fn format_f64_with(f: f64, format: &FloatFormat) -> String {
+    let mut out = String::new();
+    write_f64(&mut out, f, format);
+    out
+}
+
+/// Writes `f` formatted according to `format` to `out`.
+pub fn write_f64<W: Write + ?Sized>(out: &mut W, f: f64, format: &FloatFormat) {
+    // 目标缓冲区（String / Text / FloatBuf）写入不会失败
+    let _ = write_f64_impl(out, f, format);
+}
+
+fn write_f64_impl<W: Write + ?Sized>(out: &mut W, f: f64, format: &FloatFormat) -> fmt::Result {
+    if format.significant_digits.is_none() && format.sci_min_exp <= -5 && format.sci_max_exp >= 15 {
+        // Fast paths: whole numbers and values ryu already prints positionally.
+        let abs = f.abs();
+        if f.fract() == 0.0 && abs < 1e15 {
+            let mut buf = itoa::Buffer::new();
+            return out.write_str(buf.format(f as i64));
+        }
+        if (1e-5..1e15).contains(&abs) {
+            let mut buf = ryu::Buffer::new();
+            return out.write_str(buf.format_finite(f));
+        }
+    }
+    if f.is_nan() {
+        return out.write_str("NaN");
+    }
+    if f.is_infinite() {
+        return out.write_str(if f > 0.0 { "inf" } else { "-inf" });
+    }
+    if f == 0.0 {
+        return out.write_str("0");
+    }
+    let mut sci = FloatBuf(SmallVec::new());
+    match format.significant_digits {
+        Some(n) => write!(sci, "{:.*e}", n.max(1) as usize - 1, f)?,
+        None => write!(sci, "{f:e}")?,
+    }
+    let (mantissa, exp) = sci
+        .as_str()
+        .split_once('e')
+        .expect("exponent in {:e} output");
+    let exp: i32 = exp.parse().expect("integer exponent");
+    let (neg, mantissa) = match mantissa.strip_prefix('-') {
+        Some(m) => (true, m),
+        None => (false, mantissa),
+    };
+    let mut digits = FloatBuf(mantissa.bytes().filter(|c| *c != b'.').collect());
+    while digits.0.len() > 1 && digits.0.last() == Some(&b'0') {
+        digits.0.pop();
+    }
+    let digits = digits.as_str();
+
+    if neg {
+        out.write_char('-')?;
+    }
+    if exp < format.sci_min_exp || exp >= format.sci_max_exp {
+        out.write_str(&digits[..1])?;
+        if digits.len() > 1 {
+            out.write_char('.')?;
+            out.write_str(&digits[1..])?;
+        }
+        write!(out, "e{exp}")
+    } else if exp >= 0 {
+        let int_len = exp as usize + 1;
+        if digits.len() <= int_len {
+            out.write_str(digits)?;
+            (digits.len()..int_len).try_for_each(|_| out.write_char('0'))
+        } else {
+            out.write_str(&digits[..int_len])?;
+            out.write_char('.')?;
+            out.write_str(&digits[int_len..])
+        }
+    } else {
+        out.write_str("0.")?;
+        (1..-exp).try_for_each(|_| out.write_char('0'))?;
+        out.write_str(digits)
+    }
+}
@@ -21,12 +21,7 @@ fn value_to_string_impl(v: &Value, heap: &Heap, visited: &mut HashSet<usize>) ->
     } else if v.is_int() {
         i64_to_string_fast(v.as_i64())
     } else if v.is_f64() {
-        let f = v.as_f64();
-        if f.fract() == 0.0 {
-            i64_to_string_fast(f as i64)
-        } else {
-            f.to_string()
-        }
+        super::float_fmt::format_f64_with(v.as_f64(), &heap.float_format)
     } else {
         let tag = v.get_tag();
         let id = v.as_obj_id();
@@ -75,21 +70,21 @@ fn value_to_string_impl(v: &Value, heap: &Heap, visited: &mut HashSet<usize>) ->
                 visited.insert(id.0);
                 if let crate::core::heap::ManagedObject::Dict(items) = heap.get(id) {
                     let strs: Vec<_> = items
-                        .map
-                        .iter()
+                        .entries()
                         .map(|(k, v)| {
                             let ks = match k {
                                 DictKey::StrRef { obj_id, .. } => {
                                     // Get string from heap
-                                    if let crate::core::heap::ManagedObject::Str(s) = heap.get(crate::core::heap::ObjectId(*obj_id)) {
+                                    if let crate::core::heap::ManagedObject::Str(s) = heap.get(crate::core::heap::ObjectId(obj_id)) {
                                         s.as_str().to_string()
                                     } else {
                                         "<invalid>".to_string()
                                     }
                                 }
                                 DictKey::Int(i) => i.to_string(),
+                                _ => value_to_string_impl(&k.to_value(heap), heap, visited),
                             };
-                            format!("\"{}\":{}", ks, value_to_string_impl(v, heap, visited))
+                            format!("\"{}\":{}", ks, value_to_string_impl(&v, heap, visited))
                         })
                         .collect();
                     format!("{{{}}}", strs.join(","))
@@ -124,6 +119,7 @@ fn value_to_string_impl(v: &Value, heap: &Heap, visited: &mut HashSet<usize>) ->
                 }
             }
             crate::core::value::TAG_FUNC => "function".to_string(),
+            crate::core::value::TAG_CHANNEL => "channel".to_string(),
             crate::core::value::TAG_FILE => {
                 if let crate::core::heap::ManagedObject::File(h) = heap.get(id) {
                     format!("file({})", h.path)
@@ -166,7 +162,7 @@ pub(crate) fn type_matches(ty: &str, v: &Value, heap: &Heap) -> bool {
         "any" => true,
         "int" => v.is_int(),
         "float" => v.is_f64() || v.is_int(),
-        "string" => v.get_tag() == crate::core::value::TAG_STR,
+        "string" | "text" | "str" => v.get_tag() == crate::core::value::TAG_STR,
         "bool" | "?" => v.is_bool(),
         "list" => v.get_tag() == crate::core::value::TAG_LIST,
         "dict" => v.get_tag() == crate::core::value::TAG_DICT,
@@ -174,6 +170,7 @@ pub(crate) fn type_matches(ty: &str, v: &Value, heap: &Heap) -> bool {
         "module" => v.get_tag() == crate::core::value::TAG_MODULE,
         "range" => v.get_tag() == crate::core::value::TAG_RANGE,
         "file" => v.get_tag() == crate::core::value::TAG_FILE,
+        "channel" => v.get_tag() == crate::core::value::TAG_CHANNEL,
         "unit" => v.is_unit(),
         _ => {
             let tag = v.get_tag();
@@ -3,11 +3,25 @@
 mod appendable;
 mod capabilities;
 mod diag;
+mod float_fmt;
 mod helpers;
 mod pattern;
+mod pretty;
+mod replay;
 
 pub use appendable::Appendable;
-pub use capabilities::{Capabilities, Clock, FileStat, FileSystem, RngAlgorithm};
+pub use float_fmt::{FloatFormat, format_f64_with, write_f64};
+pub use pretty::PrettyOptions;
+pub(crate) use pretty::inspect_value;
+pub(crate) use float_fmt::FloatBuf;
+pub use capabilities::{
+    CapabilityAuditEntry, CapabilityPolicy, Capabilities, Clock, DenyAllPolicy, DirEntry, EnvVars,
+    FileStat, FileSystem, HostSignals, OsSignals, RngAlgorithm, SignalSource, StdTerminal, Terminal,
+};
+pub use replay::{ClockSource, Trace, TraceEvent};
+pub(crate) use capabilities::SharedCapabilities;
+pub(crate) use replay::Tap;
+pub(crate) use capabilities::{FFI, FS_READ, FS_WRITE, KNOWN_CAPABILITIES, SIGNAL, Stopwatch, time_seed};
 pub(crate) use helpers::{value_to_string, to_i64, type_matches};
 pub(crate) use pattern::match_pattern;
 pub(crate) use diag::render_parse_error;
@@ -0,0 +1,233 @@
+//! Inspecting values: a readable rendering of any value with indentation,
+//! truncation and cycle markers, behind the `inspect` builtin.
+//!
+//! Unlike the `println` form, strings are quoted and escaped so `"1"` and `1`
+//! look different. Containers nested deeper than
+//! [`max_depth`](PrettyOptions::max_depth) render as `[...]`, a container
+//! already being rendered further up renders as `<cycle>`, and containers
+//! with more than [`max_items`](PrettyOptions::max_items) entries end with
+//! `... (N more)`. In pretty mode a container whose one-line form does not
+//! fit in [`width`](PrettyOptions::width) puts each entry on its own line.
+
+use crate::Value;
+use crate::core::heap::{Heap, ManagedObject, ObjectId};
+use crate::core::value::{
+    TAG_BUILDER, TAG_DICT, TAG_ENUM, TAG_FILE, TAG_FUNC, TAG_LIST, TAG_MODULE, TAG_OPTION,
+    TAG_RANGE, TAG_SPLIT_ITER, TAG_CHANNEL, TAG_STR, TAG_STRUCT, TAG_TUPLE, i64_to_string_fast,
+};
+
+/// How [`Runtime::inspect`](crate::Runtime::inspect) renders a value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrettyOptions {
+    /// Break containers that do not fit in `width` over several lines.
+    pub pretty: bool,
+    /// Spaces per nesting level in pretty mode.
+    pub indent: usize,
+    /// Line width pretty mode tries to stay within.
+    pub width: usize,
+    /// Containers nested deeper than this render as `[...]`.
+    pub max_depth: usize,
+    /// Entries shown per container.
+    pub max_items: usize,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            pretty: false,
+            indent: 2,
+            width: 80,
+            max_depth: xu_syntax::INSPECT_MAX_DEPTH as usize,
+            max_items: xu_syntax::INSPECT_MAX_ITEMS as usize,
+        }
+    }
+}
+
+/// Rendering tree: a leaf, or a container whose entries may be laid out on
+/// one line or one per line.
+enum Doc {
+    Text(String),
+    Group {
+        open: String,
+        /// Entries as (prefix, value), e.g. a dict key with its `: `.
+        items: Vec<(String, Doc)>,
+        close: &'static str,
+    },
+}
+
+impl Doc {
+    fn flat(&self, out: &mut String) {
+        match self {
+            Doc::Text(s) => out.push_str(s),
+            Doc::Group { open, items, close } => {
+                out.push_str(open);
+                for (i, (prefix, item)) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    out.push_str(prefix);
+                    item.flat(out);
+                }
+                out.push_str(close);
+            }
+        }
+    }
+
+    fn layout(&self, out: &mut String, level: usize, column: usize, opts: &PrettyOptions) {
+        let mut flat = String::new();
+        self.flat(&mut flat);
+        let Doc::Group { open, items, close } = self else {
+            out.push_str(&flat);
+            return;
+        };
+        if !opts.pretty || items.is_empty() || column + flat.chars().count() <= opts.width {
+            out.push_str(&flat);
+            return;
+        }
+        let inner = " ".repeat((level + 1) * opts.indent);
+        out.push_str(open);
+        out.push('\n');
+        for (prefix, item) in items {
+            out.push_str(&inner);
+            out.push_str(prefix);
+            let column = inner.len() + prefix.chars().count();
+            item.layout(out, level + 1, column, opts);
+            out.push_str(",\n");
+        }
+        out.push_str(&" ".repeat(level * opts.indent));
+        out.push_str(close);
+    }
+}
+
+/// Renders `v` according to `opts`.
+pub(crate) fn inspect_value(v: &Value, heap: &Heap, opts: &PrettyOptions) -> String {
+    let mut builder = DocBuilder { heap, opts, path: Vec::new() };
+    let doc = builder.doc(*v, 0);
+    let mut out = String::new();
+    doc.layout(&mut out, 0, 0, opts);
+    out
+}
+
+struct DocBuilder<'a> {
+    heap: &'a Heap,
+    opts: &'a PrettyOptions,
+    /// Containers being rendered, outermost first.
+    path: Vec<usize>,
+}
+
+impl DocBuilder<'_> {
+    fn doc(&mut self, v: Value, depth: usize) -> Doc {
+        if v.is_unit() {
+            return Doc::Text("()".into());
+        }
+        if v.is_bool() {
+            return Doc::Text(if v.as_bool() { "true" } else { "false" }.into());
+        }
+        if v.is_int() {
+            return Doc::Text(i64_to_string_fast(v.as_i64()));
+        }
+        if v.is_f64() {
+            return Doc::Text(super::float_fmt::format_f64_with(v.as_f64(), &self.heap.float_format));
+        }
+        let id = v.as_obj_id();
+        let tag = v.get_tag();
+        match (tag, self.heap.get(id)) {
+            (TAG_STR, ManagedObject::Str(s)) => Doc::Text(quote(s.as_str())),
+            (TAG_BUILDER, ManagedObject::Builder(s)) => Doc::Text(format!("builder({})", quote(s))),
+            (TAG_LIST, ManagedObject::List(items)) => {
+                self.group(id, depth, "[".into(), "]", items.iter().map(|v| (String::new(), *v)).collect())
+            }
+            (TAG_TUPLE, ManagedObject::Tuple(items)) => {
+                self.group(id, depth, "(".into(), ")", items.iter().map(|v| (String::new(), *v)).collect())
+            }
+            (TAG_DICT, ManagedObject::Dict(d)) => {
+                let entries: Vec<_> = d.entries().map(|(k, v)| (k.to_value(self.heap), v)).collect();
+                let heap = self.heap;
+                let entries = entries
+                    .into_iter()
+                    .map(|(k, v)| {
+                        // Keys are hashable, so they cannot form cycles
+                        let mut key = String::new();
+                        DocBuilder { heap, opts: self.opts, path: Vec::new() }.doc(k, depth + 1).flat(&mut key);
+                        key.push_str(": ");
+                        (key, v)
+                    })
+                    .collect();
+                self.group(id, depth, "{".into(), "}", entries)
+            }
+            (TAG_STRUCT, ManagedObject::Struct(s)) => {
+                let fields = s
+                    .field_names
+                    .iter()
+                    .zip(s.fields.iter())
+                    .map(|(name, v)| (format!("{name}: "), *v))
+                    .collect();
+                self.group(id, depth, format!("{}{{", s.ty), "}", fields)
+            }
+            (TAG_ENUM, ManagedObject::Enum(e)) => {
+                let (ty, variant, payload) = e.as_ref();
+                if payload.is_empty() {
+                    Doc::Text(format!("{ty}#{variant}"))
+                } else {
+                    let items = payload.iter().map(|v| (String::new(), *v)).collect();
+                    self.group(id, depth, format!("{ty}#{variant}("), ")", items)
+                }
+            }
+            (TAG_OPTION, ManagedObject::OptionSome(inner)) => {
+                self.group(id, depth, "Option#some(".into(), ")", vec![(String::new(), *inner)])
+            }
+            (TAG_RANGE, ManagedObject::Range(start, end, inclusive)) => {
+                Doc::Text(format!("[{start}..{}{end}]", if *inclusive { "=" } else { "" }))
+            }
+            (TAG_FILE, ManagedObject::File(h)) => Doc::Text(format!("file({})", quote(&h.path))),
+            (TAG_FUNC, _) => Doc::Text("function".into()),
+            (TAG_MODULE, _) => Doc::Text("module".into()),
+            (TAG_SPLIT_ITER, _) => Doc::Text("split_iter".into()),
+            (TAG_CHANNEL, ManagedObject::Channel(ch)) => Doc::Text(match ch.capacity() {
+                Some(n) => format!("channel({n})"),
+                None => "channel".into(),
+            }),
+            _ => Doc::Text("unknown".into()),
+        }
+    }
+
+    fn group(&mut self, id: ObjectId, depth: usize, open: String, close: &'static str, entries: Vec<(String, Value)>) -> Doc {
+        if self.path.contains(&id.0) {
+            return Doc::Text("<cycle>".into());
+        }
+        if depth >= self.opts.max_depth && !entries.is_empty() {
+            return Doc::Text(format!("{open}...{close}"));
+        }
+        self.path.push(id.0);
+        let total = entries.len();
+        let mut items: Vec<(String, Doc)> = entries
+            .into_iter()
+            .take(self.opts.max_items)
+            .map(|(prefix, v)| (prefix, self.doc(v, depth + 1)))
+            .collect();
+        if total > self.opts.max_items {
+            items.push((String::new(), Doc::Text(format!("... ({} more)", total - self.opts.max_items))));
+        }
+        self.path.pop();
+        Doc::Group { open, items, close }
+    }
+}
+
+/// A string as a double-quoted literal with escapes.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,540 @@
+//! Recording and replaying the nondeterministic inputs of a run.
+//!
+//! Recording wraps the clock, file system, random number generator and
+//! environment variables of [`Capabilities`] so every value they hand to the
+//! script is appended to a [`Trace`]. Replaying installs capabilities that
+//! answer from the trace instead, so a run repeats exactly, failures
+//! included. Replayed writes report the recorded result without touching the
+//! disk.
+//!
+//! Each kind of input replays in recorded order: clock reads per clock, file
+//! operations per path and operation, environment variables per name. An
+//! input the trace has no answer for is taken from the live capability and
+//! counted as a miss, which means the run diverged from the recording.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::capabilities::{Capabilities, Clock, DirEntry, EnvVars, FileStat, FileSystem, Inputs, RngAlgorithm};
+
+/// First line of a trace file.
+const TRACE_HEADER: &str = "xu-trace 1";
+
+/// One clock of [`Clock`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClockSource {
+    UnixSecs,
+    UnixMillis,
+    MonoMicros,
+    MonoNanos,
+}
+
+impl ClockSource {
+    fn name(self) -> &'static str {
+        match self {
+            ClockSource::UnixSecs => "unix_secs",
+            ClockSource::UnixMillis => "unix_millis",
+            ClockSource::MonoMicros => "mono_micros",
+            ClockSource::MonoNanos => "mono_nanos",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "unix_secs" => ClockSource::UnixSecs,
+            "unix_millis" => ClockSource::UnixMillis,
+            "mono_micros" => ClockSource::MonoMicros,
+            "mono_nanos" => ClockSource::MonoNanos,
+            _ => return None,
+        })
+    }
+}
+
+/// One input handed to the script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    Clock(ClockSource, i64),
+    Rng(u64),
+    Env(String, Option<String>),
+    Metadata(String, Result<(), String>),
+    Stat(String, Result<FileStat, String>),
+    Canonicalize(String, Result<String, String>),
+    Read(String, Result<String, String>),
+    ReadChunk(String, Result<String, String>),
+    Write(String, Result<(), String>),
+    Append(String, Result<(), String>),
+    ReadDir(String, Result<Vec<DirEntry>, String>),
+}
+
+/// Which replay queue an event belongs to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Channel {
+    Clock(ClockSource),
+    Rng,
+    Env(String),
+    Metadata(String),
+    Stat(String),
+    Canonicalize(String),
+    Read(String),
+    ReadChunk(String),
+    Write(String),
+    Append(String),
+    ReadDir(String),
+}
+
+impl Channel {
+    /// The input as written in trace files, for divergence reports.
+    fn describe(&self) -> String {
+        match self {
+            Channel::Clock(c) => format!("clock {}", c.name()),
+            Channel::Rng => "rng".to_string(),
+            Channel::Env(k) => format!("env {}", quote(k)),
+            Channel::Metadata(p) => format!("metadata {}", quote(p)),
+            Channel::Stat(p) => format!("stat {}", quote(p)),
+            Channel::Canonicalize(p) => format!("canonicalize {}", quote(p)),
+            Channel::Read(p) => format!("read {}", quote(p)),
+            Channel::ReadChunk(p) => format!("read_chunk {}", quote(p)),
+            Channel::Write(p) => format!("write {}", quote(p)),
+            Channel::Append(p) => format!("append {}", quote(p)),
+            Channel::ReadDir(p) => format!("read_dir {}", quote(p)),
+        }
+    }
+}
+
+impl TraceEvent {
+    fn channel(&self) -> Channel {
+        match self {
+            TraceEvent::Clock(c, _) => Channel::Clock(*c),
+            TraceEvent::Rng(_) => Channel::Rng,
+            TraceEvent::Env(k, _) => Channel::Env(k.clone()),
+            TraceEvent::Metadata(p, _) => Channel::Metadata(p.clone()),
+            TraceEvent::Stat(p, _) => Channel::Stat(p.clone()),
+            TraceEvent::Canonicalize(p, _) => Channel::Canonicalize(p.clone()),
+            TraceEvent::Read(p, _) => Channel::Read(p.clone()),
+            TraceEvent::ReadChunk(p, _) => Channel::ReadChunk(p.clone()),
+            TraceEvent::Write(p, _) => Channel::Write(p.clone()),
+            TraceEvent::Append(p, _) => Channel::Append(p.clone()),
+            TraceEvent::ReadDir(p, _) => Channel::ReadDir(p.clone()),
+        }
+    }
+}
+
+/// The recorded inputs of a run, in the order they were read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub events: Vec<TraceEvent>,
+}
+
+impl Trace {
+    /// The trace file format: a header line, then one event per line as
+    /// space-separated fields. Strings are quoted with `\` escapes; a failed
+    /// operation stores `err` and its message instead of `ok` and its value.
+    /// A directory listing is its entries' names, each followed by `dir` or
+    /// `file`.
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{TRACE_HEADER}\n");
+        for e in &self.events {
+            let line = match e {
+                TraceEvent::Clock(c, v) => format!("clock {} {v}", c.name()),
+                TraceEvent::Rng(v) => format!("rng {v}"),
+                TraceEvent::Env(k, None) => format!("env {} unset", quote(k)),
+                TraceEvent::Env(k, Some(v)) => format!("env {} {}", quote(k), quote(v)),
+                TraceEvent::Metadata(p, r) => format!("metadata {} {}", quote(p), result(r, |_| None)),
+                TraceEvent::Stat(p, r) => format!(
+                    "stat {} {}",
+                    quote(p),
+                    result(r, |s| Some(match s.modified_nanos {
+                        Some(m) => format!("{} {m}", s.len),
+                        None => format!("{} -", s.len),
+                    }))
+                ),
+                TraceEvent::Canonicalize(p, r) => format!("canonicalize {} {}", quote(p), result(r, |v| Some(quote(v)))),
+                TraceEvent::Read(p, r) => format!("read {} {}", quote(p), result(r, |v| Some(quote(v)))),
+                TraceEvent::ReadChunk(p, r) => format!("read_chunk {} {}", quote(p), result(r, |v| Some(quote(v)))),
+                TraceEvent::Write(p, r) => format!("write {} {}", quote(p), result(r, |_| None)),
+                TraceEvent::Append(p, r) => format!("append {} {}", quote(p), result(r, |_| None)),
+                TraceEvent::ReadDir(p, r) => format!(
+                    "read_dir {} {}",
+                    quote(p),
+                    result(r, |entries| Some(
+                        entries
+                            .iter()
+                            .map(|e| format!("{} {}", quote(&e.name), if e.is_dir { "dir" } else { "file" }))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    ))
+                ),
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Reads a trace written by [`Trace::to_text`].
+    pub fn parse(text: &str) -> Result<Trace, String> {
+        let mut lines = text.lines();
+        if lines.next() != Some(TRACE_HEADER) {
+            return Err(format!("Not a trace file: expected `{TRACE_HEADER}`"));
+        }
+        let mut events = Vec::new();
+        for (n, line) in lines.enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let fields = split_fields(line).ok_or_else(|| format!("Trace line {}: unterminated string", n + 2))?;
+            let event = parse_event(&fields).ok_or_else(|| format!("Trace line {}: malformed event", n + 2))?;
+            events.push(event);
+        }
+        Ok(Trace { events })
+    }
+}
+
+fn result<T>(r: &Result<T, String>, ok: impl Fn(&T) -> Option<String>) -> String {
+    match r {
+        Ok(v) => match ok(v) {
+            Some(v) => format!("ok {v}"),
+            None => "ok".to_string(),
+        },
+        Err(e) => format!("err {}", quote(e)),
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A field of a trace line: a bare word or an unescaped quoted string.
+#[derive(Debug, PartialEq, Eq)]
+enum Field {
+    Word(String),
+    Str(String),
+}
+
+fn split_fields(line: &str) -> Option<Vec<Field>> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c == ' ' {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => s.push(match chars.next()? {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        other => other,
+                    }),
+                    c => s.push(c),
+                }
+            }
+            fields.push(Field::Str(s));
+        } else {
+            let mut w = String::new();
+            while let Some(&c) = chars.peek().filter(|c| **c != ' ') {
+                w.push(c);
+                chars.next();
+            }
+            fields.push(Field::Word(w));
+        }
+    }
+    Some(fields)
+}
+
+fn parse_event(fields: &[Field]) -> Option<TraceEvent> {
+    let word = |i: usize| match fields.get(i) {
+        Some(Field::Word(w)) => Some(w.as_str()),
+        _ => None,
+    };
+    let string = |i: usize| match fields.get(i) {
+        Some(Field::Str(s)) => Some(s.clone()),
+        _ => None,
+    };
+    // `ok ...` or `err "message"` from field 2 on.
+    let outcome = |ok: &dyn Fn() -> Option<()>| -> Option<Result<(), String>> {
+        match word(2)? {
+            "ok" => ok().map(Ok),
+            "err" => Some(Err(string(3)?)),
+            _ => None,
+        }
+    };
+    let with_value = |value: &dyn Fn() -> Option<String>| -> Option<Result<String, String>> {
+        match word(2)? {
+            "ok" => value().map(Ok),
+            "err" => Some(Err(string(3)?)),
+            _ => None,
+        }
+    };
+    Some(match word(0)? {
+        "clock" => TraceEvent::Clock(ClockSource::parse(word(1)?)?, word(2)?.parse().ok()?),
+        "rng" => TraceEvent::Rng(word(1)?.parse().ok()?),
+        "env" => TraceEvent::Env(string(1)?, if word(2) == Some("unset") { None } else { Some(string(2)?) }),
+        "metadata" => TraceEvent::Metadata(string(1)?, outcome(&|| Some(()))?),
+        "write" => TraceEvent::Write(string(1)?, outcome(&|| Some(()))?),
+        "append" => TraceEvent::Append(string(1)?, outcome(&|| Some(()))?),
+        "canonicalize" => TraceEvent::Canonicalize(string(1)?, with_value(&|| string(3))?),
+        "read" => TraceEvent::Read(string(1)?, with_value(&|| string(3))?),
+        "read_chunk" => TraceEvent::ReadChunk(string(1)?, with_value(&|| string(3))?),
+        "stat" => {
+            let stat = match word(2)? {
+                "ok" => Ok(FileStat {
+                    len: word(3)?.parse().ok()?,
+                    modified_nanos: match word(4)? {
+                        "-" => None,
+                        m => Some(m.parse().ok()?),
+                    },
+                }),
+                "err" => Err(string(3)?),
+                _ => return None,
+            };
+            TraceEvent::Stat(string(1)?, stat)
+        }
+        "read_dir" => {
+            let entries = match word(2)? {
+                "ok" => {
+                    let mut entries = Vec::new();
+                    for i in (3..fields.len()).step_by(2) {
+                        let is_dir = match word(i + 1)? {
+                            "dir" => true,
+                            "file" => false,
+                            _ => return None,
+                        };
+                        entries.push(DirEntry { name: string(i)?, is_dir });
+                    }
+                    Ok(entries)
+                }
+                "err" => Err(string(3)?),
+                _ => return None,
+            };
+            TraceEvent::ReadDir(string(1)?, entries)
+        }
+        _ => return None,
+    })
+}
+
+/// The live capabilities behind a recording, and the trace so far.
+pub(crate) struct Recording {
+    clock: Arc<dyn Clock>,
+    fs: Arc<dyn FileSystem>,
+    rng: Arc<dyn RngAlgorithm>,
+    env: Arc<dyn EnvVars>,
+    trace: Mutex<Trace>,
+}
+
+impl Recording {
+    fn log<T>(&self, event: impl FnOnce(&T) -> TraceEvent, value: T) -> T {
+        lock(&self.trace).events.push(event(&value));
+        value
+    }
+}
+
+/// Feeds recorded inputs back, falling back to the live capabilities.
+pub(crate) struct Replay {
+    clock: Arc<dyn Clock>,
+    fs: Arc<dyn FileSystem>,
+    rng: Arc<dyn RngAlgorithm>,
+    env: Arc<dyn EnvVars>,
+    queues: Mutex<HashMap<Channel, VecDeque<TraceEvent>>>,
+    misses: Mutex<Vec<String>>,
+}
+
+impl Replay {
+    fn next(&self, channel: Channel) -> Option<TraceEvent> {
+        let event = lock(&self.queues).get_mut(&channel).and_then(VecDeque::pop_front);
+        if event.is_none() {
+            lock(&self.misses).push(channel.describe());
+        }
+        event
+    }
+}
+
+/// Locks a trace or queue; a worker that panicked while holding it left it
+/// consistent, as every update is a single push or pop.
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Recording or replaying, shared by the capability wrappers and by the
+/// runtime's workers.
+#[derive(Clone)]
+pub(crate) enum Tap {
+    Record(Arc<Recording>),
+    Replay(Arc<Replay>),
+}
+
+struct TapClock(Tap);
+struct TapFs(Tap);
+struct TapRng(Tap);
+struct TapEnv(Tap);
+
+impl TapClock {
+    fn read(&self, source: ClockSource, live: impl Fn(&dyn Clock) -> i64) -> i64 {
+        match &self.0 {
+            Tap::Record(r) => r.log(|v| TraceEvent::Clock(source, *v), live(&*r.clock)),
+            Tap::Replay(r) => match r.next(Channel::Clock(source)) {
+                Some(TraceEvent::Clock(_, v)) => v,
+                _ => live(&*r.clock),
+            },
+        }
+    }
+}
+
+impl Clock for TapClock {
+    fn unix_secs(&self) -> i64 {
+        self.read(ClockSource::UnixSecs, |c| c.unix_secs())
+    }
+    fn unix_millis(&self) -> i64 {
+        self.read(ClockSource::UnixMillis, |c| c.unix_millis())
+    }
+    fn mono_micros(&self) -> i64 {
+        self.read(ClockSource::MonoMicros, |c| c.mono_micros())
+    }
+    fn mono_nanos(&self) -> i64 {
+        self.read(ClockSource::MonoNanos, |c| c.mono_nanos())
+    }
+    fn sleep_millis(&self, ms: u64) {
+        // A replay reads its clock from the trace, so it need not wait.
+        if let Tap::Record(r) = &self.0 {
+            r.clock.sleep_millis(ms);
+        }
+    }
+}
+
+/// Records or replays one file-system call.
+macro_rules! tap_fs {
+    ($self:ident, $variant:ident, $path:expr, $live:expr) => {
+        match &$self.0 {
+            Tap::Record(r) => r.log(|v| TraceEvent::$variant($path.to_string(), v.clone()), $live(&*r.fs)),
+            Tap::Replay(r) => match r.next(Channel::$variant($path.to_string())) {
+                Some(TraceEvent::$variant(_, v)) => v,
+                _ => $live(&*r.fs),
+            },
+        }
+    };
+}
+
+impl FileSystem for TapFs {
+    fn metadata(&self, path: &str) -> Result<(), String> {
+        tap_fs!(self, Metadata, path, |fs: &dyn FileSystem| fs.metadata(path))
+    }
+    fn stat(&self, path: &str) -> Result<FileStat, String> {
+        tap_fs!(self, Stat, path, |fs: &dyn FileSystem| fs.stat(path))
+    }
+    fn canonicalize(&self, path: &str) -> Result<String, String> {
+        tap_fs!(self, Canonicalize, path, |fs: &dyn FileSystem| fs.canonicalize(path))
+    }
+    fn is_symlink(&self, path: &str) -> bool {
+        match &self.0 {
+            Tap::Record(r) => r.fs.is_symlink(path),
+            Tap::Replay(r) => r.fs.is_symlink(path),
+        }
+    }
+    fn read_to_string(&self, path: &str) -> Result<String, String> {
+        tap_fs!(self, Read, path, |fs: &dyn FileSystem| fs.read_to_string(path))
+    }
+    fn read_chunk(&self, path: &str, offset: u64, max: usize) -> Result<String, String> {
+        tap_fs!(self, ReadChunk, path, |fs: &dyn FileSystem| fs.read_chunk(path, offset, max))
+    }
+    fn write(&self, path: &str, contents: &str) -> Result<(), String> {
+        tap_fs!(self, Write, path, |fs: &dyn FileSystem| fs.write(path, contents))
+    }
+    fn append(&self, path: &str, contents: &str) -> Result<(), String> {
+        tap_fs!(self, Append, path, |fs: &dyn FileSystem| fs.append(path, contents))
+    }
+    fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, String> {
+        tap_fs!(self, ReadDir, path, |fs: &dyn FileSystem| fs.read_dir(path))
+    }
+}
+
+impl RngAlgorithm for TapRng {
+    fn next_u64(&self, state: &mut u64) -> u64 {
+        match &self.0 {
+            Tap::Record(r) => r.log(|v| TraceEvent::Rng(*v), r.rng.next_u64(state)),
+            Tap::Replay(r) => match r.next(Channel::Rng) {
+                Some(TraceEvent::Rng(v)) => v,
+                _ => r.rng.next_u64(state),
+            },
+        }
+    }
+}
+
+impl EnvVars for TapEnv {
+    fn var(&self, key: &str) -> Option<String> {
+        match &self.0 {
+            Tap::Record(r) => r.log(|v| TraceEvent::Env(key.to_string(), v.clone()), r.env.var(key)),
+            Tap::Replay(r) => match r.next(Channel::Env(key.to_string())) {
+                Some(TraceEvent::Env(_, v)) => v,
+                _ => r.env.var(key),
+            },
+        }
+    }
+}
+
+impl Tap {
+    /// Moves the live capabilities of `caps` behind a recording.
+    pub(crate) fn record(caps: &mut Capabilities) -> Tap {
+        let Inputs { clock, fs, rng, env } = caps.take_inputs();
+        let tap = Tap::Record(Arc::new(Recording { clock, fs, rng, env, trace: Mutex::default() }));
+        tap.install(caps);
+        tap
+    }
+
+    /// Moves the live capabilities of `caps` behind a replay of `trace`.
+    pub(crate) fn replay(caps: &mut Capabilities, trace: Trace) -> Tap {
+        let Inputs { clock, fs, rng, env } = caps.take_inputs();
+        let mut queues: HashMap<Channel, VecDeque<TraceEvent>> = HashMap::new();
+        for e in trace.events {
+            queues.entry(e.channel()).or_default().push_back(e);
+        }
+        let tap = Tap::Replay(Arc::new(Replay {
+            clock,
+            fs,
+            rng,
+            env,
+            queues: Mutex::new(queues),
+            misses: Mutex::default(),
+        }));
+        tap.install(caps);
+        tap
+    }
+
+    fn install(&self, caps: &mut Capabilities) {
+        caps.clock = Arc::new(TapClock(self.clone()));
+        caps.fs = Arc::new(TapFs(self.clone()));
+        caps.rng = Arc::new(TapRng(self.clone()));
+        caps.env = Arc::new(TapEnv(self.clone()));
+    }
+
+    /// The inputs recorded so far; empty while replaying.
+    pub(crate) fn trace(&self) -> Trace {
+        match self {
+            Tap::Record(r) => lock(&r.trace).clone(),
+            Tap::Replay(_) => Trace::default(),
+        }
+    }
+
+    /// Inputs the replayed trace had no answer for, like `read "data.txt"`.
+    pub(crate) fn misses(&self) -> Vec<String> {
+        match self {
+            Tap::Record(_) => Vec::new(),
+            Tap::Replay(r) => lock(&r.misses).clone(),
+        }
+    }
+}
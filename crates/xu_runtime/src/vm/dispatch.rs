@@ -6,9 +6,10 @@ use crate::core::heap::ManagedObject;
 use crate::util::value_to_string;
 use crate::{Flow, Runtime};
 use super::exception::throw_value;
+use super::ops::helpers::try_throw_error;
 
 use super::ops::dict as dict_ops;
-use super::ops::{access, assign, call, collection, compare, iter, math, string, types};
+use super::ops::{access, assign, call, collection, compare, iter, math, string, types, vars};
 use super::stack::{stack_underflow, Handler, IterState, Pending};
 
 pub(crate) fn run_bytecode(rt: &mut Runtime, bc: &Bytecode) -> Result<Flow, String> {
@@ -48,309 +49,353 @@ pub(crate) fn run_bytecode(rt: &mut Runtime, bc: &Bytecode) -> Result<Flow, Stri
         iters: &mut iters,
         handlers: &mut handlers,
     };
+
+    if !rt.error_trace.is_empty() {
+        rt.error_trace.clear();
+    }
+    let mut ip = 0;
+    #[cfg(not(feature = "threaded-dispatch"))]
+    let res = run_match(rt, bc, &mut stack, &mut iters, &mut handlers, &mut ip);
+    #[cfg(feature = "threaded-dispatch")]
+    let res = super::threaded::run_threaded(rt, bc, &mut stack, &mut iters, &mut handlers, &mut ip);
+    if matches!(res, Err(_) | Ok(Flow::Throw(_))) {
+        rt.record_trace_frame(bc, ip);
+    }
+    res
+}
+
+/// The `match`-based interpreter loop over pooled scratch buffers.
+#[cfg_attr(feature = "threaded-dispatch", allow(dead_code))]
+#[inline(always)]
+fn run_match(
+    rt: &mut Runtime,
+    bc: &Bytecode,
+    stack: &mut Vec<Value>,
+    iters: &mut Vec<IterState>,
+    handlers: &mut Vec<Handler>,
+    ip: &mut usize,
+) -> Result<Flow, String> {
     let mut pending: Option<Pending> = None;
     let mut thrown: Option<Value> = None;
 
-    let mut ip: usize = 0;
     let ops = &bc.ops;
     let ops_len = ops.len();
     let mut stmt_count: usize = 0;
 
-    while ip < ops_len {
+    while *ip < ops_len {
         // SAFETY: ip is always < ops_len due to the loop condition above,
         // so this index is always in bounds.
-        let op = unsafe { ops.get_unchecked(ip) };
+        let op = unsafe { ops.get_unchecked(*ip) };
         stmt_count = stmt_count.wrapping_add(1);
-        // Check GC every 1024 instructions
+        // Check GC, interrupts and signals every 1024 instructions
         if stmt_count & 1023 == 0 {
-            rt.maybe_gc_with_roots(&stack);
+            rt.maybe_gc_with_roots(stack);
+            rt.check_interrupt()?;
+            rt.check_signals()?;
         }
         match op {
             Op::ConstInt(i) => stack.push(Value::from_i64(*i)),
             Op::ConstFloat(f) => stack.push(Value::from_f64(*f)),
-            Op::Const(idx) => {
-                let c = rt.get_constant(*idx, &bc.constants);
-                match c {
-                    xu_ir::Constant::Str(s) => {
-                        let bc_ptr = bc as *const Bytecode as usize;
-                        stack.push(rt.get_string_const(bc_ptr, *idx, s));
-                    }
-                    xu_ir::Constant::Int(i) => stack.push(Value::from_i64(*i)),
-                    xu_ir::Constant::Float(f) => stack.push(Value::from_f64(*f)),
-                    _ => return Err("Unexpected constant type in VM loop".into()),
-                }
-            }
+            Op::Const(idx) => vars::op_const(rt, bc, stack, *idx)?,
             Op::ConstBool(b) => stack.push(Value::from_bool(*b)),
             Op::ConstNull => stack.push(Value::UNIT),
+            Op::ConstEmptyStr => stack.push(rt.empty_str()),
             Op::Pop => {
-                let _ = stack.pop().ok_or_else(|| stack_underflow(ip, op))?;
+                let _ = stack.pop().ok_or_else(|| stack_underflow(*ip, op))?;
             }
             Op::Dup => {
                 let v = stack.last().cloned().ok_or_else(|| "Stack underflow".to_string())?;
                 stack.push(v);
             }
-            Op::LoadLocal(idx) => {
-                let Some(val) = rt.get_local_by_index(*idx) else {
-                    return Err(format!("Undefined local variable index: {}", idx));
-                };
-                stack.push(val);
-            }
-            Op::StoreLocal(idx) => {
-                let val = stack.pop().ok_or_else(|| stack_underflow(ip, op))?;
-                if !rt.set_local_by_index(*idx, val) {
-                    while rt.get_local_by_index(*idx).is_none() {
-                        rt.define_local(format!("_tmp_{}", idx), Value::UNIT);
-                    }
-                    rt.set_local_by_index(*idx, val);
-                }
-            }
-            Op::LoadName(idx) => {
-                let name = rt.get_const_str(*idx, &bc.constants);
-                let v = if rt.locals.is_active() {
-                    if let Some(v) = rt.get_local(name) {
-                        v
-                    } else {
-                        rt.env.get_cached(name).ok_or_else(|| {
-                            rt.error(xu_syntax::DiagnosticKind::UndefinedIdentifier(name.to_string()))
-                        })?
-                    }
-                } else {
-                    rt.env.get_cached(name).ok_or_else(|| {
-                        rt.error(xu_syntax::DiagnosticKind::UndefinedIdentifier(name.to_string()))
-                    })?
-                };
-                stack.push(v);
-            }
-            Op::StoreName(idx) => {
-                let name = rt.get_const_str(*idx, &bc.constants);
-                let v = stack.pop().ok_or_else(|| stack_underflow(ip, op))?;
-                if rt.locals.is_active() {
-                    if !rt.set_local(name, v) && !rt.env.assign(name, v) {
-                        rt.define_local(name.to_string(), v);
-                    }
-                } else if !rt.env.assign(name, v) {
-                    rt.env.define(name.to_string(), v);
-                }
-            }
+            Op::LoadLocal(idx) => vars::op_load_local(rt, stack, *idx)?,
+            Op::StoreLocal(idx) => vars::op_store_local(rt, stack, *ip, op, *idx)?,
+            Op::LoadName(idx) => vars::op_load_name(rt, bc, stack, *idx)?,
+            Op::StoreName(idx) => vars::op_store_name(rt, bc, stack, *ip, op, *idx)?,
             Op::Use(path_idx, alias_idx) => {
-                let path = rt.get_const_str(*path_idx, &bc.constants);
-                let alias = rt.get_const_str(*alias_idx, &bc.constants);
-                match crate::modules::import_path(rt, path) {
-                    Ok(module_obj) => rt.env.define(alias.to_string(), module_obj),
-                    Err(e) => {
-                        let err_val = Value::str(rt.alloc(ManagedObject::Str(e.into())));
-                        if let Some(flow) = throw_value(rt, &mut ip, &mut handlers, &mut stack, &mut iters, &mut pending, &mut thrown, err_val) {
-                            return Ok(flow);
-                        }
-                        ip += 1;
-                        continue;
-                    }
+                if let Some(flow) = vars::op_use(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *path_idx, *alias_idx)? {
+                    return Ok(flow);
                 }
             }
             // Arithmetic operations
             Op::Add => {
-                if let Some(flow) = math::op_add(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = math::op_add(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             Op::Sub => {
-                if let Some(flow) = math::op_sub(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = math::op_sub(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             Op::Mul => {
-                if let Some(flow) = math::op_mul(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = math::op_mul(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             Op::Div => {
-                if let Some(flow) = math::op_div(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = math::op_div(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             Op::Mod => {
-                if let Some(flow) = math::op_mod(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = math::op_mod(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
+                    return Ok(flow);
+                }
+            }
+            Op::Pow => {
+                if let Some(flow) = math::op_pow(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
+                    return Ok(flow);
+                }
+            }
+            Op::BitAnd => {
+                if let Some(flow) = math::op_bit_and(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
+                    return Ok(flow);
+                }
+            }
+            Op::BitOr => {
+                if let Some(flow) = math::op_bit_or(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
+                    return Ok(flow);
+                }
+            }
+            Op::BitXor => {
+                if let Some(flow) = math::op_bit_xor(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
+                    return Ok(flow);
+                }
+            }
+            Op::Shl => {
+                if let Some(flow) = math::op_shl(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
+                    return Ok(flow);
+                }
+            }
+            Op::Shr => {
+                if let Some(flow) = math::op_shr(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             // Logical operations
             Op::And => {
-                if let Some(flow) = math::op_and(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = math::op_and(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             Op::Or => {
-                if let Some(flow) = math::op_or(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = math::op_or(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             Op::Not => {
-                if let Some(flow) = math::op_not(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = math::op_not(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
+                    return Ok(flow);
+                }
+            }
+            Op::BitNot => {
+                if let Some(flow) = math::op_bit_not(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             // Comparison operations
-            Op::Eq => compare::op_eq(rt, &mut stack)?,
-            Op::Ne => compare::op_ne(rt, &mut stack)?,
+            Op::Eq => compare::op_eq(rt, stack)?,
+            Op::Ne => compare::op_ne(rt, stack)?,
             Op::Gt => {
-                if let Some(flow) = compare::op_gt(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = compare::op_gt(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             Op::Lt => {
-                if let Some(flow) = compare::op_lt(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = compare::op_lt(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             Op::Ge => {
-                if let Some(flow) = compare::op_ge(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = compare::op_ge(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
             Op::Le => {
-                if let Some(flow) = compare::op_le(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = compare::op_le(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
+                    return Ok(flow);
+                }
+            }
+            Op::CmpLink(op, to) => match compare::op_cmp_link(rt, stack, *op) {
+                Ok(true) => {}
+                Ok(false) => {
+                    *ip = *to as usize;
+                    continue;
+                }
+                Err(e) => {
+                    if let Some(flow) = try_throw_error(rt, ip, handlers, stack, iters, &mut pending, &mut thrown, e) {
+                        return Ok(flow);
+                    }
+                    continue;
+                }
+            },
+            Op::In => {
+                if let Some(flow) = compare::op_in(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
+                    return Ok(flow);
+                }
+            }
+            Op::InRange(inclusive) => {
+                if let Some(flow) =
+                    compare::op_in_range(rt, stack, ip, handlers, iters, &mut pending, &mut thrown, *inclusive)?
+                {
                     return Ok(flow);
                 }
             }
             // String operations
             Op::StrAppend => {
-                if let Some(flow) = string::op_str_append(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown)? {
+                if let Some(flow) = string::op_str_append(rt, stack, ip, handlers, iters, &mut pending, &mut thrown)? {
                     return Ok(flow);
                 }
             }
-            Op::BuilderNewCap(cap) => string::op_builder_new_cap(rt, &mut stack, *cap),
-            Op::BuilderAppend => string::op_builder_append(rt, &mut stack)?,
-            Op::BuilderFinalize => string::op_builder_finalize(rt, &mut stack)?,
+            Op::BuilderNewCap(cap) => string::op_builder_new_cap(rt, stack, *cap),
+            Op::BuilderAppend => string::op_builder_append(rt, stack)?,
+            Op::BuilderFinalize => string::op_builder_finalize(rt, stack)?,
             // Assignment operations
             Op::AddAssignName(idx) => {
-                if let Some(flow) = assign::op_add_assign_name(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *idx)? {
+                if let Some(flow) = assign::op_add_assign_name(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *idx)? {
                     return Ok(flow);
                 }
             }
             Op::AddAssignLocal(idx) => {
-                if let Some(flow) = assign::op_add_assign_local(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *idx)? {
+                if let Some(flow) = assign::op_add_assign_local(rt, stack, ip, handlers, iters, &mut pending, &mut thrown, *idx)? {
                     return Ok(flow);
                 }
             }
             Op::IncLocal(idx) => {
-                if let Some(flow) = assign::op_inc_local(rt, &mut ip, &mut handlers, &mut stack, &mut iters, &mut pending, &mut thrown, *idx)? {
+                if let Some(flow) = assign::op_inc_local(rt, ip, handlers, stack, iters, &mut pending, &mut thrown, *idx)? {
                     return Ok(flow);
                 }
             }
             // Type operations
             Op::AssertType(idx) => {
-                if let Some(flow) = types::op_assert_type(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *idx)? {
+                if let Some(flow) = types::op_assert_type(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *idx)? {
                     return Ok(flow);
                 }
             }
             Op::DefineStruct(idx) => types::op_define_struct(rt, bc, *idx),
             Op::DefineEnum(idx) => types::op_define_enum(rt, bc, *idx),
             Op::StructInit(t_idx, n_idx) => {
-                if let Some(flow) = types::op_struct_init(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *t_idx, *n_idx)? {
+                if let Some(flow) = types::op_struct_init(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *t_idx, *n_idx)? {
                     return Ok(flow);
                 }
             }
             Op::StructInitSpread(t_idx, n_idx) => {
-                if let Some(flow) = types::op_struct_init_spread(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *t_idx, *n_idx)? {
+                if let Some(flow) = types::op_struct_init_spread(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *t_idx, *n_idx)? {
                     return Ok(flow);
                 }
             }
-            Op::EnumCtor(t_idx, v_idx) => types::op_enum_ctor(rt, bc, &mut stack, *t_idx, *v_idx)?,
-            Op::EnumCtorN(t_idx, v_idx, argc) => types::op_enum_ctor_n(rt, bc, &mut stack, *t_idx, *v_idx, *argc)?,
+            Op::EnumCtor(t_idx, v_idx) => types::op_enum_ctor(rt, bc, stack, *t_idx, *v_idx)?,
+            Op::EnumCtorN(t_idx, v_idx, argc) => types::op_enum_ctor_n(rt, bc, stack, *t_idx, *v_idx, *argc)?,
             // Function operations
-            Op::MakeFunction(f_idx) => call::op_make_function(rt, bc, &mut stack, *f_idx)?,
+            Op::MakeFunction(f_idx) => call::op_make_function(rt, bc, stack, *f_idx)?,
             Op::Call(n) => {
-                if let Some(flow) = call::op_call(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *n)? {
+                if let Some(flow) = call::op_call(rt, stack, ip, handlers, iters, &mut pending, &mut thrown, *n)? {
                     return Ok(flow);
                 }
             }
             Op::CallMethod(m_idx, method_hash, n, slot_idx) => {
-                if let Some(flow) = call::op_call_method(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *m_idx, *method_hash, *n, *slot_idx)? {
+                if let Some(flow) = call::op_call_method(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *m_idx, *method_hash, *n, *slot_idx)? {
                     return Ok(flow);
                 }
             }
-            Op::CallStaticOrMethod(type_idx, m_idx, method_hash, n, slot_idx) => {
-                if let Some(flow) = call::op_call_static_or_method(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *type_idx, *m_idx, *method_hash, *n, *slot_idx)? {
+            Op::CallStaticOrMethod(type_idx, m_idx, n, slot_idx) => {
+                if let Some(flow) = call::op_call_static_or_method(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *type_idx, *m_idx, *n, *slot_idx)? {
+                    return Ok(flow);
+                }
+            }
+            Op::Return => return call::op_return(stack),
+            Op::Try => {
+                if let Some(flow) = call::op_try(rt, stack)? {
                     return Ok(flow);
                 }
             }
-            Op::Return => return call::op_return(&mut stack),
             // Collection operations
-            Op::ListNew(n) => collection::op_list_new(rt, &mut stack, *n)?,
+            Op::ListNew(n) => collection::op_list_new(rt, stack, *n)?,
             Op::TupleNew(n) => {
-                if collection::op_tuple_new(rt, &mut stack, *n)? {
-                    ip += 1;
+                if collection::op_tuple_new(rt, stack, *n)? {
+                    *ip += 1;
                     continue;
                 }
             }
-            Op::DictNew(n) => collection::op_dict_new(rt, &mut stack, *n)?,
-            Op::MakeRange(inclusive) => collection::op_make_range(rt, &mut stack, *inclusive)?,
-            Op::ListAppend(n) => collection::op_list_append(rt, &mut stack, *n)?,
-            Op::DictInsert => dict_ops::op_dict_insert(rt, &mut stack)?,
-            Op::DictMerge => dict_ops::op_dict_merge(rt, &mut stack)?,
+            Op::DictNew(n) => collection::op_dict_new(rt, stack, *n)?,
+            Op::MakeRange(inclusive) => collection::op_make_range(rt, stack, *inclusive)?,
+            Op::ListAppend(n) => collection::op_list_append(rt, stack, *n)?,
+            Op::ListReserveFor => collection::op_list_reserve_for(rt, stack)?,
+            Op::DictInsert => dict_ops::op_dict_insert(rt, stack)?,
+            Op::DictMerge => dict_ops::op_dict_merge(rt, stack)?,
             // Access operations
             Op::GetMember(idx, slot_idx) => {
-                if let Some(flow) = access::op_get_member(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *idx, *slot_idx)? {
+                if let Some(flow) = access::op_get_member(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *idx, *slot_idx)? {
+                    return Ok(flow);
+                }
+            }
+            Op::GetMemberAt(idx, offset, slot_idx) => {
+                if let Some(flow) = access::op_get_member_at(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *idx, *offset, *slot_idx)? {
                     return Ok(flow);
                 }
             }
             Op::GetIndex(slot_cell) => {
-                if let Some(flow) = access::op_get_index(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *slot_cell)? {
+                if let Some(flow) = access::op_get_index(rt, stack, ip, handlers, iters, &mut pending, &mut thrown, *slot_cell)? {
                     return Ok(flow);
                 }
             }
             Op::AssignMember(idx, op_type) => {
-                if let Some(flow) = access::op_assign_member(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *idx, *op_type)? {
+                if let Some(flow) = access::op_assign_member(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *idx, *op_type)? {
+                    return Ok(flow);
+                }
+            }
+            Op::AssignMemberAt(idx, offset, op_type) => {
+                if let Some(flow) = access::op_assign_member_at(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *idx, *offset, *op_type)? {
                     return Ok(flow);
                 }
             }
             Op::AssignIndex(aop) => {
-                if let Some(flow) = access::op_assign_index(rt, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *aop)? {
+                if let Some(flow) = access::op_assign_index(rt, stack, ip, handlers, iters, &mut pending, &mut thrown, *aop)? {
                     return Ok(flow);
                 }
             }
             // Static field operations
             Op::GetStaticField(type_idx, field_idx) => {
-                if let Some(flow) = access::op_get_static_field(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *type_idx, *field_idx)? {
+                if let Some(flow) = access::op_get_static_field(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *type_idx, *field_idx)? {
                     return Ok(flow);
                 }
             }
             Op::SetStaticField(type_idx, field_idx) => {
-                if let Some(flow) = access::op_set_static_field(rt, bc, &mut stack, &mut ip, &mut handlers, &mut iters, &mut pending, &mut thrown, *type_idx, *field_idx)? {
+                if let Some(flow) = access::op_set_static_field(rt, bc, stack, ip, handlers, iters, &mut pending, &mut thrown, *type_idx, *field_idx)? {
                     return Ok(flow);
                 }
             }
             Op::InitStaticField(type_idx, field_idx) => {
-                access::op_init_static_field(rt, bc, &mut stack, *type_idx, *field_idx)?;
+                access::op_init_static_field(rt, bc, stack, *type_idx, *field_idx)?;
             }
             // Iterator operations
             Op::ForEachInit(idx, var_idx, end) => {
-                if iter::op_foreach_init(rt, bc, &mut stack, &mut iters, &mut ip, *idx, *var_idx, *end)? {
+                if iter::op_foreach_init(rt, bc, stack, iters, ip, *idx, *var_idx, *end)? {
                     continue;
                 }
             }
             Op::ForEachNext(idx, var_idx, loop_start, end) => {
-                if iter::op_foreach_next(rt, bc, &mut iters, &mut ip, *idx, *var_idx, *loop_start, *end)? {
+                if iter::op_foreach_next(rt, bc, iters, ip, *idx, *var_idx, *loop_start, *end)? {
                     continue;
                 }
             }
-            Op::IterPop => iter::op_iter_pop(&mut iters)?,
+            Op::IterPop => iter::op_iter_pop(iters)?,
             // Control flow
             Op::Jump(to) => {
-                ip = *to;
+                *ip = *to as usize;
                 continue;
             }
             Op::JumpIfFalse(to) => {
                 let v = stack.pop().ok_or_else(|| "Stack underflow".to_string())?;
                 if v.is_bool() {
                     if !v.as_bool() {
-                        ip = *to;
+                        *ip = *to as usize;
                         continue;
                     }
                 } else {
                     let msg = rt.error(xu_syntax::DiagnosticKind::InvalidConditionType(v.type_name().to_string()));
                     let err_val = Value::str(rt.alloc(ManagedObject::Str(msg.into())));
-                    if let Some(flow) = throw_value(rt, &mut ip, &mut handlers, &mut stack, &mut iters, &mut pending, &mut thrown, err_val) {
+                    if let Some(flow) = throw_value(rt, ip, handlers, stack, iters, &mut pending, &mut thrown, err_val) {
                         return Ok(flow);
                     }
                     continue;
@@ -359,12 +404,12 @@ pub(crate) fn run_bytecode(rt: &mut Runtime, bc: &Bytecode) -> Result<Flow, Stri
             Op::JumpIfTrue(to) => {
                 let v = stack.pop().ok_or_else(|| "Stack underflow".to_string())?;
                 if v.is_bool() && v.as_bool() {
-                    ip = *to;
+                    *ip = *to as usize;
                     continue;
                 }
             }
             Op::Break(to) | Op::Continue(to) => {
-                ip = *to;
+                *ip = *to as usize;
                 continue;
             }
             // Environment operations
@@ -373,27 +418,22 @@ pub(crate) fn run_bytecode(rt: &mut Runtime, bc: &Bytecode) -> Result<Flow, Stri
             Op::LocalsPush => rt.push_locals(),
             Op::LocalsPop => rt.pop_locals(),
             // Pattern matching
-            Op::MatchPattern(pat_idx) => {
-                let v = stack.last().cloned().ok_or_else(|| "Stack underflow".to_string())?;
-                let c = rt.get_constant(*pat_idx, &bc.constants);
-                if let xu_ir::Constant::Pattern(pat) = c {
-                    let matched = crate::util::match_pattern(rt, pat, &v).is_some();
-                    stack.push(Value::from_bool(matched));
-                } else {
-                    return Err("Expected pattern constant".into());
+            Op::MatchPattern(pat_idx) => types::op_match_pattern(rt, bc, stack, *pat_idx)?,
+            Op::MatchBindings(pat_idx) => types::op_match_bindings(rt, bc, stack, *pat_idx)?,
+            Op::Switch(table_idx) => {
+                *ip = types::op_switch(rt, bc, stack, *table_idx)?;
+                continue;
+            }
+            Op::OptLink(to) => {
+                if types::op_opt_link(rt, stack)? {
+                    *ip = *to as usize;
+                    continue;
                 }
             }
-            Op::MatchBindings(pat_idx) => {
-                let v = stack.pop().ok_or_else(|| "Stack underflow".to_string())?;
-                let c = rt.get_constant(*pat_idx, &bc.constants);
-                if let xu_ir::Constant::Pattern(pat) = c {
-                    if let Some(bindings) = crate::util::match_pattern(rt, pat, &v) {
-                        for (_, val) in bindings {
-                            stack.push(val);
-                        }
-                    }
-                } else {
-                    return Err("Expected pattern constant".into());
+            Op::Coalesce(to) => {
+                if types::op_coalesce(rt, stack)? {
+                    *ip = *to as usize;
+                    continue;
                 }
             }
             // I/O
@@ -403,12 +443,12 @@ pub(crate) fn run_bytecode(rt: &mut Runtime, bc: &Bytecode) -> Result<Flow, Stri
             }
             // Unsupported/special
             Op::RunPending => {
-                ip += 1;
+                *ip += 1;
                 continue;
             }
             Op::Halt => return Ok(Flow::None),
         }
-        ip += 1;
+        *ip += 1;
     }
     Ok(Flow::None)
 }
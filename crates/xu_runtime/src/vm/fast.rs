@@ -10,6 +10,16 @@ pub(crate) fn run_bytecode_fast(
     rt: &mut Runtime,
     bc: &Bytecode,
 ) -> Option<Result<Flow, String>> {
+    let mut at = 0;
+    let res = run_fast(rt, bc, &mut at);
+    if matches!(res, Some(Err(_))) {
+        rt.record_trace_frame(bc, at);
+    }
+    res
+}
+
+/// `at` tracks the running op for the error trace.
+fn run_fast(rt: &mut Runtime, bc: &Bytecode, at: &mut usize) -> Option<Result<Flow, String>> {
     if bc.ops.len() > 16 {
         return None;
     }
@@ -20,6 +30,7 @@ pub(crate) fn run_bytecode_fast(
             | Op::Const(_)
             | Op::ConstBool(_)
             | Op::ConstNull
+            | Op::ConstEmptyStr
             | Op::Add
             | Op::AddAssignName(_)
             | Op::AddAssignLocal(_)
@@ -32,6 +43,7 @@ pub(crate) fn run_bytecode_fast(
             | Op::StoreName(_)
             | Op::StoreLocal(_)
             | Op::GetMember(_, _)
+            | Op::GetMemberAt(_, _, _)
             | Op::Return
             | Op::Pop => {}
             _ => return None,
@@ -87,7 +99,8 @@ pub(crate) fn run_bytecode_fast(
 
     let mut stack: [Value; 8] = [Value::UNIT; 8];
     let mut sp: usize = 0;
-    for op in bc.ops.iter() {
+    for (i, op) in bc.ops.iter().enumerate() {
+        *at = i;
         match op {
             Op::ConstInt(i) => {
                 stack[sp] = Value::from_i64(*i);
@@ -97,6 +110,10 @@ pub(crate) fn run_bytecode_fast(
                 stack[sp] = Value::from_f64(*f);
                 sp += 1;
             }
+            Op::ConstEmptyStr => {
+                stack[sp] = rt.empty_str();
+                sp += 1;
+            }
             Op::Const(idx) => {
                 let c = &bc.constants[*idx as usize];
                 match c {
@@ -133,7 +150,7 @@ pub(crate) fn run_bytecode_fast(
                 sp += 1;
             }
             Op::LoadLocal(idx) => {
-                stack[sp] = match rt.get_local_by_index(*idx) {
+                stack[sp] = match rt.get_local_by_index(*idx as usize) {
                     Some(v) => v,
                     None => return Some(Err(format!("Undefined local variable index: {}", idx))),
                 };
@@ -153,11 +170,11 @@ pub(crate) fn run_bytecode_fast(
                 }
                 sp -= 1;
                 let val = stack[sp];
-                if !rt.set_local_by_index(*idx, val) {
-                    while rt.get_local_by_index(*idx).is_none() {
+                if !rt.set_local_by_index(*idx as usize, val) {
+                    while rt.get_local_by_index(*idx as usize).is_none() {
                         rt.define_local(format!("_tmp_{}", idx), Value::UNIT);
                     }
-                    rt.set_local_by_index(*idx, val);
+                    rt.set_local_by_index(*idx as usize, val);
                 }
             }
             Op::AddAssignName(idx) => {
@@ -187,7 +204,7 @@ pub(crate) fn run_bytecode_fast(
                 }
                 sp -= 1;
                 let rhs = stack[sp];
-                let mut cur = match rt.get_local_by_index(*idx) {
+                let mut cur = match rt.get_local_by_index(*idx as usize) {
                     Some(v) => v,
                     None => return Some(Err(format!("Undefined local variable index: {}", idx))),
                 };
@@ -199,10 +216,10 @@ pub(crate) fn run_bytecode_fast(
                         Err(e) => return Some(Err(e)),
                     };
                 }
-                rt.set_local_by_index(*idx, cur);
+                rt.set_local_by_index(*idx as usize, cur);
             }
             Op::IncLocal(idx) => {
-                let mut cur = match rt.get_local_by_index(*idx) {
+                let mut cur = match rt.get_local_by_index(*idx as usize) {
                     Some(v) => v,
                     None => return Some(Err(format!("Undefined local variable index: {}", idx))),
                 };
@@ -218,7 +235,7 @@ pub(crate) fn run_bytecode_fast(
                         Err(e) => return Some(Err(e)),
                     };
                 }
-                rt.set_local_by_index(*idx, cur);
+                rt.set_local_by_index(*idx as usize, cur);
             }
             Op::Add => {
                 if sp < 2 {
@@ -245,7 +262,21 @@ pub(crate) fn run_bytecode_fast(
                 sp -= 1;
                 let obj = stack[sp];
                 let field = rt.get_const_str(*idx, &bc.constants);
-                let v = match rt.get_member_with_ic_raw(obj, field, *slot) {
+                let v = match rt.get_member_with_ic_raw(obj, field, slot.map(|s| s as usize)) {
+                    Ok(v) => v,
+                    Err(e) => return Some(Err(e)),
+                };
+                stack[sp] = v;
+                sp += 1;
+            }
+            Op::GetMemberAt(idx, offset, slot) => {
+                if sp == 0 {
+                    return Some(Err("Stack underflow".into()));
+                }
+                sp -= 1;
+                let obj = stack[sp];
+                let field = rt.get_const_str(*idx, &bc.constants);
+                let v = match rt.get_member_at(obj, field, *offset, *slot) {
                     Ok(v) => v,
                     Err(e) => return Some(Err(e)),
                 };
@@ -262,11 +293,28 @@ pub(crate) fn run_bytecode_fast(
     Some(Ok(Flow::None))
 }
 
+/// Runs a function of `def` without setting up a call frame; the function
+/// is not `current_func`, so the error trace names it from `def`.
 pub(crate) fn run_bytecode_fast_params_only(
+    rt: &mut Runtime,
+    bc: &Bytecode,
+    def: &xu_ir::FuncDef,
+    args: &[Value],
+) -> Option<Result<Value, String>> {
+    let mut at = 0;
+    let res = run_fast_params_only(rt, bc, &def.params, args, &mut at);
+    if matches!(res, Some(Err(_))) {
+        rt.push_trace_frame(bc, at, Some(&def.name));
+    }
+    res
+}
+
+fn run_fast_params_only(
     rt: &mut Runtime,
     bc: &Bytecode,
     params: &[xu_ir::Param],
     args: &[Value],
+    at: &mut usize,
 ) -> Option<Result<Value, String>> {
     if params.len() != args.len() {
         return None;
@@ -283,7 +331,7 @@ pub(crate) fn run_bytecode_fast_params_only(
                 }
             }
             Op::LoadLocal(idx) => {
-                if *idx >= args.len() {
+                if *idx as usize >= args.len() {
                     return None;
                 }
             }
@@ -292,6 +340,7 @@ pub(crate) fn run_bytecode_fast_params_only(
             | Op::Const(_)
             | Op::ConstBool(_)
             | Op::ConstNull
+            | Op::ConstEmptyStr
             | Op::Add
             | Op::Sub
             | Op::Mul
@@ -303,6 +352,7 @@ pub(crate) fn run_bytecode_fast_params_only(
             | Op::Eq
             | Op::Ne
             | Op::GetMember(_, _)
+            | Op::GetMemberAt(_, _, _)
             | Op::Return
             | Op::Pop => {}
             _ => return None,
@@ -311,7 +361,8 @@ pub(crate) fn run_bytecode_fast_params_only(
 
     let mut stack: [Value; 16] = [Value::UNIT; 16];
     let mut sp: usize = 0;
-    for op in bc.ops.iter() {
+    for (i, op) in bc.ops.iter().enumerate() {
+        *at = i;
         match op {
             Op::ConstInt(i) => {
                 stack[sp] = Value::from_i64(*i);
@@ -321,6 +372,10 @@ pub(crate) fn run_bytecode_fast_params_only(
                 stack[sp] = Value::from_f64(*f);
                 sp += 1;
             }
+            Op::ConstEmptyStr => {
+                stack[sp] = rt.empty_str();
+                sp += 1;
+            }
             Op::Const(idx) => {
                 let c = &bc.constants[*idx as usize];
                 match c {
@@ -349,7 +404,7 @@ pub(crate) fn run_bytecode_fast_params_only(
                 sp -= 1;
             }
             Op::LoadLocal(idx) => {
-                stack[sp] = args[*idx];
+                stack[sp] = args[*idx as usize];
                 sp += 1;
             }
             Op::LoadName(idx) => {
@@ -464,7 +519,21 @@ pub(crate) fn run_bytecode_fast_params_only(
                 sp -= 1;
                 let obj = stack[sp];
                 let field = rt.get_const_str(*idx, &bc.constants);
-                let v = match rt.get_member_with_ic_raw(obj, field, *slot) {
+                let v = match rt.get_member_with_ic_raw(obj, field, slot.map(|s| s as usize)) {
+                    Ok(v) => v,
+                    Err(e) => return Some(Err(e)),
+                };
+                stack[sp] = v;
+                sp += 1;
+            }
+            Op::GetMemberAt(idx, offset, slot) => {
+                if sp == 0 {
+                    return Some(Err("Stack underflow".into()));
+                }
+                sp -= 1;
+                let obj = stack[sp];
+                let field = rt.get_const_str(*idx, &bc.constants);
+                let v = match rt.get_member_at(obj, field, *offset, *slot) {
                     Ok(v) => v,
                     Err(e) => return Some(Err(e)),
                 };
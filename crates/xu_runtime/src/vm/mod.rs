@@ -7,6 +7,8 @@ mod exception;
 mod fast;
 pub(crate) mod ops;
 pub(crate) mod stack;
+#[cfg(feature = "threaded-dispatch")]
+mod threaded;
 
 pub use dispatch::VM;
 pub(crate) use dispatch::run_bytecode;
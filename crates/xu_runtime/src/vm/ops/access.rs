@@ -2,6 +2,7 @@
 //!
 //! This module contains operations for:
 //! - GetMember: Access struct/object members
+//! - GetMemberAt / AssignMemberAt: Struct fields at compiler-resolved offsets
 //! - GetIndex: Array/dict indexing
 //! - AssignMember: Assign to struct/object members
 //! - AssignIndex: Assign to array/dict elements
@@ -9,14 +10,13 @@
 //! - SetStaticField: Assign to static fields
 //! - InitStaticField: Initialize static fields
 
-use indexmap::map::RawEntryApiV1;
 use xu_ir::Bytecode;
 
 use crate::core::heap::ManagedObject;
 use crate::core::value::{TAG_DICT, TAG_LIST, TAG_STR, TAG_TUPLE, ELEMENTS_MAX};
 use crate::core::Value;
 use crate::errors::messages::NOT_A_LIST;
-use crate::runtime::DictCacheIntLast;
+use crate::runtime::{DictCacheIntLast, IcKind, record_ic};
 use crate::vm::ops::helpers::{pop_stack, pop2_stack, try_throw_error, handle_result, handle_result_push};
 use crate::vm::stack::{Handler, IterState, Pending};
 use crate::{Flow, Runtime};
@@ -33,8 +33,9 @@ pub(crate) fn op_get_member(
     pending: &mut Option<Pending>,
     thrown: &mut Option<Value>,
     idx: u32,
-    slot_idx: Option<usize>,
+    slot_idx: Option<u32>,
 ) -> Result<Option<Flow>, String> {
+    let slot_idx = slot_idx.map(|i| i as usize);
     let obj = pop_stack(stack)?;
     let tag = obj.get_tag();
     let field = rt.get_const_str(idx, &bc.constants);
@@ -47,9 +48,14 @@ pub(crate) fn op_get_member(
             if let Some(idx_slot) = slot_idx {
                 if idx_slot < rt.caches.ic_slots.len() {
                     let c = &rt.caches.ic_slots[idx_slot];
-                    if c.struct_ty_hash == s.ty_hash && c.key_hash == field_hash {
+                    if c.struct_ty_hash == s.ty_hash
+                        && c.key_hash == field_hash
+                        && c.layout_id == s.layout_id()
+                        && c.generation == rt.caches.ic_generation
+                    {
                         if let Some(offset) = c.field_offset {
                             stack.push(s.fields[offset]);
+                            record_ic(&mut rt.caches.ic_stats, &rt.heap, IcKind::Field, idx_slot, field, obj, true);
                             return Ok(None);
                         }
                     }
@@ -67,6 +73,27 @@ pub(crate) fn op_get_member(
     handle_result_push(rt, ip, handlers, stack, iters, pending, thrown, result)
 }
 
+/// Execute Op::GetMemberAt - struct field at a compiler-resolved offset
+#[inline(always)]
+pub(crate) fn op_get_member_at(
+    rt: &mut Runtime,
+    bc: &Bytecode,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+    idx: u32,
+    offset: u32,
+    slot_idx: Option<u32>,
+) -> Result<Option<Flow>, String> {
+    let obj = pop_stack(stack)?;
+    let field = rt.get_const_str(idx, &bc.constants);
+    let result = rt.get_member_at(obj, field, offset, slot_idx);
+    handle_result_push(rt, ip, handlers, stack, iters, pending, thrown, result)
+}
+
 /// Execute Op::GetIndex - array/dict indexing
 #[inline(always)]
 pub(crate) fn op_get_index(
@@ -77,8 +104,9 @@ pub(crate) fn op_get_index(
     iters: &mut Vec<IterState>,
     pending: &mut Option<Pending>,
     thrown: &mut Option<Value>,
-    slot_cell: Option<usize>,
+    slot_cell: Option<u32>,
 ) -> Result<Option<Flow>, String> {
+    let slot_cell = slot_cell.map(|i| i as usize);
     let (obj, idx) = pop2_stack(stack)?;
     let tag = obj.get_tag();
 
@@ -105,7 +133,7 @@ pub(crate) fn op_get_index(
                 if val.is_none() {
                     // Use raw_entry to avoid creating DictKey
                     let hash = Runtime::hash_dict_key_int(me.map.hasher(), key);
-                    if let Some((_, v)) = me.map.raw_entry_v1().from_hash(hash, |k| matches!(k, crate::core::value::DictKey::Int(i) if *i == key)) {
+                    if let Some((_, v)) = me.map.get_by_hash(hash, |k| matches!(k, crate::core::value::DictKey::Int(i) if *i == key)) {
                         val = Some(*v);
                         rt.caches.dict_cache_int_last = Some(DictCacheIntLast {
                             id: id.0,
@@ -143,7 +171,7 @@ pub(crate) fn op_get_index(
                     if val.is_none() {
                         let key_hash = Runtime::hash_bytes(me.map.hasher(), key_text.as_bytes());
                         if let Some(v) =
-                            Runtime::dict_get_by_str_with_hash(me, key_text.as_str(), key_hash)
+                            Runtime::dict_get_by_str_with_hash(&rt.heap, me, key_text.as_str(), key_hash)
                         {
                             val = Some(v);
                         }
@@ -247,6 +275,28 @@ pub(crate) fn op_assign_member(
     handle_result(rt, ip, handlers, stack, iters, pending, thrown, result)
 }
 
+/// Execute Op::AssignMemberAt - assign to a struct field at a compiler-resolved offset
+#[inline(always)]
+pub(crate) fn op_assign_member_at(
+    rt: &mut Runtime,
+    bc: &Bytecode,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+    idx: u32,
+    offset: u32,
+    op_type: xu_ir::AssignOp,
+) -> Result<Option<Flow>, String> {
+    let offset = offset as usize;
+    let (rhs, obj) = pop2_stack(stack)?;
+    let field = rt.get_const_str(idx, &bc.constants);
+    let result = rt.assign_member_at(obj, field, offset, op_type, rhs);
+    handle_result(rt, ip, handlers, stack, iters, pending, thrown, result)
+}
+
 /// Execute Op::AssignIndex - assign to array/dict element
 #[inline(always)]
 pub(crate) fn op_assign_index(
@@ -137,8 +137,9 @@ pub(crate) fn op_add_assign_local(
     iters: &mut Vec<IterState>,
     pending: &mut Option<Pending>,
     thrown: &mut Option<Value>,
-    idx: usize,
+    idx: u32,
 ) -> Result<Option<Flow>, String> {
+    let idx = idx as usize;
     let rhs = pop_stack(stack)?;
     let Some(mut cur) = rt.get_local_by_index(idx) else {
         return Err(format!("Undefined local variable index: {}", idx));
@@ -162,8 +163,9 @@ pub(crate) fn op_inc_local(
     iters: &mut Vec<IterState>,
     pending: &mut Option<Pending>,
     thrown: &mut Option<Value>,
-    idx: usize,
+    idx: u32,
 ) -> Result<Option<Flow>, String> {
+    let idx = idx as usize;
     let Some(cur) = rt.get_local_by_index(idx) else {
         return Err(format!("Undefined local variable index: {}", idx));
     };
@@ -1,12 +1,12 @@
 //! Function call operations for the VM.
 
-use indexmap::map::RawEntryApiV1;
 use smallvec::SmallVec;
-use xu_ir::{Bytecode, Op};
+use xu_ir::Bytecode;
 
 use crate::core::heap::ManagedObject;
 use crate::core::value::{DictKey, Function, TAG_DICT, TAG_STR};
 use crate::core::Value;
+use crate::runtime::{IcKind, record_ic};
 use crate::vm::ops::helpers::{pop_stack, try_throw_error};
 use crate::vm::fast::run_bytecode_fast_params_only;
 use crate::vm::stack::{Handler, IterState, Pending};
@@ -46,8 +46,9 @@ pub(crate) fn op_call(
     iters: &mut Vec<IterState>,
     pending: &mut Option<Pending>,
     thrown: &mut Option<Value>,
-    n: usize,
+    n: u32,
 ) -> Result<Option<Flow>, String> {
+    let n = n as usize;
     if stack.len() < n + 1 {
         return Err("Stack underflow in Call".to_string());
     }
@@ -68,7 +69,7 @@ pub(crate) fn op_call(
             {
                 let args = &stack[args_start..];
                 if let Some(res) =
-                    run_bytecode_fast_params_only(rt, &f.bytecode, &f.def.params, args)
+                    run_bytecode_fast_params_only(rt, &f.bytecode, &f.def, args)
                 {
                     fast_res = Some(res);
                 }
@@ -101,9 +102,11 @@ pub(crate) fn op_call_method(
     thrown: &mut Option<Value>,
     m_idx: u32,
     method_hash: u64,
-    n: usize,
-    slot_idx: Option<usize>,
+    n: u32,
+    slot_idx: Option<u32>,
 ) -> Result<Option<Flow>, String> {
+    let n = n as usize;
+    let slot_idx = slot_idx.map(|i| i as usize);
     if stack.len() < n + 1 {
         return Err("Stack underflow in CallMethod".to_string());
     }
@@ -152,7 +155,10 @@ pub(crate) fn op_call_method(
     if let Some(idx) = slot_idx {
         if idx < rt.caches.ic_method_slots.len() {
             let slot = &rt.caches.ic_method_slots[idx];
-            if slot.tag == tag && slot.method_hash == method_hash {
+            if slot.tag == tag
+                && slot.method_hash == method_hash
+                && slot.generation == rt.caches.ic_generation
+            {
                 if tag == crate::core::value::TAG_STRUCT {
                     let id = recv.as_obj_id();
                     if let ManagedObject::Struct(s) = rt.heap.get(id) {
@@ -163,7 +169,7 @@ pub(crate) fn op_call_method(
                                     if let Some(res) = run_bytecode_fast_params_only(
                                         rt,
                                         &f.bytecode,
-                                        &f.def.params,
+                                        &f.def,
                                         all_args,
                                     ) {
                                         fast_res = Some(res);
@@ -178,6 +184,10 @@ pub(crate) fn op_call_method(
     }
 
     let result = if let Some(res) = fast_res {
+        if let Some(idx) = slot_idx {
+            let method = rt.get_const_str(m_idx, &bc.constants);
+            record_ic(&mut rt.caches.ic_stats, &rt.heap, IcKind::Method, idx, method, recv, true);
+        }
         stack.truncate(args_start - 1);
         res
     } else {
@@ -231,12 +241,14 @@ fn try_dict_get_fast(
                 if &c.key_short[..key_bytes.len()] == key_bytes {
                     if let ManagedObject::Dict(me) = rt.heap.get(dict_id) {
                         if c.ver == me.ver {
+                            record_ic(&mut rt.caches.ic_stats, &rt.heap, IcKind::DictGet, *idx, "get", recv, true);
                             return Some(c.option_some_cached);
                         }
                     }
                 }
             }
         }
+        record_ic(&mut rt.caches.ic_stats, &rt.heap, IcKind::DictGet, *idx, "get", recv, false);
     }
 
     // SAFETY: key_ptr still valid
@@ -244,7 +256,7 @@ fn try_dict_get_fast(
     if let ManagedObject::Dict(me) = rt.heap.get(dict_id) {
         let cur_ver = me.ver;
         let key_hash = Runtime::hash_bytes(me.map.hasher(), key_bytes);
-        if let Some(v) = Runtime::dict_get_by_str_with_hash(me, key_str, key_hash) {
+        if let Some(v) = Runtime::dict_get_by_str_with_hash(&rt.heap, me, key_str, key_hash) {
             // Create Option::some and cache it
             let opt = rt.option_some(v);
             if let Some(idx) = slot_idx {
@@ -299,7 +311,7 @@ fn try_dict_contains_fast(
 
     // Get key pointer/len without cloning
     let (key_ptr, key_len, dict_key_hash) = if let ManagedObject::Str(s) = rt.heap.get(key_id) {
-        (s.as_str().as_ptr(), s.as_str().len(), DictKey::hash_str(s.as_str()))
+        (s.as_str().as_ptr(), s.as_str().len(), DictKey::hash_str(s.as_str(), &rt.heap))
     } else {
         return None;
     };
@@ -316,9 +328,7 @@ fn try_dict_contains_fast(
 
         // Use raw_entry for efficient lookup - compare by hash
         let found = me
-            .map
-            .raw_entry_v1()
-            .from_hash(key_hash, |k| k.eq_str(key_str, dict_key_hash, &rt.heap))
+            .map.get_by_hash(key_hash, |k| k.eq_str(key_str, dict_key_hash, &rt.heap))
             .is_some();
 
         return Some(Value::from_bool(found));
@@ -353,7 +363,7 @@ fn try_dict_insert_fast(
 
     // Get key hash
     let dict_key_hash = if let ManagedObject::Str(s) = rt.heap.get(key_id) {
-        DictKey::hash_str(s.as_str())
+        DictKey::hash_str(s.as_str(), &rt.heap)
     } else {
         return false;
     };
@@ -368,6 +378,8 @@ fn try_dict_insert_fast(
                 cached_hash = Some(c.key_hash);
             }
         }
+        let hit = cached_hash.is_some();
+        record_ic(&mut rt.caches.ic_stats, &rt.heap, IcKind::DictInsert, *idx, "insert", recv, hit);
     }
 
     // Write barrier for generational GC (before getting mutable reference)
@@ -397,8 +409,8 @@ fn try_dict_insert_fast(
             hash
         };
 
-        use indexmap::map::raw_entry_v1::RawEntryMut;
-        match me.map.raw_entry_mut_v1().from_hash(key_hash, |kk| {
+        use crate::core::dict_map::DictEntry;
+        match me.map.entry_by_hash(key_hash, |kk| {
             // Compare by hash - if hash matches, it's the same key
             if let DictKey::StrRef { hash, obj_id } = kk {
                 if *hash != dict_key_hash {
@@ -410,10 +422,10 @@ fn try_dict_insert_fast(
                 false
             }
         }) {
-            RawEntryMut::Occupied(mut o) => {
-                *o.get_mut() = value;
+            DictEntry::Occupied(slot) => {
+                *slot = value;
             }
-            RawEntryMut::Vacant(vac) => {
+            DictEntry::Vacant(vac) => {
                 // Use ObjectId directly - no string copy!
                 let key = DictKey::from_str_obj(key_id, dict_key_hash);
                 vac.insert(key, value);
@@ -456,12 +468,13 @@ fn try_dict_insert_int_fast(
     rt.write_barrier(dict_id);
 
     if let ManagedObject::Dict(me) = rt.heap.get_mut(dict_id) {
-            let was_unit = me.set_element(idx, value);
-            if was_unit {
-                me.ver += 1;
+            if let Some(was_unit) = me.try_set_element(idx, value) {
+                if was_unit {
+                    me.ver += 1;
+                }
+                return Some(Value::UNIT);
             }
         }
-        return Some(Value::UNIT);
     }
 
     // Slow path for large integers
@@ -472,12 +485,12 @@ fn try_dict_insert_int_fast(
         let key_hash = Runtime::hash_dict_key_int(me.map.hasher(), key_int);
         let key = DictKey::Int(key_int);
 
-        use indexmap::map::raw_entry_v1::RawEntryMut;
-        match me.map.raw_entry_mut_v1().from_hash(key_hash, |kk| kk == &key) {
-            RawEntryMut::Occupied(mut o) => {
-                *o.get_mut() = value;
+        use crate::core::dict_map::DictEntry;
+        match me.map.entry_by_hash(key_hash, |kk| kk == &key) {
+            DictEntry::Occupied(slot) => {
+                *slot = value;
             }
-            RawEntryMut::Vacant(vac) => {
+            DictEntry::Vacant(vac) => {
                 vac.insert(key, value);
                 me.ver += 1;
             }
@@ -502,7 +515,7 @@ pub(crate) fn op_make_function(
 
         // 检查是否需要捕获变量
         let has_locals = rt.locals.is_active() && rt.locals.has_bindings();
-        let has_params = rt.current_param_bindings.as_ref().map_or(false, |b| !b.is_empty());
+        let has_params = rt.current_param_bindings.as_ref().is_some_and(|b| !b.is_empty());
         let needs_capture = has_locals || has_params;
 
         // 只有在需要捕获变量时才创建新的环境帧
@@ -531,11 +544,6 @@ pub(crate) fn op_make_function(
             }
         }
 
-        let needs_env_frame = bytecode
-            .ops
-            .iter()
-            .any(|op| matches!(op, Op::MakeFunction(_)));
-
         let captured_env = if needs_capture {
             let env = rt.env.freeze();
             rt.env.pop_without_clear();
@@ -549,7 +557,7 @@ pub(crate) fn op_make_function(
             def: def.clone(),
             bytecode: std::rc::Rc::clone(bytecode),
             env: captured_env,
-            needs_env_frame,
+            needs_env_frame: func_bc.needs_env_frame,
             locals_count,
             type_sig_ic: std::cell::Cell::new(None),
         };
@@ -578,10 +586,11 @@ pub(crate) fn op_call_static_or_method(
     thrown: &mut Option<Value>,
     type_idx: u32,
     m_idx: u32,
-    method_hash: u64,
-    n: usize,
-    slot_idx: Option<usize>,
+    n: u32,
+    slot_idx: Option<u32>,
 ) -> Result<Option<Flow>, String> {
+    let n = n as usize;
+    let slot_idx = slot_idx.map(|i| i as usize);
     let type_name = rt.get_const_str(type_idx, &bc.constants);
     let method = rt.get_const_str(m_idx, &bc.constants);
 
@@ -610,6 +619,7 @@ pub(crate) fn op_call_static_or_method(
             return Err("Stack underflow in CallStaticOrMethod".to_string());
         }
         let args_start = stack.len() - n;
+        let method_hash = xu_ir::stable_hash64(method);
         let res = rt.call_method_with_ic_raw(recv, method, method_hash, &stack[args_start..], slot_idx);
         stack.truncate(args_start);
         return handle_call_result(rt, stack, ip, handlers, iters, pending, thrown, res);
@@ -629,3 +639,16 @@ pub(crate) fn op_return(stack: &mut Vec<Value>) -> Result<Flow, String> {
     let v = pop_stack(stack)?;
     Ok(Flow::Return(v))
 }
+
+/// Execute Op::Try - unwrap ok/some, or return err/none from the function
+#[inline]
+pub(crate) fn op_try(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result<Option<Flow>, String> {
+    let v = pop_stack(stack)?;
+    match rt.try_payload(v)? {
+        Some(inner) => {
+            stack.push(inner);
+            Ok(None)
+        }
+        None => Ok(Some(Flow::Return(v))),
+    }
+}
@@ -10,7 +10,7 @@
 use smallvec::SmallVec;
 
 use crate::core::heap::ManagedObject;
-use crate::core::value::{DictKey, TAG_LIST, TAG_STR};
+use crate::core::value::{DictKey, TAG_DICT, TAG_LIST, TAG_RANGE, TAG_STR, TAG_TUPLE};
 use crate::core::Value;
 use crate::errors::messages::NOT_A_STRING;
 use crate::util::to_i64;
@@ -19,35 +19,27 @@ use crate::Runtime;
 
 /// Execute Op::ListNew - create a new list
 #[inline(always)]
-pub(crate) fn op_list_new(rt: &mut Runtime, stack: &mut Vec<Value>, n: usize) -> Result<(), String> {
-    // Try to get a pooled list for small lists (≤8 elements)
-    let mut items = if let Some(pooled) = rt.pools.get_small_list(n) {
-        pooled
-    } else {
-        Vec::with_capacity(n)
-    };
+pub(crate) fn op_list_new(rt: &mut Runtime, stack: &mut Vec<Value>, n: u32) -> Result<(), String> {
+    let n = n as usize;
+    let mut items = rt.heap.take_list(n);
     for _ in 0..n {
         items.push(pop_stack(stack)?);
     }
     items.reverse();
-    let id = rt.alloc(ManagedObject::List(items));
+    let id = rt.alloc(ManagedObject::List(items.into()));
     stack.push(Value::list(id));
     Ok(())
 }
 
 /// Execute Op::TupleNew - create a new tuple
 #[inline(always)]
-pub(crate) fn op_tuple_new(rt: &mut Runtime, stack: &mut Vec<Value>, n: usize) -> Result<bool, String> {
+pub(crate) fn op_tuple_new(rt: &mut Runtime, stack: &mut Vec<Value>, n: u32) -> Result<bool, String> {
+    let n = n as usize;
     if n == 0 {
         stack.push(Value::UNIT);
         return Ok(true); // Signal to continue (skip ip increment)
     }
-    // Try to get a pooled list for small tuples (≤8 elements)
-    let mut items = if let Some(pooled) = rt.pools.get_small_list(n) {
-        pooled
-    } else {
-        Vec::with_capacity(n)
-    };
+    let mut items = rt.heap.take_list(n);
     for _ in 0..n {
         items.push(pop_stack(stack)?);
     }
@@ -59,23 +51,28 @@ pub(crate) fn op_tuple_new(rt: &mut Runtime, stack: &mut Vec<Value>, n: usize) -
 
 /// Execute Op::DictNew - create a new dictionary
 #[inline(always)]
-pub(crate) fn op_dict_new(rt: &mut Runtime, stack: &mut Vec<Value>, n: usize) -> Result<(), String> {
-    let mut map = crate::core::value::dict_with_capacity(n);
-    for _ in 0..n {
-        let v = pop_stack(stack)?;
-        let k = pop_stack(stack)?;
+pub(crate) fn op_dict_new(rt: &mut Runtime, stack: &mut Vec<Value>, n: u32) -> Result<(), String> {
+    let n = n as usize;
+    let mut map = rt.heap.take_dict(n);
+    // Entries are inserted in source order so the literal's order is kept
+    let Some(start) = stack.len().checked_sub(2 * n) else {
+        return Err("Stack underflow".to_string());
+    };
+    let pairs: SmallVec<[Value; 16]> = stack.drain(start..).collect();
+    for pair in pairs.chunks_exact(2) {
+        let (k, v) = (pair[0], pair[1]);
         let key = if k.get_tag() == TAG_STR {
             let key_id = k.as_obj_id();
             if let ManagedObject::Str(s) = rt.heap.get(key_id) {
                 // Use ObjectId directly - no string copy!
-                DictKey::from_str_obj(key_id, DictKey::hash_str(s.as_str()))
+                DictKey::from_str_obj(key_id, DictKey::hash_str(s.as_str(), &rt.heap))
             } else {
                 return Err(NOT_A_STRING.into());
             }
         } else if k.is_int() {
             DictKey::Int(k.as_i64())
         } else {
-            return Err(rt.error(xu_syntax::DiagnosticKind::DictKeyRequired));
+            crate::methods::get_dict_key_from_value(rt, &k)?
         };
         map.map.insert(key, v);
     }
@@ -89,8 +86,9 @@ pub(crate) fn op_dict_new(rt: &mut Runtime, stack: &mut Vec<Value>, n: usize) ->
 pub(crate) fn op_list_append(
     rt: &mut Runtime,
     stack: &mut Vec<Value>,
-    n: usize,
+    n: u32,
 ) -> Result<(), String> {
+    let n = n as usize;
     let mut items: SmallVec<[Value; 8]> = SmallVec::with_capacity(n);
     for _ in 0..n {
         items.push(pop_stack(stack)?);
@@ -107,6 +105,7 @@ pub(crate) fn op_list_append(
 
     let id = recv.as_obj_id();
     if let ManagedObject::List(vs) = rt.heap_get_mut(id) {
+        let vs = vs.make_mut();
         vs.reserve(items.len());
         for v in items {
             vs.push(v);
@@ -116,6 +115,34 @@ pub(crate) fn op_list_append(
     Ok(())
 }
 
+/// Largest pre-size `ListReserveFor` applies; a loop usually appends once per
+/// iteration, but may stop early.
+const LOOP_PRESIZE_MAX: usize = 1 << 20;
+
+/// Execute Op::ListReserveFor - reserve room in a list for one append per
+/// element of the iterable a loop is about to walk
+#[inline(always)]
+pub(crate) fn op_list_reserve_for(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result<(), String> {
+    let (iter, list) = pop2_stack(stack)?;
+    let sized = matches!(iter.get_tag(), TAG_LIST | TAG_TUPLE | TAG_DICT | TAG_RANGE);
+    if list.get_tag() != TAG_LIST || !sized {
+        return Ok(());
+    }
+    let n = match rt.heap.get(iter.as_obj_id()) {
+        ManagedObject::List(v) => v.len(),
+        ManagedObject::Tuple(v) => v.len(),
+        ManagedObject::Dict(d) => d.len(),
+        ManagedObject::Range(start, end, inclusive) => {
+            end.abs_diff(*start).saturating_add(*inclusive as u64) as usize
+        }
+        _ => return Ok(()),
+    };
+    if let ManagedObject::List(vs) = rt.heap_get_mut(list.as_obj_id()) {
+        vs.make_mut().reserve(n.min(LOOP_PRESIZE_MAX));
+    }
+    Ok(())
+}
+
 /// Execute Op::MakeRange - create a range object
 #[inline(always)]
 pub(crate) fn op_make_range(
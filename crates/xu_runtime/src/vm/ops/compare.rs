@@ -4,10 +4,13 @@
 //! - Eq: Equality comparison
 //! - Ne: Inequality comparison
 //! - Gt/Lt/Ge/Le: Ordered comparisons (unified implementation)
+//! - CmpLink: One link of a chained comparison
+//! - In/InRange: Membership tests
 
 use crate::core::heap::ManagedObject;
 use crate::core::value::{ValueExt, TAG_STR};
 use crate::core::Value;
+use crate::util::to_i64;
 use crate::vm::ops::helpers::{pop_stack, peek_last_mut, try_throw_error};
 use crate::vm::stack::{Handler, IterState, Pending};
 use crate::{Flow, Runtime};
@@ -50,6 +53,16 @@ impl CmpKind {
         }
     }
 
+    #[inline(always)]
+    fn from_binary_op(op: xu_ir::BinaryOp) -> Self {
+        match op {
+            xu_ir::BinaryOp::Gt => CmpKind::Gt,
+            xu_ir::BinaryOp::Lt => CmpKind::Lt,
+            xu_ir::BinaryOp::Ge => CmpKind::Ge,
+            _ => CmpKind::Le,
+        }
+    }
+
     #[inline(always)]
     fn binary_op(self) -> xu_ir::BinaryOp {
         match self {
@@ -76,6 +89,22 @@ pub(crate) fn op_cmp(
     let b = pop_stack(stack)?;
     let a = peek_last_mut(stack)?;
 
+    match compare(rt, kind, *a, b) {
+        Ok(r) => *a = r,
+        Err(e) => {
+            if let Some(flow) = try_throw_error(
+                rt, ip, handlers, stack, iters, pending, thrown, e,
+            ) {
+                return Ok(Some(flow));
+            }
+            return Ok(None);
+        }
+    }
+    Ok(None)
+}
+
+#[inline(always)]
+fn compare(rt: &Runtime, kind: CmpKind, a: Value, b: Value) -> Result<Value, String> {
     if a.get_tag() == TAG_STR && b.get_tag() == TAG_STR {
         let sa = if let ManagedObject::Str(s) = rt.heap.get(a.as_obj_id()) {
             s.as_str()
@@ -87,17 +116,76 @@ pub(crate) fn op_cmp(
         } else {
             ""
         };
-        *a = Value::from_bool(kind.compare_str(sa, sb));
+        Ok(Value::from_bool(kind.compare_str(sa, sb)))
     } else {
-        match a.bin_op(kind.binary_op(), b) {
-            Ok(r) => *a = r,
-            Err(e) => {
-                if let Some(flow) = try_throw_error(
-                    rt, ip, handlers, stack, iters, pending, thrown, e,
-                ) {
-                    return Ok(Some(flow));
-                }
-                return Ok(None);
+        a.bin_op(kind.binary_op(), b)
+    }
+}
+
+/// Execute Op::CmpLink - a non-final link of a chained comparison.
+/// Leaves `b` for the next link and returns true when `a op b` holds;
+/// otherwise leaves `false` and returns false so the caller jumps to the
+/// end of the chain.
+#[inline(always)]
+pub(crate) fn op_cmp_link(rt: &Runtime, stack: &mut Vec<Value>, op: xu_ir::BinaryOp) -> Result<bool, String> {
+    let b = pop_stack(stack)?;
+    let a = peek_last_mut(stack)?;
+    let holds = compare(rt, CmpKind::from_binary_op(op), *a, b)?;
+    if holds.as_bool() {
+        *a = b;
+        Ok(true)
+    } else {
+        *a = holds;
+        Ok(false)
+    }
+}
+
+/// Execute Op::In - membership test
+#[inline(always)]
+pub(crate) fn op_in(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+) -> Result<Option<Flow>, String> {
+    let coll = pop_stack(stack)?;
+    let x = *peek_last_mut(stack)?;
+    match rt.contains(coll, x) {
+        Ok(found) => *peek_last_mut(stack)? = Value::from_bool(found),
+        Err(e) => {
+            if let Some(flow) = try_throw_error(rt, ip, handlers, stack, iters, pending, thrown, e) {
+                return Ok(Some(flow));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Execute Op::InRange - membership test against a range literal
+#[inline(always)]
+pub(crate) fn op_in_range(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+    inclusive: bool,
+) -> Result<Option<Flow>, String> {
+    let end = pop_stack(stack)?;
+    let start = pop_stack(stack)?;
+    match to_i64(&start).and_then(|s| Ok((s, to_i64(&end)?))) {
+        Ok((start, end)) => {
+            let x = peek_last_mut(stack)?;
+            *x = Value::from_bool(crate::runtime::in_range(*x, start, end, inclusive));
+        }
+        Err(e) => {
+            if let Some(flow) = try_throw_error(rt, ip, handlers, stack, iters, pending, thrown, e) {
+                return Ok(Some(flow));
             }
         }
     }
@@ -1,6 +1,6 @@
-use indexmap::map::RawEntryApiV1;
 
 use crate::core::Value;
+use crate::core::dict_map::DictEntry;
 use crate::core::heap::ManagedObject;
 use crate::core::value::{DictKey, TAG_DICT, TAG_STR, ELEMENTS_MAX};
 use crate::errors::messages::{NOT_A_DICT, NOT_A_STRING};
@@ -29,15 +29,16 @@ pub(crate) fn op_dict_insert(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result
         if key_int >= 0 && key_int < ELEMENTS_MAX {
             let idx = key_int as usize;
             if let ManagedObject::Dict(d) = rt.heap_get_mut(id) {
-                // 使用辅助方法设置元素
-                let was_unit = d.set_element(idx, v);
-                if was_unit {
-                    d.ver += 1;
-                    rt.caches.dict_version_last = Some((id.0, d.ver));
+                // 使用辅助方法设置元素；不能保持插入顺序时走 map 路径
+                if let Some(was_unit) = d.try_set_element(idx, v) {
+                    if was_unit {
+                        d.ver += 1;
+                        rt.caches.dict_version_last = Some((id.0, d.ver));
+                    }
+                    stack.push(recv);
+                    return Ok(());
                 }
             }
-            stack.push(recv);
-            return Ok(());
         }
     }
 
@@ -59,17 +60,17 @@ pub(crate) fn op_dict_insert(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result
                 };
 
                 // Direct lookup with cached hash
-                match d.map.raw_entry_mut_v1().from_hash(hash, |dk| {
+                match d.map.entry_by_hash(hash, |dk| {
                     if let DictKey::StrRef { hash: h, obj_id } = dk {
                         *h == dict_key_hash && *obj_id == key_obj_id.0
                     } else {
                         false
                     }
                 }) {
-                    indexmap::map::raw_entry_v1::RawEntryMut::Occupied(mut o) => {
-                        *o.get_mut() = v;
+                    DictEntry::Occupied(slot) => {
+                        *slot = v;
                     }
-                    indexmap::map::raw_entry_v1::RawEntryMut::Vacant(vac) => {
+                    DictEntry::Vacant(vac) => {
                         let key = DictKey::from_str_obj(key_obj_id, dict_key_hash);
                         vac.insert(key, v);
                         d.ver += 1;
@@ -94,7 +95,7 @@ pub(crate) fn op_dict_insert(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result
                 return Err(NOT_A_DICT.into());
             };
             // Compute hash for HashMap lookup (uses pre-computed DictKey hash)
-            let dict_key_hash = DictKey::hash_str(key_str.as_str());
+            let dict_key_hash = DictKey::hash_str(key_str.as_str(), &rt.heap);
             let hash = compute_map_hash(d.map.hasher(), dict_key_hash);
             (hash, dict_key_hash)
         };
@@ -115,7 +116,7 @@ pub(crate) fn op_dict_insert(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result
         };
 
         // Look up by hash, comparing ObjectId or string content
-        match d.map.raw_entry_mut_v1().from_hash(hash, |dk| {
+        match d.map.entry_by_hash(hash, |dk| {
             if let DictKey::StrRef { hash: h, obj_id } = dk {
                 if *h != dict_key_hash {
                     return false;
@@ -132,10 +133,10 @@ pub(crate) fn op_dict_insert(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result
                 false
             }
         }) {
-            indexmap::map::raw_entry_v1::RawEntryMut::Occupied(mut o) => {
-                *o.get_mut() = v;
+            DictEntry::Occupied(slot) => {
+                *slot = v;
             }
-            indexmap::map::raw_entry_v1::RawEntryMut::Vacant(vac) => {
+            DictEntry::Vacant(vac) => {
                 // Create DictKey with ObjectId reference (no string copy!)
                 let key = DictKey::from_str_obj(key_obj_id, dict_key_hash);
                 vac.insert(key, v);
@@ -152,11 +153,11 @@ pub(crate) fn op_dict_insert(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result
         let key = DictKey::Int(k.as_i64());
         if let ManagedObject::Dict(d) = rt.heap_get_mut(id) {
             let h = d.map.hasher().hash_one(key);
-            match d.map.raw_entry_mut_v1().from_hash(h, |kk| kk == &key) {
-                indexmap::map::raw_entry_v1::RawEntryMut::Occupied(mut o) => {
-                    *o.get_mut() = v;
+            match d.map.entry_by_hash(h, |kk| kk == &key) {
+                DictEntry::Occupied(slot) => {
+                    *slot = v;
                 }
-                indexmap::map::raw_entry_v1::RawEntryMut::Vacant(vac) => {
+                DictEntry::Vacant(vac) => {
                     vac.insert(key, v);
                     d.ver += 1;
                     rt.caches.dict_version_last = Some((id.0, d.ver));
@@ -167,7 +168,16 @@ pub(crate) fn op_dict_insert(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result
         return Ok(());
     }
 
-    Err(rt.error(xu_syntax::DiagnosticKind::DictKeyRequired))
+    // Bool, float, tuple and struct keys
+    let key = crate::methods::get_dict_key_from_value(rt, &k)?;
+    if let ManagedObject::Dict(d) = rt.heap_get_mut(id) {
+        if d.insert_entry(key, v) {
+            d.ver += 1;
+            rt.caches.dict_version_last = Some((id.0, d.ver));
+        }
+    }
+    stack.push(recv);
+    Ok(())
 }
 
 pub(crate) fn op_dict_merge(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result<(), String> {
@@ -195,25 +205,26 @@ pub(crate) fn op_dict_merge(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result<
         return Ok(());
     }
 
-    let other_dict_map = if let ManagedObject::Dict(b) = rt.heap.get(bid) {
-        b.map.clone()
+    // Merge in the other dict's iteration order: elements, then map.
+    let other_entries: Vec<(DictKey, Value)> = if let ManagedObject::Dict(b) = rt.heap.get(bid) {
+        let mut entries: Vec<_> = b.elements().map_or(Vec::new(), |e| {
+            e.iter()
+                .enumerate()
+                .filter(|(_, v)| v.get_tag() != crate::core::value::TAG_UNIT)
+                .map(|(i, v)| (DictKey::Int(i as i64), *v))
+                .collect()
+        });
+        entries.extend(b.map.iter().map(|(k, v)| (*k, *v)));
+        entries
     } else {
         return Err(rt.error(xu_syntax::DiagnosticKind::Raw(NOT_A_DICT.into())));
     };
 
     if let ManagedObject::Dict(a) = rt.heap_get_mut(aid) {
-        a.map.reserve(other_dict_map.len());
+        a.map.reserve(other_entries.len());
         let mut changed = false;
-        for (k, v) in other_dict_map {
-            match a.map.entry(k) {
-                indexmap::map::Entry::Vacant(e) => {
-                    e.insert(v);
-                    changed = true;
-                }
-                indexmap::map::Entry::Occupied(mut e) => {
-                    *e.get_mut() = v;
-                }
-            }
+        for (k, v) in other_entries {
+            changed |= a.insert_entry(k, v);
         }
         if changed {
             a.ver += 1;
@@ -7,7 +7,7 @@
 
 use xu_ir::Bytecode;
 
-use crate::core::heap::{ManagedObject, ObjectId};
+use crate::core::heap::ManagedObject;
 use crate::core::value::{DictKey, TAG_DICT, TAG_LIST, TAG_RANGE, TAG_SPLIT_ITER};
 use crate::core::Value;
 use crate::errors::messages::{NOT_A_DICT, NOT_A_LIST};
@@ -15,8 +15,8 @@ use crate::vm::ops::helpers::pop_stack;
 use crate::vm::stack::IterState;
 use crate::Runtime;
 
-/// Dict iteration data: (raw_pairs, shape_keys, elements)
-type DictIterData = (Vec<(DictKey, Value)>, Vec<(String, Value)>, Vec<(i64, Value)>);
+/// Dict iteration data: (raw_pairs, shape_keys)
+type DictIterData = (Vec<(DictKey, Value)>, Vec<(String, Value)>);
 
 /// Set loop variable value
 #[inline(always)]
@@ -41,9 +41,11 @@ pub(crate) fn op_foreach_init(
     iters: &mut Vec<IterState>,
     ip: &mut usize,
     idx: u32,
-    var_idx: Option<usize>,
-    end: usize,
+    var_idx: Option<u32>,
+    end: u32,
 ) -> Result<bool, String> {
+    let var_idx = var_idx.map(|i| i as usize);
+    let end = end as usize;
     let iterable = pop_stack(stack)?;
     let tag = iterable.get_tag();
     let var = rt.get_const_str(idx, &bc.constants);
@@ -99,12 +101,10 @@ pub(crate) fn op_foreach_init(
         if is_kv_loop {
             // Key-value pair loop: return (key, value) tuples
             // First collect raw data to avoid borrow conflicts
-            let (raw_pairs, shape_keys, elements): DictIterData = match rt.heap.get(id) {
+            let (raw_pairs, shape_keys): DictIterData = match rt.heap.get(id) {
                 ManagedObject::Dict(d) => {
-                    let mut result = Vec::with_capacity(d.map.len());
-                    for (k, v) in d.map.iter() {
-                        result.push((*k, *v));
-                    }
+                    // Insertion order: elements, then map.
+                    let result: Vec<(DictKey, Value)> = d.entries().collect();
                     // Collect shape keys as strings
                     let shape_keys: Vec<(String, Value)> = if let Some(sid) = d.shape {
                         if let ManagedObject::Shape(shape) = rt.heap.get(sid) {
@@ -123,14 +123,7 @@ pub(crate) fn op_foreach_init(
                     } else {
                         Vec::new()
                     };
-                    // Collect elements
-                    let elements: Vec<(i64, Value)> = d.elements().map_or(Vec::new(), |e| {
-                        e.iter().enumerate()
-                            .filter(|(_, v)| v.get_tag() != crate::core::value::TAG_UNIT)
-                            .map(|(i, v)| (i as i64, *v))
-                            .collect()
-                    });
-                    (result, shape_keys, elements)
+                    (result, shape_keys)
                 }
                 _ => {
                     return Err(rt.error(xu_syntax::DiagnosticKind::Raw(NOT_A_DICT.into())));
@@ -143,9 +136,6 @@ pub(crate) fn op_foreach_init(
                 let key = DictKey::from_str_alloc(&k, &mut rt.heap);
                 all_pairs.push((key, v));
             }
-            for (i, v) in elements {
-                all_pairs.push((DictKey::Int(i), v));
-            }
 
             if all_pairs.is_empty() {
                 *ip = end;
@@ -155,13 +145,7 @@ pub(crate) fn op_foreach_init(
             let items: Vec<Value> = all_pairs
                 .into_iter()
                 .map(|(k, v)| {
-                    let key_val = match k {
-                        DictKey::StrRef { obj_id, .. } => {
-                            // Directly use the ObjectId - no string copy!
-                            Value::str(ObjectId(obj_id))
-                        }
-                        DictKey::Int(i) => Value::from_i64(i),
-                    };
+                    let key_val = k.to_script_value(&mut rt.heap);
                     Value::tuple(rt.alloc(ManagedObject::Tuple(vec![key_val, v])))
                 })
                 .collect();
@@ -171,7 +155,7 @@ pub(crate) fn op_foreach_init(
         } else {
             // Normal dict loop: only return keys
             let raw_keys: Vec<DictKey> = match rt.heap.get(id) {
-                ManagedObject::Dict(d) => d.map.keys().copied().collect(),
+                ManagedObject::Dict(d) => d.entries().map(|(k, _)| k).collect(),
                 _ => {
                     return Err(rt.error(xu_syntax::DiagnosticKind::Raw(NOT_A_DICT.into())));
                 }
@@ -182,13 +166,7 @@ pub(crate) fn op_foreach_init(
             }
             let keys: Vec<Value> = raw_keys
                 .into_iter()
-                .map(|k| match k {
-                    DictKey::StrRef { obj_id, .. } => {
-                        // Directly use the ObjectId - no string copy!
-                        Value::str(ObjectId(obj_id))
-                    }
-                    DictKey::Int(i) => Value::from_i64(i),
-                })
+                .map(|k| k.to_script_value(&mut rt.heap))
                 .collect();
             let first = keys[0];
             iters.push(IterState::Dict { keys, idx: 1 });
@@ -251,10 +229,13 @@ pub(crate) fn op_foreach_next(
     iters: &mut Vec<IterState>,
     ip: &mut usize,
     idx: u32,
-    var_idx: Option<usize>,
-    loop_start: usize,
-    end: usize,
+    var_idx: Option<u32>,
+    loop_start: u32,
+    end: u32,
 ) -> Result<bool, String> {
+    let var_idx = var_idx.map(|i| i as usize);
+    let loop_start = loop_start as usize;
+    let end = end as usize;
     let Some(state) = iters.last_mut() else {
         return Err(rt.error(xu_syntax::DiagnosticKind::Raw("Iterator underflow".into())));
     };
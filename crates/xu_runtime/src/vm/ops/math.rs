@@ -6,9 +6,13 @@
 //! - Mul: Multiplication
 //! - Div: Division
 //! - Mod: Modulo
+//! - Pow: Exponentiation
+//! - BitAnd / BitOr / BitXor: Bitwise operations on ints
+//! - Shl / Shr: Shifts on ints
 //! - And: Logical AND
 //! - Or: Logical OR
 //! - Not: Logical NOT
+//! - BitNot: Bitwise NOT
 
 use crate::core::heap::ManagedObject;
 use crate::core::Value;
@@ -116,6 +120,90 @@ pub(crate) fn op_mod(
     exec_binary_op(rt, stack, ip, handlers, iters, pending, thrown, BinaryOp::Mod)
 }
 
+/// Execute Op::Pow - power
+#[inline(always)]
+pub(crate) fn op_pow(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+) -> Result<Option<Flow>, String> {
+    exec_binary_op(rt, stack, ip, handlers, iters, pending, thrown, BinaryOp::Pow)
+}
+
+/// Execute Op::BitAnd - bitwise AND
+#[inline(always)]
+pub(crate) fn op_bit_and(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+) -> Result<Option<Flow>, String> {
+    exec_binary_op(rt, stack, ip, handlers, iters, pending, thrown, BinaryOp::BitAnd)
+}
+
+/// Execute Op::BitOr - bitwise OR
+#[inline(always)]
+pub(crate) fn op_bit_or(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+) -> Result<Option<Flow>, String> {
+    exec_binary_op(rt, stack, ip, handlers, iters, pending, thrown, BinaryOp::BitOr)
+}
+
+/// Execute Op::BitXor - bitwise XOR
+#[inline(always)]
+pub(crate) fn op_bit_xor(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+) -> Result<Option<Flow>, String> {
+    exec_binary_op(rt, stack, ip, handlers, iters, pending, thrown, BinaryOp::BitXor)
+}
+
+/// Execute Op::Shl - left shift
+#[inline(always)]
+pub(crate) fn op_shl(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+) -> Result<Option<Flow>, String> {
+    exec_binary_op(rt, stack, ip, handlers, iters, pending, thrown, BinaryOp::Shl)
+}
+
+/// Execute Op::Shr - arithmetic right shift
+#[inline(always)]
+pub(crate) fn op_shr(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+) -> Result<Option<Flow>, String> {
+    exec_binary_op(rt, stack, ip, handlers, iters, pending, thrown, BinaryOp::Shr)
+}
+
 /// Execute Op::And - logical AND
 #[inline(always)]
 pub(crate) fn op_and(
@@ -171,3 +259,30 @@ pub(crate) fn op_not(
     Ok(None)
 }
 
+/// Execute Op::BitNot - bitwise NOT
+#[inline(always)]
+pub(crate) fn op_bit_not(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+) -> Result<Option<Flow>, String> {
+    let v = pop_stack(stack)?;
+    if v.is_int() {
+        stack.push(Value::from_i64(!v.as_i64()));
+        return Ok(None);
+    }
+    let err_msg = rt.error(xu_syntax::DiagnosticKind::InvalidUnaryOperand {
+        op: '~',
+        expected: "int".to_string(),
+    });
+    let err_val = Value::str(rt.alloc(ManagedObject::Str(err_msg.into())));
+    if let Some(flow) = throw_value(rt, ip, handlers, stack, iters, pending, thrown, err_val) {
+        return Ok(Some(flow));
+    }
+    Ok(None)
+}
+
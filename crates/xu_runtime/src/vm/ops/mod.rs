@@ -11,3 +11,4 @@ pub(crate) mod iter;
 pub(crate) mod math;
 pub(crate) mod string;
 pub(crate) mod types;
+pub(crate) mod vars;
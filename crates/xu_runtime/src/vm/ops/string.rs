@@ -5,7 +5,6 @@
 //! - Builder*: String builder operations
 
 use crate::core::heap::ManagedObject;
-use crate::core::text::Text;
 use crate::core::value::{TAG_BUILDER, TAG_STR};
 use crate::core::Value;
 use crate::errors::messages::NOT_A_STRING;
@@ -55,21 +54,9 @@ pub(crate) fn op_str_append(
 ) -> Result<Option<Flow>, String> {
     let (a, b) = pop2_stack(stack)?;
     if a.get_tag() == TAG_STR {
-        // Fast path: both operands are strings - use concat2
+        // Fast path: both operands are strings
         if b.get_tag() == TAG_STR {
-            let result = {
-                let sa = if let ManagedObject::Str(s) = rt.heap.get(a.as_obj_id()) {
-                    s
-                } else {
-                    return Err(NOT_A_STRING.into());
-                };
-                let sb = if let ManagedObject::Str(s) = rt.heap.get(b.as_obj_id()) {
-                    s
-                } else {
-                    return Err(NOT_A_STRING.into());
-                };
-                Text::concat2(sa, sb)
-            };
+            let result = rt.heap.concat_texts(a.as_obj_id(), b.as_obj_id()).ok_or(NOT_A_STRING)?;
             stack.push(Value::str(rt.alloc(ManagedObject::Str(result))));
         } else {
             // Slow path: need to convert b to string
@@ -89,7 +76,8 @@ pub(crate) fn op_str_append(
 
 /// Execute Op::BuilderNewCap - create a new string builder with capacity
 #[inline(always)]
-pub(crate) fn op_builder_new_cap(rt: &mut Runtime, stack: &mut Vec<Value>, cap: usize) {
+pub(crate) fn op_builder_new_cap(rt: &mut Runtime, stack: &mut Vec<Value>, cap: u32) {
+    let cap = cap as usize;
     let s = rt.builder_pool_get(cap);
     let id = rt.alloc(ManagedObject::Builder(s));
     stack.push(Value::builder(id));
@@ -137,19 +125,9 @@ pub(crate) fn op_builder_append(
             sb.push_str(s_ref);
         }
     } else if v.is_f64() {
-        let f = v.as_f64();
-        if f.fract() == 0.0 {
-            let mut buf = itoa::Buffer::new();
-            let digits = buf.format(f as i64);
-            if let ManagedObject::Builder(s) = rt.heap_get_mut(id) {
-                s.push_str(digits);
-            }
-        } else {
-            let mut buf = ryu::Buffer::new();
-            let digits = buf.format(f);
-            if let ManagedObject::Builder(s) = rt.heap_get_mut(id) {
-                s.push_str(digits);
-            }
+        let format = rt.heap.float_format;
+        if let ManagedObject::Builder(s) = rt.heap_get_mut(id) {
+            crate::util::write_f64(s, v.as_f64(), &format);
         }
     } else if v.is_bool() {
         let piece = if v.as_bool() { "true" } else { "false" };
@@ -182,17 +160,13 @@ pub(crate) fn op_builder_finalize(rt: &mut Runtime, stack: &mut Vec<Value>) -> R
     }
     let id = b.as_obj_id();
     // Take ownership of the builder string and return it to pool
-    let (out, builder_str) = if let ManagedObject::Builder(s) = rt.heap_get_mut(id) {
-        let text = crate::core::text::Text::from_str(s.as_str());
-        let taken = std::mem::take(s);
-        (text, Some(taken))
+    let builder_str = if let ManagedObject::Builder(s) = rt.heap_get_mut(id) {
+        std::mem::take(s)
     } else {
         return Err("Not a builder".into());
     };
-    // Return the string to the pool for reuse
-    if let Some(s) = builder_str {
-        rt.builder_pool_return(s);
-    }
+    let out = rt.heap.take_text(&builder_str);
+    rt.builder_pool_return(builder_str);
     let sid = rt.alloc(ManagedObject::Str(out));
     stack.push(Value::str(sid));
     Ok(())
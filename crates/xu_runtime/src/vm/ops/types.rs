@@ -7,6 +7,9 @@
 //! - EnumCtor: Create an enum variant (no payload)
 //! - EnumCtorN: Create an enum variant (with payload)
 //! - AssertType: Assert value matches expected type
+//! - MatchPattern / MatchBindings: Test a value against a pattern and push its bindings
+//! - Switch: Jump to the arm of a literal `match` through its table
+//! - OptLink / Coalesce: Look through `Option` for `?.`, `?[` and `??`
 
 use xu_ir::Bytecode;
 
@@ -61,8 +64,9 @@ fn throw_unknown_struct(
 pub(crate) fn op_define_struct(rt: &mut Runtime, bc: &Bytecode, idx: u32) {
     let c = rt.get_constant(idx, &bc.constants);
     if let xu_ir::Constant::Struct(def) = c {
-        let layout: std::rc::Rc<[String]> = def.fields.iter().map(|f| f.name.clone()).collect();
-        rt.types.struct_layouts.insert(def.name.clone(), layout);
+        if rt.types.define_struct_layout(&def.name, &def.fields) {
+            rt.caches.invalidate_code_caches();
+        }
         rt.types.structs.insert(def.name.clone(), def.clone());
     }
 }
@@ -129,6 +133,12 @@ pub(crate) fn op_struct_init(
         }
     }
 
+    if let Err(e) = rt.check_struct_fields(&ty, &values) {
+        if let Some(flow) = try_throw_error(rt, ip, handlers, stack, iters, pending, thrown, e) {
+            return Ok(Some(flow));
+        }
+        return Ok(None);
+    }
     create_struct_instance(rt, stack, &ty, &layout, values);
     Ok(None)
 }
@@ -208,7 +218,7 @@ pub(crate) fn op_struct_init_spread(
                 }
                 // Check map
                 let hash = crate::Runtime::hash_bytes(db.map.hasher(), fname.as_bytes());
-                if let Some(v) = crate::Runtime::dict_get_by_str_with_hash(db, fname.as_str(), hash) {
+                if let Some(v) = crate::Runtime::dict_get_by_str_with_hash(&rt.heap, db, fname.as_str(), hash) {
                     values[pos] = v;
                 }
             }
@@ -222,6 +232,12 @@ pub(crate) fn op_struct_init_spread(
         }
     }
 
+    if let Err(e) = rt.check_struct_fields(&ty, &values) {
+        if let Some(flow) = try_throw_error(rt, ip, handlers, stack, iters, pending, thrown, e) {
+            return Ok(Some(flow));
+        }
+        return Ok(None);
+    }
     create_struct_instance(rt, stack, &ty, &layout, values);
     Ok(None)
 }
@@ -250,8 +266,9 @@ pub(crate) fn op_enum_ctor_n(
     stack: &mut Vec<Value>,
     t_idx: u32,
     v_idx: u32,
-    argc: usize,
+    argc: u32,
 ) -> Result<(), String> {
+    let argc = argc as usize;
     let ty = rt.get_const_str(t_idx, &bc.constants);
     let variant = rt.get_const_str(v_idx, &bc.constants);
     let mut payload: Vec<Value> = Vec::with_capacity(argc);
@@ -293,3 +310,85 @@ pub(crate) fn op_assert_type(
     }
     Ok(None)
 }
+
+/// Execute Op::MatchPattern - push whether the top of stack matches the pattern
+#[inline(always)]
+pub(crate) fn op_match_pattern(rt: &mut Runtime, bc: &Bytecode, stack: &mut Vec<Value>, pat_idx: u32) -> Result<(), String> {
+    let v = *peek_last(stack)?;
+    let c = rt.get_constant(pat_idx, &bc.constants);
+    if let xu_ir::Constant::Pattern(pat) = c {
+        let matched = crate::util::match_pattern(rt, pat, &v).is_some();
+        stack.push(Value::from_bool(matched));
+        Ok(())
+    } else {
+        Err("Expected pattern constant".into())
+    }
+}
+
+/// Execute Op::Switch - the target of the arm matching the top of stack
+#[inline(always)]
+pub(crate) fn op_switch(rt: &Runtime, bc: &Bytecode, stack: &[Value], table_idx: u32) -> Result<usize, String> {
+    let v = *peek_last(stack)?;
+    let xu_ir::Constant::Switch(table) = rt.get_constant(table_idx, &bc.constants) else {
+        return Err("Expected switch constant".into());
+    };
+    let to = if v.is_int() {
+        table.int_target(v.as_i64())
+    } else if v.get_tag() == crate::core::value::TAG_STR {
+        match rt.heap.get(v.as_obj_id()) {
+            ManagedObject::Str(s) => table.str_target(s.as_str()),
+            _ => table.default,
+        }
+    } else {
+        table.default
+    };
+    Ok(to as usize)
+}
+
+/// Execute Op::MatchBindings - pop a value and push the pattern's bindings
+#[inline(always)]
+pub(crate) fn op_match_bindings(rt: &mut Runtime, bc: &Bytecode, stack: &mut Vec<Value>, pat_idx: u32) -> Result<(), String> {
+    let v = pop_stack(stack)?;
+    let c = rt.get_constant(pat_idx, &bc.constants);
+    if let xu_ir::Constant::Pattern(pat) = c {
+        if let Some(bindings) = crate::util::match_pattern(rt, pat, &v) {
+            for (_, val) in bindings {
+                stack.push(val);
+            }
+        }
+        Ok(())
+    } else {
+        Err("Expected pattern constant".into())
+    }
+}
+
+/// Execute Op::OptLink - unwrap some, or replace none with none and report
+/// that the chain ends here
+#[inline]
+pub(crate) fn op_opt_link(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result<bool, String> {
+    let v = pop_stack(stack)?;
+    match rt.present_value(v) {
+        Some(inner) => {
+            stack.push(inner);
+            Ok(false)
+        }
+        None => {
+            stack.push(rt.option_none());
+            Ok(true)
+        }
+    }
+}
+
+/// Execute Op::Coalesce - unwrap some and report that the right operand is
+/// skipped; pop none
+#[inline]
+pub(crate) fn op_coalesce(rt: &mut Runtime, stack: &mut Vec<Value>) -> Result<bool, String> {
+    let v = pop_stack(stack)?;
+    match rt.present_value(v) {
+        Some(inner) => {
+            stack.push(inner);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,131 @@
+//! Constant and variable operations for the VM.
+//!
+//! This module contains operations for:
+//! - Const: Push a constant pool entry
+//! - LoadLocal / StoreLocal: Access locals by slot index
+//! - LoadName / StoreName: Access variables by name
+//! - Use: Import a module under an alias
+
+use xu_ir::{Bytecode, Op};
+
+use crate::core::heap::ManagedObject;
+use crate::core::Value;
+use crate::vm::exception::throw_value;
+use crate::vm::stack::{stack_underflow, Handler, IterState, Pending};
+use crate::{Flow, Runtime};
+
+/// Execute Op::Const - push a string or number constant
+#[inline(always)]
+pub(crate) fn op_const(rt: &mut Runtime, bc: &Bytecode, stack: &mut Vec<Value>, idx: u32) -> Result<(), String> {
+    let c = rt.get_constant(idx, &bc.constants);
+    match c {
+        xu_ir::Constant::Str(s) => {
+            let bc_ptr = bc as *const Bytecode as usize;
+            stack.push(rt.get_string_const(bc_ptr, idx, s));
+        }
+        xu_ir::Constant::Int(i) => stack.push(Value::from_i64(*i)),
+        xu_ir::Constant::Float(f) => stack.push(Value::from_f64(*f)),
+        _ => return Err("Unexpected constant type in VM loop".into()),
+    }
+    Ok(())
+}
+
+/// Execute Op::LoadLocal - push a local by slot index
+#[inline(always)]
+pub(crate) fn op_load_local(rt: &mut Runtime, stack: &mut Vec<Value>, idx: u32) -> Result<(), String> {
+    let idx = idx as usize;
+    let Some(val) = rt.get_local_by_index(idx) else {
+        return Err(format!("Undefined local variable index: {}", idx));
+    };
+    stack.push(val);
+    Ok(())
+}
+
+/// Execute Op::StoreLocal - pop into a local slot, growing the frame if needed
+#[inline(always)]
+pub(crate) fn op_store_local(
+    rt: &mut Runtime,
+    stack: &mut Vec<Value>,
+    ip: usize,
+    op: &Op,
+    idx: u32,
+) -> Result<(), String> {
+    let idx = idx as usize;
+    let val = stack.pop().ok_or_else(|| stack_underflow(ip, op))?;
+    if !rt.set_local_by_index(idx, val) {
+        while rt.get_local_by_index(idx).is_none() {
+            rt.define_local(format!("_tmp_{}", idx), Value::UNIT);
+        }
+        rt.set_local_by_index(idx, val);
+    }
+    Ok(())
+}
+
+/// Execute Op::LoadName - push a local or global by name
+#[inline(always)]
+pub(crate) fn op_load_name(rt: &mut Runtime, bc: &Bytecode, stack: &mut Vec<Value>, idx: u32) -> Result<(), String> {
+    let name = rt.get_const_str(idx, &bc.constants);
+    let v = if rt.locals.is_active() {
+        if let Some(v) = rt.get_local(name) {
+            v
+        } else {
+            rt.env.get_cached(name).ok_or_else(|| {
+                rt.error(xu_syntax::DiagnosticKind::UndefinedIdentifier(name.to_string()))
+            })?
+        }
+    } else {
+        rt.env.get_cached(name).ok_or_else(|| {
+            rt.error(xu_syntax::DiagnosticKind::UndefinedIdentifier(name.to_string()))
+        })?
+    };
+    stack.push(v);
+    Ok(())
+}
+
+/// Execute Op::StoreName - pop into a variable, defining it if it does not exist
+#[inline(always)]
+pub(crate) fn op_store_name(
+    rt: &mut Runtime,
+    bc: &Bytecode,
+    stack: &mut Vec<Value>,
+    ip: usize,
+    op: &Op,
+    idx: u32,
+) -> Result<(), String> {
+    let name = rt.get_const_str(idx, &bc.constants);
+    let v = stack.pop().ok_or_else(|| stack_underflow(ip, op))?;
+    if rt.locals.is_active() {
+        if !rt.set_local(name, v) && !rt.env.assign(name, v) {
+            rt.define_local(name.to_string(), v);
+        }
+    } else if !rt.env.assign(name, v) {
+        rt.env.define(name.to_string(), v);
+    }
+    Ok(())
+}
+
+/// Execute Op::Use - import a module; a failed import is thrown
+#[inline(always)]
+pub(crate) fn op_use(
+    rt: &mut Runtime,
+    bc: &Bytecode,
+    stack: &mut Vec<Value>,
+    ip: &mut usize,
+    handlers: &mut Vec<Handler>,
+    iters: &mut Vec<IterState>,
+    pending: &mut Option<Pending>,
+    thrown: &mut Option<Value>,
+    path_idx: u32,
+    alias_idx: u32,
+) -> Result<Option<Flow>, String> {
+    let path = rt.get_const_str(path_idx, &bc.constants);
+    let alias = rt.get_const_str(alias_idx, &bc.constants);
+    match crate::modules::import_path(rt, path) {
+        Ok(module_obj) => rt.env.define(alias.to_string(), module_obj),
+        Err(e) => {
+            let err_val = Value::str(rt.alloc(ManagedObject::Str(e.into())));
+            return Ok(throw_value(rt, ip, handlers, stack, iters, pending, thrown, err_val));
+        }
+    }
+    Ok(None)
+}
@@ -98,22 +98,19 @@ pub(crate) fn add_with_heap(rt: &mut Runtime, a: Value, b: Value) -> Result<Valu
     }
 
     if at == TAG_STR && bt == TAG_STR {
-        // Fast path: both are strings - use Text::concat2
-        let result = {
-            let sa = if let ManagedObject::Str(s) = rt.heap.get(a.as_obj_id()) {
-                s
-            } else {
-                return Err(NOT_A_STRING.into());
-            };
-            let sb = if let ManagedObject::Str(s) = rt.heap.get(b.as_obj_id()) {
-                s
-            } else {
-                return Err(NOT_A_STRING.into());
-            };
-            crate::core::text::Text::concat2(sa, sb)
+        // Fast path: both are strings
+        let result = rt.heap.concat_texts(a.as_obj_id(), b.as_obj_id()).ok_or(NOT_A_STRING)?;
+        Ok(Value::str(rt.alloc(ManagedObject::Str(result))))
+    } else if at == TAG_STR {
+        // Slow path: append b's text; long results accumulate as ropes
+        let mut result = if let ManagedObject::Str(s) = rt.heap.get(a.as_obj_id()) {
+            s.clone()
+        } else {
+            return Err(NOT_A_STRING.into());
         };
+        result.append_value(&b, &rt.heap);
         Ok(Value::str(rt.alloc(ManagedObject::Str(result))))
-    } else if at == TAG_STR || bt == TAG_STR {
+    } else if bt == TAG_STR {
         // Slow path: one is string, one is not
         // Pre-calculate lengths to avoid reallocations
         let a_len = if at == TAG_STR {
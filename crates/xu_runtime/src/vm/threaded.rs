@@ -0,0 +1,713 @@
+//! Table-driven dispatch for the bytecode interpreter.
+//!
+//! Enabled with the `threaded-dispatch` feature. Each opcode has a handler
+//! function and the loop calls through [`HANDLERS`], indexed by
+//! [`Op::opcode`], instead of branching through one large `match`. The
+//! handlers share their implementations with the `match` loop in
+//! `dispatch.rs` through the `ops` modules, so both dispatchers execute the
+//! same semantics and `xtask perf dispatch` can compare them directly.
+
+use xu_ir::{Bytecode, Op};
+
+use crate::core::Value;
+use crate::core::heap::ManagedObject;
+
+use crate::util::value_to_string;
+use crate::{Flow, Runtime};
+use super::exception::throw_value;
+use super::ops::helpers::try_throw_error;
+
+use super::ops::dict as dict_ops;
+use super::ops::{access, assign, call, collection, compare, iter, math, string, types, vars};
+use super::stack::{stack_underflow, Handler, IterState, Pending};
+
+/// Interpreter state shared by all handlers of one `run_bytecode` call.
+struct Frame<'s> {
+    stack: &'s mut Vec<Value>,
+    iters: &'s mut Vec<IterState>,
+    handlers: &'s mut Vec<Handler>,
+    pending: Option<Pending>,
+    thrown: Option<Value>,
+    ip: usize,
+}
+
+/// What the loop does after a handler returns.
+enum Step {
+    /// Continue with the next instruction.
+    Next,
+    /// `ip` was set by the handler.
+    Goto,
+    /// Leave the function with this flow.
+    Exit(Flow),
+}
+
+type OpHandler = fn(&mut Runtime, &Bytecode, &Op, &mut Frame<'_>) -> Result<Step, String>;
+
+/// Handlers in [`Op::opcode`] order.
+static HANDLERS: [OpHandler; Op::COUNT] = [
+    const_int, // ConstInt
+    const_float, // ConstFloat
+    const_bool, // ConstBool
+    const_null, // ConstNull
+    const_empty_str, // ConstEmptyStr
+    constant, // Const
+    pop, // Pop
+    dup, // Dup
+    add, // Add
+    add_assign_name, // AddAssignName
+    add_assign_local, // AddAssignLocal
+    sub, // Sub
+    mul, // Mul
+    div, // Div
+    modulo, // Mod
+    str_append, // StrAppend
+    eq, // Eq
+    ne, // Ne
+    and, // And
+    or, // Or
+    gt, // Gt
+    lt, // Lt
+    ge, // Ge
+    le, // Le
+    not, // Not
+    jump, // Jump
+    jump_if_false, // JumpIfFalse
+    jump_if_true, // JumpIfTrue
+    load_name, // LoadName
+    load_local, // LoadLocal
+    store_name, // StoreName
+    store_local, // StoreLocal
+    use_module, // Use
+    assert_type, // AssertType
+    define_struct, // DefineStruct
+    define_enum, // DefineEnum
+    struct_init, // StructInit
+    struct_init_spread, // StructInitSpread
+    enum_ctor, // EnumCtor
+    enum_ctor_n, // EnumCtorN
+    make_function, // MakeFunction
+    call, // Call
+    call_method, // CallMethod
+    call_static_or_method, // CallStaticOrMethod
+    inc_local, // IncLocal
+    make_range, // MakeRange
+    get_member, // GetMember
+    get_index, // GetIndex
+    assign_member, // AssignMember
+    get_member_at, // GetMemberAt
+    assign_member_at, // AssignMemberAt
+    assign_index, // AssignIndex
+    break_or_continue, // Break
+    break_or_continue, // Continue
+    ret, // Return
+    run_pending, // RunPending
+    builder_new_cap, // BuilderNewCap
+    builder_append, // BuilderAppend
+    builder_finalize, // BuilderFinalize
+    for_each_init, // ForEachInit
+    for_each_next, // ForEachNext
+    iter_pop, // IterPop
+    env_push, // EnvPush
+    env_pop, // EnvPop
+    list_new, // ListNew
+    tuple_new, // TupleNew
+    dict_new, // DictNew
+    dict_insert, // DictInsert
+    dict_merge, // DictMerge
+    list_append, // ListAppend
+    list_reserve_for, // ListReserveFor
+    print, // Print
+    halt, // Halt
+    match_pattern, // MatchPattern
+    match_bindings, // MatchBindings
+    locals_push, // LocalsPush
+    locals_pop, // LocalsPop
+    get_static_field, // GetStaticField
+    set_static_field, // SetStaticField
+    init_static_field, // InitStaticField
+    try_op, // Try
+    opt_link, // OptLink
+    coalesce, // Coalesce
+    pow, // Pow
+    bit_and, // BitAnd
+    bit_or, // BitOr
+    bit_xor, // BitXor
+    shl, // Shl
+    shr, // Shr
+    bit_not, // BitNot
+    cmp_link, // CmpLink
+    in_op, // In
+    in_range, // InRange
+    switch, // Switch
+];
+
+pub(super) fn run_threaded(
+    rt: &mut Runtime,
+    bc: &Bytecode,
+    stack: &mut Vec<Value>,
+    iters: &mut Vec<IterState>,
+    handlers: &mut Vec<Handler>,
+    ip: &mut usize,
+) -> Result<Flow, String> {
+    let mut f = Frame { stack, iters, handlers, pending: None, thrown: None, ip: 0 };
+    let res = run_frame(rt, bc, &mut f);
+    *ip = f.ip;
+    res
+}
+
+fn run_frame(rt: &mut Runtime, bc: &Bytecode, f: &mut Frame<'_>) -> Result<Flow, String> {
+    let ops = &bc.ops;
+    let ops_len = ops.len();
+    let mut stmt_count: usize = 0;
+
+    while f.ip < ops_len {
+        // SAFETY: ip is always < ops_len due to the loop condition above,
+        // so this index is always in bounds.
+        let op = unsafe { ops.get_unchecked(f.ip) };
+        stmt_count = stmt_count.wrapping_add(1);
+        // Check GC, interrupts and signals every 1024 instructions
+        if stmt_count & 1023 == 0 {
+            rt.maybe_gc_with_roots(f.stack);
+            rt.check_interrupt()?;
+            rt.check_signals()?;
+        }
+        match HANDLERS[op.opcode() as usize](rt, bc, op, f)? {
+            Step::Next => f.ip += 1,
+            Step::Goto => {}
+            Step::Exit(flow) => return Ok(flow),
+        }
+    }
+    Ok(Flow::None)
+}
+
+/// Binds the operands of `op`, which the table guarantees to be of the
+/// handler's variant.
+macro_rules! operands {
+    ($op:ident, $pat:pat) => {
+        let $pat = $op else {
+            unreachable!("opcode table out of order at {:?}", $op)
+        };
+    };
+}
+
+/// The step after an operation that may have thrown: `Some` means the
+/// exception left this function.
+#[inline(always)]
+fn step(flow: Option<Flow>) -> Result<Step, String> {
+    Ok(match flow {
+        Some(flow) => Step::Exit(flow),
+        None => Step::Next,
+    })
+}
+
+fn const_int(_rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::ConstInt(i));
+    f.stack.push(Value::from_i64(*i));
+    Ok(Step::Next)
+}
+
+fn const_float(_rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::ConstFloat(x));
+    f.stack.push(Value::from_f64(*x));
+    Ok(Step::Next)
+}
+
+fn const_bool(_rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::ConstBool(b));
+    f.stack.push(Value::from_bool(*b));
+    Ok(Step::Next)
+}
+
+fn const_null(_rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    f.stack.push(Value::UNIT);
+    Ok(Step::Next)
+}
+
+fn const_empty_str(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    f.stack.push(rt.empty_str());
+    Ok(Step::Next)
+}
+
+fn constant(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::Const(idx));
+    vars::op_const(rt, bc, f.stack, *idx)?;
+    Ok(Step::Next)
+}
+
+fn pop(_rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    f.stack.pop().ok_or_else(|| stack_underflow(f.ip, op))?;
+    Ok(Step::Next)
+}
+
+fn dup(_rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    let v = f.stack.last().cloned().ok_or_else(|| "Stack underflow".to_string())?;
+    f.stack.push(v);
+    Ok(Step::Next)
+}
+
+fn add(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_add(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn add_assign_name(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::AddAssignName(idx));
+    step(assign::op_add_assign_name(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *idx)?)
+}
+
+fn add_assign_local(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::AddAssignLocal(idx));
+    step(assign::op_add_assign_local(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *idx)?)
+}
+
+fn sub(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_sub(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn mul(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_mul(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn div(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_div(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn modulo(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_mod(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn pow(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_pow(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn bit_and(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_bit_and(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn bit_or(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_bit_or(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn bit_xor(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_bit_xor(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn shl(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_shl(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn shr(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_shr(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn str_append(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(string::op_str_append(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn eq(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    compare::op_eq(rt, f.stack)?;
+    Ok(Step::Next)
+}
+
+fn ne(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    compare::op_ne(rt, f.stack)?;
+    Ok(Step::Next)
+}
+
+fn and(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_and(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn or(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_or(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn gt(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(compare::op_gt(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn lt(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(compare::op_lt(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn ge(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(compare::op_ge(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn le(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(compare::op_le(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn cmp_link(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::CmpLink(cmp, to));
+    match compare::op_cmp_link(rt, f.stack, *cmp) {
+        Ok(true) => Ok(Step::Next),
+        Ok(false) => {
+            f.ip = *to as usize;
+            Ok(Step::Goto)
+        }
+        Err(e) => match try_throw_error(rt, &mut f.ip, f.handlers, f.stack, f.iters, &mut f.pending, &mut f.thrown, e) {
+            Some(flow) => Ok(Step::Exit(flow)),
+            None => Ok(Step::Goto),
+        },
+    }
+}
+
+fn in_op(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(compare::op_in(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn in_range(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::InRange(inclusive));
+    step(compare::op_in_range(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *inclusive)?)
+}
+
+fn not(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_not(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn bit_not(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    step(math::op_bit_not(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown)?)
+}
+
+fn jump(_rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::Jump(to));
+    f.ip = *to as usize;
+    Ok(Step::Goto)
+}
+
+fn jump_if_false(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::JumpIfFalse(to));
+    let v = f.stack.pop().ok_or_else(|| "Stack underflow".to_string())?;
+    if v.is_bool() {
+        if !v.as_bool() {
+            f.ip = *to as usize;
+            return Ok(Step::Goto);
+        }
+        return Ok(Step::Next);
+    }
+    let msg = rt.error(xu_syntax::DiagnosticKind::InvalidConditionType(v.type_name().to_string()));
+    let err_val = Value::str(rt.alloc(ManagedObject::Str(msg.into())));
+    match throw_value(rt, &mut f.ip, f.handlers, f.stack, f.iters, &mut f.pending, &mut f.thrown, err_val) {
+        Some(flow) => Ok(Step::Exit(flow)),
+        None => Ok(Step::Goto),
+    }
+}
+
+fn jump_if_true(_rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::JumpIfTrue(to));
+    let v = f.stack.pop().ok_or_else(|| "Stack underflow".to_string())?;
+    if v.is_bool() && v.as_bool() {
+        f.ip = *to as usize;
+        return Ok(Step::Goto);
+    }
+    Ok(Step::Next)
+}
+
+fn load_name(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::LoadName(idx));
+    vars::op_load_name(rt, bc, f.stack, *idx)?;
+    Ok(Step::Next)
+}
+
+fn load_local(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::LoadLocal(idx));
+    vars::op_load_local(rt, f.stack, *idx)?;
+    Ok(Step::Next)
+}
+
+fn store_name(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::StoreName(idx));
+    vars::op_store_name(rt, bc, f.stack, f.ip, op, *idx)?;
+    Ok(Step::Next)
+}
+
+fn store_local(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::StoreLocal(idx));
+    vars::op_store_local(rt, f.stack, f.ip, op, *idx)?;
+    Ok(Step::Next)
+}
+
+fn use_module(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::Use(path_idx, alias_idx));
+    step(vars::op_use(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *path_idx, *alias_idx)?)
+}
+
+fn assert_type(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::AssertType(idx));
+    step(types::op_assert_type(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *idx)?)
+}
+
+fn define_struct(rt: &mut Runtime, bc: &Bytecode, op: &Op, _f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::DefineStruct(idx));
+    types::op_define_struct(rt, bc, *idx);
+    Ok(Step::Next)
+}
+
+fn define_enum(rt: &mut Runtime, bc: &Bytecode, op: &Op, _f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::DefineEnum(idx));
+    types::op_define_enum(rt, bc, *idx);
+    Ok(Step::Next)
+}
+
+fn struct_init(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::StructInit(t_idx, n_idx));
+    step(types::op_struct_init(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *t_idx, *n_idx)?)
+}
+
+fn struct_init_spread(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::StructInitSpread(t_idx, n_idx));
+    step(types::op_struct_init_spread(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *t_idx, *n_idx)?)
+}
+
+fn enum_ctor(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::EnumCtor(t_idx, v_idx));
+    types::op_enum_ctor(rt, bc, f.stack, *t_idx, *v_idx)?;
+    Ok(Step::Next)
+}
+
+fn enum_ctor_n(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::EnumCtorN(t_idx, v_idx, argc));
+    types::op_enum_ctor_n(rt, bc, f.stack, *t_idx, *v_idx, *argc)?;
+    Ok(Step::Next)
+}
+
+fn make_function(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::MakeFunction(f_idx));
+    call::op_make_function(rt, bc, f.stack, *f_idx)?;
+    Ok(Step::Next)
+}
+
+fn call(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::Call(n));
+    step(call::op_call(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *n)?)
+}
+
+fn call_method(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::CallMethod(m_idx, method_hash, n, slot_idx));
+    step(call::op_call_method(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *m_idx, *method_hash, *n, *slot_idx)?)
+}
+
+fn call_static_or_method(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::CallStaticOrMethod(type_idx, m_idx, n, slot_idx));
+    step(call::op_call_static_or_method(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *type_idx, *m_idx, *n, *slot_idx)?)
+}
+
+fn inc_local(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::IncLocal(idx));
+    step(assign::op_inc_local(rt, &mut f.ip, f.handlers, f.stack, f.iters, &mut f.pending, &mut f.thrown, *idx)?)
+}
+
+fn make_range(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::MakeRange(inclusive));
+    collection::op_make_range(rt, f.stack, *inclusive)?;
+    Ok(Step::Next)
+}
+
+fn get_member(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::GetMember(idx, slot_idx));
+    step(access::op_get_member(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *idx, *slot_idx)?)
+}
+
+fn get_index(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::GetIndex(slot_cell));
+    step(access::op_get_index(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *slot_cell)?)
+}
+
+fn assign_member(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::AssignMember(idx, op_type));
+    step(access::op_assign_member(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *idx, *op_type)?)
+}
+
+fn get_member_at(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::GetMemberAt(idx, offset, slot_idx));
+    step(access::op_get_member_at(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *idx, *offset, *slot_idx)?)
+}
+
+fn assign_member_at(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::AssignMemberAt(idx, offset, op_type));
+    step(access::op_assign_member_at(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *idx, *offset, *op_type)?)
+}
+
+fn assign_index(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::AssignIndex(aop));
+    step(access::op_assign_index(rt, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *aop)?)
+}
+
+fn break_or_continue(_rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, (Op::Break(to) | Op::Continue(to)));
+    f.ip = *to as usize;
+    Ok(Step::Goto)
+}
+
+fn ret(_rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    Ok(Step::Exit(call::op_return(f.stack)?))
+}
+
+fn try_op(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    match call::op_try(rt, f.stack)? {
+        Some(flow) => Ok(Step::Exit(flow)),
+        None => Ok(Step::Next),
+    }
+}
+
+fn run_pending(_rt: &mut Runtime, _bc: &Bytecode, _op: &Op, _f: &mut Frame<'_>) -> Result<Step, String> {
+    Ok(Step::Next)
+}
+
+fn builder_new_cap(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::BuilderNewCap(cap));
+    string::op_builder_new_cap(rt, f.stack, *cap);
+    Ok(Step::Next)
+}
+
+fn builder_append(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    string::op_builder_append(rt, f.stack)?;
+    Ok(Step::Next)
+}
+
+fn builder_finalize(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    string::op_builder_finalize(rt, f.stack)?;
+    Ok(Step::Next)
+}
+
+fn for_each_init(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::ForEachInit(idx, var_idx, end));
+    if iter::op_foreach_init(rt, bc, f.stack, f.iters, &mut f.ip, *idx, *var_idx, *end)? {
+        return Ok(Step::Goto);
+    }
+    Ok(Step::Next)
+}
+
+fn for_each_next(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::ForEachNext(idx, var_idx, loop_start, end));
+    if iter::op_foreach_next(rt, bc, f.iters, &mut f.ip, *idx, *var_idx, *loop_start, *end)? {
+        return Ok(Step::Goto);
+    }
+    Ok(Step::Next)
+}
+
+fn iter_pop(_rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    iter::op_iter_pop(f.iters)?;
+    Ok(Step::Next)
+}
+
+fn env_push(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, _f: &mut Frame<'_>) -> Result<Step, String> {
+    rt.env.push();
+    Ok(Step::Next)
+}
+
+fn env_pop(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, _f: &mut Frame<'_>) -> Result<Step, String> {
+    rt.env.pop();
+    Ok(Step::Next)
+}
+
+fn list_new(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::ListNew(n));
+    collection::op_list_new(rt, f.stack, *n)?;
+    Ok(Step::Next)
+}
+
+fn tuple_new(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::TupleNew(n));
+    collection::op_tuple_new(rt, f.stack, *n)?;
+    Ok(Step::Next)
+}
+
+fn dict_new(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::DictNew(n));
+    collection::op_dict_new(rt, f.stack, *n)?;
+    Ok(Step::Next)
+}
+
+fn dict_insert(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    dict_ops::op_dict_insert(rt, f.stack)?;
+    Ok(Step::Next)
+}
+
+fn dict_merge(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    dict_ops::op_dict_merge(rt, f.stack)?;
+    Ok(Step::Next)
+}
+
+fn list_append(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::ListAppend(n));
+    collection::op_list_append(rt, f.stack, *n)?;
+    Ok(Step::Next)
+}
+
+fn list_reserve_for(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    collection::op_list_reserve_for(rt, f.stack)?;
+    Ok(Step::Next)
+}
+
+fn print(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    let v = f.stack.pop().ok_or_else(|| "Stack underflow".to_string())?;
+    rt.write_output(&value_to_string(&v, &rt.heap));
+    Ok(Step::Next)
+}
+
+fn halt(_rt: &mut Runtime, _bc: &Bytecode, _op: &Op, _f: &mut Frame<'_>) -> Result<Step, String> {
+    Ok(Step::Exit(Flow::None))
+}
+
+fn match_pattern(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::MatchPattern(pat_idx));
+    types::op_match_pattern(rt, bc, f.stack, *pat_idx)?;
+    Ok(Step::Next)
+}
+
+fn match_bindings(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::MatchBindings(pat_idx));
+    types::op_match_bindings(rt, bc, f.stack, *pat_idx)?;
+    Ok(Step::Next)
+}
+
+fn switch(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::Switch(table_idx));
+    f.ip = types::op_switch(rt, bc, f.stack, *table_idx)?;
+    Ok(Step::Goto)
+}
+
+fn opt_link(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::OptLink(to));
+    if types::op_opt_link(rt, f.stack)? {
+        f.ip = *to as usize;
+        return Ok(Step::Goto);
+    }
+    Ok(Step::Next)
+}
+
+fn coalesce(rt: &mut Runtime, _bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::Coalesce(to));
+    if types::op_coalesce(rt, f.stack)? {
+        f.ip = *to as usize;
+        return Ok(Step::Goto);
+    }
+    Ok(Step::Next)
+}
+
+fn locals_push(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, _f: &mut Frame<'_>) -> Result<Step, String> {
+    rt.push_locals();
+    Ok(Step::Next)
+}
+
+fn locals_pop(rt: &mut Runtime, _bc: &Bytecode, _op: &Op, _f: &mut Frame<'_>) -> Result<Step, String> {
+    rt.pop_locals();
+    Ok(Step::Next)
+}
+
+fn get_static_field(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::GetStaticField(type_idx, field_idx));
+    step(access::op_get_static_field(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *type_idx, *field_idx)?)
+}
+
+fn set_static_field(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::SetStaticField(type_idx, field_idx));
+    step(access::op_set_static_field(rt, bc, f.stack, &mut f.ip, f.handlers, f.iters, &mut f.pending, &mut f.thrown, *type_idx, *field_idx)?)
+}
+
+fn init_static_field(rt: &mut Runtime, bc: &Bytecode, op: &Op, f: &mut Frame<'_>) -> Result<Step, String> {
+    operands!(op, Op::InitStaticField(type_idx, field_idx));
+    access::op_init_static_field(rt, bc, f.stack, *type_idx, *field_idx)?;
+    Ok(Step::Next)
+}
@@ -0,0 +1,93 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use xu_lexer::{Lexer, normalize_source};
+use xu_parser::Parser;
+use xu_runtime::{BenchConfig, Runtime};
+
+/// Advances by one more millisecond on every read, so the n-th timed run of
+/// a block (reading the clock twice) takes `2n + 1` ms.
+#[derive(Default)]
+struct SteppingClock {
+    reads: AtomicI64,
+    now: AtomicI64,
+}
+
+impl xu_runtime::Clock for SteppingClock {
+    fn unix_secs(&self) -> i64 {
+        0
+    }
+    fn unix_millis(&self) -> i64 {
+        0
+    }
+    fn mono_micros(&self) -> i64 {
+        self.mono_nanos() / 1000
+    }
+    fn mono_nanos(&self) -> i64 {
+        let now = self.now.load(Ordering::Relaxed) + self.reads.fetch_add(1, Ordering::Relaxed) * 1_000_000;
+        self.now.store(now, Ordering::Relaxed);
+        now
+    }
+}
+
+fn parse(src: &str) -> xu_parser::Module {
+    let normalized = normalize_source(src);
+    assert!(normalized.diagnostics.is_empty());
+    let lex = Lexer::new(&normalized.text).lex();
+    assert!(lex.diagnostics.is_empty());
+    let bump = bumpalo::Bump::new();
+    let parse = Parser::new(&normalized.text, &lex.tokens, &bump).parse();
+    let errors: Vec<_> = parse
+        .diagnostics
+        .into_iter()
+        .filter(|d| matches!(d.severity, xu_syntax::Severity::Error))
+        .collect();
+    assert!(errors.is_empty(), "{errors:?}");
+    parse.module
+}
+
+const SRC: &str = "var runs = 0
+bench \"count\": runs += 1
+bench \"lists\" {
+    let l: [int] = [1, 2, 3]
+    l.push(runs)
+}
+println(runs)
+let bench = \"still a name\"
+println(bench)
+";
+
+#[test]
+fn bench_blocks_are_timed_in_bench_mode() {
+    let module = parse(SRC);
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_clock(Box::new(SteppingClock::default()));
+    rt.set_bench(Some(BenchConfig { warmup: 2, iterations: 5 }));
+    let out = rt.exec_module(&module).unwrap().output;
+    assert_eq!(out, "7\nstill a name\n");
+
+    let results = rt.bench_results();
+    let names: Vec<_> = results.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["count", "lists"]);
+    let count = &results[0];
+    assert_eq!(count.iterations, 5);
+    assert_eq!((count.mean_ms, count.median_ms, count.p99_ms), (5.0, 5.0, 9.0));
+    assert!(results[1].allocations >= 1, "{:?}", results[1]);
+
+    // Results belong to one execution.
+    rt.exec_module(&parse("println(1)\n")).unwrap();
+    assert!(rt.bench_results().is_empty());
+}
+
+#[test]
+fn bench_blocks_are_skipped_otherwise() {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let out = rt.exec_module(&parse(SRC)).unwrap().output;
+    assert_eq!(out, "0\nstill a name\n");
+    assert!(rt.bench_results().is_empty());
+
+    rt.set_bench(Some(BenchConfig::default()));
+    let err = rt.exec_module(&parse("bench \"boom\": 1 / 0\n")).unwrap_err();
+    assert!(err.contains("zero"), "{err}");
+}
@@ -1,7 +1,12 @@
+use std::collections::BTreeSet;
+
+use xu_driver::Driver;
+use xu_ir::{Executable, Frontend};
+use xu_runtime::Runtime;
 use xu_runtime::builtins_registry::{
     BuiltinProvider, BuiltinRegistry, StdBuiltinProvider,
 };
-use xu_syntax::BUILTIN_NAMES;
+use xu_syntax::{BUILTIN_NAMES, builtin_methods};
 
 #[test]
 fn builtins_registry_matches_syntax_list() {
@@ -16,3 +21,27 @@ fn builtins_registry_matches_syntax_list() {
     b.sort();
     assert_eq!(a, b);
 }
+
+#[test]
+fn builtin_method_tables_match_the_runtime() {
+    let receivers =
+        [("text", "\"a\""), ("list", "[1]"), ("dict", "{\"a\": 1}"), ("int", "1"), ("float", "1.5"), ("bool", "true")];
+    let names: BTreeSet<&str> =
+        receivers.iter().flat_map(|(ty, _)| builtin_methods(ty).unwrap().iter().copied()).collect();
+    for (ty, recv) in receivers {
+        let methods = builtin_methods(ty).unwrap();
+        // `add` is only compiled to an append once the analyzer knows the receiver is a list
+        for name in names.iter().filter(|n| **n != "add") {
+            let src = format!("let r = {recv}.{name}()\n");
+            let program = match Driver::new().compile_text_no_analyze("<methods>", &src).expect("compile").executable {
+                Executable::Bytecode(p) => p,
+                Executable::Ast(_) => panic!("expected bytecode"),
+            };
+            let known = match Runtime::new().exec_program(&program) {
+                Ok(_) => true,
+                Err(e) => !(e.contains("Unsupported method") || e.contains(" method: ")),
+            };
+            assert_eq!(known, methods.contains(name), "{ty}.{name}");
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use xu_ir::bundle;
+use xu_runtime::Runtime;
+
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xu_bundle_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, text) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+    dir
+}
+
+fn build(dir: &std::path::Path, entry: &str) -> Result<bundle::Bundle, String> {
+    let path = dir.join(entry).to_string_lossy().into_owned();
+    let compiled = xu_driver::Driver::new().compile_file(&path, true).unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(&path).unwrap();
+    rt.build_bundle(&compiled.executable)
+}
+
+#[test]
+fn bundles_run_without_their_sources() {
+    let main = "use \"lib/util.xu\" as util\nuse \"../shared/names.xu\" as names\nuse \"std/math\"\nprintln(util.greet(names.first()))\nprintln(math.pi > 3.0)\n";
+    let util = "use \"../../shared/names.xu\" as names\npub func greet(n: string) -> string { return \"hi \" + n + \" and \" + names.first() }\n";
+    let dir = project(
+        "run",
+        &[("app/main.xu", main), ("app/lib/util.xu", util), ("shared/names.xu", "pub func first() -> string { return \"ada\" }\n")],
+    );
+    let built = build(&dir, "app/main.xu").unwrap();
+    assert_eq!(built.entry, "app/main.xu");
+    let keys: Vec<_> = built.modules.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, ["app/main.xu", "app/lib/util.xu", "shared/names.xu", "std/math.xu"]);
+
+    // The runtime of the bundled binary only sees the encoded section.
+    std::fs::remove_dir_all(&dir).unwrap();
+    let decoded = bundle::decode(&bundle::encode(&built)).unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let entry = rt.load_bundle(decoded).unwrap();
+    let out = rt.exec_executable(&entry).unwrap().output;
+    assert_eq!(out, "hi ada and ada\ntrue\n");
+}
+
+#[test]
+fn unbundlable_imports_are_reported() {
+    let dir = project("errors", &[("main.xu", "use \"missing.xu\"\n"), ("abs.xu", "")]);
+    let err = build(&dir, "main.xu").unwrap_err();
+    assert!(err.contains("missing.xu"), "{err}");
+
+    let abs = dir.join("abs.xu").to_string_lossy().into_owned();
+    std::fs::write(dir.join("main.xu"), format!("use \"{abs}\"\n")).unwrap();
+    let err = build(&dir, "main.xu").unwrap_err();
+    assert!(err.contains("absolute import path"), "{err}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn loaded_bundles_reject_unknown_imports() {
+    let driver = xu_driver::Driver::new();
+    let main = xu_ir::Frontend::compile_text_no_analyze(&driver, "main.xu", "use \"other.xu\"\n").unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let b = bundle::Bundle {
+        lang_version: "0".into(),
+        entry: "main.xu".into(),
+        modules: vec![("main.xu".into(), main.executable)],
+    };
+    let entry = rt.load_bundle(b.clone()).unwrap();
+    let err = rt.exec_executable(&entry).unwrap_err();
+    assert!(err.contains("other.xu is not in the bundle"), "{err}");
+
+    let err = Runtime::new().load_bundle(bundle::Bundle { entry: "gone.xu".into(), ..b }).unwrap_err();
+    assert!(err.contains("gone.xu"), "{err}");
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use xu_lexer::{Lexer, normalize_source};
+use xu_parser::Parser;
+use xu_runtime::{CapabilityAuditEntry, Runtime};
+
+fn parse_source(src: &str) -> xu_parser::Module {
+    let normalized = normalize_source(src);
+    assert!(normalized.diagnostics.is_empty());
+    let lex = Lexer::new(&normalized.text).lex();
+    assert!(lex.diagnostics.is_empty());
+    let bump = bumpalo::Bump::new();
+    let parse = Parser::new(&normalized.text, &lex.tokens, &bump).parse();
+    let errors: Vec<_> = parse
+        .diagnostics
+        .into_iter()
+        .filter(|d| matches!(d.severity, xu_syntax::Severity::Error))
+        .collect();
+    assert!(errors.is_empty(), "{errors:?}");
+    parse.module
+}
+
+fn sandboxed_runtime() -> (Runtime, String) {
+    let dir = std::env::temp_dir().join("xu_runtime_capability_grant_tests");
+    let _ = fs::create_dir_all(&dir);
+    let data = dir.join("data.txt");
+    fs::write(&data, "secret\n").unwrap();
+    let data = fs::canonicalize(&data).unwrap().to_string_lossy().to_string();
+
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let root = std::env::temp_dir().join("xu_runtime_capability_grant_root");
+    let _ = fs::create_dir_all(&root);
+    rt.add_allowed_root(root.to_string_lossy().as_ref()).unwrap();
+    (rt, data)
+}
+
+#[test]
+fn read_outside_allowed_roots_is_denied() {
+    let (mut rt, data) = sandboxed_runtime();
+    let module = parse_source(&format!("let f = open(\"{data}\")\n"));
+    let err = rt.exec_module(&module).unwrap_err();
+    assert!(err.contains("Path is not within allowed roots"), "{err}");
+}
+
+#[test]
+fn approved_grant_allows_read_inside_block_only() {
+    let (mut rt, data) = sandboxed_runtime();
+    rt.set_capability_policy(Box::new(|cap: &str| cap == "fs.read"));
+    let src = format!(
+        "let r = with_capability(\"fs.read\", || {{\n    let f = open(\"{data}\")\n    return f.read()\n}})\nprintln(r)\nlet g = open(\"{data}\")\n"
+    );
+    let module = parse_source(&src);
+    let err = rt.exec_module(&module).unwrap_err();
+    assert!(err.contains("Path is not within allowed roots"), "{err}");
+
+    let log = rt.capability_audit_log();
+    assert_eq!(
+        log[0],
+        CapabilityAuditEntry { capability: "fs.read".into(), target: None, granted: true }
+    );
+    assert!(log[1..].iter().any(|e| e.target.as_deref() == Some(data.as_str()) && e.granted));
+    assert!(!log.last().unwrap().granted);
+}
+
+#[test]
+fn denied_grant_is_recorded() {
+    let (mut rt, data) = sandboxed_runtime();
+    rt.set_capability_policy(Box::new(|_: &str| false));
+    let src = format!("with_capability(\"fs.read\", || {{ open(\"{data}\") }})\n");
+    let module = parse_source(&src);
+    let err = rt.exec_module(&module).unwrap_err();
+    assert!(err.contains("Capability denied: fs.read"), "{err}");
+    let log = rt.take_capability_audit_log();
+    assert_eq!(log.len(), 1);
+    assert!(!log[0].granted);
+    assert!(rt.capability_audit_log().is_empty());
+}
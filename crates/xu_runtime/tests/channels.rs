@@ -0,0 +1,40 @@
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn run(src: &str) -> Result<String, String> {
+    let exe = xu_driver::Driver::new()
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile")
+        .executable;
+    Runtime::new().exec_executable(&exe).map(|r| r.output)
+}
+
+#[test]
+fn queued_messages_survive_close() {
+    let out = run("let ch = channel()\nch.send(1)\nch.send(2)\nch.close()\nprintln(ch.recv())\nprintln(ch.recv())\nprintln(ch.recv())\n").unwrap();
+    assert_eq!(out, "1\n2\nOption#none\n");
+}
+
+#[test]
+fn send_after_close_is_an_error() {
+    let err = run("let ch = channel()\nch.close()\nch.send(1)\n").unwrap_err();
+    assert!(err.contains("closed"), "{err}");
+}
+
+#[test]
+fn recv_on_a_lone_empty_channel_is_an_error() {
+    let err = run("let ch = channel()\nch.recv()\n").unwrap_err();
+    assert!(err.contains("no other thread can send"), "{err}");
+}
+
+#[test]
+fn recv_any_on_lone_empty_channels_is_an_error() {
+    let err = run("let a = channel()\nlet b = channel(1)\nrecv_any([a, b])\n").unwrap_err();
+    assert!(err.contains("no other thread can send"), "{err}");
+}
+
+#[test]
+fn capacity_must_be_positive() {
+    let err = run("channel(0)\n").unwrap_err();
+    assert!(err.contains("channel"), "{err}");
+}
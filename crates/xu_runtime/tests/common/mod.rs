@@ -1,6 +1,14 @@
-use std::fs;
+//! Helpers shared by the test binaries, each of which uses only some.
+#![allow(dead_code)]
+
 use std::path::PathBuf;
 
+use xu_ir::{Executable, Frontend, Program};
+use xu_runtime::{ExecResult, Runtime};
+#[allow(unused_imports)]
+pub use xu_testkit::find_files;
+use xu_testkit::{Golden, Redactions};
+
 pub fn repo_root() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .parent()
@@ -9,53 +17,37 @@ pub fn repo_root() -> PathBuf {
         .to_path_buf()
 }
 
-pub fn golden_path_for(subdir: &str, name: &str) -> PathBuf {
-    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("tests")
-        .join("golden")
-        .join(subdir)
-        .join(format!("{name}.txt"))
+/// Snapshots in `tests/golden/<subdir>`, with the checkout path redacted.
+pub fn golden(subdir: &str) -> Golden {
+    Golden::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(subdir))
+        .redactions(Redactions::new().path(repo_root(), "<root>"))
 }
 
-pub fn golden_update_enabled() -> bool {
-    let v = std::env::var("XU_UPDATE_GOLDEN").ok();
-    if v.as_deref().is_some_and(|v| v == "1" || v == "true") {
-        return true;
+pub fn program(src: &str) -> Program {
+    match xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile").executable {
+        Executable::Bytecode(p) => p,
+        Executable::Ast(_) => panic!("expected bytecode"),
     }
-    std::env::var("HAOSCRIPT_UPDATE_GOLDEN").is_ok_and(|v| v == "1" || v == "true")
 }
 
-pub fn assert_or_update(path: PathBuf, actual: &str) {
-    let update = golden_update_enabled();
-    if update {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).unwrap();
-        }
-        fs::write(&path, actual).unwrap();
-        return;
+/// Runs `src` on the AST executor and on the VM, each in a runtime made by
+/// `runtime`, which must agree. Gives the VM's runtime and result.
+pub fn run_with(src: &str, mut runtime: impl FnMut() -> Runtime) -> (Runtime, Result<ExecResult, String>) {
+    let p = program(src);
+    let mut ast_rt = runtime();
+    let ast = ast_rt.exec_module(&p.module);
+    let mut vm_rt = runtime();
+    let vm = vm_rt.exec_program(&p);
+    match (&ast, &vm) {
+        (Ok(a), Ok(v)) => assert_eq!((&a.output, a.exit_code), (&v.output, v.exit_code)),
+        (Err(a), Err(v)) => assert_eq!(a, v),
+        _ => panic!("engines differ: {:?} vs {:?}", ast.as_ref().err(), vm.as_ref().err()),
     }
-    let expected = fs::read_to_string(&path).unwrap_or_default();
-    assert_eq!(
-        actual.trim_end(),
-        expected.trim_end(),
-        "Golden mismatch for {:?}",
-        path
-    );
+    assert_eq!(ast_rt.panicked(), vm_rt.panicked());
+    (vm_rt, vm)
 }
 
-pub fn find_files(dir: &PathBuf, ext: &str) -> Vec<PathBuf> {
-    let mut files = Vec::new();
-    if dir.is_dir() {
-        for entry in fs::read_dir(dir).expect("read_dir") {
-            let entry = entry.expect("entry");
-            let path = entry.path();
-            if path.is_dir() {
-                files.extend(find_files(&path, ext));
-            } else if path.extension().and_then(|s| s.to_str()) == Some(ext) {
-                files.push(path);
-            }
-        }
-    }
-    files.sort();
-    files
+/// [`run_with`] in default runtimes, giving what the script printed.
+pub fn run(src: &str) -> Result<String, String> {
+    run_with(src, Runtime::new).1.map(|r| r.output)
 }
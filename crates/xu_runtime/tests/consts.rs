@@ -0,0 +1,109 @@
+use xu_ir::{Bytecode, Constant, Executable, Op};
+use xu_runtime::Runtime;
+
+mod common;
+use common::{program, run};
+
+/// Names the code of `bc` and of the functions it defines load or read as
+/// members, by name.
+fn names_read(bc: &Bytecode, out: &mut Vec<String>) {
+    for op in &bc.ops {
+        let idx = match op {
+            Op::LoadName(i) | Op::GetMember(i, _) => *i,
+            _ => continue,
+        };
+        if let Constant::Str(s) = &bc.constants[idx as usize] {
+            out.push(s.clone());
+        }
+    }
+    for c in &bc.constants {
+        if let Constant::Func(f) = c {
+            names_read(&f.bytecode, out);
+        }
+    }
+}
+
+fn diagnostics(src: &str) -> Vec<String> {
+    let compiled = xu_driver::Driver::new().compile_text("<consts>", src, true).expect("compile");
+    compiled.diagnostics.into_iter().map(|d| d.message).collect()
+}
+
+#[test]
+fn constants_fold_and_load_as_immediates() {
+    let src = concat!(
+        "const WIDTH = 4 * 8\n",
+        "const AREA = WIDTH ** 2 - 1\n",
+        "pub const NAME = \"grid-\" + WIDTH\n",
+        "const HALF = WIDTH / 3.0 > 10 && true\n",
+        "func area() -> int {\n    return AREA + WIDTH\n}\n",
+        "println(WIDTH, AREA, NAME, HALF, area())\n",
+    );
+    assert_eq!(run(src).unwrap(), "32\n1023\ngrid-32\ntrue\n1055\n");
+    let mut names = Vec::new();
+    names_read(program(src).bytecode.as_ref().unwrap(), &mut names);
+    assert!(!names.iter().any(|n| ["WIDTH", "AREA", "NAME", "HALF"].contains(&n.as_str())), "{names:?}");
+}
+
+#[test]
+fn rebound_names_are_looked_up() {
+    let src = concat!(
+        "const N = 1\n",
+        "func f(N) {\n    return N * 10\n}\n",
+        "func g() -> int {\n    var total = 0\n    for N in [5, 6] {\n        total += N\n    }\n    return total\n}\n",
+        "println(N, f(2), g())\n",
+    );
+    assert_eq!(run(src).unwrap(), "1\n20\n11\n");
+    let mut names = Vec::new();
+    names_read(program(src).bytecode.as_ref().unwrap(), &mut names);
+    assert!(names.iter().any(|n| n == "N"), "{names:?}");
+}
+
+#[test]
+fn constants_cannot_be_reassigned_at_runtime() {
+    let p = program("const N = 1\nN = 2\n");
+    let err = Runtime::new().exec_module(&p.module).unwrap_err();
+    assert!(err.contains("Cannot reassign immutable variable"), "{err}");
+}
+
+#[test]
+fn analyzer_checks_constant_declarations() {
+    assert!(diagnostics("const A = 2\nconst B = -A << 3 | 1\nprintln(B)\n").is_empty());
+    let diags = diagnostics("let xs = [1]\nconst N = xs.length\nconst D = 1 / 0\n");
+    assert!(diags.iter().any(|m| m == "The value of constant 'N' must be computable at compile time"), "{diags:?}");
+    assert!(diags.iter().any(|m| m.contains("constant 'D'")), "{diags:?}");
+    let diags = diagnostics("func f() -> int {\n    const N = 1\n    return N\n}\nprintln(f())\n");
+    assert!(diags.iter().any(|m| m == "Constants can only be declared at module level"), "{diags:?}");
+    let diags = diagnostics("const N = 1\nN += 1\n");
+    assert!(diags.iter().any(|m| m == "Cannot assign to constant 'N'"), "{diags:?}");
+}
+
+#[test]
+fn public_constants_are_exported_and_inlined_by_importers() {
+    let dir = std::env::temp_dir().join(format!("xu_consts_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("sizes.xu"), "pub const CELL = 16\npub const ROW = CELL * 10\nconst SECRET = 7\n").unwrap();
+    let main = dir.join("main.xu");
+    let src = concat!(
+        "use \"sizes.xu\" as sizes\n",
+        "const GRID = sizes.ROW * sizes.CELL\n",
+        "println(sizes.CELL, GRID)\n",
+    );
+    std::fs::write(&main, src).unwrap();
+    let main = main.to_string_lossy();
+    let compiled = xu_driver::Driver::new().compile_file(&main, true).unwrap();
+    assert!(compiled.diagnostics.is_empty(), "{:?}", compiled.diagnostics);
+    let Executable::Bytecode(p) = &compiled.executable else { panic!("expected bytecode") };
+    let mut names = Vec::new();
+    names_read(p.bytecode.as_ref().unwrap(), &mut names);
+    assert!(!names.iter().any(|n| n == "CELL" || n == "GRID"), "{names:?}");
+
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(&main).unwrap();
+    assert_eq!(rt.exec_executable(&compiled.executable).unwrap().output, "16\n2560\n");
+
+    // Private constants stay private.
+    std::fs::write(dir.join("main.xu"), "use \"sizes.xu\" as sizes\nconst S = sizes.SECRET\n").unwrap();
+    let diags = xu_driver::Driver::new().compile_file(&main, true).unwrap().diagnostics;
+    assert!(diags.iter().any(|d| d.message.contains("constant 'S'")), "{diags:?}");
+}
@@ -0,0 +1,93 @@
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+const SRC: &str = "@requires(n >= 0, \"n must be non-negative\")
+@ensures(result > n)
+func next(n: int) -> int {
+    if n == 7 {
+        return n
+    }
+    return n + 1
+}
+println(next(ARG))
+";
+
+fn run_vm(driver: &xu_driver::Driver, src: &str) -> Result<String, String> {
+    let cu = driver.compile_text_no_analyze("contracts.xu", src)?;
+    let mut rt = Runtime::new();
+    rt.exec_executable(&cu.executable).map(|r| r.output)
+}
+
+fn run_ast(driver: &xu_driver::Driver, src: &str) -> Result<String, String> {
+    let parsed = driver.parse_text_no_analyze("contracts.xu", src)?;
+    let mut rt = Runtime::new();
+    rt.exec_module(&parsed.module).map(|r| r.output)
+}
+
+fn with_arg(n: i64) -> String {
+    SRC.replace("ARG", &n.to_string())
+}
+
+#[test]
+fn contracts_pass_through_valid_calls() {
+    let driver = xu_driver::Driver::new();
+    assert_eq!(run_vm(&driver, &with_arg(1)).unwrap(), "2\n");
+    assert_eq!(run_ast(&driver, &with_arg(1)).unwrap(), "2\n");
+}
+
+#[test]
+fn requires_violation_blames_caller() {
+    let driver = xu_driver::Driver::new();
+    for run in [run_vm, run_ast] {
+        let e = run(&driver, &with_arg(-1)).unwrap_err();
+        assert!(
+            e.contains("next requires `n >= 0` at 1:11 (blame: caller): n must be non-negative"),
+            "{e}"
+        );
+    }
+}
+
+#[test]
+fn ensures_violation_blames_function() {
+    let driver = xu_driver::Driver::new();
+    for run in [run_vm, run_ast] {
+        let e = run(&driver, &with_arg(7)).unwrap_err();
+        assert!(e.contains("next ensures `result > n` at 2:10 (blame: next)"), "{e}");
+    }
+}
+
+#[test]
+fn release_builds_strip_contracts() {
+    let driver = xu_driver::Driver::new().with_contracts(false);
+    assert_eq!(run_vm(&driver, &with_arg(-1)).unwrap(), "0\n");
+    assert_eq!(run_ast(&driver, &with_arg(7)).unwrap(), "7\n");
+}
+
+#[test]
+fn annotations_must_precede_a_function() {
+    let parsed = xu_driver::Driver::new()
+        .parse_text("contracts.xu", "@requires(true)\nlet x = 1\n", true)
+        .unwrap();
+    assert!(
+        parsed
+            .diagnostics
+            .iter()
+            .any(|d| d.message.contains("Contract annotations must precede a function")),
+        "{:?}",
+        parsed.diagnostics
+    );
+}
+
+#[test]
+fn ensures_on_result_needs_a_value_on_every_path() {
+    let src = "@ensures(result > 0)\nfunc positive(x: int) {\n    if x > 0 {\n        return x\n    }\n}\n";
+    let parsed = xu_driver::Driver::new().parse_text("contracts.xu", src, true).unwrap();
+    let errors: Vec<_> = parsed.diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(errors, ["@ensures uses `result`, but positive may end without returning a value"]);
+    // Clauses that do not use `result` still check the fall-through path.
+    let src = "var calls = 0\n@ensures(calls > 0)\nfunc touch(x: int) {\n    if x > 0 {\n        return x\n    }\n}\ntouch(-1)\n";
+    let driver = xu_driver::Driver::new();
+    for run in [run_vm, run_ast] {
+        assert!(run(&driver, src).unwrap_err().contains("touch ensures `calls > 0`"));
+    }
+}
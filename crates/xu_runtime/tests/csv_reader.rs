@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use xu_ir::Frontend;
+use xu_runtime::{FileStat, FileSystem, Runtime};
+
+/// Files in memory that are only handed out a few bytes at a time, so every
+/// record of a reader crosses chunk boundaries.
+#[derive(Clone, Default)]
+struct ChunkedFs {
+    files: Arc<Mutex<HashMap<String, String>>>,
+    chunks: Arc<Mutex<usize>>,
+}
+
+impl FileSystem for ChunkedFs {
+    fn metadata(&self, path: &str) -> Result<(), String> {
+        self.stat(path).map(|_| ())
+    }
+    fn stat(&self, path: &str) -> Result<FileStat, String> {
+        let files = self.files.lock().unwrap();
+        let len = files.get(path).ok_or_else(|| format!("{path}: no such file"))?.len() as u64;
+        Ok(FileStat { len, modified_nanos: None })
+    }
+    fn canonicalize(&self, path: &str) -> Result<String, String> {
+        Ok(path.to_string())
+    }
+    fn read_to_string(&self, path: &str) -> Result<String, String> {
+        panic!("{path} was read whole");
+    }
+    fn read_chunk(&self, path: &str, offset: u64, _max: usize) -> Result<String, String> {
+        *self.chunks.lock().unwrap() += 1;
+        let files = self.files.lock().unwrap();
+        let text = files.get(path).ok_or_else(|| format!("{path}: no such file"))?;
+        let start = offset as usize;
+        let mut end = (start + 4).min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        Ok(text[start..end].to_string())
+    }
+}
+
+fn read_all(csv: &str) -> (Result<String, String>, usize) {
+    let fs = ChunkedFs::default();
+    fs.files.lock().unwrap().insert("/mem/data.csv".into(), csv.into());
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_file_system(Box::new(fs.clone()));
+    rt.add_allowed_root("/mem").unwrap();
+    let src = "use \"std/csv\" as csv\nlet r = csv.reader(open(\"/mem/data.csv\"))\nvar row = r.next()\nwhile row.has() {\n    println(row.get())\n    row = r.next()\n}\nprintln(r.count)\n";
+    let cu = xu_driver::Driver::new().compile_text_no_analyze("/mem/main.xu", src).unwrap();
+    let out = rt.exec_executable(&cu.executable).map(|r| r.output);
+    let chunks = *fs.chunks.lock().unwrap();
+    (out, chunks)
+}
+
+#[test]
+fn records_are_read_across_chunks_and_lines() {
+    let csv = "id,note\r\n1,\"première\r\nligne \"\"citée\"\"\"\r\n\r\n2,中文\n3,\"a,b\"";
+    let (out, chunks) = read_all(csv);
+    assert_eq!(
+        out.unwrap(),
+        "{\"id\":1,\"note\":première\r\nligne \"citée\"}\n{\"id\":2,\"note\":中文}\n{\"id\":3,\"note\":a,b}\n3\n"
+    );
+    assert!(chunks > csv.len() / 4, "read in {chunks} chunks");
+}
+
+#[test]
+fn an_unterminated_quote_reports_the_line_of_its_record() {
+    let (out, _) = read_all("id,note\n1,ok\n2,\"open\nstill open\n");
+    let err = out.unwrap_err();
+    assert!(err.contains("csv: unterminated quoted field in the record on line 3"), "{err}");
+}
@@ -0,0 +1,66 @@
+use xu_ir::Frontend;
+use xu_runtime::{DictHashing, HostValue, Runtime, RuntimeConfig};
+
+const SRC: &str = r#"
+var table: {string: int} = {}
+var i = 0
+while i < 500 {
+    table["key" + i] = i
+    i = i + 1
+}
+
+func lookup(name: string) -> int {
+    return table.get_or_default(name, -1)
+}
+
+func lookup_in(d: {string: int}, name: string) -> int {
+    return d.get_or_default(name, -1)
+}
+
+println(table.length)
+println(lookup("key123"))
+println(table.keys()[0])
+"#;
+
+fn runtime(hashing: DictHashing) -> (Runtime, String) {
+    let driver = xu_driver::Driver::new();
+    let cu = driver.compile_text_no_analyze("dict_hashing.xu", SRC).unwrap();
+    let mut rt = Runtime::with_config(RuntimeConfig {
+        dict_hashing: hashing,
+        ..RuntimeConfig::default()
+    });
+    let out = rt.exec_executable(&cu.executable).unwrap().output;
+    (rt, out)
+}
+
+#[test]
+fn keyed_and_fixed_hashing_agree() {
+    let (_, keyed) = runtime(DictHashing::Keyed);
+    let (_, fixed) = runtime(DictHashing::Fixed);
+    assert_eq!(keyed, "500\n123\nkey0\n");
+    assert_eq!(keyed, fixed);
+}
+
+#[test]
+fn runtimes_on_one_thread_keep_their_own_seeds() {
+    let (mut a, _) = runtime(DictHashing::Keyed);
+    // Creating and running B on the same thread leaves A's seeds alone.
+    let (mut b, _) = runtime(DictHashing::Keyed);
+    let (_, _) = runtime(DictHashing::Fixed);
+
+    for rt in [&mut a, &mut b] {
+        let name = rt.intern_str_value("key321");
+        let v = rt.call_global("lookup", &[name]).unwrap();
+        assert_eq!(v.as_i64(), 321);
+    }
+}
+
+#[test]
+fn host_built_dicts_use_the_seeds_of_their_runtime() {
+    let (mut a, _) = runtime(DictHashing::Keyed);
+    let dict = || HostValue::Dict(vec![(HostValue::Str("k".into()), HostValue::Int(7))]);
+    let call = |rt: &mut Runtime| rt.call_global_host("lookup_in", vec![dict(), HostValue::Str("k".into())]);
+    assert_eq!(call(&mut a).unwrap(), HostValue::Int(7));
+    let _b = Runtime::new();
+    assert_eq!(call(&mut a).unwrap(), HostValue::Int(7));
+}
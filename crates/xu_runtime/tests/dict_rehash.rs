@@ -0,0 +1,75 @@
+use ahash::RandomState;
+use xu_runtime::Value;
+use xu_runtime::core::dict_map::{DictEntry, DictMap, INCREMENTAL_REHASH_MIN};
+use xu_runtime::core::value::DictKey;
+
+fn int(i: i64) -> DictKey {
+    DictKey::Int(i)
+}
+
+fn val(i: i64) -> Value {
+    Value::from_i64(i)
+}
+
+/// Inserts keys until the map starts moving its positions into a grown table.
+fn migrating_map() -> (DictMap, i64) {
+    let mut map = DictMap::with_hasher(RandomState::with_seeds(1, 2, 3, 4));
+    let mut n = 0;
+    while !map.is_migrating() {
+        map.insert(int(n), val(n));
+        n += 1;
+    }
+    assert!(n as usize > INCREMENTAL_REHASH_MIN / 2, "small tables grow at once, grew at {n}");
+    (map, n)
+}
+
+#[test]
+fn lookups_see_every_key_while_migrating() {
+    let (mut map, n) = migrating_map();
+    for i in 0..n {
+        assert_eq!(map.get(&int(i)).map(|v| v.as_i64()), Some(i), "key {i}");
+    }
+    assert!(map.get(&int(n)).is_none());
+    // 迁移中覆盖旧键不会重复插入
+    assert_eq!(map.insert(int(0), val(-1)).map(|v| v.as_i64()), Some(0));
+    let hash = map.hasher().hash_one(int(1));
+    match map.entry_by_hash(hash, |k| *k == int(1)) {
+        DictEntry::Occupied(slot) => *slot = val(-2),
+        DictEntry::Vacant(_) => panic!("key 1 is missing"),
+    }
+    assert!(map.is_migrating());
+    assert_eq!(map.len(), n as usize);
+    assert_eq!(map.get(&int(0)).map(|v| v.as_i64()), Some(-1));
+    assert_eq!(map.get(&int(1)).map(|v| v.as_i64()), Some(-2));
+}
+
+#[test]
+fn migration_finishes_and_keeps_insertion_order() {
+    let (mut map, n) = migrating_map();
+    let mut total = n;
+    while map.is_migrating() {
+        map.insert(int(total), val(total));
+        total += 1;
+    }
+    // 每次插入迁移多个位置，迁完前新表不会再次装满
+    assert!(total < 2 * n, "migration took {} inserts", total - n);
+    let keys: Vec<i64> = map.keys().map(|k| if let DictKey::Int(i) = k { *i } else { -1 }).collect();
+    assert_eq!(keys, (0..total).collect::<Vec<_>>());
+    assert!((0..total).all(|i| map.get(&int(i)).is_some_and(|v| v.as_i64() == i)));
+}
+
+#[test]
+fn removal_during_migration_keeps_the_rest() {
+    let (mut map, n) = migrating_map();
+    assert_eq!(map.shift_remove(&int(5)).map(|v| v.as_i64()), Some(5));
+    assert!(!map.is_migrating());
+    assert!(map.get(&int(5)).is_none());
+    assert_eq!(map.len(), n as usize - 1);
+    assert_eq!(map.keys().nth(5), Some(&int(6)));
+    assert!((0..n).filter(|&i| i != 5).all(|i| map.get(&int(i)).is_some_and(|v| v.as_i64() == i)));
+    // 克隆得到独立的副本
+    let copy = map.clone();
+    map.clear();
+    assert_eq!(copy.len(), n as usize - 1);
+    assert!(map.is_empty() && !map.is_migrating());
+}
@@ -0,0 +1,76 @@
+use std::cell::Cell;
+
+use xu_ir::{Executable, Frontend, Program};
+use xu_runtime::{Runtime, compare_engines};
+
+fn program(src: &str) -> Program {
+    let cu = xu_driver::Driver::new()
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile");
+    match cu.executable {
+        Executable::Bytecode(p) => {
+            assert!(p.bytecode.is_some(), "expected bytecode");
+            p
+        }
+        Executable::Ast(_) => panic!("expected bytecode"),
+    }
+}
+
+fn runtime() -> Runtime {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt
+}
+
+#[test]
+fn engines_agree_on_nondeterministic_inputs() {
+    let src = "let t = time_millis()
+let r = rand(1000000)
+let user = env_get(\"XU_DIFFERENTIAL_UNSET\")
+var total = 0
+for i in [1..10] { total += i * r }
+println(t)
+println(r)
+println(total)
+func twice(x: int) -> int { return x * 2 }
+let doubled = twice(total)
+";
+    let cmp = compare_engines(&program(src), runtime);
+    assert!(cmp.divergences.is_empty(), "{:?}", cmp.divergences);
+    assert_eq!(cmp.ast.error, None);
+    assert_eq!(cmp.vm.output, cmp.ast.output);
+    assert_eq!(cmp.ast.output.lines().count(), 3, "{}", cmp.ast.output);
+
+    let names: Vec<_> = cmp.ast.globals.iter().map(|(n, _)| n.as_str()).collect();
+    for name in ["doubled", "r", "t", "total", "user"] {
+        assert!(names.contains(&name), "{names:?}");
+    }
+    assert!(!names.contains(&"twice"), "functions are not compared: {names:?}");
+}
+
+#[test]
+fn engines_agree_on_runtime_errors() {
+    let cmp = compare_engines(&program("println(\"before\")\nlet z = 0\nprintln(1 / z)\n"), runtime);
+    assert!(cmp.divergences.is_empty(), "{:?}", cmp.divergences);
+    let err = cmp.ast.error.expect("division by zero");
+    assert!(err.contains("zero"), "{err}");
+    assert_eq!(cmp.vm.output, "before\n");
+}
+
+#[test]
+fn divergences_are_reported() {
+    // Runtimes configured differently stand in for an engine bug.
+    let calls = Cell::new(0);
+    let src = "let n = os_args().length\nprintln(n)\nprintln(\"same\")\n";
+    let cmp = compare_engines(&program(src), || {
+        let mut rt = runtime();
+        calls.set(calls.get() + 1);
+        rt.set_args(vec!["x".repeat(calls.get()); calls.get()]);
+        rt
+    });
+    assert_eq!(calls.get(), 2);
+    let what: Vec<_> = cmp.divergences.iter().map(|d| d.what.as_str()).collect();
+    assert_eq!(what, ["output line 1", "global n"], "{:?} {:?}", cmp.ast, cmp.vm);
+    assert_eq!(cmp.divergences[0].to_string(), "output line 1:\n  ast: \"1\"\n  vm:  \"2\"");
+    assert_eq!((cmp.divergences[1].ast.as_str(), cmp.divergences[1].vm.as_str()), ("1", "2"));
+}
@@ -18,3 +18,325 @@ while i < 3 {
     let res = rt.exec_executable(&cu.executable).expect("exec");
     assert_eq!(res.output.trim_end(), "0\n1\n2");
 }
+
+fn compiled_ops(src: &str) -> Vec<xu_ir::Op> {
+    let driver = xu_driver::Driver::new();
+    let cu = driver
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile");
+    match &cu.executable {
+        xu_ir::Executable::Bytecode(p) => p.bytecode.as_ref().expect("bytecode").ops.clone(),
+        xu_ir::Executable::Ast(_) => panic!("expected bytecode"),
+    }
+}
+
+#[test]
+fn driver_presizes_list_filled_by_loop() {
+    let src = r#"
+xs = [];
+for i in [0..100] {
+    if i % 2 == 0 {
+        xs.push(i);
+    }
+}
+ys = [];
+for x in xs {
+    let y = x * 2;
+    ys.push(y);
+}
+println(ys.length());
+"#;
+    let ops = compiled_ops(src);
+    let reserves = ops.iter().filter(|op| **op == xu_ir::Op::ListReserveFor).count();
+    assert_eq!(reserves, 1);
+
+    let driver = xu_driver::Driver::new();
+    let cu = driver.compile_text_no_analyze("<mem>", src).expect("compile");
+    let mut rt = Runtime::new();
+    let res = rt.exec_executable(&cu.executable).expect("exec");
+    assert_eq!(res.output.trim_end(), "51");
+}
+
+#[test]
+fn driver_skips_presize_for_conditional_appends() {
+    // Both loops append to `xs` only for some items.
+    let src = r#"
+xs = [];
+for i in [0..10] {
+    if i % 3 == 0 {
+        continue;
+    }
+    xs.push(i);
+}
+for i in [0..10] {
+    while i > 5 {
+        break;
+    }
+    if i > 8 {
+        xs.push(i);
+    }
+}
+println(xs.length());
+"#;
+    let ops = compiled_ops(src);
+    assert!(!ops.contains(&xu_ir::Op::ListReserveFor));
+}
+
+#[test]
+fn driver_skips_presize_without_list_literal() {
+    // `xs` is not known to be defined before the loop
+    let src = r#"
+for i in [0..3] {
+    xs.push(i);
+}
+"#;
+    let ops = compiled_ops(src);
+    assert!(!ops.contains(&xu_ir::Op::ListReserveFor));
+}
+
+/// Ops of the program and of every function compiled into it.
+fn all_compiled_ops(src: &str) -> Vec<xu_ir::Op> {
+    fn collect(bc: &xu_ir::Bytecode, out: &mut Vec<xu_ir::Op>) {
+        out.extend(bc.ops.iter().cloned());
+        for c in &bc.constants {
+            if let xu_ir::Constant::Func(f) = c {
+                collect(&f.bytecode, out);
+            }
+        }
+    }
+    let cu = xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile");
+    let mut out = Vec::new();
+    match &cu.executable {
+        xu_ir::Executable::Bytecode(p) => collect(p.bytecode.as_ref().expect("bytecode"), &mut out),
+        xu_ir::Executable::Ast(_) => panic!("expected bytecode"),
+    }
+    out
+}
+
+fn run_output(src: &str) -> String {
+    let cu = xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile");
+    Runtime::new().exec_executable(&cu.executable).expect("exec").output
+}
+
+const BODIES: &str = r#"
+Vec2 has {
+    x: int
+    y: int
+
+    func len2() {
+        return self.x * self.x + self.y * self.y
+    }
+}
+Body has {
+    pos: Vec2
+    mass: int
+}
+func weigh(b: Body) {
+    b.mass += 1
+    return b.pos.y * b.mass
+}
+func untyped(b) {
+    return b.mass
+}
+let b = Body{ pos: Vec2{ x: 3, y: 4 }, mass: 10 }
+b.pos.x = 5
+println("{weigh(b)} {b.pos.len2()} {untyped(b)}")
+"#;
+
+#[test]
+fn driver_resolves_field_offsets_of_statically_typed_structs() {
+    let ops = all_compiled_ops(BODIES);
+    let at = |name_offsets: &[u32]| {
+        let mut found: Vec<u32> = ops
+            .iter()
+            .filter_map(|op| match op {
+                xu_ir::Op::GetMemberAt(_, off, _) => Some(*off),
+                _ => None,
+            })
+            .collect();
+        found.sort();
+        assert_eq!(found, name_offsets);
+    };
+    // `pos`/`x` at 0: self.x twice, b.pos in `weigh` and twice at top level.
+    // `mass`/`y` at 1: self.y twice, .y and b.mass in `weigh`.
+    at(&[0, 0, 0, 0, 0, 1, 1, 1, 1]);
+    let assigns: Vec<_> = ops.iter().filter(|op| matches!(op, xu_ir::Op::AssignMemberAt(..))).collect();
+    assert_eq!(assigns.len(), 2, "{ops:?}");
+    // The untyped parameter keeps the IC path.
+    assert!(ops.iter().any(|op| matches!(op, xu_ir::Op::GetMember(..))));
+    assert_eq!(run_output(BODIES), "44 41 11\n");
+}
+
+#[test]
+fn resolved_offsets_fall_back_for_other_layouts() {
+    // `gety` is compiled against the second layout but also reads an
+    // instance created before the redefinition, and one of another type.
+    let src = r#"
+P has {
+    x: int
+    y: int
+}
+Q has {
+    w: int
+    y: int
+}
+func gety(p: P) {
+    return p.y
+}
+let old = P{ x: 1, y: 2 }
+P has {
+    y: int
+    x: int
+}
+let new = P{ y: 5, x: 6 }
+var q = Q{ w: 7, y: 8 }
+q = new
+q.x += 1
+println("{gety(old)} {gety(new)} {gety(Q{ w: 3, y: 9 })} {new.x}")
+"#;
+    assert!(all_compiled_ops(src).iter().any(|op| matches!(op, xu_ir::Op::GetMemberAt(_, 0, _))));
+    assert_eq!(run_output(src), "2 5 9 7\n");
+}
+
+fn all_constants(src: &str) -> Vec<xu_ir::Constant> {
+    fn collect(bc: &xu_ir::Bytecode, out: &mut Vec<Vec<xu_ir::Constant>>) {
+        out.push(bc.constants.clone());
+        for c in &bc.constants {
+            if let xu_ir::Constant::Func(f) = c {
+                collect(&f.bytecode, out);
+            }
+        }
+    }
+    let cu = xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile");
+    let mut pools = Vec::new();
+    match &cu.executable {
+        xu_ir::Executable::Bytecode(p) => collect(p.bytecode.as_ref().expect("bytecode"), &mut pools),
+        xu_ir::Executable::Ast(_) => panic!("expected bytecode"),
+    }
+    for pool in &pools {
+        for (i, c) in pool.iter().enumerate() {
+            assert!(!pool[..i].contains(c), "duplicate constant {c:?} in {pool:?}");
+        }
+    }
+    pools.into_iter().flatten().collect()
+}
+
+#[test]
+fn constant_pools_hold_each_constant_once() {
+    let src = r#"
+func tag(n) {
+    let label = "item"
+    return "{label}-{n}-item"
+}
+var s = "item"
+for i in 0..3 {
+    s = s + "item" + tag(i)
+}
+let big = 100000000000
+println("{s} {big + 100000000000} {0.5 + 0.5}")
+"#;
+    let consts = all_constants(src);
+    let items = consts.iter().filter(|c| matches!(c, xu_ir::Constant::Str(s) if s == "item")).count();
+    // Once at top level and once in the pool of `tag`.
+    assert_eq!(items, 2, "{consts:?}");
+    assert_eq!(
+        run_output(src),
+        "itemitemitem-0-itemitemitem-1-itemitemitem-2-item 200000000000 1\n"
+    );
+}
+
+#[test]
+fn empty_strings_are_pushed_without_a_constant() {
+    let src = r#"
+let n = 3
+var s = ""
+s = s + "{n}"
+let e = "" + ""
+let same = e == ""
+let len = "".length
+println("[{s}] [{e}] {same} {len}")
+"#;
+    let consts = all_constants(src);
+    assert!(!consts.contains(&xu_ir::Constant::Str(String::new())), "{consts:?}");
+    assert!(all_compiled_ops(src).iter().any(|op| matches!(op, xu_ir::Op::ConstEmptyStr)));
+    assert_eq!(run_output(src), "[3] [] true 0\n");
+}
+
+const SWITCHES: &str = r#"
+func name(n: int) -> string {
+    return match n {
+        1 { "one" }
+        2 { "two" }
+        4 { "four" }
+        2 { "again" }
+        _ { "many" }
+    }
+}
+func code(s) {
+    match s {
+        "add" { return 1 }
+        "sub" { return 2 }
+        "mul" { return 3 }
+        _ { }
+    }
+    return 0
+}
+func big(n: int) -> int {
+    return match n {
+        0 { 1 }
+        1000 { 2 }
+        1000000 { 3 }
+        _ { 0 }
+    }
+}
+var total = 0
+for i in 0..10 {
+    match i % 4 {
+        0 { continue }
+        1 { total += 1 }
+        100 { total += 100 }
+        _ { total += 10 }
+    }
+}
+println("{name(1)} {name(2)} {name(3)} {name(4)} {name(-1)}")
+println(code("sub"), code("mul"), code("div"), code(3))
+println(big(0), big(1000), big(1000000), big(5), total)
+"#;
+
+#[test]
+fn literal_matches_dispatch_through_a_switch_table() {
+    let ops = all_compiled_ops(SWITCHES);
+    assert_eq!(ops.iter().filter(|op| matches!(op, xu_ir::Op::Switch(_))).count(), 4);
+    assert!(!ops.iter().any(|op| matches!(op, xu_ir::Op::MatchPattern(_))));
+    let tables: Vec<_> = all_constants(SWITCHES)
+        .into_iter()
+        .filter_map(|c| match c {
+            xu_ir::Constant::Switch(t) => Some(t.cases),
+            _ => None,
+        })
+        .collect();
+    assert!(tables.iter().any(|c| matches!(c, xu_ir::SwitchCases::Dense { base: 1, targets } if targets.len() == 4)));
+    assert!(tables.iter().any(|c| matches!(c, xu_ir::SwitchCases::Ints(cases) if cases.len() == 3)));
+    assert!(tables.iter().any(|c| matches!(c, xu_ir::SwitchCases::Strs(cases) if cases.len() == 3)));
+    assert_eq!(run_output(SWITCHES), "one two many four many\n2\n3\n0\n0\n1\n2\n3\n0\n43\n");
+}
+
+#[test]
+fn other_matches_test_arms_in_turn() {
+    let src = r#"
+let v = 2
+let a = match v {
+    1 { "one" }
+    2.0 { "float" }
+    _ { "other" }
+}
+let c = match v {
+    1 { "one" }
+    _ { "other" }
+}
+println("{a} {c}")
+"#;
+    let ops = all_compiled_ops(src);
+    assert!(!ops.iter().any(|op| matches!(op, xu_ir::Op::Switch(_))));
+    assert_eq!(run_output(src), "float other\n");
+}
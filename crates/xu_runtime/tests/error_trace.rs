@@ -0,0 +1,68 @@
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+use xu_syntax::SourceText;
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new()
+        .compile_text_no_analyze("trace.xu", src)
+        .expect("compile")
+        .executable
+}
+
+/// `(line, function)` per frame, lines 1-based.
+fn frames(rt: &Runtime, src: &str) -> Vec<(u32, Option<String>)> {
+    let text = SourceText::new(src.to_string());
+    rt.error_trace()
+        .iter()
+        .map(|f| {
+            assert_eq!(f.file, "trace.xu");
+            (text.line_col(f.span.start.0).0 + 1, f.function.clone())
+        })
+        .collect()
+}
+
+const SHAPES: &str = "Circle has {\n    r: int\n\n    func area() {\n        return self.r * self.missing\n    }\n}\nfunc total(shapes) {\n    var sum = 0\n    for s in shapes {\n        sum += s.area()\n    }\n    return sum\n}\nlet shapes = [Circle{ r: 1 }]\nprintln(total(shapes))\n";
+
+#[test]
+fn errors_are_traced_innermost_first() {
+    let mut rt = Runtime::new();
+    let err = rt.exec_executable(&compile(SHAPES)).unwrap_err();
+    assert!(err.contains("missing"), "{err}");
+    let name = |s: &str| Some(s.to_string());
+    assert_eq!(frames(&rt, SHAPES), [(5, name("Circle.area")), (11, name("total")), (16, None)]);
+}
+
+#[test]
+fn closures_and_loops_report_their_statement() {
+    let src = "func apply(xs) {\n    let f = |i| xs[i]\n    var out = 0\n    for i in 0..4 {\n        out += i\n        out += f(i)\n    }\n    return out\n}\nlet v = apply([1, 2])\n";
+    let mut rt = Runtime::new();
+    rt.exec_executable(&compile(src)).unwrap_err();
+    let name = |s: &str| Some(s.to_string());
+    assert_eq!(frames(&rt, src), [(2, name("<closure>")), (6, name("apply")), (10, None)]);
+}
+
+#[test]
+fn successful_runs_leave_no_trace() {
+    let mut rt = Runtime::new();
+    rt.exec_executable(&compile(SHAPES)).unwrap_err();
+    assert!(!rt.error_trace().is_empty());
+    rt.exec_executable(&compile("println(1)\n")).unwrap();
+    assert!(rt.error_trace().is_empty());
+}
+
+#[test]
+fn ops_map_back_to_statement_lines() {
+    let src = "let a = 1\n\nlet b = a + 2\nif b > 2 {\n    println(b)\n}\n";
+    let xu_ir::Executable::Bytecode(p) = compile(src) else { panic!("expected bytecode") };
+    let bc = p.bytecode.expect("bytecode");
+    let text = SourceText::new(src.to_string());
+    let lines: Vec<u32> = (0..bc.ops.len())
+        .filter_map(|ip| bc.spans.span_at(ip))
+        .map(|s| text.line_col(s.start.0).0 + 1)
+        .collect();
+    assert_eq!(lines.first(), Some(&1));
+    assert!(lines.windows(2).all(|w| w[0] <= w[1]), "{lines:?}");
+    let mut distinct = lines.clone();
+    distinct.dedup();
+    assert_eq!(distinct, [1, 3, 4, 5]);
+}
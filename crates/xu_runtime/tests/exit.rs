@@ -0,0 +1,53 @@
+use xu_runtime::Runtime;
+
+mod common;
+use common::run_with;
+
+#[test]
+fn exit_unwinds_out_of_nested_calls() {
+    let src = concat!(
+        "func check(n: int) {\n",
+        "    if n > 1 {\n",
+        "        println(\"stopping at {n}\")\n",
+        "        exit(3)\n",
+        "    }\n",
+        "}\n",
+        "for i in 0..5 {\n",
+        "    check(i)\n",
+        "    println(i)\n",
+        "}\n",
+        "println(\"unreachable\")\n",
+    );
+    let (_, res) = run_with(src, Runtime::new);
+    let res = res.unwrap();
+    assert_eq!(res.exit_code, Some(3));
+    assert_eq!(res.output, "0\n1\nstopping at 2\n");
+}
+
+#[test]
+fn exit_from_main_and_without_code() {
+    let (_, res) = run_with("func main() {\n    println(\"hi\")\n    exit()\n}\n", Runtime::new);
+    let res = res.unwrap();
+    assert_eq!((res.output.as_str(), res.exit_code), ("hi\n", Some(0)));
+    let (_, res) = run_with("println(\"done\")\n", Runtime::new);
+    assert_eq!(res.unwrap().exit_code, None);
+}
+
+#[test]
+fn exit_code_must_fit_a_process_status() {
+    let (rt, res) = run_with("exit(256)\n", Runtime::new);
+    assert!(res.unwrap_err().contains("exit: code must be from 0 to 255, got 256"));
+    assert_eq!(rt.exit_requested(), None);
+    let (_, res) = run_with("exit(\"1\")\n", Runtime::new);
+    assert!(res.unwrap_err().contains("exit expects an int code, got"));
+}
+
+#[test]
+fn panic_fails_the_run_as_a_panic() {
+    let (rt, res) = run_with("println(\"a\")\npanic(\"boom {1 + 1}\")\n", Runtime::new);
+    assert!(res.unwrap_err().starts_with("panic: boom 2"));
+    assert!(rt.panicked());
+    let (rt, res) = run_with("let x = [1][3]\n", Runtime::new);
+    assert!(res.is_err());
+    assert!(!rt.panicked());
+}
@@ -0,0 +1,72 @@
+//! Needs the `ffi` feature: `cargo test -p xu_runtime --features ffi --test ffi`.
+//! Calls into the C and math libraries of glibc.
+#![cfg(target_os = "linux")]
+
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn run(rt: &mut Runtime, src: &str) -> Result<String, String> {
+    let cu = xu_driver::Driver::new().compile_text_no_analyze("main.xu", src).unwrap();
+    rt.exec_executable(&cu.executable).map(|r| r.output)
+}
+
+fn runtime(grant: bool) -> Runtime {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_capability_policy(Box::new(move |cap: &str| grant && cap == "ffi"));
+    rt
+}
+
+const CALLS: &str = r#"use "std/ffi" as ffi
+with_capability("ffi", || {
+    let libm = ffi.load("libm.so.6")
+    println(libm.symbol("cos", ["f64"], "f64").call([0]))
+    println(libm.symbol("ldexp", ["f64", "i32"], "f64").call([1.5, 3]))
+    println(libm.symbol("powf", ["f32", "f32"], "f32").call([2.0, 10]))
+    let libc = ffi.load("libc.so.6")
+    println(libc.symbol("abs", ["i32"], "i32").call([-7]))
+    println(libc.symbol("strlen", ["string"], "i64").call(["hello"]))
+    let buf = [0, 0, 0, 0]
+    libc.symbol("memset", ["bytes", "i32", "i64"], "ptr").call([buf, 65, 3])
+    println(buf)
+    libm.close()
+})
+"#;
+
+#[test]
+fn native_functions_are_called_under_the_grant() {
+    let mut rt = runtime(true);
+    assert_eq!(run(&mut rt, CALLS).unwrap(), "1\n12\n1024\n7\n5\n[65,65,65,0]\n");
+    let log = rt.capability_audit_log();
+    assert!(log.iter().all(|e| e.capability == "ffi" && e.granted));
+    assert!(log.iter().any(|e| e.target.as_deref() == Some("libc.so.6:strlen")));
+}
+
+#[test]
+fn calls_without_the_grant_are_denied() {
+    let err = run(&mut runtime(false), CALLS).unwrap_err();
+    assert!(err.contains("Capability denied: ffi"), "{err}");
+
+    let src = "use \"std/ffi\" as ffi\nffi.load(\"libm.so.6\")\n";
+    let err = run(&mut runtime(true), src).unwrap_err();
+    assert!(err.contains("Capability denied: ffi"), "{err}");
+}
+
+#[test]
+fn sandboxed_runtimes_refuse_native_calls() {
+    let mut rt = runtime(true);
+    rt.add_allowed_root(std::env::temp_dir().to_string_lossy().as_ref()).unwrap();
+    let err = run(&mut rt, CALLS).unwrap_err();
+    assert!(err.contains("native calls are disabled"), "{err}");
+}
+
+#[test]
+fn bad_signatures_are_rejected_where_declared() {
+    let src = "use \"std/ffi\" as ffi\nwith_capability(\"ffi\", || {\n    let libc = ffi.load(\"libc.so.6\")\n    libc.symbol(\"abs\", [\"int\"], \"i32\")\n})\n";
+    let err = run(&mut runtime(true), src).unwrap_err();
+    assert!(err.contains("ffi: abs: unknown type \"int\""), "{err}");
+
+    let src = "use \"std/ffi\" as ffi\nwith_capability(\"ffi\", || {\n    ffi.load(\"libc.so.6\").symbol(\"no_such_function\")\n})\n";
+    let err = run(&mut runtime(true), src).unwrap_err();
+    assert!(err.contains("no_such_function"), "{err}");
+}
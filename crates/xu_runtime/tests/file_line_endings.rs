@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::PathBuf;
+
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(rt: &mut Runtime, src: &str) -> Result<String, String> {
+    let cu = xu_driver::Driver::new()
+        .compile_text_no_analyze("main.xu", src)
+        .unwrap();
+    rt.exec_executable(&cu.executable).map(|r| r.output)
+}
+
+fn runtime() -> Runtime {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt
+}
+
+#[test]
+fn write_converts_line_endings() {
+    let dir = temp_dir("xu_runtime_file_line_endings_write");
+    let crlf = dir.join("crlf.txt").to_string_lossy().replace('\\', "/");
+    let lf = dir.join("lf.txt").to_string_lossy().replace('\\', "/");
+    let cr = dir.join("cr.txt").to_string_lossy().replace('\\', "/");
+    let src = format!(
+        "let a = open(\"{crlf}\", \"w\")\na.write(\"one\\ntwo\\n\", \"crlf\")\na.write(\"three\\r\\n\", \"crlf\")\n\
+         let b = open(\"{lf}\", \"w\")\nb.write(\"one\\r\\ntwo\\rthree\\n\")\n\
+         let c = open(\"{cr}\", \"w\")\nc.write(\"x\\ny\", \"cr\")\n"
+    );
+    run(&mut runtime(), &src).unwrap();
+    assert_eq!(fs::read(dir.join("crlf.txt")).unwrap(), b"one\r\ntwo\r\nthree\r\n");
+    assert_eq!(fs::read(dir.join("lf.txt")).unwrap(), b"one\ntwo\nthree\n");
+    assert_eq!(fs::read(dir.join("cr.txt")).unwrap(), b"x\ry");
+}
+
+#[test]
+fn read_can_normalize_line_endings() {
+    let dir = temp_dir("xu_runtime_file_line_endings_read");
+    let path = dir.join("in.txt");
+    fs::write(&path, "a\r\nb\rc\n").unwrap();
+    let path = path.to_string_lossy().replace('\\', "/");
+    let src = format!(
+        "let f = open(\"{path}\")\nprintln(f.read(\"lf\") == \"a\\nb\\nc\")\nprintln(f.read().contains(\"\\r\"))\n"
+    );
+    assert_eq!(run(&mut runtime(), &src).unwrap(), "true\ntrue\n");
+}
+
+#[test]
+fn write_requires_write_mode_and_known_newline() {
+    let dir = temp_dir("xu_runtime_file_line_endings_errors");
+    let path = dir.join("out.txt");
+    fs::write(&path, "x").unwrap();
+    let path = path.to_string_lossy().replace('\\', "/");
+
+    let err = run(&mut runtime(), &format!("open(\"{path}\").write(\"y\")\n")).unwrap_err();
+    assert!(err.contains("not opened for writing"), "{err}");
+    let err = run(&mut runtime(), &format!("open(\"{path}\", \"w\").write(\"y\", \"lfcr\")\n"))
+        .unwrap_err();
+    assert!(err.contains("unknown newline"), "{err}");
+    let err = run(&mut runtime(), &format!("open(\"{path}\", \"a\")\n")).unwrap_err();
+    assert!(err.contains("open mode"), "{err}");
+    assert_eq!(fs::read_to_string(dir.join("out.txt")).unwrap(), "x");
+}
+
+#[test]
+fn write_outside_allowed_roots_is_denied() {
+    let dir = temp_dir("xu_runtime_file_line_endings_sandbox");
+    let root = temp_dir("xu_runtime_file_line_endings_root");
+    let path = dir.join("out.txt").to_string_lossy().replace('\\', "/");
+    let mut rt = runtime();
+    rt.add_allowed_root(root.to_string_lossy().as_ref()).unwrap();
+    let err = run(&mut rt, &format!("open(\"{path}\", \"w\").write(\"y\")\n")).unwrap_err();
+    assert!(err.contains("Path is not within allowed roots"), "{err}");
+    assert!(!dir.join("out.txt").exists());
+}
+
+#[test]
+fn each_write_appends_after_the_first_truncates() {
+    let dir = temp_dir("xu_runtime_file_line_endings_append");
+    let path = dir.join("log.txt");
+    fs::write(&path, "old contents\n").unwrap();
+    let p = path.to_string_lossy().replace('\\', "/");
+    let src = format!(
+        "let f = open(\"{p}\", \"w\")\nfor i in 0..3 {{\n    f.write(\"line {{i}}\\n\")\n}}\nf.close()\n"
+    );
+    run(&mut runtime(), &src).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "line 0\nline 1\nline 2\n");
+    // Opening again for writing starts over.
+    run(&mut runtime(), &format!("let f = open(\"{p}\", \"w\")\nf.write(\"new\")\n")).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+}
+
+#[cfg(unix)]
+#[test]
+fn write_through_link_out_of_allowed_roots_is_denied() {
+    let root = temp_dir("xu_runtime_file_line_endings_link_root");
+    let outside = temp_dir("xu_runtime_file_line_endings_link_outside");
+    fs::write(outside.join("target.txt"), "keep").unwrap();
+    fs::write(root.join("inside.txt"), "").unwrap();
+    std::os::unix::fs::symlink(outside.join("target.txt"), root.join("out.txt")).unwrap();
+    std::os::unix::fs::symlink(outside.join("new.txt"), root.join("dangling.txt")).unwrap();
+    std::os::unix::fs::symlink(root.join("inside.txt"), root.join("in.txt")).unwrap();
+    let mut rt = runtime();
+    rt.add_allowed_root(root.to_string_lossy().as_ref()).unwrap();
+
+    for name in ["out.txt", "dangling.txt"] {
+        let p = root.join(name).to_string_lossy().replace('\\', "/");
+        let err = run(&mut rt, &format!("open(\"{p}\", \"w\").write(\"y\")\n")).unwrap_err();
+        assert!(err.contains("Path is not within allowed roots"), "{name}: {err}");
+    }
+    assert_eq!(fs::read_to_string(outside.join("target.txt")).unwrap(), "keep");
+    assert!(!outside.join("new.txt").exists());
+
+    // A link that stays inside the roots is fine.
+    let p = root.join("in.txt").to_string_lossy().replace('\\', "/");
+    run(&mut rt, &format!("open(\"{p}\", \"w\").write(\"y\")\n")).unwrap();
+    assert_eq!(fs::read_to_string(root.join("inside.txt")).unwrap(), "y");
+}
+
+#[cfg(unix)]
+#[test]
+fn append_through_link_out_of_allowed_roots_is_denied() {
+    let root = temp_dir("xu_runtime_file_line_endings_append_root");
+    let outside = temp_dir("xu_runtime_file_line_endings_append_outside");
+    fs::write(outside.join("target.txt"), "keep").unwrap();
+    let path = root.join("log.txt");
+    let p = path.to_string_lossy().replace('\\', "/");
+    let mut rt = runtime();
+    rt.add_allowed_root(root.to_string_lossy().as_ref()).unwrap();
+    let src = format!("let f = open(\"{p}\", \"w\")\nf.write(\"a\")\nfunc more() {{\n    f.write(\"b\")\n}}\n");
+    run(&mut rt, &src).unwrap();
+
+    // The next write appends, now through a link leading out of the root.
+    fs::remove_file(&path).unwrap();
+    std::os::unix::fs::symlink(outside.join("target.txt"), &path).unwrap();
+    let err = rt.call_global_host("more", vec![]).unwrap_err();
+    assert!(err.contains("Path is not within allowed roots"), "{err}");
+    assert_eq!(fs::read_to_string(outside.join("target.txt")).unwrap(), "keep");
+}
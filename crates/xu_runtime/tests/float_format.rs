@@ -0,0 +1,121 @@
+use xu_ir::Frontend;
+use xu_runtime::{FloatFormat, Runtime, format_f64_with};
+
+#[test]
+fn default_format_is_shortest_round_trip() {
+    let f = FloatFormat::default();
+    let cases = [
+        (0.1 + 0.2, "0.30000000000000004"),
+        (2.5, "2.5"),
+        (-3.0, "-3"),
+        (0.0, "0"),
+        (-0.0, "0"),
+        (1e20, "100000000000000000000"),
+        (1e21, "1e21"),
+        (1.5e300, "1.5e300"),
+        (1e-7, "1e-7"),
+        (1.5e-6, "0.0000015"),
+        (f64::NAN, "NaN"),
+        (f64::NEG_INFINITY, "-inf"),
+    ];
+    for (v, want) in cases {
+        assert_eq!(format_f64_with(v, &f), want, "{v:e}");
+    }
+}
+
+#[test]
+fn significant_digits_and_thresholds() {
+    let f = FloatFormat {
+        significant_digits: Some(3),
+        sci_min_exp: -3,
+        sci_max_exp: 6,
+    };
+    assert_eq!(format_f64_with(4.5678, &f), "4.57");
+    assert_eq!(format_f64_with(2.0, &f), "2");
+    assert_eq!(format_f64_with(-0.0012345, &f), "-0.00123");
+    assert_eq!(format_f64_with(0.00012345, &f), "1.23e-4");
+    assert_eq!(format_f64_with(123456.0, &f), "123000");
+    assert_eq!(format_f64_with(1234567.0, &f), "1.23e6");
+    assert_eq!(format_f64_with(9.999, &f), "10");
+}
+
+const SRC: &str = r#"let x = 1.23456;
+let big = 1e22;
+println(x);
+println("x={x}");
+println("v=" + to_text(x));
+println([x, big]);
+println(big);
+"#;
+
+fn run(format: Option<FloatFormat>, bytecode: bool) -> String {
+    let path = std::env::temp_dir().join("xu_runtime_float_format_tests.xu");
+    std::fs::write(&path, SRC).unwrap();
+    let path = path.to_string_lossy();
+    let driver = xu_driver::Driver::new();
+    let mut rt = Runtime::new();
+    if let Some(f) = format {
+        rt.set_float_format(f);
+    }
+    let res = if bytecode {
+        let cu = driver.compile_text_no_analyze(&path, SRC).unwrap();
+        rt.exec_executable(&cu.executable)
+    } else {
+        rt.exec_module(&driver.parse_file(&path, false).unwrap().module)
+    };
+    res.unwrap().output
+}
+
+#[test]
+fn print_interpolation_and_to_text_agree() {
+    let want_default = "1.23456\nx=1.23456\nv=1.23456\n[1.23456,1e22]\n1e22\n";
+    let f = FloatFormat {
+        significant_digits: Some(2),
+        ..FloatFormat::default()
+    };
+    let want = "1.2\nx=1.2\nv=1.2\n[1.2,1e22]\n1e22\n";
+    for bytecode in [false, true] {
+        assert_eq!(run(None, bytecode), want_default);
+        assert_eq!(run(Some(f), bytecode), want);
+    }
+}
+
+#[test]
+fn runtimes_on_one_thread_keep_their_own_format() {
+    let p = match xu_driver::Driver::new()
+        .compile_text_no_analyze("<fmt>", "println(2.0 / 3.0)\n")
+        .unwrap()
+        .executable
+    {
+        xu_ir::Executable::Bytecode(p) => p,
+        xu_ir::Executable::Ast(_) => panic!("expected bytecode"),
+    };
+    let mut short = Runtime::new();
+    short.set_float_format(FloatFormat {
+        significant_digits: Some(2),
+        ..FloatFormat::default()
+    });
+    let mut plain = Runtime::new();
+    for _ in 0..2 {
+        assert_eq!(short.exec_program(&p).unwrap().output, "0.67\n");
+        assert_eq!(
+            plain.exec_program(&p).unwrap().output,
+            "0.6666666666666666\n"
+        );
+        assert_eq!(short.exec_module(&p.module).unwrap().output, "0.67\n");
+    }
+}
+
+#[test]
+fn long_positional_output_is_not_truncated() {
+    let f = FloatFormat {
+        sci_min_exp: -40,
+        sci_max_exp: 40,
+        ..FloatFormat::default()
+    };
+    assert_eq!(
+        format_f64_with(1.5e-30, &f),
+        format!("0.{}15", "0".repeat(29))
+    );
+    assert_eq!(format_f64_with(-1e35, &f), format!("-1{}", "0".repeat(35)));
+}
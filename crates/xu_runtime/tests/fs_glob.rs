@@ -0,0 +1,55 @@
+use std::fs;
+
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn run_in(rt: &mut Runtime, src: &str) -> Result<String, String> {
+    let exe = xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile").executable;
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_stdlib_path(concat!(env!("CARGO_MANIFEST_DIR"), "/../../stdlib").to_string());
+    rt.exec_executable(&exe).map(|r| r.output)
+}
+
+fn tree(name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("xu_fs_glob_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    for file in ["a1.txt", "a2.txt", "b1.txt", "[x].txt", "sub/c.txt"] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+    dir.to_string_lossy().to_string()
+}
+
+#[test]
+fn glob_components_match_sets_and_single_characters() {
+    let dir = tree("sets");
+    let mut rt = Runtime::new();
+    let src = format!(
+        "println(__fs_glob(\"{dir}/a?.txt\"), __fs_glob(\"{dir}/[!a]*.txt\"), __fs_glob(\"{dir}/[a-b]1.*\"), __fs_glob(\"{dir}/*/*\"))\n"
+    );
+    let out = run_in(&mut rt, &src).unwrap();
+    let expected = format!(
+        "[{dir}/a1.txt,{dir}/a2.txt]\n[{dir}/[x].txt,{dir}/b1.txt]\n[{dir}/a1.txt,{dir}/b1.txt]\n[{dir}/sub/c.txt]\n"
+    );
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn walking_a_missing_directory_is_an_error() {
+    let dir = tree("missing");
+    let mut rt = Runtime::new();
+    let src = format!("use \"std/fs\" as fs\nfs.walk(\"{dir}/nope\").next()\n");
+    let err = run_in(&mut rt, &src).unwrap_err();
+    assert!(err.starts_with(&format!("fs.walk: cannot read {dir}/nope")), "{err}");
+}
+
+#[test]
+fn directories_outside_allowed_roots_are_refused() {
+    let dir = tree("roots");
+    let mut rt = Runtime::new();
+    let other = std::env::temp_dir().join(format!("xu_fs_glob_other_{}", std::process::id()));
+    fs::create_dir_all(&other).unwrap();
+    rt.add_allowed_root(other.to_string_lossy().as_ref()).unwrap();
+    let err = run_in(&mut rt, &format!("__fs_glob(\"{dir}/*.txt\")\n")).unwrap_err();
+    assert!(err.contains("Path is not within allowed roots"), "{err}");
+}
@@ -0,0 +1,60 @@
+use xu_ir::Frontend;
+use xu_runtime::{Runtime, RuntimeConfig};
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new()
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile")
+        .executable
+}
+
+/// A runtime that can import `std/...` modules.
+fn runtime_with_std() -> Runtime {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt
+}
+
+const CHURN: &str = "var n = 0\nfor i in 0..60000 {\n    let s = \"item {i}\"\n    n += s.length\n}\nprintln(n)\n";
+
+#[test]
+fn a_low_threshold_collects_more_often() {
+    let mut rt = Runtime::with_config(RuntimeConfig { gc_threshold: 1000, ..RuntimeConfig::default() });
+    rt.exec_executable(&compile(CHURN)).unwrap();
+    let low = rt.gc_stats();
+
+    let mut rt = Runtime::new();
+    rt.exec_executable(&compile(CHURN)).unwrap();
+    let default = rt.gc_stats();
+
+    assert!(low.collections > default.collections, "{low:?} vs {default:?}");
+    assert!(low.objects_freed > 0);
+    assert!(low.max_pause <= low.total_pause);
+}
+
+#[test]
+fn explicit_collections_are_counted() {
+    let mut rt = runtime_with_std();
+    let res = rt
+        .exec_executable(&compile("use \"std/gc\" as gc\ngc.collect()\ngc.collect()\nprintln(gc.stats()[\"collections\"])\n"))
+        .unwrap();
+    assert_eq!(res.output, "2\n");
+    assert_eq!(rt.gc_stats().collections, 2);
+}
+
+#[test]
+fn stats_restart_with_each_execution() {
+    let mut rt = Runtime::new();
+    rt.exec_executable(&compile("gc()\n")).unwrap();
+    assert_eq!(rt.gc_stats().collections, 1);
+    rt.exec_executable(&compile("println(1)\n")).unwrap();
+    assert_eq!(rt.gc_stats().collections, 0);
+}
+
+#[test]
+fn collecting_inside_a_module_function_keeps_the_callers_bindings() {
+    let mut rt = runtime_with_std();
+    let src = "use \"std/gc\" as gc\nlet keep = [\"a\", \"b\"]\ngc.collect()\nprintln(keep)\nprintln(\"done\")\n";
+    let res = rt.exec_executable(&compile(src)).unwrap();
+    assert_eq!(res.output, "[a,b]\ndone\n");
+}
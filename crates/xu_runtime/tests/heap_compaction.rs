@@ -0,0 +1,123 @@
+use std::fs;
+
+use xu_ir::Frontend;
+use xu_runtime::{Runtime, RuntimeConfig};
+
+// Survivors end up scattered between many dead objects and are reached
+// through env slots, dict string keys, struct fields, option payloads and
+// closure captures.
+const SRC: &str = r#"
+Point has { x: int, label: string }
+
+let keep: {string: string} = {}
+let points: [Point] = []
+var junk: [string] = []
+for i in 0..20000 {
+    junk.push("junk-{i}")
+    if i % 500 == 0 {
+        keep["k{i}"] = "v{i}"
+        points.push(Point{ x: i, label: "p{i}" })
+    }
+}
+junk = ["x"]
+let tag = "prefix"
+let add = |s| tag + ":" + s
+let maybe = Option#some(["inner", "list"])
+
+func summary() {
+    var total = 0
+    for p in points {
+        total = total + p.x + p.label.length()
+    }
+    var inner = "none"
+    match maybe {
+        Option#some(l) { inner = l.join("+") }
+        _ { inner = "none" }
+    }
+    let last = keep["k19500"]
+    let added = add("x")
+    return "{last} {keep.length()} {total} {added} {inner}"
+}
+
+func report() {
+    println(summary())
+}
+
+func echo(s) {
+    println(s)
+}
+"#;
+
+const EXPECTED: &str = "v19500 40 390216 prefix:x inner+list";
+
+fn runtime(name: &str, config: RuntimeConfig, ast: bool) -> Runtime {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, SRC).unwrap();
+    let path = path.to_string_lossy().to_string();
+    let mut rt = Runtime::with_config(config);
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let driver = xu_driver::Driver::new();
+    if ast {
+        let parsed = driver.parse_file(&path, false).unwrap();
+        rt.exec_module(&parsed.module).unwrap();
+    } else {
+        let cu = driver.compile_text_no_analyze(&path, SRC).unwrap();
+        rt.exec_executable(&cu.executable).unwrap();
+    }
+    rt
+}
+
+fn report(rt: &mut Runtime) -> String {
+    rt.call_global("report", &[]).unwrap();
+    rt.take_output().trim_end().to_string()
+}
+
+fn check_compaction(name: &str, ast: bool) {
+    let mut rt = runtime(name, RuntimeConfig::default(), ast);
+    assert_eq!(report(&mut rt), EXPECTED);
+    let held = rt.call_global("summary", &[]).unwrap();
+
+    let epoch = rt.heap_epoch();
+    let mut roots = [held];
+    let stats = rt.compact_heap(&mut roots).unwrap();
+    assert!(stats.moved > 0, "{stats:?}");
+    assert!(stats.slots_after < stats.slots_before, "{stats:?}");
+    assert_eq!(stats.slots_after, stats.live);
+    assert!(rt.heap_epoch() > epoch);
+
+    assert_eq!(report(&mut rt), EXPECTED);
+    rt.call_global("echo", &roots).unwrap();
+    assert_eq!(rt.take_output().trim_end(), EXPECTED);
+
+    // Nothing left to move: the epoch stays put.
+    let epoch = rt.heap_epoch();
+    let again = rt.compact_heap(&mut roots).unwrap();
+    assert_eq!(again.moved, 0);
+    assert_eq!(rt.heap_epoch(), epoch);
+    assert_eq!(report(&mut rt), EXPECTED);
+}
+
+#[test]
+fn compaction_keeps_live_objects_reachable_vm() {
+    check_compaction("xu_runtime_heap_compaction_vm.xu", false);
+}
+
+#[test]
+fn compaction_keeps_live_objects_reachable_ast() {
+    check_compaction("xu_runtime_heap_compaction_ast.xu", true);
+}
+
+#[test]
+fn configured_compaction_runs_after_collections() {
+    let config = RuntimeConfig { heap_compaction: true, ..RuntimeConfig::default() };
+    let mut rt = runtime("xu_runtime_heap_compaction_auto.xu", config, false);
+    let epoch = rt.heap_epoch();
+    rt.gc(&[]);
+    assert_eq!(report(&mut rt), EXPECTED);
+    assert!(rt.heap_epoch() > epoch);
+
+    // Without a collection since the last compaction nothing happens.
+    let epoch = rt.heap_epoch();
+    assert_eq!(report(&mut rt), EXPECTED);
+    assert_eq!(rt.heap_epoch(), epoch);
+}
@@ -0,0 +1,94 @@
+use xu_ir::Frontend;
+use xu_runtime::{HeapSnapshot, Runtime};
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new()
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile")
+        .executable
+}
+
+fn snapshot_after(src: &str) -> HeapSnapshot {
+    let mut rt = Runtime::new();
+    rt.exec_executable(&compile(src)).unwrap();
+    rt.heap_snapshot()
+}
+
+const CACHE: &str = "var cache: {string: string} = {}\nfor i in 0..200 {\n    cache[\"k{i}\"] = \"value number {i}\"\n}\n";
+
+#[test]
+fn a_global_cache_retains_its_entries() {
+    let snap = snapshot_after(CACHE);
+    let dict = snap.kinds.iter().find(|k| k.kind == "dict").expect("dict kind");
+    let strings = snap.kinds.iter().find(|k| k.kind == "string").expect("string kind");
+    assert_eq!(dict.count, 1);
+    assert_eq!(dict.path, ["global cache"]);
+    assert!(strings.count >= 400, "{strings:?}");
+    assert!(dict.retained_bytes >= dict.shallow_bytes + strings.shallow_bytes / 2, "{dict:?} {strings:?}");
+    assert_eq!(strings.path.first().map(String::as_str), Some("global cache"));
+
+    let top = &snap.largest[0];
+    assert_eq!(top.kind, "dict");
+    assert_eq!(top.path, ["global cache"]);
+    assert_eq!(top.retained_bytes, dict.retained_bytes);
+}
+
+#[test]
+fn paths_name_struct_fields_and_list_indices() {
+    let src = "Holder has {\n    items: [[int]]\n}\nlet h = Holder{ items: [[1], [2, 3]] }\n";
+    let snap = snapshot_after(src);
+    let holder = snap.kinds.iter().find(|k| k.kind == "Holder").expect("Holder kind");
+    assert_eq!(holder.count, 1);
+    assert_eq!(holder.path, ["global h"]);
+    let lists = snap.kinds.iter().find(|k| k.kind == "list").expect("list kind");
+    assert_eq!(lists.count, 3);
+    // Nested lists count once toward the kind.
+    assert!(lists.retained_bytes <= lists.shallow_bytes, "{lists:?}");
+    assert_eq!(lists.path, ["global h", ".items"]);
+}
+
+#[test]
+fn closures_are_grouped_by_definition() {
+    let src = "func make_handler(n: int) {\n    return |x: int| x + n\n}\nlet handlers = [make_handler(1), make_handler(2)]\nprintln(handlers[1](1))\n";
+    let mut rt = Runtime::new();
+    let exe = match compile(src) {
+        xu_ir::Executable::Bytecode(p) => xu_ir::Executable::Ast(p.module),
+        exe => exe,
+    };
+    assert_eq!(rt.exec_executable(&exe).unwrap().output, "3\n");
+    let snap = rt.heap_snapshot();
+    let closures: Vec<_> = snap.kinds.iter().filter(|k| k.kind.starts_with("fn __anon")).collect();
+    assert_eq!(closures.len(), 1, "{snap:?}");
+    assert_eq!(closures[0].count, 2);
+    assert_eq!(closures[0].path[0], "global handlers");
+    assert!(snap.kinds.iter().any(|k| k.kind == "fn make_handler"), "{snap:?}");
+}
+
+#[test]
+fn the_snapshot_leaves_garbage_for_the_collector() {
+    let mut rt = Runtime::new();
+    rt.exec_executable(&compile("for i in 0..100 {\n    let s = \"temp {i}\"\n}\n")).unwrap();
+    let before = rt.heap_snapshot();
+    assert!(before.unreachable_objects > 0, "{before:?}");
+    assert_eq!(rt.heap_snapshot(), before);
+    rt.gc(&[]);
+    let after = rt.heap_snapshot();
+    assert_eq!(after.unreachable_objects, 0);
+    assert_eq!(after.objects, before.objects);
+}
+
+#[test]
+fn json_carries_the_same_data() {
+    let snap = snapshot_after(CACHE);
+    let json: serde_json::Value = serde_json::from_str(&snap.to_json()).expect("valid json");
+    assert_eq!(json["objects"], snap.objects);
+    assert_eq!(json["bytes"], snap.bytes);
+    assert_eq!(json["kinds"].as_array().unwrap().len(), snap.kinds.len());
+    assert_eq!(json["kinds"][0]["kind"], snap.kinds[0].kind.as_str());
+    assert_eq!(json["kinds"][0]["retained_bytes"], snap.kinds[0].retained_bytes);
+    assert_eq!(json["largest"][0]["path"][0], "global cache");
+
+    let text = snap.to_text();
+    assert!(text.starts_with(&format!("heap: {} live objects", snap.objects)), "{text}");
+    assert!(text.contains("global cache -> [\""), "{text}");
+}
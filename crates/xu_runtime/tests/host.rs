@@ -0,0 +1,133 @@
+use xu_runtime::{BuiltinProvider, BuiltinRegistry, HostValue, Runtime, Value};
+
+fn runtime(src: &str) -> Runtime {
+    let driver = xu_driver::Driver::new();
+    let compiled = driver.compile_text("<host>", src, true).unwrap();
+    assert!(compiled.diagnostics.is_empty(), "{:?}", compiled.diagnostics);
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.exec_executable(&compiled.executable).unwrap();
+    rt
+}
+
+#[test]
+fn host_values_round_trip_through_scripts() {
+    let mut rt = runtime(
+        "P has {\n  x: int\n  y: string\n}\nShape with [ dot | square(side: int) ]\n\
+         func echo(v: any) -> any { return v }\n\
+         func make() -> (P, Shape, Option[int], Option[int]) { return (P{ x: 1, y: \"a\" }, Shape#square(3), Option#some(2), Option#none) }\n",
+    );
+    let made = rt.call_global_host("make", vec![]).unwrap();
+    let HostValue::Tuple(items) = &made else { panic!("{made:?}") };
+    assert_eq!(
+        items[0],
+        HostValue::Struct { ty: "P".into(), fields: vec![("x".into(), HostValue::Int(1)), ("y".into(), HostValue::Str("a".into()))] }
+    );
+    assert_eq!(
+        items[1],
+        HostValue::Enum { ty: "Shape".into(), variant: "square".into(), payload: vec![HostValue::Int(3)] }
+    );
+    assert_eq!(items[2], HostValue::Option(Some(Box::new(HostValue::Int(2)))));
+    assert_eq!(items[3], HostValue::Option(None));
+    assert_eq!(rt.call_global_host("echo", vec![made.clone()]).unwrap(), made);
+
+    let nested = HostValue::Dict(vec![
+        (HostValue::Str("xs".into()), HostValue::List(vec![HostValue::Float(0.5), HostValue::Bool(true), HostValue::Unit])),
+        (HostValue::Int(7), HostValue::Range(1, 4, false)),
+    ]);
+    assert_eq!(rt.call_global_host("echo", vec![nested.clone()]).unwrap(), nested);
+}
+
+#[test]
+fn values_without_a_host_form_are_rejected() {
+    let mut rt = runtime("func one() -> int { return 1 }\nfunc f() -> any { return one }\nfunc echo(v: any) -> any { return v }\n");
+    let err = rt.call_global_host("f", vec![]).unwrap_err();
+    assert!(err.contains("Cannot pass a"), "{err}");
+    let key = HostValue::List(vec![]);
+    let err = rt.call_global_host("echo", vec![HostValue::Dict(vec![(key, HostValue::Unit)])]).unwrap_err();
+    assert!(err.contains("hashable"), "{err}");
+}
+
+#[test]
+fn ints_beyond_48_bits_are_rejected() {
+    let mut rt = runtime("func echo(v: any) -> any { return v }\n");
+    for i in [*xu_runtime::INT_RANGE.start(), *xu_runtime::INT_RANGE.end()] {
+        assert_eq!(rt.call_global_host("echo", vec![HostValue::Int(i)]).unwrap(), HostValue::Int(i));
+    }
+    for i in [xu_runtime::INT_RANGE.end() + 1, xu_runtime::INT_RANGE.start() - 1, i64::MAX] {
+        let err = rt.call_global_host("echo", vec![HostValue::Int(i)]).unwrap_err();
+        assert!(err.contains("48 bits"), "{err}");
+        let err = rt.call_global_host("echo", vec![HostValue::List(vec![HostValue::Range(0, i, false)])]).unwrap_err();
+        assert!(err.contains("48 bits"), "{err}");
+    }
+}
+
+#[test]
+fn scripts_call_host_functions() {
+    let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let seen = calls.clone();
+    rt.define_host_function("scale", move |args| {
+        seen.set(seen.get() + 1);
+        match args {
+            [HostValue::List(xs), HostValue::Int(k)] => Ok(HostValue::List(
+                xs.iter().map(|x| if let HostValue::Int(x) = x { HostValue::Int(x * k) } else { x.clone() }).collect(),
+            )),
+            _ => Err("scale: expected a list and an int".into()),
+        }
+    });
+    rt.define_host_function("fail", |_| Err("host says no".into()));
+    assert_eq!(rt.host_function_names(), ["scale", "fail"]);
+
+    let src = "let xs = scale([1, 2, 3], 10)\nprintln(xs[2])\nprintln(scale([4], 2).length)\n";
+    let driver = xu_driver::Driver::new();
+    let err = driver.compile_text("<host>", src, true).unwrap().diagnostics;
+    assert!(err.iter().any(|d| d.message.contains("scale")), "{err:?}");
+    let compiled = driver.compile_text_with_predefs("<host>", src, true, &rt.host_function_names()).unwrap();
+    assert!(compiled.diagnostics.is_empty(), "{:?}", compiled.diagnostics);
+    assert_eq!(rt.exec_executable(&compiled.executable).unwrap().output, "30\n1\n");
+    assert_eq!(calls.get(), 2);
+
+    // Host errors are runtime errors, and definitions survive resets.
+    rt.reset();
+    let compiled = xu_driver::Driver::new().compile_text_with_predefs("<host>", "fail()\n", true, &["fail"]).unwrap();
+    let err = rt.exec_executable(&compiled.executable).unwrap_err();
+    assert!(err.contains("host says no"), "{err}");
+    let err = rt.call_global_host("scale", vec![HostValue::Unit]).unwrap_err();
+    assert!(err.contains("expected a list"), "{err}");
+}
+
+struct Twice;
+
+impl BuiltinProvider for Twice {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("twice", |_, args| Ok(Value::from_i64(args[0].as_i64() * 2)));
+        registry.register("abs", |_, _| Ok(Value::from_i64(-1)));
+    }
+}
+
+#[test]
+fn added_providers_install_builtins() {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.add_builtin_provider(Twice);
+    assert_eq!(rt.extra_builtin_names(), ["twice", "abs"]);
+
+    // Imports see the driver's predefined names too.
+    let dir = std::env::temp_dir().join(format!("xu_host_providers_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("lib.xu"), "pub func quad(x: int) -> int { return twice(twice(x)) }\n").unwrap();
+    let main = dir.join("main.xu");
+    std::fs::write(&main, "use \"lib.xu\" as lib;\nprintln(lib.quad(3))\nprintln(abs(5))\n").unwrap();
+    let main = main.to_string_lossy();
+    let driver = xu_driver::Driver::new();
+    let diags = driver.compile_file(&main, true).unwrap().diagnostics;
+    assert!(diags.iter().any(|d| d.message.contains("twice")), "{diags:?}");
+    let driver = xu_driver::Driver::new();
+    driver.add_predefs(rt.extra_builtin_names());
+    let compiled = driver.compile_file(&main, true).unwrap();
+    assert!(compiled.diagnostics.is_empty(), "{:?}", compiled.diagnostics);
+    rt.set_entry_path(&main).unwrap();
+    assert_eq!(rt.exec_executable(&compiled.executable).unwrap().output, "12\n-1\n");
+}
@@ -0,0 +1,141 @@
+use std::fs;
+
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn setup(name: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::create_dir_all(&dir);
+    let lib = dir.join("lib.xu");
+    let main = dir.join("main.xu");
+    fs::write(&lib, "pub func greet() { return \"v1\"; }\npub let version = 1;\n").unwrap();
+    fs::write(
+        &main,
+        "use \"lib.xu\" as m;\nlet held = m.greet;\nfunc tick() { println(m.greet() + \" \" + held() + \" {m.version}\"); }\n",
+    )
+    .unwrap();
+    (lib, main)
+}
+
+fn run_reload(rt: &mut Runtime, lib: &std::path::Path) {
+    rt.call_global("tick", &[]).unwrap();
+    assert_eq!(rt.take_output().trim_end(), "v1 v1 1");
+
+    fs::write(lib, "pub func greet() { return \"v2\"; }\npub let version = 2;\n").unwrap();
+    rt.reload_module(lib.to_string_lossy().as_ref()).unwrap();
+
+    rt.call_global("tick", &[]).unwrap();
+    assert_eq!(rt.take_output().trim_end(), "v2 v2 2");
+}
+
+#[test]
+fn reload_swaps_exported_functions_in_place_ast() {
+    let (lib, main) = setup("xu_runtime_hot_reload_ast_tests");
+    let driver = xu_driver::Driver::new();
+    let parsed = driver
+        .parse_file(main.to_string_lossy().as_ref(), false)
+        .unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(main.to_string_lossy().as_ref()).unwrap();
+    rt.exec_module(&parsed.module).unwrap();
+    run_reload(&mut rt, &lib);
+}
+
+#[test]
+fn reload_swaps_exported_functions_in_place_vm() {
+    let (lib, main) = setup("xu_runtime_hot_reload_vm_tests");
+    let driver = xu_driver::Driver::new();
+    let src = fs::read_to_string(&main).unwrap();
+    let cu = driver
+        .compile_text_no_analyze(main.to_string_lossy().as_ref(), &src)
+        .unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(main.to_string_lossy().as_ref()).unwrap();
+    rt.exec_executable(&cu.executable).unwrap();
+    run_reload(&mut rt, &lib);
+}
+
+#[test]
+fn failed_reload_keeps_previous_module() {
+    let (lib, main) = setup("xu_runtime_hot_reload_error_tests");
+    let driver = xu_driver::Driver::new();
+    let parsed = driver
+        .parse_file(main.to_string_lossy().as_ref(), false)
+        .unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(main.to_string_lossy().as_ref()).unwrap();
+    rt.exec_module(&parsed.module).unwrap();
+
+    fs::write(&lib, "pub func greet( {\n").unwrap();
+    assert!(rt.reload_module(lib.to_string_lossy().as_ref()).is_err());
+
+    rt.call_global("tick", &[]).unwrap();
+    assert_eq!(rt.take_output().trim_end(), "v1 v1 1");
+}
+
+fn setup_struct(name: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::create_dir_all(&dir);
+    let lib = dir.join("shapes.xu");
+    let main = dir.join("main.xu");
+    fs::write(
+        &lib,
+        "pub Point has {\n    x: int\n    y: int\n}\npub func make() { return Point{ x: 1, y: 2 }; }\n",
+    )
+    .unwrap();
+    fs::write(
+        &main,
+        "use \"shapes.xu\" as s;\nlet old = s.make();\nfunc read(p) { return p.y; }\nfunc tick() { println(\"{read(old)} {read(s.make())}\"); }\n",
+    )
+    .unwrap();
+    (lib, main)
+}
+
+fn run_struct_reload(rt: &mut Runtime, lib: &std::path::Path) {
+    rt.call_global("tick", &[]).unwrap();
+    assert_eq!(rt.take_output().trim_end(), "2 2");
+
+    fs::write(
+        lib,
+        "pub Point has {\n    y: int\n    z: int\n    x: int\n}\npub func make() { return Point{ x: 4, y: 5, z: 6 }; }\n",
+    )
+    .unwrap();
+    rt.reload_module(lib.to_string_lossy().as_ref()).unwrap();
+
+    for _ in 0..3 {
+        rt.call_global("tick", &[]).unwrap();
+        assert_eq!(rt.take_output().trim_end(), "2 5");
+    }
+}
+
+#[test]
+fn struct_redefinition_does_not_reuse_stale_field_ics_ast() {
+    let (lib, main) = setup_struct("xu_runtime_hot_reload_struct_ast_tests");
+    let driver = xu_driver::Driver::new();
+    let parsed = driver
+        .parse_file(main.to_string_lossy().as_ref(), false)
+        .unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(main.to_string_lossy().as_ref()).unwrap();
+    rt.exec_module(&parsed.module).unwrap();
+    run_struct_reload(&mut rt, &lib);
+}
+
+#[test]
+fn struct_redefinition_does_not_reuse_stale_field_ics_vm() {
+    let (lib, main) = setup_struct("xu_runtime_hot_reload_struct_vm_tests");
+    let driver = xu_driver::Driver::new();
+    let src = fs::read_to_string(&main).unwrap();
+    let cu = driver
+        .compile_text_no_analyze(main.to_string_lossy().as_ref(), &src)
+        .unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(main.to_string_lossy().as_ref()).unwrap();
+    rt.exec_executable(&cu.executable).unwrap();
+    run_struct_reload(&mut rt, &lib);
+}
@@ -0,0 +1,107 @@
+use xu_ir::Frontend;
+use xu_runtime::{IcKind, IcStats, Runtime, RuntimeConfig};
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new()
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile")
+        .executable
+}
+
+fn stats_after(mut rt: Runtime, exe: &xu_ir::Executable) -> IcStats {
+    rt.set_ic_stats(true);
+    rt.exec_executable(exe).unwrap();
+    rt.ic_stats()
+}
+
+const SHAPES: &str = "Circle has {\n    r: int\n\n    func area() {\n        return self.r * self.r * 3\n    }\n}\nSquare has {\n    s: int\n\n    func area() {\n        return self.s * self.s\n    }\n}\nlet shapes = [Circle{ r: 1 }, Square{ s: 2 }, Circle{ r: 3 }]\nvar total = 0\nfor i in 0..300 {\n    let sh = shapes[i % 3]\n    total += sh.area()\n}\nlet c = Circle{ r: 2 }\nfor i in 0..100 {\n    total += c.r\n}\nprintln(total)\n";
+
+#[test]
+fn polymorphic_method_sites_are_reported_with_their_receivers() {
+    let stats = stats_after(Runtime::new(), &compile(SHAPES));
+    let poly: Vec<_> = stats.polymorphic().collect();
+    assert_eq!(poly.len(), 1, "{stats:?}");
+    assert_eq!(poly[0].kind, IcKind::Method);
+    assert_eq!(poly[0].name, "area");
+    assert_eq!(poly[0].shapes, ["Circle", "Square"]);
+    assert_eq!(poly[0].lookups(), 300);
+    assert!(poly[0].misses > poly[0].hits, "{:?}", poly[0]);
+
+    // Field reads in different methods have slots of their own.
+    let fields: Vec<_> = stats.sites.iter().filter(|s| s.kind == IcKind::Field).collect();
+    assert!(fields.iter().any(|s| s.name == "r" && s.shapes == ["Circle"]));
+    assert!(fields.iter().any(|s| s.name == "s" && s.shapes == ["Square"]));
+    let field = stats.kinds.iter().find(|k| k.kind == IcKind::Field).expect("field kind");
+    assert!(field.hits > 95 * (field.hits + field.misses) / 100, "{field:?}");
+    assert_eq!(stats.sites[0].lookups(), 300);
+}
+
+#[test]
+fn counting_is_off_by_default() {
+    let mut rt = Runtime::new();
+    rt.exec_executable(&compile(SHAPES)).unwrap();
+    assert_eq!(rt.ic_stats(), IcStats::default());
+}
+
+#[test]
+fn counts_survive_collections_and_restart_with_each_execution() {
+    let exe = compile(SHAPES);
+    let config = RuntimeConfig { gc_threshold: 20, ..RuntimeConfig::default() };
+    let mut rt = Runtime::with_config(config);
+    rt.set_ic_stats(true);
+    rt.exec_executable(&exe).unwrap();
+    assert!(rt.gc_stats().collections > 0);
+    let first = rt.ic_stats();
+    assert_eq!(first.polymorphic().next().map(|s| s.lookups()), Some(300));
+    rt.exec_executable(&exe).unwrap();
+    assert_eq!(rt.ic_stats().sites.len(), first.sites.len());
+    assert_eq!(rt.ic_stats().polymorphic().next().map(|s| s.lookups()), Some(300));
+    rt.reset();
+    rt.exec_executable(&exe).unwrap();
+    assert!(!rt.ic_stats().sites.is_empty());
+}
+
+#[test]
+fn dict_fast_paths_are_counted() {
+    let src = "func fill() {\n    var d: {string: int} = {}\n    for i in 0..50 {\n        d.insert(\"k\", i)\n    }\n    for i in 0..50 {\n        let v = d.get(\"k\")\n    }\n    return d.length\n}\nprintln(fill())\n";
+    let stats = stats_after(Runtime::new(), &compile(src));
+    let kind = |kind| stats.kinds.iter().find(|k| k.kind == kind).unwrap_or_else(|| panic!("{kind:?} in {stats:?}"));
+    let insert = kind(IcKind::DictInsert);
+    assert_eq!((insert.hits, insert.misses), (49, 1));
+    let get = kind(IcKind::DictGet);
+    assert_eq!((get.hits, get.misses), (49, 1));
+}
+
+#[test]
+fn the_tree_walker_is_counted() {
+    let ast = |src: &str| match compile(src) {
+        xu_ir::Executable::Bytecode(p) => xu_ir::Executable::Ast(p.module),
+        exe => exe,
+    };
+    let stats = stats_after(Runtime::new(), &ast(SHAPES));
+    let site = stats.polymorphic().next().expect("polymorphic site");
+    assert_eq!((site.kind, site.name.as_str()), (IcKind::Method, "area"));
+    assert_eq!(site.shapes, ["Circle", "Square"]);
+
+    let src = "let e = {\"a\": 1}\nvar n = 0\nfor i in 0..50 {\n    n += e[\"a\"] + e.a\n}\n";
+    let stats = stats_after(Runtime::new(), &ast(src));
+    for kind in [IcKind::Index, IcKind::Field] {
+        let k = stats.kinds.iter().find(|k| k.kind == kind).unwrap_or_else(|| panic!("{kind:?} in {stats:?}"));
+        assert_eq!((k.hits, k.misses), (49, 1), "{k:?}");
+    }
+}
+
+#[test]
+fn reports_as_text_and_json() {
+    let stats = stats_after(Runtime::new(), &compile(SHAPES));
+    let text = stats.to_text(10);
+    assert!(text.starts_with("inline caches: "), "{text}");
+    assert!(text.contains("polymorphic sites:\n  method area (slot "), "{text}");
+    assert!(text.contains("Circle, Square"), "{text}");
+
+    let json: serde_json::Value = serde_json::from_str(&stats.to_json()).expect("valid json");
+    assert_eq!(json["sites"].as_array().unwrap().len(), stats.sites.len());
+    assert_eq!(json["sites"][0]["kind"], "method");
+    assert_eq!(json["sites"][0]["shapes"][1], "Square");
+    assert_eq!(json["kinds"][0]["kind"], "field");
+}
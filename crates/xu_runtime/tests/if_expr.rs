@@ -0,0 +1,37 @@
+mod common;
+use common::run;
+
+#[test]
+fn if_gives_a_value_in_let_assignment_and_arguments() {
+    let src = concat!(
+        "func grade(score: int) -> string {\n",
+        "    return if score > 90: \"A\" else if score > 80: \"B\" else: \"C\"\n",
+        "}\n",
+        "let label = if 95 > 90: \"A\" else: \"B\"\n",
+        "var n = 0\n",
+        "n = if label == \"A\" { 10 } else { 20 }\n",
+        "println(label, n, grade(85), grade(10))\n",
+        "println(n + if n > 5: 1 else: 2)\n",
+        "let long = if n > 5:\n",
+        "    \"big\"\n",
+        "else:\n",
+        "    \"small\"\n",
+        "println(long)\n",
+    );
+    assert_eq!(run(src).unwrap(), "A\n10\nB\nC\n11\nbig\n");
+}
+
+#[test]
+fn only_the_taken_branch_is_evaluated() {
+    let src = concat!(
+        "func loud(s: string) -> string {\n",
+        "    println(\"eval {s}\")\n",
+        "    return s\n",
+        "}\n",
+        "let xs = [1, 2, 3]\n",
+        "let v = if xs.length > 5: xs[9] else: loud(\"else\")\n",
+        "let f = |x| if x > 0: loud(\"pos\") else: loud(\"neg\")\n",
+        "println(v, f(-1))\n",
+    );
+    assert_eq!(run(src).unwrap(), "eval else\neval neg\nelse\nneg\n");
+}
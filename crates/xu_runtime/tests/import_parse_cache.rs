@@ -18,7 +18,7 @@ fn import_parse_cache_invalidates_on_file_change() {
     let _ = fs::create_dir_all(&dir);
 
     let dep = dir.join("dep.xu");
-    fs::write(&dep, "x @ 1;").unwrap();
+    fs::write(&dep, "x $ 1;").unwrap();
 
     let dep_path = dep.to_string_lossy();
     let main_src = format!(
@@ -44,3 +44,23 @@ println(dep.value);
     let res2 = rt.exec_module(&module).unwrap();
     assert_eq!(res2.output.trim_end(), "1");
 }
+
+#[test]
+fn import_parse_cache_notices_edits_that_keep_size_and_modification_time() {
+    let dir = std::env::temp_dir().join("xu_runtime_import_parse_cache_hash_tests");
+    let _ = fs::create_dir_all(&dir);
+
+    let dep = dir.join("dep.xu");
+    fs::write(&dep, "pub let value = 1;").unwrap();
+    let modified = fs::metadata(&dep).unwrap().modified().unwrap();
+
+    let dep_path = dep.to_string_lossy();
+    let module = parse_source(&format!("use \"{dep_path}\" as dep;\nprintln(dep.value);\n"));
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    assert_eq!(rt.exec_module(&module).unwrap().output.trim_end(), "1");
+
+    fs::write(&dep, "pub let value = 2;").unwrap();
+    fs::File::options().write(true).open(&dep).unwrap().set_modified(modified).unwrap();
+    assert_eq!(rt.exec_module(&module).unwrap().output.trim_end(), "2");
+}
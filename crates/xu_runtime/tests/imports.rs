@@ -77,40 +77,62 @@ use "{path}";
 }
 
 #[test]
-fn circular_import_is_reported_with_chain() {
+fn circular_import_binds_partially_initialized_module() {
     let dir = std::env::temp_dir().join("xu_runtime_circular_import_tests");
     let _ = fs::create_dir_all(&dir);
     let a = dir.join("a.xu");
     let b = dir.join("b.xu");
 
-    fs::write(&a, "").unwrap();
-    fs::write(&b, "").unwrap();
-
     let a_path = a.to_string_lossy().to_string();
     let b_path = b.to_string_lossy().to_string();
 
-    fs::write(&a, format!("use \"{b_path}\";")).unwrap();
-    fs::write(&b, format!("use \"{a_path}\";")).unwrap();
+    fs::write(
+        &a,
+        format!(
+            "use \"{b_path}\" as b;\npub func name() {{ return \"a\"; }}\npub func greet() {{ return b.name(); }}\n"
+        ),
+    )
+    .unwrap();
+    fs::write(
+        &b,
+        format!(
+            "use \"{a_path}\" as a;\npub func name() {{ return \"b\" + a.name(); }}\n"
+        ),
+    )
+    .unwrap();
 
-    let a_key = std::fs::canonicalize(&a)
-        .unwrap()
-        .to_string_lossy()
-        .to_string();
-    let b_key = std::fs::canonicalize(&b)
-        .unwrap()
-        .to_string_lossy()
-        .to_string();
+    let main_src = format!("use \"{a_path}\" as a;\nprintln(a.greet());");
+    let module = parse_source(&main_src);
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let res = rt.exec_module(&module).unwrap();
+    assert_eq!(res.output.trim_end(), "ba");
+}
+
+#[test]
+fn circular_import_members_are_unavailable_during_init() {
+    let dir = std::env::temp_dir().join("xu_runtime_circular_import_init_tests");
+    let _ = fs::create_dir_all(&dir);
+    let a = dir.join("a.xu");
+    let b = dir.join("b.xu");
+
+    let a_path = a.to_string_lossy().to_string();
+    let b_path = b.to_string_lossy().to_string();
+
+    fs::write(
+        &a,
+        format!("use \"{b_path}\" as b;\npub func name() {{ return \"a\"; }}\n"),
+    )
+    .unwrap();
+    fs::write(&b, format!("use \"{a_path}\" as a;\nprintln(a.name());\n")).unwrap();
 
     let main_src = format!("use \"{a_path}\";");
     let module = parse_source(&main_src);
     let mut rt = Runtime::new();
     rt.set_frontend(Box::new(xu_driver::Driver::new()));
     let err = rt.exec_module(&module).unwrap_err();
-    assert!(err.contains("Circular import:"), "error was: {err}");
-    assert!(
-        err.contains(&format!("{a_key} -> {b_key} -> {a_key}")),
-        "error was: {err}"
-    );
+    assert!(err.contains("Unknown member: name"), "error was: {err}");
+    assert!(!err.contains("Circular import"), "error was: {err}");
 }
 
 #[test]
@@ -149,7 +171,7 @@ fn import_stack_is_cleared_on_import_error() {
     let _ = fs::create_dir_all(&dir);
     let bad = dir.join("bad.xu");
 
-    fs::write(&bad, "x @ 1;").unwrap();
+    fs::write(&bad, "x $ 1;").unwrap();
     let path = bad.to_string_lossy();
 
     let main_src = format!(r#"use "{path}";"#);
@@ -269,3 +291,86 @@ println(m._private);
         "{err}"
     );
 }
+
+#[test]
+fn std_imports_fall_back_to_embedded_bundle() {
+    assert!(xu_runtime::embedded_std_modules().any(|n| n == "std/time.xu"));
+
+    let module = parse_source("use \"std/time\" as t;\nprintln(\"ok\");");
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let missing = std::env::temp_dir().join("xu_runtime_no_stdlib_here");
+    rt.set_stdlib_path(missing.to_string_lossy().to_string());
+    let res = rt.exec_module(&module).unwrap();
+    assert_eq!(res.output.trim_end(), "ok");
+
+    let module = parse_source("use \"std/does_not_exist\";");
+    let err = rt.exec_module(&module).unwrap_err();
+    assert!(err.contains("Import failed"), "{err}");
+}
+
+#[test]
+fn embedded_std_modules_are_precompiled() {
+    // No frontend: the embedded module must come compiled from the build.
+    let module = parse_source("use \"std/time\" as t;\nlet w = t.stopwatch();\nprintln(w.elapsed_ms() >= 0);");
+    let mut rt = Runtime::new();
+    let res = rt.exec_module(&module).unwrap();
+    assert_eq!(res.output.trim_end(), "true");
+
+    // 不在内嵌库中的 std 模块仍需要前端
+    let dir = std::env::temp_dir().join("xu_runtime_std_on_disk");
+    let _ = fs::create_dir_all(dir.join("std"));
+    fs::write(dir.join("std").join("local.xu"), "pub func one() { return 1; }\n").unwrap();
+    rt.set_stdlib_path(dir.to_string_lossy().to_string());
+    let module = parse_source("use \"std/local\" as l;\nprintln(l.one());");
+    let err = rt.exec_module(&module).unwrap_err();
+    assert!(err.contains("frontend is not configured"), "{err}");
+}
+
+#[test]
+fn reexecuting_program_with_imports_reuses_runtime() {
+    use xu_ir::Frontend;
+
+    let dir = std::env::temp_dir().join("xu_runtime_import_rerun_tests");
+    let _ = fs::create_dir_all(&dir);
+    fs::write(dir.join("lib.xu"), "pub func shout(s) { return s + \"!\"; }\n").unwrap();
+    let main = dir.join("main.xu");
+    let src = "use \"lib.xu\" as l;\nprintln(l.shout(\"x\"));\n";
+    fs::write(&main, src).unwrap();
+
+    let cu = xu_driver::Driver::new()
+        .compile_text_no_analyze(main.to_string_lossy().as_ref(), src)
+        .unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(main.to_string_lossy().as_ref()).unwrap();
+    for _ in 0..3 {
+        let res = rt.exec_executable(&cu.executable).unwrap();
+        assert_eq!(res.output, "x!\n");
+    }
+}
+
+#[test]
+fn diamond_imports_execute_the_shared_module_once() {
+    use xu_ir::Frontend;
+
+    let dir = std::env::temp_dir().join("xu_runtime_import_diamond_tests");
+    let sub = dir.join("sub");
+    let _ = fs::create_dir_all(&sub);
+    fs::write(sub.join("shared.xu"), "println(\"shared\")\npub let v = 1\n").unwrap();
+    fs::write(dir.join("left.xu"), "use \"sub/shared.xu\" as s\npub let a = s.v\n").unwrap();
+    // A different spelling of the same file still names the same module.
+    fs::write(sub.join("right.xu"), "use \"../sub/./shared.xu\" as s\npub let b = s.v + 1\n").unwrap();
+    let main = dir.join("main.xu");
+    let src = "use \"left.xu\" as l\nuse \"sub/right.xu\" as r\nprintln(l.a + r.b)\n";
+    fs::write(&main, src).unwrap();
+
+    let cu = xu_driver::Driver::new().compile_text_no_analyze(main.to_string_lossy().as_ref(), src).unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(main.to_string_lossy().as_ref()).unwrap();
+    assert_eq!(rt.exec_module(&parse_source(src)).unwrap().output, "shared\n3\n");
+    for _ in 0..2 {
+        assert_eq!(rt.exec_executable(&cu.executable).unwrap().output, "shared\n3\n");
+    }
+}
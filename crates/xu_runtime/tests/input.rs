@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use xu_ir::Frontend;
+use xu_runtime::{Runtime, Terminal};
+
+/// A terminal answering from a script, noting which answers were read as
+/// secrets.
+struct Scripted {
+    answers: RefCell<VecDeque<&'static str>>,
+    secrets: Rc<RefCell<Vec<String>>>,
+}
+
+impl Terminal for Scripted {
+    fn is_tty(&self) -> bool {
+        false
+    }
+
+    fn read_line(&self) -> Option<String> {
+        self.answers.borrow_mut().pop_front().map(str::to_string)
+    }
+
+    fn read_secret(&self) -> Option<String> {
+        let line = self.read_line()?;
+        self.secrets.borrow_mut().push(line.clone());
+        Some(line)
+    }
+}
+
+fn run_with(answers: &[&'static str], src: &str) -> (Result<String, String>, Vec<String>) {
+    let exe = xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile").executable;
+    let secrets = Rc::new(RefCell::new(Vec::new()));
+    let mut rt = Runtime::new();
+    rt.set_terminal(Box::new(Scripted {
+        answers: RefCell::new(answers.iter().copied().collect()),
+        secrets: secrets.clone(),
+    }));
+    let out = rt.exec_executable(&exe).map(|r| r.output);
+    let secrets = secrets.borrow().clone();
+    (out, secrets)
+}
+
+fn run(answers: &[&'static str], src: &str) -> String {
+    run_with(answers, src).0.unwrap()
+}
+
+#[test]
+fn input_reads_from_the_terminal() {
+    let src = "let name = input(\"name? \")\nprintln(\"hi \" + name)\n";
+    assert_eq!(run(&["Ada"], src), "name? hi Ada\n");
+    assert_eq!(run(&[], "println(\"[\" + input() + \"]\")\n"), "[]\n");
+}
+
+#[test]
+fn input_takes_default_for_empty_answers_and_at_eof() {
+    let src = "println(input(\"lang? \", default: \"xu\"))\n";
+    assert_eq!(run(&["  "], src), "lang? xu\n");
+    assert_eq!(run(&["rust"], src), "lang? rust\n");
+    assert_eq!(run(&[], src), "lang? xu\n");
+}
+
+#[test]
+fn input_int_asks_again_until_a_number() {
+    let src = "println(inspect(input_int(\"n? \")))\n";
+    let out = run(&["ten", "", " 10 "], src);
+    assert_eq!(out, "n? please enter a whole number\nn? please enter a whole number\nn? Option#some(10)\n");
+    assert_eq!(run(&[], src), "n? Option#none\n");
+    assert_eq!(run(&[""], "println(inspect(input_int(\"n? \", default: 3)))\n"), "n? Option#some(3)\n");
+}
+
+#[test]
+fn input_float_widens_int_defaults() {
+    let src = "println(type_of(input_float(\"x? \", default: 2).get()))\n";
+    assert_eq!(run(&["", "1.5"], &format!("{src}{src}")), "x? float\nx? float\n");
+    assert_eq!(run(&["inf", "0.25"], "println(inspect(input_float()))\n"), "please enter a number\nOption#some(0.25)\n");
+}
+
+#[test]
+fn validate_turns_answers_down() {
+    let src = concat!(
+        "let age = input_int(\"age? \", validate: |n| if n < 0 { \"must not be negative\" } else { n < 150 })\n",
+        "println(inspect(age))\n",
+    );
+    let out = run(&["-1", "200", "42"], src);
+    assert_eq!(out, "age? must not be negative\nage? invalid answer, please try again\nage? Option#some(42)\n");
+}
+
+#[test]
+fn input_secret_reads_without_echo() {
+    let src = "println(inspect(input_secret(\"password: \")))\n";
+    let (out, secrets) = run_with(&["hunter2"], src);
+    assert_eq!(out.unwrap(), "password: Option#some(\"hunter2\")\n");
+    assert_eq!(secrets, vec!["hunter2".to_string()]);
+}
+
+#[test]
+fn wrong_defaults_and_validators_are_errors() {
+    let (out, _) = run_with(&["1"], "input_int(\"n? \", default: \"x\")\n");
+    assert!(out.unwrap_err().contains("input_int: default must be int, got"));
+    let (out, _) = run_with(&["1"], "input_int(\"n? \", validate: 3)\n");
+    assert!(out.unwrap_err().contains("input_int: validate must be a function, got int"));
+    let (out, _) = run_with(&["1"], "input_int(\"n? \", validate: |n| n)\n");
+    assert!(out.unwrap_err().contains("input_int: validate must return bool or text, got int"));
+}
@@ -0,0 +1,90 @@
+mod common;
+use common::run;
+
+#[test]
+fn power_is_int_for_non_negative_int_exponents() {
+    let src = "println(2 ** 10, 2 ** 0, 2 ** -1, 2.5 ** 2, 2 ** 0.5 > 1.41, (-3) ** 3)\n";
+    assert_eq!(run(src).unwrap(), "1024\n1\n0.5\n6.25\ntrue\n-27\n");
+}
+
+#[test]
+fn power_binds_tighter_than_unary_minus_and_to_the_right() {
+    assert_eq!(run("println(-2 ** 2, 2 ** 3 ** 2, 2 * 3 ** 2)\n").unwrap(), "-4\n512\n18\n");
+}
+
+#[test]
+fn bitwise_operators_on_ints() {
+    let src = "println(12 & 10, 12 | 10, 12 ^ 10, ~0, ~-8)\nprintln(1 << 40, -17 >> 2, 255 >> 4)\n";
+    assert_eq!(run(src).unwrap(), "8\n14\n6\n-1\n7\n1099511627776\n-5\n15\n");
+}
+
+#[test]
+fn bitwise_precedence_sits_between_comparison_and_arithmetic() {
+    // `x & 1 == 1` compares the masked value; `1 << 2 + 1` shifts by 3;
+    // `|` binds looser than `^`, which binds looser than `&`.
+    let src = "let x = 5\nprintln(x & 1 == 1, 1 << 2 + 1, 1 | 6 ^ 3 & 2)\n";
+    assert_eq!(run(src).unwrap(), "true\n8\n5\n");
+}
+
+#[test]
+fn folded_literals_agree_with_variables_at_the_int_limits() {
+    // Operators on two literals are folded by the compiler, which must
+    // neither overflow nor get another result than the engines.
+    let max = "140737488355327";
+    for rhs in [max, "7"] {
+        for op in ["+", "-", "*", "/", "%", "&", "|", "^"] {
+            let folded = run(&format!("println({max} {op} {rhs})\n")).unwrap();
+            let computed = run(&format!("let a = {max}\nlet b = {rhs}\nprintln(a {op} b)\n")).unwrap();
+            assert_eq!(folded, computed, "{max} {op} {rhs}");
+        }
+    }
+    let computed = run(&format!("let a = {max}\nprintln(~a)\n")).unwrap();
+    assert_eq!(run(&format!("println(~{max})\n")).unwrap(), computed);
+}
+
+#[test]
+fn compound_assignments_for_every_operator() {
+    let src = concat!(
+        "var x = 7\n",
+        "x %= 4\n",
+        "x **= 3\n",
+        "x <<= 2\n",
+        "x >>= 1\n",
+        "x &= 62\n",
+        "x |= 1\n",
+        "x ^= 3\n",
+        "println(x)\n",
+        "var d = {\"n\": 3}\n",
+        "d[\"n\"] **= 2\n",
+        "d[\"n\"] %= 5\n",
+        "println(d[\"n\"])\n",
+        "Box has {\n    v: int\n}\n",
+        "let b = Box{ v: 6 }\n",
+        "b.v ^= 5\n",
+        "b.v <<= 1\n",
+        "println(b.v)\n",
+    );
+    assert_eq!(run(src).unwrap(), "52\n4\n6\n");
+}
+
+#[test]
+fn compound_assignments_inside_functions() {
+    let src = "func f(n: int) -> int {\n    var acc = 1\n    acc <<= n\n    acc |= 1\n    acc %= 7\n    return acc\n}\nprintln(f(4))\n";
+    assert_eq!(run(src).unwrap(), "3\n");
+}
+
+#[test]
+fn bitwise_operators_reject_non_ints() {
+    let err = run("println(1.5 & 1)\n").unwrap_err();
+    assert!(err.contains("Bitwise operator '&' requires int operands, got float and int"), "{err}");
+    let err = run("println(~true)\n").unwrap_err();
+    assert!(err.contains("Unary operator '~' expects int type"), "{err}");
+}
+
+#[test]
+fn shift_amounts_must_fit_in_an_int() {
+    let err = run("println(1 << 64)\n").unwrap_err();
+    assert!(err.contains("Shift amount must be from 0 to 63, got 64"), "{err}");
+    let err = run("var x = 8\nx >>= -1\n").unwrap_err();
+    assert!(err.contains("Shift amount must be from 0 to 63, got -1"), "{err}");
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new()
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile")
+        .executable
+}
+
+/// The same program run by the tree-walking interpreter.
+fn as_ast(exe: xu_ir::Executable) -> xu_ir::Executable {
+    match exe {
+        xu_ir::Executable::Bytecode(p) => xu_ir::Executable::Ast(p.module),
+        ast => ast,
+    }
+}
+
+const SPIN: &str = "var i = 0\nwhile true {\n    i += 1\n}\n";
+
+const SPIN_IN_FUNC: &str = "func spin() {\n    var n = 0\n    for i in 0..1000000000 {\n        n += i\n    }\n    return n\n}\nspin()\n";
+
+fn interrupt_after(rt: &Runtime, ms: u64) -> std::thread::JoinHandle<()> {
+    let handle = rt.interrupt_handle();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(ms));
+        handle.interrupt();
+    })
+}
+
+#[test]
+fn interrupt_stops_the_vm() {
+    let mut rt = Runtime::new();
+    let timer = interrupt_after(&rt, 50);
+    let err = rt.exec_executable(&compile(SPIN)).unwrap_err();
+    timer.join().unwrap();
+    assert_eq!(err, "Execution interrupted");
+    // The interrupt was used up by stopping the run.
+    assert!(!rt.interrupt_handle().is_interrupted());
+}
+
+#[test]
+fn interrupt_stops_the_tree_walker() {
+    let mut rt = Runtime::new();
+    let timer = interrupt_after(&rt, 50);
+    let err = rt.exec_executable(&as_ast(compile(SPIN))).unwrap_err();
+    timer.join().unwrap();
+    assert_eq!(err, "Execution interrupted");
+}
+
+#[test]
+fn interrupt_stops_a_loop_inside_a_function() {
+    let mut rt = Runtime::new();
+    let timer = interrupt_after(&rt, 50);
+    let err = rt.exec_executable(&compile(SPIN_IN_FUNC)).unwrap_err();
+    timer.join().unwrap();
+    assert_eq!(err, "Execution interrupted");
+}
+
+#[test]
+fn an_interrupt_before_the_run_stops_it_once() {
+    let mut rt = Runtime::new();
+    rt.interrupt_handle().interrupt();
+    let err = rt.exec_executable(&compile(SPIN)).unwrap_err();
+    assert_eq!(err, "Execution interrupted");
+    let res = rt.exec_executable(&compile("var n = 0\nfor i in 0..5000 {\n    n += i\n}\nprintln(n)\n")).unwrap();
+    assert_eq!(res.output, "12497500\n");
+    assert!(!rt.interrupt_handle().is_interrupted());
+}
@@ -0,0 +1,193 @@
+//! Needs the `jit` feature: `cargo test -p xu_runtime --features jit --test jit`.
+
+use xu_ir::Frontend;
+use xu_runtime::{JitStats, Runtime, RuntimeConfig};
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new().compile_text_no_analyze("<jit>", src).expect("compile").executable
+}
+
+fn run_with(threshold: u32, src: &str) -> (Result<String, String>, JitStats) {
+    let mut rt = Runtime::with_config(RuntimeConfig { jit_threshold: threshold, ..RuntimeConfig::default() });
+    let res = rt.exec_executable(&compile(src)).map(|r| r.output);
+    (res, rt.jit_stats())
+}
+
+/// Runs `src` with every function compiled on its first call, checks the
+/// result against the interpreter's and returns the JIT's stats.
+fn agree(src: &str) -> JitStats {
+    let (jit, stats) = run_with(1, src);
+    let (interpreted, off) = run_with(0, src);
+    assert_eq!(off, JitStats::default());
+    assert_eq!(jit, interpreted, "{src}");
+    assert!(jit.is_ok(), "{jit:?}");
+    stats
+}
+
+const LOOPS: &str = r#"
+func sum_to(n: int) -> int {
+  var s = 0
+  var i = 0
+  while i < n {
+    if i % 3 == 0 || i % 5 == 0 {
+      s += i
+    }
+    i += 1
+  }
+  return s
+}
+
+func mean(n: int) -> float {
+  var total = 0.0
+  var i = 1
+  while i <= n {
+    total = total + i / 2.0
+    i += 1
+  }
+  return total / n
+}
+
+func collatz(x) {
+  var steps = 0
+  while x != 1 {
+    if x % 2 == 0 { x = x / 2 } else { x = 3 * x + 1 }
+    steps += 1
+  }
+  return steps
+}
+
+var i = 0
+var acc = 0
+while i < 50 {
+  acc = acc + sum_to(i * 7) + collatz(i + 1)
+  i += 1
+}
+println(acc)
+println(mean(10))
+println(collatz(27))
+"#;
+
+#[test]
+fn hot_numeric_functions_run_as_native_code() {
+    let stats = agree(LOOPS);
+    assert_eq!(stats.compiled, 3);
+    assert_eq!(stats.rejected, 0);
+    assert_eq!(stats.bailouts, 0);
+    assert_eq!(stats.native_calls, 102);
+}
+
+#[test]
+fn functions_compile_once_they_reach_the_threshold() {
+    let src =
+        "func inc(x) {\n  if x > 0 { return x + 1 }\n  return 0\n}\nvar i = 0\nwhile i < 10 {\n  inc(i)\n  i += 1\n}\n";
+    let (_, stats) = run_with(4, src);
+    assert_eq!((stats.compiled, stats.native_calls), (1, 7));
+    let (_, stats) = run_with(11, src);
+    assert_eq!(stats, JitStats::default());
+}
+
+#[test]
+fn numbers_follow_the_interpreter() {
+    let stats = agree(
+        r#"
+func op(k, a, b) {
+  if k == 0 { return a + b }
+  if k == 1 { return a - b }
+  if k == 2 { return a * b }
+  if k == 3 { return a / b }
+  if k == 4 { return a % b }
+  if k == 5 { return a < b }
+  if k == 6 { return a >= b }
+  if k == 7 { return a == b }
+  return a != b
+}
+func logic(a, b) {
+  if !a && b { return 1 }
+  if a || b { return 2 }
+  return 3
+}
+for (a, b) in [(7, 2), (-7, 2), (7, -2), (1, 1.0), (0.1, 0.2), (2.5, 4), (1e308, 1e308), (-0.0, 1), (1099511627776, 1099511627776), (140737488355327, 2)] {
+  var line: list = []
+  for k in 0..9 { line.push(op(k, a, b)) }
+  println(line)
+}
+println([logic(true, true), logic(false, true), logic(false, false)])
+"#,
+    );
+    assert_eq!((stats.compiled, stats.rejected), (2, 0));
+    // Float remainders and int products beyond 64 bits
+    assert_eq!(stats.bailouts, 6);
+}
+
+#[test]
+fn unsupported_operands_bail_out_to_the_interpreter() {
+    let src = r#"
+func add(a, b) {
+  if a == b { return a }
+  return a + b
+}
+println(add(1, 2))
+println(add("x", "y"))
+println(add([1], [1]))
+println(add(1.5, 2))
+"#;
+    let stats = agree(src);
+    assert_eq!((stats.compiled, stats.native_calls, stats.bailouts), (1, 2, 2));
+
+    let (err, stats) = run_with(1, "func div(a, b) {\n  if a > b { return a / b }\n  return 0\n}\ndiv(1, 0)\n");
+    assert!(err.unwrap_err().contains("Division by zero"));
+    assert_eq!(stats.bailouts, 1);
+}
+
+#[test]
+fn functions_that_keep_bailing_out_are_deoptimized() {
+    let src = r#"
+func twice(x) {
+  if x == x { return x + x }
+  return x
+}
+var i = 0
+while i < 100 {
+  twice("ab")
+  i += 1
+}
+println(twice(21))
+"#;
+    let stats = agree(src);
+    assert_eq!(stats.deoptimized, 1);
+    assert_eq!((stats.bailouts, stats.native_calls), (32, 0));
+}
+
+#[test]
+fn functions_with_other_ops_stay_interpreted() {
+    let src = r#"
+func total(xs) {
+  var s = 0
+  for x in xs { s += x }
+  return s
+}
+func show(n) {
+  if n > 1 { println(n) }
+  return n
+}
+println(total([1, 2, 3]))
+show(2)
+"#;
+    let stats = agree(src);
+    assert_eq!((stats.compiled, stats.rejected), (0, 2));
+}
+
+#[test]
+fn native_loops_stop_on_interrupt() {
+    let mut rt = Runtime::with_config(RuntimeConfig { jit_threshold: 1, ..RuntimeConfig::default() });
+    let exe = compile("func spin(n) {\n  while n >= 0 { n += 1 }\n  return n\n}\nspin(0)\n");
+    let handle = rt.interrupt_handle();
+    let stopper = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        handle.interrupt();
+    });
+    let err = rt.exec_executable(&exe).unwrap_err();
+    stopper.join().unwrap();
+    assert!(err.contains("E3007") || err.contains("nterrupt"), "{err}");
+    assert_eq!(rt.jit_stats().bailouts, 1);
+}
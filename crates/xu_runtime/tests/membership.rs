@@ -0,0 +1,67 @@
+mod common;
+use common::run;
+
+#[test]
+fn chained_comparisons_test_every_link() {
+    let src = concat!(
+        "let x = 5\n",
+        "println(0 <= x < 10, 0 <= x < 5, 10 > x >= 5 > 1, 1 < 2 < 3 < 2)\n",
+        "println(\"a\" < \"b\" <= \"b\", 1 < 1.5 < 2)\n",
+    );
+    assert_eq!(run(src).unwrap(), "true\nfalse\ntrue\nfalse\ntrue\ntrue\n");
+}
+
+#[test]
+fn chained_comparisons_evaluate_each_operand_once_and_short_circuit() {
+    let src = concat!(
+        "var calls = 0\n",
+        "func f(n: int) -> int {\n    calls += 1\n    return n\n}\n",
+        "println(0 < f(5) < 10, calls)\n",
+        "println(f(3) < 2 < f(9), calls)\n",
+    );
+    assert_eq!(run(src).unwrap(), "true\n1\nfalse\n2\n");
+}
+
+#[test]
+fn grouped_comparisons_do_not_chain() {
+    let err = run("println((1 < 2) < 3)\n").unwrap_err();
+    assert!(!err.is_empty());
+}
+
+#[test]
+fn in_tests_range_bounds_without_building_the_range() {
+    let src = concat!(
+        "let x = 42\n",
+        "println(x in 1..100, 100 in 1..100, 100 in 1..=100, 0 in 1..100)\n",
+        "println(2.0 in 1..3, 2.5 in 1..3, \"2\" in 1..3)\n",
+        "let r = 1..=3\n",
+        "println(3 in r, 4 in r)\n",
+    );
+    assert_eq!(run(src).unwrap(), "true\nfalse\ntrue\nfalse\ntrue\nfalse\nfalse\ntrue\nfalse\n");
+}
+
+#[test]
+fn in_tests_collections_by_element_key_or_substring() {
+    let src = concat!(
+        "let xs = [1, [2, 3], \"four\"]\n",
+        "println(1 in xs, [2, 3] in xs, \"four\" in xs, 5 in xs)\n",
+        "let d = {\"a\": 1, \"b\": 2}\n",
+        "println(\"a\" in d, 1 in d, \"ell\" in \"hello\", \"\" in \"\", \"z\" in \"hello\")\n",
+        "println(2 in (1, 2), !(3 in (1, 2)))\n",
+    );
+    assert_eq!(run(src).unwrap(), "true\ntrue\ntrue\nfalse\ntrue\nfalse\ntrue\ntrue\nfalse\ntrue\ntrue\n");
+}
+
+#[test]
+fn in_binds_like_a_comparison() {
+    let src = "let xs = [3, 4]\nprintln(1 + 2 in xs, 1 in xs || 4 in xs, 1 in xs == false)\n";
+    assert_eq!(run(src).unwrap(), "true\ntrue\ntrue\n");
+}
+
+#[test]
+fn in_rejects_values_without_members() {
+    let err = run("println(1 in 5)\n").unwrap_err();
+    assert!(err.contains("'in' expects a range, list, tuple, dict or string, got int"), "{err}");
+    let err = run("println(1 in \"123\")\n").unwrap_err();
+    assert!(err.contains("'in' on a string needs a string, got int"), "{err}");
+}
@@ -0,0 +1,70 @@
+use xu_ir::Frontend;
+use xu_runtime::{PrettyOptions, Runtime, Value, xu_builtin};
+
+xu_builtin! {
+    /// Builtins of the tests below.
+    pub struct Shapes;
+
+    pub fn hypot(a: f64, b: f64) -> f64 {
+        a.hypot(b)
+    }
+
+    /// Repeats `s`, twice by default.
+    #[name = "__repeat"]
+    pub fn repeat(s: String, n: Option<i64>) -> Result<String, String> {
+        let n = n.unwrap_or(2);
+        usize::try_from(n).map(|n| s.repeat(n)).map_err(|_| format!("__repeat: negative count {n}"))
+    }
+
+    pub fn evens(items: Vec<i64>) -> Vec<i64> {
+        items.into_iter().filter(|n| n % 2 == 0).collect()
+    }
+
+    pub fn first_word(s: String) -> Option<String> {
+        s.split_whitespace().next().map(str::to_string)
+    }
+
+    pub fn show(rt: &Runtime, v: Value) -> String {
+        rt.inspect(&v, &PrettyOptions::default())
+    }
+
+    pub fn shout(rt: &mut Runtime, s: String) -> Value {
+        rt.intern_str_value(&s.to_uppercase())
+    }
+}
+
+fn run(src: &str) -> Result<String, String> {
+    let cu = xu_driver::Driver::new().compile_text_no_analyze("main.xu", src).unwrap();
+    let mut rt = Runtime::new();
+    rt.add_builtin_provider(Shapes);
+    rt.exec_executable(&cu.executable).map(|r| r.output)
+}
+
+#[test]
+fn arguments_and_results_are_converted() {
+    let out = run(concat!(
+        "println(hypot(3, 4.0))\n",
+        "println(__repeat(\"ab\"), __repeat(\"ab\", 3))\n",
+        "println(evens([1, 2, 3, 4]))\n",
+        "println(first_word(\"hello world\"), first_word(\"\"))\n",
+        "println(show([1, \"a\"]))\n",
+        "println(shout(\"hi\"))\n",
+    ))
+    .unwrap();
+    assert_eq!(out, "5\nabab\nababab\n[2,4]\nhello\nOption#none\n[1, \"a\"]\nHI\n");
+}
+
+#[test]
+fn bad_calls_are_reported_like_hand_written_builtins() {
+    assert_eq!(run("hypot(1)\n").unwrap_err(), "hypot expects 2 arguments");
+    assert_eq!(run("__repeat()\n").unwrap_err(), "__repeat expects 1 or 2 arguments");
+    assert_eq!(run("hypot(1, \"x\")\n").unwrap_err(), "hypot expects number, got string");
+    assert_eq!(run("evens([1, 2.5])\n").unwrap_err(), "evens expects int, got float");
+    assert_eq!(run("__repeat(\"x\", -1)\n").unwrap_err(), "__repeat: negative count -1");
+}
+
+#[test]
+fn std_math_builtins_use_the_macro() {
+    assert_eq!(run("println(sqrt(16), gcd(12, -18), lcm(4, 6))\n").unwrap(), "4\n6\n12\n");
+    assert_eq!(run("gcd(1.5, 2)\n").unwrap_err(), "gcd expects int, got float");
+}
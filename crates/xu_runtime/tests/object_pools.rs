@@ -0,0 +1,59 @@
+use xu_ir::Frontend;
+use xu_runtime::{PoolLimits, Runtime, RuntimeConfig};
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new()
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile")
+        .executable
+}
+
+fn run(config: RuntimeConfig, src: &str) -> (String, u64) {
+    let mut rt = Runtime::with_config(config);
+    let out = rt.exec_executable(&compile(src)).unwrap().output;
+    (out, rt.gc_stats().pool_reuses)
+}
+
+fn churn_config(pool_limits: PoolLimits) -> RuntimeConfig {
+    RuntimeConfig { gc_threshold: 500, pool_limits, ..RuntimeConfig::default() }
+}
+
+const CHURN: &str = "var total = 0\nfor i in 0..5000 {\n    let pair = [i, i + 1]\n    let row = {\"id\": i, \"name\": \"row number {i} of the table\"}\n    total += pair[1] - pair[0] + row[\"name\"].length\n}\nprintln(total)\n";
+
+#[test]
+fn freed_buffers_back_new_allocations() {
+    let (out, reuses) = run(churn_config(PoolLimits::default()), CHURN);
+    let (plain, none) = run(churn_config(PoolLimits::NONE), CHURN);
+    assert_eq!(out, plain);
+    assert!(reuses > 1000, "{reuses}");
+    assert_eq!(none, 0);
+}
+
+#[test]
+fn each_kind_has_its_own_limit() {
+    let only_lists = PoolLimits { lists: 64, ..PoolLimits::NONE };
+    let (_, reuses) = run(churn_config(only_lists), "for i in 0..5000 {\n    let row = {\"id\": i}\n}\n");
+    assert_eq!(reuses, 0);
+    let (_, reuses) = run(churn_config(only_lists), "for i in 0..5000 {\n    let pair = [i, i]\n}\n");
+    assert!(reuses > 0);
+}
+
+#[test]
+fn reused_dicts_start_empty() {
+    // Dicts with integer keys use a separate element array; a dict built
+    // from a recycled one must not see old entries of either kind.
+    let src = "var leaked = 0\nfor i in 0..3000 {\n    var d: {int: int} = {}\n    leaked += d.length\n    d[0] = i\n    d[1] = i\n    d[1000 + i] = i\n    let e = {\"k\": i}\n    leaked += e.length - 1\n}\nprintln(leaked)\n";
+    let (out, reuses) = run(churn_config(PoolLimits::default()), src);
+    assert_eq!(out, "0\n");
+    assert!(reuses > 0);
+}
+
+#[test]
+fn reused_texts_hold_only_their_own_content() {
+    let src = "var total = 0\nfor i in 0..3000 {\n    let s = \"a fairly long interpolated text {i}\"\n    let t = s + \" and then some\"\n    if i % 1000 == 999 {\n        println(t)\n    }\n    total += t.length\n}\nprintln(total)\n";
+    let (out, reuses) = run(churn_config(PoolLimits::default()), src);
+    let (plain, _) = run(churn_config(PoolLimits::NONE), src);
+    assert_eq!(out, plain);
+    assert!(out.starts_with("a fairly long interpolated text 999 and then some\n"), "{out}");
+    assert!(reuses > 0);
+}
@@ -0,0 +1,70 @@
+mod common;
+use common::run;
+
+const USERS: &str = concat!(
+    "User has {\n",
+    "    name: string\n",
+    "    tags: list\n",
+    "}\n",
+    "func find(id: int) -> Option {\n",
+    "    if id == 1 {\n",
+    "        return Option#some(User{ name: \"ann\", tags: [\"x\", \"y\"] })\n",
+    "    }\n",
+    "    return Option#none\n",
+    "}\n",
+);
+
+#[test]
+fn optional_member_and_index_unwrap_or_give_none() {
+    let src = format!(
+        "{USERS}{}",
+        concat!(
+            "println(inspect(find(1)?.name), inspect(find(2)?.name))\n",
+            "println(inspect(find(1)?.tags?[1]))\n",
+            "let d = {\"a\": 1}\n",
+            "println(d?[\"a\"], inspect(Option#some(d)?[\"a\"]), inspect(Option#none?[\"a\"]))\n",
+        )
+    );
+    assert_eq!(run(&src).unwrap(), "\"ann\"\nOption#none\n\"y\"\n1\n1\nOption#none\n");
+}
+
+#[test]
+fn none_skips_the_rest_of_the_chain() {
+    let src = format!(
+        "{USERS}{}",
+        concat!(
+            "println(inspect(find(1)?.name.length), inspect(find(2)?.name.length))\n",
+            "println(inspect(find(2)?.tags[5]))\n",
+            "println(inspect(find(1)?.name.to_upper()), inspect(find(2)?.name.to_upper()))\n",
+        )
+    );
+    assert_eq!(run(&src).unwrap(), "3\nOption#none\nOption#none\n\"ANN\"\nOption#none\n");
+    let err = run(&format!("{USERS}println(find(1)?.tags[5])\n")).unwrap_err();
+    assert!(err.contains("Index out of range"), "{err}");
+}
+
+#[test]
+fn coalesce_only_evaluates_the_fallback_for_none() {
+    let src = format!(
+        "{USERS}{}",
+        concat!(
+            "func loud(x: int) -> int {\n",
+            "    println(\"eval {x}\")\n",
+            "    return x\n",
+            "}\n",
+            "println(1 ?? loud(2))\n",
+            "println(Option#some(2) ?? loud(9))\n",
+            "println(Option#none ?? loud(3))\n",
+            "println(Option#none ?? Option#none ?? 4)\n",
+            "println(find(2)?.name ?? \"nobody\", find(1)?.name ?? \"nobody\")\n",
+            "let f = |id| find(id)?.name ?? \"?\"\n",
+            "println(f(1), f(2))\n",
+        )
+    );
+    assert_eq!(run(&src).unwrap(), "1\n2\neval 3\n3\n4\nnobody\nann\nann\n?\n");
+}
+
+#[test]
+fn coalesce_binds_between_comparison_and_arithmetic() {
+    assert_eq!(run("println(Option#some(1) ?? 1 + 2, Option#some(5) ?? 0 < 1)\n").unwrap(), "1\nfalse\n");
+}
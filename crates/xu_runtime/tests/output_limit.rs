@@ -0,0 +1,87 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+use xu_ir::Frontend;
+use xu_runtime::{OutputOverflow, Runtime};
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new()
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile")
+        .executable
+}
+
+const LOUD: &str = "for i in 0..1000 {\n    println(\"line {i}\")\n}\n";
+
+fn expected() -> String {
+    let mut s = String::new();
+    for i in 0..1000 {
+        s.push_str(&format!("line {i}\n"));
+    }
+    s
+}
+
+#[derive(Clone, Default)]
+struct SharedSink(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn output_under_limit_stays_in_memory() {
+    let mut rt = Runtime::new();
+    rt.set_output_limit(Some(1 << 20));
+    let res = rt.exec_executable(&compile(LOUD)).unwrap();
+    assert_eq!(res.output, expected());
+    assert!(res.overflow.is_none());
+}
+
+#[test]
+fn writers_take_streamed_output_past_the_limit() {
+    let sink = SharedSink::default();
+    let mut rt = Runtime::new();
+    rt.set_output_limit(Some(256));
+    let writer: Box<dyn Write> = Box::new(sink.clone());
+    rt.set_output_streams(Box::new(writer));
+    let res = rt.exec_executable(&compile(LOUD)).unwrap();
+    assert!(res.output.is_empty());
+    assert!(res.overflow.is_none());
+    assert_eq!(String::from_utf8(sink.0.borrow().clone()).unwrap(), expected());
+}
+
+#[test]
+fn output_over_limit_spills_to_file() {
+    let mut rt = Runtime::new();
+    rt.set_output_limit(Some(256));
+    let res = rt.exec_executable(&compile(LOUD)).unwrap();
+    assert!(res.output.is_empty());
+    let Some(OutputOverflow::Spilled { path, bytes }) = res.overflow else {
+        panic!("expected spill, got {:?}", res.overflow);
+    };
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(bytes as usize, content.len());
+    assert_eq!(content, expected());
+
+    // Each spill gets a file of its own.
+    let res = rt.exec_executable(&compile(LOUD)).unwrap();
+    let Some(OutputOverflow::Spilled { path: next, .. }) = res.overflow else {
+        panic!("expected spill, got {:?}", res.overflow);
+    };
+    assert_ne!(next, path);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), expected());
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&next);
+
+    // The next execution starts with a fresh buffer.
+    let res = rt.exec_executable(&compile("println(1)\n")).unwrap();
+    assert_eq!(res.output, "1\n");
+    assert!(res.overflow.is_none());
+}
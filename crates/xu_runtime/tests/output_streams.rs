@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use xu_ir::Frontend;
+use xu_runtime::{OutputSink, OutputStream, Runtime};
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile").executable
+}
+
+const BOTH: &str = "println(\"out 1\")\neprint(\"err 1\", 2)\nprintln(\"out 2\")\neprintln(\"err 2\")\n";
+
+/// Every write, tagged with its stream, in order.
+#[derive(Clone, Default)]
+struct Recorder(Rc<RefCell<Vec<(OutputStream, String)>>>);
+
+impl OutputSink for Recorder {
+    fn write(&mut self, stream: OutputStream, text: &str) -> std::io::Result<()> {
+        let mut writes = self.0.borrow_mut();
+        match writes.last_mut() {
+            Some((s, t)) if *s == stream => t.push_str(text),
+            _ => writes.push((stream, text.to_string())),
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn eprint_is_kept_apart_from_output() {
+    let mut rt = Runtime::new();
+    let res = rt.exec_executable(&compile(BOTH)).unwrap();
+    assert_eq!(res.output, "out 1\nout 2\n");
+    assert_eq!(res.error_output, "err 1\n2\nerr 2\n");
+}
+
+#[test]
+fn error_output_survives_a_failed_run() {
+    let mut rt = Runtime::new();
+    assert!(rt.exec_executable(&compile("eprint(\"before\")\nlet x = [1][3]\n")).is_err());
+    assert_eq!(rt.take_error_output(), "before\n");
+}
+
+#[test]
+fn streams_receive_writes_in_order() {
+    let recorder = Recorder::default();
+    let mut rt = Runtime::new();
+    rt.set_output_streams(Box::new(recorder.clone()));
+    let res = rt.exec_executable(&compile(BOTH)).unwrap();
+    assert!(res.output.is_empty() && res.error_output.is_empty());
+    let writes = recorder.0.borrow().clone();
+    assert_eq!(
+        writes,
+        vec![
+            (OutputStream::Stdout, "out 1\n".to_string()),
+            (OutputStream::Stderr, "err 1\n2\n".to_string()),
+            (OutputStream::Stdout, "out 2\n".to_string()),
+            (OutputStream::Stderr, "err 2\n".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn streams_bypass_the_output_limit() {
+    let recorder = Recorder::default();
+    let mut rt = Runtime::new();
+    rt.set_output_limit(Some(16));
+    rt.set_output_streams(Box::new(recorder.clone()));
+    let res = rt.exec_executable(&compile("for i in 0..100 {\n    println(\"line {i}\")\n}\n")).unwrap();
+    assert!(res.overflow.is_none());
+    let writes = recorder.0.borrow();
+    assert_eq!(writes.len(), 1);
+    assert!(writes[0].1.ends_with("line 99\n"));
+}
@@ -0,0 +1,120 @@
+use xu_ir::Frontend;
+use xu_runtime::{Runtime, RuntimeConfig};
+
+const SRC: &str = r##"
+func label(n) {
+    println("item {n}")
+    return "#{n}"
+}
+func fail_on_3(n) {
+    if n == 3 {
+        let l = [0]
+        return l[5]
+    }
+    return n
+}
+"##;
+
+fn run(workers: usize, main: &str) -> Result<String, String> {
+    let dir = std::env::temp_dir().join(format!("xu_parallel_map_{}_{workers}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.xu");
+    let src = format!("{SRC}{main}");
+    std::fs::write(&path, &src).unwrap();
+    let driver = xu_driver::Driver::new();
+    let cu = driver
+        .compile_text_no_analyze(path.to_string_lossy().as_ref(), &src)
+        .unwrap();
+    let mut rt = Runtime::with_config(RuntimeConfig {
+        parallel_workers: workers,
+        ..RuntimeConfig::default()
+    });
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_entry_path(&path.to_string_lossy()).unwrap();
+    let res = rt.exec_executable(&cu.executable).map(|r| r.output);
+    let _ = std::fs::remove_dir_all(&dir);
+    res
+}
+
+#[test]
+fn one_worker_maps_in_order() {
+    let out = run(1, "println(parallel_map([1, 2, 3], label))\n").unwrap();
+    assert_eq!(out, "item 1\nitem 2\nitem 3\n[#1,#2,#3]\n");
+}
+
+#[test]
+fn results_keep_item_order_across_workers() {
+    let out = run(4, "let r = parallel_map([1, 2, 3, 4, 5, 6, 7, 8], label)\nprintln(r)\n").unwrap();
+    assert!(out.ends_with("[#1,#2,#3,#4,#5,#6,#7,#8]\n"), "{out}");
+    assert_eq!(out.matches("item ").count(), 8);
+}
+
+#[test]
+fn errors_name_the_item() {
+    let err = run(2, "println(parallel_map([1, 2, 3, 4], fail_on_3))\n").unwrap_err();
+    assert!(err.contains("parallel_map: item 2:"), "{err}");
+}
+
+#[test]
+fn anonymous_functions_are_rejected() {
+    let err = run(2, "println(parallel_map([1], |x| x))\n").unwrap_err();
+    assert!(err.contains("top-level function"), "{err}");
+}
+
+/// Files kept in memory, so nothing the pool reads exists on disk.
+#[derive(Default)]
+struct MemFs(std::collections::HashMap<String, String>);
+
+impl xu_runtime::FileSystem for MemFs {
+    fn metadata(&self, path: &str) -> Result<(), String> {
+        self.read_to_string(path).map(|_| ())
+    }
+    fn stat(&self, path: &str) -> Result<xu_runtime::FileStat, String> {
+        let len = self.read_to_string(path)?.len() as u64;
+        Ok(xu_runtime::FileStat { len, modified_nanos: None })
+    }
+    fn canonicalize(&self, path: &str) -> Result<String, String> {
+        Ok(path.to_string())
+    }
+    fn read_to_string(&self, path: &str) -> Result<String, String> {
+        self.0.get(path).cloned().ok_or_else(|| format!("{path}: no such file"))
+    }
+}
+
+/// Runs `main` from `/mem/main.xu`, which exists only in the runtime's file
+/// system, sandboxed to `/mem` with `fs.read` grants approved.
+fn run_in_memory(main: &str) -> (Result<String, String>, Vec<xu_runtime::CapabilityAuditEntry>) {
+    let src = format!(
+        "func read_item(name) {{\n    return open(name).read()\n}}\nfunc read_granted(name) {{\n    return with_capability(\"fs.read\", || read_item(name))\n}}\n{main}"
+    );
+    let mut fs = MemFs::default();
+    fs.0.insert("/mem/main.xu".into(), src.clone());
+    fs.0.insert("/mem/a.txt".into(), "A".into());
+    fs.0.insert("/etc/b.txt".into(), "B".into());
+    let mut rt = Runtime::with_config(RuntimeConfig {
+        parallel_workers: 2,
+        ..RuntimeConfig::default()
+    });
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_file_system(Box::new(fs));
+    rt.add_allowed_root("/mem").unwrap();
+    rt.set_capability_policy(Box::new(|cap: &str| cap == "fs.read"));
+    rt.set_entry_path("/mem/main.xu").unwrap();
+    let cu = xu_driver::Driver::new()
+        .compile_text_no_analyze("/mem/main.xu", &src)
+        .unwrap();
+    let res = rt.exec_executable(&cu.executable).map(|r| r.output);
+    (res, rt.take_capability_audit_log())
+}
+
+#[test]
+fn pool_threads_use_the_capabilities_of_the_caller() {
+    let main = "println(parallel_map([\"/mem/a.txt\", \"/etc/b.txt\"], read_granted))\n";
+    let (res, log) = run_in_memory(main);
+    assert_eq!(res.unwrap(), "[A,B]\n");
+    assert!(log.iter().any(|e| e.target.as_deref() == Some("/etc/b.txt") && e.granted), "{log:?}");
+
+    let main = "println(parallel_map([\"/mem/a.txt\", \"/etc/b.txt\"], read_item))\n";
+    let err = run_in_memory(main).0.unwrap_err();
+    assert!(err.contains("item 1:") && err.contains("Path is not within allowed roots"), "{err}");
+}
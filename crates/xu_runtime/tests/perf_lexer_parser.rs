@@ -10,6 +10,27 @@ fn make_source(lines: usize) -> String {
     s
 }
 
+/// About `bytes` of typical code: indented blocks with long identifiers,
+/// CJK names, comments and string literals, with CRLF line endings.
+fn make_mixed_source(bytes: usize) -> String {
+    let mut s = String::with_capacity(bytes + 512);
+    let mut i = 0usize;
+    while s.len() < bytes {
+        s.push_str(&format!("// 计算第 {i} 组统计数据，结果写入 report_{i}\r\n"));
+        s.push_str(&format!("func compute_statistics_for_group_{i}(values: [int], 名称: string) -> string {{\r\n"));
+        s.push_str("    var accumulated_total_value = 0\r\n");
+        s.push_str("    for current_value in values {\r\n");
+        s.push_str("        accumulated_total_value = accumulated_total_value + current_value * 2\r\n");
+        s.push_str("    }\r\n");
+        s.push_str("    /* 汇总结果\r\n       并返回描述文本 */\r\n");
+        s.push_str(&format!("    let 描述 = \"group {i}: the accumulated total of all values is \\\"{{accumulated_total_value}}\\\"\"\r\n"));
+        s.push_str("    return 名称 + r\"C:\\reports\\summary.txt\" + 描述\r\n");
+        s.push_str("}\r\n\r\n");
+        i += 1;
+    }
+    s
+}
+
 #[test]
 #[ignore]
 fn perf_lexer_parser_large_sequential_assign() {
@@ -42,6 +63,30 @@ fn perf_lexer_parser_large_sequential_assign() {
     );
 }
 
+#[test]
+#[ignore]
+fn perf_lexer_mixed_source_1mb() {
+    let scale: usize = std::env::var("BENCH_SCALE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(5000);
+    let input = make_mixed_source(scale * 200);
+    let t0 = Instant::now();
+    let normalized = normalize_source(&input);
+    let t1 = Instant::now();
+    let lex = Lexer::new(&normalized.text).lex();
+    let t2 = Instant::now();
+
+    println!(
+        "PERF|perf_lexer_mixed_source_1mb|normalize_ms={}|lex_ms={}",
+        (t1 - t0).as_millis(),
+        (t2 - t1).as_millis()
+    );
+
+    assert!(normalized.diagnostics.is_empty());
+    assert!(lex.diagnostics.is_empty());
+}
+
 #[test]
 #[ignore]
 fn perf_parse_interpolated_string_many_repeated_exprs() {
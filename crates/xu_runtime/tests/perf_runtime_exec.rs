@@ -1,7 +1,7 @@
 use std::time::Instant;
 use xu_driver::Driver;
 use xu_ir::Frontend;
-use xu_runtime::Runtime;
+use xu_runtime::{PoolLimits, Runtime, RuntimeConfig};
 
 fn run(src: &str) -> String {
     let compiled = Driver::new()
@@ -69,3 +69,72 @@ func main() {{
     );
     assert!(out.trim_end() == scale.to_string());
 }
+
+#[test]
+#[ignore]
+fn perf_runtime_pooled_allocation_churn() {
+    let scale: usize = std::env::var("BENCH_SCALE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(50000);
+    let src = format!(
+        r#"
+func main() {{
+  var total = 0
+  for i in [1..{scale}] {{
+    let pair = [i, i + 1]
+    let row = {{"id": i, "name": "row number " + to_text(i) + " of the table"}}
+    total += pair[1] - pair[0] + row["name"].length
+  }}
+  println(total)
+}}
+"#
+    );
+    let compiled = Driver::new().compile_text_no_analyze("test.xu", &src).unwrap();
+    let timed = |pool_limits: PoolLimits| {
+        let mut rt = Runtime::with_config(RuntimeConfig { pool_limits, ..RuntimeConfig::default() });
+        let t0 = Instant::now();
+        let out = rt.exec_executable(&compiled.executable).unwrap().output;
+        ((Instant::now() - t0).as_millis(), out, rt.gc_stats())
+    };
+    let (plain_ms, plain_out, _) = timed(PoolLimits::NONE);
+    let (pooled_ms, pooled_out, stats) = timed(PoolLimits::default());
+    println!(
+        "PERF|perf_runtime_pooled_allocation_churn|exec_ms={pooled_ms}|unpooled_ms={plain_ms}|reuses={}",
+        stats.pool_reuses
+    );
+    assert_eq!(pooled_out, plain_out);
+    // Every collection refills the pools, so most of the loop's lists,
+    // dicts and texts come from recycled buffers.
+    assert!(stats.collections > 0);
+    assert!(stats.pool_reuses as usize > scale, "{stats:?}");
+}
+
+#[test]
+#[ignore]
+fn perf_runtime_make_closures() {
+    let scale: usize = std::env::var("BENCH_SCALE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(50000);
+    let src = format!(
+        r#"
+func main() {{
+  var total = 0
+  for i in [1..{scale}] {{
+    let add = |x| x + i
+    total += add(1) - i
+  }}
+  println(total)
+}}
+"#
+    );
+    let t0 = Instant::now();
+    let out = run(&src);
+    let t1 = Instant::now();
+    println!(
+        "PERF|perf_runtime_make_closures|exec_ms={}",
+        (t1 - t0).as_millis()
+    );
+    assert!(out.trim_end() == scale.to_string());
+}
@@ -0,0 +1,39 @@
+//! Needs the `plugins` feature: `cargo test -p xu_runtime --features plugins --test plugins`.
+
+use xu_runtime::{BuiltinProvider, BuiltinRegistry, Runtime, Value};
+
+struct Greet;
+
+impl BuiltinProvider for Greet {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("plugin_answer", |_, _| Ok(Value::from_i64(42)));
+    }
+}
+
+struct Shadow;
+
+impl BuiltinProvider for Shadow {
+    fn install(&self, registry: &mut BuiltinRegistry) {
+        registry.register("plugin_answer", |_, _| Ok(Value::from_i64(7)));
+    }
+}
+
+xu_runtime::xu_plugin!("greet", Greet);
+xu_runtime::xu_plugin!("a_shadow", Shadow);
+
+#[test]
+fn linked_plugins_are_discovered_and_installed() {
+    let plugins = xu_runtime::plugin::plugins();
+    let names: Vec<_> = plugins.iter().map(|p| p.name).collect();
+    assert_eq!(names, ["a_shadow", "greet"]);
+    assert_eq!(plugins[0].version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(xu_runtime::plugin::builtin_names(), ["plugin_answer", "plugin_answer"]);
+
+    let mut rt = Runtime::new();
+    assert_eq!(rt.extra_builtin_names(), xu_runtime::plugin::builtin_names());
+    let driver = xu_driver::Driver::new();
+    driver.add_predefs(xu_runtime::plugin::builtin_names());
+    let compiled = driver.compile_text("<plugins>", "println(plugin_answer())\n", true).unwrap();
+    assert!(compiled.diagnostics.is_empty(), "{:?}", compiled.diagnostics);
+    assert_eq!(rt.exec_executable(&compiled.executable).unwrap().output, "42\n");
+}
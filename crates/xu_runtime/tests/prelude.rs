@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::PathBuf;
+
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+struct Project {
+    prelude: PathBuf,
+    main: PathBuf,
+}
+
+fn setup(name: &str) -> Project {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::create_dir_all(&dir);
+    let prelude = dir.join("prelude.xu");
+    let lib = dir.join("lib.xu");
+    let main = dir.join("main.xu");
+    fs::write(
+        &prelude,
+        "pub func shout(s) { return s + \"!\"; }\npub let answer = 42;\nfunc hidden() { return 0; }\n",
+    )
+    .unwrap();
+    fs::write(
+        &lib,
+        "pub func greet(n) { return shout(\"hi \" + n); }\nfunc answer_twice() { return answer * 2; }\npub func twice() { return answer_twice(); }\n",
+    )
+    .unwrap();
+    fs::write(
+        &main,
+        "use \"lib.xu\" as l;\nprintln(shout(\"main\"));\nprintln(l.greet(\"bob\"));\nprintln(l.twice());\n",
+    )
+    .unwrap();
+    Project { prelude, main }
+}
+
+fn runtime(p: &Project) -> Runtime {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_prelude(p.prelude.to_string_lossy().as_ref());
+    rt.set_entry_path(p.main.to_string_lossy().as_ref()).unwrap();
+    rt
+}
+
+const EXPECTED: &str = "main!\nhi bob!\n84\n";
+
+#[test]
+fn prelude_exports_are_visible_in_every_module_ast() {
+    let p = setup("xu_runtime_prelude_ast_tests");
+    let parsed = xu_driver::Driver::new()
+        .parse_file(p.main.to_string_lossy().as_ref(), false)
+        .unwrap();
+    let mut rt = runtime(&p);
+    assert_eq!(rt.exec_module(&parsed.module).unwrap().output, EXPECTED);
+}
+
+#[test]
+fn prelude_exports_are_visible_in_every_module_vm() {
+    let p = setup("xu_runtime_prelude_vm_tests");
+    let src = fs::read_to_string(&p.main).unwrap();
+    let cu = xu_driver::Driver::new()
+        .compile_text_no_analyze(p.main.to_string_lossy().as_ref(), &src)
+        .unwrap();
+    let mut rt = runtime(&p);
+    assert_eq!(rt.exec_executable(&cu.executable).unwrap().output, EXPECTED);
+    // Re-running reinstalls the prelude.
+    assert_eq!(rt.exec_executable(&cu.executable).unwrap().output, EXPECTED);
+}
+
+#[test]
+fn private_prelude_names_stay_hidden() {
+    let p = setup("xu_runtime_prelude_private_tests");
+    let cu = xu_driver::Driver::new()
+        .compile_text_no_analyze(p.main.to_string_lossy().as_ref(), "println(hidden());\n")
+        .unwrap();
+    let mut rt = runtime(&p);
+    assert!(rt.exec_executable(&cu.executable).is_err());
+}
+
+#[test]
+fn analyzer_accepts_prelude_names() {
+    let p = setup("xu_runtime_prelude_check_tests");
+    let path = p.main.to_string_lossy().to_string();
+    let undefined = |driver: &xu_driver::Driver| {
+        driver
+            .parse_file(&path, true)
+            .unwrap()
+            .diagnostics
+            .iter()
+            .filter(|d| d.message.contains("Undefined identifier"))
+            .count()
+    };
+
+    assert!(undefined(&xu_driver::Driver::new()) > 0);
+
+    let driver = xu_driver::Driver::new();
+    driver.set_prelude(p.prelude.to_string_lossy().as_ref()).unwrap();
+    assert_eq!(undefined(&driver), 0);
+}
@@ -0,0 +1,83 @@
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn run(src: &str, seed: u64) -> Result<String, String> {
+    let path = std::env::temp_dir().join("xu_runtime_random_tests.xu");
+    let cu = xu_driver::Driver::new()
+        .compile_text_no_analyze(path.to_string_lossy().as_ref(), src)
+        .unwrap();
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_rng_seed(seed);
+    rt.exec_executable(&cu.executable).map(|r| r.output)
+}
+
+const SAMPLE: &str = r#"use "std/random" as random;
+println(random.int(1, 6));
+println(random.float());
+println(random.gauss(10.0, 2.0));
+println(random.choice(["a", "b", "c"]));
+let xs = [1, 2, 3, 4, 5, 6, 7, 8];
+random.shuffle(xs);
+println(xs);
+"#;
+
+#[test]
+fn seeded_runs_are_reproducible() {
+    let a = run(SAMPLE, 7).unwrap();
+    assert_eq!(a, run(SAMPLE, 7).unwrap());
+    assert_ne!(a, run(SAMPLE, 8).unwrap());
+
+    // `random.seed` overrides the runtime seed.
+    let seeded = format!("{SAMPLE}random.seed(99);\nprintln(random.int(0, 1000000));\n");
+    let tail = |s: String| s.lines().last().unwrap().to_string();
+    assert_eq!(tail(run(&seeded, 1).unwrap()), tail(run(&seeded, 2).unwrap()));
+}
+
+#[test]
+fn draws_stay_in_range() {
+    let src = r#"use "std/random" as random;
+var lo = 100;
+var hi = -100;
+var flo = 1.0;
+var fhi = 0.0;
+var sum = 0.0;
+for i in [0..2000] {
+    let n = random.int(-3, 3);
+    if n < lo { lo = n; }
+    if n > hi { hi = n; }
+    let f = random.float();
+    if f < flo { flo = f; }
+    if f > fhi { fhi = f; }
+    sum = sum + random.gauss(5.0, 1.0);
+}
+println(lo);
+println(hi);
+println(flo >= 0.0 && fhi < 1.0);
+let mean = sum / 2000.0;
+println(mean > 4.8 && mean < 5.2);
+println(random.int(4, 4));
+"#;
+    assert_eq!(run(src, 3).unwrap(), "-3\n3\ntrue\ntrue\n4\n");
+}
+
+#[test]
+fn shuffle_keeps_elements() {
+    let src = r#"use "std/random" as random;
+let xs = [1, 2, 3, 4, 5, 6, 7, 8];
+random.shuffle(xs);
+var sum = 0;
+for x in xs { sum = sum + x; }
+println(xs.length);
+println(sum);
+"#;
+    assert_eq!(run(src, 11).unwrap(), "8\n36\n");
+}
+
+#[test]
+fn invalid_arguments_are_errors() {
+    let prelude = "use \"std/random\" as random;\n";
+    for body in ["random.int(5, 1);", "random.choice([]);", "random.gauss(0.0, -1.0);"] {
+        assert!(run(&format!("{prelude}{body}\n"), 1).is_err(), "{body}");
+    }
+}
@@ -0,0 +1,30 @@
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn run(src: &str) -> Result<String, String> {
+    let exe = xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile").executable;
+    Runtime::new().exec_executable(&exe).map(|r| r.output)
+}
+
+const POINT: &str = "Point has {\n    x: int\n    y: int = 0\n}\n";
+
+#[test]
+fn struct_from_dict_rejects_unknown_and_missing_fields() {
+    let err = run(&format!("{POINT}struct_from_dict(\"Point\", {{\"x\": 1, \"z\": 2}})\n")).unwrap_err();
+    assert_eq!(err, "struct_from_dict: Point has no field z");
+    let err = run(&format!("{POINT}struct_from_dict(\"Point\", {{\"y\": 1}})\n")).unwrap_err();
+    assert_eq!(err, "struct_from_dict: missing field x of Point");
+    let err = run("struct_from_dict(\"Nope\", {})\n").unwrap_err();
+    assert!(err.contains("Nope"), "{err}");
+}
+
+#[test]
+fn fields_of_needs_a_struct() {
+    assert_eq!(run("fields_of([1])\n").unwrap_err(), "fields_of expects a struct, got list");
+}
+
+#[test]
+fn call_by_name_reports_unknown_methods() {
+    let err = run(&format!("{POINT}call_by_name(Point{{ x: 1 }}, \"nope\")\n")).unwrap_err();
+    assert!(err.contains("nope"), "{err}");
+}
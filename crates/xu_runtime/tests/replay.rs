@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use xu_lexer::{Lexer, normalize_source};
+use xu_parser::Parser;
+use xu_runtime::{DirEntry, Runtime, Trace, TraceEvent};
+
+struct FixedClock(i64);
+impl xu_runtime::Clock for FixedClock {
+    fn unix_secs(&self) -> i64 {
+        self.0
+    }
+    fn unix_millis(&self) -> i64 {
+        self.0 * 1000
+    }
+    fn mono_micros(&self) -> i64 {
+        self.0
+    }
+    fn mono_nanos(&self) -> i64 {
+        self.0 * 1000
+    }
+}
+
+struct MapEnv(HashMap<String, String>);
+impl xu_runtime::EnvVars for MapEnv {
+    fn var(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+}
+
+fn parse(src: &str) -> xu_parser::Module {
+    let normalized = normalize_source(src);
+    assert!(normalized.diagnostics.is_empty());
+    let lex = Lexer::new(&normalized.text).lex();
+    assert!(lex.diagnostics.is_empty());
+    let bump = bumpalo::Bump::new();
+    let parse = Parser::new(&normalized.text, &lex.tokens, &bump).parse();
+    let errors: Vec<_> = parse
+        .diagnostics
+        .into_iter()
+        .filter(|d| matches!(d.severity, xu_syntax::Severity::Error))
+        .collect();
+    assert!(errors.is_empty(), "{errors:?}");
+    parse.module
+}
+
+fn runtime(clock: i64, env: &[(&str, &str)]) -> Runtime {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_clock(Box::new(FixedClock(clock)));
+    rt.set_env_vars(Box::new(MapEnv(env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect())));
+    rt
+}
+
+#[test]
+fn replay_reproduces_a_recorded_run() {
+    let dir = std::env::temp_dir().join(format!("xu_replay_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("data.txt");
+    std::fs::write(&data, "from disk").unwrap();
+    let src = format!(
+        "println(time_unix())\nprintln(time_millis())\nprintln(rand(1000))\nprintln(env_get(\"XU_REPLAY_USER\"))\nlet f = open(\"{}\")\nprintln(f.read())\n",
+        data.display()
+    );
+    let module = parse(&src);
+
+    let mut rt = runtime(100, &[("XU_REPLAY_USER", "ada")]);
+    rt.start_recording();
+    let recorded = rt.exec_module(&module).unwrap().output;
+    let trace = rt.recorded_trace().expect("recording");
+    assert!(trace.events.contains(&TraceEvent::Env("XU_REPLAY_USER".into(), Some("ada".into()))));
+    assert!(trace.events.iter().any(|e| matches!(e, TraceEvent::Read(_, Ok(s)) if s == "from disk")));
+    assert_eq!(Trace::parse(&trace.to_text()).unwrap(), trace);
+
+    // A different clock, environment and file system state must not leak in.
+    std::fs::remove_dir_all(&dir).unwrap();
+    let mut rt = runtime(999, &[]);
+    rt.replay_trace(Trace::parse(&trace.to_text()).unwrap());
+    let replayed = rt.exec_module(&module).unwrap().output;
+    assert_eq!(replayed, recorded);
+    assert!(recorded.contains("100\n100000\n"), "{recorded}");
+    assert!(rt.replay_misses().is_empty(), "{:?}", rt.replay_misses());
+    assert!(rt.recorded_trace().is_none());
+}
+
+#[test]
+fn inputs_missing_from_the_trace_are_reported() {
+    let mut rt = runtime(5, &[]);
+    rt.start_recording();
+    rt.exec_module(&parse("println(time_unix())\n")).unwrap();
+    let trace = rt.recorded_trace().unwrap();
+
+    let mut rt = runtime(7, &[("XU_REPLAY_EXTRA", "x")]);
+    rt.replay_trace(trace);
+    let out = rt.exec_module(&parse("println(time_unix())\nprintln(env_get(\"XU_REPLAY_EXTRA\"))\n")).unwrap().output;
+    assert_eq!(out, "5\nx\n");
+    let misses = rt.replay_misses();
+    assert_eq!(misses.len(), 1, "{misses:?}");
+    assert!(misses[0].contains("XU_REPLAY_EXTRA"), "{misses:?}");
+}
+
+#[test]
+fn malformed_traces_are_rejected() {
+    assert!(Trace::parse("clock unix_secs 1\n").unwrap_err().contains("Not a trace file"));
+    let bad = Trace::parse("xu-trace 1\nclock unix_secs\n").unwrap_err();
+    assert!(bad.contains("line 2"), "{bad}");
+    assert!(Trace::parse("xu-trace 1\nenv \"open\n").unwrap_err().contains("unterminated"));
+}
+
+#[test]
+fn directory_listings_round_trip_through_trace_text() {
+    let trace = Trace {
+        events: vec![
+            TraceEvent::ReadDir(
+                "src".to_string(),
+                Ok(vec![
+                    DirEntry { name: "main.xu".to_string(), is_dir: false },
+                    DirEntry { name: "lib dir".to_string(), is_dir: true },
+                ]),
+            ),
+            TraceEvent::ReadDir("empty".to_string(), Ok(Vec::new())),
+            TraceEvent::ReadDir("gone".to_string(), Err("not found".to_string())),
+        ],
+    };
+    let text = trace.to_text();
+    assert!(text.contains("read_dir \"src\" ok \"main.xu\" file \"lib dir\" dir\n"), "{text}");
+    assert_eq!(Trace::parse(&text).unwrap(), trace);
+}
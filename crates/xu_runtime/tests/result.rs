@@ -0,0 +1,124 @@
+mod common;
+use common::run;
+
+/// The messages of the analyzer's `?` diagnostics.
+fn try_errors(src: &str) -> Vec<String> {
+    let parsed = xu_driver::Driver::new().parse_text("<test>", src, true).unwrap();
+    parsed.diagnostics.iter().filter(|d| d.code == Some("E1007")).map(|d| d.message.clone()).collect()
+}
+
+#[test]
+fn question_mark_unwraps_or_returns_early() {
+    let src = concat!(
+        "func parse(s: string) -> Result {\n",
+        "    if s == \"\" {\n",
+        "        return err(\"empty\")\n",
+        "    }\n",
+        "    return ok(s.length)\n",
+        "}\n",
+        "func total(a: string, b: string) -> Result {\n",
+        "    let x = parse(a)?\n",
+        "    println(\"parsed {a}\")\n",
+        "    return ok(x + parse(b)?)\n",
+        "}\n",
+        "println(inspect(total(\"ab\", \"cde\")))\n",
+        "println(inspect(total(\"\", \"cde\")))\n",
+        "println(inspect(total(\"ab\", \"\")))\n",
+    );
+    let out = run(src).unwrap();
+    assert_eq!(
+        out,
+        "parsed ab\nResult#ok(5)\nResult#err(\"empty\")\nparsed ab\nResult#err(\"empty\")\n"
+    );
+}
+
+#[test]
+fn question_mark_works_on_options_and_in_loops() {
+    let src = concat!(
+        "func first_even(xs: list[int]) -> Option {\n",
+        "    for x in xs {\n",
+        "        let half = (if x % 2 == 0 { Option#some(x / 2) } else { Option#none })?\n",
+        "        return Option#some(half * 2)\n",
+        "    }\n",
+        "    return Option#none\n",
+        "}\n",
+        "println(inspect(first_even([4, 1])))\n",
+        "println(inspect(first_even([3, 4])))\n",
+        "let f = |r| r? + 1\n",
+        "println(inspect(f(ok(1))), inspect(f(err(\"no\"))))\n",
+    );
+    let out = run(src).unwrap();
+    assert_eq!(out, "Option#some(4)\nOption#none\n2\nResult#err(\"no\")\n");
+}
+
+#[test]
+fn question_mark_on_other_values_is_an_error() {
+    let err = run("func f() {\n    return 1?\n}\nf()\n").unwrap_err();
+    assert!(err.contains("The '?' operator expects a Result or Option, but got int"), "{err}");
+}
+
+#[test]
+fn ok_and_err_match_as_patterns() {
+    let src = concat!(
+        "func show(r) -> string {\n",
+        "    return match r {\n",
+        "        ok(v) { \"ok {v}\" }\n",
+        "        err(e) { \"err {e}\" }\n",
+        "    }\n",
+        "}\n",
+        "println(show(ok(1)))\n",
+        "println(show(err(\"bad\")))\n",
+        "println(show(Result#ok(2)))\n",
+        "println(inspect(ok()))\n",
+    );
+    assert_eq!(run(src).unwrap(), "ok 1\nerr bad\nok 2\nResult#ok(())\n");
+}
+
+#[test]
+fn result_methods() {
+    let src = concat!(
+        "let good = ok(2)\n",
+        "let bad = err(\"boom\")\n",
+        "println(good.is_ok(), good.is_err(), bad.is_ok(), bad.is_err())\n",
+        "println(good.unwrap_or(0), bad.unwrap_or(0))\n",
+        "println(inspect(good.map(|x| x * 10)), inspect(bad.map(|x| x * 10)))\n",
+        "println(inspect(bad.map_err(|e| \"{e}!\")), inspect(good.map_err(|e| \"{e}!\")))\n",
+        "println(inspect(good.and_then(|x| err(\"odd {x}\"))), inspect(bad.and_then(|x| ok(x))))\n",
+        "println(good.expect(\"needed a value\"))\n",
+        "println(Option#some(3).expect(\"needed a value\"))\n",
+    );
+    let out = run(src).unwrap();
+    assert_eq!(
+        out,
+        concat!(
+            "true\nfalse\nfalse\ntrue\n",
+            "2\n0\n",
+            "Result#ok(20)\nResult#err(\"boom\")\n",
+            "Result#err(\"boom!\")\nResult#ok(2)\n",
+            "Result#err(\"odd 2\")\nResult#err(\"boom\")\n",
+            "2\n3\n",
+        )
+    );
+    let e = run("err(\"disk full\").expect(\"could not save\")\n").unwrap_err();
+    assert!(e.contains("could not save: disk full"), "{e}");
+    let e = run("Option#none.expect(\"no user\")\n").unwrap_err();
+    assert!(e.contains("no user"), "{e}");
+}
+
+#[test]
+fn analyzer_checks_where_question_mark_is_used() {
+    assert_eq!(
+        try_errors("func f() -> Result {\n    return ok(1)\n}\nlet x = f()?\n"),
+        vec!["The '?' operator can only be used inside a function"]
+    );
+    assert_eq!(
+        try_errors("func f() -> Result {\n    return ok(1)\n}\nfunc g() -> int {\n    return f()?\n}\n"),
+        vec!["The '?' operator returns early, but the function returns int rather than Result or Option"]
+    );
+    assert_eq!(
+        try_errors("func g(n: int) -> Result {\n    return ok(n?)\n}\n"),
+        vec!["The '?' operator expects a Result or Option, but got int"]
+    );
+    let fine = "func f(r: Result) -> Result {\n    let v = r?\n    return ok(v)\n}\nfunc g(r) {\n    return r?\n}\n";
+    assert!(try_errors(fine).is_empty());
+}
@@ -14,7 +14,7 @@ use xu_parser::Parser;
 use xu_runtime::Runtime;
 
 mod common;
-use common::{assert_or_update, find_files, golden_path_for, repo_root};
+use common::{find_files, golden, repo_root};
 
 #[derive(Clone)]
 enum Strategy {
@@ -387,8 +387,7 @@ fn run_test(suite_name: &str, _suite_root: &str, path: &PathBuf, strategy: Strat
                 result.unwrap_or_else(|e| panic!("Runtime error in {}: {}", path.display(), e));
 
             let name = path.file_stem().unwrap().to_string_lossy();
-            let subdir = golden_subdir;
-            assert_or_update(golden_path_for(subdir, &name), &result.output);
+            golden(golden_subdir).assert(&name, &result.output);
         }
         Strategy::AstVsVm => {
             let src = fs::read_to_string(path).expect("read source");
@@ -400,49 +399,26 @@ fn run_test(suite_name: &str, _suite_root: &str, path: &PathBuf, strategy: Strat
             let compiled = Driver::new()
                 .compile_text_no_analyze(path.to_string_lossy().as_ref(), &src)
                 .unwrap();
-
-            if let Executable::Bytecode(program) = compiled.executable {
-                if program.bytecode.is_some() {
-                    // Run VM
-                    let mut rt_vm = Runtime::new();
-                    setup_rt_for_edge(&mut rt_vm, path);
-
-                    let out_vm = rt_vm
-                        .exec_executable(&Executable::Bytecode(program.clone()))
-                        .unwrap_or_else(|e| {
-                            let out = rt_vm.take_output();
-                            panic!("VM failed for {}: {}\nOutput: {}", path.display(), e, out);
-                        })
-                        .output;
-
-                    // Run AST
-                    let mut rt_ast = Runtime::new();
-                    setup_rt_for_edge(&mut rt_ast, path);
-
-                    let out_ast = rt_ast
-                        .exec_module(&program.module)
-                        .unwrap_or_else(|e| {
-                            let out = rt_ast.take_output();
-                            panic!("AST failed for {}: {}\nOutput: {}", path.display(), e, out);
-                        })
-                        .output;
-
-                    assert_eq!(
-                        out_vm,
-                        out_ast,
-                        "AST vs VM output mismatch for {}",
-                        path.display()
-                    );
-                } else {
-                    // No bytecode generated (maybe empty?), just run AST
-                    let mut rt_ast = Runtime::new();
-                    setup_rt_for_edge(&mut rt_ast, path);
-                    rt_ast.exec_module(&program.module).unwrap();
-                }
-            } else {
+            let Executable::Bytecode(program) = compiled.executable else {
                 panic!("Expected Bytecode executable for {}", path.display());
-            }
+            };
+            let comparison = xu_runtime::compare_engines(&program, || {
+                let mut rt = Runtime::new();
+                setup_rt_for_edge(&mut rt, path);
+                rt
+            });
             std::env::set_current_dir(old_cwd).unwrap();
+
+            if let Some(e) = &comparison.ast.error {
+                panic!("AST failed for {}: {}\nOutput: {}", path.display(), e, comparison.ast.output);
+            }
+            let divergences: Vec<String> = comparison.divergences.iter().map(|d| d.to_string()).collect();
+            assert!(
+                divergences.is_empty(),
+                "AST vs VM mismatch for {}:\n{}",
+                path.display(),
+                divergences.join("\n")
+            );
         }
     }
 }
@@ -0,0 +1,69 @@
+use std::fs;
+
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn compile(src: &str) -> xu_ir::Executable {
+    xu_driver::Driver::new()
+        .compile_text_no_analyze("<mem>", src)
+        .expect("compile")
+        .executable
+}
+
+const WORKLOAD: &str = r#"
+let items: [text] = []
+for i in 0..2000 {
+    items.push("item{i}")
+}
+let d: {text: int} = {}
+for s in items {
+    d[s] = s.length
+}
+func total() { return d.length }
+println(total())
+"#;
+
+#[test]
+fn reset_releases_heap_between_executions() {
+    let mut rt = Runtime::new();
+    let baseline = rt.heap_object_count();
+    let exe = compile(WORKLOAD);
+    for _ in 0..5 {
+        let res = rt.exec_executable(&exe).unwrap();
+        assert_eq!(res.output.trim_end(), "2000");
+        assert!(rt.heap_object_count() > baseline);
+        rt.reset();
+        assert_eq!(rt.heap_object_count(), baseline);
+    }
+}
+
+#[test]
+fn reset_drops_globals_and_output() {
+    let mut rt = Runtime::new();
+    rt.exec_executable(&compile(WORKLOAD)).unwrap();
+    rt.write_output("pending");
+    rt.reset();
+    assert!(rt.take_output().is_empty());
+    assert!(rt.call_global("total", &[]).is_err());
+    assert!(rt.has_builtin("println"));
+}
+
+#[test]
+fn reset_preserving_std_keeps_std_imports_working() {
+    let dir = std::env::temp_dir().join("xu_runtime_reset_std_tests");
+    let std_dir = dir.join("std");
+    let _ = fs::create_dir_all(&std_dir);
+    fs::write(std_dir.join("greet.xu"), "pub func hi() { return \"hi\"; }\n").unwrap();
+
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_stdlib_path(dir.to_string_lossy().to_string());
+    let exe = compile("use \"std/greet\" as g;\nprintln(g.hi());\n");
+    let baseline = rt.heap_object_count();
+    for _ in 0..3 {
+        let res = rt.exec_executable(&exe).unwrap();
+        assert_eq!(res.output.trim_end(), "hi");
+        rt.reset_preserving_std();
+        assert_eq!(rt.heap_object_count(), baseline);
+    }
+}
@@ -0,0 +1,23 @@
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn run(src: &str) -> Result<String, String> {
+    let exe = xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile").executable;
+    Runtime::new().exec_executable(&exe).map(|r| r.output)
+}
+
+#[test]
+fn mistakes_in_the_schema_are_errors() {
+    assert_eq!(run("validate(1, {\"mni\": 0})\n").unwrap_err(), "validate: unknown schema key \"mni\"");
+    assert_eq!(run("validate(1, \"integer\")\n").unwrap_err(), "validate: unknown type \"integer\" in schema");
+    assert_eq!(run("validate(1, {\"min\": \"0\"})\n").unwrap_err(), "validate: schema key \"min\" must be a number");
+    assert_eq!(run("validate(1, [\"int\"])\n").unwrap_err(), "validate: a schema is a type name or a dict, got list");
+    assert!(run("validate(\"a\", {\"pattern\": \"(\"})\n").unwrap_err().starts_with("validate: invalid pattern"));
+}
+
+#[test]
+fn declared_types_can_be_named() {
+    let src =
+        "Point has {\n    x: int\n}\nprintln(inspect(validate(Point{ x: 1 }, \"Point\")))\nprintln(inspect(validate(1, \"Point\")))\n";
+    assert_eq!(run(src).unwrap(), "Result#ok(Point{x: 1})\nResult#err([\"$: expected Point, got int\"])\n");
+}
@@ -0,0 +1,35 @@
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn run(src: &str) -> Result<String, String> {
+    let exe = xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile").executable;
+    Runtime::new().exec_executable(&exe).map(|r| r.output)
+}
+
+#[test]
+fn values_without_an_encoding_are_rejected() {
+    assert_eq!(run("serialize(println)\n").unwrap_err(), "serialize: cannot encode a function");
+    assert_eq!(run("serialize([channel()])\n").unwrap_err(), "serialize: cannot encode a channel");
+}
+
+#[test]
+fn malformed_bytes_are_reported() {
+    assert_eq!(run("deserialize([1, 2, 3])\n").unwrap_err(), "deserialize: not serialized data");
+    assert_eq!(run("deserialize([88, 85, 9, 0])\n").unwrap_err(), "deserialize: unsupported version 9");
+    assert_eq!(run("deserialize([88, 85, 1, 6, 5])\n").unwrap_err(), "deserialize: truncated data");
+    assert_eq!(run("deserialize([88, 85, 1, 0, 0])\n").unwrap_err(), "deserialize: trailing bytes after the value");
+    assert_eq!(run("deserialize([88, 85, 1, 99])\n").unwrap_err(), "deserialize: unknown tag 99");
+    let err = run("deserialize([88, 85, 1, 13, 0])\n").unwrap_err();
+    assert_eq!(err, "deserialize: back-reference to a value not yet read");
+}
+
+#[test]
+fn deep_nesting_is_refused_instead_of_overflowing() {
+    let err = run("var v = [1]\nfor i in [0..300] {\n    v = [v]\n}\nserialize(v)\n").unwrap_err();
+    assert_eq!(err, "serialize: value is nested too deeply");
+    let mut bytes = vec!["88".to_string(), "85".to_string(), "1".to_string()];
+    bytes.extend(std::iter::repeat_n("10".to_string(), 300));
+    bytes.push("0".to_string());
+    let err = run(&format!("deserialize([{}])\n", bytes.join(", "))).unwrap_err();
+    assert_eq!(err, "deserialize: value is nested too deeply");
+}
@@ -0,0 +1,71 @@
+use xu_runtime::{ExecResult, HostValue, Runtime, Signal};
+
+mod common;
+use common::run_with;
+
+/// A runtime where `raise_int()` raises Ctrl-C, approving the `signal`
+/// capability when `grant` is set.
+fn runtime(grant: bool) -> Runtime {
+    let mut rt = Runtime::new();
+    rt.set_capability_policy(Box::new(move |cap: &str| grant && cap == "signal"));
+    let handle = rt.signal_handle();
+    rt.define_host_function("raise_int", move |_| {
+        handle.raise(Signal::Int);
+        Ok(HostValue::Unit)
+    });
+    rt
+}
+
+/// Runs `src` on both engines in runtimes from [`runtime`].
+fn run(grant: bool, src: &str) -> Result<ExecResult, String> {
+    run_with(src, || runtime(grant)).1
+}
+
+const BUSY_LOOP: &str = "var n = 0\nfor i in 0..100000 {\n    n += 1\n}\n";
+
+#[test]
+fn handler_runs_at_the_next_safepoint() {
+    let src = format!(
+        "with_capability(\"signal\", || on_signal(\"int\", || println(\"flushing\")))\nraise_int()\nprintln(\"raised\")\n{BUSY_LOOP}println(\"done\")\n"
+    );
+    assert_eq!(run(true, &src).unwrap().output, "raised\nflushing\ndone\n");
+}
+
+#[test]
+fn handler_can_shut_down_with_exit() {
+    let src = format!(
+        "with_capability(\"signal\", || on_signal(\"int\", || {{\n    println(\"saving state\")\n    exit(130)\n}}))\nraise_int()\n{BUSY_LOOP}println(\"unreachable\")\n"
+    );
+    let res = run(true, &src).unwrap();
+    assert_eq!((res.output.as_str(), res.exit_code), ("saving state\n", Some(130)));
+}
+
+#[test]
+fn later_handlers_replace_earlier_ones() {
+    let src = format!(
+        "with_capability(\"signal\", || {{\n    on_signal(\"int\", || println(\"first\"))\n    on_signal(\"int\", || println(\"second\"))\n}})\nraise_int()\n{BUSY_LOOP}"
+    );
+    assert_eq!(run(true, &src).unwrap().output, "second\n");
+}
+
+#[test]
+fn unhandled_signals_interrupt_the_run() {
+    let src = format!("raise_int()\n{BUSY_LOOP}println(\"unreachable\")\n");
+    assert!(run(false, &src).unwrap_err().contains("Execution interrupted"));
+}
+
+#[test]
+fn on_signal_needs_the_signal_capability() {
+    let err = run(false, "with_capability(\"signal\", || on_signal(\"int\", || 0))\n").unwrap_err();
+    assert!(err.contains("signal"), "{err}");
+    let err = run(true, "on_signal(\"int\", || 0)\n").unwrap_err();
+    assert!(err.contains("signal"), "{err}");
+}
+
+#[test]
+fn on_signal_checks_its_arguments() {
+    let err = run(true, "with_capability(\"signal\", || on_signal(\"usr1\", || 0))\n").unwrap_err();
+    assert!(err.contains("on_signal: unknown signal 'usr1' (expected int, term or hup)"), "{err}");
+    let err = run(true, "with_capability(\"signal\", || on_signal(\"int\", 1))\n").unwrap_err();
+    assert!(err.contains("on_signal expects a function"), "{err}");
+}
@@ -0,0 +1,84 @@
+use xu_runtime::Runtime;
+
+mod common;
+use common::{program, run};
+
+const NUMBERS: &str = "var xs: [int] = []\nfor i in 0..100 {\n    xs.push(i)\n}\n";
+
+#[test]
+fn list_slices_do_not_see_later_writes() {
+    let src = format!(
+        "{NUMBERS}{}",
+        concat!(
+            "let tail = xs.drop(50)\n",
+            "let head = xs.take(40)\n",
+            "xs[60] = -1\n",
+            "xs.push(100)\n",
+            "println(\"{tail.length} {tail[0]} {tail[10]} {head.length} {head[39]} {xs[60]} {xs.length}\")\n",
+        )
+    );
+    assert_eq!(run(&src).unwrap(), "50 50 60 40 39 -1 101\n");
+}
+
+#[test]
+fn writes_to_a_slice_stay_in_the_slice() {
+    let src = format!(
+        "{NUMBERS}{}",
+        concat!(
+            "var mid = xs.drop(10).take(30)\n",
+            "mid[0] = -5\n",
+            "mid.push(7)\n",
+            "var short = xs.take(3)\n",
+            "short.push(9)\n",
+            "println(\"{mid.length} {mid[0]} {mid[29]} {mid[30]} {xs[10]} {xs.length} {short}\")\n",
+        )
+    );
+    assert_eq!(run(&src).unwrap(), "31 -5 39 7 10 100 [0,1,2,9]\n");
+}
+
+#[test]
+fn substrings_match_copies() {
+    let src = concat!(
+        "var line = \"\"\n",
+        "for i in 0..20 {\n    line = line + \"field{i};\"\n}\n",
+        "let rest = line.substr(7, 60)\n",
+        "println(rest.length, rest.substr(0, 7), rest == line.substr(7, 60))\n",
+        "let parts = line.split(\"field1\")\n",
+        "println(parts.length, parts[1].substr(0, 3), parts[2].length)\n",
+        "println((\"   \" + line + \"  \").trim() == line, line.substr(142, 9), line.substr(500, 5) == \"\")\n",
+        "var accents = \"\"\n",
+        "for i in 0..40 {\n    accents = accents + \"é{i % 10}\"\n}\n",
+        "let view = accents.substr(5, 70)\n",
+        "println(view.length, view.substr(0, 3), view.substr(68, 9))\n",
+    );
+    assert_eq!(run(src).unwrap(), "60\nfield1;\ntrue\n12\n;fi\n2\ntrue\nfield19;\ntrue\n70\n2é3\n6é\n");
+}
+
+#[test]
+fn slices_outlive_their_parents() {
+    let src = concat!(
+        "func tail() {\n",
+        "    var xs: [string] = []\n",
+        "    for i in 0..200 {\n        xs.push(\"item-{i}\")\n    }\n",
+        "    return xs.drop(150)\n",
+        "}\n",
+        "func middle() {\n",
+        "    var line = \"\"\n",
+        "    for i in 0..100 {\n        line = line + \"<{i}>\"\n    }\n",
+        "    return line.substr(100, 80)\n",
+        "}\n",
+        "func show(items, text) {\n",
+        "    println(\"{items.length} {items[0]} {items[49]} {text.length} {text.substr(0, 6)}\")\n",
+        "}\n",
+    );
+    let p = program(src);
+    let mut rt = Runtime::new();
+    rt.exec_program(&p).unwrap();
+    let mut held = [rt.call_global("tail", &[]).unwrap(), rt.call_global("middle", &[]).unwrap()];
+    for _ in 0..2 {
+        rt.gc(&held);
+        rt.call_global("show", &held).unwrap();
+        assert_eq!(rt.take_output(), "50 item-150 item-199 80 7><28>\n");
+        rt.compact_heap(&mut held).unwrap();
+    }
+}
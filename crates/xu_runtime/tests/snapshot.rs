@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use xu_ir::Frontend;
+use xu_ir::image::{ImageReader, ImageWriter};
+use xu_runtime::Runtime;
+
+fn workspace_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+fn setup(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::create_dir_all(&dir);
+    let lib = dir.join("lib.xu");
+    let main = dir.join("main.xu");
+    fs::write(
+        &lib,
+        "pub Point has {\n    x: int\n    y: int\n}\npub func sum(p) { return p.x + p.y; }\npub let origin = Point{ x: 0, y: 0 };\n",
+    )
+    .unwrap();
+    fs::write(
+        &main,
+        format!(
+            "use \"{}\" as l;\nprintln(l.sum(l.Point{{ x: 2, y: 3 }}));\n",
+            lib.display()
+        ),
+    )
+    .unwrap();
+    (lib, main)
+}
+
+fn build(lib: &Path) -> Vec<u8> {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let report = rt.build_snapshot(&[lib.to_string_lossy().as_ref()]);
+    assert!(report.skipped.is_empty(), "{:?}", report.skipped);
+    assert_eq!(report.modules.len(), 1);
+    report.bytes
+}
+
+fn run_main(rt: &mut Runtime, main: &Path) -> Result<String, String> {
+    let src = fs::read_to_string(main).unwrap();
+    let cu = xu_driver::Driver::new()
+        .compile_text_no_analyze(main.to_string_lossy().as_ref(), &src)
+        .unwrap();
+    rt.set_entry_path(main.to_string_lossy().as_ref()).unwrap();
+    rt.exec_executable(&cu.executable).map(|r| r.output)
+}
+
+#[test]
+fn image_round_trips_compiled_programs() {
+    let driver = xu_driver::Driver::new();
+    for name in ["examples/bank_system/main.xu", "examples/rule_engine_dsl.xu"] {
+        let path = workspace_root().join(name);
+        let src = fs::read_to_string(&path).unwrap();
+        let cu = driver
+            .compile_text_no_analyze(path.to_string_lossy().as_ref(), &src)
+            .unwrap();
+        let mut w = ImageWriter::new();
+        w.put(&cu.executable);
+        let bytes = w.finish();
+        let mut r = ImageReader::new(&bytes);
+        let decoded: xu_ir::Executable = r.get().unwrap();
+        assert!(r.is_empty());
+        assert_eq!(decoded, cu.executable, "{name}");
+    }
+}
+
+#[test]
+fn snapshot_modules_import_without_frontend() {
+    let (lib, main) = setup("xu_runtime_snapshot_load_tests");
+    let bytes = build(&lib);
+
+    // No frontend: the import only succeeds if it comes from the snapshot.
+    let mut rt = Runtime::new();
+    assert_eq!(rt.load_snapshot(&bytes).unwrap(), 1);
+    assert_eq!(run_main(&mut rt, &main).unwrap(), "5\n");
+}
+
+#[test]
+fn stale_snapshot_entries_are_reparsed() {
+    let (lib, main) = setup("xu_runtime_snapshot_stale_tests");
+    let bytes = build(&lib);
+    fs::write(
+        &lib,
+        "pub Point has {\n    x: int\n    y: int\n}\npub func sum(p) { return p.x * p.y; }\n",
+    )
+    .unwrap();
+
+    let mut rt = Runtime::new();
+    rt.load_snapshot(&bytes).unwrap();
+    assert!(run_main(&mut rt, &main).is_err());
+
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.load_snapshot(&bytes).unwrap();
+    assert_eq!(run_main(&mut rt, &main).unwrap(), "6\n");
+}
+
+#[test]
+fn invalid_images_are_rejected() {
+    let (lib, _) = setup("xu_runtime_snapshot_invalid_tests");
+    let bytes = build(&lib);
+    let mut rt = Runtime::new();
+    assert!(rt.load_snapshot(b"not an image").is_err());
+    assert!(rt.load_snapshot(&bytes[..bytes.len() - 3]).is_err());
+    let mut extra = bytes.clone();
+    extra.push(0);
+    assert!(rt.load_snapshot(&extra).is_err());
+}
+
+#[test]
+fn unresolvable_imports_are_reported_as_skipped() {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let report = rt.build_snapshot(&["/nonexistent/xu_snapshot_missing.xu"]);
+    assert!(report.modules.is_empty());
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(rt.load_snapshot(&report.bytes).unwrap(), 0);
+}
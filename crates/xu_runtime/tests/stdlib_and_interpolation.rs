@@ -93,3 +93,68 @@ println(h.read());
     assert!(out.contains("a\nb"), "{out}");
     assert!(!out.contains("a\nb\n\n\n"), "{out}");
 }
+
+#[test]
+fn math_builtins_coerce_ints_and_floats() {
+    let src = r#"
+use "std/math" as m;
+println(sqrt(16));
+println(pow(2, 0.5) == sqrt(2));
+println(log2(1024));
+println(exp(0));
+println(log(exp(2)));
+println(sin(0) + cos(0) + tan(0));
+println(clamp(15, 0, 10));
+println(clamp(-1.5, 0, 10));
+println(clamp(2, 0.5, 10));
+println(max(2, 3.5));
+println(gcd(12, -18));
+println(gcd(0, 0));
+println(lcm(4, 6));
+println(lcm(0, 9));
+println(m.pi > 3.14 && m.pi < 3.15);
+println(m.e > 2.71 && m.tau == 2 * m.pi);
+"#;
+    let module = parse_source(src);
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    let out = rt.exec_module(&module).unwrap().output;
+    assert_eq!(
+        out,
+        "4\ntrue\n10\n1\n2\n1\n10\n0\n2\n3.5\n6\n0\n12\n0\ntrue\ntrue\n"
+    );
+
+    for bad in ["gcd(1.5, 2);", "lcm(4, \"6\");", "clamp(1, 5, 0);"] {
+        let module = parse_source(bad);
+        assert!(rt.exec_module(&module).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn local_shadowing_a_builtin_gets_its_own_slot() {
+    let path = std::env::temp_dir().join("xu_runtime_shadow_builtin_tests.xu");
+    fs::write(
+        &path,
+        "func f(v: int) {\n    let exp = v + 1\n    for i in 0..2 {\n        println(i)\n    }\n    println(exp)\n}\nf(1)\n",
+    )
+    .unwrap();
+    let parsed = xu_driver::Driver::new()
+        .parse_file(path.to_string_lossy().as_ref(), false)
+        .unwrap();
+    let mut rt = Runtime::new();
+    assert_eq!(rt.exec_module(&parsed.module).unwrap().output, "0\n1\n2\n");
+}
+
+#[test]
+fn list_higher_order_methods_reject_bad_arguments() {
+    for (src, expected) in [
+        ("[1, 2].chunk(0);\n", "chunk size must be positive"),
+        ("[1, 2].any(|n| n);\n", "int"),
+        ("[1, 2].zip(3);\n", "list"),
+    ] {
+        let module = parse_source(src);
+        let mut rt = Runtime::new();
+        let err = rt.exec_module(&module).unwrap_err();
+        assert!(err.contains(expected), "{src}: {err}");
+    }
+}
@@ -0,0 +1,51 @@
+use xu_runtime::{Runtime, RuntimeConfig};
+
+mod common;
+use common::run_with;
+
+const POINT: &str = "Point has {\n    x: int\n    y: float\n    label: text = \"p\"\n    tag: T\n}\n";
+
+/// Runs `src` after the `Point` declaration on the AST executor and on the
+/// VM, which must agree.
+fn run(src: &str, strict_fields: bool) -> Result<String, String> {
+    let config = RuntimeConfig { strict_fields, ..RuntimeConfig::default() };
+    run_with(&format!("{POINT}{src}"), || Runtime::with_config(config)).1.map(|r| r.output)
+}
+
+#[test]
+fn struct_literals_check_field_types() {
+    let err = run("let p = Point { x: \"a\", y: 2.0 }\n", true).unwrap_err();
+    assert!(err.contains("Type mismatch for field 'x' of struct Point: expected int but got text"), "{err}");
+    let err = run("let p = Point { ...{\"x\": 1, \"y\": true} }\n", true).unwrap_err();
+    assert!(err.contains("field 'y' of struct Point: expected float but got bool"), "{err}");
+    // Ints pass as floats, unset fields stay unit and unknown type names are not checked.
+    let src = "let p = Point { x: 1, y: 2, label: \"q\", tag: [1] }\nprintln(p.y, p.label, Point { x: 1 }.tag)\n";
+    assert_eq!(run(src, true).unwrap(), "2\nq\n()\n");
+}
+
+#[test]
+fn field_assignment_checks_the_declared_type() {
+    let err = run("var p = Point { x: 1 }\np.x = \"b\"\n", true).unwrap_err();
+    assert!(err.contains("field 'x' of struct Point: expected int but got text"), "{err}");
+    let err = run("var p = Point { x: 1 }\np.x += 0.5\n", true).unwrap_err();
+    assert!(err.contains("expected int but got float"), "{err}");
+    let err = run("var p = Point { x: 1 }\np.label = 3\n", true).unwrap_err();
+    assert!(err.contains("field 'label' of struct Point: expected text but got int"), "{err}");
+    let src = "var p = Point { x: 1 }\np.y = 3\np.label += \"!\"\nprintln(p.y, p.label)\n";
+    assert_eq!(run(src, true).unwrap(), "3\np!\n");
+}
+
+#[test]
+fn strict_fields_can_be_turned_off() {
+    let src = "var p = Point { x: \"a\" }\np.y = false\nprintln(p.x, p.y)\n";
+    assert_eq!(run(src, false).unwrap(), "a\nfalse\n");
+}
+
+#[test]
+fn runtime_and_analyzer_report_a_mismatch_alike() {
+    let src = "let p = Point { x: \"a\", y: 2.0 }\n";
+    let parsed = xu_driver::Driver::new().parse_text("<fields>", &format!("{POINT}{src}"), true).unwrap();
+    let analyzed = parsed.diagnostics.iter().find(|d| d.message.contains("field 'x'")).expect("analyzer error");
+    let err = run(src, true).unwrap_err();
+    assert!(err.contains(&analyzed.message), "{err}");
+}
@@ -24,6 +24,7 @@ fn top_level_define_errors_in_strict_mode() {
     let module = parse_module("x = 1;\n");
     let mut rt = Runtime::with_config(RuntimeConfig {
         strict_vars: true,
+        ..RuntimeConfig::default()
     });
     rt.set_frontend(Box::new(xu_driver::Driver::new()));
     let result = rt.exec_module(&module);
@@ -39,6 +40,7 @@ fn top_level_define_allowed_in_non_strict_mode() {
     let module = parse_module("x = 1;\nprintln(x);\n");
     let mut rt = Runtime::with_config(RuntimeConfig {
         strict_vars: false,
+        ..RuntimeConfig::default()
     });
     rt.set_frontend(Box::new(xu_driver::Driver::new()));
     let result = rt.exec_module(&module);
@@ -0,0 +1,30 @@
+use xu_ir::Frontend;
+use xu_runtime::Runtime;
+
+fn run(src: &str) -> Result<String, String> {
+    let exe = xu_driver::Driver::new().compile_text_no_analyze("<mem>", src).expect("compile").executable;
+    Runtime::new().exec_executable(&exe).map(|r| r.output)
+}
+
+fn render(template: &str) -> Result<String, String> {
+    run(&format!("println(__template_render(r\"{template}\", {{\"n\": 1, \"xs\": [1]}}))\n"))
+}
+
+#[test]
+fn renders_names_conditions_and_loops() {
+    assert_eq!(render("{{ n }}{% if xs %}+{% endif %}{% for x in xs %}[{{ x }}]{% endfor %}").unwrap(), "1+[1]\n");
+    assert_eq!(render("{% if missing %}a{% elif not n %}b{% else %}c{% endif %}").unwrap(), "c\n");
+}
+
+#[test]
+fn mistakes_in_the_template_are_errors() {
+    assert_eq!(render("{{ m }}").unwrap_err(), "template: line 1: m is not defined");
+    assert_eq!(render("{% if n %}").unwrap_err(), "template: line 1: if without endif");
+    assert_eq!(render("{% endfor %}").unwrap_err(), "template: line 1: endfor without for");
+    assert_eq!(render("{% for x in n %}{% endfor %}").unwrap_err(), "template: line 1: n is not a list but int");
+    assert_eq!(render("{{ n | shout }}").unwrap_err(), "template: line 1: unknown filter shout");
+    assert_eq!(render("{% while n %}").unwrap_err(), "template: line 1: unknown tag {% while n %}");
+    assert_eq!(render("{{ n").unwrap_err(), "template: line 1: unclosed {{");
+    let two_lines = "print(__template_render(r\"a\" + \"\\n\" + r\"{{ b }}\", {}))\n";
+    assert_eq!(run(two_lines).unwrap_err(), "template: line 2: b is not defined");
+}
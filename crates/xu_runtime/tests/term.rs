@@ -0,0 +1,67 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use xu_ir::Frontend;
+use xu_runtime::{Runtime, Terminal};
+
+/// A terminal answering prompts from a script.
+struct Scripted {
+    tty: bool,
+    answers: RefCell<VecDeque<&'static str>>,
+}
+
+impl Terminal for Scripted {
+    fn is_tty(&self) -> bool {
+        self.tty
+    }
+
+    fn read_line(&self) -> Option<String> {
+        self.answers.borrow_mut().pop_front().map(str::to_string)
+    }
+}
+
+struct MapEnv(HashMap<&'static str, &'static str>);
+
+impl xu_runtime::EnvVars for MapEnv {
+    fn var(&self, key: &str) -> Option<String> {
+        self.0.get(key).map(|v| v.to_string())
+    }
+}
+
+fn run(tty: bool, env: &[(&'static str, &'static str)], answers: &[&'static str], src: &str) -> String {
+    let src = format!("use \"std/term\" as term\n{src}");
+    let exe = xu_driver::Driver::new().compile_text_no_analyze("<mem>", &src).expect("compile").executable;
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_stdlib_path(concat!(env!("CARGO_MANIFEST_DIR"), "/../../stdlib").to_string());
+    rt.set_env_vars(Box::new(MapEnv(env.iter().copied().collect())));
+    rt.set_terminal(Box::new(Scripted { tty, answers: RefCell::new(answers.iter().copied().collect()) }));
+    rt.exec_executable(&exe).map(|r| r.output).unwrap()
+}
+
+#[test]
+fn colors_need_a_terminal_and_respect_no_color() {
+    let src = "println(term.red(\"x\"))\n";
+    assert_eq!(run(true, &[], &[], src), "\x1b[31mx\x1b[0m\n");
+    assert_eq!(run(true, &[("NO_COLOR", "1")], &[], src), "x\n");
+    assert_eq!(run(true, &[("TERM", "dumb")], &[], src), "x\n");
+    assert_eq!(run(false, &[], &[], src), "x\n");
+}
+
+#[test]
+fn progress_bars_redraw_in_place_on_a_terminal() {
+    let src = "let p = term.progress(2)\np.width = 4\np.tick()\np.finish()\n";
+    assert_eq!(run(true, &[], &[], src), "\r[##--]  50% 1/2\r[####] 100% 2/2\n");
+}
+
+#[test]
+fn prompts_read_from_the_terminal() {
+    let src = concat!(
+        "println(term.prompt(\"name?\"))\n",
+        "println(term.prompt(\"lang?\", default: \"xu\"))\n",
+        "println(term.confirm(\"sure?\"))\n",
+        "println(term.confirm(\"again?\", default: true))\n",
+    );
+    let out = run(false, &[], &["  Ada ", "", "maybe", "YES"], src);
+    assert_eq!(out, "name? Ada\nlang? [xu] xu\nsure? [y/N] sure? [y/N] true\nagain? [Y/n] true\n");
+}
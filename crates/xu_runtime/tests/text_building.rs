@@ -0,0 +1,31 @@
+mod common;
+use common::run;
+
+#[test]
+fn long_texts_built_piece_by_piece_read_back_whole() {
+    let src = concat!(
+        "func build(n: int) -> string {\n",
+        "    var s = \"\"\n",
+        "    for i in 0..n {\n        s = s + \"<{i}>\" + i % 7 + (i % 2 == 0)\n    }\n",
+        "    return s\n",
+        "}\n",
+        "let s = build(3000)\n",
+        "println(s.length, s.substr(0, 12), s.substr(s.length - 12, 12))\n",
+        "println(s.find(\"<2999>\"), s.split(\"<1500>\")[1].substr(0, 10))\n",
+    );
+    assert_eq!(run(src).unwrap(), "33390\n<0>0true<1>1\n<2999>3false\n33378\n2true<1501\n");
+}
+
+#[test]
+fn appending_to_an_earlier_text_leaves_later_ones_alone() {
+    let src = concat!(
+        "var base = \"\"\n",
+        "for i in 0..1000 {\n    base = base + \"abcde\"\n}\n",
+        "let longer = base + \"-longer\"\n",
+        "let other = base + \"-other\" + 1.5\n",
+        "let grown = longer + \"!\"\n",
+        "println(base.length, longer.substr(4995, 12), other.substr(4995, 15), grown.substr(5000, 8))\n",
+        "println(longer == base + \"-longer\", base.length)\n",
+    );
+    assert_eq!(run(src).unwrap(), "5000\nabcde-longer\nabcde-other1.5\n-longer!\ntrue\n5000\n");
+}
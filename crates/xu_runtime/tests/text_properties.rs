@@ -2,6 +2,7 @@ use proptest::prelude::*;
 use xu_runtime::Text;
 
 const INLINE_CAP: usize = 22;
+const ROPE_MIN: usize = 4096;
 
 proptest! {
     #[test]
@@ -36,11 +37,42 @@ proptest! {
                 Text::Inline { .. } => {},
                 _ => prop_assert!(false, "expected Inline for total<=INLINE_CAP"),
             }
-        } else {
+        } else if total < ROPE_MIN {
             match t {
                 Text::Heap { .. } => {},
                 _ => prop_assert!(false, "expected Heap for total>INLINE_CAP"),
             }
+        } else {
+            match t {
+                Text::Rope(_) => {},
+                _ => prop_assert!(false, "expected Rope for total>=ROPE_MIN"),
+            }
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn text_append_ropes_read_like_strings(
+        base in "[a-zé]{2100,2200}",
+        pieces in prop::collection::vec(".{0,40}", 1..60),
+        fork in any::<prop::sample::Index>(),
+    ) {
+        let mut texts = vec![Text::from_str(&base)];
+        let mut strings = vec![base];
+        for piece in &pieces {
+            texts.push(Text::append(texts.last().unwrap(), piece));
+            strings.push(format!("{}{}", strings.last().unwrap(), piece));
+        }
+        // Appending to an earlier rope leaves the ropes built after it alone.
+        let i = fork.index(texts.len());
+        let mut forked = texts[i].clone();
+        forked.push_str("fork");
+        prop_assert_eq!(forked.as_str(), format!("{}fork", strings[i]));
+        for (t, s) in texts.iter().zip(&strings) {
+            prop_assert_eq!(t.len(), s.len());
+            prop_assert_eq!(t.char_count(), s.chars().count());
+            prop_assert_eq!(t.as_str(), s.as_str());
         }
     }
 }
@@ -0,0 +1,117 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use xu_runtime::{Clock, HostValue, Runtime, Signal};
+
+mod common;
+use common::run_with;
+
+/// A clock that only moves when slept on, noting each wait.
+#[derive(Clone, Default)]
+struct FakeClock {
+    micros: Arc<AtomicI64>,
+    sleeps: Arc<Mutex<Vec<u64>>>,
+}
+
+impl Clock for FakeClock {
+    fn unix_secs(&self) -> i64 {
+        self.micros.load(Ordering::Relaxed) / 1_000_000
+    }
+
+    fn unix_millis(&self) -> i64 {
+        self.micros.load(Ordering::Relaxed) / 1000
+    }
+
+    fn mono_micros(&self) -> i64 {
+        self.micros.load(Ordering::Relaxed)
+    }
+
+    fn mono_nanos(&self) -> i64 {
+        self.micros.load(Ordering::Relaxed) * 1000
+    }
+
+    fn sleep_millis(&self, ms: u64) {
+        self.micros.fetch_add(ms as i64 * 1000, Ordering::Relaxed);
+        self.sleeps.lock().unwrap().push(ms);
+    }
+}
+
+fn runtime(clock: &FakeClock) -> Runtime {
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_stdlib_path(concat!(env!("CARGO_MANIFEST_DIR"), "/../../stdlib").to_string());
+    rt.set_clock(Box::new(clock.clone()));
+    rt
+}
+
+/// Runs `src` on the AST executor and on the VM with fresh fake clocks; both
+/// must print the same and wait the same. Returns the VM's output and waits.
+fn run(src: &str, setup: impl Fn(&mut Runtime)) -> (Result<String, String>, Vec<u64>) {
+    let mut clocks = Vec::new();
+    let (_, vm) = run_with(src, || {
+        let clock = FakeClock::default();
+        clocks.push(clock.clone());
+        let mut rt = runtime(&clock);
+        setup(&mut rt);
+        rt
+    });
+    let [ast_clock, vm_clock] = &clocks[..] else { unreachable!("one runtime per engine") };
+    let sleeps = vm_clock.sleeps.lock().unwrap().clone();
+    assert_eq!(*ast_clock.sleeps.lock().unwrap(), sleeps);
+    (vm.map(|r| r.output), sleeps)
+}
+
+#[test]
+fn sleep_goes_through_the_clock() {
+    let src = "let start = mono_micros()\nsleep(120)\nsleep(0)\nprintln(mono_micros() - start)\n";
+    let (out, sleeps) = run(src, |_| {});
+    assert_eq!(out.unwrap(), "120000\n");
+    assert_eq!(sleeps, vec![50, 50, 20]);
+}
+
+#[test]
+fn stopwatch_measures_and_resets() {
+    let src = concat!(
+        "use \"std/time\" as t\n",
+        "let sw = t.stopwatch()\n",
+        "sleep(1500)\n",
+        "println(sw.elapsed_ms(), sw.elapsed_us())\n",
+        "sw.reset()\n",
+        "sleep(7)\n",
+        "println(sw.elapsed_ms(), t.unix_millis())\n",
+    );
+    let (out, _) = run(src, |_| {});
+    assert_eq!(out.unwrap(), "1500\n1500000\n7\n1507\n");
+}
+
+#[test]
+fn signal_handlers_run_during_a_sleep() {
+    let src = concat!(
+        "with_capability(\"signal\", || on_signal(\"int\", || {\n",
+        "    println(\"woken\")\n",
+        "    exit(130)\n",
+        "}))\n",
+        "raise_int()\n",
+        "sleep(60000)\n",
+        "println(\"unreachable\")\n",
+    );
+    let (out, sleeps) = run(src, |rt| {
+        rt.set_capability_policy(Box::new(|cap: &str| cap == "signal"));
+        let handle = rt.signal_handle();
+        rt.define_host_function("raise_int", move |_| {
+            handle.raise(Signal::Int);
+            Ok(HostValue::Unit)
+        });
+    });
+    assert_eq!(out.unwrap(), "woken\n");
+    assert_eq!(sleeps, vec![50]);
+}
+
+#[test]
+fn sleep_takes_a_non_negative_int() {
+    let (out, sleeps) = run("sleep(-1)\n", |_| {});
+    assert!(out.unwrap_err().contains("sleep: milliseconds must not be negative, got -1"));
+    assert!(sleeps.is_empty());
+    let (out, _) = run("sleep(1.5)\n", |_| {});
+    assert!(out.unwrap_err().contains("sleep expects an int number of milliseconds, got float"));
+}
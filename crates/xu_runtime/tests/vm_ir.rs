@@ -54,14 +54,14 @@ fn vm_ir_while_loop_prints_0_1_2() {
     let i_name = add_const_str(&mut bc, "i");
     bc.ops.push(Op::StoreName(i_name));
     // loop start:
-    let loop_start = bc.ops.len();
+    let loop_start = bc.ops.len() as u32;
     // i < 3
     bc.ops.push(Op::LoadName(i_name));
     bc.ops.push(Op::ConstInt(3));
     bc.ops.push(Op::Lt);
     // placeholder JumpIfFalse, will be patched by VM run loop via positions
     let jfalse_pos = bc.ops.len();
-    bc.ops.push(Op::JumpIfFalse(usize::MAX));
+    bc.ops.push(Op::JumpIfFalse(u32::MAX));
     // print i
     bc.ops.push(Op::LoadName(i_name));
     bc.ops.push(Op::Print);
@@ -73,7 +73,7 @@ fn vm_ir_while_loop_prints_0_1_2() {
     // jump to loop_start
     bc.ops.push(Op::Jump(loop_start));
     // end label
-    let end = bc.ops.len();
+    let end = bc.ops.len() as u32;
     if let Op::JumpIfFalse(to) = &mut bc.ops[jfalse_pos] {
         *to = end;
     }
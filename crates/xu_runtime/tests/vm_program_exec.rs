@@ -8,6 +8,7 @@ fn runtime_executes_bytecode_when_present() {
     let bc = Bytecode {
         ops: vec![Op::Const(0), Op::Print, Op::Halt],
         constants: vec![Constant::Str("X".to_string())],
+        ..Bytecode::default()
     };
     let program = Program {
         module: xu_ir::Module {
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use xu_ir::Frontend;
+use xu_runtime::{CapabilityAuditEntry, FileStat, FileSystem, Runtime, TraceEvent};
+
+/// Files kept in memory, so nothing a worker reads exists on disk.
+#[derive(Clone, Default)]
+struct MemFs(Arc<Mutex<HashMap<String, String>>>);
+
+impl FileSystem for MemFs {
+    fn metadata(&self, path: &str) -> Result<(), String> {
+        self.read_to_string(path).map(|_| ())
+    }
+    fn stat(&self, path: &str) -> Result<FileStat, String> {
+        let len = self.read_to_string(path)?.len() as u64;
+        Ok(FileStat { len, modified_nanos: None })
+    }
+    fn canonicalize(&self, path: &str) -> Result<String, String> {
+        Ok(path.to_string())
+    }
+    fn read_to_string(&self, path: &str) -> Result<String, String> {
+        self.0.lock().unwrap().get(path).cloned().ok_or_else(|| format!("{path}: no such file"))
+    }
+}
+
+struct FixedClock(i64);
+impl xu_runtime::Clock for FixedClock {
+    fn unix_secs(&self) -> i64 {
+        self.0
+    }
+    fn unix_millis(&self) -> i64 {
+        self.0 * 1000
+    }
+    fn mono_micros(&self) -> i64 {
+        self.0
+    }
+    fn mono_nanos(&self) -> i64 {
+        self.0 * 1000
+    }
+}
+
+struct OneVar;
+impl xu_runtime::EnvVars for OneVar {
+    fn var(&self, key: &str) -> Option<String> {
+        (key == "XU_WORKER_USER").then(|| "ada".to_string())
+    }
+}
+
+const ENTRY: &str = "/mem/main.xu";
+
+/// A runtime whose entry script, data and inputs exist only in its
+/// capabilities, sandboxed to `/mem`.
+fn runtime(main: &str) -> (Runtime, String) {
+    let src = format!(
+        "use \"std/worker\" as worker\n\nfunc probe() {{\n    let f = open(\"/mem/data.txt\")\n    return \"{{env_get(\\\"XU_WORKER_USER\\\")}} {{time_unix()}} {{f.read()}}\"\n}}\n\nfunc outside() {{\n    return with_capability(\"fs.read\", || open(\"/etc/data.txt\").read())\n}}\n\n{main}"
+    );
+    let fs = MemFs::default();
+    fs.0.lock().unwrap().insert(ENTRY.into(), src.clone());
+    fs.0.lock().unwrap().insert("/mem/data.txt".into(), "in memory".into());
+    fs.0.lock().unwrap().insert("/etc/data.txt".into(), "granted".into());
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(xu_driver::Driver::new()));
+    rt.set_file_system(Box::new(fs));
+    rt.set_clock(Box::new(FixedClock(42)));
+    rt.set_env_vars(Box::new(OneVar));
+    rt.add_allowed_root("/mem").unwrap();
+    rt.set_entry_path(ENTRY).unwrap();
+    (rt, src)
+}
+
+fn exec(rt: &mut Runtime, src: &str) -> Result<String, String> {
+    let cu = xu_driver::Driver::new().compile_text_no_analyze(ENTRY, src).unwrap();
+    rt.exec_executable(&cu.executable).map(|r| r.output)
+}
+
+#[test]
+fn workers_read_files_env_and_clock_through_the_parent_capabilities() {
+    let (mut rt, src) = runtime("println(worker.spawn(probe).join())\n");
+    assert_eq!(exec(&mut rt, &src).unwrap(), "ada 42 in memory\n");
+}
+
+#[test]
+fn workers_keep_the_sandbox_and_policy_of_the_parent() {
+    let (mut rt, src) = runtime("println(worker.spawn(outside).join())\n");
+    let err = exec(&mut rt, &src).unwrap_err();
+    assert!(err.contains("Capability denied: fs.read"), "{err}");
+
+    let (mut rt, src) = runtime("println(worker.spawn(outside).join())\n");
+    rt.set_capability_policy(Box::new(|cap: &str| cap == "fs.read"));
+    assert_eq!(exec(&mut rt, &src).unwrap(), "granted\n");
+    let log = rt.capability_audit_log();
+    assert_eq!(log[0], CapabilityAuditEntry { capability: "fs.read".into(), target: None, granted: true });
+    assert!(log.iter().any(|e| e.target.as_deref() == Some("/etc/data.txt") && e.granted));
+}
+
+#[test]
+fn a_recording_includes_what_workers_read() {
+    let (mut rt, src) = runtime("println(worker.spawn(probe).join())\n");
+    rt.start_recording();
+    exec(&mut rt, &src).unwrap();
+    let trace = rt.recorded_trace().unwrap();
+    assert!(trace.events.contains(&TraceEvent::Env("XU_WORKER_USER".into(), Some("ada".into()))));
+    assert!(
+        trace
+            .events
+            .iter()
+            .any(|e| matches!(e, TraceEvent::Read(p, Ok(s)) if p == "/mem/data.txt" && s == "in memory"))
+    );
+}
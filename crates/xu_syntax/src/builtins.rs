@@ -2,19 +2,73 @@ pub const BUILTIN_NAMES: &[&str] = &[
     "__builtin_assert",
     "__builtin_assert_eq",
     "__heap_stats",
+    "__gc_collect",
+    "__gc_stats",
+    "__bench",
     "__set_from_list",
+    "dict",
+    "inspect",
+    "help",
+    "__random_seed",
+    "__random_int",
+    "__random_float",
+    "__random_gauss",
+    "__random_choice",
+    "__random_shuffle",
+    "__crypto_sha256",
+    "__crypto_sha1",
+    "__crypto_md5",
+    "__crypto_hmac_sha256",
+    "__crypto_crc32",
+    "__csv_parse",
+    "__csv_next",
+    "__csv_write",
+    "__fs_glob",
+    "__fs_walk_next",
+    "__unix_time",
+    "__unix_millis",
+    "__mono_micros",
+    "__mono_nanos",
+    "__term_is_tty",
+    "__term_colors",
+    "__term_style",
+    "__term_width",
+    "__term_write",
+    "__term_read_line",
+    "__term_bar",
+    "__term_table",
+    "__template_render",
+    "__worker_spawn",
+    "__worker_send",
+    "__worker_recv",
+    "__worker_close",
+    "__worker_join",
+    "__ffi_open",
+    "__ffi_symbol",
+    "__ffi_call",
+    "__ffi_close",
     "os_args",
     "env_get",
     "print",
     "println",
+    "eprint",
+    "eprintln",
+    "exit",
+    "panic",
+    "ok",
+    "err",
     "gc",
     "gen_id",
     "open",
     "input",
+    "input_int",
+    "input_float",
+    "input_secret",
     "time_unix",
     "time_millis",
     "mono_micros",
     "mono_nanos",
+    "sleep",
     "abs",
     "sin",
     "cos",
@@ -22,12 +76,27 @@ pub const BUILTIN_NAMES: &[&str] = &[
     "sqrt",
     "log",
     "pow",
+    "log2",
+    "exp",
+    "clamp",
+    "gcd",
+    "lcm",
     "max",
     "min",
     "rand",
     "to_text",
     "parse_int",
     "parse_float",
+    "base64_encode",
+    "base64_decode",
+    "hex_encode",
+    "hex_decode",
+    "serialize",
+    "deserialize",
+    "validate",
+    "parallel_map",
+    "channel",
+    "recv_any",
     "builder_new",
     "builder_new_cap",
     "builder_push",
@@ -36,23 +105,299 @@ pub const BUILTIN_NAMES: &[&str] = &[
     "starts_with",
     "ends_with",
     "process_rss",
+    "with_capability",
+    "on_signal",
+    "type_of",
+    "fields_of",
+    "methods_of",
+    "struct_from_dict",
+    "call_by_name",
 ];
 
 pub fn builtin_return_type(name: &str) -> Option<&'static str> {
     match name {
-        "__builtin_assert" | "__builtin_assert_eq" | "__heap_stats" => Some("unit"),
-        "__set_from_list" => Some("dict"),
-        "print" | "println" | "builder_push" | "gc" => Some("unit"),
-        "gen_id" | "time_unix" | "time_millis" | "mono_micros" | "mono_nanos" | "abs" | "max"
-        | "min" | "rand" | "parse_int" => Some("int"),
+        "__builtin_assert" | "__builtin_assert_eq" | "__heap_stats" | "__gc_collect" | "__bench" => {
+            Some("unit")
+        }
+        "__set_from_list" | "dict" | "__gc_stats" => Some("dict"),
+        "__random_seed" | "__random_shuffle" => Some("unit"),
+        "__random_int" => Some("int"),
+        "__random_float" | "__random_gauss" => Some("float"),
+        "__crypto_sha256" | "__crypto_sha1" | "__crypto_md5" | "__crypto_hmac_sha256"
+        | "__crypto_crc32" | "__csv_write" | "__template_render" => Some("text"),
+        "__csv_parse" | "__fs_glob" | "parallel_map" => Some("list"),
+        "__worker_spawn" => Some("int"),
+        "__worker_send" | "__worker_close" => Some("unit"),
+        "__ffi_open" => Some("int"),
+        "__ffi_symbol" | "__ffi_close" => Some("unit"),
+        "print" | "println" | "eprint" | "eprintln" | "on_signal" | "sleep" | "builder_push" | "gc" | "help" => Some("unit"),
+        "ok" | "err" => Some("Result"),
+        "gen_id" | "time_unix" | "time_millis" | "mono_micros" | "mono_nanos" | "__unix_time"
+        | "__unix_millis" | "__mono_micros" | "__mono_nanos" | "abs" | "max" | "min" | "rand" | "parse_int" | "gcd" | "lcm" => Some("int"),
         "open" => Some("file"),
-        "os_args" => Some("list"),
-        "env_get" => Some("text"),
-        "input" | "to_text" | "builder_finalize" => Some("text"),
-        "parse_float" | "sin" | "cos" | "tan" | "sqrt" | "log" | "pow" => Some("float"),
+        "channel" => Some("channel"),
+        "os_args" | "base64_decode" | "hex_decode" | "serialize" => Some("list"),
+        "env_get" | "type_of" => Some("text"),
+        "fields_of" => Some("dict"),
+        "methods_of" => Some("list"),
+        "input" | "to_text" | "builder_finalize" | "inspect" | "base64_encode" | "hex_encode" => {
+            Some("text")
+        }
+        "parse_float" | "sin" | "cos" | "tan" | "sqrt" | "log" | "pow" | "log2"
+        | "exp" => Some("float"),
         "builder_new" | "builder_new_cap" => Some("builder"),
         "contains" | "starts_with" | "ends_with" => Some("bool"),
         "process_rss" => Some("int"),
         _ => None,
     }
 }
+
+/// Parameters of a builtin meant to be called by scripts, checked by the
+/// analyzer at call sites. Each entry of `params` is the type a parameter
+/// takes (`"any"` for values of any type, `"float"` also takes ints); the
+/// arguments from `min` on may be left out, and a builtin without a `max`
+/// takes any number of arguments. The result type is [`builtin_return_type`].
+///
+/// `names` names the parameters of the builtins that take labeled
+/// arguments, as in `inspect(v, max_items: 3)`. A labeled call passes `()`
+/// for the optional parameters it skips, which the builtin treats as left
+/// out.
+#[derive(Clone, Copy, Debug)]
+pub struct BuiltinSig {
+    pub name: &'static str,
+    pub min: usize,
+    pub max: Option<usize>,
+    pub params: &'static [&'static str],
+    pub names: &'static [&'static str],
+}
+
+impl BuiltinSig {
+    pub fn return_type(&self) -> Option<&'static str> {
+        builtin_return_type(self.name)
+    }
+}
+
+macro_rules! sig {
+    ($name:literal, $min:literal.., [$($ty:literal),*]) => {
+        BuiltinSig { name: $name, min: $min, max: None, params: &[$($ty),*], names: &[] }
+    };
+    ($name:literal, $min:literal..=$max:literal, [$($ty:literal),*]) => {
+        BuiltinSig { name: $name, min: $min, max: Some($max), params: &[$($ty),*], names: &[] }
+    };
+    ($name:literal, $min:literal..=$max:literal, [$($ty:literal),*], names [$($n:literal),*]) => {
+        BuiltinSig { name: $name, min: $min, max: Some($max), params: &[$($ty),*], names: &[$($n),*] }
+    };
+}
+
+/// Signatures of the builtins documented in `BUILTIN_DOCS`.
+static BUILTIN_SIGS: &[BuiltinSig] = &[
+    sig!("print", 0.., []),
+    sig!("println", 0.., []),
+    sig!("eprint", 0.., []),
+    sig!("eprintln", 0.., []),
+    sig!("exit", 0..=1, ["int"]),
+    sig!("panic", 1..=1, ["any"]),
+    sig!("ok", 0..=1, ["any"]),
+    sig!("err", 1..=1, ["any"]),
+    sig!("input", 0..=3, ["any", "any", "any"], names ["prompt", "default", "validate"]),
+    sig!("input_int", 0..=3, ["any", "any", "any"], names ["prompt", "default", "validate"]),
+    sig!("input_float", 0..=3, ["any", "any", "any"], names ["prompt", "default", "validate"]),
+    sig!("input_secret", 0..=3, ["any", "any", "any"], names ["prompt", "default", "validate"]),
+    sig!("inspect", 1..=4, ["any", "bool", "int", "int"], names ["value", "pretty", "max_depth", "max_items"]),
+    sig!("dict", 0..=1, ["int"], names ["capacity"]),
+    sig!("help", 1..=1, ["any"]),
+    sig!("to_text", 1..=1, ["any"]),
+    sig!("parse_int", 1..=1, ["any"]),
+    sig!("parse_float", 1..=1, ["any"]),
+    sig!("base64_encode", 1..=1, ["any"]),
+    sig!("base64_decode", 1..=1, ["text"]),
+    sig!("hex_encode", 1..=1, ["any"]),
+    sig!("hex_decode", 1..=1, ["text"]),
+    sig!("serialize", 1..=1, ["any"]),
+    sig!("deserialize", 1..=1, ["list"]),
+    sig!("validate", 2..=2, ["any", "any"]),
+    sig!("parallel_map", 2..=2, ["list", "func"]),
+    sig!("channel", 0..=1, ["int"]),
+    sig!("recv_any", 1..=1, ["list"]),
+    sig!("open", 1..=2, ["text", "text"]),
+    sig!("os_args", 0..=0, []),
+    sig!("env_get", 1..=1, ["text"]),
+    sig!("gc", 0..=0, []),
+    sig!("gen_id", 0..=0, []),
+    sig!("time_unix", 0..=0, []),
+    sig!("time_millis", 0..=0, []),
+    sig!("mono_micros", 0..=0, []),
+    sig!("mono_nanos", 0..=0, []),
+    sig!("sleep", 1..=1, ["int"]),
+    sig!("process_rss", 0..=0, []),
+    sig!("rand", 0..=1, ["int"]),
+    sig!("abs", 1..=1, ["float"]),
+    sig!("min", 2..=2, ["float", "float"]),
+    sig!("max", 2..=2, ["float", "float"]),
+    sig!("clamp", 3..=3, ["float", "float", "float"]),
+    sig!("sqrt", 1..=1, ["float"]),
+    sig!("pow", 2..=2, ["float", "float"]),
+    sig!("exp", 1..=1, ["float"]),
+    sig!("log", 1..=1, ["float"]),
+    sig!("log2", 1..=1, ["float"]),
+    sig!("sin", 1..=1, ["float"]),
+    sig!("cos", 1..=1, ["float"]),
+    sig!("tan", 1..=1, ["float"]),
+    sig!("gcd", 2..=2, ["int", "int"]),
+    sig!("lcm", 2..=2, ["int", "int"]),
+    sig!("contains", 2..=2, ["text", "text"]),
+    sig!("starts_with", 2..=2, ["text", "text"]),
+    sig!("ends_with", 2..=2, ["text", "text"]),
+    sig!("builder_new", 0..=0, []),
+    sig!("builder_new_cap", 1..=1, ["int"]),
+    sig!("builder_push", 2..=2, ["builder", "any"]),
+    sig!("builder_finalize", 1..=1, ["builder"]),
+    sig!("type_of", 1..=1, ["any"]),
+    sig!("fields_of", 1..=1, ["any"]),
+    sig!("methods_of", 1..=1, ["text"]),
+    sig!("struct_from_dict", 2..=2, ["text", "dict"]),
+    sig!("call_by_name", 2..=3, ["any", "text", "list"]),
+    sig!("with_capability", 2..=2, ["text", "func"]),
+    sig!("on_signal", 2..=2, ["text", "func"]),
+];
+
+pub fn builtin_signature(name: &str) -> Option<&'static BuiltinSig> {
+    BUILTIN_SIGS.iter().find(|s| s.name == name)
+}
+
+/// Methods the runtime provides on text values.
+const TEXT_METHODS: &[&str] = &[
+    "get", "get_int", "format", "split", "split_lazy", "to_int", "to_float", "try_to_int", "try_to_float",
+    "to_upper", "to_lower", "contains", "starts_with", "ends_with", "trim", "trim_start", "trim_end", "replace",
+    "find", "substr", "length", "char_length", "byte_length", "chars", "code_points", "graphemes", "to_bytes",
+    "normalize", "match",
+];
+
+/// Methods the runtime provides on lists; `add` is compiled to an append
+/// when the receiver is known to be a list.
+const LIST_METHODS: &[&str] = &[
+    "get", "get_int", "push", "add", "insert", "remove", "pop", "clear", "contains", "reverse", "join", "length",
+    "filter", "map", "each", "any", "all", "flat_map", "zip", "enumerate", "take", "drop", "chunk", "sort",
+    "sorted", "sort_by_key", "reduce", "find", "find_index", "find_or", "first", "repeat", "reserve", "to_string",
+];
+
+/// Methods the runtime provides on dicts.
+const DICT_METHODS: &[&str] = &[
+    "get", "get_int", "get_or_default", "has", "contains", "insert", "insert_int", "remove", "pop",
+    "entry_or_insert", "clear", "merge", "update", "keys", "keys_sorted", "values", "items", "length",
+];
+
+const INT_METHODS: &[&str] = &["to_string", "abs", "to_base", "is_even", "is_odd"];
+const FLOAT_METHODS: &[&str] = &["to_string", "abs", "to_int", "round", "floor", "ceil"];
+const BOOL_METHODS: &[&str] = &["to_string", "not"];
+
+/// Names of the builtin methods of a builtin type (`"text"`, `"list"`,
+/// `"dict"`, `"int"`, `"float"` or `"bool"`). These types take no methods
+/// from `does` blocks, so calling any other name fails at run time.
+pub fn builtin_methods(ty: &str) -> Option<&'static [&'static str]> {
+    match ty {
+        "text" => Some(TEXT_METHODS),
+        "list" => Some(LIST_METHODS),
+        "dict" => Some(DICT_METHODS),
+        "int" => Some(INT_METHODS),
+        "float" => Some(FLOAT_METHODS),
+        "bool" => Some(BOOL_METHODS),
+        _ => None,
+    }
+}
+
+/// Default `max_depth` of `inspect`.
+pub const INSPECT_MAX_DEPTH: i64 = 8;
+/// Default `max_items` of `inspect`.
+pub const INSPECT_MAX_ITEMS: i64 = 100;
+
+/// Signature and one-paragraph description of a builtin, shown by `help`.
+#[derive(Clone, Copy, Debug)]
+pub struct BuiltinDoc {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub doc: &'static str,
+}
+
+macro_rules! doc {
+    ($name:literal, $sig:literal, $doc:literal) => {
+        BuiltinDoc { name: $name, signature: $sig, doc: $doc }
+    };
+}
+
+/// Documentation of the builtins meant to be called by scripts; hidden
+/// `__` hooks are left out.
+static BUILTIN_DOCS: &[BuiltinDoc] = &[
+    doc!("print", "print(...values)", "Writes each value on its own line."),
+    doc!("println", "println(...values)", "Same as `print`."),
+    doc!("eprint", "eprint(...values)", "Writes each value on its own line to standard error."),
+    doc!("eprintln", "eprintln(...values)", "Same as `eprint`."),
+    doc!("exit", "exit(code?: int)", "Ends the program with the exit code `code` (0 to 255, default 0). Output written so far is kept."),
+    doc!("panic", "panic(message)", "Fails the program with `message`, reported as a panic; `xu run` exits with code 101."),
+    doc!("ok", "ok(value?)", "Builds `Result#ok(value)`; `value` defaults to `()`."),
+    doc!("err", "err(error)", "Builds `Result#err(error)`. Inside a function, `result?` returns an error like this one early."),
+    doc!("input", "input(prompt?: text, default: text, validate: func) -> text", "Writes the prompt, then reads one line from the terminal without its line ending. An empty answer gives `default`; answers `validate` turns down are asked again. Gives `default` or \"\" at the end of input."),
+    doc!("input_int", "input_int(prompt?: text, default: int, validate: func) -> Option", "Asks until a whole number is typed. An empty answer gives `default`; answers `validate` turns down are asked again. None at the end of input."),
+    doc!("input_float", "input_float(prompt?: text, default: float, validate: func) -> Option", "Asks until a number is typed. An empty answer gives `default`; answers `validate` turns down are asked again. None at the end of input."),
+    doc!("input_secret", "input_secret(prompt?: text, default: text, validate: func) -> Option", "Reads a line like `input` without showing what is typed. None at the end of input."),
+    doc!("inspect", "inspect(v, pretty: bool = false, max_depth: int = 8, max_items: int = 100) -> text", "Renders a value for debugging: strings are quoted, deep or long containers are truncated and cycles are marked."),
+    doc!("help", "help(name: text | func)", "Prints the signature and doc comment of a builtin, a stdlib function or a user function."),
+    doc!("dict", "dict(capacity: int) -> dict", "Creates an empty dict with room for `capacity` entries."),
+    doc!("to_text", "to_text(v) -> text", "Converts a value to the text `println` would show."),
+    doc!("parse_int", "parse_int(s: text | number) -> int", "Parses an integer, truncating numeric text with a fraction."),
+    doc!("parse_float", "parse_float(s: text | number) -> float", "Parses a float."),
+    doc!("base64_encode", "base64_encode(data: text | [int]) -> text", "Standard base64 with padding of a byte list, or of the UTF-8 bytes of a text."),
+    doc!("base64_decode", "base64_decode(s: text) -> [int]", "Bytes of a base64 text; whitespace is skipped and padding is optional."),
+    doc!("hex_encode", "hex_encode(data: text | [int]) -> text", "Lowercase hex digits of a byte list, or of the UTF-8 bytes of a text."),
+    doc!("hex_decode", "hex_decode(s: text) -> [int]", "Bytes of a text of hex digit pairs, in either case."),
+    doc!("serialize", "serialize(v) -> [int]", "Compact binary encoding of a value: numbers, text, lists, tuples, dicts, ranges, options, enums and structs; shared and cyclic parts are kept."),
+    doc!("deserialize", "deserialize(bytes: [int])", "Rebuilds a value from the bytes of `serialize`."),
+    doc!("validate", "validate(value, schema) -> Result", "Checks a value against a schema (a type name, or a dict of `type`, `min`, `max`, `min_length`, `max_length`, `pattern`, `one_of`, `items`, `keys`, `required`, `values`, `extra`); ok with the value, or err with every violation as \"path: problem\"."),
+    doc!("parallel_map", "parallel_map(items: list, f: func) -> list", "Applies the top-level function `f` to copies of the items on a pool of threads, each a fresh runtime; results keep the item order."),
+    doc!("channel", "channel(capacity?: int) -> channel", "A queue of copied values with `send`, `recv`, `try_recv` and `close`; `send` waits while `capacity` messages are queued. Channels can be sent to workers."),
+    doc!("recv_any", "recv_any(channels: [channel]) -> Option", "Waits for a message on any of the channels and returns `(index, value)`, from the first ready one in list order; none once all are closed and drained."),
+    doc!("open", "open(path: text, mode: text = \"r\") -> file", "Opens a file for reading, or for writing with mode \"w\"; needs the fs capability."),
+    doc!("os_args", "os_args() -> [text]", "Command line arguments passed to the script."),
+    doc!("env_get", "env_get(key: text) -> text", "Value of an environment variable, or \"\" when unset."),
+    doc!("gc", "gc()", "Runs a full garbage collection."),
+    doc!("gen_id", "gen_id() -> int", "Returns a new id, unique within the runtime."),
+    doc!("time_unix", "time_unix() -> int", "Wall-clock time in seconds since the Unix epoch."),
+    doc!("time_millis", "time_millis() -> int", "Wall-clock time in milliseconds since the Unix epoch."),
+    doc!("mono_micros", "mono_micros() -> int", "Monotonic clock in microseconds, for measuring durations."),
+    doc!("mono_nanos", "mono_nanos() -> int", "Monotonic clock in nanoseconds, for measuring durations."),
+    doc!("sleep", "sleep(ms: int)", "Blocks for `ms` milliseconds on the runtime clock; signal handlers still run while sleeping."),
+    doc!("process_rss", "process_rss() -> int", "Peak resident set size of the process as reported by the OS."),
+    doc!("rand", "rand(upper?: int) -> int", "Random integer in [0, upper), or a raw 64-bit draw without a bound."),
+    doc!("abs", "abs(x: number) -> number", "Absolute value."),
+    doc!("min", "min(a: number, b: number) -> number", "Smaller of two numbers; int when both are ints."),
+    doc!("max", "max(a: number, b: number) -> number", "Larger of two numbers; int when both are ints."),
+    doc!("clamp", "clamp(x: number, lo: number, hi: number) -> number", "Limits x to [lo, hi]; int when all three are ints."),
+    doc!("sqrt", "sqrt(x: float) -> float", "Square root."),
+    doc!("pow", "pow(x: float, y: float) -> float", "x raised to the power y."),
+    doc!("exp", "exp(x: float) -> float", "e raised to the power x."),
+    doc!("log", "log(x: float) -> float", "Natural logarithm."),
+    doc!("log2", "log2(x: float) -> float", "Base-2 logarithm."),
+    doc!("sin", "sin(x: float) -> float", "Sine of an angle in radians."),
+    doc!("cos", "cos(x: float) -> float", "Cosine of an angle in radians."),
+    doc!("tan", "tan(x: float) -> float", "Tangent of an angle in radians."),
+    doc!("gcd", "gcd(a: int, b: int) -> int", "Greatest common divisor."),
+    doc!("lcm", "lcm(a: int, b: int) -> int", "Least common multiple."),
+    doc!("contains", "contains(s: text, part: text) -> bool", "Whether `part` occurs in `s`."),
+    doc!("starts_with", "starts_with(s: text, prefix: text) -> bool", "Whether `s` begins with `prefix`."),
+    doc!("ends_with", "ends_with(s: text, suffix: text) -> bool", "Whether `s` ends with `suffix`."),
+    doc!("builder_new", "builder_new() -> builder", "Creates an empty text builder."),
+    doc!("builder_new_cap", "builder_new_cap(n: int) -> builder", "Creates an empty text builder with room for n bytes."),
+    doc!("builder_push", "builder_push(b: builder, v)", "Appends the text form of v to the builder."),
+    doc!("builder_finalize", "builder_finalize(b: builder) -> text", "Returns the builder's contents as text."),
+    doc!("type_of", "type_of(v) -> text", "The struct or enum name of v, or the name of its builtin type (\"int\", \"string\", \"list\", ...)."),
+    doc!("fields_of", "fields_of(v: struct) -> dict", "The fields of a struct value as a dict of name to value, in declaration order."),
+    doc!("methods_of", "methods_of(type_name: text) -> [text]", "Sorted method names of a struct or enum type; empty for other types."),
+    doc!("struct_from_dict", "struct_from_dict(type_name: text, fields: dict)", "Builds a struct from a dict of field values; left-out fields take their defaults, unknown or missing fields are errors."),
+    doc!("call_by_name", "call_by_name(obj, method: text, args: list = [])", "Calls `obj.method(...args)` with the method given by name."),
+    doc!("with_capability", "with_capability(name: text, f: func)", "Calls f with the named capability granted, if the policy allows it."),
+    doc!("on_signal", "on_signal(name: text, handler: func)", "Runs handler() when the signal \"int\" (Ctrl-C), \"term\" or \"hup\" arrives, at the next loop iteration or so. Needs the signal capability."),
+];
+
+pub fn builtin_doc(name: &str) -> Option<&'static BuiltinDoc> {
+    BUILTIN_DOCS.iter().find(|d| d.name == name)
+}
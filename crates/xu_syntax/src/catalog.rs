@@ -0,0 +1,242 @@
+//! Diagnostic message catalog.
+//!
+//! Every [`DiagnosticKind`](crate::DiagnosticKind) renders through a template
+//! looked up here by key. Templates use `{name}` placeholders. A language
+//! without a translation for a key falls back to English.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Languages the catalog has translations for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    ZhCn,
+}
+
+impl Lang {
+    /// Parses a language tag such as `en`, `zh-CN` or `zh_CN`.
+    pub fn parse(tag: &str) -> Option<Lang> {
+        let tag = tag.to_ascii_lowercase().replace('_', "-");
+        match tag.as_str() {
+            "en" | "en-us" | "en-gb" => Some(Lang::En),
+            "zh" | "zh-cn" | "zh-hans" => Some(Lang::ZhCn),
+            _ => None,
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::ZhCn => "zh-CN",
+        }
+    }
+}
+
+static CURRENT_LANG: AtomicU8 = AtomicU8::new(0);
+
+/// Sets the process-wide language used when rendering diagnostics.
+pub fn set_lang(lang: Lang) {
+    CURRENT_LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+/// The language named by the `XU_LANG` environment variable, if it holds a
+/// tag [`Lang::parse`] accepts.
+pub fn lang_from_env() -> Option<Lang> {
+    std::env::var("XU_LANG").ok().and_then(|tag| Lang::parse(&tag))
+}
+
+pub fn current_lang() -> Lang {
+    match CURRENT_LANG.load(Ordering::Relaxed) {
+        1 => Lang::ZhCn,
+        _ => Lang::En,
+    }
+}
+
+/// One catalog entry.
+#[derive(Clone, Copy, Debug)]
+pub struct MessageEntry {
+    /// Stable key, named after the `DiagnosticKind` variant.
+    pub key: &'static str,
+    /// Diagnostic code (see [`codes`](crate::codes)), if the message has one.
+    pub code: Option<&'static str>,
+    pub en: &'static str,
+    pub zh_cn: Option<&'static str>,
+}
+
+impl MessageEntry {
+    /// Template for `lang`, falling back to English.
+    pub fn template(&self, lang: Lang) -> &'static str {
+        match lang {
+            Lang::En => self.en,
+            Lang::ZhCn => self.zh_cn.unwrap_or(self.en),
+        }
+    }
+}
+
+/// All messages, for tooling such as editors and language servers.
+pub fn messages() -> &'static [MessageEntry] {
+    MESSAGES
+}
+
+pub fn lookup(key: &str) -> Option<&'static MessageEntry> {
+    MESSAGES.iter().find(|m| m.key == key)
+}
+
+pub fn lookup_code(code: &str) -> Option<&'static MessageEntry> {
+    MESSAGES.iter().find(|m| m.code == Some(code))
+}
+
+/// Renders the message `key` in `lang`, substituting `{name}` placeholders.
+pub fn render(key: &str, args: &[(&str, String)], lang: Lang) -> String {
+    let Some(entry) = lookup(key) else {
+        return key.to_string();
+    };
+    let template = entry.template(lang);
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let name = &after[..end];
+                match args.iter().find(|(n, _)| *n == name) {
+                    Some((_, v)) => out.push_str(v),
+                    None => {
+                        out.push('{');
+                        out.push_str(name);
+                        out.push('}');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+macro_rules! msg {
+    ($key:literal, $code:expr, $en:literal, $zh:literal) => {
+        MessageEntry {
+            key: $key,
+            code: $code,
+            en: $en,
+            zh_cn: Some($zh),
+        }
+    };
+}
+
+use crate::codes;
+
+static MESSAGES: &[MessageEntry] = &[
+    // Lexer
+    msg!("TabNotAllowed", None, "Tab is not allowed; use ASCII spaces", "不允许使用制表符，请使用 ASCII 空格"),
+    msg!("FullWidthSpaceNotAllowed", None, "Full-width space is not allowed; use ASCII spaces", "不允许使用全角空格，请使用 ASCII 空格"),
+    msg!("UnterminatedBlockComment", Some(codes::UNTERMINATED_BLOCK_COMMENT), "Unterminated block comment", "块注释未闭合"),
+    msg!("UnmatchedDelimiter", None, "Unmatched '{delim}'", "未匹配的 '{delim}'"),
+    msg!("DotNotTerminator", None, "Dot is not a statement terminator", "点号不能作为语句结束符"),
+    msg!("UnterminatedString", Some(codes::UNTERMINATED_STRING), "Unterminated string literal", "字符串字面量未闭合"),
+    msg!("UnexpectedChar", Some(codes::UNEXPECTED_CHAR), "Unexpected character: {char}", "意外的字符：{char}"),
+    msg!("UnclosedDelimiter", Some(codes::UNCLOSED_DELIMITER), "Unclosed '{delim}'", "未闭合的 '{delim}'"),
+    // Parser
+    msg!("ExpectedToken", Some(codes::EXPECTED_TOKEN), "Expected {token}", "应为 {token}"),
+    msg!("ExpectedExpression", Some(codes::EXPECTED_EXPRESSION), "Expected expression", "应为表达式"),
+    msg!("InvalidAssignmentTarget", Some(codes::INVALID_ASSIGNMENT_TARGET), "Invalid assignment target", "无效的赋值目标"),
+    msg!("ExpectedImportPath", None, "Expected string literal or argument list after import", "import 之后应为字符串字面量或参数列表"),
+    msg!("TrailingInterpolationTokens", None, "Interpolation expression has trailing tokens", "插值表达式后有多余的记号"),
+    msg!("KeywordAsIdentifier", Some(codes::KEYWORD_AS_IDENTIFIER), "Keyword '{keyword}' cannot be used as an identifier", "关键字 '{keyword}' 不能用作标识符"),
+    msg!("MissingMatchDefault", None, "Match statement requires a default branch '_'", "match 语句需要默认分支 '_'"),
+    msg!("IfExprWithoutElse", None, "An if expression requires an else branch", "if 表达式需要 else 分支"),
+    msg!("InvalidArgumentLabel", None, "Invalid argument label '{label}' in call to {func}", "调用 {func} 时的参数标签 '{label}' 无效"),
+    msg!("UnknownAnnotation", None, "Unknown annotation: @{name}", "未知的注解：@{name}"),
+    msg!("ContractWithoutFunction", None, "Contract annotations must precede a function", "契约注解必须位于函数之前"),
+    msg!(
+        "EnsuresMayNotReturn",
+        None,
+        "@ensures uses `result`, but {func} may end without returning a value",
+        "@ensures 使用了 `result`，但 {func} 可能不返回值就结束"
+    ),
+    msg!("StaticFieldWithoutDefault", None, "Static field requires a default value", "静态字段需要默认值"),
+    msg!("CannotExtendBuiltin", None, "cannot extend builtin type: {ty}", "不能扩展内置类型：{ty}"),
+    msg!("WhenIsNotMatch", None, "Use 'match' for pattern matching. 'when' is for optional binding: when x = expr { ... } else { ... }", "模式匹配请使用 'match'。'when' 用于可选绑定：when x = expr { ... } else { ... }"),
+    msg!("WhenBindingExpectsEq", None, "Expected '=' after identifier in 'when' binding. Use 'match' for pattern matching.", "'when' 绑定中的标识符之后应为 '='。模式匹配请使用 'match'。"),
+    msg!("NestingTooDeep", Some(codes::NESTING_TOO_DEEP), "Expression or block is nested too deeply", "表达式或代码块嵌套过深"),
+    // Analyzer
+    msg!("UnreachableCode", Some(codes::UNREACHABLE_CODE), "Unreachable code", "不可达的代码"),
+    msg!("UnitAssignment", Some(codes::VOID_ASSIGNMENT), "Cannot assign unit to a variable", "不能将 unit 赋值给变量"),
+    msg!("EmptyContainerNeedsAnnotation", None, "Type annotation required for empty container literal", "空容器字面量需要类型注解"),
+    msg!("TryOutsideFunction", Some(codes::INVALID_TRY), "The '?' operator can only be used inside a function", "'?' 运算符只能在函数内使用"),
+    msg!("TryInNonResultFunction", Some(codes::INVALID_TRY), "The '?' operator returns early, but the function returns {ty} rather than Result or Option", "'?' 运算符会提前返回，但函数的返回类型为 {ty}，不是 Result 或 Option"),
+    msg!("ConstNotAtModuleLevel", None, "Constants can only be declared at module level", "常量只能在模块顶层声明"),
+    msg!("ConstNotEvaluable", None, "The value of constant '{name}' must be computable at compile time", "常量 '{name}' 的值必须能在编译期求出"),
+    msg!("ConstReassignment", None, "Cannot assign to constant '{name}'", "不能给常量 '{name}' 赋值"),
+    msg!("Shadowing", Some(codes::SHADOWING), "Variable '{name}' shadows an existing binding", "变量 '{name}' 遮蔽了已有的绑定"),
+    msg!("DidYouMean", None, "Did you mean '{name}'?", "你是不是想写 '{name}'？"),
+    msg!("VariableDefinedHere", None, "Variable is defined here", "变量在此处定义"),
+    msg!("ReturnTypeDeclaredHere", None, "Function return type is declared at definition", "函数的返回类型在定义处声明"),
+    msg!("ArgumentHasWrongType", None, "Argument {index} has wrong type", "第 {index} 个参数类型错误"),
+    msg!("UndefinedIdentifier", Some(codes::UNDEFINED_IDENTIFIER), "Undefined identifier: {name}", "未定义的标识符：{name}"),
+    msg!("TypeMismatch", Some(codes::TYPE_MISMATCH), "Type mismatch: expected {expected} but got {actual}", "类型不匹配：期望 {expected}，实际为 {actual}"),
+    msg!("ArgumentCountMismatch", Some(codes::ARGUMENT_COUNT_MISMATCH), "Argument count mismatch: expected {expected} but got {actual}", "参数个数不匹配：期望 {expected} 个，实际为 {actual} 个"),
+    msg!("ArgumentCountMismatchRange", None, "Argument count mismatch: expected {min}..{max} but got {actual}", "参数个数不匹配：期望 {min}..{max} 个，实际为 {actual} 个"),
+    // Runtime
+    msg!("IndexOutOfRange", Some(codes::INDEX_OUT_OF_RANGE), "Index out of range", "索引越界"),
+    msg!("KeyNotFound", Some(codes::KEY_NOT_FOUND), "Key not found: {key}", "键不存在：{key}"),
+    msg!("FileNotOpen", None, "File is not open", "文件未打开"),
+    msg!("CircularImport", Some(codes::CIRCULAR_IMPORT), "Circular import: {chain}", "循环导入：{chain}"),
+    msg!("TopLevelBreakContinue", None, "Break or continue is not allowed at top level", "顶层不允许使用 break 或 continue"),
+    msg!("DivisionByZero", Some(codes::DIVISION_BY_ZERO), "Division by zero", "除以零"),
+    msg!("IntegerOverflow", Some(codes::INTEGER_OVERFLOW), "Integer overflow", "整数溢出"),
+    msg!("NotCallable", Some(codes::NOT_CALLABLE), "'{name}' is not callable", "'{name}' 不可调用"),
+    msg!("UnknownMember", Some(codes::UNKNOWN_MEMBER), "Unknown member: {name}", "未知成员：{name}"),
+    msg!("UnknownStruct", Some(codes::UNKNOWN_STRUCT), "Unknown struct type: {name}", "未知的结构体类型：{name}"),
+    msg!("UnknownEnumVariant", Some(codes::UNKNOWN_ENUM_VARIANT), "Unknown enum variant: {ty}#{variant}", "未知的枚举变体：{ty}#{variant}"),
+    msg!("ImportFailed", Some(codes::IMPORT_FAILED), "Import failed: {reason}", "导入失败：{reason}"),
+    msg!("FileNotFound", Some(codes::FILE_NOT_FOUND), "File not found: {path}", "文件不存在：{path}"),
+    msg!("PathNotAllowed", Some(codes::PATH_NOT_ALLOWED), "Path is not within allowed roots", "路径不在允许的根目录内"),
+    msg!("CapabilityDenied", None, "Capability denied: {capability}", "能力被拒绝：{capability}"),
+    msg!("RecursionLimitExceeded", Some(codes::RECURSION_LIMIT_EXCEEDED), "Recursion limit exceeded", "超出递归深度限制"),
+    msg!("Interrupted", Some(codes::INTERRUPTED), "Execution interrupted", "执行被中断"),
+    msg!("InvalidConditionType", Some(codes::INVALID_CONDITION_TYPE), "Condition must be of type ?, but got {actual}", "条件必须为 ? 类型，实际为 {actual}"),
+    msg!("InvalidIteratorType", Some(codes::INVALID_ITERATOR_TYPE), "Iteration requires {expected} or Range type, but got {actual} (iter={iter})", "迭代需要 {expected} 或 Range 类型，实际为 {actual}（iter={iter}）"),
+    msg!("InvalidUnaryOperand", Some(codes::INVALID_UNARY_OPERAND), "Unary operator '{op}' expects {expected} type", "一元运算符 '{op}' 需要 {expected} 类型"),
+    msg!("TryOnNonResult", Some(codes::INVALID_TRY), "The '?' operator expects a Result or Option, but got {actual}", "'?' 运算符需要 Result 或 Option，实际为 {actual}"),
+    msg!("TypeMismatchDetailed", None, "Type mismatch for parameter '{param}' of function {name}: expected {expected} but got {actual}", "函数 {name} 的参数 '{param}' 类型不匹配：期望 {expected}，实际为 {actual}"),
+    msg!("FieldTypeMismatch", Some(codes::TYPE_MISMATCH), "Type mismatch for field '{field}' of struct {ty}: expected {expected} but got {actual}", "结构体 {ty} 的字段 '{field}' 类型不匹配：期望 {expected}，实际为 {actual}"),
+    msg!("ReturnTypeMismatch", Some(codes::RETURN_TYPE_MISMATCH), "Type mismatch for return: expected {expected} but got {actual}", "返回值类型不匹配：期望 {expected}，实际为 {actual}"),
+    msg!("UnexpectedControlFlowInFunction", None, "Unexpected {op} in function", "函数中出现意外的 {op}"),
+    msg!("InvalidMemberAccess", Some(codes::INVALID_MEMBER_ACCESS), "Unsupported member access: {field} on type {ty}", "不支持的成员访问：类型 {ty} 上的 {field}"),
+    msg!("InvalidIndexAccess", None, "Index access requires {expected} type, but got {actual}", "索引访问需要 {expected} 类型，实际为 {actual}"),
+    msg!("ListIndexRequired", None, "List index must be a number", "列表索引必须是数字"),
+    msg!("DictKeyRequired", None, "Dict key must be of type Str", "字典键必须是 Str 类型"),
+    msg!("InsertKeyRequired", None, "Insert requires key of type Str", "insert 需要 Str 类型的键"),
+    msg!("GetKeyRequired", None, "Get requires key of type Str", "get 需要 Str 类型的键"),
+    msg!("FormatDictRequired", None, "Format requires Dict argument", "format 需要 Dict 参数"),
+    msg!("SplitParamRequired", None, "Split requires Str argument", "split 需要 Str 参数"),
+    msg!("ReplaceParamRequired", None, "Replace requires Str arguments", "replace 需要 Str 参数"),
+    msg!("JoinParamRequired", None, "Join requires Str argument", "join 需要 Str 参数"),
+    msg!("UnsupportedMethod", Some(codes::UNSUPPORTED_METHOD), "Unsupported method: {method} for type {ty}", "类型 {ty} 不支持方法：{method}"),
+    msg!("UnknownListMethod", None, "Unknown list method: {name}", "未知的列表方法：{name}"),
+    msg!("UnknownDictMethod", None, "Unknown dict method: {name}", "未知的字典方法：{name}"),
+    msg!("UnknownFileMethod", None, "Unknown file method: {name}", "未知的文件方法：{name}"),
+    msg!("UnknownStrMethod", None, "Unknown text method: {name}", "未知的文本方法：{name}"),
+    msg!("ParseIntError", None, "Failed to parse integer: {input}", "无法解析整数：{input}"),
+    msg!("ParseFloatError", None, "Failed to parse float: {input}", "无法解析浮点数：{input}"),
+    msg!("FileClosed", None, "File is closed", "文件已关闭"),
+    msg!("UnsupportedReceiver", None, "Unsupported method receiver: {ty}", "不支持的方法接收者：{ty}"),
+    // Hints after rendered diagnostics (see `explain::explain_hint`)
+    msg!("ExplainHint", None, "For more information about this error, try `xu check --explain {code}`.", "运行 `xu check --explain {code}` 查看此错误的详细说明。"),
+    msg!("ExplainHintMany", None, "Some errors have detailed explanations: {codes}.\nFor more information about an error, try `xu check --explain {code}`.", "部分错误有详细说明：{codes}。\n运行 `xu check --explain {code}` 查看其中一个错误的详细说明。"),
+    MessageEntry {
+        key: "Raw",
+        code: None,
+        en: "{message}",
+        zh_cn: None,
+    },
+];
@@ -124,6 +124,7 @@ pub mod codes {
     pub const INVALID_CONDITION_TYPE: &str = "E1004";
     pub const INVALID_ITERATOR_TYPE: &str = "E1005";
     pub const INVALID_UNARY_OPERAND: &str = "E1006";
+    pub const INVALID_TRY: &str = "E1007";
 
     // 2xxx - Syntax / Parsing
     pub const EXPECTED_TOKEN: &str = "E2001";
@@ -134,6 +135,7 @@ pub mod codes {
     pub const UNEXPECTED_CHAR: &str = "E2006";
     pub const UNCLOSED_DELIMITER: &str = "E2007";
     pub const KEYWORD_AS_IDENTIFIER: &str = "E2008";
+    pub const NESTING_TOO_DEEP: &str = "E2009";
 
     // 3xxx - Runtime
     pub const INDEX_OUT_OF_RANGE: &str = "E3001";
@@ -142,6 +144,7 @@ pub mod codes {
     pub const INTEGER_OVERFLOW: &str = "E3004";
     pub const RECURSION_LIMIT_EXCEEDED: &str = "E3005";
     pub const NOT_CALLABLE: &str = "E3006";
+    pub const INTERRUPTED: &str = "E3007";
 
     // 4xxx - Import / Module
     pub const CIRCULAR_IMPORT: &str = "E4001";
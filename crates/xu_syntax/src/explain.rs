@@ -0,0 +1,343 @@
+//! Long-form explanations of diagnostic codes, printed by
+//! `xu check --explain <code>`.
+//!
+//! Each entry describes when the diagnostic is reported, shows code that
+//! triggers it and says how to fix it. Explanations are English only; the
+//! short messages in the [`catalog`](crate::catalog) are the translated ones.
+
+use crate::{catalog, codes};
+
+/// The explanation of one diagnostic code.
+#[derive(Clone, Copy, Debug)]
+pub struct Explanation {
+    /// Diagnostic code (see [`codes`](crate::codes)).
+    pub code: &'static str,
+    /// One-line summary.
+    pub title: &'static str,
+    /// When and why the diagnostic is reported.
+    pub description: &'static str,
+    /// Xu code that triggers the diagnostic.
+    pub example: &'static str,
+    /// How to fix it.
+    pub fix: &'static str,
+}
+
+impl Explanation {
+    /// The explanation as plain text, the example indented by four spaces.
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{}: {}\n\n{}\n\nErroneous code example:\n\n", self.code, self.title, self.description);
+        for line in self.example.lines() {
+            if !line.is_empty() {
+                out.push_str("    ");
+                out.push_str(line);
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+        out.push_str(self.fix);
+        out.push('\n');
+        out
+    }
+}
+
+/// All explanations, in code order.
+pub fn explanations() -> &'static [Explanation] {
+    EXPLANATIONS
+}
+
+/// The explanation of `code`; lowercase codes such as `e1002` are accepted.
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS.iter().find(|e| e.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// The hint printed after diagnostics whose codes have explanations, in the
+/// current language, or `None` when no code has one. `codes` may contain
+/// duplicates.
+pub fn explain_hint<'a>(codes: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut known: Vec<&'static str> = codes.into_iter().filter_map(|c| explain(c).map(|e| e.code)).collect();
+    known.sort_unstable();
+    known.dedup();
+    match known.as_slice() {
+        [] => None,
+        [one] => Some(catalog::render("ExplainHint", &[("code", one.to_string())], catalog::current_lang())),
+        [first, ..] => Some(catalog::render(
+            "ExplainHintMany",
+            &[("codes", known.join(", ")), ("code", first.to_string())],
+            catalog::current_lang(),
+        )),
+    }
+}
+
+macro_rules! explanation {
+    ($code:expr, $title:literal, $description:literal, $example:literal, $fix:literal) => {
+        Explanation {
+            code: $code,
+            title: $title,
+            description: $description,
+            example: $example,
+            fix: $fix,
+        }
+    };
+}
+
+static EXPLANATIONS: &[Explanation] = &[
+    // 0xxx - General / Identifiers
+    explanation!(
+        codes::UNDEFINED_IDENTIFIER,
+        "Undefined identifier",
+        "A name was used that is not defined in the current scope. Variables must be\ndeclared with `let` or `var` before they are read, and functions, structs and\nenums must be declared in the module or imported with `use`.",
+        "func greet() {\n    println(mesage)\n}",
+        "Check the spelling (the diagnostic suggests close matches), declare the name\nbefore using it, or import the module that defines it:\n\n    let message = \"hi\"\n    println(message)"
+    ),
+    // 1xxx - Type System
+    explanation!(
+        codes::TYPE_MISMATCH,
+        "Type mismatch",
+        "A value of one type was used where another type is required, for example as\nthe initial value of an annotated variable or as an argument to a typed\nparameter.",
+        "let n: int = \"42\"",
+        "Pass a value of the expected type or convert it first:\n\n    let n: int = \"42\".to_int()"
+    ),
+    explanation!(
+        codes::ARGUMENT_COUNT_MISMATCH,
+        "Argument count mismatch",
+        "A function was called with more or fewer arguments than it has parameters.\nParameters with default values may be left out; all others are required.",
+        "func add(a: int, b: int) -> int {\n    return a + b\n}\nprintln(add(1))",
+        "Pass one argument per required parameter, or give the parameter a default\nvalue in the function definition:\n\n    println(add(1, 2))"
+    ),
+    explanation!(
+        codes::RETURN_TYPE_MISMATCH,
+        "Return type mismatch",
+        "A `return` statement returns a value whose type differs from the return type\ndeclared after `->` in the function signature.",
+        "func half(n: int) -> int {\n    return \"half\"\n}",
+        "Return a value of the declared type, or change the declared return type to\nmatch what the function returns."
+    ),
+    explanation!(
+        codes::INVALID_CONDITION_TYPE,
+        "Condition is not a bool",
+        "The condition of an `if` or `while` must be a `bool`. Xu does not treat\nnumbers, strings or collections as true or false.",
+        "let items = [1, 2]\nif items.length() {\n    println(\"not empty\")\n}",
+        "Write the comparison out:\n\n    if items.length() > 0 {\n        println(\"not empty\")\n    }"
+    ),
+    explanation!(
+        codes::INVALID_ITERATOR_TYPE,
+        "Value cannot be iterated",
+        "A `for` loop needs a list, a dict, a string or a range such as `0..10` to\niterate over; other values cannot be iterated.",
+        "let n = 10\nfor i in n {\n    println(i)\n}",
+        "Iterate over a range or a collection instead:\n\n    for i in 0..n {\n        println(i)\n    }"
+    ),
+    explanation!(
+        codes::INVALID_UNARY_OPERAND,
+        "Invalid operand for a unary operator",
+        "A unary operator was applied to a value of a type it does not support: `-`\nneeds a number and `!` (or `not`) needs a `bool`.",
+        "let s = \"5\"\nlet n = -s",
+        "Convert the operand to the type the operator expects:\n\n    let n = -s.to_int()"
+    ),
+    explanation!(
+        codes::INVALID_TRY,
+        "Invalid use of the `?` operator",
+        "`expr?` unwraps a `Result#ok` or `Option#some` and otherwise returns the error\nor none from the enclosing function. It needs a Result or Option operand, and\na function declared to return a Result or Option.",
+        "func total(path: string) -> int {\n    let text = read_text(path)?\n    return text.length()\n}",
+        "Declare the function to return a Result (or Option) and wrap the value it\nreturns:\n\n    func total(path: string) -> Result[int, string] {\n        let text = read_text(path)?\n        return ok(text.length())\n    }"
+    ),
+    // 2xxx - Syntax / Parsing
+    explanation!(
+        codes::EXPECTED_TOKEN,
+        "Expected a different token",
+        "The parser reached a token that cannot appear at this point; the message\nnames what it expected, such as a name after `let` or a `{` starting a block.",
+        "let = 5",
+        "Insert the expected token at the position shown, or remove the unexpected\none:\n\n    let count = 5"
+    ),
+    explanation!(
+        codes::EXPECTED_EXPRESSION,
+        "Expected an expression",
+        "A value was required, for example after an operator, after `=` or as an\nargument, but the code ends or continues with something that is not an\nexpression.",
+        "let total = 1 +",
+        "Complete the expression or remove the dangling operator:\n\n    let total = 1 + 2"
+    ),
+    explanation!(
+        codes::INVALID_ASSIGNMENT_TARGET,
+        "Invalid assignment target",
+        "The left side of `=` or a compound assignment such as `+=` must be a\nvariable, a field (`obj.field`) or an element (`list[i]`). Literals, calls and\nother expressions cannot be assigned to.",
+        "func next() -> int {\n    return 1\n}\nnext() = 2",
+        "Assign to a variable, field or element instead:\n\n    var n = next()\n    n = 2"
+    ),
+    explanation!(
+        codes::UNTERMINATED_STRING,
+        "Unterminated string literal",
+        "A string literal has no closing quote before the end of the line or file.",
+        "let greeting = \"hello\nprintln(greeting)",
+        "Add the closing quote. Use `\\n` for a newline inside a string:\n\n    let greeting = \"hello\""
+    ),
+    explanation!(
+        codes::UNTERMINATED_BLOCK_COMMENT,
+        "Unterminated block comment",
+        "A block comment opened with `/*` is never closed with `*/`, so the rest of\nthe file is part of the comment.",
+        "/* helper functions\nfunc helper() {}",
+        "Close the comment with `*/` where it should end:\n\n    /* helper functions */"
+    ),
+    explanation!(
+        codes::UNEXPECTED_CHAR,
+        "Unexpected character",
+        "The source contains a character that does not start any token, such as `$`\nor a stray full-width punctuation mark pasted from another document.",
+        "let price = 5$",
+        "Remove the character, or put it inside a string literal if it is part of the\ntext:\n\n    let price = \"5$\""
+    ),
+    explanation!(
+        codes::UNCLOSED_DELIMITER,
+        "Unclosed delimiter",
+        "A `(`, `[` or `{` is never closed. The diagnostic points at the opening\ndelimiter; the matching closing one is missing somewhere after it.",
+        "func main() {\n    if true {\n        println(\"yes\")\n}",
+        "Add the missing closing delimiter. Consistent indentation makes it easier to\nsee which block is not closed."
+    ),
+    explanation!(
+        codes::KEYWORD_AS_IDENTIFIER,
+        "Keyword used as an identifier",
+        "A reserved word such as `func`, `match` or `return` was used as the name of\na variable, parameter, function or field.",
+        "let match = 1",
+        "Choose another name, for example by adding a suffix:\n\n    let match_count = 1"
+    ),
+    explanation!(
+        codes::NESTING_TOO_DEEP,
+        "Nesting too deep",
+        "Parentheses, brackets, blocks, types or patterns are nested more than 128\nlevels deep, or a chain of operators or member accesses such as `a + b + c`\nor `a.b.c` has more than 256 links. Such code is almost always generated,\nand the limits keep the compiler from running out of stack.",
+        "let x = ((((((((((((((((((((1))))))))))))))))))))  // ...and so on",
+        "Split the expression into smaller ones bound to local variables, or move\nnested blocks into functions:\n\n    let inner = (((1)))\n    let x = (((inner)))"
+    ),
+    // 3xxx - Runtime
+    explanation!(
+        codes::INDEX_OUT_OF_RANGE,
+        "Index out of range",
+        "A list or string was indexed at a position past its end. Indexes start at\n0, so the last valid index is `length() - 1`.",
+        "let items = [1, 2, 3]\nprintln(items[3])",
+        "Check the index against `length()` first, or iterate over the collection\ninstead of indexing it:\n\n    if 3 < items.length() {\n        println(items[3])\n    }"
+    ),
+    explanation!(
+        codes::DIVISION_BY_ZERO,
+        "Division by zero",
+        "An integer was divided by zero or taken modulo zero.",
+        "let parts = 0\nprintln(10 / parts)",
+        "Check the divisor before dividing:\n\n    if parts != 0 {\n        println(10 / parts)\n    }"
+    ),
+    explanation!(
+        codes::KEY_NOT_FOUND,
+        "Key not found",
+        "A dict was indexed with a key it does not contain. Indexing with `d[key]`\nrequires the key to be present.",
+        "let ages = {\"ann\": 31}\nprintln(ages[\"bob\"])",
+        "Use `get`, which returns an Option, or check with `contains` first:\n\n    match ages.get(\"bob\") {\n        Option#some(age) { println(age) }\n        _ { println(\"unknown\") }\n    }"
+    ),
+    explanation!(
+        codes::INTEGER_OVERFLOW,
+        "Integer overflow",
+        "An integer operation produced a result too large or too small to be\nrepresented as an `int`.",
+        "let big = 9223372036854775807 * 2",
+        "Keep values within range, or use floats when approximate results are\nacceptable."
+    ),
+    explanation!(
+        codes::RECURSION_LIMIT_EXCEEDED,
+        "Recursion limit exceeded",
+        "Function calls nested deeper than the runtime allows, usually because a\nrecursive function has no base case or its base case is never reached.",
+        "func countdown(n: int) {\n    countdown(n - 1)\n}\ncountdown(10)",
+        "Add a base case that stops the recursion, or rewrite the function as a loop:\n\n    func countdown(n: int) {\n        if n == 0 {\n            return\n        }\n        countdown(n - 1)\n    }"
+    ),
+    explanation!(
+        codes::NOT_CALLABLE,
+        "Value is not callable",
+        "A call was made on a value that is not a function, closure or struct method,\nfor example a variable holding a number.",
+        "let limit = 10\nprintln(limit())",
+        "Remove the parentheses to use the value, or call the function that was\nmeant:\n\n    println(limit)"
+    ),
+    explanation!(
+        codes::INTERRUPTED,
+        "Execution interrupted",
+        "The host program embedding Xu asked the script to stop, for example because\na time limit was reached. Execution ends at the next loop iteration or call.",
+        "while true {\n}",
+        "Nothing in the script needs to change when the interruption was expected.\nOtherwise make sure long loops terminate, or ask the host for a longer limit."
+    ),
+    // 4xxx - Import / Module
+    explanation!(
+        codes::CIRCULAR_IMPORT,
+        "Circular import",
+        "A module imports itself, directly or through other modules. The message\nlists the chain of imports that leads back to the first module.",
+        "// a.xu\nuse \"b.xu\" as b\n\n// b.xu\nuse \"a.xu\" as a",
+        "Move the definitions both modules need into a third module that imports\nneither of them."
+    ),
+    explanation!(
+        codes::IMPORT_FAILED,
+        "Import failed",
+        "A module was found but could not be loaded, usually because it has syntax or\ntype errors of its own. The message includes the underlying error.",
+        "use \"broken.xu\" as broken",
+        "Run `xu check` on the imported file and fix the errors it reports."
+    ),
+    explanation!(
+        codes::FILE_NOT_FOUND,
+        "File not found",
+        "A `use` statement or file operation names a path that does not exist.\nRelative import paths are resolved against the importing file's directory.",
+        "use \"utils/strings.xu\" as strings",
+        "Check the spelling and the directory the path is relative to."
+    ),
+    explanation!(
+        codes::PATH_NOT_ALLOWED,
+        "Path not allowed",
+        "A script tried to import or open a file outside the directories the runtime\nallows. Embedding hosts can limit file access to a set of roots.",
+        "use \"/etc/settings.xu\" as settings",
+        "Move the file into the project, or ask the host to allow its directory."
+    ),
+    // 5xxx - Methods / Members
+    explanation!(
+        codes::UNKNOWN_STRUCT,
+        "Unknown struct type",
+        "A struct literal names a type that is not declared or imported.",
+        "let p = Pont{ x: 1, y: 2 }",
+        "Check the type name, or declare the struct with `has`:\n\n    Point has {\n        x: int\n        y: int\n    }"
+    ),
+    explanation!(
+        codes::UNKNOWN_MEMBER,
+        "Unknown member",
+        "A field or method was accessed that the value's type does not declare.",
+        "Point has {\n    x: int\n}\nlet p = Point{ x: 1 }\nprintln(p.y)",
+        "Check the member name against the type's declaration, or add the field or\nmethod to the type."
+    ),
+    explanation!(
+        codes::UNKNOWN_ENUM_VARIANT,
+        "Unknown enum variant",
+        "An enum value was written with a variant name that the enum does not\ndeclare.",
+        "Color with [\n    Red |\n    Green\n]\nlet c = Color#Blue",
+        "Use one of the declared variants, or add the variant to the enum:\n\n    Color with [\n        Red |\n        Green |\n        Blue\n    ]"
+    ),
+    explanation!(
+        codes::UNSUPPORTED_METHOD,
+        "Unsupported method",
+        "A built-in method was called on a type that does not provide it, for\nexample a list method on a string.",
+        "let name = \"xu\"\nname.push(\"!\")",
+        "Use a method the type supports, or convert the value first:\n\n    let shout = name + \"!\""
+    ),
+    explanation!(
+        codes::INVALID_MEMBER_ACCESS,
+        "Invalid member access",
+        "`.` was used on a value whose type has no members of that kind, such as\nreading a field from a number.",
+        "let n = 3\nprintln(n.value)",
+        "Only access fields on structs and dicts; call a method or use the value\ndirectly for other types."
+    ),
+    // 0xxx - Warnings
+    explanation!(
+        codes::UNREACHABLE_CODE,
+        "Unreachable code",
+        "Statements follow a `return`, `break` or `continue` in the same block, so\nthey can never run.",
+        "func f() -> int {\n    return 1\n    println(\"done\")\n}",
+        "Remove the statements, or move them before the statement that leaves the\nblock."
+    ),
+    explanation!(
+        codes::SHADOWING,
+        "Variable shadows an existing binding",
+        "A new variable has the same name as one declared in an enclosing scope, so\nthe outer one cannot be used until the inner scope ends. This is allowed but\noften a mistake.",
+        "let count = 0\nif true {\n    let count = 1\n}",
+        "Assign to the existing variable (declared with `var`) if that was the\nintent, or give the new variable a different name."
+    ),
+    explanation!(
+        codes::VOID_ASSIGNMENT,
+        "Unit assigned to a variable",
+        "The unit value `()` was assigned to a variable. Unit means \"no value\", so\nthe variable would hold nothing useful.",
+        "let r = ()",
+        "Assign a real value, or drop the variable if nothing needs to be stored:\n\n    let r = 0"
+    ),
+];
@@ -1,7 +1,9 @@
 //! Xu syntax utilities: diagnostics, spans, source mapping, and token definitions.
 
 mod builtins;
+pub mod catalog;
 mod diagnostic;
+pub mod explain;
 mod loc;
 mod render;
 mod source;
@@ -9,15 +11,21 @@ mod span;
 mod str_util;
 mod token;
 mod types;
+mod unicode;
+mod unicode_tables;
 mod util;
 
-pub use builtins::{BUILTIN_NAMES, builtin_return_type};
+pub use builtins::{
+    BUILTIN_NAMES, BuiltinDoc, BuiltinSig, INSPECT_MAX_DEPTH, INSPECT_MAX_ITEMS, builtin_doc, builtin_methods,
+    builtin_return_type, builtin_signature,
+};
 pub use diagnostic::{Diagnostic, Severity, codes};
 pub use loc::{DiagnosticKind, DiagnosticsFormatter};
-pub use render::{render_diagnostic, render_diagnostics};
-pub use source::{SourceFile, SourceId, SourceText};
+pub use render::{RenderOptions, display_width, render_diagnostic, render_diagnostic_with_options, render_diagnostics};
+pub use source::{ColumnUnit, LineIndex, SourceFile, SourceId, SourceText};
 pub use span::{ByteIndex, Span};
 pub use str_util::{InterpolationParser, InterpolationPiece, unescape, unquote};
 pub use token::{Token, TokenKind};
 pub use types::{Type, TypeId, TypeInterner};
+pub use unicode::{Graphemes, NormalizationForm, graphemes, normalize};
 pub use util::{find_best_match, is_cjk, is_ident_continue, is_ident_start, levenshtein_distance};
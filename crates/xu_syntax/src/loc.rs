@@ -17,10 +17,29 @@ pub enum DiagnosticKind {
     TrailingInterpolationTokens,
     KeywordAsIdentifier(String),
     MissingMatchDefault,
+    IfExprWithoutElse,
+    InvalidArgumentLabel {
+        label: String,
+        func: String,
+    },
+    UnknownAnnotation(String),
+    ContractWithoutFunction,
+    EnsuresMayNotReturn(String),
+    StaticFieldWithoutDefault,
+    CannotExtendBuiltin(String),
+    WhenIsNotMatch,
+    WhenBindingExpectsEq,
+    NestingTooDeep,
 
     // Analyzer - Errors
     UnreachableCode,
     UnitAssignment,
+    EmptyContainerNeedsAnnotation,
+    TryOutsideFunction,
+    TryInNonResultFunction(String),
+    ConstNotAtModuleLevel,
+    ConstNotEvaluable(String),
+    ConstReassignment(String),
 
     // Analyzer - Warnings
     Shadowing(String),
@@ -35,6 +54,9 @@ pub enum DiagnosticKind {
     },
     UndefinedIdentifier(String),
     DidYouMean(String),
+    VariableDefinedHere,
+    ReturnTypeDeclaredHere,
+    ArgumentHasWrongType(usize),
 
     // Runtime
     IndexOutOfRange,
@@ -51,7 +73,9 @@ pub enum DiagnosticKind {
     ImportFailed(String),
     FileNotFound(String),
     PathNotAllowed,
+    CapabilityDenied(String),
     RecursionLimitExceeded,
+    Interrupted,
     InvalidConditionType(String),
     InvalidIteratorType {
         expected: String,
@@ -62,6 +86,7 @@ pub enum DiagnosticKind {
         op: char,
         expected: String,
     },
+    TryOnNonResult(String),
     TypeMismatchDetailed {
         name: String,
         param: String,
@@ -72,6 +97,12 @@ pub enum DiagnosticKind {
         expected: String,
         actual: String,
     },
+    FieldTypeMismatch {
+        ty: String,
+        field: String,
+        expected: String,
+        actual: String,
+    },
     UnexpectedControlFlowInFunction(&'static str),
     InvalidMemberAccess {
         field: String,
@@ -108,149 +139,197 @@ pub enum DiagnosticKind {
 
 pub struct DiagnosticsFormatter;
 
-impl DiagnosticsFormatter {
-    fn format_en(kind: &DiagnosticKind) -> String {
-        match kind {
-            DiagnosticKind::TabNotAllowed => "Tab is not allowed; use ASCII spaces".into(),
-            DiagnosticKind::FullWidthSpaceNotAllowed => {
-                "Full-width space is not allowed; use ASCII spaces".into()
-            }
-            DiagnosticKind::UnterminatedBlockComment => "Unterminated block comment".into(),
-            DiagnosticKind::UnmatchedDelimiter(c) => format!("Unmatched '{}'", c),
-            DiagnosticKind::DotNotTerminator => "Dot is not a statement terminator".into(),
-            DiagnosticKind::UnterminatedString => "Unterminated string literal".into(),
-            DiagnosticKind::UnexpectedChar(c) => format!("Unexpected character: {}", c),
-            DiagnosticKind::UnclosedDelimiter(c) => format!("Unclosed '{}'", c),
+impl DiagnosticKind {
+    /// Catalog key and template arguments for this message.
+    pub fn message_parts(&self) -> (&'static str, Vec<(&'static str, String)>) {
+        fn a(name: &'static str, v: impl ToString) -> (&'static str, String) {
+            (name, v.to_string())
+        }
+        match self {
+            DiagnosticKind::TabNotAllowed => ("TabNotAllowed", vec![]),
+            DiagnosticKind::FullWidthSpaceNotAllowed => ("FullWidthSpaceNotAllowed", vec![]),
+            DiagnosticKind::UnterminatedBlockComment => ("UnterminatedBlockComment", vec![]),
+            DiagnosticKind::UnmatchedDelimiter(c) => ("UnmatchedDelimiter", vec![a("delim", c)]),
+            DiagnosticKind::DotNotTerminator => ("DotNotTerminator", vec![]),
+            DiagnosticKind::UnterminatedString => ("UnterminatedString", vec![]),
+            DiagnosticKind::UnexpectedChar(c) => ("UnexpectedChar", vec![a("char", c)]),
+            DiagnosticKind::UnclosedDelimiter(c) => ("UnclosedDelimiter", vec![a("delim", c)]),
 
-            DiagnosticKind::UnreachableCode => "Unreachable code".into(),
-            DiagnosticKind::UnitAssignment => "Cannot assign unit to a variable".into(),
-            DiagnosticKind::Shadowing(name) => format!("Variable '{}' shadows an existing binding", name),
-            DiagnosticKind::DidYouMean(s) => format!("Did you mean '{}'?", s),
+            DiagnosticKind::UnreachableCode => ("UnreachableCode", vec![]),
+            DiagnosticKind::UnitAssignment => ("UnitAssignment", vec![]),
+            DiagnosticKind::EmptyContainerNeedsAnnotation => ("EmptyContainerNeedsAnnotation", vec![]),
+            DiagnosticKind::TryOutsideFunction => ("TryOutsideFunction", vec![]),
+            DiagnosticKind::TryInNonResultFunction(ty) => ("TryInNonResultFunction", vec![a("ty", ty)]),
+            DiagnosticKind::ConstNotAtModuleLevel => ("ConstNotAtModuleLevel", vec![]),
+            DiagnosticKind::ConstNotEvaluable(name) => ("ConstNotEvaluable", vec![a("name", name)]),
+            DiagnosticKind::ConstReassignment(name) => ("ConstReassignment", vec![a("name", name)]),
+            DiagnosticKind::Shadowing(name) => ("Shadowing", vec![a("name", name)]),
+            DiagnosticKind::DidYouMean(s) => ("DidYouMean", vec![a("name", s)]),
+            DiagnosticKind::VariableDefinedHere => ("VariableDefinedHere", vec![]),
+            DiagnosticKind::ReturnTypeDeclaredHere => ("ReturnTypeDeclaredHere", vec![]),
+            DiagnosticKind::ArgumentHasWrongType(n) => ("ArgumentHasWrongType", vec![a("index", n)]),
 
-            DiagnosticKind::ExpectedToken(s) => format!("Expected {}", s),
-            DiagnosticKind::ExpectedExpression => "Expected expression".into(),
-            DiagnosticKind::InvalidAssignmentTarget => "Invalid assignment target".into(),
-            DiagnosticKind::ExpectedImportPath => {
-                "Expected string literal or argument list after import".into()
-            }
-            DiagnosticKind::TrailingInterpolationTokens => {
-                "Interpolation expression has trailing tokens".into()
-            }
+            DiagnosticKind::ExpectedToken(s) => ("ExpectedToken", vec![a("token", s)]),
+            DiagnosticKind::ExpectedExpression => ("ExpectedExpression", vec![]),
+            DiagnosticKind::InvalidAssignmentTarget => ("InvalidAssignmentTarget", vec![]),
+            DiagnosticKind::ExpectedImportPath => ("ExpectedImportPath", vec![]),
+            DiagnosticKind::TrailingInterpolationTokens => ("TrailingInterpolationTokens", vec![]),
             DiagnosticKind::KeywordAsIdentifier(kw) => {
-                format!("Keyword '{}' cannot be used as an identifier", kw)
+                ("KeywordAsIdentifier", vec![a("keyword", kw)])
             }
-            DiagnosticKind::MissingMatchDefault => {
-                "Match statement requires a default branch '_'".into()
+            DiagnosticKind::InvalidArgumentLabel { label, func } => {
+                ("InvalidArgumentLabel", vec![a("label", label), a("func", func)])
             }
+            DiagnosticKind::MissingMatchDefault => ("MissingMatchDefault", vec![]),
+            DiagnosticKind::IfExprWithoutElse => ("IfExprWithoutElse", vec![]),
+            DiagnosticKind::UnknownAnnotation(name) => ("UnknownAnnotation", vec![a("name", name)]),
+            DiagnosticKind::ContractWithoutFunction => ("ContractWithoutFunction", vec![]),
+            DiagnosticKind::EnsuresMayNotReturn(func) => ("EnsuresMayNotReturn", vec![a("func", func)]),
+            DiagnosticKind::StaticFieldWithoutDefault => ("StaticFieldWithoutDefault", vec![]),
+            DiagnosticKind::CannotExtendBuiltin(ty) => ("CannotExtendBuiltin", vec![a("ty", ty)]),
+            DiagnosticKind::WhenIsNotMatch => ("WhenIsNotMatch", vec![]),
+            DiagnosticKind::WhenBindingExpectsEq => ("WhenBindingExpectsEq", vec![]),
+            DiagnosticKind::NestingTooDeep => ("NestingTooDeep", vec![]),
 
-            DiagnosticKind::UndefinedIdentifier(name) => format!("Undefined identifier: {}", name),
-            DiagnosticKind::TypeMismatch { expected, actual } => {
-                format!("Type mismatch: expected {} but got {}", expected, actual)
+            DiagnosticKind::UndefinedIdentifier(name) => {
+                ("UndefinedIdentifier", vec![a("name", name)])
             }
+            DiagnosticKind::TypeMismatch { expected, actual } => (
+                "TypeMismatch",
+                vec![a("expected", expected), a("actual", actual)],
+            ),
             DiagnosticKind::ArgumentCountMismatch {
                 expected_min,
                 expected_max,
                 actual,
             } => {
                 if expected_min == expected_max {
-                    format!(
-                        "Argument count mismatch: expected {} but got {}",
-                        expected_min, actual
+                    (
+                        "ArgumentCountMismatch",
+                        vec![a("expected", expected_min), a("actual", actual)],
                     )
                 } else {
-                    format!(
-                        "Argument count mismatch: expected {}..{} but got {}",
-                        expected_min, expected_max, actual
+                    (
+                        "ArgumentCountMismatchRange",
+                        vec![
+                            a("min", expected_min),
+                            a("max", expected_max),
+                            a("actual", actual),
+                        ],
                     )
                 }
             }
-            DiagnosticKind::IndexOutOfRange => "Index out of range".into(),
-            DiagnosticKind::KeyNotFound(key) => format!("Key not found: {}", key),
-            DiagnosticKind::FileNotOpen => "File is not open".into(),
+            DiagnosticKind::IndexOutOfRange => ("IndexOutOfRange", vec![]),
+            DiagnosticKind::KeyNotFound(key) => ("KeyNotFound", vec![a("key", key)]),
+            DiagnosticKind::FileNotOpen => ("FileNotOpen", vec![]),
             DiagnosticKind::CircularImport(chain) => {
-                format!("Circular import: {}", chain.join(" -> "))
-            }
-            DiagnosticKind::TopLevelBreakContinue => {
-                "Break or continue is not allowed at top level".into()
+                ("CircularImport", vec![a("chain", chain.join(" -> "))])
             }
-            DiagnosticKind::DivisionByZero => "Division by zero".into(),
-            DiagnosticKind::IntegerOverflow => "Integer overflow".into(),
-            DiagnosticKind::NotCallable(name) => format!("'{}' is not callable", name),
-            DiagnosticKind::UnknownMember(name) => format!("Unknown member: {}", name),
-            DiagnosticKind::UnknownStruct(name) => format!("Unknown struct type: {}", name),
-            DiagnosticKind::UnknownEnumVariant(ty, var) => {
-                format!("Unknown enum variant: {}#{}", ty, var)
+            DiagnosticKind::TopLevelBreakContinue => ("TopLevelBreakContinue", vec![]),
+            DiagnosticKind::DivisionByZero => ("DivisionByZero", vec![]),
+            DiagnosticKind::IntegerOverflow => ("IntegerOverflow", vec![]),
+            DiagnosticKind::NotCallable(name) => ("NotCallable", vec![a("name", name)]),
+            DiagnosticKind::UnknownMember(name) => ("UnknownMember", vec![a("name", name)]),
+            DiagnosticKind::UnknownStruct(name) => ("UnknownStruct", vec![a("name", name)]),
+            DiagnosticKind::UnknownEnumVariant(ty, var) => (
+                "UnknownEnumVariant",
+                vec![a("ty", ty), a("variant", var)],
+            ),
+            DiagnosticKind::ImportFailed(msg) => ("ImportFailed", vec![a("reason", msg)]),
+            DiagnosticKind::FileNotFound(path) => ("FileNotFound", vec![a("path", path)]),
+            DiagnosticKind::PathNotAllowed => ("PathNotAllowed", vec![]),
+            DiagnosticKind::CapabilityDenied(cap) => {
+                ("CapabilityDenied", vec![a("capability", cap)])
             }
-            DiagnosticKind::ImportFailed(msg) => format!("Import failed: {}", msg),
-            DiagnosticKind::FileNotFound(path) => format!("File not found: {}", path),
-            DiagnosticKind::PathNotAllowed => "Path is not within allowed roots".into(),
-            DiagnosticKind::RecursionLimitExceeded => "Recursion limit exceeded".into(),
+            DiagnosticKind::RecursionLimitExceeded => ("RecursionLimitExceeded", vec![]),
+            DiagnosticKind::Interrupted => ("Interrupted", vec![]),
             DiagnosticKind::InvalidConditionType(actual) => {
-                format!("Condition must be of type ?, but got {}", actual)
+                ("InvalidConditionType", vec![a("actual", actual)])
             }
             DiagnosticKind::InvalidIteratorType {
                 expected,
                 actual,
                 iter_desc,
-            } => format!(
-                "Iteration requires {} or {} type, but got {} (iter={})",
-                expected, "Range", actual, iter_desc
+            } => (
+                "InvalidIteratorType",
+                vec![
+                    a("expected", expected),
+                    a("actual", actual),
+                    a("iter", iter_desc),
+                ],
             ),
-            DiagnosticKind::InvalidUnaryOperand { op, expected } => {
-                format!("Unary operator '{}' expects {} type", op, expected)
-            }
+            DiagnosticKind::InvalidUnaryOperand { op, expected } => (
+                "InvalidUnaryOperand",
+                vec![a("op", op), a("expected", expected)],
+            ),
+            DiagnosticKind::TryOnNonResult(actual) => ("TryOnNonResult", vec![a("actual", actual)]),
             DiagnosticKind::TypeMismatchDetailed {
                 name,
                 param,
                 expected,
                 actual,
-            } => format!(
-                "Type mismatch for parameter '{}' of function {}: expected {} but got {}",
-                param, name, expected, actual
+            } => (
+                "TypeMismatchDetailed",
+                vec![
+                    a("name", name),
+                    a("param", param),
+                    a("expected", expected),
+                    a("actual", actual),
+                ],
             ),
-            DiagnosticKind::ReturnTypeMismatch { expected, actual } => format!(
-                "Type mismatch for return: expected {} but got {}",
-                expected, actual
+            DiagnosticKind::ReturnTypeMismatch { expected, actual } => (
+                "ReturnTypeMismatch",
+                vec![a("expected", expected), a("actual", actual)],
+            ),
+            DiagnosticKind::FieldTypeMismatch { ty, field, expected, actual } => (
+                "FieldTypeMismatch",
+                vec![a("ty", ty), a("field", field), a("expected", expected), a("actual", actual)],
             ),
             DiagnosticKind::UnexpectedControlFlowInFunction(op) => {
-                format!("Unexpected {} in function", op)
-            }
-            DiagnosticKind::InvalidMemberAccess { field, ty } => {
-                format!("Unsupported member access: {} on type {}", field, ty)
+                ("UnexpectedControlFlowInFunction", vec![a("op", op)])
             }
-            DiagnosticKind::InvalidIndexAccess { expected, actual } => format!(
-                "Index access requires {} type, but got {}",
-                expected, actual
+            DiagnosticKind::InvalidMemberAccess { field, ty } => (
+                "InvalidMemberAccess",
+                vec![a("field", field), a("ty", ty)],
             ),
-            DiagnosticKind::ListIndexRequired => "List index must be a number".into(),
-            DiagnosticKind::DictKeyRequired => "Dict key must be of type Str".into(),
-            DiagnosticKind::InsertKeyRequired => "Insert requires key of type Str".into(),
-            DiagnosticKind::GetKeyRequired => "Get requires key of type Str".into(),
-            DiagnosticKind::FormatDictRequired => "Format requires Dict argument".into(),
-            DiagnosticKind::SplitParamRequired => "Split requires Str argument".into(),
-            DiagnosticKind::ReplaceParamRequired => "Replace requires Str arguments".into(),
-            DiagnosticKind::JoinParamRequired => "Join requires Str argument".into(),
-            DiagnosticKind::UnsupportedMethod { method, ty } => {
-                format!("Unsupported method: {} for type {}", method, ty)
-            }
-            DiagnosticKind::UnknownListMethod(name) => format!("Unknown list method: {}", name),
-            DiagnosticKind::UnknownDictMethod(name) => format!("Unknown dict method: {}", name),
-            DiagnosticKind::UnknownFileMethod(name) => format!("Unknown file method: {}", name),
-            DiagnosticKind::UnknownStrMethod(name) => format!("Unknown text method: {}", name),
-            DiagnosticKind::ParseIntError(s) => format!("Failed to parse integer: {}", s),
-            DiagnosticKind::ParseFloatError(s) => format!("Failed to parse float: {}", s),
-            DiagnosticKind::FileClosed => "File is closed".into(),
-            DiagnosticKind::UnsupportedReceiver(ty) => {
-                format!("Unsupported method receiver: {}", ty)
-            }
+            DiagnosticKind::InvalidIndexAccess { expected, actual } => (
+                "InvalidIndexAccess",
+                vec![a("expected", expected), a("actual", actual)],
+            ),
+            DiagnosticKind::ListIndexRequired => ("ListIndexRequired", vec![]),
+            DiagnosticKind::DictKeyRequired => ("DictKeyRequired", vec![]),
+            DiagnosticKind::InsertKeyRequired => ("InsertKeyRequired", vec![]),
+            DiagnosticKind::GetKeyRequired => ("GetKeyRequired", vec![]),
+            DiagnosticKind::FormatDictRequired => ("FormatDictRequired", vec![]),
+            DiagnosticKind::SplitParamRequired => ("SplitParamRequired", vec![]),
+            DiagnosticKind::ReplaceParamRequired => ("ReplaceParamRequired", vec![]),
+            DiagnosticKind::JoinParamRequired => ("JoinParamRequired", vec![]),
+            DiagnosticKind::UnsupportedMethod { method, ty } => (
+                "UnsupportedMethod",
+                vec![a("method", method), a("ty", ty)],
+            ),
+            DiagnosticKind::UnknownListMethod(name) => ("UnknownListMethod", vec![a("name", name)]),
+            DiagnosticKind::UnknownDictMethod(name) => ("UnknownDictMethod", vec![a("name", name)]),
+            DiagnosticKind::UnknownFileMethod(name) => ("UnknownFileMethod", vec![a("name", name)]),
+            DiagnosticKind::UnknownStrMethod(name) => ("UnknownStrMethod", vec![a("name", name)]),
+            DiagnosticKind::ParseIntError(s) => ("ParseIntError", vec![a("input", s)]),
+            DiagnosticKind::ParseFloatError(s) => ("ParseFloatError", vec![a("input", s)]),
+            DiagnosticKind::FileClosed => ("FileClosed", vec![]),
+            DiagnosticKind::UnsupportedReceiver(ty) => ("UnsupportedReceiver", vec![a("ty", ty)]),
 
-            DiagnosticKind::Raw(s) => s.clone(),
+            DiagnosticKind::Raw(s) => ("Raw", vec![a("message", s)]),
         }
     }
+}
 
+impl DiagnosticsFormatter {
+    /// Renders `kind` in the current language (see [`crate::catalog::set_lang`]).
     pub fn format(kind: &DiagnosticKind) -> String {
-        Self::format_en(kind)
+        Self::format_in(kind, crate::catalog::current_lang())
     }
 
-    // Removed Chinese formatter; always format in English.
+    pub fn format_in(kind: &DiagnosticKind, lang: crate::catalog::Lang) -> String {
+        let (key, args) = kind.message_parts();
+        crate::catalog::render(key, &args, lang)
+    }
 }
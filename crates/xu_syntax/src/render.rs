@@ -1,4 +1,42 @@
-use crate::{Diagnostic, SourceFile};
+//! Human-readable diagnostic rendering.
+//!
+//! A diagnostic renders as a header line (`Error [E0001]:5:9: main.xu: ...`),
+//! the source lines it points at with line numbers, `^` under the primary span
+//! and `-` under each label followed by the label's message, then the
+//! suggestion and help notes. Colors are opt-in through [`RenderOptions`].
+
+use std::ops::Range;
+
+use crate::util::is_cjk;
+use crate::{ColumnUnit, Diagnostic, Severity, SourceFile};
+
+/// Spans covering more lines than this show their first and last lines only.
+const MAX_SPAN_LINES: usize = 4;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// How [`render_diagnostic_with_options`] formats its output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Color severities, underlines and the line-number gutter with ANSI
+    /// escape sequences.
+    pub color: bool,
+}
+
+impl RenderOptions {
+    fn paint(self, style: &str, text: &str) -> String {
+        if self.color && !text.is_empty() {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
 
 fn floor_char_boundary(text: &str, mut idx: usize) -> usize {
     idx = idx.min(text.len());
@@ -8,111 +46,165 @@ fn floor_char_boundary(text: &str, mut idx: usize) -> usize {
     idx
 }
 
+/// Terminal columns taken by `s`: CJK and full-width characters take two.
+pub fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| if is_cjk(c) || matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF00}'..='\u{FF60}') { 2 } else { 1 })
+        .sum()
+}
+
+/// One underlined span: the primary span or a label.
+struct Annotation<'a> {
+    start: usize,
+    end: usize,
+    primary: bool,
+    message: Option<&'a str>,
+}
+
+/// One underline below a source line.
+struct Underline<'a> {
+    col: usize,
+    width: usize,
+    primary: bool,
+    message: Option<&'a str>,
+}
+
+struct SnippetLine<'a> {
+    line: usize,
+    start: usize,
+    end: usize,
+    underlines: Vec<Underline<'a>>,
+}
+
+fn annotate<'a>(snippet: &mut Vec<SnippetLine<'a>>, source: &SourceFile, a: &Annotation<'a>) {
+    let text = source.text.as_str();
+    let index = source.text.lines();
+    let mut lines: Vec<u32> = Vec::new();
+    let mut line = index.line(a.start as u32);
+    loop {
+        lines.push(line);
+        let le = index.line_range(line).end;
+        if le + 1 >= a.end || le >= text.len() {
+            break;
+        }
+        line += 1;
+    }
+    if lines.len() > MAX_SPAN_LINES {
+        let last = lines[lines.len() - 1];
+        lines.truncate(MAX_SPAN_LINES - 1);
+        lines.push(last);
+    }
+    let count = lines.len();
+    for (i, line) in lines.into_iter().enumerate() {
+        let Range { start: ls, end: le } = index.line_range(line);
+        let seg_start = a.start.max(ls);
+        let seg_end = floor_char_boundary(text, a.end.min(le));
+        let (_, col) = index.line_col(text, seg_start as u32, ColumnUnit::Display);
+        let width = if seg_end > seg_start { display_width(&text[seg_start..seg_end]) } else { 1 };
+        let underline = Underline {
+            col: col as usize,
+            width,
+            primary: a.primary,
+            message: if i + 1 == count { a.message } else { None },
+        };
+        let line = line as usize;
+        match snippet.iter_mut().find(|l| l.line == line) {
+            Some(l) => l.underlines.push(underline),
+            None => snippet.push(SnippetLine { line, start: ls, end: le, underlines: vec![underline] }),
+        }
+    }
+}
+
 pub fn render_diagnostic(source: &SourceFile, diag: &Diagnostic) -> String {
-    match diag.span {
-        Some(span) => {
-            let text = source.text.as_str();
-            let start = floor_char_boundary(text, span.start.0 as usize);
-            let (line, col) = source.text.line_col(start as u32);
-            let line_start = text[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
-            let line_end = text[start..]
-                .find('\n')
-                .map(|i| start + i)
-                .unwrap_or(text.len());
-            let line_text = &text[line_start..line_end];
-
-            let mut out = String::new();
-            let code_str = diag.code.map(|c| format!(" [{c}]")).unwrap_or_default();
-            out.push_str(&format!(
-                "{:?}{}:{}:{}: {}: {}",
-                diag.severity,
-                code_str,
-                line + 1,
-                col + 1,
-                source.name,
-                diag.message
-            ));
+    render_diagnostic_with_options(source, diag, RenderOptions::default())
+}
+
+pub fn render_diagnostic_with_options(source: &SourceFile, diag: &Diagnostic, opts: RenderOptions) -> String {
+    let text = source.text.as_str();
+    let (severity, color) = match diag.severity {
+        Severity::Error => ("Error", RED),
+        Severity::Warning => ("Warning", YELLOW),
+        Severity::Info => ("Info", CYAN),
+    };
+    let code_str = diag.code.map(|c| format!(" [{c}]")).unwrap_or_default();
+    let mut out = opts.paint(color, &format!("{severity}{code_str}"));
+    let primary = diag.span.map(|span| {
+        let start = floor_char_boundary(text, span.start.0 as usize);
+        let end = floor_char_boundary(text, span.end.0 as usize).max(start);
+        (start, end)
+    });
+    if let Some((start, _)) = primary {
+        let (line, col) = source.text.line_col(start as u32);
+        out.push_str(&format!(":{}:{}", line + 1, col + 1));
+    }
+    out.push_str(&format!(": {}: ", source.name));
+    out.push_str(&opts.paint(BOLD, &diag.message));
+
+    // A label on the primary span annotates the `^` underline itself.
+    let mut annotations: Vec<Annotation> = Vec::new();
+    let mut primary_message = None;
+    for label in &diag.labels {
+        let start = floor_char_boundary(text, label.span.start.0 as usize);
+        let end = floor_char_boundary(text, label.span.end.0 as usize).max(start);
+        if primary_message.is_none() && primary == Some((start, end)) {
+            primary_message = Some(label.message.as_str());
+        } else {
+            annotations.push(Annotation { start, end, primary: false, message: Some(&label.message) });
+        }
+    }
+    if let Some((start, end)) = primary {
+        annotations.insert(0, Annotation { start, end, primary: true, message: primary_message });
+    }
+
+    let mut snippet: Vec<SnippetLine> = Vec::new();
+    for a in &annotations {
+        annotate(&mut snippet, source, a);
+    }
+    snippet.sort_by_key(|l| l.line);
+    let gutter = snippet.last().map(|l| (l.line + 1).to_string().len()).unwrap_or(1);
+    let bar = opts.paint(BLUE, "|");
+    let mut prev_line = None;
+    for l in &snippet {
+        if prev_line.is_some_and(|p| l.line > p + 1) {
             out.push('\n');
-            out.push_str("  | ");
-            out.push_str(line_text);
+            out.push_str(&opts.paint(BLUE, "..."));
+        }
+        prev_line = Some(l.line);
+        out.push('\n');
+        out.push_str(&opts.paint(BLUE, &format!("{:>gutter$}", l.line + 1)));
+        out.push(' ');
+        out.push_str(&bar);
+        if l.start < l.end {
+            out.push(' ');
+            out.push_str(&text[l.start..l.end]);
+        }
+        for u in &l.underlines {
+            let (mark, style) = if u.primary { ('^', color) } else { ('-', BLUE) };
             out.push('\n');
-            out.push_str("  | ");
-            out.extend(std::iter::repeat_n(' ', col as usize));
-            out.push('^');
-            if let Some(s) = &diag.suggestion {
-                out.push('\n');
-                out.push_str("  = suggestion: ");
-                out.push_str(s);
-            }
-            for label in &diag.labels {
-                let lstart = floor_char_boundary(text, label.span.start.0 as usize);
-                let (ll, lc) = source.text.line_col(lstart as u32);
-                let lline_start = text[..lstart].rfind('\n').map(|i| i + 1).unwrap_or(0);
-                let lline_end = text[lstart..]
-                    .find('\n')
-                    .map(|i| lstart + i)
-                    .unwrap_or(text.len());
-                let lline_text = &text[lline_start..lline_end];
-                out.push('\n');
-                out.push_str("  = note: ");
-                out.push_str(&label.message);
-                out.push('\n');
-                out.push_str("    | ");
-                out.push_str(lline_text);
-                out.push('\n');
-                out.push_str("    | ");
-                out.extend(std::iter::repeat_n(' ', lc as usize));
-                out.push('^');
-                out.push_str(&format!("  ({}:{}:{})", source.name, ll + 1, lc + 1));
+            out.push_str(&format!("{:gutter$} ", ""));
+            out.push_str(&bar);
+            out.push(' ');
+            out.extend(std::iter::repeat_n(' ', u.col));
+            let mut marks: String = std::iter::repeat_n(mark, u.width).collect();
+            if let Some(m) = u.message {
+                marks.push(' ');
+                marks.push_str(m);
             }
-            if let Some(h) = &diag.help {
-                out.push('\n');
-                out.push_str("  = help: ");
-                out.push_str(h);
-            }
-            out
+            out.push_str(&opts.paint(style, &marks));
         }
-        None => {
-            let code_str = diag.code.map(|c| format!(" [{c}]")).unwrap_or_default();
-            let mut out = format!(
-                "{:?}{}: {}: {}",
-                diag.severity, code_str, source.name, diag.message
-            );
-            if let Some(s) = &diag.suggestion {
-                out.push('\n');
-                out.push_str("  = suggestion: ");
-                out.push_str(s);
-            }
-            for label in &diag.labels {
-                let text = source.text.as_str();
-                let lstart = floor_char_boundary(text, label.span.start.0 as usize);
-                let (ll, lc) = source.text.line_col(lstart as u32);
-                let lline_start = text[..lstart].rfind('\n').map(|i| i + 1).unwrap_or(0);
-                let lline_end = text[lstart..]
-                    .find('\n')
-                    .map(|i| lstart + i)
-                    .unwrap_or(text.len());
-                let lline_text = &text[lline_start..lline_end];
-                out.push('\n');
-                out.push_str("  = note: ");
-                out.push_str(&label.message);
-                out.push('\n');
-                out.push_str("    | ");
-                out.push_str(lline_text);
-                out.push('\n');
-                out.push_str("    | ");
-                out.extend(std::iter::repeat_n(' ', lc as usize));
-                out.push('^');
-                out.push_str(&format!("  ({}:{}:{})", source.name, ll + 1, lc + 1));
-            }
-            if let Some(h) = &diag.help {
-                out.push('\n');
-                out.push_str("  = help: ");
-                out.push_str(h);
-            }
-            out
+    }
+
+    let notes = [("suggestion", &diag.suggestion), ("help", &diag.help)];
+    for (name, note) in notes {
+        if let Some(note) = note {
+            out.push('\n');
+            out.push_str(&format!("{:gutter$} = ", ""));
+            out.push_str(&opts.paint(BOLD, &format!("{name}:")));
+            out.push(' ');
+            out.push_str(note);
         }
     }
+    out
 }
 
 pub fn render_diagnostics(source: &SourceFile, diagnostics: &[Diagnostic]) -> String {
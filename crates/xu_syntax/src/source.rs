@@ -1,24 +1,88 @@
+use std::ops::Range;
+
 use crate::Span;
+use crate::render::display_width;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct SourceId(pub u32);
 
+/// How [`LineIndex::line_col`] counts the characters before an offset on its
+/// line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// Unicode scalar values, as in `file:line:col` locations.
+    Char,
+    /// UTF-16 code units, as LSP positions count them.
+    Utf16,
+    /// Terminal columns: CJK and full-width characters take two.
+    Display,
+}
+
+/// Start offsets of the lines of a text, built in one pass so that mapping
+/// an offset to its line is a binary search rather than a rescan.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineIndex {
+    starts: Vec<u32>,
+    len: u32,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut starts = Vec::with_capacity(text.len().saturating_div(64).max(32));
+        starts.push(0u32);
+        starts.extend(memchr::memchr_iter(b'\n', text.as_bytes()).map(|i| (i + 1) as u32));
+        Self { starts, len: text.len() as u32 }
+    }
+
+    /// Number of lines; text after the last newline, even none, is a line.
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// The 0-based line containing `byte`. Offsets past the end are on the
+    /// last line.
+    pub fn line(&self, byte: u32) -> u32 {
+        (self.starts.partition_point(|&s| s <= byte) - 1) as u32
+    }
+
+    /// Byte range of `line`, without its newline.
+    pub fn line_range(&self, line: u32) -> Range<usize> {
+        let line = (line as usize).min(self.starts.len() - 1);
+        let end = self.starts.get(line + 1).map_or(self.len, |next| next - 1);
+        self.starts[line] as usize..end as usize
+    }
+
+    /// 0-based line and column of `byte` in `text`, the text the index was
+    /// built from. An offset inside a character counts from its start.
+    pub fn line_col(&self, text: &str, byte: u32, unit: ColumnUnit) -> (u32, u32) {
+        let byte = byte.min(self.len);
+        let line = self.line(byte);
+        let line_start = self.starts[line as usize] as usize;
+        let mut target = byte as usize;
+        while target > line_start && !text.is_char_boundary(target) {
+            target -= 1;
+        }
+        let before = &text[line_start..target];
+        let col = match unit {
+            _ if before.is_ascii() => before.len(),
+            ColumnUnit::Char => before.chars().count(),
+            ColumnUnit::Utf16 => before.encode_utf16().count(),
+            ColumnUnit::Display => display_width(before),
+        };
+        (line, col as u32)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SourceText {
     text: String,
-    line_starts: Vec<u32>,
+    lines: LineIndex,
 }
 
 impl SourceText {
     pub fn new(text: String) -> Self {
-        let mut line_starts = Vec::with_capacity(text.len().saturating_div(64).max(32));
-        line_starts.push(0u32);
-        for (i, b) in text.bytes().enumerate() {
-            if b == b'\n' {
-                line_starts.push((i + 1) as u32);
-            }
-        }
-        Self { text, line_starts }
+        let lines = LineIndex::new(&text);
+        Self { text, lines }
     }
 
     pub fn as_str(&self) -> &str {
@@ -31,20 +95,13 @@ impl SourceText {
         &self.text[start..end]
     }
 
+    pub fn lines(&self) -> &LineIndex {
+        &self.lines
+    }
+
+    /// 0-based line and column, in characters, of `byte`.
     pub fn line_col(&self, byte: u32) -> (u32, u32) {
-        let byte = byte.min(self.text.len() as u32);
-        let idx = match self.line_starts.binary_search(&byte) {
-            Ok(i) => i,
-            Err(i) => i.saturating_sub(1),
-        };
-        let line = idx as u32;
-        let line_start = self.line_starts[idx] as usize;
-        let mut target = byte as usize;
-        while target > line_start && !self.text.is_char_boundary(target) {
-            target = target.saturating_sub(1);
-        }
-        let col = self.text[line_start..target].chars().count() as u32;
-        (line, col)
+        self.lines.line_col(&self.text, byte, ColumnUnit::Char)
     }
 }
 
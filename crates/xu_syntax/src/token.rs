@@ -55,8 +55,22 @@ pub enum TokenKind {
     Slash,
     /// `%`
     Percent,
+    /// `**`
+    StarStar,
     Hash,
+    /// `@`
+    At,
     Pipe,
+    /// `&`
+    Amp,
+    /// `^`
+    Caret,
+    /// `~`
+    Tilde,
+    /// `<<`
+    Shl,
+    /// `>>`
+    Shr,
     /// `&&`
     AmpAmp,
     /// `||`
@@ -70,6 +84,20 @@ pub enum TokenKind {
     StarEq,
     /// `/=`
     SlashEq,
+    /// `%=`
+    PercentEq,
+    /// `**=`
+    StarStarEq,
+    /// `&=`
+    AmpEq,
+    /// `|=`
+    PipeEq,
+    /// `^=`
+    CaretEq,
+    /// `<<=`
+    ShlEq,
+    /// `>>=`
+    ShrEq,
 
     /// `>`
     Gt,
@@ -83,6 +111,8 @@ pub enum TokenKind {
     /// `let`
     KwLet,
     KwVar,
+    /// `const`
+    KwConst,
     /// `is` (reserved keyword)
     KwIs,
     /// `with`
@@ -131,6 +161,10 @@ pub enum TokenKind {
     /// `!`
     Bang,
     Question,
+    /// `?.`
+    QuestionDot,
+    /// `??`
+    QuestionQuestion,
 
     /// Statement terminator (`;`).
     StmtEnd,
@@ -161,6 +195,7 @@ impl TokenKind {
                 | TokenKind::False
                 | TokenKind::KwLet
                 | TokenKind::KwVar
+                | TokenKind::KwConst
                 | TokenKind::KwIs
                 | TokenKind::KwWith
                 | TokenKind::KwHas
@@ -0,0 +1,318 @@
+//! Unicode text operations behind the string methods: grapheme clusters and
+//! canonical normalization.
+//!
+//! Grapheme segmentation follows the extended grapheme cluster rules of
+//! UAX #29 without the Prepend class: CR LF, Hangul syllable sequences,
+//! combining marks, ZWJ emoji sequences and regional-indicator flag pairs
+//! stay together. Normalization implements NFC and NFD from the tables in
+//! `unicode_tables.rs`; Hangul syllables are handled algorithmically.
+
+use crate::unicode_tables::{COMBINING_CLASS, COMPOSITION, DECOMPOSITION, MARKS};
+use crate::util::is_cjk;
+
+/// A Unicode normalization form accepted by [`normalize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalizationForm {
+    /// Canonical decomposition followed by canonical composition.
+    Nfc,
+    /// Canonical decomposition.
+    Nfd,
+}
+
+impl NormalizationForm {
+    /// Parses a form name such as `"NFC"`, ignoring case.
+    pub fn parse(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("nfc") {
+            Some(Self::Nfc)
+        } else if name.eq_ignore_ascii_case("nfd") {
+            Some(Self::Nfd)
+        } else {
+            None
+        }
+    }
+}
+
+/// Iterator over the grapheme clusters of a string; see [`graphemes`].
+#[derive(Clone, Debug)]
+pub struct Graphemes<'a> {
+    rest: &'a str,
+}
+
+/// The grapheme clusters of `s`, in order.
+pub fn graphemes(s: &str) -> Graphemes<'_> {
+    Graphemes { rest: s }
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut prev = class(first);
+        // Whether the cluster is a pictographic followed only by extenders,
+        // so a ZWJ may join the next pictographic.
+        let mut pictographic = prev == Class::Pictographic;
+        let mut regional = usize::from(prev == Class::Regional);
+        let mut end = self.rest.len();
+        for (i, c) in chars {
+            let cur = class(c);
+            if is_boundary(prev, cur, pictographic, regional) {
+                end = i;
+                break;
+            }
+            pictographic = match cur {
+                Class::Extend | Class::Zwj => pictographic,
+                Class::Pictographic => true,
+                _ => false,
+            };
+            if cur == Class::Regional {
+                regional += 1;
+            }
+            prev = cur;
+        }
+        let (head, tail) = self.rest.split_at(end);
+        self.rest = tail;
+        Some(head)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    Cr,
+    Lf,
+    Control,
+    Extend,
+    Zwj,
+    Regional,
+    Pictographic,
+    L,
+    V,
+    T,
+    Lv,
+    Lvt,
+    Other,
+}
+
+fn class(c: char) -> Class {
+    // ASCII and CJK ideographs are by far the most common and never extend.
+    if c.is_ascii() {
+        return match c {
+            '\r' => Class::Cr,
+            '\n' => Class::Lf,
+            c if c.is_ascii_control() => Class::Control,
+            _ => Class::Other,
+        };
+    }
+    if is_cjk(c) {
+        return Class::Other;
+    }
+    match c {
+        '\u{200D}' => Class::Zwj,
+        '\u{200C}' | '\u{FF9E}' | '\u{FF9F}' | '\u{1F3FB}'..='\u{1F3FF}' | '\u{E0020}'..='\u{E007F}' => {
+            Class::Extend
+        }
+        '\u{1F1E6}'..='\u{1F1FF}' => Class::Regional,
+        '\u{1100}'..='\u{115F}' | '\u{A960}'..='\u{A97C}' => Class::L,
+        '\u{1160}'..='\u{11A7}' | '\u{D7B0}'..='\u{D7C6}' => Class::V,
+        '\u{11A8}'..='\u{11FF}' | '\u{D7CB}'..='\u{D7FB}' => Class::T,
+        '\u{AC00}'..='\u{D7A3}' => {
+            if (c as u32 - S_BASE) % T_COUNT == 0 {
+                Class::Lv
+            } else {
+                Class::Lvt
+            }
+        }
+        '\u{2028}' | '\u{2029}' => Class::Control,
+        c if c.is_control() => Class::Control,
+        c if in_ranges(MARKS, c) => Class::Extend,
+        c if is_pictographic(c) => Class::Pictographic,
+        _ => Class::Other,
+    }
+}
+
+/// Approximates the `Extended_Pictographic` property: the emoji and
+/// pictograph blocks.
+fn is_pictographic(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00A9}'
+            | '\u{00AE}'
+            | '\u{203C}'
+            | '\u{2049}'
+            | '\u{2122}'
+            | '\u{2139}'
+            | '\u{2194}'..='\u{21AA}'
+            | '\u{231A}'..='\u{23FF}'
+            | '\u{24C2}'
+            | '\u{25AA}'..='\u{27BF}'
+            | '\u{2934}'..='\u{2935}'
+            | '\u{2B05}'..='\u{2B55}'
+            | '\u{3030}'
+            | '\u{303D}'
+            | '\u{3297}'
+            | '\u{3299}'
+            | '\u{1F000}'..='\u{1F1E5}'
+            | '\u{1F200}'..='\u{1F3FA}'
+            | '\u{1F400}'..='\u{1FAFF}'
+    )
+}
+
+fn is_boundary(prev: Class, cur: Class, pictographic: bool, regional: usize) -> bool {
+    use Class::*;
+    match (prev, cur) {
+        (Cr, Lf) => false,
+        (Cr | Lf | Control, _) | (_, Cr | Lf | Control) => true,
+        (L, L | V | Lv | Lvt) | (Lv | V, V | T) | (Lvt | T, T) => false,
+        (_, Extend | Zwj) => false,
+        (Zwj, Pictographic) => !pictographic,
+        (Regional, Regional) => regional % 2 == 0,
+        _ => true,
+    }
+}
+
+fn in_ranges(ranges: &[(char, char)], c: char) -> bool {
+    ranges
+        .binary_search_by(|&(lo, hi)| {
+            if hi < c {
+                std::cmp::Ordering::Less
+            } else if lo > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+const S_BASE: u32 = 0xAC00;
+const L_BASE: u32 = 0x1100;
+const V_BASE: u32 = 0x1161;
+const T_BASE: u32 = 0x11A7;
+const L_COUNT: u32 = 19;
+const V_COUNT: u32 = 21;
+const T_COUNT: u32 = 28;
+const S_COUNT: u32 = L_COUNT * V_COUNT * T_COUNT;
+
+/// `s` in the normalization form `form`.
+pub fn normalize(s: &str, form: NormalizationForm) -> String {
+    // ASCII is unchanged by both forms.
+    if s.is_ascii() {
+        return s.to_string();
+    }
+    let mut chars = Vec::with_capacity(s.len());
+    for c in s.chars() {
+        decompose(c, &mut chars);
+    }
+    reorder(&mut chars);
+    if form == NormalizationForm::Nfc {
+        compose(&mut chars);
+    }
+    chars.into_iter().collect()
+}
+
+fn combining_class(c: char) -> u8 {
+    if c.is_ascii() {
+        return 0;
+    }
+    COMBINING_CLASS
+        .binary_search_by(|&(lo, hi, _)| {
+            if hi < c {
+                std::cmp::Ordering::Less
+            } else if lo > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .map_or(0, |i| COMBINING_CLASS[i].2)
+}
+
+/// Appends the full canonical decomposition of `c`.
+fn decompose(c: char, out: &mut Vec<char>) {
+    let code = c as u32;
+    if (S_BASE..S_BASE + S_COUNT).contains(&code) {
+        let index = code - S_BASE;
+        let jamo = |v: u32| char::from_u32(v).unwrap_or(c);
+        out.push(jamo(L_BASE + index / (V_COUNT * T_COUNT)));
+        out.push(jamo(V_BASE + (index % (V_COUNT * T_COUNT)) / T_COUNT));
+        if index % T_COUNT != 0 {
+            out.push(jamo(T_BASE + index % T_COUNT));
+        }
+        return;
+    }
+    match DECOMPOSITION.binary_search_by_key(&c, |&(k, _, _)| k) {
+        Ok(i) => {
+            let (_, first, second) = DECOMPOSITION[i];
+            decompose(first, out);
+            if second != '\0' {
+                decompose(second, out);
+            }
+        }
+        Err(_) => out.push(c),
+    }
+}
+
+/// Puts each run of combining marks in canonical order.
+fn reorder(chars: &mut [char]) {
+    let mut i = 0;
+    while i < chars.len() {
+        if combining_class(chars[i]) == 0 {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && combining_class(chars[i]) != 0 {
+            i += 1;
+        }
+        // Stable, so marks of equal class keep their order.
+        chars[start..i].sort_by_key(|&c| combining_class(c));
+    }
+}
+
+fn compose_pair(a: char, b: char) -> Option<char> {
+    let (a32, b32) = (a as u32, b as u32);
+    if (L_BASE..L_BASE + L_COUNT).contains(&a32) && (V_BASE..V_BASE + V_COUNT).contains(&b32) {
+        let index = (a32 - L_BASE) * V_COUNT + (b32 - V_BASE);
+        return char::from_u32(S_BASE + index * T_COUNT);
+    }
+    if (S_BASE..S_BASE + S_COUNT).contains(&a32)
+        && (a32 - S_BASE) % T_COUNT == 0
+        && (T_BASE + 1..T_BASE + T_COUNT).contains(&b32)
+    {
+        return char::from_u32(a32 + b32 - T_BASE);
+    }
+    COMPOSITION
+        .binary_search_by_key(&(a, b), |&(x, y, _)| (x, y))
+        .ok()
+        .map(|i| COMPOSITION[i].2)
+}
+
+/// Canonical composition of a decomposed, reordered sequence, in place.
+fn compose(chars: &mut Vec<char>) {
+    let Some(&first) = chars.first() else {
+        return;
+    };
+    let mut starter = 0;
+    // Class of the last kept character: 0 when it is the starter itself,
+    // 256 while there is no starter to compose with.
+    let mut last_class: u16 = if combining_class(first) == 0 { 0 } else { 256 };
+    let mut out = 1;
+    for i in 1..chars.len() {
+        let c = chars[i];
+        let class = u16::from(combining_class(c));
+        if last_class == 0 || last_class < class {
+            if let Some(composed) = compose_pair(chars[starter], c) {
+                chars[starter] = composed;
+                continue;
+            }
+        }
+        if class == 0 {
+            starter = out;
+        }
+        last_class = class;
+        chars[out] = c;
+        out += 1;
+    }
+    chars.truncate(out);
+}
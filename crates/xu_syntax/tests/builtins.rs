@@ -0,0 +1,14 @@
+use xu_syntax::{BUILTIN_NAMES, builtin_doc, builtin_signature};
+
+#[test]
+fn every_script_facing_builtin_has_a_signature() {
+    for name in BUILTIN_NAMES.iter().filter(|n| !n.starts_with("__")) {
+        let sig = builtin_signature(name).unwrap_or_else(|| panic!("{name} has no signature"));
+        assert!(builtin_doc(name).is_some(), "{name} has no doc");
+        match sig.max {
+            Some(max) => assert!(sig.min <= max && sig.params.len() == max, "{name}: {sig:?}"),
+            None => assert!(sig.params.len() <= sig.min, "{name}: {sig:?}"),
+        }
+    }
+    assert!(builtin_signature("__gc_collect").is_none());
+}
@@ -0,0 +1,45 @@
+use xu_syntax::catalog::{self, Lang};
+use xu_syntax::{DiagnosticKind, DiagnosticsFormatter};
+
+#[test]
+fn catalog_keys_are_unique_and_translated() {
+    let mut keys: Vec<&str> = catalog::messages().iter().map(|m| m.key).collect();
+    let n = keys.len();
+    keys.sort();
+    keys.dedup();
+    assert_eq!(keys.len(), n);
+    assert!(catalog::messages().iter().all(|m| m.zh_cn.is_some() || m.key == "Raw"));
+}
+
+#[test]
+fn kinds_render_through_the_catalog() {
+    let kinds = [
+        DiagnosticKind::UndefinedIdentifier("x".into()),
+        DiagnosticKind::ArgumentCountMismatch { expected_min: 1, expected_max: 1, actual: 2 },
+        DiagnosticKind::ArgumentCountMismatch { expected_min: 1, expected_max: 3, actual: 0 },
+        DiagnosticKind::CircularImport(vec!["a".into(), "b".into()]),
+        DiagnosticKind::ArgumentHasWrongType(2),
+    ];
+    for kind in &kinds {
+        let (key, _) = kind.message_parts();
+        assert!(catalog::lookup(key).is_some(), "{key}");
+        for lang in [Lang::En, Lang::ZhCn] {
+            let text = DiagnosticsFormatter::format_in(kind, lang);
+            assert!(!text.contains('{'), "{text}");
+        }
+    }
+    assert_eq!(DiagnosticsFormatter::format_in(&kinds[0], Lang::En), "Undefined identifier: x");
+    assert_eq!(DiagnosticsFormatter::format_in(&kinds[3], Lang::En), "Circular import: a -> b");
+    // Raw messages pass through untouched, braces included.
+    let raw = DiagnosticKind::Raw("custom {x}".into());
+    assert_eq!(DiagnosticsFormatter::format_in(&raw, Lang::ZhCn), "custom {x}");
+}
+
+#[test]
+fn lookup_by_code_and_fallback() {
+    let entry = catalog::messages().iter().find(|m| m.code.is_some()).unwrap();
+    assert_eq!(catalog::lookup_code(entry.code.unwrap()).unwrap().key, entry.key);
+    assert_eq!(catalog::render("NoSuchKey", &[], Lang::ZhCn), "NoSuchKey");
+    assert_eq!(Lang::parse("zh_cn"), Some(Lang::ZhCn));
+    assert_eq!(Lang::parse("fr"), None);
+}
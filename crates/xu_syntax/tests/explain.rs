@@ -0,0 +1,37 @@
+use xu_syntax::{catalog, explain};
+
+#[test]
+fn every_code_has_one_explanation() {
+    let mut codes: Vec<&str> = explain::explanations().iter().map(|e| e.code).collect();
+    let n = codes.len();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), n);
+    for code in catalog::messages().iter().filter_map(|m| m.code) {
+        assert!(explain::explain(code).is_some(), "{code}");
+    }
+    for code in [xu_syntax::codes::INTERRUPTED, xu_syntax::codes::VOID_ASSIGNMENT] {
+        assert_eq!(explain::explain(&code.to_ascii_lowercase()).map(|e| e.code), Some(code));
+    }
+    assert!(explain::explain("E9999").is_none());
+}
+
+#[test]
+fn explanations_render_as_text() {
+    let text = explain::explain("E2008").unwrap().to_text();
+    assert!(text.starts_with("E2008: Keyword used as an identifier\n\n"), "{text}");
+    assert!(text.contains("Erroneous code example:\n\n    let match = 1\n\n"), "{text}");
+    assert!(text.ends_with("    let match_count = 1\n"), "{text}");
+}
+
+#[test]
+fn hints_list_each_known_code_once() {
+    assert_eq!(explain::explain_hint(["E9999"]), None);
+    assert_eq!(
+        explain::explain_hint(["E0001", "E0001"]).as_deref(),
+        Some("For more information about this error, try `xu check --explain E0001`.")
+    );
+    let hint = explain::explain_hint(["E1002", "E0001", "E9999"]).unwrap();
+    assert!(hint.starts_with("Some errors have detailed explanations: E0001, E1002.\n"), "{hint}");
+    assert!(hint.ends_with("try `xu check --explain E0001`."), "{hint}");
+}
@@ -0,0 +1,78 @@
+use xu_syntax::{Diagnostic, RenderOptions, SourceFile, SourceId, Span, render_diagnostic, render_diagnostic_with_options};
+
+fn source(text: &str) -> SourceFile {
+    SourceFile::new(SourceId(0), "main.xu", text.to_string())
+}
+
+fn span_of(src: &SourceFile, needle: &str) -> Span {
+    let start = src.text.as_str().find(needle).unwrap() as u32;
+    Span::new(start, start + needle.len() as u32)
+}
+
+#[test]
+fn underlines_the_whole_span_with_line_numbers() {
+    let src = source("let a = 1\nprintln(undefined_name)\n");
+    let d = Diagnostic::error("Undefined identifier: undefined_name", Some(span_of(&src, "undefined_name")))
+        .with_code("E0001")
+        .with_help("declare it first");
+    assert_eq!(
+        render_diagnostic(&src, &d),
+        "Error [E0001]:2:9: main.xu: Undefined identifier: undefined_name\n\
+         2 | println(undefined_name)\n  |         ^^^^^^^^^^^^^^\n  = help: declare it first"
+    );
+}
+
+#[test]
+fn labels_are_underlined_on_their_own_lines() {
+    let text = "let total: int = 0\nlet b = 2\nlet c = 3\nlet d = 4\nlet e = 5\nlet f = 6\nlet g = 7\nlet h = 8\ntotal = \"x\"\n";
+    let src = source(text);
+    let d = Diagnostic::error("Type mismatch", Some(span_of(&src, "\"x\"")))
+        .with_label("declared as int here", span_of(&src, "int"))
+        .with_label("expected because of this", span_of(&src, "\"x\""));
+    assert_eq!(
+        render_diagnostic(&src, &d),
+        "Error:9:9: main.xu: Type mismatch\n\
+          1 | let total: int = 0\n  |            --- declared as int here\n\
+         ...\n\
+         9 | total = \"x\"\n  |         ^^^ expected because of this"
+    );
+}
+
+#[test]
+fn long_spans_show_their_first_and_last_lines() {
+    let text = "match x {\n  a { 1 }\n  b { 2 }\n  c { 3 }\n  d { 4 }\n  e { 5 }\n}\n";
+    let src = source(text);
+    let d = Diagnostic::error("Non-exhaustive match", Some(Span::new(0, text.len() as u32 - 1)));
+    let out = render_diagnostic(&src, &d);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[1], "1 | match x {");
+    assert_eq!(lines[2], "  | ^^^^^^^^^");
+    assert_eq!(lines[5], "3 |   b { 2 }");
+    assert_eq!(lines[7], "...");
+    assert_eq!(lines[8], "7 | }");
+    assert_eq!(lines.len(), 10, "{out}");
+}
+
+#[test]
+fn wide_characters_take_two_columns() {
+    let src = source("println(名字 + 1)\n");
+    let d = Diagnostic::error("bad", Some(span_of(&src, "名字")));
+    assert!(render_diagnostic(&src, &d).ends_with("\n  |         ^^^^"));
+}
+
+#[test]
+fn colors_are_opt_in() {
+    let src = source("let x = y\n");
+    let d = Diagnostic::warning("unused", Some(span_of(&src, "y"))).with_code("W0001");
+    assert!(!render_diagnostic(&src, &d).contains('\x1b'));
+    let colored = render_diagnostic_with_options(&src, &d, RenderOptions { color: true });
+    assert!(colored.starts_with("\x1b[1;33mWarning [W0001]\x1b[0m:1:9"), "{colored:?}");
+    assert!(colored.contains("\x1b[1;33m^\x1b[0m"), "{colored:?}");
+}
+
+#[test]
+fn diagnostics_without_a_span_have_no_snippet() {
+    let src = source("let x = 1\n");
+    let d = Diagnostic::error("Import failed", None).with_suggestion("check the path");
+    assert_eq!(render_diagnostic(&src, &d), "Error: main.xu: Import failed\n  = suggestion: check the path");
+}
@@ -0,0 +1,38 @@
+use xu_syntax::{ColumnUnit, LineIndex, SourceText};
+
+#[test]
+fn offsets_map_to_lines_and_line_ranges() {
+    let text = "let a = 1\n\nprintln(a)\n";
+    let index = LineIndex::new(text);
+    assert_eq!(index.line_count(), 4);
+    let lines: Vec<_> = [0, 9, 10, 11, 21, 22, 500].iter().map(|&b| index.line(b)).collect();
+    assert_eq!(lines, vec![0, 0, 1, 2, 2, 3, 3]);
+    assert_eq!(&text[index.line_range(0)], "let a = 1");
+    assert_eq!(&text[index.line_range(1)], "");
+    assert_eq!(&text[index.line_range(2)], "println(a)");
+    assert_eq!(index.line_range(3), 22..22);
+    assert_eq!(LineIndex::new("").line_range(0), 0..0);
+}
+
+#[test]
+fn columns_count_characters_code_units_or_terminal_cells() {
+    let text = "x = 1\nlet 名称 = \"😀\" + y\n";
+    let index = LineIndex::new(text);
+    let y = text.find('y').unwrap() as u32;
+    let col = |unit| index.line_col(text, y, unit);
+    assert_eq!(col(ColumnUnit::Char), (1, 15));
+    assert_eq!(col(ColumnUnit::Utf16), (1, 16));
+    assert_eq!(col(ColumnUnit::Display), (1, 17));
+    // An offset inside a character counts from its start.
+    let inside = text.find('称').unwrap() as u32 + 1;
+    assert_eq!(index.line_col(text, inside, ColumnUnit::Char), (1, 5));
+    assert_eq!(index.line_col(text, 3, ColumnUnit::Display), (0, 3));
+}
+
+#[test]
+fn source_text_columns_are_in_characters() {
+    let src = SourceText::new("a\n中文 b".to_string());
+    assert_eq!(src.line_col(src.as_str().find('b').unwrap() as u32), (1, 3));
+    assert_eq!(src.line_col(1000), (1, 4));
+    assert_eq!(src.lines().line_count(), 2);
+}
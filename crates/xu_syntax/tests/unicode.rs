@@ -0,0 +1,45 @@
+use xu_syntax::{NormalizationForm, graphemes, normalize};
+
+fn clusters(s: &str) -> Vec<&str> {
+    graphemes(s).collect()
+}
+
+#[test]
+fn graphemes_keep_marks_and_emoji_sequences_together() {
+    assert_eq!(clusters("abc"), ["a", "b", "c"]);
+    assert_eq!(clusters("你好"), ["你", "好"]);
+    assert_eq!(clusters("e\u{301}x"), ["e\u{301}", "x"]);
+    assert_eq!(clusters("a\r\nb"), ["a", "\r\n", "b"]);
+    // Family (ZWJ sequence), thumbs up with skin tone, two flags.
+    let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+    assert_eq!(clusters(family), [family]);
+    assert_eq!(clusters("\u{1F44D}\u{1F3FD}!"), ["\u{1F44D}\u{1F3FD}", "!"]);
+    assert_eq!(
+        clusters("\u{1F1E8}\u{1F1F3}\u{1F1EF}\u{1F1F5}"),
+        ["\u{1F1E8}\u{1F1F3}", "\u{1F1EF}\u{1F1F5}"]
+    );
+    // Conjoining jamo form one syllable.
+    assert_eq!(clusters("\u{1100}\u{1161}\u{11A8}\u{AC00}"), ["\u{1100}\u{1161}\u{11A8}", "\u{AC00}"]);
+    assert!(clusters("").is_empty());
+}
+
+#[test]
+fn normalize_composes_and_decomposes() {
+    assert_eq!(normalize("e\u{301}", NormalizationForm::Nfc), "\u{E9}");
+    assert_eq!(normalize("\u{E9}", NormalizationForm::Nfd), "e\u{301}");
+    // Marks are put in canonical order before composing.
+    assert_eq!(normalize("a\u{301}\u{323}", NormalizationForm::Nfd), "a\u{323}\u{301}");
+    assert_eq!(normalize("a\u{301}\u{323}", NormalizationForm::Nfc), "\u{1EA1}\u{301}");
+    assert_eq!(normalize("\u{1100}\u{1161}\u{11A8}", NormalizationForm::Nfc), "\u{AC01}");
+    assert_eq!(normalize("\u{AC01}", NormalizationForm::Nfd), "\u{1100}\u{1161}\u{11A8}");
+    // Singletons decompose and never recompose.
+    assert_eq!(normalize("\u{212B}", NormalizationForm::Nfc), "\u{C5}");
+    assert_eq!(normalize("中文 ok", NormalizationForm::Nfc), "中文 ok");
+}
+
+#[test]
+fn normalization_form_names() {
+    assert_eq!(NormalizationForm::parse("NFC"), Some(NormalizationForm::Nfc));
+    assert_eq!(NormalizationForm::parse("nfd"), Some(NormalizationForm::Nfd));
+    assert_eq!(NormalizationForm::parse("NFKC"), None);
+}
@@ -0,0 +1,145 @@
+//! Golden (snapshot) testing for Xu programs.
+//!
+//! A [`Golden`] compares text, usually what a program printed, with a
+//! snapshot file kept next to the tests. Before comparing or saving, the text
+//! goes through the [`Redactions`] of the suite, so that paths and timings
+//! that differ between machines and runs do not end up in snapshots.
+//! [`run_file`] runs a program the way `xu run` does and returns its output.
+//!
+//! Setting `XU_UPDATE_GOLDEN=1` rewrites every snapshot from the actual text
+//! instead of comparing:
+//!
+//! ```text
+//! XU_UPDATE_GOLDEN=1 cargo test
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod redact;
+mod run;
+
+pub use redact::Redactions;
+pub use run::{run_file, run_source};
+
+/// Environment variable that switches [`Golden`] to update mode.
+pub const UPDATE_ENV: &str = "XU_UPDATE_GOLDEN";
+
+/// Whether `XU_UPDATE_GOLDEN` is set to `1` or `true`.
+pub fn update_enabled() -> bool {
+    std::env::var(UPDATE_ENV).is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// File name (without `.txt`) of the snapshot called `name`: letters,
+/// digits, `-`, `_` and `.` are kept, `/` becomes `__` and anything else `_`.
+pub fn snapshot_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '/' | '\\' => out.push_str("__"),
+            c if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') => out.push(c),
+            _ => out.push('_'),
+        }
+    }
+    if out.is_empty() { "_".to_string() } else { out }
+}
+
+/// Snapshot name of the program at `path`: its path relative to `root`
+/// without the extension, so `tests/io/read.xu` under `tests` becomes
+/// `io__read`. Programs outside `root` are named after their file stem.
+pub fn snapshot_name_for(path: &Path, root: &Path) -> String {
+    let rel = path.strip_prefix(root).unwrap_or_else(|_| Path::new(path.file_name().unwrap_or_default()));
+    let rel = rel.with_extension("");
+    let parts: Vec<String> = rel.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+    snapshot_name(&parts.join("/"))
+}
+
+/// Files under `dir` (recursively) with extension `ext`, sorted by path.
+pub fn find_files(dir: &Path, ext: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+            if path.is_dir() {
+                files.extend(find_files(&path, ext));
+            } else if path.extension().and_then(|s| s.to_str()) == Some(ext) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// A directory of snapshots, one `<name>.txt` file per snapshot.
+#[derive(Clone, Debug)]
+pub struct Golden {
+    dir: PathBuf,
+    update: bool,
+    redactions: Redactions,
+}
+
+impl Golden {
+    /// Snapshots in `dir`, with no redactions. Update mode follows
+    /// `XU_UPDATE_GOLDEN`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), update: update_enabled(), redactions: Redactions::new() }
+    }
+
+    /// Forces update mode on or off.
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Applies `redactions` to every text before it is compared or saved.
+    pub fn redactions(mut self, redactions: Redactions) -> Self {
+        self.redactions = redactions;
+        self
+    }
+
+    /// The snapshot file for `name`, see [`snapshot_name`].
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.txt", snapshot_name(name)))
+    }
+
+    /// Compares `actual`, redacted and with `\r\n` turned into `\n`, with the
+    /// snapshot `name`, ignoring trailing whitespace. In update mode the
+    /// snapshot is written instead.
+    pub fn check(&self, name: &str, actual: &str) -> Result<(), String> {
+        let path = self.path(name);
+        let actual = self.redactions.apply(&actual.replace("\r\n", "\n"));
+        if self.update {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+            }
+            return fs::write(&path, &actual).map_err(|e| format!("{}: {e}", path.display()));
+        }
+        let expected = match fs::read_to_string(&path) {
+            Ok(text) => text.replace("\r\n", "\n"),
+            Err(_) => {
+                return Err(format!(
+                    "{}: no snapshot; run with {UPDATE_ENV}=1 to create it",
+                    path.display()
+                ));
+            }
+        };
+        if actual.trim_end() == expected.trim_end() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Golden mismatch for {}:\n--- expected\n{}\n--- actual\n{}",
+                path.display(),
+                expected.trim_end(),
+                actual.trim_end()
+            ))
+        }
+    }
+
+    /// [`Golden::check`], panicking on a mismatch.
+    #[track_caller]
+    pub fn assert(&self, name: &str, actual: &str) {
+        if let Err(e) = self.check(name, actual) {
+            panic!("{e}");
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Replacing the parts of program output that change between machines and
+//! runs with fixed placeholders.
+
+use std::path::Path;
+
+use regex::Regex;
+
+/// Replacements applied to text before it is compared with a snapshot.
+///
+/// Paths are replaced first, longest first, then timings, then custom
+/// patterns in the order they were added.
+#[derive(Clone, Debug, Default)]
+pub struct Redactions {
+    paths: Vec<(String, String)>,
+    timings: bool,
+    patterns: Vec<(Regex, String)>,
+}
+
+impl Redactions {
+    /// No redactions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces `path` with `placeholder`, in the form given and in its
+    /// canonical form when that differs (such as `/private/tmp` for
+    /// `/tmp` on macOS).
+    pub fn path(mut self, path: impl AsRef<Path>, placeholder: &str) -> Self {
+        let path = path.as_ref();
+        let mut forms = vec![path.to_string_lossy().into_owned()];
+        if let Ok(canonical) = path.canonicalize() {
+            forms.push(canonical.to_string_lossy().into_owned());
+        }
+        for form in forms {
+            let form = form.trim_end_matches(['/', '\\']).to_string();
+            if !form.is_empty() && !self.paths.iter().any(|(p, _)| *p == form) {
+                self.paths.push((form, placeholder.to_string()));
+            }
+        }
+        self.paths.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        self
+    }
+
+    /// Replaces durations such as `12ms`, `0.75 s` or `3µs` with `<time>`.
+    pub fn timings(mut self) -> Self {
+        self.timings = true;
+        self
+    }
+
+    /// Replaces matches of the regular expression `pattern` with
+    /// `replacement`, which may refer to groups as `$1` or `${name}`.
+    ///
+    /// # Panics
+    ///
+    /// If `pattern` is not a valid regular expression.
+    pub fn pattern(mut self, pattern: &str, replacement: &str) -> Self {
+        let re = Regex::new(pattern).unwrap_or_else(|e| panic!("invalid redaction pattern {pattern:?}: {e}"));
+        self.patterns.push((re, replacement.to_string()));
+        self
+    }
+
+    /// `text` with every redaction applied.
+    pub fn apply(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for (path, placeholder) in &self.paths {
+            out = out.replace(path.as_str(), placeholder);
+        }
+        if self.timings {
+            out = timing_regex().replace_all(&out, "${1}<time>").into_owned();
+        }
+        for (re, replacement) in &self.patterns {
+            out = re.replace_all(&out, replacement.as_str()).into_owned();
+        }
+        out
+    }
+}
+
+fn timing_regex() -> &'static Regex {
+    static RE: std::sync::OnceLock<Regex> = std::sync::OnceLock::new();
+    // The number must not continue an identifier or a decimal, and the unit
+    // must end the word.
+    RE.get_or_init(|| Regex::new(r"(^|[^\w.])\d+(?:\.\d+)? ?(?:ns|µs|us|ms|s)\b").unwrap())
+}
@@ -0,0 +1,54 @@
+//! Running programs for snapshots.
+
+use std::path::Path;
+
+use xu_driver::Driver;
+use xu_runtime::Runtime;
+use xu_syntax::Severity;
+
+/// Compiles and runs the program at `path` with strict checks and random
+/// seed 1, and returns what it printed. `configure` can adjust the runtime
+/// before the run, for example to set the stdlib path or capabilities.
+///
+/// Compile errors are returned as rendered diagnostics, runtime errors as
+/// the error message.
+pub fn run_file(path: &Path, configure: impl FnOnce(&mut Runtime)) -> Result<String, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let name = path.to_string_lossy();
+    run(&name, &source, |rt| {
+        rt.set_entry_path(&name)?;
+        configure(rt);
+        Ok(())
+    })
+}
+
+/// [`run_file`] for `source` held in memory; `name` is the path shown in
+/// diagnostics.
+pub fn run_source(name: &str, source: &str, configure: impl FnOnce(&mut Runtime)) -> Result<String, String> {
+    run(name, source, |rt| {
+        configure(rt);
+        Ok(())
+    })
+}
+
+fn run(
+    name: &str,
+    source: &str,
+    configure: impl FnOnce(&mut Runtime) -> Result<(), String>,
+) -> Result<String, String> {
+    let compiled = Driver::new().compile_text(name, source, true)?;
+    let errors: Vec<_> = compiled
+        .diagnostics
+        .iter()
+        .filter(|d| matches!(d.severity, Severity::Error))
+        .cloned()
+        .collect();
+    if !errors.is_empty() {
+        return Err(xu_syntax::render_diagnostics(&compiled.source, &errors));
+    }
+    let mut rt = Runtime::new();
+    rt.set_frontend(Box::new(Driver::new()));
+    rt.set_rng_seed(1);
+    configure(&mut rt)?;
+    rt.exec_executable(&compiled.executable).map(|r| r.output)
+}
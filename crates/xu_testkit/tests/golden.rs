@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use xu_testkit::{Golden, Redactions, run_source, snapshot_name, snapshot_name_for};
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("xu_testkit_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn snapshot_names_are_file_names() {
+    assert_eq!(snapshot_name("01_basics"), "01_basics");
+    assert_eq!(snapshot_name("io/read file?"), "io__read_file_");
+    assert_eq!(snapshot_name("数据.v2"), "数据.v2");
+    assert_eq!(snapshot_name(""), "_");
+    let root = Path::new("tests");
+    assert_eq!(snapshot_name_for(Path::new("tests/io/read.xu"), root), "io__read");
+    assert_eq!(snapshot_name_for(Path::new("elsewhere/main.xu"), root), "main");
+}
+
+#[test]
+fn redactions_hide_paths_and_timings() {
+    let r = Redactions::new()
+        .path("/home/ada/project", "<root>")
+        .path("/home/ada/project/target/", "<target>")
+        .timings()
+        .pattern(r"id=\d+", "id=<n>");
+    let text = "ran /home/ada/project/main.xu in 12ms (0.5 s, 30µs)\n\
+                wrote /home/ada/project/target/out id=42\n\
+                v2s 3 items x1ms";
+    assert_eq!(
+        r.apply(text),
+        "ran <root>/main.xu in <time> (<time>, <time>)\nwrote <target>/out id=<n>\nv2s 3 items x1ms"
+    );
+    assert_eq!(Redactions::new().apply("took 3ms"), "took 3ms");
+}
+
+#[test]
+fn golden_compares_and_updates() {
+    let dir = temp_dir("golden");
+    let golden = Golden::new(&dir).update(false).redactions(Redactions::new().timings());
+    let err = golden.check("first", "done in 5ms\n").unwrap_err();
+    assert!(err.contains("XU_UPDATE_GOLDEN=1"), "{err}");
+
+    golden.clone().update(true).check("first", "done in 5ms\r\n").unwrap();
+    assert_eq!(std::fs::read_to_string(golden.path("first")).unwrap(), "done in <time>\n");
+    golden.check("first", "done in 7ms").unwrap();
+    let err = golden.check("first", "failed after 7ms").unwrap_err();
+    assert!(err.contains("--- expected\ndone in <time>\n--- actual\nfailed after <time>"), "{err}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn run_source_snapshots_programs() {
+    let out = run_source("<mem>", "let r = rand(1000000)\nprintln(r == r)\nprintln(\"hi\")\n", |_| {}).unwrap();
+    assert_eq!(out, "true\nhi\n");
+    let seeded = |src: &str| run_source("<mem>", src, |_| {}).unwrap();
+    assert_eq!(seeded("println(rand(1000000))\n"), seeded("println(rand(1000000))\n"));
+
+    let err = run_source("<mem>", "let z = 0\nprintln(1 / z)\n", |_| {}).unwrap_err();
+    assert!(err.contains("zero"), "{err}");
+    let err = run_source("<mem>", "let = 1\n", |_| {}).unwrap_err();
+    assert!(err.contains("Error"), "{err}");
+
+    let out = run_source("<mem>", "println(os_args().length)\n", |rt| rt.set_args(vec!["a".into(), "b".into()]));
+    assert_eq!(out.unwrap(), "2\n");
+}
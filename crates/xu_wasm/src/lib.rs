@@ -0,0 +1,137 @@
+//! WebAssembly build of the interpreter, for the browser playground.
+//!
+//! Built for `wasm32-unknown-unknown` with `wasm-pack`, the module exports
+//! [`compile_and_run`]:
+//!
+//! ```js
+//! import init, { compile_and_run } from "./pkg/xu_wasm.js";
+//!
+//! await init();
+//! const res = compile_and_run('println("hi")');
+//! res.output;       // "hi\n"
+//! res.diagnostics;  // [{ severity, code, message, line, column, start, end }]
+//! res.error;        // runtime error message, or undefined
+//! ```
+//!
+//! The runtime and driver are built without their `sys` feature: there is
+//! no file system, so imports other than the embedded stdlib fail, and
+//! there are no threads for workers. The clock reads the browser's
+//! `Date.now()`.
+
+use wasm_bindgen::prelude::*;
+
+use xu_driver::Driver;
+use xu_runtime::Runtime;
+use xu_syntax::{Severity, SourceFile};
+
+/// What [`compile_and_run`] produced.
+#[wasm_bindgen(getter_with_clone)]
+pub struct RunResult {
+    /// What the program printed, up to a runtime error.
+    pub output: String,
+    /// Errors and warnings of the compile; with any error the program did
+    /// not run.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The runtime error that stopped the program.
+    pub error: Option<String>,
+}
+
+/// A compile diagnostic with its position in the source.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    /// `"error"`, `"warning"` or `"info"`.
+    pub severity: String,
+    pub code: Option<String>,
+    pub message: String,
+    /// 1-based line and column of the span start; 0 without a span.
+    pub line: u32,
+    pub column: u32,
+    /// Byte offsets of the span.
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Diagnostic {
+    fn new(source: &SourceFile, d: &xu_syntax::Diagnostic) -> Self {
+        let severity = match d.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        let (line, column, start, end) = match d.span {
+            Some(s) => {
+                let (line, col) = source.text.line_col(s.start.0);
+                (line + 1, col + 1, s.start.0, s.end.0)
+            }
+            None => (0, 0, 0, 0),
+        };
+        Diagnostic {
+            severity: severity.to_string(),
+            code: d.code.map(str::to_string),
+            message: d.message.clone(),
+            line,
+            column,
+            start,
+            end,
+        }
+    }
+}
+
+/// Compiles `source` with strict checks and, if there are no errors, runs
+/// it in a fresh runtime.
+#[wasm_bindgen]
+pub fn compile_and_run(source: &str) -> RunResult {
+    let driver = Driver::new();
+    let compiled = match driver.compile_text("<playground>", source, true) {
+        Ok(c) => c,
+        Err(e) => return RunResult { output: String::new(), diagnostics: Vec::new(), error: Some(e) },
+    };
+    let diagnostics: Vec<Diagnostic> =
+        compiled.diagnostics.iter().map(|d| Diagnostic::new(&compiled.source, d)).collect();
+    if diagnostics.iter().any(|d| d.severity == "error") {
+        return RunResult { output: String::new(), diagnostics, error: None };
+    }
+    let mut rt = Runtime::new();
+    rt.set_strict_vars(true);
+    rt.set_frontend(Box::new(Driver::new()));
+    #[cfg(target_arch = "wasm32")]
+    rt.set_clock(Box::new(clock::BrowserClock));
+    match rt.exec_executable(&compiled.executable) {
+        Ok(res) => RunResult { output: res.output, diagnostics, error: None },
+        Err(e) => RunResult { output: rt.take_output(), diagnostics, error: Some(e) },
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod clock {
+    use wasm_bindgen::prelude::*;
+    use xu_runtime::Clock;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = Date)]
+        fn now() -> f64;
+    }
+
+    /// `Date.now()`, at millisecond resolution for every reading.
+    pub struct BrowserClock;
+
+    impl Clock for BrowserClock {
+        fn unix_secs(&self) -> i64 {
+            (now() / 1000.0) as i64
+        }
+
+        fn unix_millis(&self) -> i64 {
+            now() as i64
+        }
+
+        fn mono_micros(&self) -> i64 {
+            (now() * 1000.0) as i64
+        }
+
+        fn mono_nanos(&self) -> i64 {
+            (now() * 1_000_000.0) as i64
+        }
+    }
+}
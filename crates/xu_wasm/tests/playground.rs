@@ -0,0 +1,28 @@
+use xu_wasm::compile_and_run;
+
+#[test]
+fn runs_programs_and_reports_output() {
+    let res = compile_and_run("println(\"hi\")\nprintln([3, 1, 2].length)\n");
+    assert_eq!(res.output, "hi\n3\n");
+    assert!(res.diagnostics.is_empty(), "{:?}", res.diagnostics);
+    assert_eq!(res.error, None);
+}
+
+#[test]
+fn compile_errors_are_diagnostics_with_positions() {
+    let res = compile_and_run("let x = 1\nprintln(missing)\n");
+    assert_eq!(res.output, "");
+    assert_eq!(res.error, None);
+    let d = &res.diagnostics[0];
+    assert_eq!(d.severity, "error");
+    assert!(d.message.contains("missing"), "{}", d.message);
+    assert_eq!((d.line, d.column), (2, 9));
+    assert_eq!(&"let x = 1\nprintln(missing)\n"[d.start as usize..d.end as usize], "missing");
+}
+
+#[test]
+fn runtime_errors_keep_the_output_before_them() {
+    let res = compile_and_run("let xs = [1]\nprintln(\"before\")\nprintln(xs[4])\n");
+    assert_eq!(res.output, "before\n");
+    assert!(res.error.as_deref().is_some_and(|e| e.contains("out of range")), "{:?}", res.error);
+}
@@ -0,0 +1,84 @@
+"""Generates crates/xu_syntax/src/unicode_tables.rs from Python's unicodedata.
+
+Run from the repository root: python3 scripts/gen_unicode_tables.py
+Hangul syllables are composed and decomposed algorithmically and are not in
+the tables.
+"""
+import sys
+import unicodedata
+
+OUT = "crates/xu_syntax/src/unicode_tables.rs"
+EXTEND_CATEGORIES = ("Mn", "Me", "Mc")
+
+def ch(cp):
+  return "'\\u{%X}'" % cp
+
+def ranges(cps):
+  out = []
+  for cp in cps:
+    if out and out[-1][1] == cp - 1:
+      out[-1][1] = cp
+    else:
+      out.append([cp, cp])
+  return out
+
+def main():
+  ccc = []
+  decomp = []
+  comp = []
+  extend = []
+  for cp in range(sys.maxunicode + 1):
+    c = chr(cp)
+    k = unicodedata.combining(c)
+    if k:
+      if ccc and ccc[-1][1] == cp - 1 and ccc[-1][2] == k:
+        ccc[-1][1] = cp
+      else:
+        ccc.append([cp, cp, k])
+    d = unicodedata.decomposition(c)
+    if d and not d.startswith("<"):
+      parts = [int(p, 16) for p in d.split()]
+      decomp.append((cp, parts))
+      if len(parts) == 2 and unicodedata.normalize("NFC", chr(parts[0]) + chr(parts[1])) == c:
+        comp.append((parts[0], parts[1], cp))
+    if unicodedata.category(c) in EXTEND_CATEGORIES:
+      extend.append(cp)
+  comp.sort()
+  lines = [
+    "//! Unicode data tables generated by `scripts/gen_unicode_tables.py` from",
+    "//! Unicode %s. Do not edit by hand." % unicodedata.unidata_version,
+    "",
+    "/// Ranges with a nonzero canonical combining class: `(first, last, class)`.",
+    "pub(crate) static COMBINING_CLASS: &[(char, char, u8)] = &[",
+  ]
+  lines += ["    (%s, %s, %d)," % (ch(a), ch(b), k) for a, b, k in ccc]
+  lines += [
+    "];",
+    "",
+    "/// Canonical decompositions, one level deep: `(char, first, second)` with",
+    "/// `'\\0'` as `second` for singletons.",
+    "pub(crate) static DECOMPOSITION: &[(char, char, char)] = &[",
+  ]
+  for cp, parts in decomp:
+    second = ch(parts[1]) if len(parts) > 1 else "'\\0'"
+    lines.append("    (%s, %s, %s)," % (ch(cp), ch(parts[0]), second))
+  lines += [
+    "];",
+    "",
+    "/// Primary composites by their pair: `(first, second, composite)`, sorted.",
+    "pub(crate) static COMPOSITION: &[(char, char, char)] = &[",
+  ]
+  lines += ["    (%s, %s, %s)," % (ch(a), ch(b), ch(c)) for a, b, c in comp]
+  lines += [
+    "];",
+    "",
+    "/// Combining and spacing marks (categories Mn, Me, Mc).",
+    "pub(crate) static MARKS: &[(char, char)] = &[",
+  ]
+  lines += ["    (%s, %s)," % (ch(a), ch(b)) for a, b in ranges(extend)]
+  lines += ["];", ""]
+  with open(OUT, "w", encoding="utf-8") as f:
+    f.write("\n".join(lines))
+
+if __name__ == "__main__":
+  main()